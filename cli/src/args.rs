@@ -25,7 +25,11 @@
 */
 
 use {
-    crate::error::{CliError, CliResult},
+    crate::{
+        error::{CliError, CliResult},
+        profile::{self, Profile},
+        resp::Format,
+    },
     crossterm::{
         event::{self, Event, KeyCode, KeyEvent},
         terminal,
@@ -33,8 +37,8 @@ use {
     libsky::CliAction,
     std::{
         collections::HashMap,
-        fs,
-        io::{self, Write},
+        env, fs,
+        io::{self, IsTerminal, Read, Write},
         process::exit,
     },
 };
@@ -46,14 +50,16 @@ pub struct ClientConfig {
     pub kind: ClientConfigKind,
     pub username: String,
     pub password: String,
+    pub format: Format,
 }
 
 impl ClientConfig {
-    pub fn new(kind: ClientConfigKind, username: String, password: String) -> Self {
+    pub fn new(kind: ClientConfigKind, username: String, password: String, format: Format) -> Self {
         Self {
             kind,
             username,
             password,
+            format,
         }
     }
 }
@@ -61,36 +67,62 @@ impl ClientConfig {
 #[derive(Debug)]
 pub enum ClientConfigKind {
     Tcp(String, u16),
-    Tls(String, u16, String),
+    Tls(String, u16, String, Option<ClientIdentity>),
+}
+
+#[derive(Debug)]
+/// A client certificate and its private key (both PEM), presented to servers that ask for a client certificate
+pub struct ClientIdentity {
+    pub cert: String,
+    pub key: String,
+}
+
+#[derive(Debug)]
+/// Statements to run (instead of opening the shell)
+pub struct Script {
+    pub source: String,
+    /// keep going if a statement fails
+    pub force: bool,
 }
 
 #[derive(Debug)]
 pub enum Task {
     HelpMessage(String),
     OpenShell(ClientConfig),
+    RunScript(ClientConfig, Script),
 }
 
 enum TaskInner {
     HelpMsg(String),
-    OpenShell(HashMap<String, String>),
+    OpenShell(HashMap<String, String>, bool),
 }
 
 fn load_env() -> CliResult<TaskInner> {
-    let action = libsky::parse_cli_args_disallow_duplicate()?;
+    // `--force` is the only flag that doesn't take a value, so we pick it out ourselves
+    let mut force = false;
+    let args = env::args().filter(|arg| {
+        let is_force = arg == "--force";
+        force |= is_force;
+        !is_force
+    });
+    let action = libsky::parse_args_deny_duplicate(args)?;
     match action {
         CliAction::Help => Ok(TaskInner::HelpMsg(TXT_HELP.into())),
         CliAction::Version => Ok(TaskInner::HelpMsg(libsky::version_msg("skysh"))),
-        CliAction::Action(a) => Ok(TaskInner::OpenShell(a)),
+        CliAction::Action(a) => Ok(TaskInner::OpenShell(a, force)),
     }
 }
 
 pub fn parse() -> CliResult<Task> {
-    let mut args = match load_env()? {
+    let (mut args, force) = match load_env()? {
         TaskInner::HelpMsg(msg) => return Ok(Task::HelpMessage(msg)),
-        TaskInner::OpenShell(args) => args,
+        TaskInner::OpenShell(args, force) => (args, force),
     };
+    // anything that is set using an argument overrides the profile
+    let profile = profile::load(args.remove("--profile").as_deref())?.unwrap_or_default();
+    let identity = client_identity(&mut args, &profile)?;
     let endpoint = match args.remove("--endpoint") {
-        None => ClientConfigKind::Tcp("127.0.0.1".into(), 2003),
+        None => profile_endpoint(&profile, args.remove("--tls-cert"), identity)?,
         Some(ep) => {
             // should be in the format protocol@host:port
             let proto_host_port: Vec<&str> = ep.split("@").collect();
@@ -111,9 +143,16 @@ pub fn parse() -> CliResult<Task> {
                     )))
                 }
             };
-            let tls_cert = args.remove("--tls-cert");
+            let tls_cert = args
+                .remove("--tls-cert")
+                .or_else(|| profile.tls_cert.clone().map(profile::expand_home));
             match protocol {
                 "tcp" => {
+                    if identity.is_some() {
+                        return Err(CliError::ArgsErr(
+                            "a client certificate can only be used with a TLS endpoint".into(),
+                        ));
+                    }
                     // TODO(@ohsayan): warn!
                     ClientConfigKind::Tcp(host.into(), port)
                 }
@@ -122,7 +161,7 @@ pub fn parse() -> CliResult<Task> {
                     match tls_cert {
                         Some(path) => {
                             let cert = fs::read_to_string(path)?;
-                            ClientConfigKind::Tls(host.into(), port, cert)
+                            ClientConfigKind::Tls(host.into(), port, cert, identity)
                         }
                         None => {
                             return Err(CliError::ArgsErr(format!(
@@ -139,23 +178,106 @@ pub fn parse() -> CliResult<Task> {
             }
         }
     };
-    let username = match args.remove("--user") {
+    let username = match args.remove("--user").or(profile.user) {
         Some(u) => u,
         None => {
             // default
             "root".into()
         }
     };
-    let password = match args.remove("--password") {
+    let format = match args.remove("--format") {
+        Some(f) => match Format::from_str(&f) {
+            Some(f) => f,
+            None => {
+                return Err(CliError::ArgsErr(format!(
+                    "unknown output format `{f}`. expected one of `table`, `json` or `csv`"
+                )))
+            }
+        },
+        None => Format::Table,
+    };
+    let file = match (args.remove("--file"), args.remove("-f")) {
+        (Some(_), Some(_)) => {
+            return Err(CliError::ArgsErr(
+                "duplicate value for `--file` (also set with `-f`)".into(),
+            ))
+        }
+        (file, f) => file.or(f),
+    };
+    let password = match args
+        .remove("--password")
+        .or_else(|| env::var(profile::PASSWORD_ENV).ok())
+    {
         Some(p) => p,
         None => read_password("Enter password: ")?,
     };
-    if args.is_empty() {
-        Ok(Task::OpenShell(ClientConfig::new(
-            endpoint, username, password,
-        )))
-    } else {
-        Err(CliError::ArgsErr(format!("found unknown arguments")))
+    if !args.is_empty() {
+        return Err(CliError::ArgsErr(format!("found unknown arguments")));
+    }
+    let cfg = ClientConfig::new(endpoint, username, password, format);
+    // run the script in the file, or the statements that were piped in
+    let source = match file {
+        Some(path) => Some(fs::read_to_string(path)?),
+        None if !io::stdin().is_terminal() => {
+            let mut source = String::new();
+            io::stdin().read_to_string(&mut source)?;
+            Some(source)
+        }
+        None => None,
+    };
+    match source {
+        Some(source) => Ok(Task::RunScript(cfg, Script { source, force })),
+        None if force => Err(CliError::ArgsErr(
+            "`--force` can only be used when running a script".into(),
+        )),
+        None => Ok(Task::OpenShell(cfg)),
+    }
+}
+
+/// Returns the endpoint in the profile (a TCP endpoint on localhost by default). The connection uses TLS if the
+/// profile (or `tls_cert`) has a certificate
+fn profile_endpoint(
+    profile: &Profile,
+    tls_cert: Option<String>,
+    identity: Option<ClientIdentity>,
+) -> CliResult<ClientConfigKind> {
+    let host = profile.host.clone().unwrap_or_else(|| "127.0.0.1".into());
+    let tls_cert = tls_cert.or_else(|| profile.tls_cert.clone().map(profile::expand_home));
+    Ok(match (tls_cert, identity) {
+        (Some(path), identity) => {
+            let cert = fs::read_to_string(path)?;
+            ClientConfigKind::Tls(host, profile.port.unwrap_or(2004), cert, identity)
+        }
+        (None, None) => ClientConfigKind::Tcp(host, profile.port.unwrap_or(2003)),
+        (None, Some(_)) => {
+            return Err(CliError::ArgsErr(
+                "a client certificate can only be used with a TLS endpoint".into(),
+            ))
+        }
+    })
+}
+
+/// Returns the client certificate and its key (set with `--tls-client-cert` and `--tls-client-key`, or in the profile)
+/// if there is one. The certificate and the key must be set together
+fn client_identity(
+    args: &mut HashMap<String, String>,
+    profile: &Profile,
+) -> CliResult<Option<ClientIdentity>> {
+    let cert = args
+        .remove("--tls-client-cert")
+        .or_else(|| profile.tls_client_cert.clone().map(profile::expand_home));
+    let key = args
+        .remove("--tls-client-key")
+        .or_else(|| profile.tls_client_key.clone().map(profile::expand_home));
+    match (cert, key) {
+        (Some(cert), Some(key)) => Ok(Some(ClientIdentity {
+            cert: fs::read_to_string(cert)?,
+            key: fs::read_to_string(key)?,
+        })),
+        (None, None) => Ok(None),
+        _ => Err(CliError::ArgsErr(
+            "`--tls-client-cert` and `--tls-client-key` must be set together".into(),
+        )),
     }
 }
 
@@ -0,0 +1,356 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    crate::query::IsConnection,
+    rustyline::{
+        completion::{Completer, Pair},
+        highlight::Highlighter,
+        hint::Hinter,
+        validate::Validator,
+        Context, Helper,
+    },
+    skytable::{
+        response::{Response, Value},
+        Query,
+    },
+    std::collections::BTreeMap,
+};
+
+/// BlueQL keywords (and a few other words that show up in statements)
+const KEYWORDS: &[&str] = &[
+    // statements
+    "sysctl",
+    "create",
+    "alter",
+    "drop",
+    "use",
+    "inspect",
+    "describe",
+    "insert",
+    "select",
+    "update",
+    "delete",
+    "exists",
+    "explain",
+    // misc
+    "table",
+    "model",
+    "space",
+    "index",
+    "type",
+    "function",
+    "rename",
+    "add",
+    "remove",
+    "transform",
+    "set",
+    "return",
+    "returning",
+    "order",
+    "sort",
+    "group",
+    "limit",
+    "offset",
+    "asc",
+    "desc",
+    "all",
+    "by",
+    "with",
+    "on",
+    "from",
+    "into",
+    "as",
+    "to",
+    "in",
+    "of",
+    "for",
+    "and",
+    "or",
+    "not",
+    "if",
+    "else",
+    "where",
+    "contains",
+    "like",
+    "within",
+    "when",
+    "allow",
+    "auto",
+    "default",
+    "null",
+    "transaction",
+    "batch",
+    "lock",
+    "read",
+    "write",
+    "begin",
+    "end",
+    "key",
+    "value",
+    "values",
+    "primary",
+    "truncate",
+    // not keywords, but just as common
+    "global",
+    "models",
+    "bool",
+    "uint8",
+    "uint16",
+    "uint32",
+    "uint64",
+    "sint8",
+    "sint16",
+    "sint32",
+    "sint64",
+    "float32",
+    "float64",
+    "binary",
+    "string",
+    "list",
+    "timestamp",
+    "sint128",
+    "geopoint",
+    "dict",
+    "fulltext",
+    "schema",
+];
+
+#[derive(Debug, Default)]
+/// The spaces, models and fields that the user can see
+pub struct Schema {
+    /// space -> models
+    spaces: BTreeMap<String, Vec<String>>,
+    /// `space.model` -> fields
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+impl Schema {
+    /// Fetch the schema from the server. Anything that we fail to fetch (for example, because the user can't see
+    /// it) is skipped
+    pub fn fetch<C: IsConnection>(con: &mut C) -> Self {
+        let mut me = Self::default();
+        if let Ok(Response::Value(Value::String(global))) =
+            con.execute_query(Query::new("inspect global"))
+        {
+            for space in parse_spaces(&global) {
+                me.spaces.entry(space).or_default();
+            }
+        }
+        let mut models = vec![];
+        if let Ok(Response::Rows(rows)) = con.execute_query(Query::new("inspect models")) {
+            for row in rows {
+                let mut columns = row.into_values().into_iter();
+                if let (Some(Value::String(space)), Some(Value::String(model))) =
+                    (columns.next(), columns.next())
+                {
+                    models.push((space, model));
+                }
+            }
+        }
+        for (space, model) in models {
+            let entity = format!("{space}.{model}");
+            if let Ok(Response::Row(row)) =
+                con.execute_query(Query::new(&format!("inspect model {entity}")))
+            {
+                // the last column has the fields, as `(name, type, nullable)`
+                if let Some(Value::List(fields)) = row.into_values().into_iter().nth(5) {
+                    let fields = fields
+                        .into_iter()
+                        .filter_map(|field| match field {
+                            Value::List(field) => match field.into_iter().next() {
+                                Some(Value::String(name)) => Some(name),
+                                _ => None,
+                            },
+                            _ => None,
+                        })
+                        .collect();
+                    me.fields.insert(entity, fields);
+                }
+            }
+            me.spaces.entry(space).or_default().push(model);
+        }
+        me
+    }
+}
+
+/// Returns the spaces listed in the response to `inspect global`
+fn parse_spaces(global: &str) -> Vec<String> {
+    let Some((_, spaces)) = global.split_once("\"spaces\":[") else {
+        return vec![];
+    };
+    let spaces = spaces.split(']').next().unwrap_or_default();
+    spaces
+        .split(',')
+        .map(|space| space.trim_matches('"'))
+        .filter(|space| !space.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Returns true if the statement can change the schema (in which case it should be fetched again)
+pub fn is_ddl(statement: &str) -> bool {
+    match statement.split_ascii_whitespace().next() {
+        Some(stmt) => ["create", "alter", "drop"]
+            .iter()
+            .any(|ddl| stmt.eq_ignore_ascii_case(ddl)),
+        None => false,
+    }
+}
+
+/// Completes keywords, and space, model and field names (depending on where the cursor is)
+pub struct SkyshHelper {
+    schema: Schema,
+    space: Option<String>,
+}
+
+impl SkyshHelper {
+    pub fn new(schema: Schema) -> Self {
+        Self {
+            schema,
+            space: None,
+        }
+    }
+    pub fn set_schema(&mut self, schema: Schema) {
+        self.schema = schema;
+    }
+    /// The space that we're using (if any), which is where models are looked up if they don't have a space
+    pub fn set_space(&mut self, space: Option<String>) {
+        self.space = space;
+    }
+    fn candidates(&self, line: &str, before: &str, word: &str) -> Vec<Pair> {
+        let mut ret = vec![];
+        let mut push = |candidate: &str| {
+            if candidate.starts_with(word) && candidate != word {
+                ret.push(Pair {
+                    display: candidate.to_owned(),
+                    replacement: candidate.to_owned(),
+                });
+            }
+        };
+        if word.contains('.') {
+            // an entity
+            self.schema
+                .spaces
+                .iter()
+                .flat_map(|(space, models)| models.iter().map(move |model| (space, model)))
+                .for_each(|(space, model)| push(&format!("{space}.{model}")));
+            return ret;
+        }
+        let previous = before
+            .split(|c: char| c.is_ascii_whitespace() || c == '(')
+            .rfind(|token| !token.is_empty())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match previous.as_str() {
+            "use" | "space" => {
+                if previous == "use" {
+                    ["null", "$current"].into_iter().for_each(&mut push);
+                }
+                self.schema.spaces.keys().for_each(|space| push(space));
+            }
+            "from" | "into" | "model" | "update" => {
+                if let Some(models) = self.space.as_ref().and_then(|s| self.schema.spaces.get(s)) {
+                    models.iter().for_each(|model| push(model));
+                }
+                self.schema
+                    .spaces
+                    .keys()
+                    .for_each(|space| push(&format!("{space}.")));
+            }
+            _ => {
+                self.entities_in(line)
+                    .filter_map(|entity| self.schema.fields.get(&entity))
+                    .flatten()
+                    .for_each(|field| push(field));
+                let upper = !word.is_empty() && word.bytes().all(|b| !b.is_ascii_lowercase());
+                KEYWORDS.iter().for_each(|kw| {
+                    if upper {
+                        push(&kw.to_ascii_uppercase())
+                    } else {
+                        push(kw)
+                    }
+                });
+            }
+        }
+        ret
+    }
+    /// Returns the (fully qualified) models that the statement uses
+    fn entities_in<'a>(&'a self, line: &'a str) -> impl Iterator<Item = String> + 'a {
+        let mut tokens = line
+            .split(|c: char| c.is_ascii_whitespace() || c == '(')
+            .filter(|token| !token.is_empty())
+            .peekable();
+        core::iter::from_fn(move || loop {
+            let token = tokens.next()?;
+            if ["from", "into", "model", "update"]
+                .iter()
+                .any(|kw| token.eq_ignore_ascii_case(kw))
+            {
+                match tokens.peek() {
+                    Some(entity) if entity.contains('.') => return Some(entity.to_string()),
+                    Some(model) => match self.space {
+                        Some(ref space) => return Some(format!("{space}.{model}")),
+                        None => continue,
+                    },
+                    None => return None,
+                }
+            }
+        })
+    }
+}
+
+impl Completer for SkyshHelper {
+    type Candidate = Pair;
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !(c.is_ascii_alphanumeric() || *c == '_' || *c == '.' || *c == '$'))
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+        Ok((
+            start,
+            self.candidates(line, &before[..start], &before[start..]),
+        ))
+    }
+}
+
+impl Hinter for SkyshHelper {
+    type Hint = String;
+}
+
+impl Highlighter for SkyshHelper {}
+
+impl Validator for SkyshHelper {}
+
+impl Helper for SkyshHelper {}
@@ -32,10 +32,13 @@ macro_rules! fatal {
 }
 
 mod args;
+mod complete;
 mod error;
+mod profile;
 mod query;
 mod repl;
 mod resp;
+mod script;
 
 use args::Task;
 
@@ -50,6 +53,11 @@ fn run() -> error::CliResult<()> {
     match args::parse()? {
         Task::HelpMessage(msg) => println!("{msg}"),
         Task::OpenShell(cfg) => repl::start(cfg)?,
+        Task::RunScript(cfg, script) => {
+            if !script::start(cfg, script)? {
+                std::process::exit(0x01);
+            }
+        }
     }
     Ok(())
 }
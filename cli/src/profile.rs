@@ -0,0 +1,145 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Connection profiles, which are kept in `~/.skysh/config.toml`:
+//!
+//! ```toml
+//! [profiles.prod]
+//! host = "db.example.com"
+//! port = 2004
+//! # the connection uses TLS if a certificate is set
+//! tls_cert = "~/certs/prod.pem"
+//! # a client certificate (and its key) for servers that require one
+//! tls_client_cert = "~/certs/skysh.pem"
+//! tls_client_key = "~/certs/skysh.key"
+//! user = "admin"
+//! ```
+//!
+//! The `default` profile (if there is one) is used when no profile is selected. Passwords can't be stored in a profile;
+//! see [`PASSWORD_ENV`] instead
+
+use {
+    crate::error::{CliError, CliResult},
+    serde::Deserialize,
+    std::{collections::HashMap, env, fs, io::ErrorKind, path::PathBuf},
+    toml::{Table, Value},
+};
+
+/// The password is read from this environment variable if it isn't passed as an argument
+pub const PASSWORD_ENV: &str = "SKYSH_PASSWORD";
+/// The profile that is used if none is selected
+const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    /// the path to the certificate, if TLS is to be used
+    pub tls_cert: Option<String>,
+    /// the path to the client certificate, if the server requires one
+    pub tls_client_cert: Option<String>,
+    /// the path to the private key of the client certificate
+    pub tls_client_key: Option<String>,
+    pub user: Option<String>,
+}
+
+/// Returns the path to the config file (or [`None`] if we don't know where the home directory is)
+fn config_path() -> Option<PathBuf> {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".skysh").join("config.toml"))
+}
+
+/// Expand a leading `~` in a path (from the config file) to the home directory
+pub fn expand_home(path: String) -> String {
+    match (
+        path.strip_prefix("~/"),
+        env::var("HOME").or_else(|_| env::var("USERPROFILE")),
+    ) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path,
+    }
+}
+
+/// Load the given profile, or the default profile if there is one (and no profile was selected)
+pub fn load(name: Option<&str>) -> CliResult<Option<Profile>> {
+    let Some(path) = config_path() else {
+        return match name {
+            Some(_) => Err(CliError::ArgsErr(
+                "can't find the config file since the home directory is unknown".into(),
+            )),
+            None => Ok(None),
+        };
+    };
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) if e.kind() == ErrorKind::NotFound && name.is_none() => return Ok(None),
+        Err(e) => {
+            return Err(CliError::ArgsErr(format!(
+                "failed to read `{}`. {e}",
+                path.display()
+            )))
+        }
+    };
+    let mut profiles = parse(&source)
+        .map_err(|e| CliError::ArgsErr(format!("bad config file `{}`. {e}", path.display())))?;
+    match name {
+        Some(name) => match profiles.remove(name) {
+            Some(profile) => Ok(Some(profile)),
+            None => Err(CliError::ArgsErr(format!(
+                "no profile named `{name}` in `{}`",
+                path.display()
+            ))),
+        },
+        None => Ok(profiles.remove(DEFAULT_PROFILE)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+/// Parse the config file, returning the profiles (by name)
+fn parse(source: &str) -> Result<HashMap<String, Profile>, String> {
+    let config: Table = source.parse().map_err(|e: toml::de::Error| e.to_string())?;
+    // this is an easy mistake to make, so it gets an error of its own
+    if let Some(Value::Table(profiles)) = config.get("profiles") {
+        for (name, profile) in profiles {
+            if matches!(profile, Value::Table(profile) if profile.contains_key("password")) {
+                return Err(format!(
+                    "passwords can't be stored in a profile (`{name}`). use {PASSWORD_ENV} instead"
+                ));
+            }
+        }
+    }
+    Value::Table(config)
+        .try_into::<ConfigFile>()
+        .map(|config| config.profiles)
+        .map_err(|e| e.to_string())
+}
@@ -25,9 +25,13 @@
 */
 
 use {
-    crate::error::{CliError, CliResult},
+    crate::{
+        args::ClientIdentity,
+        error::{CliError, CliResult},
+    },
     skytable::{
-        error::ClientResult, query::SQParam, response::Response, Connection, ConnectionTls, Query,
+        error::ClientResult, query::SQParam, response::Response, Config, Connection, ConnectionTls,
+        Query,
     },
 };
 
@@ -47,6 +51,18 @@ impl IsConnection for ConnectionTls {
     }
 }
 
+/// Connect over TLS (trusting `cert`), presenting the client certificate if one is set
+pub fn connect_tls(
+    config: &Config,
+    cert: &str,
+    identity: Option<&ClientIdentity>,
+) -> ClientResult<ConnectionTls> {
+    match identity {
+        Some(identity) => config.connect_tls_with_identity(cert, &identity.cert, &identity.key),
+        None => config.connect_tls(cert),
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum Item {
     UInt(u64),
@@ -68,6 +84,36 @@ impl SQParam for Item {
     }
 }
 
+/// Returns true if the statement isn't complete yet because a string (or binary) literal wasn't terminated, or because
+/// a bracket, brace or parenthesis wasn't closed. Extra closing brackets are left for the server to complain about
+pub fn is_incomplete(q: &str) -> bool {
+    let q = q.as_bytes();
+    let mut open = 0usize;
+    let mut i = 0;
+    while i < q.len() {
+        match q[i] {
+            b'(' | b'[' | b'{' => open += 1,
+            b')' | b']' | b'}' => open = open.saturating_sub(1),
+            quote_style @ (b'"' | b'\'' | b'`') => {
+                i += 1;
+                while i < q.len() && q[i] != quote_style {
+                    // binary literals don't have escapes
+                    if q[i] == b'\\' && quote_style != b'`' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if i >= q.len() {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    open != 0
+}
+
 pub struct Parameterizer {
     buf: Vec<u8>,
     i: usize,
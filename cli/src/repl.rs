@@ -29,44 +29,104 @@ use crate::query::ExecKind;
 use {
     crate::{
         args::{ClientConfig, ClientConfigKind},
+        complete::{self, Schema, SkyshHelper},
         error::{CliError, CliResult},
         query::{self, IsConnection},
-        resp,
+        resp::{self, Format},
     },
     crossterm::{cursor, execute, terminal},
-    rustyline::{config::Configurer, error::ReadlineError, DefaultEditor},
-    skytable::Config,
-    std::io::{stdout, ErrorKind},
+    rustyline::{config::Configurer, error::ReadlineError, history::DefaultHistory, Editor},
+    skytable::{
+        error::{ClientResult, Error},
+        response::Response,
+        Config, Query,
+    },
+    std::{
+        io::{stdout, ErrorKind},
+        thread,
+        time::{Duration, Instant},
+    },
 };
 
 const SKYSH_HISTORY_FILE: &str = ".sky_history";
+/// the prompt for the lines that follow the first line of a statement
+const SKYSH_BLANK: &str = "     > ";
 const TXT_WELCOME: &str = include_str!("../help_text/welcome");
+/// the number of times we try to connect again after losing the connection
+const RECONNECT_ATTEMPTS: u32 = 8;
+/// the delay before the first attempt (doubled after every attempt)
+const RECONNECT_DELAY: Duration = Duration::from_millis(250);
+/// the longest that we wait between two attempts
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(8);
 
 pub fn start(cfg: ClientConfig) -> CliResult<()> {
     match cfg.kind {
         ClientConfigKind::Tcp(host, port) => {
-            let c = Config::new(&host, port, &cfg.username, &cfg.password).connect()?;
+            let config = Config::new(&host, port, &cfg.username, &cfg.password);
+            let c = config.connect()?;
             println!(
                 "Authenticated as '{}' on {}:{} over Skyhash/TCP\n---",
                 &cfg.username, &host, &port
             );
-            repl(c)
+            repl(c, || config.connect(), cfg.format)
         }
-        ClientConfigKind::Tls(host, port, cert) => {
-            let c = Config::new(&host, port, &cfg.username, &cfg.password).connect_tls(&cert)?;
+        ClientConfigKind::Tls(host, port, cert, identity) => {
+            let config = Config::new(&host, port, &cfg.username, &cfg.password);
+            let c = query::connect_tls(&config, &cert, identity.as_ref())?;
             println!(
                 "Authenticated as '{}' on {}:{} over Skyhash/TLS\n---",
                 &cfg.username, &host, &port
             );
-            repl(c)
+            repl(
+                c,
+                || query::connect_tls(&config, &cert, identity.as_ref()),
+                cfg.format,
+            )
+        }
+    }
+}
+
+/// The state of a shell session (set with `use` or with the `!` commands)
+pub struct Session {
+    /// how responses are printed
+    pub format: Format,
+    /// the space that was switched to with `use` (if any)
+    pub space: Option<String>,
+    /// if set, the round trip time of every statement is printed after its response
+    pub timing: bool,
+}
+
+impl Session {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            space: None,
+            timing: false,
         }
     }
+    fn prompt(&self) -> String {
+        match &self.space {
+            Some(space) => format!("{space}> "),
+            None => "> ".into(),
+        }
+    }
+    fn print(&self) {
+        println!("space = {}", self.space.as_deref().unwrap_or("null"));
+        println!("format = {}", self.format.as_str());
+        println!("timing = {}", if self.timing { "on" } else { "off" });
+    }
 }
 
-fn repl<C: IsConnection>(mut con: C) -> CliResult<()> {
+/// Run the shell. `connect` opens a new (authenticated) connection, and is used when we need to reconnect
+fn repl<C: IsConnection>(
+    mut con: C,
+    connect: impl Fn() -> ClientResult<C>,
+    format: Format,
+) -> CliResult<()> {
     let init_editor = || {
-        let mut editor = DefaultEditor::new()?;
-        editor.set_auto_add_history(true);
+        let mut editor = Editor::<SkyshHelper, DefaultHistory>::new()?;
+        // statements can span multiple lines, so we add them to the history ourselves
+        editor.set_auto_add_history(false);
         editor.set_history_ignore_dups(true)?;
         editor.bind_sequence(
             rustyline::KeyEvent(
@@ -93,52 +153,119 @@ fn repl<C: IsConnection>(mut con: C) -> CliResult<()> {
         Ok(e) => e,
         Err(e) => fatal!("error: failed to init REPL. {e}"),
     };
-    let mut prompt = "> ".to_owned();
+    editor.set_helper(Some(SkyshHelper::new(Schema::fetch(&mut con))));
+    let mut session = Session::new(format);
+    let mut prompt = session.prompt();
+    // the lines of a statement that isn't complete yet
+    let mut statement = String::new();
     loop {
-        match editor.readline(&prompt) {
-            Ok(line) => match line.as_str() {
-                "!help" => println!("{TXT_WELCOME}"),
-                "exit" => break,
-                "clear" => clear_screen()?,
-                _ => {
-                    if line.is_empty() {
-                        continue;
+        let current_prompt = if statement.is_empty() {
+            prompt.as_str()
+        } else {
+            SKYSH_BLANK
+        };
+        match editor.readline(current_prompt) {
+            Ok(line) if !statement.is_empty() => {
+                statement.push('\n');
+                statement.push_str(&line);
+                if query::is_incomplete(&statement) {
+                    continue;
+                }
+                let _ = editor.add_history_entry(statement.as_str());
+                let statement = core::mem::take(&mut statement);
+                run(
+                    &mut con,
+                    &connect,
+                    &mut editor,
+                    statement,
+                    &mut session,
+                    &mut prompt,
+                )?;
+            }
+            Ok(line) => {
+                if !line.is_empty() {
+                    let _ = editor.add_history_entry(line.as_str());
+                }
+                match line.as_str() {
+                    "!help" => println!("{TXT_WELCOME}"),
+                    "exit" => break,
+                    "clear" => clear_screen()?,
+                    "!format" => println!("current format: {}", session.format.as_str()),
+                    _ if line.starts_with("!format ") => {
+                        set_format(&mut session, &line["!format ".len()..])
                     }
-                    match query::Parameterizer::new(line).parameterize() {
-                        Ok(q) => {
-                            let mut new_prompt = None;
-                            let mut special = false;
-                            let q = match q {
-                                ExecKind::Standard(q) => q,
-                                ExecKind::UseNull(q) => {
-                                    new_prompt = Some("> ".into());
-                                    q
-                                }
-                                ExecKind::UseSpace(q, space) => {
-                                    new_prompt = Some(format!("{space}> "));
-                                    q
-                                }
-                                ExecKind::PrintSpecial(q) => {
-                                    special = true;
-                                    q
-                                }
-                            };
-                            if resp::format_response(con.execute_query(q)?, special) {
-                                if let Some(pr) = new_prompt {
-                                    prompt = pr;
+                    "!timing" => {
+                        println!("timing is {}", if session.timing { "on" } else { "off" })
+                    }
+                    _ if line.starts_with("!timing ") => {
+                        set_timing(&mut session, &line["!timing ".len()..])
+                    }
+                    "!reconnect" => {
+                        if let Some(new_con) = reconnect(&connect, &mut session) {
+                            con = new_con;
+                            println!("reconnected");
+                        }
+                        prompt = session.prompt();
+                        if let Some(helper) = editor.helper_mut() {
+                            helper.set_space(session.space.clone());
+                            helper.set_schema(Schema::fetch(&mut con));
+                        }
+                    }
+                    "!set" => session.print(),
+                    _ if line.starts_with("!set ") => {
+                        let mut args = line["!set ".len()..].split_whitespace();
+                        match (args.next(), args.next(), args.next()) {
+                            (Some("space"), Some(space), None) => {
+                                if is_ident(space) {
+                                    // let the server switch the space, so that statements are qualified exactly like
+                                    // they are after a `use`
+                                    run(
+                                        &mut con,
+                                        &connect,
+                                        &mut editor,
+                                        format!("use {space}"),
+                                        &mut session,
+                                        &mut prompt,
+                                    )?;
+                                } else {
+                                    eprintln!("[skysh error]: bad space name `{space}`");
                                 }
                             }
-                        }
-                        Err(e) => match e {
-                            CliError::QueryError(e) => {
-                                eprintln!("[skysh error]: bad query. {e}");
-                                continue;
+                            (Some("format"), Some(format), None) => {
+                                set_format(&mut session, format)
                             }
-                            _ => return Err(e),
-                        },
-                    };
+                            (Some("timing"), Some(timing), None) => {
+                                set_timing(&mut session, timing)
+                            }
+                            _ => eprintln!(
+                                "[skysh error]: expected `!set space <name>|null`, `!set format <format>` or `!set timing on|off`"
+                            ),
+                        }
+                    }
+                    _ => {
+                        if line.is_empty() {
+                            continue;
+                        }
+                        if query::is_incomplete(&line) {
+                            // keep reading lines until the statement is complete
+                            statement = line;
+                            continue;
+                        }
+                        run(
+                            &mut con,
+                            &connect,
+                            &mut editor,
+                            line,
+                            &mut session,
+                            &mut prompt,
+                        )?;
+                    }
                 }
-            },
+            }
+            Err(ReadlineError::Interrupted) if !statement.is_empty() => {
+                // only discard the statement that we were reading
+                statement.clear();
+            }
             Err(e) => match e {
                 ReadlineError::Interrupted | ReadlineError::Eof => {
                     // done
@@ -156,6 +283,157 @@ fn repl<C: IsConnection>(mut con: C) -> CliResult<()> {
     Ok(())
 }
 
+/// Run a complete statement, and then update the prompt and the completer (if the statement switched the space or
+/// changed the schema). If the connection was lost, we reconnect and run the statement once more
+fn run<C: IsConnection>(
+    con: &mut C,
+    connect: &impl Fn() -> ClientResult<C>,
+    editor: &mut Editor<SkyshHelper, DefaultHistory>,
+    statement: String,
+    session: &mut Session,
+    prompt: &mut String,
+) -> CliResult<()> {
+    let ddl = complete::is_ddl(&statement);
+    match run_statement(con, statement.clone(), session, true) {
+        Err(CliError::ClientError(Error::IoError(e))) => {
+            eprintln!("[skysh error]: lost the connection to the server. {e}");
+            if let Some(new_con) = reconnect(connect, session) {
+                *con = new_con;
+                run_statement(con, statement, session, true)?;
+            }
+        }
+        ret => {
+            ret?;
+        }
+    }
+    *prompt = session.prompt();
+    if let Some(helper) = editor.helper_mut() {
+        helper.set_space(session.space.clone());
+        if ddl {
+            helper.set_schema(Schema::fetch(con));
+        }
+    }
+    Ok(())
+}
+
+/// Run a complete statement and print the response (using a pager if `paged` is set). The session's space is updated
+/// if the statement switched the space. Returns false if the statement failed
+pub fn run_statement<C: IsConnection>(
+    con: &mut C,
+    statement: String,
+    session: &mut Session,
+    paged: bool,
+) -> CliResult<bool> {
+    match query::Parameterizer::new(statement).parameterize() {
+        Ok(q) => {
+            let mut new_space = None;
+            let mut special = false;
+            let q = match q {
+                ExecKind::Standard(q) => q,
+                ExecKind::UseNull(q) => {
+                    new_space = Some(None);
+                    q
+                }
+                ExecKind::UseSpace(q, space) => {
+                    new_space = Some(Some(space));
+                    q
+                }
+                ExecKind::PrintSpecial(q) => {
+                    special = true;
+                    q
+                }
+            };
+            let start = Instant::now();
+            let resp = con.execute_query(q)?;
+            // the protocol doesn't tell us how long the server spent on the query, so this is the full round trip
+            let elapsed = start.elapsed();
+            let okay = resp::format_response(resp, special, session.format, paged);
+            if session.timing {
+                println!(
+                    "Time: {:.3} ms (round trip)",
+                    elapsed.as_secs_f64() * 1000.0
+                );
+            }
+            if okay {
+                if let Some(new_space) = new_space {
+                    session.space = new_space;
+                }
+            }
+            Ok(okay)
+        }
+        Err(CliError::QueryError(e)) => {
+            eprintln!("[skysh error]: bad query. {e}");
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Open a new connection (backing off exponentially between attempts) and switch to the session's space again.
+/// Returns [`None`] if we couldn't reconnect (the session is left as is, so that the user can try again with
+/// `!reconnect`)
+fn reconnect<C: IsConnection>(
+    connect: &impl Fn() -> ClientResult<C>,
+    session: &mut Session,
+) -> Option<C> {
+    let mut delay = RECONNECT_DELAY;
+    let mut attempt = 1;
+    let mut con = loop {
+        eprintln!("[skysh]: reconnecting (attempt {attempt} of {RECONNECT_ATTEMPTS}) ...");
+        match connect() {
+            Ok(con) => break con,
+            // the server is (probably) still starting up
+            Err(Error::IoError(e)) if attempt < RECONNECT_ATTEMPTS => {
+                eprintln!("[skysh error]: failed to connect. {e}");
+                thread::sleep(delay);
+                delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+                attempt += 1;
+            }
+            Err(e) => {
+                eprintln!("[skysh error]: failed to reconnect. {e}. use `!reconnect` to try again");
+                return None;
+            }
+        }
+    };
+    if let Some(space) = &session.space {
+        let restored = matches!(
+            con.execute_query(Query::new(&format!("use {space}"))),
+            Ok(Response::Empty)
+        );
+        if !restored {
+            eprintln!("[skysh error]: failed to switch to space `{space}` after reconnecting");
+            session.space = None;
+        }
+    }
+    Some(con)
+}
+
+fn set_format(session: &mut Session, format: &str) {
+    match Format::from_str(format.trim()) {
+        Some(format) => session.format = format,
+        None => {
+            eprintln!("[skysh error]: unknown format. expected one of `table`, `json` or `csv`")
+        }
+    }
+}
+
+fn set_timing(session: &mut Session, timing: &str) {
+    match timing.trim() {
+        "on" => session.timing = true,
+        "off" => session.timing = false,
+        _ => eprintln!("[skysh error]: expected `on` or `off`"),
+    }
+}
+
+/// Check if `name` can be used as a space name (`null` switches back to no space)
+fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 fn clear_screen() -> std::io::Result<()> {
     let mut stdout = stdout();
     execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
@@ -25,110 +25,403 @@
 */
 
 use {
-    crossterm::style::Stylize,
+    crossterm::{style::Stylize, terminal},
     skytable::response::{Response, Row, Value},
+    std::{
+        fmt::Write as _,
+        io::{self, IsTerminal, Write},
+        process::{Command, Stdio},
+    },
 };
 
-pub fn format_response(resp: Response, print_special: bool) -> bool {
+/// The pager that is used if `PAGER` isn't set
+const DEFAULT_PAGER: &str = "less -FRX";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// How responses are printed
+pub enum Format {
+    /// values as is, and rows as aligned tables
+    Table,
+    /// a JSON value for every response
+    Json,
+    /// a CSV line for every row
+    Csv,
+}
+
+impl Format {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "table" => Some(Self::Table),
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Table => "table",
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// Print the response (using a pager if `paged` is set and it's large). Returns false if it's an error
+pub fn format_response(resp: Response, print_special: bool, format: Format, paged: bool) -> bool {
+    let mut out = String::new();
+    let okay = match format {
+        Format::Table => table_response(&mut out, resp, print_special),
+        Format::Json => json_response(&mut out, resp),
+        Format::Csv => csv_response(&mut out, resp),
+    };
+    if paged {
+        page(out);
+    } else {
+        print(out);
+    }
+    okay
+}
+
+/*
+    table
+*/
+
+fn table_response(out: &mut String, resp: Response, print_special: bool) -> bool {
     match resp {
-        Response::Empty => println!("{}", "(Okay)".cyan()),
+        Response::Empty => {
+            let _ = writeln!(out, "{}", "(Okay)".cyan());
+        }
         Response::Error(e) => {
-            println!("{}", format!("(server error code: {e})").red());
+            let _ = writeln!(out, "{}", format!("(server error code: {e})").red());
             return false;
         }
         Response::Value(v) => {
-            print_value(v, print_special);
-            println!();
-        }
-        Response::Row(r) => {
-            print_row(r);
-            println!();
+            write_value(out, v, print_special, true);
+            out.push('\n');
         }
+        Response::Row(r) => write_table(out, vec![r]),
         Response::Rows(rows) => {
             if rows.is_empty() {
-                println!("{}", "[0 rows returned]".grey().italic());
+                let _ = writeln!(out, "{}", "[0 rows returned]".grey().italic());
             } else {
-                for (i, row) in rows.into_iter().enumerate().map(|(i, r)| (i + 1, r)) {
-                    print!("{} ", format!("({i})").grey().bold());
-                    print_row(row);
-                    println!();
-                }
+                let count = rows.len();
+                write_table(out, rows);
+                let rows = if count == 1 { "row" } else { "rows" };
+                let _ = writeln!(out, "{}", format!("({count} {rows})").grey().italic());
             }
         }
-    };
+    }
     true
 }
 
-fn print_row(r: Row) {
-    print!("(");
-    let mut columns = r.into_values().into_iter().peekable();
-    while let Some(cell) = columns.next() {
-        print_value(cell, false);
-        if columns.peek().is_some() {
-            print!(", ");
+/// Write the rows out as a table, with every column as wide as its widest value
+fn write_table(out: &mut String, rows: Vec<Row>) {
+    let rows: Vec<Vec<String>> = rows
+        .into_iter()
+        .map(|row| {
+            row.into_values()
+                .into_iter()
+                .map(|v| {
+                    let mut cell = String::new();
+                    write_value(&mut cell, v, false, false);
+                    cell
+                })
+                .collect()
+        })
+        .collect();
+    let mut widths: Vec<usize> = vec![];
+    for row in rows.iter() {
+        for (i, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(i) {
+                Some(w) => *w = (*w).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+    let mut border = String::from("+");
+    for width in widths.iter() {
+        border.push_str(&"-".repeat(width + 2));
+        border.push('+');
+    }
+    let _ = writeln!(out, "{border}");
+    for row in rows {
+        out.push('|');
+        for (i, width) in widths.iter().enumerate() {
+            let cell = row.get(i).map(String::as_str).unwrap_or_default();
+            let _ = write!(out, " {cell:<width$} |");
         }
+        out.push('\n');
     }
-    print!(")");
-}
-
-fn print_value(v: Value, print_special: bool) {
-    match v {
-        Value::Null => print!("{}", "null".grey().italic()),
-        Value::String(s) => print_string(&s, print_special),
-        Value::Binary(b) => print_binary(&b),
-        Value::Bool(b) => print!("{b}"),
-        Value::UInt8(i) => print!("{i}"),
-        Value::UInt16(i) => print!("{i}"),
-        Value::UInt32(i) => print!("{i}"),
-        Value::UInt64(i) => print!("{i}"),
-        Value::SInt8(i) => print!("{i}"),
-        Value::SInt16(i) => print!("{i}"),
-        Value::SInt32(i) => print!("{i}"),
-        Value::SInt64(i) => print!("{i}"),
-        Value::Float32(f) => print!("{f}"),
-        Value::Float64(f) => print!("{f}"),
+    let _ = writeln!(out, "{border}");
+}
+
+/// Write the value out the way that it would be written in a query. If `styled` is set, some values (like `null`)
+/// are styled for the terminal
+fn write_value(out: &mut String, v: Value, print_special: bool, styled: bool) {
+    let _ = match v {
+        Value::Null if styled => write!(out, "{}", "null".grey().italic()),
+        Value::Null => write!(out, "null"),
+        Value::String(s) => {
+            write_string(out, &s, print_special, styled);
+            Ok(())
+        }
+        Value::Binary(b) => {
+            write_binary(out, &b);
+            Ok(())
+        }
+        Value::Bool(b) => write!(out, "{b}"),
+        Value::UInt8(i) => write!(out, "{i}"),
+        Value::UInt16(i) => write!(out, "{i}"),
+        Value::UInt32(i) => write!(out, "{i}"),
+        Value::UInt64(i) => write!(out, "{i}"),
+        Value::SInt8(i) => write!(out, "{i}"),
+        Value::SInt16(i) => write!(out, "{i}"),
+        Value::SInt32(i) => write!(out, "{i}"),
+        Value::SInt64(i) => write!(out, "{i}"),
+        Value::Float32(f) => write!(out, "{f}"),
+        Value::Float64(f) => write!(out, "{f}"),
         Value::List(items) => {
-            print!("[");
+            out.push('[');
             let mut items = items.into_iter().peekable();
             while let Some(item) = items.next() {
-                print_value(item, print_special);
+                write_value(out, item, print_special, styled);
                 if items.peek().is_some() {
-                    print!(", ");
+                    out.push_str(", ");
                 }
             }
-            print!("]");
+            out.push(']');
+            Ok(())
         }
-    }
+    };
 }
 
-fn print_binary(b: &[u8]) {
-    let mut it = b.into_iter().peekable();
-    print!("[");
+fn write_binary(out: &mut String, b: &[u8]) {
+    let mut it = b.iter().peekable();
+    out.push('[');
     while let Some(byte) = it.next() {
-        print!("{byte}");
+        let _ = write!(out, "{byte}");
         if it.peek().is_some() {
-            print!(", ");
+            out.push_str(", ");
         }
     }
-    print!("]");
+    out.push(']');
 }
 
-fn print_string(s: &str, print_special: bool) {
+fn write_string(out: &mut String, s: &str, print_special: bool, styled: bool) {
     if print_special {
-        print!("{}", s.italic().grey());
+        if styled {
+            let _ = write!(out, "{}", s.italic().grey());
+        } else {
+            out.push_str(s);
+        }
     } else {
-        print!("\"");
+        out.push('"');
         for ch in s.chars() {
             if ch == '"' {
-                print!("\\{ch}");
+                let _ = write!(out, "\\{ch}");
             } else if ch == '\t' {
-                print!("\\t");
+                out.push_str("\\t");
             } else if ch == '\n' {
-                print!("\\n");
+                out.push_str("\\n");
             } else {
-                print!("{ch}");
+                out.push(ch);
             }
         }
-        print!("\"");
+        out.push('"');
+    }
+}
+
+/*
+    json
+*/
+
+/// Write the response out as a JSON value: `null` if it's empty, an array for a row (and an array of arrays for
+/// rows), and `{"error": {"code": ...}}` for an error
+fn json_response(out: &mut String, resp: Response) -> bool {
+    match resp {
+        Response::Empty => out.push_str("null"),
+        Response::Error(e) => {
+            let _ = writeln!(out, "{{\"error\":{{\"code\":{e}}}}}");
+            return false;
+        }
+        Response::Value(v) => write_json(out, v),
+        Response::Row(r) => write_json(out, Value::List(r.into_values())),
+        Response::Rows(rows) => {
+            out.push('[');
+            let mut rows = rows.into_iter().peekable();
+            while let Some(row) = rows.next() {
+                write_json(out, Value::List(row.into_values()));
+                if rows.peek().is_some() {
+                    out.push(',');
+                }
+            }
+            out.push(']');
+        }
+    }
+    out.push('\n');
+    true
+}
+
+fn write_json(out: &mut String, v: Value) {
+    let _ = match v {
+        Value::Null => write!(out, "null"),
+        Value::String(s) => {
+            write_json_str(out, &s);
+            Ok(())
+        }
+        Value::Binary(b) => {
+            // without the spaces
+            out.push('[');
+            for (i, byte) in b.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                let _ = write!(out, "{byte}");
+            }
+            out.push(']');
+            Ok(())
+        }
+        Value::Bool(b) => write!(out, "{b}"),
+        Value::UInt8(i) => write!(out, "{i}"),
+        Value::UInt16(i) => write!(out, "{i}"),
+        Value::UInt32(i) => write!(out, "{i}"),
+        Value::UInt64(i) => write!(out, "{i}"),
+        Value::SInt8(i) => write!(out, "{i}"),
+        Value::SInt16(i) => write!(out, "{i}"),
+        Value::SInt32(i) => write!(out, "{i}"),
+        Value::SInt64(i) => write!(out, "{i}"),
+        // JSON doesn't have NaN or the infinities
+        Value::Float32(f) if !f.is_finite() => write!(out, "null"),
+        Value::Float64(f) if !f.is_finite() => write!(out, "null"),
+        Value::Float32(f) => write!(out, "{f}"),
+        Value::Float64(f) => write!(out, "{f}"),
+        Value::List(items) => {
+            out.push('[');
+            let mut items = items.into_iter().peekable();
+            while let Some(item) = items.next() {
+                write_json(out, item);
+                if items.peek().is_some() {
+                    out.push(',');
+                }
+            }
+            out.push(']');
+            Ok(())
+        }
+    };
+}
+
+fn write_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\x00'..='\x1f' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/*
+    csv
+*/
+
+/// Write the response out as CSV: a line for every row (or for the value). Nothing is written if the response is
+/// empty, and errors are written to stderr
+fn csv_response(out: &mut String, resp: Response) -> bool {
+    match resp {
+        Response::Empty => {}
+        Response::Error(e) => {
+            eprintln!("(server error code: {e})");
+            return false;
+        }
+        Response::Value(v) => write_csv_line(out, vec![v]),
+        Response::Row(r) => write_csv_line(out, r.into_values()),
+        Response::Rows(rows) => rows
+            .into_iter()
+            .for_each(|row| write_csv_line(out, row.into_values())),
+    }
+    true
+}
+
+fn write_csv_line(out: &mut String, values: Vec<Value>) {
+    for (i, v) in values.into_iter().enumerate() {
+        if i != 0 {
+            out.push(',');
+        }
+        let field = match v {
+            // empty
+            Value::Null => continue,
+            Value::String(s) => s,
+            // lists (and binary values) are written as JSON
+            v @ (Value::List(_) | Value::Binary(_)) => {
+                let mut json = String::new();
+                write_json(&mut json, v);
+                json
+            }
+            v => {
+                let mut field = String::new();
+                write_value(&mut field, v, false, false);
+                field
+            }
+        };
+        if field.contains([',', '"', '\n', '\r']) {
+            out.push('"');
+            out.push_str(&field.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(&field);
+        }
+    }
+    out.push('\n');
+}
+
+/*
+    pager
+*/
+
+/// Print the output, using a pager (`PAGER`, or `less` by default) if it won't fit on the screen. An empty `PAGER`
+/// turns paging off
+fn page(out: String) {
+    if io::stdout().is_terminal() {
+        let rows = terminal::size().map(|(_, rows)| rows as usize).unwrap_or(0);
+        if rows != 0 && out.lines().count() >= rows && run_pager(&out) {
+            return;
+        }
+    }
+    print(out)
+}
+
+fn print(out: String) {
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(out.as_bytes());
+    let _ = stdout.flush();
+}
+
+/// Returns false if the pager couldn't be run
+fn run_pager(out: &str) -> bool {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| DEFAULT_PAGER.into());
+    let mut pager = pager.split_ascii_whitespace();
+    let Some(program) = pager.next() else {
+        return false;
+    };
+    let Ok(mut child) = Command::new(program)
+        .args(pager)
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // the pager might be closed before it reads everything
+        let _ = stdin.write_all(out.as_bytes());
     }
+    child.wait().is_ok()
 }
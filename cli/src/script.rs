@@ -0,0 +1,99 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Run statements from a file (or from stdin) instead of opening the shell. Every line is a statement, unless a
+//! bracket or string is still open at the end of it, in which case the statement continues on the next line (just
+//! like in the shell)
+
+use {
+    crate::{
+        args::{ClientConfig, ClientConfigKind, Script},
+        error::CliResult,
+        query::{self, IsConnection},
+        repl::{self, Session},
+        resp::Format,
+    },
+    skytable::Config,
+};
+
+/// Run the script. Returns false if a statement failed
+pub fn start(cfg: ClientConfig, script: Script) -> CliResult<bool> {
+    match cfg.kind {
+        ClientConfigKind::Tcp(host, port) => {
+            let mut c = Config::new(&host, port, &cfg.username, &cfg.password).connect()?;
+            run(&mut c, &script, cfg.format)
+        }
+        ClientConfigKind::Tls(host, port, cert, identity) => {
+            let config = Config::new(&host, port, &cfg.username, &cfg.password);
+            let mut c = query::connect_tls(&config, &cert, identity.as_ref())?;
+            run(&mut c, &script, cfg.format)
+        }
+    }
+}
+
+/// Run the statements one after the other. We stop at the first statement that fails, unless the script is to be
+/// forced through (in which case we run everything, but still return false)
+fn run<C: IsConnection>(con: &mut C, script: &Script, format: Format) -> CliResult<bool> {
+    let mut session = Session::new(format);
+    let mut okay = true;
+    for (line, statement) in statements(&script.source) {
+        if !repl::run_statement(con, statement, &mut session, false)? {
+            eprintln!("[skysh error]: statement on line {line} failed");
+            okay = false;
+            if !script.force {
+                break;
+            }
+        }
+    }
+    Ok(okay)
+}
+
+/// Returns every statement in the script, along with the line that it starts on
+fn statements(source: &str) -> Vec<(usize, String)> {
+    let mut ret = vec![];
+    let mut statement = String::new();
+    let mut start = 0;
+    for (i, line) in source.lines().enumerate() {
+        if statement.is_empty() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            start = i + 1;
+            statement.push_str(line);
+        } else {
+            statement.push('\n');
+            statement.push_str(line);
+        }
+        if !query::is_incomplete(&statement) {
+            ret.push((start, core::mem::take(&mut statement)));
+        }
+    }
+    if !statement.is_empty() {
+        // let the server complain about it
+        ret.push((start, statement));
+    }
+    ret
+}
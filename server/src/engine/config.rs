@@ -25,10 +25,20 @@
 */
 
 use {
-    crate::engine::{error::RuntimeResult, fractal},
+    crate::engine::{
+        error::RuntimeResult,
+        fractal::{self, BackpressurePolicy},
+        storage::{
+            engine::{IoBackend, StorageEngineKind},
+            v1::{
+                data_batch::RecoveryMode, durability::DurabilityPolicy, encryption::EncryptionKey,
+                spec::BatchCompression,
+            },
+        },
+    },
     core::fmt,
     serde::Deserialize,
-    std::{collections::HashMap, fs},
+    std::{collections::HashMap, fs, path::Path},
 };
 
 /*
@@ -78,6 +88,16 @@ pub struct Configuration {
     pub mode: ConfigMode,
     pub system: ConfigSystem,
     pub auth: ConfigAuth,
+    /// the endpoint to serve metrics on (if any)
+    pub metrics: Option<ConfigEndpointTcp>,
+    /// the endpoint to serve the web bridge (JSON over HTTP and websockets) on (if any)
+    pub web: Option<ConfigEndpointTcp>,
+    /// the RESP adapter (if enabled)
+    pub resp: Option<ConfigResp>,
+    /// the endpoint that replicas connect to (if any)
+    pub replication: Option<ConfigEndpointTcp>,
+    /// the primary that we replicate from (if we're a replica)
+    pub replica_of: Option<ConfigReplicaOf>,
 }
 
 impl Configuration {
@@ -93,11 +113,41 @@ impl Configuration {
             mode,
             system,
             auth,
+            metrics: None,
+            web: None,
+            resp: None,
+            replication: None,
+            replica_of: None,
         }
     }
+    #[cfg(test)]
+    pub fn with_metrics(mut self, metrics: ConfigEndpointTcp) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_web(mut self, web: ConfigEndpointTcp) -> Self {
+        self.web = Some(web);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_resp(mut self, resp: ConfigResp) -> Self {
+        self.resp = Some(resp);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_replication(mut self, replication: ConfigEndpointTcp) -> Self {
+        self.replication = Some(replication);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_replica_of(mut self, primary: ConfigReplicaOf) -> Self {
+        self.replica_of = Some(primary);
+        self
+    }
     const DEFAULT_HOST: &'static str = "127.0.0.1";
     const DEFAULT_PORT_TCP: u16 = 2003;
-    pub fn default_dev_mode(auth: DecodedAuth) -> Self {
+    pub fn default_dev_mode(auth: ConfigAuth) -> Self {
         Self {
             endpoints: ConfigEndpoint::Insecure(ConfigEndpointTcp {
                 host: Self::DEFAULT_HOST.to_owned(),
@@ -105,7 +155,12 @@ impl Configuration {
             }),
             mode: ConfigMode::Dev,
             system: ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
-            auth: ConfigAuth::new(auth.plugin, auth.root_pass),
+            auth,
+            metrics: None,
+            web: None,
+            resp: None,
+            replication: None,
+            replica_of: None,
         }
     }
 }
@@ -120,6 +175,16 @@ pub enum ConfigEndpoint {
     Multi(ConfigEndpointTcp, ConfigEndpointTls),
 }
 
+impl ConfigEndpoint {
+    /// The TLS endpoint (if one is configured)
+    pub fn tls(&self) -> Option<&ConfigEndpointTls> {
+        match self {
+            Self::Insecure(_) => None,
+            Self::Secure(tls) | Self::Multi(_, tls) => Some(tls),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 /// TCP endpoint configuration
 pub struct ConfigEndpointTcp {
@@ -147,6 +212,7 @@ pub struct ConfigEndpointTls {
     cert: String,
     private_key: String,
     pkey_pass: String,
+    client_ca: Option<String>,
 }
 
 impl ConfigEndpointTls {
@@ -156,12 +222,14 @@ impl ConfigEndpointTls {
         cert: String,
         private_key: String,
         pkey_pass: String,
+        client_ca: Option<String>,
     ) -> Self {
         Self {
             tcp,
             cert,
             private_key,
             pkey_pass,
+            client_ca,
         }
     }
     pub fn tcp(&self) -> &ConfigEndpointTcp {
@@ -176,6 +244,54 @@ impl ConfigEndpointTls {
     pub fn pkey_pass(&self) -> &str {
         self.pkey_pass.as_ref()
     }
+    /// The CA certificate(s) used to verify client certificates (if client authentication is enabled)
+    pub fn client_ca(&self) -> Option<&str> {
+        self.client_ca.as_deref()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// RESP adapter configuration
+pub struct ConfigResp {
+    pub endpoint: ConfigEndpointTcp,
+    /// the model that keys are stored in (`space.model`)
+    space: String,
+    model: String,
+}
+
+impl ConfigResp {
+    #[cfg(test)]
+    pub fn new(endpoint: ConfigEndpointTcp, space: String, model: String) -> Self {
+        Self {
+            endpoint,
+            space,
+            model,
+        }
+    }
+    pub fn space(&self) -> &str {
+        self.space.as_ref()
+    }
+    pub fn model(&self) -> &str {
+        self.model.as_ref()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// The primary that a replica replicates from
+pub struct ConfigReplicaOf {
+    pub primary: ConfigEndpointTcp,
+    /// the CA certificate(s) that the primary's TLS certificate must be signed by
+    ca: String,
+}
+
+impl ConfigReplicaOf {
+    #[cfg(test)]
+    pub fn new(primary: ConfigEndpointTcp, ca: String) -> Self {
+        Self { primary, ca }
+    }
+    pub fn ca(&self) -> &str {
+        self.ca.as_ref()
+    }
 }
 
 /*
@@ -202,12 +318,173 @@ pub enum ConfigMode {
 pub struct ConfigSystem {
     /// time window in seconds for the reliability system to kick-in automatically
     pub reliability_system_window: u64,
+    /// the storage engine that keeps the data of models
+    pub storage_engine: StorageEngineKind,
+    /// the backend that the storage engine uses for file I/O
+    pub io_backend: IoBackend,
+    /// the number of tasks that may block on the disk at a time (the number of CPUs if unset)
+    pub io_threads: Option<usize>,
+    /// the compression used for new (and compacted) data batch journals
+    pub batch_compression: BatchCompression,
+    /// when data batch journals and the GNS log are synced to disk
+    pub durability: DurabilityPolicy,
+    /// what happens to a data batch journal that wasn't closed (because we crashed) on startup
+    pub recovery_mode: RecoveryMode,
+    /// what happens to writes to a model that has too many unflushed deltas
+    pub backpressure: BackpressurePolicy,
+    /// the number of unflushed deltas at which a model's writes are held back (no limit if unset)
+    pub backpressure_limit: Option<usize>,
+    /// the memory budget (in bytes) shared by the row caches of all models (rows aren't cached if unset)
+    pub row_cache_size: Option<usize>,
+    /// the estimated memory (in bytes) that all models together may use before writes are rejected (no limit if unset)
+    pub memory_limit: Option<usize>,
+    /// the estimated memory (in bytes) that a single model may use before its writes are rejected (no limit if unset)
+    pub model_memory_limit: Option<usize>,
+    /// the time (in milliseconds) a query may run for before it's cancelled (no limit if unset)
+    pub query_timeout: Option<u64>,
+    /// the time (in seconds) a client connection may send nothing for before it's closed (kept open if unset)
+    pub idle_timeout: Option<u64>,
+    /// the master key that new files are encrypted with (files aren't encrypted if unset)
+    pub encryption_key: Option<EncryptionKey>,
+    /// the directory that models are exported into (exports are disabled if unset)
+    pub export_dir: Option<String>,
 }
 
 impl ConfigSystem {
     pub fn new(reliability_system_window: u64) -> Self {
         Self {
             reliability_system_window,
+            storage_engine: StorageEngineKind::Journal,
+            io_backend: IoBackend::Std,
+            io_threads: None,
+            batch_compression: BatchCompression::None,
+            durability: DurabilityPolicy::Always,
+            recovery_mode: RecoveryMode::Strict,
+            backpressure: BackpressurePolicy::Stall,
+            backpressure_limit: None,
+            row_cache_size: None,
+            memory_limit: None,
+            model_memory_limit: None,
+            query_timeout: None,
+            idle_timeout: None,
+            encryption_key: None,
+            export_dir: None,
+        }
+    }
+    #[cfg(test)]
+    pub fn with_batch_compression(mut self, batch_compression: BatchCompression) -> Self {
+        self.batch_compression = batch_compression;
+        self
+    }
+    #[cfg(test)]
+    pub fn with_durability(mut self, durability: DurabilityPolicy) -> Self {
+        self.durability = durability;
+        self
+    }
+    #[cfg(test)]
+    pub fn with_backpressure(mut self, backpressure: BackpressurePolicy, limit: usize) -> Self {
+        self.backpressure = backpressure;
+        self.backpressure_limit = Some(limit);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_row_cache_size(mut self, size: usize) -> Self {
+        self.row_cache_size = Some(size);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_memory_limits(mut self, global: Option<usize>, model: Option<usize>) -> Self {
+        self.memory_limit = global;
+        self.model_memory_limit = model;
+        self
+    }
+    #[cfg(test)]
+    pub fn with_query_timeout(mut self, timeout: u64) -> Self {
+        self.query_timeout = Some(timeout);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_idle_timeout(mut self, timeout: u64) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_export_dir(mut self, dir: &str) -> Self {
+        self.export_dir = Some(dir.into());
+        self
+    }
+}
+
+/*
+    config recovery
+*/
+
+#[derive(Debug, PartialEq, Default)]
+/// One-off recovery actions that must run before the storage engine is loaded
+pub struct ConfigRecovery {
+    /// restore the backup at this path
+    pub restore: Option<String>,
+    /// recover models to a point in time
+    pub until: Vec<ConfigRecoverUntil>,
+}
+
+impl ConfigRecovery {
+    pub fn is_empty(&self) -> bool {
+        self.restore.is_none() & self.until.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// Recover a model to the state it was in right after the given txn id
+pub struct ConfigRecoverUntil {
+    space: String,
+    model: String,
+    txn_id: u64,
+}
+
+impl ConfigRecoverUntil {
+    pub fn new(space: String, model: String, txn_id: u64) -> Self {
+        Self {
+            space,
+            model,
+            txn_id,
+        }
+    }
+    /// Parse a recovery target in the format: `space.model@txn_id`
+    fn parse(target: &str) -> Option<Self> {
+        let (entity, txn_id) = target.rsplit_once('@')?;
+        let (space, model) = entity.split_once('.')?;
+        if space.is_empty() | model.is_empty() {
+            return None;
+        }
+        Some(Self::new(space.into(), model.into(), txn_id.parse().ok()?))
+    }
+    pub fn space(&self) -> &str {
+        &self.space
+    }
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+    pub fn txn_id(&self) -> u64 {
+        self.txn_id
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// Check (and optionally repair) the database files instead of starting up
+pub enum ConfigRepairMode {
+    /// only report what's corrupted
+    Check,
+    /// truncate any file that can't be read to the end to its last consistent point
+    Truncate,
+}
+
+impl ConfigRepairMode {
+    fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "check" => Some(Self::Check),
+            "truncate" => Some(Self::Truncate),
+            _ => None,
         }
     }
 }
@@ -226,11 +503,27 @@ pub enum AuthDriver {
 pub struct ConfigAuth {
     pub plugin: AuthDriver,
     pub root_key: String,
+    /// the minimum length for the passwords of (non-root) users
+    pub password_min_len: usize,
+    /// the cost used to hash passwords
+    pub hash_cost: u32,
+    /// if set, the root key is only used to bootstrap the root account on first boot, and must be changed (with
+    /// `sysctl alter user root`) before any DDL can be run
+    pub root_rotate: bool,
 }
 
 impl ConfigAuth {
+    pub const DEFAULT_PASSWORD_MIN_LEN: usize = 8;
+    /// the range of costs accepted by bcrypt
+    pub const HASH_COST_RANGE: core::ops::RangeInclusive<u32> = 4..=31;
     pub fn new(plugin: AuthDriver, root_key: String) -> Self {
-        Self { plugin, root_key }
+        Self {
+            plugin,
+            root_key,
+            password_min_len: Self::DEFAULT_PASSWORD_MIN_LEN,
+            hash_cost: rcrypt::DEFAULT_COST,
+            root_rotate: false,
+        }
     }
 }
 
@@ -259,7 +552,11 @@ impl Default for DecodedConfiguration {
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct DecodedAuth {
     plugin: AuthDriver,
-    root_pass: String,
+    root_pass: Option<String>,
+    root_pass_file: Option<String>,
+    password_min_length: Option<usize>,
+    hash_cost: Option<u32>,
+    root_rotate: Option<bool>,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -267,6 +564,23 @@ pub struct DecodedAuth {
 pub struct DecodedSystemConfig {
     mode: Option<ConfigMode>,
     rs_window: Option<u64>,
+    storage_engine: Option<StorageEngineKind>,
+    io_backend: Option<IoBackend>,
+    io_threads: Option<usize>,
+    batch_compression: Option<BatchCompression>,
+    durability: Option<String>,
+    recovery_mode: Option<RecoveryMode>,
+    backpressure: Option<BackpressurePolicy>,
+    backpressure_limit: Option<usize>,
+    row_cache_size: Option<usize>,
+    memory_limit: Option<usize>,
+    model_memory_limit: Option<usize>,
+    query_timeout: Option<u64>,
+    idle_timeout: Option<u64>,
+    replica_of: Option<String>,
+    replica_ca: Option<String>,
+    encryption_key_file: Option<String>,
+    export_dir: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -274,6 +588,10 @@ pub struct DecodedSystemConfig {
 pub struct DecodedEPConfig {
     secure: Option<DecodedEPSecureConfig>,
     insecure: Option<DecodedEPInsecureConfig>,
+    metrics: Option<DecodedEPInsecureConfig>,
+    web: Option<DecodedEPInsecureConfig>,
+    resp: Option<DecodedEPRespConfig>,
+    replication: Option<DecodedEPInsecureConfig>,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -284,6 +602,7 @@ pub struct DecodedEPSecureConfig {
     cert: String,
     private_key: String,
     pkey_passphrase: String,
+    client_ca: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -302,6 +621,14 @@ impl DecodedEPInsecureConfig {
     }
 }
 
+#[derive(Debug, PartialEq, Deserialize)]
+/// Decoded RESP adapter configuration
+pub struct DecodedEPRespConfig {
+    host: String,
+    port: u16,
+    model: String,
+}
+
 /*
     errors and misc
 */
@@ -380,12 +707,35 @@ pub enum ConfigErrorKind {
 pub(super) trait ConfigurationSource {
     const KEY_AUTH_DRIVER: &'static str;
     const KEY_AUTH_ROOT_PASSWORD: &'static str;
+    const KEY_AUTH_PASSWORD_MIN_LEN: &'static str;
+    const KEY_AUTH_HASH_COST: &'static str;
+    const KEY_AUTH_ROOT_PASSWORD_FILE: &'static str;
+    const KEY_AUTH_ROOT_ROTATE: &'static str;
     const KEY_TLS_CERT: &'static str;
     const KEY_TLS_KEY: &'static str;
     const KEY_TLS_PKEY_PASS: &'static str;
+    const KEY_TLS_CLIENT_CA: &'static str;
     const KEY_ENDPOINTS: &'static str;
     const KEY_RUN_MODE: &'static str;
     const KEY_SERVICE_WINDOW: &'static str;
+    const KEY_STORAGE_ENGINE: &'static str;
+    const KEY_IO_BACKEND: &'static str;
+    const KEY_IO_THREADS: &'static str;
+    const KEY_BATCH_COMPRESSION: &'static str;
+    const KEY_DURABILITY: &'static str;
+    const KEY_RECOVERY_MODE: &'static str;
+    const KEY_BACKPRESSURE: &'static str;
+    const KEY_BACKPRESSURE_LIMIT: &'static str;
+    const KEY_ROW_CACHE_SIZE: &'static str;
+    const KEY_MEMORY_LIMIT: &'static str;
+    const KEY_MODEL_MEMORY_LIMIT: &'static str;
+    const KEY_QUERY_TIMEOUT: &'static str;
+    const KEY_IDLE_TIMEOUT: &'static str;
+    const KEY_REPLICA_OF: &'static str;
+    const KEY_REPLICA_CA: &'static str;
+    const KEY_ENCRYPTION_KEY_FILE: &'static str;
+    const KEY_EXPORT_DIR: &'static str;
+    const KEY_RESP_MODEL: &'static str;
     const SOURCE: ConfigSource;
     /// Formats an error `Invalid value for {key}`
     fn err_invalid_value_for(key: &str) -> ConfigError {
@@ -426,6 +776,14 @@ fn argck_duplicate_values<CS: ConfigurationSource>(
 enum ConnectionProtocol {
     Tcp,
     Tls,
+    /// plain HTTP, only used to serve metrics
+    Http,
+    /// plain HTTP (and websockets), used to run queries with JSON
+    Web,
+    /// a subset of RESP, used by Redis clients
+    Resp,
+    /// only used to ship batch journals to replicas
+    Repl,
 }
 
 /// Parse an endpoint (`protocol@host:port`)
@@ -455,32 +813,63 @@ fn parse_endpoint(source: ConfigSource, s: &str) -> RuntimeResult<(ConnectionPro
     let protocol = match protocol {
         "tcp" => ConnectionProtocol::Tcp,
         "tls" => ConnectionProtocol::Tls,
+        "http" => ConnectionProtocol::Http,
+        "web" => ConnectionProtocol::Web,
+        "resp" => ConnectionProtocol::Resp,
+        "repl" => ConnectionProtocol::Repl,
         _ => return err(),
     };
     Ok((protocol, host, port))
 }
 
-/// Decode a TLS endpoint (read in cert and private key)
+/// Decode a TLS endpoint (read in cert, private key and the client CA, if any)
 fn decode_tls_ep(
     cert_path: &str,
     key_path: &str,
     pkey_pass: &str,
+    client_ca_path: Option<&str>,
     host: &str,
     port: u16,
 ) -> RuntimeResult<DecodedEPSecureConfig> {
     super::fractal::context::set_dmsg("loading TLS configuration from disk");
-    let tls_key = fs::read_to_string(key_path)?;
-    let tls_cert = fs::read_to_string(cert_path)?;
-    let tls_priv_key_passphrase = fs::read_to_string(pkey_pass)?;
+    let tls_key = read_tls_file(key_path, "private key")?;
+    let tls_cert = read_tls_file(cert_path, "certificate")?;
+    let tls_priv_key_passphrase = read_tls_file(pkey_pass, "private key passphrase")?;
+    let tls_client_ca = match client_ca_path {
+        Some(path) => Some(read_tls_file(path, "client CA")?),
+        None => None,
+    };
     Ok(DecodedEPSecureConfig {
         host: host.into(),
         port,
         cert: tls_cert,
         private_key: tls_key,
         pkey_passphrase: tls_priv_key_passphrase,
+        client_ca: tls_client_ca,
+    })
+}
+
+/// Read one of the TLS files (the error says which file couldn't be read, and why)
+fn read_tls_file(path: &str, what: &str) -> RuntimeResult<String> {
+    fs::read_to_string(path).map_err(|e| {
+        ConfigError::new(ConfigErrorKind::ErrorString(format!(
+            "failed to read the TLS {what} from `{path}`. {e}"
+        )))
+        .into()
     })
 }
 
+/// Read the root password from the given file (trailing newlines are ignored)
+fn read_root_password_file(path: &str) -> RuntimeResult<String> {
+    match fs::read_to_string(path) {
+        Ok(password) => Ok(password.trim_end_matches(['\r', '\n']).to_owned()),
+        Err(e) => Err(ConfigError::new(ConfigErrorKind::ErrorString(format!(
+            "failed to read the root password from `{path}`. {e}"
+        )))
+        .into()),
+    }
+}
+
 /// Helper for decoding a TLS endpoint (we read in the cert and private key)
 fn arg_decode_tls_endpoint<CS: ConfigurationSource>(
     args: &mut ParsedRawArgs,
@@ -508,10 +897,15 @@ fn arg_decode_tls_endpoint<CS: ConfigurationSource>(
     argck_duplicate_values::<CS>(&tls_cert, CS::KEY_TLS_CERT)?;
     argck_duplicate_values::<CS>(&tls_key, CS::KEY_TLS_KEY)?;
     argck_duplicate_values::<CS>(&tls_passphrase, CS::KEY_TLS_PKEY_PASS)?;
+    let tls_client_ca = args.remove(CS::KEY_TLS_CLIENT_CA);
+    if let Some(ref client_ca) = tls_client_ca {
+        argck_duplicate_values::<CS>(client_ca, CS::KEY_TLS_CLIENT_CA)?;
+    }
     Ok(decode_tls_ep(
         &tls_cert[0],
         &tls_key[0],
         &tls_passphrase[0],
+        tls_client_ca.as_ref().map(|ca| ca[0].as_str()),
         host,
         port,
     )?)
@@ -525,33 +919,65 @@ fn arg_decode_auth<CS: ConfigurationSource>(
     src_args: &mut ParsedRawArgs,
     config: &mut ModifyGuard<DecodedConfiguration>,
 ) -> RuntimeResult<()> {
-    let (Some(auth_driver), Some(mut root_key)) = (
+    let root_pass = src_args.remove(CS::KEY_AUTH_ROOT_PASSWORD);
+    let root_pass_file = src_args.remove(CS::KEY_AUTH_ROOT_PASSWORD_FILE);
+    let (Some(auth_driver), true) = (
         src_args.remove(CS::KEY_AUTH_DRIVER),
-        src_args.remove(CS::KEY_AUTH_ROOT_PASSWORD),
+        root_pass.is_some() | root_pass_file.is_some(),
     ) else {
         return Err(ConfigError::with_src(
             CS::SOURCE,
             ConfigErrorKind::ErrorString(format!(
-                "to enable auth, you must provide values for both {} and {}",
+                "to enable auth, you must provide values for both {} and {} (or {})",
                 CS::KEY_AUTH_DRIVER,
-                CS::KEY_AUTH_ROOT_PASSWORD
+                CS::KEY_AUTH_ROOT_PASSWORD,
+                CS::KEY_AUTH_ROOT_PASSWORD_FILE,
             )),
         )
         .into());
     };
     argck_duplicate_values::<CS>(&auth_driver, CS::KEY_AUTH_DRIVER)?;
-    argck_duplicate_values::<CS>(&root_key, CS::KEY_AUTH_DRIVER)?;
+    let root_pass_value = |value: Option<Vec<String>>, key| match value {
+        Some(mut value) => argck_duplicate_values::<CS>(&value, key).map(|_| Some(value.remove(0))),
+        None => Ok(None),
+    };
+    let root_pass = root_pass_value(root_pass, CS::KEY_AUTH_ROOT_PASSWORD)?;
+    let root_pass_file = root_pass_value(root_pass_file, CS::KEY_AUTH_ROOT_PASSWORD_FILE)?;
     let auth_plugin = match auth_driver[0].as_str() {
         "pwd" => AuthDriver::Pwd,
         _ => return Err(CS::err_invalid_value_for(CS::KEY_AUTH_DRIVER).into()),
     };
+    // password policy (optional)
+    let password_min_length =
+        arg_decode_optional::<CS, usize>(src_args, CS::KEY_AUTH_PASSWORD_MIN_LEN)?;
+    let hash_cost = arg_decode_optional::<CS, u32>(src_args, CS::KEY_AUTH_HASH_COST)?;
+    let root_rotate = arg_decode_optional::<CS, bool>(src_args, CS::KEY_AUTH_ROOT_ROTATE)?;
     config.auth = Some(DecodedAuth {
         plugin: auth_plugin,
-        root_pass: root_key.remove(0),
+        root_pass,
+        root_pass_file,
+        password_min_length,
+        hash_cost,
+        root_rotate,
     });
     Ok(())
 }
 
+/// Decode an optional argument (an integer or a boolean)
+fn arg_decode_optional<CS: ConfigurationSource, T: core::str::FromStr>(
+    src_args: &mut ParsedRawArgs,
+    key: &'static str,
+) -> RuntimeResult<Option<T>> {
+    let Some(value) = src_args.remove(key) else {
+        return Ok(None);
+    };
+    argck_duplicate_values::<CS>(&value, key)?;
+    match value[0].parse() {
+        Ok(v) => Ok(Some(v)),
+        Err(_) => Err(CS::err_invalid_value_for(key).into()),
+    }
+}
+
 /// Decode the endpoints (`protocol@host:port`)
 fn arg_decode_endpoints<CS: ConfigurationSource>(
     args: &mut ParsedRawArgs,
@@ -559,10 +985,14 @@ fn arg_decode_endpoints<CS: ConfigurationSource>(
 ) -> RuntimeResult<()> {
     let mut insecure = None;
     let mut secure = None;
+    let mut metrics = None;
+    let mut web = None;
+    let mut resp = None;
+    let mut replication = None;
     let Some(endpoints) = args.remove(CS::KEY_ENDPOINTS) else {
         return Ok(());
     };
-    if endpoints.len() > 2 {
+    if endpoints.len() > 6 {
         return Err(CS::err_too_many_values_for(CS::KEY_ENDPOINTS).into());
     }
     for ep in endpoints {
@@ -574,6 +1004,30 @@ fn arg_decode_endpoints<CS: ConfigurationSource>(
             ConnectionProtocol::Tls if secure.is_none() => {
                 secure = Some(arg_decode_tls_endpoint::<CS>(args, host, port)?);
             }
+            ConnectionProtocol::Http if metrics.is_none() => {
+                metrics = Some(DecodedEPInsecureConfig::new(host, port));
+            }
+            ConnectionProtocol::Web if web.is_none() => {
+                web = Some(DecodedEPInsecureConfig::new(host, port));
+            }
+            ConnectionProtocol::Resp if resp.is_none() => {
+                let Some(model) = args.remove(CS::KEY_RESP_MODEL) else {
+                    return Err(CS::custom_err(format!(
+                        "must supply a value for `{}` when using the `resp` endpoint",
+                        CS::KEY_RESP_MODEL
+                    ))
+                    .into());
+                };
+                argck_duplicate_values::<CS>(&model, CS::KEY_RESP_MODEL)?;
+                resp = Some(DecodedEPRespConfig {
+                    host: host.to_owned(),
+                    port,
+                    model: model[0].clone(),
+                });
+            }
+            ConnectionProtocol::Repl if replication.is_none() => {
+                replication = Some(DecodedEPInsecureConfig::new(host, port));
+            }
             _ => {
                 return Err(CS::custom_err(format!(
                     "duplicate endpoints specified in `{}`",
@@ -583,8 +1037,21 @@ fn arg_decode_endpoints<CS: ConfigurationSource>(
             }
         }
     }
-    if insecure.is_some() | secure.is_some() {
-        config.endpoints = Some(DecodedEPConfig { secure, insecure });
+    if insecure.is_some()
+        | secure.is_some()
+        | metrics.is_some()
+        | web.is_some()
+        | resp.is_some()
+        | replication.is_some()
+    {
+        config.endpoints = Some(DecodedEPConfig {
+            secure,
+            insecure,
+            metrics,
+            web,
+            resp,
+            replication,
+        });
     }
     Ok(())
 }
@@ -608,6 +1075,23 @@ fn arg_decode_mode<CS: ConfigurationSource>(
             config.system = Some(DecodedSystemConfig {
                 mode: Some(mode),
                 rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
             })
         }
     }
@@ -627,6 +1111,23 @@ fn arg_decode_rs_window<CS: ConfigurationSource>(
                 config.system = Some(DecodedSystemConfig {
                     mode: None,
                     rs_window: Some(n),
+                    storage_engine: None,
+                    io_backend: None,
+                    io_threads: None,
+                    batch_compression: None,
+                    durability: None,
+                    recovery_mode: None,
+                    backpressure: None,
+                    backpressure_limit: None,
+                    row_cache_size: None,
+                    memory_limit: None,
+                    model_memory_limit: None,
+                    query_timeout: None,
+                    idle_timeout: None,
+                    replica_of: None,
+                    replica_ca: None,
+                    encryption_key_file: None,
+                    export_dir: None,
                 })
             }
         },
@@ -635,6 +1136,680 @@ fn arg_decode_rs_window<CS: ConfigurationSource>(
     Ok(())
 }
 
+/// Decode the storage engine:
+/// - Journal
+fn arg_decode_storage_engine<CS: ConfigurationSource>(
+    engine: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(engine, CS::KEY_STORAGE_ENGINE)?;
+    let engine = match engine[0].as_str() {
+        "journal" => StorageEngineKind::Journal,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_STORAGE_ENGINE).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.storage_engine = Some(engine),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: Some(engine),
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the I/O backend:
+/// - std OR
+/// - io_uring OR
+/// - direct
+fn arg_decode_io_backend<CS: ConfigurationSource>(
+    backend: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(backend, CS::KEY_IO_BACKEND)?;
+    let backend = match backend[0].as_str() {
+        "std" => IoBackend::Std,
+        "io_uring" => IoBackend::IoUring,
+        "direct" => IoBackend::Direct,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_IO_BACKEND).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.io_backend = Some(backend),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: Some(backend),
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the number of I/O threads
+fn arg_decode_io_threads<CS: ConfigurationSource>(
+    threads: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(threads, CS::KEY_IO_THREADS)?;
+    let threads = match threads[0].parse::<usize>() {
+        Ok(n) if n != 0 => n,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_IO_THREADS).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.io_threads = Some(threads),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: Some(threads),
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the batch compression:
+/// - None OR
+/// - LZ4
+fn arg_decode_batch_compression<CS: ConfigurationSource>(
+    compression: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&compression, CS::KEY_BATCH_COMPRESSION)?;
+    let compression = match compression[0].as_str() {
+        "none" => BatchCompression::None,
+        "lz4" => BatchCompression::Lz4,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_BATCH_COMPRESSION).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.batch_compression = Some(compression),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: Some(compression),
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the durability policy (`always`, `os` or a sync interval like `100ms`). It's validated later, along with the
+/// rest of the configuration
+fn arg_decode_durability<CS: ConfigurationSource>(
+    durability: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&durability, CS::KEY_DURABILITY)?;
+    match config.system.as_mut() {
+        Some(sys) => sys.durability = Some(durability[0].clone()),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: Some(durability[0].clone()),
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the recovery mode:
+/// - strict OR
+/// - truncate
+fn arg_decode_recovery_mode<CS: ConfigurationSource>(
+    mode: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(mode, CS::KEY_RECOVERY_MODE)?;
+    let mode = match mode[0].as_str() {
+        "strict" => RecoveryMode::Strict,
+        "truncate" => RecoveryMode::TruncateTail,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_RECOVERY_MODE).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.recovery_mode = Some(mode),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: Some(mode),
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the backpressure policy:
+/// - Stall OR
+/// - Reject
+fn arg_decode_backpressure<CS: ConfigurationSource>(
+    backpressure: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&backpressure, CS::KEY_BACKPRESSURE)?;
+    let backpressure = match backpressure[0].as_str() {
+        "stall" => BackpressurePolicy::Stall,
+        "reject" => BackpressurePolicy::Reject,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_BACKPRESSURE).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.backpressure = Some(backpressure),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: Some(backpressure),
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the number of unflushed deltas at which a model's writes are held back
+fn arg_decode_backpressure_limit<CS: ConfigurationSource>(
+    limit: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&limit, CS::KEY_BACKPRESSURE_LIMIT)?;
+    let limit = match limit[0].parse::<usize>() {
+        Ok(n) if n != 0 => n,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_BACKPRESSURE_LIMIT).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.backpressure_limit = Some(limit),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: Some(limit),
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the memory budget (in bytes) for the row caches
+fn arg_decode_row_cache_size<CS: ConfigurationSource>(
+    size: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&size, CS::KEY_ROW_CACHE_SIZE)?;
+    let size = match size[0].parse::<usize>() {
+        Ok(n) if n != 0 => n,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_ROW_CACHE_SIZE).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.row_cache_size = Some(size),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: Some(size),
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the estimated memory (in bytes) that all models together may use
+fn arg_decode_memory_limit<CS: ConfigurationSource>(
+    limit: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&limit, CS::KEY_MEMORY_LIMIT)?;
+    let limit = match limit[0].parse::<usize>() {
+        Ok(n) if n != 0 => n,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_MEMORY_LIMIT).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.memory_limit = Some(limit),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: Some(limit),
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the estimated memory (in bytes) that a single model may use
+fn arg_decode_model_memory_limit<CS: ConfigurationSource>(
+    limit: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&limit, CS::KEY_MODEL_MEMORY_LIMIT)?;
+    let limit = match limit[0].parse::<usize>() {
+        Ok(n) if n != 0 => n,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_MODEL_MEMORY_LIMIT).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.model_memory_limit = Some(limit),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: Some(limit),
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the time (in milliseconds) a query may run for before it's cancelled
+fn arg_decode_query_timeout<CS: ConfigurationSource>(
+    timeout: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&timeout, CS::KEY_QUERY_TIMEOUT)?;
+    let timeout = match timeout[0].parse::<u64>() {
+        Ok(n) if n != 0 => n,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_QUERY_TIMEOUT).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.query_timeout = Some(timeout),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: Some(timeout),
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the idle timeout (in seconds)
+fn arg_decode_idle_timeout<CS: ConfigurationSource>(
+    timeout: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&timeout, CS::KEY_IDLE_TIMEOUT)?;
+    let timeout = match timeout[0].parse::<u64>() {
+        Ok(n) if n != 0 => n,
+        _ => return Err(CS::err_invalid_value_for(CS::KEY_IDLE_TIMEOUT).into()),
+    };
+    match config.system.as_mut() {
+        Some(sys) => sys.idle_timeout = Some(timeout),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: Some(timeout),
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the address of the primary to replicate from (`host:port`). It's validated later, along with the rest of
+/// the configuration
+fn arg_decode_replica_of<CS: ConfigurationSource>(
+    primary: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&primary, CS::KEY_REPLICA_OF)?;
+    match config.system.as_mut() {
+        Some(sys) => sys.replica_of = Some(primary[0].clone()),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: Some(primary[0].clone()),
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the path to the CA certificate(s) that the primary's certificate must be signed by. It's read later, along
+/// with the rest of the configuration
+fn arg_decode_replica_ca<CS: ConfigurationSource>(
+    path: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(path, CS::KEY_REPLICA_CA)?;
+    match config.system.as_mut() {
+        Some(sys) => sys.replica_ca = Some(path[0].clone()),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: Some(path[0].clone()),
+                encryption_key_file: None,
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the path to the file that holds the master encryption key. The key is loaded later, along with the rest of
+/// the configuration
+fn arg_decode_encryption_key_file<CS: ConfigurationSource>(
+    path: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(&path, CS::KEY_ENCRYPTION_KEY_FILE)?;
+    match config.system.as_mut() {
+        Some(sys) => sys.encryption_key_file = Some(path[0].clone()),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: Some(path[0].clone()),
+                export_dir: None,
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Decode the directory that models are exported into. It's checked later, along with the rest of the configuration
+fn arg_decode_export_dir<CS: ConfigurationSource>(
+    path: &[String],
+    config: &mut ModifyGuard<DecodedConfiguration>,
+) -> RuntimeResult<()> {
+    argck_duplicate_values::<CS>(path, CS::KEY_EXPORT_DIR)?;
+    match config.system.as_mut() {
+        Some(sys) => sys.export_dir = Some(path[0].clone()),
+        None => {
+            config.system = Some(DecodedSystemConfig {
+                mode: None,
+                rs_window: None,
+                storage_engine: None,
+                io_backend: None,
+                io_threads: None,
+                batch_compression: None,
+                durability: None,
+                recovery_mode: None,
+                backpressure: None,
+                backpressure_limit: None,
+                row_cache_size: None,
+                memory_limit: None,
+                model_memory_limit: None,
+                query_timeout: None,
+                idle_timeout: None,
+                replica_of: None,
+                replica_ca: None,
+                encryption_key_file: None,
+                export_dir: Some(path[0].clone()),
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Load the master encryption key from the given key file
+fn load_encryption_key<CS: ConfigurationSource>(path: &str) -> RuntimeResult<EncryptionKey> {
+    match EncryptionKey::parse(&fs::read(path)?) {
+        Some(key) => Ok(key),
+        None => Err(CS::custom_err(format!(
+            "invalid key in the file given by {}. should be a 32 byte key (either raw or in hex)",
+            CS::KEY_ENCRYPTION_KEY_FILE
+        ))
+        .into()),
+    }
+}
+
 /*
     CLI args process
 */
@@ -648,18 +1823,89 @@ skyd is the Skytable database server daemon and can be used to serve database re
 Flags:
   -h, --help                 Display this help menu and exit.
   -v, --version              Display the version number and exit.
+  --validate-config          Check the configuration (along with the TLS certificates,
+                             the permissions of the data directory and the other
+                             settings) and exit without starting up.
 
 Options:
   --config <path>             Set configuration options using the config file
   --tlscert <path>            Specify the path to the TLS certificate.
   --tlskey <path>             Specify the path to the TLS private key.
+  --tls-passphrase <path>     Specify the path to the TLS private key passphrase.
+  --tls-client-ca <path>      Specify the path to the CA certificate(s) used to verify
+                              client certificates. Enables client certificate auth.
   --endpoint <definition>     Designate an endpoint. Format: protocol@host:port.
                               This option can be repeated to define multiple endpoints.
+                              Use `http` as the protocol to serve Prometheus metrics,
+                              `web` to run queries with JSON over HTTP or websockets,
+                              `resp` to serve Redis clients and `repl` to let replicas
+                              connect.
+  --resp-model <space.model>  Specify the model that the `resp` endpoint stores keys in.
   --service-window <seconds>  Establish the time window for the background service in seconds.
+  --storage-engine <journal>  Set the storage engine that keeps the data of models.
+  --io-backend <std/io_uring/direct>
+                              Set the backend used to read and write data batch
+                              journals. direct bypasses the page cache (O_DIRECT).
+                              io_uring and direct are only used on Linux.
+  --io-threads <n>            Set the number of tasks (like journal writes) that may block
+                              on the disk at a time (the number of CPUs by default).
+  --batch-compression <none/lz4>
+                              Set the compression used for data batch journals.
+  --durability <always/os/Nms>
+                              Set when journals are synced to disk: on every commit
+                              (default), never (leave it to the OS) or every N ms.
+  --recovery-mode <strict/truncate>
+                              Set what happens to a data batch journal that wasn't
+                              closed: refuse to start (default) or discard a partially
+                              written last batch and continue.
+  --backpressure <stall/reject>
+                              Set what happens to writes to a model that has too many
+                              unflushed changes: wait for them to be flushed (default)
+                              or fail right away.
+  --backpressure-limit <count>
+                              Set the number of unflushed changes at which a model's
+                              writes are held back (no limit by default).
+  --row-cache-size <bytes>    Cache the most read rows of all models in memory, within the
+                              given budget (rows aren't cached by default).
+  --memory-limit <bytes>      Reject writes once all models together use more than the
+                              given amount of memory (no limit by default).
+  --model-memory-limit <bytes>
+                              Reject writes to a model once it uses more than the given
+                              amount of memory (no limit by default).
+  --query-timeout <ms>        Cancel queries that run for longer than the given number of
+                              milliseconds (no limit by default).
+  --idle-timeout <seconds>    Close client connections that send nothing (not even a ping)
+                              for the given number of seconds (kept open by default).
+  --replica-of <host:port>    Replicate all models from the primary at the given address.
+  --replica-ca <path>         Only replicate from a primary whose TLS certificate is signed
+                              by the CA certificate(s) in the given file.
+  --encryption-key-file <path>
+                              Encrypt new database files with the 32 byte key (raw or in
+                              hex) in the given file. Existing encrypted files can only
+                              be opened with the key they were encrypted with.
+  --export-dir <path>         Allow `sysctl export` to write files into the given
+                              directory (exports are disabled by default).
   --auth <plugin_name>        Identify the authentication plugin by name.
   --mode <dev/prod>           Set the operational mode. Note: This option is mandatory.
   --auth-plugin <plugin>      Set the auth plugin. `pwd` is a supported option
   --auth-root-password <pass> Set the root password
+  --auth-root-password-file <path>
+                              Read the root password from the given file instead.
+  --auth-root-rotate <true/false>
+                              Only use the root password to set up the root account on
+                              first boot. DDL is refused until it is changed with
+                              `sysctl alter user root` (default: false).
+  --auth-password-min-length <length>
+                              Set the minimum length for user passwords (default: 8).
+  --auth-hash-cost <cost>     Set the cost used to hash passwords.
+  --restore <path>            Restore the backup at the given path before starting up.
+  --recover-until <target>    Recover a model to the state it was in right after a txn id.
+                              Format: space.model@txn_id. This option can be repeated
+                              to recover multiple models.
+  --repair <check/truncate>   Check the database files for corruption and exit. With
+                              `truncate`, files that can't be read to the end are
+                              truncated to their last consistent point. Only
+                              --encryption-key-file can be combined with this option.
 
 Examples:
   skyd --mode=dev --auth-root-password \"password12345678\"
@@ -667,7 +1913,38 @@ Examples:
 Notes:
   - If no `--mode` is provided, we default to `dev`
   - You must provide `--auth-root-password` to set the default root password
-  - To use TLS, you must provide both `--tlscert` and `--tlskey`
+  - To use TLS, you must provide `--tlscert`, `--tlskey` and `--tls-passphrase`
+  - If `--tls-client-ca` is set, clients that don't present a certificate signed by
+    the given CA are rejected during the TLS handshake
+  - `--restore` will not overwrite an existing database; remove the existing data files first
+  - `--recover-until` keeps the original data file alongside the recovered one
+  - The `http` endpoint only serves metrics (at `/metrics`) and has no authentication
+  - The `web` endpoint runs queries posted to `/query` as JSON (`{\"query\": ..., \"params\": [...]}`)
+    and serves websockets at `/ws`. It uses HTTP basic auth and has no TLS
+  - The `resp` endpoint requires `--resp-model`, a model with a primary key and exactly one
+    other field. It supports `AUTH`, `PING`, `ECHO`, `SELECT 0`, `QUIT`, `GET`, `SET` (without
+    options), `DEL` and `EXISTS`, and has no TLS
+  - With `--idle-timeout`, clients that stay connected without running queries have to send
+    pings (a `K` byte, answered with a pong) to keep their connection open
+  - The `repl` endpoint requires a `tls` endpoint (replicas connect with the same TLS
+    settings, including `--tls-client-ca`), and `--replica-of` requires `--replica-ca`. If
+    the primary has `--tls-client-ca` set, the replica presents the certificate of its own
+    `tls` endpoint
+  - A replica must have the same root password as its primary, and the models that are
+    replicated must be created on the replica before it's started with `--replica-of`
+    (DDL is not replicated)
+  - A replica rejects all DDL and DML queries until it is promoted with `sysctl promote`;
+    remove `--replica-of` before restarting a promoted replica
+  - `--batch-compression` only applies to new journals and to journals as they are compacted
+  - With `--durability` set to `os` or an interval, commits that weren't synced yet can be lost
+    if the host crashes (but not if only skyd crashes)
+  - `--backpressure` requires `--backpressure-limit`, and stalled writes that still can't
+    go through after 10 seconds fail just like they would with `reject`
+  - If `--auth-hash-cost` is changed, passwords are rehashed as users log in
+  - `sysctl export` only takes a file name, and refuses to overwrite a file that already
+    exists in `--export-dir`
+  - `--repair` can't be combined with any other option, and `truncate` discards everything
+    after the first unreadable part of a file
 
 For further assistance, refer to the official documentation here: https://docs.skytable.org
 ";
@@ -711,6 +1988,11 @@ pub fn parse_cli_args<'a, T: 'a + AsRef<str>>(
         if arg == "--version" || arg == "-v" {
             return Ok(CLIConfigParseReturn::Version);
         }
+        if arg == CSCommandLine::ARG_VALIDATE_CONFIG {
+            // this is a flag, so it doesn't have a value
+            cli_args.entry(arg.to_string()).or_default();
+            continue;
+        }
         if !arg.starts_with("--") {
             return Err(ConfigError::with_src(
                 ConfigSource::Cli,
@@ -768,15 +2050,38 @@ pub fn parse_cli_args<'a, T: 'a + AsRef<str>>(
 
 /// Parse environment variables
 pub fn parse_env_args() -> RuntimeResult<Option<ParsedRawArgs>> {
-    const KEYS: [&str; 8] = [
+    const KEYS: [&str; 31] = [
         CSEnvArgs::KEY_AUTH_DRIVER,
         CSEnvArgs::KEY_AUTH_ROOT_PASSWORD,
+        CSEnvArgs::KEY_AUTH_ROOT_PASSWORD_FILE,
+        CSEnvArgs::KEY_AUTH_PASSWORD_MIN_LEN,
+        CSEnvArgs::KEY_AUTH_HASH_COST,
+        CSEnvArgs::KEY_AUTH_ROOT_ROTATE,
         CSEnvArgs::KEY_ENDPOINTS,
         CSEnvArgs::KEY_RUN_MODE,
         CSEnvArgs::KEY_SERVICE_WINDOW,
+        CSEnvArgs::KEY_STORAGE_ENGINE,
+        CSEnvArgs::KEY_IO_BACKEND,
+        CSEnvArgs::KEY_IO_THREADS,
+        CSEnvArgs::KEY_BATCH_COMPRESSION,
+        CSEnvArgs::KEY_DURABILITY,
+        CSEnvArgs::KEY_RECOVERY_MODE,
+        CSEnvArgs::KEY_BACKPRESSURE,
+        CSEnvArgs::KEY_BACKPRESSURE_LIMIT,
+        CSEnvArgs::KEY_ROW_CACHE_SIZE,
+        CSEnvArgs::KEY_MEMORY_LIMIT,
+        CSEnvArgs::KEY_MODEL_MEMORY_LIMIT,
+        CSEnvArgs::KEY_QUERY_TIMEOUT,
+        CSEnvArgs::KEY_IDLE_TIMEOUT,
+        CSEnvArgs::KEY_REPLICA_OF,
+        CSEnvArgs::KEY_REPLICA_CA,
+        CSEnvArgs::KEY_ENCRYPTION_KEY_FILE,
+        CSEnvArgs::KEY_EXPORT_DIR,
         CSEnvArgs::KEY_TLS_CERT,
         CSEnvArgs::KEY_TLS_KEY,
         CSEnvArgs::KEY_TLS_PKEY_PASS,
+        CSEnvArgs::KEY_TLS_CLIENT_CA,
+        CSEnvArgs::KEY_RESP_MODEL,
     ];
     let mut ret = HashMap::new();
     for key in KEYS {
@@ -836,6 +2141,89 @@ fn apply_config_changes<CS: ConfigurationSource>(
             key: CS::KEY_SERVICE_WINDOW,
             f: arg_decode_rs_window::<CS>,
         },
+        // storage engine
+        DecodeKind::Simple {
+            key: CS::KEY_STORAGE_ENGINE,
+            f: arg_decode_storage_engine::<CS>,
+        },
+        // io backend
+        DecodeKind::Simple {
+            key: CS::KEY_IO_BACKEND,
+            f: arg_decode_io_backend::<CS>,
+        },
+        // io threads
+        DecodeKind::Simple {
+            key: CS::KEY_IO_THREADS,
+            f: arg_decode_io_threads::<CS>,
+        },
+        // batch compression
+        DecodeKind::Simple {
+            key: CS::KEY_BATCH_COMPRESSION,
+            f: arg_decode_batch_compression::<CS>,
+        },
+        // durability
+        DecodeKind::Simple {
+            key: CS::KEY_DURABILITY,
+            f: arg_decode_durability::<CS>,
+        },
+        // recovery mode
+        DecodeKind::Simple {
+            key: CS::KEY_RECOVERY_MODE,
+            f: arg_decode_recovery_mode::<CS>,
+        },
+        // backpressure
+        DecodeKind::Simple {
+            key: CS::KEY_BACKPRESSURE,
+            f: arg_decode_backpressure::<CS>,
+        },
+        DecodeKind::Simple {
+            key: CS::KEY_BACKPRESSURE_LIMIT,
+            f: arg_decode_backpressure_limit::<CS>,
+        },
+        // row cache
+        DecodeKind::Simple {
+            key: CS::KEY_ROW_CACHE_SIZE,
+            f: arg_decode_row_cache_size::<CS>,
+        },
+        // memory limits
+        DecodeKind::Simple {
+            key: CS::KEY_MEMORY_LIMIT,
+            f: arg_decode_memory_limit::<CS>,
+        },
+        DecodeKind::Simple {
+            key: CS::KEY_MODEL_MEMORY_LIMIT,
+            f: arg_decode_model_memory_limit::<CS>,
+        },
+        // query timeout
+        DecodeKind::Simple {
+            key: CS::KEY_QUERY_TIMEOUT,
+            f: arg_decode_query_timeout::<CS>,
+        },
+        // idle timeout
+        DecodeKind::Simple {
+            key: CS::KEY_IDLE_TIMEOUT,
+            f: arg_decode_idle_timeout::<CS>,
+        },
+        // primary to replicate from
+        DecodeKind::Simple {
+            key: CS::KEY_REPLICA_OF,
+            f: arg_decode_replica_of::<CS>,
+        },
+        // CA that the primary's certificate must be signed by
+        DecodeKind::Simple {
+            key: CS::KEY_REPLICA_CA,
+            f: arg_decode_replica_ca::<CS>,
+        },
+        // encryption key
+        DecodeKind::Simple {
+            key: CS::KEY_ENCRYPTION_KEY_FILE,
+            f: arg_decode_encryption_key_file::<CS>,
+        },
+        // directory that models are exported into
+        DecodeKind::Simple {
+            key: CS::KEY_EXPORT_DIR,
+            f: arg_decode_export_dir::<CS>,
+        },
         // endpoints
         DecodeKind::Complex {
             f: arg_decode_endpoints::<CS>,
@@ -871,16 +2259,43 @@ fn apply_config_changes<CS: ConfigurationSource>(
 pub struct CSCommandLine;
 impl CSCommandLine {
     const ARG_CONFIG_FILE: &'static str = "--config";
+    const ARG_RESTORE: &'static str = "--restore";
+    const ARG_RECOVER_UNTIL: &'static str = "--recover-until";
+    const ARG_REPAIR: &'static str = "--repair";
+    const ARG_VALIDATE_CONFIG: &'static str = "--validate-config";
 }
 impl ConfigurationSource for CSCommandLine {
     const KEY_AUTH_DRIVER: &'static str = "--auth-plugin";
     const KEY_AUTH_ROOT_PASSWORD: &'static str = "--auth-root-password";
+    const KEY_AUTH_PASSWORD_MIN_LEN: &'static str = "--auth-password-min-length";
+    const KEY_AUTH_HASH_COST: &'static str = "--auth-hash-cost";
+    const KEY_AUTH_ROOT_PASSWORD_FILE: &'static str = "--auth-root-password-file";
+    const KEY_AUTH_ROOT_ROTATE: &'static str = "--auth-root-rotate";
     const KEY_TLS_CERT: &'static str = "--tlscert";
     const KEY_TLS_KEY: &'static str = "--tlskey";
     const KEY_TLS_PKEY_PASS: &'static str = "--tls-passphrase";
+    const KEY_TLS_CLIENT_CA: &'static str = "--tls-client-ca";
     const KEY_ENDPOINTS: &'static str = "--endpoint";
     const KEY_RUN_MODE: &'static str = "--mode";
     const KEY_SERVICE_WINDOW: &'static str = "--service-window";
+    const KEY_STORAGE_ENGINE: &'static str = "--storage-engine";
+    const KEY_IO_BACKEND: &'static str = "--io-backend";
+    const KEY_IO_THREADS: &'static str = "--io-threads";
+    const KEY_BATCH_COMPRESSION: &'static str = "--batch-compression";
+    const KEY_DURABILITY: &'static str = "--durability";
+    const KEY_RECOVERY_MODE: &'static str = "--recovery-mode";
+    const KEY_BACKPRESSURE: &'static str = "--backpressure";
+    const KEY_BACKPRESSURE_LIMIT: &'static str = "--backpressure-limit";
+    const KEY_ROW_CACHE_SIZE: &'static str = "--row-cache-size";
+    const KEY_MEMORY_LIMIT: &'static str = "--memory-limit";
+    const KEY_MODEL_MEMORY_LIMIT: &'static str = "--model-memory-limit";
+    const KEY_QUERY_TIMEOUT: &'static str = "--query-timeout";
+    const KEY_IDLE_TIMEOUT: &'static str = "--idle-timeout";
+    const KEY_REPLICA_OF: &'static str = "--replica-of";
+    const KEY_REPLICA_CA: &'static str = "--replica-ca";
+    const KEY_ENCRYPTION_KEY_FILE: &'static str = "--encryption-key-file";
+    const KEY_EXPORT_DIR: &'static str = "--export-dir";
+    const KEY_RESP_MODEL: &'static str = "--resp-model";
     const SOURCE: ConfigSource = ConfigSource::Cli;
 }
 
@@ -888,12 +2303,35 @@ pub struct CSEnvArgs;
 impl ConfigurationSource for CSEnvArgs {
     const KEY_AUTH_DRIVER: &'static str = "SKYDB_AUTH_PLUGIN";
     const KEY_AUTH_ROOT_PASSWORD: &'static str = "SKYDB_AUTH_ROOT_PASSWORD";
+    const KEY_AUTH_PASSWORD_MIN_LEN: &'static str = "SKYDB_AUTH_PASSWORD_MIN_LENGTH";
+    const KEY_AUTH_HASH_COST: &'static str = "SKYDB_AUTH_HASH_COST";
+    const KEY_AUTH_ROOT_PASSWORD_FILE: &'static str = "SKYDB_AUTH_ROOT_PASSWORD_FILE";
+    const KEY_AUTH_ROOT_ROTATE: &'static str = "SKYDB_AUTH_ROOT_ROTATE";
     const KEY_TLS_CERT: &'static str = "SKYDB_TLS_CERT";
     const KEY_TLS_KEY: &'static str = "SKYDB_TLS_KEY";
     const KEY_TLS_PKEY_PASS: &'static str = "SKYDB_TLS_PRIVATE_KEY_PASSWORD";
+    const KEY_TLS_CLIENT_CA: &'static str = "SKYDB_TLS_CLIENT_CA";
     const KEY_ENDPOINTS: &'static str = "SKYDB_ENDPOINTS";
     const KEY_RUN_MODE: &'static str = "SKYDB_RUN_MODE";
     const KEY_SERVICE_WINDOW: &'static str = "SKYDB_SERVICE_WINDOW";
+    const KEY_STORAGE_ENGINE: &'static str = "SKYDB_STORAGE_ENGINE";
+    const KEY_IO_BACKEND: &'static str = "SKYDB_IO_BACKEND";
+    const KEY_IO_THREADS: &'static str = "SKYDB_IO_THREADS";
+    const KEY_BATCH_COMPRESSION: &'static str = "SKYDB_BATCH_COMPRESSION";
+    const KEY_DURABILITY: &'static str = "SKYDB_DURABILITY";
+    const KEY_RECOVERY_MODE: &'static str = "SKYDB_RECOVERY_MODE";
+    const KEY_BACKPRESSURE: &'static str = "SKYDB_BACKPRESSURE";
+    const KEY_BACKPRESSURE_LIMIT: &'static str = "SKYDB_BACKPRESSURE_LIMIT";
+    const KEY_ROW_CACHE_SIZE: &'static str = "SKYDB_ROW_CACHE_SIZE";
+    const KEY_MEMORY_LIMIT: &'static str = "SKYDB_MEMORY_LIMIT";
+    const KEY_MODEL_MEMORY_LIMIT: &'static str = "SKYDB_MODEL_MEMORY_LIMIT";
+    const KEY_QUERY_TIMEOUT: &'static str = "SKYDB_QUERY_TIMEOUT";
+    const KEY_IDLE_TIMEOUT: &'static str = "SKYDB_IDLE_TIMEOUT";
+    const KEY_REPLICA_OF: &'static str = "SKYDB_REPLICA_OF";
+    const KEY_REPLICA_CA: &'static str = "SKYDB_REPLICA_CA";
+    const KEY_ENCRYPTION_KEY_FILE: &'static str = "SKYDB_ENCRYPTION_KEY_FILE";
+    const KEY_EXPORT_DIR: &'static str = "SKYDB_EXPORT_DIR";
+    const KEY_RESP_MODEL: &'static str = "SKYDB_RESP_MODEL";
     const SOURCE: ConfigSource = ConfigSource::Env;
 }
 
@@ -901,12 +2339,35 @@ pub struct CSConfigFile;
 impl ConfigurationSource for CSConfigFile {
     const KEY_AUTH_DRIVER: &'static str = "auth.plugin";
     const KEY_AUTH_ROOT_PASSWORD: &'static str = "auth.root_password";
+    const KEY_AUTH_PASSWORD_MIN_LEN: &'static str = "auth.password_min_length";
+    const KEY_AUTH_HASH_COST: &'static str = "auth.hash_cost";
+    const KEY_AUTH_ROOT_PASSWORD_FILE: &'static str = "auth.root_pass_file";
+    const KEY_AUTH_ROOT_ROTATE: &'static str = "auth.root_rotate";
     const KEY_TLS_CERT: &'static str = "endpoints.secure.cert";
     const KEY_TLS_KEY: &'static str = "endpoints.secure.key";
     const KEY_TLS_PKEY_PASS: &'static str = "endpoints.secure.pkey_passphrase";
+    const KEY_TLS_CLIENT_CA: &'static str = "endpoints.secure.client_ca";
     const KEY_ENDPOINTS: &'static str = "endpoints";
     const KEY_RUN_MODE: &'static str = "system.mode";
     const KEY_SERVICE_WINDOW: &'static str = "system.service_window";
+    const KEY_STORAGE_ENGINE: &'static str = "system.storage_engine";
+    const KEY_IO_BACKEND: &'static str = "system.io_backend";
+    const KEY_IO_THREADS: &'static str = "system.io_threads";
+    const KEY_BATCH_COMPRESSION: &'static str = "system.batch_compression";
+    const KEY_DURABILITY: &'static str = "system.durability";
+    const KEY_RECOVERY_MODE: &'static str = "system.recovery_mode";
+    const KEY_BACKPRESSURE: &'static str = "system.backpressure";
+    const KEY_BACKPRESSURE_LIMIT: &'static str = "system.backpressure_limit";
+    const KEY_ROW_CACHE_SIZE: &'static str = "system.row_cache_size";
+    const KEY_MEMORY_LIMIT: &'static str = "system.memory_limit";
+    const KEY_MODEL_MEMORY_LIMIT: &'static str = "system.model_memory_limit";
+    const KEY_QUERY_TIMEOUT: &'static str = "system.query_timeout";
+    const KEY_IDLE_TIMEOUT: &'static str = "system.idle_timeout";
+    const KEY_REPLICA_OF: &'static str = "system.replica_of";
+    const KEY_REPLICA_CA: &'static str = "system.replica_ca";
+    const KEY_ENCRYPTION_KEY_FILE: &'static str = "system.encryption_key_file";
+    const KEY_EXPORT_DIR: &'static str = "system.export_dir";
+    const KEY_RESP_MODEL: &'static str = "endpoints.resp.model";
     const SOURCE: ConfigSource = ConfigSource::File;
 }
 
@@ -946,18 +2407,149 @@ fn validate_configuration<CS: ConfigurationSource>(
         )
         .into());
     };
+    let root_key = match (auth.root_pass, auth.root_pass_file) {
+        (Some(root_pass), None) => root_pass,
+        (None, Some(root_pass_file)) => read_root_password_file(&root_pass_file)?,
+        (root_pass, _) => {
+            return Err(ConfigError::with_src(
+                CS::SOURCE,
+                ConfigErrorKind::ErrorString(format!(
+                    "{} the root password with {} or {}",
+                    if root_pass.is_some() {
+                        "you can't set both"
+                    } else {
+                        "you must set"
+                    },
+                    CS::KEY_AUTH_ROOT_PASSWORD,
+                    CS::KEY_AUTH_ROOT_PASSWORD_FILE
+                )),
+            )
+            .into())
+        }
+    };
     // initialize our default configuration
-    let mut config = Configuration::default_dev_mode(auth);
+    let mut config = Configuration::default_dev_mode(ConfigAuth::new(auth.plugin, root_key));
+    if_some!(auth.password_min_length => |len| config.auth.password_min_len = len);
+    if_some!(auth.hash_cost => |cost| config.auth.hash_cost = cost);
+    if_some!(auth.root_rotate => |rotate| config.auth.root_rotate = rotate);
+    let mut durability = None;
+    let mut backpressure = None;
+    let mut replica_of = None;
+    let mut replica_ca = None;
+    let mut encryption_key_file = None;
+    let mut export_dir = None;
+    let mut resp = None;
     // mutate
     if_some!(
         system => |system: DecodedSystemConfig| {
             if_some!(system.mode => |mode| config.mode = mode);
             if_some!(system.rs_window => |window| config.system.reliability_system_window = window);
+            if_some!(system.storage_engine => |engine| config.system.storage_engine = engine);
+            if_some!(system.io_backend => |backend| config.system.io_backend = backend);
+            if_some!(system.io_threads => |threads| config.system.io_threads = Some(threads));
+            if_some!(system.batch_compression => |compression| config.system.batch_compression = compression);
+            durability = system.durability;
+            if_some!(system.recovery_mode => |mode| config.system.recovery_mode = mode);
+            backpressure = system.backpressure;
+            if_some!(system.backpressure_limit => |limit| config.system.backpressure_limit = Some(limit));
+            if_some!(system.row_cache_size => |size| config.system.row_cache_size = Some(size));
+            if_some!(system.memory_limit => |limit| config.system.memory_limit = Some(limit));
+            if_some!(system.model_memory_limit => |limit| config.system.model_memory_limit = Some(limit));
+            if_some!(system.query_timeout => |timeout| config.system.query_timeout = Some(timeout));
+            if_some!(system.idle_timeout => |timeout| config.system.idle_timeout = Some(timeout));
+            replica_of = system.replica_of;
+            replica_ca = system.replica_ca;
+            encryption_key_file = system.encryption_key_file;
+            export_dir = system.export_dir;
         }
     );
+    if let Some(path) = encryption_key_file {
+        config.system.encryption_key = Some(load_encryption_key::<CS>(&path)?);
+    }
+    if let Some(dir) = export_dir {
+        if !Path::new(&dir).is_dir() {
+            return Err(CS::custom_err(format!(
+                "invalid value for {}. `{dir}` is not a directory",
+                CS::KEY_EXPORT_DIR
+            ))
+            .into());
+        }
+        config.system.export_dir = Some(dir);
+    }
+    if let Some(durability) = durability {
+        config.system.durability = match durability.as_str() {
+            "always" => DurabilityPolicy::Always,
+            "os" => DurabilityPolicy::OsBuffered,
+            interval => match interval.strip_suffix("ms").map(str::parse::<u64>) {
+                Some(Ok(ms)) if ms != 0 => DurabilityPolicy::Periodic(ms),
+                _ => {
+                    return Err(CS::custom_err(format!(
+                        "invalid value for {}. should be `always`, `os` or a sync interval like `100ms`",
+                        CS::KEY_DURABILITY
+                    ))
+                    .into())
+                }
+            },
+        };
+    }
+    if let Some(backpressure) = backpressure {
+        if config.system.backpressure_limit.is_none() {
+            return Err(CS::custom_err(format!(
+                "{} requires {} to be set",
+                CS::KEY_BACKPRESSURE,
+                CS::KEY_BACKPRESSURE_LIMIT
+            ))
+            .into());
+        }
+        config.system.backpressure = backpressure;
+    }
+    if let Some(primary) = replica_of {
+        let Some(ca) = replica_ca else {
+            return Err(CS::custom_err(format!(
+                "{} requires {} to be set",
+                CS::KEY_REPLICA_OF,
+                CS::KEY_REPLICA_CA
+            ))
+            .into());
+        };
+        let Some((host, port)) = primary
+            .rsplit_once(':')
+            .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+        else {
+            return Err(CS::custom_err(format!(
+                "invalid value for {}. should be `hostname:port`",
+                CS::KEY_REPLICA_OF
+            ))
+            .into());
+        };
+        config.replica_of = Some(ConfigReplicaOf {
+            primary: ConfigEndpointTcp {
+                host: host.to_owned(),
+                port,
+            },
+            ca: read_tls_file(&ca, "replica CA")?,
+        });
+    } else if replica_ca.is_some() {
+        return Err(CS::custom_err(format!(
+            "{} requires {} to be set",
+            CS::KEY_REPLICA_CA,
+            CS::KEY_REPLICA_OF
+        ))
+        .into());
+    }
     if_some!(
         endpoints => |ep: DecodedEPConfig| {
             let has_insecure = ep.insecure.is_some();
+            if_some!(ep.metrics => |metrics: DecodedEPInsecureConfig| {
+                config.metrics = Some(ConfigEndpointTcp { host: metrics.host, port: metrics.port });
+            });
+            if_some!(ep.web => |web: DecodedEPInsecureConfig| {
+                config.web = Some(ConfigEndpointTcp { host: web.host, port: web.port });
+            });
+            resp = ep.resp;
+            if_some!(ep.replication => |replication: DecodedEPInsecureConfig| {
+                config.replication = Some(ConfigEndpointTcp { host: replication.host, port: replication.port });
+            });
             if_some!(ep.insecure => |insecure: DecodedEPInsecureConfig| {
                 config.endpoints = ConfigEndpoint::Insecure(ConfigEndpointTcp { host: insecure.host, port: insecure.port });
             });
@@ -970,6 +2562,7 @@ fn validate_configuration<CS: ConfigurationSource>(
                     cert: secure.cert,
                     private_key: secure.private_key,
                     pkey_pass: secure.pkey_passphrase,
+                    client_ca: secure.client_ca,
                 };
                 match &config.endpoints {
                     ConfigEndpoint::Insecure(is) => if has_insecure {
@@ -984,6 +2577,24 @@ fn validate_configuration<CS: ConfigurationSource>(
             })
         }
     );
+    if let Some(resp) = resp {
+        let DecodedEPRespConfig { host, port, model } = resp;
+        let Some((space, model)) = model
+            .split_once('.')
+            .filter(|(space, model)| !space.is_empty() & !model.is_empty() & !model.contains('.'))
+        else {
+            return Err(CS::custom_err(format!(
+                "invalid value for {}. should be `space.model`",
+                CS::KEY_RESP_MODEL
+            ))
+            .into());
+        };
+        config.resp = Some(ConfigResp {
+            endpoint: ConfigEndpointTcp { host, port },
+            space: space.to_owned(),
+            model: model.to_owned(),
+        });
+    }
     // now check a few things
     err_if!(
         if config.system.reliability_system_window == 0 => ConfigError::with_src(
@@ -994,6 +2605,21 @@ fn validate_configuration<CS: ConfigurationSource>(
             CS::SOURCE,
             ConfigErrorKind::ErrorString("the root password must have at least 16 characters".into()),
         ).into(),
+        if !ConfigAuth::HASH_COST_RANGE.contains(&config.auth.hash_cost) => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString(format!(
+                "invalid value for {}. must be between {} and {}",
+                CS::KEY_AUTH_HASH_COST,
+                ConfigAuth::HASH_COST_RANGE.start(),
+                ConfigAuth::HASH_COST_RANGE.end()
+            )),
+        ).into(),
+        if config.replication.is_some() & config.endpoints.tls().is_none() => ConfigError::with_src(
+            CS::SOURCE,
+            ConfigErrorKind::ErrorString(
+                "the `repl` endpoint is served with the TLS settings of the `tls` endpoint, so one must be set up".into()
+            ),
+        ).into(),
     );
     Ok(config)
 }
@@ -1009,6 +2635,13 @@ pub enum ConfigReturn {
     HelpMessage(String),
     /// A configuration that we have fully validated was provided
     Config(Configuration),
+    /// A configuration that we have fully validated was provided, along with recovery actions that must be run
+    /// before we start
+    Recover(Configuration, ConfigRecovery),
+    /// Check (and optionally repair) the database files (which might be encrypted with the given key) and exit
+    Repair(ConfigRepairMode, Option<EncryptionKey>),
+    /// Check that we could start with this configuration (and recovery actions) and exit, without touching any data
+    Validate(Configuration, ConfigRecovery),
 }
 
 impl ConfigReturn {
@@ -1125,7 +2758,7 @@ pub fn check_configuration() -> RuntimeResult<ConfigReturn> {
     let env_args = parse_env_args()?;
     // read in our CLI args (since that can tell us whether we need a configuration file)
     let read_cli_args = parse_cli_args(get_cli_from_store().into_iter())?;
-    let cli_args = match read_cli_args {
+    let mut cli_args = match read_cli_args {
         CLIConfigParseReturn::Default => {
             // no options were provided in the CLI
             None
@@ -1140,6 +2773,88 @@ pub fn check_configuration() -> RuntimeResult<ConfigReturn> {
         }
         CLIConfigParseReturn::YieldedConfig(cfg) => Some(cfg),
     };
+    // a repair doesn't need (or accept) any configuration
+    if let Some(args) = cli_args.as_mut() {
+        if let Some((mode, key)) = arg_decode_repair(args)? {
+            return Ok(ConfigReturn::Repair(mode, key));
+        }
+    }
+    // the configuration itself can come from any source, so we only take the flag out
+    let validate = cli_args
+        .as_mut()
+        .is_some_and(|args| args.remove(CSCommandLine::ARG_VALIDATE_CONFIG).is_some());
+    // recovery actions are one-off, so we only accept them from the command line
+    let recovery = match cli_args.as_mut() {
+        Some(args) => arg_decode_recovery(args)?,
+        None => ConfigRecovery::default(),
+    };
+    if cli_args.as_ref().map_or(false, |args| args.is_empty()) {
+        cli_args = None;
+    }
+    match check_configuration_sources(cli_args, env_args)? {
+        ConfigReturn::Config(cfg) if validate => Ok(ConfigReturn::Validate(cfg, recovery)),
+        ConfigReturn::Config(cfg) if !recovery.is_empty() => {
+            Ok(ConfigReturn::Recover(cfg, recovery))
+        }
+        ret => Ok(ret),
+    }
+}
+
+/// Decode (and remove) the repair mode from the CLI args, along with the encryption key (the only option that it can be
+/// combined with, since encrypted files can't be checked without it)
+fn arg_decode_repair(
+    args: &mut ParsedRawArgs,
+) -> RuntimeResult<Option<(ConfigRepairMode, Option<EncryptionKey>)>> {
+    let Some(mut mode) = args.remove(CSCommandLine::ARG_REPAIR) else {
+        return Ok(None);
+    };
+    argck_duplicate_values::<CSCommandLine>(&mode, CSCommandLine::ARG_REPAIR)?;
+    let Some(mode) = ConfigRepairMode::parse(&mode.remove(0)) else {
+        return Err(CSCommandLine::err_invalid_value_for(CSCommandLine::ARG_REPAIR).into());
+    };
+    let mut key = None;
+    if let Some(path) = args.remove(CSCommandLine::KEY_ENCRYPTION_KEY_FILE) {
+        argck_duplicate_values::<CSCommandLine>(&path, CSCommandLine::KEY_ENCRYPTION_KEY_FILE)?;
+        key = Some(load_encryption_key::<CSCommandLine>(&path[0])?);
+    }
+    if !args.is_empty() {
+        return Err(ConfigError::with_src(
+            ConfigSource::Cli,
+            ConfigErrorKind::ErrorString(format!(
+                "{} can't be combined with other options",
+                CSCommandLine::ARG_REPAIR
+            )),
+        )
+        .into());
+    }
+    Ok(Some((mode, key)))
+}
+
+/// Decode (and remove) any recovery actions from the CLI args
+fn arg_decode_recovery(args: &mut ParsedRawArgs) -> RuntimeResult<ConfigRecovery> {
+    let mut recovery = ConfigRecovery::default();
+    if let Some(mut backup) = args.remove(CSCommandLine::ARG_RESTORE) {
+        argck_duplicate_values::<CSCommandLine>(&backup, CSCommandLine::ARG_RESTORE)?;
+        recovery.restore = Some(backup.remove(0));
+    }
+    if let Some(targets) = args.remove(CSCommandLine::ARG_RECOVER_UNTIL) {
+        for target in targets {
+            let Some(target) = ConfigRecoverUntil::parse(&target) else {
+                return Err(
+                    CSCommandLine::err_invalid_value_for(CSCommandLine::ARG_RECOVER_UNTIL).into(),
+                );
+            };
+            recovery.until.push(target);
+        }
+    }
+    Ok(recovery)
+}
+
+/// Check the configuration using the CLI args, ENV variables or the configuration file (if any)
+fn check_configuration_sources(
+    cli_args: Option<ParsedRawArgs>,
+    env_args: Option<ParsedRawArgs>,
+) -> RuntimeResult<ConfigReturn> {
     match cli_args {
         Some(cfg_from_cli) => {
             // we have some CLI args
@@ -1202,12 +2917,16 @@ fn check_config_file(
             Some(ep) => match ep.secure.as_mut() {
                 Some(secure_ep) => {
                     super::fractal::context::set_dmsg("loading TLS configuration from disk");
-                    let cert = fs::read_to_string(&secure_ep.cert)?;
-                    let private_key = fs::read_to_string(&secure_ep.private_key)?;
-                    let private_key_passphrase = fs::read_to_string(&secure_ep.pkey_passphrase)?;
+                    let cert = read_tls_file(&secure_ep.cert, "certificate")?;
+                    let private_key = read_tls_file(&secure_ep.private_key, "private key")?;
+                    let private_key_passphrase =
+                        read_tls_file(&secure_ep.pkey_passphrase, "private key passphrase")?;
                     secure_ep.cert = cert;
                     secure_ep.private_key = private_key;
                     secure_ep.pkey_passphrase = private_key_passphrase;
+                    if let Some(client_ca_path) = secure_ep.client_ca.as_ref() {
+                        secure_ep.client_ca = Some(read_tls_file(client_ca_path, "client CA")?);
+                    }
                 }
                 None => {}
             },
@@ -0,0 +1,491 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! The system catalog
+//!
+//! Metadata about the database (spaces, models, fields, users, connections and metrics) is exposed as read-only
+//! virtual models in the `sys` space, that can be queried with `select` and `select all` like any other model. The
+//! rows are generated from the in-memory state every time a catalog model is queried, so the storage layer is never
+//! involved
+
+use {
+    super::{
+        ddl_misc::{can_access, disk_size},
+        dml::{self, encode_cell, SortKey},
+        model::Model,
+        EntityIDRef,
+    },
+    crate::engine::{
+        data::{cell::Datacell, tag::TagClass},
+        error::{QueryError, QueryResult},
+        fractal::{sys_store::SysAuthUser, GlobalInstanceLike, ModelUniqueID},
+        idx::STIndexSeq,
+        mem::IntegerRepr,
+        metrics,
+        net::{
+            connections,
+            protocol::{ClientLocalState, Response, ResponseType},
+        },
+        ql::dml::sel::{SelectAllStatement, SelectPathSegment, SelectStatement, SelectWindow},
+    },
+};
+
+/// The space that the catalog models are in
+pub const SYS_SPACE: &str = "sys";
+
+type CatalogRow = Vec<Datacell>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A (virtual) model in the system catalog
+enum CatalogModel {
+    Spaces,
+    Models,
+    Fields,
+    Users,
+    Connections,
+    Metrics,
+}
+
+impl CatalogModel {
+    const ALL: [Self; 6] = [
+        Self::Spaces,
+        Self::Models,
+        Self::Fields,
+        Self::Users,
+        Self::Connections,
+        Self::Metrics,
+    ];
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Spaces => "spaces",
+            Self::Models => "models",
+            Self::Fields => "fields",
+            Self::Users => "users",
+            Self::Connections => "connections",
+            Self::Metrics => "metrics",
+        }
+    }
+    /// Returns the columns of the model (in order)
+    fn columns(&self) -> &'static [&'static str] {
+        match self {
+            Self::Spaces => &["name", "models"],
+            Self::Models => &[
+                "space_name",
+                "name",
+                "primary_key",
+                "rows",
+                "size",
+                "schema_version",
+            ],
+            Self::Fields => &[
+                "space_name",
+                "model_name",
+                "name",
+                "field_type",
+                "nullable",
+                "primary_key",
+            ],
+            Self::Users => &[
+                "name",
+                "root",
+                "grants",
+                "queries_per_sec",
+                "result_bytes",
+                "connections",
+            ],
+            Self::Connections => &[
+                "id",
+                "user",
+                "address",
+                "state",
+                "connected",
+                "duration",
+                "query",
+            ],
+            Self::Metrics => &["name", "kind", "space_name", "model_name", "sample"],
+        }
+    }
+    fn column(&self, name: &str) -> QueryResult<usize> {
+        self.columns()
+            .iter()
+            .position(|column| *column == name)
+            .ok_or(QueryError::QExecUnknownField)
+    }
+    /// Returns all the rows that the client can see
+    fn rows(
+        &self,
+        g: &impl GlobalInstanceLike,
+        c: &ClientLocalState,
+    ) -> QueryResult<Vec<CatalogRow>> {
+        match self {
+            Self::Spaces => Ok(spaces(g, c)),
+            Self::Models => models(g, c),
+            Self::Fields => Ok(fields(g, c)),
+            Self::Users => Ok(users(g, c)),
+            Self::Connections => Ok(connections(c)),
+            Self::Metrics => Ok(metrics(g, c)),
+        }
+    }
+}
+
+/// Returns true if the entity is in the system catalog. A space named `sys` that was created before the catalog was
+/// introduced shadows it
+pub fn is_catalog(g: &impl GlobalInstanceLike, entity: EntityIDRef) -> bool {
+    (entity.space() == SYS_SPACE) && !g.namespace().contains_space(SYS_SPACE)
+}
+
+fn lookup(entity: EntityIDRef) -> QueryResult<CatalogModel> {
+    CatalogModel::ALL
+        .into_iter()
+        .find(|model| model.name() == entity.entity())
+        .ok_or(QueryError::QExecObjectNotFound)
+}
+
+/*
+    select
+*/
+
+/// A column to return, along with the path to the nested value that was selected from it (if any)
+type Projection<'a> = (usize, Vec<SelectPathSegment<'a>>);
+
+/// Run a `select` on a catalog model. Unlike a regular model, the where clause can filter on any column (and doesn't
+/// have to be there at all), so this always returns a set of rows
+pub fn select_resp(
+    g: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    mut select: SelectStatement,
+) -> QueryResult<Response> {
+    let model = lookup(select.entity())?;
+    if select.wants_version() {
+        // catalog rows aren't versioned
+        return Err(QueryError::QExecDmlValidationError);
+    }
+    let filters = select
+        .clauses_mut()
+        .clauses_mut()
+        .drain()
+        .map(|(column, expr)| Ok((model.column(column.as_str())?, expr)))
+        .collect::<QueryResult<Vec<_>>>()?;
+    let window = select.take_window();
+    let projection = if select.is_wildcard() {
+        all_columns(model)
+    } else {
+        select
+            .into_fields()
+            .iter()
+            .map(|field| Ok((model.column(field.as_str())?, field.path().to_vec())))
+            .collect::<QueryResult<_>>()?
+    };
+    let rows = model
+        .rows(g, c)?
+        .into_iter()
+        .filter(|row| {
+            filters
+                .iter()
+                .all(|(column, expr)| dml::matches(&row[*column], expr))
+        })
+        .collect();
+    Ok(rows_resp(window_rows(model, rows, &window)?, &projection))
+}
+
+/// Run a `select all` on a catalog model
+pub fn select_all_resp(
+    g: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    select: SelectAllStatement,
+) -> QueryResult<Response> {
+    let model = lookup(select.entity)?;
+    if select.version {
+        return Err(QueryError::QExecDmlValidationError);
+    }
+    let projection = if select.wildcard {
+        all_columns(model)
+    } else {
+        select
+            .fields
+            .iter()
+            .map(|field| Ok((model.column(field.as_str())?, vec![])))
+            .collect::<QueryResult<_>>()?
+    };
+    let rows = window_rows(model, model.rows(g, c)?, &select.window())?;
+    Ok(rows_resp(rows, &projection))
+}
+
+fn all_columns(model: CatalogModel) -> Vec<Projection<'static>> {
+    (0..model.columns().len())
+        .map(|column| (column, vec![]))
+        .collect()
+}
+
+/// Order and page the rows
+fn window_rows(
+    model: CatalogModel,
+    mut rows: Vec<CatalogRow>,
+    window: &SelectWindow,
+) -> QueryResult<Vec<CatalogRow>> {
+    if let Some(order) = window.order {
+        let column = model.column(order.field().as_str())?;
+        if rows
+            .iter()
+            .any(|row| !row[column].is_null() && (row[column].kind() == TagClass::List))
+        {
+            // lists can't be ordered
+            return Err(QueryError::QExecDmlValidationError);
+        }
+        let mut keyed: Vec<_> = rows
+            .into_iter()
+            .map(|row| (SortKey::new(&row[column]), row))
+            .collect();
+        keyed.sort_by(|(a, _), (b, _)| {
+            let ord = a.cmp(b);
+            if order.is_desc() {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+        rows = keyed.into_iter().map(|(_, row)| row).collect();
+    }
+    let limit = window.limit.map_or(usize::MAX, |limit| limit as usize);
+    Ok(rows
+        .into_iter()
+        .skip(window.offset as usize)
+        .take(limit)
+        .collect())
+}
+
+fn rows_resp(rows: Vec<CatalogRow>, projection: &[Projection]) -> Response {
+    let mut data = vec![];
+    for row in rows.iter() {
+        IntegerRepr::scoped(projection.len() as u64, |repr| data.extend(repr));
+        data.push(b'\n');
+        for (column, path) in projection {
+            dml::project(&row[*column], path, &mut |dc| encode_cell(&mut data, dc));
+        }
+    }
+    Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: rows.len(),
+        data: data.into(),
+    }
+}
+
+/*
+    rows
+*/
+
+fn str_cell(s: &str) -> Datacell {
+    Datacell::new_str(s.into())
+}
+
+fn uint_cell(u: u64) -> Datacell {
+    Datacell::new_uint_default(u)
+}
+
+fn str_or_null(s: Option<&str>) -> Datacell {
+    s.map_or_else(Datacell::null, str_cell)
+}
+
+/// Returns what `f` returns for every model that the client can see, sorted by space and model name
+fn visible_models<T>(
+    g: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    f: impl Fn(&Model) -> T,
+) -> Vec<(ModelUniqueID, T)> {
+    let snapshot = {
+        let models = g.namespace().idx_models().read();
+        models
+            .iter()
+            .map(|(id, model)| {
+                (
+                    ModelUniqueID::new(id.space(), id.entity(), model.get_uuid()),
+                    f(model),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    // the grants are checked once we've released the namespace lock
+    let mut ret: Vec<_> = snapshot
+        .into_iter()
+        .filter(|(id, _)| can_access(g, c, id.space(), id.model()))
+        .collect();
+    ret.sort_unstable_by(|(a, _), (b, _)| (a.space(), a.model()).cmp(&(b.space(), b.model())));
+    ret
+}
+
+/// `sys.spaces`: `(name, models)`, with the number of models in the space that the client can see
+fn spaces(g: &impl GlobalInstanceLike, c: &ClientLocalState) -> Vec<CatalogRow> {
+    let mut spaces: Vec<Box<str>> = g.namespace().idx().read().keys().cloned().collect();
+    spaces.sort_unstable();
+    let models = visible_models(g, c, |_| ());
+    spaces
+        .into_iter()
+        .map(|space| {
+            let count = models
+                .iter()
+                .filter(|(id, _)| id.space() == &*space)
+                .count();
+            vec![Datacell::new_str(space), uint_cell(count as u64)]
+        })
+        .collect()
+}
+
+/// `sys.models`: `(space_name, name, primary_key, rows, size, schema_version)`
+fn models(g: &impl GlobalInstanceLike, c: &ClientLocalState) -> QueryResult<Vec<CatalogRow>> {
+    visible_models(g, c, |mdl| {
+        (
+            str_cell(mdl.p_key()),
+            mdl.primary_index().count() as u64,
+            mdl.delta_state().schema_current_version().value_u64(),
+        )
+    })
+    .into_iter()
+    .map(|(id, (p_key, rows, schema_version))| {
+        Ok(vec![
+            str_cell(id.space()),
+            str_cell(id.model()),
+            p_key,
+            uint_cell(rows),
+            uint_cell(disk_size(g, &id)?),
+            uint_cell(schema_version),
+        ])
+    })
+    .collect()
+}
+
+/// `sys.fields`: `(space_name, model_name, name, field_type, nullable, primary_key)`
+fn fields(g: &impl GlobalInstanceLike, c: &ClientLocalState) -> Vec<CatalogRow> {
+    visible_models(g, c, |mdl| {
+        mdl.fields()
+            .stseq_ord_kv()
+            .map(|(name, field)| {
+                (
+                    str_cell(name),
+                    str_cell(&field.describe_type()),
+                    Datacell::new_bool(field.is_nullable()),
+                    Datacell::new_bool(name.as_str() == mdl.p_key()),
+                )
+            })
+            .collect::<Vec<_>>()
+    })
+    .into_iter()
+    .flat_map(|(id, fields)| {
+        fields
+            .into_iter()
+            .map(move |(field, ty, nullable, primary_key)| {
+                vec![
+                    str_cell(id.space()),
+                    str_cell(id.model()),
+                    field,
+                    ty,
+                    nullable,
+                    primary_key,
+                ]
+            })
+    })
+    .collect()
+}
+
+/// `sys.users`: `(name, root, grants, queries_per_sec, result_bytes, connections)`. The grants are null if the user
+/// isn't restricted, and a limit of `0` means that there is no limit. Users other than root can only see themselves
+fn users(g: &impl GlobalInstanceLike, c: &ClientLocalState) -> Vec<CatalogRow> {
+    let auth = g.sys_store().system_store().auth_data().read();
+    let mut users: Vec<_> = auth
+        .users()
+        .iter()
+        .filter(|(username, _)| c.is_root() || (c.username() == username.as_ref()))
+        .map(|(username, user)| {
+            let grants = match user.grants() {
+                Some(grants) => Datacell::new_list(
+                    grants
+                        .spaces()
+                        .map(str_cell)
+                        .chain(
+                            grants
+                                .models()
+                                .map(|(space, model)| str_cell(&format!("{space}.{model}"))),
+                        )
+                        .collect(),
+                ),
+                None => Datacell::null(),
+            };
+            let quota = user.quota();
+            vec![
+                str_cell(username),
+                Datacell::new_bool(username.as_ref() == SysAuthUser::USER_ROOT),
+                grants,
+                uint_cell(quota.queries_per_sec()),
+                uint_cell(quota.result_bytes()),
+                uint_cell(quota.connections()),
+            ]
+        })
+        .collect();
+    users.sort_unstable_by(|a, b| a[0].str().cmp(b[0].str()));
+    users
+}
+
+/// `sys.connections`: `(id, user, address, state, connected, duration, query)`, just like `sysctl report connections`
+fn connections(c: &ClientLocalState) -> Vec<CatalogRow> {
+    connections::list((!c.is_root()).then_some(c.username()))
+        .into_iter()
+        .map(|connection| {
+            vec![
+                uint_cell(connection.id),
+                str_or_null(connection.user.as_deref()),
+                str_cell(&connection.peer.to_string()),
+                str_cell(connection.state.as_str()),
+                uint_cell(connection.connected.as_millis() as u64),
+                uint_cell(connection.in_state.as_millis() as u64),
+                str_or_null(connection.query.as_deref()),
+            ]
+        })
+        .collect()
+}
+
+/// `sys.metrics`: `(name, kind, space_name, model_name, sample)`, with the same samples that the metrics endpoint
+/// serves. The space and model are null unless this is a per-model sample
+fn metrics(g: &impl GlobalInstanceLike, c: &ClientLocalState) -> Vec<CatalogRow> {
+    metrics::samples(g)
+        .into_iter()
+        .filter(|sample| match &sample.model {
+            Some((space, model)) => can_access(g, c, space, model),
+            None => true,
+        })
+        .map(|sample| {
+            let (space, model) = match sample.model {
+                Some((space, model)) => (Datacell::new_str(space), Datacell::new_str(model)),
+                None => (Datacell::null(), Datacell::null()),
+            };
+            vec![
+                str_cell(sample.name),
+                str_cell(sample.kind),
+                space,
+                model,
+                uint_cell(sample.value),
+            ]
+        })
+        .collect()
+}
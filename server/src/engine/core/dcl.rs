@@ -25,44 +25,441 @@
 */
 
 use crate::engine::{
-    data::{tag::TagClass, DictEntryGeneric},
+    core::{ddl_misc, dml::encode_cell, export, import, EntityIDRef},
+    data::{cell::Datacell, tag::TagClass, DictEntryGeneric, DictGeneric},
     error::{QueryError, QueryResult},
-    fractal::GlobalInstanceLike,
-    net::protocol::ClientLocalState,
-    ql::dcl::{SysctlCommand, UserDecl, UserDel},
+    fractal::{cdc::Subscription, startup, GlobalInstanceLike, MemoryUsage},
+    logging,
+    mem::IntegerRepr,
+    net::{
+        connections,
+        protocol::{ClientLocalState, Response, ResponseType},
+    },
+    ql::dcl::{ModelFile, SysctlCommand, UserDecl, UserDel, UserGrant},
+    storage::v1::audit::{AuditEventKind, AuditFilter},
 };
 
 const KEY_PASSWORD: &str = "password";
+const KEY_QUOTA_QUERIES_PER_SEC: &str = "queries_per_sec";
+const KEY_QUOTA_RESULT_BYTES: &str = "result_bytes";
+const KEY_QUOTA_CONNECTIONS: &str = "connections";
 
 pub fn exec<G: GlobalInstanceLike>(
     g: G,
     current_user: &ClientLocalState,
     cmd: SysctlCommand,
-) -> QueryResult<()> {
+) -> QueryResult<Response> {
     if cmd.needs_root() & !current_user.is_root() {
         return Err(QueryError::SysPermissionDenied);
     }
     match cmd {
         SysctlCommand::CreateUser(new) => create_user(&g, new),
         SysctlCommand::DropUser(drop) => drop_user(&g, current_user, drop),
-        SysctlCommand::AlterUser(usermod) => alter_user(&g, current_user, usermod),
+        SysctlCommand::AlterUser(usermod) => alter_user(&g, usermod),
+        SysctlCommand::GrantUser(grant) => grant_user(&g, grant),
+        SysctlCommand::RevokeUser(revoke) => revoke_user(&g, revoke),
+        SysctlCommand::QuotaUser(quota) => quota_user(&g, quota),
         SysctlCommand::ReportStatus => Ok(()),
+        SysctlCommand::Backup(path) => backup(&g, &path),
+        SysctlCommand::Export(model) => export_model(&g, model),
+        SysctlCommand::Import(_) if g.replication_role().is_replica() => {
+            // a replica only accepts writes from its primary
+            return Err(QueryError::SysReadOnly);
+        }
+        SysctlCommand::Import(model) => return import_model(&g, model),
+        SysctlCommand::Subscribe(models) => return subscribe(&g, current_user, models),
+        SysctlCommand::IssueToken => return issue_token(&g, current_user),
+        SysctlCommand::ReportMemory => return report_memory(&g),
+        SysctlCommand::ReportStartup => return report_startup(),
+        SysctlCommand::ReportAudit(filter) => return report_audit(&g, filter),
+        SysctlCommand::ReportQueries => return report_queries(&g, current_user),
+        SysctlCommand::ReportConnections => return report_connections(current_user),
+        SysctlCommand::Kill(id) => kill(&g, current_user, id),
+        SysctlCommand::Promote => return promote(&g),
+        SysctlCommand::Shutdown => shutdown(&g, current_user),
+        SysctlCommand::LogLevel(directives) => set_log_level(current_user, &directives),
     }
+    .map(|_| Response::Empty)
 }
 
-fn alter_user(
+fn issue_token(
     global: &impl GlobalInstanceLike,
     cstate: &ClientLocalState,
-    user: UserDecl,
-) -> QueryResult<()> {
-    if cstate.is_root() {
-        // the root password can only be changed by shutting down the server
-        return Err(QueryError::SysAuthError);
+) -> QueryResult<Response> {
+    let token = global
+        .sys_store()
+        .system_store()
+        .issue_token(cstate.username())?;
+    Ok(Response::Serialized {
+        ty: ResponseType::String,
+        size: token.len(),
+        data: token.into_bytes().into(),
+    })
+}
+
+/// Returns one row for every model: `(space, model, index, deltas, cache, total)`, with the estimated memory (in
+/// bytes) used by the model's rows, its unflushed deltas, its cached rows and all of these together
+fn report_memory(global: &impl GlobalInstanceLike) -> QueryResult<Response> {
+    let mut models = global
+        .namespace()
+        .idx_models()
+        .read()
+        .iter()
+        .map(|(id, model)| {
+            (
+                id.space().to_owned(),
+                id.entity().to_owned(),
+                MemoryUsage::of(model),
+            )
+        })
+        .collect::<Vec<_>>();
+    models.sort_unstable_by(|(sa, ma, _), (sb, mb, _)| (sa, ma).cmp(&(sb, mb)));
+    let mut data = vec![];
+    for (space, model, usage) in models.iter() {
+        IntegerRepr::scoped(6u64, |repr| data.extend(repr));
+        data.push(b'\n');
+        encode_cell(&mut data, &Datacell::new_str(space.as_str().into()));
+        encode_cell(&mut data, &Datacell::new_str(model.as_str().into()));
+        for bytes in [usage.index(), usage.deltas(), usage.cache(), usage.total()] {
+            encode_cell(&mut data, &Datacell::new_uint_default(bytes as u64));
+        }
+    }
+    Ok(Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: models.len(),
+        data: data.into(),
+    })
+}
+
+/// Report the progress of restoring every model on this start, as rows of the space, the model, the number of bytes
+/// restored and the total number of bytes, whether the model was completely restored and the time spent restoring it
+/// (in milliseconds)
+///
+/// This is also served while the data is still being restored
+pub fn report_startup() -> QueryResult<Response> {
+    let models = startup::progress();
+    let mut data = vec![];
+    for model in models.iter() {
+        IntegerRepr::scoped(6u64, |repr| data.extend(repr));
+        data.push(b'\n');
+        encode_cell(&mut data, &Datacell::new_str(model.space().into()));
+        encode_cell(&mut data, &Datacell::new_str(model.model().into()));
+        encode_cell(&mut data, &Datacell::new_uint_default(model.done()));
+        encode_cell(&mut data, &Datacell::new_uint_default(model.total()));
+        encode_cell(&mut data, &Datacell::new_bool(model.finished()));
+        encode_cell(
+            &mut data,
+            &Datacell::new_uint_default(model.elapsed().as_millis() as u64),
+        );
+    }
+    Ok(Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: models.len(),
+        data: data.into(),
+    })
+}
+
+/// Returns the (most recent) events in the audit log that match the filter, oldest first, as rows of the time (in
+/// milliseconds since the UNIX epoch), the kind of event, whether the action succeeded, the user, the address of the
+/// client and the query (or, for logins, the auth mode)
+fn report_audit(
+    global: &impl GlobalInstanceLike,
+    filter: Option<DictGeneric>,
+) -> QueryResult<Response> {
+    let filter = match filter {
+        Some(filter) => get_audit_filter(filter)?,
+        None => AuditFilter::all(),
+    };
+    let events = global.sys_store().read_audit_log(filter)?;
+    let mut data = vec![];
+    for event in events.iter() {
+        IntegerRepr::scoped(6u64, |repr| data.extend(repr));
+        data.push(b'\n');
+        encode_cell(&mut data, &Datacell::new_uint_default(event.time()));
+        encode_cell(&mut data, &Datacell::new_str(event.kind().as_str().into()));
+        encode_cell(&mut data, &Datacell::new_bool(event.succeeded()));
+        encode_cell(&mut data, &Datacell::new_str(event.user().into()));
+        encode_cell(&mut data, &Datacell::new_str(event.addr().into()));
+        encode_cell(&mut data, &Datacell::new_str(event.detail().into()));
+    }
+    Ok(Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: events.len(),
+        data: data.into(),
+    })
+}
+
+/// Returns one row for every query that is being run (by the current user, unless it's root):
+/// `(id, user, elapsed, killed, query)`, with the time (in milliseconds) that the query has been running for
+fn report_queries(
+    global: &impl GlobalInstanceLike,
+    cstate: &ClientLocalState,
+) -> QueryResult<Response> {
+    let queries = global
+        .queries()
+        .list((!cstate.is_root()).then_some(cstate.username()));
+    let mut data = vec![];
+    for query in queries.iter() {
+        IntegerRepr::scoped(5u64, |repr| data.extend(repr));
+        data.push(b'\n');
+        encode_cell(&mut data, &Datacell::new_uint_default(query.id()));
+        encode_cell(&mut data, &Datacell::new_str(query.user().into()));
+        encode_cell(
+            &mut data,
+            &Datacell::new_uint_default(query.elapsed().as_millis() as u64),
+        );
+        encode_cell(&mut data, &Datacell::new_bool(query.is_killed()));
+        encode_cell(&mut data, &Datacell::new_str(query.query().into()));
+    }
+    Ok(Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: queries.len(),
+        data: data.into(),
+    })
+}
+
+/// Returns one row for every open connection (of the current user, unless it's root):
+/// `(id, user, address, state, connected, duration, query)`, with the time (in milliseconds) since the connection was
+/// accepted and since it went into its current state. The user is null until the client authenticates, and the query
+/// is null unless one is running (or started a subscription)
+fn report_connections(cstate: &ClientLocalState) -> QueryResult<Response> {
+    let connections = connections::list((!cstate.is_root()).then_some(cstate.username()));
+    let mut data = vec![];
+    for connection in connections.iter() {
+        let str_or_null = |s: &Option<Box<str>>| match s {
+            Some(s) => Datacell::new_str(s.clone()),
+            None => Datacell::null(),
+        };
+        IntegerRepr::scoped(7u64, |repr| data.extend(repr));
+        data.push(b'\n');
+        encode_cell(&mut data, &Datacell::new_uint_default(connection.id));
+        encode_cell(&mut data, &str_or_null(&connection.user));
+        encode_cell(
+            &mut data,
+            &Datacell::new_str(connection.peer.to_string().into()),
+        );
+        encode_cell(
+            &mut data,
+            &Datacell::new_str(connection.state.as_str().into()),
+        );
+        encode_cell(
+            &mut data,
+            &Datacell::new_uint_default(connection.connected.as_millis() as u64),
+        );
+        encode_cell(
+            &mut data,
+            &Datacell::new_uint_default(connection.in_state.as_millis() as u64),
+        );
+        encode_cell(&mut data, &str_or_null(&connection.query));
+    }
+    Ok(Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: connections.len(),
+        data: data.into(),
+    })
+}
+
+/// Kill a query. Users other than root can only kill their own queries
+fn kill(global: &impl GlobalInstanceLike, cstate: &ClientLocalState, id: u64) -> QueryResult<()> {
+    global
+        .queries()
+        .kill(id, (!cstate.is_root()).then_some(cstate.username()))
+}
+
+fn get_audit_filter(mut filter: DictGeneric) -> QueryResult<AuditFilter> {
+    let user = take_dict_key(&mut filter, "user", |d| {
+        d.into_str().map(String::into_boxed_str)
+    })?;
+    let kind = take_dict_key(&mut filter, "kind", |d| {
+        d.try_str().and_then(AuditEventKind::from_str)
+    })?;
+    let since = take_dict_key(&mut filter, "since", |d| d.try_uint())?;
+    let until = take_dict_key(&mut filter, "until", |d| d.try_uint())?;
+    let count = take_dict_key(&mut filter, "count", |d| {
+        d.try_uint().and_then(|count| usize::try_from(count).ok())
+    })?;
+    if !filter.is_empty() {
+        // invalid properties
+        return Err(QueryError::QExecDdlInvalidProperties);
+    }
+    Ok(AuditFilter::new(user, kind, since, until, count))
+}
+
+fn take_dict_key<T>(
+    filter: &mut DictGeneric,
+    key: &str,
+    f: impl FnOnce(Datacell) -> Option<T>,
+) -> QueryResult<Option<T>> {
+    match filter.remove(key) {
+        None => Ok(None),
+        Some(DictEntryGeneric::Data(d)) => {
+            f(d).map(Some).ok_or(QueryError::QExecDdlInvalidProperties)
+        }
+        Some(_) => Err(QueryError::QExecDdlInvalidProperties),
+    }
+}
+
+fn backup(global: &impl GlobalInstanceLike, path: &str) -> QueryResult<()> {
+    match global.backup_to(path) {
+        Ok(()) => {
+            info!("wrote backup to `{path}`");
+            Ok(())
+        }
+        Err(e) => {
+            error!("failed to write backup to `{path}`: {e}");
+            Err(e.into())
+        }
+    }
+}
+
+/// Returns true if this replica was promoted to a primary, and false if it already was one
+fn promote(global: &impl GlobalInstanceLike) -> QueryResult<Response> {
+    match global.promote() {
+        Ok(promoted) => {
+            if promoted {
+                info!("promoted replica to primary. remove `replica_of` from the configuration before restarting");
+            }
+            Ok(Response::Bool(promoted))
+        }
+        Err(e) => {
+            error!("failed to promote replica: {e}");
+            Err(e.into())
+        }
+    }
+}
+
+/// The server stops accepting connections and shuts down once the queries that are running (including this one) complete
+fn shutdown(global: &impl GlobalInstanceLike, current_user: &ClientLocalState) -> QueryResult<()> {
+    info!("shutdown requested by user `{}`", current_user.username());
+    global.request_shutdown();
+    Ok(())
+}
+
+/// Replace the log filter directives, until the server restarts
+fn set_log_level(current_user: &ClientLocalState, directives: &str) -> QueryResult<()> {
+    if !logging::set_filter(directives) {
+        return Err(QueryError::QLInvalidSyntax);
+    }
+    info!(
+        "log filter set to `{directives}` by user `{}`",
+        current_user.username()
+    );
+    Ok(())
+}
+
+fn export_model(global: &impl GlobalInstanceLike, model: ModelFile) -> QueryResult<()> {
+    let entity = model.entity();
+    match export::export_model(global, entity, model.path(), model.format()) {
+        Ok(rows) => {
+            info!(
+                "exported {rows} rows from {}.{} to `{}`",
+                entity.space(),
+                entity.entity(),
+                model.path()
+            );
+            Ok(())
+        }
+        Err(e) => {
+            error!(
+                "failed to export {}.{} to `{}`: {e:?}",
+                entity.space(),
+                entity.entity(),
+                model.path()
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Returns a row with the number of rows that were imported, the number of rows that were rejected and a list of
+/// `[line, error code]` for the rejected rows
+fn import_model(global: &impl GlobalInstanceLike, model: ModelFile) -> QueryResult<Response> {
+    let entity = model.entity();
+    let report = match import::import_model(global, entity, model.path(), model.format()) {
+        Ok(report) => report,
+        Err(e) => {
+            error!(
+                "failed to import `{}` into {}.{}: {e:?}",
+                model.path(),
+                entity.space(),
+                entity.entity(),
+            );
+            return Err(e);
+        }
+    };
+    info!(
+        "imported {} rows from `{}` into {}.{} ({} rejected)",
+        report.imported(),
+        model.path(),
+        entity.space(),
+        entity.entity(),
+        report.rejected()
+    );
+    let errors = report
+        .errors()
+        .iter()
+        .map(|(line, e)| {
+            Datacell::from([
+                Datacell::new_uint_default(*line),
+                Datacell::new_uint_default(e.value_u8() as u64),
+            ])
+        })
+        .collect();
+    let mut data = vec![];
+    for cell in [
+        Datacell::new_uint_default(report.imported()),
+        Datacell::new_uint_default(report.rejected()),
+        Datacell::new_list(errors),
+    ] {
+        encode_cell(&mut data, &cell);
+    }
+    Ok(Response::Serialized {
+        ty: ResponseType::Row,
+        size: 3,
+        data: data.into(),
+    })
+}
+
+/// Subscribe to the changes of all the given models (the client needs access to every one of them)
+pub(super) fn subscribe(
+    global: &impl GlobalInstanceLike,
+    cstate: &ClientLocalState,
+    models: Vec<EntityIDRef>,
+) -> QueryResult<Response> {
+    let mut sub = Subscription::new();
+    for entity in models {
+        if !ddl_misc::can_access(global, cstate, entity.space(), entity.entity()) {
+            return Err(QueryError::SysPermissionDenied);
+        }
+        global.namespace().with_model(entity, |mdl| {
+            sub.attach(
+                mdl.delta_state().change_feed(),
+                entity.space(),
+                entity.entity(),
+            );
+            Ok(())
+        })?;
     }
+    Ok(Response::Subscription(sub))
+}
+
+fn alter_user(global: &impl GlobalInstanceLike, user: UserDecl) -> QueryResult<()> {
     let (username, password) = get_user_data(user)?;
     global.sys_store().alter_user(username, password)
 }
 
+fn grant_user(global: &impl GlobalInstanceLike, grant: UserGrant) -> QueryResult<()> {
+    global
+        .sys_store()
+        .grant_user(grant.username(), grant.space(), grant.model())
+}
+
+fn revoke_user(global: &impl GlobalInstanceLike, revoke: UserGrant) -> QueryResult<()> {
+    global
+        .sys_store()
+        .revoke_user(revoke.username(), revoke.space(), revoke.model())
+}
+
 fn create_user(global: &impl GlobalInstanceLike, user: UserDecl) -> QueryResult<()> {
     let (username, password) = get_user_data(user)?;
     global.sys_store().create_new_user(username, password)
@@ -82,6 +479,22 @@ fn get_user_data(mut user: UserDecl) -> Result<(String, String), QueryError> {
     Ok((username, password))
 }
 
+/// Set the resource quota for a user, as in `sysctl quota user sayan with { queries_per_sec: 100, connections: 4 }`.
+/// Limits that aren't provided are left as they are, and a limit of `0` removes it
+fn quota_user(global: &impl GlobalInstanceLike, mut user: UserDecl) -> QueryResult<()> {
+    let mut limit = |key| take_dict_key(user.options_mut(), key, |d| d.try_uint());
+    let queries_per_sec = limit(KEY_QUOTA_QUERIES_PER_SEC)?;
+    let result_bytes = limit(KEY_QUOTA_RESULT_BYTES)?;
+    let connections = limit(KEY_QUOTA_CONNECTIONS)?;
+    if !user.options().is_empty() {
+        // invalid properties
+        return Err(QueryError::QExecDdlInvalidProperties);
+    }
+    global
+        .sys_store()
+        .set_user_quota(user.username(), queries_per_sec, result_bytes, connections)
+}
+
 fn drop_user(
     global: &impl GlobalInstanceLike,
     cstate: &ClientLocalState,
@@ -25,12 +25,141 @@
 */
 
 use crate::engine::{
+    core::{dml::encode_cell, EntityIDRef},
+    data::cell::Datacell,
     error::{QueryError, QueryResult},
-    fractal::GlobalInstanceLike,
+    fractal::{GlobalInstanceLike, ModelUniqueID},
+    idx::STIndexSeq,
+    mem::IntegerRepr,
     net::protocol::{ClientLocalState, Response, ResponseType},
     ql::ddl::Inspect,
 };
 
+/// Returns true if the client can see the given model. Users without a grant on a model can't see it at all
+pub(super) fn can_access(
+    g: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    space: &str,
+    model: &str,
+) -> bool {
+    c.is_root()
+        || g.sys_store()
+            .system_store()
+            .auth_data()
+            .read()
+            .verify_user_access(c.username(), space, Some(model))
+            .is_ok()
+}
+
+/// Returns the size of the model's storage on disk (zero if the model doesn't have a driver yet)
+pub(super) fn disk_size(g: &impl GlobalInstanceLike, id: &ModelUniqueID) -> QueryResult<u64> {
+    match g.model_drivers().read().get(id) {
+        Some(drv) => Ok(drv.storage().lock().size()?),
+        None => Ok(0),
+    }
+}
+
+/// Returns the ID, row count and size of every model in `space` (or in every space, if no space is given) that the
+/// client can see, sorted by space and model name
+fn list_models(
+    g: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    space: Option<&str>,
+) -> QueryResult<Vec<(ModelUniqueID, usize, u64)>> {
+    let snapshot = {
+        let models = g.namespace().idx_models().read();
+        models
+            .iter()
+            .filter(|(id, _)| space.map_or(true, |space| id.space() == space))
+            .map(|(id, model)| {
+                (
+                    ModelUniqueID::new(id.space(), id.entity(), model.get_uuid()),
+                    model.primary_index().count(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    // we don't hold the namespace lock while reading the drivers because fractal acquires these in the reverse order
+    let mut ret = snapshot
+        .into_iter()
+        .filter(|(id, _)| can_access(g, c, id.space(), id.model()))
+        .map(|(id, rows)| disk_size(g, &id).map(|size| (id, rows, size)))
+        .collect::<QueryResult<Vec<_>>>()?;
+    ret.sort_unstable_by(|(a, _, _), (b, _, _)| {
+        (a.space(), a.model()).cmp(&(b.space(), b.model()))
+    });
+    Ok(ret)
+}
+
+/// Returns a single row describing the model:
+/// `(primary key, declaration, rows, size, schema version, [(field name, type, nullable), ...])`
+fn inspect_model(
+    g: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    entity: EntityIDRef,
+) -> QueryResult<Response> {
+    if !can_access(g, c, entity.space(), entity.entity()) {
+        return Err(QueryError::SysPermissionDenied);
+    }
+    let (id, [p_key, decl, rows, schema_version, fields]) =
+        g.namespace().with_model(entity, |mdl| {
+            let fields = mdl
+                .fields()
+                .stseq_ord_kv()
+                .map(|(name, field)| {
+                    Datacell::from([
+                        Datacell::new_str(name.to_string().into_boxed_str()),
+                        Datacell::new_str(field.describe_type().into_boxed_str()),
+                        Datacell::new_bool(field.is_nullable()),
+                    ])
+                })
+                .collect();
+            Ok((
+                ModelUniqueID::new(entity.space(), entity.entity(), mdl.get_uuid()),
+                [
+                    Datacell::new_str(mdl.p_key().into()),
+                    Datacell::new_str(mdl.describe().into()),
+                    Datacell::new_uint_default(mdl.primary_index().count() as u64),
+                    Datacell::new_uint_default(
+                        mdl.delta_state().schema_current_version().value_u64(),
+                    ),
+                    Datacell::new_list(fields),
+                ],
+            ))
+        })?;
+    let size = Datacell::new_uint_default(disk_size(g, &id)?);
+    let row = [p_key, decl, rows, size, schema_version, fields];
+    let mut data = vec![];
+    row.iter().for_each(|cell| encode_cell(&mut data, cell));
+    Ok(Response::Serialized {
+        ty: ResponseType::Row,
+        size: row.len(),
+        data: data.into(),
+    })
+}
+
+/// Returns one row for every model: `(space, model, rows, size)`. The space is skipped if we're only listing
+/// the models in a single space
+fn models_resp(models: Vec<(ModelUniqueID, usize, u64)>, with_space: bool) -> Response {
+    let col_c = 3 + with_space as u64;
+    let mut data = vec![];
+    for (id, rows, size) in models.iter() {
+        IntegerRepr::scoped(col_c, |repr| data.extend(repr));
+        data.push(b'\n');
+        if with_space {
+            encode_cell(&mut data, &Datacell::new_str(id.space().into()));
+        }
+        encode_cell(&mut data, &Datacell::new_str(id.model().into()));
+        encode_cell(&mut data, &Datacell::new_uint_default(*rows as u64));
+        encode_cell(&mut data, &Datacell::new_uint_default(*size));
+    }
+    Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: models.len(),
+        data: data.into(),
+    }
+}
+
 pub fn inspect(
     g: &impl GlobalInstanceLike,
     c: &ClientLocalState,
@@ -70,35 +199,18 @@ pub fn inspect(
             ret.push_str("],\"settings\":{}}");
             ret
         }
-        Inspect::Model(m) => match g.namespace().idx_models().read().get(&m) {
-            Some(m) => format!(
-                "{{\"decl\":\"{}\",\"rows\":{},\"properties\":{{}}}}",
-                m.describe(),
-                m.primary_index().count()
-            ),
-            None => return Err(QueryError::QExecObjectNotFound),
-        },
-        Inspect::Space(s) => match g.namespace().idx().read().get(s.as_str()) {
-            Some(s) => {
-                let mut ret = format!("{{\"models\":[");
-                let mut models_iter = s.models().iter().peekable();
-                while let Some(mdl) = models_iter.next() {
-                    ret.push('\"');
-                    ret.push_str(&mdl);
-                    ret.push('\"');
-                    if models_iter.peek().is_some() {
-                        ret.push(',');
-                    }
-                }
-                ret.push_str("]}}");
-                ret
+        Inspect::Model(m) => return inspect_model(g, c, m),
+        Inspect::Space(s) => {
+            if !g.namespace().contains_space(s.as_str()) {
+                return Err(QueryError::QExecObjectNotFound);
             }
-            None => return Err(QueryError::QExecObjectNotFound),
-        },
+            return Ok(models_resp(list_models(g, c, Some(s.as_str()))?, false));
+        }
+        Inspect::Models => return Ok(models_resp(list_models(g, c, None)?, true)),
     };
     Ok(Response::Serialized {
         ty: ResponseType::String,
         size: ret.len(),
-        data: ret.into_bytes(),
+        data: ret.into_bytes().into(),
     })
 }
@@ -0,0 +1,231 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::sel::{encode_cell, RowIteratorAll, SortKey},
+    crate::engine::{
+        core::{index::Row, model::Model},
+        data::{
+            cell::{Datacell, VirtualDatacell},
+            tag::{DataTag, FullTag, TagClass},
+        },
+        error::{QueryError, QueryResult},
+        fractal::{queries, GlobalInstanceLike},
+        idx::STIndex,
+        net::protocol::{Response, ResponseType},
+        ql::{
+            dml::agg::{Aggregate, AggregateFn, AggregateStatement},
+            lex::Ident,
+        },
+        sync,
+    },
+    std::ops::Bound,
+};
+
+pub fn aggregate_resp(
+    global: &impl GlobalInstanceLike,
+    aggregate: AggregateStatement,
+) -> QueryResult<Response> {
+    let cells = self::aggregate(global, aggregate)?;
+    let mut data = vec![];
+    for cell in cells.iter() {
+        encode_cell(&mut data, cell);
+    }
+    Ok(Response::Serialized {
+        ty: ResponseType::Row,
+        size: cells.len(),
+        data: data.into(),
+    })
+}
+
+/// Run the aggregates over all the rows of the model, or the rows with primary keys matching the where clause
+/// (`pk = x` or `pk > x and pk < y`), returning one cell per aggregate
+pub fn aggregate(
+    global: &impl GlobalInstanceLike,
+    mut aggregate: AggregateStatement,
+) -> QueryResult<Vec<Datacell>> {
+    global.namespace().with_model(aggregate.entity(), |mdl| {
+        let mut accumulators = aggregate
+            .aggregates()
+            .iter()
+            .map(|agg| Accumulator::new(mdl, agg))
+            .collect::<QueryResult<Vec<_>>>()?;
+        let g = sync::atm::cpin();
+        let clause = aggregate.clauses_mut();
+        if clause.clauses_mut().is_empty() {
+            accumulate(mdl, RowIteratorAll::new(&g, mdl), &mut accumulators)?;
+        } else {
+            let range = if clause.has_range() {
+                mdl.resolve_where_range(clause)?
+            } else {
+                let pk = mdl.resolve_where(clause)?;
+                (Bound::Included(pk.clone()), Bound::Included(pk))
+            };
+            // only the primary key can be used to pick rows
+            if !clause.clauses_mut().is_empty() {
+                return Err(QueryError::QExecDmlWhereHasUnindexedColumn);
+            }
+            accumulate(
+                mdl,
+                RowIteratorAll::new_range(&g, mdl, range),
+                &mut accumulators,
+            )?;
+        }
+        Ok(accumulators.into_iter().map(Accumulator::finish).collect())
+    })
+}
+
+fn accumulate<'g, I: Iterator<Item = &'g Row>, L>(
+    mdl: &Model,
+    rows: RowIteratorAll<'g, I, L>,
+    accumulators: &mut [Accumulator],
+) -> QueryResult<()> {
+    for (key, data) in rows {
+        queries::check()?;
+        let vdc = VirtualDatacell::new_pk(key, mdl.p_tag());
+        for accumulator in accumulators.iter_mut() {
+            // count(*) counts primary keys (which are never null)
+            let dc = match accumulator.field {
+                Some(field) if field.as_str() != mdl.p_key() => {
+                    data.fields().st_get(field.as_str()).unwrap()
+                }
+                _ => &*vdc,
+            };
+            accumulator.update(dc)?;
+        }
+    }
+    Ok(())
+}
+
+/// The running value of an aggregate
+enum AggregateState {
+    Count(u64),
+    Min(FullTag, Option<SortKey>),
+    Max(FullTag, Option<SortKey>),
+    SumUInt(Option<u64>),
+    SumSInt(Option<i64>),
+    SumFloat(Option<f64>),
+    Avg { sum: f64, count: u64 },
+}
+
+struct Accumulator<'a> {
+    field: Option<Ident<'a>>,
+    state: AggregateState,
+}
+
+impl<'a> Accumulator<'a> {
+    fn new(mdl: &Model, aggregate: &Aggregate<'a>) -> QueryResult<Self> {
+        let Some(field) = aggregate.field() else {
+            return Ok(Self {
+                field: None,
+                state: AggregateState::Count(0),
+            });
+        };
+        let Some(definition) = mdl.fields().st_get(field.as_str()) else {
+            return Err(QueryError::QExecUnknownField);
+        };
+        let tag = definition.layers()[0].tag();
+        let state = match (aggregate.func(), tag.tag_class()) {
+            (AggregateFn::Count, _) => AggregateState::Count(0),
+            (_, TagClass::List | TagClass::Geo | TagClass::Dict) => {
+                return Err(QueryError::QExecDmlValidationError)
+            }
+            (AggregateFn::Min, _) => AggregateState::Min(tag, None),
+            (AggregateFn::Max, _) => AggregateState::Max(tag, None),
+            (AggregateFn::Sum, TagClass::UnsignedInt) => AggregateState::SumUInt(None),
+            (AggregateFn::Sum, TagClass::SignedInt) => AggregateState::SumSInt(None),
+            (AggregateFn::Sum, TagClass::Float) => AggregateState::SumFloat(None),
+            (AggregateFn::Avg, TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Float) => {
+                AggregateState::Avg { sum: 0.0, count: 0 }
+            }
+            // bools, strings and binary can't be added up
+            _ => return Err(QueryError::QExecDmlValidationError),
+        };
+        Ok(Self {
+            field: Some(field),
+            state,
+        })
+    }
+    /// Add a value to the aggregate. Nulls are ignored
+    fn update(&mut self, dc: &Datacell) -> QueryResult<()> {
+        if dc.is_null() {
+            return Ok(());
+        }
+        match &mut self.state {
+            AggregateState::Count(count) => *count += 1,
+            AggregateState::Min(_, min) => {
+                let key = SortKey::new(dc);
+                if min.as_ref().map_or(true, |min| key.cmp(min).is_lt()) {
+                    *min = Some(key);
+                }
+            }
+            AggregateState::Max(_, max) => {
+                let key = SortKey::new(dc);
+                if max.as_ref().map_or(true, |max| key.cmp(max).is_gt()) {
+                    *max = Some(key);
+                }
+            }
+            AggregateState::SumUInt(sum) => match sum.unwrap_or(0).checked_add(dc.uint()) {
+                Some(new_sum) => *sum = Some(new_sum),
+                None => return Err(QueryError::QExecDmlValidationError),
+            },
+            AggregateState::SumSInt(sum) => match sum.unwrap_or(0).checked_add(dc.sint()) {
+                Some(new_sum) => *sum = Some(new_sum),
+                None => return Err(QueryError::QExecDmlValidationError),
+            },
+            AggregateState::SumFloat(sum) => *sum = Some(sum.unwrap_or(0.0) + dc.float()),
+            AggregateState::Avg { sum, count } => {
+                *sum += match dc.kind() {
+                    TagClass::UnsignedInt => dc.uint() as f64,
+                    TagClass::SignedInt => dc.sint() as f64,
+                    _ => dc.float(),
+                };
+                *count += 1;
+            }
+        }
+        Ok(())
+    }
+    /// Returns the value of the aggregate (null if no values were aggregated, except for count)
+    fn finish(self) -> Datacell {
+        match self.state {
+            AggregateState::Count(count) => Datacell::new_uint_default(count),
+            AggregateState::Min(tag, key) | AggregateState::Max(tag, key) => {
+                key.map_or_else(Datacell::null, |key| key.into_cell(tag))
+            }
+            AggregateState::SumUInt(sum) => {
+                sum.map_or_else(Datacell::null, Datacell::new_uint_default)
+            }
+            AggregateState::SumSInt(sum) => {
+                sum.map_or_else(Datacell::null, Datacell::new_sint_default)
+            }
+            AggregateState::SumFloat(sum) => {
+                sum.map_or_else(Datacell::null, Datacell::new_float_default)
+            }
+            AggregateState::Avg { count: 0, .. } => Datacell::null(),
+            AggregateState::Avg { sum, count } => Datacell::new_float_default(sum / count as f64),
+        }
+    }
+}
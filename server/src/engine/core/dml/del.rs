@@ -25,9 +25,14 @@
 */
 
 use crate::engine::{
-    core::{self, dml::QueryExecMeta, model::delta::DataDeltaKind},
+    core::{
+        self,
+        dml::{txn, Changes, QueryExecMeta, ReturnedRows},
+        index::{Row, RowData},
+        model::{delta::DataDeltaKind, Model},
+    },
     error::{QueryError, QueryResult},
-    fractal::GlobalInstanceLike,
+    fractal::{queries, GlobalInstanceLike},
     idx::MTIndex,
     net::protocol::Response,
     ql::dml::del::DeleteStatement,
@@ -38,31 +43,127 @@ pub fn delete_resp(
     global: &impl GlobalInstanceLike,
     delete: DeleteStatement,
 ) -> QueryResult<Response> {
-    self::delete(global, delete).map(|_| Response::Empty)
+    let entity = delete.entity();
+    super::log_write("delete", entity, self::delete_rows(global, delete))
+        .map(Changes::into_response)
 }
 
-pub fn delete(global: &impl GlobalInstanceLike, mut delete: DeleteStatement) -> QueryResult<()> {
-    core::with_model_for_data_update(global, delete.entity(), |model| {
-        let g = sync::atm::cpin();
-        let delta_state = model.delta_state();
-        let _idx_latch = model.primary_index().acquire_cd();
-        // create new version
-        let new_version = delta_state.create_new_data_delta_version();
-        match model
-            .primary_index()
-            .__raw_index()
-            .mt_delete_return_entry(&model.resolve_where(delete.clauses_mut())?, &g)
-        {
-            Some(row) => {
-                let dp = delta_state.append_new_data_delta_with(
-                    DataDeltaKind::Delete,
-                    row.clone(),
-                    new_version,
-                    &g,
-                );
-                Ok(QueryExecMeta::new(dp))
+#[cfg(test)]
+pub fn delete(global: &impl GlobalInstanceLike, delete: DeleteStatement) -> QueryResult<()> {
+    delete_rows(global, delete).map(|_| ())
+}
+
+/// Run a delete, returning the deleted rows if they were asked for (`returning ...`). With a limit (`delete from
+/// model where ... limit n`), every row that satisfies the where clause is deleted (up to the limit) and the number
+/// of rows that were deleted is returned. Every row is checked again once it's locked and each delete is journaled on
+/// its own
+pub fn delete_rows(
+    global: &impl GlobalInstanceLike,
+    mut delete: DeleteStatement,
+) -> QueryResult<Changes> {
+    let returning = delete.take_returning();
+    let mut count = None;
+    let mut returned = None;
+    core::with_model_for_data_delete(global, delete.entity(), |model| {
+        returned = ReturnedRows::resolve(model, returning)?;
+        match delete.limit() {
+            Some(limit) => {
+                let (deleted, meta) = delete_many(model, delete, limit, &mut returned)?;
+                count = Some(deleted);
+                Ok(meta)
             }
-            None => Err(QueryError::QExecDmlRowNotFound),
+            None => delete_one(model, delete, &mut returned),
         }
-    })
+    })?;
+    Ok(Changes::new(count, returned))
+}
+
+/// Delete the single row picked by the where clause
+fn delete_one(
+    model: &Model,
+    mut delete: DeleteStatement,
+    returned: &mut Option<ReturnedRows>,
+) -> QueryResult<QueryExecMeta> {
+    let g = sync::atm::cpin();
+    let delta_state = model.delta_state();
+    let _idx_latch = model.primary_index().acquire_cd();
+    let key = model.resolve_where(delete.clauses_mut())?;
+    // hold the row lock across the version check, saving the row for pinned snapshots and the delete
+    let Some(row) = model.primary_index().select(key.clone(), &g) else {
+        return Err(QueryError::QExecDmlRowNotFound);
+    };
+    let row_data_wl = row.resolve_schema_deltas_and_lock(delta_state);
+    if let Some(version) = delete.version() {
+        if row_data_wl.get_version().value_u64() != version {
+            return Err(QueryError::QExecDmlConditionFailed);
+        }
+    }
+    txn::take_intent(model, row.d_key())?;
+    match delete_locked_row(model, row, &row_data_wl, &g, returned) {
+        Some(dp) => Ok(QueryExecMeta::new(dp)),
+        None => Err(QueryError::QExecDmlRowNotFound),
+    }
+}
+
+/// Delete every row that satisfies the where clause, up to the limit, returning the number of rows that were deleted
+fn delete_many(
+    model: &Model,
+    mut delete: DeleteStatement,
+    limit: u64,
+    returned: &mut Option<ReturnedRows>,
+) -> QueryResult<(u64, QueryExecMeta)> {
+    let g = sync::atm::cpin();
+    let delta_state = model.delta_state();
+    let _idx_latch = model.primary_index().acquire_cd();
+    let filter = model.resolve_where_filter(delete.clauses_mut())?;
+    let mut deleted = 0;
+    let mut meta = QueryExecMeta::zero();
+    filter.scan(model, &g, |row| {
+        queries::check()?;
+        if deleted == limit {
+            return Ok(false);
+        }
+        let row_data_wl = row.resolve_schema_deltas_and_lock(delta_state);
+        if filter.matches(model, row.d_key(), row_data_wl.fields()) {
+            txn::take_intent(model, row.d_key())?;
+            if let Some(dp) = delete_locked_row(model, row, &row_data_wl, &g, returned) {
+                meta = QueryExecMeta::new(dp);
+                deleted += 1;
+            }
+        }
+        Ok(true)
+    })?;
+    Ok((deleted, meta))
+}
+
+/// Delete a row that is locked (and at the current schema version), returning the delta hint. Returns [`None`] if
+/// the row was already deleted
+fn delete_locked_row(
+    model: &Model,
+    row: &Row,
+    data: &RowData,
+    g: &sync::atm::Guard,
+    returned: &mut Option<ReturnedRows>,
+) -> Option<usize> {
+    let delta_state = model.delta_state();
+    // create new version (before looking for snapshots, see `Model::pin_snapshot`)
+    let new_version = delta_state.create_new_data_delta_version();
+    delta_state.snapshot_preserve(row, data);
+    let row = model
+        .primary_index()
+        .__raw_index()
+        .mt_delete_return_entry(row.d_key(), g)?;
+    delta_state.account_row(row.estimated_size(data), 0);
+    txn::record_undo(model, || {
+        txn::restore_row(model, row.d_key(), data.fields())
+    });
+    let delta = txn::publish_change(
+        model,
+        DataDeltaKind::Delete,
+        new_version,
+        row,
+        data.fields(),
+    );
+    super::push_returned(returned, model, row.d_key(), data.fields());
+    Some(delta.map_or(0, |delta| delta_state.append_new_data_delta(delta, g)))
 }
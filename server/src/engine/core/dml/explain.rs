@@ -0,0 +1,329 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{dml::RangeScan, model::Model, query_meta::AssignmentOperator},
+    data::{
+        cell::Datacell,
+        tag::{DataTag, TagClass},
+    },
+    error::{QueryError, QueryResult},
+    fractal::GlobalInstanceLike,
+    idx::{MTIndexExt, STIndex},
+    net::protocol::{ClientLocalState, Response, ResponseType},
+    ql::{
+        dml::{explain::ExplainStatement, sel::SelectWindow, upd::AssignmentExpression},
+        lex::Ident,
+    },
+    sync::atm::cpin,
+};
+
+/*
+    explain
+    ---
+    An explained statement goes through the same planning (and validation) steps that it would if it were actually
+    executed, but we stop right before we touch any rows. Since we don't keep any statistics on the data, the row
+    estimates are upper bounds derived from the size of the primary index. The one exception are primary key ranges
+    (and prefixes): the primary index is ordered, so we seek to the start of the range and count the keys in it (up to
+    the end of the window).
+*/
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The path used to find the rows that a statement touches
+pub enum AccessPath {
+    /// a single row is found using its primary key
+    PrimaryKeyLookup,
+    /// rows in a primary key range are scanned
+    PrimaryKeyRange,
+    /// rows are found using the full-text index of a field
+    TextIndexLookup,
+    /// rows are found using the geohash index of a field
+    GeoIndexLookup,
+    /// rows with primary keys that start with a prefix are scanned
+    PrimaryKeyPrefix,
+    /// a batch of rows is found using their primary keys
+    PrimaryKeyBatch,
+    /// rows are scanned in index order (up to a limit)
+    FullScan,
+}
+
+impl AccessPath {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::PrimaryKeyLookup => "pk_lookup",
+            Self::PrimaryKeyRange => "pk_range_scan",
+            Self::TextIndexLookup => "fulltext_lookup",
+            Self::GeoIndexLookup => "geo_lookup",
+            Self::PrimaryKeyPrefix => "pk_prefix_scan",
+            Self::PrimaryKeyBatch => "pk_batch_lookup",
+            Self::FullScan => "full_scan",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// The plan chosen for a DML statement
+pub struct QueryPlan {
+    statement: &'static str,
+    access: AccessPath,
+    estimated_rows: u64,
+    model_rows: u64,
+}
+
+impl QueryPlan {
+    fn new(
+        statement: &'static str,
+        access: AccessPath,
+        estimated_rows: u64,
+        model_rows: u64,
+    ) -> Self {
+        Self {
+            statement,
+            access,
+            estimated_rows,
+            model_rows,
+        }
+    }
+    pub fn statement(&self) -> &'static str {
+        self.statement
+    }
+    pub fn access(&self) -> AccessPath {
+        self.access
+    }
+    /// An upper bound on the number of rows that the statement will touch
+    pub fn estimated_rows(&self) -> u64 {
+        self.estimated_rows
+    }
+    pub fn model_rows(&self) -> u64 {
+        self.model_rows
+    }
+}
+
+pub fn explain_resp(
+    global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    explain: ExplainStatement,
+) -> QueryResult<Response> {
+    let plan = self::explain(global, c, explain)?;
+    let mut data = vec![];
+    for cell in [
+        Datacell::new_str(plan.statement().into()),
+        Datacell::new_str(plan.access().as_str().into()),
+        Datacell::new_uint_default(plan.estimated_rows()),
+        Datacell::new_uint_default(plan.model_rows()),
+    ] {
+        super::sel::encode_cell(&mut data, &cell);
+    }
+    Ok(Response::Serialized {
+        ty: ResponseType::Row,
+        size: 4,
+        data: data.into(),
+    })
+}
+
+pub fn explain(
+    global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    explain: ExplainStatement,
+) -> QueryResult<QueryPlan> {
+    let space = explain.entity().space();
+    global
+        .namespace()
+        .with_model_and_index(explain.entity(), |mdl, models| {
+            let model_rows = mdl.primary_index().count_approx() as u64;
+            let lookup = 1.min(model_rows);
+            let plan = match explain {
+                ExplainStatement::Insert(insert) => {
+                    let rows = insert.into_rows();
+                    let row_count = rows.len() as u64;
+                    for row in rows {
+                        // validate only: generated keys aren't reserved
+                        super::ins::prepare_insert(mdl, row, None)?;
+                    }
+                    QueryPlan::new(
+                        "insert",
+                        AccessPath::PrimaryKeyLookup,
+                        row_count,
+                        model_rows,
+                    )
+                }
+                ExplainStatement::Select(mut select) => {
+                    let plan = if select.is_index_search() {
+                        let search = mdl.resolve_where_search(select.clauses_mut())?;
+                        let window = select.take_window();
+                        super::sel::check_window(mdl, &window)?;
+                        let path = if search.is_geo() {
+                            AccessPath::GeoIndexLookup
+                        } else {
+                            AccessPath::TextIndexLookup
+                        };
+                        QueryPlan::new(
+                            "select",
+                            path,
+                            window_estimate(&window, search.estimate() as u64),
+                            model_rows,
+                        )
+                    } else if select.is_pattern_scan() {
+                        let scan = mdl.resolve_where_pattern(select.clauses_mut())?;
+                        let window = select.take_window();
+                        super::sel::check_window(mdl, &window)?;
+                        QueryPlan::new(
+                            "select",
+                            AccessPath::PrimaryKeyPrefix,
+                            window_estimate(&window, range_estimate(mdl, &scan, &window)),
+                            model_rows,
+                        )
+                    } else if select.is_range_scan() {
+                        let scan = mdl.resolve_where_range_scan(select.clauses_mut())?;
+                        let window = select.take_window();
+                        super::sel::check_window(mdl, &window)?;
+                        QueryPlan::new(
+                            "select",
+                            AccessPath::PrimaryKeyRange,
+                            window_estimate(&window, range_estimate(mdl, &scan, &window)),
+                            model_rows,
+                        )
+                    } else if select.is_key_batch() {
+                        let batch = mdl.resolve_where_keys(select.clauses_mut())?;
+                        QueryPlan::new(
+                            "select",
+                            AccessPath::PrimaryKeyBatch,
+                            (batch.keys().len() as u64).min(model_rows),
+                            model_rows,
+                        )
+                    } else {
+                        mdl.resolve_where(select.clauses_mut())?;
+                        QueryPlan::new("select", AccessPath::PrimaryKeyLookup, lookup, model_rows)
+                    };
+                    if !select.is_wildcard() {
+                        let fields = select.into_fields();
+                        super::sel::check_select_fields(mdl, &fields)?;
+                        super::sel::resolve_lookups(global, c, models, mdl, space, &fields)?;
+                    }
+                    plan
+                }
+                ExplainStatement::SelectAll(select) => {
+                    if !select.wildcard {
+                        check_fields(mdl, &select.fields)?;
+                    }
+                    let window = select.window();
+                    super::sel::check_window(mdl, &window)?;
+                    QueryPlan::new(
+                        "select all",
+                        AccessPath::FullScan,
+                        window_estimate(&window, model_rows),
+                        model_rows,
+                    )
+                }
+                ExplainStatement::Update(mut update) => {
+                    let plan = if super::upd::is_single_row(mdl, &mut update) {
+                        mdl.resolve_where(update.clauses_mut())?;
+                        mdl.resolve_where_conditions(update.clauses_mut())?;
+                        QueryPlan::new("update", AccessPath::PrimaryKeyLookup, lookup, model_rows)
+                    } else {
+                        let filter = mdl.resolve_where_filter(update.clauses_mut())?;
+                        let (path, rows) = filter.plan(mdl);
+                        QueryPlan::new("update", path, (rows as u64).min(model_rows), model_rows)
+                    };
+                    for expression in update.expressions() {
+                        check_assignment(mdl, expression)?;
+                    }
+                    plan
+                }
+                ExplainStatement::Delete(mut delete) => match delete.limit() {
+                    Some(limit) => {
+                        let filter = mdl.resolve_where_filter(delete.clauses_mut())?;
+                        let (path, rows) = filter.plan(mdl);
+                        let estimate = (rows as u64).min(limit).min(model_rows);
+                        QueryPlan::new("delete", path, estimate, model_rows)
+                    }
+                    None => {
+                        mdl.resolve_where(delete.clauses_mut())?;
+                        QueryPlan::new("delete", AccessPath::PrimaryKeyLookup, lookup, model_rows)
+                    }
+                },
+            };
+            Ok(plan)
+        })
+}
+
+/// The most rows that can be returned from a window over `rows` rows
+/// Count the keys in the scanned range, stopping at the end of the window (if the rows aren't reordered)
+fn range_estimate(mdl: &Model, scan: &RangeScan, window: &SelectWindow) -> u64 {
+    let upto = match window.order {
+        None => window
+            .limit
+            .map_or(u64::MAX, |limit| limit.saturating_add(window.offset)),
+        Some(_) => u64::MAX,
+    };
+    let g = cpin();
+    let _latch = mdl.primary_index().acquire_cd();
+    mdl.primary_index()
+        .__raw_index()
+        .mt_range(scan.range(), &g)
+        .take(upto.try_into().unwrap_or(usize::MAX))
+        .count() as u64
+}
+
+fn window_estimate(window: &SelectWindow, rows: u64) -> u64 {
+    rows.saturating_sub(window.offset)
+        .min(window.limit.unwrap_or(u64::MAX))
+}
+
+fn check_fields(mdl: &Model, fields: &[Ident]) -> QueryResult<()> {
+    if fields.len() > mdl.fields().len()
+        || fields.iter().any(|f| !mdl.fields().st_contains(f.as_str()))
+    {
+        return Err(QueryError::QExecUnknownField);
+    }
+    Ok(())
+}
+
+/// Check an assignment against the schema. Checks that depend on the current value of the field are left to
+/// the actual update
+fn check_assignment(mdl: &Model, expression: &AssignmentExpression) -> QueryResult<()> {
+    let field = match mdl.fields().st_get(expression.lhs.as_str()) {
+        // the primary key can't be updated
+        Some(field) if expression.lhs.as_str() != mdl.p_key() => field,
+        _ => return Err(QueryError::QExecUnknownField),
+    };
+    let (tag_a, tag_b) = (
+        field.layers()[0].tag().tag_class(),
+        expression.rhs.kind().tag_class(),
+    );
+    let okay = match tag_a {
+        TagClass::List => {
+            (expression.operator_fn == AssignmentOperator::AddAssign)
+                & (field.layers()[1].tag().tag_class() == tag_b)
+        }
+        _ => (tag_a == tag_b) | super::upd::rhs_coerces(tag_a, tag_b),
+    };
+    if okay {
+        Ok(())
+    } else {
+        Err(QueryError::QExecDmlValidationError)
+    }
+}
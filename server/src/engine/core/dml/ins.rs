@@ -24,70 +24,238 @@
  *
 */
 
-use crate::engine::{
-    core::{
-        self,
-        dml::QueryExecMeta,
-        index::{DcFieldIndex, PrimaryIndexKey, Row},
-        model::{delta::DataDeltaKind, Model},
+use {
+    crate::engine::{
+        core::{
+            self,
+            dml::{txn, Changes, QueryExecMeta, ReplicatedChange, ReturnedRows},
+            index::{DcFieldIndex, PrimaryIndexKey, Row},
+            model::{
+                delta::{DataDelta, DataDeltaKind},
+                Model,
+            },
+            EntityIDRef,
+        },
+        error::{QueryError, QueryResult},
+        fractal::GlobalInstanceLike,
+        idx::{IndexBaseSpec, MTIndex, STIndex, STIndexSeq},
+        net::protocol::Response,
+        ql::{
+            dml::ins::{InsertData, InsertStatement},
+            lex::Ident,
+        },
+        sync::atm::cpin,
+        txn::gns as gnstxn,
     },
-    error::{QueryError, QueryResult},
-    fractal::GlobalInstanceLike,
-    idx::{IndexBaseSpec, MTIndex, STIndex, STIndexSeq},
-    net::protocol::Response,
-    ql::dml::ins::{InsertData, InsertStatement},
-    sync::atm::cpin,
+    std::collections::HashSet,
 };
 
 pub fn insert_resp(
     global: &impl GlobalInstanceLike,
     insert: InsertStatement,
 ) -> QueryResult<Response> {
-    self::insert(global, insert).map(|_| Response::Empty)
+    let entity = insert.entity();
+    super::log_write("insert", entity, self::insert_rows(global, insert))
+        .map(Changes::into_response)
 }
 
+#[cfg(test)]
 pub fn insert(global: &impl GlobalInstanceLike, insert: InsertStatement) -> QueryResult<()> {
-    core::with_model_for_data_update(global, insert.entity(), |mdl| {
-        let (pk, data) = prepare_insert(mdl, insert.data())?;
+    insert_rows(global, insert).map(|_| ())
+}
+
+/// Insert the rows, returning the new rows if they were asked for (`returning ...`)
+pub fn insert_rows(
+    global: &impl GlobalInstanceLike,
+    mut insert: InsertStatement,
+) -> QueryResult<Changes> {
+    let entity = insert.entity();
+    let returning = insert.take_returning();
+    let mut returned = None;
+    core::with_model_for_data_update(global, entity, |mdl| {
+        returned = ReturnedRows::resolve(mdl, returning)?;
+        let reserve = reserve_keys(global, entity, mdl);
+        let mut rows = insert.into_rows();
+        if rows.len() != 1 {
+            return self::insert_multi(mdl, rows, &reserve, &mut returned);
+        }
+        let (pk, data) = prepare_insert(mdl, rows.pop().unwrap(), Some(&reserve))?;
         let _idx_latch = mdl.primary_index().acquire_cd();
+        // a transaction might be holding the key of a row that it deleted
+        txn::take_intent(mdl, &pk)?;
         let g = cpin();
         let ds = mdl.delta_state();
         // create new version
         let new_version = ds.create_new_data_delta_version();
         let row = Row::new(pk, data, ds.schema_current_version(), new_version);
+        // keep updates to the new row from publishing their changes before we do
+        let row_data = row.d_data().read();
         if mdl.primary_index().__raw_index().mt_insert(row.clone(), &g) {
+            ds.account_row(0, row.estimated_size(&row_data));
+            txn::record_undo(mdl, || ReplicatedChange::Delete(row.d_key().clone()));
+            let delta = txn::publish_change(
+                mdl,
+                DataDeltaKind::Insert,
+                new_version,
+                &row,
+                row_data.fields(),
+            );
+            super::push_returned(&mut returned, mdl, row.d_key(), row_data.fields());
+            drop(row_data);
             // append delta for new version
-            let dp = ds.append_new_data_delta_with(DataDeltaKind::Insert, row, new_version, &g);
+            let dp = delta.map_or(0, |delta| ds.append_new_data_delta(delta, &g));
             Ok(QueryExecMeta::new(dp))
         } else {
             Err(QueryError::QExecDmlDuplicate)
         }
-    })
+    })?;
+    Ok(Changes::new(None, returned))
+}
+
+/// Insert all the given rows, or none of them if any row fails validation or violates uniqueness. All the rows
+/// share a single data delta version and their deltas are appended as one group
+fn insert_multi(
+    mdl: &Model,
+    rows: Vec<InsertData>,
+    reserve: &dyn Fn(u64) -> QueryResult<()>,
+    returned: &mut Option<ReturnedRows>,
+) -> QueryResult<QueryExecMeta> {
+    let prepared = rows
+        .into_iter()
+        .map(|row| prepare_insert(mdl, row, Some(reserve)))
+        .collect::<QueryResult<Vec<_>>>()?;
+    // block other inserts and deletes so that our uniqueness check holds until all the rows are in
+    let _idx_latch = mdl.primary_index().acquire_exclusive();
+    let g = cpin();
+    let mut keys = HashSet::with_capacity(prepared.len());
+    if prepared
+        .iter()
+        .any(|(pk, _)| !keys.insert(pk) || mdl.primary_index().__raw_index().mt_contains(pk, &g))
+    {
+        return Err(QueryError::QExecDmlDuplicate);
+    }
+    drop(keys);
+    for (pk, _) in prepared.iter() {
+        txn::take_intent(mdl, pk)?;
+    }
+    let ds = mdl.delta_state();
+    // create new version
+    let new_version = ds.create_new_data_delta_version();
+    let mut deltas = Vec::with_capacity(prepared.len());
+    for (pk, data) in prepared {
+        let row = Row::new(pk, data, ds.schema_current_version(), new_version);
+        let row_data = row.d_data().read();
+        let _okay = mdl.primary_index().__raw_index().mt_insert(row.clone(), &g);
+        debug_assert!(_okay, "uniqueness was checked under the latch");
+        ds.account_row(0, row.estimated_size(&row_data));
+        txn::record_undo(mdl, || ReplicatedChange::Delete(row.d_key().clone()));
+        deltas.extend(txn::publish_change(
+            mdl,
+            DataDeltaKind::Insert,
+            new_version,
+            &row,
+            row_data.fields(),
+        ));
+        super::push_returned(returned, mdl, row.d_key(), row_data.fields());
+    }
+    let dp = ds.append_new_data_deltas(deltas, &g);
+    Ok(QueryExecMeta::new(dp))
 }
 
+/// Insert rows that were prepared with [`prepare_insert`]. Unlike [`insert_multi`], a row that violates uniqueness
+/// doesn't stop the others from being inserted: its tag is handed to `on_duplicate` instead. All the rows share a
+/// single data delta version
+pub(in crate::engine::core) fn insert_batch<T>(
+    mdl: &Model,
+    rows: impl IntoIterator<Item = (T, PrimaryIndexKey, DcFieldIndex)>,
+    mut on_duplicate: impl FnMut(T),
+) -> QueryExecMeta {
+    let _idx_latch = mdl.primary_index().acquire_cd();
+    let g = cpin();
+    let ds = mdl.delta_state();
+    // create new version
+    let new_version = ds.create_new_data_delta_version();
+    let mut deltas = vec![];
+    for (tag, pk, data) in rows {
+        let row = Row::new(pk, data, ds.schema_current_version(), new_version);
+        let row_data = row.d_data().read();
+        if mdl.primary_index().__raw_index().mt_insert(row.clone(), &g) {
+            ds.account_row(0, row.estimated_size(&row_data));
+            mdl.publish_change(
+                DataDeltaKind::Insert,
+                new_version,
+                row.d_key(),
+                row_data.fields(),
+            );
+            drop(row_data);
+            deltas.push(DataDelta::new(new_version, row, DataDeltaKind::Insert));
+        } else {
+            on_duplicate(tag);
+        }
+    }
+    QueryExecMeta::new(ds.append_new_data_deltas(deltas, &g))
+}
+
+/// Returns the function that persists a new high water mark for the keys generated for the model (see
+/// [`Model::generate_key`])
+pub(in crate::engine::core) fn reserve_keys<'a, G: GlobalInstanceLike>(
+    global: &'a G,
+    entity: EntityIDRef<'a>,
+    mdl: &'a Model,
+) -> impl Fn(u64) -> QueryResult<()> + 'a {
+    move |high_water| {
+        if G::FS_IS_NON_NULL {
+            let txn = gnstxn::ReserveModelKeysTxn::new(entity, mdl.get_uuid(), high_water);
+            global.namespace_txn_driver().lock().try_commit(txn)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validate a row and turn it into the primary key and the rest of the fields. If the model generates its keys,
+/// `reserve` is used to reserve them (see [`reserve_keys`]) and without it, the row only gets a placeholder key
 // TODO(@ohsayan): optimize null case
-fn prepare_insert(
+pub(in crate::engine::core) fn prepare_insert(
     model: &Model,
     insert: InsertData,
+    reserve: Option<&dyn Fn(u64) -> QueryResult<()>>,
 ) -> QueryResult<(PrimaryIndexKey, DcFieldIndex)> {
     let fields = model.fields();
-    let mut okay = fields.len() == insert.column_count();
+    // fields that are skipped are set to their default (or null, if they don't have one)
+    let mut okay = fields.len() >= insert.column_count();
     let mut prepared_data = DcFieldIndex::idx_init_cap(fields.len());
+    // the primary key, if it's generated and was skipped
+    let mut generated = None;
     match insert {
         InsertData::Ordered(tuple) => {
+            // only trailing fields can be skipped (and computed fields and generated keys don't take a value)
             let mut fields = fields.stseq_ord_kv();
             let mut tuple = tuple.into_iter();
-            while (tuple.len() != 0) & okay {
-                let mut data;
-                let field;
-                unsafe {
-                    // UNSAFE(@ohsayan): safe because of invariant
-                    data = tuple.next().unwrap_unchecked();
-                    // UNSAFE(@ohsayan): safe because of flag
-                    field = fields.next().unwrap_unchecked();
+            while (fields.len() != 0) & okay {
+                let (field_id, field) = unsafe {
+                    // UNSAFE(@ohsayan): safe because of loop invariant
+                    fields.next().unwrap_unchecked()
+                };
+                if field.compute().is_some() {
+                    continue;
+                }
+                if field.generate().is_some() {
+                    generated = Some(field_id);
+                    continue;
                 }
-                let (field_id, field) = field;
-                okay &= field.vt_data_fpath(&mut data);
+                let data = match tuple.next() {
+                    Some(mut data) => {
+                        okay &= field.vt_data_fpath(&mut data);
+                        data
+                    }
+                    None => match field.materialize_default() {
+                        Some(data) => data,
+                        None => {
+                            okay = false;
+                            break;
+                        }
+                    },
+                };
                 okay &= prepared_data.st_insert(
                     unsafe {
                         // UNSAFE(@ohsayan): the model is right here, so we're good
@@ -96,17 +264,38 @@ fn prepare_insert(
                     data,
                 );
             }
+            okay &= tuple.len() == 0;
         }
-        InsertData::Map(map) => {
-            let mut inserted = 0;
-            let mut iter = fields.st_iter_kv().zip(map.into_iter());
-            while (iter.len() != 0) & (okay) {
-                let ((model_field_key, model_field_spec), (this_field_key, mut this_field_data)) = unsafe {
+        InsertData::Map(mut map) => {
+            // the map is unordered, so look up each field of the model instead of zipping
+            let mut fields = fields.stseq_ord_kv();
+            while (fields.len() != 0) & okay {
+                let (model_field_key, model_field_spec) = unsafe {
                     // UNSAFE(@ohsayan): safe because of loop invariant
-                    iter.next().unwrap_unchecked()
+                    fields.next().unwrap_unchecked()
+                };
+                if model_field_spec.compute().is_some() {
+                    // computed fields can't be set
+                    okay &= !map.contains_key(&Ident::new_str(model_field_key.as_str()));
+                    continue;
+                }
+                let this_field_data = match map.remove(&Ident::new_str(model_field_key.as_str())) {
+                    Some(mut this_field_data) => {
+                        okay &= model_field_spec.vt_data_fpath(&mut this_field_data);
+                        this_field_data
+                    }
+                    None if model_field_spec.generate().is_some() => {
+                        generated = Some(model_field_key);
+                        continue;
+                    }
+                    None => match model_field_spec.materialize_default() {
+                        Some(this_field_data) => this_field_data,
+                        None => {
+                            okay = false;
+                            break;
+                        }
+                    },
                 };
-                okay &= model_field_spec.vt_data_fpath(&mut this_field_data);
-                okay &= model_field_key.as_str() == this_field_key.as_str();
                 prepared_data.st_insert(
                     unsafe {
                         // UNSAFE(@ohsayan): the model is right here. it saves us the work!
@@ -114,9 +303,37 @@ fn prepare_insert(
                     },
                     this_field_data,
                 );
-                inserted += 1;
             }
-            okay &= inserted == fields.len();
+            // any field that's left over isn't in the model
+            okay &= map.is_empty();
+        }
+    }
+    if okay {
+        match generated {
+            Some(field_id) => {
+                prepared_data.st_insert(
+                    unsafe {
+                        // UNSAFE(@ohsayan): the model is right here, so we're good
+                        field_id.clone()
+                    },
+                    model.generate_key(reserve)?,
+                );
+            }
+            // the key was set explicitly, so make sure that we never generate it
+            None => {
+                if let (Some(reserve), Some(key)) = (reserve, prepared_data.st_get(model.p_key())) {
+                    model.observe_key(key, reserve)?;
+                }
+            }
+        }
+        for (field_id, data) in model.compute_fields(&prepared_data, None, |_| true)? {
+            prepared_data.st_insert(
+                unsafe {
+                    // UNSAFE(@ohsayan): the model is right here, so we're good
+                    field_id.clone()
+                },
+                data,
+            );
         }
     }
     let primary_key = prepared_data.remove(model.p_key());
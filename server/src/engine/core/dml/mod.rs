@@ -24,35 +24,344 @@
  *
 */
 
+mod agg;
 mod del;
+mod explain;
 mod ins;
+mod pattern;
+mod replica;
 mod sel;
+mod txn;
 mod upd;
 
-use crate::{
-    engine::{
-        core::model::Model,
-        data::{lit::Lit, tag::DataTag},
-        error::{QueryError, QueryResult},
-        ql::dml::WhereClause,
+use {
+    crate::{
+        engine::{
+            core::{
+                index::{DcFieldIndex, PrimaryIndexKey, Row},
+                model::{
+                    delta::{DataDeltaKind, DeltaVersion},
+                    Model,
+                },
+                EntityIDRef,
+            },
+            data::{
+                cell::{Datacell, VirtualDatacell},
+                lit::Lit,
+                tag::{DataTag, TagClass},
+            },
+            error::{QueryError, QueryResult},
+            fractal::cdc::ChangeEvent,
+            idx::{
+                fts::{self, TextIndex},
+                geo::{self, GeoIndex},
+                MTIndex, MTIndexExt, STIndex, STIndexSeq,
+            },
+            mem::IntegerRepr,
+            net::protocol::{Response, ResponseBody, ResponseType, ResponseWrite},
+            ql::{
+                dml::{RelationalExpr, Returning, WhereClause},
+                lex::Ident,
+            },
+            sync,
+        },
+        util::compiler,
     },
-    util::compiler,
+    pattern::LikePattern,
+    std::{cmp::Ordering, ops::Bound},
 };
 
+pub(super) use ins::{insert_batch, prepare_insert, reserve_keys};
+pub use replica::ReplicatedChange;
+pub(in crate::engine) use sel::encode_cell;
+pub(super) use sel::{project, SortKey};
+pub use txn::Transaction;
 #[cfg(test)]
 pub use {
-    del::delete,
-    ins::insert,
-    sel::{select_all, select_custom},
-    upd::{collect_trace_path as update_flow_trace, update},
+    agg::aggregate,
+    del::{delete, delete_rows},
+    explain::{explain, AccessPath},
+    ins::{insert, insert_rows},
+    sel::{select_all, select_custom, select_keys, select_range},
+    upd::{collect_trace_path as update_flow_trace, update, update_rows},
 };
 pub use {
+    agg::aggregate_resp,
     del::delete_resp,
+    explain::explain_resp,
     ins::insert_resp,
     sel::{select_all_resp, select_resp},
     upd::update_resp,
 };
 
+/// Returns true if the cell satisfies the expression from the where clause
+pub(super) fn matches(dc: &Datacell, expr: &RelationalExpr) -> bool {
+    if expr.is_text_search() {
+        return match (dc.try_str(), expr.rhs().try_str()) {
+            (Some(text), Some(words)) => fts::contains_all(text, &fts::tokenize(words)),
+            _ => false,
+        };
+    }
+    if let Some((center, radius)) = expr.circle() {
+        return match dc.try_geopoint() {
+            Some(point) => geo::distance(point, center) <= radius,
+            None => false,
+        };
+    }
+    if expr.is_pattern() {
+        return match (
+            dc.try_str(),
+            expr.rhs().try_str().and_then(LikePattern::parse),
+        ) {
+            (Some(text), Some(pattern)) => pattern.matches(text),
+            _ => false,
+        };
+    }
+    let value = SortKey::new(dc);
+    let cmp = |lit: &Lit| value.cmp(&SortKey::new(&Datacell::from(lit.clone())));
+    if expr.is_in() {
+        return expr.values().iter().any(|lit| cmp(lit) == Ordering::Equal);
+    }
+    match expr.range_bounds() {
+        Some((start, end)) => {
+            let above = match &start {
+                Bound::Included(lit) => cmp(lit) != Ordering::Less,
+                Bound::Excluded(lit) => cmp(lit) == Ordering::Greater,
+                Bound::Unbounded => true,
+            };
+            let below = match &end {
+                Bound::Included(lit) => cmp(lit) != Ordering::Greater,
+                Bound::Excluded(lit) => cmp(lit) == Ordering::Less,
+                Bound::Unbounded => true,
+            };
+            above & below
+        }
+        // either `=` or `!=`
+        None => (cmp(&expr.rhs()) == Ordering::Equal) == expr.filter_hint_none(),
+    }
+}
+
+/// The secondary index lookup used by an [`IndexSearch`]
+enum IndexLookup<'m> {
+    /// the rows that have all the given words in a full-text indexed field
+    Text {
+        index: &'m TextIndex<PrimaryIndexKey>,
+        words: Vec<Box<str>>,
+    },
+    /// the rows with points (in a geohash indexed field) that are at most `radius` meters from `center`
+    Geo {
+        index: &'m GeoIndex<PrimaryIndexKey>,
+        center: (f64, f64),
+        radius: f64,
+    },
+}
+
+impl<'m> IndexLookup<'m> {
+    /// Returns the keys of the rows that matched when they were last indexed
+    fn candidates(&self) -> Vec<PrimaryIndexKey> {
+        match self {
+            Self::Text { index, words } => index.search(words),
+            Self::Geo {
+                index,
+                center,
+                radius,
+            } => index.search(*center, *radius),
+        }
+    }
+    fn estimate(&self) -> usize {
+        match self {
+            Self::Text { index, words } => index.estimate(words),
+            Self::Geo {
+                index,
+                center,
+                radius,
+            } => index.estimate(*center, *radius),
+        }
+    }
+}
+
+/// A search for rows using a secondary index (full-text or geohash)
+pub(super) struct IndexSearch<'m, 'a> {
+    lookup: IndexLookup<'m>,
+    /// every clause of the where clause (including the one that is searched for)
+    conditions: Vec<(Ident<'a>, RelationalExpr<'a>)>,
+}
+
+impl<'m, 'a> IndexSearch<'m, 'a> {
+    /// Returns the keys of the rows that matched when they were last indexed. The rows have to be checked again
+    /// (with [`Self::matches`]) since they might have changed after the search
+    pub(super) fn candidates(&self) -> Vec<PrimaryIndexKey> {
+        self.lookup.candidates()
+    }
+    pub(super) fn estimate(&self) -> usize {
+        self.lookup.estimate()
+    }
+    /// Returns true if the geohash index is used
+    pub(super) fn is_geo(&self) -> bool {
+        matches!(self.lookup, IndexLookup::Geo { .. })
+    }
+    /// Returns true if the row satisfies every clause of the where clause
+    pub(super) fn matches(&self, mdl: &Model, pk: &PrimaryIndexKey, data: &DcFieldIndex) -> bool {
+        matches_all(mdl, &self.conditions, pk, data)
+    }
+}
+
+/// A scan over the rows with primary keys in a range (`pk > x and pk < y`), or with (string) primary keys that match a
+/// pattern (`pk like 'abc%'`), in which case only the keys that start with the pattern's prefix are scanned. Since the
+/// primary index is ordered, the scan seeks to the start of the range and stops at its end
+pub(super) struct RangeScan<'a> {
+    range: (Bound<Lit<'a>>, Bound<Lit<'a>>),
+    /// the clauses that every row in the range has to be checked against. this is empty if there's nothing else in
+    /// the where clause (and for a pattern, if it's just a prefix)
+    conditions: Vec<(Ident<'a>, RelationalExpr<'a>)>,
+}
+
+impl<'a> RangeScan<'a> {
+    pub(super) fn range(&self) -> (Bound<Lit<'a>>, Bound<Lit<'a>>) {
+        self.range.clone()
+    }
+    /// Returns true if every row in the range is a match
+    pub(super) fn is_unfiltered(&self) -> bool {
+        self.conditions.is_empty()
+    }
+    /// Returns true if the row satisfies every clause of the where clause
+    pub(super) fn matches(&self, mdl: &Model, pk: &PrimaryIndexKey, data: &DcFieldIndex) -> bool {
+        matches_all(mdl, &self.conditions, pk, data)
+    }
+}
+
+/// A batch of rows that are looked up using their primary keys (`pk in (k1, k2, ...)`)
+pub(super) struct KeyBatch<'a> {
+    keys: Vec<Lit<'a>>,
+    /// the other clauses of the where clause, which every row has to satisfy
+    conditions: Vec<(Ident<'a>, RelationalExpr<'a>)>,
+}
+
+impl<'a> KeyBatch<'a> {
+    /// The keys, in the order that they were given in (which can have duplicates)
+    pub(super) fn keys(&self) -> &[Lit<'a>] {
+        &self.keys
+    }
+    /// Returns true if the row satisfies every other clause of the where clause
+    pub(super) fn matches(&self, mdl: &Model, pk: &PrimaryIndexKey, data: &DcFieldIndex) -> bool {
+        matches_all(mdl, &self.conditions, pk, data)
+    }
+}
+
+/// Where the rows that a [`RowFilter`] checks come from
+enum RowSource<'m, 'a> {
+    /// the rows with the given primary keys (`pk = x` or `pk in (x, y)`)
+    Keys(Vec<Lit<'a>>),
+    /// the rows found with a secondary index
+    Index(IndexLookup<'m>),
+    /// the rows with primary keys in a range (`pk > x` or `pk like 'abc%'`)
+    Range((Bound<Lit<'a>>, Bound<Lit<'a>>)),
+    /// every row
+    All,
+}
+
+/// The rows that satisfy every clause of a where clause, for statements that can touch any number of rows
+/// (`delete from model where ... limit n`). The rows that might match are found using the primary key (or a
+/// secondary index) if the where clause allows it, and are scanned otherwise
+pub(super) struct RowFilter<'m, 'a> {
+    source: RowSource<'m, 'a>,
+    conditions: Vec<(Ident<'a>, RelationalExpr<'a>)>,
+}
+
+impl<'m, 'a> RowFilter<'m, 'a> {
+    /// Pass the rows that might match to `f`, until it returns false. Every row has to be checked again with
+    /// [`Self::matches`] (once locked)
+    pub(super) fn scan<'g>(
+        &self,
+        mdl: &'g Model,
+        g: &'g sync::atm::Guard,
+        mut f: impl FnMut(&'g Row) -> QueryResult<bool>,
+    ) -> QueryResult<()> {
+        let idx = mdl.primary_index().__raw_index();
+        match &self.source {
+            RowSource::Keys(keys) => {
+                for key in keys {
+                    if let Some(row) = idx.mt_get_element(key, g) {
+                        if !f(row)? {
+                            break;
+                        }
+                    }
+                }
+            }
+            RowSource::Index(lookup) => {
+                for key in lookup.candidates() {
+                    if let Some(row) = idx.mt_get_element(&key, g) {
+                        if !f(row)? {
+                            break;
+                        }
+                    }
+                }
+            }
+            RowSource::Range(range) => {
+                for row in idx.mt_range(range.clone(), g) {
+                    if !f(row)? {
+                        break;
+                    }
+                }
+            }
+            RowSource::All => {
+                for row in idx.mt_iter_entry(g) {
+                    if !f(row)? {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Returns the access path and the most rows that will be checked
+    pub(super) fn plan(&self, mdl: &Model) -> (explain::AccessPath, usize) {
+        let model_rows = mdl.primary_index().count_approx();
+        match &self.source {
+            RowSource::Keys(keys) => (explain::AccessPath::PrimaryKeyBatch, keys.len()),
+            RowSource::Index(lookup @ IndexLookup::Text { .. }) => {
+                (explain::AccessPath::TextIndexLookup, lookup.estimate())
+            }
+            RowSource::Index(lookup @ IndexLookup::Geo { .. }) => {
+                (explain::AccessPath::GeoIndexLookup, lookup.estimate())
+            }
+            RowSource::Range(_) => (explain::AccessPath::PrimaryKeyRange, model_rows),
+            RowSource::All => (explain::AccessPath::FullScan, model_rows),
+        }
+    }
+    /// Returns true if the row satisfies every clause of the where clause
+    pub(super) fn matches(&self, mdl: &Model, pk: &PrimaryIndexKey, data: &DcFieldIndex) -> bool {
+        matches_all(mdl, &self.conditions, pk, data)
+    }
+}
+
+/// Returns the range of (string) keys that start with the prefix
+fn prefix_range<'a>(prefix: String) -> (Bound<Lit<'a>>, Bound<Lit<'a>>) {
+    let end = match pattern::prefix_successor(&prefix) {
+        Some(end) => Bound::Excluded(Lit::new_string(end)),
+        None => Bound::Unbounded,
+    };
+    (Bound::Included(Lit::new_string(prefix)), end)
+}
+
+/// Returns true if the row satisfies every one of the conditions (see [`matches`])
+fn matches_all(
+    mdl: &Model,
+    conditions: &[(Ident, RelationalExpr)],
+    pk: &PrimaryIndexKey,
+    data: &DcFieldIndex,
+) -> bool {
+    conditions.iter().all(
+        |(field_name, expr)| match data.st_get(field_name.as_str()) {
+            Some(dc) => matches(dc, expr),
+            None if mdl.p_key() == field_name.as_str() => {
+                matches(&VirtualDatacell::new_pk(pk, mdl.p_tag()), expr)
+            }
+            None => false,
+        },
+    )
+}
+
 impl Model {
     pub(self) fn resolve_where<'a>(
         &self,
@@ -68,6 +377,367 @@ impl Model {
             _ => compiler::cold_rerr(QueryError::QExecDmlWhereHasUnindexedColumn),
         }
     }
+    pub(self) fn resolve_where_range<'a>(
+        &self,
+        where_clause: &mut WhereClause<'a>,
+    ) -> QueryResult<(Bound<Lit<'a>>, Bound<Lit<'a>>)> {
+        let tag_matches = |bound: &Bound<Lit>| match bound {
+            Bound::Included(lit) | Bound::Excluded(lit) => {
+                lit.kind().tag_unique() == self.p_tag().tag_unique()
+            }
+            Bound::Unbounded => true,
+        };
+        match where_clause
+            .clauses_mut()
+            .remove(self.p_key().as_bytes())
+            .and_then(|clause| clause.range_bounds())
+        {
+            Some((start, end)) if tag_matches(&start) & tag_matches(&end) => Ok((start, end)),
+            _ => compiler::cold_rerr(QueryError::QExecDmlWhereHasUnindexedColumn),
+        }
+    }
+    /// Resolve a where clause that matches the primary key against a range (`pk > x and pk < y and ...`). The other
+    /// clauses are resolved as conditions (see [`Self::resolve_where_conditions`])
+    pub(self) fn resolve_where_range_scan<'a>(
+        &self,
+        where_clause: &mut WhereClause<'a>,
+    ) -> QueryResult<RangeScan<'a>> {
+        let range = self.resolve_where_range(where_clause)?;
+        let conditions = self.resolve_where_conditions(where_clause)?;
+        Ok(RangeScan { range, conditions })
+    }
+    /// Resolve a where clause that matches the primary key against a list of keys (`pk in (k1, k2, ...) and ...`).
+    /// The other clauses are resolved as conditions (see [`Self::resolve_where_conditions`])
+    pub(self) fn resolve_where_keys<'a>(
+        &self,
+        where_clause: &mut WhereClause<'a>,
+    ) -> QueryResult<KeyBatch<'a>> {
+        let keys = match where_clause.clauses_mut().remove(self.p_key().as_bytes()) {
+            Some(clause)
+                if clause.is_in()
+                    & clause
+                        .values()
+                        .iter()
+                        .all(|key| key.kind().tag_unique() == self.p_tag().tag_unique()) =>
+            {
+                clause.into_values()
+            }
+            _ => return compiler::cold_rerr(QueryError::QExecDmlWhereHasUnindexedColumn),
+        };
+        let conditions = self.resolve_where_conditions(where_clause)?;
+        Ok(KeyBatch { keys, conditions })
+    }
+    /// Resolve the clauses that remain after the primary key has been resolved (`where pk = x and col > y`) into
+    /// conditions that a row must satisfy (see [`matches`]). The types must match the schema, except that timestamps
+    /// can also be compared with unsigned integers (micros) and 128-bit integers with any integer. Words can only be
+    /// looked for in string fields and circles only in point fields
+    pub(self) fn resolve_where_conditions<'a>(
+        &self,
+        where_clause: &mut WhereClause<'a>,
+    ) -> QueryResult<Vec<(Ident<'a>, RelationalExpr<'a>)>> {
+        let mut conditions = Vec::with_capacity(where_clause.clauses_mut().len());
+        for (field_name, clause) in where_clause.clauses_mut().drain() {
+            let Some(field) = self.fields().st_get(field_name.as_str()) else {
+                return compiler::cold_rerr(QueryError::QExecUnknownField);
+            };
+            let class = field.layers()[0].tag().tag_class();
+            let compatible = |lit: &Lit| {
+                let lit_class = lit.kind().tag_class();
+                (lit_class == class)
+                    | ((class == TagClass::Timestamp) & (lit_class == TagClass::UnsignedInt))
+                    | ((class == TagClass::BigInt)
+                        & matches!(lit_class, TagClass::UnsignedInt | TagClass::SignedInt))
+            };
+            let okay = match clause.range_bounds() {
+                Some((start, end)) => [start, end].iter().all(|bound| match bound {
+                    Bound::Included(lit) | Bound::Excluded(lit) => compatible(lit),
+                    Bound::Unbounded => true,
+                }),
+                None if clause.is_pattern() => {
+                    (class == TagClass::Str)
+                        & clause
+                            .rhs()
+                            .try_str()
+                            .and_then(LikePattern::parse)
+                            .is_some()
+                }
+                None if clause.is_in() => clause.values().iter().all(compatible),
+                None if clause.is_geo_search() => {
+                    (class == TagClass::Geo)
+                        & clause.circle().is_some_and(|(center, radius)| {
+                            geo::is_valid_point(center) & radius.is_finite() & (radius >= 0.0)
+                        })
+                }
+                None if clause.is_text_search() => {
+                    (class == TagClass::Str)
+                        & clause
+                            .rhs()
+                            .try_str()
+                            .is_some_and(|words| !fts::tokenize(words).is_empty())
+                }
+                None => compatible(&clause.rhs()),
+            };
+            if !okay {
+                return compiler::cold_rerr(QueryError::QExecDmlValidationError);
+            }
+            conditions.push((field_name, clause));
+        }
+        Ok(conditions)
+    }
+    /// Resolve a where clause that looks for words in a field (`where body contains 'disk full' and ...`) or for
+    /// points in a circle (`where location within (lat, lon, radius) and ...`). At least one of the fields that are
+    /// searched has to have a secondary index, and the other clauses are resolved as conditions (see
+    /// [`Self::resolve_where_conditions`])
+    pub(self) fn resolve_where_search<'a>(
+        &self,
+        where_clause: &mut WhereClause<'a>,
+    ) -> QueryResult<IndexSearch<'_, 'a>> {
+        let conditions = self.resolve_where_conditions(where_clause)?;
+        match self.index_lookup(&conditions) {
+            Some(lookup) => Ok(IndexSearch { lookup, conditions }),
+            None => compiler::cold_rerr(QueryError::QExecDmlWhereHasUnindexedColumn),
+        }
+    }
+    /// Returns the secondary index lookup (for one of the conditions) that is expected to return the fewest rows, if
+    /// any of the conditions can use a secondary index
+    fn index_lookup(&self, conditions: &[(Ident, RelationalExpr)]) -> Option<IndexLookup<'_>> {
+        conditions
+            .iter()
+            .filter_map(|(field_name, expr)| {
+                if expr.is_text_search() {
+                    let index = self.text_index(field_name.as_str())?;
+                    let words = fts::tokenize(expr.rhs().try_str()?);
+                    Some(IndexLookup::Text { index, words })
+                } else {
+                    let index = self.geo_index(field_name.as_str())?;
+                    let (center, radius) = expr.circle()?;
+                    Some(IndexLookup::Geo {
+                        index,
+                        center,
+                        radius,
+                    })
+                }
+            })
+            .min_by_key(IndexLookup::estimate)
+    }
+    /// Resolve any where clause into the rows that satisfy all of its clauses (see [`RowFilter`]). The primary key
+    /// is used to find the rows if it's compared with `=`, `in`, a range or a pattern, although a secondary index
+    /// is preferred over a range (or pattern)
+    pub(self) fn resolve_where_filter<'a>(
+        &self,
+        where_clause: &mut WhereClause<'a>,
+    ) -> QueryResult<RowFilter<'_, 'a>> {
+        let pk_source = match where_clause.clauses_mut().get(self.p_key().as_bytes()) {
+            Some(clause) if clause.filter_hint_none() => Some(RowSource::Keys(vec![clause.rhs()])),
+            Some(clause) if clause.is_in() => Some(RowSource::Keys(clause.values().to_vec())),
+            Some(clause) if clause.is_range() => clause.range_bounds().map(RowSource::Range),
+            Some(clause) if clause.is_pattern() & (self.p_tag().tag_class() == TagClass::Str) => {
+                clause
+                    .rhs()
+                    .try_str()
+                    .and_then(LikePattern::parse)
+                    .map(|pattern| pattern.prefix())
+                    .filter(|prefix| !prefix.is_empty())
+                    .map(|prefix| RowSource::Range(prefix_range(prefix)))
+            }
+            _ => None,
+        };
+        // this also checks the primary key's clause
+        let conditions = self.resolve_where_conditions(where_clause)?;
+        let source = match (pk_source, self.index_lookup(&conditions)) {
+            (Some(keys @ RowSource::Keys(_)), _) => keys,
+            (_, Some(lookup)) => RowSource::Index(lookup),
+            (Some(range), None) => range,
+            (None, None) => RowSource::All,
+        };
+        Ok(RowFilter { source, conditions })
+    }
+    /// Resolve a where clause that matches the primary key against a pattern (`pk like 'abc%' and ...`) into the
+    /// range of keys that start with the pattern's prefix. The pattern can't start with a wildcard (that would need
+    /// a full scan), and the other clauses are resolved as conditions (see [`Self::resolve_where_conditions`])
+    pub(self) fn resolve_where_pattern<'a>(
+        &self,
+        where_clause: &mut WhereClause<'a>,
+    ) -> QueryResult<RangeScan<'a>> {
+        let pattern = where_clause
+            .clauses_mut()
+            .get(self.p_key().as_bytes())
+            .filter(|clause| clause.is_pattern() & (self.p_tag().tag_class() == TagClass::Str))
+            .and_then(|clause| clause.rhs().try_str().map(LikePattern::parse));
+        let pattern = match pattern {
+            Some(Some(pattern)) => pattern,
+            Some(None) => return compiler::cold_rerr(QueryError::QExecDmlValidationError),
+            None => return compiler::cold_rerr(QueryError::QExecDmlWhereHasUnindexedColumn),
+        };
+        let prefix = pattern.prefix();
+        if prefix.is_empty() {
+            return compiler::cold_rerr(QueryError::QExecDmlWhereHasUnindexedColumn);
+        }
+        let range = prefix_range(prefix);
+        let mut conditions = self.resolve_where_conditions(where_clause)?;
+        if pattern.is_prefix() & (conditions.len() == 1) {
+            // every key in the range matches
+            conditions.clear();
+        }
+        Ok(RangeScan { range, conditions })
+    }
+    /// Publish a change to the model's subscribers (if any) and secondary (full-text and geohash) indexes. `data`
+    /// must be at the current schema version and for updates and deletes, the row must still be locked so that
+    /// changes to it are published in order
+    pub(self) fn publish_change(
+        &self,
+        kind: DataDeltaKind,
+        version: DeltaVersion,
+        pk: &PrimaryIndexKey,
+        data: &DcFieldIndex,
+    ) {
+        self.index_row(pk, data, kind == DataDeltaKind::Delete);
+        let feed = self.delta_state().change_feed();
+        if !feed.has_subscribers() {
+            return;
+        }
+        let pk = VirtualDatacell::new_pk(pk, self.p_tag());
+        let null = Datacell::null();
+        let mut cells = vec![];
+        for field in self.fields().stseq_ord_key() {
+            let cell = if field.as_str() == self.p_key() {
+                &*pk
+            } else {
+                data.st_get(field).unwrap_or(&null)
+            };
+            encode_cell(&mut cells, cell);
+        }
+        feed.publish(ChangeEvent::new(
+            version.value_u64(),
+            kind,
+            self.fields().len(),
+            cells,
+        ));
+    }
+}
+
+/// Log the outcome of a write. The logger tags the record with the connection and the query that it is run for
+fn log_write<T>(op: &str, entity: EntityIDRef, r: QueryResult<T>) -> QueryResult<T> {
+    match &r {
+        Ok(_) => trace!("{op} on `{}.{}` completed", entity.space(), entity.entity()),
+        Err(e) => debug!(
+            "{op} on `{}.{}` failed: {e:?}",
+            entity.space(),
+            entity.entity()
+        ),
+    }
+    r
+}
+
+/// The fields of the rows that a DML statement changed, which are sent back to the client (`returning ...`)
+#[derive(Debug)]
+pub struct ReturnedRows {
+    fields: Vec<Box<str>>,
+    rows: Vec<Vec<Datacell>>,
+}
+
+impl ReturnedRows {
+    /// Resolve the `returning` clause of a statement (if it has one). Every field has to be in the model
+    fn resolve(mdl: &Model, returning: Option<Returning>) -> QueryResult<Option<Self>> {
+        let Some(returning) = returning else {
+            return Ok(None);
+        };
+        let fields = if returning.is_wildcard() {
+            mdl.fields()
+                .stseq_ord_key()
+                .map(|f| f.as_str().into())
+                .collect()
+        } else {
+            let fields = returning.fields();
+            if fields.iter().any(|f| !mdl.fields().st_contains(f.as_str())) {
+                return compiler::cold_rerr(QueryError::QExecUnknownField);
+            }
+            fields.iter().map(|f| f.as_str().into()).collect()
+        };
+        Ok(Some(Self {
+            fields,
+            rows: vec![],
+        }))
+    }
+    /// Save the fields of a row that was changed. `data` has to be at the current schema version (and for updates,
+    /// has to be the new data)
+    fn push(&mut self, mdl: &Model, pk: &PrimaryIndexKey, data: &DcFieldIndex) {
+        let row = self
+            .fields
+            .iter()
+            .map(|field| match data.st_get(field.as_ref()) {
+                Some(dc) => dc.clone(),
+                None => (*VirtualDatacell::new_pk(pk, mdl.p_tag())).clone(),
+            })
+            .collect();
+        self.rows.push(row);
+    }
+    #[cfg(test)]
+    pub fn rows(&self) -> &[Vec<Datacell>] {
+        &self.rows
+    }
+    fn into_response(self) -> Response {
+        let mut data = ResponseBody::new();
+        IntegerRepr::scoped(self.fields.len() as u64, |repr| data.write_bytes(repr));
+        data.write_byte(b'\n');
+        for cell in self.rows.iter().flatten() {
+            encode_cell(&mut data, cell);
+        }
+        Response::Serialized {
+            ty: ResponseType::MultiRow,
+            size: self.rows.len(),
+            data,
+        }
+    }
+}
+
+/// Save the fields of a changed row if they are sent back (see [`ReturnedRows::push`])
+fn push_returned(
+    returned: &mut Option<ReturnedRows>,
+    mdl: &Model,
+    pk: &PrimaryIndexKey,
+    data: &DcFieldIndex,
+) {
+    if let Some(returned) = returned {
+        returned.push(mdl, pk, data);
+    }
+}
+
+/// What a DML statement that changes rows reports back to the client
+#[derive(Debug)]
+pub struct Changes {
+    /// the number of rows that were changed, for statements that can change any number of rows
+    count: Option<u64>,
+    /// the changed rows (`returning ...`)
+    returned: Option<ReturnedRows>,
+}
+
+impl Changes {
+    fn new(count: Option<u64>, returned: Option<ReturnedRows>) -> Self {
+        Self { count, returned }
+    }
+    #[cfg(test)]
+    pub fn count(&self) -> Option<u64> {
+        self.count
+    }
+    #[cfg(test)]
+    pub fn returned(&self) -> Option<&ReturnedRows> {
+        self.returned.as_ref()
+    }
+    /// The changed rows if they were asked for, and otherwise the number of rows that were changed (if the
+    /// statement could change any number of rows)
+    fn into_response(self) -> Response {
+        match self {
+            Self {
+                returned: Some(returned),
+                ..
+            } => returned.into_response(),
+            Self {
+                count: Some(count), ..
+            } => Response::UInt(count),
+            _ => Response::Empty,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -0,0 +1,136 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    like patterns
+    ---
+    `where username like 'sa%'`: `%` matches any run of characters (including none), `_` matches exactly one
+    character and `\` matches the character after it literally (so `\%` matches a `%`). everything else matches
+    itself
+*/
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Part {
+    Char(char),
+    One,
+    Many,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LikePattern {
+    parts: Vec<Part>,
+}
+
+impl LikePattern {
+    /// Parse a pattern, returning [`None`] if it ends with a lone `\`
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let mut parts = Vec::with_capacity(pattern.len());
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let part = match c {
+                '%' => Part::Many,
+                '_' => Part::One,
+                '\\' => Part::Char(chars.next()?),
+                c => Part::Char(c),
+            };
+            // `%%` is just `%`
+            if !((part == Part::Many) & (parts.last() == Some(&Part::Many))) {
+                parts.push(part);
+            }
+        }
+        Some(Self { parts })
+    }
+    /// Returns the characters that every match has to start with
+    pub fn prefix(&self) -> String {
+        self.parts
+            .iter()
+            .map_while(|part| match part {
+                Part::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+    /// Returns true if the pattern matches every string that starts with its prefix (`'abc%'`)
+    pub fn is_prefix(&self) -> bool {
+        let rest = self
+            .parts
+            .iter()
+            .skip_while(|part| matches!(part, Part::Char(_)));
+        rest.eq([Part::Many].iter())
+    }
+    /// Returns true if the whole of the text matches the pattern
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let (mut p, mut t) = (0, 0);
+        // where the last `%` was and where in the text we started matching after it
+        let mut backtrack = None;
+        while t < text.len() {
+            match self.parts.get(p) {
+                Some(Part::Many) => {
+                    p += 1;
+                    backtrack = Some((p, t));
+                }
+                Some(Part::One) => {
+                    p += 1;
+                    t += 1;
+                }
+                Some(Part::Char(c)) if *c == text[t] => {
+                    p += 1;
+                    t += 1;
+                }
+                _ => match backtrack {
+                    // let the last `%` eat one more character and try again
+                    Some((bp, bt)) => {
+                        p = bp;
+                        t = bt + 1;
+                        backtrack = Some((bp, t));
+                    }
+                    None => return false,
+                },
+            }
+        }
+        self.parts[p..].iter().all(|part| *part == Part::Many)
+    }
+}
+
+/// Returns the smallest string that is greater than every string starting with `prefix`, or [`None`] if there's no
+/// such string (every character of the prefix is [`char::MAX`]). Strings are ordered by their UTF-8 bytes, which is
+/// the same as ordering them by their characters
+pub fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(c) = chars.pop() {
+        let next = match c {
+            // skip the surrogates
+            '\u{D7FF}' => Some('\u{E000}'),
+            c => char::from_u32(c as u32 + 1),
+        };
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
@@ -0,0 +1,201 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{
+        index::{DcFieldIndex, PrimaryIndexKey, Row},
+        model::{delta::DataDeltaKind, Model},
+    },
+    data::{cell::Datacell, tag::DataTag},
+    error::{RuntimeResult, StorageError},
+    idx::{IndexBaseSpec, MTIndex, MTIndexExt, STIndex, STIndexSeq},
+    sync::atm::cpin,
+};
+
+#[derive(Debug)]
+/// A change to a row that was replicated from the primary
+pub enum ReplicatedChange {
+    /// the row was inserted or updated and now has this data (for every field except the primary key, in order)
+    Upsert(PrimaryIndexKey, Vec<Datacell>),
+    /// the row was deleted
+    Delete(PrimaryIndexKey),
+}
+
+impl Model {
+    /// Apply changes that were replicated from the primary, in order
+    ///
+    /// All the changes are validated before any of them is applied, so a batch that doesn't match the model (for
+    /// example, because the model was altered on the primary but not here) is rejected as a whole
+    pub fn apply_replicated_batch(&self, changes: Vec<ReplicatedChange>) -> RuntimeResult<()> {
+        self.apply_changes(changes, true)
+    }
+    /// Undo the changes of a transaction that were never published (see [`super::txn::Transaction::rollback`]). The
+    /// restored rows are still journaled, since a batch flushed while the transaction was running skips the older
+    /// deltas of any row that the transaction changed
+    pub(super) fn undo_changes(&self, changes: Vec<ReplicatedChange>) -> RuntimeResult<()> {
+        self.apply_changes(changes, false)
+    }
+    fn apply_changes(&self, changes: Vec<ReplicatedChange>, publish: bool) -> RuntimeResult<()> {
+        let changes = changes
+            .into_iter()
+            .map(|change| match change {
+                ReplicatedChange::Upsert(pk, row) => self
+                    .prepare_replicated_row(&pk, row)
+                    .map(|data| (pk, Some(data))),
+                ReplicatedChange::Delete(pk) => self.check_replicated_pk(&pk).map(|_| (pk, None)),
+            })
+            .collect::<RuntimeResult<Vec<_>>>()?;
+        // block inserts and deletes so that a row can't show up (or go away) between looking it up and changing it
+        let _idx_latch = self.primary_index().acquire_exclusive();
+        let g = cpin();
+        let ds = self.delta_state();
+        let p_index = self.primary_index().__raw_index();
+        for (pk, data) in changes {
+            match (p_index.mt_get_element(&pk, &g), data) {
+                (Some(row), Some(data)) => {
+                    let mut row_data_wl = row.resolve_schema_deltas_and_lock(ds);
+                    let new_version = ds.create_new_data_delta_version();
+                    ds.snapshot_preserve(row, &row_data_wl);
+                    let old_size = row.estimated_size(&row_data_wl);
+                    *row_data_wl.fields_mut() = data;
+                    row_data_wl.set_txn_revised(new_version);
+                    ds.account_row(old_size, row.estimated_size(&row_data_wl));
+                    if publish {
+                        self.publish_change(
+                            DataDeltaKind::Update,
+                            new_version,
+                            row.d_key(),
+                            row_data_wl.fields(),
+                        );
+                    }
+                    ds.append_new_data_delta_with(
+                        DataDeltaKind::Update,
+                        row.clone(),
+                        new_version,
+                        &g,
+                    );
+                }
+                (None, Some(data)) => {
+                    let new_version = ds.create_new_data_delta_version();
+                    let row = Row::new(pk, data, ds.schema_current_version(), new_version);
+                    let row_data = row.d_data().read();
+                    let _okay = p_index.mt_insert(row.clone(), &g);
+                    debug_assert!(_okay, "the row was looked up under the latch");
+                    ds.account_row(0, row.estimated_size(&row_data));
+                    if publish {
+                        self.publish_change(
+                            DataDeltaKind::Insert,
+                            new_version,
+                            row.d_key(),
+                            row_data.fields(),
+                        );
+                    }
+                    drop(row_data);
+                    ds.append_new_data_delta_with(DataDeltaKind::Insert, row, new_version, &g);
+                }
+                (Some(row), None) => {
+                    let row_data_wl = row.resolve_schema_deltas_and_lock(ds);
+                    let new_version = ds.create_new_data_delta_version();
+                    ds.snapshot_preserve(row, &row_data_wl);
+                    if let Some(row) = p_index.mt_delete_return_entry(&pk, &g) {
+                        ds.account_row(row.estimated_size(&row_data_wl), 0);
+                        if publish {
+                            self.publish_change(
+                                DataDeltaKind::Delete,
+                                new_version,
+                                row.d_key(),
+                                row_data_wl.fields(),
+                            );
+                        }
+                        ds.append_new_data_delta_with(
+                            DataDeltaKind::Delete,
+                            row.clone(),
+                            new_version,
+                            &g,
+                        );
+                    }
+                }
+                (None, None) => {
+                    // we never saw this row (it was inserted and deleted before we started replicating the journal)
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Delete every row in the model (before it is replicated from scratch)
+    pub fn truncate_replicated(&self) -> RuntimeResult<()> {
+        let g = cpin();
+        let keys = self
+            .primary_index()
+            .__raw_index()
+            .mt_iter_entry(&g)
+            .map(|row| ReplicatedChange::Delete(row.d_key().clone()))
+            .collect();
+        self.apply_replicated_batch(keys)?;
+        // every row is gone, so don't wait for the next maintenance run to give the memory back
+        self.compact_index();
+        Ok(())
+    }
+    fn check_replicated_pk(&self, pk: &PrimaryIndexKey) -> RuntimeResult<()> {
+        if pk.tag() == self.p_tag().tag_unique() {
+            Ok(())
+        } else {
+            Err(StorageError::DataBatchReplicaModelMismatch.into())
+        }
+    }
+    fn prepare_replicated_row(
+        &self,
+        pk: &PrimaryIndexKey,
+        row: Vec<Datacell>,
+    ) -> RuntimeResult<DcFieldIndex> {
+        self.check_replicated_pk(pk)?;
+        if row.len() != self.fields().len() - 1 {
+            return Err(StorageError::DataBatchReplicaModelMismatch.into());
+        }
+        let mut data = DcFieldIndex::idx_init_cap(row.len());
+        let fields = self
+            .fields()
+            .stseq_ord_kv()
+            .filter(|(field_id, _)| field_id.as_str() != self.p_key());
+        for ((field_id, field), mut cell) in fields.zip(row) {
+            // dicts of fields with a schema are persisted (and restored) as lists
+            if let Some(schema) = field.dict_schema() {
+                schema.unpack(&mut cell);
+            }
+            if !field.vt_data_fpath(&mut cell) {
+                return Err(StorageError::DataBatchReplicaModelMismatch.into());
+            }
+            data.st_insert(
+                unsafe {
+                    // UNSAFE(@ohsayan): the model is right here, so we're good
+                    field_id.clone()
+                },
+                cell,
+            );
+        }
+        Ok(data)
+    }
+}
@@ -24,33 +24,61 @@
  *
 */
 
-use crate::engine::{
-    core::{
-        index::{
-            DcFieldIndex, IndexLatchHandleExclusive, PrimaryIndexKey, Row, RowData, RowDataLck,
+use {
+    crate::engine::{
+        core::{
+            dml::{txn, IndexSearch, RangeScan},
+            exec::check_access,
+            index::{
+                DcFieldIndex, IndexLatchHandleExclusive, IndexLatchHandleShared, PrimaryIndexKey,
+                Row, RowData, RowDataLck,
+            },
+            model::Model,
+            EntityID, EntityIDRef,
         },
-        model::Model,
-    },
-    data::{
-        cell::{Datacell, VirtualDatacell},
-        tag::{DataTag, TagClass},
+        data::{
+            cell::{Datacell, VirtualDatacell},
+            lit::Lit,
+            tag::{DataTag, FloatSpec, FullTag, SIntSpec, TagClass, TagSelector, UIntSpec},
+        },
+        error::{QueryError, QueryResult},
+        fractal::{queries, GlobalInstanceLike},
+        idx::{IndexMTOrd, IndexST, MTIndex, MTIndexExt, STIndex, STIndexSeq},
+        mem::IntegerRepr,
+        net::protocol::{ClientLocalState, Response, ResponseBody, ResponseType, ResponseWrite},
+        ql::{
+            dml::sel::{
+                OrderBy, SelectAllStatement, SelectField, SelectPathSegment, SelectStatement,
+                SelectWindow,
+            },
+            lex::Ident,
+        },
+        sync,
     },
-    error::{QueryError, QueryResult},
-    fractal::GlobalInstanceLike,
-    idx::{IndexMTRaw, MTIndexExt, STIndex, STIndexSeq},
-    mem::IntegerRepr,
-    net::protocol::{Response, ResponseType},
-    ql::dml::sel::{SelectAllStatement, SelectStatement},
-    sync,
+    std::{cmp::Ordering, ops::Bound, vec},
 };
 
 pub fn select_resp(
     global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
     select: SelectStatement,
 ) -> QueryResult<Response> {
-    let mut data = vec![];
+    if select.is_range_scan() | select.is_index_search() | select.is_pattern_scan() {
+        return self::select_range_resp(global, c, select);
+    }
+    if select.is_key_batch() {
+        return self::select_keys_resp(global, c, select);
+    }
+    if select.is_wildcard()
+        & !select.wants_version()
+        & !select.is_for_update()
+        & global.row_cache().is_enabled()
+    {
+        return self::select_cached_resp(global, select);
+    }
+    let mut data = ResponseBody::new();
     let mut i = 0usize;
-    self::select_custom(global, select, |item| {
+    self::select_custom(global, c, select, |item| {
         encode_cell(&mut data, item);
         i += 1;
     })?;
@@ -61,18 +89,63 @@ pub fn select_resp(
     })
 }
 
+/// Select an entire row, using the model's row cache
+fn select_cached_resp(
+    global: &impl GlobalInstanceLike,
+    mut select: SelectStatement,
+) -> QueryResult<Response> {
+    global.namespace().with_model(select.entity(), |mdl| {
+        let target_key = mdl.resolve_where(select.clauses_mut())?;
+        let cache = mdl.delta_state().row_cache();
+        let size = mdl.fields().len();
+        let fill = match cache.lookup(global.row_cache(), &target_key) {
+            Ok(data) => {
+                return Ok(Response::Serialized {
+                    ty: ResponseType::Row,
+                    size,
+                    data: data.into(),
+                })
+            }
+            Err(fill) => fill,
+        };
+        let g = sync::atm::cpin();
+        let Some(row) = mdl.primary_index().select(target_key.clone(), &g) else {
+            return Err(QueryError::QExecDmlRowNotFound);
+        };
+        let mut data = vec![];
+        {
+            let r = row.resolve_schema_deltas_and_freeze(mdl.delta_state());
+            for key in mdl.fields().stseq_ord_key() {
+                match r.fields().st_get(key.as_str()) {
+                    Some(dc) => encode_cell(&mut data, dc),
+                    None => encode_cell(
+                        &mut data,
+                        &VirtualDatacell::new(target_key.clone(), mdl.p_tag().tag_unique()),
+                    ),
+                }
+            }
+        }
+        cache.fill(global.row_cache(), fill, row.d_key(), &data);
+        Ok(Response::Serialized {
+            ty: ResponseType::Row,
+            size,
+            data: data.into(),
+        })
+    })
+}
+
 pub fn select_all_resp(
     global: &impl GlobalInstanceLike,
     select: SelectAllStatement,
 ) -> QueryResult<Response> {
-    let mut ret_buf = Vec::new();
+    let mut ret_buf = ResponseBody::new();
     let i = self::select_all(
         global,
         select,
         &mut ret_buf,
         |buf, _, col_c| {
-            IntegerRepr::scoped(col_c as u64, |repr| buf.extend(repr));
-            buf.push(b'\n');
+            IntegerRepr::scoped(col_c as u64, |repr| buf.write_bytes(repr));
+            buf.write_byte(b'\n');
         },
         |buf, data, _| encode_cell(buf, data),
     )?;
@@ -83,6 +156,253 @@ pub fn select_all_resp(
     })
 }
 
+fn select_keys_resp(
+    global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    select: SelectStatement,
+) -> QueryResult<Response> {
+    let mut ret_buf = ResponseBody::new();
+    let i = self::select_keys(
+        global,
+        c,
+        select,
+        &mut ret_buf,
+        |buf, _, col_c| {
+            IntegerRepr::scoped(col_c as u64, |repr| buf.write_bytes(repr));
+            buf.write_byte(b'\n');
+        },
+        |buf, data, _| encode_cell(buf, data),
+    )?;
+    Ok(Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: i,
+        data: ret_buf,
+    })
+}
+
+fn select_range_resp(
+    global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    select: SelectStatement,
+) -> QueryResult<Response> {
+    let mut ret_buf = ResponseBody::new();
+    let i = self::select_range(
+        global,
+        c,
+        select,
+        &mut ret_buf,
+        |buf, _, col_c| {
+            IntegerRepr::scoped(col_c as u64, |repr| buf.write_bytes(repr));
+            buf.write_byte(b'\n');
+        },
+        |buf, data, _| encode_cell(buf, data),
+    )?;
+    Ok(Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: i,
+        data: ret_buf,
+    })
+}
+
+/// How the rows of a multi-row select are found
+enum RowScan<'m, 'a> {
+    /// the rows with primary keys in a range (`pk > x and pk < y`) or that match a pattern (`pk like 'abc%'`)
+    Range(RangeScan<'a>),
+    /// the rows found with a secondary index (`body contains 'x'` or `location within (lat, lon, radius)`)
+    Search(IndexSearch<'m, 'a>),
+}
+
+impl<'m, 'a> RowScan<'m, 'a> {
+    fn resolve(mdl: &'m Model, select: &mut SelectStatement<'a>) -> QueryResult<Self> {
+        if select.is_index_search() {
+            mdl.resolve_where_search(select.clauses_mut())
+                .map(Self::Search)
+        } else if select.is_pattern_scan() {
+            mdl.resolve_where_pattern(select.clauses_mut())
+                .map(Self::Range)
+        } else {
+            mdl.resolve_where_range_scan(select.clauses_mut())
+                .map(Self::Range)
+        }
+    }
+    /// Order and page the rows that are found (see [`RowIteratorAll::into_window`])
+    fn into_window<'g>(
+        self,
+        g: &'g sync::atm::Guard,
+        mdl: &'g Model,
+        window: &SelectWindow,
+    ) -> QueryResult<RowIteratorAll<'g, vec::IntoIter<&'g Row>, IndexLatchHandleShared<'g>>> {
+        match self {
+            Self::Range(scan) if scan.is_unfiltered() => {
+                RowIteratorAll::new_range(g, mdl, scan.range()).into_window(window)
+            }
+            Self::Range(scan) => RowIteratorAll::new_range_scan(g, mdl, scan)?.into_window(window),
+            Self::Search(search) => RowIteratorAll::new_search(g, mdl, search)?.into_window(window),
+        }
+    }
+}
+
+/// Select all rows with primary keys in the range given by the where clause (`pk > x and pk < y`) or that match its
+/// pattern (`pk like 'abc%'`), or that have the words that the where clause looks for (`body contains 'x'`)
+pub fn select_range<Fm, F, T>(
+    global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    mut select: SelectStatement,
+    serialize_target: &mut T,
+    mut f_mdl: Fm,
+    mut f: F,
+) -> QueryResult<usize>
+where
+    Fm: FnMut(&mut T, &Model, usize),
+    F: FnMut(&mut T, &Datacell, usize),
+{
+    global
+        .namespace()
+        .with_model_and_index(select.entity(), |mdl, models| {
+            let scan = RowScan::resolve(mdl, &mut select)?;
+            let window = select.take_window();
+            check_window(mdl, &window)?;
+            let version = select.wants_version();
+            let g = sync::atm::cpin();
+            let mut i = 0;
+            if select.is_wildcard() {
+                let rows = scan.into_window(&g, mdl, &window)?;
+                let col_c = mdl.fields().len() + version as usize;
+                f_mdl(serialize_target, mdl, col_c);
+                for (key, data) in rows {
+                    let vdc = VirtualDatacell::new_pk(key, mdl.p_tag());
+                    for key in mdl.fields().stseq_ord_key() {
+                        let r = if key.as_str() == mdl.p_key() {
+                            &*vdc
+                        } else {
+                            data.fields().get(key).unwrap()
+                        };
+                        f(serialize_target, r, col_c);
+                    }
+                    if version {
+                        f(serialize_target, &version_cell(&data), col_c);
+                    }
+                    i += 1;
+                }
+            } else {
+                let space = select.entity().space();
+                let fields = select.into_fields();
+                // schema check
+                check_select_fields(mdl, &fields)?;
+                let lookups = resolve_lookups(global, c, models, mdl, space, &fields)?;
+                let rows = scan.into_window(&g, mdl, &window)?;
+                let col_c = fields.len() + version as usize;
+                f_mdl(serialize_target, mdl, col_c);
+                for (key, data) in rows {
+                    let vdc = VirtualDatacell::new_pk(key, mdl.p_tag());
+                    for (key, lookup) in fields.iter().zip(lookups.iter()) {
+                        let r = if key.as_str() == mdl.p_key() {
+                            &*vdc
+                        } else {
+                            data.fields().st_get(key.as_str()).unwrap()
+                        };
+                        match lookup {
+                            Some(lookup) => f(serialize_target, &lookup.read(r, &g), col_c),
+                            None => {
+                                project(r, key.path(), &mut |dc| f(serialize_target, dc, col_c))
+                            }
+                        }
+                    }
+                    if version {
+                        f(serialize_target, &version_cell(&data), col_c);
+                    }
+                    i += 1;
+                }
+            }
+            Ok(i)
+        })
+}
+
+/// Select the rows with the primary keys given by the where clause (`pk in (k1, k2, ...)`). There's a row in the
+/// response for every key (in order) which starts with whether a row was found for the key, followed by the fields
+/// (which are null if no row was found). All the keys are looked up with the same pin
+pub fn select_keys<Fm, F, T>(
+    global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    mut select: SelectStatement,
+    serialize_target: &mut T,
+    mut f_mdl: Fm,
+    mut f: F,
+) -> QueryResult<usize>
+where
+    Fm: FnMut(&mut T, &Model, usize),
+    F: FnMut(&mut T, &Datacell, usize),
+{
+    global
+        .namespace()
+        .with_model_and_index(select.entity(), |mdl, models| {
+            let batch = mdl.resolve_where_keys(select.clauses_mut())?;
+            let version = select.wants_version();
+            let wildcard = select.is_wildcard();
+            let space = select.entity().space();
+            let fields = if wildcard {
+                vec![]
+            } else {
+                select.into_fields()
+            };
+            // schema check
+            check_select_fields(mdl, &fields)?;
+            let lookups = resolve_lookups(global, c, models, mdl, space, &fields)?;
+            let field_c = if wildcard {
+                mdl.fields().len()
+            } else {
+                fields.len()
+            };
+            let col_c = 1 + field_c + version as usize;
+            f_mdl(serialize_target, mdl, col_c);
+            let g = sync::atm::cpin();
+            let null = Datacell::null();
+            for key in batch.keys() {
+                queries::check()?;
+                let found = mdl
+                    .primary_index()
+                    .select(key.clone(), &g)
+                    .map(|row| (row, row.resolve_schema_deltas_and_freeze(mdl.delta_state())))
+                    .filter(|(row, data)| batch.matches(mdl, row.d_key(), data.fields()));
+                let Some((row, data)) = found else {
+                    f(serialize_target, &Datacell::new_bool(false), col_c);
+                    for _ in 1..col_c {
+                        f(serialize_target, &null, col_c);
+                    }
+                    continue;
+                };
+                f(serialize_target, &Datacell::new_bool(true), col_c);
+                let vdc = VirtualDatacell::new_pk(row.d_key(), mdl.p_tag());
+                let read = |field: &str| {
+                    if field == mdl.p_key() {
+                        &*vdc
+                    } else {
+                        data.fields().st_get(field).unwrap()
+                    }
+                };
+                if wildcard {
+                    for key in mdl.fields().stseq_ord_key() {
+                        f(serialize_target, read(key.as_str()), col_c);
+                    }
+                } else {
+                    for (key, lookup) in fields.iter().zip(lookups.iter()) {
+                        let r = read(key.as_str());
+                        match lookup {
+                            Some(lookup) => f(serialize_target, &lookup.read(r, &g), col_c),
+                            None => {
+                                project(r, key.path(), &mut |dc| f(serialize_target, dc, col_c))
+                            }
+                        }
+                    }
+                }
+                if version {
+                    f(serialize_target, &version_cell(&data), col_c);
+                }
+            }
+            Ok(batch.keys().len())
+        })
+}
+
 pub fn select_all<Fm, F, T>(
     global: &impl GlobalInstanceLike,
     select: SelectAllStatement,
@@ -95,11 +415,15 @@ where
     F: FnMut(&mut T, &Datacell, usize),
 {
     global.namespace().with_model(select.entity, |mdl| {
+        let window = select.window();
+        check_window(mdl, &window)?;
         let g = sync::atm::cpin();
         let mut i = 0;
         if select.wildcard {
-            f_mdl(serialize_target, mdl, mdl.fields().len());
-            for (key, data) in RowIteratorAll::new(&g, mdl, select.limit as usize) {
+            let rows = RowIteratorAll::new(&g, mdl).into_window(&window)?;
+            let col_c = mdl.fields().len() + select.version as usize;
+            f_mdl(serialize_target, mdl, col_c);
+            for (key, data) in rows {
                 let vdc = VirtualDatacell::new_pk(key, mdl.p_tag());
                 for key in mdl.fields().stseq_ord_key() {
                     let r = if key.as_str() == mdl.p_key() {
@@ -107,7 +431,10 @@ where
                     } else {
                         data.fields().get(key).unwrap()
                     };
-                    f(serialize_target, r, mdl.fields().len());
+                    f(serialize_target, r, col_c);
+                }
+                if select.version {
+                    f(serialize_target, &version_cell(&data), col_c);
                 }
                 i += 1;
             }
@@ -121,8 +448,10 @@ where
             {
                 return Err(QueryError::QExecUnknownField);
             }
-            f_mdl(serialize_target, mdl, select.fields.len());
-            for (key, data) in RowIteratorAll::new(&g, mdl, select.limit as usize) {
+            let rows = RowIteratorAll::new(&g, mdl).into_window(&window)?;
+            let col_c = select.fields.len() + select.version as usize;
+            f_mdl(serialize_target, mdl, col_c);
+            for (key, data) in rows {
                 let vdc = VirtualDatacell::new_pk(key, mdl.p_tag());
                 for key in select.fields.iter() {
                     let r = if key.as_str() == mdl.p_key() {
@@ -130,7 +459,10 @@ where
                     } else {
                         data.fields().st_get(key.as_str()).unwrap()
                     };
-                    f(serialize_target, r, select.fields.len());
+                    f(serialize_target, r, col_c);
+                }
+                if select.version {
+                    f(serialize_target, &version_cell(&data), col_c);
                 }
                 i += 1;
             }
@@ -139,89 +471,538 @@ where
     })
 }
 
-fn encode_cell(resp: &mut Vec<u8>, item: &Datacell) {
-    resp.push((item.tag().tag_selector().value_u8() + 1) * (item.is_init() as u8));
+pub(in crate::engine) fn encode_cell(resp: &mut impl ResponseWrite, item: &Datacell) {
+    // clients don't know about timestamps, 128-bit integers, points and dicts (yet), so they're sent as the micros since
+    // the epoch, as strings with the digits, as `"lat,lon"` strings and as lists of keys, each followed by its value,
+    // respectively
+    let selector = match item.tag().tag_selector() {
+        TagSelector::Timestamp => TagSelector::UInt64,
+        TagSelector::SInt128 | TagSelector::GeoPoint => TagSelector::String,
+        TagSelector::Dict => TagSelector::List,
+        selector => selector,
+    };
+    resp.write_byte((selector.value_u8() + 1) * (item.is_init() as u8));
     if item.is_null() {
         return;
     }
     unsafe {
         // UNSAFE(@ohsayan): +tagck
         match item.tag().tag_class() {
-            TagClass::Bool => return resp.push(item.read_bool() as _),
-            TagClass::UnsignedInt => IntegerRepr::scoped(item.read_uint(), |b| resp.extend(b)),
-            TagClass::SignedInt => IntegerRepr::scoped(item.read_sint(), |b| resp.extend(b)),
-            TagClass::Float => resp.extend(item.read_float().to_string().as_bytes()),
+            TagClass::Bool => return resp.write_byte(item.read_bool() as _),
+            TagClass::UnsignedInt | TagClass::Timestamp => {
+                IntegerRepr::scoped(item.read_uint(), |b| resp.write_bytes(b))
+            }
+            TagClass::SignedInt => IntegerRepr::scoped(item.read_sint(), |b| resp.write_bytes(b)),
+            TagClass::Float => resp.write_bytes(item.read_float().to_string().as_bytes()),
             TagClass::Bin | TagClass::Str => {
                 let slc = item.read_bin();
-                IntegerRepr::scoped(slc.len() as u64, |b| resp.extend(b));
-                resp.push(b'\n');
-                resp.extend(slc);
+                IntegerRepr::scoped(slc.len() as u64, |b| resp.write_bytes(b));
+                resp.write_byte(b'\n');
+                resp.write_value(slc);
+                return;
+            }
+            TagClass::BigInt => {
+                let int = item.read_sint128().to_string();
+                IntegerRepr::scoped(int.len() as u64, |b| resp.write_bytes(b));
+                resp.write_byte(b'\n');
+                resp.write_value(int.as_bytes());
+                return;
+            }
+            TagClass::Geo => {
+                let (lat, lon) = item.read_geopoint();
+                let point = format!("{lat},{lon}");
+                IntegerRepr::scoped(point.len() as u64, |b| resp.write_bytes(b));
+                resp.write_byte(b'\n');
+                resp.write_value(point.as_bytes());
                 return;
             }
             TagClass::List => {
                 let list = item.read_list();
                 let ls = list.read();
-                IntegerRepr::scoped(ls.len() as u64, |b| resp.extend(b));
-                resp.push(b'\n');
+                IntegerRepr::scoped(ls.len() as u64, |b| resp.write_bytes(b));
+                resp.write_byte(b'\n');
                 for item in ls.iter() {
                     encode_cell(resp, item);
                 }
                 return;
             }
+            TagClass::Dict => {
+                let dict = item.read_dict();
+                IntegerRepr::scoped(dict.len() as u64 * 2, |b| resp.write_bytes(b));
+                resp.write_byte(b'\n');
+                for (key, value) in dict {
+                    encode_cell(resp, &Datacell::new_str(key.clone()));
+                    encode_cell(resp, value);
+                }
+                return;
+            }
         }
     }
-    resp.push(b'\n');
+    resp.write_byte(b'\n');
 }
 
 pub fn select_custom<F>(
     global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
     mut select: SelectStatement,
     mut cellfn: F,
 ) -> QueryResult<()>
 where
     F: FnMut(&Datacell),
 {
-    global.namespace().with_model(select.entity(), |mdl| {
-        let target_key = mdl.resolve_where(select.clauses_mut())?;
-        let pkdc = VirtualDatacell::new(target_key.clone(), mdl.p_tag().tag_unique());
-        let g = sync::atm::cpin();
-        let mut read_field = |key, fields: &DcFieldIndex| {
-            match fields.st_get(key) {
-                Some(dc) => cellfn(dc),
-                None if key == mdl.p_key() => cellfn(&pkdc),
-                None => return Err(QueryError::QExecUnknownField),
+    global
+        .namespace()
+        .with_model_and_index(select.entity(), |mdl, models| {
+            let target_key = mdl.resolve_where(select.clauses_mut())?;
+            let version = select.wants_version();
+            let pkdc = VirtualDatacell::new(target_key.clone(), mdl.p_tag().tag_unique());
+            let g = sync::atm::cpin();
+            let mut read_field = |key,
+                                  path: &[SelectPathSegment],
+                                  lookup: Option<&Lookup>,
+                                  fields: &DcFieldIndex| {
+                let dc = match fields.st_get(key) {
+                    Some(dc) => dc,
+                    None if key == mdl.p_key() => &*pkdc,
+                    None => return Err(QueryError::QExecUnknownField),
+                };
+                match lookup {
+                    Some(lookup) => cellfn(&lookup.read(dc, &g)),
+                    None => project(dc, path, &mut cellfn),
+                }
+                Ok(())
+            };
+            match mdl.primary_index().select(target_key.clone(), &g) {
+                Some(row) => {
+                    // take the intent before reading, so that no other write can land after the read (outside of a
+                    // transaction, the statement is its own transaction and the intent is released right away)
+                    if select.is_for_update() {
+                        txn::take_intent(mdl, row.d_key())?;
+                    }
+                    let r = row.resolve_schema_deltas_and_freeze(mdl.delta_state());
+                    if select.is_wildcard() {
+                        for key in mdl.fields().stseq_ord_key() {
+                            read_field(key.as_ref(), &[], None, r.fields())?;
+                        }
+                    } else {
+                        let space = select.entity().space();
+                        let fields = select.into_fields();
+                        check_select_fields(mdl, &fields)?;
+                        let lookups = resolve_lookups(global, c, models, mdl, space, &fields)?;
+                        for (key, lookup) in fields.iter().zip(lookups.iter()) {
+                            read_field(key.as_str(), key.path(), lookup.as_ref(), r.fields())?;
+                        }
+                    }
+                    if version {
+                        cellfn(&version_cell(&r));
+                    }
+                }
+                None => return Err(QueryError::QExecDmlRowNotFound),
             }
             Ok(())
+        })
+}
+
+/// The version of the row, as returned by `with version`
+fn version_cell(data: &RowData) -> Datacell {
+    Datacell::new_uint_default(data.get_version().value_u64())
+}
+
+/// Check that all the selected fields exist, and that their paths (if any) only index into lists and look up keys of
+/// dicts. Since the values of a dict without a schema can be anything, whatever follows a key of such a dict is only
+/// resolved when the row is read
+pub(super) fn check_select_fields(mdl: &Model, fields: &[SelectField]) -> QueryResult<()> {
+    for field in fields {
+        let Some(definition) = mdl.fields().st_get(field.as_str()) else {
+            return Err(QueryError::QExecUnknownField);
         };
-        match mdl.primary_index().select(target_key.clone(), &g) {
-            Some(row) => {
-                let r = row.resolve_schema_deltas_and_freeze(mdl.delta_state());
-                if select.is_wildcard() {
-                    for key in mdl.fields().stseq_ord_key() {
-                        read_field(key.as_ref(), r.fields())?;
-                    }
-                } else {
-                    for key in select.into_fields() {
-                        read_field(key.as_str(), r.fields())?;
-                    }
+        let layers = definition.layers();
+        for (depth, segment) in field.path().iter().enumerate() {
+            let class = layers.get(depth).map(|layer| layer.tag().tag_class());
+            match (segment, class, definition.dict_schema()) {
+                (SelectPathSegment::Index(_), Some(TagClass::List), _) => {}
+                (SelectPathSegment::Key(_), Some(TagClass::Dict), None) => break,
+                // the values in a dict with a schema are never lists or dicts
+                (SelectPathSegment::Key(key), Some(TagClass::Dict), Some(schema))
+                    if (depth == field.path().len() - 1)
+                        & schema.fields().iter().any(|(k, _)| **k == *key.as_str()) => {}
+                _ => return Err(QueryError::QExecDmlValidationError),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A lookup of a selected field (`other_model[fk_field]`), resolved against the model that is looked up
+pub(super) struct Lookup<'m, 'a> {
+    mdl: &'m Model,
+    field: Option<Ident<'a>>,
+}
+
+impl<'m, 'a> Lookup<'m, 'a> {
+    /// Returns the row (as a list of all its fields) or the field of the row that the key refers to, or null if
+    /// there's no such row
+    fn read(&self, key: &Datacell, g: &sync::atm::Guard) -> Datacell {
+        if key.is_null() | (key.tag().tag_unique() != self.mdl.p_tag().tag_unique()) {
+            return Datacell::null();
+        }
+        let key = unsafe {
+            // UNSAFE(@ohsayan): the tag is the same as the tag of the model's primary key
+            PrimaryIndexKey::new_from_dc(key.clone())
+        };
+        let Some(row) = self
+            .mdl
+            .primary_index()
+            .__raw_index()
+            .mt_get_element(&key, g)
+        else {
+            return Datacell::null();
+        };
+        let data = row.resolve_schema_deltas_and_freeze(self.mdl.delta_state());
+        let read = |field: &str| match data.fields().st_get(field) {
+            Some(dc) => dc.clone(),
+            None => (*VirtualDatacell::new_pk(row.d_key(), self.mdl.p_tag())).clone(),
+        };
+        match self.field {
+            Some(field) => read(field.as_str()),
+            None => Datacell::new_list(
+                self.mdl
+                    .fields()
+                    .stseq_ord_key()
+                    .map(|field| read(field.as_str()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Resolve the lookups of the selected fields (one for every field, which is `None` if the field isn't a lookup).
+/// The model that is looked up has to be in the same space and its primary key has to have the same type as the
+/// (non-list) field that is used to look up rows. The client must be able to access every model that is looked up
+pub(super) fn resolve_lookups<'m, 'a>(
+    global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    models: &'m IndexST<EntityID, Model>,
+    mdl: &Model,
+    space: &str,
+    fields: &[SelectField<'a>],
+) -> QueryResult<Vec<Option<Lookup<'m, 'a>>>> {
+    fields
+        .iter()
+        .map(|field| {
+            let Some(lookup) = field.lookup() else {
+                return Ok(None);
+            };
+            let entity = EntityIDRef::new(space, lookup.model().as_str());
+            check_access(global, c, (entity.space(), Some(entity.entity())))?;
+            let Some(other) = models.get(&entity) else {
+                return Err(QueryError::QExecObjectNotFound);
+            };
+            if lookup
+                .field()
+                .is_some_and(|field| !other.fields().st_contains(field.as_str()))
+            {
+                return Err(QueryError::QExecUnknownField);
+            }
+            let layers = mdl.fields().st_get(field.as_str()).unwrap().layers();
+            if (layers.len() != 1) | (layers[0].tag().tag_unique() != other.p_tag().tag_unique()) {
+                return Err(QueryError::QExecDmlValidationError);
+            }
+            Ok(Some(Lookup {
+                mdl: other,
+                field: lookup.field(),
+            }))
+        })
+        .collect()
+}
+
+/// Pass the nested value (a list element or the value of a dict key) at the given path to `f`, or null if there's no
+/// such value
+pub(in crate::engine::core) fn project<F: FnMut(&Datacell)>(
+    dc: &Datacell,
+    path: &[SelectPathSegment],
+    f: &mut F,
+) {
+    match path.split_first() {
+        None => f(dc),
+        Some(_) if dc.is_null() => f(&Datacell::null()),
+        Some((SelectPathSegment::Index(index), rest)) if dc.kind() == TagClass::List => {
+            match dc.list().read().get(*index as usize) {
+                Some(element) => project(element, rest, f),
+                None => f(&Datacell::null()),
+            }
+        }
+        Some((SelectPathSegment::Key(key), rest)) if dc.kind() == TagClass::Dict => {
+            match dc.dict().get(key.as_str()) {
+                Some(value) => project(value, rest, f),
+                None => f(&Datacell::null()),
+            }
+        }
+        // only possible past a key of a dict without a schema
+        Some(_) => f(&Datacell::null()),
+    }
+}
+
+/// Check that the field used to order the rows (if any) exists and can be ordered
+pub(super) fn check_window(mdl: &Model, window: &SelectWindow) -> QueryResult<()> {
+    let Some(order) = window.order else {
+        return Ok(());
+    };
+    match mdl.fields().st_get(order.field().as_str()) {
+        Some(field)
+            if !matches!(
+                field.layers()[0].tag().tag_class(),
+                TagClass::List | TagClass::Geo | TagClass::Dict
+            ) =>
+        {
+            Ok(())
+        }
+        Some(_) => Err(QueryError::QExecDmlValidationError),
+        None => Err(QueryError::QExecUnknownField),
+    }
+}
+
+/// An owned copy of the value that rows are ordered (or aggregated) by
+pub(in crate::engine::core) enum SortKey {
+    Null,
+    Bool(bool),
+    UInt(u64),
+    SInt(i64),
+    SInt128(i128),
+    Float(f64),
+    Bytes(Box<[u8]>),
+}
+
+impl SortKey {
+    pub(in crate::engine::core) fn new(dc: &Datacell) -> Self {
+        if dc.is_null() {
+            return Self::Null;
+        }
+        match dc.kind() {
+            TagClass::Bool => Self::Bool(dc.bool()),
+            TagClass::UnsignedInt => Self::UInt(dc.uint()),
+            TagClass::Timestamp => Self::UInt(dc.timestamp()),
+            TagClass::SignedInt => Self::SInt(dc.sint()),
+            TagClass::BigInt => Self::SInt128(dc.sint128()),
+            TagClass::Float => Self::Float(dc.float()),
+            TagClass::Bin => Self::Bytes(dc.bin().into()),
+            TagClass::Str => Self::Bytes(dc.str().as_bytes().into()),
+            // lists, points and dicts can't be ordered (see check_window)
+            TagClass::List | TagClass::Geo | TagClass::Dict => Self::Null,
+        }
+    }
+    pub(in crate::engine::core) fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::UInt(a), Self::UInt(b)) => a.cmp(b),
+            (Self::SInt(a), Self::SInt(b)) => a.cmp(b),
+            (Self::SInt128(a), Self::SInt128(b)) => a.cmp(b),
+            // 128-bit integers are compared with the integer literals that they're filtered by
+            (Self::SInt128(a), Self::UInt(b)) => a.cmp(&(*b as i128)),
+            (Self::SInt128(a), Self::SInt(b)) => a.cmp(&(*b as i128)),
+            (Self::UInt(a), Self::SInt128(b)) => (*a as i128).cmp(b),
+            (Self::SInt(a), Self::SInt128(b)) => (*a as i128).cmp(b),
+            (Self::Float(a), Self::Float(b)) => a.total_cmp(b),
+            (Self::Bytes(a), Self::Bytes(b)) => a.cmp(b),
+            // nulls go first
+            (Self::Null, Self::Null) => Ordering::Equal,
+            (Self::Null, _) => Ordering::Less,
+            _ => Ordering::Greater,
+        }
+    }
+    /// Turn the key back into a cell of the given (field) type
+    pub(super) fn into_cell(self, tag: FullTag) -> Datacell {
+        unsafe {
+            // UNSAFE(@ohsayan): the key was read from a cell with this tag
+            match self {
+                Self::Null => Datacell::null(),
+                Self::Bool(b) => Datacell::new_bool(b),
+                Self::UInt(u) if tag.tag_class() == TagClass::Timestamp => {
+                    Datacell::new_timestamp(u)
                 }
+                Self::UInt(u) => Datacell::new_uint(u, UIntSpec::from_full(tag)),
+                Self::SInt(s) => Datacell::new_sint(s, SIntSpec::from_full(tag)),
+                Self::SInt128(s) => Datacell::new_sint128(s),
+                Self::Float(f) => Datacell::new_float(f, FloatSpec::from_full(tag)),
+                Self::Bytes(b) if tag.tag_class() == TagClass::Str => {
+                    Datacell::new_str(String::from_utf8_unchecked(b.into_vec()).into_boxed_str())
+                }
+                Self::Bytes(b) => Datacell::new_bin(b),
             }
-            None => return Err(QueryError::QExecDmlRowNotFound),
         }
-        Ok(())
-    })
+    }
+}
+
+/// The most rows that we'll hold in memory to sort a result that isn't bounded by a limit
+const SORT_MAX_ROWS: usize = 1 << 16;
+
+/// Sort the rows, keeping only the first `keep` of them
+fn sort_rows<'g>(
+    mdl: &Model,
+    rows: impl Iterator<Item = &'g Row>,
+    order: OrderBy,
+    keep: usize,
+) -> QueryResult<Vec<&'g Row>> {
+    if keep == 0 {
+        return Ok(vec![]);
+    }
+    let mut sorted = Vec::new();
+    let is_pk = order.field().as_str() == mdl.p_key();
+    let sort = |sorted: &mut Vec<(SortKey, &'g Row)>| {
+        sorted.sort_by(|(a, _), (b, _)| {
+            let ord = a.cmp(b);
+            if order.is_desc() {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+        sorted.truncate(keep);
+    };
+    for row in rows {
+        queries::check()?;
+        let key = if is_pk {
+            SortKey::new(&VirtualDatacell::new_pk(row.d_key(), mdl.p_tag()))
+        } else {
+            let data = row.resolve_schema_deltas_and_freeze(mdl.delta_state());
+            match data.fields().st_get(order.field().as_str()) {
+                Some(dc) => SortKey::new(dc),
+                None => return Err(QueryError::QExecUnknownField),
+            }
+        };
+        sorted.push((key, row));
+        /*
+            if the window is small enough, we keep (at most) twice the rows we need and periodically throw away the
+            ones we don't. otherwise, we refuse to buffer an unbounded number of rows
+        */
+        if keep <= SORT_MAX_ROWS {
+            if sorted.len() == keep.saturating_mul(2) {
+                sort(&mut sorted);
+            }
+        } else if sorted.len() > SORT_MAX_ROWS {
+            return Err(QueryError::QExecDmlSortTooLarge);
+        }
+    }
+    sort(&mut sorted);
+    Ok(sorted.into_iter().map(|(_, row)| row).collect())
 }
 
-struct RowIteratorAll<'g> {
+/// Skip the first `offset` rows and return (at most) `limit` of the rows that follow
+fn page_rows<'g>(
+    mut rows: impl Iterator<Item = &'g Row>,
+    offset: usize,
+    limit: usize,
+) -> QueryResult<Vec<&'g Row>> {
+    let mut page = Vec::new();
+    let mut skipped = 0;
+    while page.len() < limit {
+        queries::check()?;
+        let Some(row) = rows.next() else {
+            break;
+        };
+        if skipped < offset {
+            skipped += 1;
+        } else {
+            page.push(row);
+        }
+    }
+    Ok(page)
+}
+
+type RowIteratorEntries<'g> =
+    <IndexMTOrd<Row> as MTIndexExt<Row, PrimaryIndexKey, RowDataLck>>::IterEntry<'g, 'g, 'g>;
+type RowIteratorRange<'g, 'a> =
+    <IndexMTOrd<Row> as MTIndexExt<Row, PrimaryIndexKey, RowDataLck>>::IterRange<
+        'g,
+        'g,
+        'g,
+        Lit<'a>,
+        (Bound<Lit<'a>>, Bound<Lit<'a>>),
+    >;
+
+/// Iterates over the rows of a model, holding a latch on the primary index until done. Full scans hold the exclusive
+/// latch so that they see the model as of one point in time; lookups and range scans seek in the (ordered) index and
+/// only hold the shared latch, so they run alongside writers
+pub(super) struct RowIteratorAll<
+    'g,
+    I: Iterator<Item = &'g Row> = RowIteratorEntries<'g>,
+    L = IndexLatchHandleExclusive<'g>,
+> {
     _g: &'g sync::atm::Guard,
     mdl: &'g Model,
-    iter: <IndexMTRaw<Row> as MTIndexExt<Row, PrimaryIndexKey, RowDataLck>>::IterEntry<'g, 'g, 'g>,
-    _latch: IndexLatchHandleExclusive<'g>,
-    limit: usize,
+    iter: I,
+    _latch: L,
+}
+
+impl<'g, 'a> RowIteratorAll<'g, RowIteratorRange<'g, 'a>, IndexLatchHandleShared<'g>> {
+    pub(super) fn new_range(
+        g: &'g sync::atm::Guard,
+        mdl: &'g Model,
+        range: (Bound<Lit<'a>>, Bound<Lit<'a>>),
+    ) -> Self {
+        let idx = mdl.primary_index();
+        let latch = idx.acquire_cd();
+        Self {
+            _g: g,
+            mdl,
+            iter: idx.__raw_index().mt_range(range, g),
+            _latch: latch,
+        }
+    }
+}
+
+impl<'g> RowIteratorAll<'g, vec::IntoIter<&'g Row>, IndexLatchHandleShared<'g>> {
+    fn new_search(
+        g: &'g sync::atm::Guard,
+        mdl: &'g Model,
+        search: IndexSearch,
+    ) -> QueryResult<Self> {
+        let idx = mdl.primary_index();
+        let latch = idx.acquire_cd();
+        let mut rows = vec![];
+        for key in search.candidates() {
+            queries::check()?;
+            let Some(row) = idx.__raw_index().mt_get_element(&key, g) else {
+                continue;
+            };
+            let data = row.resolve_schema_deltas_and_freeze(mdl.delta_state());
+            if search.matches(mdl, row.d_key(), data.fields()) {
+                rows.push(row);
+            }
+        }
+        Ok(Self {
+            _g: g,
+            mdl,
+            iter: rows.into_iter(),
+            _latch: latch,
+        })
+    }
+    fn new_range_scan(
+        g: &'g sync::atm::Guard,
+        mdl: &'g Model,
+        scan: RangeScan,
+    ) -> QueryResult<Self> {
+        let idx = mdl.primary_index();
+        let latch = idx.acquire_cd();
+        let mut rows = vec![];
+        for row in idx.__raw_index().mt_range(scan.range(), g) {
+            queries::check()?;
+            let data = row.resolve_schema_deltas_and_freeze(mdl.delta_state());
+            if scan.matches(mdl, row.d_key(), data.fields()) {
+                rows.push(row);
+            }
+        }
+        Ok(Self {
+            _g: g,
+            mdl,
+            iter: rows.into_iter(),
+            _latch: latch,
+        })
+    }
 }
 
 impl<'g> RowIteratorAll<'g> {
-    fn new(g: &'g sync::atm::Guard, mdl: &'g Model, limit: usize) -> Self {
+    pub(super) fn new(g: &'g sync::atm::Guard, mdl: &'g Model) -> Self {
         let idx = mdl.primary_index();
         let latch = idx.acquire_exclusive();
         Self {
@@ -229,19 +1010,17 @@ impl<'g> RowIteratorAll<'g> {
             mdl,
             iter: idx.__raw_index().mt_iter_entry(g),
             _latch: latch,
-            limit,
         }
     }
+}
+
+impl<'g, I: Iterator<Item = &'g Row>, L> RowIteratorAll<'g, I, L> {
     fn _next(
         &mut self,
     ) -> Option<(
         &'g PrimaryIndexKey,
         parking_lot::RwLockReadGuard<'g, RowData>,
     )> {
-        if self.limit == 0 {
-            return None;
-        }
-        self.limit -= 1;
         self.iter.next().map(|row| {
             (
                 row.d_key(),
@@ -251,7 +1030,38 @@ impl<'g> RowIteratorAll<'g> {
     }
 }
 
-impl<'g> Iterator for RowIteratorAll<'g> {
+impl<'g, I: Iterator<Item = &'g Row>, L> RowIteratorAll<'g, I, L> {
+    /// Order and page the rows, returning an iterator over just the rows in the window
+    fn into_window(
+        self,
+        window: &SelectWindow,
+    ) -> QueryResult<RowIteratorAll<'g, vec::IntoIter<&'g Row>, L>> {
+        let Self {
+            _g,
+            mdl,
+            iter,
+            _latch,
+        } = self;
+        let offset = window.offset as usize;
+        let limit = window.limit.map_or(usize::MAX, |limit| limit as usize);
+        let rows: Vec<_> = match window.order {
+            None => page_rows(iter, offset, limit)?,
+            Some(order) => sort_rows(mdl, iter, order, offset.saturating_add(limit))?
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .collect(),
+        };
+        Ok(RowIteratorAll {
+            _g,
+            mdl,
+            iter: rows.into_iter(),
+            _latch,
+        })
+    }
+}
+
+impl<'g, I: Iterator<Item = &'g Row>, L> Iterator for RowIteratorAll<'g, I, L> {
     type Item = (
         &'g PrimaryIndexKey,
         parking_lot::RwLockReadGuard<'g, RowData>,
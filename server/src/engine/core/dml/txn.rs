@@ -0,0 +1,233 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{QueryExecMeta, ReplicatedChange},
+    crate::engine::{
+        core::{
+            index::{DcFieldIndex, PrimaryIndexKey, Row},
+            model::{
+                delta::{DataDelta, DataDeltaKind, DeltaState, DeltaVersion},
+                intent::IntentScope,
+                Model,
+            },
+        },
+        data::{cell::Datacell, uuid::Uuid},
+        error::{QueryError, QueryResult},
+        fractal::GlobalInstanceLike,
+        idx::{STIndex, STIndexSeq},
+        sync::atm::cpin,
+    },
+    std::cell::RefCell,
+};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Transaction>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Default)]
+/// A group of DML statements whose changes are committed (see [`Transaction::commit`]) or undone (see
+/// [`Transaction::rollback`]) together.
+///
+/// The rows that a transaction reads for update or changes have their write intents held until it ends, so no one
+/// else can change them in the meantime. The changes are made to the primary index right away (so readers can see
+/// them), but their deltas are held back along with publishing them to subscribers and secondary indexes. On commit,
+/// the deltas for every model are appended as one group (so the journal has all of the transaction's changes to the
+/// model or none of them) and on rollback, they're dropped. A crash before the transaction commits leaves none of
+/// its changes in the journal
+pub struct Transaction {
+    intents: IntentScope,
+    /// how to undo every change, in the order that they were made
+    undo: Vec<(Uuid, ReplicatedChange)>,
+    /// the delta of every change, in the order that they were made
+    held: Vec<(Uuid, DataDelta)>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Run `f` as a part of this transaction, so that the statements that it runs take their intents and record their
+    /// changes here
+    ///
+    /// Just like [`RunningQuery::scope`](crate::engine::fractal::queries::RunningQuery::scope), the scope must not
+    /// span an `await`
+    pub fn scope<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        struct Restore<'a>(&'a mut Transaction);
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                if let Some(txn) = CURRENT.with(|current| current.borrow_mut().take()) {
+                    *self.0 = txn;
+                }
+            }
+        }
+        let txn = core::mem::take(self);
+        let nested = CURRENT.with(|current| current.borrow_mut().replace(txn));
+        debug_assert!(nested.is_none(), "transactions can't be nested");
+        let _restore = Restore(self);
+        f()
+    }
+    /// Journal and publish every change that was made in the transaction and end it. The changes are published with
+    /// the data that their rows have now (the rows can't have been changed by anyone else in the meantime)
+    pub fn commit(mut self, global: &impl GlobalInstanceLike) {
+        let held = core::mem::take(&mut self.held);
+        if held.is_empty() {
+            return;
+        }
+        let models = global.namespace().idx_models().read();
+        let g = cpin();
+        let mut uuids: Vec<Uuid> = held.iter().map(|(uuid, _)| *uuid).collect();
+        uuids.sort_unstable();
+        uuids.dedup();
+        for uuid in uuids {
+            // if the model was dropped since, so were its rows
+            let Some((entity, model)) = models.iter().find(|(_, model)| model.get_uuid() == uuid)
+            else {
+                continue;
+            };
+            let ds = model.delta_state();
+            let deltas: Vec<_> = held
+                .iter()
+                .filter(|(of, _)| *of == uuid)
+                .map(|(_, delta)| {
+                    let data = delta.row().resolve_schema_deltas_and_freeze(ds);
+                    model.publish_change(
+                        delta.change(),
+                        delta.data_version(),
+                        delta.row().d_key(),
+                        data.fields(),
+                    );
+                    delta.clone()
+                })
+                .collect();
+            let count = deltas.len();
+            let hint = ds.append_new_data_deltas(deltas, &g);
+            ds.release_data_deltas(count);
+            DeltaState::guard_delta_overflow(
+                global,
+                entity.space(),
+                entity.entity(),
+                model,
+                QueryExecMeta::new(hint),
+            );
+        }
+    }
+    /// Undo every change that was made in the transaction (most recent first) and end it. None of the changes were
+    /// published, so undoing them isn't published either (but see [`Model::undo_changes`]). If a change can't be
+    /// undone, the ones before it stay in place
+    pub fn rollback(mut self, global: &impl GlobalInstanceLike) -> QueryResult<()> {
+        let models = global.namespace().idx_models().read();
+        for (uuid, _) in self.held.drain(..) {
+            if let Some(model) = models.values().find(|model| model.get_uuid() == uuid) {
+                model.delta_state().release_data_deltas(1);
+            }
+        }
+        while let Some((uuid, change)) = self.undo.pop() {
+            let mut changes = vec![change];
+            while self.undo.last().is_some_and(|(next, _)| *next == uuid) {
+                changes.extend(self.undo.pop().map(|(_, change)| change));
+            }
+            // if the model was dropped since, there's nothing left to undo
+            let Some(model) = models.values().find(|model| model.get_uuid() == uuid) else {
+                continue;
+            };
+            if let Err(e) = model.undo_changes(changes) {
+                error!("failed to undo the changes of a transaction (some of them remain): {e}");
+                return Err(QueryError::SysTransactionalError);
+            }
+        }
+        Ok(())
+    }
+    #[cfg(test)]
+    pub fn changes(&self) -> usize {
+        self.undo.len()
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        debug_assert!(
+            self.held.is_empty() | std::thread::panicking(),
+            "a transaction with changes must be committed or rolled back"
+        );
+    }
+}
+
+/// Take the write intent on a row that is about to be read for update or changed, failing if another transaction
+/// holds it. Outside of a transaction, this only checks that no transaction holds it
+pub(in crate::engine::core) fn take_intent(mdl: &Model, key: &PrimaryIndexKey) -> QueryResult<()> {
+    CURRENT.with(|current| match current.borrow_mut().as_mut() {
+        Some(txn) => txn.intents.acquire(mdl, key),
+        None => mdl.row_intents().check(key, None),
+    })
+}
+
+/// Publish a change to a row (see [`Model::publish_change`]) and return its delta for the caller to append, or hold
+/// both back until the transaction that made the change commits (returning [`None`])
+pub(in crate::engine::core) fn publish_change(
+    mdl: &Model,
+    kind: DataDeltaKind,
+    version: DeltaVersion,
+    row: &Row,
+    data: &DcFieldIndex,
+) -> Option<DataDelta> {
+    let delta = DataDelta::new(version, row.clone(), kind);
+    CURRENT.with(|current| match current.borrow_mut().as_mut() {
+        Some(txn) => {
+            mdl.delta_state().hold_data_delta(row.d_key());
+            txn.held.push((mdl.get_uuid(), delta));
+            None
+        }
+        None => {
+            mdl.publish_change(kind, version, row.d_key(), data);
+            Some(delta)
+        }
+    })
+}
+
+/// Record how to undo a change to a row, if it's made in a transaction
+pub(in crate::engine::core) fn record_undo(mdl: &Model, undo: impl FnOnce() -> ReplicatedChange) {
+    CURRENT.with(|current| {
+        if let Some(txn) = current.borrow_mut().as_mut() {
+            txn.undo.push((mdl.get_uuid(), undo()));
+        }
+    })
+}
+
+/// Returns the change that puts a row back the way it was (see [`ReplicatedChange::Upsert`])
+pub(in crate::engine::core) fn restore_row(
+    mdl: &Model,
+    key: &PrimaryIndexKey,
+    data: &DcFieldIndex,
+) -> ReplicatedChange {
+    let row = mdl
+        .fields()
+        .stseq_ord_key()
+        .filter(|field| field.as_str() != mdl.p_key())
+        .map(|field| data.st_get(field).cloned().unwrap_or_else(Datacell::null))
+        .collect();
+    ReplicatedChange::Upsert(key.clone(), row)
+}
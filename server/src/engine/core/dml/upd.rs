@@ -31,7 +31,10 @@ use {
     crate::{
         engine::{
             core::{
-                self, dml::QueryExecMeta, model::delta::DataDeltaKind,
+                self,
+                dml::{txn, Changes, QueryExecMeta, ReturnedRows},
+                index::{Row, RowData},
+                model::{delta::DataDeltaKind, Field, Model},
                 query_meta::AssignmentOperator,
             },
             data::{
@@ -40,15 +43,15 @@ use {
                 tag::{DataTag, FloatSpec, SIntSpec, TagClass, UIntSpec},
             },
             error::{QueryError, QueryResult},
-            fractal::GlobalInstanceLike,
-            idx::STIndex,
+            fractal::{queries, GlobalInstanceLike},
+            idx::{geo, MTIndex, STIndex},
             net::protocol::Response,
             ql::dml::upd::{AssignmentExpression, UpdateStatement},
             sync,
         },
         util::compiler,
     },
-    std::mem,
+    std::{mem, ptr},
 };
 
 #[inline(always)]
@@ -82,8 +85,11 @@ unsafe fn dc_op_uint_mul(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
 }
 unsafe fn dc_op_uint_div(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
     let kind = UIntSpec::from_full(dc.tag());
-    let (uint, did_of) = dc.uint().overflowing_div(rhs.uint());
-    (kind.check(uint) & !did_of, Datacell::new_uint(uint, kind))
+    // division by zero is treated like an overflow
+    match dc.uint().checked_div(rhs.uint()) {
+        Some(uint) => (kind.check(uint), Datacell::new_uint(uint, kind)),
+        None => dc_op_fail(dc, rhs),
+    }
 }
 // sint
 unsafe fn dc_op_sint_ass(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
@@ -108,8 +114,11 @@ unsafe fn dc_op_sint_mul(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
 }
 unsafe fn dc_op_sint_div(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
     let kind = SIntSpec::from_full(dc.tag());
-    let (sint, did_of) = dc.sint().overflowing_div(rhs.sint());
-    (kind.check(sint) & !did_of, Datacell::new_sint(sint, kind))
+    // division by zero is treated like an overflow
+    match dc.sint().checked_div(rhs.sint()) {
+        Some(sint) => (kind.check(sint), Datacell::new_sint(sint, kind)),
+        None => dc_op_fail(dc, rhs),
+    }
 }
 /*
     float
@@ -187,8 +196,96 @@ unsafe fn dc_op_str_add(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
     (true, Datacell::new_str(str.into_boxed_str()))
 }
 
+// timestamp
+unsafe fn dc_op_timestamp_ass(_: &Datacell, rhs: Lit) -> (bool, Datacell) {
+    (true, Datacell::new_timestamp(rhs.uint()))
+}
+unsafe fn dc_op_timestamp_add(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
+    match dc.read_timestamp().checked_add(rhs.uint()) {
+        Some(micros) => (true, Datacell::new_timestamp(micros)),
+        None => dc_op_fail(dc, rhs),
+    }
+}
+unsafe fn dc_op_timestamp_sub(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
+    match dc.read_timestamp().checked_sub(rhs.uint()) {
+        Some(micros) => (true, Datacell::new_timestamp(micros)),
+        None => dc_op_fail(dc, rhs),
+    }
+}
+
+// sint128
+/// Read an integer literal (or a string with the digits) as a 128-bit integer
+unsafe fn lit_sint128(rhs: &Lit) -> Option<i128> {
+    match rhs.kind().tag_class() {
+        TagClass::UnsignedInt => Some(rhs.uint() as i128),
+        TagClass::SignedInt => Some(rhs.sint() as i128),
+        TagClass::Str => rhs.str().parse().ok(),
+        _ => None,
+    }
+}
+/// Apply a checked operation, failing (instead of rounding or wrapping) if the result can't be represented exactly
+unsafe fn dc_op_sint128(
+    dc: &Datacell,
+    rhs: Lit,
+    op: fn(i128, i128) -> Option<i128>,
+) -> (bool, Datacell) {
+    match lit_sint128(&rhs).and_then(|rhs| op(dc.read_sint128(), rhs)) {
+        Some(int) => (true, Datacell::new_sint128(int)),
+        None => dc_op_fail(dc, rhs),
+    }
+}
+unsafe fn dc_op_sint128_ass(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
+    match lit_sint128(&rhs) {
+        Some(int) => (true, Datacell::new_sint128(int)),
+        None => dc_op_fail(dc, rhs),
+    }
+}
+unsafe fn dc_op_sint128_add(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
+    dc_op_sint128(dc, rhs, i128::checked_add)
+}
+unsafe fn dc_op_sint128_sub(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
+    dc_op_sint128(dc, rhs, i128::checked_sub)
+}
+unsafe fn dc_op_sint128_mul(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
+    dc_op_sint128(dc, rhs, i128::checked_mul)
+}
+unsafe fn dc_op_sint128_div(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
+    // the division must be exact (no remainder)
+    dc_op_sint128(dc, rhs, |a, b| match a.checked_rem(b) {
+        Some(0) => a.checked_div(b),
+        _ => None,
+    })
+}
+
+// geopoint
+unsafe fn dc_op_geopoint_ass(dc: &Datacell, rhs: Lit) -> (bool, Datacell) {
+    // there are no point literals: `location = '12.97,77.59'`
+    match geo::parse_point(rhs.str()) {
+        Some((lat, lon)) => (true, Datacell::new_geopoint(lat, lon)),
+        None => dc_op_fail(dc, rhs),
+    }
+}
+
+/// Returns true if the RHS of an assignment can be used with a field of the given class, even though it's of a
+/// different class
+pub(super) fn rhs_coerces(field: TagClass, rhs: TagClass) -> bool {
+    match field {
+        // `counter += 1`
+        TagClass::SignedInt => rhs == TagClass::UnsignedInt,
+        // micros: `expires_at += 3600000000`
+        TagClass::Timestamp => rhs == TagClass::UnsignedInt,
+        // there are no 128-bit literals: `balance += 100` or `balance = '170141183460469231731687303715884105727'`
+        TagClass::BigInt => matches!(
+            rhs,
+            TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Str
+        ),
+        TagClass::Geo => rhs == TagClass::Str,
+        _ => false,
+    }
+}
+
 static OPERATOR: [unsafe fn(&Datacell, Lit) -> (bool, Datacell); {
-    TagClass::MAX as usize * AssignmentOperator::VARIANTS
+    (TagClass::MAX as usize + 1) * AssignmentOperator::VARIANTS
 }] = [
     // bool
     dc_op_bool_ass,
@@ -229,6 +326,40 @@ static OPERATOR: [unsafe fn(&Datacell, Lit) -> (bool, Datacell); {
     dc_op_fail,
     dc_op_fail,
     dc_op_fail,
+    // list (see list_op)
+    // -- pad: 5
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
+    // timestamp
+    dc_op_timestamp_ass,
+    dc_op_timestamp_add,
+    dc_op_timestamp_sub,
+    // -- pad: 2
+    dc_op_fail,
+    dc_op_fail,
+    // sint128
+    dc_op_sint128_ass,
+    dc_op_sint128_add,
+    dc_op_sint128_sub,
+    dc_op_sint128_mul,
+    dc_op_sint128_div,
+    // geopoint
+    dc_op_geopoint_ass,
+    // -- pad: 4
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
+    // dict (there are no dict literals)
+    // -- pad: 5
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
+    dc_op_fail,
 ];
 
 #[inline(always)]
@@ -254,126 +385,404 @@ pub fn collect_trace_path() -> Vec<&'static str> {
     ROUTE_TRACE.with(|v| v.borrow().iter().cloned().collect())
 }
 
+/// How to undo a change to a list, if the update has to be rolled back
+enum ListUndo {
+    Pop,
+    Insert(usize, Datacell),
+    Remove(usize),
+    Set(usize, Datacell),
+}
+
+impl ListUndo {
+    fn apply(self, list: &mut Vec<Datacell>) {
+        match self {
+            Self::Pop => {
+                list.pop();
+            }
+            Self::Insert(i, dc) => list.insert(i, dc),
+            Self::Remove(i) => {
+                list.remove(i);
+            }
+            Self::Set(i, dc) => list[i] = dc,
+        }
+    }
+}
+
+/// Apply a list operation, returning how to undo it:
+/// - push: `list += x`
+/// - remove-at: `list -= i` (negative indices count from the end, so `list -= -1` pops)
+/// - set-at: `list[i] = x`
+/// - insert-at: `list[i] += x` (where `i` can be one past the end)
+fn list_op(
+    field: &Field,
+    list: &mut Vec<Datacell>,
+    index: Option<u64>,
+    operator_fn: AssignmentOperator,
+    rhs: Lit,
+) -> QueryResult<ListUndo> {
+    let element = |rhs: Lit| {
+        let mut dc = Datacell::from(rhs);
+        if field.vt_list_element(&mut dc) {
+            Ok(dc)
+        } else {
+            input_trace("list;badtag");
+            Err(QueryError::QExecDmlValidationError)
+        }
+    };
+    let len = list.len();
+    match (index.map(|i| i as usize), operator_fn) {
+        (None, AssignmentOperator::AddAssign) => {
+            let dc = element(rhs)?;
+            if list.try_reserve(1).is_err() {
+                return Err(QueryError::SysOutOfMemory);
+            }
+            input_trace("list;sametag");
+            list.push(dc);
+            Ok(ListUndo::Pop)
+        }
+        (None, AssignmentOperator::SubAssign) => {
+            let index = unsafe {
+                // UNSAFE(@ohsayan): +tagck
+                match rhs.kind().tag_class() {
+                    TagClass::UnsignedInt => usize::try_from(rhs.uint()).ok(),
+                    TagClass::SignedInt if rhs.sint() < 0 => {
+                        len.checked_sub(rhs.sint().unsigned_abs() as usize)
+                    }
+                    TagClass::SignedInt => usize::try_from(rhs.sint()).ok(),
+                    _ => None,
+                }
+            };
+            match index {
+                Some(i) if i < len => {
+                    input_trace("list;remove");
+                    Ok(ListUndo::Insert(i, list.remove(i)))
+                }
+                _ => Err(QueryError::QExecDmlValidationError),
+            }
+        }
+        (Some(i), AssignmentOperator::Assign) if i < len => {
+            let dc = element(rhs)?;
+            input_trace("list;set");
+            Ok(ListUndo::Set(i, mem::replace(&mut list[i], dc)))
+        }
+        (Some(i), AssignmentOperator::AddAssign) if i <= len => {
+            let dc = element(rhs)?;
+            if list.try_reserve(1).is_err() {
+                return Err(QueryError::SysOutOfMemory);
+            }
+            input_trace("list;insert");
+            list.insert(i, dc);
+            Ok(ListUndo::Remove(i))
+        }
+        _ => Err(QueryError::QExecDmlValidationError),
+    }
+}
+
 pub fn update_resp(
     global: &impl GlobalInstanceLike,
     update: UpdateStatement,
 ) -> QueryResult<Response> {
-    self::update(global, update).map(|_| Response::Empty)
+    let entity = update.entity();
+    super::log_write("update", entity, self::update_rows(global, update))
+        .map(Changes::into_response)
 }
 
-pub fn update(global: &impl GlobalInstanceLike, mut update: UpdateStatement) -> QueryResult<()> {
+#[cfg(test)]
+pub fn update(global: &impl GlobalInstanceLike, update: UpdateStatement) -> QueryResult<()> {
+    update_rows(global, update).map(|_| ())
+}
+
+/// Run an update. If the where clause doesn't pick a single row using its primary key (`where pk = x`), every row
+/// that satisfies it is updated (`update model set f = v where <predicate>`) and the number of rows that were
+/// updated is returned. The updated rows are also returned if they were asked for (`returning ...`).
+///
+/// With multiple rows, every row is checked again once it's locked and each one is updated (and journaled) on its
+/// own, so an error (such as an overflow) stops the update without undoing the rows that were already updated
+pub fn update_rows(
+    global: &impl GlobalInstanceLike,
+    mut update: UpdateStatement,
+) -> QueryResult<Changes> {
+    let returning = update.take_returning();
+    let mut count = None;
+    let mut returned = None;
     core::with_model_for_data_update(global, update.entity(), |mdl| {
-        let mut ret = Ok(QueryExecMeta::zero());
-        // prepare row fetch
-        let key = mdl.resolve_where(update.clauses_mut())?;
-        // fetch row
+        returned = ReturnedRows::resolve(mdl, returning)?;
+        if is_single_row(mdl, &mut update) {
+            return update_row(mdl, update, &mut returned);
+        }
+        let filter = mdl.resolve_where_filter(update.clauses_mut())?;
         let g = sync::atm::cpin();
-        let Some(row) = mdl.primary_index().select(key, &g) else {
-            return Err(QueryError::QExecDmlRowNotFound);
+        let mut updated = 0;
+        let mut meta = QueryExecMeta::zero();
+        filter.scan(mdl, &g, |row| {
+            queries::check()?;
+            let mut row_data_wl = row.resolve_schema_deltas_and_lock(mdl.delta_state());
+            // the row might have been deleted (or changed) since it was found
+            let current = mdl
+                .primary_index()
+                .__raw_index()
+                .mt_get_element(row.d_key(), &g);
+            if !current.is_some_and(|current| ptr::eq(current.d_data(), row.d_data()))
+                || !filter.matches(mdl, row.d_key(), row_data_wl.fields())
+            {
+                return Ok(true);
+            }
+            let dp = update_locked_row(
+                mdl,
+                row,
+                &mut row_data_wl,
+                update.expressions().iter().cloned(),
+                &g,
+                &mut returned,
+            )?;
+            meta = QueryExecMeta::new(dp);
+            updated += 1;
+            Ok(true)
+        })?;
+        count = Some(updated);
+        Ok(meta)
+    })?;
+    Ok(Changes::new(count, returned))
+}
+
+/// Returns true if the update is for a single row (`where pk = x`, or with `if version = n`)
+pub(super) fn is_single_row(mdl: &Model, update: &mut UpdateStatement) -> bool {
+    update.version().is_some()
+        || update
+            .clauses_mut()
+            .clauses_mut()
+            .get(mdl.p_key().as_bytes())
+            .is_some_and(|clause| clause.filter_hint_none())
+}
+
+/// Update the single row picked by the where clause, failing if it doesn't satisfy the other clauses
+fn update_row(
+    mdl: &Model,
+    mut update: UpdateStatement,
+    returned: &mut Option<ReturnedRows>,
+) -> QueryResult<QueryExecMeta> {
+    // prepare row fetch
+    let key = mdl.resolve_where(update.clauses_mut())?;
+    let conditions = mdl.resolve_where_conditions(update.clauses_mut())?;
+    // fetch row
+    let g = sync::atm::cpin();
+    let Some(row) = mdl.primary_index().select(key, &g) else {
+        return Err(QueryError::QExecDmlRowNotFound);
+    };
+    // lock row (bringing it up to date with the schema, if it isn't)
+    let mut row_data_wl = row.resolve_schema_deltas_and_lock(mdl.delta_state());
+    // check conditions (under the row lock so that this is an atomic compare-and-set)
+    if let Some(version) = update.version() {
+        if row_data_wl.get_version().value_u64() != version {
+            return Err(QueryError::QExecDmlConditionFailed);
+        }
+    }
+    for (field_name, expr) in conditions {
+        // (the field can only be missing if it was dropped since the conditions were resolved)
+        match row_data_wl.fields().st_get(field_name.as_str()) {
+            Some(current) if super::matches(current, &expr) => {}
+            _ => return Err(QueryError::QExecDmlConditionFailed),
+        }
+    }
+    update_locked_row(
+        mdl,
+        row,
+        &mut row_data_wl,
+        update.into_expressions().into_iter(),
+        &g,
+        returned,
+    )
+    .map(QueryExecMeta::new)
+}
+
+/// Apply the assignments to a locked row (that's at the current schema version), returning the delta hint. If any
+/// of the assignments fails, the ones that were already applied are undone
+fn update_locked_row<'a>(
+    mdl: &Model,
+    row: &Row,
+    row_data_wl: &mut RowData,
+    mut assn_expressions: impl ExactSizeIterator<Item = AssignmentExpression<'a>>,
+    g: &sync::atm::Guard,
+    returned: &mut Option<ReturnedRows>,
+) -> QueryResult<usize> {
+    txn::take_intent(mdl, row.d_key())?;
+    txn::record_undo(mdl, || {
+        txn::restore_row(mdl, row.d_key(), row_data_wl.fields())
+    });
+    let mut ret = Ok(0);
+    let ds = mdl.delta_state();
+    let old_size = row.estimated_size(row_data_wl);
+    // create new version (before looking for snapshots, see `Model::pin_snapshot`)
+    let new_version = ds.create_new_data_delta_version();
+    ds.snapshot_preserve(row, row_data_wl);
+    // process changes
+    let mut rollback_now = false;
+    let mut rollback_data = Vec::with_capacity(assn_expressions.len());
+    let mut list_rollback_data = Vec::new();
+    /*
+        FIXME(@ohsayan): where's my usual magic? I'll do it once we have the SE stabilized
+    */
+    // apply changes
+    while (assn_expressions.len() != 0) & (!rollback_now) {
+        let AssignmentExpression {
+            lhs,
+            index,
+            rhs,
+            operator_fn,
+        } = unsafe {
+            // UNSAFE(@ohsayan): pre-loop cond
+            assn_expressions.next().unwrap_unchecked()
         };
-        // lock row
-        let mut row_data_wl = row.d_data().write();
-        // create new version
-        let ds = mdl.delta_state();
-        let new_version = ds.create_new_data_delta_version();
-        // process changes
-        let mut rollback_now = false;
-        let mut rollback_data = Vec::with_capacity(update.expressions().len());
-        let mut assn_expressions = update.into_expressions().into_iter();
-        /*
-            FIXME(@ohsayan): where's my usual magic? I'll do it once we have the SE stabilized
-        */
-        // apply changes
-        while (assn_expressions.len() != 0) & (!rollback_now) {
-            let AssignmentExpression {
-                lhs,
-                rhs,
-                operator_fn,
-            } = unsafe {
-                // UNSAFE(@ohsayan): pre-loop cond
-                assn_expressions.next().unwrap_unchecked()
-            };
-            let field_definition;
-            let field_data;
-            match (
-                mdl.fields().st_get(lhs.as_str()),
-                row_data_wl.fields_mut().st_get_mut(lhs.as_str()),
-            ) {
-                (Some(fdef), Some(fdata)) => {
-                    field_definition = fdef;
-                    field_data = fdata;
-                }
-                _ => {
-                    input_trace("fieldnotfound");
-                    rollback_now = true;
-                    ret = Err(QueryError::QExecUnknownField);
-                    break;
-                }
+        let field_definition;
+        let field_data;
+        match (
+            mdl.fields().st_get(lhs.as_str()),
+            row_data_wl.fields_mut().st_get_mut(lhs.as_str()),
+        ) {
+            (Some(fdef), Some(fdata)) => {
+                field_definition = fdef;
+                field_data = fdata;
             }
-            match (
-                field_definition.layers()[0].tag().tag_class(),
-                rhs.kind().tag_class(),
-            ) {
-                (tag_a, tag_b)
-                    if (tag_a == tag_b) & (tag_a < TagClass::List) & field_data.is_init() =>
-                {
-                    let (okay, new) = unsafe { OPERATOR[opc(tag_a, operator_fn)](field_data, rhs) };
-                    rollback_now &= !okay;
-                    rollback_data.push((lhs.as_str(), mem::replace(field_data, new)));
-                    input_trace("sametag;nonnull");
-                }
-                (tag_a, tag_b)
-                    if (tag_a == tag_b)
-                        & field_data.is_null()
-                        & (operator_fn == AssignmentOperator::Assign) =>
-                {
-                    rollback_data.push((lhs.as_str(), mem::replace(field_data, rhs.into())));
-                    input_trace("sametag;orignull");
-                }
-                (TagClass::List, tag_b) if operator_fn == AssignmentOperator::AddAssign => {
-                    if field_definition.layers()[1].tag().tag_class() == tag_b {
-                        unsafe {
-                            // UNSAFE(@ohsayan): matched tags
-                            let mut list = field_data.read_list().write();
-                            if list.try_reserve(1).is_ok() {
-                                input_trace("list;sametag");
-                                list.push(rhs.into());
-                            } else {
-                                rollback_now = true;
-                                ret = Err(QueryError::SysOutOfMemory);
-                                break;
-                            }
-                        }
-                    } else {
-                        input_trace("list;badtag");
-                        rollback_now = true;
+            _ => {
+                input_trace("fieldnotfound");
+                rollback_now = true;
+                ret = Err(QueryError::QExecUnknownField);
+                break;
+            }
+        }
+        if field_definition.compute().is_some() {
+            // computed fields can't be set
+            input_trace("computed");
+            rollback_now = true;
+            ret = Err(QueryError::QExecDmlValidationError);
+            break;
+        }
+        let field_tag = field_definition.layers()[0].tag().tag_class();
+        // unsigned literals can be used with signed fields (`counter += 1`) as long as they fit
+        let rhs = match (field_tag, rhs.kind().tag_class()) {
+            // with timestamps, they are micros (`expires_at += 3600000000`)
+            (TagClass::Timestamp, TagClass::UnsignedInt) => Lit::new_timestamp(unsafe {
+                // UNSAFE(@ohsayan): +tagck
+                rhs.uint()
+            }),
+            (TagClass::SignedInt, TagClass::UnsignedInt) => {
+                match i64::try_from(unsafe {
+                    // UNSAFE(@ohsayan): +tagck
+                    rhs.uint()
+                }) {
+                    Ok(sint) => Lit::new_sint(sint),
+                    Err(_) => {
+                        input_trace("sametag;overflow");
                         ret = Err(QueryError::QExecDmlValidationError);
+                        rollback_now = true;
                         break;
                     }
                 }
-                _ => {
-                    input_trace("unknown_reason;exitmainloop");
+            }
+            _ => rhs,
+        };
+        // 128-bit integers and points are always updated with the (checked) operators since there are no
+        // literals for them
+        let no_literal = matches!(field_tag, TagClass::BigInt | TagClass::Geo)
+            & rhs_coerces(field_tag, rhs.kind().tag_class());
+        match (field_tag, rhs.kind().tag_class()) {
+            (tag_a, tag_b)
+                if ((tag_a == tag_b) | no_literal)
+                    & (tag_a != TagClass::List)
+                    & (field_data.is_init()
+                        | (no_literal & (operator_fn == AssignmentOperator::Assign)))
+                    & index.is_none() =>
+            {
+                let (okay, new) = unsafe { OPERATOR[opc(tag_a, operator_fn)](field_data, rhs) };
+                if compiler::unlikely(!okay) {
+                    // the result overflowed (or doesn't fit in the field's type)
+                    input_trace("sametag;overflow");
                     ret = Err(QueryError::QExecDmlValidationError);
                     rollback_now = true;
                     break;
                 }
+                rollback_data.push((lhs.as_str(), mem::replace(field_data, new)));
+                input_trace("sametag;nonnull");
+            }
+            (tag_a, tag_b)
+                if (tag_a == tag_b)
+                    & field_data.is_null()
+                    & (operator_fn == AssignmentOperator::Assign)
+                    & index.is_none() =>
+            {
+                rollback_data.push((lhs.as_str(), mem::replace(field_data, rhs.into())));
+                input_trace("sametag;orignull");
+            }
+            (TagClass::List, _) if field_data.is_init() => {
+                let mut list = field_data.list().write();
+                match list_op(field_definition, &mut list, index, operator_fn, rhs) {
+                    Ok(undo) => list_rollback_data.push((lhs.as_str(), undo)),
+                    Err(e) => {
+                        rollback_now = true;
+                        ret = Err(e);
+                        break;
+                    }
+                }
+            }
+            _ => {
+                input_trace("unknown_reason;exitmainloop");
+                ret = Err(QueryError::QExecDmlValidationError);
+                rollback_now = true;
+                break;
             }
         }
-        if compiler::unlikely(rollback_now) {
-            input_trace("rollback");
+    }
+    if !rollback_now {
+        // bring the computed fields that use any of the updated fields up to date
+        let computed = mdl.compute_fields(row_data_wl.fields(), Some(row.d_key()), |compute| {
             rollback_data
-                .into_iter()
-                .for_each(|(field_id, restored_data)| {
-                    row_data_wl.fields_mut().st_update(field_id, restored_data);
-                });
-        } else {
-            // update revised tag
-            row_data_wl.set_txn_revised(new_version);
-            // publish delta
-            let dp =
-                ds.append_new_data_delta_with(DataDeltaKind::Update, row.clone(), new_version, &g);
-            ret = Ok(QueryExecMeta::new(dp))
+                .iter()
+                .any(|(field_id, _)| compute.depends_on(field_id))
+        });
+        match computed {
+            Ok(computed) => {
+                for (field_id, data) in computed {
+                    if let Some(field_data) = row_data_wl.fields_mut().st_get_mut(field_id.as_str())
+                    {
+                        rollback_data.push((field_id.as_str(), mem::replace(field_data, data)));
+                    }
+                }
+            }
+            Err(e) => {
+                ret = Err(e);
+                rollback_now = true;
+            }
         }
-        ret
-    })
+    }
+    if compiler::unlikely(rollback_now) {
+        input_trace("rollback");
+        // list changes are undone in the reverse order in which they were applied
+        list_rollback_data
+            .into_iter()
+            .rev()
+            .for_each(|(field_id, undo)| {
+                let field = row_data_wl.fields_mut().st_get_mut(field_id).unwrap();
+                undo.apply(&mut field.list().write());
+            });
+        rollback_data
+            .into_iter()
+            .for_each(|(field_id, restored_data)| {
+                row_data_wl.fields_mut().st_update(field_id, restored_data);
+            });
+    } else {
+        // update revised tag
+        row_data_wl.set_txn_revised(new_version);
+        ds.account_row(old_size, row.estimated_size(row_data_wl));
+        let delta = txn::publish_change(
+            mdl,
+            DataDeltaKind::Update,
+            new_version,
+            row,
+            row_data_wl.fields(),
+        );
+        super::push_returned(returned, mdl, row.d_key(), row_data_wl.fields());
+        // publish delta
+        ret = Ok(delta.map_or(0, |delta| ds.append_new_data_delta(delta, g)))
+    }
+    ret
 }
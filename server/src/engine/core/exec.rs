@@ -24,16 +24,35 @@
  *
 */
 
-use crate::engine::{
-    core::{ddl_misc, dml, model::Model, space::Space},
-    error::{QueryError, QueryResult},
-    fractal::{Global, GlobalInstanceLike},
-    net::protocol::{ClientLocalState, Response, ResponseType, SQuery},
-    ql::{
-        ast::{traits::ASTNode, InplaceData, State},
-        ddl::Use,
-        lex::KeywordStmt,
+use {
+    crate::engine::{
+        core::{
+            catalog, ddl_misc,
+            dml::{self, Transaction},
+            model::Model,
+            space::Space,
+            EntityIDRef,
+        },
+        error::{QueryError, QueryResult},
+        fractal::{queries::RunningQuery, Global, GlobalInstanceLike},
+        net::protocol::{ClientLocalState, QueryKind, Response, ResponseType, SQuery},
+        ql::{
+            ast::{traits::ASTNode, InplaceData, State},
+            dcl::SysctlCommand,
+            ddl::{alt::AlterModel, crt::CreateModel, drop::DropModel, Use},
+            dml::{
+                agg::AggregateStatement,
+                del::DeleteStatement,
+                explain::ExplainStatement,
+                ins::InsertStatement,
+                sel::{SelectAllStatement, SelectStatement},
+                upd::UpdateStatement,
+            },
+            lex::{KeywordStmt, SecureLexer, Token},
+        },
+        storage::v1::audit::{AuditEvent, AuditEventKind},
     },
+    std::sync::Arc,
 };
 
 /*
@@ -46,19 +65,202 @@ pub async fn dispatch_to_executor<'a>(
     cstate: &mut ClientLocalState,
     query: SQuery<'a>,
 ) -> QueryResult<Response> {
-    let tokens =
-        crate::engine::ql::lex::SecureLexer::new_with_segments(query.query(), query.params())
-            .lex()?;
+    // enforce the user's quota
+    let sys_cfg = global.sys_store().system_store();
+    let quota = sys_cfg.user_quota(cstate.username());
+    sys_cfg.quota_usage().run_query(cstate.username(), quota)?;
+    let prepared;
+    let (text, tokens) = match query.kind() {
+        QueryKind::Simple => (
+            query.query(),
+            SecureLexer::new_with_segments(query.query(), query.params()).lex()?,
+        ),
+        QueryKind::Prepare => return prepare(cstate, query),
+        QueryKind::Execute => {
+            prepared = cstate.prepared().get(query.query())?;
+            (prepared.query(), prepared.bind(query.params())?)
+        }
+    };
+    // the query can be killed (or time out) until it completes
+    let running = global
+        .queries()
+        .register(cstate.username(), &String::from_utf8_lossy(text));
+    match run_query(global, cstate, text, &tokens, &running).await? {
+        Response::Serialized { data, .. } if quota.exceeds_result_bytes(data.len()) => {
+            Err(QueryError::SysQuotaResultSize)
+        }
+        r => Ok(r),
+    }
+}
+
+/// Run a batch of queries in a [`Transaction`], returning the response of every query (in order). If any of the
+/// queries fails, the changes made by the ones before it are undone and only its error is returned
+///
+/// Readers can see the changes as each query runs, but they're only journaled (and published to subscribers and
+/// secondary indexes) once the whole batch has run, so a crash never leaves a part of the batch in place
+///
+/// Only DML can be run in a batch, since DDL can't be undone
+pub fn dispatch_batch_to_executor(
+    global: &Global,
+    cstate: &mut ClientLocalState,
+    queries: Vec<SQuery<'_>>,
+) -> QueryResult<Vec<Response>> {
+    let mut txn = Transaction::new();
+    let r = txn.scope(|| {
+        queries
+            .into_iter()
+            .map(|query| run_batched_query(global, cstate, query))
+            .collect::<QueryResult<Vec<_>>>()
+    });
+    match r {
+        Ok(responses) => {
+            txn.commit(global);
+            Ok(responses)
+        }
+        Err(e) => {
+            txn.rollback(global)?;
+            Err(e)
+        }
+    }
+}
+
+fn run_batched_query(
+    global: &Global,
+    cstate: &mut ClientLocalState,
+    query: SQuery<'_>,
+) -> QueryResult<Response> {
+    // enforce the user's quota
+    let sys_cfg = global.sys_store().system_store();
+    let quota = sys_cfg.user_quota(cstate.username());
+    sys_cfg.quota_usage().run_query(cstate.username(), quota)?;
+    let prepared;
+    let (text, tokens) = match query.kind() {
+        QueryKind::Simple => (
+            query.query(),
+            SecureLexer::new_with_segments(query.query(), query.params()).lex()?,
+        ),
+        QueryKind::Prepare => return Err(QueryError::SysBatchIllegalStatement),
+        QueryKind::Execute => {
+            prepared = cstate.prepared().get(query.query())?;
+            (prepared.query(), prepared.bind(query.params())?)
+        }
+    };
     let mut state = State::new_inplace(&tokens);
     state.set_space_maybe(unsafe {
         // UNSAFE(@ohsayan): exclusively used within this scope
         core::mem::transmute(cstate.get_cs())
     });
     let stmt = state.try_statement()?;
+    if !stmt.is_dml() {
+        return Err(QueryError::SysBatchIllegalStatement);
+    }
+    if stmt.is_write() & global.replication_role().is_replica() {
+        // a replica only accepts writes from its primary
+        return Err(QueryError::SysReadOnly);
+    }
+    let running = global
+        .queries()
+        .register(cstate.username(), &String::from_utf8_lossy(text));
+    match cstate
+        .correlation()
+        .scope(|| running.scope(|| run_nb(global, cstate, state, stmt)))?
+    {
+        Response::Serialized { data, .. } if quota.exceeds_result_bytes(data.len()) => {
+            Err(QueryError::SysQuotaResultSize)
+        }
+        Response::Subscription(_) => Err(QueryError::SysPipelineSubscription),
+        r => Ok(r),
+    }
+}
+
+/// Prepare a statement for the client, responding with the id of the statement
+fn prepare(cstate: &mut ClientLocalState, query: SQuery<'_>) -> QueryResult<Response> {
+    if !query.params().is_empty() {
+        // the params are only sent when the statement is executed
+        return Err(QueryError::LexInvalidInput);
+    }
+    cstate
+        .prepared_mut()
+        .prepare(query.query())
+        .map(Response::UInt)
+}
+
+async fn run_query<'a>(
+    global: &Global,
+    cstate: &mut ClientLocalState,
+    text: &[u8],
+    tokens: &'a [Token<'a>],
+    running: &Arc<RunningQuery>,
+) -> QueryResult<Response> {
+    let mut state = State::new_inplace(tokens);
+    state.set_space_maybe(unsafe {
+        // UNSAFE(@ohsayan): exclusively used within this scope
+        core::mem::transmute(cstate.get_cs())
+    });
+    let stmt = state.try_statement()?;
+    if stmt.is_write() & global.replication_role().is_replica() {
+        // a replica only accepts writes from its primary
+        return Err(QueryError::SysReadOnly);
+    }
+    if stmt.is_ddl() && global.sys_store().system_store().root_must_rotate() {
+        // `sysctl alter user root` has to be run first
+        return Err(QueryError::SysAuthRootMustRotate);
+    }
     if stmt.is_blocking() {
-        run_blocking_stmt(global, cstate, state, stmt).await
+        // every DDL statement and sysctl command is audited
+        let kind = audit_kind(stmt, &state);
+        let r = run_blocking_stmt(global, cstate, state, stmt, running.clone()).await;
+        let event = AuditEvent::new(
+            kind,
+            r.is_ok(),
+            cstate.username(),
+            &cstate.peer_addr(),
+            &String::from_utf8_lossy(text),
+        );
+        let g = global.clone();
+        global
+            .blocking_pool()
+            .run(move || g.sys_store().audit(event))
+            .await;
+        r
     } else {
-        run_nb(global, cstate, state, stmt)
+        cstate
+            .correlation()
+            .scope(|| running.scope(|| run_nb(global, cstate, state, stmt)))
+    }
+}
+
+/// Returns the kind of event that a blocking statement is audited as (the tokens following the statement keyword
+/// haven't been read yet)
+fn audit_kind(stmt: KeywordStmt, state: &State<'_, InplaceData>) -> AuditEventKind {
+    if stmt != KeywordStmt::Sysctl {
+        return AuditEventKind::Ddl;
+    }
+    match state.current() {
+        [a, b, ..] if Token![create].eq(a) & b.ident_eq("user") => AuditEventKind::UserCreate,
+        [a, b, ..] if Token![drop].eq(a) & b.ident_eq("user") => AuditEventKind::UserDrop,
+        _ => AuditEventKind::Sysctl,
+    }
+}
+
+/// Run a query while the data is still being restored. The only query that can be run is `sysctl report startup` (by
+/// the root account), and everything else is rejected with [`QueryError::SysServerStarting`]
+pub fn dispatch_during_startup(
+    cstate: &ClientLocalState,
+    query: SQuery<'_>,
+) -> QueryResult<Response> {
+    if query.kind() != QueryKind::Simple {
+        return Err(QueryError::SysServerStarting);
+    }
+    let tokens = SecureLexer::new_with_segments(query.query(), query.params()).lex()?;
+    let mut state = State::new_inplace(&tokens);
+    if state.try_statement()? != KeywordStmt::Sysctl {
+        return Err(QueryError::SysServerStarting);
+    }
+    match SysctlCommand::parse_from_state_hardened(&mut state)? {
+        SysctlCommand::ReportStartup if cstate.is_root() => super::dcl::report_startup(),
+        SysctlCommand::ReportStartup => Err(QueryError::SysPermissionDenied),
+        _ => Err(QueryError::SysServerStarting),
     }
 }
 
@@ -73,24 +275,82 @@ fn _callgs_map<A: ASTNode<'static> + core::fmt::Debug, T>(
 }
 
 #[inline(always)]
-fn _callgs<A: ASTNode<'static> + core::fmt::Debug, T>(
+fn _callgcs<A: ASTNode<'static> + core::fmt::Debug, T>(
     g: &Global,
+    cstate: &ClientLocalState,
     state: &mut State<'static, InplaceData>,
-    f: impl FnOnce(&Global, A) -> Result<T, QueryError>,
+    f: impl FnOnce(&Global, &ClientLocalState, A) -> Result<T, QueryError>,
 ) -> QueryResult<T> {
-    let cs = ASTNode::parse_from_state_hardened(state)?;
-    f(&g, cs)
+    let a = ASTNode::parse_from_state_hardened(state)?;
+    f(&g, cstate, a)
+}
+
+/// The entity that a query will act on: a model (for DML) or a space (for model DDL)
+type AccessScope = (&'static str, Option<&'static str>);
+
+fn model_scope(e: EntityIDRef<'static>) -> AccessScope {
+    (e.space(), Some(e.entity()))
+}
+
+fn space_scope(e: EntityIDRef<'static>) -> AccessScope {
+    (e.space(), None)
+}
+
+/// Verify that the current user can access the given scope
+///
+/// This is the one place where per-user grants are checked, so every statement that touches a model must be run
+/// through here (including the models that a statement reads from besides its own, such as lookups)
+pub(super) fn check_access(
+    g: &impl GlobalInstanceLike,
+    cstate: &ClientLocalState,
+    (space, model): (&str, Option<&str>),
+) -> QueryResult<()> {
+    if cstate.is_root() {
+        return Ok(());
+    }
+    g.sys_store()
+        .system_store()
+        .auth_data()
+        .read()
+        .verify_user_access(cstate.username(), space, model)
 }
 
 #[inline(always)]
-fn _callgcs<A: ASTNode<'static> + core::fmt::Debug, T>(
+fn _callgcs_acl<A: ASTNode<'static> + core::fmt::Debug, T>(
     g: &Global,
     cstate: &ClientLocalState,
     state: &mut State<'static, InplaceData>,
-    f: impl FnOnce(&Global, &ClientLocalState, A) -> Result<T, QueryError>,
+    scope: impl FnOnce(&A) -> AccessScope,
+    f: impl FnOnce(&Global, A) -> Result<T, QueryError>,
 ) -> QueryResult<T> {
     let a = ASTNode::parse_from_state_hardened(state)?;
-    f(&g, cstate, a)
+    check_access(g, cstate, scope(&a))?;
+    f(&g, a)
+}
+
+/// Same as [`_callgcs_acl`], except that statements on the system catalog are run by `catalog` instead (which decides
+/// what the client can see by itself)
+#[inline(always)]
+fn _callgcs_acl_or_catalog<A: ASTNode<'static> + core::fmt::Debug>(
+    g: &Global,
+    cstate: &ClientLocalState,
+    state: &mut State<'static, InplaceData>,
+    entity: impl FnOnce(&A) -> EntityIDRef<'static>,
+    catalog: impl FnOnce(&Global, &ClientLocalState, A) -> QueryResult<Response>,
+    f: impl FnOnce(&Global, A) -> QueryResult<Response>,
+) -> QueryResult<Response> {
+    let a = ASTNode::parse_from_state_hardened(state)?;
+    let entity = entity(&a);
+    if catalog::is_catalog(g, entity) {
+        return catalog(g, cstate, a);
+    }
+    check_access(g, cstate, model_scope(entity))?;
+    f(&g, a)
+}
+
+/// The catalog models are read-only
+fn catalog_read_only<A>(_: &Global, _: &ClientLocalState, _: A) -> QueryResult<Response> {
+    Err(QueryError::SysPermissionDenied)
 }
 
 #[inline(always)]
@@ -106,33 +366,49 @@ async fn run_blocking_stmt(
     cstate: &mut ClientLocalState,
     mut state: State<'_, InplaceData>,
     stmt: KeywordStmt,
+    running: Arc<RunningQuery>,
 ) -> Result<Response, QueryError> {
-    if !(cstate.is_root() | (stmt == KeywordStmt::Sysctl)) {
-        // all the actions here need root permission (but we do an exception for sysctl which allows status to be called by anyone)
+    let sysctl = stmt == KeywordStmt::Sysctl;
+    let model_ddl = matches!(
+        stmt,
+        KeywordStmt::Create | KeywordStmt::Alter | KeywordStmt::Drop
+    ) & state.cursor_rounded_eq(Token![model]);
+    if !(cstate.is_root() | sysctl | model_ddl) {
+        /*
+            all the actions here need root permission but we make two exceptions:
+            - sysctl: which allows status to be called by anyone (the rest are checked by the DCL executor)
+            - model DDL: which can be run by any user holding a grant on the whole space (checked once we know the space)
+        */
         return Err(QueryError::SysPermissionDenied);
     }
-    state.ensure_minimum_for_blocking_stmt()?;
+    if !sysctl {
+        // sysctl has single token commands (the DCL parser checks what it needs)
+        state.ensure_minimum_for_blocking_stmt()?;
+    }
     /*
         IMPORTANT: DDL queries will NOT pick up the currently set space. instead EVERY DDL query must manually fully specify the entity that
         they want to manipulate. this prevents a whole set of exciting errors like dropping a model with the same model name from another space
     */
     state.unset_space();
-    let (a, b) = (&state.current()[0], &state.current()[1]);
-    let sysctl = stmt == KeywordStmt::Sysctl;
-    let create = stmt == KeywordStmt::Create;
-    let alter = stmt == KeywordStmt::Alter;
-    let drop = stmt == KeywordStmt::Drop;
-    let last_id = b.is_ident();
-    let last_allow = Token![allow].eq(b);
-    let last_if = Token![if].eq(b);
-    let c_s = (create & Token![space].eq(a) & (last_id | last_if)) as u8 * 2;
-    let c_m = (create & Token![model].eq(a) & (last_id | last_if)) as u8 * 3;
-    let a_s = (alter & Token![space].eq(a) & last_id) as u8 * 4;
-    let a_m = (alter & Token![model].eq(a) & last_id) as u8 * 5;
-    let d_s = (drop & Token![space].eq(a) & (last_id | last_allow | last_if)) as u8 * 6;
-    let d_m = (drop & Token![model].eq(a) & (last_id | last_allow | last_if)) as u8 * 7;
-    let fc = sysctl as u8 | c_s | c_m | a_s | a_m | d_s | d_m;
-    state.cursor_ahead_if(!sysctl);
+    let fc = if sysctl {
+        1
+    } else {
+        let (a, b) = (&state.current()[0], &state.current()[1]);
+        let create = stmt == KeywordStmt::Create;
+        let alter = stmt == KeywordStmt::Alter;
+        let drop = stmt == KeywordStmt::Drop;
+        let last_id = b.is_ident();
+        let last_allow = Token![allow].eq(b);
+        let last_if = Token![if].eq(b);
+        let c_s = (create & Token![space].eq(a) & (last_id | last_if)) as u8 * 2;
+        let c_m = (create & Token![model].eq(a) & (last_id | last_if)) as u8 * 3;
+        let a_s = (alter & Token![space].eq(a) & last_id) as u8 * 4;
+        let a_m = (alter & Token![model].eq(a) & last_id) as u8 * 5;
+        let d_s = (drop & Token![space].eq(a) & (last_id | last_allow | last_if)) as u8 * 6;
+        let d_m = (drop & Token![model].eq(a) & (last_id | last_allow | last_if)) as u8 * 7;
+        state.cursor_ahead();
+        c_s | c_m | a_s | a_m | d_s | d_m
+    };
     static BLK_EXEC: [fn(
         Global,
         &ClientLocalState,
@@ -148,31 +424,55 @@ async fn run_blocking_stmt(
                 translate_ddl_result,
             )
         },
-        |g, _, t| {
-            _callgs_map(
+        |g, c, t| {
+            _callgcs_acl(
                 &g,
+                c,
                 t,
+                |m: &CreateModel| space_scope(m.model_name),
                 Model::transactional_exec_create,
-                translate_ddl_result,
             )
+            .map(translate_ddl_result)
         },
         |g, _, t| _callgs_map(&g, t, Space::transactional_exec_alter, |_| Response::Empty),
-        |g, _, t| _callgs_map(&g, t, Model::transactional_exec_alter, |_| Response::Empty),
+        |g, c, t| {
+            _callgcs_acl(
+                &g,
+                c,
+                t,
+                |m: &AlterModel| space_scope(m.model),
+                Model::transactional_exec_alter,
+            )
+            .map(|_| Response::Empty)
+        },
         |g, _, t| _callgs_map(&g, t, Space::transactional_exec_drop, translate_ddl_result),
-        |g, _, t| _callgs_map(&g, t, Model::transactional_exec_drop, translate_ddl_result),
+        |g, c, t| {
+            _callgcs_acl(
+                &g,
+                c,
+                t,
+                |m: &DropModel| space_scope(m.entity),
+                Model::transactional_exec_drop,
+            )
+            .map(translate_ddl_result)
+        },
     ];
-    let r = unsafe {
+    unsafe {
         // UNSAFE(@ohsayan): the only await is within this block
         let c_glob = global.clone();
         let static_cstate: &'static ClientLocalState = core::mem::transmute(cstate);
         let static_state: &'static mut State<'static, InplaceData> =
             core::mem::transmute(&mut state);
-        tokio::task::spawn_blocking(move || {
-            BLK_EXEC[fc as usize](c_glob, static_cstate, static_state)
-        })
-        .await
-    };
-    r.unwrap()
+        let correlation = static_cstate.correlation();
+        global
+            .blocking_pool()
+            .run(move || {
+                correlation.scope(|| {
+                    running.scope(|| BLK_EXEC[fc as usize](c_glob, static_cstate, static_state))
+                })
+            })
+            .await
+    }
 }
 
 fn blocking_exec_sysctl(
@@ -181,7 +481,7 @@ fn blocking_exec_sysctl(
     state: &mut State<'static, InplaceData>,
 ) -> QueryResult<Response> {
     let r = ASTNode::parse_from_state_hardened(state)?;
-    super::dcl::exec(g, cstate, r).map(|_| Response::Empty)
+    super::dcl::exec(g, cstate, r)
 }
 
 /*
@@ -201,7 +501,9 @@ fn cstate_use(
                 NB: just like SQL, we don't really care about what this is set to as it's basically a shorthand.
                 so we do a simple vanity check
             */
-            if !global.namespace().contains_space(new_space.as_str()) {
+            if !(global.namespace().contains_space(new_space.as_str())
+                | (new_space.as_str() == catalog::SYS_SPACE))
+            {
                 return Err(QueryError::QExecObjectNotFound);
             }
             cstate.set_cs(new_space.boxed_str());
@@ -209,14 +511,14 @@ fn cstate_use(
         Use::RefreshCurrent => match cstate.get_cs() {
             None => return Ok(Response::Null),
             Some(space) => {
-                if !global.namespace().contains_space(space) {
+                if !(global.namespace().contains_space(space) | (space == catalog::SYS_SPACE)) {
                     cstate.unset_cs();
                     return Err(QueryError::QExecObjectNotFound);
                 }
                 return Ok(Response::Serialized {
                     ty: ResponseType::String,
                     size: space.len(),
-                    data: space.to_owned().into_bytes(),
+                    data: space.to_owned().into_bytes().into(),
                 });
             }
         },
@@ -235,21 +537,88 @@ fn run_nb(
         &Global,
         &mut ClientLocalState,
         &mut State<'static, InplaceData>,
-    ) -> QueryResult<Response>; 9] = [
+    ) -> QueryResult<Response>; 11] = [
         cstate_use, // use
         |g, c, s| _callgcs(g, c, s, ddl_misc::inspect),
         |_, _, _| Err(QueryError::QLUnknownStatement), // describe
-        |g, _, s| _callgs(g, s, dml::insert_resp),
-        |g, _, s| _callgs(g, s, dml::select_resp),
-        |g, _, s| _callgs(g, s, dml::update_resp),
-        |g, _, s| _callgs(g, s, dml::delete_resp),
+        |g, c, s| {
+            _callgcs_acl_or_catalog(
+                g,
+                c,
+                s,
+                |i: &InsertStatement| i.entity(),
+                catalog_read_only,
+                dml::insert_resp,
+            )
+        },
+        |g, c, s| {
+            _callgcs_acl_or_catalog(
+                g,
+                c,
+                s,
+                |s: &SelectStatement| s.entity(),
+                catalog::select_resp,
+                |g, s| dml::select_resp(g, c, s),
+            )
+        },
+        |g, c, s| {
+            _callgcs_acl_or_catalog(
+                g,
+                c,
+                s,
+                |u: &UpdateStatement| u.entity(),
+                catalog_read_only,
+                dml::update_resp,
+            )
+        },
+        |g, c, s| {
+            _callgcs_acl_or_catalog(
+                g,
+                c,
+                s,
+                |d: &DeleteStatement| d.entity(),
+                catalog_read_only,
+                dml::delete_resp,
+            )
+        },
         |_, _, _| Err(QueryError::QLUnknownStatement), // exists
-        |g, _, s| _callgs(g, s, dml::select_all_resp),
+        |g, c, s| {
+            _callgcs_acl(
+                g,
+                c,
+                s,
+                |e: &ExplainStatement| model_scope(e.entity()),
+                |g, e| dml::explain_resp(g, c, e),
+            )
+        },
+        |g, c, s| {
+            _callgcs_acl_or_catalog(
+                g,
+                c,
+                s,
+                |s: &SelectAllStatement| s.entity,
+                catalog::select_all_resp,
+                dml::select_all_resp,
+            )
+        },
+        |g, c, s| {
+            _callgcs_acl(
+                g,
+                c,
+                s,
+                |a: &AggregateStatement| model_scope(a.entity()),
+                dml::aggregate_resp,
+            )
+        },
     ];
     {
         let n_offset_adjust = (stmt == KeywordStmt::Select) & state.cursor_rounded_eq(Token![all]);
         state.cursor_ahead_if(n_offset_adjust);
-        let corrected_offset = (n_offset_adjust as u8 * 8) | (stmt_c * (!n_offset_adjust as u8));
+        let aggregate =
+            (stmt == KeywordStmt::Select) & AggregateStatement::is_aggregate(state.current());
+        let corrected_offset = (n_offset_adjust as u8 * 9)
+            | (aggregate as u8 * 10)
+            | (stmt_c * (!(n_offset_adjust | aggregate) as u8));
         let mut state = unsafe {
             // UNSAFE(@ohsayan): this is a lifetime issue with the token handle
             core::mem::transmute(state)
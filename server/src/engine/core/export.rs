@@ -0,0 +1,309 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Export the rows of a model into a file (`sysctl export model ...`)
+//!
+//! Rows are read through a snapshot, so an export doesn't block writers. DDL is blocked while the rows are encoded (in
+//! memory), but not while they're written out, so a slow disk never holds it up. Exports only go into the configured
+//! export directory, and never overwrite an existing file. Both formats encode cells the same way:
+//! - null, booleans and numbers are written as-is (except non-finite floats which are written as null in JSON)
+//! - binary cells are written as a hex string
+//! - points are written the way they're inserted, as a `lat,lon` string
+//! - lists and dicts are written as JSON arrays and objects (in CSV, as a quoted cell holding the JSON text)
+
+use {
+    crate::engine::{
+        core::{model::Model, EntityIDRef},
+        data::{
+            cell::{Datacell, VirtualDatacell},
+            tag::TagClass,
+        },
+        error::{ErrorKind, QueryError, QueryResult},
+        fractal::GlobalInstanceLike,
+        idx::{STIndex, STIndexSeq},
+        ql::dcl::DataFormat,
+        storage::v1::{RawFSInterface, RawFileInterfaceWrite, RawFileInterfaceWriteExt},
+        sync,
+    },
+    std::io,
+};
+
+/// Export every row of the model into a new file called `file` in the export directory, returning the number of rows
+/// that were written
+///
+/// DDL on all models is blocked while the rows are encoded. If the export fails, the (partial) file is removed
+pub fn export_model<G: GlobalInstanceLike>(
+    global: &G,
+    entity: EntityIDRef,
+    file: &str,
+    format: DataFormat,
+) -> QueryResult<u64> {
+    let path = export_path(global, file)?;
+    let (rows, data) = global
+        .namespace()
+        .with_model(entity, |mdl| encode_rows(mdl, format))?;
+    let f = match G::FileSystem::fs_fcreate_rw(&path) {
+        Ok(f) => f,
+        Err(e) => {
+            return Err(match e.kind() {
+                ErrorKind::IoError(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    QueryError::QExecDdlObjectAlreadyExists
+                }
+                _ => e.into(),
+            })
+        }
+    };
+    match write_file::<G::FileSystem>(f, &data) {
+        Ok(()) => Ok(rows),
+        Err(e) => {
+            let _ = G::FileSystem::fs_remove_file(&path);
+            Err(e)
+        }
+    }
+}
+
+/// Returns the path of the file called `file` in the export directory. Only a plain file name is accepted, so that
+/// nothing outside the export directory can be written to
+fn export_path(global: &impl GlobalInstanceLike, file: &str) -> QueryResult<String> {
+    let Some(dir) = global.sys_store().export_dir() else {
+        // exports are disabled
+        return Err(QueryError::SysPermissionDenied);
+    };
+    if file.is_empty() | (file == ".") | (file == "..") | file.contains(['/', '\\', '\0']) {
+        return Err(QueryError::SysPermissionDenied);
+    }
+    Ok(format!("{dir}/{file}"))
+}
+
+fn write_file<Fs: RawFSInterface>(mut f: Fs::File, data: &[u8]) -> QueryResult<()> {
+    f.fw_write_all(data)?;
+    f.fwext_fsync_all()?;
+    Ok(())
+}
+
+/// Encode every row of the model, returning the number of rows and the encoded data
+fn encode_rows(mdl: &Model, format: DataFormat) -> QueryResult<(u64, Vec<u8>)> {
+    let mut buf = Vec::new();
+    match format {
+        DataFormat::Json => buf.push(b'['),
+        DataFormat::Csv => {
+            let mut fields = mdl.fields().stseq_ord_key().peekable();
+            while let Some(field) = fields.next() {
+                csv_escape(&mut buf, field.as_bytes());
+                if fields.peek().is_some() {
+                    buf.push(b',');
+                }
+            }
+        }
+    }
+    let g = sync::atm::cpin();
+    let mut rows = 0u64;
+    mdl.pin_snapshot().scan(&g, |pk, data| {
+        let pk = VirtualDatacell::new_pk(pk, mdl.p_tag());
+        let cells = mdl.fields().stseq_ord_key().map(|field| {
+            let cell = if field.as_str() == mdl.p_key() {
+                &*pk
+            } else {
+                data.st_get(field).unwrap()
+            };
+            (field, cell)
+        });
+        match format {
+            DataFormat::Json => {
+                buf.extend(if rows == 0 { &b"\n{"[..] } else { b",\n{" });
+                let mut cells = cells.peekable();
+                while let Some((field, cell)) = cells.next() {
+                    json_escape(&mut buf, field.as_bytes());
+                    buf.push(b':');
+                    json_encode(&mut buf, cell);
+                    if cells.peek().is_some() {
+                        buf.push(b',');
+                    }
+                }
+                buf.push(b'}');
+            }
+            DataFormat::Csv => {
+                buf.push(b'\n');
+                let mut cells = cells.peekable();
+                while let Some((_, cell)) = cells.next() {
+                    csv_encode(&mut buf, cell);
+                    if cells.peek().is_some() {
+                        buf.push(b',');
+                    }
+                }
+            }
+        }
+        rows += 1;
+        QueryResult::Ok(())
+    })?;
+    match format {
+        DataFormat::Json => buf.extend(b"\n]\n"),
+        DataFormat::Csv => buf.push(b'\n'),
+    }
+    Ok((rows, buf))
+}
+
+fn json_encode(buf: &mut Vec<u8>, cell: &Datacell) {
+    if cell.is_null() {
+        return buf.extend(b"null");
+    }
+    unsafe {
+        // UNSAFE(@ohsayan): +tagck
+        match cell.kind() {
+            TagClass::Float if !cell.read_float().is_finite() => buf.extend(b"null"),
+            TagClass::Bin => {
+                buf.push(b'"');
+                hex_encode(buf, cell.read_bin());
+                buf.push(b'"');
+            }
+            TagClass::Str => json_escape(buf, cell.read_bin()),
+            // most JSON parsers can't read numbers this big exactly, so they are strings (like binary)
+            TagClass::BigInt => {
+                buf.push(b'"');
+                scalar_encode(buf, cell);
+                buf.push(b'"');
+            }
+            // points are written the way they're inserted: `"lat,lon"`
+            TagClass::Geo => {
+                let (lat, lon) = cell.read_geopoint();
+                buf.extend(format!("\"{lat},{lon}\"").as_bytes());
+            }
+            TagClass::List => {
+                buf.push(b'[');
+                let list = cell.read_list().read();
+                let mut items = list.iter().peekable();
+                while let Some(item) = items.next() {
+                    json_encode(buf, item);
+                    if items.peek().is_some() {
+                        buf.push(b',');
+                    }
+                }
+                buf.push(b']');
+            }
+            TagClass::Dict => {
+                buf.push(b'{');
+                let mut entries = cell.read_dict().iter().peekable();
+                while let Some((key, value)) = entries.next() {
+                    json_escape(buf, key.as_bytes());
+                    buf.push(b':');
+                    json_encode(buf, value);
+                    if entries.peek().is_some() {
+                        buf.push(b',');
+                    }
+                }
+                buf.push(b'}');
+            }
+            _ => scalar_encode(buf, cell),
+        }
+    }
+}
+
+fn csv_encode(buf: &mut Vec<u8>, cell: &Datacell) {
+    if cell.is_null() {
+        return;
+    }
+    unsafe {
+        // UNSAFE(@ohsayan): +tagck
+        match cell.kind() {
+            TagClass::Bin => hex_encode(buf, cell.read_bin()),
+            TagClass::Str => csv_escape(buf, cell.read_bin()),
+            TagClass::Geo => {
+                let (lat, lon) = cell.read_geopoint();
+                csv_escape(buf, format!("{lat},{lon}").as_bytes());
+            }
+            TagClass::List | TagClass::Dict => {
+                let mut json = Vec::new();
+                json_encode(&mut json, cell);
+                csv_escape(buf, &json);
+            }
+            _ => scalar_encode(buf, cell),
+        }
+    }
+}
+
+/// Encode a boolean or a number
+fn scalar_encode(buf: &mut Vec<u8>, cell: &Datacell) {
+    unsafe {
+        // UNSAFE(@ohsayan): +tagck
+        match cell.kind() {
+            TagClass::Bool => buf.extend(if cell.read_bool() {
+                &b"true"[..]
+            } else {
+                b"false"
+            }),
+            TagClass::UnsignedInt | TagClass::Timestamp => {
+                buf.extend(cell.read_uint().to_string().as_bytes())
+            }
+            TagClass::SignedInt => buf.extend(cell.read_sint().to_string().as_bytes()),
+            TagClass::BigInt => buf.extend(cell.read_sint128().to_string().as_bytes()),
+            TagClass::Float => buf.extend(cell.read_float().to_string().as_bytes()),
+            TagClass::Bin | TagClass::Str | TagClass::List | TagClass::Geo | TagClass::Dict => {
+                unreachable!()
+            }
+        }
+    }
+}
+
+fn hex_encode(buf: &mut Vec<u8>, bytes: &[u8]) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    for byte in bytes {
+        buf.push(HEX[(byte >> 4) as usize]);
+        buf.push(HEX[(byte & 0x0F) as usize]);
+    }
+}
+
+/// Write the (UTF-8) string as a quoted JSON string
+fn json_escape(buf: &mut Vec<u8>, s: &[u8]) {
+    buf.push(b'"');
+    for &byte in s {
+        match byte {
+            b'"' => buf.extend(b"\\\""),
+            b'\\' => buf.extend(b"\\\\"),
+            b'\n' => buf.extend(b"\\n"),
+            b'\r' => buf.extend(b"\\r"),
+            b'\t' => buf.extend(b"\\t"),
+            0x00..=0x1F => buf.extend(format!("\\u{byte:04x}").as_bytes()),
+            _ => buf.push(byte),
+        }
+    }
+    buf.push(b'"');
+}
+
+/// Write the string as a CSV field, quoting it only if it has to be (RFC 4180) or if it's empty (so that it isn't
+/// read back as a null)
+fn csv_escape(buf: &mut Vec<u8>, s: &[u8]) {
+    if !s.is_empty() & !s.iter().any(|b| matches!(b, b',' | b'"' | b'\n' | b'\r')) {
+        return buf.extend(s);
+    }
+    buf.push(b'"');
+    for &byte in s {
+        if byte == b'"' {
+            buf.push(b'"');
+        }
+        buf.push(byte);
+    }
+    buf.push(b'"');
+}
@@ -0,0 +1,607 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Load rows into a model from a file (`sysctl import model ...`)
+//!
+//! The file is read a chunk at a time and its rows are validated against the model's schema just like an insert's.
+//! Valid rows are inserted in large batches, while the ones that fail (bad syntax, a validation error or a duplicate
+//! primary key) are rejected and reported by their line number, without stopping the import. Cells are read the way
+//! that an export writes them:
+//! - JSON: one object per line. A JSON array, like the one written by an export, is accepted too as long as it has
+//!   one object per line. Binary cells are hex strings
+//! - CSV: a header with the field names followed by one record per row. An unquoted, empty cell is null if the
+//!   field is nullable. Binary cells are hex strings, lists are JSON arrays and dicts are JSON objects
+
+use {
+    crate::engine::{
+        core::{
+            self,
+            dml::{self, QueryExecMeta},
+            index::{DcFieldIndex, PrimaryIndexKey},
+            model::{Field, Layer, Model},
+            EntityIDRef,
+        },
+        data::{
+            cell::Datacell,
+            dict::DictCell,
+            tag::{DataTag, TagClass},
+        },
+        error::{QueryError, QueryResult},
+        fractal::GlobalInstanceLike,
+        idx::STIndex,
+        ql::{dcl::DataFormat, dml::ins::InsertData, lex::Ident},
+        storage::v1::{RawFSInterface, RawFileInterfaceExt, RawFileInterfaceRead},
+    },
+    std::{
+        collections::{hash_map::Entry, HashMap},
+        str,
+    },
+};
+
+/// The number of rows that are inserted together (sharing a single delta version)
+pub const IMPORT_BATCH_SIZE: usize = 4096;
+/// The number of bytes that are read from the file at a time
+const IMPORT_READ_CHUNK: usize = 64 * 1024;
+/// Only the first rejected rows are reported back (all of them are counted)
+pub const IMPORT_MAX_REPORTED_ERRORS: usize = 1024;
+/// Nested JSON values deeper than this are rejected
+const JSON_MAX_DEPTH: usize = 64;
+
+#[derive(Debug, PartialEq, Default)]
+pub struct ImportReport {
+    imported: u64,
+    rejected: u64,
+    errors: Vec<(u64, QueryError)>,
+}
+
+impl ImportReport {
+    fn reject(&mut self, line: u64, e: QueryError) {
+        self.rejected += 1;
+        if self.errors.len() < IMPORT_MAX_REPORTED_ERRORS {
+            self.errors.push((line, e));
+        }
+    }
+    /// Returns the number of rows that were inserted
+    pub fn imported(&self) -> u64 {
+        self.imported
+    }
+    /// Returns the number of rows that were rejected
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+    /// Returns the line number and error of (at most [`IMPORT_MAX_REPORTED_ERRORS`]) rejected rows, in order
+    pub fn errors(&self) -> &[(u64, QueryError)] {
+        &self.errors
+    }
+}
+
+/// Load the rows in the file at `path` into the model
+///
+/// The import is not atomic: rows are visible as soon as their batch is in, and if the import fails midway (for
+/// example because of an I/O error) the batches that made it in are kept
+pub fn import_model<G: GlobalInstanceLike>(
+    global: &G,
+    entity: EntityIDRef,
+    path: &str,
+    format: DataFormat,
+) -> QueryResult<ImportReport> {
+    let f = G::FileSystem::fs_fopen_rw(path)?;
+    let mut records = RecordReader::new(f, format == DataFormat::Csv)?;
+    let mut report = ImportReport::default();
+    let columns = match format {
+        DataFormat::Json => vec![],
+        DataFormat::Csv => {
+            let Some((_, header)) = records.next()? else {
+                return Ok(report);
+            };
+            let columns = csv_split(header)
+                .ok_or(QueryError::QLInvalidSyntax)?
+                .into_iter()
+                .map(|(column, _)| column.into_boxed_str())
+                .collect::<Vec<_>>();
+            global.namespace().with_model(entity, |mdl| {
+                for (i, column) in columns.iter().enumerate() {
+                    if !mdl.fields().st_contains(column.as_ref()) {
+                        return Err(QueryError::QExecUnknownField);
+                    }
+                    if columns[..i].contains(column) {
+                        return Err(QueryError::QLInvalidSyntax);
+                    }
+                }
+                Ok(())
+            })?;
+            columns
+        }
+    };
+    let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+    let mut done = false;
+    while !done {
+        core::with_model_for_data_update(global, entity, |mdl| {
+            let reserve = dml::reserve_keys(global, entity, mdl);
+            while batch.len() < IMPORT_BATCH_SIZE {
+                let Some((line, record)) = records.next()? else {
+                    done = true;
+                    break;
+                };
+                let row = match format {
+                    DataFormat::Json => json_row(mdl, record, &reserve),
+                    DataFormat::Csv => csv_row(mdl, &columns, record, &reserve),
+                };
+                match row {
+                    Ok(Some((pk, data))) => batch.push((line, pk, data)),
+                    Ok(None) => {}
+                    Err(e) => report.reject(line, e),
+                }
+            }
+            let count = batch.len() as u64;
+            let mut duplicates = 0;
+            let r = dml::insert_batch(mdl, batch.drain(..), |line| {
+                duplicates += 1;
+                report.reject(line, QueryError::QExecDmlDuplicate)
+            });
+            report.imported += count - duplicates;
+            // duplicates are only found after the batch's other rows were rejected
+            report.errors.sort_by_key(|(line, _)| *line);
+            Ok::<QueryExecMeta, QueryError>(r)
+        })?;
+    }
+    Ok(report)
+}
+
+type PreparedRow = (PrimaryIndexKey, DcFieldIndex);
+
+/// Prepare the row on a line of a JSON import (`None` if the line doesn't hold a row)
+fn json_row(
+    mdl: &Model,
+    record: &[u8],
+    reserve: &dyn Fn(u64) -> QueryResult<()>,
+) -> QueryResult<Option<PreparedRow>> {
+    let record = str::from_utf8(record)
+        .map_err(|_| QueryError::QLInvalidSyntax)?
+        .trim();
+    // skip the brackets and commas of a JSON array
+    let record = record.strip_suffix(',').unwrap_or(record).trim_end();
+    if matches!(record, "" | "[" | "]") {
+        return Ok(None);
+    }
+    let Some(Json::Object(object)) = JsonParser::parse(record) else {
+        return Err(QueryError::QLInvalidSyntax);
+    };
+    let mut map = HashMap::with_capacity(object.len());
+    for (key, value) in object.iter() {
+        let field = mdl.fields().st_get(key.as_str());
+        if field.is_some_and(|field| field.compute().is_some()) {
+            // computed fields are computed again when the row is inserted
+            continue;
+        }
+        let cell = json_cell(field.map(Field::layers).unwrap_or(&[]), value)?;
+        match map.entry(Ident::new_str(key)) {
+            Entry::Vacant(ve) => {
+                ve.insert(cell);
+            }
+            Entry::Occupied(_) => return Err(QueryError::QLInvalidSyntax),
+        }
+    }
+    dml::prepare_insert(mdl, InsertData::Map(map), Some(reserve)).map(Some)
+}
+
+/// Prepare the row in a record of a CSV import (`None` if the record is empty)
+fn csv_row(
+    mdl: &Model,
+    columns: &[Box<str>],
+    record: &[u8],
+    reserve: &dyn Fn(u64) -> QueryResult<()>,
+) -> QueryResult<Option<PreparedRow>> {
+    if record.is_empty() {
+        return Ok(None);
+    }
+    let cells = csv_split(record).ok_or(QueryError::QLInvalidSyntax)?;
+    if cells.len() != columns.len() {
+        return Err(QueryError::QLInvalidSyntax);
+    }
+    let mut map = HashMap::with_capacity(columns.len());
+    for (column, (raw, quoted)) in columns.iter().zip(cells) {
+        let field = mdl
+            .fields()
+            .st_get(column.as_ref())
+            .ok_or(QueryError::QExecUnknownField)?;
+        if field.compute().is_some() {
+            continue;
+        }
+        map.insert(Ident::new_str(column), csv_cell(field, raw, quoted)?);
+    }
+    dml::prepare_insert(mdl, InsertData::Map(map), Some(reserve)).map(Some)
+}
+
+/// Convert a CSV cell into a cell for the field (it's only validated later, when the row is prepared)
+fn csv_cell(field: &Field, raw: String, quoted: bool) -> QueryResult<Datacell> {
+    if raw.is_empty() & !quoted & field.is_nullable() {
+        return Ok(Datacell::null());
+    }
+    fn bad<E>(_: E) -> QueryError {
+        QueryError::QExecDmlValidationError
+    }
+    Ok(match field.layers()[0].tag().tag_class() {
+        TagClass::Bool => match raw.as_str() {
+            "true" => Datacell::new_bool(true),
+            "false" => Datacell::new_bool(false),
+            _ => return Err(QueryError::QExecDmlValidationError),
+        },
+        // timestamps are imported as the micros since the epoch
+        TagClass::UnsignedInt | TagClass::Timestamp => {
+            Datacell::new_uint_default(raw.parse().map_err(bad)?)
+        }
+        TagClass::SignedInt => Datacell::new_sint_default(raw.parse().map_err(bad)?),
+        TagClass::Float => Datacell::new_float_default(raw.parse().map_err(bad)?),
+        TagClass::Bin => Datacell::new_bin(hex_decode(&raw)?.into_boxed_slice()),
+        // 128-bit integers and points are parsed when the row is validated
+        TagClass::Str | TagClass::BigInt | TagClass::Geo => Datacell::new_str(raw.into_boxed_str()),
+        TagClass::List => match JsonParser::parse(&raw) {
+            Some(list @ Json::List(_)) => json_cell(field.layers(), &list)?,
+            _ => return Err(QueryError::QExecDmlValidationError),
+        },
+        TagClass::Dict => match JsonParser::parse(&raw) {
+            Some(dict @ Json::Object(_)) => json_cell(field.layers(), &dict)?,
+            _ => return Err(QueryError::QExecDmlValidationError),
+        },
+    })
+}
+
+/// Convert a JSON value into a cell for the given layers (it's only validated later, when the row is prepared)
+fn json_cell(layers: &[Layer], value: &Json) -> QueryResult<Datacell> {
+    let class = layers.first().map(|layer| layer.tag().tag_class());
+    Ok(match value {
+        Json::Null => Datacell::null(),
+        Json::Bool(b) => Datacell::new_bool(*b),
+        Json::UInt(u) if class == Some(TagClass::Float) => Datacell::new_float_default(*u as f64),
+        Json::UInt(u) if class == Some(TagClass::SignedInt) => Datacell::new_sint_default(
+            i64::try_from(*u).map_err(|_| QueryError::QExecDmlValidationError)?,
+        ),
+        Json::UInt(u) => Datacell::new_uint_default(*u),
+        Json::SInt(s) if class == Some(TagClass::Float) => Datacell::new_float_default(*s as f64),
+        Json::SInt(s) => Datacell::new_sint_default(*s),
+        Json::Float(f) => Datacell::new_float_default(*f),
+        Json::Str(s) if class == Some(TagClass::Bin) => {
+            Datacell::new_bin(hex_decode(s)?.into_boxed_slice())
+        }
+        Json::Str(s) => Datacell::new_str(s.as_str().into()),
+        Json::List(items) => {
+            let inner = layers.get(1..).unwrap_or(&[]);
+            Datacell::new_list(
+                items
+                    .iter()
+                    .map(|item| json_cell(inner, item))
+                    .collect::<QueryResult<_>>()?,
+            )
+        }
+        // the values in a dict have no layers, so any object in them is a dict too
+        Json::Object(entries) if matches!(class, Some(TagClass::Dict) | None) => {
+            let mut dict = DictCell::new();
+            for (key, value) in entries {
+                if dict
+                    .insert(key.as_str().into(), json_cell(&[], value)?)
+                    .is_some()
+                {
+                    return Err(QueryError::QExecDmlValidationError);
+                }
+            }
+            Datacell::new_dict(dict)
+        }
+        Json::Object(_) => return Err(QueryError::QExecDmlValidationError),
+    })
+}
+
+fn hex_decode(s: &str) -> QueryResult<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return Err(QueryError::QExecDmlValidationError);
+    }
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or(QueryError::QExecDmlValidationError)
+        })
+        .collect()
+}
+
+/*
+    reader
+*/
+
+/// Reads records (lines, or for CSV, lines that aren't inside quotes) from a file a chunk at a time
+struct RecordReader<F> {
+    f: F,
+    remaining: u64,
+    buf: Vec<u8>,
+    start: usize,
+    line: u64,
+    csv: bool,
+}
+
+impl<F: RawFileInterfaceRead + RawFileInterfaceExt> RecordReader<F> {
+    fn new(f: F, csv: bool) -> QueryResult<Self> {
+        let remaining = f.fext_file_length()?;
+        Ok(Self {
+            f,
+            remaining,
+            buf: vec![],
+            start: 0,
+            line: 1,
+            csv,
+        })
+    }
+    /// Returns the next record and the line that it starts on
+    fn next(&mut self) -> QueryResult<Option<(u64, &[u8])>> {
+        loop {
+            let mut quoted = false;
+            let mut newlines = 0;
+            let end = self.buf[self.start..].iter().position(|&b| {
+                quoted ^= self.csv & (b == b'"');
+                newlines += (b == b'\n') as u64;
+                (b == b'\n') & !quoted
+            });
+            let (start, end) = match end {
+                Some(end) => (self.start, self.start + end),
+                None if self.remaining != 0 => {
+                    // pull in the next chunk
+                    self.buf.drain(..self.start);
+                    self.start = 0;
+                    let chunk = self.remaining.min(IMPORT_READ_CHUNK as u64) as usize;
+                    let len = self.buf.len();
+                    self.buf.resize(len + chunk, 0);
+                    self.f.fr_read_exact(&mut self.buf[len..])?;
+                    self.remaining -= chunk as u64;
+                    continue;
+                }
+                None if self.start == self.buf.len() => return Ok(None),
+                // the last record doesn't end with a newline
+                None => (self.start, self.buf.len()),
+            };
+            let line = self.line;
+            self.line += newlines;
+            self.start = (end + 1).min(self.buf.len());
+            let record = &self.buf[start..end];
+            return Ok(Some((line, record.strip_suffix(b"\r").unwrap_or(record))));
+        }
+    }
+}
+
+/// Split a CSV record into its cells, returning each cell along with whether it was quoted
+fn csv_split(record: &[u8]) -> Option<Vec<(String, bool)>> {
+    let record = str::from_utf8(record).ok()?;
+    let mut cells = vec![];
+    let mut chars = record.chars().peekable();
+    loop {
+        let mut cell = String::new();
+        let quoted = chars.peek() == Some(&'"');
+        if quoted {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        cell.push('"');
+                    }
+                    '"' => break,
+                    c => cell.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                if c == '"' {
+                    return None;
+                }
+                cell.push(c);
+            }
+        }
+        cells.push((cell, quoted));
+        match chars.next() {
+            Some(',') => {}
+            None => return Some(cells),
+            Some(_) => return None,
+        }
+    }
+}
+
+/*
+    json
+*/
+
+#[derive(Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    UInt(u64),
+    SInt(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+struct JsonParser<'a> {
+    src: &'a [u8],
+    i: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn parse(src: &'a str) -> Option<Json> {
+        let mut me = Self {
+            src: src.as_bytes(),
+            i: 0,
+        };
+        let value = me.value(0)?;
+        me.skip_ws();
+        (me.i == me.src.len()).then_some(value)
+    }
+    fn skip_ws(&mut self) {
+        while self.i < self.src.len() && self.src[self.i].is_ascii_whitespace() {
+            self.i += 1;
+        }
+    }
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.i).copied()
+    }
+    fn eat(&mut self, b: u8) -> bool {
+        self.skip_ws();
+        let okay = self.peek() == Some(b);
+        self.i += okay as usize;
+        okay
+    }
+    fn eat_word(&mut self, word: &[u8]) -> bool {
+        let okay = self.src[self.i..].starts_with(word);
+        self.i += okay as usize * word.len();
+        okay
+    }
+    fn value(&mut self, depth: usize) -> Option<Json> {
+        if depth > JSON_MAX_DEPTH {
+            return None;
+        }
+        self.skip_ws();
+        match self.peek()? {
+            b'n' if self.eat_word(b"null") => Some(Json::Null),
+            b't' if self.eat_word(b"true") => Some(Json::Bool(true)),
+            b'f' if self.eat_word(b"false") => Some(Json::Bool(false)),
+            b'"' => self.string().map(Json::Str),
+            b'[' => {
+                self.i += 1;
+                let mut items = vec![];
+                if self.eat(b']') {
+                    return Some(Json::List(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    if self.eat(b']') {
+                        return Some(Json::List(items));
+                    }
+                    if !self.eat(b',') {
+                        return None;
+                    }
+                }
+            }
+            b'{' => {
+                self.i += 1;
+                let mut entries = vec![];
+                if self.eat(b'}') {
+                    return Some(Json::Object(entries));
+                }
+                loop {
+                    self.skip_ws();
+                    let key = self.string()?;
+                    if !self.eat(b':') {
+                        return None;
+                    }
+                    entries.push((key, self.value(depth + 1)?));
+                    if self.eat(b'}') {
+                        return Some(Json::Object(entries));
+                    }
+                    if !self.eat(b',') {
+                        return None;
+                    }
+                }
+            }
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => None,
+        }
+    }
+    fn number(&mut self) -> Option<Json> {
+        let start = self.i;
+        let mut float = false;
+        while let Some(b) = self.peek() {
+            match b {
+                b'0'..=b'9' | b'-' | b'+' => {}
+                b'.' | b'e' | b'E' => float = true,
+                _ => break,
+            }
+            self.i += 1;
+        }
+        let number = str::from_utf8(&self.src[start..self.i]).ok()?;
+        if float {
+            number.parse().ok().map(Json::Float)
+        } else if number.starts_with('-') {
+            number.parse().ok().map(Json::SInt)
+        } else {
+            number.parse().ok().map(Json::UInt)
+        }
+    }
+    fn string(&mut self) -> Option<String> {
+        if self.peek()? != b'"' {
+            return None;
+        }
+        self.i += 1;
+        let mut s = vec![];
+        loop {
+            match self.peek()? {
+                b'"' => {
+                    self.i += 1;
+                    return String::from_utf8(s).ok();
+                }
+                b'\\' => {
+                    self.i += 1;
+                    let escaped = self.peek()?;
+                    self.i += 1;
+                    match escaped {
+                        b'"' | b'\\' | b'/' => s.push(escaped),
+                        b'b' => s.push(0x08),
+                        b'f' => s.push(0x0C),
+                        b'n' => s.push(b'\n'),
+                        b'r' => s.push(b'\r'),
+                        b't' => s.push(b'\t'),
+                        b'u' => {
+                            let mut c = self.hex4()? as u32;
+                            if (0xD800..0xDC00).contains(&c) {
+                                // a surrogate pair
+                                if !self.eat_word(b"\\u") {
+                                    return None;
+                                }
+                                let low = self.hex4()? as u32;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return None;
+                                }
+                                c = 0x10000 + ((c - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            let mut utf8 = [0; 4];
+                            s.extend(char::from_u32(c)?.encode_utf8(&mut utf8).as_bytes());
+                        }
+                        _ => return None,
+                    }
+                }
+                0x00..=0x1F => return None,
+                b => {
+                    self.i += 1;
+                    s.push(b);
+                }
+            }
+        }
+    }
+    fn hex4(&mut self) -> Option<u16> {
+        let hex = self.src.get(self.i..self.i + 4)?;
+        self.i += 4;
+        u16::from_str_radix(str::from_utf8(hex).ok()?, 16).ok()
+    }
+}
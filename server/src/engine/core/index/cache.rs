@@ -0,0 +1,375 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Read cache for hot rows
+//!
+//! A point read of an entire row (`select * from model where pk = ?`) has to look the row up in the primary index, lock
+//! it, apply any pending schema deltas and then encode every field. Every model has a cache for the encoded rows that
+//! are read the most, which is checked before the primary index. All models share a single memory budget, and a row is
+//! only cached if it fits in the budget (after the model's cold rows are evicted with the clock algorithm). The fractal
+//! manager also drops every row that wasn't read since its last maintenance run, so models that aren't being read any
+//! more give up their share of the budget.
+//!
+//! Every change to a row invalidates its entry. A read only fills the cache if no row in the same shard changed since
+//! the read started, so a read that raced with a write never caches the old version of the row.
+
+use {
+    super::key::PrimaryIndexKey,
+    crate::engine::{
+        data::{
+            lit::Lit,
+            tag::{DataTag, TagUnique},
+        },
+        idx::meta::hash::HasherNativeFx,
+    },
+    parking_lot::Mutex,
+    std::{
+        collections::HashMap,
+        hash::{Hash, Hasher},
+        sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Arc, OnceLock,
+        },
+    },
+};
+
+/// The number of shards that every model's cache is split into (to keep readers from contending on a single lock)
+const SHARDS: usize = 16;
+/// What a cached row costs apart from the encoded row and the key's data
+const ENTRY_OVERHEAD: usize = sizeof!(Slot) + sizeof!(u64, 2);
+
+#[derive(Debug)]
+/// The memory budget that is shared by the caches of all models, along with the cache metrics
+pub struct RowCacheBudget {
+    limit: usize,
+    used: AtomicUsize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl RowCacheBudget {
+    /// A budget of `limit` bytes (the cache is disabled if this is zero)
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            used: AtomicUsize::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+    #[cfg(test)]
+    /// A budget that doesn't allow anything to be cached
+    pub fn disabled() -> Self {
+        Self::new(0)
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.limit != 0
+    }
+    /// Returns the number of bytes that the caches can use
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+    /// Returns the number of bytes that are currently used by the caches
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+    /// Returns the number of reads that were served from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+    /// Returns the number of (cacheable) reads that had to go to the primary index
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+    /// Returns the number of rows that were evicted to make room for others, or because they went cold
+    pub fn evictions(&self) -> u64 {
+        self.evictions.load(Ordering::Relaxed)
+    }
+    fn try_reserve(&self, size: usize) -> bool {
+        self.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                let new = used + size;
+                (new <= self.limit).then_some(new)
+            })
+            .is_ok()
+    }
+    fn release(&self, size: usize) {
+        self.used.fetch_sub(size, Ordering::AcqRel);
+    }
+}
+
+/// Returned by [`RowCache::lookup`] on a miss, and needed to fill the cache with the row that was read instead
+pub struct RowCacheFill {
+    hash: u64,
+    generation: u64,
+}
+
+#[derive(Debug)]
+/// The read cache of a model, which maps primary keys to encoded rows
+pub struct RowCache {
+    budget: OnceLock<Arc<RowCacheBudget>>,
+    // our share of the budget
+    used: AtomicUsize,
+    shards: [Mutex<Shard>; SHARDS],
+}
+
+#[derive(Debug, Default)]
+struct Shard {
+    slots: Vec<Slot>,
+    index: HashMap<u64, usize, HasherNativeFx>,
+    hand: usize,
+    // bumped whenever a row in this shard changes
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct Slot {
+    hash: u64,
+    key: PrimaryIndexKey,
+    row: Box<[u8]>,
+    referenced: bool,
+}
+
+impl Slot {
+    fn size(&self) -> usize {
+        entry_size(&self.key, &self.row)
+    }
+}
+
+fn entry_size(key: &PrimaryIndexKey, row: &[u8]) -> usize {
+    let key_data = key
+        .bin()
+        .or_else(|| key.str().map(str::as_bytes))
+        .map_or(0, <[u8]>::len);
+    ENTRY_OVERHEAD + key_data + row.len()
+}
+
+impl RowCache {
+    pub fn new() -> Self {
+        Self {
+            budget: OnceLock::new(),
+            used: AtomicUsize::new(0),
+            shards: Default::default(),
+        }
+    }
+    /// Returns a copy of the cached row with the given key, or what's needed to fill the cache after the row has been
+    /// read from the primary index
+    pub fn lookup(&self, budget: &RowCacheBudget, key: &Lit) -> Result<Vec<u8>, RowCacheFill> {
+        let hash = hash_lit(key);
+        let mut shard = self.shard(hash).lock();
+        match shard.index.get(&hash).copied() {
+            Some(slot) if shard.slots[slot].key == *key => {
+                budget.hits.fetch_add(1, Ordering::Relaxed);
+                let slot = &mut shard.slots[slot];
+                slot.referenced = true;
+                Ok(slot.row.to_vec())
+            }
+            _ => {
+                budget.misses.fetch_add(1, Ordering::Relaxed);
+                Err(RowCacheFill {
+                    hash,
+                    generation: shard.generation,
+                })
+            }
+        }
+    }
+    /// Cache the (encoded) row that was read after a miss, if no row in its shard changed in the meantime and if it
+    /// fits in the budget
+    pub fn fill(
+        &self,
+        budget: &Arc<RowCacheBudget>,
+        fill: RowCacheFill,
+        key: &PrimaryIndexKey,
+        row: &[u8],
+    ) {
+        let budget = self.budget.get_or_init(|| budget.clone());
+        let mut shard = self.shard(fill.hash).lock();
+        if (shard.generation != fill.generation) | shard.index.contains_key(&fill.hash) {
+            return;
+        }
+        let size = entry_size(key, row);
+        while !budget.try_reserve(size) {
+            let Some(freed) = shard.evict_one() else {
+                // we can't make enough room in our own shard
+                return;
+            };
+            self.release(freed);
+            budget.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        self.used.fetch_add(size, Ordering::AcqRel);
+        let slot = shard.slots.len();
+        shard.slots.push(Slot {
+            hash: fill.hash,
+            key: key.clone(),
+            row: row.into(),
+            referenced: true,
+        });
+        shard.index.insert(fill.hash, slot);
+    }
+    /// Drop the cached copy of the row with the given key (if any). This must be called after the row was changed
+    pub fn invalidate(&self, key: &PrimaryIndexKey) {
+        let hash = hash_key(key);
+        let mut shard = self.shard(hash).lock();
+        shard.generation += 1;
+        if let Some(slot) = shard.index.get(&hash).copied() {
+            let slot = shard.remove(slot);
+            self.release(slot.size());
+        }
+    }
+    /// Drop every cached row (for example, because the model's schema changed)
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            let mut shard = shard.lock();
+            shard.generation += 1;
+            shard.index.clear();
+            shard.hand = 0;
+            let freed = shard.slots.drain(..).map(|slot| slot.size()).sum();
+            self.release(freed);
+        }
+    }
+    /// Drop every row that wasn't read since the last time this was called
+    pub fn evict_cold(&self) {
+        let Some(budget) = self.budget.get() else {
+            // nothing was ever cached
+            return;
+        };
+        for shard in &self.shards {
+            let mut shard = shard.lock();
+            let mut i = 0;
+            while i < shard.slots.len() {
+                if shard.slots[i].referenced {
+                    shard.slots[i].referenced = false;
+                    i += 1;
+                } else {
+                    let slot = shard.remove(i);
+                    self.release(slot.size());
+                    budget.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+    /// Returns the number of bytes that this cache takes up out of the shared budget
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+    #[cfg(test)]
+    /// Returns the number of rows that are cached
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().slots.len())
+            .sum()
+    }
+    fn shard(&self, hash: u64) -> &Mutex<Shard> {
+        &self.shards[hash as usize % SHARDS]
+    }
+    fn release(&self, size: usize) {
+        if let Some(budget) = self.budget.get() {
+            self.used.fetch_sub(size, Ordering::AcqRel);
+            budget.release(size)
+        }
+    }
+}
+
+impl Drop for RowCache {
+    fn drop(&mut self) {
+        // give back our share of the budget
+        self.clear()
+    }
+}
+
+impl Shard {
+    fn remove(&mut self, slot: usize) -> Slot {
+        let removed = self.slots.swap_remove(slot);
+        self.index.remove(&removed.hash);
+        if let Some(moved) = self.slots.get(slot) {
+            self.index.insert(moved.hash, slot);
+        }
+        if self.hand >= self.slots.len() {
+            self.hand = 0;
+        }
+        removed
+    }
+    /// Evict the first row that wasn't read since the clock hand last passed it, returning the size of the evicted
+    /// entry (or `None` if the shard is empty)
+    fn evict_one(&mut self) -> Option<usize> {
+        loop {
+            if self.slots.is_empty() {
+                return None;
+            }
+            let hand = self.hand;
+            if self.slots[hand].referenced {
+                self.slots[hand].referenced = false;
+                self.hand = (hand + 1) % self.slots.len();
+            } else {
+                return Some(self.remove(hand).size());
+            }
+        }
+    }
+}
+
+/*
+    the hash impls of literals and keys only look at the tag and the variable-length data, so every integer key would
+    land in the same slot. we hash the integer's value as well, in the same way for both
+*/
+
+fn hash_lit(key: &Lit) -> u64 {
+    let tag = key.kind().tag_unique();
+    let int = match tag {
+        TagUnique::UnsignedInt => unsafe {
+            // UNSAFE(@ohsayan): +tagck
+            key.uint()
+        },
+        TagUnique::SignedInt => unsafe {
+            // UNSAFE(@ohsayan): +tagck
+            key.sint() as u64
+        },
+        _ => 0,
+    };
+    hash_parts(tag, int, key.__vdata())
+}
+
+fn hash_key(key: &PrimaryIndexKey) -> u64 {
+    let int = key
+        .uint()
+        .or_else(|| key.sint().map(|int| int as u64))
+        .unwrap_or(0);
+    let data = key
+        .bin()
+        .or_else(|| key.str().map(str::as_bytes))
+        .unwrap_or(&[]);
+    hash_parts(key.tag(), int, data)
+}
+
+fn hash_parts(tag: TagUnique, int: u64, data: &[u8]) -> u64 {
+    let mut hasher = HasherNativeFx::default();
+    tag.hash(&mut hasher);
+    int.hash(&mut hasher);
+    data.hash(&mut hasher);
+    hasher.finish()
+}
@@ -37,6 +37,7 @@ use {
         mem::{self, DwordNN, DwordQN, SpecialPaddedWord, WordIO, ZERO_BLOCK},
     },
     core::{
+        cmp::Ordering,
         fmt,
         hash::{Hash, Hasher},
         mem::ManuallyDrop,
@@ -52,7 +53,12 @@ pub struct PrimaryIndexKey {
 impl Clone for PrimaryIndexKey {
     fn clone(&self) -> Self {
         match self.tag {
-            TagUnique::SignedInt | TagUnique::UnsignedInt => {
+            TagUnique::SignedInt | TagUnique::UnsignedInt => Self {
+                tag: self.tag,
+                data: unsafe { core::mem::transmute_copy(&self.data) },
+            },
+            TagUnique::Bin | TagUnique::Str => {
+                // we own the block, so the clone needs its own copy
                 let (qw, nw) = self.data.dwordqn_load_qw_nw();
                 unsafe {
                     let slice = slice::from_raw_parts(nw as *const u8, qw as _);
@@ -63,10 +69,6 @@ impl Clone for PrimaryIndexKey {
                     }
                 }
             }
-            TagUnique::Bin | TagUnique::Str => Self {
-                tag: self.tag,
-                data: unsafe { core::mem::transmute_copy(&self.data) },
-            },
             _ => unreachable!(),
         }
     }
@@ -251,16 +253,57 @@ impl<'a> PartialEq<Lit<'a>> for PrimaryIndexKey {
     }
 }
 
+impl PartialOrd for PrimaryIndexKey {
+    /// Keys are only ordered against keys of the same type; keys with different tags are incomparable
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.tag != other.tag {
+            return None;
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): +tagck
+            Some(match self.tag {
+                TagUnique::UnsignedInt => self.read_uint().cmp(&other.read_uint()),
+                TagUnique::SignedInt => self.read_sint().cmp(&other.read_sint()),
+                TagUnique::Bin | TagUnique::Str => self.virtual_block().cmp(other.virtual_block()),
+                TagUnique::Illegal => impossible!(),
+            })
+        }
+    }
+}
+
+impl<'a> PartialOrd<Lit<'a>> for PrimaryIndexKey {
+    fn partial_cmp(&self, key: &Lit<'a>) -> Option<Ordering> {
+        if self.tag != key.kind().tag_unique() {
+            return None;
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): +tagck
+            Some(match self.tag {
+                TagUnique::UnsignedInt => self.read_uint().cmp(&key.uint()),
+                TagUnique::SignedInt => self.read_sint().cmp(&key.sint()),
+                TagUnique::Bin | TagUnique::Str => self.virtual_block().cmp(key.__vdata()),
+                TagUnique::Illegal => impossible!(),
+            })
+        }
+    }
+}
+
 impl<'a> Comparable<Lit<'a>> for PrimaryIndexKey {
     fn cmp_eq(&self, key: &Lit<'a>) -> bool {
         <PrimaryIndexKey as PartialEq<Lit>>::eq(self, key)
     }
+    fn cmp_ord(&self, key: &Lit<'a>) -> Option<Ordering> {
+        <PrimaryIndexKey as PartialOrd<Lit>>::partial_cmp(self, key)
+    }
 }
 
 impl<'a> Comparable<PrimaryIndexKey> for Lit<'a> {
     fn cmp_eq(&self, key: &PrimaryIndexKey) -> bool {
         <PrimaryIndexKey as PartialEq<Lit>>::eq(key, self)
     }
+    fn cmp_ord(&self, key: &PrimaryIndexKey) -> Option<Ordering> {
+        <PrimaryIndexKey as PartialOrd<Lit>>::partial_cmp(key, self).map(Ordering::reverse)
+    }
 }
 
 impl fmt::Debug for PrimaryIndexKey {
@@ -344,6 +387,22 @@ fn check_pk_eq_hash() {
     }
 }
 
+#[test]
+fn check_pk_clone_outlives_original() {
+    let data = [
+        Datacell::from(100),
+        Datacell::from(-100),
+        Datacell::from("binary".as_bytes()),
+        Datacell::from("string"),
+    ];
+    for datum in data {
+        let pk = PrimaryIndexKey::try_from_dc(datum.clone()).unwrap();
+        let cloned = pk.clone();
+        drop(pk);
+        assert_eq!(cloned, PrimaryIndexKey::try_from_dc(datum).unwrap());
+    }
+}
+
 #[test]
 fn check_pk_lit_eq_hash() {
     let state = test_utils::randomstate();
@@ -379,3 +438,33 @@ fn check_pk_extremes() {
     assert_eq!(d2, Lit::new_uint(u64::MAX));
     assert_eq!(d1.uint().unwrap(), u64::MAX);
 }
+
+#[test]
+fn check_pk_ord() {
+    let pk = |dc: Datacell| PrimaryIndexKey::try_from_dc(dc).unwrap();
+    assert!(pk(Datacell::new_uint_default(1)) < pk(Datacell::new_uint_default(11)));
+    assert!(pk(Datacell::from(-100)) < pk(Datacell::from(-1)));
+    assert!(pk(Datacell::from("apple")) < pk(Datacell::from("banana")));
+    assert!(pk(Datacell::from("binary".as_bytes())) > pk(Datacell::from("bin".as_bytes())));
+    assert_eq!(
+        pk(Datacell::from("string")).partial_cmp(&pk(Datacell::from("string"))),
+        Some(Ordering::Equal)
+    );
+    // different types are incomparable
+    assert_eq!(
+        pk(Datacell::new_uint_default(1)).partial_cmp(&pk(Datacell::from("1"))),
+        None
+    );
+}
+
+#[test]
+fn check_pk_lit_ord() {
+    let pk = PrimaryIndexKey::try_from_dc(Datacell::new_uint_default(100)).unwrap();
+    assert!(pk > Lit::new_uint(99));
+    assert!(pk < Lit::new_uint(101));
+    assert!(pk >= Lit::new_uint(100));
+    assert_eq!(pk.partial_cmp(&Lit::new_sint(100)), None);
+    let pk = PrimaryIndexKey::try_from_dc(Datacell::from("sayan")).unwrap();
+    assert!(pk > Lit::new_str("ohsayan"));
+    assert!(pk < Lit::new_str("skytable"));
+}
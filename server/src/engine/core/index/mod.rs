@@ -24,32 +24,35 @@
  *
 */
 
+mod cache;
 mod key;
 mod row;
 
 use crate::engine::{
     data::lit::Lit,
-    idx::{IndexBaseSpec, IndexMTRaw, MTIndex},
+    idx::{IndexBaseSpec, IndexMTOrd, MTIndex},
     sync::atm::Guard,
 };
 
 pub use {
+    cache::{RowCache, RowCacheBudget},
     key::PrimaryIndexKey,
-    row::{DcFieldIndex, Row, RowData},
+    row::{DcFieldIndex, Row, RowData, ROW_INDEX_OVERHEAD},
 };
 
 pub type RowDataLck = parking_lot::RwLock<RowData>;
 
 #[derive(Debug)]
 pub struct PrimaryIndex {
-    data: IndexMTRaw<row::Row>,
+    /// ordered by key, so that range and prefix scans can seek instead of walking every row
+    data: IndexMTOrd<row::Row>,
     latch: IndexLatch,
 }
 
 impl PrimaryIndex {
     pub fn new_empty() -> Self {
         Self {
-            data: IndexMTRaw::idx_init(),
+            data: IndexMTOrd::idx_init(),
             latch: IndexLatch::new(),
         }
     }
@@ -62,12 +65,17 @@ impl PrimaryIndex {
     pub fn select<'a, 'v, 't: 'v, 'g: 't>(&'t self, key: Lit<'a>, g: &'g Guard) -> Option<&'v Row> {
         self.data.mt_get_element(&key, g)
     }
-    pub fn __raw_index(&self) -> &IndexMTRaw<row::Row> {
+    pub fn __raw_index(&self) -> &IndexMTOrd<row::Row> {
         &self.data
     }
     pub fn count(&self) -> usize {
         self.data.mt_len()
     }
+    /// Returns the number of rows, without waiting for any concurrent writes to land. Use this for stats and
+    /// heuristics
+    pub fn count_approx(&self) -> usize {
+        self.data.mt_len_approx()
+    }
 }
 
 #[derive(Debug)]
@@ -28,7 +28,7 @@ use {
     super::key::PrimaryIndexKey,
     crate::{
         engine::{
-            core::model::{DeltaState, DeltaVersion, SchemaDeltaKind},
+            core::model::{DeltaState, DeltaVersion, Layer, SchemaDeltaKind},
             data::cell::Datacell,
             idx::{meta::hash::HasherNativeFx, mtchm::meta::TreeElement, IndexST, STIndex},
             mem::RawStr,
@@ -41,6 +41,14 @@ use {
 };
 
 pub type DcFieldIndex = IndexST<RawStr, Datacell, HasherNativeFx>;
+/// What a row's slot in the primary index costs at the least: the row handle and its skiplist node (the element
+/// pointer, the refcount and a one link tower). Taller towers are measured whenever the memory used by the model is
+/// recounted
+pub const ROW_INDEX_OVERHEAD: usize = sizeof!(Row) + sizeof!(usize, 5);
+/// What a row costs apart from its key's and its fields' data: its slot in the primary index and its refcounted data
+const ROW_OVERHEAD: usize = ROW_INDEX_OVERHEAD + sizeof!(RwLock<RowData>);
+/// What every field slot of a row costs apart from the field's data (the field's name is shared with the model)
+const FIELD_OVERHEAD: usize = sizeof!(RawStr) + sizeof!(Datacell) + sizeof!(usize);
 
 #[derive(Debug)]
 pub struct Row {
@@ -73,6 +81,11 @@ impl RowData {
     pub fn get_restored_txn_revised(&self) -> DeltaVersion {
         self.restore_txn_id
     }
+    /// Returns the version of the last change to this row. Unlike [`Self::get_txn_revised`], this is stable across
+    /// restarts (restored rows keep the version that they were journaled with), so it can be handed out to clients
+    pub fn get_version(&self) -> DeltaVersion {
+        self.txn_revised_data.max(self.restore_txn_id)
+    }
 }
 
 impl TreeElement for Row {
@@ -140,6 +153,20 @@ impl Row {
     pub fn d_data(&self) -> &RwLock<RowData> {
         self.__rc.data()
     }
+    /// Returns an estimate of the memory that this row takes up in the primary index, given its (locked) data
+    pub fn estimated_size(&self, data: &RowData) -> usize {
+        let key = self
+            .d_key()
+            .bin()
+            .or_else(|| self.d_key().str().map(str::as_bytes))
+            .map_or(0, <[u8]>::len);
+        let fields = data
+            .fields()
+            .st_iter_value()
+            .map(Datacell::heap_size)
+            .sum::<usize>();
+        ROW_OVERHEAD + key + data.fields().st_capacity() * FIELD_OVERHEAD + fields
+    }
     #[cfg(test)]
     pub fn cloned_data(&self) -> Vec<(Box<str>, Datacell)> {
         self.d_data()
@@ -168,6 +195,28 @@ impl Row {
         }
         // we have deltas to apply
         let mut wl = RwLockUpgradableReadGuard::upgrade(rwl_ug);
+        Self::resolve_schema_deltas(&mut wl, delta_state);
+        return RwLockWriteGuard::downgrade(wl);
+    }
+    pub fn resolve_schema_deltas_and_freeze<'g>(
+        &'g self,
+        delta_state: &DeltaState,
+    ) -> RwLockReadGuard<'g, RowData> {
+        self.resolve_schema_deltas_and_freeze_if(delta_state, |_| true)
+    }
+    /// Apply any pending schema deltas and return a write lock on the row's data
+    pub fn resolve_schema_deltas_and_lock<'g>(
+        &'g self,
+        delta_state: &DeltaState,
+    ) -> RwLockWriteGuard<'g, RowData> {
+        let mut wl = self.d_data().write();
+        let current_version = delta_state.schema_current_version();
+        if compiler::unlikely(current_version > wl.txn_revised_schema_version) {
+            Self::resolve_schema_deltas(&mut wl, delta_state);
+        }
+        wl
+    }
+    fn resolve_schema_deltas(wl: &mut RowData, delta_state: &DeltaState) {
         let mut max_delta = wl.txn_revised_schema_version;
         for (delta_id, delta) in delta_state.resolve_iter_since(wl.txn_revised_schema_version) {
             match delta.kind() {
@@ -185,18 +234,33 @@ impl Row {
                 SchemaDeltaKind::FieldRem(f) => {
                     wl.fields.st_delete(f);
                 }
+                SchemaDeltaKind::FieldRetag(f, layers) => {
+                    if let Some(dc) = wl.fields.st_get_mut(f) {
+                        retag(dc, layers);
+                    }
+                }
             }
-            max_delta = *delta_id;
+            max_delta = delta_id.step();
         }
         // we've revised upto the most most recent delta version (that we saw at this point)
         wl.txn_revised_schema_version = max_delta;
-        return RwLockWriteGuard::downgrade(wl);
     }
-    pub fn resolve_schema_deltas_and_freeze<'g>(
-        &'g self,
-        delta_state: &DeltaState,
-    ) -> RwLockReadGuard<'g, RowData> {
-        self.resolve_schema_deltas_and_freeze_if(delta_state, |_| true)
+}
+
+/// Give the cell (and its elements, if it's a list) the tags of the (widened) layers
+fn retag(dc: &mut Datacell, layers: &[Layer]) {
+    if dc.is_null() {
+        return;
+    }
+    unsafe {
+        // UNSAFE(@ohsayan): widening never changes the tag class, so the data stays valid
+        dc.set_tag(layers[0].tag());
+        if layers.len() > 1 {
+            dc.read_list()
+                .write()
+                .iter_mut()
+                .for_each(|element| retag(element, &layers[1..]));
+        }
     }
 }
 
@@ -24,12 +24,16 @@
  *
 */
 
+pub(in crate::engine) mod catalog;
 pub(in crate::engine) mod dcl;
 pub(super) mod ddl_misc;
 pub(in crate::engine) mod dml;
 pub(in crate::engine) mod exec;
+mod export;
+mod import;
 pub(in crate::engine) mod index;
 pub(in crate::engine) mod model;
+pub(in crate::engine) mod prepared;
 pub(in crate::engine) mod query_meta;
 pub(in crate::engine) mod space;
 // util
@@ -121,6 +125,18 @@ impl GlobalNS {
         };
         f(model)
     }
+    /// Same as [`Self::with_model`], but `f` also gets the index of models so that it can read other models (without
+    /// locking the index again)
+    pub fn with_model_and_index<'a, T, F>(&self, entity: EntityIDRef<'a>, f: F) -> QueryResult<T>
+    where
+        F: FnOnce(&Model, &IndexST<EntityID, Model>) -> QueryResult<T>,
+    {
+        let mdl_idx = self.idx_mdl.read();
+        let Some(model) = mdl_idx.get(&entity) else {
+            return Err(QueryError::QExecObjectNotFound);
+        };
+        f(model, &mdl_idx)
+    }
     pub fn idx_models(&self) -> &RWLIdx<EntityID, Model> {
         &self.idx_mdl
     }
@@ -147,6 +163,21 @@ pub(self) fn with_model_for_data_update<'a, F>(
 where
     F: FnOnce(&Model) -> QueryResult<QueryExecMeta>,
 {
+    global.memory().admit_write(global, entity)?;
+    with_model_for_data_delete(global, entity, f)
+}
+
+/// Same as [`with_model_for_data_update`], except that the memory limits don't apply (since deletes only ever give
+/// memory back)
+pub(self) fn with_model_for_data_delete<'a, F>(
+    global: &impl GlobalInstanceLike,
+    entity: EntityIDRef<'a>,
+    f: F,
+) -> QueryResult<()>
+where
+    F: FnOnce(&Model) -> QueryResult<QueryExecMeta>,
+{
+    global.backpressure().admit_write(global, entity)?;
     let mdl_idx = global.namespace().idx_mdl.read();
     let Some(model) = mdl_idx.get(&entity) else {
         return Err(QueryError::QExecObjectNotFound);
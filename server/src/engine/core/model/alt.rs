@@ -106,7 +106,8 @@ impl<'a> AlterPlan<'a> {
                     let not_pk = mdl.not_pk(id);
                     let exists = !no_field(mdl, id.as_str());
                     not_found = !exists;
-                    not_pk & exists
+                    // a field can't be removed while a computed field uses it
+                    not_pk & exists && !mdl.is_compute_source(id.as_str())
                 }) {
                     can_ignore!(AlterAction::Remove(r))
                 } else if not_found {
@@ -126,7 +127,11 @@ impl<'a> AlterPlan<'a> {
                     } = fields.next().unwrap();
                     okay &= no_field(mdl, &field_name) & mdl.not_pk(&field_name);
                     let is_nullable = check_nullable(&mut props)?;
+                    okay &= props.is_empty();
                     let layers = Field::parse_layers(layers, is_nullable)?;
+                    // existing rows are only ever backfilled with nulls, so defaults (and computed fields) can only
+                    // be set at creation
+                    okay &= layers.default().is_none() & layers.compute().is_none();
                     okay &= add.st_insert(field_name.as_str().into(), layers);
                 }
                 can_ignore!(AlterAction::Add(add))
@@ -0,0 +1,190 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    Computed fields
+    ---
+    A computed field's value is derived from the other (string) fields of the row whenever the row is written. The
+    expression is declared as a string when the field is created:
+
+        create model myspace.users(username: string, username_lc: string { compute: "lower(username)" })
+
+    <expr> ::= <field> | <literal> | <function> "(" <expr> ("," <expr>)* ")"
+    <function> ::= "lower" | "upper" | "concat"
+
+    where a literal is quoted with `'` or `"`. If any of the fields that an expression uses is null, so is the result.
+*/
+
+/// Expressions can't be nested any deeper than this
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, PartialEq, Clone)]
+/// The expression that computes the value of a field
+pub struct FieldCompute {
+    decl: Box<str>,
+    expr: Expr,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Expr {
+    Field(Box<str>),
+    Lit(Box<str>),
+    Lower(Box<Expr>),
+    Upper(Box<Expr>),
+    Concat(Box<[Expr]>),
+}
+
+impl FieldCompute {
+    /// Parse an expression, returning [`None`] if it isn't valid
+    pub fn parse(decl: &str) -> Option<Self> {
+        let decl = decl.trim();
+        let mut parser = Parser { src: decl, pos: 0 };
+        let expr = parser.expr(0)?;
+        parser.skip_ws();
+        if parser.pos == decl.len() {
+            Some(Self {
+                decl: decl.into(),
+                expr,
+            })
+        } else {
+            None
+        }
+    }
+    /// The expression as it was declared
+    pub fn decl(&self) -> &str {
+        &self.decl
+    }
+    /// Returns the fields that this expression uses
+    pub fn sources(&self) -> Vec<&str> {
+        let mut ret = vec![];
+        self.expr.sources(&mut ret);
+        ret
+    }
+    pub fn depends_on(&self, field: &str) -> bool {
+        self.sources().contains(&field)
+    }
+    /// Evaluate the expression, with `get` returning the value of a (string) field or [`None`] if it is null
+    pub fn eval<'a>(&self, get: impl Fn(&str) -> Option<&'a str>) -> Option<String> {
+        let mut ret = String::new();
+        self.expr.eval(&get, &mut ret).then_some(ret)
+    }
+}
+
+impl Expr {
+    fn sources<'a>(&'a self, sources: &mut Vec<&'a str>) {
+        match self {
+            Self::Field(field) => sources.push(field),
+            Self::Lit(_) => {}
+            Self::Lower(e) | Self::Upper(e) => e.sources(sources),
+            Self::Concat(args) => args.iter().for_each(|e| e.sources(sources)),
+        }
+    }
+    /// Append the result to `out`, returning false if the result is null
+    fn eval<'a>(&self, get: &impl Fn(&str) -> Option<&'a str>, out: &mut String) -> bool {
+        match self {
+            Self::Field(field) => match get(field) {
+                Some(value) => {
+                    out.push_str(value);
+                    true
+                }
+                None => false,
+            },
+            Self::Lit(lit) => {
+                out.push_str(lit);
+                true
+            }
+            Self::Lower(e) | Self::Upper(e) => {
+                let mut arg = String::new();
+                let okay = e.eval(get, &mut arg);
+                if matches!(self, Self::Lower(_)) {
+                    out.push_str(&arg.to_lowercase());
+                } else {
+                    out.push_str(&arg.to_uppercase());
+                }
+                okay
+            }
+            Self::Concat(args) => args.iter().all(|e| e.eval(get, out)),
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rem(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+    fn skip_ws(&mut self) {
+        let rem = self.rem();
+        self.pos += rem.len() - rem.trim_start().len();
+    }
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        let okay = self.rem().starts_with(c);
+        self.pos += okay as usize;
+        okay
+    }
+    fn expr(&mut self, depth: usize) -> Option<Expr> {
+        if depth == MAX_DEPTH {
+            return None;
+        }
+        self.skip_ws();
+        let rem = self.rem();
+        let quote = rem.chars().next()?;
+        if (quote == '\'') | (quote == '"') {
+            let len = rem[1..].find(quote)?;
+            self.pos += len + 2;
+            return Some(Expr::Lit(rem[1..len + 1].into()));
+        }
+        let len = rem
+            .find(|c: char| !(c.is_ascii_alphanumeric() | (c == '_')))
+            .unwrap_or(rem.len());
+        let ident = &rem[..len];
+        if ident.is_empty() || ident.as_bytes()[0].is_ascii_digit() {
+            return None;
+        }
+        self.pos += len;
+        if !self.eat('(') {
+            return Some(Expr::Field(ident.into()));
+        }
+        let mut args = vec![self.expr(depth + 1)?];
+        while self.eat(',') {
+            args.push(self.expr(depth + 1)?);
+        }
+        if !self.eat(')') {
+            return None;
+        }
+        match (ident.to_ascii_lowercase().as_str(), args.len()) {
+            ("lower", 1) => Some(Expr::Lower(Box::new(args.pop().unwrap()))),
+            ("upper", 1) => Some(Expr::Upper(Box::new(args.pop().unwrap()))),
+            ("concat", _) => Some(Expr::Concat(args.into_boxed_slice())),
+            _ => None,
+        }
+    }
+}
@@ -25,17 +25,23 @@
 */
 
 use {
-    super::Model,
+    super::{snapshot::SnapshotRegistry, Layer, Model},
     crate::engine::{
-        core::{dml::QueryExecMeta, index::Row},
-        fractal::{FractalToken, GlobalInstanceLike},
+        core::{
+            dml::QueryExecMeta,
+            index::{PrimaryIndexKey, Row, RowCache, RowData},
+        },
+        fractal::{cdc::ChangeFeed, FractalToken, GlobalInstanceLike},
         mem::RawStr,
         sync::atm::Guard,
         sync::queue::Queue,
     },
     std::{
         collections::btree_map::{BTreeMap, Range},
-        sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+        sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Arc,
+        },
     },
 };
 
@@ -49,6 +55,16 @@ pub struct DeltaState {
     data_current_version: AtomicU64,
     data_deltas: Queue<DataDelta>,
     data_deltas_size: AtomicUsize,
+    data_deltas_pending: AtomicUsize,
+    // deltas held back by open transactions
+    data_deltas_held: AtomicUsize,
+    // estimated size of the rows in the primary index
+    index_bytes: AtomicUsize,
+    // memory allocated for the primary index beyond what the rows account for (as of the last recount)
+    index_slack: AtomicUsize,
+    snapshots: SnapshotRegistry,
+    change_feed: Arc<ChangeFeed>,
+    row_cache: RowCache,
 }
 
 impl DeltaState {
@@ -60,6 +76,13 @@ impl DeltaState {
             data_current_version: AtomicU64::new(0),
             data_deltas: Queue::new(),
             data_deltas_size: AtomicUsize::new(0),
+            data_deltas_pending: AtomicUsize::new(0),
+            data_deltas_held: AtomicUsize::new(0),
+            index_bytes: AtomicUsize::new(0),
+            index_slack: AtomicUsize::new(0),
+            snapshots: SnapshotRegistry::new(),
+            change_feed: Arc::new(ChangeFeed::new()),
+            row_cache: RowCache::new(),
         }
     }
 }
@@ -89,12 +112,99 @@ impl DeltaState {
         self.append_new_data_delta(DataDelta::new(data_version, row, kind), g)
     }
     pub fn append_new_data_delta(&self, delta: DataDelta, g: &Guard) -> usize {
+        self.row_cache.invalidate(delta.row().d_key());
         self.data_deltas.blocking_enqueue(delta, g);
+        self.data_deltas_pending.fetch_add(1, Ordering::Release);
         self.data_deltas_size.fetch_add(1, Ordering::Release) + 1
     }
+    /// Append a group of deltas. The queue size is only bumped once all of them have been enqueued, so a batch
+    /// flush sees either all or none of them
+    pub fn append_new_data_deltas(&self, deltas: Vec<DataDelta>, g: &Guard) -> usize {
+        let count = deltas.len();
+        deltas.into_iter().for_each(|delta| {
+            self.row_cache.invalidate(delta.row().d_key());
+            self.data_deltas.blocking_enqueue(delta, g)
+        });
+        self.data_deltas_pending.fetch_add(count, Ordering::Release);
+        self.data_deltas_size.fetch_add(count, Ordering::Release) + count
+    }
+    /// Hold back the delta of a change that was made in a transaction, until it commits (see
+    /// [`Transaction`](crate::engine::core::dml::Transaction)). Readers can see the change already, so the row's cache
+    /// entry is invalidated right away
+    pub(in crate::engine::core) fn hold_data_delta(&self, key: &PrimaryIndexKey) {
+        self.row_cache.invalidate(key);
+        self.data_deltas_held.fetch_add(1, Ordering::Release);
+    }
+    /// Stop holding back deltas, once the transaction that made the changes has appended (or dropped) them
+    pub(in crate::engine::core) fn release_data_deltas(&self, count: usize) {
+        self.data_deltas_held.fetch_sub(count, Ordering::Release);
+    }
+    /// Returns true if open transactions have changed rows without journaling the changes yet, in which case a full
+    /// snapshot of the rows would include changes that might still be rolled back
+    pub fn has_held_data_deltas(&self) -> bool {
+        self.data_deltas_held.load(Ordering::Acquire) != 0
+    }
     pub fn create_new_data_delta_version(&self) -> DeltaVersion {
         DeltaVersion(self.__data_delta_step())
     }
+    /// Returns the version that the next data delta will get
+    pub(in crate::engine) fn data_current_version(&self) -> DeltaVersion {
+        DeltaVersion(self.data_current_version.load(Ordering::Acquire))
+    }
+    /// Save a copy of the row for any pinned snapshot that can see it. Writers must call this with the row locked,
+    /// after they create the version of the change and before they change or delete the row
+    pub(in crate::engine::core) fn snapshot_preserve(&self, row: &Row, data: &RowData) {
+        self.snapshots.preserve(row, data)
+    }
+    pub(super) fn snapshots(&self) -> &SnapshotRegistry {
+        &self.snapshots
+    }
+    /// Returns the feed that the changes to this model's rows are published to
+    pub fn change_feed(&self) -> &Arc<ChangeFeed> {
+        &self.change_feed
+    }
+    /// Returns the cache for this model's hot rows (every data delta invalidates the row that it changed)
+    pub fn row_cache(&self) -> &RowCache {
+        &self.row_cache
+    }
+    /// Record that a row of `old` bytes in the primary index was replaced by one of `new` bytes (`old` is zero for
+    /// inserts and `new` is zero for deletes). See [`Row::estimated_size`]
+    pub(in crate::engine::core) fn account_row(&self, old: usize, new: usize) {
+        if new >= old {
+            self.index_bytes.fetch_add(new - old, Ordering::AcqRel);
+        } else {
+            // the estimate could have been recounted in the meantime
+            let _ = self
+                .index_bytes
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |bytes| {
+                    Some(bytes.saturating_sub(old - new))
+                });
+        }
+    }
+    /// Returns the estimated memory used by the rows in the primary index
+    pub fn index_bytes(&self) -> usize {
+        self.index_bytes.load(Ordering::Acquire)
+    }
+    /// Returns the memory allocated for the primary index's structure beyond what is accounted for by the rows (see
+    /// [`Row::estimated_size`]), as of the last recount
+    pub fn index_slack(&self) -> usize {
+        self.index_slack.load(Ordering::Acquire)
+    }
+    /// Replace the running estimate of the memory used by the primary index with a fresh count
+    pub(in crate::engine::core) fn __reset_index_bytes(&self, bytes: usize, slack: usize) {
+        self.index_bytes.store(bytes, Ordering::Release);
+        self.index_slack.store(slack, Ordering::Release);
+    }
+    /// Returns the number of data deltas that haven't yet been picked up for a batch write. Use
+    /// [`Self::data_delta_backlog`] for the number of deltas that are yet to be flushed to disk
+    pub fn data_delta_queue_len(&self) -> usize {
+        self.data_deltas_size.load(Ordering::Acquire)
+    }
+    /// Returns the number of data deltas that are yet to be flushed to disk (including the ones that are being
+    /// written right now)
+    pub fn data_delta_backlog(&self) -> usize {
+        self.data_deltas_pending.load(Ordering::Acquire)
+    }
 }
 
 impl DeltaState {
@@ -102,7 +212,16 @@ impl DeltaState {
         self.data_current_version.fetch_add(1, Ordering::AcqRel)
     }
     pub fn __data_delta_dequeue(&self, g: &Guard) -> Option<DataDelta> {
-        self.data_deltas.blocking_try_dequeue(g)
+        let delta = self.data_deltas.blocking_try_dequeue(g);
+        if delta.is_some() {
+            self.data_deltas_pending.fetch_sub(1, Ordering::AcqRel);
+        }
+        delta
+    }
+    /// Make sure that any new data delta is versioned after `last` (used after restoring data from disk)
+    pub fn __restore_data_version(&self, last: DeltaVersion) {
+        self.data_current_version
+            .fetch_max(last.value_u64() + 1, Ordering::AcqRel);
     }
 }
 
@@ -112,7 +231,8 @@ impl DeltaState {
         &self,
         current_version: DeltaVersion,
     ) -> Range<DeltaVersion, SchemaDeltaPart> {
-        self.schema_deltas.range(current_version.step()..)
+        // a row at `current_version` has seen every delta before it
+        self.schema_deltas.range(current_version..)
     }
     pub fn schema_current_version(&self) -> DeltaVersion {
         DeltaVersion(self.schema_current_version)
@@ -123,6 +243,9 @@ impl DeltaState {
     pub fn unresolved_append_field_rem(&mut self, field_name: RawStr) {
         self.__schema_append_unresolved_delta(SchemaDeltaPart::field_rem(field_name));
     }
+    pub fn unresolved_append_field_retag(&mut self, field_name: RawStr, layers: Box<[Layer]>) {
+        self.__schema_append_unresolved_delta(SchemaDeltaPart::field_retag(field_name, layers));
+    }
 }
 
 impl DeltaState {
@@ -132,6 +255,8 @@ impl DeltaState {
         DeltaVersion(current)
     }
     fn __schema_append_unresolved_delta(&mut self, part: SchemaDeltaPart) -> DeltaVersion {
+        // the cached rows were encoded with the old schema
+        self.row_cache.clear();
         let v = self.__schema_delta_step();
         self.schema_deltas.insert(v, part);
         v
@@ -154,7 +279,7 @@ impl DeltaVersion {
     pub const fn __new(v: u64) -> Self {
         Self(v)
     }
-    fn step(&self) -> Self {
+    pub(in crate::engine::core) fn step(&self) -> Self {
         Self(self.0 + 1)
     }
     pub const fn value_u64(&self) -> u64 {
@@ -181,6 +306,8 @@ impl SchemaDeltaPart {
 pub enum SchemaDeltaKind {
     FieldAdd(RawStr),
     FieldRem(RawStr),
+    /// the field was widened (for example, from `uint32` to `uint64`) and its cells need the new layers' tags
+    FieldRetag(RawStr, Box<[Layer]>),
 }
 
 impl SchemaDeltaPart {
@@ -193,6 +320,9 @@ impl SchemaDeltaPart {
     fn field_rem(field_name: RawStr) -> Self {
         Self::new(SchemaDeltaKind::FieldRem(field_name))
     }
+    fn field_retag(field_name: RawStr, layers: Box<[Layer]>) -> Self {
+        Self::new(SchemaDeltaKind::FieldRetag(field_name, layers))
+    }
 }
 
 /*
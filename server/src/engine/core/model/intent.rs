@@ -0,0 +1,140 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::Model,
+    crate::engine::{
+        core::index::PrimaryIndexKey,
+        error::{QueryError, QueryResult},
+    },
+    parking_lot::Mutex,
+    std::{
+        collections::hash_map::{Entry, HashMap},
+        sync::{
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+};
+
+/// the owners of intents. every transaction gets a new one
+static NEXT_OWNER: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Default)]
+/// The write intents held on the rows of a model (`select ... for update`). A row with an intent can only be changed by
+/// the transaction holding it, until the transaction ends and the intent is released
+pub struct RowIntents {
+    held: Mutex<HashMap<PrimaryIndexKey, u64>>,
+    /// the number of intents held, so that writes don't need to take the lock when there are none (which is almost
+    /// always)
+    count: AtomicUsize,
+}
+
+impl RowIntents {
+    /// Take the intent on the row for `owner`, returning true if it didn't already hold it
+    fn acquire(&self, key: &PrimaryIndexKey, owner: u64) -> QueryResult<bool> {
+        let mut held = self.held.lock();
+        match held.entry(key.clone()) {
+            Entry::Occupied(e) if *e.get() == owner => Ok(false),
+            Entry::Occupied(_) => Err(QueryError::QExecDmlRowLocked),
+            Entry::Vacant(e) => {
+                e.insert(owner);
+                self.count.fetch_add(1, Ordering::Release);
+                Ok(true)
+            }
+        }
+    }
+    /// Check that the row can be changed by `owner` (or by a statement that runs outside of a transaction, if there's
+    /// none). This must be called under the row's lock
+    pub(in crate::engine::core) fn check(
+        &self,
+        key: &PrimaryIndexKey,
+        owner: Option<u64>,
+    ) -> QueryResult<()> {
+        if self.count.load(Ordering::Acquire) == 0 {
+            return Ok(());
+        }
+        match self.held.lock().get(key) {
+            Some(holder) if Some(*holder) != owner => Err(QueryError::QExecDmlRowLocked),
+            _ => Ok(()),
+        }
+    }
+    /// Release all the intents held by `owner`
+    fn release(&self, owner: u64) {
+        let mut held = self.held.lock();
+        held.retain(|_, holder| *holder != owner);
+        self.count.store(held.len(), Ordering::Release);
+    }
+    #[cfg(test)]
+    pub fn held(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Debug)]
+/// The write intents taken by a transaction. They're all released once the scope is dropped (when the transaction
+/// ends). A statement that runs on its own is its own transaction
+pub struct IntentScope {
+    owner: u64,
+    models: Vec<Arc<RowIntents>>,
+}
+
+impl IntentScope {
+    pub fn new() -> Self {
+        Self {
+            owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed),
+            models: Vec::new(),
+        }
+    }
+    /// Take the intent on a row of the model, failing if another transaction holds it
+    pub(in crate::engine::core) fn acquire(
+        &mut self,
+        mdl: &Model,
+        key: &PrimaryIndexKey,
+    ) -> QueryResult<()> {
+        let intents = mdl.row_intents();
+        if intents.acquire(key, self.owner)?
+            && !self.models.iter().any(|held| Arc::ptr_eq(held, intents))
+        {
+            self.models.push(intents.clone());
+        }
+        Ok(())
+    }
+}
+
+impl Default for IntentScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for IntentScope {
+    fn drop(&mut self) {
+        for intents in self.models.drain(..) {
+            intents.release(self.owner);
+        }
+    }
+}
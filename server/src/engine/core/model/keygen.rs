@@ -0,0 +1,218 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    Generated primary keys
+    ---
+    A model can generate the primary key of a row when an insert skips it:
+
+        create model myspace.users(id: uint64 { generate: 'increment' }, username: string)
+
+    - `increment`: 1, 2, 3, ... (for unsigned integer keys)
+    - `snowflake`: the milliseconds since the snowflake epoch followed by a sequence number (for `uint64` keys)
+    - `uuid`: a UUIDv7 (for string and binary keys)
+
+    Increments and snowflakes are allocated from a counter that never goes back, even across restarts. Before a key
+    is handed out, the highest key that can be handed out (the high water mark) is raised in blocks and persisted in
+    the model's metadata, so after a crash, we just continue from the last high water mark (skipping the keys in the
+    block that weren't handed out).
+*/
+
+use {
+    super::{Field, Model},
+    crate::engine::{
+        data::{
+            cell::Datacell,
+            tag::{DataTag, TagClass, TagSelector, UIntSpec},
+            uuid::Uuid,
+        },
+        error::{QueryError, QueryResult},
+        idx::STIndex,
+    },
+    std::time::{SystemTime, UNIX_EPOCH},
+};
+
+/// 2020-01-01T00:00:00Z
+const SNOWFLAKE_EPOCH_MS: u64 = 1_577_836_800_000;
+const SNOWFLAKE_SEQ_BITS: u32 = 22;
+/// The number of keys that the high water mark is raised by for increments
+const INCREMENT_BLOCK: u64 = 1024;
+/// The number of keys that the high water mark is raised by for snowflakes (about a second's worth)
+const SNOWFLAKE_BLOCK: u64 = 1000 << SNOWFLAKE_SEQ_BITS;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// How the primary key of a model is generated
+pub enum KeyGenerator {
+    Increment,
+    Snowflake,
+    Uuid,
+}
+
+impl KeyGenerator {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "increment" => Some(Self::Increment),
+            "snowflake" => Some(Self::Snowflake),
+            "uuid" => Some(Self::Uuid),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Increment => "increment",
+            Self::Snowflake => "snowflake",
+            Self::Uuid => "uuid",
+        }
+    }
+    /// Returns true if this can generate values for the field
+    pub(super) fn check(&self, field: &Field) -> bool {
+        let tag = field.layers()[0].tag();
+        (field.layers().len() == 1)
+            & match self {
+                Self::Increment => tag.tag_class() == TagClass::UnsignedInt,
+                Self::Snowflake => tag.tag_selector() == TagSelector::UInt64,
+                Self::Uuid => matches!(tag.tag_class(), TagClass::Str | TagClass::Bin),
+            }
+    }
+}
+
+#[derive(Debug, Default)]
+/// The counter that increments and snowflakes are allocated from
+pub(super) struct KeyAllocator {
+    next: u64,
+    high_water: u64,
+}
+
+impl Model {
+    pub fn key_generator(&self) -> Option<KeyGenerator> {
+        self.fields.st_get(&self.p_key).and_then(Field::generate)
+    }
+    #[cfg(test)]
+    pub fn key_high_water(&self) -> u64 {
+        self.keys.lock().high_water
+    }
+    /// Restore the high water mark of the keys (keys are then allocated from here on)
+    pub fn restore_key_high_water(&self, high_water: u64) {
+        let mut keys = self.keys.lock();
+        keys.high_water = keys.high_water.max(high_water);
+        keys.next = keys.next.max(high_water);
+    }
+    /// Generate a new primary key. `reserve` persists a new high water mark, and if there's none, this is only a
+    /// placeholder (nothing is allocated)
+    pub(in crate::engine::core) fn generate_key(
+        &self,
+        reserve: Option<&dyn Fn(u64) -> QueryResult<()>>,
+    ) -> QueryResult<Datacell> {
+        let Some(generator) = self.key_generator() else {
+            return Err(QueryError::QExecDmlValidationError);
+        };
+        let key = match (generator, reserve) {
+            (KeyGenerator::Uuid, _) => {
+                let uuid = Uuid::new_v7();
+                return Ok(if self.p_tag.tag_class() == TagClass::Str {
+                    Datacell::new_str(uuid.to_string().into_boxed_str())
+                } else {
+                    Datacell::new_bin(uuid.to_be_bytes().into())
+                });
+            }
+            (_, None) => 0,
+            (KeyGenerator::Increment, Some(reserve)) => self.allocate_key(1, reserve)?,
+            (KeyGenerator::Snowflake, Some(reserve)) => {
+                self.allocate_key(snowflake_floor(), reserve)?
+            }
+        };
+        // UNSAFE(@ohsayan): the generator was checked against the primary key's type when the model was created
+        let spec = unsafe { UIntSpec::from_full(self.p_tag) };
+        if spec.check(key) {
+            Ok(Datacell::new_uint(key, spec))
+        } else {
+            // all the keys that fit have been used up
+            Err(QueryError::QExecDmlValidationError)
+        }
+    }
+    /// Make sure that keys allocated later are greater than this key (which was set explicitly)
+    pub(in crate::engine::core) fn observe_key(
+        &self,
+        key: &Datacell,
+        reserve: &dyn Fn(u64) -> QueryResult<()>,
+    ) -> QueryResult<()> {
+        match (self.key_generator(), key.try_uint()) {
+            (Some(KeyGenerator::Increment | KeyGenerator::Snowflake), Some(key)) => {
+                let mut keys = self.keys.lock();
+                if key >= keys.next {
+                    let next = key
+                        .checked_add(1)
+                        .ok_or(QueryError::QExecDmlValidationError)?;
+                    self.raise_high_water(&mut keys, next, reserve)?;
+                    keys.next = next;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+    fn allocate_key(
+        &self,
+        floor: u64,
+        reserve: &dyn Fn(u64) -> QueryResult<()>,
+    ) -> QueryResult<u64> {
+        let mut keys = self.keys.lock();
+        let key = keys.next.max(floor);
+        let next = key
+            .checked_add(1)
+            .ok_or(QueryError::QExecDmlValidationError)?;
+        self.raise_high_water(&mut keys, next, reserve)?;
+        keys.next = next;
+        Ok(key)
+    }
+    /// Make sure that every key below `next` is under the high water mark, persisting a new one if needed
+    fn raise_high_water(
+        &self,
+        keys: &mut KeyAllocator,
+        next: u64,
+        reserve: &dyn Fn(u64) -> QueryResult<()>,
+    ) -> QueryResult<()> {
+        if next > keys.high_water {
+            let block = match self.key_generator() {
+                Some(KeyGenerator::Snowflake) => SNOWFLAKE_BLOCK,
+                _ => INCREMENT_BLOCK,
+            };
+            let high_water = next.saturating_add(block);
+            reserve(high_water)?;
+            keys.high_water = high_water;
+        }
+        Ok(())
+    }
+}
+
+/// The smallest snowflake for the current time
+fn snowflake_floor() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    now.saturating_sub(SNOWFLAKE_EPOCH_MS) << SNOWFLAKE_SEQ_BITS
+}
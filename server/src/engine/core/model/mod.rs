@@ -25,37 +25,61 @@
 */
 
 pub(super) mod alt;
+pub(in crate::engine) mod compute;
 pub(in crate::engine) mod delta;
+pub(in crate::engine) mod intent;
+pub(in crate::engine) mod keygen;
+pub(in crate::engine) mod schema;
+pub(in crate::engine::core) mod snapshot;
 
 #[cfg(test)]
 use std::cell::RefCell;
 
 use {
-    super::index::PrimaryIndex,
+    super::index::{DcFieldIndex, PrimaryIndex, PrimaryIndexKey, ROW_INDEX_OVERHEAD},
     crate::engine::{
         data::{
             cell::Datacell,
             tag::{DataTag, FloatSpec, FullTag, SIntSpec, TagClass, TagSelector, UIntSpec},
             uuid::Uuid,
+            DictEntryGeneric,
         },
         error::{QueryError, QueryResult},
         fractal::{GenericTask, GlobalInstanceLike, Task},
-        idx::{self, IndexBaseSpec, IndexSTSeqCns, STIndex, STIndexSeq},
+        idx::{
+            self,
+            fts::TextIndex,
+            geo::{self, GeoIndex},
+            IndexBaseSpec, IndexSTSeqCns, MTIndex, MTIndexExt, STIndex, STIndexSeq,
+        },
         mem::{RawStr, VInline},
         ql::ddl::{
             crt::CreateModel,
             drop::DropModel,
             syn::{FieldSpec, LayerSpec},
         },
+        sync::atm::cpin,
         txn::gns::{self as gnstxn, SpaceIDRef},
     },
-    std::collections::hash_map::{Entry, HashMap},
+    parking_lot::Mutex,
+    std::{
+        collections::hash_map::{Entry, HashMap},
+        sync::Arc,
+    },
 };
 
 pub(in crate::engine::core) use self::delta::{DeltaState, DeltaVersion, SchemaDeltaKind};
+use self::{
+    compute::FieldCompute,
+    intent::RowIntents,
+    keygen::{KeyAllocator, KeyGenerator},
+    schema::DictSchema,
+};
 
 use super::util::{EntityID, EntityIDRef};
 type Fields = IndexSTSeqCns<RawStr, Field>;
+type TextIndexes = HashMap<Box<str>, TextIndex<PrimaryIndexKey>>;
+type GeoIndexes = HashMap<Box<str>, GeoIndex<PrimaryIndexKey>>;
 
 #[derive(Debug)]
 pub struct Model {
@@ -67,6 +91,19 @@ pub struct Model {
     delta: DeltaState,
     private: ModelPrivate,
     decl: String,
+    keys: Mutex<KeyAllocator>,
+    /// the full-text indexes of the fields that have them (see [`Field::is_fulltext`]). these aren't persisted, and
+    /// are rebuilt from the rows when the model is loaded
+    text_indexes: TextIndexes,
+    /// the geohash indexes of the point fields (see [`Field::has_geo_index`]). like the full-text indexes, these are
+    /// rebuilt when the model is loaded
+    geo_indexes: GeoIndexes,
+    /// the write intents held on the rows (`select ... for update`). these only live as long as the transactions
+    /// holding them, so they're never persisted
+    intents: Arc<RowIntents>,
+    /// set for models created `with { durability: 'none' }`. the rows of an ephemeral model are only kept in memory
+    /// (no batch journal is kept for it), so it's empty after a restart
+    ephemeral: bool,
 }
 
 #[cfg(test)]
@@ -76,6 +113,7 @@ impl PartialEq for Model {
             && self.p_key == m.p_key
             && self.p_tag == m.p_tag
             && self.fields == m.fields
+            && self.ephemeral == m.ephemeral
     }
 }
 
@@ -89,6 +127,15 @@ impl Model {
     pub fn p_tag(&self) -> FullTag {
         self.p_tag
     }
+    /// Returns true if the rows of this model are only kept in memory
+    pub fn is_ephemeral(&self) -> bool {
+        self.ephemeral
+    }
+    /// Only keep the rows of this model in memory
+    pub fn into_ephemeral(mut self) -> Self {
+        self.ephemeral = true;
+        self
+    }
     fn is_pk(&self, new: &str) -> bool {
         self.p_key.as_bytes() == new.as_bytes()
     }
@@ -111,6 +158,141 @@ impl Model {
     pub fn fields(&self) -> &Fields {
         &self.fields
     }
+    /// Returns true if a computed field uses this field
+    fn is_compute_source(&self, field: &str) -> bool {
+        self.fields
+            .stseq_ord_value()
+            .filter_map(Field::compute)
+            .any(|compute| compute.depends_on(field))
+    }
+    /// Computed fields can only be derived from (non-list) string fields that aren't computed themselves, and the
+    /// primary key can't be computed
+    fn check_computed(fields: &Fields, p_key: &str) -> bool {
+        fields
+            .stseq_ord_kv()
+            .all(|(name, field)| match field.compute() {
+                Some(compute) => {
+                    name.as_str() != p_key
+                        && compute.sources().into_iter().all(|source| {
+                            fields.st_get(source).is_some_and(|source| {
+                                (source.layers().len() == 1)
+                                    & (source.layers()[0].tag().tag_class() == TagClass::Str)
+                                    & source.compute().is_none()
+                            })
+                        })
+                }
+                None => true,
+            })
+    }
+    /// Only the primary key can be generated
+    fn check_generated(fields: &Fields, p_key: &str) -> bool {
+        fields
+            .stseq_ord_kv()
+            .all(|(name, field)| field.generate().is_none() | (name.as_str() == p_key))
+    }
+    /// Evaluate the computed fields of a row. `data` has every field of the row except (maybe) the primary key, which
+    /// is `pk` instead
+    pub(in crate::engine::core) fn compute_fields<'a>(
+        &'a self,
+        data: &DcFieldIndex,
+        pk: Option<&PrimaryIndexKey>,
+        mut filter: impl FnMut(&FieldCompute) -> bool,
+    ) -> QueryResult<Vec<(&'a RawStr, Datacell)>> {
+        let get = |field: &str| match data.st_get(field) {
+            Some(dc) => dc.try_str(),
+            None if self.is_pk(field) => pk.and_then(PrimaryIndexKey::str),
+            None => None,
+        };
+        let mut ret = vec![];
+        for (name, field) in self.fields.stseq_ord_kv() {
+            let Some(compute) = field.compute().filter(|compute| filter(compute)) else {
+                continue;
+            };
+            let mut value = match compute.eval(get) {
+                Some(value) => Datacell::new_str(value.into_boxed_str()),
+                None => Datacell::null(),
+            };
+            if !field.vt_data_fpath(&mut value) {
+                return Err(QueryError::QExecDmlValidationError);
+            }
+            ret.push((name, value));
+        }
+        Ok(ret)
+    }
+    /// Returns the full-text index of the field, if it has one
+    pub(in crate::engine::core) fn text_index(
+        &self,
+        field: &str,
+    ) -> Option<&TextIndex<PrimaryIndexKey>> {
+        self.text_indexes.get(field)
+    }
+    /// Returns the geohash index of the field, if it has one
+    pub(in crate::engine::core) fn geo_index(
+        &self,
+        field: &str,
+    ) -> Option<&GeoIndex<PrimaryIndexKey>> {
+        self.geo_indexes.get(field)
+    }
+    /// Returns the write intents held on the rows of the model
+    pub(in crate::engine::core) fn row_intents(&self) -> &Arc<RowIntents> {
+        &self.intents
+    }
+    /// Bring the full-text and geohash indexes up to date with a change to a row. `data` is the new data of the row
+    /// (or the data that it had, if it was deleted)
+    pub(in crate::engine::core) fn index_row(
+        &self,
+        pk: &PrimaryIndexKey,
+        data: &DcFieldIndex,
+        deleted: bool,
+    ) {
+        for (field_id, index) in self.text_indexes.iter() {
+            match data.st_get(&**field_id).and_then(Datacell::try_str) {
+                Some(text) if !deleted => index.index(pk, Some(text)),
+                _ => index.remove(pk),
+            }
+        }
+        for (field_id, index) in self.geo_indexes.iter() {
+            match data.st_get(&**field_id).and_then(Datacell::try_geopoint) {
+                Some(point) if !deleted => index.index(pk, Some(point)),
+                _ => index.remove(pk),
+            }
+        }
+    }
+    /// Build the full-text and geohash indexes from scratch, using the rows in the primary index
+    pub fn rebuild_secondary_indexes(&self) {
+        if self.text_indexes.is_empty() & self.geo_indexes.is_empty() {
+            return;
+        }
+        self.text_indexes.values().for_each(TextIndex::clear);
+        self.geo_indexes.values().for_each(GeoIndex::clear);
+        let g = cpin();
+        for row in self.primary_index().__raw_index().mt_iter_entry(&g) {
+            let data = row.resolve_schema_deltas_and_freeze(self.delta_state());
+            self.index_row(row.d_key(), data.fields(), false);
+        }
+    }
+    /// Count the memory used by the rows in the primary index from scratch, replacing the running estimate (which
+    /// drifts with changes that aren't accounted for row by row, like schema changes and restores). This also measures
+    /// what the index's structure takes up beyond the slots accounted for by the rows
+    pub fn recount_index_bytes(&self) {
+        let g = cpin();
+        let ds = self.delta_state();
+        let idx = self.primary_index().__raw_index();
+        let (rows, bytes) = idx
+            .mt_iter_entry(&g)
+            .map(|row| row.estimated_size(&row.resolve_schema_deltas_and_freeze(ds)))
+            .fold((0, 0), |(rows, bytes), size| (rows + 1, bytes + size));
+        let slack = idx
+            .mt_capacity_bytes(&g)
+            .saturating_sub(rows * ROW_INDEX_OVERHEAD);
+        ds.__reset_index_bytes(bytes, slack);
+    }
+    /// Give back the memory that the primary index is holding on to after deletes, and recount the memory used by it
+    /// (see [`Self::recount_index_bytes`])
+    pub fn compact_index(&self) {
+        self.primary_index().__raw_index().mt_compact();
+        self.recount_index_bytes();
+    }
     pub fn model_mutator<'a>(&'a mut self) -> ModelMutator<'a> {
         ModelMutator { model: self }
     }
@@ -134,19 +316,7 @@ impl Model {
             }
             ret.push_str(&field_name);
             ret.push(':');
-            // TODO(@ohsayan): it's all lists right now, so this is okay but fix it later
-            if field_decl.layers().len() == 1 {
-                ret.push_str(field_decl.layers()[0].tag().tag_selector().name_str());
-            } else {
-                ret.push_str(&"[".repeat(field_decl.layers().len() - 1));
-                ret.push_str(
-                    field_decl.layers()[field_decl.layers().len() - 1]
-                        .tag()
-                        .tag_selector()
-                        .name_str(),
-                );
-                ret.push_str(&"]".repeat(field_decl.layers().len() - 1))
-            }
+            ret.push_str(&field_decl.describe_type());
             if it.peek().is_some() {
                 ret.push(',');
             }
@@ -164,6 +334,16 @@ impl Model {
         fields: Fields,
         private: ModelPrivate,
     ) -> Self {
+        let text_indexes = fields
+            .stseq_ord_kv()
+            .filter(|(_, field)| field.is_fulltext())
+            .map(|(field_id, _)| (field_id.as_str().into(), TextIndex::new()))
+            .collect();
+        let geo_indexes = fields
+            .stseq_ord_kv()
+            .filter(|(_, field)| field.has_geo_index())
+            .map(|(field_id, _)| (field_id.as_str().into(), GeoIndex::new()))
+            .collect();
         let mut slf = Self {
             uuid,
             p_key,
@@ -173,6 +353,11 @@ impl Model {
             delta: DeltaState::new_resolved(),
             private,
             decl: String::new(),
+            keys: Mutex::new(KeyAllocator::default()),
+            text_indexes,
+            geo_indexes,
+            intents: Arc::default(),
+            ephemeral: false,
         };
         slf.sync_decl();
         slf
@@ -209,12 +394,25 @@ impl Model {
         CreateModel {
             model_name: _,
             fields,
-            props,
+            mut props,
             ..
         }: CreateModel,
     ) -> QueryResult<Self> {
         let mut private = ModelPrivate::empty();
-        let mut okay = props.is_empty() & !fields.is_empty();
+        let mut okay = !fields.is_empty();
+        // the only durability that can be picked is none: `with { durability: 'none' }`
+        let ephemeral = match props.remove("durability") {
+            Some(DictEntryGeneric::Data(dc)) => {
+                okay &= dc.try_str() == Some("none");
+                true
+            }
+            Some(DictEntryGeneric::Map(_)) => {
+                okay = false;
+                false
+            }
+            None => false,
+        };
+        okay &= props.is_empty();
         // validate fields
         let mut field_spec = fields.into_iter();
         let mut fields = Fields::idx_init_cap(field_spec.len());
@@ -248,15 +446,22 @@ impl Model {
                 // UNSAFE(@ohsayan): once again, all of this is allocated
                 fields.stseq_ord_key().next().unwrap().clone()
             });
-            let tag = fields.st_get(&last_pk).unwrap().layers()[0].tag;
-            if tag.tag_unique().is_unique() {
-                return Ok(Self::new_with_private(
-                    Uuid::new(),
-                    last_pk,
-                    tag,
-                    fields,
-                    private,
-                ));
+            let pk = fields.st_get(&last_pk).unwrap();
+            let tag = pk.layers()[0].tag;
+            // a default primary key would only ever let us insert one row
+            // the primary key isn't stored in the row, so it can't be indexed for full-text search either
+            if tag.tag_unique().is_unique()
+                & pk.default().is_none()
+                & !pk.is_fulltext()
+                & Self::check_computed(&fields, last_pk.as_str())
+                & Self::check_generated(&fields, last_pk.as_str())
+            {
+                let model = Self::new_with_private(Uuid::new(), last_pk, tag, fields, private);
+                return Ok(if ephemeral {
+                    model.into_ephemeral()
+                } else {
+                    model
+                });
             }
         }
         Err(QueryError::QExecDdlModelBadDefinition)
@@ -295,9 +500,14 @@ impl Model {
                     space.get_uuid(),
                     &model_name,
                     model.get_uuid(),
+                    model.is_ephemeral(),
                 )?;
                 // commit txn
-                match txn_driver.try_commit(txn) {
+                let commit = txn_driver.try_commit(txn);
+                // inserts reserve keys (with the txn driver) while holding the model index, so we can't hold the
+                // driver while we lock the model index below
+                drop(txn_driver);
+                match commit {
                     Ok(()) => {}
                     Err(e) => {
                         // failed to commit, request cleanup
@@ -447,6 +657,8 @@ impl<'a> ModelMutator<'a> {
     pub fn remove_field(&mut self, name: &str) -> bool {
         // remove
         let r = self.model.fields.st_delete(name);
+        self.model.text_indexes.remove(name);
+        self.model.geo_indexes.remove(name);
         // recycle
         let ptr = unsafe { self.model.private.mark_pending_remove(name) };
         // publish delta
@@ -454,6 +666,15 @@ impl<'a> ModelMutator<'a> {
         r
     }
     pub fn add_field(&mut self, name: Box<str>, field: Field) -> bool {
+        if field.is_fulltext() {
+            // existing rows are backfilled with nulls, so there's nothing to index yet
+            self.model
+                .text_indexes
+                .insert(name.clone(), TextIndex::new());
+        }
+        if field.has_geo_index() {
+            self.model.geo_indexes.insert(name.clone(), GeoIndex::new());
+        }
         unsafe {
             // allocate
             let fkeyptr = self.model.private.push_allocated(name);
@@ -465,7 +686,28 @@ impl<'a> ModelMutator<'a> {
         }
     }
     pub fn update_field(&mut self, name: &str, field: Field) -> bool {
-        self.model.fields.st_update(name, field)
+        let layers: Box<[Layer]> = field.layers().into();
+        let retag = match self.model.fields.st_get(name) {
+            Some(current) => current.layers() != &*layers,
+            None => return false,
+        };
+        let r = self.model.fields.st_update(name, field);
+        if retag {
+            // rows are upgraded lazily, when they're next read or written
+            let fkeyptr = unsafe {
+                // UNSAFE(@ohsayan): the key is owned by the model, which also owns the delta
+                self.model
+                    .fields
+                    .stseq_ord_key()
+                    .find(|key| key.as_str() == name)
+                    .unwrap()
+                    .clone()
+            };
+            self.model
+                .delta
+                .unresolved_append_field_retag(fkeyptr, layers);
+        }
+        r
     }
 }
 
@@ -479,11 +721,13 @@ impl<'a> Drop for ModelMutator<'a> {
     Layer
 */
 
-static G: [u8; 15] = [0, 13, 12, 5, 6, 4, 3, 6, 1, 10, 4, 5, 7, 5, 5];
-static S1: [u8; 7] = [13, 9, 4, 14, 2, 4, 7];
-static S2: [u8; 7] = [12, 8, 2, 6, 4, 9, 9];
+static G: [u8; 18] = [
+    0, 13, 10, 1, 13, 17, 15, 2, 0, 10, 9, 7, 17, 17, 1, 0, 10, 17,
+];
+static S1: [u8; 7] = [7, 5, 14, 5, 11, 1, 0];
+static S2: [u8; 7] = [12, 7, 10, 12, 1, 15, 17];
 
-static LUT: [(&str, FullTag); 14] = [
+static LUT: [(&str, FullTag); 18] = [
     ("bool", FullTag::BOOL),
     ("uint8", FullTag::new_uint(TagSelector::UInt8)),
     ("uint16", FullTag::new_uint(TagSelector::UInt16)),
@@ -498,6 +742,10 @@ static LUT: [(&str, FullTag); 14] = [
     ("binary", FullTag::BIN),
     ("string", FullTag::STR),
     ("list", FullTag::LIST),
+    ("timestamp", FullTag::TIMESTAMP),
+    ("sint128", FullTag::SINT128),
+    ("geopoint", FullTag::GEOPOINT),
+    ("dict", FullTag::DICT),
 ];
 
 #[cfg(test)]
@@ -514,16 +762,81 @@ pub static TY_BINARY: &str = LUT[11].0;
 pub static TY_STRING: &str = LUT[12].0;
 #[cfg(test)]
 pub static TY_LIST: &str = LUT[13].0;
+#[cfg(test)]
+pub static TY_TIMESTAMP: &str = LUT[14].0;
+#[cfg(test)]
+pub static TY_SINT128: &str = LUT[15].0;
+#[cfg(test)]
+pub static TY_GEOPOINT: &str = LUT[16].0;
+#[cfg(test)]
+pub static TY_DICT: &str = LUT[17].0;
+
+/// The default value of a (non-list) field
+#[derive(Debug, PartialEq)]
+pub struct FieldDefault(Datacell);
+
+impl FieldDefault {
+    pub fn cell(&self) -> &Datacell {
+        &self.0
+    }
+    /// Create a new cell holding the default value
+    pub fn materialize(&self) -> Datacell {
+        let dc = &self.0;
+        if dc.is_null() {
+            return Datacell::null();
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): +tagck; the default was validated against the (non-list) field
+            match dc.kind() {
+                TagClass::Bool => Datacell::new_bool(dc.read_bool()),
+                TagClass::UnsignedInt
+                | TagClass::SignedInt
+                | TagClass::Float
+                | TagClass::Timestamp => Datacell::new_qw(dc.read_uint(), dc.tag()),
+                TagClass::BigInt => Datacell::new_sint128(dc.read_sint128()),
+                TagClass::Geo => {
+                    let (lat, lon) = dc.read_geopoint();
+                    Datacell::new_geopoint(lat, lon)
+                }
+                TagClass::Bin => Datacell::new_bin(dc.read_bin().into()),
+                TagClass::Str => Datacell::new_str(dc.read_str().into()),
+                TagClass::List => unreachable!("lists can't have a default value"),
+                TagClass::Dict => {
+                    unreachable!("a default is a literal, and there are no dict literals")
+                }
+            }
+        }
+    }
+}
+
+impl Clone for FieldDefault {
+    fn clone(&self) -> Self {
+        Self(self.materialize())
+    }
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Field {
     layers: VInline<1, Layer>,
     nullable: bool,
+    default: Option<FieldDefault>,
+    compute: Option<FieldCompute>,
+    generate: Option<KeyGenerator>,
+    fulltext: bool,
+    dict_schema: Option<DictSchema>,
 }
 
 impl Field {
     pub fn new(layers: VInline<1, Layer>, nullable: bool) -> Self {
-        Self { layers, nullable }
+        Self {
+            layers,
+            nullable,
+            default: None,
+            compute: None,
+            generate: None,
+            fulltext: false,
+            dict_schema: None,
+        }
     }
     pub fn is_nullable(&self) -> bool {
         self.nullable
@@ -531,13 +844,148 @@ impl Field {
     pub fn layers(&self) -> &[Layer] {
         &self.layers
     }
+    /// Returns the type of this field as it is shown to users (for example, `String` or `[UInt8]`)
+    pub fn describe_type(&self) -> String {
+        // TODO(@ohsayan): it's all lists right now, so this is okay but fix it later
+        let depth = self.layers.len() - 1;
+        let mut ret = "[".repeat(depth);
+        ret.push_str(self.layers[depth].tag().tag_selector().name_str());
+        ret.push_str(&"]".repeat(depth));
+        ret
+    }
+    pub fn default(&self) -> Option<&FieldDefault> {
+        self.default.as_ref()
+    }
+    /// Set the value that an insert uses if it skips this field. Returns false if the value isn't valid for
+    /// this field (lists, computed and generated fields can't have defaults)
+    pub fn set_default(&mut self, mut default: Datacell) -> bool {
+        let okay = (self.layers.len() == 1)
+            & self.compute.is_none()
+            & self.generate.is_none()
+            & self.vt_data_fpath(&mut default);
+        if okay {
+            self.default = Some(FieldDefault(default));
+        }
+        okay
+    }
+    pub fn compute(&self) -> Option<&FieldCompute> {
+        self.compute.as_ref()
+    }
+    /// Compute the value of this field from the other fields of the row. Returns false if this isn't a (non-list)
+    /// string field, or if it has a default
+    pub fn set_compute(&mut self, compute: FieldCompute) -> bool {
+        let okay = (self.layers.len() == 1)
+            & (self.layers[0].tag().tag_class() == TagClass::Str)
+            & self.default.is_none()
+            & self.generate.is_none();
+        if okay {
+            self.compute = Some(compute);
+        }
+        okay
+    }
+    pub fn generate(&self) -> Option<KeyGenerator> {
+        self.generate
+    }
+    /// Generate the values of this field if an insert skips it. Returns false if the generator can't generate
+    /// values of this type, or if the field has a default or is computed
+    pub fn set_generate(&mut self, generate: KeyGenerator) -> bool {
+        let okay = generate.check(self) & self.default.is_none() & self.compute.is_none();
+        if okay {
+            self.generate = Some(generate);
+        }
+        okay
+    }
+    /// Returns true if the words in this field are indexed (for `where field contains 'word'`)
+    pub fn is_fulltext(&self) -> bool {
+        self.fulltext
+    }
+    /// Index the words in this field. Returns false if this isn't a (non-list) string field
+    /// Returns true if the field is a point (and not a list of them), which always has a geohash index
+    pub fn has_geo_index(&self) -> bool {
+        (self.layers.len() == 1) & (self.layers[0].tag().tag_class() == TagClass::Geo)
+    }
+    pub fn set_fulltext(&mut self) -> bool {
+        let okay = (self.layers.len() == 1) & (self.layers[0].tag().tag_class() == TagClass::Str);
+        self.fulltext = okay;
+        okay
+    }
+    pub fn dict_schema(&self) -> Option<&DictSchema> {
+        self.dict_schema.as_ref()
+    }
+    /// Restrict the dicts of this field to the given schema. Returns false if this isn't a (non-list) dict field, since
+    /// the dicts in a list can't have a schema (see [`schema`])
+    pub fn set_dict_schema(&mut self, schema: DictSchema) -> bool {
+        let okay = (self.layers.len() == 1) & (self.layers[0].tag().tag_class() == TagClass::Dict);
+        if okay {
+            self.dict_schema = Some(schema);
+        }
+        okay
+    }
+    /// Returns the value to use if an insert skips this field: the default if it has one, null if it's nullable
+    /// and [`None`] if the field can't be skipped
+    pub fn materialize_default(&self) -> Option<Datacell> {
+        match self.default {
+            Some(ref default) => Some(default.materialize()),
+            None if self.nullable => Some(Datacell::null()),
+            None => None,
+        }
+    }
     pub fn parse_layers(spec: Vec<LayerSpec>, nullable: bool) -> QueryResult<Self> {
         let mut layers = spec.into_iter().rev();
         let mut okay = true;
         let mut fin = false;
         let mut layerview = VInline::new();
+        let mut default = None;
+        let mut compute = None;
+        let mut generate = None;
+        let mut fulltext = false;
+        let mut dict_schema = None;
         while (layers.len() != 0) & okay & !fin {
-            let LayerSpec { ty, props } = layers.next().unwrap();
+            let LayerSpec { ty, mut props } = layers.next().unwrap();
+            if layerview.is_empty() {
+                // the default is declared on the outermost layer: `field: type { default: value }`
+                match props.remove("default") {
+                    Some(DictEntryGeneric::Data(dc)) => default = Some(dc),
+                    Some(DictEntryGeneric::Map(_)) => okay = false,
+                    None => {}
+                }
+                // and so is the expression for a computed field: `field: string { compute: "lower(other)" }`
+                match props.remove("compute") {
+                    Some(DictEntryGeneric::Data(dc)) => {
+                        compute = dc.try_str().and_then(FieldCompute::parse);
+                        okay &= compute.is_some();
+                    }
+                    Some(DictEntryGeneric::Map(_)) => okay = false,
+                    None => {}
+                }
+                // and how the key is generated: `id: uint64 { generate: "increment" }`
+                match props.remove("generate") {
+                    Some(DictEntryGeneric::Data(dc)) => {
+                        generate = dc.try_str().and_then(KeyGenerator::parse);
+                        okay &= generate.is_some();
+                    }
+                    Some(DictEntryGeneric::Map(_)) => okay = false,
+                    None => {}
+                }
+                // and if the words in it are indexed: `body: string { fulltext: true }`
+                match props.remove("fulltext") {
+                    Some(DictEntryGeneric::Data(dc)) => match dc.try_bool() {
+                        Some(ft) => fulltext = ft,
+                        None => okay = false,
+                    },
+                    Some(DictEntryGeneric::Map(_)) => okay = false,
+                    None => {}
+                }
+                // and the schema of a dict: `profile: dict { schema: { name: 'string', age: 'uint8' } }`
+                match props.remove("schema") {
+                    Some(DictEntryGeneric::Map(decl)) => {
+                        dict_schema = DictSchema::parse(decl);
+                        okay &= dict_schema.is_some();
+                    }
+                    Some(DictEntryGeneric::Data(_)) => okay = false,
+                    None => {}
+                }
+            }
             okay &= props.is_empty(); // FIXME(@ohsayan): you know what to do here
             match Layer::get_layer(&ty) {
                 Some(l) => {
@@ -549,22 +997,40 @@ impl Field {
         }
         okay &= fin & (layers.len() == 0);
         if okay {
-            Ok(Self {
-                layers: layerview,
-                nullable,
-            })
-        } else {
-            Err(QueryError::QExecDdlInvalidTypeDefinition)
+            let mut field = Self::new(layerview, nullable);
+            if let Some(default) = default {
+                okay &= field.set_default(default);
+            }
+            if let Some(compute) = compute {
+                okay &= field.set_compute(compute);
+            }
+            if let Some(generate) = generate {
+                okay &= field.set_generate(generate);
+            }
+            if fulltext {
+                okay &= field.set_fulltext();
+            }
+            if let Some(dict_schema) = dict_schema {
+                okay &= field.set_dict_schema(dict_schema);
+            }
+            if okay {
+                return Ok(field);
+            }
         }
+        Err(QueryError::QExecDdlInvalidTypeDefinition)
     }
     #[inline(always)]
     fn compute_index(&self, dc: &Datacell) -> usize {
+        let class = self.layers[0].tag.tag_class();
+        let coerce = can_coerce(class, dc.kind()) & !dc.is_null();
         if {
             ((!self.is_nullable()) & dc.is_null())
-                | ((self.layers[0].tag.tag_class() != dc.kind()) & !dc.is_null())
+                | ((class != dc.kind()) & !dc.is_null() & !coerce)
         } {
             // illegal states: (1) bad null (2) tags don't match
-            7
+            11
+        } else if coerce {
+            class.value_word()
         } else {
             dc.kind().value_word()
         }
@@ -572,11 +1038,19 @@ impl Field {
     pub fn vt_data_fpath(&self, data: &mut Datacell) -> bool {
         if (self.layers.len() == 1) | (data.is_null()) {
             layertrace("fpath");
-            unsafe { VTFN[self.compute_index(data)](self.layers()[0], data) }
+            let okay = unsafe { VTFN[self.compute_index(data)](self.layers()[0], data) };
+            okay && match self.dict_schema {
+                Some(ref schema) if !data.is_null() => schema.vt_data(data),
+                _ => true,
+            }
         } else {
             Self::rvt_data(self.layers(), data)
         }
     }
+    /// Validate a single element that's being added to this (list) field
+    pub fn vt_list_element(&self, data: &mut Datacell) -> bool {
+        (self.layers.len() > 1) && Self::rvt_data(&self.layers[1..], data)
+    }
     fn rvt_data(layers: &[Layer], data: &mut Datacell) -> bool {
         let layer = layers[0];
         let layers = &layers[1..];
@@ -598,9 +1072,9 @@ impl Field {
                 }
                 okay
             }
-            (tag_a, tag_b) if tag_a == tag_b => {
+            (tag_a, tag_b) if (tag_a == tag_b) | can_coerce(tag_a, tag_b) => {
                 unsafe {
-                    // UNSAFE(@ohsayan): same tags and lists have non-null elements
+                    // UNSAFE(@ohsayan): same (or coercible) tags and lists have non-null elements
                     VTFN[tag_a.value_word()](layer, data)
                 }
             }
@@ -658,6 +1132,18 @@ impl Layer {
     pub const fn list() -> Self {
         Self::empty(FullTag::LIST)
     }
+    pub const fn timestamp() -> Self {
+        Self::empty(FullTag::TIMESTAMP)
+    }
+    pub const fn sint128() -> Self {
+        Self::empty(FullTag::SINT128)
+    }
+    pub const fn geopoint() -> Self {
+        Self::empty(FullTag::GEOPOINT)
+    }
+    pub const fn dict() -> Self {
+        Self::empty(FullTag::DICT)
+    }
 }
 
 impl Layer {
@@ -680,10 +1166,14 @@ impl Layer {
             tot += v[i % v.len()] as u16 * key[i] as u16;
             i += 1;
         }
-        tot % 15
+        tot % 18
     }
     fn pf(key: &[u8]) -> u16 {
-        (G[Self::hf(key, S1) as usize] as u16 + G[Self::hf(key, S2) as usize] as u16) % 15
+        (G[Self::hf(key, S1) as usize] as u16 + G[Self::hf(key, S2) as usize] as u16) % 18
+    }
+    /// Returns the keyword that declares this layer
+    fn name(&self) -> &'static str {
+        LUT.iter().find(|(_, tag)| *tag == self.tag).unwrap().0
     }
     fn get_layer(ident: &str) -> Option<Self> {
         let idx = Self::pf(ident.as_bytes()) as usize;
@@ -718,7 +1208,22 @@ pub(super) fn layer_traces() -> Box<[Box<str>]> {
     })
 }
 
-static VTFN: [unsafe fn(Layer, &mut Datacell) -> bool; 8] = [
+/// Returns true if (non-null) data of the given class can be converted into data for a layer of the given class.
+/// Unsigned integers are accepted as timestamps (the micros since the epoch), integers and strings (with the digits)
+/// as 128-bit integers and `"lat,lon"` strings as points, since there are no literals for them
+fn can_coerce(layer: TagClass, data: TagClass) -> bool {
+    match layer {
+        TagClass::Timestamp => data == TagClass::UnsignedInt,
+        TagClass::BigInt => matches!(
+            data,
+            TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Str
+        ),
+        TagClass::Geo => data == TagClass::Str,
+        _ => false,
+    }
+}
+
+static VTFN: [unsafe fn(Layer, &mut Datacell) -> bool; 12] = [
     vt_bool,
     vt_uint,
     vt_sint,
@@ -726,6 +1231,10 @@ static VTFN: [unsafe fn(Layer, &mut Datacell) -> bool; 8] = [
     vt_bin,
     vt_str,
     vt_list,
+    vt_timestamp,
+    vt_sint128,
+    vt_geopoint,
+    vt_dict,
     |_, _| false,
 ];
 unsafe fn vt_bool(_: Layer, _: &mut Datacell) -> bool {
@@ -759,3 +1268,39 @@ unsafe fn vt_list(_: Layer, _: &mut Datacell) -> bool {
     layertrace("list");
     true
 }
+unsafe fn vt_timestamp(l: Layer, dc: &mut Datacell) -> bool {
+    layertrace("timestamp");
+    dc.set_tag(l.tag());
+    true
+}
+unsafe fn vt_sint128(_: Layer, dc: &mut Datacell) -> bool {
+    layertrace("sint128");
+    let int = match dc.kind() {
+        TagClass::BigInt => return true,
+        TagClass::UnsignedInt => dc.read_uint() as i128,
+        TagClass::SignedInt => dc.read_sint() as i128,
+        _ => match dc.read_str().parse() {
+            Ok(int) => int,
+            Err(_) => return false,
+        },
+    };
+    *dc = Datacell::new_sint128(int);
+    true
+}
+unsafe fn vt_dict(_: Layer, _: &mut Datacell) -> bool {
+    layertrace("dict");
+    true
+}
+unsafe fn vt_geopoint(_: Layer, dc: &mut Datacell) -> bool {
+    layertrace("geopoint");
+    if dc.kind() == TagClass::Geo {
+        return true;
+    }
+    match geo::parse_point(dc.read_str()) {
+        Some((lat, lon)) => {
+            *dc = Datacell::new_geopoint(lat, lon);
+            true
+        }
+        None => false,
+    }
+}
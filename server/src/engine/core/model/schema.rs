@@ -0,0 +1,147 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    Dict schemas
+    ---
+    Dict fields are schemaless, but a (non-list) dict field can declare the keys that its dicts can have and the types
+    of their values:
+
+        create model myspace.users(username: string, profile: dict { schema: { name: 'string', age: 'uint8' } })
+
+    A dict that is written to the field can't have any other keys (but it can skip some), and its values are validated
+    (and converted) just like the fields of a row. Since the keys are known, these dicts are persisted as the list of
+    their values in the order of the schema (with a null for every key that a dict skips) instead of with their keys.
+    This list is decoded like any other list, so it's turned back into a dict when the row is restored.
+
+    Only the field itself can be a dict with a schema. The dicts in a list (`list { type: dict }`) are always schemaless
+    since only the cells of a row (and not the cells nested in them) can be persisted without their keys, so declaring
+    a schema on them is rejected as an invalid type definition.
+*/
+
+use {
+    super::{Field, Layer},
+    crate::engine::data::{
+        cell::Datacell,
+        dict::{DictCell, DictEntryGeneric},
+        tag::{DataTag, TagClass},
+        DictGeneric,
+    },
+};
+
+#[derive(Debug, PartialEq, Clone)]
+/// The keys that the dicts of a field can have (in order), and the types of their values
+pub struct DictSchema {
+    fields: Box<[(Box<str>, Layer)]>,
+}
+
+impl DictSchema {
+    /// Parse the declaration of a schema (`{ key: 'type', ... }`). The types can't be lists or dicts
+    pub fn parse(decl: DictGeneric) -> Option<Self> {
+        let mut fields = Vec::with_capacity(decl.len());
+        for (key, ty) in decl {
+            let DictEntryGeneric::Data(ty) = ty else {
+                return None;
+            };
+            fields.push((key, scalar_layer(ty.try_str()?)?));
+        }
+        Self::new(fields)
+    }
+    /// Returns the schema as it's persisted: a dict of the keys and the names of their types
+    pub fn to_cell(&self) -> Datacell {
+        Datacell::new_dict(
+            self.fields
+                .iter()
+                .map(|(key, layer)| (key.clone(), Datacell::new_str(layer.name().into())))
+                .collect(),
+        )
+    }
+    /// Decode a persisted schema (see [`Self::to_cell`])
+    pub fn from_cell(cell: Datacell) -> Option<Self> {
+        let dict = cell.into_dict()?;
+        let mut fields = Vec::with_capacity(dict.len());
+        for (key, ty) in dict {
+            fields.push((key, scalar_layer(ty.try_str()?)?));
+        }
+        Self::new(fields)
+    }
+    fn new(mut fields: Vec<(Box<str>, Layer)>) -> Option<Self> {
+        fields.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        (!fields.is_empty()).then(|| Self {
+            fields: fields.into_boxed_slice(),
+        })
+    }
+    pub fn fields(&self) -> &[(Box<str>, Layer)] {
+        &self.fields
+    }
+    /// Returns the values of the dict in the order of the schema ([`None`] for the keys that it doesn't have)
+    pub fn values<'a>(
+        &'a self,
+        dict: &'a DictCell,
+    ) -> impl Iterator<Item = Option<&'a Datacell>> + 'a {
+        self.fields.iter().map(|(key, _)| dict.get(key))
+    }
+    /// Validate (and convert) the entries of a (non-null) dict
+    pub(super) fn vt_data(&self, data: &mut Datacell) -> bool {
+        let Some(mut dict) = core::mem::replace(data, Datacell::null()).into_dict() else {
+            return false;
+        };
+        let okay = dict.iter_mut().all(|(key, value)| {
+            match self.fields.binary_search_by(|(k, _)| k.as_ref().cmp(key)) {
+                Ok(i) => {
+                    !value.is_null()
+                        && Field::rvt_data(core::slice::from_ref(&self.fields[i].1), value)
+                }
+                Err(_) => false,
+            }
+        });
+        *data = Datacell::new_dict(dict);
+        okay
+    }
+    /// Turn a dict that was persisted with this schema (as the list of its values) back into a dict. Anything else is
+    /// left as is
+    pub fn unpack(&self, data: &mut Datacell) {
+        if data.kind() != TagClass::List {
+            return;
+        }
+        let values = core::mem::replace(data, Datacell::null())
+            .into_list()
+            .unwrap();
+        *data = Datacell::new_dict(
+            self.fields
+                .iter()
+                .zip(values)
+                .filter(|(_, value)| !value.is_null())
+                .map(|((key, _), value)| (key.clone(), value))
+                .collect(),
+        );
+    }
+}
+
+fn scalar_layer(ty: &str) -> Option<Layer> {
+    Layer::get_layer(ty)
+        .filter(|layer| !matches!(layer.tag().tag_class(), TagClass::List | TagClass::Dict))
+}
@@ -0,0 +1,188 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{DeltaVersion, Model},
+    crate::engine::{
+        core::index::{DcFieldIndex, PrimaryIndexKey, Row, RowData},
+        idx::{IndexBaseSpec, MTIndexExt, STIndex},
+        sync::atm::Guard,
+    },
+    parking_lot::{Mutex, RwLock},
+    std::{
+        collections::{HashMap, HashSet},
+        sync::Arc,
+    },
+};
+
+/// A copy of a row's data, as it was before it was changed (or deleted)
+#[derive(Debug)]
+struct Preimage {
+    row: Row,
+    fields: DcFieldIndex,
+}
+
+/// The cut that a snapshot reads: every change versioned before `version`
+///
+/// Writers save a preimage of a row here before they change or delete a row that the snapshot can see. Preimages are
+/// keyed by the row's identity (and not its primary key), so that a row that was deleted and inserted again is still
+/// read as the old row
+#[derive(Debug)]
+pub(super) struct SnapshotCut {
+    version: DeltaVersion,
+    preimages: Mutex<HashMap<usize, Arc<Preimage>>>,
+}
+
+/// The snapshots that are currently pinned on a model
+#[derive(Debug)]
+pub(super) struct SnapshotRegistry {
+    cuts: RwLock<Vec<Arc<SnapshotCut>>>,
+}
+
+fn row_id(row: &Row) -> usize {
+    row.d_data() as *const _ as usize
+}
+
+impl SnapshotRegistry {
+    pub(super) fn new() -> Self {
+        Self {
+            cuts: RwLock::new(Vec::new()),
+        }
+    }
+    /// Save a copy of the row for every pinned snapshot that can see it
+    ///
+    /// This must be called with the row locked, after the version of the change was created and before the row is
+    /// changed or deleted. Since the cut's version is read under the lock that we read the cuts with, any snapshot
+    /// that we don't see here was pinned after our version was created, so it reads the changed row instead
+    pub(super) fn preserve(&self, row: &Row, data: &RowData) {
+        let cuts = self.cuts.read();
+        for cut in cuts
+            .iter()
+            .filter(|cut| data.get_txn_revised() < cut.version)
+        {
+            cut.preimages.lock().entry(row_id(row)).or_insert_with(|| {
+                let mut fields = DcFieldIndex::idx_init_cap(data.fields().st_len());
+                data.fields().st_iter_kv().for_each(|(id, dc)| {
+                    fields.st_insert(
+                        unsafe {
+                            // UNSAFE(@ohsayan): the field names live as long as the model (and so do we)
+                            id.clone()
+                        },
+                        dc.clone(),
+                    );
+                });
+                Arc::new(Preimage {
+                    row: row.clone(),
+                    fields,
+                })
+            });
+        }
+    }
+}
+
+/// A consistent view of a model's data, as it was when the snapshot was pinned
+///
+/// A long scan (for example, to feed an export) that reads the primary index directly can observe a mix of versions
+/// since writers keep running. Reading through a snapshot doesn't block writers: instead, they save a copy of any row
+/// that the snapshot can see before they change it, so the snapshot should be dropped as soon as it is read
+pub struct Snapshot<'a> {
+    mdl: &'a Model,
+    cut: Arc<SnapshotCut>,
+}
+
+impl Model {
+    /// Pin a snapshot of this model's data
+    ///
+    /// The model must not be altered while the snapshot is held (for example, by holding the model index lock)
+    pub fn pin_snapshot(&self) -> Snapshot<'_> {
+        // block inserts and deletes so that every change before our version is complete
+        let _latch = self.primary_index().acquire_exclusive();
+        // a writer that has already created its version (but hasn't looked for snapshots yet) either sees this cut
+        // or has a version that is before it
+        let mut cuts = self.delta_state().snapshots().cuts.write();
+        let cut = Arc::new(SnapshotCut {
+            version: self.delta_state().data_current_version(),
+            preimages: Mutex::new(HashMap::new()),
+        });
+        cuts.push(cut.clone());
+        drop(cuts);
+        Snapshot { mdl: self, cut }
+    }
+}
+
+impl<'a> Snapshot<'a> {
+    /// Returns the version of this snapshot. It includes every change versioned before this
+    pub fn version(&self) -> DeltaVersion {
+        self.cut.version
+    }
+    /// Visit every row in the snapshot (in no particular order), stopping at the first error
+    pub fn scan<E>(
+        &self,
+        g: &Guard,
+        mut f: impl FnMut(&PrimaryIndexKey, &DcFieldIndex) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let ds = self.mdl.delta_state();
+        let mut seen = HashSet::new();
+        for row in self.mdl.primary_index().__raw_index().mt_iter_entry(g) {
+            let data = row.resolve_schema_deltas_and_freeze(ds);
+            if data.get_txn_revised() < self.cut.version {
+                f(row.d_key(), data.fields())?;
+            } else {
+                // the row was changed after we were pinned (or didn't exist then)
+                let preimage = self.cut.preimages.lock().get(&row_id(row)).cloned();
+                drop(data);
+                match preimage {
+                    Some(preimage) => f(row.d_key(), &preimage.fields)?,
+                    None => continue,
+                }
+            }
+            seen.insert(row_id(row));
+        }
+        // rows that were deleted after we were pinned
+        let deleted: Vec<_> = self
+            .cut
+            .preimages
+            .lock()
+            .iter()
+            .filter(|(id, _)| !seen.contains(*id))
+            .map(|(_, preimage)| preimage.clone())
+            .collect();
+        deleted
+            .iter()
+            .try_for_each(|preimage| f(preimage.row.d_key(), &preimage.fields))
+    }
+}
+
+impl<'a> Drop for Snapshot<'a> {
+    fn drop(&mut self) {
+        self.mdl
+            .delta_state()
+            .snapshots()
+            .cuts
+            .write()
+            .retain(|cut| !Arc::ptr_eq(cut, &self.cut));
+    }
+}
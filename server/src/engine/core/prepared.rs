@@ -0,0 +1,105 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Prepared statements
+//!
+//! A client can prepare a query once and then execute it any number of times, sending just the params. The query is
+//! lexed (and checked to be a statement) when it's prepared, and executing it only binds the params to the
+//! placeholders in its tokens (see [`PreparedTokens`]). Since the params are never spliced into the query, clients
+//! don't have to escape them either
+//!
+//! Prepared statements belong to the connection that prepared them, and are discarded once it's closed
+
+use {
+    crate::engine::{
+        error::{QueryError, QueryResult},
+        ql::{
+            ast::State,
+            lex::{PreparedTokens, SecureLexer, Token},
+        },
+    },
+    std::{collections::HashMap, sync::Arc},
+};
+
+/// The most statements that a connection can have prepared
+pub const MAX_PREPARED_STATEMENTS: usize = 256;
+
+#[derive(Debug, PartialEq)]
+pub struct PreparedStatement {
+    // NB: the tokens borrow from the query, so they must be dropped first
+    tokens: PreparedTokens<'static>,
+    query: Box<[u8]>,
+}
+
+impl PreparedStatement {
+    fn new(query: &[u8]) -> QueryResult<Self> {
+        let query: Box<[u8]> = query.into();
+        let tokens = SecureLexer::lex_prepared(unsafe {
+            // UNSAFE(@ohsayan): the query is on the heap (so it never moves) and it lives as long as the tokens do
+            core::mem::transmute::<&[u8], &'static [u8]>(&query)
+        })?;
+        State::new_inplace(tokens.tokens()).try_statement()?;
+        Ok(Self { tokens, query })
+    }
+    /// The text of the query (with placeholders for the params)
+    pub fn query(&self) -> &[u8] {
+        &self.query
+    }
+    /// Returns the tokens of the query with the given params bound to it
+    pub fn bind<'a>(&'a self, params: &'a [u8]) -> QueryResult<Vec<Token<'a>>> {
+        self.tokens.bind(params)
+    }
+}
+
+/// The statements that a connection has prepared
+#[derive(Debug, PartialEq, Default)]
+pub struct PreparedStatements {
+    next_id: u64,
+    statements: HashMap<u64, Arc<PreparedStatement>>,
+}
+
+impl PreparedStatements {
+    /// Prepare the query, returning the id of the statement
+    pub fn prepare(&mut self, query: &[u8]) -> QueryResult<u64> {
+        if self.statements.len() >= MAX_PREPARED_STATEMENTS {
+            return Err(QueryError::QLPreparedStatementLimit);
+        }
+        let statement = PreparedStatement::new(query)?;
+        // statements are numbered from 1 (in the order that they were prepared)
+        self.next_id += 1;
+        self.statements.insert(self.next_id, Arc::new(statement));
+        Ok(self.next_id)
+    }
+    /// Returns the statement with the given id (which the client sends as a decimal)
+    pub fn get(&self, id: &[u8]) -> QueryResult<Arc<PreparedStatement>> {
+        core::str::from_utf8(id)
+            .ok()
+            .and_then(|id| id.parse::<u64>().ok())
+            .and_then(|id| self.statements.get(&id))
+            .cloned()
+            .ok_or(QueryError::QLUnknownPreparedStatement)
+    }
+}
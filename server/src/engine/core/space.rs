@@ -155,6 +155,10 @@ impl Space {
             space,
             if_not_exists,
         } = Self::process_create(space)?;
+        if &*space_name == super::catalog::SYS_SPACE {
+            // reserved for the system catalog
+            return Err(QueryError::QExecDdlObjectAlreadyExists);
+        }
         // lock the global namespace
         global.namespace().ddl_with_spaces_write(|spaces| {
             if spaces.st_contains(&space_name) {
@@ -0,0 +1,287 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{catalog, dml::encode_cell, space::Space},
+    data::cell::Datacell,
+    error::{QueryError, QueryResult},
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    mem::IntegerRepr,
+    net::protocol::{ClientLocalState, Response, ResponseType},
+    ql::{
+        ast::parse_ast_node_full,
+        dml::sel::{SelectAllStatement, SelectStatement},
+        tests::lex_insecure,
+    },
+};
+
+fn select(
+    global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    q: &str,
+) -> QueryResult<Response> {
+    let tok = lex_insecure(q.as_bytes()).unwrap();
+    if q.starts_with("select all") {
+        let stmt = parse_ast_node_full::<SelectAllStatement>(&tok[2..]).unwrap();
+        assert!(catalog::is_catalog(global, stmt.entity));
+        catalog::select_all_resp(global, c, stmt)
+    } else {
+        let stmt = parse_ast_node_full::<SelectStatement>(&tok[1..]).unwrap();
+        assert!(catalog::is_catalog(global, stmt.entity()));
+        catalog::select_resp(global, c, stmt)
+    }
+}
+
+fn rows<const N: usize>(rows: Vec<[Datacell; N]>) -> Response {
+    let mut data = vec![];
+    for row in rows.iter() {
+        IntegerRepr::scoped(N as u64, |repr| data.extend(repr));
+        data.push(b'\n');
+        row.iter().for_each(|cell| encode_cell(&mut data, cell));
+    }
+    Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: rows.len(),
+        data: data.into(),
+    }
+}
+
+fn s(s: &str) -> Datacell {
+    Datacell::new_str(s.into())
+}
+
+fn u(u: u64) -> Datacell {
+    Datacell::new_uint_default(u)
+}
+
+fn setup(global: &impl GlobalInstanceLike) {
+    for space in ["create space myspace", "create space otherspace"] {
+        let tok = lex_insecure(space.as_bytes()).unwrap();
+        Space::transactional_exec_create(global, parse_ast_node_full(&tok[2..]).unwrap()).unwrap();
+    }
+    for model in [
+        "create model myspace.users(username: string, null email: string)",
+        "create model myspace.logs(id: uint64, msg: string)",
+        "create model otherspace.kv(k: binary, v: binary)",
+    ] {
+        super::ddl_model::exec_create(global, model, false).unwrap();
+    }
+    let sys = global.sys_store();
+    sys.create_new_user("sayan".into(), "password12345678".into())
+        .unwrap();
+    sys.grant_user("sayan", "otherspace", None).unwrap();
+}
+
+#[test]
+fn catalog_spaces_and_models() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let root = ClientLocalState::new_test("root", true);
+    let sayan = ClientLocalState::new_test("sayan", false);
+    assert_eq!(
+        select(&global, &root, "select * from sys.spaces").unwrap(),
+        rows(vec![[s("myspace"), u(2)], [s("otherspace"), u(1)]])
+    );
+    // only the models that the user can see are counted
+    assert_eq!(
+        select(&global, &sayan, "select * from sys.spaces").unwrap(),
+        rows(vec![[s("myspace"), u(0)], [s("otherspace"), u(1)]])
+    );
+    assert_eq!(
+        select(
+            &global,
+            &root,
+            "select space_name, name, primary_key, rows from sys.models"
+        )
+        .unwrap(),
+        rows(vec![
+            [s("myspace"), s("logs"), s("id"), u(0)],
+            [s("myspace"), s("users"), s("username"), u(0)],
+            [s("otherspace"), s("kv"), s("k"), u(0)],
+        ])
+    );
+    assert_eq!(
+        select(&global, &sayan, "select name from sys.models").unwrap(),
+        rows(vec![[s("kv")]])
+    );
+}
+
+#[test]
+fn catalog_fields_where() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let root = ClientLocalState::new_test("root", true);
+    assert_eq!(
+        select(
+            &global,
+            &root,
+            "select name, field_type, nullable, primary_key from sys.fields where space_name = 'myspace' and model_name = 'users'"
+        )
+        .unwrap(),
+        rows(vec![
+            [
+                s("username"),
+                s("String"),
+                Datacell::new_bool(false),
+                Datacell::new_bool(true)
+            ],
+            [
+                s("email"),
+                s("String"),
+                Datacell::new_bool(true),
+                Datacell::new_bool(false)
+            ],
+        ])
+    );
+    assert_eq!(
+        select(
+            &global,
+            &root,
+            "select model_name, name from sys.fields where name != 'msg' and space_name = 'myspace'"
+        )
+        .unwrap(),
+        rows(vec![
+            [s("logs"), s("id")],
+            [s("users"), s("username")],
+            [s("users"), s("email")],
+        ])
+    );
+}
+
+#[test]
+fn catalog_users() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let root = ClientLocalState::new_test("root", true);
+    let sayan = ClientLocalState::new_test("sayan", false);
+    assert_eq!(
+        select(&global, &root, "select name, root, grants from sys.users").unwrap(),
+        rows(vec![
+            [s("root"), Datacell::new_bool(true), Datacell::null()],
+            [
+                s("sayan"),
+                Datacell::new_bool(false),
+                Datacell::new_list(vec![s("otherspace")])
+            ],
+        ])
+    );
+    // other users only see themselves
+    assert_eq!(
+        select(
+            &global,
+            &sayan,
+            "select name, grants[0], grants[1] from sys.users"
+        )
+        .unwrap(),
+        rows(vec![[s("sayan"), s("otherspace"), Datacell::null()]])
+    );
+}
+
+#[test]
+fn catalog_select_all_window() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let root = ClientLocalState::new_test("root", true);
+    assert_eq!(
+        select(
+            &global,
+            &root,
+            "select all name from sys.models order by name desc limit 2"
+        )
+        .unwrap(),
+        rows(vec![[s("users")], [s("logs")]])
+    );
+    assert_eq!(
+        select(
+            &global,
+            &root,
+            "select all name from sys.models limit 5 offset 2"
+        )
+        .unwrap(),
+        rows(vec![[s("kv")]])
+    );
+    assert_eq!(
+        select(
+            &global,
+            &root,
+            "select all name from sys.users order by grants limit 5"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+}
+
+#[test]
+fn catalog_metrics() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let sayan = ClientLocalState::new_test("sayan", false);
+    assert_eq!(
+        select(
+            &global,
+            &sayan,
+            "select kind, sample from sys.metrics where name = 'skytable_models'"
+        )
+        .unwrap(),
+        rows(vec![[s("gauge"), u(3)]])
+    );
+    // per-model samples are only listed for models that the user can see
+    assert_eq!(
+        select(
+            &global,
+            &sayan,
+            "select space_name, model_name from sys.metrics where name = 'skytable_model_rows'"
+        )
+        .unwrap(),
+        rows(vec![[s("otherspace"), s("kv")]])
+    );
+}
+
+#[test]
+fn catalog_errors() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let root = ClientLocalState::new_test("root", true);
+    assert_eq!(
+        select(&global, &root, "select * from sys.nope").unwrap_err(),
+        QueryError::QExecObjectNotFound
+    );
+    assert_eq!(
+        select(&global, &root, "select nope from sys.models").unwrap_err(),
+        QueryError::QExecUnknownField
+    );
+    assert_eq!(
+        select(&global, &root, "select * from sys.models where nope = 1").unwrap_err(),
+        QueryError::QExecUnknownField
+    );
+    // the space is reserved
+    let tok = lex_insecure(b"create space sys").unwrap();
+    assert_eq!(
+        Space::transactional_exec_create(&global, parse_ast_node_full(&tok[2..]).unwrap())
+            .unwrap_err(),
+        QueryError::QExecDdlObjectAlreadyExists
+    );
+}
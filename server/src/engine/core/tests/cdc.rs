@@ -0,0 +1,194 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{dcl, dml, space::Space},
+    data::cell::Datacell,
+    error::QueryError,
+    fractal::{
+        cdc::{Subscription, CDC_SUBSCRIBER_BUFFER},
+        test_utils::TestGlobal,
+        GlobalInstanceLike,
+    },
+    net::protocol::{ClientLocalState, Response, ResponseType},
+    ql::{ast::parse_ast_node_full, tests::lex_insecure},
+};
+
+fn setup(global: &impl GlobalInstanceLike) {
+    let tok = lex_insecure(b"create space myspace").unwrap();
+    Space::transactional_exec_create(global, parse_ast_node_full(&tok[2..]).unwrap()).unwrap();
+    for model in [
+        "create model myspace.users(username: string, age: uint64)",
+        "create model myspace.logs(id: uint64, msg: string)",
+    ] {
+        super::ddl_model::exec_create(global, model, false).unwrap();
+    }
+}
+
+fn subscribe(global: &impl GlobalInstanceLike, models: &[(&str, &str)]) -> Subscription {
+    let root = ClientLocalState::new_test("root", true);
+    let models = models.iter().map(|&entity| entity.into()).collect();
+    match dcl::subscribe(global, &root, models).unwrap() {
+        Response::Subscription(sub) => sub,
+        r => panic!("expected a subscription, got {r:?}"),
+    }
+}
+
+fn run(global: &impl GlobalInstanceLike, queries: &[&str]) {
+    for query in queries {
+        let tok = lex_insecure(query.as_bytes()).unwrap();
+        let stmt = &tok[1..];
+        match query.split(' ').next().unwrap() {
+            "insert" => dml::insert(global, parse_ast_node_full(stmt).unwrap()),
+            "update" => dml::update(global, parse_ast_node_full(stmt).unwrap()),
+            "delete" => dml::delete(global, parse_ast_node_full(stmt).unwrap()),
+            _ => unreachable!(),
+        }
+        .unwrap();
+    }
+}
+
+fn event<const N: usize>(model: &str, txn_id: u64, kind: &str, cells: [Datacell; N]) -> Response {
+    let mut data = vec![];
+    for cell in [
+        Datacell::new_str("myspace".into()),
+        Datacell::new_str(model.into()),
+        Datacell::new_uint_default(txn_id),
+        Datacell::new_str(kind.into()),
+    ]
+    .iter()
+    .chain(cells.iter())
+    {
+        dml::encode_cell(&mut data, cell);
+    }
+    Response::Serialized {
+        ty: ResponseType::Event,
+        size: N + 4,
+        data: data.into(),
+    }
+}
+
+#[test]
+fn subscribe_receives_changes() {
+    let global = TestGlobal::new_with_vfs_driver("subscribe_receives_changes.global.db-tlog");
+    setup(&global);
+    // changes made before subscribing aren't sent
+    run(&global, &["insert into myspace.users('elena', 20)"]);
+    let mut sub = subscribe(&global, &[("myspace", "users"), ("myspace", "logs")]);
+    run(
+        &global,
+        &[
+            "insert into myspace.users('sayan', 10)",
+            "update myspace.users set age += 1 where username = 'sayan'",
+            "insert into myspace.logs(1, 'hello')",
+            "delete from myspace.users where username = 'sayan'",
+        ],
+    );
+    let sayan = |age: u64| {
+        [
+            Datacell::new_str("sayan".into()),
+            Datacell::new_uint_default(age),
+        ]
+    };
+    for expected in [
+        event("users", 1, "insert", sayan(10)),
+        event("users", 2, "update", sayan(11)),
+        event(
+            "logs",
+            0,
+            "insert",
+            [
+                Datacell::new_uint_default(1),
+                Datacell::new_str("hello".into()),
+            ],
+        ),
+        event("users", 3, "delete", sayan(11)),
+    ] {
+        assert_eq!(sub.try_next().unwrap().unwrap(), expected);
+    }
+    assert!(sub.try_next().is_none());
+}
+
+#[test]
+fn subscribe_needs_access() {
+    let global = TestGlobal::new_with_vfs_driver("subscribe_needs_access.global.db-tlog");
+    setup(&global);
+    let user = ClientLocalState::new_test("sayan", false);
+    assert_eq!(
+        dcl::subscribe(&global, &user, vec![("myspace", "users").into()]).unwrap_err(),
+        QueryError::SysPermissionDenied
+    );
+    let root = ClientLocalState::new_test("root", true);
+    assert_eq!(
+        dcl::subscribe(
+            &global,
+            &root,
+            vec![("myspace", "users").into(), ("myspace", "nope").into()]
+        )
+        .unwrap_err(),
+        QueryError::QExecObjectNotFound
+    );
+    // the failed subscription was detached from the models that it did find
+    assert!(!has_subscribers(&global, "users"));
+}
+
+#[test]
+fn dropped_subscription_is_detached() {
+    let global = TestGlobal::new_with_vfs_driver("dropped_subscription_is_detached.global.db-tlog");
+    setup(&global);
+    let sub = subscribe(&global, &[("myspace", "users")]);
+    assert!(has_subscribers(&global, "users"));
+    assert!(!has_subscribers(&global, "logs"));
+    drop(sub);
+    assert!(!has_subscribers(&global, "users"));
+}
+
+#[test]
+fn lagging_subscriber_is_cut_off() {
+    let global = TestGlobal::new_with_vfs_driver("lagging_subscriber_is_cut_off.global.db-tlog");
+    setup(&global);
+    let mut sub = subscribe(&global, &[("myspace", "logs")]);
+    for id in 0..=CDC_SUBSCRIBER_BUFFER {
+        run(
+            &global,
+            &[&format!("insert into myspace.logs({id}, 'hello')")],
+        );
+    }
+    assert_eq!(
+        sub.try_next().unwrap().unwrap_err(),
+        QueryError::SysChangeFeedLagged
+    );
+    assert!(!has_subscribers(&global, "logs"));
+}
+
+fn has_subscribers(global: &impl GlobalInstanceLike, model: &str) -> bool {
+    global
+        .namespace()
+        .with_model(("myspace", model).into(), |mdl| {
+            Ok(mdl.delta_state().change_feed().has_subscribers())
+        })
+        .unwrap()
+}
@@ -0,0 +1,233 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{
+        ddl_misc,
+        dml::{self, encode_cell},
+        space::Space,
+    },
+    data::cell::Datacell,
+    error::{QueryError, QueryResult},
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike, ModelUniqueID},
+    mem::IntegerRepr,
+    net::protocol::{ClientLocalState, Response, ResponseType},
+    ql::{ast::parse_ast_node_full, ddl::Inspect, dml::ins::InsertStatement, tests::lex_insecure},
+};
+
+fn inspect(
+    global: &impl GlobalInstanceLike,
+    c: &ClientLocalState,
+    q: &str,
+) -> QueryResult<Response> {
+    let tok = lex_insecure(q.as_bytes()).unwrap();
+    let stmt = parse_ast_node_full::<Inspect>(&tok[1..]).unwrap();
+    ddl_misc::inspect(global, c, stmt)
+}
+
+fn insert(global: &impl GlobalInstanceLike, q: &str) {
+    let tok = lex_insecure(q.as_bytes()).unwrap();
+    dml::insert(
+        global,
+        parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap(),
+    )
+    .unwrap();
+}
+
+fn disk_size(global: &impl GlobalInstanceLike, space: &str, model: &str) -> u64 {
+    let uuid = global
+        .namespace()
+        .with_model((space, model).into(), |mdl| Ok(mdl.get_uuid()))
+        .unwrap();
+    global
+        .model_drivers()
+        .read()
+        .get(&ModelUniqueID::new(space, model, uuid))
+        .unwrap()
+        .storage()
+        .lock()
+        .size()
+        .unwrap()
+}
+
+fn row<const N: usize>(cells: [Datacell; N]) -> Response {
+    let mut data = vec![];
+    cells.iter().for_each(|cell| encode_cell(&mut data, cell));
+    Response::Serialized {
+        ty: ResponseType::Row,
+        size: N,
+        data: data.into(),
+    }
+}
+
+fn rows<const N: usize>(rows: Vec<[Datacell; N]>) -> Response {
+    let mut data = vec![];
+    for row in rows.iter() {
+        IntegerRepr::scoped(N as u64, |repr| data.extend(repr));
+        data.push(b'\n');
+        row.iter().for_each(|cell| encode_cell(&mut data, cell));
+    }
+    Response::Serialized {
+        ty: ResponseType::MultiRow,
+        size: rows.len(),
+        data: data.into(),
+    }
+}
+
+fn setup(global: &impl GlobalInstanceLike) {
+    for space in ["create space myspace", "create space otherspace"] {
+        let tok = lex_insecure(space.as_bytes()).unwrap();
+        Space::transactional_exec_create(global, parse_ast_node_full(&tok[2..]).unwrap()).unwrap();
+    }
+    for model in [
+        "create model myspace.users(username: string, null email: string, tags: list { type: uint8 })",
+        "create model myspace.logs(id: uint64, msg: string)",
+        "create model otherspace.kv(k: binary, v: binary)",
+    ] {
+        super::ddl_model::exec_create(global, model, false).unwrap();
+    }
+    insert(global, "insert into myspace.users('sayan', null, [1, 2])");
+    insert(global, "insert into myspace.users('elena', null, [])");
+}
+
+#[test]
+fn inspect_model() {
+    let global = TestGlobal::new_with_vfs_driver("inspect_model.global.db-tlog");
+    setup(&global);
+    let root = ClientLocalState::new_test("root", true);
+    assert_eq!(
+        inspect(&global, &root, "inspect model myspace.users").unwrap(),
+        row([
+            Datacell::new_str("username".into()),
+            Datacell::new_str("{*username:String,?email:String,!tags:[UInt8]}".into()),
+            Datacell::new_uint_default(2),
+            Datacell::new_uint_default(disk_size(&global, "myspace", "users")),
+            Datacell::new_uint_default(0),
+            Datacell::new_list(vec![
+                Datacell::from([
+                    Datacell::new_str("username".into()),
+                    Datacell::new_str("String".into()),
+                    Datacell::new_bool(false),
+                ]),
+                Datacell::from([
+                    Datacell::new_str("email".into()),
+                    Datacell::new_str("String".into()),
+                    Datacell::new_bool(true),
+                ]),
+                Datacell::from([
+                    Datacell::new_str("tags".into()),
+                    Datacell::new_str("[UInt8]".into()),
+                    Datacell::new_bool(false),
+                ]),
+            ]),
+        ])
+    );
+    assert_eq!(
+        inspect(&global, &root, "inspect model myspace.nope").unwrap_err(),
+        QueryError::QExecObjectNotFound
+    );
+}
+
+#[test]
+fn inspect_space_and_models() {
+    let global = TestGlobal::new_with_vfs_driver("inspect_space_and_models.global.db-tlog");
+    setup(&global);
+    let root = ClientLocalState::new_test("root", true);
+    let logs_size = disk_size(&global, "myspace", "logs");
+    let users_size = disk_size(&global, "myspace", "users");
+    let kv_size = disk_size(&global, "otherspace", "kv");
+    assert_eq!(
+        inspect(&global, &root, "inspect space myspace").unwrap(),
+        rows(vec![
+            [
+                Datacell::new_str("logs".into()),
+                Datacell::new_uint_default(0),
+                Datacell::new_uint_default(logs_size),
+            ],
+            [
+                Datacell::new_str("users".into()),
+                Datacell::new_uint_default(2),
+                Datacell::new_uint_default(users_size),
+            ],
+        ])
+    );
+    assert_eq!(
+        inspect(&global, &root, "inspect models").unwrap(),
+        rows(vec![
+            [
+                Datacell::new_str("myspace".into()),
+                Datacell::new_str("logs".into()),
+                Datacell::new_uint_default(0),
+                Datacell::new_uint_default(logs_size),
+            ],
+            [
+                Datacell::new_str("myspace".into()),
+                Datacell::new_str("users".into()),
+                Datacell::new_uint_default(2),
+                Datacell::new_uint_default(users_size),
+            ],
+            [
+                Datacell::new_str("otherspace".into()),
+                Datacell::new_str("kv".into()),
+                Datacell::new_uint_default(0),
+                Datacell::new_uint_default(kv_size),
+            ],
+        ])
+    );
+    assert_eq!(
+        inspect(&global, &root, "inspect space nope").unwrap_err(),
+        QueryError::QExecObjectNotFound
+    );
+}
+
+#[test]
+fn inspect_hides_models_without_grant() {
+    let global =
+        TestGlobal::new_with_vfs_driver("inspect_hides_models_without_grant.global.db-tlog");
+    setup(&global);
+    let sys = global.sys_store();
+    sys.create_new_user("sayan".into(), "password12345678".into())
+        .unwrap();
+    sys.grant_user("sayan", "otherspace", None).unwrap();
+    let sayan = ClientLocalState::new_test("sayan", false);
+    assert_eq!(
+        inspect(&global, &sayan, "inspect model myspace.users").unwrap_err(),
+        QueryError::SysPermissionDenied
+    );
+    assert_eq!(
+        inspect(&global, &sayan, "inspect space myspace").unwrap(),
+        rows::<3>(vec![])
+    );
+    assert_eq!(
+        inspect(&global, &sayan, "inspect models").unwrap(),
+        rows(vec![[
+            Datacell::new_str("otherspace".into()),
+            Datacell::new_str("kv".into()),
+            Datacell::new_uint_default(0),
+            Datacell::new_uint_default(disk_size(&global, "otherspace", "kv")),
+        ]])
+    );
+}
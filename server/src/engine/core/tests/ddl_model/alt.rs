@@ -179,6 +179,36 @@ mod plan {
         );
     }
     #[test]
+    fn illegal_remove_compute_source() {
+        assert_eq!(
+            super::with_plan(
+                "create model myspace.mymodel(username: string, email: string, email_lc: string { compute: 'lower(email)' })",
+                "alter model myspace.mymodel remove email",
+                |_| {}
+            )
+            .unwrap_err(),
+            QueryError::QExecDdlModelAlterIllegal
+        );
+        // but the computed field itself can go
+        super::plan(
+            "create model myspace.mymodel(username: string, email: string, email_lc: string { compute: 'lower(email)' })",
+            "alter model myspace.mymodel remove email_lc",
+            |_| {},
+        );
+    }
+    #[test]
+    fn illegal_add_computed() {
+        assert_eq!(
+            super::with_plan(
+                "create model myspace.mymodel(username: string, email: string)",
+                "alter model myspace.mymodel add email_lc { type: string, nullable: true, compute: 'lower(email)' }",
+                |_| {}
+            )
+            .unwrap_err(),
+            QueryError::QExecDdlModelAlterIllegal
+        );
+    }
+    #[test]
     fn illegal_add_pk() {
         assert_eq!(
             super::with_plan(
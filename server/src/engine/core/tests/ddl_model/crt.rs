@@ -28,10 +28,13 @@ mod validation {
     use {
         super::super::create,
         crate::engine::{
-            core::model::{DeltaVersion, Field, Layer},
-            data::tag::{DataTag, FullTag},
+            core::model::{keygen::KeyGenerator, DeltaVersion, Field, Layer},
+            data::{
+                cell::Datacell,
+                tag::{DataTag, FullTag},
+            },
             error::QueryError,
-            idx::STIndexSeq,
+            idx::{STIndex, STIndexSeq},
         },
     };
 
@@ -110,6 +113,237 @@ mod validation {
     );
     }
 
+    #[test]
+    fn ephemeral() {
+        let model = create(
+            "create model myspace.mymodel(username: string, password: binary) with { durability: 'none' }",
+        )
+        .unwrap();
+        assert!(model.is_ephemeral());
+        assert!(
+            !create("create model myspace.mymodel(username: string, password: binary)")
+                .unwrap()
+                .is_ephemeral()
+        );
+        for model in [
+            "create model myspace.mymodel(username: string, password: binary) with { durability: 'always' }",
+            "create model myspace.mymodel(username: string, password: binary) with { durability: false }",
+            "create model myspace.mymodel(username: string, password: binary) with { durability: { level: 'none' } }",
+        ] {
+            assert_eq!(
+                create(model).unwrap_err(),
+                QueryError::QExecDdlModelBadDefinition,
+                "{model}"
+            );
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let model = create(
+            "create model myspace.mymodel(username: string, visits: uint8 { default: 0 }, null status: string { default: 'active' }, null email: string)",
+        )
+        .unwrap();
+        let default = |field| model.fields().st_get(field).unwrap().materialize_default();
+        assert_eq!(default("username"), None);
+        assert_eq!(default("visits"), Some(Datacell::new_uint_default(0)));
+        assert_eq!(default("status"), Some(Datacell::from("active")));
+        assert_eq!(default("email"), Some(Datacell::null()));
+    }
+
+    #[test]
+    fn illegal_defaults() {
+        for (model, error) in [
+            (
+                "create model myspace.mymodel(username: string { default: 'sayan' }, password: binary)",
+                QueryError::QExecDdlModelBadDefinition,
+            ),
+            (
+                "create model myspace.mymodel(username: string, visits: uint8 { default: 256 })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            (
+                "create model myspace.mymodel(username: string, visits: uint8 { default: 'zero' })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            (
+                "create model myspace.mymodel(username: string, email: string { default: null })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            (
+                "create model myspace.mymodel(username: string, tags: list { type: string, default: 'a' })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+        ] {
+            assert_eq!(create(model).unwrap_err(), error, "{model}");
+        }
+    }
+
+    #[test]
+    fn computed() {
+        let model = create(
+            "create model myspace.mymodel(username: string, null bio: string, username_lc: string { compute: \"lower(username)\" }, null tagline: string { compute: \"concat(upper(username), ': ', bio)\" })",
+        )
+        .unwrap();
+        let compute = |field| {
+            model
+                .fields()
+                .st_get(field)
+                .unwrap()
+                .compute()
+                .map(|compute| compute.decl().to_owned())
+        };
+        assert_eq!(compute("bio"), None);
+        assert_eq!(compute("username_lc").unwrap(), "lower(username)");
+        assert_eq!(
+            compute("tagline").unwrap(),
+            "concat(upper(username), ': ', bio)"
+        );
+    }
+
+    #[test]
+    fn generated_key() {
+        for (decl, generator) in [
+            (
+                "id: uint64 { generate: 'increment' }",
+                KeyGenerator::Increment,
+            ),
+            (
+                "id: uint8 { generate: 'increment' }",
+                KeyGenerator::Increment,
+            ),
+            (
+                "id: uint64 { generate: 'snowflake' }",
+                KeyGenerator::Snowflake,
+            ),
+            ("id: string { generate: 'uuid' }", KeyGenerator::Uuid),
+            ("id: binary { generate: 'uuid' }", KeyGenerator::Uuid),
+        ] {
+            let model = create(&format!(
+                "create model myspace.mymodel({decl}, username: string)"
+            ))
+            .unwrap();
+            assert_eq!(model.p_key(), "id");
+            assert_eq!(model.key_generator(), Some(generator), "{decl}");
+        }
+        let model =
+            create("create model myspace.mymodel(username: string, password: binary)").unwrap();
+        assert_eq!(model.key_generator(), None);
+    }
+
+    #[test]
+    fn illegal_generated_key() {
+        for (model, error) in [
+            // wrong types
+            (
+                "create model myspace.mymodel(id: sint64 { generate: 'increment' }, username: string)",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            (
+                "create model myspace.mymodel(id: uint32 { generate: 'snowflake' }, username: string)",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            (
+                "create model myspace.mymodel(id: uint64 { generate: 'uuid' }, username: string)",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            // unknown generator
+            (
+                "create model myspace.mymodel(id: uint64 { generate: 'random' }, username: string)",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            // can't have a default too
+            (
+                "create model myspace.mymodel(id: uint64 { generate: 'increment', default: 1 }, username: string)",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            // not the primary key
+            (
+                "create model myspace.mymodel(username: string, visits: uint64 { generate: 'increment' })",
+                QueryError::QExecDdlModelBadDefinition,
+            ),
+        ] {
+            assert_eq!(create(model).unwrap_err(), error, "{model}");
+        }
+    }
+
+    #[test]
+    fn fulltext() {
+        let model = create(
+            "create model myspace.mymodel(username: string, bio: string { fulltext: true }, null notes: string { fulltext: false })",
+        )
+        .unwrap();
+        let fulltext = |field| model.fields().st_get(field).unwrap().is_fulltext();
+        assert!(fulltext("bio"));
+        assert!(!fulltext("notes"));
+        assert!(!fulltext("username"));
+        for model in [
+            // not a string
+            "create model myspace.mymodel(username: string, visits: uint64 { fulltext: true })",
+            "create model myspace.mymodel(username: string, tags: list { type: string, fulltext: true })",
+            "create model myspace.mymodel(username: string, bio: string { fulltext: 'yes' })",
+            // the primary key
+            "create model myspace.mymodel(username: string { fulltext: true }, bio: string)",
+        ] {
+            assert!(create(model).is_err(), "{model}");
+        }
+    }
+
+    #[test]
+    fn illegal_computed() {
+        for (model, error) in [
+            // not a string
+            (
+                "create model myspace.mymodel(username: string, visits: uint8 { compute: 'lower(username)' })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            // bad expressions
+            (
+                "create model myspace.mymodel(username: string, lc: string { compute: 'lower(username' })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            (
+                "create model myspace.mymodel(username: string, lc: string { compute: 'lower(username, username)' })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            (
+                "create model myspace.mymodel(username: string, lc: string { compute: 'reverse(username)' })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            (
+                "create model myspace.mymodel(username: string, lc: string { compute: 1 })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            // can't have a default too
+            (
+                "create model myspace.mymodel(username: string, lc: string { compute: 'lower(username)', default: 'a' })",
+                QueryError::QExecDdlInvalidTypeDefinition,
+            ),
+            // unknown field
+            (
+                "create model myspace.mymodel(username: string, lc: string { compute: 'lower(email)' })",
+                QueryError::QExecDdlModelBadDefinition,
+            ),
+            // not a string field
+            (
+                "create model myspace.mymodel(username: string, visits: uint8, lc: string { compute: 'lower(visits)' })",
+                QueryError::QExecDdlModelBadDefinition,
+            ),
+            // another computed field
+            (
+                "create model myspace.mymodel(username: string, lc: string { compute: 'lower(username)' }, uc: string { compute: 'upper(lc)' })",
+                QueryError::QExecDdlModelBadDefinition,
+            ),
+            // the primary key
+            (
+                "create model myspace.mymodel(primary lc: string { compute: 'lower(username)' }, username: string)",
+                QueryError::QExecDdlModelBadDefinition,
+            ),
+        ] {
+            assert_eq!(create(model).unwrap_err(), error, "{model}");
+        }
+    }
+
     #[test]
     fn illegal_pk() {
         assert_eq!(
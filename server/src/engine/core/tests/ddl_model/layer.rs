@@ -42,7 +42,10 @@ fn layerview(layer_def: &str) -> QueryResult<Field> {
 mod layer_spec_validation {
     use {
         super::layerview,
-        crate::engine::{core::model::Layer, error::QueryError},
+        crate::engine::{
+            core::model::{self, Layer},
+            error::QueryError,
+        },
     };
 
     #[test]
@@ -50,6 +53,71 @@ mod layer_spec_validation {
         assert_eq!(layerview("string").unwrap().layers(), [Layer::str()]);
     }
 
+    #[test]
+    fn timestamp() {
+        assert_eq!(
+            layerview(model::TY_TIMESTAMP).unwrap().layers(),
+            [Layer::timestamp()]
+        );
+    }
+
+    #[test]
+    fn sint128() {
+        assert_eq!(
+            layerview(model::TY_SINT128).unwrap().layers(),
+            [Layer::sint128()]
+        );
+    }
+
+    #[test]
+    fn geopoint() {
+        assert_eq!(
+            layerview(model::TY_GEOPOINT).unwrap().layers(),
+            [Layer::geopoint()]
+        );
+    }
+
+    #[test]
+    fn dict() {
+        assert_eq!(layerview(model::TY_DICT).unwrap().layers(), [Layer::dict()]);
+        assert_eq!(
+            layerview("list { type: dict }").unwrap().layers(),
+            [Layer::list(), Layer::dict()]
+        );
+    }
+
+    #[test]
+    fn dict_schema() {
+        let field = layerview("dict { schema: { name: 'string', age: 'uint8' } }").unwrap();
+        assert_eq!(field.layers(), [Layer::dict()]);
+        // the keys are ordered
+        let expected: [(Box<str>, Layer); 2] = [
+            ("age".into(), Layer::uint8()),
+            ("name".into(), Layer::str()),
+        ];
+        assert_eq!(field.dict_schema().unwrap().fields(), expected);
+        for bad in [
+            // the values can only have scalar types
+            "dict { schema: { tags: 'list' } }",
+            "dict { schema: { address: 'dict' } }",
+            "dict { schema: { address: { city: 'string' } } }",
+            "dict { schema: { name: 'str' } }",
+            "dict { schema: { name: 1 } }",
+            "dict { schema: {} }",
+            "dict { schema: 'string' }",
+            // and only (non-list) dicts have a schema
+            "string { schema: { name: 'string' } }",
+            "list { type: dict, schema: { name: 'string' } }",
+            "list { type: dict { schema: { name: 'string' } } }",
+        ] {
+            assert_eq!(
+                layerview(bad).unwrap_err(),
+                QueryError::QExecDdlInvalidTypeDefinition,
+                "{bad}"
+            );
+        }
+    }
+
     #[test]
     fn nested_list() {
         assert_eq!(
@@ -193,6 +261,127 @@ mod layer_data_validation {
         assert_vecstreq_exact!(model::layer_traces(), ["fpath", "string"]);
     }
     #[test]
+    fn timestamp() {
+        let layer = layerview("timestamp").unwrap();
+        assert!(layer.vt_data_fpath(&mut Datacell::new_timestamp(1673187839000000)));
+        // unsigned integers are the micros since the epoch
+        let mut dc = Datacell::new_uint_default(1673187839000000);
+        assert!(layer.vt_data_fpath(&mut dc));
+        assert_eq!(dc, Datacell::new_timestamp(1673187839000000));
+        assert!(!layer.vt_data_fpath(&mut Datacell::new_sint_default(-1)));
+        assert_vecstreq_exact!(
+            model::layer_traces(),
+            ["fpath", "timestamp", "fpath", "timestamp", "fpath"]
+        );
+    }
+    #[test]
+    fn sint128() {
+        let layer = layerview("sint128").unwrap();
+        // there are no 128-bit literals, so integers and strings are converted
+        for (mut dc, int) in [
+            (Datacell::new_uint_default(u64::MAX), u64::MAX as i128),
+            (Datacell::new_sint_default(i64::MIN), i64::MIN as i128),
+            (
+                Datacell::from("-170141183460469231731687303715884105728"),
+                i128::MIN,
+            ),
+        ] {
+            assert!(layer.vt_data_fpath(&mut dc));
+            assert_eq!(dc, Datacell::new_sint128(int));
+        }
+        assert!(!layer.vt_data_fpath(&mut Datacell::from("1.5")));
+        assert!(!layer.vt_data_fpath(&mut Datacell::from(
+            "170141183460469231731687303715884105728"
+        )));
+        assert!(!layer.vt_data_fpath(&mut Datacell::new_float_default(1.5)));
+        assert_vecstreq_exact!(
+            model::layer_traces(),
+            [
+                "fpath", "sint128", "fpath", "sint128", "fpath", "sint128", "fpath", "sint128",
+                "fpath", "sint128", "fpath"
+            ]
+        );
+    }
+    #[test]
+    fn geopoint() {
+        let layer = layerview("geopoint").unwrap();
+        // points are sent as `lat,lon` strings
+        let mut dc = Datacell::from("48.8584,2.2945");
+        assert!(layer.vt_data_fpath(&mut dc));
+        assert_eq!(dc, Datacell::new_geopoint(48.8584, 2.2945));
+        assert!(layer.vt_data_fpath(&mut dc));
+        for bad in ["48.8584", "91,0", "0,-180.5", "north,east", "NaN,0"] {
+            assert!(!layer.vt_data_fpath(&mut Datacell::from(bad)));
+        }
+        assert!(!layer.vt_data_fpath(&mut Datacell::new_float_default(48.8584)));
+        assert_vecstreq_exact!(
+            model::layer_traces(),
+            [
+                "fpath", "geopoint", "fpath", "geopoint", "fpath", "geopoint", "fpath", "geopoint",
+                "fpath", "geopoint", "fpath", "geopoint", "fpath", "geopoint", "fpath"
+            ]
+        );
+    }
+    #[test]
+    fn dict() {
+        let layer = layerview("dict").unwrap();
+        // the values in a dict can be of any type
+        let mut dc = Datacell::new_dict(into_dict!(
+            "name" => Datacell::from("sayan"),
+            "tags" => Datacell::new_list(vec![Datacell::from("rust")]),
+            "address" => Datacell::new_dict(into_dict!("zip" => Datacell::new_uint_default(700001)))
+        ));
+        assert!(layer.vt_data_fpath(&mut dc));
+        assert!(!layer.vt_data_fpath(&mut Datacell::from("{}")));
+        assert!(!layer.vt_data_fpath(&mut Datacell::null()));
+        assert_vecstreq_exact!(model::layer_traces(), ["fpath", "dict", "fpath", "fpath"]);
+    }
+    #[test]
+    fn dict_schema() {
+        let layer =
+            layerview("dict { schema: { name: 'string', age: 'uint8', seen: 'timestamp' } }")
+                .unwrap();
+        // keys can be skipped, and the values are converted just like the fields of a row
+        let mut dc = Datacell::new_dict(into_dict!(
+            "name" => Datacell::from("sayan"),
+            "seen" => Datacell::new_uint_default(1)
+        ));
+        assert!(layer.vt_data_fpath(&mut dc));
+        assert_eq!(
+            dc,
+            Datacell::new_dict(into_dict!(
+                "name" => Datacell::from("sayan"),
+                "seen" => Datacell::new_timestamp(1)
+            ))
+        );
+        for bad in [
+            into_dict!("email" => Datacell::from("sayan@example.com")),
+            into_dict!("age" => Datacell::new_uint_default(256)),
+            into_dict!("age" => Datacell::from("24")),
+            into_dict!("name" => Datacell::null()),
+        ] {
+            assert!(!layer.vt_data_fpath(&mut Datacell::new_dict(bad)));
+        }
+        assert_vecstreq_exact!(
+            model::layer_traces(),
+            [
+                "fpath",
+                "dict",
+                "string",
+                "timestamp",
+                "fpath",
+                "dict",
+                "fpath",
+                "dict",
+                "uint",
+                "fpath",
+                "dict",
+                "fpath",
+                "dict"
+            ]
+        );
+    }
+    #[test]
     fn list_simple() {
         let layer = layerview("list { type: string }").unwrap();
         let mut dc = Datacell::new_list(vec![
@@ -0,0 +1,138 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::dml,
+    data::{
+        cell::Datacell,
+        tag::{DataTag, TagSelector},
+    },
+    error::{QueryError, QueryResult},
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    ql::{ast::parse_ast_node_full, tests::lex_insecure},
+};
+
+fn aggregate(global: &impl GlobalInstanceLike, query: &str) -> QueryResult<Vec<Datacell>> {
+    let tok = lex_insecure(query.as_bytes()).unwrap();
+    let stmt = parse_ast_node_full(&tok[1..]).unwrap();
+    dml::aggregate(global, stmt)
+}
+
+fn setup(global: &impl GlobalInstanceLike) {
+    super::_exec_only_create_space_model(
+        global,
+        "create model myspace.mymodel(id: uint64, username: string, null score: sint8, null rating: uint8, notes: list { type: string })",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel(1, 'sayan', -10, 4, [])",
+        "insert into myspace.mymodel(2, 'robot', -20, null, [])",
+        "insert into myspace.mymodel(3, 'douglas', null, 2, [])",
+        "insert into myspace.mymodel(4, 'hgwells', -40, 3, [])",
+    ] {
+        super::exec_insert_only(global, insert).unwrap();
+    }
+}
+
+#[test]
+fn aggregate_all() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    assert_eq!(
+        aggregate(
+            &global,
+            "select count(*), count(score), min(username), max(username), sum(score), avg(rating) from myspace.mymodel"
+        )
+        .unwrap(),
+        intovec![4u64, 3u64, "douglas", "sayan", -70i64, 3.0]
+    );
+}
+
+#[test]
+fn aggregate_range() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let ret = aggregate(
+        &global,
+        "select COUNT(*), sum(id), min(score), max(score) from myspace.mymodel where id >= 2 and id < 4",
+    )
+    .unwrap();
+    assert_eq!(ret, intovec![2u64, 5u64, -20i64, -20i64]);
+    // min and max keep the type of the field
+    assert_eq!(ret[2].tag().tag_selector(), TagSelector::SInt8);
+    assert_eq!(
+        aggregate(
+            &global,
+            "select count(*), sum(id) from myspace.mymodel where id = 3"
+        )
+        .unwrap(),
+        intovec![1u64, 3u64]
+    );
+}
+
+#[test]
+fn aggregate_empty() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    assert_eq!(
+        aggregate(
+            &global,
+            "select count(*), min(id), sum(score), avg(rating) from myspace.mymodel where id > 4"
+        )
+        .unwrap(),
+        intovec![0u64, Datacell::null(), Datacell::null(), Datacell::null()]
+    );
+}
+
+#[test]
+fn aggregate_bad() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    for (query, error) in [
+        (
+            "select sum(username) from myspace.mymodel",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "select max(notes) from myspace.mymodel",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "select count(nope) from myspace.mymodel",
+            QueryError::QExecUnknownField,
+        ),
+        (
+            "select count(*) from myspace.mymodel where username = 'sayan'",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        (
+            "select count(*) from myspace.mymodel where id > 1 and username = 'sayan'",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+    ] {
+        assert_eq!(aggregate(&global, query).unwrap_err(), error, "{query}");
+    }
+}
@@ -24,7 +24,13 @@
  *
 */
 
-use crate::engine::{error::QueryError, fractal::test_utils::TestGlobal};
+use crate::engine::{
+    core::dml,
+    error::QueryError,
+    fractal::test_utils::TestGlobal,
+    ql::{ast::parse_ast_node_full, tests::lex_insecure},
+    storage::v1::memfs::NullFS,
+};
 
 #[test]
 fn simple_delete() {
@@ -54,3 +60,186 @@ fn delete_nonexisting() {
         QueryError::QExecDmlRowNotFound
     );
 }
+
+#[test]
+fn delete_versioned() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    let row = super::exec_select(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+        "insert into myspace.mymodel('sayan', 'pass123')",
+        "select username from myspace.mymodel where username = 'sayan' with version",
+    )
+    .unwrap();
+    let version = row[1].uint();
+    let stale = format!(
+        "delete from myspace.mymodel where username = 'sayan' if version = {}",
+        version + 1
+    );
+    let stale = lex_insecure(stale.as_bytes()).unwrap();
+    assert_eq!(
+        dml::delete(&global, parse_ast_node_full(&stale[1..]).unwrap()).unwrap_err(),
+        QueryError::QExecDmlConditionFailed
+    );
+    super::_exec_delete_only(
+        &global,
+        &format!("delete from myspace.mymodel where username = 'sayan' if version = {version}"),
+        "sayan",
+    )
+    .unwrap();
+}
+
+const USER_MODEL: &str =
+    "create model myspace.mymodel(username: string, followers: uint64, bio: string { fulltext: true })";
+const USER_INSERTS: [&str; 5] = [
+    "insert into myspace.mymodel('sayan', 100, 'rust and databases')",
+    "insert into myspace.mymodel('elana', 50, 'rockets')",
+    "insert into myspace.mymodel('jeff', 10, 'rust and rockets')",
+    "insert into myspace.mymodel('bill', 0, 'windows')",
+    "insert into myspace.mymodel('steve', 10, 'phones')",
+];
+
+fn setup_users(global: &TestGlobal<NullFS>) {
+    super::_exec_only_create_space_model(global, USER_MODEL).unwrap();
+    for insert in USER_INSERTS {
+        super::exec_insert_only(global, insert).unwrap();
+    }
+}
+
+/// Returns the usernames (of the ones given) that are still around
+fn remaining(global: &TestGlobal<NullFS>, users: &[&str]) -> Vec<String> {
+    users
+        .iter()
+        .filter(|user| {
+            super::exec_select_only(
+                global,
+                &format!("select username from myspace.mymodel where username = '{user}'"),
+            )
+            .is_ok()
+        })
+        .map(|user| user.to_string())
+        .collect()
+}
+
+const USERS: [&str; 5] = ["sayan", "elana", "jeff", "bill", "steve"];
+
+#[test]
+fn delete_with_conditions_and_limit() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup_users(&global);
+    // only as many rows as the limit are deleted
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where followers <= 10 limit 2"
+        ),
+        Ok(2)
+    );
+    assert_eq!(remaining(&global, &USERS).len(), 3);
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where followers <= 10 limit 10"
+        ),
+        Ok(1)
+    );
+    assert_eq!(remaining(&global, &USERS), ["sayan", "elana"]);
+    // nothing matches
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where followers > 1000 limit 10"
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where followers = 50 limit 0"
+        ),
+        Ok(0)
+    );
+    assert_eq!(remaining(&global, &USERS), ["sayan", "elana"]);
+}
+
+#[test]
+fn delete_with_limit_using_keys() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup_users(&global);
+    // primary key range with another condition
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where username > 'f' and followers > 20 limit 10"
+        ),
+        Ok(1)
+    );
+    assert_eq!(
+        remaining(&global, &USERS),
+        ["elana", "jeff", "bill", "steve"]
+    );
+    // primary key pattern
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where username like 'st%' limit 10"
+        ),
+        Ok(1)
+    );
+    // a batch of keys (with some that don't exist)
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where username in ('bill', 'nobody', 'bill') limit 10"
+        ),
+        Ok(1)
+    );
+    assert_eq!(remaining(&global, &USERS), ["elana", "jeff"]);
+}
+
+#[test]
+fn delete_with_limit_using_text_index() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup_users(&global);
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where bio contains 'rust' and followers < 100 limit 10"
+        ),
+        Ok(1)
+    );
+    assert_eq!(
+        remaining(&global, &USERS),
+        ["sayan", "elana", "bill", "steve"]
+    );
+    // the index no longer finds the deleted row
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where bio contains 'rockets' limit 10"
+        ),
+        Ok(1)
+    );
+    assert_eq!(remaining(&global, &USERS), ["sayan", "bill", "steve"]);
+}
+
+#[test]
+fn delete_with_limit_validates() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup_users(&global);
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where stars = 10 limit 10"
+        ),
+        Err(QueryError::QExecUnknownField)
+    );
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where followers = 'ten' limit 10"
+        ),
+        Err(QueryError::QExecDmlValidationError)
+    );
+    assert_eq!(remaining(&global, &USERS).len(), 5);
+}
@@ -0,0 +1,276 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::dml::{self, AccessPath},
+    error::{QueryError, QueryResult},
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    net::protocol::ClientLocalState,
+    ql::{ast::parse_ast_node_full, tests::lex_insecure},
+};
+
+fn explain(global: &impl GlobalInstanceLike, query: &str) -> QueryResult<(AccessPath, u64, u64)> {
+    let tok = lex_insecure(query.as_bytes()).unwrap();
+    let stmt = parse_ast_node_full(&tok[1..]).unwrap();
+    dml::explain(global, &ClientLocalState::new_test("root", true), stmt)
+        .map(|plan| (plan.access(), plan.estimated_rows(), plan.model_rows()))
+}
+
+fn setup(global: &impl GlobalInstanceLike) {
+    super::_exec_only_create_space_model(
+        global,
+        "create model myspace.mymodel(username: string, password: string, notes: list { type: string })",
+    )
+    .unwrap();
+    for user in ["sayan", "elon", "jeff"] {
+        super::exec_insert_only(
+            global,
+            &format!("insert into myspace.mymodel('{user}', 'pass123', [])"),
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn explain_access_paths() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    for (query, plan) in [
+        (
+            "explain insert into myspace.mymodel('bill', 'pass123', [])",
+            (AccessPath::PrimaryKeyLookup, 1, 3),
+        ),
+        (
+            "explain select * from myspace.mymodel where username = 'sayan'",
+            (AccessPath::PrimaryKeyLookup, 1, 3),
+        ),
+        (
+            "explain select password from myspace.mymodel where username > 'a'",
+            (AccessPath::PrimaryKeyRange, 3, 3),
+        ),
+        (
+            "explain select password from myspace.mymodel where username like 's%' limit 1",
+            (AccessPath::PrimaryKeyPrefix, 1, 3),
+        ),
+        // only the keys in the range are counted
+        (
+            "explain select password from myspace.mymodel where username like 'e%'",
+            (AccessPath::PrimaryKeyPrefix, 1, 3),
+        ),
+        (
+            "explain select password from myspace.mymodel where username like 'x%'",
+            (AccessPath::PrimaryKeyPrefix, 0, 3),
+        ),
+        (
+            "explain select password from myspace.mymodel where username > 'f'",
+            (AccessPath::PrimaryKeyRange, 2, 3),
+        ),
+        (
+            "explain select password from myspace.mymodel where username in ('sayan', 'bill')",
+            (AccessPath::PrimaryKeyBatch, 2, 3),
+        ),
+        (
+            "explain select all * from myspace.mymodel limit 2",
+            (AccessPath::FullScan, 2, 3),
+        ),
+        (
+            "explain update myspace.mymodel set notes += 'x' where username = 'sayan'",
+            (AccessPath::PrimaryKeyLookup, 1, 3),
+        ),
+        (
+            "explain update myspace.mymodel set notes += 'x' where password = 'pass123'",
+            (AccessPath::FullScan, 3, 3),
+        ),
+        (
+            "explain update myspace.mymodel set notes += 'x' where username in ('sayan', 'elon')",
+            (AccessPath::PrimaryKeyBatch, 2, 3),
+        ),
+        (
+            "explain delete from myspace.mymodel where username = 'sayan'",
+            (AccessPath::PrimaryKeyLookup, 1, 3),
+        ),
+        (
+            "explain delete from myspace.mymodel where password = 'pass123' limit 2",
+            (AccessPath::FullScan, 2, 3),
+        ),
+        (
+            "explain delete from myspace.mymodel where username > 'f' and password = 'x' limit 10",
+            (AccessPath::PrimaryKeyRange, 3, 3),
+        ),
+        (
+            "explain delete from myspace.mymodel where username in ('sayan', 'bill') limit 10",
+            (AccessPath::PrimaryKeyBatch, 2, 3),
+        ),
+    ] {
+        assert_eq!(explain(&global, query).unwrap(), plan, "{query}");
+    }
+    // nothing was executed
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select password from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["pass123"]
+    );
+}
+
+#[test]
+fn explain_geo_search() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(name: string, location: geopoint, bio: string { fulltext: true })",
+    )
+    .unwrap();
+    for (name, location, bio) in [
+        ("a", "10,20", "rust"),
+        ("b", "10.1,20", "rockets"),
+        ("c", "-10,-20", "rust"),
+    ] {
+        super::exec_insert_only(
+            &global,
+            &format!("insert into myspace.mymodel('{name}', '{location}', '{bio}')"),
+        )
+        .unwrap();
+    }
+    assert_eq!(
+        explain(
+            &global,
+            "explain select * from myspace.mymodel where location within (10, 20, 20000)"
+        )
+        .unwrap(),
+        (AccessPath::GeoIndexLookup, 2, 3)
+    );
+    // the index that finds fewer rows is used
+    assert_eq!(
+        explain(
+            &global,
+            "explain select * from myspace.mymodel where location within (10, 20, 20000) and bio contains 'rockets'"
+        )
+        .unwrap(),
+        (AccessPath::TextIndexLookup, 1, 3)
+    );
+    assert_eq!(
+        explain(
+            &global,
+            "explain select * from myspace.mymodel where location within (-10, -20, 1000) and bio contains 'rust'"
+        )
+        .unwrap(),
+        (AccessPath::GeoIndexLookup, 1, 3)
+    );
+}
+
+#[test]
+fn explain_text_search() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, bio: string { fulltext: true })",
+    )
+    .unwrap();
+    for (user, bio) in [
+        ("sayan", "rust and databases"),
+        ("elon", "rockets"),
+        ("jeff", "rust and rockets"),
+    ] {
+        super::exec_insert_only(
+            &global,
+            &format!("insert into myspace.mymodel('{user}', '{bio}')"),
+        )
+        .unwrap();
+    }
+    assert_eq!(
+        explain(
+            &global,
+            "explain select * from myspace.mymodel where bio contains 'rockets rust'"
+        )
+        .unwrap(),
+        (AccessPath::TextIndexLookup, 2, 3)
+    );
+    assert_eq!(
+        explain(
+            &global,
+            "explain select * from myspace.mymodel where bio contains 'databases' limit 5"
+        )
+        .unwrap(),
+        (AccessPath::TextIndexLookup, 1, 3)
+    );
+    assert_eq!(
+        explain(
+            &global,
+            "explain delete from myspace.mymodel where bio contains 'rust' limit 5"
+        )
+        .unwrap(),
+        (AccessPath::TextIndexLookup, 2, 3)
+    );
+    assert_eq!(
+        explain(
+            &global,
+            "explain select * from myspace.mymodel where username contains 'sayan'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlWhereHasUnindexedColumn
+    );
+}
+
+#[test]
+fn explain_validates() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    for (query, error) in [
+        (
+            "explain insert into myspace.mymodel('bill', 'pass123')",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "explain select * from myspace.mymodel where password = 'pass123'",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        (
+            "explain select * from myspace.mymodel where username like '%n'",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        (
+            "explain select all email from myspace.mymodel limit 10",
+            QueryError::QExecUnknownField,
+        ),
+        (
+            "explain update myspace.mymodel set username = 'x' where username = 'sayan'",
+            QueryError::QExecUnknownField,
+        ),
+        (
+            "explain update myspace.mymodel set password = 100 where username = 'sayan'",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "explain delete from myspace.othermodel where username = 'sayan'",
+            QueryError::QExecObjectNotFound,
+        ),
+    ] {
+        assert_eq!(explain(&global, query).unwrap_err(), error, "{query}");
+    }
+}
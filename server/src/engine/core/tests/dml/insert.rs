@@ -24,7 +24,19 @@
  *
 */
 
-use crate::engine::{data::cell::Datacell, error::QueryError, fractal::test_utils::TestGlobal};
+use {
+    crate::engine::{
+        data::{cell::Datacell, dict::DictCell},
+        error::QueryError,
+        fractal::{
+            test_utils::TestGlobal, Backpressure, BackpressurePolicy, GlobalInstanceLike,
+            MemoryLimits, MemoryUsage,
+        },
+        ql::dml::ins::T_TIMESEC,
+        storage::v1::memfs::NullFS,
+    },
+    std::time::Duration,
+};
 
 #[derive(sky_macros::Wrapper, Debug)]
 struct Tuple(Vec<(Box<str>, Datacell)>);
@@ -86,3 +98,656 @@ fn insert_duplicate() {
         QueryError::QExecDmlDuplicate
     );
 }
+
+#[test]
+fn insert_multi() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+        "insert into myspace.mymodel (username, password) values ('sayan', 'pass123'), ('elon', 'pass321')",
+        "elon",
+        |row| {
+            assert_veceq_transposed!(row.cloned_data(), Tuple(pairvec!(("password", "pass321"))));
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select password from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["pass123"]
+    );
+}
+
+#[test]
+fn insert_multi_is_atomic() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+        "insert into myspace.mymodel('sayan', 'pass123')",
+        "sayan",
+        |_| {},
+    )
+    .unwrap();
+    for query in [
+        // conflicts with an existing row
+        "insert into myspace.mymodel('elon', 'pass321'), ('sayan', 'pass321')",
+        // conflicts within the statement
+        "insert into myspace.mymodel('elon', 'pass321'), ('elon', 'pass321')",
+        // bad row
+        "insert into myspace.mymodel('elon', 'pass321'), ('jeff')",
+    ] {
+        assert!(super::exec_insert_only(&global, query).is_err());
+        assert_eq!(
+            super::_exec_only_select(
+                &global,
+                "select password from myspace.mymodel where username = 'elon'"
+            )
+            .unwrap_err(),
+            QueryError::QExecDmlRowNotFound
+        );
+    }
+}
+
+#[test]
+fn insert_partial() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, password: string, visits: uint8 { default: 0 }, null email: string)",
+        "insert into myspace.mymodel('sayan', 'pass123')",
+        "sayan",
+        |row| {
+            assert_veceq_transposed!(
+                row.cloned_data(),
+                Tuple(pairvec!(
+                    ("password", "pass123"),
+                    ("visits", 0u64),
+                    ("email", Datacell::null())
+                ))
+            );
+        },
+    )
+    .unwrap();
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel { username: 'elon', email: 'elon@example.com', password: 'pass321' }",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'elon'"
+        )
+        .unwrap(),
+        intovec!["elon", "pass321", 0u64, "elon@example.com"]
+    );
+    for insert in [
+        // password has no default and isn't nullable
+        "insert into myspace.mymodel('elon')",
+        "insert into myspace.mymodel { username: 'elon', visits: 10 }",
+        // unknown field
+        "insert into myspace.mymodel { username: 'elon', password: 'pass321', lol: 1 }",
+        // too many fields
+        "insert into myspace.mymodel('elon', 'pass321', 1, null, 1)",
+    ] {
+        assert_eq!(
+            super::exec_insert_only(&global, insert).unwrap_err(),
+            QueryError::QExecDmlValidationError,
+            "{insert}"
+        );
+    }
+}
+
+#[test]
+fn insert_computed() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_insert(
+        &global,
+        "create model myspace.mymodel(username: string, username_lc: string { compute: 'lower(username)' }, null bio: string, null tagline: string { compute: \"concat(upper(username), ': ', bio)\" })",
+        "insert into myspace.mymodel('Sayan', 'Hello')",
+        "Sayan",
+        |row| {
+            assert_veceq_transposed!(
+                row.cloned_data(),
+                Tuple(pairvec!(
+                    ("username_lc", "sayan"),
+                    ("bio", "Hello"),
+                    ("tagline", "SAYAN: Hello")
+                ))
+            );
+        },
+    )
+    .unwrap();
+    // null in, null out
+    super::exec_insert_only(&global, "insert into myspace.mymodel { username: 'Elon' }").unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select username_lc, tagline from myspace.mymodel where username = 'Elon'"
+        )
+        .unwrap(),
+        intovec!["elon", Datacell::null()]
+    );
+    for insert in [
+        "insert into myspace.mymodel('Steve', 'steve', 'Hello')",
+        "insert into myspace.mymodel { username: 'Steve', username_lc: 'steve' }",
+    ] {
+        assert_eq!(
+            super::exec_insert_only(&global, insert).unwrap_err(),
+            QueryError::QExecDmlValidationError,
+            "{insert}"
+        );
+    }
+}
+
+#[test]
+fn insert_generated_key() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(id: uint64 { generate: 'increment' }, username: string)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('sayan')",
+        "insert into myspace.mymodel('elana')",
+        "insert into myspace.mymodel { username: 'steve' }",
+        // an explicit key moves the next key past it
+        "insert into myspace.mymodel { id: 10, username: 'elon' }",
+        "insert into myspace.mymodel('bill')",
+    ] {
+        super::exec_insert_only(&global, insert).unwrap();
+    }
+    for (id, username) in [
+        (1, "sayan"),
+        (2, "elana"),
+        (3, "steve"),
+        (10, "elon"),
+        (11, "bill"),
+    ] {
+        assert_eq!(
+            super::exec_select_only(
+                &global,
+                &format!("select username from myspace.mymodel where id = {id}")
+            )
+            .unwrap(),
+            intovec![username]
+        );
+    }
+    assert_eq!(
+        super::exec_insert_only(
+            &global,
+            "insert into myspace.mymodel { id: 2, username: 'jeff' }"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlDuplicate
+    );
+    // the key doesn't take a position in an ordered insert
+    assert_eq!(
+        super::exec_insert_only(&global, "insert into myspace.mymodel(12, 'jeff')").unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+}
+
+#[test]
+fn insert_generated_uuid() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    let rows = super::exec_select_all(
+        &global,
+        "create model myspace.mymodel(id: string { generate: 'uuid' }, username: string)",
+        &[
+            "insert into myspace.mymodel('sayan')",
+            "insert into myspace.mymodel { username: 'elana' }",
+        ],
+        "select all id from myspace.mymodel LIMIT 10",
+    )
+    .unwrap();
+    let ids: Vec<String> = rows
+        .into_iter()
+        .map(|mut row| row.swap_remove(0).into_str().unwrap())
+        .collect();
+    assert_eq!(ids.len(), 2);
+    assert_ne!(ids[0], ids[1]);
+    for id in ids {
+        // version 7
+        assert_eq!((id.len(), &id[14..15]), (36, "7"), "{id}");
+    }
+}
+
+#[test]
+fn insert_timestamp() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, created_at: timestamp, logins: list { type: timestamp })",
+    )
+    .unwrap();
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('sayan', @now(), [1000, 2000])",
+    )
+    .unwrap();
+    // unsigned integers are the micros since the epoch
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel { username: 'elana', created_at: 1000, logins: [] }",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![
+            "sayan",
+            Datacell::new_timestamp(T_TIMESEC * 1_000_000),
+            Datacell::new_list(intovec![
+                Datacell::new_timestamp(1000),
+                Datacell::new_timestamp(2000)
+            ])
+        ]
+    );
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'elana'"
+        )
+        .unwrap(),
+        intovec![
+            "elana",
+            Datacell::new_timestamp(1000),
+            Datacell::new_list(vec![])
+        ]
+    );
+    assert_eq!(
+        super::exec_insert_only(&global, "insert into myspace.mymodel('emily', -1, [])")
+            .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+}
+
+#[test]
+fn insert_sint128() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, balance: sint128, history: list { type: sint128 })",
+    )
+    .unwrap();
+    // there are no 128-bit literals, so they're either integers or strings
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('sayan', '-170141183460469231731687303715884105728', ['18446744073709551616', '-1'])",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![
+            "sayan",
+            Datacell::new_sint128(i128::MIN),
+            Datacell::new_list(intovec![
+                Datacell::new_sint128(u64::MAX as i128 + 1),
+                Datacell::new_sint128(-1)
+            ])
+        ]
+    );
+    for insert in [
+        "insert into myspace.mymodel('elana', '1e3', [])",
+        "insert into myspace.mymodel('elana', true, [])",
+        "insert into myspace.mymodel('elana', 0, ['one'])",
+    ] {
+        assert_eq!(
+            super::exec_insert_only(&global, insert).unwrap_err(),
+            QueryError::QExecDmlValidationError
+        );
+    }
+}
+
+#[test]
+fn insert_dict() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, null profile: dict, logins: list { type: dict })",
+    )
+    .unwrap();
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('sayan', { name: 'Sayan', age: 24, tags: ['rust', 'db'], address: { city: 'Kolkata', zip: 700001 } }, [{ at: 1 }, {}])",
+    )
+    .unwrap();
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel { username: 'elana', profile: null, logins: [] }",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![
+            "sayan",
+            Datacell::new_dict(into_dict!(
+                "name" => Datacell::from("Sayan"),
+                "age" => Datacell::new_uint_default(24),
+                "tags" => Datacell::new_list(intovec!["rust", "db"]),
+                "address" => Datacell::new_dict(into_dict!(
+                    "city" => Datacell::from("Kolkata"),
+                    "zip" => Datacell::new_uint_default(700001)
+                ))
+            )),
+            Datacell::new_list(vec![
+                Datacell::new_dict(into_dict!("at" => Datacell::new_uint_default(1))),
+                Datacell::new_dict(DictCell::new()),
+            ])
+        ]
+    );
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select profile from myspace.mymodel where username = 'elana'"
+        )
+        .unwrap(),
+        intovec![Datacell::null()]
+    );
+    for insert in [
+        "insert into myspace.mymodel('emily', 'a string', [])",
+        "insert into myspace.mymodel('emily', {}, [1])",
+    ] {
+        assert_eq!(
+            super::exec_insert_only(&global, insert).unwrap_err(),
+            QueryError::QExecDmlValidationError
+        );
+    }
+}
+
+#[test]
+fn insert_dict_schema() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, profile: dict { schema: { name: 'string', age: 'uint8', home: 'geopoint' } })",
+    )
+    .unwrap();
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('sayan', { name: 'Sayan', home: '22.5726,88.3639' })",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![
+            "sayan",
+            Datacell::new_dict(into_dict!(
+                "name" => Datacell::from("Sayan"),
+                "home" => Datacell::new_geopoint(22.5726, 88.3639)
+            ))
+        ]
+    );
+    for insert in [
+        "insert into myspace.mymodel('emily', { name: 'Emily', email: 'emily@example.com' })",
+        "insert into myspace.mymodel('emily', { age: 256 })",
+        "insert into myspace.mymodel('emily', { age: 'twenty' })",
+        "insert into myspace.mymodel('emily', { name: 'Emily', address: { city: 'Paris' } })",
+    ] {
+        assert_eq!(
+            super::exec_insert_only(&global, insert).unwrap_err(),
+            QueryError::QExecDmlValidationError
+        );
+    }
+}
+
+fn backlogged_global(policy: BackpressurePolicy, stall_timeout: Duration) -> TestGlobal<NullFS> {
+    let global = TestGlobal::new_with_tmp_nullfs_driver()
+        .with_backpressure(Backpressure::new(policy, Some(2)).with_stall_timeout(stall_timeout));
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    // nothing is flushed in tests, so these fill up the backlog
+    for insert in [
+        "insert into myspace.mymodel('sayan', 'pass123')",
+        "insert into myspace.mymodel('elana', 'pass123')",
+    ] {
+        super::exec_insert_only(&global, insert).unwrap();
+    }
+    global
+}
+
+#[test]
+fn insert_backpressure_reject() {
+    let global = backlogged_global(BackpressurePolicy::Reject, Duration::ZERO);
+    assert_eq!(
+        super::exec_insert_only(&global, "insert into myspace.mymodel('emily', 'pass123')")
+            .unwrap_err(),
+        QueryError::SysWriteBacklogFull
+    );
+    assert_eq!(global.backpressure().stalled_writes(), 0);
+    assert_eq!(global.backpressure().rejected_writes(), 1);
+}
+
+#[test]
+fn insert_backpressure_stall_timeout() {
+    let global = backlogged_global(BackpressurePolicy::Stall, Duration::from_millis(10));
+    assert_eq!(
+        super::exec_insert_only(&global, "insert into myspace.mymodel('emily', 'pass123')")
+            .unwrap_err(),
+        QueryError::SysWriteBacklogFull
+    );
+    assert_eq!(global.backpressure().stalled_writes(), 1);
+    assert_eq!(global.backpressure().rejected_writes(), 1);
+    // the stalled write should have asked for the backlog to be flushed
+    global
+        .namespace()
+        .with_model(("myspace", "mymodel").into(), |mdl| {
+            assert_eq!(mdl.delta_state().data_delta_queue_len(), 0);
+            assert_eq!(mdl.delta_state().data_delta_backlog(), 2);
+            Ok(())
+        })
+        .unwrap();
+}
+
+fn memory_usage(global: &impl GlobalInstanceLike, model: &str) -> MemoryUsage {
+    global
+        .namespace()
+        .with_model(("myspace", model).into(), |mdl| Ok(MemoryUsage::of(mdl)))
+        .unwrap()
+}
+
+/// Returns the running estimate of the memory used by the rows, and a fresh count
+fn index_bytes(global: &impl GlobalInstanceLike) -> (usize, usize) {
+    global
+        .namespace()
+        .with_model(("myspace", "mymodel").into(), |mdl| {
+            let running = mdl.delta_state().index_bytes();
+            mdl.recount_index_bytes();
+            Ok((running, mdl.delta_state().index_bytes()))
+        })
+        .unwrap()
+}
+
+#[test]
+fn insert_memory_accounting() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string, tags: list { type: string })",
+    )
+    .unwrap();
+    assert_eq!(index_bytes(&global), (0, 0));
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('sayan', 'pass123', ['a', 'b'])",
+    )
+    .unwrap();
+    super::exec_insert_only(
+        &global,
+        "insert into myspace.mymodel('elana', 'pass123', [])",
+    )
+    .unwrap();
+    let (running, counted) = index_bytes(&global);
+    assert_eq!(running, counted);
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set password = 'a much longer password' where username = 'sayan'",
+    )
+    .unwrap();
+    let (after_update, counted) = index_bytes(&global);
+    assert_eq!(after_update, counted);
+    assert_eq!(
+        after_update,
+        running + "a much longer password".len() - "pass123".len()
+    );
+    super::_exec_delete_only(
+        &global,
+        "delete from myspace.mymodel where username = 'sayan'",
+        "sayan",
+    )
+    .unwrap();
+    let (after_delete, counted) = index_bytes(&global);
+    assert_eq!(after_delete, counted);
+    assert!(after_delete < after_update);
+    let usage = memory_usage(&global, "mymodel");
+    let slack = global
+        .namespace()
+        .with_model(("myspace", "mymodel").into(), |mdl| {
+            Ok(mdl.delta_state().index_slack())
+        })
+        .unwrap();
+    assert_eq!(usage.index(), after_delete + slack);
+    // nothing is flushed in tests
+    assert_ne!(usage.deltas(), 0);
+    assert_eq!(usage.total(), usage.index() + usage.deltas());
+}
+
+#[test]
+fn memory_accounting_bulk_delete() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    for i in 0..100 {
+        super::exec_insert_only(
+            &global,
+            &format!("insert into myspace.mymodel('user{i}', 'pass123')"),
+        )
+        .unwrap();
+    }
+    let (running, counted) = index_bytes(&global);
+    assert_eq!(running, counted);
+    let full = memory_usage(&global, "mymodel");
+    // the index's structure is more than just the slots for the rows
+    assert!(full.index() > counted);
+    for i in 0..100 {
+        let user = format!("user{i}");
+        super::_exec_delete_only(
+            &global,
+            &format!("delete from myspace.mymodel where username = '{user}'"),
+            &user,
+        )
+        .unwrap();
+    }
+    global
+        .namespace()
+        .with_model(("myspace", "mymodel").into(), |mdl| {
+            mdl.compact_index();
+            assert_eq!(mdl.primary_index().count_approx(), 0);
+            assert_eq!(mdl.delta_state().index_bytes(), 0);
+            Ok(())
+        })
+        .unwrap();
+    let empty = memory_usage(&global, "mymodel");
+    assert!(empty.index() < full.index() / 10);
+}
+
+#[test]
+fn insert_memory_limit_model() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver()
+        .with_memory_limits(MemoryLimits::new(None, Some(1)));
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    super::exec_insert_only(&global, "insert into myspace.mymodel('sayan', 'pass123')").unwrap();
+    assert_eq!(
+        super::exec_insert_only(&global, "insert into myspace.mymodel('elana', 'pass123')")
+            .unwrap_err(),
+        QueryError::SysMemoryLimitExceeded
+    );
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set password = 'pass321' where username = 'sayan'"
+        )
+        .unwrap_err(),
+        QueryError::SysMemoryLimitExceeded
+    );
+    assert_eq!(global.memory().rejected_writes(), 2);
+    // the rejected write should have asked for the queued deltas to be flushed
+    global
+        .namespace()
+        .with_model(("myspace", "mymodel").into(), |mdl| {
+            assert_eq!(mdl.delta_state().data_delta_queue_len(), 0);
+            Ok(())
+        })
+        .unwrap();
+    // deletes give memory back, so they always go through
+    super::_exec_delete_only(
+        &global,
+        "delete from myspace.mymodel where username = 'sayan'",
+        "sayan",
+    )
+    .unwrap();
+}
+
+#[test]
+fn insert_memory_limit_global() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver()
+        .with_memory_limits(MemoryLimits::new(Some(1), None));
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.othermodel(username: string, password: string)",
+    )
+    .unwrap();
+    super::exec_insert_only(&global, "insert into myspace.mymodel('sayan', 'pass123')").unwrap();
+    // the other model is empty, but the database as a whole is over the limit
+    assert_eq!(memory_usage(&global, "othermodel").total(), 0);
+    assert_eq!(
+        super::exec_insert_only(
+            &global,
+            "insert into myspace.othermodel('elana', 'pass123')"
+        )
+        .unwrap_err(),
+        QueryError::SysMemoryLimitExceeded
+    );
+    assert_eq!(global.memory().rejected_writes(), 1);
+    assert_eq!(
+        MemoryUsage::of_all(&global),
+        memory_usage(&global, "mymodel")
+    );
+}
@@ -24,9 +24,14 @@
  *
 */
 
+mod aggregate;
 mod delete;
+mod explain;
 mod insert;
+mod returning;
 mod select;
+mod snapshot;
+mod transaction;
 mod update;
 
 use crate::engine::{
@@ -34,6 +39,7 @@ use crate::engine::{
     data::{cell::Datacell, lit::Lit},
     error::QueryResult,
     fractal::GlobalInstanceLike,
+    net::protocol::ClientLocalState,
     ql::{
         ast::parse_ast_node_full,
         dml::{del::DeleteStatement, ins::InsertStatement},
@@ -99,11 +105,22 @@ fn _exec_delete_only(global: &impl GlobalInstanceLike, delete: &str, key: &str)
     Ok(())
 }
 
+fn _exec_only_delete_many(global: &impl GlobalInstanceLike, delete: &str) -> QueryResult<u64> {
+    let lex_del = lex_insecure(delete.as_bytes()).unwrap();
+    let delete = parse_ast_node_full::<DeleteStatement>(&lex_del[1..]).unwrap();
+    dml::delete_rows(global, delete).map(|changes| changes.count().unwrap())
+}
+
 fn _exec_only_select(global: &impl GlobalInstanceLike, select: &str) -> QueryResult<Vec<Datacell>> {
     let lex_sel = lex_insecure(select.as_bytes()).unwrap();
     let select = parse_ast_node_full(&lex_sel[1..]).unwrap();
     let mut r = Vec::new();
-    dml::select_custom(global, select, |cell| r.push(cell.clone()))?;
+    dml::select_custom(
+        global,
+        &ClientLocalState::new_test("root", true),
+        select,
+        |cell| r.push(cell.clone()),
+    )?;
     Ok(r)
 }
 
@@ -113,6 +130,15 @@ fn _exec_only_update(global: &impl GlobalInstanceLike, update: &str) -> QueryRes
     dml::update(global, update)
 }
 
+fn _exec_only_update_rows(
+    global: &impl GlobalInstanceLike,
+    update: &str,
+) -> QueryResult<Option<u64>> {
+    let lex_upd = lex_insecure(update.as_bytes()).unwrap();
+    let update = parse_ast_node_full(&lex_upd[1..]).unwrap();
+    dml::update_rows(global, update).map(|changes| changes.count())
+}
+
 pub(self) fn exec_insert<T: Default>(
     global: &impl GlobalInstanceLike,
     model: &str,
@@ -181,6 +207,61 @@ pub(self) fn exec_select_all(
     Ok(r)
 }
 
+pub(self) fn exec_select_range(
+    global: &impl GlobalInstanceLike,
+    model: &str,
+    inserts: &[&str],
+    select: &str,
+) -> QueryResult<Vec<Vec<Datacell>>> {
+    _exec_only_create_space_model(global, model)?;
+    for insert in inserts {
+        _exec_only_insert(global, insert, |_| {})?;
+    }
+    _exec_only_select_range(global, select)
+}
+
+fn _exec_only_select_range(
+    global: &impl GlobalInstanceLike,
+    select: &str,
+) -> QueryResult<Vec<Vec<Datacell>>> {
+    let lex_sel = lex_insecure(select.as_bytes()).unwrap();
+    let select = parse_ast_node_full(&lex_sel[1..]).unwrap();
+    let mut r: Vec<Vec<Datacell>> = Vec::new();
+    dml::select_range(
+        global,
+        &ClientLocalState::new_test("root", true),
+        select,
+        &mut r,
+        |_, _, _| {},
+        |rows, dc, col_cnt| match rows.last_mut() {
+            Some(row) if row.len() != col_cnt => row.push(dc.clone()),
+            _ => rows.push(vec![dc.clone()]),
+        },
+    )?;
+    Ok(r)
+}
+
+fn _exec_only_select_keys(
+    global: &impl GlobalInstanceLike,
+    select: &str,
+) -> QueryResult<Vec<Vec<Datacell>>> {
+    let lex_sel = lex_insecure(select.as_bytes()).unwrap();
+    let select = parse_ast_node_full(&lex_sel[1..]).unwrap();
+    let mut r: Vec<Vec<Datacell>> = Vec::new();
+    dml::select_keys(
+        global,
+        &ClientLocalState::new_test("root", true),
+        select,
+        &mut r,
+        |_, _, _| {},
+        |rows, dc, col_cnt| match rows.last_mut() {
+            Some(row) if row.len() != col_cnt => row.push(dc.clone()),
+            _ => rows.push(vec![dc.clone()]),
+        },
+    )?;
+    Ok(r)
+}
+
 pub(self) fn exec_select_only(
     global: &impl GlobalInstanceLike,
     select: &str,
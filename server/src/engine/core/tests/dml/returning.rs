@@ -0,0 +1,203 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::dml::{self, Changes},
+    data::cell::Datacell,
+    error::{QueryError, QueryResult},
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    net::protocol::{Response, ResponseType},
+    ql::{ast::parse_ast_node_full, tests::lex_insecure},
+};
+
+fn exec(global: &impl GlobalInstanceLike, query: &str) -> QueryResult<Changes> {
+    let tok = lex_insecure(query.as_bytes()).unwrap();
+    match query.split_once(' ').unwrap().0 {
+        "insert" => dml::insert_rows(global, parse_ast_node_full(&tok[1..]).unwrap()),
+        "update" => dml::update_rows(global, parse_ast_node_full(&tok[1..]).unwrap()),
+        _ => dml::delete_rows(global, parse_ast_node_full(&tok[1..]).unwrap()),
+    }
+}
+
+fn returned(global: &impl GlobalInstanceLike, query: &str) -> Vec<Vec<Datacell>> {
+    exec(global, query)
+        .unwrap()
+        .returned()
+        .expect("no rows returned")
+        .rows()
+        .to_vec()
+}
+
+fn setup(global: &impl GlobalInstanceLike) {
+    super::_exec_only_create_space_model(
+        global,
+        "create model myspace.mymodel(id: uint64 { generate: 'increment' }, username: string, followers: uint64)",
+    )
+    .unwrap();
+}
+
+#[test]
+fn insert_returning() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    // the generated key
+    assert_eq!(
+        returned(
+            &global,
+            "insert into myspace.mymodel('sayan', 100) returning id"
+        ),
+        vec![intovec![1u64]]
+    );
+    assert_eq!(
+        returned(
+            &global,
+            "insert into myspace.mymodel('elana', 50), ('jeff', 10) returning *"
+        ),
+        vec![
+            intovec![2u64, "elana", 50u64],
+            intovec![3u64, "jeff", 10u64]
+        ]
+    );
+    assert!(exec(&global, "insert into myspace.mymodel('bill', 0)")
+        .unwrap()
+        .returned()
+        .is_none());
+}
+
+#[test]
+fn update_returning() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    exec(
+        &global,
+        "insert into myspace.mymodel('sayan', 100), ('elana', 50), ('jeff', 10)",
+    )
+    .unwrap();
+    // the new values
+    assert_eq!(
+        returned(
+            &global,
+            "update myspace.mymodel set followers += 1 where id = 1 returning *"
+        ),
+        vec![intovec![1u64, "sayan", 101u64]]
+    );
+    let changes = exec(
+        &global,
+        "update myspace.mymodel set followers = 0 where followers < 100 returning username, followers",
+    )
+    .unwrap();
+    assert_eq!(changes.count(), Some(2));
+    assert_eq!(
+        changes.returned().unwrap().rows(),
+        [intovec!["elana", 0u64], intovec!["jeff", 0u64]]
+    );
+    // nothing matched
+    assert!(returned(
+        &global,
+        "update myspace.mymodel set followers = 1 where followers > 1000 returning id"
+    )
+    .is_empty());
+}
+
+#[test]
+fn delete_returning() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    exec(
+        &global,
+        "insert into myspace.mymodel('sayan', 100), ('elana', 50), ('jeff', 10)",
+    )
+    .unwrap();
+    // the deleted values
+    assert_eq!(
+        returned(
+            &global,
+            "delete from myspace.mymodel where id = 1 returning username, followers"
+        ),
+        vec![intovec!["sayan", 100u64]]
+    );
+    assert_eq!(
+        returned(
+            &global,
+            "delete from myspace.mymodel where followers < 100 limit 10 returning id"
+        ),
+        vec![intovec![2u64], intovec![3u64]]
+    );
+    assert!(
+        super::_exec_only_select_range(&global, "select * from myspace.mymodel where id > 0")
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn returning_validates() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    exec(&global, "insert into myspace.mymodel('sayan', 100)").unwrap();
+    for query in [
+        "insert into myspace.mymodel('elana', 50) returning stars",
+        "update myspace.mymodel set followers = 1 where id = 1 returning stars",
+        "update myspace.mymodel set followers = 1 where followers > 0 returning id, stars",
+        "delete from myspace.mymodel where id = 1 returning stars",
+    ] {
+        assert_eq!(
+            exec(&global, query).unwrap_err(),
+            QueryError::QExecUnknownField,
+            "{query}"
+        );
+    }
+    // nothing was changed
+    assert_eq!(
+        returned(
+            &global,
+            "update myspace.mymodel set followers += 0 where id > 0 returning *"
+        ),
+        vec![intovec![1u64, "sayan", 100u64]]
+    );
+}
+
+#[test]
+fn returning_response() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let insert = |query: &str| {
+        let tok = lex_insecure(query.as_bytes()).unwrap();
+        dml::insert_resp(&global, parse_ast_node_full(&tok[1..]).unwrap()).unwrap()
+    };
+    assert_eq!(
+        insert("insert into myspace.mymodel('sayan', 100)"),
+        Response::Empty
+    );
+    assert!(matches!(
+        insert("insert into myspace.mymodel('elana', 50), ('jeff', 10) returning id"),
+        Response::Serialized {
+            ty: ResponseType::MultiRow,
+            size: 2,
+            ..
+        }
+    ));
+}
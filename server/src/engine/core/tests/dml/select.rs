@@ -25,8 +25,19 @@
 */
 
 use {
-    crate::engine::{data::cell::Datacell, error::QueryError, fractal::test_utils::TestGlobal},
-    std::collections::HashMap,
+    crate::engine::{
+        core::{
+            dml::{self, Transaction},
+            model::Model,
+        },
+        data::cell::Datacell,
+        error::{QueryError, QueryResult},
+        fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+        net::protocol::{ClientLocalState, Response},
+        ql::{ast::parse_ast_node_full, tests::lex_insecure},
+        storage::v1::memfs::NullFS,
+    },
+    std::{collections::HashMap, sync::mpsc, thread, time::Duration},
 };
 
 #[test]
@@ -161,3 +172,1321 @@ fn select_all_onefield() {
     assert_eq!(ret.get("hgwells").unwrap(), &intovec![]);
     assert_eq!(ret.get("orwell").unwrap(), &intovec![]);
 }
+
+/*
+    select range
+*/
+
+const RANGE_INSERTS: [&str; 5] = [
+    "insert into myspace.mymodel(1, 'sayan')",
+    "insert into myspace.mymodel(2, 'robot')",
+    "insert into myspace.mymodel(3, 'douglas')",
+    "insert into myspace.mymodel(4, 'hgwells')",
+    "insert into myspace.mymodel(5, 'orwell')",
+];
+
+fn select_range_sorted(select: &str) -> Vec<Vec<Datacell>> {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    let mut ret = super::exec_select_range(
+        &global,
+        "create model myspace.mymodel(id: uint64, username: string)",
+        &RANGE_INSERTS,
+        select,
+    )
+    .unwrap();
+    ret.sort_by_key(|row| row[0].uint());
+    ret
+}
+
+#[test]
+fn select_range_exclusive() {
+    assert_eq!(
+        select_range_sorted("select * from myspace.mymodel where id > 1 and id < 4"),
+        vec![intovec![2u64, "robot"], intovec![3u64, "douglas"]]
+    );
+}
+
+#[test]
+fn select_range_inclusive_reversed() {
+    assert_eq!(
+        select_range_sorted("select id, username from myspace.mymodel where id <= 4 and id >= 3"),
+        vec![intovec![3u64, "douglas"], intovec![4u64, "hgwells"]]
+    );
+}
+
+#[test]
+fn select_range_open() {
+    assert_eq!(
+        select_range_sorted("select id from myspace.mymodel where id >= 4"),
+        vec![intovec![4u64], intovec![5u64]]
+    );
+    assert_eq!(
+        select_range_sorted("select id from myspace.mymodel where id < 2"),
+        vec![intovec![1u64]]
+    );
+}
+
+#[test]
+fn select_range_empty() {
+    assert!(select_range_sorted("select * from myspace.mymodel where id > 5").is_empty());
+}
+
+#[test]
+fn select_range_with_conditions() {
+    assert_eq!(
+        select_range_sorted("select * from myspace.mymodel where id > 1 and username = 'douglas'"),
+        vec![intovec![3u64, "douglas"]]
+    );
+    assert_eq!(
+        select_range_sorted(
+            "select id from myspace.mymodel where id >= 2 and id < 5 and username > 'h'"
+        ),
+        vec![intovec![2u64], intovec![4u64]]
+    );
+    assert!(select_range_sorted(
+        "select * from myspace.mymodel where id > 3 and username = 'robot'"
+    )
+    .is_empty());
+}
+
+#[test]
+fn select_range_with_bad_condition() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_select_range(
+            &global,
+            "create model myspace.mymodel(id: uint64, username: string)",
+            &RANGE_INSERTS,
+            "select * from myspace.mymodel where id > 1 and username = 10",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+}
+
+#[test]
+fn select_range_non_pk() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_select_range(
+            &global,
+            "create model myspace.mymodel(id: uint64, username: string)",
+            &RANGE_INSERTS,
+            "select * from myspace.mymodel where username > 'a'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlWhereHasUnindexedColumn
+    );
+}
+
+#[test]
+fn select_range_window() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_select_range(
+            &global,
+            "create model myspace.mymodel(id: uint64, username: string)",
+            &RANGE_INSERTS,
+            "select id from myspace.mymodel where id > 1 order by username limit 2 offset 1",
+        )
+        .unwrap(),
+        // douglas, hgwells, orwell, robot
+        vec![intovec![4u64], intovec![5u64]]
+    );
+}
+
+#[test]
+fn select_range_window_pk_desc() {
+    assert_eq!(
+        select_range_sorted("select id from myspace.mymodel where id < 5 order by id desc limit 2"),
+        vec![intovec![3u64], intovec![4u64]]
+    );
+    assert!(select_range_sorted("select id from myspace.mymodel where id < 5 offset 4").is_empty());
+}
+
+#[test]
+fn select_range_key_order() {
+    // the primary index is ordered, so ranges come back in key order no matter how the rows were inserted
+    let inserts: Vec<String> = (0..50u64)
+        .map(|i| (i * 37) % 50)
+        .map(|id| format!("insert into myspace.mymodel({id}, 'user{id}')"))
+        .collect();
+    let inserts: Vec<&str> = inserts.iter().map(String::as_str).collect();
+    let model = "create model myspace.mymodel(id: uint64, username: string)";
+    let ret = super::exec_select_range(
+        &TestGlobal::new_with_tmp_nullfs_driver(),
+        model,
+        &inserts,
+        "select id from myspace.mymodel where id >= 10 and id < 30",
+    )
+    .unwrap();
+    assert_eq!(ret, (10..30u64).map(|id| intovec![id]).collect::<Vec<_>>());
+    let ret = super::exec_select_all(
+        &TestGlobal::new_with_tmp_nullfs_driver(),
+        model,
+        &inserts,
+        "select all id from myspace.mymodel limit 100",
+    )
+    .unwrap();
+    assert_eq!(ret, (0..50u64).map(|id| intovec![id]).collect::<Vec<_>>());
+}
+
+#[test]
+fn select_range_alongside_writer() {
+    // range scans only take the shared latch, so they don't wait for writers (which also hold it) to finish
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_select_range(
+        &global,
+        "create model myspace.mymodel(id: uint64, username: string)",
+        &RANGE_INSERTS,
+        "select * from myspace.mymodel where id > 5",
+    )
+    .unwrap();
+    global
+        .namespace()
+        .with_model(("myspace", "mymodel").into(), |mdl| {
+            let latch = mdl.primary_index().acquire_cd();
+            let (tx, rx) = mpsc::channel();
+            thread::scope(|s| {
+                s.spawn(|| {
+                    let ret = super::_exec_only_select_range(
+                        &global,
+                        "select id from myspace.mymodel where id >= 4",
+                    );
+                    tx.send(ret).unwrap();
+                });
+                let ret = rx.recv_timeout(Duration::from_secs(10));
+                drop(latch);
+                assert_eq!(ret.unwrap().unwrap(), vec![intovec![4u64], intovec![5u64]]);
+            });
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn select_all_window() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_select_all(
+            &global,
+            "create model myspace.mymodel(id: uint64, username: string)",
+            &RANGE_INSERTS,
+            "select all username from myspace.mymodel order by id desc limit 3 offset 1",
+        )
+        .unwrap(),
+        vec![intovec!["hgwells"], intovec!["douglas"], intovec!["robot"]]
+    );
+}
+
+#[test]
+fn select_all_killed() {
+    for select in [
+        "select all username from myspace.mymodel limit 3",
+        "select all username from myspace.mymodel order by id desc limit 3",
+    ] {
+        let global = TestGlobal::new_with_tmp_nullfs_driver();
+        let query = global.queries().register("root", select);
+        global.queries().kill(query.id(), None).unwrap();
+        assert_eq!(
+            query.scope(|| super::exec_select_all(
+                &global,
+                "create model myspace.mymodel(id: uint64, username: string)",
+                &RANGE_INSERTS,
+                select,
+            )),
+            Err(QueryError::SysQueryKilled)
+        );
+    }
+}
+
+#[test]
+fn select_window_bad_order() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_select_all(
+            &global,
+            "create model myspace.mymodel(id: uint64, notes: list { type: string })",
+            &["insert into myspace.mymodel(1, ['note'])"],
+            "select all id from myspace.mymodel order by notes limit 10",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+    assert_eq!(
+        super::exec_select_all(
+            &global,
+            "create model myspace.mymodel2(id: uint64, notes: list { type: string })",
+            &[],
+            "select all id from myspace.mymodel2 order by nope limit 10",
+        )
+        .unwrap_err(),
+        QueryError::QExecUnknownField
+    );
+}
+
+#[test]
+fn select_range_with_version() {
+    let rows = select_range_sorted("select id from myspace.mymodel where id > 3 with version");
+    assert_eq!(rows.len(), 2);
+    // every row was inserted separately, so every row has a different version
+    assert_eq!(rows[0].len(), 2);
+    assert!(rows[0][1].uint() < rows[1][1].uint());
+}
+
+const TEXT_MODEL: &str =
+    "create model myspace.mymodel(title: string, body: string { fulltext: true }, stars: uint64)";
+const TEXT_INSERTS: [&str; 4] = [
+    "insert into myspace.mymodel('rust', 'Fearless concurrency, zero-cost abstractions', 10)",
+    "insert into myspace.mymodel('go', 'Simple concurrency with goroutines', 7)",
+    "insert into myspace.mymodel('c', 'Zero abstractions and no safety net', 3)",
+    "insert into myspace.mymodel('haskell', 'Lazy, pure and fearless', 5)",
+];
+
+fn select_text_sorted(global: &TestGlobal<NullFS>, select: &str) -> Vec<Vec<Datacell>> {
+    let mut ret = super::_exec_only_select_range(global, select).unwrap();
+    ret.sort_by(|a, b| a[0].str().cmp(b[0].str()));
+    ret
+}
+
+#[test]
+fn select_text_search() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_select_range(
+        &global,
+        TEXT_MODEL,
+        &TEXT_INSERTS,
+        "select title from myspace.mymodel where body contains 'x'",
+    )
+    .unwrap();
+    assert_eq!(
+        select_text_sorted(
+            &global,
+            "select title from myspace.mymodel where body contains 'CONCURRENCY'"
+        ),
+        vec![intovec!["go"], intovec!["rust"]]
+    );
+    // every word must be present
+    assert_eq!(
+        select_text_sorted(
+            &global,
+            "select title from myspace.mymodel where body contains 'zero abstractions'"
+        ),
+        vec![intovec!["c"], intovec!["rust"]]
+    );
+    assert!(select_text_sorted(
+        &global,
+        "select title from myspace.mymodel where body contains 'zero goroutines'"
+    )
+    .is_empty());
+    // with other conditions and a window
+    assert_eq!(
+        select_text_sorted(
+            &global,
+            "select title from myspace.mymodel where body contains 'fearless' and stars > 5"
+        ),
+        vec![intovec!["rust"]]
+    );
+    assert_eq!(
+        super::_exec_only_select_range(
+            &global,
+            "select title from myspace.mymodel where body contains 'abstractions' order by stars limit 1"
+        )
+        .unwrap(),
+        vec![intovec!["c"]]
+    );
+}
+
+#[test]
+fn select_text_search_tracks_changes() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_select_range(
+        &global,
+        TEXT_MODEL,
+        &TEXT_INSERTS,
+        "select title from myspace.mymodel where body contains 'x'",
+    )
+    .unwrap();
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set body = 'Memory safety without fear' where title = 'rust'",
+    )
+    .unwrap();
+    super::_exec_delete_only(
+        &global,
+        "delete from myspace.mymodel where title = 'go'",
+        "go",
+    )
+    .unwrap();
+    assert!(select_text_sorted(
+        &global,
+        "select title from myspace.mymodel where body contains 'concurrency'"
+    )
+    .is_empty());
+    assert_eq!(
+        select_text_sorted(
+            &global,
+            "select title from myspace.mymodel where body contains 'safety'"
+        ),
+        vec![intovec!["c"], intovec!["rust"]]
+    );
+    // a condition on an update
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set stars += 1 where title = 'c' and body contains 'goroutines'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlConditionFailed
+    );
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set stars += 1 where title = 'c' and body contains 'net'",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select stars from myspace.mymodel where title = 'c'"
+        )
+        .unwrap(),
+        intovec![4u64]
+    );
+}
+
+#[test]
+fn select_text_search_bad() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_select_range(
+        &global,
+        TEXT_MODEL,
+        &TEXT_INSERTS,
+        "select title from myspace.mymodel where body contains 'x'",
+    )
+    .unwrap();
+    for (select, error) in [
+        // no full-text index
+        (
+            "select title from myspace.mymodel where title contains 'rust'",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        // not a string
+        (
+            "select title from myspace.mymodel where body contains 10",
+            QueryError::QExecDmlValidationError,
+        ),
+        // no words to search for
+        (
+            "select title from myspace.mymodel where body contains ', ;'",
+            QueryError::QExecDmlValidationError,
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_select_range(&global, select).unwrap_err(),
+            error
+        );
+    }
+}
+
+const GEO_MODEL: &str =
+    "create model myspace.mymodel(name: string, location: geopoint, stars: uint64)";
+const GEO_INSERTS: [&str; 5] = [
+    "insert into myspace.mymodel('a', '10,20', 10)",
+    "insert into myspace.mymodel('b', '10.1,20', 7)",
+    "insert into myspace.mymodel('c', '11,20', 3)",
+    "insert into myspace.mymodel('d', '0,179.9', 5)",
+    "insert into myspace.mymodel('e', '-10,-20', 1)",
+];
+
+#[test]
+fn select_geo_search() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_select_range(
+        &global,
+        GEO_MODEL,
+        &GEO_INSERTS,
+        "select name from myspace.mymodel where location within (0, 0, 1)",
+    )
+    .unwrap();
+    assert_eq!(
+        select_text_sorted(
+            &global,
+            "select name from myspace.mymodel where location within (10, 20, 20000)"
+        ),
+        vec![intovec!["a"], intovec!["b"]]
+    );
+    assert_eq!(
+        select_text_sorted(
+            &global,
+            "select name from myspace.mymodel where location within (10, 20, 200000)"
+        ),
+        vec![intovec!["a"], intovec!["b"], intovec!["c"]]
+    );
+    // across the antimeridian
+    assert_eq!(
+        select_text_sorted(
+            &global,
+            "select name from myspace.mymodel where location within (0, -180, 50000)"
+        ),
+        vec![intovec!["d"]]
+    );
+    // with other conditions and a window
+    assert_eq!(
+        select_text_sorted(
+            &global,
+            "select name from myspace.mymodel where location within (10, 20, 200000) and stars < 10"
+        ),
+        vec![intovec!["b"], intovec!["c"]]
+    );
+    assert_eq!(
+        super::_exec_only_select_range(
+            &global,
+            "select name, location from myspace.mymodel where location within (10, 20, 200000) order by stars limit 1"
+        )
+        .unwrap(),
+        vec![vec![Datacell::from("c"), Datacell::new_geopoint(11.0, 20.0)]]
+    );
+}
+
+#[test]
+fn select_geo_search_tracks_changes() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_select_range(
+        &global,
+        GEO_MODEL,
+        &GEO_INSERTS,
+        "select name from myspace.mymodel where location within (0, 0, 1)",
+    )
+    .unwrap();
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set location = '-10,-20.01' where name = 'a'",
+    )
+    .unwrap();
+    super::_exec_delete_only(&global, "delete from myspace.mymodel where name = 'b'", "b").unwrap();
+    assert!(select_text_sorted(
+        &global,
+        "select name from myspace.mymodel where location within (10, 20, 20000)"
+    )
+    .is_empty());
+    assert_eq!(
+        select_text_sorted(
+            &global,
+            "select name from myspace.mymodel where location within (-10, -20, 5000)"
+        ),
+        vec![intovec!["a"], intovec!["e"]]
+    );
+    // a condition on an update
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set stars += 1 where name = 'c' and location within (10, 20, 1000)",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlConditionFailed
+    );
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set stars += 1 where name = 'c' and location within (10, 20, 200000)",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select stars from myspace.mymodel where name = 'c'"
+        )
+        .unwrap(),
+        intovec![4u64]
+    );
+}
+
+#[test]
+fn select_geo_search_bad() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_select_range(
+        &global,
+        GEO_MODEL,
+        &GEO_INSERTS,
+        "select name from myspace.mymodel where location within (0, 0, 1)",
+    )
+    .unwrap();
+    for (select, error) in [
+        // not a point
+        (
+            "select name from myspace.mymodel where stars within (10, 20, 1000)",
+            QueryError::QExecDmlValidationError,
+        ),
+        // not a valid center
+        (
+            "select name from myspace.mymodel where location within (91, 20, 1000)",
+            QueryError::QExecDmlValidationError,
+        ),
+        // negative radius
+        (
+            "select name from myspace.mymodel where location within (10, 20, -1)",
+            QueryError::QExecDmlValidationError,
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_select_range(&global, select).unwrap_err(),
+            error
+        );
+    }
+    for insert in [
+        "insert into myspace.mymodel('f', '10', 1)",
+        "insert into myspace.mymodel('f', '10,181', 1)",
+        "insert into myspace.mymodel('f', 10, 1)",
+    ] {
+        assert_eq!(
+            super::exec_insert_only(&global, insert).unwrap_err(),
+            QueryError::QExecDmlValidationError
+        );
+    }
+}
+
+const PATTERN_INSERTS: [&str; 7] = [
+    "insert into myspace.mymodel('sayan', 10)",
+    "insert into myspace.mymodel('sam', 7)",
+    "insert into myspace.mymodel('samuel', 3)",
+    "insert into myspace.mymodel('sa_m', 5)",
+    "insert into myspace.mymodel('s', 1)",
+    "insert into myspace.mymodel('tom', 4)",
+    "insert into myspace.mymodel('\u{10FFFF}x', 2)",
+];
+
+fn select_pattern(select: &str) -> QueryResult<Vec<Vec<Datacell>>> {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    let mut ret = super::exec_select_range(
+        &global,
+        "create model myspace.mymodel(username: string, visits: uint64)",
+        &PATTERN_INSERTS,
+        select,
+    )?;
+    ret.sort_by(|a, b| a[0].str().cmp(b[0].str()));
+    Ok(ret)
+}
+
+#[test]
+fn select_pattern_prefix() {
+    assert_eq!(
+        select_pattern("select username from myspace.mymodel where username like 'sa%'").unwrap(),
+        vec![
+            intovec!["sa_m"],
+            intovec!["sam"],
+            intovec!["samuel"],
+            intovec!["sayan"]
+        ]
+    );
+    assert_eq!(
+        select_pattern("select username from myspace.mymodel where username like 'sam%'").unwrap(),
+        vec![intovec!["sam"], intovec!["samuel"]]
+    );
+    assert!(
+        select_pattern("select username from myspace.mymodel where username like 'x%'")
+            .unwrap()
+            .is_empty()
+    );
+    // with a window
+    assert_eq!(
+        select_pattern(
+            "select username from myspace.mymodel where username like 's%' order by visits desc limit 2"
+        )
+        .unwrap(),
+        vec![intovec!["sam"], intovec!["sayan"]]
+    );
+}
+
+#[test]
+fn select_pattern_wildcards() {
+    for (pattern, expected) in [
+        // `_` is any one character
+        ("sa_", vec![intovec!["sam"]]),
+        (
+            "sa_%",
+            vec![
+                intovec!["sa_m"],
+                intovec!["sam"],
+                intovec!["samuel"],
+                intovec!["sayan"],
+            ],
+        ),
+        // `%` can be anywhere
+        (
+            "s%m%",
+            vec![intovec!["sa_m"], intovec!["sam"], intovec!["samuel"]],
+        ),
+        ("s%n", vec![intovec!["sayan"]]),
+        (
+            "s%%",
+            vec![
+                intovec!["s"],
+                intovec!["sa_m"],
+                intovec!["sam"],
+                intovec!["samuel"],
+                intovec!["sayan"],
+            ],
+        ),
+        // escapes
+        ("sa\\\\_%", vec![intovec!["sa_m"]]),
+        // no wildcards at all
+        ("sam", vec![intovec!["sam"]]),
+        // a prefix with no successor
+        ("\u{10FFFF}%", vec![intovec!["\u{10FFFF}x"]]),
+    ] {
+        assert_eq!(
+            select_pattern(&format!(
+                "select username from myspace.mymodel where username like '{pattern}'"
+            ))
+            .unwrap(),
+            expected,
+            "{pattern}"
+        );
+    }
+}
+
+#[test]
+fn select_pattern_with_conditions() {
+    assert_eq!(
+        select_pattern(
+            "select username from myspace.mymodel where username like 'sa%' and visits > 5"
+        )
+        .unwrap(),
+        vec![intovec!["sam"], intovec!["sayan"]]
+    );
+    // patterns can be conditions too
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_select_range(
+        &global,
+        "create model myspace.mymodel(username: string, email: string)",
+        &["insert into myspace.mymodel('sayan', 'sayan@example.com')"],
+        "select username from myspace.mymodel where username like 's%'",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set email = 'x' where username = 'sayan' and email like '%@skytable.io'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlConditionFailed
+    );
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set email = 'sayan@skytable.io' where username = 'sayan' and email like '%@example.com'",
+    )
+    .unwrap();
+}
+
+#[test]
+fn select_pattern_bad() {
+    for (select, error) in [
+        // would need a full scan
+        (
+            "select username from myspace.mymodel where username like '%an'",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        (
+            "select username from myspace.mymodel where username like '_am'",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        // not the primary key
+        (
+            "select username from myspace.mymodel where visits like '1%'",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        // not a string
+        (
+            "select username from myspace.mymodel where username like 10",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        // a lone escape
+        (
+            "select username from myspace.mymodel where username like 'sa\\\\'",
+            QueryError::QExecDmlValidationError,
+        ),
+    ] {
+        assert_eq!(select_pattern(select).unwrap_err(), error, "{select}");
+    }
+}
+
+#[test]
+fn select_list_elements() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, notes: list { type: string }, null tags: list { type: string })",
+            "insert into myspace.mymodel('sayan', ['first', 'second'], null)",
+            "select notes[1], notes[5], tags[0], username from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap(),
+        intovec!["second", Datacell::null(), Datacell::null(), "sayan"]
+    );
+    for select in [
+        "select username[0] from myspace.mymodel where username = 'sayan'",
+        "select notes[0][0] from myspace.mymodel where username = 'sayan'",
+    ] {
+        assert_eq!(
+            super::_exec_only_select(&global, select).unwrap_err(),
+            QueryError::QExecDmlValidationError
+        );
+    }
+}
+
+#[test]
+fn select_dict_keys() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, profile: dict, logins: list { type: dict }, card: dict { schema: { name: 'string', home: 'geopoint' } })",
+            "insert into myspace.mymodel('sayan', { name: 'Sayan', tags: ['rust', 'db'], address: { city: 'Kolkata' } }, [{ at: 1 }, {}], { name: 'Sayan' })",
+            "select profile.name, profile.address.city, profile.tags[1], profile.missing, profile.name.first, profile.name[0], \
+            logins[0].at, logins[1].at, logins[5].at, card.name, card.home from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap(),
+        intovec![
+            "Sayan",
+            "Kolkata",
+            "db",
+            Datacell::null(),
+            Datacell::null(),
+            Datacell::null(),
+            1u64,
+            Datacell::null(),
+            Datacell::null(),
+            "Sayan",
+            Datacell::null()
+        ]
+    );
+    for select in [
+        "select username.name from myspace.mymodel where username = 'sayan'",
+        "select profile[0] from myspace.mymodel where username = 'sayan'",
+        "select logins.at from myspace.mymodel where username = 'sayan'",
+        "select logins[0][0] from myspace.mymodel where username = 'sayan'",
+        // only the keys in the schema, which are never lists or dicts
+        "select card.age from myspace.mymodel where username = 'sayan'",
+        "select card.name.first from myspace.mymodel where username = 'sayan'",
+        "select card.name[0] from myspace.mymodel where username = 'sayan'",
+    ] {
+        assert_eq!(
+            super::_exec_only_select(&global, select).unwrap_err(),
+            QueryError::QExecDmlValidationError,
+            "{select}"
+        );
+    }
+}
+
+#[test]
+fn select_keys() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_select_range(
+        &global,
+        "create model myspace.mymodel(username: string, followers: uint64, notes: list { type: string })",
+        &[
+            "insert into myspace.mymodel('sayan', 100, ['a', 'b'])",
+            "insert into myspace.mymodel('elana', 50, [])",
+            "insert into myspace.mymodel('jeff', 10, ['c'])",
+        ],
+        "select username from myspace.mymodel where username > 'a'",
+    )
+    .unwrap();
+    // one row for every key, in order
+    assert_eq!(
+        super::_exec_only_select_keys(
+            &global,
+            "select followers, notes[0] from myspace.mymodel where username in ('jeff', 'nobody', 'sayan', 'jeff')"
+        )
+        .unwrap(),
+        vec![
+            intovec![true, 10u64, "c"],
+            intovec![false, Datacell::null(), Datacell::null()],
+            intovec![true, 100u64, "a"],
+            intovec![true, 10u64, "c"],
+        ]
+    );
+    assert_eq!(
+        super::_exec_only_select_keys(
+            &global,
+            "select * from myspace.mymodel where username in ('elana', 'nobody')"
+        )
+        .unwrap(),
+        vec![
+            intovec![true, "elana", 50u64, Datacell::new_list(vec![])],
+            intovec![false, Datacell::null(), Datacell::null(), Datacell::null()],
+        ]
+    );
+    // rows that don't satisfy the other clauses are misses
+    assert_eq!(
+        super::_exec_only_select_keys(
+            &global,
+            "select username from myspace.mymodel where username in ('sayan', 'elana') and followers > 60"
+        )
+        .unwrap(),
+        vec![intovec![true, "sayan"], intovec![false, Datacell::null()]]
+    );
+    for (select, error) in [
+        // not the primary key
+        (
+            "select username from myspace.mymodel where followers in (100, 50)",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        // not the primary key's type
+        (
+            "select username from myspace.mymodel where username in ('sayan', 1)",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+        (
+            "select nope from myspace.mymodel where username in ('sayan')",
+            QueryError::QExecUnknownField,
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_select_keys(&global, select).unwrap_err(),
+            error
+        );
+    }
+    // `in` can also be used as a condition
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set followers += 1 where username = 'jeff' and followers in (50, 100)",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlConditionFailed
+    );
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set followers += 1 where username = 'jeff' and followers in (10, 100)",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select followers from myspace.mymodel where username = 'jeff'"
+        )
+        .unwrap(),
+        intovec![11u64]
+    );
+}
+
+fn create_lookup_models(global: &TestGlobal<NullFS>) {
+    super::_exec_only_create_space_model(
+        global,
+        "create model myspace.users(username: string, name: string, followers: uint64)",
+    )
+    .unwrap();
+    super::_exec_only_create_space_model(
+        global,
+        "create model myspace.posts(id: uint64, title: string, author: string, null editor: string)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.users('sayan', 'Sayan', 100)",
+        "insert into myspace.users('elana', 'Elana', 50)",
+        "insert into myspace.posts(1, 'hello', 'sayan', 'elana')",
+        "insert into myspace.posts(2, 'world', 'elana', null)",
+        "insert into myspace.posts(3, 'orphan', 'nobody', null)",
+    ] {
+        super::exec_insert_only(global, insert).unwrap();
+    }
+}
+
+#[test]
+fn select_lookup() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    create_lookup_models(&global);
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select title, users[author], users[editor].name from myspace.posts where id = 1"
+        )
+        .unwrap(),
+        intovec![
+            "hello",
+            Datacell::new_list(intovec!["sayan", "Sayan", 100u64]),
+            "Elana"
+        ]
+    );
+    // a null key or a missing row is null
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select users[editor], users[author].followers from myspace.posts where id = 3"
+        )
+        .unwrap(),
+        intovec![Datacell::null(), Datacell::null()]
+    );
+    // every row of a multi-row select
+    assert_eq!(
+        super::_exec_only_select_range(
+            &global,
+            "select id, users[author].name from myspace.posts where id > 0 order by id"
+        )
+        .unwrap(),
+        vec![
+            intovec![1u64, "Sayan"],
+            intovec![2u64, "Elana"],
+            intovec![3u64, Datacell::null()]
+        ]
+    );
+    // the same model
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select users[username].followers from myspace.users where username = 'elana'"
+        )
+        .unwrap(),
+        intovec![50u64]
+    );
+}
+
+#[test]
+fn select_lookup_bad() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    create_lookup_models(&global);
+    for (select, error) in [
+        (
+            "select nope[author] from myspace.posts where id = 1",
+            QueryError::QExecObjectNotFound,
+        ),
+        (
+            "select users[nope] from myspace.posts where id = 1",
+            QueryError::QExecUnknownField,
+        ),
+        (
+            "select users[author].nope from myspace.posts where id = 1",
+            QueryError::QExecUnknownField,
+        ),
+        // the key isn't a string
+        (
+            "select users[id] from myspace.posts where id = 1",
+            QueryError::QExecDmlValidationError,
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_select(&global, select).unwrap_err(),
+            error
+        );
+    }
+}
+
+#[test]
+fn select_lookup_needs_grant() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    create_lookup_models(&global);
+    let sys = global.sys_store();
+    sys.create_new_user("sayan".into(), "password12345678".into())
+        .unwrap();
+    sys.grant_user("sayan", "myspace", Some("posts")).unwrap();
+    let sayan = ClientLocalState::new_test("sayan", false);
+    let select = |query: &str| {
+        let tok = lex_insecure(query.as_bytes()).unwrap();
+        dml::select_resp(&global, &sayan, parse_ast_node_full(&tok[1..]).unwrap())
+    };
+    assert!(select("select title, author from myspace.posts where id = 1").is_ok());
+    // the user can't read the users model, not even through a lookup
+    for query in [
+        "select title, users[author] from myspace.posts where id = 1",
+        "select users[author].name from myspace.posts where id > 0",
+        "select users[author].name from myspace.posts where id in (1, 2)",
+    ] {
+        assert_eq!(select(query).unwrap_err(), QueryError::SysPermissionDenied);
+    }
+    let tok =
+        lex_insecure(b"explain select users[author] from myspace.posts where id = 1").unwrap();
+    assert_eq!(
+        dml::explain(&global, &sayan, parse_ast_node_full(&tok[1..]).unwrap()).unwrap_err(),
+        QueryError::SysPermissionDenied
+    );
+}
+
+#[test]
+fn select_row_cache() {
+    fn select(global: &TestGlobal<NullFS>, select: &str) -> QueryResult<Response> {
+        let tok = lex_insecure(select.as_bytes()).unwrap();
+        dml::select_resp(
+            global,
+            &ClientLocalState::new_test("root", true),
+            parse_ast_node_full(&tok[1..]).unwrap(),
+        )
+    }
+    fn run(global: &TestGlobal<NullFS>, query: &str) {
+        let tok = lex_insecure(query.as_bytes()).unwrap();
+        match query.split_once(' ').unwrap().0 {
+            "insert" => super::exec_insert_only(global, query).unwrap(),
+            "update" => super::_exec_only_update(global, query).unwrap(),
+            "alter" => {
+                Model::transactional_exec_alter(global, parse_ast_node_full(&tok[2..]).unwrap())
+                    .unwrap()
+            }
+            _ => {
+                dml::delete(global, parse_ast_node_full(&tok[1..]).unwrap()).unwrap();
+            }
+        }
+    }
+    let cached = TestGlobal::new_with_tmp_nullfs_driver().with_row_cache(1 << 20);
+    let uncached = TestGlobal::new_with_tmp_nullfs_driver();
+    for global in [&cached, &uncached] {
+        super::_exec_only_create_space_model(
+            global,
+            "create model myspace.mymodel(username: string, password: string)",
+        )
+        .unwrap();
+        run(global, "insert into myspace.mymodel('sayan', 'pass123')");
+    }
+    let sayan = "select * from myspace.mymodel where username = 'sayan'";
+    let expect_same = |hits, misses| {
+        assert_eq!(select(&cached, sayan), select(&uncached, sayan));
+        assert_eq!(cached.row_cache().hits(), hits);
+        assert_eq!(cached.row_cache().misses(), misses);
+    };
+    // the first read fills the cache, and the second one is served from it
+    expect_same(0, 1);
+    expect_same(1, 1);
+    assert_ne!(cached.row_cache().used(), 0);
+    // only full rows are cached
+    for global in [&cached, &uncached] {
+        select(
+            global,
+            "select password from myspace.mymodel where username = 'sayan'",
+        )
+        .unwrap();
+        select(
+            global,
+            "select * from myspace.mymodel where username = 'sayan' with version",
+        )
+        .unwrap();
+    }
+    assert_eq!(cached.row_cache().misses(), 1);
+    // changes to the row (or the schema) invalidate it
+    for (query, used) in [
+        (
+            "update myspace.mymodel set password = 'pass456' where username = 'sayan'",
+            2,
+        ),
+        (
+            "alter model myspace.mymodel add email { type: string, nullable: true }",
+            3,
+        ),
+    ] {
+        for global in [&cached, &uncached] {
+            run(global, query);
+        }
+        assert_eq!(cached.row_cache().used(), 0);
+        expect_same(used - 1, used);
+        expect_same(used, used);
+    }
+    for global in [&cached, &uncached] {
+        run(
+            global,
+            "delete from myspace.mymodel where username = 'sayan'",
+        );
+    }
+    assert_eq!(cached.row_cache().used(), 0);
+    assert_eq!(
+        select(&cached, sayan).unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+}
+
+#[test]
+fn select_row_cache_budget() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver().with_row_cache(1 << 10);
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(id: uint64, notes: string)",
+    )
+    .unwrap();
+    let notes = "x".repeat(100);
+    for id in 0..100 {
+        super::exec_insert_only(
+            &global,
+            &format!("insert into myspace.mymodel({id}, '{notes}')"),
+        )
+        .unwrap();
+    }
+    for _ in 0..2 {
+        for id in 0..100 {
+            let select = format!("select * from myspace.mymodel where id = {id}");
+            let tok = lex_insecure(select.as_bytes()).unwrap();
+            dml::select_resp(
+                &global,
+                &ClientLocalState::new_test("root", true),
+                parse_ast_node_full(&tok[1..]).unwrap(),
+            )
+            .unwrap();
+        }
+    }
+    // the budget only fits a few rows, so the cold ones were evicted to make room
+    let budget = global.row_cache();
+    assert!(budget.used() <= budget.limit());
+    assert_ne!(budget.evictions(), 0);
+    global
+        .namespace()
+        .with_model(("myspace", "mymodel").into(), |mdl| {
+            let cache = mdl.delta_state().row_cache();
+            assert_ne!(cache.len(), 0);
+            // everything that was cached was read since, so the first pass just ages the rows
+            cache.evict_cold();
+            assert_ne!(cache.len(), 0);
+            cache.evict_cold();
+            assert_eq!(cache.len(), 0);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(global.row_cache().used(), 0);
+}
+
+fn select_for_update(
+    global: &TestGlobal<NullFS>,
+    txn: &mut Transaction,
+    select: &str,
+) -> QueryResult<Vec<Datacell>> {
+    txn.scope(|| super::_exec_only_select(global, select))
+}
+
+fn held_intents(global: &TestGlobal<NullFS>) -> usize {
+    global
+        .namespace()
+        .with_model(("myspace", "mymodel").into(), |mdl| {
+            Ok(mdl.row_intents().held())
+        })
+        .unwrap()
+}
+
+#[test]
+fn select_for_update_standalone() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_select(
+            &global,
+            "create model myspace.mymodel(username: string, password: string)",
+            "insert into myspace.mymodel('sayan', 'pass123')",
+            "select * from myspace.mymodel where username = 'sayan' for update",
+        )
+        .unwrap(),
+        intovec!["sayan", "pass123"]
+    );
+    // the statement was its own transaction, so the intent was released when it was done
+    assert_eq!(held_intents(&global), 0);
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set password = 'pass321' where username = 'sayan'",
+    )
+    .unwrap();
+}
+
+#[test]
+fn select_for_update_holds_intent() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('sayan', 'pass123')",
+        "insert into myspace.mymodel('joe', 'pass456')",
+    ] {
+        super::exec_insert_only(&global, insert).unwrap();
+    }
+    let sayan = "select * from myspace.mymodel where username = 'sayan' for update";
+    let mut txn = Transaction::new();
+    assert_eq!(
+        select_for_update(&global, &mut txn, sayan).unwrap(),
+        intovec!["sayan", "pass123"]
+    );
+    // taking it again in the same transaction is fine
+    select_for_update(&global, &mut txn, sayan).unwrap();
+    assert_eq!(held_intents(&global), 1);
+    // other transactions can still read the row, but they can't take its intent or change it
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "pass123"]
+    );
+    assert_eq!(
+        select_for_update(&global, &mut Transaction::new(), sayan).unwrap_err(),
+        QueryError::QExecDmlRowLocked
+    );
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set password = 'pass321' where username = 'sayan'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowLocked
+    );
+    assert_eq!(
+        super::_exec_only_update_rows(
+            &global,
+            "update myspace.mymodel set password = 'pass321' where password = 'pass123'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowLocked
+    );
+    assert_eq!(
+        super::_exec_only_delete_many(
+            &global,
+            "delete from myspace.mymodel where password = 'pass123' limit 10",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowLocked
+    );
+    assert_eq!(
+        super::_exec_delete_only(
+            &global,
+            "delete from myspace.mymodel where username = 'sayan'",
+            "sayan",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowLocked
+    );
+    // the other rows are unaffected
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set password = 'pass654' where username = 'joe'",
+    )
+    .unwrap();
+    // once the transaction ends, the row can be changed again
+    drop(txn);
+    assert_eq!(held_intents(&global), 0);
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set password = 'pass321' where username = 'sayan'",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select password from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["pass321"]
+    );
+}
+
+#[test]
+fn select_for_update_missing_row() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    let mut txn = Transaction::new();
+    assert_eq!(
+        select_for_update(
+            &global,
+            &mut txn,
+            "select * from myspace.mymodel where username = 'sayan' for update"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+    assert_eq!(held_intents(&global), 0);
+}
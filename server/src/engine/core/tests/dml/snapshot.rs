@@ -0,0 +1,168 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    crate::engine::{
+        core::model::snapshot::Snapshot,
+        fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+        idx::STIndex,
+        sync,
+    },
+    std::{
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+    },
+};
+
+fn read(snapshot: &Snapshot) -> Vec<(String, String)> {
+    let g = sync::atm::cpin();
+    let mut rows = vec![];
+    snapshot
+        .scan(&g, |pk, fields| {
+            rows.push((
+                pk.str().unwrap().to_owned(),
+                fields.st_get("password").unwrap().str().to_owned(),
+            ));
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+    rows.sort();
+    rows
+}
+
+fn rows(rows: &[(&str, &str)]) -> Vec<(String, String)> {
+    rows.iter()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+        .collect()
+}
+
+#[test]
+fn snapshot_ignores_later_changes() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel('sayan', 'pass1')",
+        "insert into myspace.mymodel('elena', 'pass2')",
+        "insert into myspace.mymodel('adam', 'pass3')",
+    ] {
+        super::exec_insert_only(&global, insert).unwrap();
+    }
+    global
+        .namespace()
+        .with_model(("myspace", "mymodel").into(), |mdl| {
+            let snapshot = mdl.pin_snapshot();
+            // change every row in some way after the snapshot was pinned
+            super::_exec_only_update(
+                &global,
+                "update myspace.mymodel set password = 'newpass1' where username = 'sayan'",
+            )
+            .unwrap();
+            super::_exec_delete_only(
+                &global,
+                "delete from myspace.mymodel where username = 'elena'",
+                "elena",
+            )
+            .unwrap();
+            super::_exec_delete_only(
+                &global,
+                "delete from myspace.mymodel where username = 'adam'",
+                "adam",
+            )
+            .unwrap();
+            super::exec_insert_only(&global, "insert into myspace.mymodel('adam', 'newpass3')")
+                .unwrap();
+            super::exec_insert_only(&global, "insert into myspace.mymodel('joe', 'pass4')")
+                .unwrap();
+            assert_eq!(
+                read(&snapshot),
+                rows(&[("adam", "pass3"), ("elena", "pass2"), ("sayan", "pass1")])
+            );
+            // a new snapshot sees everything
+            assert_eq!(
+                read(&mdl.pin_snapshot()),
+                rows(&[
+                    ("adam", "newpass3"),
+                    ("joe", "pass4"),
+                    ("sayan", "newpass1")
+                ])
+            );
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn snapshot_sees_every_row_during_updates() {
+    const USERS: usize = 4;
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::_exec_only_create_space_model(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+    )
+    .unwrap();
+    for user in 0..USERS {
+        super::exec_insert_only(
+            &global,
+            &format!("insert into myspace.mymodel('user{user}', 'pass0')"),
+        )
+        .unwrap();
+    }
+    let done = AtomicBool::new(false);
+    let sizes = thread::scope(|s| {
+        for user in 0..USERS {
+            let (global, done) = (&global, &done);
+            s.spawn(move || {
+                let mut i = 0u64;
+                while !done.load(Ordering::Acquire) {
+                    i += 1;
+                    super::_exec_only_update(
+                        global,
+                        &format!(
+                            "update myspace.mymodel set password = 'pass{i}' where username = 'user{user}'"
+                        ),
+                    )
+                    .unwrap();
+                }
+            });
+        }
+        let sizes = global
+            .namespace()
+            .with_model(("myspace", "mymodel").into(), |mdl| {
+                Ok((0..5000)
+                    .map(|_| read(&mdl.pin_snapshot()).len())
+                    .collect::<Vec<_>>())
+            })
+            .unwrap();
+        done.store(true, Ordering::Release);
+        sizes
+    });
+    // a row that is being updated while the snapshot is pinned is read either before or after the update
+    assert!(sizes.iter().all(|size| *size == USERS));
+}
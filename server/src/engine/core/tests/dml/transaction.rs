@@ -0,0 +1,217 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::dml::Transaction,
+    data::cell::Datacell,
+    error::QueryError,
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    storage::v1::memfs::NullFS,
+};
+
+fn setup(global: &TestGlobal<NullFS>) {
+    super::_exec_only_create_space_model(
+        global,
+        "create model myspace.mymodel(id: uint64, username: string, followers: uint64)",
+    )
+    .unwrap();
+    for insert in [
+        "insert into myspace.mymodel(1, 'sayan', 100)",
+        "insert into myspace.mymodel(2, 'elana', 50)",
+        "insert into myspace.mymodel(3, 'jeff', 10)",
+    ] {
+        super::exec_insert_only(global, insert).unwrap();
+    }
+}
+
+fn rows(global: &impl GlobalInstanceLike) -> Vec<Vec<Datacell>> {
+    super::_exec_only_select_range(
+        global,
+        "select * from myspace.mymodel where id >= 0 order by id",
+    )
+    .unwrap()
+}
+
+fn initial_rows() -> Vec<Vec<Datacell>> {
+    vec![
+        intovec![1u64, "sayan", 100u64],
+        intovec![2u64, "elana", 50u64],
+        intovec![3u64, "jeff", 10u64],
+    ]
+}
+
+#[test]
+fn rollback_undoes_changes() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let mut txn = Transaction::new();
+    txn.scope(|| {
+        super::exec_insert_only(&global, "insert into myspace.mymodel(4, 'joe', 5)").unwrap();
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set followers += 1 where id = 1",
+        )
+        .unwrap();
+        assert_eq!(
+            super::_exec_only_update_rows(
+                &global,
+                "update myspace.mymodel set followers = 0 where followers < 60",
+            )
+            .unwrap(),
+            Some(3)
+        );
+        super::_exec_only_delete_many(&global, "delete from myspace.mymodel where id = 2 limit 1")
+            .unwrap();
+        // the id is free again, and is held by the transaction
+        super::exec_insert_only(&global, "insert into myspace.mymodel(2, 'elana2', 1)").unwrap();
+    });
+    // the changes are visible before the transaction ends
+    assert_eq!(
+        rows(&global),
+        vec![
+            intovec![1u64, "sayan", 101u64],
+            intovec![2u64, "elana2", 1u64],
+            intovec![3u64, "jeff", 0u64],
+            intovec![4u64, "joe", 0u64],
+        ]
+    );
+    assert_eq!(txn.changes(), 7);
+    txn.rollback(&global).unwrap();
+    assert_eq!(rows(&global), initial_rows());
+    // and the intents were released
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set followers += 1 where id = 2",
+    )
+    .unwrap();
+}
+
+#[test]
+fn rollback_undoes_failed_statement() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let mut txn = Transaction::new();
+    // the update stops at the first row that overflows, after the rows that were updated before it
+    let r = txn.scope(|| {
+        super::_exec_only_update_rows(
+            &global,
+            "update myspace.mymodel set followers -= 20 where id >= 0",
+        )
+    });
+    assert_eq!(r.unwrap_err(), QueryError::QExecDmlValidationError);
+    assert_ne!(txn.changes(), 0);
+    txn.rollback(&global).unwrap();
+    assert_eq!(rows(&global), initial_rows());
+}
+
+#[test]
+fn commit_keeps_changes() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let mut txn = Transaction::new();
+    txn.scope(|| {
+        super::_exec_only_delete_many(&global, "delete from myspace.mymodel where id = 3 limit 1")
+            .unwrap();
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set followers += 1 where id = 1",
+        )
+        .unwrap();
+    });
+    // the rows that were changed can't be changed by anyone else (even the deleted row can't be inserted again)
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set followers += 1 where id = 1",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowLocked
+    );
+    assert_eq!(
+        super::exec_insert_only(&global, "insert into myspace.mymodel(3, 'jeff', 10)").unwrap_err(),
+        QueryError::QExecDmlRowLocked
+    );
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set followers += 1 where id = 2",
+    )
+    .unwrap();
+    txn.commit(&global);
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set followers += 1 where id = 1",
+    )
+    .unwrap();
+    assert_eq!(
+        rows(&global),
+        vec![
+            intovec![1u64, "sayan", 102u64],
+            intovec![2u64, "elana", 51u64],
+        ]
+    );
+}
+
+#[test]
+fn transactions_conflict() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup(&global);
+    let (mut a, mut b) = (Transaction::new(), Transaction::new());
+    a.scope(|| {
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set followers += 1 where id = 1",
+        )
+    })
+    .unwrap();
+    assert_eq!(
+        b.scope(|| {
+            super::_exec_only_select(
+                &global,
+                "select * from myspace.mymodel where id = 1 for update",
+            )
+        })
+        .unwrap_err(),
+        QueryError::QExecDmlRowLocked
+    );
+    b.scope(|| {
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set followers += 1 where id = 2",
+        )
+    })
+    .unwrap();
+    // rolling back one doesn't touch the changes of the other
+    a.rollback(&global).unwrap();
+    b.commit(&global);
+    assert_eq!(
+        rows(&global),
+        vec![
+            intovec![1u64, "sayan", 100u64],
+            intovec![2u64, "elana", 51u64],
+            intovec![3u64, "jeff", 10u64],
+        ]
+    );
+}
@@ -25,7 +25,14 @@
 */
 
 use crate::engine::{
-    core::dml, data::cell::Datacell, error::QueryError, fractal::test_utils::TestGlobal,
+    core::{dml, model::Model},
+    data::{
+        cell::Datacell,
+        tag::{FullTag, TagSelector},
+    },
+    error::QueryError,
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    ql::{ast::parse_ast_node_full, dml::ins::T_TIMESEC, tests::lex_insecure},
 };
 
 #[test]
@@ -158,3 +165,667 @@ fn fail_typedef_violation() {
         intovec!["sayan", "pass123", 1u64]
     );
 }
+
+#[test]
+fn conditional() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, password: string, null email: string)",
+            "insert into myspace.mymodel('sayan', 'pass123', null)",
+            "update myspace.mymodel set password = 'pass321' where username = 'sayan' and password = 'pass123'",
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["sayan", "pass321", Datacell::null()]
+    );
+    // the condition no longer holds
+    for (update, error) in [
+        (
+            "update myspace.mymodel set password = 'pass000' where username = 'sayan' and password = 'pass123'",
+            QueryError::QExecDmlConditionFailed,
+        ),
+        (
+            "update myspace.mymodel set password = 'pass000' where username = 'sayan' and email = 'sayan@example.com'",
+            QueryError::QExecDmlConditionFailed,
+        ),
+        (
+            "update myspace.mymodel set password = 'pass000' where username = 'sayan' and password != 'pass321'",
+            QueryError::QExecDmlConditionFailed,
+        ),
+        (
+            "update myspace.mymodel set password = 'pass000' where username = 'sayan' and password = 100",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "update myspace.mymodel set password = 'pass000' where username = 'sayan' and pass = 'pass321'",
+            QueryError::QExecUnknownField,
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_update(&global, update).unwrap_err(),
+            error
+        );
+    }
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select password from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["pass321"]
+    );
+}
+
+#[test]
+fn timestamp() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    let now = T_TIMESEC * 1_000_000;
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, created_at: timestamp, null expires_at: timestamp)",
+            "insert into myspace.mymodel('sayan', @now(), 1000)",
+            "update myspace.mymodel set expires_at += 3600000000 where username = 'sayan' and expires_at < @now()",
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![
+            "sayan",
+            Datacell::new_timestamp(now),
+            Datacell::new_timestamp(3600001000)
+        ]
+    );
+    for (update, error) in [
+        (
+            "update myspace.mymodel set expires_at = @now() where username = 'sayan' and expires_at > @now()",
+            QueryError::QExecDmlConditionFailed,
+        ),
+        (
+            "update myspace.mymodel set expires_at = @now() where username = 'sayan' and created_at > 0 and created_at < @now()",
+            QueryError::QExecDmlConditionFailed,
+        ),
+        (
+            "update myspace.mymodel set expires_at = @now() where username = 'sayan' and created_at = 'yesterday'",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "update myspace.mymodel set expires_at -= 3600001001 where username = 'sayan'",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "update myspace.mymodel set expires_at *= 2 where username = 'sayan'",
+            QueryError::QExecDmlValidationError,
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_update(&global, update).unwrap_err(),
+            error
+        );
+    }
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set expires_at = @now() where username = 'sayan' and created_at > 0 and created_at <= @now()",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select expires_at from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_timestamp(now)]
+    );
+}
+
+#[test]
+fn sint128() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, balance: sint128)",
+            "insert into myspace.mymodel('sayan', '170141183460469231731687303715884105727')",
+            "update myspace.mymodel set balance -= 1 where username = 'sayan' and balance > 0",
+            "select balance from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_sint128(i128::MAX - 1)]
+    );
+    for (update, error) in [
+        // never wraps
+        (
+            "update myspace.mymodel set balance += 2 where username = 'sayan'",
+            QueryError::QExecDmlValidationError,
+        ),
+        // never rounds
+        (
+            "update myspace.mymodel set balance /= 5 where username = 'sayan'",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "update myspace.mymodel set balance /= 0 where username = 'sayan'",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "update myspace.mymodel set balance = '1.5' where username = 'sayan'",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "update myspace.mymodel set balance = false where username = 'sayan'",
+            QueryError::QExecDmlValidationError,
+        ),
+        (
+            "update myspace.mymodel set balance = 0 where username = 'sayan' and balance < -1",
+            QueryError::QExecDmlConditionFailed,
+        ),
+        (
+            "update myspace.mymodel set balance = 0 where username = 'sayan' and balance > 'zero'",
+            QueryError::QExecDmlValidationError,
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_update(&global, update).unwrap_err(),
+            error
+        );
+    }
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set balance /= 2 where username = 'sayan'",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select balance from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_sint128(i128::MAX / 2)]
+    );
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set balance = -5, balance *= 3 where username = 'sayan'",
+    )
+    .unwrap();
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select balance from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_sint128(-15)]
+    );
+}
+
+#[test]
+fn conditional_failure_leaves_row_unchanged() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    let row = super::exec_update(
+        &global,
+        "create model myspace.mymodel(username: string, password: string, null email: string)",
+        "insert into myspace.mymodel('sayan', 'pass123', null)",
+        "update myspace.mymodel set password = 'pass321' where username = 'sayan'",
+        "select * from myspace.mymodel where username = 'sayan' with version",
+    )
+    .unwrap();
+    let data_version = || {
+        global
+            .namespace()
+            .with_model(("myspace", "mymodel").into(), |mdl| {
+                Ok(mdl.delta_state().data_current_version())
+            })
+            .unwrap()
+    };
+    let before = data_version();
+    for update in [
+        "update myspace.mymodel set password = 'pass000' where username = 'sayan' and password = 'pass123'",
+        "update myspace.mymodel set password = 'pass000' where username = 'sayan' and email = 'sayan@example.com'",
+    ] {
+        assert_eq!(
+            super::_exec_only_update(&global, update).unwrap_err(),
+            QueryError::QExecDmlConditionFailed
+        );
+    }
+    assert_eq!(data_version(), before);
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select * from myspace.mymodel where username = 'sayan' with version"
+        )
+        .unwrap(),
+        row
+    );
+}
+
+#[test]
+fn versioned() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    let row = super::exec_update(
+        &global,
+        "create model myspace.mymodel(username: string, password: string)",
+        "insert into myspace.mymodel('sayan', 'pass123')",
+        "update myspace.mymodel set password = 'pass321' where username = 'sayan'",
+        "select password from myspace.mymodel where username = 'sayan' with version",
+    )
+    .unwrap();
+    assert_eq!(row[0], Datacell::from("pass321"));
+    let version = row[1].uint();
+    // a stale version is rejected
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            &format!(
+                "update myspace.mymodel set password = 'pass000' where username = 'sayan' if version = {}",
+                version + 1
+            )
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlConditionFailed
+    );
+    // the current version goes through, and bumps the version
+    super::_exec_only_update(
+        &global,
+        &format!(
+            "update myspace.mymodel set password = 'pass000' where username = 'sayan' if version = {version}"
+        ),
+    )
+    .unwrap();
+    let row = super::exec_select_only(
+        &global,
+        "select password from myspace.mymodel where username = 'sayan' with version",
+    )
+    .unwrap();
+    assert_eq!(row[0], Datacell::from("pass000"));
+    assert!(row[1].uint() > version);
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            &format!(
+                "update myspace.mymodel set password = 'pass111' where username = 'sayan' if version = {version}"
+            )
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlConditionFailed
+    );
+}
+
+#[test]
+fn list_elements() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_update(
+        &global,
+        "create model myspace.mymodel(link: string, tags: list { type: string })",
+        "insert into myspace.mymodel('example.com', ['a', 'b', 'c'])",
+        "update myspace.mymodel set tags += 'd' where link = 'example.com'",
+        "select tags from myspace.mymodel where link = 'example.com'",
+    )
+    .unwrap();
+    let mut traces = vec!["list;sametag"];
+    for (update, trace, tags) in [
+        ("tags -= -1", "list;remove", intovec!["a", "b", "c"]),
+        ("tags -= 0", "list;remove", intovec!["b", "c"]),
+        ("tags[1] = 'x'", "list;set", intovec!["b", "x"]),
+        ("tags[0] += 'y'", "list;insert", intovec!["y", "b", "x"]),
+        (
+            "tags[3] += 'z'",
+            "list;insert",
+            intovec!["y", "b", "x", "z"],
+        ),
+    ] {
+        super::_exec_only_update(
+            &global,
+            &format!("update myspace.mymodel set {update} where link = 'example.com'"),
+        )
+        .unwrap();
+        traces.push(trace);
+        assert_eq!(dml::update_flow_trace(), traces);
+        assert_eq!(
+            super::_exec_only_select(
+                &global,
+                "select tags from myspace.mymodel where link = 'example.com'"
+            )
+            .unwrap(),
+            intovec![Datacell::new_list(tags)]
+        );
+    }
+}
+
+#[test]
+fn fail_list_elements() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_update(
+        &global,
+        "create model myspace.mymodel(link: string, tags: list { type: string }, hits: uint64)",
+        "insert into myspace.mymodel('example.com', ['a', 'b'], 0)",
+        "update myspace.mymodel set tags += 'c' where link = 'example.com'",
+        "select tags from myspace.mymodel where link = 'example.com'",
+    )
+    .unwrap();
+    for update in [
+        // out of range
+        "tags -= 3",
+        "tags -= -4",
+        "tags[3] = 'x'",
+        "tags[4] += 'x'",
+        // bad element
+        "tags[0] = 1",
+        "tags -= 'a'",
+        // not a list
+        "hits[0] = 1",
+        // list changes are rolled back with the rest of the update
+        "tags -= 0, tags[0] = 'x', tags += 'd', hits += 1, tags[9] = 'y'",
+    ] {
+        assert_eq!(
+            super::_exec_only_update(
+                &global,
+                &format!("update myspace.mymodel set {update} where link = 'example.com'"),
+            )
+            .unwrap_err(),
+            QueryError::QExecDmlValidationError,
+            "{update}"
+        );
+        assert_eq!(
+            super::_exec_only_select(
+                &global,
+                "select * from myspace.mymodel where link = 'example.com'"
+            )
+            .unwrap(),
+            intovec![
+                "example.com",
+                Datacell::new_list(intovec!["a", "b", "c"]),
+                0u64
+            ]
+        );
+    }
+}
+
+#[test]
+fn increment_decrement() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    let pending_deltas = || {
+        global
+            .namespace()
+            .with_model(("myspace", "mymodel").into(), |mdl| {
+                Ok(mdl.delta_state().data_delta_queue_len())
+            })
+            .unwrap()
+    };
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, hits: uint8, balance: sint64)",
+            "insert into myspace.mymodel('sayan', 254, -1)",
+            "update myspace.mymodel set hits += 1, balance += 1 where username = 'sayan'",
+            "select hits, balance from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![255u64, 0i64]
+    );
+    let deltas = pending_deltas();
+    // the new value is published for the batch journal
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set hits -= 5, balance -= 10 where username = 'sayan'",
+    )
+    .unwrap();
+    assert_eq!(pending_deltas(), deltas + 1);
+    assert_eq!(
+        super::_exec_only_select(
+            &global,
+            "select hits, balance from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![250u64, -10i64]
+    );
+    // overflows are rejected and the row is left untouched
+    for update in [
+        "hits += 6",
+        "hits -= 251",
+        "hits /= 0",
+        "balance -= 9223372036854775799",
+        "balance += 9223372036854775808",
+        "balance -= 1, hits += 10",
+    ] {
+        assert_eq!(
+            super::_exec_only_update(
+                &global,
+                &format!("update myspace.mymodel set {update} where username = 'sayan'")
+            )
+            .unwrap_err(),
+            QueryError::QExecDmlValidationError,
+            "{update}"
+        );
+        assert_eq!(
+            dml::update_flow_trace().last().copied(),
+            Some("rollback"),
+            "{update}"
+        );
+        assert_eq!(pending_deltas(), deltas + 1);
+        assert_eq!(
+            super::_exec_only_select(
+                &global,
+                "select hits, balance from myspace.mymodel where username = 'sayan'"
+            )
+            .unwrap(),
+            intovec![250u64, -10i64]
+        );
+    }
+}
+
+#[test]
+fn after_widening() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    super::exec_update(
+        &global,
+        "create model myspace.mymodel(username: string, visits: uint8, history: list { type: uint8 })",
+        "insert into myspace.mymodel('sayan', 254, [254])",
+        "update myspace.mymodel set visits += 1, history += 255 where username = 'sayan'",
+        "select * from myspace.mymodel where username = 'sayan'",
+    )
+    .unwrap();
+    super::exec_insert_only(&global, "insert into myspace.mymodel('elon', 1, [1])").unwrap();
+    for update in ["visits += 1", "history += 256"] {
+        assert_eq!(
+            super::_exec_only_update(
+                &global,
+                &format!("update myspace.mymodel set {update} where username = 'sayan'")
+            )
+            .unwrap_err(),
+            QueryError::QExecDmlValidationError
+        );
+    }
+    // widen; the rows are upgraded when they're next used
+    let alter = lex_insecure(
+        b"alter model myspace.mymodel update (visits { type: uint64 }, history { type: list { type: uint16 } })",
+    )
+    .unwrap();
+    Model::transactional_exec_alter(&global, parse_ast_node_full(&alter[2..]).unwrap()).unwrap();
+    super::_exec_only_update(
+        &global,
+        "update myspace.mymodel set visits += 1, history += 256 where username = 'sayan'",
+    )
+    .unwrap();
+    let uint64 = FullTag::new_uint(TagSelector::UInt64);
+    let uint16 = FullTag::new_uint(TagSelector::UInt16);
+    for (username, visits, history) in [
+        ("sayan", 256u64, intovec![254u64, 255u64, 256u64]),
+        ("elon", 1, intovec![1u64]),
+    ] {
+        let row = super::_exec_only_select(
+            &global,
+            &format!("select visits, history from myspace.mymodel where username = '{username}'"),
+        )
+        .unwrap();
+        assert_eq!(row, intovec![visits, Datacell::new_list(history)]);
+        assert_eq!(row[0].tag(), uint64);
+        assert!(row[1].list().read().iter().all(|dc| dc.tag() == uint16));
+    }
+}
+
+#[test]
+fn computed() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    assert_eq!(
+        super::exec_update(
+            &global,
+            "create model myspace.mymodel(username: string, email: string, email_lc: string { compute: 'lower(email)' }, handle: string { compute: \"concat(username, '@', lower(email))\" })",
+            "insert into myspace.mymodel('sayan', 'Sayan@Example.com')",
+            "update myspace.mymodel set email = 'SN@Example.com' where username = 'sayan'",
+            "select * from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![
+            "sayan",
+            "SN@Example.com",
+            "sn@example.com",
+            "sayan@sn@example.com"
+        ]
+    );
+    // computed fields can't be set
+    assert_eq!(
+        super::_exec_only_update(
+            &global,
+            "update myspace.mymodel set email = 'a@b.com', email_lc = 'x' where username = 'sayan'",
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlValidationError
+    );
+    assert!(dml::update_flow_trace().ends_with(&["sametag;nonnull", "computed", "rollback"]));
+    assert_eq!(
+        super::exec_select_only(
+            &global,
+            "select email, email_lc from myspace.mymodel where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec!["SN@Example.com", "sn@example.com"]
+    );
+}
+
+const USER_MODEL: &str =
+    "create model myspace.mymodel(username: string, followers: uint64, verified: bool)";
+const USER_INSERTS: [&str; 4] = [
+    "insert into myspace.mymodel('sayan', 100, false)",
+    "insert into myspace.mymodel('elana', 50, false)",
+    "insert into myspace.mymodel('jeff', 10, false)",
+    "insert into myspace.mymodel('bill', 0, false)",
+];
+
+fn setup_users(global: &impl GlobalInstanceLike) {
+    super::_exec_only_create_space_model(global, USER_MODEL).unwrap();
+    for insert in USER_INSERTS {
+        super::exec_insert_only(global, insert).unwrap();
+    }
+}
+
+fn followers_and_verified(global: &impl GlobalInstanceLike, user: &str) -> Vec<Datacell> {
+    super::exec_select_only(
+        global,
+        &format!("select followers, verified from myspace.mymodel where username = '{user}'"),
+    )
+    .unwrap()
+}
+
+#[test]
+fn update_many() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup_users(&global);
+    assert_eq!(
+        super::_exec_only_update_rows(
+            &global,
+            "update myspace.mymodel set verified = true, followers += 1 where followers >= 50"
+        ),
+        Ok(Some(2))
+    );
+    assert_eq!(
+        followers_and_verified(&global, "sayan"),
+        intovec![101u64, true]
+    );
+    assert_eq!(
+        followers_and_verified(&global, "elana"),
+        intovec![51u64, true]
+    );
+    assert_eq!(
+        followers_and_verified(&global, "jeff"),
+        intovec![10u64, false]
+    );
+    // the new values are what the next update sees
+    assert_eq!(
+        super::_exec_only_update_rows(
+            &global,
+            "update myspace.mymodel set followers = 0 where verified = false and username > 'c'"
+        ),
+        Ok(Some(1))
+    );
+    assert_eq!(
+        followers_and_verified(&global, "jeff"),
+        intovec![0u64, false]
+    );
+    assert_eq!(
+        super::_exec_only_update_rows(
+            &global,
+            "update myspace.mymodel set followers = 1 where username in ('bill', 'nobody')"
+        ),
+        Ok(Some(1))
+    );
+    assert_eq!(
+        followers_and_verified(&global, "bill"),
+        intovec![1u64, false]
+    );
+    // nothing matches
+    assert_eq!(
+        super::_exec_only_update_rows(
+            &global,
+            "update myspace.mymodel set followers = 1 where followers > 1000"
+        ),
+        Ok(Some(0))
+    );
+    // a single row (by its primary key) doesn't return a count
+    assert_eq!(
+        super::_exec_only_update_rows(
+            &global,
+            "update myspace.mymodel set followers = 5 where username = 'bill'"
+        ),
+        Ok(None)
+    );
+}
+
+#[test]
+fn update_many_fails() {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    setup_users(&global);
+    for (update, error) in [
+        (
+            "update myspace.mymodel set followers = 1 where stars > 10",
+            QueryError::QExecUnknownField,
+        ),
+        (
+            "update myspace.mymodel set stars = 1 where followers > 10",
+            QueryError::QExecUnknownField,
+        ),
+        (
+            "update myspace.mymodel set followers = 'many' where followers > 10",
+            QueryError::QExecDmlValidationError,
+        ),
+        // only a single row can be updated with a version
+        (
+            "update myspace.mymodel set followers = 1 where followers > 10 if version = 1",
+            QueryError::QExecDmlWhereHasUnindexedColumn,
+        ),
+    ] {
+        assert_eq!(
+            super::_exec_only_update_rows(&global, update).unwrap_err(),
+            error,
+            "{update}"
+        );
+    }
+    // nothing was changed
+    assert_eq!(
+        followers_and_verified(&global, "sayan"),
+        intovec![100u64, false]
+    );
+    assert_eq!(
+        followers_and_verified(&global, "elana"),
+        intovec![50u64, false]
+    );
+}
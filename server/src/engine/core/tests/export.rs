@@ -0,0 +1,259 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{dml, export::export_model, space::Space},
+    error::QueryError,
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    ql::{
+        ast::parse_ast_node_full, dcl::DataFormat, dml::ins::InsertStatement, tests::lex_insecure,
+    },
+    storage::v1::{memfs::VirtualFS, RawFSInterface, RawFileInterfaceExt, RawFileInterfaceRead},
+};
+
+fn setup(global: &impl GlobalInstanceLike) {
+    let tok = lex_insecure(b"create space myspace").unwrap();
+    Space::transactional_exec_create(global, parse_ast_node_full(&tok[2..]).unwrap()).unwrap();
+    for model in [
+        "create model myspace.users(username: string, null email: string, tags: list { type: uint8 })",
+        "create model myspace.misc(id: sint64, null ratio: float64, blob: binary, ok: bool)",
+        "create model myspace.empty(id: uint64, name: string)",
+        "create model myspace.profiles(id: uint64, profile: dict)",
+        "create model myspace.places(name: string, location: geopoint)",
+    ] {
+        super::ddl_model::exec_create(global, model, false).unwrap();
+    }
+    for insert in [
+        &b"insert into myspace.users('sayan', null, [1, 2])"[..],
+        b"insert into myspace.users('elena', 'elena@\"home\", inc', [])",
+        b"insert into myspace.misc(-1, null, \r3\nab\n, true)",
+        b"insert into myspace.profiles(1, { name: 'sayan', tags: [1, 2], address: { zip: 7 } })",
+        b"insert into myspace.places('home', '10.5,-20')",
+    ] {
+        let tok = lex_insecure(insert).unwrap();
+        dml::insert(
+            global,
+            parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap(),
+        )
+        .unwrap();
+    }
+}
+
+/// Returns a global that exports into the (new) directory `export_dir`
+fn new_global(log_name: &str, export_dir: &str) -> TestGlobal {
+    VirtualFS::fs_create_dir_all(export_dir).unwrap();
+    TestGlobal::new_with_vfs_driver(log_name).with_export_dir(export_dir)
+}
+
+fn read_export(global: &impl GlobalInstanceLike, file: &str) -> String {
+    let dir = global.sys_store().export_dir().unwrap();
+    let mut f = VirtualFS::fs_fopen_rw(&format!("{dir}/{file}")).unwrap();
+    let mut data = vec![0; f.fext_file_length().unwrap() as usize];
+    f.fr_read_exact(&mut data).unwrap();
+    String::from_utf8(data).unwrap()
+}
+
+fn export(
+    global: &impl GlobalInstanceLike,
+    model: &str,
+    file: &str,
+    format: DataFormat,
+) -> Result<(u64, String), QueryError> {
+    let rows = export_model(global, ("myspace", model).into(), file, format)?;
+    Ok((rows, read_export(global, file)))
+}
+
+/// the rows of a model are scanned in no particular order
+fn either(got: &str, header: &str, rows: [&str; 2], sep: &str, footer: &str) -> bool {
+    [[0, 1], [1, 0]]
+        .into_iter()
+        .any(|[a, b]| got == format!("{header}{}{sep}{}{footer}", rows[a], rows[b]))
+}
+
+#[test]
+fn export_json() {
+    let global = new_global("export_json.global.db-tlog", "export_json");
+    setup(&global);
+    let (rows, json) =
+        export(&global, "users", "export_json_users.json", DataFormat::Json).unwrap();
+    assert_eq!(rows, 2);
+    assert!(either(
+        &json,
+        "[\n",
+        [
+            r#"{"username":"sayan","email":null,"tags":[1,2]}"#,
+            r#"{"username":"elena","email":"elena@\"home\", inc","tags":[]}"#,
+        ],
+        ",\n",
+        "\n]\n"
+    ));
+    assert_eq!(
+        export(&global, "misc", "export_json_misc.json", DataFormat::Json).unwrap(),
+        (
+            1,
+            "[\n{\"id\":-1,\"ratio\":null,\"blob\":\"61620a\",\"ok\":true}\n]\n".into()
+        )
+    );
+    assert_eq!(
+        export(&global, "empty", "export_json_empty.json", DataFormat::Json).unwrap(),
+        (0, "[\n]\n".into())
+    );
+    assert_eq!(
+        export(
+            &global,
+            "places",
+            "export_json_places.json",
+            DataFormat::Json
+        )
+        .unwrap(),
+        (
+            1,
+            "[\n{\"name\":\"home\",\"location\":\"10.5,-20\"}\n]\n".into()
+        )
+    );
+    assert_eq!(
+        export(&global, "profiles", "export_json_profiles.json", DataFormat::Json).unwrap(),
+        (
+            1,
+            "[\n{\"id\":1,\"profile\":{\"address\":{\"zip\":7},\"name\":\"sayan\",\"tags\":[1,2]}}\n]\n"
+                .into()
+        )
+    );
+}
+
+#[test]
+fn export_csv() {
+    let global = new_global("export_csv.global.db-tlog", "export_csv");
+    setup(&global);
+    let (rows, csv) = export(&global, "users", "export_csv_users.csv", DataFormat::Csv).unwrap();
+    assert_eq!(rows, 2);
+    assert!(either(
+        &csv,
+        "username,email,tags\n",
+        ["sayan,,\"[1,2]\"", "elena,\"elena@\"\"home\"\", inc\",[]"],
+        "\n",
+        "\n"
+    ));
+    assert_eq!(
+        export(&global, "misc", "export_csv_misc.csv", DataFormat::Csv).unwrap(),
+        (1, "id,ratio,blob,ok\n-1,,61620a,true\n".into())
+    );
+    assert_eq!(
+        export(&global, "empty", "export_csv_empty.csv", DataFormat::Csv).unwrap(),
+        (0, "id,name\n".into())
+    );
+    assert_eq!(
+        export(&global, "places", "export_csv_places.csv", DataFormat::Csv).unwrap(),
+        (1, "name,location\nhome,\"10.5,-20\"\n".into())
+    );
+    assert_eq!(
+        export(&global, "profiles", "export_csv_profiles.csv", DataFormat::Csv).unwrap(),
+        (
+            1,
+            "id,profile\n1,\"{\"\"address\"\":{\"\"zip\"\":7},\"\"name\"\":\"\"sayan\"\",\"\"tags\"\":[1,2]}\"\n"
+                .into()
+        )
+    );
+}
+
+#[test]
+fn export_fails_if_target_exists() {
+    let global = new_global(
+        "export_fails_if_target_exists.global.db-tlog",
+        "export_fails_if_target_exists",
+    );
+    setup(&global);
+    let path = "export_fails_if_target_exists.json";
+    export(&global, "misc", path, DataFormat::Json).unwrap();
+    assert_eq!(
+        export(&global, "users", path, DataFormat::Csv).unwrap_err(),
+        QueryError::QExecDdlObjectAlreadyExists
+    );
+    // the original export is untouched
+    assert_eq!(
+        export(
+            &global,
+            "misc",
+            "export_fails_if_target_exists.2.json",
+            DataFormat::Json
+        )
+        .unwrap()
+        .1,
+        read_export(&global, path)
+    );
+    assert_eq!(
+        export(
+            &global,
+            "nope",
+            "export_fails_if_target_exists.3.json",
+            DataFormat::Json
+        )
+        .unwrap_err(),
+        QueryError::QExecObjectNotFound
+    );
+}
+
+#[test]
+fn export_only_into_export_dir() {
+    // exports are disabled unless there's an export directory
+    let global = TestGlobal::new_with_vfs_driver("export_only_into_export_dir.global.db-tlog");
+    setup(&global);
+    assert_eq!(
+        export_model(
+            &global,
+            ("myspace", "misc").into(),
+            "misc.json",
+            DataFormat::Json
+        )
+        .unwrap_err(),
+        QueryError::SysPermissionDenied
+    );
+    // and only a plain file name is accepted
+    let global = new_global(
+        "export_only_into_export_dir.2.global.db-tlog",
+        "export_only_into_export_dir",
+    );
+    setup(&global);
+    for file in [
+        "",
+        ".",
+        "..",
+        "../misc.json",
+        "nested/misc.json",
+        "/tmp/misc.json",
+    ] {
+        assert_eq!(
+            export_model(&global, ("myspace", "misc").into(), file, DataFormat::Json).unwrap_err(),
+            QueryError::SysPermissionDenied
+        );
+    }
+    assert_eq!(
+        export(&global, "misc", "misc.json", DataFormat::Json)
+            .unwrap()
+            .0,
+        1
+    );
+}
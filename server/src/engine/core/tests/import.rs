@@ -0,0 +1,289 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{
+        dml,
+        export::export_model,
+        import::{import_model, ImportReport},
+        space::Space,
+    },
+    data::cell::Datacell,
+    error::{QueryError, QueryResult},
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    net::protocol::ClientLocalState,
+    ql::{ast::parse_ast_node_full, dcl::DataFormat, tests::lex_insecure},
+    storage::v1::{
+        memfs::VirtualFS, RawFSInterface, RawFileInterfaceExt, RawFileInterfaceRead,
+        RawFileInterfaceWrite,
+    },
+};
+
+const USERS: &str = "(username: string, null email: string, tags: list { type: uint8 })";
+const MISC: &str = "(id: sint64, null ratio: float64, blob: binary, ok: bool)";
+
+fn setup(global: &impl GlobalInstanceLike, models: &[(&str, &str)]) {
+    let tok = lex_insecure(b"create space myspace").unwrap();
+    Space::transactional_exec_create(global, parse_ast_node_full(&tok[2..]).unwrap()).unwrap();
+    for (name, decl) in models {
+        super::ddl_model::exec_create(global, &format!("create model myspace.{name}{decl}"), false)
+            .unwrap();
+    }
+}
+
+fn write_file(path: &str, data: &str) {
+    let mut f = VirtualFS::fs_fcreate_rw(path).unwrap();
+    f.fw_write_all(data.as_bytes()).unwrap();
+}
+
+fn read_file(path: &str) -> String {
+    let mut f = VirtualFS::fs_fopen_rw(path).unwrap();
+    let mut data = vec![0; f.fext_file_length().unwrap() as usize];
+    f.fr_read_exact(&mut data).unwrap();
+    String::from_utf8(data).unwrap()
+}
+
+fn import(
+    global: &impl GlobalInstanceLike,
+    model: &str,
+    path: &str,
+    format: DataFormat,
+) -> QueryResult<(u64, u64, Vec<(u64, QueryError)>)> {
+    import_model(global, ("myspace", model).into(), path, format)
+        .map(|r: ImportReport| (r.imported(), r.rejected(), r.errors().to_vec()))
+}
+
+fn select(global: &impl GlobalInstanceLike, select: &str) -> QueryResult<Vec<Datacell>> {
+    let tok = lex_insecure(select.as_bytes()).unwrap();
+    let mut r = vec![];
+    dml::select_custom(
+        global,
+        &ClientLocalState::new_test("root", true),
+        parse_ast_node_full(&tok[1..]).unwrap(),
+        |cell| r.push(cell.clone()),
+    )?;
+    Ok(r)
+}
+
+#[test]
+fn import_json() {
+    let global = TestGlobal::new_with_vfs_driver("import_json.global.db-tlog");
+    setup(&global, &[("users", USERS)]);
+    write_file(
+        "import_json.jsonl",
+        concat!(
+            "{\"username\": \"sayan\", \"email\": null, \"tags\": [1, 2]}\n",
+            "{\"username\":\"elena\",\"tags\":[]}\n",
+            "{\"username\": \"sayan\", \"tags\": []}\n",
+            "{\"username\": \"bad\", \"tags\": [256]}\n",
+            "not json\n",
+            "{\"username\": \"x\", \"tags\": [], \"age\": 1}\n",
+            "\n",
+            "{\"username\": \"\\u00e9lodie\\n\", \"tags\": [3]}",
+        ),
+    );
+    assert_eq!(
+        import(&global, "users", "import_json.jsonl", DataFormat::Json).unwrap(),
+        (
+            3,
+            4,
+            vec![
+                (3, QueryError::QExecDmlDuplicate),
+                (4, QueryError::QExecDmlValidationError),
+                (5, QueryError::QLInvalidSyntax),
+                (6, QueryError::QExecDmlValidationError),
+            ]
+        )
+    );
+    assert_eq!(
+        select(
+            &global,
+            "select * from myspace.users where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![
+            "sayan",
+            Datacell::null(),
+            Datacell::new_list(intovec![1u64, 2u64])
+        ]
+    );
+    assert_eq!(
+        select(
+            &global,
+            "select tags from myspace.users where username = 'élodie\n'"
+        )
+        .unwrap(),
+        intovec![Datacell::new_list(intovec![3u64])]
+    );
+}
+
+#[test]
+fn import_csv() {
+    let global = TestGlobal::new_with_vfs_driver("import_csv.global.db-tlog");
+    setup(&global, &[("users", USERS)]);
+    write_file(
+        "import_csv.csv",
+        concat!(
+            "tags,username,email\r\n",
+            "\"[1,2]\",sayan,\r\n",
+            "[],elena,\"elena@\"\"home\"\", inc\"\r\n",
+            "[],\"multi\nline\",\"\"\n",
+            "[],sayan,\n",
+            "[1],bob\n",
+            "\"[1, x]\",bob,\n",
+        ),
+    );
+    assert_eq!(
+        import(&global, "users", "import_csv.csv", DataFormat::Csv).unwrap(),
+        (
+            3,
+            3,
+            vec![
+                (6, QueryError::QExecDmlDuplicate),
+                (7, QueryError::QLInvalidSyntax),
+                (8, QueryError::QExecDmlValidationError),
+            ]
+        )
+    );
+    assert_eq!(
+        select(
+            &global,
+            "select * from myspace.users where username = 'sayan'"
+        )
+        .unwrap(),
+        intovec![
+            "sayan",
+            Datacell::null(),
+            Datacell::new_list(intovec![1u64, 2u64])
+        ]
+    );
+    assert_eq!(
+        select(
+            &global,
+            "select email from myspace.users where username = 'elena'"
+        )
+        .unwrap(),
+        intovec!["elena@\"home\", inc"]
+    );
+    assert_eq!(
+        select(
+            &global,
+            "select email from myspace.users where username = 'multi\nline'"
+        )
+        .unwrap(),
+        intovec![""]
+    );
+}
+
+#[test]
+fn import_bad_file() {
+    let global = TestGlobal::new_with_vfs_driver("import_bad_file.global.db-tlog");
+    setup(&global, &[("users", USERS)]);
+    write_file("import_bad_file.csv", "username,age\nsayan,10\n");
+    assert_eq!(
+        import(&global, "users", "import_bad_file.csv", DataFormat::Csv).unwrap_err(),
+        QueryError::QExecUnknownField
+    );
+    assert_eq!(
+        import(&global, "users", "import_bad_file.nope", DataFormat::Csv).unwrap_err(),
+        QueryError::SysServerError
+    );
+    assert_eq!(
+        import(&global, "nope", "import_bad_file.csv", DataFormat::Csv).unwrap_err(),
+        QueryError::QExecObjectNotFound
+    );
+    assert_eq!(
+        select(
+            &global,
+            "select * from myspace.users where username = 'sayan'"
+        )
+        .unwrap_err(),
+        QueryError::QExecDmlRowNotFound
+    );
+}
+
+#[test]
+fn import_exported() {
+    VirtualFS::fs_create_dir_all("import_exported").unwrap();
+    let global = TestGlobal::new_with_vfs_driver("import_exported.global.db-tlog")
+        .with_export_dir("import_exported");
+    setup(
+        &global,
+        &[
+            ("users", USERS),
+            ("misc", MISC),
+            ("users_json", USERS),
+            ("misc_json", MISC),
+            ("users_csv", USERS),
+            ("misc_csv", MISC),
+        ],
+    );
+    write_file(
+        "import_exported.jsonl",
+        concat!(
+            "{\"username\": \"sayan\", \"email\": null, \"tags\": [1, 2]}\n",
+            "{\"username\": \"elena\", \"email\": \"\", \"tags\": []}\n",
+            "{\"username\": \"a,\\\"b\\\"\", \"email\": \"tab\\there\", \"tags\": [0]}\n",
+        ),
+    );
+    import(&global, "users", "import_exported.jsonl", DataFormat::Json).unwrap();
+    write_file(
+        "import_exported.csv",
+        "id,ratio,blob,ok\n-1,,61620a,true\n2,1.5,,false\n3,-0.25,ff00,true\n",
+    );
+    import(&global, "misc", "import_exported.csv", DataFormat::Csv).unwrap();
+    for (model, rows) in [("users", 3), ("misc", 3)] {
+        for (format, ext) in [(DataFormat::Json, "json"), (DataFormat::Csv, "csv")] {
+            let file = format!("{model}.{ext}");
+            let copy = format!("{model}_{ext}");
+            export_model(&global, ("myspace", model).into(), &file, format).unwrap();
+            let path = format!("import_exported/{file}");
+            assert_eq!(
+                import(&global, &copy, &path, format).unwrap(),
+                (rows, 0, vec![])
+            );
+            let copy_file = format!("{copy}.{ext}");
+            export_model(
+                &global,
+                ("myspace", copy.as_str()).into(),
+                &copy_file,
+                format,
+            )
+            .unwrap();
+            assert_eq!(
+                lines(&read_file(&path)),
+                lines(&read_file(&format!("import_exported/{copy_file}")))
+            );
+        }
+    }
+}
+
+/// the rows of a model are scanned in no particular order
+fn lines(s: &str) -> Vec<&str> {
+    let mut lines: Vec<_> = s.lines().map(|l| l.trim_end_matches(',')).collect();
+    lines.sort();
+    lines
+}
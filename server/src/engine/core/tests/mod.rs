@@ -24,6 +24,12 @@
  *
 */
 
+mod catalog;
+mod cdc;
+mod ddl_misc;
 mod ddl_model;
 mod ddl_space;
 mod dml;
+mod export;
+mod import;
+mod prepared;
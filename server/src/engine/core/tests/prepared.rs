@@ -0,0 +1,91 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::prepared::{PreparedStatements, MAX_PREPARED_STATEMENTS},
+    error::QueryError,
+    ql::tests::lex_secure,
+};
+
+#[test]
+fn prepare_and_bind() {
+    let mut statements = PreparedStatements::default();
+    let query = "select * from myspace.mymodel where username = ?";
+    let id = statements.prepare(query.as_bytes()).unwrap();
+    let statement = statements.get(id.to_string().as_bytes()).unwrap();
+    assert_eq!(statement.query(), query.as_bytes());
+    let full = format!("{query}\x065\nsayan");
+    assert_eq!(
+        statement.bind(b"\x065\nsayan").unwrap(),
+        lex_secure(full.as_bytes(), query.len()).unwrap()
+    );
+    // statements are numbered in the order that they were prepared
+    assert_eq!(
+        statements.prepare(b"delete from myspace.mymodel where username = ?"),
+        Ok(id + 1)
+    );
+}
+
+#[test]
+fn prepare_bad() {
+    let mut statements = PreparedStatements::default();
+    // not a statement, or not a query at all
+    assert_eq!(
+        statements.prepare(b"myspace.mymodel"),
+        Err(QueryError::QLExpectedStatement)
+    );
+    assert_eq!(
+        statements.prepare(b""),
+        Err(QueryError::QLExpectedStatement)
+    );
+    // unknown ids
+    for id in [&b"1"[..], b"", b"one", b"-1"] {
+        assert_eq!(
+            statements.get(id).unwrap_err(),
+            QueryError::QLUnknownPreparedStatement
+        );
+    }
+}
+
+#[test]
+fn prepare_limit() {
+    let mut statements = PreparedStatements::default();
+    for _ in 0..MAX_PREPARED_STATEMENTS {
+        statements
+            .prepare(b"select * from myspace.mymodel where username = ?")
+            .unwrap();
+    }
+    assert_eq!(
+        statements.prepare(b"select * from myspace.mymodel where username = ?"),
+        Err(QueryError::QLPreparedStatementLimit)
+    );
+    assert!(statements
+        .get(MAX_PREPARED_STATEMENTS.to_string().as_bytes())
+        .unwrap()
+        .bind(b"\x00")
+        .unwrap()
+        .contains(&Token![null]));
+}
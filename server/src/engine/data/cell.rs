@@ -32,6 +32,7 @@ use {
     crate::engine::{
         self,
         data::{
+            dict::DictCell,
             lit::Lit,
             tag::{DataTag, FloatSpec, FullTag, SIntSpec, TagClass, UIntSpec},
         },
@@ -161,6 +162,66 @@ impl Datacell {
     pub fn float(&self) -> f64 {
         self.try_float().unwrap()
     }
+    // timestamp (microseconds since the unix epoch)
+    pub fn new_timestamp(micros: u64) -> Self {
+        unsafe {
+            // UNSAFE(@ohsayan): Correct because we are initializing Self with the correct tag
+            Self::new(
+                FullTag::TIMESTAMP,
+                DataRaw::word(SpecialPaddedWord::store(micros).dwordqn_promote()),
+            )
+        }
+    }
+    pub unsafe fn read_timestamp(&self) -> u64 {
+        self.load_word()
+    }
+    pub fn try_timestamp(&self) -> Option<u64> {
+        self.checked_tag(TagClass::Timestamp, || unsafe {
+            // UNSAFE(@ohsayan): correct because we just verified the tag
+            self.read_timestamp()
+        })
+    }
+    pub fn timestamp(&self) -> u64 {
+        self.try_timestamp().unwrap()
+    }
+    // sint128
+    pub fn new_sint128(i: i128) -> Self {
+        unsafe {
+            // UNSAFE(@ohsayan): Correct because we are initializing Self with the correct tag
+            Self::new(FullTag::SINT128, DataRaw::word(WordIO::store(i)))
+        }
+    }
+    pub unsafe fn read_sint128(&self) -> i128 {
+        self.load_word()
+    }
+    pub fn try_sint128(&self) -> Option<i128> {
+        self.checked_tag(TagClass::BigInt, || unsafe {
+            // UNSAFE(@ohsayan): correct because we just verified the tag
+            self.read_sint128()
+        })
+    }
+    pub fn sint128(&self) -> i128 {
+        self.try_sint128().unwrap()
+    }
+    // geopoint
+    pub fn new_geopoint(lat: f64, lon: f64) -> Self {
+        unsafe {
+            // UNSAFE(@ohsayan): Correct because we are initializing Self with the correct tag
+            Self::new(FullTag::GEOPOINT, DataRaw::word(WordIO::store((lat, lon))))
+        }
+    }
+    pub unsafe fn read_geopoint(&self) -> (f64, f64) {
+        self.load_word()
+    }
+    pub fn try_geopoint(&self) -> Option<(f64, f64)> {
+        self.checked_tag(TagClass::Geo, || unsafe {
+            // UNSAFE(@ohsayan): correct because we just verified the tag
+            self.read_geopoint()
+        })
+    }
+    pub fn geopoint(&self) -> (f64, f64) {
+        self.try_geopoint().unwrap()
+    }
     // bin
     pub fn new_bin(s: Box<[u8]>) -> Self {
         let mut md = ManuallyDrop::new(s);
@@ -269,6 +330,35 @@ impl Datacell {
             Some(ManuallyDrop::into_inner(rwl).into_inner())
         }
     }
+    // dict
+    pub fn new_dict(d: DictCell) -> Self {
+        unsafe {
+            // UNSAFE(@ohsayan): Correct because we are initializing Self with the correct tag
+            Self::new(FullTag::DICT, DataRaw::dict(d))
+        }
+    }
+    pub unsafe fn read_dict(&self) -> &DictCell {
+        &self.data.dict
+    }
+    pub fn try_dict(&self) -> Option<&DictCell> {
+        self.checked_tag(TagClass::Dict, || unsafe {
+            // UNSAFE(@ohsayan): Correct because we just verified the tag
+            self.read_dict()
+        })
+    }
+    pub fn dict(&self) -> &DictCell {
+        self.try_dict().unwrap()
+    }
+    pub fn into_dict(self) -> Option<DictCell> {
+        if self.kind() != TagClass::Dict {
+            return None;
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): +tagck +avoid double free
+            let md = ManuallyDrop::new(self);
+            Some(ManuallyDrop::into_inner(core::ptr::read(&md.data.dict)))
+        }
+    }
     pub unsafe fn new_qw(qw: u64, tag: FullTag) -> Datacell {
         Self::new(
             tag,
@@ -294,13 +384,14 @@ direct_from! {
         &'static str as new_str,
         Vec<Self> as new_list,
         Box<[Self]> as new_list,
+        DictCell as new_dict,
     }
 }
 
 impl<'a> From<Lit<'a>> for Datacell {
     fn from(l: Lit<'a>) -> Self {
         match l.kind().tag_class() {
-            tag if tag < TagClass::Bin => unsafe {
+            tag if (tag < TagClass::Bin) | (tag == TagClass::Timestamp) => unsafe {
                 // UNSAFE(@ohsayan): Correct because we are using the same tag, and in this case the type doesn't need any advanced construction
                 Datacell::new(
                     l.kind(),
@@ -379,6 +470,23 @@ impl Datacell {
     unsafe fn new(tag: FullTag, data: DataRaw) -> Self {
         Self::_new(tag, data, true)
     }
+    /// Returns the number of bytes that this cell has allocated on the heap (for lists and dicts, this is an estimate)
+    pub fn heap_size(&self) -> usize {
+        if let Some(bin) = self.try_bin() {
+            bin.len()
+        } else if let Some(str) = self.try_str() {
+            str.len()
+        } else if let Some(list) = self.try_list() {
+            let list = list.read();
+            list.capacity() * sizeof!(Self) + list.iter().map(Self::heap_size).sum::<usize>()
+        } else if let Some(dict) = self.try_dict() {
+            dict.iter()
+                .map(|(k, v)| k.len() + sizeof!(Box<str>) + sizeof!(Self) + v.heap_size())
+                .sum()
+        } else {
+            0
+        }
+    }
     fn checked_tag<T>(&self, tag: TagClass, f: impl FnOnce() -> T) -> Option<T> {
         ((self.kind() == tag) & (self.is_init())).then(f)
     }
@@ -408,6 +516,10 @@ impl fmt::Debug for Datacell {
             Bin => self.bin(),
             Str => self.str(),
             List => self.list(),
+            Timestamp => self.timestamp(),
+            BigInt => self.sint128(),
+            Geo => self.geopoint(),
+            Dict => self.dict(),
         );
         f.finish()
     }
@@ -425,6 +537,9 @@ impl PartialEq for Datacell {
             (TagClass::Float, TagClass::Float) => self.float() == other.float(),
             (TagClass::Bin, TagClass::Bin) => self.bin() == other.bin(),
             (TagClass::Str, TagClass::Str) => self.str() == other.str(),
+            (TagClass::Timestamp, TagClass::Timestamp) => self.timestamp() == other.timestamp(),
+            (TagClass::BigInt, TagClass::BigInt) => self.sint128() == other.sint128(),
+            (TagClass::Geo, TagClass::Geo) => self.geopoint() == other.geopoint(),
             (TagClass::List, TagClass::List) => {
                 let l1_l = self.list().read();
                 let l2_l = other.list().read();
@@ -432,6 +547,7 @@ impl PartialEq for Datacell {
                 let l2: &[Self] = l2_l.as_ref();
                 l1 == l2
             }
+            (TagClass::Dict, TagClass::Dict) => self.dict() == other.dict(),
             _ => false,
         }
     }
@@ -443,6 +559,7 @@ union! {
     union DataRaw {
         !word: NativeQword,
         !rwl: RwLock<Vec<Datacell>>,
+        !dict: DictCell,
     }
 }
 
@@ -457,6 +574,11 @@ impl DataRaw {
             rwl: ManuallyDrop::new(rwl),
         }
     }
+    fn dict(dict: DictCell) -> Self {
+        Self {
+            dict: ManuallyDrop::new(dict),
+        }
+    }
 }
 
 impl Drop for Datacell {
@@ -471,12 +593,15 @@ impl Drop for Datacell {
                 // UNSAFE(@ohsayan): we have checked that the cell is initialized (uninit will not satisfy this class), and we have checked its class
                 ManuallyDrop::drop(&mut self.data.rwl)
             },
+            TagClass::Dict => unsafe {
+                // UNSAFE(@ohsayan): we have checked that the cell is initialized (uninit will not satisfy this class), and we have checked its class
+                ManuallyDrop::drop(&mut self.data.dict)
+            },
             _ => {}
         }
     }
 }
 
-#[cfg(test)]
 impl Clone for Datacell {
     fn clone(&self) -> Self {
         let data = match self.kind() {
@@ -493,6 +618,10 @@ impl Clone for Datacell {
                 let data = self.read_list().read().iter().cloned().collect();
                 DataRaw::rwl(RwLock::new(data))
             },
+            TagClass::Dict => unsafe {
+                // UNSAFE(@ohsayan): we have checked that the cell is initialized (uninit will not satisfy this class), and we have checked its class
+                DataRaw::dict(self.read_dict().clone())
+            },
             _ => unsafe {
                 // UNSAFE(@ohsayan): we have checked that the cell is a stack class
                 DataRaw::word(mem::transmute_copy(&self.data.word))
@@ -29,12 +29,16 @@ use {
         data::{cell::Datacell, lit::Lit},
         idx::STIndex,
     },
-    std::collections::HashMap,
+    std::collections::{BTreeMap, HashMap},
 };
 
 /// A generic dictionary built from scratch from syntactical elements
 pub type DictGeneric = HashMap<Box<str>, DictEntryGeneric>;
 
+/// The value of a dict field. Unlike [`DictGeneric`], a nested dict is just another cell, and the keys are ordered so
+/// that the same dict is always encoded the same way
+pub type DictCell = BTreeMap<Box<str>, Datacell>;
+
 #[derive(Debug, PartialEq)]
 #[cfg_attr(test, derive(Clone))]
 /// A generic dict entry: either a literal or a recursive dictionary
@@ -67,6 +67,10 @@ impl<'a> Lit<'a> {
     pub fn new_float(f: f64) -> Self {
         Self::_quad(f.to_bits(), FullTag::FLOAT)
     }
+    /// Create a new timestamp (microseconds since the unix epoch)
+    pub fn new_timestamp(micros: u64) -> Self {
+        Self::_quad(micros, FullTag::TIMESTAMP)
+    }
     /// Returns a "shallow clone"
     ///
     /// This function will fall apart if lifetimes aren't handled correctly (aka will segfault)
@@ -110,6 +114,13 @@ impl<'a> Lit<'a> {
             self.float()
         })
     }
+    /// Attempt to read a timestamp
+    pub fn try_timestamp(&self) -> Option<u64> {
+        (self.tag.tag_class() == TagClass::Timestamp).then_some(unsafe {
+            // UNSAFE(@ohsayan): +tagck
+            self.uint()
+        })
+    }
     /// Read a bool directly. This function isn't exactly unsafe, but we want to provide a type preserving API
     pub unsafe fn bool(&self) -> bool {
         self.uint() == 1
@@ -298,7 +309,11 @@ impl<'a> fmt::Debug for Lit<'a> {
                 TagClass::Float => d!(self.float()),
                 TagClass::Bin => d!(self.bin()),
                 TagClass::Str => d!(self.str()),
+                TagClass::Timestamp => d!(self.uint()),
                 TagClass::List => panic!("found 2D in 1D"),
+                TagClass::BigInt => panic!("128-bit integers can't be literals"),
+                TagClass::Geo => panic!("points can't be literals"),
+                TagClass::Dict => panic!("dicts can't be literals"),
             }
         }
         field.finish()
@@ -323,6 +338,7 @@ impl<'a> PartialEq for Lit<'a> {
                 (TagClass::Float, TagClass::Float) => self.float() == other.float(),
                 (TagClass::Bin, TagClass::Bin) => self.bin() == other.bin(),
                 (TagClass::Str, TagClass::Str) => self.str() == other.str(),
+                (TagClass::Timestamp, TagClass::Timestamp) => self.uint() == other.uint(),
                 _ => false,
             }
         }
@@ -352,7 +368,11 @@ impl<'a> ToString for Lit<'a> {
                 TagClass::Float => self.float().to_string(),
                 TagClass::Bin => format!("{:?}", self.bin()),
                 TagClass::Str => format!("{:?}", self.str()),
+                TagClass::Timestamp => self.uint().to_string(),
                 TagClass::List => panic!("found 2D in 1D"),
+                TagClass::BigInt => panic!("128-bit integers can't be literals"),
+                TagClass::Geo => panic!("points can't be literals"),
+                TagClass::Dict => panic!("dicts can't be literals"),
             }
         }
     }
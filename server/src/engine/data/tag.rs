@@ -43,6 +43,10 @@ pub enum TagClass {
     Bin = 4,
     Str = 5,
     List = 6,
+    Timestamp = 7,
+    BigInt = 8,
+    Geo = 9,
+    Dict = 10,
 }
 
 strid! {
@@ -63,6 +67,10 @@ strid! {
         Binary = 11,
         String = 12,
         List = 13,
+        Timestamp = 14,
+        SInt128 = 15,
+        GeoPoint = 16,
+        Dict = 17,
     }
 }
 
@@ -89,6 +97,10 @@ impl TagSelector {
             TagUnique::Bin,
             TagUnique::Str,
             TagUnique::Illegal,
+            TagUnique::Illegal,
+            TagUnique::Illegal,
+            TagUnique::Illegal,
+            TagUnique::Illegal,
         ][self.value_word()]
     }
     pub const fn tag_class(&self) -> TagClass {
@@ -107,6 +119,10 @@ impl TagSelector {
             TagClass::Bin,
             TagClass::Str,
             TagClass::List,
+            TagClass::Timestamp,
+            TagClass::BigInt,
+            TagClass::Geo,
+            TagClass::Dict,
         ][self.value_word()]
     }
 }
@@ -141,6 +157,10 @@ pub trait DataTag {
     const BIN: Self;
     const STR: Self;
     const LIST: Self;
+    const TIMESTAMP: Self;
+    const SINT128: Self;
+    const GEOPOINT: Self;
+    const DICT: Self;
     fn tag_class(&self) -> TagClass;
     fn tag_selector(&self) -> TagSelector;
     fn tag_unique(&self) -> TagUnique;
@@ -189,6 +209,10 @@ impl DataTag for FullTag {
     const BIN: Self = fulltag!(Bin, Binary, Bin);
     const STR: Self = fulltag!(Str, String, Str);
     const LIST: Self = fulltag!(List, List);
+    const TIMESTAMP: Self = fulltag!(Timestamp, Timestamp);
+    const SINT128: Self = fulltag!(BigInt, SInt128);
+    const GEOPOINT: Self = fulltag!(Geo, GeoPoint);
+    const DICT: Self = fulltag!(Dict, Dict);
     fn tag_class(&self) -> TagClass {
         self.class
     }
@@ -37,6 +37,18 @@ impl Uuid {
             data: uuid::Uuid::new_v4(),
         }
     }
+    /// A time-ordered (version 7) UUID
+    pub fn new_v7() -> Self {
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut random = [0u8; 10];
+        openssl::rand::rand_bytes(&mut random).expect("failed to generate random bytes");
+        Self {
+            data: uuid::Builder::from_unix_timestamp_millis(millis, &random).into_uuid(),
+        }
+    }
     pub fn from_bytes(b: [u8; 16]) -> Self {
         Self {
             data: uuid::Uuid::from_u128_le(u128::from_le_bytes(b)),
@@ -45,6 +57,10 @@ impl Uuid {
     pub fn to_le_bytes(self) -> [u8; 16] {
         self.data.to_u128_le().to_le_bytes()
     }
+    /// The bytes in the standard (big endian) order
+    pub fn to_be_bytes(self) -> [u8; 16] {
+        self.data.into_bytes()
+    }
 }
 
 impl fmt::Display for Uuid {
@@ -48,6 +48,34 @@ pub enum QueryError {
     /// insufficient permissions error
     SysPermissionDenied = 5,
     SysNetworkSystemIllegalClientPacket = 6,
+    /// the password doesn't satisfy the password policy
+    SysAuthPasswordPolicy = 7,
+    /// a change data capture subscriber fell too far behind and was cut off
+    SysChangeFeedLagged = 8,
+    /// this is a replica, which only accepts writes from its primary
+    SysReadOnly = 9,
+    /// the model has too many writes that are yet to be flushed to disk, so the write was rejected
+    SysWriteBacklogFull = 10,
+    /// the model (or the database) uses more memory than it is allowed to, so the write was rejected
+    SysMemoryLimitExceeded = 11,
+    /// the server is still restoring its data, so only `sysctl report startup` can be run
+    SysServerStarting = 12,
+    /// the user has already run as many queries as their quota allows in this second
+    SysQuotaQueryRate = 13,
+    /// the response is larger than what the user's quota allows
+    SysQuotaResultSize = 14,
+    /// the query was killed with `sysctl kill`
+    SysQueryKilled = 15,
+    /// the query ran for longer than the configured query timeout
+    SysQueryTimeout = 16,
+    /// a subscription can't be started from within a pipeline
+    SysPipelineSubscription = 17,
+    /// the client used a protocol feature that it didn't negotiate during the handshake
+    SysMissingCapability = 18,
+    /// the root password was bootstrapped from the configuration, and must be changed before any DDL can be run
+    SysAuthRootMustRotate = 19,
+    /// only DML can be run in a batch (and statements can't be prepared in one)
+    SysBatchIllegalStatement = 20,
     // QL
     /// something like an integer that randomly has a character to attached to it like `1234q`
     LexInvalidInput = 25,
@@ -67,6 +95,10 @@ pub enum QueryError {
     QLExpectedStatement = 32,
     /// unknown statement
     QLUnknownStatement = 33,
+    /// the client tried to execute a statement that it didn't prepare
+    QLUnknownPreparedStatement = 34,
+    /// the client has prepared as many statements as it can
+    QLPreparedStatementLimit = 35,
     // exec
     /// the object to be used as the "query container" is missing (for example, insert when the model was missing)
     QExecObjectNotFound = 100,
@@ -95,6 +127,12 @@ pub enum QueryError {
     QExecDmlRowNotFound = 111,
     /// this query needs a lock for execution, but that wasn't explicitly allowed anywhere
     QExecNeedLock = 112,
+    /// the row didn't satisfy the condition of a conditional update
+    QExecDmlConditionFailed = 113,
+    /// the rows to be sorted don't fit in the sort buffer (this usually means that a limit is needed)
+    QExecDmlSortTooLarge = 114,
+    /// the row can't be changed because another transaction holds its write intent (`select ... for update`)
+    QExecDmlRowLocked = 115,
 }
 
 impl From<super::fractal::error::Error> for QueryError {
@@ -109,12 +147,16 @@ impl From<super::fractal::error::Error> for QueryError {
 }
 
 macro_rules! enumerate_err {
-    ($(#[$attr:meta])* $vis:vis enum $errname:ident { $($(#[$varattr:meta])* $variant:ident = $errstring:expr),* $(,)? }) => {
+    ($(#[$attr:meta])* $vis:vis enum $errname:ident { $($(#[$varattr:meta])* $variant:ident $(($field:ident: $fieldty:ty))? = $errstring:expr),* $(,)? }) => {
         $(#[$attr])*
-        $vis enum $errname { $($(#[$varattr])* $variant),* }
+        $vis enum $errname { $($(#[$varattr])* $variant $(($fieldty))?),* }
         impl core::fmt::Display for $errname {
             fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-                match self {$( Self::$variant => write!(f, "{}", $errstring),)*}
+                match self {$( Self::$variant $(($field))? => {
+                    write!(f, "{}", $errstring)?;
+                    $(write!(f, "({})", $field)?;)?
+                    Ok(())
+                })*}
             }
         }
         impl std::error::Error for $errname {}
@@ -189,6 +231,8 @@ enumerate_err! {
         HeaderDecodeCorruptedHeader = "header-corrupted",
         /// Expected header values were not matched with the current header
         HeaderDecodeDataMismatch = "header-data-mismatch",
+        /// the file uses a format feature (the flag) that this version doesn't support
+        HeaderDecodeUnsupportedFeature(flag: u64) = "header-unsupported-feature",
         // journal
         /// While attempting to handle a basic failure (such as adding a journal entry), the recovery engine ran into an exceptional
         /// situation where it failed to make a necessary repair the log
@@ -215,6 +259,16 @@ enumerate_err! {
         DataBatchCloseError = "batch-persist-close-failed",
         /// the data batch file is corrupted
         DataBatchRestoreCorruptedBatchFile = "batch-corrupted-file",
+        /// a batch that was replicated from the primary doesn't match the model on the replica
+        DataBatchReplicaModelMismatch = "batch-replica-model-mismatch",
+        /// a snapshot of a model doesn't match what its manifest recorded about it (or the model itself)
+        SnapshotCorrupted = "snapshot-corrupted",
+        /// the file was encrypted with a key that isn't in the keyring
+        EncryptionKeyUnavailable = "encryption-key-unavailable",
+        /// an encrypted payload couldn't be decrypted, since it was either corrupted or encrypted with a different key
+        EncryptedPayloadCorrupted = "encryption-payload-corrupted",
+        /// we failed to encrypt a payload
+        EncryptionFailed = "encryption-failed",
         /// the system database is corrupted
         SysDBCorrupted = "sysdb-corrupted",
     }
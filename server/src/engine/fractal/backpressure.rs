@@ -0,0 +1,157 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Write backpressure
+//!
+//! Writes to a model are queued up as data deltas until the fractal manager writes them to the model's batch journal.
+//! If writes come in faster than they can be flushed, this queue would keep on growing, so a model can be given a
+//! limit on the number of deltas that are yet to be flushed. Once a model hits this limit, new writes are either
+//! stalled until the flusher catches up or rejected right away
+
+use {
+    super::{CriticalTask, FractalToken, GlobalInstanceLike, ModelUniqueID, Task},
+    crate::engine::{
+        core::EntityIDRef,
+        error::{QueryError, QueryResult},
+    },
+    serde::Deserialize,
+    std::{
+        sync::atomic::{AtomicU64, Ordering},
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// How long a stalled write waits for the backlog to drain before it's rejected
+const STALL_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often a stalled write checks if the backlog has drained
+const STALL_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+/// What happens to a write to a model whose write backlog is full
+pub enum BackpressurePolicy {
+    /// wait for the backlog to drain (and reject the write if it doesn't drain in time)
+    #[default]
+    #[serde(rename = "stall")]
+    Stall,
+    /// reject the write right away
+    #[serde(rename = "reject")]
+    Reject,
+}
+
+#[derive(Debug)]
+/// Applies the write backpressure policy and keeps track of the writes that were held back
+pub struct Backpressure {
+    policy: BackpressurePolicy,
+    limit: Option<usize>,
+    stall_timeout: Duration,
+    stalled: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl Backpressure {
+    /// Hold back writes to models that have at least `limit` deltas that are yet to be flushed (no limit if `None`)
+    pub fn new(policy: BackpressurePolicy, limit: Option<usize>) -> Self {
+        Self {
+            policy,
+            limit,
+            stall_timeout: STALL_TIMEOUT,
+            stalled: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+    #[cfg(test)]
+    /// Never hold back any write
+    pub fn disabled() -> Self {
+        Self::new(BackpressurePolicy::Stall, None)
+    }
+    #[cfg(test)]
+    pub fn with_stall_timeout(mut self, stall_timeout: Duration) -> Self {
+        self.stall_timeout = stall_timeout;
+        self
+    }
+    /// Returns the number of unflushed deltas at which a model's writes are held back
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+    /// Returns the number of writes that had to wait for the write backlog to drain
+    pub fn stalled_writes(&self) -> u64 {
+        self.stalled.load(Ordering::Relaxed)
+    }
+    /// Returns the number of writes that were rejected because the write backlog was full
+    pub fn rejected_writes(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+    /// Wait till a write to the given model can go through, or return an error if the write must be rejected
+    ///
+    /// The model index is not held while waiting, so this must be called before the model is locked for the write
+    pub fn admit_write(
+        &self,
+        global: &impl GlobalInstanceLike,
+        entity: EntityIDRef,
+    ) -> QueryResult<()> {
+        let Some(limit) = self.limit else {
+            return Ok(());
+        };
+        let mut deadline = None;
+        while global.namespace().with_model(entity, |model| {
+            let full = model.delta_state().data_delta_backlog() >= limit;
+            if full {
+                // make sure that the deltas which weren't picked up yet are flushed right away (the batch write
+                // threshold could be higher than our limit)
+                let queued = model
+                    .delta_state()
+                    .__fractal_take_full_from_data_delta(FractalToken::new());
+                if queued != 0 {
+                    global.taskmgr_post_high_priority(Task::new(CriticalTask::WriteBatch(
+                        ModelUniqueID::new(entity.space(), entity.entity(), model.get_uuid()),
+                        queued,
+                    )));
+                }
+            }
+            Ok(full)
+        })? {
+            if self.policy == BackpressurePolicy::Reject {
+                return Err(self.reject());
+            }
+            let now = Instant::now();
+            let give_up_at = *deadline.get_or_insert_with(|| {
+                self.stalled.fetch_add(1, Ordering::Relaxed);
+                now + self.stall_timeout
+            });
+            if now >= give_up_at {
+                return Err(self.reject());
+            }
+            // don't hold up the runtime's other tasks while we wait
+            tokio::task::block_in_place(|| thread::sleep(STALL_POLL_INTERVAL));
+        }
+        Ok(())
+    }
+    fn reject(&self) -> QueryError {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        QueryError::SysWriteBacklogFull
+    }
+}
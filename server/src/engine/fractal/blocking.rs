@@ -0,0 +1,61 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! The blocking pool
+//!
+//! The storage drivers are synchronous, so anything that touches the disk (writing and syncing journals, running DDL,
+//! recording audit events) is run on tokio's blocking threads to keep it off the workers that drive the network.
+//! tokio happily spawns hundreds of blocking threads though, so if the disk can't keep up we'd just pile up threads
+//! that all wait on it. Instead, only a limited number of tasks run at a time and everything else waits (as a
+//! future, so the worker is free to do other things) for a slot to free up, which pushes back on whoever is
+//! submitting work
+
+use tokio::sync::Semaphore;
+
+#[derive(Debug)]
+pub struct BlockingPool {
+    permits: Semaphore,
+}
+
+impl BlockingPool {
+    /// Create a pool that runs at most `limit` tasks at a time (if not set, that's the number of CPUs)
+    pub fn new(limit: Option<usize>) -> Self {
+        let limit = limit.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(usize::from)
+                .unwrap_or(1)
+        });
+        Self {
+            permits: Semaphore::new(limit),
+        }
+    }
+    /// Run `f` on a blocking thread once a slot is free, returning its result
+    pub async fn run<T: Send + 'static>(&self, f: impl FnOnce() -> T + Send + 'static) -> T {
+        // the semaphore is never closed
+        let _permit = self.permits.acquire().await.unwrap();
+        tokio::task::spawn_blocking(f).await.unwrap()
+    }
+}
@@ -0,0 +1,241 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Change data capture (CDC)
+//!
+//! Every model has a [`ChangeFeed`] that fans out the changes made to its rows to all the [`Subscription`]s that
+//! are attached to it. Each subscription has a bounded buffer: a subscriber that falls behind by more than
+//! [`CDC_SUBSCRIBER_BUFFER`] events is cut off (rather than slowing down writers) and has to subscribe again.
+//!
+//! Changes to a row are always published in order. Changes to different rows of a model are published in the order
+//! in which they were made, but concurrent writers can race, so the txn id should be used to order them globally
+
+use {
+    crate::engine::{
+        core::{dml::encode_cell, model::delta::DataDeltaKind},
+        data::cell::Datacell,
+        error::{QueryError, QueryResult},
+        net::protocol::{Response, ResponseType},
+    },
+    parking_lot::Mutex,
+    std::{
+        fmt,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+    tokio::sync::mpsc::{self, error::TrySendError},
+};
+
+/// The number of events that can be queued for a subscriber before it's considered to have fallen behind
+pub const CDC_SUBSCRIBER_BUFFER: usize = 1024;
+
+static SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(0);
+
+type FeedMessage = (usize, Arc<ChangeEvent>);
+
+/// A change made to a row
+#[derive(Debug, PartialEq)]
+pub struct ChangeEvent {
+    txn_id: u64,
+    kind: DataDeltaKind,
+    cell_count: usize,
+    cells: Vec<u8>,
+}
+
+impl ChangeEvent {
+    /// Create a new event. The cells of the row must already be encoded, in the model's field order
+    pub fn new(txn_id: u64, kind: DataDeltaKind, cell_count: usize, cells: Vec<u8>) -> Self {
+        Self {
+            txn_id,
+            kind,
+            cell_count,
+            cells,
+        }
+    }
+    /// Encode the event as a row of `(space, model, txn id, kind, ...cells)`
+    fn encode(&self, space: &str, model: &str) -> Response {
+        let kind = match self.kind {
+            DataDeltaKind::Insert => "insert",
+            DataDeltaKind::Update => "update",
+            DataDeltaKind::Delete => "delete",
+        };
+        let mut data = vec![];
+        for cell in [
+            Datacell::new_str(space.into()),
+            Datacell::new_str(model.into()),
+            Datacell::new_uint_default(self.txn_id),
+            Datacell::new_str(kind.into()),
+        ] {
+            encode_cell(&mut data, &cell);
+        }
+        data.extend(&self.cells);
+        Response::Serialized {
+            ty: ResponseType::Event,
+            size: self.cell_count + 4,
+            data: data.into(),
+        }
+    }
+}
+
+struct FeedSubscriber {
+    id: u64,
+    /// the index of the model in the subscription
+    index: usize,
+    tx: mpsc::Sender<FeedMessage>,
+    lagged: Arc<AtomicBool>,
+}
+
+/// The fan-out of a model's changes to its subscribers
+#[derive(Default)]
+pub struct ChangeFeed {
+    subscribers: Mutex<Vec<FeedSubscriber>>,
+    count: AtomicUsize,
+}
+
+impl fmt::Debug for ChangeFeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangeFeed")
+            .field("subscribers", &self.count.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl ChangeFeed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Returns true if anyone is listening (so that writers can skip building events otherwise)
+    pub fn has_subscribers(&self) -> bool {
+        self.count.load(Ordering::Acquire) != 0
+    }
+    pub fn publish(&self, event: ChangeEvent) {
+        let event = Arc::new(event);
+        let mut subscribers = self.subscribers.lock();
+        subscribers.retain(|sub| match sub.tx.try_send((sub.index, event.clone())) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                sub.lagged.store(true, Ordering::Release);
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        });
+        self.count.store(subscribers.len(), Ordering::Release);
+    }
+    fn attach(&self, sub: FeedSubscriber) {
+        let mut subscribers = self.subscribers.lock();
+        subscribers.push(sub);
+        self.count.store(subscribers.len(), Ordering::Release);
+    }
+    fn detach(&self, id: u64) {
+        let mut subscribers = self.subscribers.lock();
+        subscribers.retain(|sub| sub.id != id);
+        self.count.store(subscribers.len(), Ordering::Release);
+    }
+}
+
+/// A client's subscription to the changes of one or more models. Dropping it detaches it from all the models
+pub struct Subscription {
+    id: u64,
+    tx: mpsc::Sender<FeedMessage>,
+    rx: mpsc::Receiver<FeedMessage>,
+    lagged: Arc<AtomicBool>,
+    models: Vec<(Arc<ChangeFeed>, Box<str>, Box<str>)>,
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("id", &self.id)
+            .field(
+                "models",
+                &self
+                    .models
+                    .iter()
+                    .map(|(_, space, model)| (space, model))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl PartialEq for Subscription {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Subscription {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel(CDC_SUBSCRIBER_BUFFER);
+        Self {
+            id: SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed),
+            tx,
+            rx,
+            lagged: Arc::new(AtomicBool::new(false)),
+            models: vec![],
+        }
+    }
+    /// Start receiving the changes published to the feed of the given model
+    pub fn attach(&mut self, feed: &Arc<ChangeFeed>, space: &str, model: &str) {
+        feed.attach(FeedSubscriber {
+            id: self.id,
+            index: self.models.len(),
+            tx: self.tx.clone(),
+            lagged: self.lagged.clone(),
+        });
+        self.models.push((feed.clone(), space.into(), model.into()));
+    }
+    /// Wait for the next change, encoded for the client. This errors if the subscriber fell behind
+    pub async fn next(&mut self) -> QueryResult<Response> {
+        if self.lagged.load(Ordering::Acquire) {
+            return Err(QueryError::SysChangeFeedLagged);
+        }
+        // we hold a sender ourselves, so the channel is never closed
+        let (index, event) = self.rx.recv().await.unwrap();
+        let (_, space, model) = &self.models[index];
+        Ok(event.encode(space, model))
+    }
+    /// Returns the next change if one is ready
+    #[cfg(test)]
+    pub fn try_next(&mut self) -> Option<QueryResult<Response>> {
+        if self.lagged.load(Ordering::Acquire) {
+            return Some(Err(QueryError::SysChangeFeedLagged));
+        }
+        let (index, event) = self.rx.try_recv().ok()?;
+        let (_, space, model) = &self.models[index];
+        Some(Ok(event.encode(space, model)))
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.models
+            .iter()
+            .for_each(|(feed, _, _)| feed.detach(self.id));
+    }
+}
@@ -28,11 +28,14 @@ use {
     super::util,
     crate::engine::{
         error::RuntimeResult,
-        storage::v1::{data_batch::DataBatchPersistDriver, RawFSInterface},
+        storage::{engine::ModelStorage, v1::RawFSInterface},
         txn::gns::GNSTransactionDriverAnyFS,
     },
     parking_lot::Mutex,
-    std::sync::Arc,
+    std::sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 
 /// GNS driver
@@ -55,26 +58,36 @@ impl<Fs: RawFSInterface> FractalGNSDriver<Fs> {
 }
 
 /// Model driver
-pub struct FractalModelDriver<Fs: RawFSInterface> {
+pub struct FractalModelDriver {
     #[allow(unused)]
     hooks: Arc<FractalModelHooks>,
-    batch_driver: Mutex<DataBatchPersistDriver<Fs>>,
+    storage: Mutex<Box<dyn ModelStorage>>,
+    batch_flushes: AtomicU64,
 }
 
-impl<Fs: RawFSInterface> FractalModelDriver<Fs> {
+impl FractalModelDriver {
     /// Initialize a model driver with default settings
-    pub fn init(batch_driver: DataBatchPersistDriver<Fs>) -> Self {
+    pub fn init(storage: Box<dyn ModelStorage>) -> Self {
         Self {
             hooks: Arc::new(FractalModelHooks::new()),
-            batch_driver: Mutex::new(batch_driver),
+            storage: Mutex::new(storage),
+            batch_flushes: AtomicU64::new(0),
         }
     }
-    /// Returns a reference to the batch persist driver
-    pub fn batch_driver(&self) -> &Mutex<DataBatchPersistDriver<Fs>> {
-        &self.batch_driver
+    /// Returns a reference to the model's storage
+    pub fn storage(&self) -> &Mutex<Box<dyn ModelStorage>> {
+        &self.storage
+    }
+    /// Returns the number of batches that were flushed to the model's storage since startup
+    pub fn batch_flushes(&self) -> u64 {
+        self.batch_flushes.load(Ordering::Relaxed)
+    }
+    /// Record that a batch was flushed to the model's storage
+    pub fn note_batch_flush(&self) {
+        self.batch_flushes.fetch_add(1, Ordering::Relaxed);
     }
     pub fn close(self) -> RuntimeResult<()> {
-        self.batch_driver.into_inner().close()
+        self.storage.into_inner().close()
     }
 }
 
@@ -0,0 +1,193 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Memory accounting
+//!
+//! Every model keeps an estimate of the memory taken up by the rows in its primary index (which is kept up to date by
+//! the DML executors and recounted by the fractal manager on every maintenance run), its queue of unflushed deltas
+//! and its row cache. A limit can be set on the memory used by any single model, and on the memory used by all models
+//! together. A write that finds its model (or the database) over the limit first tries to make room by dropping the
+//! cached rows and flushing the queued deltas, and is only rejected if that doesn't bring the usage under the limit.
+//! Since a flush completes in the background, the client can retry a rejected write once the deltas were written
+//!
+//! Deletes always go through, since they only ever give memory back
+
+use {
+    super::{CriticalTask, FractalToken, GlobalInstanceLike, ModelUniqueID, Task},
+    crate::engine::{
+        core::{
+            model::{delta::DataDelta, Model},
+            EntityIDRef,
+        },
+        error::{QueryError, QueryResult},
+    },
+    std::sync::atomic::{AtomicU64, Ordering},
+};
+
+/// What a queued data delta costs (the row itself is shared with the primary index)
+const DELTA_SIZE: usize = sizeof!(DataDelta) + sizeof!(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The estimated memory used by a model (or by all models together)
+pub struct MemoryUsage {
+    index: usize,
+    deltas: usize,
+    cache: usize,
+}
+
+impl MemoryUsage {
+    /// Returns the estimated memory used by the given model
+    pub fn of(model: &Model) -> Self {
+        let ds = model.delta_state();
+        Self {
+            index: ds.index_bytes() + ds.index_slack(),
+            deltas: ds.data_delta_backlog() * DELTA_SIZE,
+            cache: ds.row_cache().used(),
+        }
+    }
+    /// Returns the estimated memory used by all models together
+    pub fn of_all(global: &impl GlobalInstanceLike) -> Self {
+        global
+            .namespace()
+            .idx_models()
+            .read()
+            .values()
+            .map(Self::of)
+            .fold(Self::default(), Self::add)
+    }
+    /// Returns the memory used by the primary index (its rows, and its structure as of the last maintenance run)
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// Returns the memory used by the deltas that are yet to be flushed to disk
+    pub fn deltas(&self) -> usize {
+        self.deltas
+    }
+    /// Returns the memory used by the cached rows
+    pub fn cache(&self) -> usize {
+        self.cache
+    }
+    pub fn total(&self) -> usize {
+        self.index + self.deltas + self.cache
+    }
+    fn add(self, other: Self) -> Self {
+        Self {
+            index: self.index + other.index,
+            deltas: self.deltas + other.deltas,
+            cache: self.cache + other.cache,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Applies the memory limits and keeps track of the writes that were rejected because of them
+pub struct MemoryLimits {
+    global: Option<usize>,
+    model: Option<usize>,
+    rejected: AtomicU64,
+}
+
+impl MemoryLimits {
+    /// Reject writes once all models together use `global` bytes, or once the model being written to uses `model`
+    /// bytes (no limit if `None`)
+    pub fn new(global: Option<usize>, model: Option<usize>) -> Self {
+        Self {
+            global,
+            model,
+            rejected: AtomicU64::new(0),
+        }
+    }
+    #[cfg(test)]
+    /// Never reject any write
+    pub fn disabled() -> Self {
+        Self::new(None, None)
+    }
+    /// Returns the memory that all models together may use
+    pub fn global_limit(&self) -> Option<usize> {
+        self.global
+    }
+    /// Returns the memory that a single model may use
+    pub fn model_limit(&self) -> Option<usize> {
+        self.model
+    }
+    /// Returns the number of writes that were rejected because a memory limit was hit
+    pub fn rejected_writes(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+    /// Check if a write to the given model can go through, making room if the model or the database is over its
+    /// limit. Returns an error if the write must be rejected
+    ///
+    /// This must be called before the model is locked for the write
+    pub fn admit_write(
+        &self,
+        global: &impl GlobalInstanceLike,
+        entity: EntityIDRef,
+    ) -> QueryResult<()> {
+        if let Some(limit) = self.model {
+            let over = global.namespace().with_model(entity, |model| {
+                if MemoryUsage::of(model).total() < limit {
+                    return Ok(false);
+                }
+                relieve(global, entity, model);
+                Ok(MemoryUsage::of(model).total() >= limit)
+            })?;
+            if over {
+                return Err(self.reject());
+            }
+        }
+        if let Some(limit) = self.global {
+            if MemoryUsage::of_all(global).total() >= limit {
+                {
+                    let models = global.namespace().idx_models().read();
+                    models.iter().for_each(|(id, model)| {
+                        relieve(global, EntityIDRef::new(id.space(), id.entity()), model)
+                    });
+                }
+                if MemoryUsage::of_all(global).total() >= limit {
+                    return Err(self.reject());
+                }
+            }
+        }
+        Ok(())
+    }
+    fn reject(&self) -> QueryError {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        QueryError::SysMemoryLimitExceeded
+    }
+}
+
+/// Give back the memory that the model can spare: drop its cached rows and have its queued deltas flushed right away
+fn relieve(global: &impl GlobalInstanceLike, entity: EntityIDRef, model: &Model) {
+    let ds = model.delta_state();
+    ds.row_cache().clear();
+    let queued = ds.__fractal_take_full_from_data_delta(FractalToken::new());
+    if queued != 0 {
+        global.taskmgr_post_high_priority(Task::new(CriticalTask::WriteBatch(
+            ModelUniqueID::new(entity.space(), entity.entity(), model.get_uuid()),
+            queued,
+        )));
+    }
+}
@@ -25,19 +25,28 @@
 */
 
 use {
-    super::ModelUniqueID,
+    super::{sys_store::SystemStore, ModelDrivers, ModelUniqueID},
     crate::{
         engine::{
             core::{
+                index::RowCacheBudget,
                 model::{delta::DataDelta, Model},
-                EntityIDRef,
+                EntityIDRef, GlobalNS,
             },
             data::uuid::Uuid,
-            storage::v1::LocalFS,
+            error::RuntimeResult,
+            storage::v1::{
+                encryption::EncryptionKey,
+                loader::{self, SEInitState},
+                spec::BatchCompression,
+                RawFSInterface,
+            },
+            txn::gns::GNSTransactionDriverAnyFS,
         },
         util::os,
     },
-    std::path::PathBuf,
+    parking_lot::{Mutex, RwLock},
+    std::{path::PathBuf, sync::Arc},
     tokio::{
         fs,
         sync::{
@@ -45,12 +54,14 @@ use {
             mpsc::{UnboundedReceiver, UnboundedSender},
         },
         task::JoinHandle,
+        time::{Interval, MissedTickBehavior},
     },
 };
 
 pub const GENERAL_EXECUTOR_WINDOW: u64 = 5 * 60;
 
 /// A task for the [`FractalMgr`] to perform
+#[cfg_attr(test, derive(Debug, PartialEq))]
 pub struct Task<T> {
     threshold: usize,
     task: T,
@@ -63,7 +74,7 @@ impl<T> Task<T> {
         Self::with_threshold(task, Self::THRESHOLD)
     }
     /// Create a task with the given threshold
-    fn with_threshold(task: T, threshold: usize) -> Self {
+    pub(super) fn with_threshold(task: T, threshold: usize) -> Self {
         Self { threshold, task }
     }
 }
@@ -100,16 +111,47 @@ impl GenericTask {
 }
 
 /// A critical task
+#[cfg_attr(test, derive(Debug, PartialEq))]
 pub enum CriticalTask {
     /// Write a new data batch
     WriteBatch(ModelUniqueID, usize),
 }
 
+impl Task<CriticalTask> {
+    /// Group commit: coalesce all batch writes for the same model into a single batch write, so that the model's
+    /// journal is only flushed (and synced) once for all of them. The deltas of a model are always written in the order
+    /// that they were queued in, so the observed sizes just add up. The group keeps the highest threshold of its tasks, so
+    /// that a write that is being retried doesn't use up the retries of the fresh writes that it was grouped with
+    pub(super) fn coalesce(tasks: Vec<Self>) -> Vec<Self> {
+        let mut coalesced: Vec<Self> = Vec::with_capacity(tasks.len());
+        for Task { threshold, task } in tasks {
+            let CriticalTask::WriteBatch(model_id, observed_size) = task;
+            let group = coalesced.iter_mut().find(|group| {
+                let CriticalTask::WriteBatch(group_model_id, _) = &group.task;
+                group_model_id == &model_id
+            });
+            match group {
+                Some(group) => {
+                    let CriticalTask::WriteBatch(_, group_size) = &mut group.task;
+                    *group_size += observed_size;
+                    group.threshold = group.threshold.max(threshold);
+                }
+                None => coalesced.push(Task::with_threshold(
+                    CriticalTask::WriteBatch(model_id, observed_size),
+                    threshold,
+                )),
+            }
+        }
+        coalesced
+    }
+}
+
 /// The task manager
 pub(super) struct FractalMgr {
     hp_dispatcher: UnboundedSender<Task<CriticalTask>>,
     general_dispatcher: UnboundedSender<Task<GenericTask>>,
     runtime_stats: FractalRTStat,
+    row_cache: Arc<RowCacheBudget>,
 }
 
 pub(super) struct FractalRTStat {
@@ -141,16 +183,22 @@ impl FractalMgr {
         hp_dispatcher: UnboundedSender<Task<CriticalTask>>,
         general_dispatcher: UnboundedSender<Task<GenericTask>>,
         model_count: usize,
+        row_cache: RowCacheBudget,
     ) -> Self {
         Self {
             hp_dispatcher,
             general_dispatcher,
             runtime_stats: FractalRTStat::init(model_count),
+            row_cache: Arc::new(row_cache),
         }
     }
     pub fn get_rt_stat(&self) -> &FractalRTStat {
         &self.runtime_stats
     }
+    /// Returns the memory budget shared by the row caches of all models
+    pub fn row_cache(&self) -> &Arc<RowCacheBudget> {
+        &self.row_cache
+    }
     /// Add a high priority task to the queue
     ///
     /// ## Panics
@@ -262,18 +310,30 @@ impl FractalMgr {
                 }
                 _ = sigterm.recv() => {
                     info!("fhp: finishing pending tasks");
+                    let mut tasks = vec![];
                     while let Ok(task) = receiver.try_recv() {
+                        tasks.push(task);
+                    }
+                    for task in Task::coalesce(tasks) {
                         let global = global.clone();
-                        tokio::task::spawn_blocking(move || self.hp_executor(global, task)).await.unwrap()
+                        global.blocking_pool().run(move || self.hp_executor(global, task)).await
                     }
                     info!("fhp: exited executor service");
                     break;
                 }
             };
-            let global = global.clone();
-            tokio::task::spawn_blocking(move || self.hp_executor(global, task))
-                .await
-                .unwrap()
+            // pick up everything that queued up while we were busy so that writers share flushes
+            let mut tasks = vec![task];
+            while let Ok(task) = receiver.try_recv() {
+                tasks.push(task);
+            }
+            for task in Task::coalesce(tasks) {
+                let global = global.clone();
+                global
+                    .blocking_pool()
+                    .run(move || self.hp_executor(global, task))
+                    .await
+            }
         }
     }
     fn hp_executor(
@@ -307,6 +367,12 @@ impl FractalMgr {
                         if observed_size != 0 {
                             info!("fhp: completed maintenance task for {model_id}, synced={observed_size}")
                         }
+                        match Self::try_rotate_model_data_batch(&global, &model_id, mdl_driver) {
+                            Ok(true) => info!("fhp: rotated data batch for {model_id}"),
+                            Ok(false) => {}
+                            // we'll just try again after the next write
+                            Err(_) => error!("fhp: failed to rotate data batch for {model_id}"),
+                        }
                     }
                     Err(_) => {
                         error!(
@@ -337,18 +403,34 @@ impl FractalMgr {
         rs_window: u64,
     ) {
         let dur = std::time::Duration::from_secs(rs_window);
+        // journals with a periodic durability policy need to be synced on schedule
+        let mut sync_interval = global
+            .get_state()
+            .config
+            .system_store()
+            .durability()
+            .sync_interval()
+            .map(|interval| {
+                let mut interval = tokio::time::interval(interval);
+                interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                interval
+            });
         loop {
             tokio::select! {
                 _ = sigterm.recv() => {
                     info!("flp: finishing any pending maintenance tasks");
                     let global = global.clone();
-                    tokio::task::spawn_blocking(|| self.general_executor(global)).await.unwrap();
+                    global.blocking_pool().run(move || self.general_executor(global)).await;
                     info!("flp: exited executor service");
                     break;
                 },
                 _ = tokio::time::sleep(dur) => {
                     let global = global.clone();
-                    tokio::task::spawn_blocking(|| self.general_executor(global)).await.unwrap()
+                    global.blocking_pool().run(move || self.general_executor(global)).await
+                }
+                _ = Self::sync_tick(&mut sync_interval) => {
+                    let global = global.clone();
+                    global.blocking_pool().run(move || Self::sync_executor(global)).await
                 }
                 task = lpq.recv() => {
                     let Task { threshold, task } = match task {
@@ -379,6 +461,27 @@ impl FractalMgr {
             }
         }
     }
+    /// Completes on every tick of the sync interval (and never, if we don't sync on a schedule)
+    async fn sync_tick(interval: &mut Option<Interval>) {
+        match interval {
+            Some(interval) => {
+                interval.tick().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+    /// Sync the GNS log and the batch journals that have commits which weren't synced yet
+    fn sync_executor(global: super::Global) {
+        let state = global.get_state();
+        if let Err(e) = state.gns_driver.txn_driver().lock().sync_if_due() {
+            error!("flp: failed to sync the GNS log: {e}");
+        }
+        for (model_id, driver) in state.get_mdl_drivers().read().iter() {
+            if let Err(e) = driver.storage().lock().sync_if_due() {
+                error!("flp: failed to sync data batch for {model_id}: {e}");
+            }
+        }
+    }
     fn general_executor(&'static self, global: super::Global) {
         let mdl_drivers = global.get_state().get_mdl_drivers().read();
         for (model_id, driver) in mdl_drivers.iter() {
@@ -391,6 +494,11 @@ impl FractalMgr {
                         // branch returning. but it is okay
                         return Ok(());
                     }
+                    // rows that weren't read since the last run give up their share of the cache budget
+                    model.delta_state().row_cache().evict_cold();
+                    // give back what the deletes since the last run freed up, and correct any drift in the running
+                    // estimate of the memory used by the rows
+                    model.compact_index();
                     // mark that we're taking these deltas
                     observed_len = model
                         .delta_state()
@@ -405,6 +513,19 @@ impl FractalMgr {
                             "flp: completed maintenance task for {model_id}, synced={observed_len}"
                         )
                     }
+                    if Self::try_compact_model_data_batch(&global, model_id, driver).is_err() {
+                        // the original journal is still intact, so we'll just try again in the next run
+                        error!("flp: failed to compact data batch for {model_id}");
+                    }
+                    match Self::try_rotate_model_data_batch(&global, model_id, driver) {
+                        Ok(true) => info!("flp: rotated data batch for {model_id}"),
+                        Ok(false) => {}
+                        Err(_) => error!("flp: failed to rotate data batch for {model_id}"),
+                    }
+                    if Self::try_snapshot_model_data(&global, model_id, driver).is_err() {
+                        // the previous snapshot (if any) is still intact, so we'll just try again in the next run
+                        error!("flp: failed to take snapshot of {model_id}");
+                    }
                 }
                 Err(_) => {
                     // this failure is *not* good, so we want to promote this to a critical task
@@ -428,15 +549,289 @@ impl FractalMgr {
     fn try_write_model_data_batch(
         model: &Model,
         observed_size: usize,
-        mdl_driver: &super::FractalModelDriver<LocalFS>,
+        mdl_driver: &super::FractalModelDriver,
     ) -> crate::engine::error::QueryResult<()> {
         if observed_size == 0 {
             // no changes, all good
             return Ok(());
         }
         // try flushing the batch
-        let mut batch_driver = mdl_driver.batch_driver().lock();
-        batch_driver.write_new_batch(model, observed_size)?;
+        let mut storage = mdl_driver.storage().lock();
+        storage.append_batch(model, observed_size)?;
+        mdl_driver.note_batch_flush();
         Ok(())
     }
+    /// Compact the model's data batch journal if it has grown large enough
+    ///
+    /// Batch writes, inserts and deletes for this model are paused while the journal is compacted
+    fn try_compact_model_data_batch(
+        global: &super::Global,
+        model_id: &ModelUniqueID,
+        mdl_driver: &super::FractalModelDriver,
+    ) -> crate::engine::error::QueryResult<()> {
+        if !mdl_driver.storage().lock().needs_compaction()? {
+            return Ok(());
+        }
+        let Some(model_path) = Self::model_path(global, model_id) else {
+            // the space was dropped
+            return Ok(());
+        };
+        global._namespace().with_model(
+            EntityIDRef::new(model_id.space().into(), model_id.model().into()),
+            |model| {
+                if model.get_uuid() != model_id.uuid() {
+                    // the model was dropped and recreated; this driver is stale
+                    return Ok(());
+                }
+                let mut storage = mdl_driver.storage().lock();
+                // block inserts and deletes so that the snapshot (and all deltas before it) stay consistent
+                let _latch = model.primary_index().acquire_exclusive();
+                if model.delta_state().has_held_data_deltas() {
+                    // the snapshot would have changes that might still be rolled back; try again on the next run
+                    return Ok(());
+                }
+                let observed_len = model
+                    .delta_state()
+                    .__fractal_take_full_from_data_delta(super::FractalToken::new());
+                if observed_len != 0 {
+                    storage.append_batch(model, observed_len)?;
+                    mdl_driver.note_batch_flush();
+                }
+                storage.compact(
+                    &model_path,
+                    model,
+                    global.get_state().batch_compression,
+                    global.get_state().encryption.as_ref(),
+                )?;
+                info!("flp: compacted data batch for {model_id}");
+                Ok(())
+            },
+        )
+    }
+    /// Seal the active segment of the model's data batch journal (and start a new one) if it has grown large enough,
+    /// returning true if it was rotated
+    ///
+    /// Batch writes for this model are paused while the journal is rotated
+    fn try_rotate_model_data_batch(
+        global: &super::Global,
+        model_id: &ModelUniqueID,
+        mdl_driver: &super::FractalModelDriver,
+    ) -> crate::engine::error::QueryResult<bool> {
+        if !mdl_driver.storage().lock().needs_rotation()? {
+            return Ok(false);
+        }
+        let Some(model_path) = Self::model_path(global, model_id) else {
+            // the space was dropped
+            return Ok(false);
+        };
+        let mut storage = mdl_driver.storage().lock();
+        // a compaction might have gotten to it first
+        if !storage.needs_rotation()? {
+            return Ok(false);
+        }
+        storage.rotate(&model_path)?;
+        Ok(true)
+    }
+    /// Take a snapshot of the model's data if enough has been written to its data batch journal since the last one
+    ///
+    /// Batch writes, inserts and deletes for this model are paused while the snapshot is taken
+    fn try_snapshot_model_data(
+        global: &super::Global,
+        model_id: &ModelUniqueID,
+        mdl_driver: &super::FractalModelDriver,
+    ) -> crate::engine::error::QueryResult<()> {
+        if !mdl_driver.storage().lock().needs_snapshot()? {
+            return Ok(());
+        }
+        let Some(model_path) = Self::model_path(global, model_id) else {
+            // the space was dropped
+            return Ok(());
+        };
+        global._namespace().with_model(
+            EntityIDRef::new(model_id.space().into(), model_id.model().into()),
+            |model| {
+                if model.get_uuid() != model_id.uuid() {
+                    // the model was dropped and recreated; this driver is stale
+                    return Ok(());
+                }
+                let mut storage = mdl_driver.storage().lock();
+                // block inserts and deletes so that the snapshot matches the journal offset it is taken at
+                let _latch = model.primary_index().acquire_exclusive();
+                let observed_len = model
+                    .delta_state()
+                    .__fractal_take_full_from_data_delta(super::FractalToken::new());
+                if observed_len != 0 {
+                    storage.append_batch(model, observed_len)?;
+                    mdl_driver.note_batch_flush();
+                }
+                let kind = match storage.snapshot(&model_path, model)? {
+                    true => "incremental",
+                    false => "full",
+                };
+                info!("flp: took {kind} snapshot of {model_id}");
+                Ok(())
+            },
+        )
+    }
+    /// Returns the path of the model's data batch journal, unless its space was dropped
+    fn model_path(global: &super::Global, model_id: &ModelUniqueID) -> Option<String> {
+        // NB: don't hold the spaces lock while we access the model (DDL locks spaces first)
+        let space_uuid = global
+            ._namespace()
+            .idx()
+            .read()
+            .get(model_id.space())
+            .map(|space| space.get_uuid())?;
+        Some(SEInitState::model_path(
+            model_id.space(),
+            space_uuid,
+            model_id.model(),
+            model_id.uuid(),
+        ))
+    }
+}
+
+// backup
+impl FractalMgr {
+    /// Write a backup of all data files into the directory at `path` (which must not exist)
+    ///
+    /// DDL queries and batch writes are paused while the backup runs. Any pending changes for a model are flushed to its
+    /// batch journal before it is copied, and all copied journals are closed so that they can be loaded independently
+    pub(super) fn backup_all<Fs: RawFSInterface>(
+        gns: &GlobalNS,
+        gns_driver: &Mutex<GNSTransactionDriverAnyFS<Fs>>,
+        gns_path: &str,
+        mdl_drivers: &RwLock<ModelDrivers>,
+        sys_store: &SystemStore<Fs>,
+        path: &str,
+    ) -> RuntimeResult<()> {
+        // lock down the namespace and the GNS driver so that no DDL can run while we're at it
+        let spaces = gns.idx().read();
+        let models = gns.idx_models().read();
+        let gns_driver = gns_driver.lock();
+        let mdl_drivers = mdl_drivers.read();
+        Fs::fs_create_dir(path)?;
+        Fs::fs_create_dir(&format!("{path}/{}", loader::DATA_DIR))?;
+        for (space_name, space) in spaces.iter() {
+            let space_uuid = space.get_uuid();
+            Fs::fs_create_dir(&format!(
+                "{path}/{}",
+                SEInitState::space_dir(space_name, space_uuid)
+            ))?;
+            for model_name in space.models().iter() {
+                let model = models
+                    .get(&EntityIDRef::new(space_name, model_name))
+                    .unwrap();
+                let model_uuid = model.get_uuid();
+                let mdl_driver = mdl_drivers
+                    .get(&ModelUniqueID::new(space_name, model_name, model_uuid))
+                    .unwrap();
+                Fs::fs_create_dir(&format!(
+                    "{path}/{}",
+                    SEInitState::model_dir(space_name, space_uuid, model_name, model_uuid)
+                ))?;
+                // pause batch writes for this model while we flush and copy
+                let mut storage = mdl_driver.storage().lock();
+                let observed_len = model
+                    .delta_state()
+                    .__fractal_take_full_from_data_delta(super::FractalToken::new());
+                if observed_len != 0 {
+                    storage.append_batch(model, observed_len)?;
+                    mdl_driver.note_batch_flush();
+                }
+                let model_path =
+                    SEInitState::model_path(space_name, space_uuid, model_name, model_uuid);
+                storage.backup_to(&model_path, &format!("{path}/{model_path}"))?;
+            }
+        }
+        gns_driver.backup_to(gns_path, &format!("{path}/{}", loader::GNS_FILE_PATH))?;
+        sys_store.backup_to(path)
+    }
+}
+
+// promotion
+impl FractalMgr {
+    /// Rotate the batch journals of all models
+    ///
+    /// Any pending changes for a model are flushed to its batch journal before it is compacted into a fresh journal
+    /// with a new epoch. DDL queries, batch writes, inserts and deletes are paused while a model's journal is rotated
+    pub(super) fn rotate_all(
+        gns: &GlobalNS,
+        mdl_drivers: &RwLock<ModelDrivers>,
+        compression: BatchCompression,
+        encryption: Option<&EncryptionKey>,
+    ) -> RuntimeResult<()> {
+        let spaces = gns.idx().read();
+        let models = gns.idx_models().read();
+        let mdl_drivers = mdl_drivers.read();
+        for (space_name, space) in spaces.iter() {
+            let space_uuid = space.get_uuid();
+            for model_name in space.models().iter() {
+                let model = models
+                    .get(&EntityIDRef::new(space_name, model_name))
+                    .unwrap();
+                let model_uuid = model.get_uuid();
+                let mdl_driver = mdl_drivers
+                    .get(&ModelUniqueID::new(space_name, model_name, model_uuid))
+                    .unwrap();
+                let mut storage = mdl_driver.storage().lock();
+                let _latch = model.primary_index().acquire_exclusive();
+                let observed_len = model
+                    .delta_state()
+                    .__fractal_take_full_from_data_delta(super::FractalToken::new());
+                if observed_len != 0 {
+                    storage.append_batch(model, observed_len)?;
+                    mdl_driver.note_batch_flush();
+                }
+                storage.compact(
+                    &SEInitState::model_path(space_name, space_uuid, model_name, model_uuid),
+                    model,
+                    compression,
+                    encryption,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// shutdown
+impl FractalMgr {
+    /// Close the batch journals of all models, so that they are known to be complete on the next start
+    ///
+    /// Any pending changes for a model (that the executors didn't get to) are flushed to its batch journal before it is
+    /// closed. Failures are logged, and we keep going so that as many journals as possible are closed cleanly. Returns
+    /// true if all of them were
+    pub(super) fn close_all(gns: &GlobalNS, mdl_drivers: ModelDrivers) -> bool {
+        let models = gns.idx_models().read();
+        let mut clean = true;
+        for (model_id, mdl_driver) in mdl_drivers {
+            let model = models
+                .get(&EntityIDRef::new(model_id.space(), model_id.model()))
+                .filter(|model| model.get_uuid() == model_id.uuid());
+            if let Some(model) = model {
+                let observed_len = model
+                    .delta_state()
+                    .__fractal_take_full_from_data_delta(super::FractalToken::new());
+                if observed_len != 0 {
+                    match mdl_driver
+                        .storage()
+                        .lock()
+                        .append_batch(model, observed_len)
+                    {
+                        Ok(()) => mdl_driver.note_batch_flush(),
+                        Err(e) => {
+                            error!("failed to flush pending changes for {model_id}: {e}");
+                            clean = false;
+                        }
+                    }
+                }
+            }
+            if let Err(e) = mdl_driver.close() {
+                error!("failed to close data batch for {model_id}: {e}");
+                clean = false;
+            }
+        }
+        clean
+    }
 }
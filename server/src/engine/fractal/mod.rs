@@ -27,35 +27,47 @@
 use {
     self::sys_store::SystemStore,
     super::{
-        core::{dml::QueryExecMeta, model::Model, GlobalNS},
+        core::{dml::QueryExecMeta, index::RowCacheBudget, model::Model, GlobalNS},
         data::uuid::Uuid,
+        repl::ReplicationRole,
         storage::{
             self,
-            v1::{LocalFS, RawFSInterface},
+            engine::{IoBackend, StorageEngineKind},
+            v1::{encryption::EncryptionKey, spec::BatchCompression, LocalFS, RawFSInterface},
         },
         txn::gns::GNSTransactionDriverAnyFS,
     },
     crate::engine::error::RuntimeResult,
     parking_lot::{Mutex, RwLock},
-    std::{collections::HashMap, fmt, mem::MaybeUninit},
-    tokio::sync::mpsc::unbounded_channel,
+    std::{collections::HashMap, fmt, mem::MaybeUninit, sync::Arc},
+    tokio::sync::{mpsc::unbounded_channel, Notify},
 };
 
+mod backpressure;
+pub mod blocking;
+pub mod cdc;
 pub mod context;
 mod drivers;
 pub mod error;
+mod memory;
 mod mgr;
+pub mod queries;
+pub mod startup;
 pub mod sys_store;
 #[cfg(test)]
 pub mod test_utils;
+#[cfg(test)]
+mod tests;
 mod util;
 pub use {
+    backpressure::{Backpressure, BackpressurePolicy},
     drivers::FractalModelDriver,
+    memory::{MemoryLimits, MemoryUsage},
     mgr::{CriticalTask, GenericTask, Task, GENERAL_EXECUTOR_WINDOW},
     util::FractalToken,
 };
 
-pub type ModelDrivers<Fs> = HashMap<ModelUniqueID, drivers::FractalModelDriver<Fs>>;
+pub type ModelDrivers = HashMap<ModelUniqueID, drivers::FractalModelDriver>;
 
 /*
     global state init
@@ -76,10 +88,30 @@ pub struct GlobalStateStart {
 pub unsafe fn load_and_enable_all(
     gns: GlobalNS,
     config: SystemStore<LocalFS>,
-    gns_driver: GNSTransactionDriverAnyFS<LocalFS>,
-    model_drivers: ModelDrivers<LocalFS>,
+    mut gns_driver: GNSTransactionDriverAnyFS<LocalFS>,
+    mut model_drivers: ModelDrivers,
+    storage_engine: StorageEngineKind,
+    io_backend: IoBackend,
+    batch_compression: BatchCompression,
+    encryption: Option<EncryptionKey>,
+    replica: bool,
+    backpressure: Backpressure,
+    row_cache: RowCacheBudget,
+    memory: MemoryLimits,
+    queries: queries::RunningQueries,
+    blocking: blocking::BlockingPool,
 ) -> GlobalStateStart {
     let model_cnt_on_boot = model_drivers.len();
+    // the rows were restored without being accounted for
+    gns.idx_models()
+        .read()
+        .values()
+        .for_each(Model::recount_index_bytes);
+    let durability = config.system_store().durability();
+    gns_driver.__journal_mut().set_durability(durability);
+    for driver in model_drivers.values_mut() {
+        driver.storage().lock().set_durability(durability);
+    }
     let gns_driver = drivers::FractalGNSDriver::new(gns_driver);
     let mdl_driver = RwLock::new(model_drivers);
     let (hp_sender, hp_recv) = unbounded_channel();
@@ -88,8 +120,17 @@ pub unsafe fn load_and_enable_all(
         gns,
         gns_driver,
         mdl_driver,
-        mgr::FractalMgr::new(hp_sender, lp_sender, model_cnt_on_boot),
+        mgr::FractalMgr::new(hp_sender, lp_sender, model_cnt_on_boot, row_cache),
         config,
+        storage_engine,
+        io_backend,
+        batch_compression,
+        encryption,
+        ReplicationRole::new(replica),
+        backpressure,
+        memory,
+        queries,
+        blocking,
     );
     *Global::__gref_raw() = MaybeUninit::new(global_state);
     let token = Global::new();
@@ -113,12 +154,14 @@ pub trait GlobalInstanceLike {
     fn namespace(&self) -> &GlobalNS;
     fn namespace_txn_driver(&self) -> &Mutex<GNSTransactionDriverAnyFS<Self::FileSystem>>;
     // model drivers
+    fn model_drivers(&self) -> &RwLock<ModelDrivers>;
     fn initialize_model_driver(
         &self,
         space_name: &str,
         space_uuid: Uuid,
         model_name: &str,
         model_uuid: Uuid,
+        ephemeral: bool,
     ) -> RuntimeResult<()>;
     fn purge_model_driver(
         &self,
@@ -140,7 +183,7 @@ pub trait GlobalInstanceLike {
         hint: QueryExecMeta,
     ) {
         let current_delta_size = hint.delta_hint();
-        let index_size = model.primary_index().count();
+        let index_size = model.primary_index().count_approx();
         let five = (index_size as f64 * 0.05) as usize;
         let max_delta = five.max(self.get_max_delta_size());
         if current_delta_size >= max_delta {
@@ -155,6 +198,25 @@ pub trait GlobalInstanceLike {
     }
     // config handle
     fn sys_store(&self) -> &SystemStore<Self::FileSystem>;
+    // backup
+    fn backup_to(&self, path: &str) -> RuntimeResult<()>;
+    // replication
+    fn replication_role(&self) -> &ReplicationRole;
+    /// Promote this replica to a primary, returning false if it already is one
+    fn promote(&self) -> RuntimeResult<bool>;
+    // shutdown
+    /// Ask the server to shut down (which happens once the queries that are running complete)
+    fn request_shutdown(&self);
+    // backpressure
+    fn backpressure(&self) -> &Backpressure;
+    // row cache
+    /// Returns the memory budget shared by the row caches of all models
+    fn row_cache(&self) -> &Arc<RowCacheBudget>;
+    // memory
+    fn memory(&self) -> &MemoryLimits;
+    // queries
+    /// Returns the queries that are being run
+    fn queries(&self) -> &queries::RunningQueries;
 }
 
 impl GlobalInstanceLike for Global {
@@ -181,7 +243,57 @@ impl GlobalInstanceLike for Global {
     fn sys_store(&self) -> &SystemStore<Self::FileSystem> {
         &self.get_state().config
     }
+    // backup
+    fn backup_to(&self, path: &str) -> RuntimeResult<()> {
+        let state = self.get_state();
+        mgr::FractalMgr::backup_all(
+            &state.gns,
+            state.gns_driver.txn_driver(),
+            storage::v1::loader::GNS_FILE_PATH,
+            state.get_mdl_drivers(),
+            &state.config,
+            path,
+        )
+    }
+    // replication
+    fn replication_role(&self) -> &ReplicationRole {
+        &self.get_state().replication_role
+    }
+    fn promote(&self) -> RuntimeResult<bool> {
+        let state = self.get_state();
+        state.replication_role.promote(|| {
+            mgr::FractalMgr::rotate_all(
+                &state.gns,
+                state.get_mdl_drivers(),
+                state.batch_compression,
+                state.encryption.as_ref(),
+            )
+        })
+    }
+    // shutdown
+    fn request_shutdown(&self) {
+        self.get_state().shutdown.notify_one()
+    }
+    // backpressure
+    fn backpressure(&self) -> &Backpressure {
+        &self.get_state().backpressure
+    }
+    // row cache
+    fn row_cache(&self) -> &Arc<RowCacheBudget> {
+        self.get_state().fractal_mgr().row_cache()
+    }
+    // memory
+    fn memory(&self) -> &MemoryLimits {
+        &self.get_state().memory
+    }
+    // queries
+    fn queries(&self) -> &queries::RunningQueries {
+        &self.get_state().queries
+    }
     // model
+    fn model_drivers(&self) -> &RwLock<ModelDrivers> {
+        self.get_state().get_mdl_drivers()
+    }
     fn purge_model_driver(
         &self,
         space_name: &str,
@@ -208,19 +320,28 @@ impl GlobalInstanceLike for Global {
         space_uuid: Uuid,
         model_name: &str,
         model_uuid: Uuid,
+        ephemeral: bool,
     ) -> RuntimeResult<()> {
         // create dir
         LocalFS::fs_create_dir(&storage::v1::loader::SEInitState::model_dir(
             space_name, space_uuid, model_name, model_uuid,
         ))?;
-        // init driver
-        let driver =
-            storage::v1::data_batch::create(&storage::v1::loader::SEInitState::model_path(
-                space_name, space_uuid, model_name, model_uuid,
-            ))?;
+        // init storage
+        let mut storage = self
+            .get_state()
+            .storage_engine
+            .local_model_engine(self.get_state().io_backend, ephemeral)
+            .create_model(
+                &storage::v1::loader::SEInitState::model_path(
+                    space_name, space_uuid, model_name, model_uuid,
+                ),
+                self.get_state().batch_compression,
+                self.get_state().encryption.as_ref(),
+            )?;
+        storage.set_durability(self.get_state().config.system_store().durability());
         self.get_state().mdl_driver.write().insert(
             ModelUniqueID::new(space_name, model_name, model_uuid),
-            drivers::FractalModelDriver::init(driver),
+            drivers::FractalModelDriver::init(storage),
         );
         Ok(())
     }
@@ -267,18 +388,38 @@ impl Global {
     unsafe fn __gref(&self) -> &'static GlobalState {
         Self::__gref_raw().assume_init_ref()
     }
+    /// Returns the pool that anything that blocks on the disk must be run on
+    pub fn blocking_pool(&self) -> &'static blocking::BlockingPool {
+        &self.get_state().blocking
+    }
+    /// Completes once a shutdown was requested (using `sysctl shutdown`)
+    pub async fn shutdown_requested(&self) {
+        self.get_state().shutdown.notified().await
+    }
+    /// Close all data files, flushing any changes that weren't written yet, and persist the system database
+    ///
+    /// Errors are logged, and we keep going so that as much as possible is closed cleanly
     pub unsafe fn unload_all(self) {
-        // TODO(@ohsayan): handle errors
         let GlobalState {
+            gns,
             gns_driver,
             mdl_driver,
+            config,
             ..
         } = Self::__gref_raw().assume_init_read();
-        let gns_driver = gns_driver.txn_driver.into_inner().into_inner();
-        let mdl_drivers = mdl_driver.into_inner();
-        gns_driver.close().unwrap();
-        for (_, driver) in mdl_drivers {
-            driver.close().unwrap();
+        if !mgr::FractalMgr::close_all(&gns, mdl_driver.into_inner()) {
+            error!("failed to cleanly close the data batch journals of some models");
+        }
+        if let Err(e) = gns_driver.txn_driver.into_inner().into_inner().close() {
+            error!("failed to close the GNS log: {e}");
+        }
+        let auth = config.system_store().auth_data().read();
+        if let Err(e) = config.sync_db(&auth) {
+            error!("failed to persist the system database: {e}");
+        }
+        drop(auth);
+        if let Some(Err(e)) = config.audit_log().map(|audit| audit.close()) {
+            error!("failed to close the audit log: {e}");
         }
     }
 }
@@ -291,18 +432,47 @@ impl Global {
 struct GlobalState {
     gns: GlobalNS,
     gns_driver: drivers::FractalGNSDriver<LocalFS>,
-    mdl_driver: RwLock<ModelDrivers<LocalFS>>,
+    mdl_driver: RwLock<ModelDrivers>,
     task_mgr: mgr::FractalMgr,
     config: SystemStore<LocalFS>,
+    /// the storage engine that keeps the data of models
+    storage_engine: StorageEngineKind,
+    /// the backend that the storage engine uses for file I/O
+    io_backend: IoBackend,
+    /// the compression used for new (and compacted) data batch journals
+    batch_compression: BatchCompression,
+    /// the key that new (and compacted) data batch journals are encrypted with (if any)
+    encryption: Option<EncryptionKey>,
+    /// whether this is a primary or a (read-only) replica
+    replication_role: ReplicationRole,
+    /// holds back writes to models that have too many unflushed deltas
+    backpressure: Backpressure,
+    /// rejects writes to models (or to the database) that use too much memory
+    memory: MemoryLimits,
+    /// the queries that are being run (and the query timeout)
+    queries: queries::RunningQueries,
+    /// runs anything that blocks on the disk
+    blocking: blocking::BlockingPool,
+    /// notified when a shutdown is requested
+    shutdown: Notify,
 }
 
 impl GlobalState {
     fn new(
         gns: GlobalNS,
         gns_driver: drivers::FractalGNSDriver<LocalFS>,
-        mdl_driver: RwLock<ModelDrivers<LocalFS>>,
+        mdl_driver: RwLock<ModelDrivers>,
         task_mgr: mgr::FractalMgr,
         config: SystemStore<LocalFS>,
+        storage_engine: StorageEngineKind,
+        io_backend: IoBackend,
+        batch_compression: BatchCompression,
+        encryption: Option<EncryptionKey>,
+        replication_role: ReplicationRole,
+        backpressure: Backpressure,
+        memory: MemoryLimits,
+        queries: queries::RunningQueries,
+        blocking: blocking::BlockingPool,
     ) -> Self {
         Self {
             gns,
@@ -310,9 +480,19 @@ impl GlobalState {
             mdl_driver,
             task_mgr,
             config,
+            storage_engine,
+            io_backend,
+            batch_compression,
+            encryption,
+            replication_role,
+            backpressure,
+            memory,
+            queries,
+            blocking,
+            shutdown: Notify::new(),
         }
     }
-    pub(self) fn get_mdl_drivers(&self) -> &RwLock<ModelDrivers<LocalFS>> {
+    pub(self) fn get_mdl_drivers(&self) -> &RwLock<ModelDrivers> {
         &self.mdl_driver
     }
     pub(self) fn fractal_mgr(&self) -> &mgr::FractalMgr {
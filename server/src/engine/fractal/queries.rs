@@ -0,0 +1,200 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Running queries
+//!
+//! Every query that a client runs is registered until it completes, so that it can be listed (with
+//! `sysctl report queries`) and killed (with `sysctl kill`). A query is executed on a single thread without yielding,
+//! so the query that is being run is kept in a thread local and scans call [`check`] between index iterations to find
+//! out if they should stop, either because the query was killed or because it ran for longer than the query timeout.
+//! Stopping releases the epoch guard (and the index latch) that the scan holds
+//!
+//! Queries that don't scan (like a point lookup) always run to completion
+
+use {
+    crate::engine::error::{QueryError, QueryResult},
+    parking_lot::Mutex,
+    std::{
+        cell::RefCell,
+        collections::HashMap,
+        ops::Deref,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<RunningQuery>>> = const { RefCell::new(None) };
+}
+
+/// Returns an error if the query that is being run on this thread was killed or has timed out
+pub fn check() -> QueryResult<()> {
+    CURRENT.with(|current| match &*current.borrow() {
+        Some(query) => query.check_at(Instant::now()),
+        None => Ok(()),
+    })
+}
+
+#[derive(Debug)]
+pub struct RunningQuery {
+    id: u64,
+    user: Box<str>,
+    query: Box<str>,
+    started: Instant,
+    deadline: Option<Instant>,
+    killed: AtomicBool,
+}
+
+impl RunningQuery {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+    /// The text of the query (with the parameters left out)
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+    pub fn is_killed(&self) -> bool {
+        self.killed.load(Ordering::Acquire)
+    }
+    fn check_at(&self, now: Instant) -> QueryResult<()> {
+        if self.is_killed() {
+            Err(QueryError::SysQueryKilled)
+        } else if self.deadline.is_some_and(|deadline| now >= deadline) {
+            Err(QueryError::SysQueryTimeout)
+        } else {
+            Ok(())
+        }
+    }
+    /// Run `f` as this query, so that the scans that it runs stop once the query is killed or times out
+    ///
+    /// Just like [`Correlation::scope`](crate::engine::logging::Correlation::scope), work that is moved to another
+    /// thread has to be run in a scope of its own and the scope must not span an `await`
+    pub fn scope<T>(self: &Arc<Self>, f: impl FnOnce() -> T) -> T {
+        struct Restore(Option<Arc<RunningQuery>>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.with(|current| *current.borrow_mut() = self.0.take())
+            }
+        }
+        let _restore = Restore(CURRENT.with(|current| current.replace(Some(self.clone()))));
+        f()
+    }
+}
+
+/// The queries that are being run
+#[derive(Debug)]
+pub struct RunningQueries {
+    timeout: Option<Duration>,
+    next_id: AtomicU64,
+    queries: Mutex<HashMap<u64, Arc<RunningQuery>>>,
+}
+
+impl RunningQueries {
+    /// Queries that run for longer than `timeout` milliseconds are stopped (if set)
+    pub fn new(timeout: Option<u64>) -> Self {
+        Self {
+            timeout: timeout.map(Duration::from_millis),
+            next_id: AtomicU64::new(1),
+            queries: Mutex::new(HashMap::new()),
+        }
+    }
+    /// Register a query that the given user is about to run. It's unregistered once the guard is dropped
+    pub fn register(&self, user: &str, query: &str) -> RunningQueryGuard<'_> {
+        self.register_at(user, query, Instant::now())
+    }
+    pub(super) fn register_at(
+        &self,
+        user: &str,
+        query: &str,
+        now: Instant,
+    ) -> RunningQueryGuard<'_> {
+        let query = Arc::new(RunningQuery {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            user: user.into(),
+            query: query.into(),
+            started: now,
+            deadline: self.timeout.map(|timeout| now + timeout),
+            killed: AtomicBool::new(false),
+        });
+        self.queries.lock().insert(query.id, query.clone());
+        RunningQueryGuard {
+            queries: self,
+            query,
+        }
+    }
+    /// Returns the queries that are being run (by the given user, if set), in the order they were started
+    pub fn list(&self, user: Option<&str>) -> Vec<Arc<RunningQuery>> {
+        let mut queries: Vec<_> = self
+            .queries
+            .lock()
+            .values()
+            .filter(|query| user.map_or(true, |user| query.user() == user))
+            .cloned()
+            .collect();
+        queries.sort_unstable_by_key(|query| query.id);
+        queries
+    }
+    /// Kill the query with the given id (only if it was run by the given user, if set). The query stops at its next
+    /// [`check`]
+    pub fn kill(&self, id: u64, user: Option<&str>) -> QueryResult<()> {
+        match self.queries.lock().get(&id) {
+            Some(query) if user.map_or(true, |user| query.user() == user) => {
+                query.killed.store(true, Ordering::Release);
+                Ok(())
+            }
+            // other users' queries are hidden
+            _ => Err(QueryError::QExecObjectNotFound),
+        }
+    }
+}
+
+/// A query that is registered with [`RunningQueries`] while the guard is held
+pub struct RunningQueryGuard<'a> {
+    queries: &'a RunningQueries,
+    query: Arc<RunningQuery>,
+}
+
+impl<'a> Deref for RunningQueryGuard<'a> {
+    type Target = Arc<RunningQuery>;
+    fn deref(&self) -> &Self::Target {
+        &self.query
+    }
+}
+
+impl<'a> Drop for RunningQueryGuard<'a> {
+    fn drop(&mut self) {
+        self.queries.queries.lock().remove(&self.query.id);
+    }
+}
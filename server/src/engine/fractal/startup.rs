@@ -0,0 +1,232 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Startup progress
+//!
+//! Restoring a large model can take a while, during which the server would otherwise appear to hang. The loader
+//! registers every model before it starts restoring them, and the restore drivers report how many bytes of the model's
+//! batch journal (including its sealed segments) they have read. The progress of large models is logged, and the
+//! progress of every model can be queried with `sysctl report startup` (which is also served while the data is still
+//! being restored)
+
+use {
+    parking_lot::Mutex,
+    std::{
+        cell::RefCell,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+/// We only log the progress of models whose journals are at least this large, since the rest are restored quickly enough
+const LOG_THRESHOLD: u64 = 16 * 1024 * 1024;
+/// The progress of a large model is logged every time it goes past a multiple of this percentage
+const LOG_STEP_PERCENT: u64 = 10;
+
+/// The models that are being restored, in the order in which they will be restored
+static MODELS: Mutex<Vec<Arc<ModelRestore>>> = Mutex::new(Vec::new());
+
+thread_local! {
+    /// The model that is being restored on this thread
+    static CURRENT: RefCell<Option<Arc<ModelRestore>>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RestoreState {
+    Pending,
+    Running(Instant),
+    Finished(Duration),
+}
+
+#[derive(Debug)]
+/// The progress of restoring a single model
+pub struct ModelRestore {
+    space: Box<str>,
+    model: Box<str>,
+    total: AtomicU64,
+    done: AtomicU64,
+    state: Mutex<RestoreState>,
+}
+
+impl ModelRestore {
+    fn new(space: &str, model: &str) -> Self {
+        Self {
+            space: space.into(),
+            model: model.into(),
+            total: AtomicU64::new(0),
+            done: AtomicU64::new(0),
+            state: Mutex::new(RestoreState::Pending),
+        }
+    }
+    /// Restore this model using `f`, recording the progress reported by the restore drivers on this thread against this
+    /// model
+    pub fn track<T>(self: &Arc<Self>, f: impl FnOnce() -> T) -> T {
+        *self.state.lock() = RestoreState::Running(Instant::now());
+        let previous = CURRENT.with(|current| current.replace(Some(self.clone())));
+        let ret = f();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+        let elapsed = {
+            let mut state = self.state.lock();
+            let elapsed = match *state {
+                RestoreState::Running(started) => started.elapsed(),
+                _ => Duration::ZERO,
+            };
+            *state = RestoreState::Finished(elapsed);
+            elapsed
+        };
+        if self.is_large() {
+            info!(
+                "restored model {}.{} ({} bytes) in {:.2}s",
+                self.space,
+                self.model,
+                self.done.load(Ordering::Relaxed),
+                elapsed.as_secs_f64()
+            );
+        }
+        ret
+    }
+    fn is_large(&self) -> bool {
+        self.total.load(Ordering::Relaxed) >= LOG_THRESHOLD
+    }
+    fn set_total(&self, bytes: u64) {
+        self.total.store(bytes, Ordering::Relaxed);
+        if self.is_large() {
+            info!(
+                "restoring model {}.{} ({bytes} bytes) ...",
+                self.space, self.model
+            );
+        }
+    }
+    fn advance(&self, bytes: u64) {
+        let before = self.done.fetch_add(bytes, Ordering::Relaxed);
+        let total = self.total.load(Ordering::Relaxed);
+        if !self.is_large() {
+            return;
+        }
+        let step = |done: u64| (done.min(total) * 100 / total) / LOG_STEP_PERCENT;
+        let after = before + bytes;
+        if step(after) > step(before) {
+            info!(
+                "restoring model {}.{}: {}% ({after}/{total} bytes)",
+                self.space,
+                self.model,
+                step(after) * LOG_STEP_PERCENT
+            );
+        }
+    }
+    /// Returns the current progress of this model
+    pub fn progress(&self) -> ModelProgress {
+        let (finished, elapsed) = match *self.state.lock() {
+            RestoreState::Pending => (false, Duration::ZERO),
+            RestoreState::Running(started) => (false, started.elapsed()),
+            RestoreState::Finished(elapsed) => (true, elapsed),
+        };
+        ModelProgress {
+            space: self.space.clone(),
+            model: self.model.clone(),
+            done: self.done.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+            finished,
+            elapsed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A snapshot of the progress of restoring a single model
+pub struct ModelProgress {
+    space: Box<str>,
+    model: Box<str>,
+    done: u64,
+    total: u64,
+    finished: bool,
+    elapsed: Duration,
+}
+
+impl ModelProgress {
+    pub fn space(&self) -> &str {
+        &self.space
+    }
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+    /// The number of bytes of the model's journal that were read so far
+    pub fn done(&self) -> u64 {
+        self.done
+    }
+    /// The number of bytes that have to be read to restore the model (zero if we haven't started restoring it yet)
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+    /// The time spent restoring this model so far
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// Register the models that are about to be restored (in the order in which they will be restored), replacing the
+/// models registered on an earlier start
+pub fn begin<'a>(models: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<Arc<ModelRestore>> {
+    let models: Vec<_> = models
+        .into_iter()
+        .map(|(space, model)| Arc::new(ModelRestore::new(space, model)))
+        .collect();
+    *MODELS.lock() = models.clone();
+    models
+}
+
+/// Returns the progress of every model that is (or was) restored on this start
+pub fn progress() -> Vec<ModelProgress> {
+    MODELS.lock().iter().map(|model| model.progress()).collect()
+}
+
+/// Set the number of bytes that have to be read to restore the model that's being restored on this thread. This does
+/// nothing if no model is being tracked on this thread
+pub fn set_total(bytes: u64) {
+    with_current(|model| model.set_total(bytes))
+}
+
+/// Record that `bytes` more bytes were read while restoring the model that's being restored on this thread. This does
+/// nothing if no model is being tracked on this thread
+pub fn advance(bytes: u64) {
+    if bytes != 0 {
+        with_current(|model| model.advance(bytes))
+    }
+}
+
+fn with_current(f: impl FnOnce(&ModelRestore)) {
+    CURRENT.with(|current| {
+        if let Some(model) = current.borrow().as_deref() {
+            f(model)
+        }
+    })
+}
@@ -26,40 +26,95 @@
 
 use {
     crate::engine::{
-        config::{ConfigAuth, ConfigMode},
-        error::{QueryError, QueryResult},
-        storage::v1::RawFSInterface,
+        config::{ConfigAuth, ConfigMode, ROOT_PASSWORD_MIN_LEN},
+        error::{QueryError, QueryResult, RuntimeResult},
+        storage::v1::{
+            audit::{AuditEvent, AuditFilter, AuditLog},
+            durability::DurabilityPolicy,
+            RawFSInterface,
+        },
     },
-    parking_lot::RwLock,
+    openssl::{hash::MessageDigest, memcmp, pkey::PKey, rand, sign::Signer},
+    parking_lot::{Mutex, RwLock},
     std::{
-        collections::{hash_map::Entry, HashMap},
+        collections::{hash_map::Entry, HashMap, HashSet},
         marker::PhantomData,
+        time::{Duration, Instant, SystemTime, UNIX_EPOCH},
     },
 };
 
 #[derive(Debug)]
-pub struct SystemStore<Fs> {
+pub struct SystemStore<Fs: RawFSInterface> {
     syscfg: SysConfig,
+    sysdb_path: Box<str>,
+    sysdb_cow_path: Box<str>,
+    audit: Option<AuditLog<Fs>>,
+    pub(super) export_dir: Option<Box<str>>,
     _fs: PhantomData<Fs>,
 }
 
-impl<Fs> SystemStore<Fs> {
+impl<Fs: RawFSInterface> SystemStore<Fs> {
     pub fn system_store(&self) -> &SysConfig {
         &self.syscfg
     }
+    /// Set the policy that decides when journals are synced to disk
+    pub fn with_durability(mut self, durability: DurabilityPolicy) -> Self {
+        self.syscfg.durability = durability;
+        self
+    }
+    /// Returns the path to the system database, and the path used to stage changes to it
+    pub fn sysdb_paths(&self) -> (&str, &str) {
+        (&self.sysdb_path, &self.sysdb_cow_path)
+    }
+    /// Record audited events in the given log
+    pub fn with_audit_log(mut self, audit: AuditLog<Fs>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+    /// Record an event in the audit log (if there is one)
+    pub fn audit(&self, event: AuditEvent) {
+        if let Some(audit) = self.audit.as_ref() {
+            audit.record(event)
+        }
+    }
+    /// Returns the audit log (if there is one)
+    pub fn audit_log(&self) -> Option<&AuditLog<Fs>> {
+        self.audit.as_ref()
+    }
+    /// Allow models to be exported into the given directory
+    pub fn with_export_dir(mut self, dir: Option<&str>) -> Self {
+        self.export_dir = dir.map(Into::into);
+        self
+    }
+    /// Returns the directory that models are exported into (if exports are enabled)
+    pub fn export_dir(&self) -> Option<&str> {
+        self.export_dir.as_deref()
+    }
+    /// Returns the events in the audit log that match the filter (none if there is no audit log)
+    pub fn read_audit_log(&self, filter: AuditFilter) -> RuntimeResult<Vec<AuditEvent>> {
+        match self.audit.as_ref() {
+            Some(audit) => audit.read(filter),
+            None => Ok(vec![]),
+        }
+    }
 }
 
 #[derive(Debug)]
 /// The global system configuration
 pub struct SysConfig {
     auth_data: RwLock<SysAuth>,
+    auth_policy: SysAuthPolicy,
+    auth_tokens: SysAuthTokens,
+    quota_usage: SysQuotaUsage,
     host_data: SysHostData,
     run_mode: ConfigMode,
+    durability: DurabilityPolicy,
 }
 
 impl PartialEq for SysConfig {
     fn eq(&self, other: &Self) -> bool {
         self.run_mode == other.run_mode
+            && self.auth_policy == other.auth_policy
             && self.host_data == other.host_data
             && self.auth_data.read().eq(&other.auth_data.read())
     }
@@ -67,50 +122,253 @@ impl PartialEq for SysConfig {
 
 impl SysConfig {
     /// Initialize a new system config
-    pub fn new(auth_data: RwLock<SysAuth>, host_data: SysHostData, run_mode: ConfigMode) -> Self {
+    pub fn new(
+        auth_data: RwLock<SysAuth>,
+        auth_policy: SysAuthPolicy,
+        host_data: SysHostData,
+        run_mode: ConfigMode,
+    ) -> Self {
         Self {
             auth_data,
+            auth_policy,
+            auth_tokens: SysAuthTokens::new(),
+            quota_usage: SysQuotaUsage::default(),
             host_data,
             run_mode,
+            durability: DurabilityPolicy::Always,
         }
     }
     pub fn new_full(new_auth: ConfigAuth, host_data: SysHostData, run_mode: ConfigMode) -> Self {
-        Self::new(
-            RwLock::new(SysAuth::new(
-                into_dict!(SysAuthUser::USER_ROOT => SysAuthUser::new(
-                rcrypt::hash(new_auth.root_key.as_str(), rcrypt::DEFAULT_COST)
-                    .unwrap()
-                    .into_boxed_slice())),
-            )),
-            host_data,
-            run_mode,
+        let auth_policy = SysAuthPolicy::new(&new_auth);
+        // a bootstrapped root key has to be changed before the database can be used
+        let auth_data = SysAuth::new(
+            into_dict!(SysAuthUser::USER_ROOT => auth_policy.new_user(new_auth.root_key.as_str())),
         )
+        .with_root_must_rotate(new_auth.root_rotate);
+        Self::new(RwLock::new(auth_data), auth_policy, host_data, run_mode)
     }
     pub fn new_auth(new_auth: ConfigAuth, run_mode: ConfigMode) -> Self {
         Self::new_full(new_auth, SysHostData::new(0, 0), run_mode)
     }
+    /// Create a new system config that keeps all the users (and their grants) from the previous config, resetting
+    /// the root key to the one in the given auth settings
+    ///
+    /// If the root key is only used to bootstrap the root account, the previous root key is retained instead (along
+    /// with whether it still has to be rotated)
+    pub fn new_with_users_from(
+        prev: Self,
+        new_auth: ConfigAuth,
+        host_data: SysHostData,
+        run_mode: ConfigMode,
+    ) -> Self {
+        let auth_policy = SysAuthPolicy::new(&new_auth);
+        let mut auth_data = prev.auth_data.into_inner();
+        if !new_auth.root_rotate {
+            auth_data.users.insert(
+                SysAuthUser::USER_ROOT.into(),
+                auth_policy.new_user(new_auth.root_key.as_str()),
+            );
+            auth_data.root_must_rotate = false;
+        }
+        Self::new(RwLock::new(auth_data), auth_policy, host_data, run_mode)
+    }
     #[cfg(test)]
     /// A test-mode default setting with the root password set to `password12345678`
     pub(super) fn test_default() -> Self {
+        let auth_policy = SysAuthPolicy::new(&ConfigAuth::new(
+            crate::engine::config::AuthDriver::Pwd,
+            "password12345678".into(),
+        ));
         Self {
             auth_data: RwLock::new(SysAuth::new(
-                into_dict!(SysAuthUser::USER_ROOT => SysAuthUser::new(
-                rcrypt::hash("password12345678", rcrypt::DEFAULT_COST)
-                    .unwrap()
-                    .into_boxed_slice())),
+                into_dict!(SysAuthUser::USER_ROOT => auth_policy.new_user("password12345678")),
             )),
+            auth_policy,
+            auth_tokens: SysAuthTokens::new(),
+            quota_usage: SysQuotaUsage::default(),
             host_data: SysHostData::new(0, 0),
             run_mode: ConfigMode::Dev,
+            durability: DurabilityPolicy::Always,
         }
     }
     /// Returns a handle to the authentication data
     pub fn auth_data(&self) -> &RwLock<SysAuth> {
         &self.auth_data
     }
+    /// Returns the password policy
+    pub fn auth_policy(&self) -> &SysAuthPolicy {
+        &self.auth_policy
+    }
+    /// Returns true if the root key was bootstrapped from the configuration, and is yet to be changed
+    pub fn root_must_rotate(&self) -> bool {
+        self.auth_data.read().root_must_rotate
+    }
+    /// Issue a token for the given user, that expires [`SysAuthTokens::TOKEN_TTL_SECS`] seconds from now
+    pub fn issue_token(&self, username: &str) -> QueryResult<String> {
+        self.issue_token_at(username, SysAuthTokens::now())
+    }
+    /// Verify a token issued to the given user, returning true if this is the root account
+    pub fn verify_token(&self, username: &str, token: &[u8]) -> QueryResult<bool> {
+        self.verify_token_at(username, token, SysAuthTokens::now())
+    }
+    /// Same as [`Self::issue_token`], but as if it was the given time (in seconds since the UNIX epoch)
+    pub fn issue_token_at(&self, username: &str, now: u64) -> QueryResult<String> {
+        let auth = self.auth_data.read();
+        match auth.users.get(username) {
+            Some(user) => Ok(self.auth_tokens.issue(username, user.key(), now)),
+            None => Err(QueryError::SysAuthError),
+        }
+    }
+    /// Same as [`Self::verify_token`], but as if it was the given time (in seconds since the UNIX epoch)
+    pub fn verify_token_at(&self, username: &str, token: &[u8], now: u64) -> QueryResult<bool> {
+        let auth = self.auth_data.read();
+        match auth.users.get(username) {
+            Some(user) if self.auth_tokens.verify(username, user.key(), token, now) => {
+                Ok(username == SysAuthUser::USER_ROOT)
+            }
+            Some(_) | None => Err(QueryError::SysAuthError),
+        }
+    }
+    /// Returns the resource quota for the given user (a user that doesn't exist has no limits)
+    pub fn user_quota(&self, username: &str) -> SysAuthQuota {
+        self.auth_data
+            .read()
+            .users
+            .get(username)
+            .map(SysAuthUser::quota)
+            .unwrap_or_default()
+    }
+    /// Returns the resources that every user is currently using
+    pub fn quota_usage(&self) -> &SysQuotaUsage {
+        &self.quota_usage
+    }
     /// Returns a reference to host data
     pub fn host_data(&self) -> &SysHostData {
         &self.host_data
     }
+    /// Returns the policy that decides when journals are synced to disk
+    pub fn durability(&self) -> DurabilityPolicy {
+        self.durability
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// The password policy (from the auth configuration)
+pub struct SysAuthPolicy {
+    password_min_len: usize,
+    hash_cost: u32,
+    root_rotate: bool,
+}
+
+impl SysAuthPolicy {
+    /// New [`SysAuthPolicy`] using the given auth settings
+    pub fn new(auth: &ConfigAuth) -> Self {
+        Self {
+            password_min_len: auth.password_min_len,
+            hash_cost: auth.hash_cost,
+            root_rotate: auth.root_rotate,
+        }
+    }
+    /// Returns the cost that passwords are hashed with
+    pub fn hash_cost(&self) -> u32 {
+        self.hash_cost
+    }
+    /// Check if the password is acceptable for a new (or altered) user
+    fn verify_password(&self, password: &str) -> QueryResult<()> {
+        if password.len() < self.password_min_len {
+            return Err(QueryError::SysAuthPasswordPolicy);
+        }
+        Ok(())
+    }
+    fn hash(&self, password: impl AsRef<[u8]>) -> Box<[u8]> {
+        rcrypt::hash(password, self.hash_cost)
+            .unwrap()
+            .into_boxed_slice()
+    }
+    /// Create a new (unrestricted) user, hashing the password with the configured cost
+    fn new_user(&self, password: &str) -> SysAuthUser {
+        SysAuthUser::new(self.hash(password), self.hash_cost)
+    }
+}
+
+/// Issues and verifies short-lived auth tokens
+///
+/// A token looks like `[expiry].[mac]`, where the MAC is a HMAC-SHA256 over the username, the expiry and the user's
+/// current key. The HMAC secret is generated on startup and never persisted, so tokens don't survive a restart.
+/// Since the key is a part of the MAC, changing the password (or dropping the user) revokes all tokens issued to
+/// the user
+pub struct SysAuthTokens {
+    secret: [u8; Self::SECRET_LEN],
+}
+
+impl core::fmt::Debug for SysAuthTokens {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // don't leak the secret
+        f.debug_struct("SysAuthTokens").finish_non_exhaustive()
+    }
+}
+
+impl SysAuthTokens {
+    /// tokens expire 15 minutes after they are issued
+    pub const TOKEN_TTL_SECS: u64 = 15 * 60;
+    const SECRET_LEN: usize = 32;
+    const SEP: u8 = b'.';
+    fn new() -> Self {
+        let mut secret = [0; Self::SECRET_LEN];
+        rand::rand_bytes(&mut secret).unwrap();
+        Self { secret }
+    }
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+    fn mac(&self, username: &str, key: &[u8], expiry: u64) -> Vec<u8> {
+        let pkey = PKey::hmac(&self.secret).unwrap();
+        let mut signer = Signer::new(MessageDigest::sha256(), &pkey).unwrap();
+        signer
+            .update(&(username.len() as u64).to_le_bytes())
+            .unwrap();
+        signer.update(username.as_bytes()).unwrap();
+        signer.update(&expiry.to_le_bytes()).unwrap();
+        signer.update(key).unwrap();
+        signer.sign_to_vec().unwrap()
+    }
+    fn issue(&self, username: &str, key: &[u8], now: u64) -> String {
+        let expiry = now + Self::TOKEN_TTL_SECS;
+        let mut token = format!("{expiry}{}", Self::SEP as char);
+        for byte in self.mac(username, key, expiry) {
+            token.push_str(&format!("{byte:02x}"));
+        }
+        token
+    }
+    fn verify(&self, username: &str, key: &[u8], token: &[u8], now: u64) -> bool {
+        let Some(sep) = token.iter().position(|b| *b == Self::SEP) else {
+            return false;
+        };
+        let (expiry, mac) = (&token[..sep], &token[sep + 1..]);
+        let Some(expiry) = core::str::from_utf8(expiry)
+            .ok()
+            .and_then(|expiry| expiry.parse::<u64>().ok())
+        else {
+            return false;
+        };
+        let Some(mac) = mac
+            .chunks(2)
+            .map(|byte| {
+                core::str::from_utf8(byte)
+                    .ok()
+                    .filter(|byte| byte.len() == 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+        else {
+            return false;
+        };
+        let expected = self.mac(username, key, expiry);
+        (now < expiry) & (mac.len() == expected.len()) && memcmp::eq(&mac, &expected)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -149,8 +407,15 @@ impl SysHostData {
 
 impl<Fs: RawFSInterface> SystemStore<Fs> {
     pub fn _new(syscfg: SysConfig) -> Self {
+        Self::_new_with_paths(syscfg, Self::SYSDB_PATH, Self::SYSDB_COW_PATH)
+    }
+    pub fn _new_with_paths(syscfg: SysConfig, sysdb_path: &str, sysdb_cow_path: &str) -> Self {
         Self {
             syscfg,
+            sysdb_path: sysdb_path.into(),
+            sysdb_cow_path: sysdb_cow_path.into(),
+            audit: None,
+            export_dir: None,
             _fs: PhantomData,
         }
     }
@@ -167,15 +432,13 @@ impl<Fs: RawFSInterface> SystemStore<Fs> {
     /// Create a new user with the given details
     pub fn create_new_user(&self, username: String, password: String) -> QueryResult<()> {
         // TODO(@ohsayan): we want to be very careful with this
+        let policy = self.system_store().auth_policy();
+        policy.verify_password(&password)?;
         let _username = username.clone();
         let mut auth = self.system_store().auth_data().write();
         match auth.users.entry(username.into()) {
             Entry::Vacant(ve) => {
-                ve.insert(SysAuthUser::new(
-                    rcrypt::hash(password, rcrypt::DEFAULT_COST)
-                        .unwrap()
-                        .into_boxed_slice(),
-                ));
+                ve.insert(policy.new_user(&password));
                 self._try_sync_or(&mut auth, |auth| {
                     auth.users.remove(_username.as_str());
                 })
@@ -183,23 +446,151 @@ impl<Fs: RawFSInterface> SystemStore<Fs> {
             Entry::Occupied(_) => Err(QueryError::SysAuthError),
         }
     }
+    /// Change the password for the given user
+    ///
+    /// The root password can only be changed if the root key is just used to bootstrap the root account (otherwise,
+    /// it is reset to the configured key on every startup). Changing it lifts the DDL restriction on a bootstrapped
+    /// root account
     pub fn alter_user(&self, username: String, password: String) -> QueryResult<()> {
+        let policy = self.system_store().auth_policy();
+        let is_root = username == SysAuthUser::USER_ROOT;
+        if is_root & !policy.root_rotate {
+            // the root password can only be changed by shutting down the server
+            return Err(QueryError::SysAuthError);
+        }
+        if is_root {
+            // the root password isn't subject to the (non-root) password policy
+            if password.len() < ROOT_PASSWORD_MIN_LEN {
+                return Err(QueryError::SysAuthPasswordPolicy);
+            }
+        } else {
+            policy.verify_password(&password)?;
+        }
         let mut auth = self.system_store().auth_data().write();
+        let last_rotate = auth.root_must_rotate;
+        auth.root_must_rotate &= !is_root;
         match auth.users.get_mut(username.as_str()) {
             Some(user) => {
-                let last_pass_hash = core::mem::replace(
-                    &mut user.key,
-                    rcrypt::hash(password, rcrypt::DEFAULT_COST)
-                        .unwrap()
-                        .into_boxed_slice(),
-                );
+                let last_key = core::mem::replace(&mut user.key, policy.hash(&password));
+                let last_cost = core::mem::replace(&mut user.cost, policy.hash_cost());
                 self._try_sync_or(&mut auth, |auth| {
-                    auth.users.get_mut(username.as_str()).unwrap().key = last_pass_hash;
+                    let user = auth.users.get_mut(username.as_str()).unwrap();
+                    user.key = last_key;
+                    user.cost = last_cost;
+                    auth.root_must_rotate = last_rotate;
                 })
             }
             None => Err(QueryError::SysAuthError),
         }
     }
+    /// Verify the user with the given details, returning true if this is the root account
+    ///
+    /// If the user's key was hashed with a cost other than the configured one, it is transparently rehashed
+    pub fn verify_user_check_root(&self, username: &str, password: &[u8]) -> QueryResult<bool> {
+        let hash_cost = self.system_store().auth_policy().hash_cost();
+        let auth = self.system_store().auth_data().read();
+        let is_root = auth.verify_user_check_root(username, password)?;
+        let user = &auth.users[username];
+        if user.cost != hash_cost {
+            let verified_key = user.key.clone();
+            drop(auth);
+            self.rehash_user(username, password, verified_key);
+        }
+        Ok(is_root)
+    }
+    fn rehash_user(&self, username: &str, password: &[u8], verified_key: Box<[u8]>) {
+        let policy = self.system_store().auth_policy();
+        // hashing is slow; don't hold the lock while we do it
+        let new_key = policy.hash(password);
+        let mut auth = self.system_store().auth_data().write();
+        match auth.users.get_mut(username) {
+            Some(user) if user.key == verified_key => {
+                let last_key = core::mem::replace(&mut user.key, new_key);
+                let last_cost = core::mem::replace(&mut user.cost, policy.hash_cost());
+                let r = self._try_sync_or(&mut auth, |auth| {
+                    let user = auth.users.get_mut(username).unwrap();
+                    user.key = last_key;
+                    user.cost = last_cost;
+                });
+                if r.is_ok() {
+                    info!("rehashed the password for user `{username}`");
+                }
+            }
+            _ => {
+                // the user was altered or dropped while we were hashing
+            }
+        }
+    }
+    /// Grant the user access to the given space (or to a single model in it, if one is provided)
+    ///
+    /// A user that has never been granted anything can access every model. The first grant restricts the user
+    /// to only what has been granted
+    pub fn grant_user(&self, username: &str, space: &str, model: Option<&str>) -> QueryResult<()> {
+        let mut auth = self.system_store().auth_data().write();
+        match auth.users.get_mut(username) {
+            Some(user) if username != SysAuthUser::USER_ROOT => {
+                let prev_grants = user.grants.clone();
+                if !user
+                    .grants
+                    .get_or_insert_with(Default::default)
+                    .grant(space, model)
+                {
+                    // already granted
+                    return Err(QueryError::SysAuthError);
+                }
+                self._try_sync_or(&mut auth, |auth| {
+                    auth.users.get_mut(username).unwrap().grants = prev_grants;
+                })
+            }
+            Some(_) | None => Err(QueryError::SysAuthError),
+        }
+    }
+    /// Revoke a grant previously given to this user
+    ///
+    /// Revoking the last grant does **not** lift the restriction; the user simply can't access any model anymore
+    pub fn revoke_user(&self, username: &str, space: &str, model: Option<&str>) -> QueryResult<()> {
+        let mut auth = self.system_store().auth_data().write();
+        let revoked = match auth.users.get_mut(username) {
+            Some(SysAuthUser {
+                grants: Some(grants),
+                ..
+            }) => grants.revoke(space, model),
+            Some(_) | None => false,
+        };
+        if !revoked {
+            return Err(QueryError::SysAuthError);
+        }
+        self._try_sync_or(&mut auth, |auth| {
+            let user = auth.users.get_mut(username).unwrap();
+            let _ = user.grants.as_mut().unwrap().grant(space, model);
+        })
+    }
+    /// Change the resource quota for the given user. Only the limits that are provided are changed, and a limit of `0`
+    /// removes it
+    ///
+    /// The root account can't be limited
+    pub fn set_user_quota(
+        &self,
+        username: &str,
+        queries_per_sec: Option<u64>,
+        result_bytes: Option<u64>,
+        connections: Option<u64>,
+    ) -> QueryResult<()> {
+        let mut auth = self.system_store().auth_data().write();
+        match auth.users.get_mut(username) {
+            Some(user) if username != SysAuthUser::USER_ROOT => {
+                let prev_quota = user.quota;
+                let quota = &mut user.quota;
+                quota.queries_per_sec = queries_per_sec.unwrap_or(quota.queries_per_sec);
+                quota.result_bytes = result_bytes.unwrap_or(quota.result_bytes);
+                quota.connections = connections.unwrap_or(quota.connections);
+                self._try_sync_or(&mut auth, |auth| {
+                    auth.users.get_mut(username).unwrap().quota = prev_quota;
+                })
+            }
+            Some(_) | None => Err(QueryError::SysAuthError),
+        }
+    }
     pub fn drop_user(&self, username: &str) -> QueryResult<()> {
         let mut auth = self.system_store().auth_data().write();
         if username == SysAuthUser::USER_ROOT {
@@ -223,12 +614,26 @@ impl<Fs: RawFSInterface> SystemStore<Fs> {
 /// The auth data section (system.auth)
 pub struct SysAuth {
     users: HashMap<Box<str>, SysAuthUser>,
+    /// the root key was bootstrapped from the configuration, and has to be changed before any DDL can be run
+    root_must_rotate: bool,
 }
 
 impl SysAuth {
     /// New [`SysAuth`] with the given settings
     pub fn new(users: HashMap<Box<str>, SysAuthUser>) -> Self {
-        Self { users }
+        Self {
+            users,
+            root_must_rotate: false,
+        }
+    }
+    /// Require the bootstrapped root key to be changed before any DDL can be run
+    pub fn with_root_must_rotate(mut self, root_must_rotate: bool) -> Self {
+        self.root_must_rotate = root_must_rotate;
+        self
+    }
+    /// Returns true if the root key has to be changed before any DDL can be run
+    pub fn root_must_rotate(&self) -> bool {
+        self.root_must_rotate
     }
     pub fn verify_user_check_root<T: AsRef<[u8]> + ?Sized>(
         &self,
@@ -253,22 +658,266 @@ impl SysAuth {
     pub fn users(&self) -> &HashMap<Box<str>, SysAuthUser> {
         &self.users
     }
+    /// Check if the user can access the given model (or manage models in the given space, if no model is provided)
+    pub fn verify_user_access(
+        &self,
+        username: &str,
+        space: &str,
+        model: Option<&str>,
+    ) -> QueryResult<()> {
+        match self.users.get(username) {
+            Some(SysAuthUser { grants: None, .. }) => Ok(()),
+            Some(SysAuthUser {
+                grants: Some(grants),
+                ..
+            }) if grants.allows(space, model) => Ok(()),
+            Some(_) | None => Err(QueryError::SysPermissionDenied),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
 /// The auth user
 pub struct SysAuthUser {
     key: Box<[u8]>,
+    cost: u32,
+    grants: Option<SysAuthGrants>,
+    quota: SysAuthQuota,
 }
 
 impl SysAuthUser {
     pub const USER_ROOT: &'static str = "root";
-    /// Create a new [`SysAuthUser`]
-    pub fn new(key: Box<[u8]>) -> Self {
-        Self { key }
+    /// Create a new [`SysAuthUser`] (with no restrictions)
+    pub fn new(key: Box<[u8]>, cost: u32) -> Self {
+        Self::new_full(key, cost, None)
+    }
+    /// Create a new [`SysAuthUser`] that is restricted to the given grants (if any)
+    pub fn new_full(key: Box<[u8]>, cost: u32, grants: Option<SysAuthGrants>) -> Self {
+        Self {
+            key,
+            cost,
+            grants,
+            quota: SysAuthQuota::default(),
+        }
+    }
+    /// Limit the resources that this user can use
+    pub fn with_quota(mut self, quota: SysAuthQuota) -> Self {
+        self.quota = quota;
+        self
     }
     /// Get the key
     pub fn key(&self) -> &[u8] {
         self.key.as_ref()
     }
+    /// Get the cost that the key was hashed with
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+    /// Get the grants for this user. If this is [`None`], then the user is not restricted to any space or model
+    pub fn grants(&self) -> Option<&SysAuthGrants> {
+        self.grants.as_ref()
+    }
+    /// Get the resource quota for this user
+    pub fn quota(&self) -> SysAuthQuota {
+        self.quota
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+/// The resources that a user can use. A limit of `0` means that there is no limit
+pub struct SysAuthQuota {
+    queries_per_sec: u64,
+    result_bytes: u64,
+    connections: u64,
+}
+
+impl SysAuthQuota {
+    pub const fn new(queries_per_sec: u64, result_bytes: u64, connections: u64) -> Self {
+        Self {
+            queries_per_sec,
+            result_bytes,
+            connections,
+        }
+    }
+    /// The number of queries that the user can run every second (across all their connections)
+    pub fn queries_per_sec(&self) -> u64 {
+        self.queries_per_sec
+    }
+    /// The size (in bytes) of the largest response that a query run by the user can return
+    pub fn result_bytes(&self) -> u64 {
+        self.result_bytes
+    }
+    /// The number of connections that the user can have open at the same time
+    pub fn connections(&self) -> u64 {
+        self.connections
+    }
+    /// Returns true if no limit is set
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+    /// Returns true if a response of `size` bytes is larger than what the user can receive
+    pub fn exceeds_result_bytes(&self, size: usize) -> bool {
+        (self.result_bytes != 0) & (size as u64 > self.result_bytes)
+    }
+}
+
+/// The resources that every user is currently using. This is only tracked in memory, so the rate limits start afresh
+/// after a restart
+#[derive(Debug, Default)]
+pub struct SysQuotaUsage {
+    users: Mutex<HashMap<Box<str>, SysUserUsage>>,
+}
+
+#[derive(Debug)]
+struct SysUserUsage {
+    connections: u64,
+    /// the start of the current one second window
+    window: Instant,
+    /// the number of queries run in the current window
+    queries: u64,
+}
+
+impl SysQuotaUsage {
+    const WINDOW: Duration = Duration::from_secs(1);
+    /// Count a new connection for the user, returning [`None`] if the user already has as many connections open as
+    /// they're allowed. The connection is counted until the returned guard is dropped
+    pub fn connect(&self, username: &str, quota: SysAuthQuota) -> Option<SysConnectionGuard<'_>> {
+        let mut users = self.users.lock();
+        let usage = users
+            .entry(username.into())
+            .or_insert_with(|| SysUserUsage {
+                connections: 0,
+                window: Instant::now(),
+                queries: 0,
+            });
+        if (quota.connections != 0) & (usage.connections >= quota.connections) {
+            return None;
+        }
+        usage.connections += 1;
+        Some(SysConnectionGuard {
+            usage: self,
+            username: username.into(),
+        })
+    }
+    /// Count a query run by the user, failing with [`QueryError::SysQuotaQueryRate`] if the user has already run as
+    /// many queries as they can in this second
+    pub fn run_query(&self, username: &str, quota: SysAuthQuota) -> QueryResult<()> {
+        self.run_query_at(username, quota, Instant::now())
+    }
+    /// Same as [`Self::run_query`], but as if it was the given time
+    pub fn run_query_at(
+        &self,
+        username: &str,
+        quota: SysAuthQuota,
+        now: Instant,
+    ) -> QueryResult<()> {
+        if quota.queries_per_sec == 0 {
+            return Ok(());
+        }
+        let mut users = self.users.lock();
+        let Some(usage) = users.get_mut(username) else {
+            // not connected (only in tests)
+            return Ok(());
+        };
+        if now.saturating_duration_since(usage.window) >= Self::WINDOW {
+            usage.window = now;
+            usage.queries = 0;
+        }
+        if usage.queries >= quota.queries_per_sec {
+            return Err(QueryError::SysQuotaQueryRate);
+        }
+        usage.queries += 1;
+        Ok(())
+    }
+    /// Returns the number of connections that the user has open
+    #[allow(unused)]
+    pub fn connections(&self, username: &str) -> u64 {
+        self.users
+            .lock()
+            .get(username)
+            .map_or(0, |usage| usage.connections)
+    }
+}
+
+/// A connection that is counted against a user's quota (until this is dropped)
+#[derive(Debug)]
+pub struct SysConnectionGuard<'a> {
+    usage: &'a SysQuotaUsage,
+    username: Box<str>,
+}
+
+impl<'a> Drop for SysConnectionGuard<'a> {
+    fn drop(&mut self) {
+        let mut users = self.usage.users.lock();
+        if let Entry::Occupied(mut usage) = users.entry(self.username.clone()) {
+            usage.get_mut().connections -= 1;
+            if usage.get().connections == 0 {
+                // no connections, so no queries either
+                usage.remove();
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+/// The spaces and models that a restricted user can access
+pub struct SysAuthGrants {
+    spaces: HashSet<Box<str>>,
+    models: HashMap<Box<str>, HashSet<Box<str>>>,
+}
+
+impl SysAuthGrants {
+    /// Add a grant, returning false if it was already present
+    pub fn grant(&mut self, space: &str, model: Option<&str>) -> bool {
+        match model {
+            Some(model) => match self.models.get_mut(space) {
+                Some(models) => models.insert(model.into()),
+                None => {
+                    self.models
+                        .insert(space.into(), HashSet::from([model.into()]));
+                    true
+                }
+            },
+            None => self.spaces.insert(space.into()),
+        }
+    }
+    /// Remove a grant, returning false if it wasn't present
+    pub fn revoke(&mut self, space: &str, model: Option<&str>) -> bool {
+        match model {
+            Some(model) => match self.models.get_mut(space) {
+                Some(models) => {
+                    let removed = models.remove(model);
+                    if models.is_empty() {
+                        self.models.remove(space);
+                    }
+                    removed
+                }
+                None => false,
+            },
+            None => self.spaces.remove(space),
+        }
+    }
+    /// Check if these grants allow access to the model (or to the whole space, if no model is provided)
+    ///
+    /// A space grant allows access to every model in the space
+    pub fn allows(&self, space: &str, model: Option<&str>) -> bool {
+        self.spaces.contains(space)
+            | model.is_some_and(|model| {
+                self.models
+                    .get(space)
+                    .is_some_and(|models| models.contains(model))
+            })
+    }
+    /// Returns an iterator over the space grants
+    pub fn spaces(&self) -> impl Iterator<Item = &str> {
+        self.spaces.iter().map(|space| space.as_ref())
+    }
+    /// Returns an iterator over the model grants as `(space, model)`
+    pub fn models(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.models.iter().flat_map(|(space, models)| {
+            models
+                .iter()
+                .map(move |model| (space.as_ref(), model.as_ref()))
+        })
+    }
 }
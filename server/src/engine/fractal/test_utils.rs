@@ -26,23 +26,28 @@
 
 use {
     super::{
+        queries::RunningQueries,
         sys_store::{SysConfig, SystemStore},
-        CriticalTask, FractalModelDriver, GenericTask, GlobalInstanceLike, ModelUniqueID, Task,
+        Backpressure, CriticalTask, FractalModelDriver, GenericTask, GlobalInstanceLike,
+        MemoryLimits, ModelDrivers, ModelUniqueID, Task,
     },
     crate::engine::{
-        core::GlobalNS,
+        core::{index::RowCacheBudget, GlobalNS},
         data::uuid::Uuid,
+        repl::ReplicationRole,
         storage::{
             self,
+            engine::StorageEngineKind,
             v1::{
                 memfs::{NullFS, VirtualFS},
+                spec::BatchCompression,
                 RawFSInterface,
             },
         },
         txn::gns::GNSTransactionDriverAnyFS,
     },
     parking_lot::{Mutex, RwLock},
-    std::collections::HashMap,
+    std::sync::Arc,
 };
 
 /// A `test` mode global implementation
@@ -53,8 +58,14 @@ pub struct TestGlobal<Fs: RawFSInterface = VirtualFS> {
     #[allow(unused)]
     max_delta_size: usize,
     txn_driver: Mutex<GNSTransactionDriverAnyFS<Fs>>,
-    model_drivers: RwLock<HashMap<ModelUniqueID, FractalModelDriver<Fs>>>,
+    model_drivers: RwLock<ModelDrivers>,
     sys_cfg: SystemStore<Fs>,
+    log_name: Box<str>,
+    replication_role: ReplicationRole,
+    backpressure: Backpressure,
+    row_cache: Arc<RowCacheBudget>,
+    memory: MemoryLimits,
+    queries: RunningQueries,
 }
 
 impl<Fs: RawFSInterface> TestGlobal<Fs> {
@@ -62,6 +73,7 @@ impl<Fs: RawFSInterface> TestGlobal<Fs> {
         gns: GlobalNS,
         max_delta_size: usize,
         txn_driver: GNSTransactionDriverAnyFS<Fs>,
+        log_name: &str,
     ) -> Self {
         Self {
             gns,
@@ -71,8 +83,46 @@ impl<Fs: RawFSInterface> TestGlobal<Fs> {
             txn_driver: Mutex::new(txn_driver),
             model_drivers: RwLock::default(),
             sys_cfg: SystemStore::_new(SysConfig::test_default()),
+            log_name: log_name.into(),
+            replication_role: ReplicationRole::new(false),
+            backpressure: Backpressure::disabled(),
+            row_cache: Arc::new(RowCacheBudget::disabled()),
+            memory: MemoryLimits::disabled(),
+            queries: RunningQueries::new(None),
         }
     }
+    /// Start as a (read-only) replica
+    pub fn with_replica(mut self) -> Self {
+        self.replication_role = ReplicationRole::new(true);
+        self
+    }
+    /// Hold back writes to models with too many unflushed deltas
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+    /// Cache hot rows within the given memory budget (in bytes)
+    pub fn with_row_cache(mut self, limit: usize) -> Self {
+        self.row_cache = Arc::new(RowCacheBudget::new(limit));
+        self
+    }
+    /// Reject writes to models (or to the database) that use too much memory
+    pub fn with_memory_limits(mut self, memory: MemoryLimits) -> Self {
+        self.memory = memory;
+        self
+    }
+    /// Allow models to be exported into the given directory
+    pub fn with_export_dir(mut self, dir: &str) -> Self {
+        self.sys_cfg.export_dir = Some(dir.into());
+        self
+    }
+    /// Flush and close the batch journals of all models, just like we do on shutdown
+    pub fn close_all_models(&self) -> bool {
+        super::mgr::FractalMgr::close_all(
+            &self.gns,
+            core::mem::take(&mut *self.model_drivers.write()),
+        )
+    }
 }
 
 impl<Fs: RawFSInterface> TestGlobal<Fs> {
@@ -81,7 +131,7 @@ impl<Fs: RawFSInterface> TestGlobal<Fs> {
         let driver = storage::v1::loader::open_gns_driver(log_name, &gns)
             .unwrap()
             .into_inner();
-        Self::new(gns, 0, GNSTransactionDriverAnyFS::new(driver))
+        Self::new(gns, 0, GNSTransactionDriverAnyFS::new(driver), log_name)
     }
 }
 
@@ -120,6 +170,47 @@ impl<Fs: RawFSInterface> GlobalInstanceLike for TestGlobal<Fs> {
     fn sys_store(&self) -> &SystemStore<Fs> {
         &self.sys_cfg
     }
+    fn backup_to(&self, path: &str) -> crate::engine::error::RuntimeResult<()> {
+        super::mgr::FractalMgr::backup_all(
+            &self.gns,
+            &self.txn_driver,
+            &self.log_name,
+            &self.model_drivers,
+            &self.sys_cfg,
+            path,
+        )
+    }
+    fn replication_role(&self) -> &ReplicationRole {
+        &self.replication_role
+    }
+    fn promote(&self) -> crate::engine::error::RuntimeResult<bool> {
+        self.replication_role.promote(|| {
+            super::mgr::FractalMgr::rotate_all(
+                &self.gns,
+                &self.model_drivers,
+                BatchCompression::None,
+                None,
+            )
+        })
+    }
+    fn request_shutdown(&self) {
+        // there's no server to shut down
+    }
+    fn backpressure(&self) -> &Backpressure {
+        &self.backpressure
+    }
+    fn row_cache(&self) -> &Arc<RowCacheBudget> {
+        &self.row_cache
+    }
+    fn memory(&self) -> &MemoryLimits {
+        &self.memory
+    }
+    fn queries(&self) -> &RunningQueries {
+        &self.queries
+    }
+    fn model_drivers(&self) -> &RwLock<ModelDrivers> {
+        &self.model_drivers
+    }
     fn purge_model_driver(
         &self,
         space_name: &str,
@@ -145,18 +236,25 @@ impl<Fs: RawFSInterface> GlobalInstanceLike for TestGlobal<Fs> {
         space_uuid: Uuid,
         model_name: &str,
         model_uuid: Uuid,
+        ephemeral: bool,
     ) -> crate::engine::error::RuntimeResult<()> {
         // create model dir
         Fs::fs_create_dir(&storage::v1::loader::SEInitState::model_dir(
             space_name, space_uuid, model_name, model_uuid,
         ))?;
-        let driver =
-            storage::v1::data_batch::create(&storage::v1::loader::SEInitState::model_path(
-                space_name, space_uuid, model_name, model_uuid,
-            ))?;
+        let mut storage = StorageEngineKind::Journal
+            .model_engine::<Fs>(ephemeral)
+            .create_model(
+                &storage::v1::loader::SEInitState::model_path(
+                    space_name, space_uuid, model_name, model_uuid,
+                ),
+                BatchCompression::None,
+                None,
+            )?;
+        storage.set_durability(self.sys_cfg.system_store().durability());
         self.model_drivers.write().insert(
             ModelUniqueID::new(space_name, model_name, model_uuid),
-            FractalModelDriver::init(driver),
+            FractalModelDriver::init(storage),
         );
         Ok(())
     }
@@ -168,3 +266,7 @@ impl<Fs: RawFSInterface> Drop for TestGlobal<Fs> {
         txn_driver.__journal_mut().__close_mut().unwrap();
     }
 }
+
+// same as the global state: fine, so that tests can run queries from several threads
+unsafe impl<Fs: RawFSInterface> Send for TestGlobal<Fs> {}
+unsafe impl<Fs: RawFSInterface> Sync for TestGlobal<Fs> {}
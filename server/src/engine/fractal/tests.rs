@@ -0,0 +1,171 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{
+        blocking::BlockingPool,
+        queries::{self, RunningQueries},
+        CriticalTask, ModelUniqueID, Task,
+    },
+    crate::engine::{data::uuid::Uuid, error::QueryError},
+    std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+#[test]
+fn coalesce_batch_writes() {
+    let (users, orders) = (Uuid::new(), Uuid::new());
+    let write = |model: &str, uuid: Uuid, observed_size: usize| {
+        Task::new(CriticalTask::WriteBatch(
+            ModelUniqueID::new("myspace", model, uuid),
+            observed_size,
+        ))
+    };
+    assert_eq!(
+        Task::coalesce(vec![
+            write("users", users, 10),
+            write("orders", orders, 5),
+            write("users", users, 7),
+            write("users", users, 3),
+        ]),
+        vec![write("users", users, 20), write("orders", orders, 5)]
+    );
+    // a model that was dropped and recreated is a different model
+    let new_users = Uuid::new();
+    assert_eq!(
+        Task::coalesce(vec![write("users", users, 1), write("users", new_users, 2)]),
+        vec![write("users", users, 1), write("users", new_users, 2)]
+    );
+    assert_eq!(Task::coalesce(vec![]), vec![]);
+}
+
+#[test]
+fn coalesce_keeps_highest_threshold() {
+    let users = ModelUniqueID::new("myspace", "users", Uuid::new());
+    let fresh =
+        |observed_size: usize| Task::new(CriticalTask::WriteBatch(users.clone(), observed_size));
+    let retried = |observed_size: usize, threshold: usize| {
+        Task::with_threshold(
+            CriticalTask::WriteBatch(users.clone(), observed_size),
+            threshold,
+        )
+    };
+    // a write that was already retried doesn't cut short the retries of a fresh one (in either order)
+    assert_eq!(
+        Task::coalesce(vec![retried(2, 1), fresh(3)]),
+        vec![fresh(5)]
+    );
+    assert_eq!(
+        Task::coalesce(vec![fresh(3), retried(2, 1)]),
+        vec![fresh(5)]
+    );
+    assert_eq!(
+        Task::coalesce(vec![retried(1, 4), retried(1, 2)]),
+        vec![retried(2, 4)]
+    );
+}
+
+#[test]
+fn running_queries() {
+    let queries = RunningQueries::new(None);
+    let q1 = queries.register("root", "select * from myspace.users");
+    let q2 = queries.register("sayan", "select all * from myspace.users");
+    assert_ne!(q1.id(), q2.id());
+    let ids = |user| -> Vec<u64> { queries.list(user).iter().map(|q| q.id()).collect() };
+    assert_eq!(ids(None), [q1.id(), q2.id()]);
+    assert_eq!(ids(Some("sayan")), [q2.id()]);
+    // users can only kill their own queries
+    assert_eq!(
+        queries.kill(q1.id(), Some("sayan")),
+        Err(QueryError::QExecObjectNotFound)
+    );
+    assert!(!q1.is_killed());
+    queries.kill(q2.id(), Some("sayan")).unwrap();
+    queries.kill(q1.id(), None).unwrap();
+    assert!(q1.is_killed() & q2.is_killed());
+    // queries are unregistered once they complete
+    let id = q1.id();
+    drop(q1);
+    assert_eq!(ids(None), [q2.id()]);
+    assert_eq!(queries.kill(id, None), Err(QueryError::QExecObjectNotFound));
+}
+
+#[test]
+fn running_query_check() {
+    let queries = RunningQueries::new(Some(1000));
+    // nothing is being run on this thread
+    assert_eq!(queries::check(), Ok(()));
+    let query = queries.register("root", "select all * from myspace.users");
+    query.scope(|| {
+        assert_eq!(queries::check(), Ok(()));
+        queries.kill(query.id(), None).unwrap();
+        assert_eq!(queries::check(), Err(QueryError::SysQueryKilled));
+    });
+    assert_eq!(queries::check(), Ok(()));
+    let started = Instant::now() - Duration::from_secs(2);
+    let query = queries.register_at("root", "select all * from myspace.users", started);
+    query.scope(|| assert_eq!(queries::check(), Err(QueryError::SysQueryTimeout)));
+    // scopes nest
+    let running = queries.register("root", "sysctl report queries");
+    running.scope(|| {
+        query.scope(|| assert_eq!(queries::check(), Err(QueryError::SysQueryTimeout)));
+        assert_eq!(queries::check(), Ok(()));
+    });
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn blocking_pool_limits_running_tasks() {
+    let pool = Arc::new(BlockingPool::new(Some(2)));
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let tasks: Vec<_> = (0..8)
+        .map(|i| {
+            let (pool, running, max_running) = (pool.clone(), running.clone(), max_running.clone());
+            tokio::spawn(async move {
+                pool.run(move || {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    i
+                })
+                .await
+            })
+        })
+        .collect();
+    let mut results = vec![];
+    for task in tasks {
+        results.push(task.await.unwrap());
+    }
+    assert_eq!(results, (0..8).collect::<Vec<_>>());
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+}
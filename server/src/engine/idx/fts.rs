@@ -0,0 +1,172 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    full-text index
+
+    an inverted index (word -> the keys of the documents that have the word) along with a forward index (key ->
+    the words of the document) so that a document can be reindexed (or removed) with just its key. words are the
+    runs of alphanumeric characters in the text, lowercased
+*/
+
+use {
+    super::AsKeyClone,
+    parking_lot::RwLock,
+    std::collections::{HashMap, HashSet},
+};
+
+/// Returns the (unique) words in the text, in sorted order
+pub fn tokenize(text: &str) -> Vec<Box<str>> {
+    let mut words: Vec<Box<str>> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase().into_boxed_str())
+        .collect();
+    words.sort_unstable();
+    words.dedup();
+    words
+}
+
+/// Returns true if the text has all the given words (as returned by [`tokenize`])
+pub fn contains_all(text: &str, words: &[Box<str>]) -> bool {
+    let text = tokenize(text);
+    words.iter().all(|word| text.binary_search(word).is_ok())
+}
+
+#[derive(Debug)]
+pub struct TextIndex<K> {
+    data: RwLock<TextIndexData<K>>,
+}
+
+#[derive(Debug)]
+struct TextIndexData<K> {
+    postings: HashMap<Box<str>, HashSet<K>>,
+    docs: HashMap<K, Box<[Box<str>]>>,
+}
+
+impl<K: AsKeyClone> TextIndex<K> {
+    pub fn new() -> Self {
+        Self {
+            data: RwLock::new(TextIndexData {
+                postings: HashMap::new(),
+                docs: HashMap::new(),
+            }),
+        }
+    }
+    /// Index the text of the document with the given key, replacing whatever was indexed for it before. If there's
+    /// no text (or it has no words), the document is removed from the index
+    pub fn index(&self, key: &K, text: Option<&str>) {
+        let words = text.map(tokenize).unwrap_or_default();
+        let mut data = self.data.write();
+        if data
+            .docs
+            .get(key)
+            .is_some_and(|current| **current == *words)
+        {
+            return;
+        }
+        data.unlink(key);
+        if words.is_empty() {
+            return;
+        }
+        for word in words.iter() {
+            data.postings
+                .entry(word.clone())
+                .or_default()
+                .insert(key.clone());
+        }
+        data.docs.insert(key.clone(), words.into_boxed_slice());
+    }
+    /// Remove the document with the given key from the index
+    pub fn remove(&self, key: &K) {
+        self.data.write().unlink(key)
+    }
+    /// Remove all documents from the index
+    pub fn clear(&self) {
+        let mut data = self.data.write();
+        data.postings.clear();
+        data.docs.clear();
+    }
+    /// Returns the keys of the documents that have all the given words (as returned by [`tokenize`]), in no
+    /// particular order
+    pub fn search(&self, words: &[Box<str>]) -> Vec<K> {
+        let data = self.data.read();
+        let mut postings = Vec::with_capacity(words.len());
+        for word in words {
+            match data.postings.get(word) {
+                Some(keys) => postings.push(keys),
+                None => return vec![],
+            }
+        }
+        // start with the rarest word so that we check as few keys as possible
+        postings.sort_unstable_by_key(|keys| keys.len());
+        let Some((rarest, rest)) = postings.split_first() else {
+            return vec![];
+        };
+        rarest
+            .iter()
+            .filter(|key| rest.iter().all(|keys| keys.contains(*key)))
+            .cloned()
+            .collect()
+    }
+    /// Returns the most documents that a search for the given words could return (the number of documents with the
+    /// rarest of the words)
+    pub fn estimate(&self, words: &[Box<str>]) -> usize {
+        let data = self.data.read();
+        words
+            .iter()
+            .map(|word| data.postings.get(word).map_or(0, HashSet::len))
+            .min()
+            .unwrap_or(0)
+    }
+    #[cfg(test)]
+    /// Returns the number of documents in the index
+    pub fn len(&self) -> usize {
+        self.data.read().docs.len()
+    }
+}
+
+impl<K: AsKeyClone> Default for TextIndex<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: AsKeyClone> TextIndexData<K> {
+    fn unlink(&mut self, key: &K) {
+        let Some(words) = self.docs.remove(key) else {
+            return;
+        };
+        for word in words.iter() {
+            if let Some(keys) = self.postings.get_mut(word) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.postings.remove(word);
+                }
+            }
+        }
+    }
+}
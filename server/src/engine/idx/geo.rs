@@ -0,0 +1,256 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    geo index
+
+    points are (latitude, longitude) pairs in degrees. the index keys every point by its geohash: the bits of the
+    longitude and latitude (as fractions of their ranges) interleaved, longitude first. points that are close to each
+    other share a prefix, so all the points in a geohash cell of any size are a range of (full) geohashes, and a
+    circle is found by scanning the few cells that cover it (and then checking the distance of every point in them)
+*/
+
+use {
+    super::AsKeyClone,
+    parking_lot::RwLock,
+    std::collections::{BTreeMap, HashMap},
+};
+
+/// The mean radius of the earth, in meters
+const EARTH_RADIUS: f64 = 6_371_008.8;
+/// The number of bits of the latitude (and of the longitude) in a geohash
+const AXIS_BITS: u32 = 26;
+/// The most cells that a circle is covered with. Larger cells (fewer bits) are used until the circle fits
+const MAX_COVER_CELLS: u64 = 16;
+
+/// Returns true if the latitude and longitude are in range
+pub fn is_valid_point((lat, lon): (f64, f64)) -> bool {
+    (-90.0..=90.0).contains(&lat) & (-180.0..=180.0).contains(&lon)
+}
+
+/// Parse a point written as `"lat,lon"` (in degrees)
+pub fn parse_point(point: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = point.split_once(',')?;
+    let point = (lat.trim().parse().ok()?, lon.trim().parse().ok()?);
+    is_valid_point(point).then_some(point)
+}
+
+/// Returns the (great-circle) distance between the points, in meters
+pub fn distance((lat_a, lon_a): (f64, f64), (lat_b, lon_b): (f64, f64)) -> f64 {
+    let (lat_a, lat_b) = (lat_a.to_radians(), lat_b.to_radians());
+    let dlat = lat_b - lat_a;
+    let dlon = (lon_b - lon_a).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+}
+
+/// Returns the cell (along one axis) that the value falls in, if the axis is split into `2^bits` cells
+fn axis_cell(value: f64, min: f64, span: f64, bits: u32) -> u64 {
+    let cells = 1u64 << bits;
+    (((value - min) / span * cells as f64) as u64).min(cells - 1)
+}
+
+/// Interleave the bits of the longitude and latitude cells, longitude first
+fn interleave(lon: u64, lat: u64, bits: u32) -> u64 {
+    (0..bits).rev().fold(0, |hash, bit| {
+        (hash << 2) | (((lon >> bit) & 1) << 1) | ((lat >> bit) & 1)
+    })
+}
+
+/// Returns the (full) geohash of the point
+pub fn geohash((lat, lon): (f64, f64)) -> u64 {
+    interleave(
+        axis_cell(lon, -180.0, 360.0, AXIS_BITS),
+        axis_cell(lat, -90.0, 180.0, AXIS_BITS),
+        AXIS_BITS,
+    )
+}
+
+/// Returns the ranges of geohashes (`start..end`) of the cells that cover the circle. The cells are the smallest ones
+/// that cover the circle with at most [`MAX_COVER_CELLS`] cells
+pub fn cover((lat, lon): (f64, f64), radius: f64) -> Vec<(u64, u64)> {
+    let angle = radius / EARTH_RADIUS;
+    let dlat = angle.to_degrees();
+    let lat_range = ((lat - dlat).max(-90.0), (lat + dlat).min(90.0));
+    // the longitudes of the circle's bounding box, which could wrap around the antimeridian
+    let ratio = angle.sin() / lat.to_radians().cos();
+    let lon_ranges = if (lat_range.0 <= -90.0) | (lat_range.1 >= 90.0) | (ratio >= 1.0) {
+        // it has a pole in it (or it's too big), so every longitude is in it
+        vec![(-180.0, 180.0)]
+    } else {
+        let dlon = ratio.asin().to_degrees();
+        let (start, end) = (lon - dlon, lon + dlon);
+        if start < -180.0 {
+            vec![(start + 360.0, 180.0), (-180.0, end)]
+        } else if end > 180.0 {
+            vec![(start, 180.0), (-180.0, end - 360.0)]
+        } else {
+            vec![(start, end)]
+        }
+    };
+    let cells = |bits| {
+        let lat_cells = (
+            axis_cell(lat_range.0, -90.0, 180.0, bits),
+            axis_cell(lat_range.1, -90.0, 180.0, bits),
+        );
+        let lon_cells: Vec<(u64, u64)> = lon_ranges
+            .iter()
+            .map(|(start, end)| {
+                (
+                    axis_cell(*start, -180.0, 360.0, bits),
+                    axis_cell(*end, -180.0, 360.0, bits),
+                )
+            })
+            .collect();
+        (lat_cells, lon_cells)
+    };
+    let count = |((lat_start, lat_end), lon_cells): &((u64, u64), Vec<(u64, u64)>)| {
+        (lat_end - lat_start + 1)
+            * lon_cells
+                .iter()
+                .map(|(start, end)| end - start + 1)
+                .sum::<u64>()
+    };
+    // with no bits there's only one cell, so this always finds a size
+    let bits = (0..=AXIS_BITS)
+        .rev()
+        .find(|bits| count(&cells(*bits)) <= MAX_COVER_CELLS)
+        .unwrap_or(0);
+    let ((lat_start, lat_end), lon_cells) = cells(bits);
+    let shift = 2 * (AXIS_BITS - bits);
+    let mut ranges = vec![];
+    for (lon_start, lon_end) in lon_cells {
+        for lon in lon_start..=lon_end {
+            for lat in lat_start..=lat_end {
+                let prefix = interleave(lon, lat, bits);
+                ranges.push((prefix << shift, (prefix + 1) << shift));
+            }
+        }
+    }
+    ranges
+}
+
+#[derive(Debug)]
+pub struct GeoIndex<K> {
+    data: RwLock<GeoIndexData<K>>,
+}
+
+#[derive(Debug)]
+struct GeoIndexData<K> {
+    /// geohash -> the keys of the documents with a point in that (smallest) cell
+    cells: BTreeMap<u64, Vec<K>>,
+    docs: HashMap<K, (f64, f64)>,
+}
+
+impl<K: AsKeyClone> GeoIndex<K> {
+    pub fn new() -> Self {
+        Self {
+            data: RwLock::new(GeoIndexData {
+                cells: BTreeMap::new(),
+                docs: HashMap::new(),
+            }),
+        }
+    }
+    /// Index the point of the document with the given key, replacing whatever was indexed for it before. If there's
+    /// no point, the document is removed from the index
+    pub fn index(&self, key: &K, point: Option<(f64, f64)>) {
+        let mut data = self.data.write();
+        if data.docs.get(key) == point.as_ref() {
+            return;
+        }
+        data.unlink(key);
+        if let Some(point) = point {
+            data.cells
+                .entry(geohash(point))
+                .or_default()
+                .push(key.clone());
+            data.docs.insert(key.clone(), point);
+        }
+    }
+    /// Remove the document with the given key from the index
+    pub fn remove(&self, key: &K) {
+        self.data.write().unlink(key)
+    }
+    /// Remove all documents from the index
+    pub fn clear(&self) {
+        let mut data = self.data.write();
+        data.cells.clear();
+        data.docs.clear();
+    }
+    /// Returns the keys of the documents with points that are at most `radius` meters from the center, in no
+    /// particular order
+    pub fn search(&self, center: (f64, f64), radius: f64) -> Vec<K> {
+        let data = self.data.read();
+        let mut keys = vec![];
+        for (start, end) in cover(center, radius) {
+            for key in data.cells.range(start..end).flat_map(|(_, keys)| keys) {
+                if distance(data.docs[key], center) <= radius {
+                    keys.push(key.clone());
+                }
+            }
+        }
+        keys
+    }
+    /// Returns the number of documents in the cells that cover the circle (the most that a search could return)
+    pub fn estimate(&self, center: (f64, f64), radius: f64) -> usize {
+        let data = self.data.read();
+        cover(center, radius)
+            .into_iter()
+            .map(|(start, end)| {
+                data.cells
+                    .range(start..end)
+                    .map(|(_, keys)| keys.len())
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+    #[cfg(test)]
+    /// Returns the number of documents in the index
+    pub fn len(&self) -> usize {
+        self.data.read().docs.len()
+    }
+}
+
+impl<K: AsKeyClone> Default for GeoIndex<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: AsKeyClone> GeoIndexData<K> {
+    fn unlink(&mut self, key: &K) {
+        let Some(point) = self.docs.remove(key) else {
+            return;
+        };
+        let hash = geohash(point);
+        if let Some(keys) = self.cells.get_mut(&hash) {
+            keys.retain(|k| k != key);
+            if keys.is_empty() {
+                self.cells.remove(&hash);
+            }
+        }
+    }
+}
@@ -28,6 +28,7 @@ pub mod hash;
 
 use core::{
     borrow::Borrow,
+    cmp::Ordering,
     hash::{BuildHasher, Hash},
 };
 
@@ -44,16 +45,21 @@ impl<T> AsHasher for T where T: BuildHasher + Default {}
 */
 pub trait Comparable<K: ?Sized>: Hash {
     fn cmp_eq(&self, key: &K) -> bool;
+    /// Order `self` against the key (for ordered indexes). Returns [`None`] if the two are incomparable
+    fn cmp_ord(&self, key: &K) -> Option<Ordering>;
 }
 
 pub trait ComparableUpgradeable<K>: Comparable<K> {
     fn upgrade(&self) -> K;
 }
 
-impl<K: Borrow<T>, T: Eq + Hash + ?Sized> Comparable<K> for T {
+impl<K: Borrow<T>, T: Eq + PartialOrd + Hash + ?Sized> Comparable<K> for T {
     fn cmp_eq(&self, key: &K) -> bool {
         self == key.borrow()
     }
+    fn cmp_ord(&self, key: &K) -> Option<Ordering> {
+        self.partial_cmp(key.borrow())
+    }
 }
 
 impl<K: Hash, T: ToOwned<Owned = K> + Hash + Comparable<K> + ?Sized> ComparableUpgradeable<K>
@@ -26,8 +26,11 @@
 
 #![deny(unreachable_patterns)]
 
+pub mod fts;
+pub mod geo;
 pub mod meta;
 pub mod mtchm;
+mod mtord;
 mod stdhm;
 mod stord;
 #[cfg(test)]
@@ -36,7 +39,7 @@ mod tests;
 use {
     self::meta::Comparable,
     crate::engine::sync::atm::Guard,
-    core::{borrow::Borrow, hash::Hash},
+    core::{borrow::Borrow, hash::Hash, ops::RangeBounds},
 };
 
 pub mod stdord_iter {
@@ -48,6 +51,7 @@ pub type IndexSTSeqCns<K, V> = stord::IndexSTSeqDll<K, V, stord::config::Conserv
 #[cfg(test)]
 pub type IndexSTSeqLib<K, V> = stord::IndexSTSeqDll<K, V, stord::config::LiberalConfig<K, V>>;
 pub type IndexMTRaw<E> = mtchm::imp::Raw<E, mtchm::meta::DefConfig>;
+pub type IndexMTOrd<E> = mtord::SkipList<E>;
 pub type IndexST<K, V, S = std::collections::hash_map::RandomState> =
     std::collections::hash_map::HashMap<K, V, S>;
 
@@ -155,7 +159,15 @@ pub trait MTIndex<E, K, V>: IndexBaseSpec {
     fn mt_iter_val<'t, 'g, 'v>(&'t self, g: &'g Guard) -> Self::IterVal<'t, 'g, 'v>;
     /// Returns the length of the index
     fn mt_len(&self) -> usize;
-    /// Attempts to compact the backing storage
+    /// Returns an estimate of the length of the index that might lag behind concurrent writes. Indexes that can't
+    /// count their entries cheaply should override this
+    fn mt_len_approx(&self) -> usize {
+        self.mt_len()
+    }
+    /// Returns the memory (in bytes) allocated for the index's structure, including the entries themselves but not
+    /// any heap memory owned by them. This has to walk the whole index
+    fn mt_capacity_bytes(&self, g: &Guard) -> usize;
+    /// Attempts to compact the backing storage, giving back any memory that was freed up by deletes
     fn mt_compact(&self) {}
     /// Clears all the entries in the MTIndex
     fn mt_clear(&self, g: &Guard);
@@ -169,6 +181,22 @@ pub trait MTIndex<E, K, V>: IndexBaseSpec {
     fn mt_upsert(&self, e: E, g: &Guard)
     where
         V: AsValue;
+    /// Builds a new index from the given entries in one go. This is meant for loading a large number of entries that
+    /// are known to be unique (say, when restoring data); if a key is repeated, the last entry wins
+    fn mt_bulk_load<I>(entries: I, g: &Guard) -> Self
+    where
+        I: IntoIterator<Item = E>,
+        V: AsValue,
+    {
+        let idx = Self::idx_init();
+        idx.mt_bulk_extend(entries, g);
+        idx
+    }
+    /// Upserts all the given entries. If the index is empty, this is just as fast as [`MTIndex::mt_bulk_load`]
+    fn mt_bulk_extend<I>(&self, entries: I, g: &Guard)
+    where
+        I: IntoIterator<Item = E>,
+        V: AsValue;
     // read
     fn mt_contains<Q>(&self, key: &Q, g: &Guard) -> bool
     where
@@ -229,7 +257,38 @@ pub trait MTIndexExt<E, K, V>: MTIndex<E, K, V> {
         V: 'v,
         E: 'v,
         Self: 't;
+    type IterRange<'t, 'g, 'v, Q, R>: Iterator<Item = &'v E>
+    where
+        'g: 't + 'v,
+        't: 'v,
+        K: 'v + PartialOrd<Q>,
+        V: 'v,
+        E: 'v,
+        R: RangeBounds<Q>,
+        Self: 't;
+    type Entry<'t, 'q, Q>
+    where
+        Q: ?Sized + 'q,
+        E: 't,
+        Self: 't;
     fn mt_iter_entry<'t, 'g, 'v>(&'t self, g: &'g Guard) -> Self::IterEntry<'t, 'g, 'v>;
+    /// Returns the entry for the given key, which can be used to get or insert (and optionally modify) the element in
+    /// one atomic step instead of a lookup followed by an insert
+    fn mt_entry<'t, 'g, 'q, Q>(&'t self, key: &'q Q, g: &'g Guard) -> Self::Entry<'t, 'q, Q>
+    where
+        'g: 't,
+        Q: ?Sized + Comparable<K>;
+    /// Returns an iterator over all the entries with keys that lie in the given range. On an unordered (hash) index
+    /// this is a filtering scan that walks every entry (so it's O(n) no matter how small the range is) and entries are
+    /// **not** returned in key order; ordered indexes seek to the start of the range instead
+    fn mt_range<'t, 'g, 'v, Q, R>(
+        &'t self,
+        range: R,
+        g: &'g Guard,
+    ) -> Self::IterRange<'t, 'g, 'v, Q, R>
+    where
+        K: 'v + PartialOrd<Q>,
+        R: RangeBounds<Q>;
 }
 
 /// An unordered STIndex
@@ -252,6 +311,10 @@ pub trait STIndex<K: ?Sized, V>: IndexBaseSpec {
         V: 'a;
     /// returns the length of the idx
     fn st_len(&self) -> usize;
+    /// Returns the number of entries that the idx can hold without reallocating
+    fn st_capacity(&self) -> usize {
+        self.st_len()
+    }
     /// Attempts to compact the backing storage
     fn st_compact(&mut self) {}
     /// Clears all the entries in the STIndex
@@ -345,8 +408,23 @@ pub trait STIndexSeq<K, V>: STIndex<K, V> {
     type OwnedIterKV: Iterator<Item = (K, V)> + DoubleEndedIterator<Item = (K, V)>;
     type OwnedIterKeys: Iterator<Item = K> + DoubleEndedIterator<Item = K>;
     type OwnedIterValues: Iterator<Item = V> + DoubleEndedIterator<Item = V>;
+    /// An ordered iterator over the KV pairs with keys starting with a given prefix
+    type IterOrdPrefixKV<'a, 'p>: Iterator<Item = (&'a K, &'a V)>
+        + DoubleEndedIterator<Item = (&'a K, &'a V)>
+    where
+        Self: 'a,
+        K: 'a + Borrow<str>,
+        V: 'a;
     /// Returns an ordered iterator over the KV pairs
     fn stseq_ord_kv<'a>(&'a self) -> Self::IterOrdKV<'a>;
+    /// Returns an ordered iterator over the KV pairs at the given positions in the sequence. Bounds past the end of
+    /// the sequence are clamped
+    fn stseq_range<'a>(&'a self, range: impl RangeBounds<usize>) -> Self::IterOrdKV<'a>;
+    /// Returns an ordered iterator over the KV pairs with keys starting with `prefix`. Since the sequence isn't
+    /// sorted by key, this still has to look at every key
+    fn stseq_prefix_kv<'a, 'p>(&'a self, prefix: &'p str) -> Self::IterOrdPrefixKV<'a, 'p>
+    where
+        K: Borrow<str>;
     /// Returns an ordered iterator over the keys
     fn stseq_ord_key<'a>(&'a self) -> Self::IterOrdKey<'a>;
     /// Returns an ordered iterator over the values
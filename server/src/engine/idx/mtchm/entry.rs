@@ -0,0 +1,92 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{
+        meta::{Config, TreeElement},
+        patch::{EntryInsert, EntryUpsert},
+        RawTree,
+    },
+    crate::engine::{idx::meta::Comparable, sync::atm::Guard},
+};
+
+/// A single entry in the tree that may or may not exist. Resolving the entry (with one of the `or_insert` methods) is
+/// a single atomic step, so there's no window between checking for the key and inserting it
+pub struct Entry<'t, 'q, T, C: Config, Q: ?Sized, M = fn(&T) -> T> {
+    t: &'t RawTree<T, C>,
+    key: &'q Q,
+    g: &'t Guard,
+    m: Option<M>,
+}
+
+impl<'t, 'q, T, C: Config, Q: ?Sized> Entry<'t, 'q, T, C, Q> {
+    pub fn new(t: &'t RawTree<T, C>, key: &'q Q, g: &'t Guard) -> Self {
+        Self { t, key, g, m: None }
+    }
+    /// If the element exists, replace it with what `m` returns for it. This only happens once the entry is resolved,
+    /// and `m` might be called more than once if we race with other writers
+    pub fn and_modify<M>(self, m: M) -> Entry<'t, 'q, T, C, Q, M>
+    where
+        M: FnMut(&T) -> T,
+    {
+        Entry {
+            t: self.t,
+            key: self.key,
+            g: self.g,
+            m: Some(m),
+        }
+    }
+}
+
+impl<'t, 'q, T, C, Q, M> Entry<'t, 'q, T, C, Q, M>
+where
+    T: TreeElement,
+    C: Config,
+    Q: ?Sized + Comparable<T::Key>,
+    M: FnMut(&T) -> T,
+{
+    /// Returns the element, if it exists
+    #[allow(unused)]
+    pub fn get(&self) -> Option<&'t T> {
+        self.t.get_full(self.key, self.g)
+    }
+    /// Returns the element (modified, if [`Entry::and_modify`] was used) or inserts the one returned by `f` if the key
+    /// is missing. `f` is called at most once
+    pub fn or_insert_with<F>(self, f: F) -> &'t T
+    where
+        F: FnOnce() -> T,
+    {
+        match self.m {
+            Some(m) => self.t.patch(EntryUpsert::new(self.key, f, m), self.g),
+            None => self.t.patch(EntryInsert::new(self.key, f), self.g),
+        }
+    }
+    /// Same as [`Entry::or_insert_with`], for when the new element is cheap to create
+    #[allow(unused)]
+    pub fn or_insert(self, new: T) -> &'t T {
+        self.or_insert_with(|| new)
+    }
+}
@@ -37,8 +37,9 @@ use {
     },
     crate::engine::{
         idx::{meta::Comparable, AsKeyClone, AsValue, AsValueClone, IndexBaseSpec, MTIndex},
-        sync::atm::Guard,
+        sync::atm::{cpin, Guard},
     },
+    core::ops::RangeBounds,
 };
 
 pub type Raw<E, C> = RawTree<E, C>;
@@ -74,9 +75,41 @@ impl<E: TreeElement, C: Config> MTIndexExt<E, E::Key, E::Value> for Raw<E, C> {
         E::Value: 'v,
         E: 'v,
         Self: 't;
+    type IterRange<'t, 'g, 'v, Q, R> = super::iter::IterRange<'t, 'g, 'v, E, C, Q, R>
+    where
+        'g: 't + 'v,
+        't: 'v,
+        E::Key: 'v + PartialOrd<Q>,
+        E::Value: 'v,
+        E: 'v,
+        R: RangeBounds<Q>,
+        Self: 't;
+    type Entry<'t, 'q, Q> = super::entry::Entry<'t, 'q, E, C, Q>
+    where
+        Q: ?Sized + 'q,
+        E: 't,
+        Self: 't;
     fn mt_iter_entry<'t, 'g, 'v>(&'t self, g: &'g Guard) -> Self::IterEntry<'t, 'g, 'v> {
         super::iter::IterEntry::new(self, g)
     }
+    fn mt_entry<'t, 'g, 'q, Q>(&'t self, key: &'q Q, g: &'g Guard) -> Self::Entry<'t, 'q, Q>
+    where
+        'g: 't,
+        Q: ?Sized + Comparable<E::Key>,
+    {
+        super::entry::Entry::new(self, key, g)
+    }
+    fn mt_range<'t, 'g, 'v, Q, R>(
+        &'t self,
+        range: R,
+        g: &'g Guard,
+    ) -> Self::IterRange<'t, 'g, 'v, Q, R>
+    where
+        E::Key: 'v + PartialOrd<Q>,
+        R: RangeBounds<Q>,
+    {
+        super::iter::IterRange::new(self, range, g)
+    }
 }
 
 impl<E: TreeElement, C: Config> MTIndex<E, E::Key, E::Value> for Raw<E, C> {
@@ -117,6 +150,13 @@ impl<E: TreeElement, C: Config> MTIndex<E, E::Key, E::Value> for Raw<E, C> {
     fn mt_len(&self) -> usize {
         self.len()
     }
+    fn mt_capacity_bytes(&self, g: &Guard) -> usize {
+        self.footprint(g)
+    }
+    fn mt_compact(&self) {
+        // deletes compress the tree as they go, so all that's left is to release the nodes that they unlinked
+        super::gc(&cpin())
+    }
     fn mt_clear(&self, g: &Guard) {
         self.transactional_clear(g)
     }
@@ -135,6 +175,14 @@ impl<E: TreeElement, C: Config> MTIndex<E, E::Key, E::Value> for Raw<E, C> {
         self.patch(VanillaUpsert(e), g)
     }
 
+    fn mt_bulk_extend<I>(&self, entries: I, g: &Guard)
+    where
+        I: IntoIterator<Item = E>,
+        E::Value: AsValue,
+    {
+        self.bulk_load(entries, g)
+    }
+
     fn mt_contains<Q>(&self, key: &Q, g: &Guard) -> bool
     where
         Q: ?Sized + Comparable<E::Key>,
@@ -33,7 +33,10 @@ use {
         mem::UArray,
         sync::atm::{Guard, Shared},
     },
-    std::marker::PhantomData,
+    std::{
+        marker::PhantomData,
+        ops::{Bound, RangeBounds},
+    },
 };
 
 pub struct IterKV<'t, 'g, 'v, T, C>
@@ -111,6 +114,73 @@ where
     }
 }
 
+/// Walks the whole tree and skips the entries with keys outside the range, so this costs O(n) and entries come back in
+/// no particular order
+pub struct IterRange<'t, 'g, 'v, T, C, Q, R>
+where
+    't: 'v,
+    'g: 'v + 't,
+    C: Config,
+    T: TreeElement,
+    T::Key: PartialOrd<Q>,
+    R: RangeBounds<Q>,
+{
+    i: RawIter<'t, 'g, 'v, T, C, CfgIterEntry>,
+    range: R,
+    _q: PhantomData<fn(&Q)>,
+}
+
+impl<'t, 'g, 'v, T, C, Q, R> IterRange<'t, 'g, 'v, T, C, Q, R>
+where
+    't: 'v,
+    'g: 'v + 't,
+    C: Config,
+    T: TreeElement,
+    T::Key: PartialOrd<Q>,
+    R: RangeBounds<Q>,
+{
+    pub fn new(t: &'t RawTree<T, C>, range: R, g: &'g Guard) -> Self {
+        Self {
+            i: RawIter::new(t, g),
+            range,
+            _q: PhantomData,
+        }
+    }
+    fn in_range(&self, key: &T::Key) -> bool {
+        let start = match self.range.start_bound() {
+            Bound::Included(start) => key >= start,
+            Bound::Excluded(start) => key > start,
+            Bound::Unbounded => true,
+        };
+        let end = match self.range.end_bound() {
+            Bound::Included(end) => key <= end,
+            Bound::Excluded(end) => key < end,
+            Bound::Unbounded => true,
+        };
+        start & end
+    }
+}
+
+impl<'t, 'g, 'v, T, C, Q, R> Iterator for IterRange<'t, 'g, 'v, T, C, Q, R>
+where
+    't: 'v,
+    'g: 'v + 't,
+    C: Config,
+    T: TreeElement,
+    T::Key: PartialOrd<Q>,
+    R: RangeBounds<Q>,
+{
+    type Item = &'v T;
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(e) = self.i.next() {
+            if self.in_range(e.key()) {
+                return Some(e);
+            }
+        }
+        None
+    }
+}
+
 pub struct IterKey<'t, 'g, 'v, T, C>
 where
     't: 'v,
@@ -70,7 +70,8 @@ impl<T: AsHasher> PreConfig for Config2B<T> {
 }
 
 pub trait TreeElement: Clone + 'static {
-    type Key: AsKey;
+    /// the key has to be ordered as well, so that the same elements can be used in ordered indexes
+    type Key: AsKey + PartialOrd;
     type IKey;
     type Value: AsValue;
     type IValue;
@@ -81,7 +82,7 @@ pub trait TreeElement: Clone + 'static {
     fn new(k: Self::IKey, v: Self::IValue, vex1: Self::VEx1, vex2: Self::VEx2) -> Self;
 }
 
-impl<K: AsKeyClone, V: AsValueClone> TreeElement for (K, V) {
+impl<K: AsKeyClone + PartialOrd, V: AsValueClone> TreeElement for (K, V) {
     type IKey = K;
     type Key = K;
     type IValue = V;
@@ -101,7 +102,7 @@ impl<K: AsKeyClone, V: AsValueClone> TreeElement for (K, V) {
     }
 }
 
-impl<K: AsKey, V: AsValue> TreeElement for Arc<(K, V)> {
+impl<K: AsKey + PartialOrd, V: AsValue> TreeElement for Arc<(K, V)> {
     type IKey = K;
     type Key = K;
     type IValue = V;
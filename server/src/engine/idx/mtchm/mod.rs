@@ -25,6 +25,7 @@
 */
 
 mod access;
+pub(super) mod entry;
 pub mod imp;
 pub(super) mod iter;
 pub mod meta;
@@ -36,7 +37,7 @@ mod tests;
 use crate::engine::sync::atm::ORD_ACQ;
 use {
     self::{
-        iter::{IterKV, IterKey, IterVal},
+        iter::{IterEntry, IterKV, IterKey, IterVal},
         meta::{CompressState, Config, DefConfig, LNode, NodeFlag, TreeElement},
     },
     crate::engine::{
@@ -275,10 +276,13 @@ impl<T: TreeElement, C: Config> RawTree<T, C> {
                     if (P::WMODE == patch::WRITEMODE_ANY) | (P::WMODE == patch::WRITEMODE_FRESH) {
                         let new = Self::new_data(patch.nx_new());
                         match current.cx_rel(node, new, g) {
-                            Ok(_) => {
+                            Ok(new) => {
                                 // we're done here
                                 self.incr_len();
-                                return P::nx_ret();
+                                return P::nx_ret_published(unsafe {
+                                    // UNSAFE(@ohsayan): we just published this snode
+                                    &Self::read_data(new)[0]
+                                });
                             }
                             Err(CompareExchangeError { new, .. }) => unsafe {
                                 /*
@@ -350,7 +354,10 @@ impl<T: TreeElement, C: Config> RawTree<T, C> {
                                                 node.as_raw() as *const LNode<_>
                                             ))
                                         }
-                                        return P::ex_ret(&data[i]);
+                                        return P::ex_ret_published(&data[i], unsafe {
+                                            // UNSAFE(@ohsayan): we just published this lnode and the patched element is always last
+                                            Self::read_data(new).last().expect("logic,empty lnode")
+                                        });
                                     }
                                     Err(CompareExchangeError { new, .. }) => {
                                         // failed to swap it in
@@ -381,7 +388,10 @@ impl<T: TreeElement, C: Config> RawTree<T, C> {
                                             ));
                                         }
                                         self.incr_len();
-                                        return P::nx_ret();
+                                        return P::nx_ret_published(unsafe {
+                                            // UNSAFE(@ohsayan): we just published this lnode and the new element is always last
+                                            Self::read_data(new).last().expect("logic,empty lnode")
+                                        });
                                     }
                                     Err(CompareExchangeError { new, .. }) => {
                                         // failed to swap it
@@ -582,6 +592,123 @@ impl<T: TreeElement, C: Config> RawTree<T, C> {
     }
 }
 
+// bulk
+impl<T: TreeElement, C: Config> RawTree<T, C> {
+    /// Load all the given entries into the tree. If the tree is empty, the tree is built bottom-up and then published
+    /// with a single CAS; otherwise (or if someone beat us to it) we just upsert every entry. If a key is repeated, the
+    /// last entry wins
+    fn bulk_load<I: IntoIterator<Item = T>>(&self, entries: I, g: &Guard) {
+        let entries: Vec<(u64, T)> = entries
+            .into_iter()
+            .map(|e| (self.hash(e.key()), e))
+            .collect();
+        if entries.is_empty() {
+            return;
+        }
+        if !self.root.ld_acq(g).is_null() {
+            // not empty (or at least, not compressed to empty); we can't just swap the root
+            return entries
+                .into_iter()
+                .for_each(|(_, e)| self.patch(patch::VanillaUpsert(e), g));
+        }
+        let (root, len) = Self::bulk_build(entries, C::LEVEL_ZERO);
+        match self.root.cx_rel(Shared::null(), root, g) {
+            Ok(_) => {
+                self.l.fetch_add(len, ORD_RLX);
+            }
+            Err(CompareExchangeError { new, .. }) => {
+                /*
+                    a writer got in before us. the subtree was never published, so put it in a tree of its own and
+                    move the entries over (it's then dropped along with that tree)
+                */
+                let mut tmp = Self::new();
+                tmp.root = Atomic::from(new);
+                tmp.l = AtomicUsize::new(len);
+                IterEntry::new(&tmp, g)
+                    .for_each(|e| self.patch(patch::VanillaUpsert(e.clone()), g));
+            }
+        }
+    }
+    /// Build the subtree for the given (hashed) entries, rooted at the given level. Returns the subtree and the number
+    /// of entries in it
+    fn bulk_build(mut entries: Vec<(u64, T)>, level: usize) -> (Owned<Node<C>>, usize) {
+        debug_assert!(!entries.is_empty(), "logic,bulk build with no entries");
+        let first_hash = entries[0].0;
+        if entries.iter().all(|(h, _)| *h == first_hash) {
+            /*
+                all of these will keep landing in the same slot, so these are either repeated keys or true collisions.
+                if it's the former, we can stop here. otherwise we keep going until we hit the max height where we can
+                put them into an lnode
+            */
+            let mut unique: Vec<(u64, T)> = Vec::with_capacity(entries.len());
+            for (h, e) in entries {
+                match unique.iter().position(|(_, x)| x.key() == e.key()) {
+                    Some(i) => unique[i].1 = e,
+                    None => unique.push((h, e)),
+                }
+            }
+            if unique.len() == 1 || level >= C::MAX_TREE_HEIGHT_UB {
+                let len = unique.len();
+                let data: LNode<T> = unique.into_iter().map(|(_, e)| e).collect();
+                return (Self::new_lnode(data), len);
+            }
+            entries = unique;
+        }
+        let mut slots: Vec<Vec<(u64, T)>> = (0..C::BRANCH_MX).map(|_| Vec::new()).collect();
+        for (h, e) in entries {
+            slots[((h >> level) & C::MASK) as usize].push((h, e));
+        }
+        let mut branch = Node::null();
+        let mut len = 0;
+        for (i, slot) in slots.into_iter().enumerate() {
+            if !slot.is_empty() {
+                let (child, child_len) = Self::bulk_build(slot, level + C::BRANCH_LG);
+                branch.branch[i] = Atomic::from(child);
+                len += child_len;
+            }
+        }
+        (Owned::new(branch), len)
+    }
+}
+
+// memory
+impl<T: TreeElement, C: Config> RawTree<T, C> {
+    /// Returns the memory allocated for the nodes of the tree (which includes the elements, but not the heap memory
+    /// that they own)
+    fn footprint(&self, g: &Guard) -> usize {
+        Self::node_footprint(self.root.ld_acq(g), g)
+    }
+    fn node_footprint(node: Shared<Node<C>>, g: &Guard) -> usize {
+        match ldfl(&node) {
+            _ if node.is_null() => 0,
+            flag if hf(flag, NodeFlag::DATA) => {
+                let data = unsafe {
+                    // UNSAFE(@ohsayan): flagck
+                    Self::read_data(node)
+                };
+                let spilled = if !data.on_stack() {
+                    data.capacity() * mem::size_of::<T>()
+                } else {
+                    0
+                };
+                mem::size_of::<LNode<T>>() + spilled
+            }
+            _ => {
+                let branch = unsafe {
+                    // UNSAFE(@ohsayan): nullck + flagck
+                    node.deref()
+                };
+                mem::size_of::<Node<C>>()
+                    + branch
+                        .branch
+                        .iter()
+                        .map(|child| Self::node_footprint(child.ld_acq(g), g))
+                        .sum::<usize>()
+            }
+        }
+    }
+}
+
 // low-level methods
 impl<T, C: Config> RawTree<T, C> {
     fn decr_len_by(&self, by: usize) {
@@ -45,16 +45,25 @@ pub const WRITEMODE_ANY: WriteFlag = 0b11;
 pub trait PatchWrite<E: TreeElement> {
     const WMODE: WriteFlag;
     type Ret<'a>;
-    type Target: Hash + Comparable<E::Key>;
+    type Target: Hash + Comparable<E::Key> + ?Sized;
     fn target<'a>(&'a self) -> &Self::Target;
     fn nx_new(&mut self) -> E {
         unreachable!()
     }
     fn nx_ret<'a>() -> Self::Ret<'a>;
+    /// Same as [`PatchWrite::nx_ret`], but called with the element that was just published by [`PatchWrite::nx_new`]
+    fn nx_ret_published<'a>(_new: &'a E) -> Self::Ret<'a> {
+        Self::nx_ret()
+    }
     fn ex_apply(&mut self, _: &E) -> E {
         unreachable!()
     }
     fn ex_ret<'a>(current: &'a E) -> Self::Ret<'a>;
+    /// Same as [`PatchWrite::ex_ret`], but also called with the element that was just published by
+    /// [`PatchWrite::ex_apply`]
+    fn ex_ret_published<'a>(current: &'a E, _new: &'a E) -> Self::Ret<'a> {
+        Self::ex_ret(current)
+    }
 }
 
 /*
@@ -168,6 +177,128 @@ impl<T: TreeElement> PatchWrite<T> for VanillaUpdateRet<T> {
     }
 }
 
+/*
+    entry
+*/
+
+/// Insert the element returned by `f` if the key is missing; returns the element that ends up in the tree
+pub struct EntryInsert<'q, T, Q: ?Sized, F> {
+    key: &'q Q,
+    f: Option<F>,
+    new: Option<T>,
+}
+
+impl<'q, T, Q: ?Sized, F> EntryInsert<'q, T, Q, F> {
+    pub fn new(key: &'q Q, f: F) -> Self {
+        Self {
+            key,
+            f: Some(f),
+            new: None,
+        }
+    }
+}
+
+impl<'q, T, Q, F> EntryInsert<'q, T, Q, F>
+where
+    T: TreeElement,
+    Q: Comparable<T::Key> + ?Sized,
+    F: FnOnce() -> T,
+{
+    /// the CAS might fail, so we only ever call `f` once and hand out clones
+    fn new_element(&mut self) -> T {
+        let f = &mut self.f;
+        let new = self
+            .new
+            .get_or_insert_with(|| f.take().expect("logic,entry fn called twice")());
+        debug_assert!(self.key.cmp_eq(new.key()), "logic,entry key mismatch");
+        new.clone()
+    }
+}
+
+impl<'q, T, Q, F> PatchWrite<T> for EntryInsert<'q, T, Q, F>
+where
+    T: TreeElement,
+    Q: Comparable<T::Key> + ?Sized,
+    F: FnOnce() -> T,
+{
+    const WMODE: WriteFlag = WRITEMODE_FRESH;
+    type Ret<'a> = &'a T;
+    type Target = Q;
+    fn target(&self) -> &Self::Target {
+        self.key
+    }
+    // nx
+    fn nx_new(&mut self) -> T {
+        self.new_element()
+    }
+    fn nx_ret<'a>() -> Self::Ret<'a> {
+        unreachable!("logic,entry insert always publishes")
+    }
+    fn nx_ret_published<'a>(new: &'a T) -> Self::Ret<'a> {
+        new
+    }
+    // ex
+    fn ex_ret<'a>(current: &'a T) -> Self::Ret<'a> {
+        current
+    }
+}
+
+/// Same as [`EntryInsert`], except that if the key exists, the element is replaced with what `m` returns for it
+pub struct EntryUpsert<'q, T, Q: ?Sized, F, M> {
+    insert: EntryInsert<'q, T, Q, F>,
+    m: M,
+}
+
+impl<'q, T, Q: ?Sized, F, M> EntryUpsert<'q, T, Q, F, M> {
+    pub fn new(key: &'q Q, f: F, m: M) -> Self {
+        Self {
+            insert: EntryInsert::new(key, f),
+            m,
+        }
+    }
+}
+
+impl<'q, T, Q, F, M> PatchWrite<T> for EntryUpsert<'q, T, Q, F, M>
+where
+    T: TreeElement,
+    Q: Comparable<T::Key> + ?Sized,
+    F: FnOnce() -> T,
+    M: FnMut(&T) -> T,
+{
+    const WMODE: WriteFlag = WRITEMODE_ANY;
+    type Ret<'a> = &'a T;
+    type Target = Q;
+    fn target(&self) -> &Self::Target {
+        self.insert.key
+    }
+    // nx
+    fn nx_new(&mut self) -> T {
+        self.insert.new_element()
+    }
+    fn nx_ret<'a>() -> Self::Ret<'a> {
+        unreachable!("logic,entry upsert always publishes")
+    }
+    fn nx_ret_published<'a>(new: &'a T) -> Self::Ret<'a> {
+        new
+    }
+    // ex
+    fn ex_apply(&mut self, current: &T) -> T {
+        // NB: if the CAS fails, this is called again with whatever the current element is at that point
+        let new = (self.m)(current);
+        debug_assert!(
+            self.insert.key.cmp_eq(new.key()),
+            "logic,entry key mismatch"
+        );
+        new
+    }
+    fn ex_ret<'a>(_: &'a T) -> Self::Ret<'a> {
+        unreachable!("logic,entry upsert always publishes")
+    }
+    fn ex_ret_published<'a>(_: &'a T, new: &'a T) -> Self::Ret<'a> {
+        new
+    }
+}
+
 /*
     delete
 */
@@ -30,11 +30,12 @@ use {
         meta::{Config, DefConfig},
     },
     crate::engine::{
-        idx::{IndexBaseSpec, MTIndex},
+        idx::{IndexBaseSpec, MTIndex, MTIndexExt},
         sync::atm::{cpin, Guard},
     },
     std::{
         hash::{BuildHasher, Hasher},
+        mem,
         sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
         thread::{self, JoinHandle},
         time::Duration,
@@ -341,3 +342,154 @@ fn multispam_lol() {
     modify_and_verify_integrity(&token, &idx, &data, _action_put, _verify_eq);
     assert_eq!(idx.idx_metrics().replnode(), SPAM_QCOUNT - 1);
 }
+
+// bulk
+#[test]
+fn bulk_load_empty() {
+    let g = cpin();
+    let idx = ChmU8::mt_bulk_load([], &g);
+    assert_eq!(idx.mt_len(), 0);
+    assert!(idx.mt_get(&10, &g).is_none());
+}
+
+#[test]
+fn bulk_load() {
+    let g = cpin();
+    let idx = Chm::<usize, usize>::mt_bulk_load((0..SPAM_QCOUNT).map(|i| (i, i + 1)), &g);
+    assert_eq!(idx.mt_len(), SPAM_QCOUNT);
+    assert!((0..SPAM_QCOUNT).all(|i| idx.mt_get(&i, &g).copied() == Some(i + 1)));
+    // should work just like any other index
+    assert!(idx.mt_insert((SPAM_QCOUNT, 0), &g));
+    assert!(!idx.mt_insert((0, 0), &g));
+    assert!(idx.mt_delete(&0, &g));
+    assert_eq!(idx.mt_len(), SPAM_QCOUNT);
+}
+
+#[test]
+fn bulk_load_repeated_keys() {
+    let g = cpin();
+    let idx = ChmU8::mt_bulk_load([(1, 1), (2, 2), (1, 10)], &g);
+    assert_eq!(idx.mt_len(), 2);
+    assert_eq!(idx.mt_get(&1, &g).copied(), Some(10));
+    assert_eq!(idx.mt_get(&2, &g).copied(), Some(2));
+}
+
+#[test]
+fn bulk_load_lol() {
+    // everything collides, so everything ends up in a single lnode
+    let g = cpin();
+    let idx = super::RawTree::<(usize, usize), super::meta::Config2B<LolState>>::mt_bulk_load(
+        (0..100).map(|i| (i, i)).chain([(0, 100)]),
+        &g,
+    );
+    assert_eq!(idx.mt_len(), 100);
+    assert_eq!(idx.mt_get(&0, &g).copied(), Some(100));
+    assert!((1..100).all(|i| idx.mt_get(&i, &g).copied() == Some(i)));
+    assert!(idx.mt_delete(&50, &g));
+    assert!(idx.mt_get(&50, &g).is_none());
+}
+
+#[test]
+fn bulk_extend_nonempty() {
+    let g = cpin();
+    let idx = ChmU8::idx_init();
+    assert!(idx.mt_insert((1, 1), &g));
+    idx.mt_bulk_extend([(1, 10), (2, 20)], &g);
+    assert_eq!(idx.mt_len(), 2);
+    assert_eq!(idx.mt_get(&1, &g).copied(), Some(10));
+    assert_eq!(idx.mt_get(&2, &g).copied(), Some(20));
+}
+
+// entry
+#[test]
+fn entry_or_insert() {
+    let g = cpin();
+    let idx = ChmU8::idx_init();
+    assert!(idx.mt_entry(&1, &g).get().is_none());
+    assert_eq!(idx.mt_entry(&1, &g).or_insert((1, 10)), &(1, 10));
+    // already exists, so we shouldn't insert
+    assert_eq!(
+        idx.mt_entry(&1, &g)
+            .or_insert_with(|| panic!("called insert fn for existing key")),
+        &(1, 10)
+    );
+    assert_eq!(idx.mt_entry(&1, &g).get(), Some(&(1, 10)));
+    assert_eq!(idx.mt_len(), 1);
+}
+
+#[test]
+fn entry_and_modify() {
+    let g = cpin();
+    let idx = ChmU8::idx_init();
+    let incr = |&(k, v): &(u8, u8)| (k, v + 1);
+    assert_eq!(
+        idx.mt_entry(&1, &g).and_modify(incr).or_insert((1, 0)),
+        &(1, 0)
+    );
+    assert_eq!(
+        idx.mt_entry(&1, &g).and_modify(incr).or_insert((1, 0)),
+        &(1, 1)
+    );
+    assert_eq!(idx.mt_get(&1, &g).copied(), Some(1));
+    assert_eq!(idx.mt_len(), 1);
+}
+
+#[test]
+fn entry_lol() {
+    // force an lnode so that we go through the lnode paths as well
+    let g = cpin();
+    let idx = super::RawTree::<(usize, usize), super::meta::Config2B<LolState>>::new();
+    for i in 0..10 {
+        assert_eq!(idx.mt_entry(&i, &g).or_insert((i, i)), &(i, i));
+    }
+    for i in 0..10 {
+        assert_eq!(
+            idx.mt_entry(&i, &g)
+                .and_modify(|&(k, v)| (k, v * 2))
+                .or_insert_with(|| unreachable!()),
+            &(i, i * 2)
+        );
+    }
+    assert_eq!(idx.mt_len(), 10);
+}
+
+#[test]
+fn multispam_entry() {
+    // everyone increments every counter, so every counter should end up at the number of tenants
+    let idx = Arc::new(Chm::<usize, usize>::idx_init());
+    let handles: Vec<JoinHandle<()>> = (0..SPAM_TENANTS)
+        .map(|_| {
+            let idx = idx.clone();
+            thread::spawn(move || {
+                let g = cpin();
+                for i in 0..SPAM_QCOUNT / SPAM_TENANTS {
+                    idx.mt_entry(&i, &g)
+                        .and_modify(|&(k, v)| (k, v + 1))
+                        .or_insert((i, 1));
+                }
+            })
+        })
+        .collect();
+    let _ = tjoin_all(handles);
+    let g = cpin();
+    assert_eq!(idx.mt_len(), SPAM_QCOUNT / SPAM_TENANTS);
+    assert!(
+        (0..SPAM_QCOUNT / SPAM_TENANTS).all(|i| idx.mt_get(&i, &g).copied() == Some(SPAM_TENANTS))
+    );
+}
+
+#[test]
+fn capacity_and_compact() {
+    let g = cpin();
+    let idx = Chm::<usize, usize>::idx_init();
+    assert_eq!(idx.mt_capacity_bytes(&g), 0);
+    (0..SPAM_QCOUNT).for_each(|i| assert!(idx.mt_insert((i, i), &g)));
+    assert_eq!(idx.mt_len_approx(), SPAM_QCOUNT);
+    let full = idx.mt_capacity_bytes(&g);
+    assert!(full >= SPAM_QCOUNT * mem::size_of::<(usize, usize)>());
+    (0..SPAM_QCOUNT).for_each(|i| assert!(idx.mt_delete(&i, &g)));
+    idx.mt_compact();
+    assert_eq!(idx.mt_len_approx(), 0);
+    // at most the root is left
+    assert!(idx.mt_capacity_bytes(&g) < full / 10);
+}
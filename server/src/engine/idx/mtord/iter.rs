@@ -0,0 +1,193 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{Node, SkipList, MARKED},
+    crate::engine::{
+        idx::mtchm::meta::TreeElement,
+        sync::atm::{Guard, Shared},
+    },
+    std::{
+        marker::PhantomData,
+        ops::{Bound, RangeBounds},
+    },
+};
+
+/// Iterates over the live elements at the bottom level (in key order)
+pub struct IterEntry<'t, 'g, 'v, T>
+where
+    't: 'v,
+    'g: 'v + 't,
+{
+    next: Shared<'g, Node<T>>,
+    g: &'g Guard,
+    _m: PhantomData<(&'t SkipList<T>, &'v T)>,
+}
+
+impl<'t, 'g, 'v, T> IterEntry<'t, 'g, 'v, T>
+where
+    't: 'v,
+    'g: 'v + 't,
+{
+    pub(super) fn new(t: &'t SkipList<T>, g: &'g Guard) -> Self {
+        Self::starting_at(t.head.tower[0].ld_acq(g).with_tag(0), g)
+    }
+    fn starting_at(next: Shared<'g, Node<T>>, g: &'g Guard) -> Self {
+        Self {
+            next,
+            g,
+            _m: PhantomData,
+        }
+    }
+}
+
+impl<'t, 'g, 'v, T> Iterator for IterEntry<'t, 'g, 'v, T>
+where
+    't: 'v,
+    'g: 'v + 't,
+{
+    type Item = &'v T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = unsafe {
+                // UNSAFE(@ohsayan): we're pinned, so whatever we can reach is still around
+                self.next.as_ref()
+            }?;
+            let next = node.tower[0].ld_acq(self.g);
+            self.next = next.with_tag(0);
+            if next.tag() != MARKED {
+                return Some(unsafe {
+                    // UNSAFE(@ohsayan): never the head
+                    node.elem(self.g)
+                });
+            }
+        }
+    }
+}
+
+/// Iterates over the live elements with keys in the given range (in key order)
+pub struct IterRange<'t, 'g, 'v, T, Q, R>
+where
+    't: 'v,
+    'g: 'v + 't,
+{
+    i: IterEntry<'t, 'g, 'v, T>,
+    range: R,
+    done: bool,
+    _q: PhantomData<Q>,
+}
+
+impl<'t, 'g, 'v, T, Q, R> IterRange<'t, 'g, 'v, T, Q, R>
+where
+    't: 'v,
+    'g: 'v + 't,
+    T: TreeElement,
+    T::Key: PartialOrd<Q>,
+    R: RangeBounds<Q>,
+{
+    pub(super) fn new(t: &'t SkipList<T>, range: R, g: &'g Guard) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(start) => t.seek(|k| k < start, g),
+            Bound::Excluded(start) => t.seek(|k| k <= start, g),
+            Bound::Unbounded => t.head.tower[0].ld_acq(g).with_tag(0),
+        };
+        Self {
+            i: IterEntry::starting_at(start, g),
+            range,
+            done: false,
+            _q: PhantomData,
+        }
+    }
+}
+
+impl<'t, 'g, 'v, T, Q, R> Iterator for IterRange<'t, 'g, 'v, T, Q, R>
+where
+    't: 'v,
+    'g: 'v + 't,
+    T: TreeElement,
+    T::Key: PartialOrd<Q>,
+    R: RangeBounds<Q>,
+{
+    type Item = &'v T;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let e = self.i.next()?;
+        let before_end = match self.range.end_bound() {
+            Bound::Included(end) => e.key() <= end,
+            Bound::Excluded(end) => e.key() < end,
+            Bound::Unbounded => true,
+        };
+        self.done = !before_end;
+        before_end.then_some(e)
+    }
+}
+
+macro_rules! mapped_iter {
+    ($($(#[$attr:meta])* $name:ident => $item:ty = $map:expr),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            pub struct $name<'t, 'g, 'v, T>
+            where
+                't: 'v,
+                'g: 'v + 't,
+            {
+                i: IterEntry<'t, 'g, 'v, T>,
+            }
+            impl<'t, 'g, 'v, T> $name<'t, 'g, 'v, T>
+            where
+                't: 'v,
+                'g: 'v + 't,
+            {
+                pub(super) fn new(t: &'t SkipList<T>, g: &'g Guard) -> Self {
+                    Self {
+                        i: IterEntry::new(t, g),
+                    }
+                }
+            }
+            impl<'t, 'g, 'v, T: TreeElement> Iterator for $name<'t, 'g, 'v, T>
+            where
+                't: 'v,
+                'g: 'v + 't,
+            {
+                type Item = $item;
+                fn next(&mut self) -> Option<Self::Item> {
+                    self.i.next().map($map)
+                }
+            }
+        )*
+    };
+}
+
+mapped_iter! {
+    /// Iterates over the keys and values (in key order)
+    IterKV => (&'v T::Key, &'v T::Value) = |e: &'v T| (e.key(), e.val()),
+    /// Iterates over the keys (in key order)
+    IterKey => &'v T::Key = TreeElement::key,
+    /// Iterates over the values (in key order)
+    IterVal => &'v T::Value = TreeElement::val,
+}
@@ -0,0 +1,747 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    A lock-free skiplist. Nodes are linked at the bottom level first (that's when they become visible) and then
+    (best-effort) at the upper levels. Deletes mark the node's tower top-down and the bottom level last (that's when
+    the node is gone); anyone who runs into a marked node on a write path helps unlink it.
+
+    Since an insert might still be linking the upper levels of a node that's being deleted (and might even link it
+    back in at some level), nodes are refcounted: one ref for every level that the node is linked at and one for the
+    thread that's inserting it. Whoever drops the last ref gets to destroy the node.
+*/
+
+mod iter;
+#[cfg(test)]
+mod tests;
+
+#[cfg(debug_assertions)]
+use super::DummyMetrics;
+use {
+    self::iter::{IterEntry, IterKV, IterKey, IterRange, IterVal},
+    super::{
+        meta::Comparable, mtchm::meta::TreeElement, AsKeyClone, AsValue, AsValueClone,
+        IndexBaseSpec, MTIndex, MTIndexExt,
+    },
+    crate::engine::sync::atm::{
+        cpin, upin, Atomic, Guard, Owned, Shared, ORD_ACQ, ORD_ACR, ORD_RLX,
+    },
+    std::{
+        cell::Cell,
+        cmp::Ordering,
+        collections::hash_map::RandomState,
+        fmt,
+        hash::{BuildHasher, Hasher},
+        mem,
+        ops::RangeBounds,
+        sync::atomic::AtomicUsize,
+    },
+};
+
+/// The maximum height of a tower. With a promotion probability of 1/4, this is good for a few billion entries
+const MAX_HEIGHT: usize = 16;
+/// The tag on a tower link that marks its node as deleted (at that level)
+const MARKED: usize = 1;
+
+fn random_height() -> usize {
+    thread_local! {
+        static SEED: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish() | 1);
+    }
+    SEED.with(|seed| {
+        // xorshift64
+        let mut x = seed.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        seed.set(x);
+        // promote with a probability of 1/4 at every level
+        ((x.trailing_zeros() / 2) as usize + 1).min(MAX_HEIGHT)
+    })
+}
+
+struct Node<T> {
+    /// the element; this is only ever null for the head
+    e: Atomic<T>,
+    /// one for every level that this node is linked at, plus one for the thread inserting it
+    refs: AtomicUsize,
+    tower: Box<[Atomic<Self>]>,
+}
+
+impl<T> Node<T> {
+    fn new(e: Atomic<T>, height: usize, refs: usize) -> Self {
+        Self {
+            e,
+            refs: AtomicUsize::new(refs),
+            tower: (0..height).map(|_| Atomic::null()).collect(),
+        }
+    }
+    fn height(&self) -> usize {
+        self.tower.len()
+    }
+    /// SAFETY: this isn't the head
+    unsafe fn elem<'g>(&self, g: &'g Guard) -> &'g T {
+        self.e.ld_acq(g).deref()
+    }
+    fn is_deleted(&self, g: &Guard) -> bool {
+        self.tower[0].ld_acq(g).tag() == MARKED
+    }
+}
+
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        unsafe {
+            // UNSAFE(@ohsayan): nobody can see this node anymore
+            let e = self.e.ld_rlx(upin());
+            if !e.is_null() {
+                drop(e.into_owned())
+            }
+        }
+    }
+}
+
+/// Where a key is (or should be) at every level
+struct Position<'g, T> {
+    found: Option<Shared<'g, Node<T>>>,
+    preds: [&'g Node<T>; MAX_HEIGHT],
+    succs: [Shared<'g, Node<T>>; MAX_HEIGHT],
+}
+
+/// The outcome of a [`SkipList::put`]
+enum Put<'g, T> {
+    /// the key was missing and we didn't insert anything
+    Missing,
+    /// the key exists and we left it alone
+    Exists(&'g T),
+    Inserted(&'g T),
+    Updated {
+        old: &'g T,
+        new: &'g T,
+    },
+}
+
+/// A concurrent ordered index
+pub struct SkipList<T> {
+    head: Node<T>,
+    l: AtomicUsize,
+}
+
+impl<T> SkipList<T> {
+    fn new() -> Self {
+        Self {
+            head: Node::new(Atomic::null(), MAX_HEIGHT, 1),
+            l: AtomicUsize::new(0),
+        }
+    }
+    fn len(&self) -> usize {
+        self.l.load(ORD_RLX)
+    }
+    /// Drop a ref to this node, destroying it if that was the last one
+    unsafe fn release(node: Shared<Node<T>>, g: &Guard) {
+        if node.deref().refs.fetch_sub(1, ORD_ACR) == 1 {
+            g.defer_destroy(node)
+        }
+    }
+}
+
+impl<T: TreeElement> SkipList<T> {
+    /// Locate the key at every level, unlinking any deleted nodes that we run into on the way
+    fn find<'g, Q>(&'g self, key: &Q, g: &'g Guard) -> Position<'g, T>
+    where
+        Q: ?Sized + Comparable<T::Key>,
+    {
+        'retry: loop {
+            let mut pred = &self.head;
+            let mut preds = [&self.head; MAX_HEIGHT];
+            let mut succs = [Shared::null(); MAX_HEIGHT];
+            for level in (0..MAX_HEIGHT).rev() {
+                let mut curr = pred.tower[level].ld_acq(g);
+                if curr.tag() == MARKED {
+                    // pred is being deleted
+                    continue 'retry;
+                }
+                while let Some(c) = unsafe { curr.as_ref() } {
+                    let succ = c.tower[level].ld_acq(g);
+                    if succ.tag() == MARKED {
+                        // this one's deleted; help unlink it
+                        match pred.tower[level].cx(curr, succ.with_tag(0), ORD_ACR, ORD_ACQ, g) {
+                            Ok(_) => {
+                                unsafe {
+                                    // UNSAFE(@ohsayan): we just dropped this link
+                                    Self::release(curr, g)
+                                }
+                                curr = succ.with_tag(0);
+                                continue;
+                            }
+                            Err(_) => continue 'retry,
+                        }
+                    }
+                    if key.cmp_ord(unsafe { c.elem(g) }.key()) == Some(Ordering::Greater) {
+                        pred = c;
+                        curr = succ;
+                    } else {
+                        break;
+                    }
+                }
+                preds[level] = pred;
+                succs[level] = curr;
+            }
+            let found = unsafe { succs[0].as_ref() }
+                .filter(|n| key.cmp_eq(unsafe { n.elem(g) }.key()))
+                .map(|_| succs[0]);
+            return Position {
+                found,
+                preds,
+                succs,
+            };
+        }
+    }
+    /// Returns the first node at the bottom level for which `right` is false. This doesn't help unlink anything and
+    /// might return a deleted node, so check for that
+    fn seek<'g>(&self, right: impl Fn(&T::Key) -> bool, g: &'g Guard) -> Shared<'g, Node<T>> {
+        let mut pred = &self.head;
+        let mut curr = Shared::null();
+        for level in (0..MAX_HEIGHT).rev() {
+            curr = pred.tower[level].ld_acq(g).with_tag(0);
+            while let Some(c) = unsafe { curr.as_ref() } {
+                if !right(unsafe { c.elem(g) }.key()) {
+                    break;
+                }
+                pred = c;
+                curr = c.tower[level].ld_acq(g).with_tag(0);
+            }
+        }
+        curr
+    }
+    fn get<'g, Q>(&'g self, key: &Q, g: &'g Guard) -> Option<&'g T>
+    where
+        Q: ?Sized + Comparable<T::Key>,
+    {
+        let mut curr = self.seek(|k| key.cmp_ord(k) == Some(Ordering::Greater), g);
+        // there might be deleted nodes with this key right before the live one
+        while let Some(c) = unsafe { curr.as_ref() } {
+            let e = unsafe { c.elem(g) };
+            if !key.cmp_eq(e.key()) {
+                return None;
+            }
+            let next = c.tower[0].ld_acq(g);
+            if next.tag() != MARKED {
+                return Some(e);
+            }
+            curr = next.with_tag(0);
+        }
+        None
+    }
+    /// Insert what `nx` returns if the key is missing, or replace the element with what `ex` returns for it if the key
+    /// exists. Either of them can return [`None`] to leave things as they are. Since we might race with other writers,
+    /// both of them can be called more than once
+    fn put<'g, Q>(
+        &'g self,
+        key: &Q,
+        mut nx: impl FnMut() -> Option<T>,
+        mut ex: impl FnMut(&T) -> Option<T>,
+        g: &'g Guard,
+    ) -> Put<'g, T>
+    where
+        Q: ?Sized + Comparable<T::Key>,
+    {
+        loop {
+            let pos = self.find(key, g);
+            if let Some(node) = pos.found {
+                let node = unsafe { node.deref() };
+                let current = node.e.ld_acq(g);
+                let current_ref = unsafe { current.deref() };
+                let new = match ex(current_ref) {
+                    Some(new) => new,
+                    None => return Put::Exists(current_ref),
+                };
+                match node.e.cx(current, Owned::new(new), ORD_ACR, ORD_ACQ, g) {
+                    Ok(new) => unsafe {
+                        // UNSAFE(@ohsayan): swapped out, so nobody will see this once the epoch moves on
+                        g.defer_destroy(current);
+                        return Put::Updated {
+                            old: current_ref,
+                            new: new.deref(),
+                        };
+                    },
+                    Err(_) => continue,
+                }
+            }
+            let new = match nx() {
+                Some(new) => Owned::new(new).into_shared(g),
+                None => return Put::Missing,
+            };
+            let height = random_height();
+            // one ref for the bottom level and one for us
+            let mut node = Owned::new(Node::new(Atomic::from(new), height, 2));
+            for level in 0..height {
+                node.tower[level] = Atomic::from(pos.succs[level]);
+            }
+            let node = node.into_shared(g);
+            if pos.preds[0].tower[0]
+                .cx(pos.succs[0], node, ORD_ACR, ORD_ACQ, g)
+                .is_err()
+            {
+                unsafe {
+                    // UNSAFE(@ohsayan): never published
+                    drop(node.into_owned());
+                }
+                continue;
+            }
+            self.l.fetch_add(1, ORD_RLX);
+            self.link_tower(key, node, pos, g);
+            return Put::Inserted(unsafe { new.deref() });
+        }
+    }
+    /// Link the upper levels of a node that we just inserted, and then drop our ref to it
+    fn link_tower<'g, Q>(
+        &'g self,
+        key: &Q,
+        node: Shared<'g, Node<T>>,
+        mut pos: Position<'g, T>,
+        g: &'g Guard,
+    ) where
+        Q: ?Sized + Comparable<T::Key>,
+    {
+        let n = unsafe { node.deref() };
+        'link: for level in 1..n.height() {
+            loop {
+                let succ = pos.succs[level];
+                let next = n.tower[level].ld_acq(g);
+                if next.tag() == MARKED {
+                    // it's being deleted already; don't bother
+                    break 'link;
+                }
+                if next != succ && n.tower[level].cx(next, succ, ORD_ACR, ORD_ACQ, g).is_err() {
+                    break 'link;
+                }
+                n.refs.fetch_add(1, ORD_ACR);
+                if pos.preds[level].tower[level]
+                    .cx(succ, node, ORD_ACR, ORD_ACQ, g)
+                    .is_ok()
+                {
+                    break;
+                }
+                // we hold a ref, so this can't be the last one
+                n.refs.fetch_sub(1, ORD_ACR);
+                pos = self.find(key, g);
+                if pos.found != Some(node) {
+                    break 'link;
+                }
+            }
+        }
+        if n.is_deleted(g) {
+            // we might have linked it back in somewhere
+            let _ = self.find(key, g);
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): this is our ref
+            Self::release(node, g)
+        }
+    }
+    fn remove<'g, Q>(&'g self, key: &Q, g: &'g Guard) -> Option<&'g T>
+    where
+        Q: ?Sized + Comparable<T::Key>,
+    {
+        let node = self.find(key, g).found?;
+        let n = unsafe { node.deref() };
+        // mark the tower top-down
+        for level in (1..n.height()).rev() {
+            let _ = n.tower[level].fetch_or(MARKED, ORD_ACR, g);
+        }
+        // whoever marks the bottom level gets to delete it
+        let mut next = n.tower[0].ld_acq(g);
+        loop {
+            if next.tag() == MARKED {
+                return None;
+            }
+            match n.tower[0].cx(next, next.with_tag(MARKED), ORD_ACR, ORD_ACQ, g) {
+                Ok(_) => break,
+                Err(e) => next = e.current,
+            }
+        }
+        self.l.fetch_sub(1, ORD_RLX);
+        let e = unsafe { n.elem(g) };
+        // now unlink it
+        let _ = self.find(key, g);
+        Some(e)
+    }
+    /// Returns the memory allocated for the nodes (which includes the elements, but not the heap memory that they own)
+    fn footprint(&self, g: &Guard) -> usize {
+        let tower = |n: &Node<T>| {
+            mem::size_of::<Node<T>>() + n.height() * mem::size_of::<Atomic<Node<T>>>()
+        };
+        let mut size = tower(&self.head);
+        let mut curr = self.head.tower[0].ld_acq(g).with_tag(0);
+        while let Some(c) = unsafe { curr.as_ref() } {
+            size += tower(c) + mem::size_of::<T>();
+            curr = c.tower[0].ld_acq(g).with_tag(0);
+        }
+        size
+    }
+    fn transactional_clear(&self, g: &Guard) {
+        IterEntry::new(self, g).for_each(|e| {
+            let _ = self.remove(e.key(), g);
+        });
+    }
+}
+
+impl<T> Drop for SkipList<T> {
+    fn drop(&mut self) {
+        unsafe {
+            /*
+                UNSAFE(@ohsayan): sole live owner. walk every level and drop the link to every node that we see; a node
+                is destroyed once we've seen it at every level that it's linked at
+            */
+            let g = upin();
+            for level in (0..MAX_HEIGHT).rev() {
+                let mut curr = self.head.tower[level].ld_rlx(g).with_tag(0);
+                while !curr.is_null() {
+                    let next = curr.deref().tower[level].ld_rlx(g).with_tag(0);
+                    if curr.deref().refs.fetch_sub(1, ORD_RLX) == 1 {
+                        drop(curr.into_owned());
+                    }
+                    curr = next;
+                }
+            }
+        }
+        cpin().flush()
+    }
+}
+
+impl<T: TreeElement> fmt::Debug for SkipList<T>
+where
+    T::Key: fmt::Debug,
+    T::Value: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let g = cpin();
+        f.debug_map().entries(IterKV::new(self, &g)).finish()
+    }
+}
+
+impl<E> IndexBaseSpec for SkipList<E> {
+    const PREALLOC: bool = false;
+
+    #[cfg(debug_assertions)]
+    type Metrics = DummyMetrics;
+
+    fn idx_init() -> Self {
+        Self::new()
+    }
+
+    fn idx_init_with(s: Self) -> Self {
+        s
+    }
+
+    #[cfg(debug_assertions)]
+    fn idx_metrics(&self) -> &Self::Metrics {
+        &DummyMetrics
+    }
+}
+
+impl<E: TreeElement> MTIndex<E, E::Key, E::Value> for SkipList<E> {
+    type IterKV<'t, 'g, 'v>
+        = IterKV<'t, 'g, 'v, E>
+    where
+        'g: 't + 'v,
+        't: 'v,
+        E::Key: 'v,
+        E::Value: 'v,
+        Self: 't;
+
+    type IterKey<'t, 'g, 'v>
+        = IterKey<'t, 'g, 'v, E>
+    where
+        'g: 't + 'v,
+        't: 'v,
+        E::Key: 'v,
+        Self: 't;
+
+    type IterVal<'t, 'g, 'v>
+        = IterVal<'t, 'g, 'v, E>
+    where
+        'g: 't + 'v,
+        't: 'v,
+        E::Value: 'v,
+        Self: 't;
+
+    fn mt_iter_kv<'t, 'g, 'v>(&'t self, g: &'g Guard) -> Self::IterKV<'t, 'g, 'v> {
+        IterKV::new(self, g)
+    }
+
+    fn mt_iter_key<'t, 'g, 'v>(&'t self, g: &'g Guard) -> Self::IterKey<'t, 'g, 'v> {
+        IterKey::new(self, g)
+    }
+
+    fn mt_iter_val<'t, 'g, 'v>(&'t self, g: &'g Guard) -> Self::IterVal<'t, 'g, 'v> {
+        IterVal::new(self, g)
+    }
+
+    fn mt_len(&self) -> usize {
+        self.len()
+    }
+
+    fn mt_capacity_bytes(&self, g: &Guard) -> usize {
+        self.footprint(g)
+    }
+
+    fn mt_compact(&self) {
+        // deleted nodes are unlinked right away, so all that's left is to release them
+        cpin().flush()
+    }
+
+    fn mt_clear(&self, g: &Guard) {
+        self.transactional_clear(g)
+    }
+
+    fn mt_insert(&self, e: E, g: &Guard) -> bool
+    where
+        E::Value: AsValue,
+    {
+        matches!(
+            self.put(e.key(), || Some(e.clone()), |_| None, g),
+            Put::Inserted(_)
+        )
+    }
+
+    fn mt_upsert(&self, e: E, g: &Guard)
+    where
+        E::Value: AsValue,
+    {
+        let _ = self.put(e.key(), || Some(e.clone()), |_| Some(e.clone()), g);
+    }
+
+    fn mt_bulk_extend<I>(&self, entries: I, g: &Guard)
+    where
+        I: IntoIterator<Item = E>,
+        E::Value: AsValue,
+    {
+        entries.into_iter().for_each(|e| self.mt_upsert(e, g))
+    }
+
+    fn mt_contains<Q>(&self, key: &Q, g: &Guard) -> bool
+    where
+        Q: ?Sized + Comparable<E::Key>,
+    {
+        self.get(key, g).is_some()
+    }
+
+    fn mt_get<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v E::Value>
+    where
+        Q: ?Sized + Comparable<E::Key>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        self.get(key, g).map(TreeElement::val)
+    }
+
+    fn mt_get_element<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v E>
+    where
+        Q: ?Sized + Comparable<E::Key>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        self.get(key, g)
+    }
+
+    fn mt_get_cloned<Q>(&self, key: &Q, g: &Guard) -> Option<E::Value>
+    where
+        Q: ?Sized + Comparable<E::Key>,
+        E::Value: AsValueClone,
+    {
+        self.get(key, g).map(|e| e.val().clone())
+    }
+
+    fn mt_update(&self, e: E, g: &Guard) -> bool
+    where
+        E::Key: AsKeyClone,
+        E::Value: AsValue,
+    {
+        matches!(
+            self.put(e.key(), || None, |_| Some(e.clone()), g),
+            Put::Updated { .. }
+        )
+    }
+
+    fn mt_update_return<'t, 'g, 'v>(&'t self, e: E, g: &'g Guard) -> Option<&'v E::Value>
+    where
+        E::Key: AsKeyClone,
+        E::Value: AsValue,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        match self.put(e.key(), || None, |_| Some(e.clone()), g) {
+            Put::Updated { old, .. } => Some(old.val()),
+            _ => None,
+        }
+    }
+
+    fn mt_delete<Q>(&self, key: &Q, g: &Guard) -> bool
+    where
+        Q: ?Sized + Comparable<E::Key>,
+    {
+        self.remove(key, g).is_some()
+    }
+
+    fn mt_delete_return<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v E::Value>
+    where
+        Q: ?Sized + Comparable<E::Key>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        self.remove(key, g).map(TreeElement::val)
+    }
+
+    fn mt_delete_return_entry<'t, 'g, 'v, Q>(&'t self, key: &Q, g: &'g Guard) -> Option<&'v E>
+    where
+        Q: ?Sized + Comparable<E::Key>,
+        't: 'v,
+        'g: 't + 'v,
+    {
+        self.remove(key, g)
+    }
+}
+
+impl<E: TreeElement> MTIndexExt<E, E::Key, E::Value> for SkipList<E> {
+    type IterEntry<'t, 'g, 'v>
+        = IterEntry<'t, 'g, 'v, E>
+    where
+        'g: 't + 'v,
+        't: 'v,
+        E::Key: 'v,
+        E::Value: 'v,
+        E: 'v,
+        Self: 't;
+    type IterRange<'t, 'g, 'v, Q, R>
+        = IterRange<'t, 'g, 'v, E, Q, R>
+    where
+        'g: 't + 'v,
+        't: 'v,
+        E::Key: 'v + PartialOrd<Q>,
+        E::Value: 'v,
+        E: 'v,
+        R: RangeBounds<Q>,
+        Self: 't;
+    type Entry<'t, 'q, Q>
+        = Entry<'t, 'q, E, Q>
+    where
+        Q: ?Sized + 'q,
+        E: 't,
+        Self: 't;
+    fn mt_iter_entry<'t, 'g, 'v>(&'t self, g: &'g Guard) -> Self::IterEntry<'t, 'g, 'v> {
+        IterEntry::new(self, g)
+    }
+    /// Unlike the hash index, this seeks to the start of the range and returns entries in key order
+    fn mt_range<'t, 'g, 'v, Q, R>(
+        &'t self,
+        range: R,
+        g: &'g Guard,
+    ) -> Self::IterRange<'t, 'g, 'v, Q, R>
+    where
+        E::Key: 'v + PartialOrd<Q>,
+        R: RangeBounds<Q>,
+    {
+        IterRange::new(self, range, g)
+    }
+    fn mt_entry<'t, 'g, 'q, Q>(&'t self, key: &'q Q, g: &'g Guard) -> Self::Entry<'t, 'q, Q>
+    where
+        'g: 't,
+        Q: ?Sized + Comparable<E::Key>,
+    {
+        Entry {
+            t: self,
+            key,
+            g,
+            m: None,
+        }
+    }
+}
+
+/// A single entry in the skiplist that may or may not exist (see the hash index's entry for how this works)
+pub struct Entry<'t, 'q, T, Q: ?Sized, M = fn(&T) -> T> {
+    t: &'t SkipList<T>,
+    key: &'q Q,
+    g: &'t Guard,
+    m: Option<M>,
+}
+
+impl<'t, 'q, T, Q: ?Sized> Entry<'t, 'q, T, Q> {
+    /// If the element exists, replace it with what `m` returns for it once the entry is resolved
+    pub fn and_modify<M>(self, m: M) -> Entry<'t, 'q, T, Q, M>
+    where
+        M: FnMut(&T) -> T,
+    {
+        Entry {
+            t: self.t,
+            key: self.key,
+            g: self.g,
+            m: Some(m),
+        }
+    }
+}
+
+impl<'t, 'q, T, Q, M> Entry<'t, 'q, T, Q, M>
+where
+    T: TreeElement,
+    Q: ?Sized + Comparable<T::Key>,
+    M: FnMut(&T) -> T,
+{
+    /// Returns the element, if it exists
+    pub fn get(&self) -> Option<&'t T> {
+        self.t.get(self.key, self.g)
+    }
+    /// Returns the element (modified, if [`Entry::and_modify`] was used) or inserts the one returned by `f` if the key
+    /// is missing. `f` is called at most once
+    pub fn or_insert_with<F>(self, f: F) -> &'t T
+    where
+        F: FnOnce() -> T,
+    {
+        let mut f = Some(f);
+        let mut new = None;
+        let nx = || {
+            let new = new.get_or_insert_with(|| f.take().expect("logic,entry fn called twice")());
+            Some(new.clone())
+        };
+        match self.m {
+            Some(mut m) => match self.t.put(self.key, nx, |current| Some(m(current)), self.g) {
+                Put::Inserted(e) | Put::Updated { new: e, .. } => e,
+                Put::Missing | Put::Exists(_) => {
+                    unreachable!("logic,entry upsert always publishes")
+                }
+            },
+            None => match self.t.put(self.key, nx, |_| None, self.g) {
+                Put::Inserted(e) | Put::Exists(e) => e,
+                Put::Missing | Put::Updated { .. } => {
+                    unreachable!("logic,entry insert never updates")
+                }
+            },
+        }
+    }
+    /// Same as [`Entry::or_insert_with`], for when the new element is cheap to create
+    pub fn or_insert(self, new: T) -> &'t T {
+        self.or_insert_with(|| new)
+    }
+}
@@ -0,0 +1,262 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::SkipList,
+    crate::engine::{
+        idx::{IndexBaseSpec, MTIndex, MTIndexExt},
+        sync::atm::cpin,
+    },
+    std::{
+        mem,
+        sync::{Arc, Barrier},
+        thread,
+    },
+};
+
+type Skl<K, V> = SkipList<(K, V)>;
+
+const SPAM_QCOUNT: usize = if crate::util::IS_ON_CI {
+    1_024
+} else if cfg!(miri) {
+    32
+} else {
+    16_384
+};
+const SPAM_TENANTS: usize = if cfg!(miri) { 2 } else { 16 };
+
+fn keys<K: Clone, V>(idx: &Skl<K, V>) -> Vec<K>
+where
+    (K, V): crate::engine::idx::mtchm::meta::TreeElement<Key = K, Value = V>,
+{
+    idx.mt_iter_key(&cpin()).cloned().collect()
+}
+
+#[test]
+fn empty() {
+    let g = cpin();
+    let idx = Skl::<u8, u8>::idx_init();
+    assert_eq!(idx.mt_len(), 0);
+    assert!(idx.mt_get(&1, &g).is_none());
+    assert!(!idx.mt_update((1, 1), &g));
+    assert!(!idx.mt_delete(&1, &g));
+    assert_eq!(idx.mt_iter_kv(&g).count(), 0);
+}
+
+#[test]
+fn insert_get_update_delete() {
+    let g = cpin();
+    let idx = Skl::<u8, u8>::idx_init();
+    assert!(idx.mt_insert((1, 10), &g));
+    assert!(!idx.mt_insert((1, 20), &g));
+    assert_eq!(idx.mt_get(&1, &g).copied(), Some(10));
+    assert!(idx.mt_contains(&1, &g));
+    assert_eq!(idx.mt_update_return((1, 20), &g).copied(), Some(10));
+    assert_eq!(idx.mt_get_cloned(&1, &g), Some(20));
+    idx.mt_upsert((2, 30), &g);
+    idx.mt_upsert((2, 40), &g);
+    assert_eq!(idx.mt_len(), 2);
+    assert_eq!(idx.mt_delete_return(&1, &g).copied(), Some(20));
+    assert!(idx.mt_get(&1, &g).is_none());
+    assert_eq!(idx.mt_delete_return_entry(&2, &g), Some(&(2, 40)));
+    assert_eq!(idx.mt_len(), 0);
+    // should be able to reinsert deleted keys
+    assert!(idx.mt_insert((1, 50), &g));
+    assert_eq!(idx.mt_get(&1, &g).copied(), Some(50));
+}
+
+#[test]
+fn ordered_iter() {
+    let g = cpin();
+    let idx = Skl::<usize, usize>::idx_init();
+    // insert in a scrambled order
+    for i in (0..SPAM_QCOUNT).map(|i| (i * 7919) % SPAM_QCOUNT) {
+        assert!(idx.mt_insert((i, i), &g));
+    }
+    assert_eq!(keys(&idx), (0..SPAM_QCOUNT).collect::<Vec<_>>());
+    // drop the odd ones
+    (1..SPAM_QCOUNT)
+        .step_by(2)
+        .for_each(|i| assert!(idx.mt_delete(&i, &g)));
+    assert_eq!(keys(&idx), (0..SPAM_QCOUNT).step_by(2).collect::<Vec<_>>());
+    assert_eq!(idx.mt_len(), SPAM_QCOUNT / 2);
+    idx.mt_clear(&g);
+    assert_eq!(idx.mt_len(), 0);
+    assert!(keys(&idx).is_empty());
+}
+
+#[test]
+fn range() {
+    let g = cpin();
+    let idx = Skl::<usize, usize>::idx_init();
+    (0..100)
+        .step_by(2)
+        .for_each(|i| assert!(idx.mt_insert((i, i), &g)));
+    let range = |r: (std::ops::Bound<usize>, std::ops::Bound<usize>)| {
+        idx.mt_range(r, &g).map(|(k, _)| *k).collect::<Vec<_>>()
+    };
+    use std::ops::Bound::{Excluded, Included, Unbounded};
+    assert_eq!(
+        range((Included(10), Included(20))),
+        [10, 12, 14, 16, 18, 20]
+    );
+    assert_eq!(range((Excluded(10), Excluded(20))), [12, 14, 16, 18]);
+    assert_eq!(range((Included(11), Excluded(17))), [12, 14, 16]);
+    assert_eq!(range((Unbounded, Excluded(6))), [0, 2, 4]);
+    assert_eq!(range((Included(94), Unbounded)), [94, 96, 98]);
+    assert!(range((Included(200), Unbounded)).is_empty());
+    assert_eq!(idx.mt_range(.., &g).count(), 50);
+}
+
+#[test]
+fn entry() {
+    let g = cpin();
+    let idx = Skl::<u8, u8>::idx_init();
+    assert!(idx.mt_entry(&1, &g).get().is_none());
+    assert_eq!(idx.mt_entry(&1, &g).or_insert((1, 1)), &(1, 1));
+    assert_eq!(
+        idx.mt_entry(&1, &g)
+            .or_insert_with(|| panic!("called insert fn for existing key")),
+        &(1, 1)
+    );
+    assert_eq!(
+        idx.mt_entry(&1, &g)
+            .and_modify(|&(k, v)| (k, v + 1))
+            .or_insert((1, 0)),
+        &(1, 2)
+    );
+    assert_eq!(idx.mt_len(), 1);
+}
+
+#[test]
+fn bulk_load() {
+    let g = cpin();
+    let idx = Skl::<usize, usize>::mt_bulk_load((0..100).rev().map(|i| (i, i)), &g);
+    assert_eq!(keys(&idx), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn drop_releases_everything() {
+    // deletes and updates go through the collector, so only look at what the drop itself is responsible for
+    let v = Arc::new(String::from("hello"));
+    {
+        let g = cpin();
+        let idx = Skl::<usize, Arc<String>>::idx_init();
+        (0..SPAM_QCOUNT).for_each(|i| assert!(idx.mt_insert((i, v.clone()), &g)));
+        assert_eq!(Arc::strong_count(&v), SPAM_QCOUNT + 1);
+    }
+    assert_eq!(Arc::strong_count(&v), 1);
+}
+
+#[test]
+fn multispam_insert_delete() {
+    let idx = Arc::new(Skl::<usize, usize>::idx_init());
+    let barrier = Arc::new(Barrier::new(SPAM_TENANTS));
+    let handles: Vec<_> = (0..SPAM_TENANTS)
+        .map(|tid| {
+            let idx = idx.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                let g = cpin();
+                barrier.wait();
+                // every thread inserts its own keys (interleaved with everyone else's) and deletes half of them
+                let mine = (tid..SPAM_QCOUNT).step_by(SPAM_TENANTS);
+                mine.clone()
+                    .for_each(|k| assert!(idx.mt_insert((k, tid), &g)));
+                mine.step_by(2).for_each(|k| assert!(idx.mt_delete(&k, &g)));
+            })
+        })
+        .collect();
+    handles.into_iter().for_each(|h| h.join().unwrap());
+    let g = cpin();
+    let expected: Vec<usize> = (0..SPAM_QCOUNT)
+        .filter(|k| (k / SPAM_TENANTS) % 2 == 1)
+        .collect();
+    assert_eq!(keys(&idx), expected);
+    assert_eq!(idx.mt_len(), expected.len());
+    assert!(expected
+        .iter()
+        .all(|k| idx.mt_get(k, &g).copied() == Some(k % SPAM_TENANTS)));
+}
+
+#[test]
+fn multispam_contended() {
+    // everyone fights over the same keys, while readers check that iteration stays ordered
+    let idx = Arc::new(Skl::<usize, usize>::idx_init());
+    let barrier = Arc::new(Barrier::new(SPAM_TENANTS));
+    let handles: Vec<_> = (0..SPAM_TENANTS)
+        .map(|tid| {
+            let idx = idx.clone();
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                barrier.wait();
+                for round in 0..4 {
+                    let g = cpin();
+                    if tid % 4 == 0 {
+                        let keys: Vec<usize> = idx.mt_iter_key(&g).copied().collect();
+                        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+                        continue;
+                    }
+                    for k in 0..SPAM_QCOUNT / 8 {
+                        match (k + tid + round) % 3 {
+                            0 => {
+                                let _ = idx.mt_insert((k, k), &g);
+                            }
+                            1 => {
+                                let _ = idx.mt_delete(&k, &g);
+                            }
+                            _ => {
+                                idx.mt_entry(&k, &g)
+                                    .and_modify(|&(k, v)| (k, v))
+                                    .or_insert((k, k));
+                            }
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    handles.into_iter().for_each(|h| h.join().unwrap());
+    let g = cpin();
+    let keys = keys(&idx);
+    assert!(keys.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(keys.len(), idx.mt_len());
+    assert!(keys.iter().all(|k| idx.mt_get(k, &g).copied() == Some(*k)));
+}
+
+#[test]
+fn capacity_and_compact() {
+    let g = cpin();
+    let idx = Skl::<usize, usize>::idx_init();
+    let empty = idx.mt_capacity_bytes(&g);
+    (0..SPAM_QCOUNT).for_each(|i| assert!(idx.mt_insert((i, i), &g)));
+    assert_eq!(idx.mt_len_approx(), SPAM_QCOUNT);
+    assert!(idx.mt_capacity_bytes(&g) >= empty + SPAM_QCOUNT * mem::size_of::<(usize, usize)>());
+    (0..SPAM_QCOUNT).for_each(|i| assert!(idx.mt_delete(&i, &g)));
+    idx.mt_compact();
+    assert_eq!(idx.mt_len_approx(), 0);
+    assert_eq!(idx.mt_capacity_bytes(&g), empty);
+}
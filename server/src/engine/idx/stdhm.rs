@@ -99,6 +99,10 @@ where
         self.len()
     }
 
+    fn st_capacity(&self) -> usize {
+        self.capacity()
+    }
+
     fn st_clear(&mut self) {
         self.clear()
     }
@@ -30,6 +30,7 @@ use {
     },
     crate::engine::idx::{AsKey, AsValue},
     std::{
+        borrow::Borrow,
         collections::{
             hash_map::{Iter as StdMapIter, Keys as StdMapIterKey, Values as StdMapIterVal},
             HashMap as StdMap,
@@ -401,6 +402,49 @@ impl<'a, K: 'a, V: 'a, C: IndexSTSeqDllIterOrdConfig<K, V>> IndexSTSeqDllIterOrd
             _l: PhantomData,
         }
     }
+    /// Only yield the entries at positions `start..end` of the sequence (`start <= end <= idx.len()`)
+    #[allow(unused)]
+    fn new_range<Mc: Config<K, V>>(
+        idx: &'a IndexSTSeqDll<K, V, Mc>,
+        start: usize,
+        end: usize,
+    ) -> Self {
+        if idx.h.is_null() {
+            return Self::new(idx);
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): nullck; the caller has checked the bounds
+            Self {
+                h: Self::nth(idx, start),
+                t: Self::nth(idx, end),
+                r: end - start,
+                _l: PhantomData,
+            }
+        }
+    }
+    /// Returns the node at position `n` of the sequence, walking from whichever end is closer. Position `idx.len()`
+    /// is the sentinel
+    ///
+    /// ## Safety
+    /// The head must be non-null and `n <= idx.len()`
+    #[allow(unused)]
+    unsafe fn nth<Mc: Config<K, V>>(
+        idx: &'a IndexSTSeqDll<K, V, Mc>,
+        n: usize,
+    ) -> *const IndexSTSeqDllNode<K, V> {
+        let len = idx.len();
+        let mut node = idx.h as *const IndexSTSeqDllNode<K, V>;
+        if n < len / 2 {
+            for _ in 0..=n {
+                node = (*node).p;
+            }
+        } else {
+            for _ in n..len {
+                node = (*node).n;
+            }
+        }
+        node
+    }
     #[inline(always)]
     fn _next(&mut self) -> Option<C::Ret<'a>> {
         if self.h == self.t {
@@ -487,6 +531,16 @@ impl<'a, K: 'a, V: 'a> IndexSTSeqDllIterOrdKV<'a, K, V> {
             i: IndexSTSeqDllIterOrdBase::new(arg),
         }
     }
+    #[allow(unused)]
+    pub(super) fn new_range<C: Config<K, V>>(
+        arg: &'a IndexSTSeqDll<K, V, C>,
+        start: usize,
+        end: usize,
+    ) -> Self {
+        Self {
+            i: IndexSTSeqDllIterOrdBase::new_range(arg, start, end),
+        }
+    }
 }
 
 // UNSAFE(@ohsayan): aliasing guarantees correctness
@@ -605,3 +659,52 @@ impl<'a, K, V> Clone for IndexSTSeqDllIterOrdValue<'a, K, V> {
         Self { i: self.i.clone() }
     }
 }
+
+#[derive(Debug)]
+pub struct IndexSTSeqDllIterOrdPrefix<'a, 'p, K: 'a, V: 'a> {
+    i: IndexSTSeqDllIterOrdKV<'a, K, V>,
+    p: &'p str,
+}
+impl<'a, 'p, K: 'a, V: 'a> IndexSTSeqDllIterOrdPrefix<'a, 'p, K, V> {
+    #[allow(unused)]
+    pub(super) fn new<C: Config<K, V>>(arg: &'a IndexSTSeqDll<K, V, C>, p: &'p str) -> Self {
+        Self {
+            i: IndexSTSeqDllIterOrdKV::new(arg),
+            p,
+        }
+    }
+}
+
+impl<'a, 'p, K: 'a + Borrow<str>, V: 'a> Iterator for IndexSTSeqDllIterOrdPrefix<'a, 'p, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let p = self.p;
+        self.i.find(|(k, _)| (*k).borrow().starts_with(p))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.i.len()))
+    }
+}
+
+impl<'a, 'p, K: 'a + Borrow<str>, V: 'a> DoubleEndedIterator
+    for IndexSTSeqDllIterOrdPrefix<'a, 'p, K, V>
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let p = self.p;
+        self.i.rfind(|(k, _)| (*k).borrow().starts_with(p))
+    }
+}
+
+impl<'a, 'p, K: 'a + Borrow<str>, V: 'a> FusedIterator
+    for IndexSTSeqDllIterOrdPrefix<'a, 'p, K, V>
+{
+}
+
+impl<'a, 'p, K, V> Clone for IndexSTSeqDllIterOrdPrefix<'a, 'p, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            i: self.i.clone(),
+            p: self.p,
+        }
+    }
+}
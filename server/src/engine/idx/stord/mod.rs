@@ -31,8 +31,9 @@ use {
     self::{
         config::{AllocStrategy, Config},
         iter::{
-            IndexSTSeqDllIterOrdKV, IndexSTSeqDllIterOrdKey, IndexSTSeqDllIterOrdValue,
-            IndexSTSeqDllIterUnordKV, IndexSTSeqDllIterUnordKey, IndexSTSeqDllIterUnordValue,
+            IndexSTSeqDllIterOrdKV, IndexSTSeqDllIterOrdKey, IndexSTSeqDllIterOrdPrefix,
+            IndexSTSeqDllIterOrdValue, IndexSTSeqDllIterUnordKV, IndexSTSeqDllIterUnordKey,
+            IndexSTSeqDllIterUnordValue,
         },
     },
     super::{AsKey, AsKeyClone, AsValue, AsValueClone, IndexBaseSpec, STIndex, STIndexSeq},
@@ -44,6 +45,7 @@ use {
         fmt::{self, Debug},
         hash::{Hash, Hasher},
         mem,
+        ops::{Bound, RangeBounds},
         ptr::{self, NonNull},
     },
 };
@@ -594,6 +596,10 @@ where
         self.len()
     }
 
+    fn st_capacity(&self) -> usize {
+        self.m.capacity()
+    }
+
     fn st_clear(&mut self) {
         self._clear()
     }
@@ -724,9 +730,35 @@ where
     type OwnedIterKV = iter::OrderedOwnedIteratorKV<K, V>;
     type OwnedIterKeys = iter::OrderedOwnedIteratorKey<K, V>;
     type OwnedIterValues = iter::OrderedOwnedIteratorValue<K, V>;
+    type IterOrdPrefixKV<'a, 'p> = IndexSTSeqDllIterOrdPrefix<'a, 'p, K, V>
+    where
+        Self: 'a,
+        K: 'a + Borrow<str>,
+        V: 'a;
     fn stseq_ord_kv<'a>(&'a self) -> Self::IterOrdKV<'a> {
         self._iter_ord_kv()
     }
+    fn stseq_range<'a>(&'a self, range: impl RangeBounds<usize>) -> Self::IterOrdKV<'a> {
+        let end = match range.end_bound() {
+            Bound::Included(&e) => e.saturating_add(1),
+            Bound::Excluded(&e) => e,
+            Bound::Unbounded => self.len(),
+        }
+        .min(self.len());
+        let start = match range.start_bound() {
+            Bound::Included(&s) => s,
+            Bound::Excluded(&s) => s.saturating_add(1),
+            Bound::Unbounded => 0,
+        }
+        .min(end);
+        IndexSTSeqDllIterOrdKV::new_range(self, start, end)
+    }
+    fn stseq_prefix_kv<'a, 'p>(&'a self, prefix: &'p str) -> Self::IterOrdPrefixKV<'a, 'p>
+    where
+        K: Borrow<str>,
+    {
+        IndexSTSeqDllIterOrdPrefix::new(self, prefix)
+    }
     fn stseq_ord_key<'a>(&'a self) -> Self::IterOrdKey<'a> {
         self._iter_ord_k()
     }
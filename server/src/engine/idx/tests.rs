@@ -184,4 +184,197 @@ mod idx_st_seq_dll {
                 assert_eq!((i + 1).to_string(), v);
             });
     }
+    #[test]
+    fn iter_range() {
+        let idx = mkidx();
+        fn keys<'a>(it: impl Iterator<Item = (&'a usize, &'a String)>) -> Vec<usize> {
+            it.map(|(k, _)| *k).collect()
+        }
+        // from both ends
+        assert_eq!(keys(idx.stseq_range(1..4)), [1, 2, 3]);
+        assert_eq!(
+            keys(idx.stseq_range(SPAM_CNT - 3..=SPAM_CNT - 1)),
+            [SPAM_CNT - 3, SPAM_CNT - 2, SPAM_CNT - 1]
+        );
+        assert_eq!(keys(idx.stseq_range(..2)), [0, 1]);
+        assert_eq!(idx.stseq_range(..).len(), SPAM_CNT);
+        assert_eq!(
+            keys(idx.stseq_range(SPAM_CNT / 2..SPAM_CNT / 2 + 2).rev()),
+            [SPAM_CNT / 2 + 1, SPAM_CNT / 2]
+        );
+        // clamped
+        assert_eq!(idx.stseq_range(SPAM_CNT - 1..SPAM_CNT + 10).len(), 1);
+        assert_eq!(idx.stseq_range(SPAM_CNT + 1..).len(), 0);
+        assert_eq!(idx.stseq_range(5..2).len(), 0);
+        // empty
+        let empty = IndexSTSeqLib::<usize, String>::idx_init();
+        assert_eq!(empty.stseq_range(0..10).count(), 0);
+    }
+    #[test]
+    fn iter_prefix() {
+        let mut idx = Index::idx_init();
+        for k in ["user_id", "username", "password", "user_pfp", "u"] {
+            assert!(idx.st_insert(s(k), s(k)));
+        }
+        let keys = |prefix| {
+            idx.stseq_prefix_kv(prefix)
+                .map(|(k, _)| k.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys("user"), ["user_id", "username", "user_pfp"]);
+        assert_eq!(keys("user_"), ["user_id", "user_pfp"]);
+        assert_eq!(keys("x"), [] as [&str; 0]);
+        assert_eq!(keys("").len(), 5);
+        assert_eq!(
+            idx.stseq_prefix_kv("user")
+                .rev()
+                .map(|(k, _)| k.as_str())
+                .collect::<Vec<_>>(),
+            ["user_pfp", "username", "user_id"]
+        );
+    }
+}
+
+mod fts {
+    use crate::engine::idx::fts::{self, TextIndex};
+    fn words(text: &str) -> Vec<Box<str>> {
+        fts::tokenize(text)
+    }
+    fn search(idx: &TextIndex<u64>, text: &str) -> Vec<u64> {
+        let mut keys = idx.search(&words(text));
+        keys.sort_unstable();
+        keys
+    }
+    #[test]
+    fn tokenize() {
+        assert_eq!(
+            words("Disk full: /dev/sda1 is FULL!"),
+            ["dev", "disk", "full", "is", "sda1"].map(Box::from)
+        );
+        assert!(words(" , ;-").is_empty());
+        assert!(fts::contains_all(
+            "Disk full on node-3",
+            &words("NODE full")
+        ));
+        assert!(!fts::contains_all(
+            "Disk full on node-3",
+            &words("node full 4")
+        ));
+    }
+    #[test]
+    fn index_and_search() {
+        let idx = TextIndex::new();
+        idx.index(&1, Some("disk full on node 1"));
+        idx.index(&2, Some("disk failure on node 2"));
+        idx.index(&3, Some("network partition"));
+        idx.index(&4, None);
+        assert_eq!(idx.len(), 3);
+        assert_eq!(search(&idx, "disk"), [1, 2]);
+        assert_eq!(search(&idx, "node disk full"), [1]);
+        assert_eq!(search(&idx, "disk partition"), [] as [u64; 0]);
+        assert_eq!(search(&idx, "nope"), [] as [u64; 0]);
+        assert_eq!(idx.estimate(&words("disk partition")), 1);
+        assert_eq!(idx.estimate(&words("disk nope")), 0);
+    }
+    #[test]
+    fn reindex_and_remove() {
+        let idx = TextIndex::new();
+        idx.index(&1, Some("disk full"));
+        idx.index(&2, Some("disk failure"));
+        idx.index(&1, Some("network partition"));
+        assert_eq!(search(&idx, "disk"), [2]);
+        assert_eq!(search(&idx, "full"), [] as [u64; 0]);
+        assert_eq!(search(&idx, "partition"), [1]);
+        idx.remove(&2);
+        assert_eq!(search(&idx, "disk"), [] as [u64; 0]);
+        idx.index(&1, Some(""));
+        assert_eq!(idx.len(), 0);
+        idx.index(&3, Some("disk"));
+        idx.clear();
+        assert_eq!(search(&idx, "disk"), [] as [u64; 0]);
+    }
+}
+
+mod geo {
+    use crate::engine::idx::geo::{self, GeoIndex};
+    const PARIS: (f64, f64) = (48.8566, 2.3522);
+    const VERSAILLES: (f64, f64) = (48.8049, 2.1204);
+    const LONDON: (f64, f64) = (51.5072, -0.1276);
+    fn search(idx: &GeoIndex<u64>, center: (f64, f64), radius: f64) -> Vec<u64> {
+        let mut keys = idx.search(center, radius);
+        keys.sort_unstable();
+        keys
+    }
+    #[test]
+    fn parse_point() {
+        assert_eq!(geo::parse_point("48.8566, 2.3522"), Some(PARIS));
+        assert_eq!(geo::parse_point("-90,180"), Some((-90.0, 180.0)));
+        for bad in [
+            "", "48.8566", "48.8566,", "90.5,0", "0,-181", "a,b", "1,2,3",
+        ] {
+            assert_eq!(geo::parse_point(bad), None);
+        }
+    }
+    #[test]
+    fn distance() {
+        assert_eq!(geo::distance(PARIS, PARIS), 0.0);
+        let d = geo::distance(PARIS, LONDON);
+        assert!((343_000.0..345_000.0).contains(&d));
+        assert_eq!(d, geo::distance(LONDON, PARIS));
+        // across the antimeridian
+        let d = geo::distance((0.0, 179.5), (0.0, -179.5));
+        assert!((111_000.0..111_400.0).contains(&d));
+    }
+    #[test]
+    fn cover() {
+        // every point in the circle is in one of the cells
+        for (center, point, radius) in [
+            (PARIS, VERSAILLES, 20_000.0),
+            (PARIS, LONDON, 400_000.0),
+            ((0.0, 179.9), (0.0, -179.9), 50_000.0),
+            ((89.9, 0.0), (89.9, 180.0), 50_000.0),
+            (PARIS, PARIS, 0.0),
+        ] {
+            assert!(geo::distance(center, point) <= radius);
+            let cells = geo::cover(center, radius);
+            assert!(cells.len() as u64 <= 16);
+            let hash = geo::geohash(point);
+            assert!(cells
+                .iter()
+                .any(|(start, end)| (*start..*end).contains(&hash)));
+        }
+    }
+    #[test]
+    fn index_and_search() {
+        let idx = GeoIndex::new();
+        idx.index(&1, Some(PARIS));
+        idx.index(&2, Some(VERSAILLES));
+        idx.index(&3, Some(LONDON));
+        idx.index(&4, Some((0.0, 179.9)));
+        idx.index(&5, None);
+        assert_eq!(idx.len(), 4);
+        assert_eq!(search(&idx, PARIS, 1_000.0), [1]);
+        assert_eq!(search(&idx, PARIS, 20_000.0), [1, 2]);
+        assert_eq!(search(&idx, PARIS, 400_000.0), [1, 2, 3]);
+        assert_eq!(search(&idx, (0.0, -179.9), 50_000.0), [4]);
+        assert_eq!(search(&idx, (0.0, 0.0), 1_000.0), [] as [u64; 0]);
+        assert!(idx.estimate(PARIS, 20_000.0) >= 2);
+        assert_eq!(idx.estimate((0.0, 0.0), 1_000.0), 0);
+    }
+    #[test]
+    fn reindex_and_remove() {
+        let idx = GeoIndex::new();
+        idx.index(&1, Some(PARIS));
+        idx.index(&2, Some(VERSAILLES));
+        idx.index(&1, Some(LONDON));
+        assert_eq!(search(&idx, PARIS, 20_000.0), [2]);
+        assert_eq!(search(&idx, LONDON, 1_000.0), [1]);
+        idx.remove(&2);
+        assert_eq!(search(&idx, PARIS, 20_000.0), [] as [u64; 0]);
+        idx.index(&1, None);
+        assert_eq!(idx.len(), 0);
+        idx.index(&3, Some(PARIS));
+        idx.clear();
+        assert_eq!(search(&idx, PARIS, 1_000.0), [] as [u64; 0]);
+    }
 }
@@ -0,0 +1,384 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! The server's logger
+//!
+//! Records are filtered by module, using directives of the form `[module=]level` separated by commas (for example,
+//! `info,skyd::engine::net=debug`). The directives are read from `SKY_LOG` on startup and can be replaced at runtime
+//! with `sysctl log level`. Records are written to stderr as text or, if `SKY_LOG_FORMAT` is set to `json`, as one JSON
+//! object per line for log shippers. Records that are logged while a query is run carry the ids of its connection and
+//! of the query itself
+
+#[cfg(test)]
+mod tests;
+
+use {
+    core::{
+        cell::Cell,
+        fmt::{self, Write},
+        time::Duration,
+    },
+    log::{Level, LevelFilter, Log, Metadata, Record},
+    parking_lot::RwLock,
+    std::{
+        env,
+        io::{self, Write as _},
+        sync::OnceLock,
+        time::SystemTime,
+    },
+};
+
+/// The variable that the filter directives are read from
+const ENV_FILTER: &str = "SKY_LOG";
+/// The variable that the output format is read from
+const ENV_FORMAT: &str = "SKY_LOG_FORMAT";
+/// The level of records that no directive applies to, if a default isn't set
+const FALLBACK_LEVEL: LevelFilter = LevelFilter::Error;
+/// The filter that is used if `SKY_LOG` isn't set
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/*
+    filter
+*/
+
+/// Per-module levels. A record is filtered by the directive for the longest module that its target is in, and by the
+/// default level if there is no such directive
+#[derive(Debug, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    /// sorted by length (longest first)
+    modules: Vec<(Box<str>, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            modules: vec![],
+        }
+    }
+    /// Parse a list of directives, returning `None` if any of them is invalid. A directive without a level enables all
+    /// records for the module, and records that no directive applies to are only logged if they are errors (unless a
+    /// default level is set)
+    pub fn parse(directives: &str) -> Option<Self> {
+        let mut default = FALLBACK_LEVEL;
+        let mut modules = vec![];
+        for directive in directives.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let module = module.trim();
+                    if module.is_empty() {
+                        return None;
+                    }
+                    modules.push((module.into(), level.trim().parse().ok()?));
+                }
+                None => match directive.parse() {
+                    Ok(level) => default = level,
+                    Err(_) => modules.push((directive.into(), LevelFilter::Trace)),
+                },
+            }
+        }
+        // the last directive for a module wins
+        modules.reverse();
+        modules
+            .sort_by(|(a, _): &(Box<str>, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        modules.dedup_by(|(a, _), (b, _)| a == b);
+        Some(Self { default, modules })
+    }
+    /// The most verbose level that records for this target are logged at
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .find(|(module, _)| {
+                target
+                    .strip_prefix(&**module)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+    pub fn enabled(&self, target: &str, level: Level) -> bool {
+        level <= self.level_for(target)
+    }
+    /// The most verbose level that any record is logged at
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+/*
+    correlation
+*/
+
+thread_local! {
+    static CORRELATION: Cell<Correlation> = const { Cell::new(Correlation::none()) };
+}
+
+/// The connection and the query that records belong to
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Correlation {
+    conn: Option<u64>,
+    query: Option<u64>,
+}
+
+impl Correlation {
+    pub const fn none() -> Self {
+        Self {
+            conn: None,
+            query: None,
+        }
+    }
+    pub const fn connection(conn: u64) -> Self {
+        Self {
+            conn: Some(conn),
+            query: None,
+        }
+    }
+    pub const fn query(conn: u64, query: u64) -> Self {
+        Self {
+            conn: Some(conn),
+            query: Some(query),
+        }
+    }
+    /// The correlation of the records that are logged on this thread
+    pub fn current() -> Self {
+        CORRELATION.with(Cell::get)
+    }
+    /// Run `f`, attaching this correlation to every record that it logs
+    ///
+    /// The correlation is thread local, so work that is moved to another thread (like a blocking query) has to be run
+    /// in a scope of its own. Similarly, the scope must not span an `await`
+    pub fn scope<T>(self, f: impl FnOnce() -> T) -> T {
+        struct Restore(Correlation);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CORRELATION.with(|c| c.set(self.0))
+            }
+        }
+        let _restore = Restore(CORRELATION.with(|c| c.replace(self)));
+        f()
+    }
+}
+
+/*
+    output
+*/
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LogFormat {
+    /// `[<time> <level> <target> conn=<id> query=<id>] <message>`
+    Text,
+    /// `{"time":"<time>","level":"<level>","target":"<target>","conn":<id>,"query":<id>,"msg":"<message>"}`
+    Json,
+}
+
+impl LogFormat {
+    fn parse(format: &str) -> Option<Self> {
+        if format.eq_ignore_ascii_case("text") {
+            Some(Self::Text)
+        } else if format.eq_ignore_ascii_case("json") {
+            Some(Self::Json)
+        } else {
+            None
+        }
+    }
+}
+
+/// Formats the time since the UNIX epoch as an RFC 3339 UTC timestamp (with milliseconds)
+struct Timestamp(Duration);
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let (days, secs_of_day) = (secs / 86400, secs % 86400);
+        // civil date from days since the epoch (see Howard Hinnant's `civil_from_days`)
+        let z = days + 719468;
+        let (era, doe) = (z / 146097, z % 146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + (month <= 2) as u64;
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+            secs_of_day / 3600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60,
+            self.0.subsec_millis()
+        )
+    }
+}
+
+/// Formats a string as a JSON string
+struct JsonStr<'a>(&'a str);
+
+impl<'a> fmt::Display for JsonStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// Format a record as a single line
+fn format_record(
+    out: &mut String,
+    format: LogFormat,
+    time: Duration,
+    level: Level,
+    target: &str,
+    correlation: Correlation,
+    msg: &fmt::Arguments,
+) -> fmt::Result {
+    match format {
+        LogFormat::Text => {
+            write!(out, "[{} {level:<5} {target}", Timestamp(time))?;
+            if let Some(conn) = correlation.conn {
+                write!(out, " conn={conn}")?;
+            }
+            if let Some(query) = correlation.query {
+                write!(out, " query={query}")?;
+            }
+            writeln!(out, "] {msg}")
+        }
+        LogFormat::Json => {
+            write!(
+                out,
+                "{{\"time\":\"{}\",\"level\":\"{level}\",\"target\":{}",
+                Timestamp(time),
+                JsonStr(target)
+            )?;
+            if let Some(conn) = correlation.conn {
+                write!(out, ",\"conn\":{conn}")?;
+            }
+            if let Some(query) = correlation.query {
+                write!(out, ",\"query\":{query}")?;
+            }
+            writeln!(out, ",\"msg\":{}}}", JsonStr(&msg.to_string()))
+        }
+    }
+}
+
+/*
+    logger
+*/
+
+struct Logger {
+    filter: RwLock<LogFilter>,
+    format: LogFormat,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.filter
+            .read()
+            .enabled(metadata.target(), metadata.level())
+    }
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut line = String::new();
+        if format_record(
+            &mut line,
+            self.format,
+            time,
+            record.level(),
+            record.target(),
+            Correlation::current(),
+            record.args(),
+        )
+        .is_ok()
+        {
+            // there's nowhere to report a failure to log
+            let _ = io::stderr().lock().write_all(line.as_bytes());
+        }
+    }
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+/// Set up the logger (using `SKY_LOG` and `SKY_LOG_FORMAT`). Nothing is logged until this is called
+pub fn init() {
+    let filter_var = env::var(ENV_FILTER).ok();
+    let format_var = env::var(ENV_FORMAT).ok();
+    let filter = filter_var
+        .as_deref()
+        .map_or(Some(LogFilter::new(DEFAULT_LEVEL)), LogFilter::parse);
+    let format = format_var
+        .as_deref()
+        .map_or(Some(LogFormat::Text), LogFormat::parse);
+    let (filter_invalid, format_invalid) = (filter.is_none(), format.is_none());
+    let logger = LOGGER.get_or_init(|| Logger {
+        filter: RwLock::new(filter.unwrap_or_else(|| LogFilter::new(DEFAULT_LEVEL))),
+        format: format.unwrap_or(LogFormat::Text),
+    });
+    log::set_max_level(logger.filter.read().max_level());
+    log::set_logger(logger).expect("the logger was already set");
+    if let (true, Some(directives)) = (filter_invalid, filter_var) {
+        warn!("ignoring invalid log filter `{directives}` in `{ENV_FILTER}`");
+    }
+    if let (true, Some(format)) = (format_invalid, format_var) {
+        warn!("ignoring unknown log format `{format}` in `{ENV_FORMAT}`");
+    }
+}
+
+/// Replace the filter directives (see [`LogFilter::parse`]). Returns false, leaving the current filter in place, if
+/// they are invalid
+pub fn set_filter(directives: &str) -> bool {
+    let Some(filter) = LogFilter::parse(directives) else {
+        return false;
+    };
+    if let Some(logger) = LOGGER.get() {
+        let mut current = logger.filter.write();
+        log::set_max_level(filter.max_level());
+        *current = filter;
+    }
+    true
+}
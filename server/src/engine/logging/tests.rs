@@ -0,0 +1,211 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{format_record, Correlation, JsonStr, LogFilter, LogFormat, Timestamp},
+    core::time::Duration,
+    log::{Level, LevelFilter},
+};
+
+/*
+    filter
+*/
+
+#[test]
+fn filter_default_level() {
+    let filter = LogFilter::parse("debug").unwrap();
+    assert_eq!(filter.level_for("skyd::engine::net"), LevelFilter::Debug);
+    assert!(filter.enabled("skyd", Level::Debug));
+    assert!(!filter.enabled("skyd", Level::Trace));
+    assert_eq!(filter.max_level(), LevelFilter::Debug);
+}
+
+#[test]
+fn filter_without_default_only_logs_errors() {
+    let filter = LogFilter::parse("skyd::engine::net=trace").unwrap();
+    assert_eq!(filter.level_for("skyd::engine::core"), LevelFilter::Error);
+    assert_eq!(filter.level_for("skyd::engine::net"), LevelFilter::Trace);
+    assert_eq!(filter.max_level(), LevelFilter::Trace);
+    assert_eq!(
+        LogFilter::parse("").unwrap(),
+        LogFilter::new(LevelFilter::Error)
+    );
+}
+
+#[test]
+fn filter_longest_module_wins() {
+    let filter = LogFilter::parse(
+        "info, skyd::engine=warn, skyd::engine::net=debug, skyd::engine::net::protocol=off",
+    )
+    .unwrap();
+    assert_eq!(filter.level_for("skyd::util"), LevelFilter::Info);
+    assert_eq!(
+        filter.level_for("skyd::engine::core::dml"),
+        LevelFilter::Warn
+    );
+    assert_eq!(filter.level_for("skyd::engine::net"), LevelFilter::Debug);
+    assert_eq!(
+        filter.level_for("skyd::engine::net::protocol"),
+        LevelFilter::Off
+    );
+    assert_eq!(
+        filter.level_for("skyd::engine::net::protocol::handshake"),
+        LevelFilter::Off
+    );
+    assert_eq!(filter.max_level(), LevelFilter::Debug);
+}
+
+#[test]
+fn filter_matches_whole_modules() {
+    let filter = LogFilter::parse("warn,skyd::engine::net=trace").unwrap();
+    assert_eq!(filter.level_for("skyd::engine::netx"), LevelFilter::Warn);
+    assert_eq!(filter.level_for("skyd::engine::ne"), LevelFilter::Warn);
+}
+
+#[test]
+fn filter_bare_module_and_repeats() {
+    let filter = LogFilter::parse("skyd::engine::net,skyd=info,skyd=warn").unwrap();
+    assert_eq!(filter.level_for("skyd::engine::net"), LevelFilter::Trace);
+    assert_eq!(filter.level_for("skyd::engine"), LevelFilter::Warn);
+}
+
+#[test]
+fn filter_invalid() {
+    assert_eq!(LogFilter::parse("skyd=loud"), None);
+    assert_eq!(LogFilter::parse("=info"), None);
+    assert_eq!(LogFilter::parse("info,skyd="), None);
+}
+
+/*
+    correlation
+*/
+
+#[test]
+fn correlation_scope() {
+    assert_eq!(Correlation::current(), Correlation::none());
+    let ret = Correlation::connection(1).scope(|| {
+        assert_eq!(Correlation::current(), Correlation::connection(1));
+        Correlation::query(1, 2)
+            .scope(|| assert_eq!(Correlation::current(), Correlation::query(1, 2)));
+        assert_eq!(Correlation::current(), Correlation::connection(1));
+        100
+    });
+    assert_eq!(ret, 100);
+    assert_eq!(Correlation::current(), Correlation::none());
+}
+
+#[test]
+fn correlation_scope_restored_on_panic() {
+    let r = std::panic::catch_unwind(|| Correlation::connection(1).scope(|| panic!("oops")));
+    assert!(r.is_err());
+    assert_eq!(Correlation::current(), Correlation::none());
+}
+
+/*
+    output
+*/
+
+#[test]
+fn timestamp() {
+    assert_eq!(
+        Timestamp(Duration::ZERO).to_string(),
+        "1970-01-01T00:00:00.000Z"
+    );
+    assert_eq!(
+        Timestamp(Duration::from_millis(1_700_000_000_123)).to_string(),
+        "2023-11-14T22:13:20.123Z"
+    );
+    // leap day
+    assert_eq!(
+        Timestamp(Duration::from_secs(951_782_400)).to_string(),
+        "2000-02-29T00:00:00.000Z"
+    );
+    assert_eq!(
+        Timestamp(Duration::from_secs(951_868_799)).to_string(),
+        "2000-02-29T23:59:59.000Z"
+    );
+}
+
+#[test]
+fn json_escape() {
+    assert_eq!(JsonStr("hello").to_string(), "\"hello\"");
+    assert_eq!(
+        JsonStr("a \"quoted\" \\path\\\n\tnext\r\u{1}").to_string(),
+        "\"a \\\"quoted\\\" \\\\path\\\\\\n\\tnext\\r\\u0001\""
+    );
+    assert_eq!(JsonStr("ünïcødé").to_string(), "\"ünïcødé\"");
+}
+
+fn format(format: LogFormat, correlation: Correlation, msg: &str) -> String {
+    let mut line = String::new();
+    format_record(
+        &mut line,
+        format,
+        Duration::from_millis(1_700_000_000_123),
+        Level::Warn,
+        "skyd::engine::net",
+        correlation,
+        &format_args!("{msg}"),
+    )
+    .unwrap();
+    line
+}
+
+#[test]
+fn format_text() {
+    assert_eq!(
+        format(LogFormat::Text, Correlation::none(), "hello"),
+        "[2023-11-14T22:13:20.123Z WARN  skyd::engine::net] hello\n"
+    );
+    assert_eq!(
+        format(LogFormat::Text, Correlation::query(3, 14), "hello"),
+        "[2023-11-14T22:13:20.123Z WARN  skyd::engine::net conn=3 query=14] hello\n"
+    );
+}
+
+#[test]
+fn format_json() {
+    assert_eq!(
+        format(LogFormat::Json, Correlation::none(), "hello"),
+        "{\"time\":\"2023-11-14T22:13:20.123Z\",\"level\":\"WARN\",\"target\":\"skyd::engine::net\",\"msg\":\"hello\"}\n"
+    );
+    assert_eq!(
+        format(LogFormat::Json, Correlation::connection(3), "say \"hello\"\n"),
+        "{\"time\":\"2023-11-14T22:13:20.123Z\",\"level\":\"WARN\",\"target\":\"skyd::engine::net\",\"conn\":3,\"msg\":\"say \\\"hello\\\"\\n\"}\n"
+    );
+    assert_eq!(
+        format(LogFormat::Json, Correlation::query(3, 14), "hello"),
+        "{\"time\":\"2023-11-14T22:13:20.123Z\",\"level\":\"WARN\",\"target\":\"skyd::engine::net\",\"conn\":3,\"query\":14,\"msg\":\"hello\"}\n"
+    );
+}
+
+#[test]
+fn format_parse() {
+    assert_eq!(LogFormat::parse("json"), Some(LogFormat::Json));
+    assert_eq!(LogFormat::parse("JSON"), Some(LogFormat::Json));
+    assert_eq!(LogFormat::parse("text"), Some(LogFormat::Text));
+    assert_eq!(LogFormat::parse("yaml"), None);
+}
@@ -242,12 +242,24 @@ macro_rules! Token {
     (limit) => {
         __kw_misc!(Limit)
     };
+    (offset) => {
+        __kw_misc!(Offset)
+    };
+    (order) => {
+        __kw_misc!(Order)
+    };
     (from) => {
         __kw_misc!(From)
     };
     (into) => {
         __kw_misc!(Into)
     };
+    (to) => {
+        __kw_misc!(To)
+    };
+    (as) => {
+        __kw_misc!(As)
+    };
     (where) => {
         __kw_misc!(Where)
     };
@@ -257,6 +269,9 @@ macro_rules! Token {
     (and) => {
         __kw_misc!(And)
     };
+    (in) => {
+        __kw_misc!(In)
+    };
     (as) => {
         __kw_misc!(As)
     };
@@ -317,6 +332,9 @@ macro_rules! Token {
     (null) => {
         __kw_misc!(Null)
     };
+    (default) => {
+        __kw_misc!(Default)
+    };
     (not) => {
         __kw_misc!(Not)
     };
@@ -329,6 +347,12 @@ macro_rules! Token {
     (all) => {
         __kw_misc!(All)
     };
+    (on) => {
+        __kw_misc!(On)
+    };
+    (values) => {
+        __kw_misc!(Values)
+    };
     (exists) => {
         __kw_stmt!(Exists)
     };
@@ -51,7 +51,11 @@ pub use {
 use std::alloc::{self, Layout};
 
 pub unsafe fn dealloc_array<T>(ptr: *mut T, l: usize) {
-    alloc::dealloc(ptr as *mut u8, Layout::array::<T>(l).unwrap_unchecked())
+    let layout = Layout::array::<T>(l).unwrap_unchecked();
+    // an empty array (like an empty boxed slice) was never allocated
+    if layout.size() != 0 {
+        alloc::dealloc(ptr as *mut u8, layout)
+    }
 }
 
 /// Native double pointer width (note, native != arch native, but host native)
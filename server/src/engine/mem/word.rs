@@ -338,6 +338,26 @@ macro_rules! impl_float_io {
 
 impl_float_io!(f32, f64);
 
+impl<T: QwordNNNN> WordIO<i128> for T {
+    fn store(v: i128) -> Self {
+        Self::qwordnnnn_store_qw_qw(v as u64, (v >> 64) as u64)
+    }
+    fn load(&self) -> i128 {
+        let [lo, hi] = self.qwordnnnn_load_qw_qw();
+        ((hi as i128) << 64) | lo as i128
+    }
+}
+
+impl<T: QwordNNNN> WordIO<(f64, f64)> for T {
+    fn store((a, b): (f64, f64)) -> Self {
+        Self::qwordnnnn_store_qw_qw(a.to_bits(), b.to_bits())
+    }
+    fn load(&self) -> (f64, f64) {
+        let [a, b] = self.qwordnnnn_load_qw_qw();
+        (f64::from_bits(a), f64::from_bits(b))
+    }
+}
+
 impl<T: DwordNN> WordIO<(usize, usize)> for T {
     fn store((a, b): (usize, usize)) -> Self {
         Self::dwordnn_store_native_full(a, b)
@@ -0,0 +1,396 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Engine metrics, served in the Prometheus text format over a (plain) HTTP endpoint
+
+#[cfg(test)]
+mod tests;
+
+use {
+    crate::engine::{
+        config::ConfigEndpointTcp,
+        error::RuntimeResult,
+        fractal::{Global, GlobalInstanceLike, MemoryUsage, ModelUniqueID},
+        net::{self, IoResult, ProtocolListener},
+    },
+    core::fmt::Write,
+    std::time::Duration,
+    tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+};
+
+/// The only path that we serve
+const METRICS_PATH: &str = "/metrics";
+/// The content type for v0.0.4 of the Prometheus text format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+/// We won't read request heads larger than this
+const MAX_REQUEST_HEAD: usize = 8192;
+/// How long a client has to send us the request head
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/*
+    metrics
+*/
+
+/// Statistics for a single model
+struct ModelStat {
+    space: Box<str>,
+    model: Box<str>,
+    rows: usize,
+    pending_deltas: usize,
+    memory: usize,
+    batch_flushes: u64,
+}
+
+/// A single sample of a metric (as listed by the `sys.metrics` catalog model)
+#[derive(Debug, PartialEq)]
+pub struct MetricSample {
+    pub name: &'static str,
+    pub kind: &'static str,
+    /// the space and model, if this is a per-model sample
+    pub model: Option<(Box<str>, Box<str>)>,
+    pub value: u64,
+}
+
+/// Where the collected metrics are written to
+trait MetricSink {
+    fn metric<'a>(
+        &mut self,
+        name: &'static str,
+        kind: &'static str,
+        help: &str,
+        samples: impl IntoIterator<Item = (Option<&'a ModelStat>, u64)>,
+    );
+}
+
+impl MetricSink for String {
+    /// Write a metric along with its samples in the Prometheus text format
+    fn metric<'a>(
+        &mut self,
+        name: &'static str,
+        kind: &'static str,
+        help: &str,
+        samples: impl IntoIterator<Item = (Option<&'a ModelStat>, u64)>,
+    ) {
+        // writing to a string is infallible
+        let _ = writeln!(self, "# HELP {name} {help}");
+        let _ = writeln!(self, "# TYPE {name} {kind}");
+        for (model, value) in samples {
+            let _ = match model {
+                /*
+                    space and model names are identifiers and so don't have anything that needs to be escaped
+                */
+                Some(m) => writeln!(
+                    self,
+                    "{name}{{space=\"{}\",model=\"{}\"}} {value}",
+                    m.space, m.model
+                ),
+                None => writeln!(self, "{name} {value}"),
+            };
+        }
+    }
+}
+
+impl MetricSink for Vec<MetricSample> {
+    fn metric<'a>(
+        &mut self,
+        name: &'static str,
+        kind: &'static str,
+        _: &str,
+        samples: impl IntoIterator<Item = (Option<&'a ModelStat>, u64)>,
+    ) {
+        self.extend(samples.into_iter().map(|(model, value)| MetricSample {
+            name,
+            kind,
+            model: model.map(|m| (m.space.clone(), m.model.clone())),
+            value,
+        }))
+    }
+}
+
+/// Render all the metrics in the Prometheus text format
+pub fn render(global: &impl GlobalInstanceLike) -> String {
+    let mut out = String::new();
+    collect(global, &mut out);
+    out
+}
+
+/// Returns every sample of every metric
+pub fn samples(global: &impl GlobalInstanceLike) -> Vec<MetricSample> {
+    let mut out = vec![];
+    collect(global, &mut out);
+    out
+}
+
+fn collect(global: &impl GlobalInstanceLike, out: &mut impl MetricSink) {
+    let snapshot = {
+        let models = global.namespace().idx_models().read();
+        models
+            .iter()
+            .map(|(id, model)| {
+                (
+                    ModelUniqueID::new(id.space(), id.entity(), model.get_uuid()),
+                    model.primary_index().count_approx(),
+                    model.delta_state().data_delta_backlog(),
+                    MemoryUsage::of(model).total(),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    /*
+        we don't hold the namespace lock while reading the drivers because fractal acquires these in the reverse order
+    */
+    let mut stats = {
+        let drivers = global.model_drivers().read();
+        snapshot
+            .into_iter()
+            .map(|(id, rows, pending_deltas, memory)| ModelStat {
+                batch_flushes: drivers.get(&id).map_or(0, |drv| drv.batch_flushes()),
+                space: id.space().into(),
+                model: id.model().into(),
+                rows,
+                pending_deltas,
+                memory,
+            })
+            .collect::<Vec<_>>()
+    };
+    stats.sort_unstable_by(|a, b| (&a.space, &a.model).cmp(&(&b.space, &b.model)));
+    out.metric(
+        "skytable_connections_active",
+        "gauge",
+        "Client connections that are currently open",
+        [(None, net::connections_active() as u64)],
+    );
+    out.metric(
+        "skytable_connections_total",
+        "counter",
+        "Client connections accepted since startup",
+        [(None, net::connections_total())],
+    );
+    out.metric(
+        "skytable_models",
+        "gauge",
+        "Models in the database",
+        [(None, stats.len() as u64)],
+    );
+    out.metric(
+        "skytable_model_rows",
+        "gauge",
+        "Rows in the primary index of the model",
+        stats.iter().map(|s| (Some(s), s.rows as u64)),
+    );
+    out.metric(
+        "skytable_model_delta_queue_depth",
+        "gauge",
+        "Changes to the model that are yet to be written to its batch journal",
+        stats.iter().map(|s| (Some(s), s.pending_deltas as u64)),
+    );
+    out.metric(
+        "skytable_model_delta_queue_limit",
+        "gauge",
+        "Queue depth at which a model's writes are held back (unset if there is no limit)",
+        global
+            .backpressure()
+            .limit()
+            .map(|limit| (None, limit as u64)),
+    );
+    out.metric(
+        "skytable_writes_stalled_total",
+        "counter",
+        "Writes that had to wait for a model's delta queue to drain since startup",
+        [(None, global.backpressure().stalled_writes())],
+    );
+    out.metric(
+        "skytable_writes_rejected_total",
+        "counter",
+        "Writes rejected because a model's delta queue was full since startup",
+        [(None, global.backpressure().rejected_writes())],
+    );
+    let row_cache = global.row_cache();
+    out.metric(
+        "skytable_row_cache_bytes",
+        "gauge",
+        "Memory used by the row caches of all models",
+        [(None, row_cache.used() as u64)],
+    );
+    out.metric(
+        "skytable_row_cache_limit_bytes",
+        "gauge",
+        "Memory budget for the row caches of all models (unset if rows aren't cached)",
+        row_cache
+            .is_enabled()
+            .then(|| (None, row_cache.limit() as u64)),
+    );
+    out.metric(
+        "skytable_row_cache_hits_total",
+        "counter",
+        "Reads served from a row cache since startup",
+        [(None, row_cache.hits())],
+    );
+    out.metric(
+        "skytable_row_cache_misses_total",
+        "counter",
+        "Cacheable reads that had to go to the primary index since startup",
+        [(None, row_cache.misses())],
+    );
+    out.metric(
+        "skytable_row_cache_evictions_total",
+        "counter",
+        "Rows evicted from a row cache since startup",
+        [(None, row_cache.evictions())],
+    );
+    out.metric(
+        "skytable_model_memory_bytes",
+        "gauge",
+        "Estimated memory used by the model's rows, unflushed changes and cached rows",
+        stats.iter().map(|s| (Some(s), s.memory as u64)),
+    );
+    out.metric(
+        "skytable_model_memory_limit_bytes",
+        "gauge",
+        "Memory at which a model's writes are rejected (unset if there is no limit)",
+        global
+            .memory()
+            .model_limit()
+            .map(|limit| (None, limit as u64)),
+    );
+    out.metric(
+        "skytable_memory_limit_bytes",
+        "gauge",
+        "Memory at which writes to any model are rejected (unset if there is no limit)",
+        global
+            .memory()
+            .global_limit()
+            .map(|limit| (None, limit as u64)),
+    );
+    out.metric(
+        "skytable_writes_rejected_memory_total",
+        "counter",
+        "Writes rejected because a memory limit was hit since startup",
+        [(None, global.memory().rejected_writes())],
+    );
+    out.metric(
+        "skytable_model_batch_flushes_total",
+        "counter",
+        "Batches written to the model's batch journal since startup",
+        stats.iter().map(|s| (Some(s), s.batch_flushes)),
+    );
+}
+
+/*
+    http
+*/
+
+/// Build the HTTP response for the given request head
+fn respond(head: &[u8], global: &impl GlobalInstanceLike) -> String {
+    let request_line = head.split(|b| *b == b'\n').next().unwrap_or_default();
+    let mut request_line = request_line
+        .strip_suffix(b"\r")
+        .unwrap_or(request_line)
+        .split(|b| *b == b' ');
+    let (status, body) = match (
+        request_line.next(),
+        request_line.next(),
+        request_line.next(),
+    ) {
+        (Some(b"GET"), Some(path), Some(version)) if version.starts_with(b"HTTP/1.") => {
+            let path = path.split(|b| *b == b'?').next().unwrap_or_default();
+            if path == METRICS_PATH.as_bytes() {
+                ("200 OK", render(global))
+            } else {
+                ("404 Not Found", "not found\n".to_owned())
+            }
+        }
+        (Some(_), Some(_), Some(version)) if version.starts_with(b"HTTP/1.") => {
+            ("405 Method Not Allowed", "method not allowed\n".to_owned())
+        }
+        _ => ("400 Bad Request", "bad request\n".to_owned()),
+    };
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Read the request head (everything up to the first empty line). Returns false if the head was too large or if the
+/// client closed the connection before sending all of it
+async fn read_request_head<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buf: &mut Vec<u8>,
+) -> IoResult<bool> {
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() >= MAX_REQUEST_HEAD {
+            return Ok(false);
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(false);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    Ok(true)
+}
+
+async fn serve<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    global: &impl GlobalInstanceLike,
+) -> IoResult<()> {
+    let mut head = Vec::with_capacity(1024);
+    let response =
+        match tokio::time::timeout(READ_TIMEOUT, read_request_head(&mut stream, &mut head)).await {
+            Ok(Ok(true)) => respond(&head, global),
+            Ok(Ok(false)) => respond(&[], global),
+            Ok(Err(e)) => return Err(e),
+            // the client took too long, so just close the connection
+            Err(_) => return Ok(()),
+        };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// A listener that serves metrics over HTTP
+pub struct MetricsListener {
+    global: Global,
+    listener: ProtocolListener,
+}
+
+impl MetricsListener {
+    pub async fn new(ep: &ConfigEndpointTcp, global: Global) -> RuntimeResult<Self> {
+        Ok(Self {
+            global,
+            listener: ProtocolListener::bind(ep, "metrics").await?,
+        })
+    }
+    pub async fn listen(&mut self) {
+        let Self { global, listener } = self;
+        listener
+            .listen(|stream, _| {
+                let global = global.clone();
+                async move { serve(stream, &global).await }
+            })
+            .await
+    }
+}
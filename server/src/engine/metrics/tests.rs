@@ -0,0 +1,95 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{dml, model::Model},
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    ql::{
+        ast::parse_ast_node_full, ddl::crt::CreateModel, dml::ins::InsertStatement,
+        tests::lex_insecure,
+    },
+};
+
+fn global_with_model() -> TestGlobal<crate::engine::storage::v1::memfs::NullFS> {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    global.namespace().create_empty_test_space("myspace");
+    let tok =
+        lex_insecure(b"create model myspace.users(username: string, password: string)").unwrap();
+    let stmt = parse_ast_node_full::<CreateModel>(&tok[2..]).unwrap();
+    Model::transactional_exec_create(&global, stmt).unwrap();
+    for insert in [
+        "insert into myspace.users('sayan', 'pass123')",
+        "insert into myspace.users('elana', 'pass456')",
+    ] {
+        let tok = lex_insecure(insert.as_bytes()).unwrap();
+        let stmt = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        dml::insert(&global, stmt).unwrap();
+    }
+    global
+}
+
+#[test]
+fn render_metrics() {
+    let global = global_with_model();
+    let metrics = super::render(&global);
+    let lines: Vec<&str> = metrics.lines().collect();
+    for expected in [
+        "# TYPE skytable_connections_active gauge",
+        "# TYPE skytable_connections_total counter",
+        "skytable_models 1",
+        "skytable_model_rows{space=\"myspace\",model=\"users\"} 2",
+        "skytable_model_delta_queue_depth{space=\"myspace\",model=\"users\"} 2",
+        "skytable_model_batch_flushes_total{space=\"myspace\",model=\"users\"} 0",
+        "skytable_writes_stalled_total 0",
+        "skytable_writes_rejected_total 0",
+        "skytable_row_cache_bytes 0",
+        "skytable_row_cache_hits_total 0",
+        "skytable_writes_rejected_memory_total 0",
+    ] {
+        assert!(
+            lines.contains(&expected),
+            "missing `{expected}` in:\n{metrics}"
+        );
+    }
+}
+
+#[test]
+fn respond_routes() {
+    let global = global_with_model();
+    let ok = super::respond(
+        b"GET /metrics?x=1 HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        &global,
+    );
+    assert!(ok.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(ok.contains("Content-Type: text/plain; version=0.0.4\r\n"));
+    assert!(ok.ends_with(&super::render(&global)));
+    let not_found = super::respond(b"GET / HTTP/1.1\r\n\r\n", &global);
+    assert!(not_found.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    let bad_method = super::respond(b"POST /metrics HTTP/1.1\r\n\r\n", &global);
+    assert!(bad_method.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    let bad_request = super::respond(b"hello", &global);
+    assert!(bad_request.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
@@ -32,12 +32,18 @@ mod data;
 mod error;
 mod fractal;
 mod idx;
+mod logging;
 mod mem;
+mod metrics;
 mod net;
+mod preflight;
 mod ql;
+mod repl;
+mod resp;
 mod storage;
 mod sync;
 mod txn;
+mod web;
 // test
 #[cfg(test)]
 mod tests;
@@ -46,83 +52,287 @@ pub use error::RuntimeResult;
 
 use {
     self::{
-        config::{ConfigEndpoint, ConfigEndpointTls, ConfigMode, ConfigReturn, Configuration},
+        config::{
+            ConfigEndpoint, ConfigEndpointTls, ConfigMode, ConfigRecovery, ConfigRepairMode,
+            ConfigReturn, Configuration,
+        },
         fractal::{
             context::{self, Subsystem},
             sys_store::SystemStore,
         },
+        net::protocol::{QueryHost, StartupHost},
         storage::v1::{
+            audit::{self, AuditLog},
+            encryption,
             loader::{self, SEInitState},
-            LocalFS,
+            repair, LocalFS,
         },
     },
     crate::util::os::TerminationSignal,
-    std::process::exit,
-    tokio::sync::broadcast,
+    std::{process::exit, sync::Arc},
+    tokio::sync::{broadcast, oneshot},
 };
 
+pub(super) fn init_logger() {
+    logging::init()
+}
+
 pub(super) fn set_context_init(msg: &'static str) {
     context::set(Subsystem::Init, msg)
 }
 
-/// Initialize all drivers, load all data
-///
-/// WARN: Must be in [`tokio::runtime::Runtime`] context!
-pub fn load_all() -> RuntimeResult<(Configuration, fractal::GlobalStateStart)> {
-    // load configuration
+/// Load the configuration. Help messages and `--validate-config` are handled here (and we exit right after), since
+/// neither needs the PID file or touches the data files
+pub fn load_config() -> RuntimeResult<ConfigReturn> {
     info!("checking configuration ...");
     context::set(Subsystem::Init, "loading configuration");
-    let config = match config::check_configuration()? {
-        ConfigReturn::Config(cfg) => cfg,
+    match config::check_configuration()? {
         ConfigReturn::HelpMessage(msg) => {
             eprintln!("{msg}");
             exit(0x00);
         }
+        ConfigReturn::Validate(config, recovery) => {
+            info!("validating configuration ...");
+            if preflight::check(&config, &recovery) {
+                info!("the configuration is valid");
+                exit(0x00);
+            }
+            error!("the configuration is invalid");
+            exit(0x01);
+        }
+        ret => Ok(ret),
+    }
+}
+
+/// Initialize all drivers, load all data
+///
+/// WARN: Must be in [`tokio::runtime::Runtime`] context!
+pub fn load_all(config: ConfigReturn) -> RuntimeResult<(Configuration, fractal::GlobalStateStart)> {
+    let (config, recovery) = match config {
+        ConfigReturn::Config(cfg) => (cfg, ConfigRecovery::default()),
+        ConfigReturn::Recover(cfg, recovery) => (cfg, recovery),
+        ConfigReturn::HelpMessage(_) | ConfigReturn::Validate(..) => {
+            unreachable!("handled when the configuration is loaded")
+        }
+        ConfigReturn::Repair(mode, key) => {
+            if let Some(key) = key {
+                encryption::register(key);
+            }
+            info!("checking database files ...");
+            context::set_dmsg("repairing database files");
+            if repair::repair_all(mode == ConfigRepairMode::Truncate)? {
+                exit(0x00);
+            }
+            error!("the database can't be loaded until it is repaired");
+            exit(0x01);
+        }
     };
     if config.mode == ConfigMode::Dev {
         warn!("running in dev mode");
     }
+    if let Some(key) = config.system.encryption_key.clone() {
+        info!("encrypting new database files with key {:06x}", key.id());
+        encryption::register(key);
+    }
+    // restore backup (if any)
+    if let Some(backup) = recovery.restore.as_deref() {
+        info!("restoring backup from `{backup}` ...");
+        context::set_dmsg("restoring backup");
+        loader::restore_backup(backup)?;
+    }
     // restore system database
     info!("loading system database ...");
     context::set_dmsg("loading system database");
     let (store, state) = SystemStore::<LocalFS>::open_or_restore(config.auth.clone(), config.mode)?;
+    let store = store
+        .with_durability(config.system.durability)
+        .with_export_dir(config.system.export_dir.as_deref());
+    info!("opening audit log ...");
+    let audit_log = AuditLog::open(audit::AUDIT_LOG_PATH, config.system.encryption_key.as_ref())?;
+    let store = store.with_audit_log(audit_log);
     let sysdb_is_new = state.is_created();
     if state.is_existing_updated_root() {
         warn!("the root account was updated");
     }
+    if store.system_store().root_must_rotate() {
+        warn!("the root password must be changed with `sysctl alter user root` before any DDL can be run");
+    }
     // now load all data
     if sysdb_is_new {
         info!("initializing storage engine ...");
     } else {
         info!("reinitializing storage engine...");
     }
-    context::set_dmsg("restoring data");
+    let store = Arc::new(store);
+    net::set_idle_timeout(config.system.idle_timeout);
+    // restoring a lot of data takes a while, so let clients see how far along we are
+    let startup_listeners = if sysdb_is_new {
+        None
+    } else {
+        Some(StartupListeners::start(&config.endpoints, store.clone())?)
+    };
+    context::set(Subsystem::Init, "restoring data");
+    let init_state = loader::SEInitState::try_init(
+        sysdb_is_new,
+        &recovery.until,
+        config.system.encryption_key.as_ref(),
+        config.system.storage_engine,
+        config.system.io_backend,
+        config.system.recovery_mode,
+    );
+    if let Some(startup_listeners) = startup_listeners {
+        startup_listeners.stop();
+    }
     let SEInitState {
         txn_driver,
         model_drivers,
         gns,
-    } = loader::SEInitState::try_init(sysdb_is_new)?;
+    } = init_state?;
+    let store = Arc::into_inner(store).expect("the startup listeners were stopped");
     let global = unsafe {
         // UNSAFE(@ohsayan): this is the only entrypoint
-        fractal::load_and_enable_all(gns, store, txn_driver, model_drivers)
+        fractal::load_and_enable_all(
+            gns,
+            store,
+            txn_driver,
+            model_drivers,
+            config.system.storage_engine,
+            config.system.io_backend,
+            config.system.batch_compression,
+            config.system.encryption_key.clone(),
+            config.replica_of.is_some(),
+            fractal::Backpressure::new(
+                config.system.backpressure,
+                config.system.backpressure_limit,
+            ),
+            core::index::RowCacheBudget::new(config.system.row_cache_size.unwrap_or(0)),
+            fractal::MemoryLimits::new(
+                config.system.memory_limit,
+                config.system.model_memory_limit,
+            ),
+            fractal::queries::RunningQueries::new(config.system.query_timeout),
+            fractal::blocking::BlockingPool::new(config.system.io_threads),
+        )
     };
     Ok((config, global))
 }
 
-enum EndpointListeners {
-    Insecure(net::Listener),
+/// Listeners that serve clients while the data is being restored (see [`StartupHost`])
+struct StartupListeners {
+    stop: oneshot::Sender<()>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl StartupListeners {
+    /// Start serving clients on the configured endpoints
+    ///
+    /// WARN: Must be in [`tokio::runtime::Runtime`] context (but not in an async context)!
+    fn start(
+        endpoints: &ConfigEndpoint,
+        sys_store: Arc<SystemStore<LocalFS>>,
+    ) -> RuntimeResult<Self> {
+        let rt = tokio::runtime::Handle::current();
+        let (signal, _) = broadcast::channel::<()>(1);
+        let (mut listeners, str) = rt.block_on(EndpointListeners::bind(
+            endpoints,
+            StartupHost::new(sys_store),
+            &signal,
+        ))?;
+        info!("{str} (only `sysctl report startup` is served until the data is restored)");
+        let (stop, stopped) = oneshot::channel();
+        let handle = rt.spawn(async move {
+            tokio::select! {
+                _ = listeners.listen() => {}
+                _ = stopped => {}
+            }
+            drop(signal);
+            listeners.finish().await;
+        });
+        Ok(Self { stop, handle })
+    }
+    /// Stop serving clients and free up the endpoints, waiting for all open connections to be closed
+    fn stop(self) {
+        let _ = self.stop.send(());
+        if let Err(e) = tokio::runtime::Handle::current().block_on(self.handle) {
+            error!("error while terminating the startup listeners: {e}");
+        }
+    }
+}
+
+enum EndpointListeners<H = fractal::Global> {
+    Insecure(net::Listener<H>),
     Secure {
-        listener: net::Listener,
+        listener: net::Listener<H>,
         ssl: openssl::ssl::SslAcceptor,
     },
     Multi {
-        tcp: net::Listener,
-        tls: net::Listener,
+        tcp: net::Listener<H>,
+        tls: net::Listener<H>,
         ssl: openssl::ssl::SslAcceptor,
     },
 }
 
-impl EndpointListeners {
+impl<H: QueryHost> EndpointListeners<H> {
+    /// Bind to all the configured endpoints, serving clients using the given host. Returns the listeners and a message
+    /// describing where we're listening
+    async fn bind(
+        endpoints: &ConfigEndpoint,
+        host: H,
+        signal: &broadcast::Sender<()>,
+    ) -> RuntimeResult<(Self, String)> {
+        context::set(Subsystem::Network, "initializing endpoints");
+        let str;
+        let listeners = match endpoints {
+            ConfigEndpoint::Secure(ConfigEndpointTls { tcp, .. })
+            | ConfigEndpoint::Insecure(tcp) => {
+                let listener =
+                    net::Listener::new(tcp.host(), tcp.port(), host, signal.clone()).await?;
+                if let ConfigEndpoint::Secure(s) = endpoints {
+                    context::set_dmsg("initializing TLS");
+                    let acceptor = net::Listener::<H>::init_tls(
+                        s.cert(),
+                        s.private_key(),
+                        s.pkey_pass(),
+                        s.client_ca(),
+                    )?;
+                    str = format!("listening on tls@{}:{}", s.tcp().host(), s.tcp().port());
+                    EndpointListeners::Secure {
+                        listener,
+                        ssl: acceptor,
+                    }
+                } else {
+                    str = format!("listening on tcp@{}:{}", tcp.host(), tcp.port());
+                    EndpointListeners::Insecure(listener)
+                }
+            }
+            ConfigEndpoint::Multi(insecure_ep, secure_ep) => {
+                let tcp_listener =
+                    net::Listener::new_cfg(insecure_ep, host.clone(), signal.clone()).await?;
+                let tls_listener =
+                    net::Listener::new_cfg(secure_ep.tcp(), host, signal.clone()).await?;
+                context::set_dmsg("initializing TLS");
+                let acceptor = net::Listener::<H>::init_tls(
+                    secure_ep.cert(),
+                    secure_ep.private_key(),
+                    secure_ep.pkey_pass(),
+                    secure_ep.client_ca(),
+                )?;
+                str = format!(
+                    "listening on tcp@{}:{} and tls@{}:{}",
+                    insecure_ep.host(),
+                    insecure_ep.port(),
+                    secure_ep.tcp().host(),
+                    secure_ep.tcp().port()
+                );
+                EndpointListeners::Multi {
+                    tcp: tcp_listener,
+                    tls: tls_listener,
+                    ssl: acceptor,
+                }
+            }
+        };
+        Ok((listeners, str))
+    }
     async fn listen(&mut self) {
         match self {
             Self::Insecure(l) => l.listen_tcp().await,
@@ -145,70 +355,134 @@ impl EndpointListeners {
 pub async fn start(
     termsig: TerminationSignal,
     Configuration {
-        endpoints, system, ..
+        endpoints,
+        system,
+        auth,
+        metrics,
+        web,
+        resp,
+        replication,
+        replica_of,
+        ..
     }: Configuration,
     fractal::GlobalStateStart { global, boot }: fractal::GlobalStateStart,
 ) -> RuntimeResult<()> {
-    // create our system-wide channel
+    // create our system-wide channels (the fractal engine is only stopped once all connections were drained, so that it
+    // can flush everything that the last queries wrote)
     let (signal, _) = broadcast::channel::<()>(1);
+    let (fractal_signal, _) = broadcast::channel::<()>(1);
     // start our services
     context::set_dmsg("starting fractal engine");
-    let fractal_handle = boot.boot(&signal, system.reliability_system_window);
+    let fractal_handle = boot.boot(&fractal_signal, system.reliability_system_window);
     // create our server
-    context::set(Subsystem::Network, "initializing endpoints");
-    let str;
-    let mut endpoint_handles = match &endpoints {
-        ConfigEndpoint::Secure(ConfigEndpointTls { tcp, .. }) | ConfigEndpoint::Insecure(tcp) => {
-            let listener =
-                net::Listener::new(tcp.host(), tcp.port(), global.clone(), signal.clone()).await?;
-            if let ConfigEndpoint::Secure(s) = endpoints {
-                context::set_dmsg("initializing TLS");
-                let acceptor = net::Listener::init_tls(s.cert(), s.private_key(), s.pkey_pass())?;
-                str = format!("listening on tls@{}:{}", s.tcp().host(), s.tcp().port());
-                EndpointListeners::Secure {
-                    listener,
-                    ssl: acceptor,
-                }
-            } else {
-                str = format!("listening on tcp@{}:{}", tcp.host(), tcp.port());
-                EndpointListeners::Insecure(listener)
-            }
+    let (mut endpoint_handles, str) =
+        EndpointListeners::bind(&endpoints, global.clone(), &signal).await?;
+    info!("{str}");
+    let mut metrics_listener = match metrics {
+        Some(ep) => {
+            context::set_dmsg("initializing metrics endpoint");
+            let listener = metrics::MetricsListener::new(&ep, global.clone()).await?;
+            info!("serving metrics on http@{}:{}", ep.host(), ep.port());
+            Some(listener)
+        }
+        None => None,
+    };
+    let metrics_handle = async {
+        match metrics_listener.as_mut() {
+            Some(listener) => listener.listen().await,
+            None => std::future::pending().await,
         }
-        ConfigEndpoint::Multi(insecure_ep, secure_ep) => {
-            let tcp_listener =
-                net::Listener::new_cfg(insecure_ep, global.clone(), signal.clone()).await?;
-            let tls_listener =
-                net::Listener::new_cfg(secure_ep.tcp(), global.clone(), signal.clone()).await?;
-            context::set_dmsg("initializing TLS");
-            let acceptor = net::Listener::init_tls(
-                secure_ep.cert(),
-                secure_ep.private_key(),
-                secure_ep.pkey_pass(),
-            )?;
-            str = format!(
-                "listening on tcp@{}:{} and tls@{}:{}",
-                insecure_ep.host(),
-                insecure_ep.port(),
-                secure_ep.tcp().host(),
-                secure_ep.tcp().port()
+    };
+    let mut web_listener = match web {
+        Some(ep) => {
+            context::set_dmsg("initializing web endpoint");
+            let listener = web::WebListener::new(&ep, global.clone(), signal.subscribe()).await?;
+            info!("serving the web bridge on web@{}:{}", ep.host(), ep.port());
+            Some(listener)
+        }
+        None => None,
+    };
+    let web_handle = async {
+        match web_listener.as_mut() {
+            Some(listener) => listener.listen().await,
+            None => std::future::pending().await,
+        }
+    };
+    let mut resp_listener = match resp {
+        Some(resp) => {
+            context::set_dmsg("initializing resp endpoint");
+            let ep = &resp.endpoint;
+            let listener =
+                resp::RespListener::new(&resp, global.clone(), signal.subscribe()).await?;
+            info!(
+                "serving redis clients on resp@{}:{} (using {}.{})",
+                ep.host(),
+                ep.port(),
+                resp.space(),
+                resp.model()
             );
-            EndpointListeners::Multi {
-                tcp: tcp_listener,
-                tls: tls_listener,
-                ssl: acceptor,
+            Some(listener)
+        }
+        None => None,
+    };
+    let resp_handle = async {
+        match resp_listener.as_mut() {
+            Some(listener) => listener.listen().await,
+            None => std::future::pending().await,
+        }
+    };
+    let mut replication_listener = match replication {
+        Some(ep) => {
+            context::set_dmsg("initializing replication endpoint");
+            // replicas are served with the settings of the TLS endpoint (the configuration makes sure there's one)
+            let tls = endpoints.tls().unwrap();
+            let listener = repl::ReplicationListener::new(&ep, tls, global.clone()).await?;
+            info!("serving replicas on repl@{}:{}", ep.host(), ep.port());
+            Some(listener)
+        }
+        None => None,
+    };
+    let replication_handle = async {
+        match replication_listener.as_mut() {
+            Some(listener) => listener.listen().await,
+            None => std::future::pending().await,
+        }
+    };
+    let mut replicator = replica_of
+        .map(|primary| {
+            context::set_dmsg("initializing replication");
+            repl::Replicator::new(&primary, endpoints.tls(), auth.root_key, global.clone())
+        })
+        .transpose()?;
+    let replicator_handle = async {
+        match replicator.as_mut() {
+            Some(replicator) => {
+                replicator.run().await;
+                // we were promoted, so keep serving as a primary
+                std::future::pending().await
             }
+            None => std::future::pending().await,
         }
     };
-    info!("{str}");
     tokio::select! {
         _ = endpoint_handles.listen() => {}
+        _ = metrics_handle => {}
+        _ = web_handle => {}
+        _ = resp_handle => {}
+        _ = replication_handle => {}
+        _ = replicator_handle => {}
         _ = termsig => {
             info!("received terminate signal. waiting for inflight tasks to complete ...");
         }
+        _ = global.shutdown_requested() => {
+            info!("received shutdown request. waiting for inflight tasks to complete ...");
+        }
     }
+    // stop accepting connections and wait for the running queries to complete
     drop(signal);
     endpoint_handles.finish().await;
     info!("waiting for fractal engine to exit ...");
+    drop(fractal_signal);
     let (hp_handle, lp_handle) = tokio::join!(fractal_handle.hp_handle, fractal_handle.lp_handle);
     match (hp_handle, lp_handle) {
         (Err(e1), Err(e2)) => {
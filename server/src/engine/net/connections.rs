@@ -0,0 +1,194 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Open connections
+//!
+//! Every client connection is registered from when it's accepted until it's closed, along with what it's doing right
+//! now, so that `sysctl report connections` can list them (which helps to find clients that are stuck). The state of a
+//! connection is only updated when a query starts or completes
+
+use {
+    parking_lot::Mutex,
+    std::{
+        collections::BTreeMap,
+        net::SocketAddr,
+        ops::Deref,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+};
+
+/// The connections that are open (by id)
+static CONNECTIONS: Mutex<BTreeMap<u64, Arc<Connection>>> = Mutex::new(BTreeMap::new());
+
+/// Returns the connections that are open (of the given user, if set), in the order they were accepted
+pub fn list(user: Option<&str>) -> Vec<ConnectionSnapshot> {
+    let now = Instant::now();
+    CONNECTIONS
+        .lock()
+        .values()
+        .map(|connection| connection.snapshot_at(now))
+        .filter(|connection| user.map_or(true, |user| connection.user.as_deref() == Some(user)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// the client is yet to authenticate
+    Handshake,
+    /// the client is authenticated, and no query is running
+    Idle,
+    /// a query is running
+    Query,
+    /// changes are being streamed to the client
+    Subscribed,
+}
+
+impl ConnectionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Handshake => "handshake",
+            Self::Idle => "idle",
+            Self::Query => "query",
+            Self::Subscribed => "subscribed",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Activity {
+    user: Option<Box<str>>,
+    state: ConnectionState,
+    since: Instant,
+    query: Option<Box<str>>,
+}
+
+/// A client connection
+#[derive(Debug)]
+pub struct Connection {
+    id: u64,
+    peer: SocketAddr,
+    connected: Instant,
+    activity: Mutex<Activity>,
+}
+
+impl Connection {
+    /// Register a connection that was just accepted. It's unregistered once the guard is dropped
+    pub fn register(id: u64, peer: SocketAddr) -> ConnectionGuard {
+        let now = Instant::now();
+        let connection = Arc::new(Self {
+            id,
+            peer,
+            connected: now,
+            activity: Mutex::new(Activity {
+                user: None,
+                state: ConnectionState::Handshake,
+                since: now,
+                query: None,
+            }),
+        });
+        CONNECTIONS.lock().insert(id, connection.clone());
+        ConnectionGuard(connection)
+    }
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    pub fn peer(&self) -> SocketAddr {
+        self.peer
+    }
+    fn set(&self, state: ConnectionState, query: Option<Box<str>>) {
+        let mut activity = self.activity.lock();
+        activity.state = state;
+        activity.since = Instant::now();
+        activity.query = query;
+    }
+    /// The client authenticated as the given user
+    pub fn authenticated(&self, user: &str) {
+        self.activity.lock().user = Some(user.into());
+        self.set(ConnectionState::Idle, None);
+    }
+    /// The client is running the given query (the text of the query, without the parameters)
+    pub fn query(&self, query: &[u8]) {
+        self.set(
+            ConnectionState::Query,
+            Some(String::from_utf8_lossy(query).into()),
+        );
+    }
+    /// The query that the client ran started a subscription (the query is kept)
+    pub fn subscribed(&self) {
+        let mut activity = self.activity.lock();
+        activity.state = ConnectionState::Subscribed;
+        activity.since = Instant::now();
+    }
+    /// The query (or the subscription) completed
+    pub fn idle(&self) {
+        self.set(ConnectionState::Idle, None);
+    }
+    fn snapshot_at(&self, now: Instant) -> ConnectionSnapshot {
+        let activity = self.activity.lock();
+        ConnectionSnapshot {
+            id: self.id,
+            peer: self.peer,
+            user: activity.user.clone(),
+            state: activity.state,
+            connected: now.saturating_duration_since(self.connected),
+            in_state: now.saturating_duration_since(activity.since),
+            query: activity.query.clone(),
+        }
+    }
+}
+
+/// A connection that is registered while the guard is held
+pub struct ConnectionGuard(Arc<Connection>);
+
+impl Deref for ConnectionGuard {
+    type Target = Connection;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        CONNECTIONS.lock().remove(&self.0.id);
+    }
+}
+
+/// What a connection was doing when it was listed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionSnapshot {
+    pub id: u64,
+    pub peer: SocketAddr,
+    /// the user that the client authenticated as (if it did)
+    pub user: Option<Box<str>>,
+    pub state: ConnectionState,
+    /// the time since the connection was accepted
+    pub connected: Duration,
+    /// the time since the connection went into its current state
+    pub in_state: Duration,
+    /// the query that is running (or that started the subscription)
+    pub query: Option<Box<str>>,
+}
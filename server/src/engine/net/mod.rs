@@ -24,21 +24,42 @@
  *
 */
 
+pub mod connections;
 pub mod protocol;
+#[cfg(test)]
+mod tests;
 
 use {
+    self::{
+        connections::{Connection, ConnectionGuard},
+        protocol::QueryHost,
+    },
     crate::engine::{
-        config::ConfigEndpointTcp, error::RuntimeResult, fractal::error::ErrorContext,
-        fractal::Global,
+        config::ConfigEndpointTcp,
+        error::{ErrorKind, RuntimeResult},
+        fractal::{
+            context::Subsystem,
+            error::{Error, ErrorContext},
+            Global,
+        },
+        logging::Correlation,
     },
     bytes::BytesMut,
     openssl::{
         pkey::PKey,
         ssl::Ssl,
-        ssl::{SslAcceptor, SslMethod},
-        x509::X509,
+        ssl::{SslAcceptor, SslConnector, SslMethod, SslVerifyMode},
+        x509::{store::X509StoreBuilder, X509},
+    },
+    std::{
+        cell::Cell,
+        fmt::Display,
+        future::Future,
+        net::SocketAddr,
+        pin::Pin,
+        sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+        time::Duration,
     },
-    std::{cell::Cell, net::SocketAddr, pin::Pin, time::Duration},
     tokio::{
         io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufWriter},
         net::{TcpListener, TcpStream},
@@ -55,11 +76,42 @@ const BUF_READ_CAP: usize = 16384;
 const CLIMIT: usize = 50000;
 
 static CLIM: Semaphore = Semaphore::const_new(CLIMIT);
+/// client connections that are currently open
+static CONN_ACTIVE: AtomicUsize = AtomicUsize::new(0);
+/// client connections accepted since startup
+static CONN_TOTAL: AtomicU64 = AtomicU64::new(0);
+/// the time (in seconds) a client connection may send nothing for before it's closed (0 if they're kept open)
+static IDLE_TIMEOUT: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of client connections that are currently open
+pub fn connections_active() -> usize {
+    CONN_ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Returns the number of client connections accepted since startup
+pub fn connections_total() -> u64 {
+    CONN_TOTAL.load(Ordering::Relaxed)
+}
+
+/// Close client connections that send nothing (not even a ping) for the given number of seconds. Only applies to
+/// connections accepted after this is set
+pub fn set_idle_timeout(timeout: Option<u64>) {
+    IDLE_TIMEOUT.store(timeout.unwrap_or(0), Ordering::Relaxed)
+}
+
+fn idle_timeout() -> Option<Duration> {
+    match IDLE_TIMEOUT.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
 
 enum QueryLoopResult {
     Fin,
     Rst,
     HSFailed,
+    /// the client sent nothing for longer than the idle timeout
+    Idle,
 }
 
 /*
@@ -91,85 +143,107 @@ impl NetBackoff {
 unsafe impl Send for NetBackoff {}
 unsafe impl Sync for NetBackoff {}
 
+/// Accept a connection, backing off (and retrying) if that fails (for example, if we've run out of file descriptors)
+async fn accept(listener: &TcpListener) -> IoResult<(TcpStream, SocketAddr)> {
+    let backoff = NetBackoff::new();
+    loop {
+        match listener.accept().await {
+            Ok(s) => return Ok(s),
+            Err(e) => {
+                if backoff.should_disconnect() {
+                    // that's enough of your crappy connection dear sir
+                    return Err(e);
+                }
+            }
+        }
+        backoff.spin().await;
+    }
+}
+
 /*
     listener
 */
 
 /// Connection handler for a remote connection
-pub struct ConnectionHandler<S> {
+pub struct ConnectionHandler<S, H = Global> {
+    conn: ConnectionGuard,
     socket: BufWriter<S>,
     buffer: BytesMut,
-    global: Global,
+    host: H,
     sig_terminate: broadcast::Receiver<()>,
     _sig_inflight_complete: mpsc::Sender<()>,
 }
 
-impl<S: Socket> ConnectionHandler<S> {
+impl<S: Socket, H: QueryHost> ConnectionHandler<S, H> {
     pub fn new(
         socket: S,
-        global: Global,
+        peer: SocketAddr,
+        host: H,
         term_sig: broadcast::Receiver<()>,
         _inflight_complete: mpsc::Sender<()>,
     ) -> Self {
+        CONN_ACTIVE.fetch_add(1, Ordering::Relaxed);
+        // connections are numbered from 1 (in the order that they were accepted)
+        let id = CONN_TOTAL.fetch_add(1, Ordering::Relaxed) + 1;
         Self {
+            conn: Connection::register(id, peer),
             socket: BufWriter::with_capacity(BUF_WRITE_CAP, socket),
             buffer: BytesMut::with_capacity(BUF_READ_CAP),
-            global,
+            host,
             sig_terminate: term_sig,
             _sig_inflight_complete: _inflight_complete,
         }
     }
     pub async fn run(&mut self) -> IoResult<()> {
         let Self {
+            conn,
             socket,
             buffer,
-            global,
+            host,
+            sig_terminate,
             ..
         } = self;
-        loop {
-            tokio::select! {
-                ret = protocol::query_loop(socket, buffer, global) => {
-                    socket.flush().await?;
-                    match ret {
-                        Ok(QueryLoopResult::Fin) => return Ok(()),
-                        Ok(QueryLoopResult::Rst) => error!("connection reset while talking to client"),
-                        Ok(QueryLoopResult::HSFailed) => error!("failed to handshake with client"),
-                        Err(e) => {
-                            error!("error while handling connection: {e}");
-                            return Err(e);
-                        }
-                    }
-                    return Ok(())
-                },
-                _ = self.sig_terminate.recv() => {
-                    return Ok(());
-                }
-            }
-        }
+        // the query loop watches for the termination signal itself so that a running query isn't cut short
+        let ret = protocol::query_loop(socket, buffer, host, sig_terminate, conn).await;
+        socket.flush().await?;
+        Correlation::connection(conn.id()).scope(|| match &ret {
+            Ok(QueryLoopResult::Fin) => {}
+            Ok(QueryLoopResult::Rst) => error!("connection reset while talking to client"),
+            Ok(QueryLoopResult::HSFailed) => error!("failed to handshake with client"),
+            Ok(QueryLoopResult::Idle) => info!("closed connection since the client was idle"),
+            Err(e) => error!("error while handling connection: {e}"),
+        });
+        ret.map(|_| ())
+    }
+}
+
+impl<S, H> Drop for ConnectionHandler<S, H> {
+    fn drop(&mut self) {
+        CONN_ACTIVE.fetch_sub(1, Ordering::Relaxed);
     }
 }
 
 /// A TCP listener bound to a socket
-pub struct Listener {
-    global: Global,
+pub struct Listener<H = Global> {
+    host: H,
     listener: TcpListener,
     sig_shutdown: broadcast::Sender<()>,
     sig_inflight: mpsc::Sender<()>,
     sig_inflight_wait: mpsc::Receiver<()>,
 }
 
-impl Listener {
+impl<H: QueryHost> Listener<H> {
     pub async fn new_cfg(
         tcp: &ConfigEndpointTcp,
-        global: Global,
+        query_host: H,
         sig_shutdown: broadcast::Sender<()>,
     ) -> RuntimeResult<Self> {
-        Self::new(tcp.host(), tcp.port(), global, sig_shutdown).await
+        Self::new(tcp.host(), tcp.port(), query_host, sig_shutdown).await
     }
     pub async fn new(
         host: &str,
         port: u16,
-        global: Global,
+        query_host: H,
         sig_shutdown: broadcast::Sender<()>,
     ) -> RuntimeResult<Self> {
         let (sig_inflight, sig_inflight_wait) = mpsc::channel(1);
@@ -177,7 +251,7 @@ impl Listener {
             .await
             .set_dmsg(format!("failed to bind to port `{host}:{port}`"))?;
         Ok(Self {
-            global,
+            host: query_host,
             listener,
             sig_shutdown,
             sig_inflight,
@@ -196,25 +270,13 @@ impl Listener {
         let _ = sig_inflight_wait.recv().await; // wait
     }
     async fn accept(&mut self) -> IoResult<(TcpStream, SocketAddr)> {
-        let backoff = NetBackoff::new();
-        loop {
-            match self.listener.accept().await {
-                Ok(s) => return Ok(s),
-                Err(e) => {
-                    if backoff.should_disconnect() {
-                        // that's enough of your crappy connection dear sir
-                        return Err(e.into());
-                    }
-                }
-            }
-            backoff.spin().await;
-        }
+        accept(&self.listener).await
     }
     pub async fn listen_tcp(&mut self) {
         loop {
             // acquire a permit
             let permit = CLIM.acquire().await.unwrap();
-            let (stream, _) = match self.accept().await {
+            let (stream, peer) = match self.accept().await {
                 Ok(s) => s,
                 Err(e) => {
                     /*
@@ -226,7 +288,8 @@ impl Listener {
             };
             let mut handler = ConnectionHandler::new(
                 stream,
-                self.global.clone(),
+                peer,
+                self.host.clone(),
                 self.sig_shutdown.subscribe(),
                 self.sig_inflight.clone(),
             );
@@ -243,7 +306,24 @@ impl Listener {
         tls_cert: &str,
         tls_priv_key: &str,
         tls_key_password: &str,
+        tls_client_ca: Option<&str>,
     ) -> RuntimeResult<SslAcceptor> {
+        // if a client CA is set, every client must present a certificate signed by it
+        let client_cas = match tls_client_ca {
+            Some(ca) => {
+                let cas = X509::stack_from_pem(ca.as_bytes())
+                    .set_dmsg("failed to load TLS client CA certificates")?;
+                if cas.is_empty() {
+                    return Err(Error::new(
+                        ErrorKind::Other("no certificates found in TLS client CA".into()),
+                        Subsystem::Network,
+                        "failed to load TLS client CA certificates",
+                    ));
+                }
+                cas
+            }
+            None => vec![],
+        };
         let build_acceptor = || {
             let cert = X509::from_pem(tls_cert.as_bytes())?;
             let priv_key = PKey::private_key_from_pem_passphrase(
@@ -254,6 +334,13 @@ impl Listener {
             builder.set_certificate(&cert)?;
             builder.set_private_key(&priv_key)?;
             builder.check_private_key()?;
+            if !client_cas.is_empty() {
+                for ca in client_cas {
+                    builder.add_client_ca(&ca)?;
+                    builder.cert_store_mut().add_cert(ca)?;
+                }
+                builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+            }
             Ok::<_, openssl::error::ErrorStack>(builder.build())
         };
         let acceptor = build_acceptor().set_dmsg("failed to initialize TLS socket")?;
@@ -262,13 +349,13 @@ impl Listener {
     pub async fn listen_tls(&mut self, acceptor: &SslAcceptor) {
         loop {
             let stream = async {
-                let (stream, _) = self.accept().await?;
+                let (stream, peer) = self.accept().await?;
                 let ssl = Ssl::new(acceptor.context())?;
                 let mut stream = SslStream::new(ssl, stream)?;
                 Pin::new(&mut stream).accept().await?;
-                RuntimeResult::Ok(stream)
+                RuntimeResult::Ok((stream, peer))
             };
-            let stream = match stream.await {
+            let (stream, peer) = match stream.await {
                 Ok(s) => s,
                 Err(e) => {
                     /*
@@ -280,7 +367,8 @@ impl Listener {
             };
             let mut handler = ConnectionHandler::new(
                 stream,
-                self.global.clone(),
+                peer,
+                self.host.clone(),
                 self.sig_shutdown.subscribe(),
                 self.sig_inflight.clone(),
             );
@@ -292,3 +380,87 @@ impl Listener {
         }
     }
 }
+
+/// A TCP listener for one of the protocols that we serve besides Skyhash (the web bridge, for example). Unlike
+/// [`Listener`], it leaves it to the protocol to track its connections
+pub struct ProtocolListener {
+    /// the name of the protocol (for logs)
+    protocol: &'static str,
+    listener: TcpListener,
+}
+
+impl ProtocolListener {
+    pub async fn bind(ep: &ConfigEndpointTcp, protocol: &'static str) -> RuntimeResult<Self> {
+        let listener = TcpListener::bind((ep.host(), ep.port()))
+            .await
+            .set_dmsg(format!(
+                "failed to bind {protocol} endpoint to port `{}:{}`",
+                ep.host(),
+                ep.port()
+            ))?;
+        Ok(Self { protocol, listener })
+    }
+    /// Accept connections forever, serving each of them in its own task (with the future that `serve` returns for it)
+    pub async fn listen<F, E>(&self, mut serve: impl FnMut(TcpStream, SocketAddr) -> F)
+    where
+        F: Future<Output = Result<(), E>> + Send + 'static,
+        E: Display,
+    {
+        let protocol = self.protocol;
+        loop {
+            let (stream, peer) = match accept(&self.listener).await {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("failed to accept connection on {protocol} socket: `{e}`");
+                    continue;
+                }
+            };
+            let serve = serve(stream, peer);
+            tokio::spawn(async move {
+                if let Err(e) = serve.await {
+                    warn!("error serving {protocol} connection from `{peer}`: `{e}`");
+                }
+            });
+        }
+    }
+}
+
+/// Set up a TLS client that only trusts servers with a certificate signed by one of the given CA certificates. If an
+/// identity (a certificate, its private key and the passphrase of the key) is given, it's presented to servers that
+/// ask for a client certificate
+pub fn init_tls_connector(
+    tls_ca: &str,
+    identity: Option<(&str, &str, &str)>,
+) -> RuntimeResult<SslConnector> {
+    let cas =
+        X509::stack_from_pem(tls_ca.as_bytes()).set_dmsg("failed to load TLS CA certificates")?;
+    if cas.is_empty() {
+        return Err(Error::new(
+            ErrorKind::Other("no certificates found in TLS CA".into()),
+            Subsystem::Network,
+            "failed to load TLS CA certificates",
+        ));
+    }
+    let build_connector = || {
+        let mut builder = SslConnector::builder(SslMethod::tls())?;
+        // don't trust the system's CAs
+        let mut store = X509StoreBuilder::new()?;
+        for ca in cas {
+            store.add_cert(ca)?;
+        }
+        builder.set_cert_store(store.build());
+        if let Some((tls_cert, tls_priv_key, tls_key_password)) = identity {
+            let cert = X509::from_pem(tls_cert.as_bytes())?;
+            let priv_key = PKey::private_key_from_pem_passphrase(
+                tls_priv_key.as_bytes(),
+                tls_key_password.as_bytes(),
+            )?;
+            builder.set_certificate(&cert)?;
+            builder.set_private_key(&priv_key)?;
+            builder.check_private_key()?;
+        }
+        Ok::<_, openssl::error::ErrorStack>(builder.build())
+    };
+    let connector = build_connector().set_dmsg("failed to initialize TLS client")?;
+    Ok(connector)
+}
@@ -0,0 +1,113 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! # Compression
+//!
+//! A client that negotiated [`Capabilities::COMPRESSION`](super::handshake::Capabilities::COMPRESSION) can send any
+//! query packet compressed (with LZ4), as `Z<compressed size>\n<original size>\n<compressed packet>`. The server does
+//! the same for responses that are larger than [`THRESHOLD`], using [`ResponseType::Compressed`] as the type.
+//!
+//! Small responses are never compressed since it isn't worth the extra work (on both ends)
+
+use {
+    super::ResponseType,
+    crate::engine::mem::scanner::{BufferedScanner, ScannerDecodeResult},
+    bytes::BytesMut,
+};
+
+/// The first byte of a compressed query packet
+pub const COMPRESSED: u8 = b'Z';
+/// Responses that are smaller than this (in bytes) are sent as is
+pub const THRESHOLD: usize = 4 * 1024;
+/// The largest query packet that we'll decompress
+pub const MAX_PACKET_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub enum Decompressed {
+    /// the whole packet isn't here yet
+    NeedMore,
+    /// the buffer now has the original packet in place of the compressed one
+    Okay,
+    /// the packet is broken (or too large)
+    Error,
+}
+
+/// Decompress the compressed packet at the start of `buf` (in place)
+pub fn decompress_packet(buf: &mut BytesMut) -> Decompressed {
+    debug_assert_eq!(buf.first(), Some(&COMPRESSED));
+    let mut scanner = BufferedScanner::new(&buf[1..]);
+    let mut header = [0u64; 2];
+    for size in header.iter_mut() {
+        if scanner.eof() {
+            return Decompressed::NeedMore;
+        }
+        *size = match scanner.try_next_ascii_u64_lf_separated_with_result_or_restore_cursor() {
+            ScannerDecodeResult::Value(v) => v,
+            ScannerDecodeResult::NeedMore => return Decompressed::NeedMore,
+            ScannerDecodeResult::Error => return Decompressed::Error,
+        };
+    }
+    let [compressed_size, original_size] = header;
+    if original_size > MAX_PACKET_SIZE || compressed_size > MAX_PACKET_SIZE {
+        return Decompressed::Error;
+    }
+    let Some(compressed) = scanner.try_next_variable_block(compressed_size as usize) else {
+        return Decompressed::NeedMore;
+    };
+    let Ok(packet) = lz4_flex::block::decompress(compressed, original_size as usize) else {
+        return Decompressed::Error;
+    };
+    if packet.len() != original_size as usize {
+        return Decompressed::Error;
+    }
+    let consumed = 1 + scanner.cursor();
+    let rest = buf.split_off(consumed);
+    buf.clear();
+    buf.extend_from_slice(&packet);
+    buf.unsplit(rest);
+    Decompressed::Okay
+}
+
+/// Compress an encoded response, if it's large enough for that to be worth it. Returns [`None`] if the response
+/// should be sent as is
+pub fn compress_response(response: &[u8]) -> Option<Vec<u8>> {
+    if response.len() < THRESHOLD {
+        return None;
+    }
+    let compressed = lz4_flex::block::compress(response);
+    if compressed.len() >= response.len() {
+        // doesn't compress well
+        return None;
+    }
+    let mut ret = Vec::with_capacity(compressed.len() + 2 * 20 + 3);
+    ret.push(ResponseType::Compressed.value_u8());
+    ret.extend_from_slice(compressed.len().to_string().as_bytes());
+    ret.push(b'\n');
+    ret.extend_from_slice(response.len().to_string().as_bytes());
+    ret.push(b'\n');
+    ret.extend_from_slice(&compressed);
+    Some(ret)
+}
@@ -68,15 +68,41 @@ pub(super) enum LFTIntParseResult {
     Error,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// What the client wants to do with a query packet
+pub enum QueryKind {
+    /// `S`: run the query (with its parameters)
+    Simple,
+    /// `P`: prepare the query, which can then be executed any number of times (it has no parameters)
+    Prepare,
+    /// `E`: execute a prepared statement. The query is the id of the statement, and it's followed by the parameters
+    Execute,
+}
+
+impl QueryKind {
+    fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            b'S' => Some(Self::Simple),
+            b'P' => Some(Self::Prepare),
+            b'E' => Some(Self::Execute),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct SQuery<'a> {
+    kind: QueryKind,
     q: &'a [u8],
     q_window: usize,
 }
 
 impl<'a> SQuery<'a> {
-    pub(super) fn new(q: &'a [u8], q_window: usize) -> Self {
-        Self { q, q_window }
+    pub(in crate::engine) fn new(kind: QueryKind, q: &'a [u8], q_window: usize) -> Self {
+        Self { kind, q, q_window }
+    }
+    pub fn kind(&self) -> QueryKind {
+        self.kind
     }
     pub fn payload(&self) -> &'a [u8] {
         self.q
@@ -165,6 +191,10 @@ impl Default for QExchangeStateInternal {
 
 #[derive(Debug, PartialEq)]
 pub(super) struct QExchangeState {
+    kind: QueryKind,
+    pipeline: bool,
+    /// set for a pipeline whose queries are run in a transaction (see [`QExchangeResult::BatchCompleted`])
+    batch: bool,
     state: QExchangeStateInternal,
     target: usize,
     md_packet_size: u64,
@@ -182,6 +212,11 @@ impl Default for QExchangeState {
 pub(super) enum QExchangeResult<'a> {
     /// We completed the exchange and yielded a [`SQuery`]
     SQCompleted(SQuery<'a>),
+    /// We completed the exchange and yielded a pipeline of queries (in the order that they should be run)
+    PipelineCompleted(Vec<SQuery<'a>>),
+    /// We completed the exchange and yielded a batch of queries, which are run in order in a transaction (if one
+    /// fails, the changes of the ones before it are undone)
+    BatchCompleted(Vec<SQuery<'a>>),
     /// We're changing states
     ChangeState(QExchangeState),
     /// We hit an error and need to terminate this exchange
@@ -196,6 +231,9 @@ impl QExchangeState {
         md_q_window: u64,
     ) -> Self {
         Self {
+            kind: QueryKind::Simple,
+            pipeline: false,
+            batch: false,
             state,
             target,
             md_packet_size,
@@ -218,6 +256,10 @@ impl QExchangeState {
     pub fn new() -> Self {
         Self::_new(QExchangeStateInternal::Initial, Self::MIN_READ, 0, 0)
     }
+    /// Returns true if we haven't started reading a packet yet
+    pub fn is_initial(&self) -> bool {
+        self.state == QExchangeStateInternal::Initial
+    }
     pub fn has_reached_target(&self, new_buffer: &[u8]) -> bool {
         new_buffer.len() >= self.target
     }
@@ -230,10 +272,20 @@ impl QExchangeState {
             QExchangeStateInternal::PendingData => self.resume_data(scanner),
         }
     }
-    fn start_initial<'a>(self, scanner: &mut BufferedScanner<'a>) -> QExchangeResult<'a> {
-        if unsafe { scanner.next_byte() } != b'S' {
-            // has to be a simple query!
-            return QExchangeResult::Error;
+    fn start_initial<'a>(mut self, scanner: &mut BufferedScanner<'a>) -> QExchangeResult<'a> {
+        match unsafe { scanner.next_byte() } {
+            // a pipeline: the second metaframe is the number of queries, and the data is the queries themselves
+            b'M' => self.pipeline = true,
+            // a batch is framed just like a pipeline
+            b'B' => {
+                self.pipeline = true;
+                self.batch = true;
+            }
+            b => match QueryKind::from_byte(b) {
+                Some(kind) => self.kind = kind,
+                // has to be a simple query, a prepare, an execute, a pipeline or a batch!
+                None => return QExchangeResult::Error,
+            },
         }
         self.resume_at_md1(scanner, true)
     }
@@ -280,9 +332,20 @@ impl QExchangeState {
     fn resume_data<'a>(mut self, scanner: &mut BufferedScanner<'a>) -> QExchangeResult<'a> {
         let df_size = self.target - scanner.cursor();
         if scanner.remaining() == df_size {
-            unsafe {
+            let data = unsafe {
+                // UNSAFE(@ohsayan): we just checked that the whole packet is here
+                scanner.next_chunk_variable(df_size)
+            };
+            if self.pipeline {
+                match scan_pipeline(&mut BufferedScanner::new(data), self.md_q_window) {
+                    Some(queries) if self.batch => QExchangeResult::BatchCompleted(queries),
+                    Some(queries) => QExchangeResult::PipelineCompleted(queries),
+                    None => QExchangeResult::Error,
+                }
+            } else {
                 QExchangeResult::SQCompleted(SQuery::new(
-                    scanner.next_chunk_variable(df_size),
+                    self.kind,
+                    data,
                     self.md_q_window as usize,
                 ))
             }
@@ -292,3 +355,53 @@ impl QExchangeState {
         }
     }
 }
+
+/*
+    pipeline
+*/
+
+/// Scan the `count` queries in a pipeline, each of which is a complete query packet. Returns [`None`] if the pipeline
+/// is empty or if it doesn't have exactly `count` well formed queries (in which case none of them should be run)
+pub(super) fn scan_pipeline<'a>(
+    scanner: &mut BufferedScanner<'a>,
+    count: u64,
+) -> Option<Vec<SQuery<'a>>> {
+    if (count == 0) | (count > scanner.remaining() as u64) {
+        return None;
+    }
+    let mut queries = Vec::with_capacity(count as usize);
+    while !scanner.eof() {
+        queries.push(scan_pipelined_query(scanner)?);
+    }
+    (queries.len() as u64 == count).then_some(queries)
+}
+
+fn scan_pipelined_query<'a>(scanner: &mut BufferedScanner<'a>) -> Option<SQuery<'a>> {
+    let kind = QueryKind::from_byte(unsafe {
+        // UNSAFE(@ohsayan): the caller checks that we're not at the end of the buffer
+        scanner.next_byte()
+    })?;
+    if scanner.eof() {
+        return None;
+    }
+    let LFTIntParseResult::Value(packet_size) = scanint(scanner, true, 0) else {
+        return None;
+    };
+    let start = scanner.cursor();
+    if scanner.eof() {
+        return None;
+    }
+    let LFTIntParseResult::Value(q_window) = scanint(scanner, true, 0) else {
+        return None;
+    };
+    // like a query packet, the size includes the second metaframe
+    let df_size = (packet_size as usize).checked_sub(scanner.cursor() - start)?;
+    if (df_size > scanner.remaining()) | (q_window as usize > df_size) {
+        return None;
+    }
+    let payload = unsafe {
+        // UNSAFE(@ohsayan): we just checked that we have these many bytes
+        scanner.next_chunk_variable(df_size)
+    };
+    Some(SQuery::new(kind, payload, q_window as usize))
+}
@@ -48,6 +48,8 @@ pub enum ProtocolError {
     /// **NB**: this can be due to either an incorrect auth flag, or incorrect auth data or disallowed auth mode. we keep it
     /// in one error for purposes of security
     RejectAuth = 5,
+    /// the user already has as many connections open as their quota allows
+    RejectQuota = 6,
 }
 
 /*
@@ -60,6 +62,52 @@ pub enum ProtocolError {
 pub enum HandshakeVersion {
     /// Skyhash/2.0 HS
     Original = 0,
+    /// Skyhash/2.0 HS, with the capabilities that the client wants (`<bitmap>\n`) right after the static block. The
+    /// server responds with the capabilities that it granted (in the same format) after the usual response
+    Capabilities = 1,
+}
+
+impl HandshakeVersion {
+    unsafe fn from_raw(v: u8) -> Self {
+        core::mem::transmute(v)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// A set of protocol features. A client asks for the ones that it wants during the handshake, and the server grants the
+/// ones that it supports, so that new features can be added without breaking old clients (which never ask for them)
+pub struct Capabilities(u64);
+
+impl Capabilities {
+    /// compressed query packets and responses (LZ4)
+    pub const COMPRESSION: Self = Self(1 << 0);
+    /// query pipelines
+    pub const PIPELINE: Self = Self(1 << 1);
+    /// subscriptions (changes that are streamed to the client)
+    pub const CDC: Self = Self(1 << 2);
+    /// authenticating with a token instead of a password
+    pub const TOKEN_AUTH: Self = Self(1 << 3);
+    /// batches (pipelines whose changes are undone if one of their queries fails)
+    pub const BATCH: Self = Self(1 << 4);
+    /// the capabilities that this server grants
+    pub const SUPPORTED: Self = Self(Self::ORIGINAL.0 | Self::COMPRESSION.0 | Self::BATCH.0);
+    /// the capabilities of clients that use the original handshake: everything that was available before
+    /// capabilities were negotiated
+    pub const ORIGINAL: Self = Self(Self::PIPELINE.0 | Self::CDC.0 | Self::TOKEN_AUTH.0);
+    pub const fn new(raw: u64) -> Self {
+        Self(raw)
+    }
+    pub const fn raw(&self) -> u64 {
+        self.0
+    }
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// Returns the capabilities that are granted to a client that asked for these (bits that we don't know of are
+    /// ignored)
+    pub const fn negotiate(&self) -> Self {
+        Self(self.0 & Self::SUPPORTED.0)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, sky_macros::EnumMethods)]
@@ -90,17 +138,27 @@ pub enum QueryMode {
 #[repr(u8)]
 /// the authentication mode
 pub enum AuthMode {
+    /// username and password
     Password = 0,
+    /// username and a token (issued with `sysctl issue token`)
+    #[allow(dead_code)] // only constructed from the raw handshake byte
+    Token = 1,
 }
 
 impl AuthMode {
     unsafe fn from_raw(v: u8) -> Self {
         core::mem::transmute(v)
     }
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Password => "password",
+            Self::Token => "token",
+        }
+    }
     /// returns the minimum number of metadata bytes need to parse the payload for this auth mode
     const fn min_payload_bytes(&self) -> usize {
         match self {
-            Self::Password => 4,
+            Self::Password | Self::Token => 4,
         }
     }
 }
@@ -114,7 +172,9 @@ impl AuthMode {
 pub enum HandshakeState {
     /// we just began the handshake
     Initial,
-    /// we just processed the static block
+    /// we just processed the static block, and are expecting the capabilities
+    ExpectingCapabilities(CHandshakeStatic),
+    /// we just processed the static block (and the capabilities, if any)
     StaticBlock(CHandshakeStatic),
     /// Expecting some more auth meta
     ExpectingMetaForVariableBlock {
@@ -153,6 +213,8 @@ pub struct CHandshakeStatic {
     query_mode: QueryMode,
     /// authentication mode
     auth_mode: AuthMode,
+    /// the capabilities that the client asked for
+    capabilities: Capabilities,
 }
 
 impl CHandshakeStatic {
@@ -169,8 +231,13 @@ impl CHandshakeStatic {
             exchange_mode,
             query_mode,
             auth_mode,
+            capabilities: Capabilities::ORIGINAL,
         }
     }
+    pub const fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
     pub fn hs_version(&self) -> HandshakeVersion {
         self.hs_version
     }
@@ -186,6 +253,10 @@ impl CHandshakeStatic {
     pub fn auth_mode(&self) -> AuthMode {
         self.auth_mode
     }
+    /// The capabilities that the client asked for ([`Capabilities::ORIGINAL`] if it used the original handshake)
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
 }
 
 /// handshake authentication
@@ -203,6 +274,7 @@ impl<'a> CHandshakeAuth<'a> {
     pub fn username(&self) -> &[u8] {
         self.username
     }
+    /// Returns the password (or the token, if this is token auth)
     pub fn password(&self) -> &[u8] {
         self.password
     }
@@ -246,6 +318,10 @@ impl<'a> CHandshake<'a> {
         match state {
             // nothing buffered yet
             HandshakeState::Initial => Self::resume_initial(scanner),
+            // buffered static block, but not the capabilities
+            HandshakeState::ExpectingCapabilities(static_block) => {
+                Self::resume_at_capabilities(scanner, static_block)
+            }
             // buffered static block
             HandshakeState::StaticBlock(static_block) => {
                 Self::resume_at_auth_metadata1(scanner, static_block)
@@ -319,8 +395,12 @@ impl<'a> CHandshake<'a> {
             );
         }
         // init header
+        let hs_version = unsafe {
+            // UNSAFE(@ohsayan): already checked
+            HandshakeVersion::from_raw(buf[1])
+        };
         let static_header = CHandshakeStatic::new(
-            HandshakeVersion::Original,
+            hs_version,
             ProtocolVersion::Original,
             DataExchangeMode::QueryTime,
             QueryMode::Bql1,
@@ -329,8 +409,28 @@ impl<'a> CHandshake<'a> {
                 AuthMode::from_raw(buf[5])
             },
         );
-        // check if we have auth data
-        Self::resume_at_auth_metadata1(scanner, static_header)
+        match hs_version {
+            // check if we have auth data
+            HandshakeVersion::Original => Self::resume_at_auth_metadata1(scanner, static_header),
+            HandshakeVersion::Capabilities => Self::resume_at_capabilities(scanner, static_header),
+        }
+    }
+    /// Resume parsing at the capabilities (which come right after the static block)
+    fn resume_at_capabilities(
+        scanner: &mut BufferedScanner<'a>,
+        static_header: CHandshakeStatic,
+    ) -> HandshakeResult<'a> {
+        match scanner.try_next_ascii_u64_lf_separated_with_result_or_restore_cursor() {
+            ScannerDecodeResult::Value(v) => Self::resume_at_auth_metadata1(
+                scanner,
+                static_header.with_capabilities(Capabilities::new(v)),
+            ),
+            ScannerDecodeResult::NeedMore => HandshakeResult::ChangeState {
+                new_state: HandshakeState::ExpectingCapabilities(static_header),
+                expect: 2, // atleast one digit and the LF
+            },
+            ScannerDecodeResult::Error => HandshakeResult::Error(ProtocolError::CorruptedHSPacket),
+        }
     }
     fn resume_at_variable_block_payload(
         scanner: &mut BufferedScanner<'a>,
@@ -374,7 +474,8 @@ impl<'a> CHandshake<'a> {
         }
         // we seem to have enough data for this auth mode
         match static_header.auth_mode {
-            AuthMode::Password => {}
+            // the token is sent in place of the password
+            AuthMode::Password | AuthMode::Token => {}
         }
         // let us see if we can parse the username length
         let uname_l = match scanner.try_next_ascii_u64_lf_separated_with_result_or_restore_cursor()
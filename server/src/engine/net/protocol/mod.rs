@@ -24,31 +24,44 @@
  *
 */
 
+mod compression;
 mod exchange;
 mod handshake;
 #[cfg(test)]
 mod tests;
 
 // re-export
-pub use exchange::SQuery;
+pub use exchange::{QueryKind, SQuery};
 
 use {
     self::{
+        compression::Decompressed,
         exchange::{QExchangeResult, QExchangeState},
         handshake::{
-            AuthMode, CHandshake, DataExchangeMode, HandshakeResult, HandshakeState,
+            AuthMode, CHandshake, Capabilities, DataExchangeMode, HandshakeResult, HandshakeState,
             HandshakeVersion, ProtocolError, ProtocolVersion, QueryMode,
         },
     },
-    super::{IoResult, QueryLoopResult, Socket},
+    super::{connections::Connection, IoResult, QueryLoopResult, Socket},
     crate::engine::{
         self,
-        error::QueryError,
-        fractal::{Global, GlobalInstanceLike},
+        core::prepared::PreparedStatements,
+        error::{QueryError, QueryResult},
+        fractal::{cdc::Subscription, sys_store::SystemStore, Global, GlobalInstanceLike},
+        logging::Correlation,
         mem::{BufferedScanner, IntegerRepr},
+        storage::v1::{
+            audit::{AuditEvent, AuditEventKind},
+            LocalFS,
+        },
     },
     bytes::{Buf, BytesMut},
-    tokio::io::{AsyncReadExt, AsyncWriteExt, BufWriter},
+    std::{future::Future, net::SocketAddr, sync::Arc, time::Duration},
+    tokio::{
+        io::{AsyncReadExt, AsyncWriteExt, BufWriter},
+        sync::broadcast,
+        time::Instant,
+    },
 };
 
 #[repr(u8)]
@@ -75,14 +88,27 @@ pub enum ResponseType {
     Row = 0x11,
     Empty = 0x12,
     MultiRow = 0x13,
+    Event = 0x14,
+    /// the response to a [`PING`]
+    Pong = 0x15,
+    /// a response that was compressed (see [`compression`])
+    Compressed = 0x16,
 }
 
+/// A client can send this byte between queries (or while subscribed) to check that the connection is alive, which the
+/// server responds to with [`ResponseType::Pong`]. It also keeps the connection from being closed for being idle
+pub const PING: u8 = b'K';
+
 #[derive(Debug, PartialEq)]
 pub struct ClientLocalState {
     username: Box<str>,
     root: bool,
     hs: handshake::CHandshakeStatic,
+    capabilities: Capabilities,
     cs: Option<Box<str>>,
+    correlation: Correlation,
+    peer: Option<SocketAddr>,
+    prepared: PreparedStatements,
 }
 
 impl ClientLocalState {
@@ -90,13 +116,45 @@ impl ClientLocalState {
         Self {
             username,
             root,
+            capabilities: hs.capabilities().negotiate(),
             hs,
             cs: None,
+            correlation: Correlation::none(),
+            peer: None,
+            prepared: PreparedStatements::default(),
         }
     }
+    /// A client that didn't handshake over the native protocol (for example, one that came in over the web bridge).
+    /// It's treated like a client that connected using the default handshake
+    pub fn new_bridged(username: Box<str>, root: bool) -> Self {
+        Self::new(
+            username,
+            root,
+            handshake::CHandshakeStatic::new(
+                HandshakeVersion::Original,
+                ProtocolVersion::Original,
+                DataExchangeMode::QueryTime,
+                QueryMode::Bql1,
+                AuthMode::Password,
+            ),
+        )
+    }
+    /// A client that connected using the default handshake
+    #[cfg(test)]
+    pub fn new_test(username: &str, root: bool) -> Self {
+        Self::new_bridged(username.into(), root)
+    }
     pub fn is_root(&self) -> bool {
         self.root
     }
+    /// The static block of the handshake that the client sent
+    pub fn handshake(&self) -> &handshake::CHandshakeStatic {
+        &self.hs
+    }
+    /// The capabilities that were negotiated during the handshake
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
     pub fn username(&self) -> &str {
         &self.username
     }
@@ -109,6 +167,105 @@ impl ClientLocalState {
     pub fn get_cs(&self) -> Option<&str> {
         self.cs.as_deref()
     }
+    /// The connection and the query that is being run (for logging)
+    pub fn correlation(&self) -> Correlation {
+        self.correlation
+    }
+    pub fn set_correlation(&mut self, correlation: Correlation) {
+        self.correlation = correlation;
+    }
+    /// The client is connected from this address
+    pub fn with_peer(mut self, peer: SocketAddr) -> Self {
+        self.peer = Some(peer);
+        self
+    }
+    /// The address of the client (for auditing). Empty if unknown
+    pub fn peer_addr(&self) -> String {
+        self.peer.map(|peer| peer.to_string()).unwrap_or_default()
+    }
+    /// The statements that the client has prepared
+    pub fn prepared(&self) -> &PreparedStatements {
+        &self.prepared
+    }
+    pub fn prepared_mut(&mut self) -> &mut PreparedStatements {
+        &mut self.prepared
+    }
+}
+
+/// Runs the queries of the clients that connect to a listener
+///
+/// Once the server has started, this is the database itself. While the data is still being restored, the listeners are
+/// started with a [`StartupHost`] instead
+pub trait QueryHost: Clone + Send + Sync + 'static {
+    /// The system store that clients are authenticated against
+    fn sys_store(&self) -> &SystemStore<LocalFS>;
+    /// Run a query for an authenticated client
+    fn dispatch<'a>(
+        &'a self,
+        cstate: &'a mut ClientLocalState,
+        query: SQuery<'a>,
+    ) -> impl Future<Output = QueryResult<Response>> + Send + 'a;
+    /// Run a batch of queries in a transaction for an authenticated client, returning the response of every query
+    /// (or the error that the batch failed with, once the changes of the queries before it were undone)
+    fn dispatch_batch(
+        &self,
+        cstate: &mut ClientLocalState,
+        queries: Vec<SQuery<'_>>,
+    ) -> QueryResult<Vec<Response>>;
+}
+
+impl QueryHost for Global {
+    fn sys_store(&self) -> &SystemStore<LocalFS> {
+        GlobalInstanceLike::sys_store(self)
+    }
+    fn dispatch<'a>(
+        &'a self,
+        cstate: &'a mut ClientLocalState,
+        query: SQuery<'a>,
+    ) -> impl Future<Output = QueryResult<Response>> + Send + 'a {
+        engine::core::exec::dispatch_to_executor(self, cstate, query)
+    }
+    fn dispatch_batch(
+        &self,
+        cstate: &mut ClientLocalState,
+        queries: Vec<SQuery<'_>>,
+    ) -> QueryResult<Vec<Response>> {
+        engine::core::exec::dispatch_batch_to_executor(self, cstate, queries)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Serves clients while the data is still being restored, so that `sysctl report startup` can be used to see how far
+/// along we are. Clients are authenticated against the system database (which is loaded before the data), and every
+/// other query is rejected
+pub struct StartupHost {
+    sys_store: Arc<SystemStore<LocalFS>>,
+}
+
+impl StartupHost {
+    pub fn new(sys_store: Arc<SystemStore<LocalFS>>) -> Self {
+        Self { sys_store }
+    }
+}
+
+impl QueryHost for StartupHost {
+    fn sys_store(&self) -> &SystemStore<LocalFS> {
+        &self.sys_store
+    }
+    fn dispatch<'a>(
+        &'a self,
+        cstate: &'a mut ClientLocalState,
+        query: SQuery<'a>,
+    ) -> impl Future<Output = QueryResult<Response>> + Send + 'a {
+        core::future::ready(engine::core::exec::dispatch_during_startup(cstate, query))
+    }
+    fn dispatch_batch(
+        &self,
+        _: &mut ClientLocalState,
+        _: Vec<SQuery<'_>>,
+    ) -> QueryResult<Vec<Response>> {
+        Err(QueryError::SysServerStarting)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -118,18 +275,165 @@ pub enum Response {
     Serialized {
         ty: ResponseType,
         size: usize,
-        data: Vec<u8>,
+        data: ResponseBody,
     },
     Bool(bool),
+    UInt(u64),
+    /// switch the connection over to streaming changes
+    Subscription(Subscription),
+}
+
+/// Something that a response can be encoded into
+pub trait ResponseWrite {
+    fn write_byte(&mut self, b: u8);
+    fn write_bytes(&mut self, b: &[u8]);
+    /// Write the payload of a string or binary value (which might be large)
+    fn write_value(&mut self, v: &[u8]) {
+        self.write_bytes(v)
+    }
 }
 
-pub(super) async fn query_loop<S: Socket>(
+impl ResponseWrite for Vec<u8> {
+    fn write_byte(&mut self, b: u8) {
+        self.push(b)
+    }
+    fn write_bytes(&mut self, b: &[u8]) {
+        self.extend_from_slice(b)
+    }
+}
+
+#[derive(Debug, Default)]
+/// The body of a serialized response, which is kept in chunks (and every chunk is freed as soon as it's written out)
+///
+/// Small items are packed into chunks of [`Self::CHUNK_SIZE`] bytes, but a large string or binary value gets a chunk
+/// of its own. This way the body never has to be grown (and copied over) to make room for a multi-megabyte value, which
+/// could otherwise need upto twice its size while the body is being encoded
+pub struct ResponseBody {
+    chunks: Vec<Vec<u8>>,
+    len: usize,
+}
+
+impl ResponseBody {
+    const CHUNK_SIZE: usize = 64 * 1024;
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Returns the size of the body in bytes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    #[allow(unused)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Returns the chunk that the next `size` bytes should be written into
+    fn chunk_for(&mut self, size: usize) -> &mut Vec<u8> {
+        self.len += size;
+        match self.chunks.last() {
+            Some(chunk) if chunk.len() + size <= Self::CHUNK_SIZE => {}
+            _ => self.chunks.push(Vec::with_capacity(size)),
+        }
+        self.chunks.last_mut().unwrap()
+    }
+    /// Returns all the bytes in the body
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.chunks.concat()
+    }
+    fn into_chunks(self) -> impl Iterator<Item = Vec<u8>> {
+        self.chunks.into_iter()
+    }
+}
+
+impl ResponseWrite for ResponseBody {
+    fn write_byte(&mut self, b: u8) {
+        self.chunk_for(1).push(b)
+    }
+    fn write_bytes(&mut self, b: &[u8]) {
+        self.chunk_for(b.len()).extend_from_slice(b)
+    }
+    fn write_value(&mut self, v: &[u8]) {
+        if v.len() < Self::CHUNK_SIZE {
+            return self.write_bytes(v);
+        }
+        // give it a chunk of its own (anything that follows goes into a new chunk)
+        self.len += v.len();
+        self.chunks.push(v.to_vec());
+    }
+}
+
+impl From<Vec<u8>> for ResponseBody {
+    fn from(v: Vec<u8>) -> Self {
+        Self {
+            len: v.len(),
+            chunks: vec![v],
+        }
+    }
+}
+
+impl PartialEq for ResponseBody {
+    fn eq(&self, other: &Self) -> bool {
+        // the same bytes could have been chunked differently
+        (self.len == other.len)
+            & self
+                .chunks
+                .iter()
+                .flatten()
+                .eq(other.chunks.iter().flatten())
+    }
+}
+
+/// Tracks when a client's connection is to be closed for being idle (if there's an idle timeout)
+struct IdleDeadline {
+    timeout: Option<Duration>,
+    at: Instant,
+}
+
+impl IdleDeadline {
+    fn new() -> Self {
+        Self::with_timeout(super::idle_timeout())
+    }
+    fn with_timeout(timeout: Option<Duration>) -> Self {
+        Self {
+            timeout,
+            at: Instant::now() + timeout.unwrap_or_default(),
+        }
+    }
+    /// The client sent something (or we just responded to it)
+    fn reset(&mut self) {
+        if let Some(timeout) = self.timeout {
+            self.at = Instant::now() + timeout;
+        }
+    }
+    /// Resolves once the client has been idle for too long (never, if there's no idle timeout)
+    async fn expired(&self) {
+        match self.timeout {
+            Some(_) => tokio::time::sleep_until(self.at).await,
+            None => core::future::pending().await,
+        }
+    }
+}
+
+/// Serve the client until it disconnects, or until we're asked to terminate (using `term`)
+///
+/// A query that is running when we're asked to terminate is allowed to complete (and its response is sent) before the
+/// connection is closed. A client that sends nothing for longer than the idle timeout (the handshake included) is
+/// disconnected, but a query that runs for longer doesn't count
+pub(super) async fn query_loop<S: Socket, H: QueryHost>(
     con: &mut BufWriter<S>,
     buf: &mut BytesMut,
-    global: &Global,
+    host: &H,
+    term: &mut broadcast::Receiver<()>,
+    conn: &Connection,
 ) -> IoResult<QueryLoopResult> {
+    let (conn_id, peer) = (conn.id(), conn.peer());
+    let mut idle = IdleDeadline::new();
     // handshake
-    let mut client_state = match do_handshake(con, buf, global).await? {
+    let handshake = tokio::select! {
+        hs = do_handshake(con, buf, host, peer) => hs?,
+        _ = term.recv() => return Ok(QueryLoopResult::Fin),
+        _ = idle.expired() => return Ok(QueryLoopResult::Idle),
+    };
+    let mut client_state = match handshake {
         PostHandshake::Okay(hs) => hs,
         PostHandshake::ConnectionClosedFin => return Ok(QueryLoopResult::Fin),
         PostHandshake::ConnectionClosedRst => return Ok(QueryLoopResult::Rst),
@@ -140,34 +444,169 @@ pub(super) async fn query_loop<S: Socket>(
             return Ok(QueryLoopResult::HSFailed);
         }
     };
+    // the connection is counted against the user's quota until we return
+    let sys_cfg = host.sys_store().system_store();
+    let quota = sys_cfg.user_quota(client_state.username());
+    let Some(_connection) = sys_cfg
+        .quota_usage()
+        .connect(client_state.username(), quota)
+    else {
+        Correlation::connection(conn_id).scope(|| {
+            warn!(
+                "rejected connection for user `{}` since they have too many connections open",
+                client_state.username()
+            )
+        });
+        let hs_err_packet = [b'H', 0, 1, ProtocolError::RejectQuota.value_u8()];
+        con.write_all(&hs_err_packet).await?;
+        return Ok(QueryLoopResult::HSFailed);
+    };
     // done handshaking
+    conn.authenticated(client_state.username());
     con.write_all(b"H\x00\x00\x00").await?;
+    if client_state.handshake().hs_version() == HandshakeVersion::Capabilities {
+        // tell the client what it got
+        let mut granted = client_state.capabilities().raw().to_string().into_bytes();
+        granted.push(b'\n');
+        con.write_all(&granted).await?;
+    }
     con.flush().await?;
+    let compress = client_state
+        .capabilities()
+        .contains(Capabilities::COMPRESSION);
     let mut state = QExchangeState::default();
     let mut cursor = Default::default();
+    let mut query_id = 0;
     loop {
-        if con.read_buf(buf).await? == 0 {
+        let read = tokio::select! {
+            read = con.read_buf(buf) => read?,
+            // no query is running, so we can close the connection right away
+            _ = term.recv() => return Ok(QueryLoopResult::Fin),
+            _ = idle.expired() => return Ok(QueryLoopResult::Idle),
+        };
+        if read == 0 {
             if buf.is_empty() {
                 return Ok(QueryLoopResult::Fin);
             } else {
                 return Ok(QueryLoopResult::Rst);
             }
         }
+        idle.reset();
+        if state.is_initial() {
+            // answer the pings that came before the next query
+            let pings = buf.iter().take_while(|b| **b == PING).count();
+            if pings != 0 {
+                buf.advance(pings);
+                for _ in 0..pings {
+                    con.write_u8(ResponseType::Pong.value_u8()).await?;
+                }
+                con.flush().await?;
+                if buf.is_empty() {
+                    continue;
+                }
+            }
+        }
+        if compress && state.is_initial() && buf.first() == Some(&compression::COMPRESSED) {
+            match compression::decompress_packet(buf) {
+                Decompressed::Okay => {}
+                Decompressed::NeedMore => continue,
+                Decompressed::Error => {
+                    Correlation::connection(conn_id)
+                        .scope(|| debug!("client sent an illegal compressed packet"));
+                    write_response(
+                        con,
+                        Err(QueryError::SysNetworkSystemIllegalClientPacket),
+                        false,
+                    )
+                    .await?;
+                    con.flush().await?;
+                    buf.clear();
+                    continue;
+                }
+            }
+        }
         if !state.has_reached_target(buf) {
             // we haven't buffered sufficient bytes; keep working
             continue;
         }
-        let sq = match unsafe {
+        match unsafe {
             // UNSAFE(@ohsayan): as the resume cursor is private, we can't access this anyways
             exchange::resume(buf, cursor, state)
         } {
-            (_, QExchangeResult::SQCompleted(sq)) => sq,
+            (_, QExchangeResult::SQCompleted(sq)) => {
+                // now execute query
+                query_id += 1;
+                client_state.set_correlation(Correlation::query(conn_id, query_id));
+                conn.query(sq.query());
+                match host.dispatch(&mut client_state, sq).await {
+                    Ok(Response::Subscription(_))
+                        if !client_state.capabilities().contains(Capabilities::CDC) =>
+                    {
+                        // the subscription is dropped (and closed) right away
+                        write_response(con, Err(QueryError::SysMissingCapability), compress).await?
+                    }
+                    Ok(Response::Subscription(sub)) => {
+                        conn.subscribed();
+                        con.write_all(&[ResponseType::Empty.value_u8()]).await?;
+                        con.flush().await?;
+                        buf.clear();
+                        if let Some(r) =
+                            stream_changes(con, buf, sub, term, &mut idle, compress).await?
+                        {
+                            return Ok(r);
+                        }
+                    }
+                    r => write_response(con, r, compress).await?,
+                }
+            }
+            (_, QExchangeResult::PipelineCompleted(_))
+                if !client_state.capabilities().contains(Capabilities::PIPELINE) =>
+            {
+                // none of the queries are run
+                write_response(con, Err(QueryError::SysMissingCapability), compress).await?
+            }
+            (_, QExchangeResult::PipelineCompleted(queries)) => {
+                // run the queries one after the other, and send all the responses (in order) once we're done
+                for sq in queries {
+                    query_id += 1;
+                    client_state.set_correlation(Correlation::query(conn_id, query_id));
+                    conn.query(sq.query());
+                    let r = match host.dispatch(&mut client_state, sq).await {
+                        Ok(Response::Subscription(_)) => Err(QueryError::SysPipelineSubscription),
+                        r => r,
+                    };
+                    write_response(con, r, compress).await?;
+                }
+            }
+            (_, QExchangeResult::BatchCompleted(_))
+                if !client_state.capabilities().contains(Capabilities::BATCH) =>
+            {
+                // none of the queries are run
+                write_response(con, Err(QueryError::SysMissingCapability), compress).await?
+            }
+            (_, QExchangeResult::BatchCompleted(queries)) => {
+                // the batch is run in one go, and either every response (in order) or the error that it failed with
+                // is sent back
+                query_id += 1;
+                client_state.set_correlation(Correlation::query(conn_id, query_id));
+                conn.query(queries[0].query());
+                match host.dispatch_batch(&mut client_state, queries) {
+                    Ok(responses) => {
+                        for r in responses {
+                            write_response(con, Ok(r), compress).await?;
+                        }
+                    }
+                    Err(e) => write_response(con, Err(e), compress).await?,
+                }
+            }
             (new_cursor, QExchangeResult::ChangeState(new_state)) => {
                 cursor = new_cursor;
                 state = new_state;
                 continue;
             }
             (_, QExchangeResult::Error) => {
+                Correlation::connection(conn_id)
+                    .scope(|| debug!("client sent an illegal query packet"));
                 // respond with error
                 let [a, b] = (QueryError::SysNetworkSystemIllegalClientPacket.value_u8() as u16)
                     .to_le_bytes();
@@ -180,31 +619,10 @@ pub(super) async fn query_loop<S: Socket>(
                 state = QExchangeState::default();
                 continue;
             }
-        };
-        // now execute query
-        match engine::core::exec::dispatch_to_executor(global, &mut client_state, sq).await {
-            Ok(Response::Empty) => {
-                con.write_all(&[ResponseType::Empty.value_u8()]).await?;
-            }
-            Ok(Response::Serialized { ty, size, data }) => {
-                con.write_u8(ty.value_u8()).await?;
-                let mut irep = IntegerRepr::new();
-                con.write_all(irep.as_bytes(size as u64)).await?;
-                con.write_u8(b'\n').await?;
-                con.write_all(&data).await?;
-            }
-            Ok(Response::Bool(b)) => {
-                con.write_all(&[ResponseType::Bool.value_u8(), b as u8])
-                    .await?
-            }
-            Ok(Response::Null) => con.write_u8(ResponseType::Null.value_u8()).await?,
-            Err(e) => {
-                let [a, b] = (e.value_u8() as u16).to_le_bytes();
-                con.write_all(&[ResponseType::Error.value_u8(), a, b])
-                    .await?;
-            }
         }
+        conn.idle();
         con.flush().await?;
+        idle.reset();
         // reset buffer, cursor and state
         buf.clear();
         cursor = Default::default();
@@ -212,6 +630,110 @@ pub(super) async fn query_loop<S: Socket>(
     }
 }
 
+/// Write the response out. If `compress` is set, a large response is compressed (see [`compression`])
+async fn write_response<S: Socket>(
+    con: &mut BufWriter<S>,
+    r: QueryResult<Response>,
+    compress: bool,
+) -> IoResult<()> {
+    match r {
+        Ok(Response::Serialized { ty, size, data })
+            if compress && data.len() >= compression::THRESHOLD =>
+        {
+            let mut irep = IntegerRepr::new();
+            let mut raw = Vec::with_capacity(data.len() + 22);
+            raw.push(ty.value_u8());
+            raw.extend_from_slice(irep.as_bytes(size as u64));
+            raw.push(b'\n');
+            for chunk in data.into_chunks() {
+                raw.extend_from_slice(&chunk);
+            }
+            match compression::compress_response(&raw) {
+                Some(compressed) => con.write_all(&compressed).await?,
+                None => con.write_all(&raw).await?,
+            }
+        }
+        Ok(Response::Empty) => {
+            con.write_all(&[ResponseType::Empty.value_u8()]).await?;
+        }
+        Ok(Response::Serialized { ty, size, data }) => {
+            con.write_u8(ty.value_u8()).await?;
+            let mut irep = IntegerRepr::new();
+            con.write_all(irep.as_bytes(size as u64)).await?;
+            con.write_u8(b'\n').await?;
+            for chunk in data.into_chunks() {
+                con.write_all(&chunk).await?;
+            }
+        }
+        Ok(Response::Bool(b)) => {
+            con.write_all(&[ResponseType::Bool.value_u8(), b as u8])
+                .await?
+        }
+        Ok(Response::Null) => con.write_u8(ResponseType::Null.value_u8()).await?,
+        Ok(Response::UInt(u)) => {
+            con.write_u8(ResponseType::UInt64.value_u8()).await?;
+            let mut irep = IntegerRepr::new();
+            con.write_all(irep.as_bytes(u)).await?;
+            con.write_u8(b'\n').await?;
+        }
+        Ok(Response::Subscription(_)) => unreachable!("subscriptions are not responses"),
+        Err(e) => {
+            let [a, b] = (e.value_u8() as u16).to_le_bytes();
+            con.write_all(&[ResponseType::Error.value_u8(), a, b])
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Stream changes to the client as events. Anything that the client sends (other than pings, which are answered) ends
+/// the subscription (and is discarded), after which the server responds with an empty response and goes back to running
+/// queries. If the client falls behind, the subscription ends with an error instead
+///
+/// Returns a result if the connection was closed
+async fn stream_changes<S: Socket>(
+    con: &mut BufWriter<S>,
+    buf: &mut BytesMut,
+    mut sub: Subscription,
+    term: &mut broadcast::Receiver<()>,
+    idle: &mut IdleDeadline,
+    compress: bool,
+) -> IoResult<Option<QueryLoopResult>> {
+    loop {
+        tokio::select! {
+            _ = term.recv() => return Ok(Some(QueryLoopResult::Fin)),
+            _ = idle.expired() => return Ok(Some(QueryLoopResult::Idle)),
+            read = con.read_buf(buf) => {
+                if read? == 0 {
+                    return Ok(Some(if buf.is_empty() {
+                        QueryLoopResult::Fin
+                    } else {
+                        QueryLoopResult::Rst
+                    }));
+                }
+                idle.reset();
+                if buf.iter().all(|b| *b == PING) {
+                    for _ in 0..buf.len() {
+                        con.write_u8(ResponseType::Pong.value_u8()).await?;
+                    }
+                    buf.clear();
+                } else {
+                    con.write_all(&[ResponseType::Empty.value_u8()]).await?;
+                    return Ok(None);
+                }
+            }
+            event = sub.next() => {
+                let lagged = event.is_err();
+                write_response(con, event, compress).await?;
+                if lagged {
+                    return Ok(None);
+                }
+            }
+        }
+        con.flush().await?;
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum PostHandshake {
     Okay(ClientLocalState),
@@ -220,10 +742,11 @@ enum PostHandshake {
     ConnectionClosedRst,
 }
 
-async fn do_handshake<S: Socket>(
+async fn do_handshake<S: Socket, H: QueryHost>(
     con: &mut BufWriter<S>,
     buf: &mut BytesMut,
-    global: &Global,
+    host: &H,
+    peer: SocketAddr,
 ) -> IoResult<PostHandshake> {
     let mut expected = CHandshake::INITIAL_READ;
     let mut state = HandshakeState::default();
@@ -260,37 +783,54 @@ async fn do_handshake<S: Socket>(
     }
     // check handshake
     if cfg!(debug_assertions) {
-        assert_eq!(
+        assert!(matches!(
             handshake.hs_static().hs_version(),
-            HandshakeVersion::Original
-        );
+            HandshakeVersion::Original | HandshakeVersion::Capabilities
+        ));
         assert_eq!(handshake.hs_static().protocol(), ProtocolVersion::Original);
         assert_eq!(
             handshake.hs_static().exchange_mode(),
             DataExchangeMode::QueryTime
         );
         assert_eq!(handshake.hs_static().query_mode(), QueryMode::Bql1);
-        assert_eq!(handshake.hs_static().auth_mode(), AuthMode::Password);
-    }
-    match core::str::from_utf8(handshake.hs_auth().username()) {
-        Ok(uname) => {
-            let auth = global.sys_store().system_store().auth_data().read();
-            let r = auth.verify_user_check_root(uname, handshake.hs_auth().password());
-            match r {
-                Ok(is_root) => {
-                    let hs = handshake.hs_static();
-                    let ret = Ok(PostHandshake::Okay(ClientLocalState::new(
-                        uname.into(),
-                        is_root,
-                        hs,
-                    )));
-                    buf.advance(cursor);
-                    return ret;
-                }
-                Err(_) => {}
-            }
-        }
-        Err(_) => {}
+    }
+    let auth_mode = handshake.hs_static().auth_mode();
+    if auth_mode == AuthMode::Token
+        && !handshake
+            .hs_static()
+            .capabilities()
+            .negotiate()
+            .contains(Capabilities::TOKEN_AUTH)
+    {
+        return Ok(PostHandshake::Error(ProtocolError::RejectAuth));
+    }
+    let username = core::str::from_utf8(handshake.hs_auth().username());
+    let r = match username {
+        Ok(uname) => match auth_mode {
+            AuthMode::Password => host
+                .sys_store()
+                .verify_user_check_root(uname, handshake.hs_auth().password()),
+            AuthMode::Token => host
+                .sys_store()
+                .system_store()
+                .verify_token(uname, handshake.hs_auth().password()),
+        },
+        Err(_) => Err(QueryError::SysAuthError),
     };
+    host.sys_store().audit(AuditEvent::new(
+        AuditEventKind::Login,
+        r.is_ok(),
+        &String::from_utf8_lossy(handshake.hs_auth().username()),
+        &peer.to_string(),
+        auth_mode.as_str(),
+    ));
+    if let (Ok(uname), Ok(is_root)) = (username, r) {
+        let hs = handshake.hs_static();
+        let ret = Ok(PostHandshake::Okay(
+            ClientLocalState::new(uname.into(), is_root, hs).with_peer(peer),
+        ));
+        buf.advance(cursor);
+        return ret;
+    }
     Ok(PostHandshake::Error(ProtocolError::RejectAuth))
 }
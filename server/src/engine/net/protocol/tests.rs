@@ -28,12 +28,15 @@ use crate::engine::net::protocol::exchange::Resume;
 
 use {
     super::{
+        compression::{self, decompress_packet, Decompressed},
         exchange::{self, scanint, LFTIntParseResult, QExchangeResult, QExchangeState},
-        handshake::ProtocolError,
-        SQuery,
+        handshake::{Capabilities, ProtocolError},
+        ClientLocalState, IdleDeadline, QueryKind, ResponseBody, ResponseType, SQuery,
     },
     crate::{
         engine::{
+            core::dml::encode_cell,
+            data::cell::Datacell,
             mem::BufferedScanner,
             net::protocol::handshake::{
                 AuthMode, CHandshake, CHandshakeAuth, CHandshakeStatic, DataExchangeMode,
@@ -42,6 +45,7 @@ use {
         },
         util::test_utils,
     },
+    bytes::BytesMut,
     rand::Rng,
 };
 
@@ -181,12 +185,94 @@ fn parse_auth_with_state_updates() {
     assert_eq!(rounds, 3); // r1 = initial read, r2 = lengths, r3 = items
 }
 
+#[test]
+fn parse_token_auth() {
+    run_state_changes_return_rounds(
+        b"H\0\0\0\0\x015\n12\nsayan1700000000.0",
+        CHandshake::new(
+            CHandshakeStatic::new(
+                HandshakeVersion::Original,
+                ProtocolVersion::Original,
+                DataExchangeMode::QueryTime,
+                QueryMode::Bql1,
+                AuthMode::Token,
+            ),
+            CHandshakeAuth::new(b"sayan", b"1700000000.0"),
+        ),
+    );
+}
+
+/*
+    handshake with capabilities
+*/
+
+const STATIC_HANDSHAKE_WITH_CAPS: CHandshakeStatic = CHandshakeStatic::new(
+    HandshakeVersion::Capabilities,
+    ProtocolVersion::Original,
+    DataExchangeMode::QueryTime,
+    QueryMode::Bql1,
+    AuthMode::Password,
+)
+.with_capabilities(Capabilities::new(6));
+
+#[test]
+fn parse_capabilities() {
+    let rounds = run_state_changes_return_rounds(
+        b"H\x01\0\0\0\06\n5\n8\nsayanpass1234",
+        CHandshake::new(
+            STATIC_HANDSHAKE_WITH_CAPS,
+            CHandshakeAuth::new(b"sayan", b"pass1234"),
+        ),
+    );
+    assert_eq!(rounds, 4); // r1 = initial read, r2 = capabilities, r3 = lengths, r4 = items
+}
+
+#[test]
+fn parse_capabilities_staged() {
+    let mut scanner = BufferedScanner::new(b"H\x01\0\0\0\0");
+    assert_eq!(
+        CHandshake::resume_with(&mut scanner, HandshakeState::Initial),
+        HandshakeResult::ChangeState {
+            new_state: HandshakeState::ExpectingCapabilities(
+                STATIC_HANDSHAKE_WITH_CAPS.with_capabilities(Capabilities::ORIGINAL)
+            ),
+            expect: 2
+        }
+    );
+    let mut scanner = BufferedScanner::new(b"H\x01\0\0\0\0x\n");
+    assert_eq!(
+        CHandshake::resume_with(&mut scanner, HandshakeState::Initial),
+        HandshakeResult::Error(ProtocolError::CorruptedHSPacket)
+    );
+}
+
+#[test]
+fn negotiate_capabilities() {
+    let all = Capabilities::new(u64::MAX).negotiate();
+    assert_eq!(all, Capabilities::SUPPORTED);
+    assert!(all.contains(Capabilities::COMPRESSION));
+    let some = Capabilities::new((1 << 63) | Capabilities::CDC.raw());
+    assert_eq!(some.negotiate(), Capabilities::CDC);
+    // compression and batches have to be asked for
+    assert!(!Capabilities::ORIGINAL.contains(Capabilities::COMPRESSION));
+    assert!(!Capabilities::ORIGINAL.contains(Capabilities::BATCH));
+    // the original handshake gets everything that was there before
+    let original = ClientLocalState::new_test("sayan", false);
+    assert_eq!(original.capabilities(), Capabilities::ORIGINAL);
+    let none = ClientLocalState::new(
+        "sayan".into(),
+        false,
+        STATIC_HANDSHAKE_WITH_CAPS.with_capabilities(Capabilities::new(0)),
+    );
+    assert!(!none.capabilities().contains(Capabilities::PIPELINE));
+}
+
 const HS_BAD_PACKET: [u8; 6] = *b"I\x00\0\0\0\0";
-const HS_BAD_VERSION_HS: [u8; 6] = *b"H\x01\0\0\0\0";
+const HS_BAD_VERSION_HS: [u8; 6] = *b"H\x02\0\0\0\0";
 const HS_BAD_VERSION_PROTO: [u8; 6] = *b"H\0\x01\0\0\0";
 const HS_BAD_MODE_XCHG: [u8; 6] = *b"H\0\0\x01\0\0";
 const HS_BAD_MODE_QUERY: [u8; 6] = *b"H\0\0\0\x01\0";
-const HS_BAD_MODE_AUTH: [u8; 6] = *b"H\0\0\0\0\x01";
+const HS_BAD_MODE_AUTH: [u8; 6] = *b"H\0\0\0\0\x02";
 
 fn scan_hs(hs: impl AsRef<[u8]>, f: impl Fn(HandshakeResult)) {
     let mut scanner = BufferedScanner::new(hs.as_ref());
@@ -412,6 +498,32 @@ fn stages_manual() {
     }
 }
 
+#[test]
+fn query_kinds() {
+    for (byte, kind) in [
+        (b'S', QueryKind::Simple),
+        (b'P', QueryKind::Prepare),
+        (b'E', QueryKind::Execute),
+    ] {
+        let mut query = create_simple_query("1", ["\x065\nsayan"]);
+        query[0] = byte;
+        match unsafe { exchange::resume(&query, Default::default(), Default::default()) } {
+            (_, QExchangeResult::SQCompleted(q)) => {
+                assert_eq!(q.kind(), kind);
+                assert_eq!(q.query_str(), "1");
+                assert_eq!(q.params_str(), "\x065\nsayan");
+            }
+            e => panic!("expected end, got {e:?}"),
+        }
+    }
+    let mut query = create_simple_query(SQ, ["sayan"]);
+    query[0] = b'X';
+    assert_eq!(
+        unsafe { exchange::resume(&query, Default::default(), Default::default()) }.1,
+        QExchangeResult::Error
+    );
+}
+
 #[test]
 fn scanint_impl() {
     let mut s = BufferedScanner::new(b"\n");
@@ -421,3 +533,235 @@ fn scanint_impl() {
     let mut s = BufferedScanner::new(b"12\n");
     assert_eq!(scanint(&mut s, true, 0), LFTIntParseResult::Value(12));
 }
+
+fn create_pipeline(count: usize, queries: &[Vec<u8>]) -> Vec<u8> {
+    let count = count.to_string();
+    let mut buf = vec![b'M'];
+    buf.extend(
+        (count.len() + 1 + queries.iter().map(Vec::len).sum::<usize>())
+            .to_string()
+            .as_bytes(),
+    );
+    buf.push(b'\n');
+    buf.extend(count.as_bytes());
+    buf.push(b'\n');
+    queries.iter().for_each(|q| buf.extend(q));
+    buf
+}
+
+#[test]
+fn pipeline() {
+    let mut prepare = create_simple_query(SQ, []);
+    prepare[0] = b'P';
+    let queries = [create_simple_query(SQ, ["sayan"]), prepare];
+    let pipeline = create_pipeline(2, &queries);
+    // nothing is run until we have the whole pipeline
+    let (cursor, state) = match unsafe {
+        exchange::resume(
+            &pipeline[..pipeline.len() - 1],
+            Default::default(),
+            Default::default(),
+        )
+    } {
+        (cursor, QExchangeResult::ChangeState(state)) => (cursor, state),
+        e => panic!("expected change state, got {e:?}"),
+    };
+    match unsafe { exchange::resume(&pipeline, cursor, state) } {
+        (_, QExchangeResult::PipelineCompleted(queries)) => {
+            assert_eq!(queries.len(), 2);
+            assert_eq!(queries[0].kind(), QueryKind::Simple);
+            assert_eq!(queries[0].query_str(), SQ);
+            assert_eq!(queries[0].params_str(), "sayan");
+            assert_eq!(queries[1].kind(), QueryKind::Prepare);
+            assert_eq!(queries[1].query_str(), SQ);
+            assert_eq!(queries[1].params_str(), "");
+        }
+        e => panic!("expected pipeline, got {e:?}"),
+    }
+}
+
+#[test]
+fn pipeline_bad() {
+    let query = create_simple_query(SQ, ["sayan"]);
+    let mut bad_kind = query.clone();
+    bad_kind[0] = b'M';
+    let mut bad_window = query.clone();
+    bad_window[query.iter().position(|b| *b == b'\n').unwrap() + 1] = b'9';
+    for pipeline in [
+        // wrong number of queries
+        create_pipeline(0, &[]),
+        create_pipeline(2, &[query.clone()]),
+        create_pipeline(1, &[query.clone(), query.clone()]),
+        // incomplete query
+        create_pipeline(1, &[query[..query.len() - 1].to_vec()]),
+        // no nested pipelines
+        create_pipeline(1, &[bad_kind]),
+        // the query window is larger than the query
+        create_pipeline(1, &[bad_window]),
+    ] {
+        assert_eq!(
+            unsafe { exchange::resume(&pipeline, Default::default(), Default::default()) }.1,
+            QExchangeResult::Error
+        );
+    }
+}
+
+#[test]
+fn batch() {
+    let queries = [
+        create_simple_query(SQ, ["sayan"]),
+        create_simple_query(SQ, ["joe"]),
+    ];
+    let mut batch = create_pipeline(2, &queries);
+    batch[0] = b'B';
+    match unsafe { exchange::resume(&batch, Default::default(), Default::default()) } {
+        (_, QExchangeResult::BatchCompleted(queries)) => {
+            assert_eq!(queries.len(), 2);
+            assert_eq!(queries[0].params_str(), "sayan");
+            assert_eq!(queries[1].params_str(), "joe");
+        }
+        e => panic!("expected batch, got {e:?}"),
+    }
+    // no nested batches
+    let mut nested = create_pipeline(1, &[batch]);
+    nested[0] = b'B';
+    assert_eq!(
+        unsafe { exchange::resume(&nested, Default::default(), Default::default()) }.1,
+        QExchangeResult::Error
+    );
+}
+
+#[test]
+fn response_body_chunks() {
+    let large = "x".repeat(ResponseBody::CHUNK_SIZE);
+    let cells = [
+        Datacell::new_uint_default(1),
+        Datacell::new_str(large.clone().into_boxed_str()),
+        Datacell::new_str("sayan".into()),
+        Datacell::new_bin(large.into_bytes().into_boxed_slice()),
+        Datacell::null(),
+    ];
+    let (mut body, mut expected) = (ResponseBody::new(), vec![]);
+    for cell in cells.iter() {
+        encode_cell(&mut body, cell);
+        encode_cell(&mut expected, cell);
+    }
+    assert_eq!(body.len(), expected.len());
+    assert_eq!(body.to_vec(), expected);
+    assert_eq!(body, ResponseBody::from(expected));
+    // the large values are in chunks of their own
+    let chunks: Vec<usize> = body.into_chunks().map(|chunk| chunk.len()).collect();
+    assert_eq!(
+        chunks,
+        [
+            // 1, and the length of the first large value
+            3 + 7,
+            ResponseBody::CHUNK_SIZE,
+            // sayan, and the length of the second large value
+            8 + 7,
+            ResponseBody::CHUNK_SIZE,
+            // null
+            1
+        ]
+    );
+}
+
+async fn expires_within(idle: &IdleDeadline, secs: u64) -> bool {
+    tokio::time::timeout(std::time::Duration::from_secs(secs), idle.expired())
+        .await
+        .is_ok()
+}
+
+#[tokio::test(start_paused = true)]
+async fn idle_deadline() {
+    use std::time::Duration;
+    let mut idle = IdleDeadline::with_timeout(Some(Duration::from_secs(10)));
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    // the client sent something, so it has another 10 seconds
+    idle.reset();
+    assert!(!expires_within(&idle, 9).await);
+    assert!(expires_within(&idle, 2).await);
+    // without an idle timeout, connections are kept open
+    let idle = IdleDeadline::with_timeout(None);
+    assert!(!expires_within(&idle, 86400).await);
+}
+
+/*
+    compression
+*/
+
+fn compressed_packet(packet: &[u8]) -> Vec<u8> {
+    let compressed = lz4_flex::block::compress(packet);
+    let mut ret = format!("Z{}\n{}\n", compressed.len(), packet.len()).into_bytes();
+    ret.extend(compressed);
+    ret
+}
+
+#[test]
+fn decompress_query_packet() {
+    let query = "select * from myspace.mymodel where username = ?".repeat(10);
+    let packet = create_simple_query(&query, ["\x06sayan"]);
+    let mut compressed = compressed_packet(&packet);
+    assert!(compressed.len() < packet.len());
+    compressed.push(b'K');
+    // every prefix is incomplete
+    for i in 1..compressed.len() - 1 {
+        let mut buf = BytesMut::from(&compressed[..i]);
+        assert_eq!(decompress_packet(&mut buf), Decompressed::NeedMore);
+        assert_eq!(buf, &compressed[..i]);
+    }
+    // and whatever follows the packet is left alone
+    let mut buf = BytesMut::from(&compressed[..]);
+    assert_eq!(decompress_packet(&mut buf), Decompressed::Okay);
+    assert_eq!(&buf[..packet.len()], packet);
+    assert_eq!(&buf[packet.len()..], b"K");
+}
+
+#[test]
+fn decompress_bad_packet() {
+    let packet = create_simple_query("select * from myspace.mymodel", []);
+    for bad in [
+        b"Zx\n1\n".to_vec(),
+        format!("Z1\n{}\n", compression::MAX_PACKET_SIZE + 1).into_bytes(),
+        b"Z4\n100\n\xff\xff\xff\xff".to_vec(),
+        {
+            // the original size is a lie
+            let compressed = lz4_flex::block::compress(&packet);
+            let mut p = format!("Z{}\n{}\n", compressed.len(), packet.len() + 1).into_bytes();
+            p.extend(compressed);
+            p
+        },
+    ] {
+        assert_eq!(
+            decompress_packet(&mut BytesMut::from(&bad[..])),
+            Decompressed::Error
+        );
+    }
+}
+
+#[test]
+fn compress_response() {
+    // too small
+    assert_eq!(compression::compress_response(&[0; 16]), None);
+    // doesn't compress
+    let mut rng = rand::thread_rng();
+    let random: Vec<u8> = (0..compression::THRESHOLD).map(|_| rng.gen()).collect();
+    assert_eq!(compression::compress_response(&random), None);
+    // large and repetitive
+    let response = b"\x0dhello, world".repeat(compression::THRESHOLD);
+    let compressed = compression::compress_response(&response).unwrap();
+    let mut scanner = BufferedScanner::new(&compressed[1..]);
+    let compressed_size = scanner
+        .try_next_ascii_u64_lf_separated_or_restore_cursor()
+        .unwrap();
+    let original_size = scanner
+        .try_next_ascii_u64_lf_separated_or_restore_cursor()
+        .unwrap();
+    assert_eq!(compressed[0], ResponseType::Compressed.value_u8());
+    assert_eq!(original_size as usize, response.len());
+    assert_eq!(scanner.remaining(), compressed_size as usize);
+    assert_eq!(
+        lz4_flex::block::decompress(scanner.current_buffer(), response.len()).unwrap(),
+        response
+    );
+}
@@ -0,0 +1,205 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{
+        connections::{self, Connection, ConnectionState},
+        init_tls_connector, Listener,
+    },
+    crate::engine::fractal::Global,
+    openssl::{
+        asn1::Asn1Time,
+        bn::BigNum,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        nid::Nid,
+        pkey::{PKey, Private},
+        ssl::{Ssl, SslAcceptor, SslConnector},
+        symm::Cipher,
+        x509::{
+            extension::{BasicConstraints, SubjectAlternativeName},
+            X509Builder, X509NameBuilder, X509,
+        },
+    },
+    std::pin::Pin,
+    tokio::io::{AsyncReadExt, AsyncWriteExt},
+    tokio_openssl::SslStream,
+};
+
+#[test]
+fn connection_registry() {
+    // the registry is shared with every other test, so we only look at our own connections
+    let ids = |user| -> Vec<u64> { connections::list(Some(user)).iter().map(|c| c.id).collect() };
+    let (c1, c2) = (
+        Connection::register(u64::MAX - 1, "127.0.0.1:49152".parse().unwrap()),
+        Connection::register(u64::MAX, "127.0.0.1:49153".parse().unwrap()),
+    );
+    // connections that haven't authenticated are only listed for root
+    assert!(connections::list(None)
+        .iter()
+        .any(|c| (c.id == c1.id()) & (c.state == ConnectionState::Handshake)));
+    assert_eq!(ids("connection_registry"), []);
+    c1.authenticated("connection_registry");
+    c2.authenticated("connection_registry");
+    assert_eq!(ids("connection_registry"), [c1.id(), c2.id()]);
+    c1.query(b"select all * from myspace.users limit ?");
+    let listed = connections::list(Some("connection_registry"));
+    assert_eq!(listed[0].state, ConnectionState::Query);
+    assert_eq!(
+        listed[0].query.as_deref(),
+        Some("select all * from myspace.users limit ?")
+    );
+    assert_eq!(listed[0].peer, c1.peer());
+    assert_eq!(
+        (listed[1].state, listed[1].query.as_deref()),
+        (ConnectionState::Idle, None)
+    );
+    c1.subscribed();
+    assert_eq!(
+        connections::list(Some("connection_registry"))[0].state,
+        ConnectionState::Subscribed
+    );
+    c1.idle();
+    assert_eq!(
+        connections::list(Some("connection_registry"))[0].query,
+        None
+    );
+    // connections are unregistered once they're closed
+    drop(c1);
+    assert_eq!(ids("connection_registry"), [u64::MAX]);
+}
+
+const TLS_PASSPHRASE: &str = "tls-passphrase";
+
+/// A certificate for `localhost` (with a fresh key), signed by `issuer` or self-signed if there's no issuer. CA
+/// certificates can sign other certificates
+fn cert(serial: u32, ca: bool, issuer: Option<&(X509, PKey<Private>)>) -> (X509, PKey<Private>) {
+    let key = EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap();
+    let key = PKey::from_ec_key(key).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", if ca { "skytable-ca" } else { "localhost" })
+        .unwrap();
+    let name = name.build();
+    let mut cert = X509Builder::new().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_serial_number(&BigNum::from_u32(serial).unwrap().to_asn1_integer().unwrap())
+        .unwrap();
+    cert.set_subject_name(&name).unwrap();
+    match issuer {
+        Some((issuer, _)) => cert.set_issuer_name(issuer.subject_name()).unwrap(),
+        None => cert.set_issuer_name(&name).unwrap(),
+    }
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    if ca {
+        cert.append_extension(BasicConstraints::new().critical().ca().build().unwrap())
+            .unwrap();
+    } else {
+        let san = SubjectAlternativeName::new()
+            .dns("localhost")
+            .build(&cert.x509v3_context(issuer.map(|(issuer, _)| &**issuer), None))
+            .unwrap();
+        cert.append_extension(san).unwrap();
+    }
+    let signing_key = issuer.map(|(_, key)| key).unwrap_or(&key);
+    cert.sign(signing_key, MessageDigest::sha256()).unwrap();
+    (cert.build(), key)
+}
+
+/// The certificate and its private key (encrypted with [`TLS_PASSPHRASE`]) as PEM
+fn to_pem((cert, key): &(X509, PKey<Private>)) -> (String, String) {
+    (
+        String::from_utf8(cert.to_pem().unwrap()).unwrap(),
+        String::from_utf8(
+            key.private_key_to_pem_pkcs8_passphrase(
+                Cipher::aes_256_cbc(),
+                TLS_PASSPHRASE.as_bytes(),
+            )
+            .unwrap(),
+        )
+        .unwrap(),
+    )
+}
+
+/// Connect to a TLS server (that uses `acceptor`) and exchange a byte with it, returning what each side made of it
+async fn tls_exchange(
+    acceptor: &SslAcceptor,
+    connector: &SslConnector,
+) -> (
+    Result<u8, Box<dyn std::error::Error>>,
+    Result<u8, Box<dyn std::error::Error>>,
+) {
+    let (a, b) = tokio::io::duplex(4096);
+    let server = async {
+        let mut stream = SslStream::new(Ssl::new(acceptor.context())?, a)?;
+        Pin::new(&mut stream).accept().await?;
+        let byte = stream.read_u8().await?;
+        stream.write_u8(byte + 1).await?;
+        stream.flush().await?;
+        Ok(byte)
+    };
+    let client = async {
+        let ssl = connector.configure()?.into_ssl("localhost")?;
+        let mut stream = SslStream::new(ssl, b)?;
+        Pin::new(&mut stream).connect().await?;
+        stream.write_u8(1).await?;
+        stream.flush().await?;
+        Ok(stream.read_u8().await?)
+    };
+    tokio::join!(server, client)
+}
+
+#[tokio::test]
+async fn tls_client_certificates() {
+    let ca = cert(1, true, None);
+    let (ca_pem, _) = to_pem(&ca);
+    let (server_cert, server_key) = to_pem(&cert(2, false, Some(&ca)));
+    let acceptor =
+        Listener::<Global>::init_tls(&server_cert, &server_key, TLS_PASSPHRASE, Some(&ca_pem))
+            .unwrap();
+    // a client that doesn't present a certificate is turned away
+    let connector = init_tls_connector(&ca_pem, None).unwrap();
+    let (server, client) = tls_exchange(&acceptor, &connector).await;
+    server.unwrap_err();
+    client.unwrap_err();
+    // and so is a client with a certificate that isn't signed by the client CA
+    let (other_cert, other_key) = to_pem(&cert(3, false, None));
+    let connector =
+        init_tls_connector(&ca_pem, Some((&other_cert, &other_key, TLS_PASSPHRASE))).unwrap();
+    let (server, client) = tls_exchange(&acceptor, &connector).await;
+    server.unwrap_err();
+    client.unwrap_err();
+    // but a client with a certificate signed by the client CA gets through
+    let (client_cert, client_key) = to_pem(&cert(4, false, Some(&ca)));
+    let connector =
+        init_tls_connector(&ca_pem, Some((&client_cert, &client_key, TLS_PASSPHRASE))).unwrap();
+    let (server, client) = tls_exchange(&acceptor, &connector).await;
+    assert_eq!(server.unwrap(), 1);
+    assert_eq!(client.unwrap(), 2);
+}
@@ -0,0 +1,179 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    preflight checks (`skyd --validate-config`)
+    ---
+    by the time we get here the configuration has already been parsed and validated (and the TLS files and encryption
+    key have been read in). what's left is everything that would otherwise only fail once we start up: loading the TLS
+    certificates, the permissions on the data directory and the recovery actions. we only ever read metadata, so it's
+    safe to run this against the data directory of a running server
+*/
+
+use {
+    super::{
+        config::{ConfigAuth, ConfigEndpoint, ConfigRecovery, Configuration},
+        fractal::{self, sys_store::SystemStore},
+        net,
+        storage::v1::{audit::AUDIT_LOG_PATH, loader, LocalFS},
+    },
+    crate::util::os::{self, EntryKind},
+    std::{fs, path::Path},
+};
+
+/// The maximum number of unwritable paths that we list
+const MAX_REPORTED_PATHS: usize = 10;
+
+/// Run all the checks, logging every problem that we find. Returns true if we should be able to start up with this
+/// configuration
+pub fn check(config: &Configuration, recovery: &ConfigRecovery) -> bool {
+    let mut okay = true;
+    okay &= check_tls(&config.endpoints);
+    okay &= check_data_directory();
+    okay &= check_restore(recovery);
+    log_auth(&config.auth);
+    okay
+}
+
+/// Load the certificates and private key exactly like we do when we start listening
+fn check_tls(endpoints: &ConfigEndpoint) -> bool {
+    let (ConfigEndpoint::Secure(tls) | ConfigEndpoint::Multi(_, tls)) = endpoints else {
+        info!("tls: not enabled");
+        return true;
+    };
+    match net::Listener::<fractal::Global>::init_tls(
+        tls.cert(),
+        tls.private_key(),
+        tls.pkey_pass(),
+        tls.client_ca(),
+    ) {
+        Ok(_) => {
+            info!("tls: certificate and private key loaded");
+            true
+        }
+        Err(e) => {
+            error!("tls: {e}");
+            false
+        }
+    }
+}
+
+/// Check that we can write to the working directory (where the database files are created) and to every database
+/// file and directory that already exists
+fn check_data_directory() -> bool {
+    let mut paths = vec![".".to_owned()];
+    for file in [
+        SystemStore::<LocalFS>::SYSDB_PATH,
+        SystemStore::<LocalFS>::SYSDB_COW_PATH,
+        loader::GNS_FILE_PATH,
+        AUDIT_LOG_PATH,
+    ] {
+        if Path::new(file).exists() {
+            paths.push(file.to_owned());
+        }
+    }
+    if Path::new(loader::DATA_DIR).exists() {
+        paths.push(loader::DATA_DIR.to_owned());
+        match os::rlistdir(loader::DATA_DIR) {
+            Ok(entries) => paths.extend(entries.into_iter().map(EntryKind::into_inner)),
+            Err(e) => {
+                error!("data directory: failed to list `{}`. {e}", loader::DATA_DIR);
+                return false;
+            }
+        }
+    }
+    let mut unwritable = vec![];
+    for path in &paths {
+        match os::is_writable(Path::new(path)) {
+            Ok(true) => {}
+            Ok(false) => unwritable.push(path.as_str()),
+            Err(e) => {
+                error!("data directory: failed to check `{path}`. {e}");
+                return false;
+            }
+        }
+    }
+    if unwritable.is_empty() {
+        info!("data directory: writable ({} paths checked)", paths.len());
+        return true;
+    }
+    for path in unwritable.iter().take(MAX_REPORTED_PATHS) {
+        error!("data directory: `{path}` is not writable");
+    }
+    if unwritable.len() > MAX_REPORTED_PATHS {
+        error!(
+            "data directory: {} more paths are not writable",
+            unwritable.len() - MAX_REPORTED_PATHS
+        );
+    }
+    false
+}
+
+/// Check that the backup can be read, and that restoring it won't run into an existing database
+fn check_restore(recovery: &ConfigRecovery) -> bool {
+    let Some(backup) = recovery.restore.as_deref() else {
+        return true;
+    };
+    let entries = match fs::read_dir(backup) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("restore: failed to read backup directory `{backup}`. {e}");
+            return false;
+        }
+    };
+    let mut okay = true;
+    for entry in entries {
+        match entry {
+            Ok(entry) if Path::new(&entry.file_name()).exists() => {
+                error!(
+                    "restore: `{}` already exists and won't be overwritten",
+                    entry.file_name().to_string_lossy()
+                );
+                okay = false;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error!("restore: failed to read backup directory `{backup}`. {e}");
+                return false;
+            }
+        }
+    }
+    if okay {
+        info!("restore: backup `{backup}` can be restored");
+    }
+    okay
+}
+
+/// The auth settings were already validated with the rest of the configuration, so we just report them
+fn log_auth(auth: &ConfigAuth) {
+    info!(
+        "auth: plugin={:?}, minimum password length={}, hash cost={}",
+        auth.plugin, auth.password_min_len, auth.hash_cost
+    );
+    if Path::new(SystemStore::<LocalFS>::SYSDB_PATH).exists() {
+        info!("auth: found an existing system database. its root password will be replaced if it doesn't match the configured one");
+    }
+}
@@ -25,6 +25,7 @@
 */
 
 use crate::engine::{
+    core::EntityIDRef,
     data::DictGeneric,
     error::{QueryError, QueryResult},
     ql::{
@@ -42,13 +43,55 @@ pub enum SysctlCommand<'a> {
     DropUser(UserDel<'a>),
     /// `systcl alter user ...`
     AlterUser(UserDecl<'a>),
+    /// `sysctl grant user ...`
+    GrantUser(UserGrant<'a>),
+    /// `sysctl revoke user ...`
+    RevokeUser(UserGrant<'a>),
+    /// `sysctl quota user ...`
+    QuotaUser(UserDecl<'a>),
     /// `sysctl status`
     ReportStatus,
+    /// `sysctl report memory`
+    ReportMemory,
+    /// `sysctl report startup`
+    ReportStartup,
+    /// `sysctl report audit [with { ... }]`
+    ReportAudit(Option<DictGeneric>),
+    /// `sysctl report queries`
+    ReportQueries,
+    /// `sysctl report connections`
+    ReportConnections,
+    /// `sysctl kill ...`
+    Kill(u64),
+    /// `sysctl issue token`
+    IssueToken,
+    /// `sysctl backup ...`
+    Backup(Box<str>),
+    /// `sysctl export model ...`
+    Export(ModelFile<'a>),
+    /// `sysctl import model ...`
+    Import(ModelFile<'a>),
+    /// `sysctl subscribe model ...`
+    Subscribe(Vec<EntityIDRef<'a>>),
+    /// `sysctl promote`
+    Promote,
+    /// `sysctl shutdown`
+    Shutdown,
+    /// `sysctl log level ...`
+    LogLevel(Box<str>),
 }
 
 impl<'a> SysctlCommand<'a> {
     pub fn needs_root(&self) -> bool {
-        !matches!(self, Self::ReportStatus)
+        !matches!(
+            self,
+            Self::ReportStatus
+                | Self::ReportQueries
+                | Self::ReportConnections
+                | Self::Kill(_)
+                | Self::IssueToken
+                | Self::Subscribe(_)
+        )
     }
 }
 
@@ -58,6 +101,16 @@ impl<'a> traits::ASTNode<'a> for SysctlCommand<'a> {
     fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
         state: &mut State<'a, Qd>,
     ) -> QueryResult<Self> {
+        if state.remaining() == 1 && state.read().ident_eq("promote") {
+            // promote
+            state.cursor_ahead();
+            return Ok(SysctlCommand::Promote);
+        }
+        if state.remaining() == 1 && state.read().ident_eq("shutdown") {
+            // shutdown
+            state.cursor_ahead();
+            return Ok(SysctlCommand::Shutdown);
+        }
         if state.remaining() < 2 {
             return Err(QueryError::QLUnexpectedEndOfStatement);
         }
@@ -65,22 +118,218 @@ impl<'a> traits::ASTNode<'a> for SysctlCommand<'a> {
         let alter = Token![alter].eq(a) & b.ident_eq("user");
         let create = Token![create].eq(a) & b.ident_eq("user");
         let drop = Token![drop].eq(a) & b.ident_eq("user");
+        let grant = a.ident_eq("grant") & b.ident_eq("user");
+        let revoke = a.ident_eq("revoke") & b.ident_eq("user");
+        let quota = a.ident_eq("quota") & b.ident_eq("user");
         let status = a.ident_eq("report") & b.ident_eq("status");
-        if !(create | drop | status | alter) {
+        let memory = a.ident_eq("report") & b.ident_eq("memory");
+        let startup = a.ident_eq("report") & b.ident_eq("startup");
+        let audit = a.ident_eq("report") & b.ident_eq("audit");
+        let queries = a.ident_eq("report") & b.ident_eq("queries");
+        let connections = a.ident_eq("report") & b.ident_eq("connections");
+        let kill = a.ident_eq("kill") & state.can_read_lit_from(b);
+        let token = a.ident_eq("issue") & b.ident_eq("token");
+        let backup = a.ident_eq("backup") & state.can_read_lit_from(b);
+        let export = a.ident_eq("export") & Token![model].eq(b);
+        let import = a.ident_eq("import") & Token![model].eq(b);
+        let subscribe = a.ident_eq("subscribe") & Token![model].eq(b);
+        let log_level = a.ident_eq("log") & b.ident_eq("level");
+        if !(create
+            | drop
+            | status
+            | memory
+            | startup
+            | audit
+            | queries
+            | connections
+            | kill
+            | alter
+            | backup
+            | grant
+            | revoke
+            | quota
+            | token
+            | export
+            | import
+            | subscribe
+            | log_level)
+        {
             return Err(QueryError::QLUnknownStatement);
         }
+        if memory {
+            // report memory
+            return if state.exhausted() {
+                Ok(SysctlCommand::ReportMemory)
+            } else {
+                Err(QueryError::QLInvalidSyntax)
+            };
+        }
+        if startup {
+            // report startup
+            return if state.exhausted() {
+                Ok(SysctlCommand::ReportStartup)
+            } else {
+                Err(QueryError::QLInvalidSyntax)
+            };
+        }
+        if audit {
+            return parse_report_audit(state).map(SysctlCommand::ReportAudit);
+        }
+        if queries {
+            // report queries
+            return if state.exhausted() {
+                Ok(SysctlCommand::ReportQueries)
+            } else {
+                Err(QueryError::QLInvalidSyntax)
+            };
+        }
+        if connections {
+            // report connections
+            return if state.exhausted() {
+                Ok(SysctlCommand::ReportConnections)
+            } else {
+                Err(QueryError::QLInvalidSyntax)
+            };
+        }
+        if kill {
+            /*
+                kill [query id]
+                                ^cursor
+            */
+            let id = unsafe {
+                // UNSAFE(@ohsayan): +lit ck
+                state.read_lit_unchecked_from(b)
+            };
+            return match id.try_uint() {
+                Some(id) if state.exhausted() => Ok(SysctlCommand::Kill(id)),
+                _ => Err(QueryError::QLInvalidSyntax),
+            };
+        }
+        if token {
+            // issue token
+            return if state.exhausted() {
+                Ok(SysctlCommand::IssueToken)
+            } else {
+                Err(QueryError::QLInvalidSyntax)
+            };
+        }
+        if backup {
+            /*
+                backup [path]
+                              ^cursor
+            */
+            let path = unsafe {
+                // UNSAFE(@ohsayan): +lit ck
+                state.read_lit_unchecked_from(b)
+            };
+            return match path.try_str() {
+                Some(path) if state.exhausted() & !path.is_empty() => {
+                    Ok(SysctlCommand::Backup(path.into()))
+                }
+                _ => Err(QueryError::QLInvalidSyntax),
+            };
+        }
+        if log_level {
+            /*
+                log level [directives]
+                          ^cursor
+            */
+            let token_buffer = state.current();
+            if (token_buffer.len() != 1) || !state.can_read_lit_from(&token_buffer[0]) {
+                return Err(QueryError::QLInvalidSyntax);
+            }
+            let directives = unsafe {
+                // UNSAFE(@ohsayan): +lit ck
+                state.read_lit_unchecked_from(&token_buffer[0])
+            };
+            return match directives.try_str() {
+                Some(directives) if !directives.is_empty() => {
+                    state.cursor_ahead();
+                    Ok(SysctlCommand::LogLevel(directives.into()))
+                }
+                _ => Err(QueryError::QLInvalidSyntax),
+            };
+        }
+        if export {
+            return ModelFile::parse_export(state).map(SysctlCommand::Export);
+        }
+        if import {
+            return ModelFile::parse_import(state).map(SysctlCommand::Import);
+        }
+        if subscribe {
+            return parse_subscribe(state).map(SysctlCommand::Subscribe);
+        }
         if create {
             UserDecl::parse(state).map(SysctlCommand::CreateUser)
         } else if drop {
             UserDel::parse(state).map(SysctlCommand::DropUser)
         } else if alter {
             UserDecl::parse(state).map(SysctlCommand::AlterUser)
+        } else if grant {
+            UserGrant::parse(state).map(SysctlCommand::GrantUser)
+        } else if revoke {
+            UserGrant::parse(state).map(SysctlCommand::RevokeUser)
+        } else if quota {
+            UserDecl::parse(state).map(SysctlCommand::QuotaUser)
         } else {
             Ok(SysctlCommand::ReportStatus)
         }
     }
 }
 
+/// Parse the (optional) filters of a `report audit` DCL command
+///
+/// MUSTENDSTREAM: YES
+fn parse_report_audit<'a, Qd: QueryData<'a>>(
+    state: &mut State<'a, Qd>,
+) -> QueryResult<Option<DictGeneric>> {
+    /*
+        [with { user: [user], kind: [kind], since: [ms], until: [ms], count: [count] }]
+        ^cursor
+    */
+    if state.exhausted() {
+        return Ok(None);
+    }
+    if state.remaining() < 3 {
+        return Err(QueryError::QLInvalidSyntax);
+    }
+    let token_buffer = state.current();
+    state.poison_if_not(token_buffer[0].eq(&Token![with]) & token_buffer[1].eq(&Token![open {}]));
+    state.cursor_ahead();
+    let Some(dict) = syn::parse_dict(state) else {
+        return Err(QueryError::QLInvalidCollectionSyntax);
+    };
+    if state.not_exhausted() | !state.okay() {
+        return Err(QueryError::QLInvalidSyntax);
+    }
+    Ok(Some(dict))
+}
+
+/// Parse the models of a `subscribe model` DCL command
+///
+/// MUSTENDSTREAM: YES
+fn parse_subscribe<'a, Qd: QueryData<'a>>(
+    state: &mut State<'a, Qd>,
+) -> QueryResult<Vec<EntityIDRef<'a>>> {
+    /*
+        [space].[model] (, [space].[model])*
+        ^cursor
+    */
+    let mut models = vec![state.try_entity_ref_result()?];
+    while state.not_exhausted() {
+        if !state.cursor_eq(Token![,]) {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        state.cursor_ahead();
+        let model = state.try_entity_ref_result()?;
+        if models.contains(&model) {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        models.push(model);
+    }
+    Ok(models)
+}
+
 fn parse<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<UserMeta<'a>> {
     /*
         [username] with { password: [password], ... }
@@ -171,3 +420,154 @@ impl<'a> UserDel<'a> {
         self.username.as_str()
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub struct UserGrant<'a> {
+    username: Ident<'a>,
+    space: Ident<'a>,
+    model: Option<Ident<'a>>,
+}
+
+impl<'a> UserGrant<'a> {
+    pub(in crate::engine::ql) fn new(
+        username: Ident<'a>,
+        space: Ident<'a>,
+        model: Option<Ident<'a>>,
+    ) -> Self {
+        Self {
+            username,
+            space,
+            model,
+        }
+    }
+    /// Parse a `user grant` or `user revoke` DCL command
+    ///
+    /// MUSTENDSTREAM: YES
+    pub fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        /*
+            [username] on [space]
+            [username] on [space].[model]
+            ^cursor
+        */
+        let token_buffer = state.current();
+        let space_grant = (token_buffer.len() == 3)
+            && token_buffer[0].is_ident()
+            && Token![on].eq(&token_buffer[1])
+            && token_buffer[2].is_ident();
+        let model_grant = (token_buffer.len() == 5)
+            && token_buffer[0].is_ident()
+            && Token![on].eq(&token_buffer[1])
+            && token_buffer[2].is_ident()
+            && Token![.].eq(&token_buffer[3])
+            && token_buffer[4].is_ident();
+        if !(space_grant | model_grant) {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        let (username, space, model) = unsafe {
+            // UNSAFE(@ohsayan): +signature ck
+            (
+                token_buffer[0].uck_read_ident(),
+                token_buffer[2].uck_read_ident(),
+                if model_grant {
+                    Some(token_buffer[4].uck_read_ident())
+                } else {
+                    None
+                },
+            )
+        };
+        state.cursor_ahead_by(token_buffer.len());
+        Ok(Self::new(username, space, model))
+    }
+    pub fn username(&self) -> &str {
+        self.username.as_str()
+    }
+    pub fn space(&self) -> &str {
+        self.space.as_str()
+    }
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_ref().map(Ident::as_str)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DataFormat {
+    /// a JSON array with one object per row (imports also accept one object per line)
+    Json,
+    /// a header with the field names, followed by one record per row
+    Csv,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ModelFile<'a> {
+    entity: EntityIDRef<'a>,
+    path: Box<str>,
+    format: DataFormat,
+}
+
+impl<'a> ModelFile<'a> {
+    pub(in crate::engine::ql) fn new(
+        entity: EntityIDRef<'a>,
+        path: Box<str>,
+        format: DataFormat,
+    ) -> Self {
+        Self {
+            entity,
+            path,
+            format,
+        }
+    }
+    /// Parse an `export model` DCL command
+    ///
+    /// MUSTENDSTREAM: YES
+    pub fn parse_export<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        Self::parse(state, true)
+    }
+    /// Parse an `import model` DCL command
+    ///
+    /// MUSTENDSTREAM: YES
+    pub fn parse_import<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        Self::parse(state, false)
+    }
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>, export: bool) -> QueryResult<Self> {
+        /*
+            [space].[model] (to|from) [path] as [format]
+            ^cursor
+        */
+        let entity = state.try_entity_ref_result()?;
+        let token_buffer = state.current();
+        let preposition = if export { Token![to] } else { Token![from] };
+        if (token_buffer.len() != 4)
+            || !(preposition.eq(&token_buffer[0])
+                && state.can_read_lit_from(&token_buffer[1])
+                && Token![as].eq(&token_buffer[2]))
+        {
+            return Err(QueryError::QLInvalidSyntax);
+        }
+        let format = if token_buffer[3].ident_eq("json") {
+            DataFormat::Json
+        } else if token_buffer[3].ident_eq("csv") {
+            DataFormat::Csv
+        } else {
+            return Err(QueryError::QLInvalidSyntax);
+        };
+        let path = unsafe {
+            // UNSAFE(@ohsayan): +lit ck
+            state.read_lit_unchecked_from(&token_buffer[1])
+        };
+        let path = match path.try_str() {
+            Some(path) if !path.is_empty() => path.into(),
+            _ => return Err(QueryError::QLInvalidSyntax),
+        };
+        state.cursor_ahead_by(token_buffer.len());
+        Ok(Self::new(entity, path, format))
+    }
+    pub fn entity(&self) -> EntityIDRef<'a> {
+        self.entity
+    }
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn format(&self) -> DataFormat {
+        self.format
+    }
+}
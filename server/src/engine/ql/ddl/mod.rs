@@ -82,6 +82,7 @@ pub enum Inspect<'a> {
     Global,
     Space(Ident<'a>),
     Model(EntityIDRef<'a>),
+    Models,
 }
 
 impl<'a> ASTNode<'a> for Inspect<'a> {
@@ -95,6 +96,7 @@ impl<'a> ASTNode<'a> for Inspect<'a> {
         }
         let me = match state.fw_read() {
             Token::Ident(id) if id.eq_ignore_ascii_case("global") => Self::Global,
+            Token::Ident(id) if id.eq_ignore_ascii_case("models") => Self::Models,
             Token![space] => {
                 if state.exhausted() {
                     return Err(QueryError::QLUnexpectedEndOfStatement);
@@ -148,6 +148,11 @@ where
                 // found a key, now expect colon
                 mstate = DictFoldState::COLON;
             }
+            (Token![default], DictFoldState::CB_OR_IDENT) => {
+                // `default` is a keyword, but it's also the key for a field's default value
+                key = MaybeInit::new(Ident::from("default"));
+                mstate = DictFoldState::COLON;
+            }
             (Token![:], DictFoldState::COLON) => {
                 // found colon, now lit or ob
                 mstate = DictFoldState::LIT_OR_OB;
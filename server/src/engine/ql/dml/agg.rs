@@ -0,0 +1,232 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+#[cfg(test)]
+use super::WhereClauseCollection;
+use {
+    super::WhereClause,
+    crate::{
+        engine::{
+            core::EntityIDRef,
+            error::{QueryError, QueryResult},
+            ql::{
+                ast::{QueryData, State},
+                lex::{Ident, Token},
+            },
+        },
+        util::compiler,
+    },
+};
+
+/*
+    Impls for aggregates
+    ---
+    Smallest statement:
+    select count(*) from model
+*/
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// An aggregate function
+pub enum AggregateFn {
+    /// `count(*)` or `count(field)` (non-null values only)
+    Count,
+    Min,
+    Max,
+    Sum,
+    Avg,
+}
+
+impl AggregateFn {
+    const NAMES: [(&'static str, Self); 5] = [
+        ("count", Self::Count),
+        ("min", Self::Min),
+        ("max", Self::Max),
+        ("sum", Self::Sum),
+        ("avg", Self::Avg),
+    ];
+    fn from_ident(id: Ident) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(id.as_str()))
+            .map(|(_, func)| *func)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// An aggregate function applied to a field (or to all rows, for `count(*)`)
+pub struct Aggregate<'a> {
+    func: AggregateFn,
+    field: Option<Ident<'a>>,
+}
+
+impl<'a> Aggregate<'a> {
+    pub fn new(func: AggregateFn, field: Option<Ident<'a>>) -> Self {
+        Self { func, field }
+    }
+    pub fn func(&self) -> AggregateFn {
+        self.func
+    }
+    /// Returns the field, or [`None`] for `count(*)`
+    pub fn field(&self) -> Option<Ident<'a>> {
+        self.field
+    }
+    /// Parse `<function>(<field>)` or `count(*)`
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<Self> {
+        state.poison_if_not(state.cursor_has_ident_rounded());
+        if !state.okay() {
+            return None;
+        }
+        let func = AggregateFn::from_ident(unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.fw_read().uck_read_ident()
+        });
+        state.poison_if(func.is_none());
+        state.poison_if_not(state.cursor_rounded_eq(Token![() open]));
+        state.cursor_ahead_if(state.okay());
+        let wildcard = state.cursor_rounded_eq(Token![*]);
+        // only count can be used with a wildcard
+        state.poison_if_not(
+            state.cursor_has_ident_rounded() | (wildcard & (func == Some(AggregateFn::Count))),
+        );
+        if !state.okay() {
+            return None;
+        }
+        let field = if wildcard {
+            state.cursor_ahead();
+            None
+        } else {
+            Some(unsafe {
+                // UNSAFE(@ohsayan): verified above
+                state.fw_read().uck_read_ident()
+            })
+        };
+        state.poison_if_not(state.cursor_rounded_eq(Token![() close]));
+        state.cursor_ahead_if(state.okay());
+        func.map(|func| Self::new(func, field))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// A select that aggregates the rows of a model, or of a primary key range (`select count(*), max(age) from model`)
+pub struct AggregateStatement<'a> {
+    entity: EntityIDRef<'a>,
+    aggregates: Vec<Aggregate<'a>>,
+    clause: WhereClause<'a>,
+}
+
+impl<'a> AggregateStatement<'a> {
+    #[cfg(test)]
+    pub(crate) fn new(
+        entity: EntityIDRef<'a>,
+        aggregates: Vec<Aggregate<'a>>,
+        clauses: WhereClauseCollection<'a>,
+    ) -> Self {
+        Self {
+            entity,
+            aggregates,
+            clause: WhereClause::new(clauses),
+        }
+    }
+    /// Check if the tokens following `select` start an aggregate (`<function>(`)
+    pub fn is_aggregate(tokens: &[Token]) -> bool {
+        matches!(tokens, [Token::Ident(_), Token![() open], ..])
+    }
+    pub fn entity(&self) -> EntityIDRef<'a> {
+        self.entity
+    }
+    pub fn aggregates(&self) -> &[Aggregate<'a>] {
+        &self.aggregates
+    }
+    pub fn clauses_mut(&mut self) -> &mut WhereClause<'a> {
+        &mut self.clause
+    }
+    pub fn parse_aggregate<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        /*
+            Smallest query:
+            select count(*) from model
+                   ^    ^^^ ^    ^
+                   1    234 5    6
+        */
+        if compiler::unlikely(state.remaining() < 6) {
+            return compiler::cold_rerr(QueryError::QLUnexpectedEndOfStatement);
+        }
+        let mut aggregates = Vec::new();
+        while state.okay() {
+            match Aggregate::parse(state) {
+                Some(aggregate) => aggregates.push(aggregate),
+                None => break,
+            }
+            let nx_comma = state.cursor_rounded_eq(Token![,]);
+            let nx_from = state.cursor_rounded_eq(Token![from]);
+            state.poison_if_not(nx_comma | nx_from);
+            state.cursor_ahead_if(nx_comma);
+            if nx_from {
+                break;
+            }
+        }
+        // we should have from + model
+        if compiler::unlikely(state.remaining() < 2 || !state.okay()) {
+            return compiler::cold_rerr(QueryError::QLInvalidSyntax);
+        }
+        state.poison_if_not(state.cursor_eq(Token![from]));
+        state.cursor_ahead(); // ignore errors
+        let entity = state.try_entity_ref_result()?;
+        let mut clauses = <_ as Default>::default();
+        if state.cursor_rounded_eq(Token![where]) {
+            state.cursor_ahead();
+            WhereClause::parse_where_and_append_to(state, &mut clauses);
+            state.poison_if(clauses.is_empty());
+        }
+        if compiler::likely(state.okay()) {
+            Ok(Self {
+                entity,
+                aggregates,
+                clause: WhereClause::new(clauses),
+            })
+        } else {
+            compiler::cold_rerr(QueryError::QLInvalidSyntax)
+        }
+    }
+}
+
+mod impls {
+    use {
+        super::AggregateStatement,
+        crate::engine::{
+            error::QueryResult,
+            ql::ast::{traits::ASTNode, QueryData, State},
+        },
+    };
+    impl<'a> ASTNode<'a> for AggregateStatement<'a> {
+        const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+        const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+        fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+            state: &mut State<'a, Qd>,
+        ) -> QueryResult<Self> {
+            Self::parse_aggregate(state)
+        }
+    }
+}
@@ -27,7 +27,7 @@
 #[cfg(test)]
 use super::WhereClauseCollection;
 use {
-    super::WhereClause,
+    super::{sel::SelectWindow, Returning, WhereClause},
     crate::{
         engine::{
             core::EntityIDRef,
@@ -49,6 +49,13 @@ use {
 pub struct DeleteStatement<'a> {
     pub(super) entity: EntityIDRef<'a>,
     pub(super) wc: WhereClause<'a>,
+    /// the version that the row must be at for the delete to go through (`if version = <n>`)
+    pub(super) version: Option<u64>,
+    /// the most rows that can be deleted (`limit <n>`). the where clause can use any field if this is set, and the
+    /// delete removes every row that matches (up to the limit) instead of a single row
+    pub(super) limit: Option<u64>,
+    /// the fields of the deleted rows to send back (`returning ...`)
+    pub(super) returning: Option<Returning<'a>>,
 }
 
 impl<'a> DeleteStatement<'a> {
@@ -58,13 +65,44 @@ impl<'a> DeleteStatement<'a> {
     pub fn clauses_mut(&mut self) -> &mut WhereClause<'a> {
         &mut self.wc
     }
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
+    pub fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+    /// Take the `returning` clause (if any)
+    pub fn take_returning(&mut self) -> Option<Returning<'a>> {
+        self.returning.take()
+    }
 }
 
 impl<'a> DeleteStatement<'a> {
     #[inline(always)]
     #[cfg(test)]
     pub(super) fn new(entity: EntityIDRef<'a>, wc: WhereClause<'a>) -> Self {
-        Self { entity, wc }
+        Self {
+            entity,
+            wc,
+            version: None,
+            limit: None,
+            returning: None,
+        }
+    }
+    #[cfg(test)]
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_returning(mut self, returning: Returning<'a>) -> Self {
+        self.returning = Some(returning);
+        self
     }
     #[inline(always)]
     #[cfg(test)]
@@ -78,6 +116,7 @@ impl<'a> DeleteStatement<'a> {
             smallest tt:
             delete from model where x = 1
                    ^1   ^2    ^3    ^4  ^5
+            optionally followed by `limit <n>` or `if version = <n>` (but not both) and `returning ...`
         */
         if compiler::unlikely(state.remaining() < 5) {
             return compiler::cold_rerr(QueryError::QLUnexpectedEndOfStatement);
@@ -90,6 +129,16 @@ impl<'a> DeleteStatement<'a> {
         state.poison_if_not(state.cursor_eq(Token![where]));
         state.cursor_ahead(); // ignore errors
         let wc = WhereClause::parse_where(state);
+        let limit = if state.okay() && state.cursor_rounded_eq(Token![limit]) {
+            state.cursor_ahead();
+            Some(SelectWindow::parse_uint(state))
+        } else {
+            None
+        };
+        let version = super::parse_if_version(state);
+        // a version only makes sense for a single row
+        state.poison_if(limit.is_some() & version.is_some());
+        let returning = super::parse_returning(state);
         if compiler::likely(state.okay()) {
             Ok(Self {
                 entity: unsafe {
@@ -97,6 +146,9 @@ impl<'a> DeleteStatement<'a> {
                     entity.assume_init()
                 },
                 wc,
+                version,
+                limit,
+                returning,
             })
         } else {
             compiler::cold_rerr(QueryError::QLInvalidSyntax)
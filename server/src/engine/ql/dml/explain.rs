@@ -0,0 +1,124 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{
+        del::DeleteStatement,
+        ins::InsertStatement,
+        sel::{SelectAllStatement, SelectStatement},
+        upd::UpdateStatement,
+    },
+    crate::{
+        engine::{
+            core::EntityIDRef,
+            error::{QueryError, QueryResult},
+            ql::{
+                ast::{traits::ASTNode, QueryData, State},
+                lex::{Keyword, KeywordStmt, Token},
+            },
+        },
+        util::compiler,
+    },
+};
+
+/*
+    Impls for explain
+    ---
+    Smallest statement:
+    explain delete from model where x = 1
+*/
+
+#[derive(Debug, PartialEq)]
+/// A DML statement that should be planned (but not executed)
+pub enum ExplainStatement<'a> {
+    Insert(InsertStatement<'a>),
+    Select(SelectStatement<'a>),
+    SelectAll(SelectAllStatement<'a>),
+    Update(UpdateStatement<'a>),
+    Delete(DeleteStatement<'a>),
+}
+
+impl<'a> ExplainStatement<'a> {
+    pub fn entity(&self) -> EntityIDRef<'a> {
+        match self {
+            Self::Insert(insert) => insert.entity(),
+            Self::Select(select) => select.entity(),
+            Self::SelectAll(select) => select.entity,
+            Self::Update(update) => update.entity(),
+            Self::Delete(delete) => delete.entity(),
+        }
+    }
+    pub fn parse_explain<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
+        if compiler::unlikely(state.exhausted()) {
+            return compiler::cold_rerr(QueryError::QLUnexpectedEndOfStatement);
+        }
+        let Token::Keyword(Keyword::Statement(stmt)) = state.fw_read() else {
+            return compiler::cold_rerr(QueryError::QLExpectedStatement);
+        };
+        match stmt {
+            KeywordStmt::Insert => {
+                <InsertStatement as ASTNode>::__base_impl_parse_from_state(state).map(Self::Insert)
+            }
+            KeywordStmt::Select if state.cursor_rounded_eq(Token![all]) => {
+                state.cursor_ahead();
+                <SelectAllStatement as ASTNode>::__base_impl_parse_from_state(state)
+                    .map(Self::SelectAll)
+            }
+            KeywordStmt::Select => {
+                <SelectStatement as ASTNode>::__base_impl_parse_from_state(state).map(Self::Select)
+            }
+            KeywordStmt::Update => {
+                <UpdateStatement as ASTNode>::__base_impl_parse_from_state(state).map(Self::Update)
+            }
+            KeywordStmt::Delete => {
+                <DeleteStatement as ASTNode>::__base_impl_parse_from_state(state).map(Self::Delete)
+            }
+            // only DML can be explained
+            _ => compiler::cold_rerr(QueryError::QLUnknownStatement),
+        }
+    }
+}
+
+mod impls {
+    use {
+        super::ExplainStatement,
+        crate::engine::{
+            error::QueryResult,
+            ql::ast::{traits::ASTNode, QueryData, State},
+        },
+    };
+    impl<'a> ASTNode<'a> for ExplainStatement<'a> {
+        const MUST_USE_FULL_TOKEN_RANGE: bool = true;
+        const VERIFIES_FULL_TOKEN_RANGE_USAGE: bool = false;
+        // the inner statements don't all verify the state before returning
+        const VERIFY_STATE_BEFORE_RETURN: bool = true;
+        fn __base_impl_parse_from_state<Qd: QueryData<'a>>(
+            state: &mut State<'a, Qd>,
+        ) -> QueryResult<Self> {
+            Self::parse_explain(state)
+        }
+    }
+}
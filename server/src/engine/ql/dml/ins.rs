@@ -25,10 +25,11 @@
 */
 
 use {
+    super::Returning,
     crate::{
         engine::{
             core::EntityIDRef,
-            data::cell::Datacell,
+            data::{cell::Datacell, dict::DictCell},
             error::{QueryError, QueryResult},
             ql::{
                 ast::{QueryData, State},
@@ -75,6 +76,14 @@ fn pfnbase_uuid() -> Uuid {
 fn pfn_timesec() -> Datacell {
     Datacell::new_uint_default(pfnbase_time().as_secs())
 }
+/// The current time, in micros since the epoch (for timestamps)
+pub(super) fn pfnbase_now() -> u64 {
+    pfnbase_time().as_micros() as u64
+}
+#[inline(always)]
+fn pfn_now() -> Datacell {
+    Datacell::new_timestamp(pfnbase_now())
+}
 #[inline(always)]
 fn pfn_uuidstr() -> Datacell {
     Datacell::new_str(pfnbase_uuid().to_string().into_boxed_str())
@@ -84,11 +93,12 @@ fn pfn_uuidbin() -> Datacell {
     Datacell::new_bin(pfnbase_uuid().as_bytes().to_vec().into_boxed_slice())
 }
 
-static PRODUCER_G: [u8; 4] = [0, 2, 3, 0];
-static PRODUCER_F: [(&[u8], ProducerFn); 3] = [
+static PRODUCER_G: [u8; 5] = [4, 0, 4, 1, 3];
+static PRODUCER_F: [(&[u8], ProducerFn); 4] = [
     (b"uuidstr", pfn_uuidstr),
     (b"uuidbin", pfn_uuidbin),
     (b"timesec", pfn_timesec),
+    (b"now", pfn_now),
 ];
 const MAGIC_1: [u8; 7] = *b"cp21rLd";
 const MAGIC_2: [u8; 7] = *b"zS8zgaK";
@@ -155,6 +165,11 @@ pub(super) fn parse_list<'a, Qd: QueryData<'a>>(
                 }
                 Datacell::new_list(nested_list)
             }
+            Token![open {}] if state.not_exhausted() => {
+                let mut dict = DictCell::new();
+                parse_dict(state, &mut dict);
+                Datacell::new_dict(dict)
+            }
             Token![@] if state.cursor_signature_match_fn_arity0_rounded() => match unsafe {
                 // UNSAFE(@ohsayan): Just verified at guard
                 handle_func_sub(state)
@@ -183,6 +198,64 @@ pub(super) fn parse_list<'a, Qd: QueryData<'a>>(
     overall_dscr
 }
 
+/// Parse a dict value (`{key: value, ...}`, with the opening brace already read). The values can be of any type (but
+/// not null), including lists and other dicts
+///
+/// ## Panics
+/// - If tt length is less than 1
+pub(super) fn parse_dict<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>, dict: &mut DictCell) {
+    let mut stop = state.cursor_eq(Token![close {}]);
+    state.cursor_ahead_if(stop);
+    while state.has_remaining(3) && state.okay() && !stop {
+        let key = state.fw_read();
+        let colon = state.fw_read();
+        let Token::Ident(key) = key else {
+            state.cursor_back_by(2);
+            state.poison();
+            break;
+        };
+        state.poison_if_not(Token![:].eq(colon));
+        let d = match state.fw_read() {
+            tok if state.can_read_lit_from(tok) => unsafe {
+                // UNSAFE(@ohsayan): the if guard guarantees correctness
+                state.read_lit_into_data_type_unchecked_from(tok)
+            },
+            Token![open []] if state.not_exhausted() => {
+                let mut l = Vec::new();
+                let _ = parse_list(state, &mut l);
+                Datacell::new_list(l)
+            }
+            Token![open {}] if state.not_exhausted() => {
+                let mut nested_dict = DictCell::new();
+                parse_dict(state, &mut nested_dict);
+                Datacell::new_dict(nested_dict)
+            }
+            Token![@] if state.cursor_signature_match_fn_arity0_rounded() => match unsafe {
+                // UNSAFE(@ohsayan): Just verified at guard
+                handle_func_sub(state)
+            } {
+                Some(value) => value,
+                None => {
+                    state.poison();
+                    break;
+                }
+            },
+            _ => {
+                state.cursor_back_by(3);
+                state.poison();
+                break;
+            }
+        };
+        state.poison_if_not(dict.insert(key.as_str().into(), d).is_none());
+        let nx_comma = state.cursor_rounded_eq(Token![,]);
+        let nx_csbrc = state.cursor_rounded_eq(Token![close {}]);
+        state.poison_if_not(nx_comma | nx_csbrc);
+        state.cursor_ahead_if(state.okay());
+        stop = nx_csbrc;
+    }
+    state.poison_if_not(stop);
+}
+
 #[inline(always)]
 /// ## Safety
 /// - Cursor must match arity(0) function signature
@@ -211,6 +284,11 @@ pub(super) fn parse_data_tuple_syntax<'a, Qd: QueryData<'a>>(
                 let _ = parse_list(state, &mut l);
                 data.push(l.into());
             }
+            Token![open {}] if state.not_exhausted() => {
+                let mut d = DictCell::new();
+                parse_dict(state, &mut d);
+                data.push(d.into());
+            }
             Token![null] => data.push(Datacell::null()),
             Token![@] if state.cursor_signature_match_fn_arity0_rounded() => match unsafe {
                 // UNSAFE(@ohsayan): Just verified at guard
@@ -266,6 +344,11 @@ pub(super) fn parse_data_map_syntax<'a, Qd: QueryData<'a>>(
                 let _ = parse_list(state, &mut l);
                 state.poison_if_not(data.insert(*id, l.into()).is_none());
             }
+            (Token::Ident(id), Token![open {}]) if state.not_exhausted() => {
+                let mut d = DictCell::new();
+                parse_dict(state, &mut d);
+                state.poison_if_not(data.insert(*id, d.into()).is_none());
+            }
             (Token::Ident(id), Token![@]) if state.cursor_signature_match_fn_arity0_rounded() => {
                 match unsafe {
                     // UNSAFE(@ohsayan): Just verified at guard
@@ -323,21 +406,69 @@ impl<'a> From<HashMap<Ident<'static>, Datacell>> for InsertData<'a> {
 #[derive(Debug, PartialEq)]
 pub struct InsertStatement<'a> {
     pub(super) entity: EntityIDRef<'a>,
-    pub(super) data: InsertData<'a>,
+    pub(super) rows: Vec<InsertData<'a>>,
+    /// the fields of the new rows to send back (`returning ...`)
+    pub(super) returning: Option<Returning<'a>>,
 }
 
 impl<'a> InsertStatement<'a> {
     #[inline(always)]
     #[cfg(test)]
     pub fn new(entity: EntityIDRef<'a>, data: InsertData<'a>) -> Self {
-        Self { entity, data }
+        Self::new_multi(entity, vec![data])
+    }
+    #[inline(always)]
+    #[cfg(test)]
+    pub fn new_multi(entity: EntityIDRef<'a>, rows: Vec<InsertData<'a>>) -> Self {
+        Self {
+            entity,
+            rows,
+            returning: None,
+        }
+    }
+    #[cfg(test)]
+    pub fn with_returning(mut self, returning: Returning<'a>) -> Self {
+        self.returning = Some(returning);
+        self
     }
     pub fn entity(&self) -> EntityIDRef<'a> {
         self.entity
     }
-    pub fn data(self) -> InsertData<'a> {
-        self.data
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+    pub fn into_rows(self) -> Vec<InsertData<'a>> {
+        self.rows
+    }
+    /// Take the `returning` clause (if any)
+    pub fn take_returning(&mut self) -> Option<Returning<'a>> {
+        self.returning.take()
+    }
+}
+
+/// Parse a column list (`(col1, col2, ...)`), ignoring the leading paren
+fn parse_column_list<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Vec<Ident<'a>> {
+    let mut columns = Vec::new();
+    let mut stop = false;
+    while state.not_exhausted() && state.okay() && !stop {
+        match state.fw_read() {
+            Token::Ident(id) => {
+                state.poison_if(columns.contains(id));
+                columns.push(*id);
+            }
+            _ => {
+                state.poison();
+                break;
+            }
+        }
+        let nx_comma = state.cursor_rounded_eq(Token![,]);
+        let nx_csprn = state.cursor_rounded_eq(Token![() close]);
+        state.poison_if_not(nx_comma | nx_csprn);
+        state.cursor_ahead_if(state.okay());
+        stop = nx_csprn;
     }
+    state.poison_if_not(stop);
+    columns
 }
 
 impl<'a> InsertStatement<'a> {
@@ -355,31 +486,59 @@ impl<'a> InsertStatement<'a> {
 
         // entity
         let entity = state.try_entity_buffered_into_state_uninit();
-        let mut data = None;
-        match state.fw_read() {
-            Token![() open] if state.not_exhausted() => {
-                let this_data = parse_data_tuple_syntax(state);
-                data = Some(InsertData::Ordered(this_data));
+        let mut rows = Vec::with_capacity(1);
+        let column_syntax = state.cursor_rounded_eq(Token![() open]) & state.has_remaining(2)
+            && state.offset_current_r(1).is_ident();
+        if column_syntax {
+            // insert into model (col1, col2) values (...), (...)
+            state.cursor_ahead();
+            let columns = parse_column_list(state);
+            state.poison_if_not(state.cursor_rounded_eq(Token![values]));
+            state.cursor_ahead_if(state.okay());
+            let mut more = true;
+            while state.okay() && more {
+                state.poison_if_not(state.has_remaining(2) && state.cursor_eq(Token![() open]));
+                state.cursor_ahead_if(state.okay());
+                let tuple = parse_data_tuple_syntax(state);
+                state.poison_if_not(tuple.len() == columns.len());
+                rows.push(InsertData::Map(
+                    columns.iter().copied().zip(tuple).collect(),
+                ));
+                more = state.cursor_rounded_eq(Token![,]);
+                state.cursor_ahead_if(more);
+                state.poison_if(more & state.exhausted());
             }
-            Token![open {}] if state.not_exhausted() => {
-                let this_data = parse_data_map_syntax(state);
-                data = Some(InsertData::Map(this_data));
-            }
-            _ => {
-                state.poison();
+        } else {
+            // insert into model (...), (...) or insert into model {...}, {...}
+            let mut more = true;
+            while state.okay() && more {
+                match state.fw_read() {
+                    Token![() open] if state.not_exhausted() => {
+                        let this_data = parse_data_tuple_syntax(state);
+                        rows.push(InsertData::Ordered(this_data));
+                    }
+                    Token![open {}] if state.not_exhausted() => {
+                        let this_data = parse_data_map_syntax(state);
+                        rows.push(InsertData::Map(this_data));
+                    }
+                    _ => {
+                        state.poison();
+                    }
+                }
+                more = state.cursor_rounded_eq(Token![,]);
+                state.cursor_ahead_if(more);
+                state.poison_if(more & state.exhausted());
             }
         }
+        let returning = super::parse_returning(state);
         if state.okay() {
-            let data = unsafe {
-                // UNSAFE(@ohsayan): state's flag guarantees correctness (see wildcard branch)
-                data.unwrap_unchecked()
-            };
             Ok(InsertStatement {
                 entity: unsafe {
                     // UNSAFE(@ohsayan): state's flag ensures correctness (see Entity::parse_entity)
                     entity.assume_init()
                 },
-                data,
+                rows,
+                returning,
             })
         } else {
             compiler::cold_rerr(QueryError::QLInvalidSyntax)
@@ -29,7 +29,9 @@
     should augment in future revisions of the QL engine
 */
 
+pub mod agg;
 pub mod del;
+pub mod explain;
 pub mod ins;
 pub mod sel;
 pub mod upd;
@@ -40,7 +42,10 @@ use {
         lex::Ident,
     },
     crate::{engine::data::lit::Lit, util::compiler},
-    std::collections::HashMap,
+    std::{
+        collections::{hash_map::Entry, HashMap},
+        ops::Bound,
+    },
 };
 
 #[inline(always)]
@@ -52,6 +57,254 @@ fn u(b: bool) -> u8 {
     Misc
 */
 
+/// Check if the cursor is at the `version` pseudo-field (used for optimistic concurrency control). This is not a
+/// keyword so that it can still be used as a field name
+fn cursor_is_version<'a, Qd: QueryData<'a>>(state: &State<'a, Qd>) -> bool {
+    state.cursor_has_ident_rounded()
+        && unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read().uck_read_ident()
+        }
+        .as_str()
+        .eq_ignore_ascii_case("version")
+}
+
+/// Returns true if the cursor is at `contains` (which isn't a keyword, so it can still be used as a name)
+fn cursor_is_contains<'a, Qd: QueryData<'a>>(state: &State<'a, Qd>) -> bool {
+    state.cursor_has_ident_rounded()
+        && unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read().uck_read_ident()
+        }
+        .as_str()
+        .eq_ignore_ascii_case("contains")
+}
+
+/// Returns true if the cursor is at `like` (which isn't a keyword, so it can still be used as a name)
+fn cursor_is_like<'a, Qd: QueryData<'a>>(state: &State<'a, Qd>) -> bool {
+    state.cursor_has_ident_rounded()
+        && unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read().uck_read_ident()
+        }
+        .as_str()
+        .eq_ignore_ascii_case("like")
+}
+
+/// Returns true if the cursor is at `within` (which isn't a keyword, so it can still be used as a name)
+fn cursor_is_within<'a, Qd: QueryData<'a>>(state: &State<'a, Qd>) -> bool {
+    state.cursor_has_ident_rounded()
+        && unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read().uck_read_ident()
+        }
+        .as_str()
+        .eq_ignore_ascii_case("within")
+}
+
+/// Parse the `(lat, lon, radius)` of a `within` expression. Any numeric literal can be used
+fn parse_circle<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<[f64; 3]> {
+    state.poison_if_not(state.cursor_rounded_eq(Token![() open]));
+    state.cursor_ahead_if(state.okay());
+    let mut circle = [0.0; 3];
+    for (i, value) in circle.iter_mut().enumerate() {
+        if i != 0 {
+            state.poison_if_not(state.cursor_rounded_eq(Token![,]));
+            state.cursor_ahead_if(state.okay());
+        }
+        state.poison_if_not(state.can_read_lit_rounded());
+        if !state.okay() {
+            return None;
+        }
+        let lit = unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read_cursor_lit_unchecked()
+        };
+        state.cursor_ahead();
+        let number = lit
+            .try_float()
+            .or_else(|| lit.try_uint().map(|u| u as f64))
+            .or_else(|| lit.try_sint().map(|s| s as f64));
+        state.poison_if(number.is_none());
+        *value = number?;
+    }
+    state.poison_if_not(state.cursor_rounded_eq(Token![() close]));
+    state.cursor_ahead_if(state.okay());
+    state.okay().then_some(circle)
+}
+
+/// Parse the (non-empty) `(a, b, c)` of an `in` expression
+fn parse_values<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<Vec<Lit<'a>>> {
+    state.poison_if_not(state.cursor_rounded_eq(Token![() open]));
+    state.cursor_ahead_if(state.okay());
+    let mut values = vec![];
+    while state.okay() {
+        let value = parse_lit_or_func(state);
+        state.poison_if(value.is_none());
+        values.extend(value);
+        let nx_comma = state.cursor_rounded_eq(Token![,]);
+        let nx_close = state.cursor_rounded_eq(Token![() close]);
+        state.poison_if_not(nx_comma | nx_close);
+        state.cursor_ahead_if(state.okay());
+        if nx_close {
+            break;
+        }
+    }
+    state.okay().then_some(values)
+}
+
+/// Read the literal at the cursor, or call the function at the cursor that produces one (`@now()`), and move past
+/// it. Only functions that can produce a literal without allocating are supported here
+fn parse_lit_or_func<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<Lit<'a>> {
+    if state.can_read_lit_rounded() {
+        let lit = unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read_cursor_lit_unchecked()
+        };
+        state.cursor_ahead();
+        return Some(lit);
+    }
+    if !state.cursor_rounded_eq(Token![@]) {
+        return None;
+    }
+    state.cursor_ahead();
+    if !state.cursor_signature_match_fn_arity0_rounded() {
+        return None;
+    }
+    let func = unsafe {
+        // UNSAFE(@ohsayan): verified above
+        state.fw_read().uck_read_ident()
+    };
+    state.cursor_ahead_by(2); // skip tt:paren
+    match func.as_bytes() {
+        b"now" => Some(Lit::new_timestamp(ins::pfnbase_now())),
+        _ => None,
+    }
+}
+
+/// Parse an optional `with version` clause, returning true if it was present
+fn parse_with_version<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> bool {
+    if !state.cursor_rounded_eq(Token![with]) {
+        return false;
+    }
+    state.cursor_ahead();
+    state.poison_if_not(cursor_is_version(state));
+    state.cursor_ahead_if(state.okay());
+    true
+}
+
+/// Parse an optional `if version = <n>` clause, returning the expected version if it was present
+fn parse_if_version<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<u64> {
+    if !state.cursor_rounded_eq(Token![if]) {
+        return None;
+    }
+    state.cursor_ahead();
+    state.poison_if_not(cursor_is_version(state));
+    state.cursor_ahead_if(state.okay());
+    state.poison_if_not(state.cursor_rounded_eq(Token![=]));
+    state.cursor_ahead_if(state.okay());
+    state.poison_if_not(state.can_read_lit_rounded());
+    if !state.okay() {
+        return None;
+    }
+    let version = unsafe {
+        // UNSAFE(@ohsayan): verified above
+        state.read_cursor_lit_unchecked()
+    }
+    .try_uint();
+    state.cursor_ahead();
+    state.poison_if(version.is_none());
+    version
+}
+
+/// Parse an optional `for update` clause, returning true if it was present
+fn parse_for_update<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> bool {
+    let is_for = state.cursor_has_ident_rounded()
+        && unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read().uck_read_ident()
+        }
+        .as_str()
+        .eq_ignore_ascii_case("for");
+    if !is_for {
+        return false;
+    }
+    state.cursor_ahead();
+    state.poison_if_not(state.cursor_rounded_eq(Token![update]));
+    state.cursor_ahead_if(state.okay());
+    true
+}
+
+/// Returns true if the cursor is at `returning` (which isn't a keyword, so it can still be used as a name)
+fn cursor_is_returning<'a, Qd: QueryData<'a>>(state: &State<'a, Qd>) -> bool {
+    state.cursor_has_ident_rounded()
+        && unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read().uck_read_ident()
+        }
+        .as_str()
+        .eq_ignore_ascii_case("returning")
+}
+
+/// The fields of the rows that a DML statement changes, which are sent back to the client (`returning *` or
+/// `returning f1, f2, ...`)
+#[derive(Debug, PartialEq)]
+pub struct Returning<'a> {
+    fields: Vec<Ident<'a>>,
+    wildcard: bool,
+}
+
+impl<'a> Returning<'a> {
+    #[cfg(test)]
+    pub fn new(fields: Vec<Ident<'a>>) -> Self {
+        Self {
+            fields,
+            wildcard: false,
+        }
+    }
+    #[cfg(test)]
+    pub fn new_wildcard() -> Self {
+        Self {
+            fields: vec![],
+            wildcard: true,
+        }
+    }
+    pub fn fields(&self) -> &[Ident<'a>] {
+        &self.fields
+    }
+    pub fn is_wildcard(&self) -> bool {
+        self.wildcard
+    }
+}
+
+/// Parse an optional `returning` clause
+fn parse_returning<'a, Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<Returning<'a>> {
+    if !cursor_is_returning(state) {
+        return None;
+    }
+    state.cursor_ahead();
+    let wildcard = state.cursor_rounded_eq(Token![*]);
+    state.cursor_ahead_if(wildcard);
+    let mut fields = Vec::new();
+    let mut more = !wildcard;
+    while state.okay() && more {
+        state.poison_if_not(state.cursor_has_ident_rounded());
+        if !state.okay() {
+            break;
+        }
+        let field = unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read().uck_read_ident()
+        };
+        state.cursor_ahead();
+        state.poison_if(fields.contains(&field));
+        fields.push(field);
+        more = state.cursor_rounded_eq(Token![,]);
+        state.cursor_ahead_if(more);
+    }
+    Some(Returning { fields, wildcard })
+}
+
 /*
     Contexts
 */
@@ -61,12 +314,60 @@ pub struct RelationalExpr<'a> {
     pub(super) lhs: Ident<'a>,
     pub(super) rhs: Lit<'a>,
     pub(super) opc: u8,
+    /// the upper bound (opc, rhs) if this is a range expression (`col > x and col < y`)
+    pub(super) upper: Option<(u8, Lit<'a>)>,
+    /// the (lat, lon) of the circle's center if this is a `within` expression (the rhs is the radius)
+    pub(super) center: Option<(f64, f64)>,
+    /// the values if this is an `in` expression (the rhs is the first value)
+    pub(super) values: Vec<Lit<'a>>,
 }
 
 impl<'a> RelationalExpr<'a> {
     #[inline(always)]
     pub(super) fn new(lhs: Ident<'a>, rhs: Lit<'a>, opc: u8) -> RelationalExpr<'a> {
-        Self { lhs, rhs, opc }
+        Self {
+            lhs,
+            rhs,
+            opc,
+            upper: None,
+            center: None,
+            values: vec![],
+        }
+    }
+    pub(super) fn new_in(lhs: Ident<'a>, values: Vec<Lit<'a>>) -> RelationalExpr<'a> {
+        Self {
+            lhs,
+            rhs: values[0].clone(),
+            opc: Self::OP_IN,
+            upper: None,
+            center: None,
+            values,
+        }
+    }
+    pub(super) fn new_circle(lhs: Ident<'a>, [lat, lon, radius]: [f64; 3]) -> RelationalExpr<'a> {
+        Self {
+            lhs,
+            rhs: Lit::new_float(radius),
+            opc: Self::OP_WITHIN,
+            upper: None,
+            center: Some((lat, lon)),
+            values: vec![],
+        }
+    }
+    #[cfg(test)]
+    pub(super) fn new_range(
+        lhs: Ident<'a>,
+        (lower_opc, lower): (u8, Lit<'a>),
+        (upper_opc, upper): (u8, Lit<'a>),
+    ) -> RelationalExpr<'a> {
+        Self {
+            lhs,
+            rhs: lower,
+            opc: lower_opc,
+            upper: Some((upper_opc, upper)),
+            center: None,
+            values: vec![],
+        }
     }
     pub(super) const OP_EQ: u8 = 1;
     pub(super) const OP_NE: u8 = 2;
@@ -74,14 +375,116 @@ impl<'a> RelationalExpr<'a> {
     pub(super) const OP_GE: u8 = 4;
     pub(super) const OP_LT: u8 = 5;
     pub(super) const OP_LE: u8 = 6;
+    /// `col contains 'some words'`, which matches if the string has all the words (see [`crate::engine::idx::fts`])
+    pub(super) const OP_CONTAINS: u8 = 7;
+    /// `col like 'abc%'`, which matches if the string matches the pattern (`%` is any run of characters and `_` is
+    /// any one character)
+    pub(super) const OP_LIKE: u8 = 8;
+    /// `col within (lat, lon, radius)`, which matches if the point is at most `radius` meters from (lat, lon)
+    pub(super) const OP_WITHIN: u8 = 9;
+    /// `col in (a, b, c)`, which matches if the value is any one of the values
+    pub(super) const OP_IN: u8 = 10;
     pub fn filter_hint_none(&self) -> bool {
         self.opc == Self::OP_EQ
     }
     pub fn rhs(&self) -> Lit<'a> {
         self.rhs.clone()
     }
+    fn is_lower_bound(opc: u8) -> bool {
+        (opc == Self::OP_GT) | (opc == Self::OP_GE)
+    }
+    fn is_upper_bound(opc: u8) -> bool {
+        (opc == Self::OP_LT) | (opc == Self::OP_LE)
+    }
+    /// Returns true if this expression looks for words in the column
+    pub fn is_text_search(&self) -> bool {
+        self.opc == Self::OP_CONTAINS
+    }
+    /// Returns the center and radius (in meters) of the circle if this is a `within` expression
+    pub fn circle(&self) -> Option<((f64, f64), f64)> {
+        let center = self.center?;
+        Some((center, self.rhs.try_float()?))
+    }
+    /// Returns true if this expression looks for points in a circle
+    pub fn is_geo_search(&self) -> bool {
+        self.opc == Self::OP_WITHIN
+    }
+    /// Returns true if this expression matches the column against a list of values
+    pub fn is_in(&self) -> bool {
+        self.opc == Self::OP_IN
+    }
+    /// The values of an `in` expression (empty for any other expression)
+    pub fn values(&self) -> &[Lit<'a>] {
+        &self.values
+    }
+    pub fn into_values(self) -> Vec<Lit<'a>> {
+        self.values
+    }
+    /// Returns true if this expression matches the column against a pattern
+    pub fn is_pattern(&self) -> bool {
+        self.opc == Self::OP_LIKE
+    }
+    /// Returns true if this expression bounds the column on either (or both) sides
+    pub fn is_range(&self) -> bool {
+        Self::is_lower_bound(self.opc) | Self::is_upper_bound(self.opc)
+    }
+    /// Returns the (start, end) bounds if this is a range expression
+    pub fn range_bounds(&self) -> Option<(Bound<Lit<'a>>, Bound<Lit<'a>>)> {
+        fn bound(opc: u8, lit: Lit) -> Bound<Lit> {
+            if (opc == RelationalExpr::OP_GT) | (opc == RelationalExpr::OP_LT) {
+                Bound::Excluded(lit)
+            } else {
+                Bound::Included(lit)
+            }
+        }
+        if Self::is_lower_bound(self.opc) {
+            let end = match self.upper.clone() {
+                Some((opc, lit)) => bound(opc, lit),
+                None => Bound::Unbounded,
+            };
+            Some((bound(self.opc, self.rhs()), end))
+        } else if Self::is_upper_bound(self.opc) {
+            Some((Bound::Unbounded, bound(self.opc, self.rhs())))
+        } else {
+            None
+        }
+    }
+    /// Attempt to merge another expression on the same column into a range expression. Only a lower and upper bound
+    /// can be merged (once)
+    fn merge_range(&mut self, other: Self) -> bool {
+        if self.upper.is_some() {
+            return false;
+        }
+        if Self::is_lower_bound(self.opc) & Self::is_upper_bound(other.opc) {
+            self.upper = Some((other.opc, other.rhs));
+            true
+        } else if Self::is_upper_bound(self.opc) & Self::is_lower_bound(other.opc) {
+            let upper_opc = core::mem::replace(&mut self.opc, other.opc);
+            let upper = core::mem::replace(&mut self.rhs, other.rhs);
+            self.upper = Some((upper_opc, upper));
+            true
+        } else {
+            false
+        }
+    }
     #[inline(always)]
     fn parse_operator<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> u8 {
+        if cursor_is_contains(state) {
+            state.cursor_ahead();
+            return Self::OP_CONTAINS;
+        }
+        if cursor_is_like(state) {
+            state.cursor_ahead();
+            return Self::OP_LIKE;
+        }
+        if cursor_is_within(state) {
+            state.cursor_ahead();
+            return Self::OP_WITHIN;
+        }
+        if state.cursor_rounded_eq(Token![in]) {
+            state.cursor_ahead();
+            return Self::OP_IN;
+        }
         let tok = state.current();
         let op_eq = u(tok[0] == Token![=]) * Self::OP_EQ;
         let op_ne = u(tok[0] == Token![!] && tok[1] == Token![=]) * Self::OP_NE;
@@ -103,17 +506,38 @@ impl<'a> RelationalExpr<'a> {
         state.poison_if_not(ident.is_ident());
         state.cursor_ahead(); // ignore any errors
         let operator = Self::parse_operator(state);
-        state.poison_if_not(state.can_read_lit_rounded());
-        if compiler::likely(state.okay()) {
-            unsafe {
-                // UNSAFE(@ohsayan): we verified this above
-                let lit = state.read_cursor_lit_unchecked();
-                state.cursor_ahead();
+        if operator == Self::OP_WITHIN {
+            let circle = parse_circle(state);
+            state.poison_if(circle.is_none());
+            return match circle {
+                Some(circle) if compiler::likely(state.okay()) => unsafe {
+                    // UNSAFE(@ohsayan): we checked if `ident` returns `is_ident` and updated state
+                    Some(Self::new_circle(ident.uck_read_ident(), circle))
+                },
+                _ => None,
+            };
+        }
+        if operator == Self::OP_IN {
+            let values = parse_values(state);
+            return match values {
+                Some(values) if compiler::likely(state.okay()) => unsafe {
+                    // UNSAFE(@ohsayan): we checked if `ident` returns `is_ident` and updated state
+                    Some(Self::new_in(ident.uck_read_ident(), values))
+                },
+                _ => None,
+            };
+        }
+        let lit = match state.okay() {
+            true => parse_lit_or_func(state),
+            false => None,
+        };
+        state.poison_if(lit.is_none());
+        match lit {
+            Some(lit) if compiler::likely(state.okay()) => unsafe {
                 // UNSAFE(@ohsayan): we checked if `ident` returns `is_ident` and updated state
                 Some(Self::new(ident.uck_read_ident(), lit, operator))
-            }
-        } else {
-            None
+            },
+            _ => None,
         }
     }
 }
@@ -133,6 +557,26 @@ impl<'a> WhereClause<'a> {
     pub fn clauses_mut(&mut self) -> &mut WhereClauseCollection<'a> {
         &mut self.c
     }
+    /// Returns true if any of the clauses is a range expression
+    pub fn has_range(&self) -> bool {
+        self.c.values().any(RelationalExpr::is_range)
+    }
+    /// Returns true if any of the clauses looks for words in a column
+    pub fn has_text_search(&self) -> bool {
+        self.c.values().any(RelationalExpr::is_text_search)
+    }
+    /// Returns true if any of the clauses looks for points in a circle
+    pub fn has_geo_search(&self) -> bool {
+        self.c.values().any(RelationalExpr::is_geo_search)
+    }
+    /// Returns true if any of the clauses matches a column against a list of values
+    pub fn has_in(&self) -> bool {
+        self.c.values().any(RelationalExpr::is_in)
+    }
+    /// Returns true if any of the clauses matches a column against a pattern
+    pub fn has_pattern(&self) -> bool {
+        self.c.values().any(RelationalExpr::is_pattern)
+    }
     #[inline(always)]
     fn parse_where_and_append_to<Qd: QueryData<'a>>(
         state: &mut State<'a, Qd>,
@@ -141,7 +585,15 @@ impl<'a> WhereClause<'a> {
         let mut has_more = true;
         while has_more && state.not_exhausted() && state.okay() {
             if let Some(expr) = RelationalExpr::try_parse(state) {
-                state.poison_if_not(c.insert(expr.lhs, expr).is_none());
+                match c.entry(expr.lhs) {
+                    Entry::Vacant(ve) => {
+                        ve.insert(expr);
+                    }
+                    Entry::Occupied(mut oe) => {
+                        // only allow a column to be repeated if it forms a range
+                        state.poison_if_not(oe.get_mut().merge_range(expr))
+                    }
+                }
             }
             has_more = state.cursor_rounded_eq(Token![and]);
             state.cursor_ahead_if(has_more);
@@ -45,16 +45,240 @@ use {
     Impls for select
 */
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// A row of another model (in the same space) that is looked up using the value of a field (`other_model[fk_field]`),
+/// and the field of that row that is needed (`other_model[fk_field].field`). The whole row is needed if there's no
+/// field
+pub struct SelectLookup<'a> {
+    model: Ident<'a>,
+    field: Option<Ident<'a>>,
+}
+
+impl<'a> SelectLookup<'a> {
+    pub fn new(model: Ident<'a>, field: Option<Ident<'a>>) -> Self {
+        Self { model, field }
+    }
+    pub fn model(&self) -> Ident<'a> {
+        self.model
+    }
+    pub fn field(&self) -> Option<Ident<'a>> {
+        self.field
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// A step along the path to a nested value: an element of a list (`[1]`) or the value of a key of a dict (`.key`)
+pub enum SelectPathSegment<'a> {
+    Index(u64),
+    Key(Ident<'a>),
+}
+
+#[derive(Debug, PartialEq)]
+/// A selected field, along with the path to a nested value if only that value is needed (`field[1][2]`,
+/// `field.key` or `field[1].key`). If the field is used to look up a row of another model (`other_model[field]`), the
+/// lookup is set
+pub struct SelectField<'a> {
+    field: Ident<'a>,
+    path: Vec<SelectPathSegment<'a>>,
+    lookup: Option<SelectLookup<'a>>,
+}
+
+impl<'a> SelectField<'a> {
+    pub fn new(field: Ident<'a>, path: Vec<SelectPathSegment<'a>>) -> Self {
+        Self {
+            field,
+            path,
+            lookup: None,
+        }
+    }
+    pub fn new_lookup(field: Ident<'a>, lookup: SelectLookup<'a>) -> Self {
+        Self {
+            field,
+            path: vec![],
+            lookup: Some(lookup),
+        }
+    }
+    pub fn as_str(&self) -> &'a str {
+        self.field.as_str()
+    }
+    /// The list indices and dict keys to follow (empty if the whole value is needed)
+    pub fn path(&self) -> &[SelectPathSegment<'a>] {
+        &self.path
+    }
+    pub fn lookup(&self) -> Option<SelectLookup<'a>> {
+        self.lookup
+    }
+    /// Parse a selected field, which is either a field followed by a (possibly empty) path or a lookup
+    /// (`other_model[fk_field]` or `other_model[fk_field].field`)
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>, name: Ident<'a>) -> Self {
+        let is_lookup = state.cursor_rounded_eq(Token![open []])
+            & matches!(state.offset_current_r(1), Token::Ident(_));
+        if !is_lookup {
+            return Self::new(name, Self::parse_path(state));
+        }
+        state.cursor_ahead();
+        let field = unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.fw_read().uck_read_ident()
+        };
+        state.poison_if_not(state.cursor_rounded_eq(Token![close []]));
+        state.cursor_ahead_if(state.okay());
+        let mut lookup = SelectLookup::new(name, None);
+        if state.okay() & state.cursor_rounded_eq(Token![.]) {
+            state.cursor_ahead();
+            state.poison_if_not(state.cursor_has_ident_rounded());
+            if state.okay() {
+                lookup.field = Some(unsafe {
+                    // UNSAFE(@ohsayan): verified above
+                    state.fw_read().uck_read_ident()
+                });
+            }
+        }
+        Self::new_lookup(field, lookup)
+    }
+    /// Parse the (possibly empty) path of list indices (`[1]`) and dict keys (`.key`) following a field
+    pub(super) fn parse_path<Qd: QueryData<'a>>(
+        state: &mut State<'a, Qd>,
+    ) -> Vec<SelectPathSegment<'a>> {
+        let mut path = Vec::new();
+        while state.okay() {
+            if state.cursor_rounded_eq(Token![.]) {
+                state.cursor_ahead();
+                state.poison_if_not(state.cursor_has_ident_rounded());
+                if state.okay() {
+                    path.push(SelectPathSegment::Key(unsafe {
+                        // UNSAFE(@ohsayan): verified above
+                        state.fw_read().uck_read_ident()
+                    }));
+                }
+                continue;
+            }
+            if !state.cursor_rounded_eq(Token![open []]) {
+                break;
+            }
+            state.cursor_ahead();
+            state.poison_if_not(state.has_remaining(2) && state.can_read_lit_rounded());
+            if !state.okay() {
+                break;
+            }
+            let index = unsafe {
+                // UNSAFE(@ohsayan): verified above
+                state.read_cursor_lit_unchecked()
+            };
+            state.cursor_ahead();
+            match index.try_uint() {
+                Some(index) => path.push(SelectPathSegment::Index(index)),
+                None => state.poison(),
+            }
+            state.poison_if_not(state.cursor_rounded_eq(Token![close []]));
+            state.cursor_ahead_if(state.okay());
+        }
+        path
+    }
+}
+
+impl<'a> From<Ident<'a>> for SelectField<'a> {
+    fn from(field: Ident<'a>) -> Self {
+        Self::new(field, vec![])
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// `order by <field> asc|desc`
+pub struct OrderBy<'a> {
+    field: Ident<'a>,
+    desc: bool,
+}
+
+impl<'a> OrderBy<'a> {
+    pub fn new(field: Ident<'a>, desc: bool) -> Self {
+        Self { field, desc }
+    }
+    pub fn field(&self) -> Ident<'a> {
+        self.field
+    }
+    pub fn is_desc(&self) -> bool {
+        self.desc
+    }
+}
+
+#[derive(Debug, PartialEq, Default)]
+/// The ordering and paging of a multi-row select (`order by <field> asc|desc limit <n> offset <n>`)
+pub struct SelectWindow<'a> {
+    pub order: Option<OrderBy<'a>>,
+    pub limit: Option<u64>,
+    pub offset: u64,
+}
+
+impl<'a> SelectWindow<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.order.is_none() & self.limit.is_none() & (self.offset == 0)
+    }
+    pub(super) fn parse_uint<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> u64 {
+        state.poison_if_not(state.can_read_lit_rounded());
+        if !state.okay() {
+            return 0;
+        }
+        let lit = unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.read_cursor_lit_unchecked()
+        };
+        state.cursor_ahead();
+        let uint = lit.try_uint();
+        state.poison_if(uint.is_none());
+        uint.unwrap_or(0)
+    }
+    fn parse_order<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Option<OrderBy<'a>> {
+        if !state.cursor_rounded_eq(Token![order]) {
+            return None;
+        }
+        state.cursor_ahead();
+        state.poison_if_not(state.cursor_rounded_eq(Token![by]));
+        state.cursor_ahead_if(state.okay());
+        state.poison_if_not(state.cursor_has_ident_rounded());
+        if !state.okay() {
+            return None;
+        }
+        let field = unsafe {
+            // UNSAFE(@ohsayan): verified above
+            state.fw_read().uck_read_ident()
+        };
+        let desc = state.cursor_rounded_eq(Token![desc]);
+        state.cursor_ahead_if(desc | state.cursor_rounded_eq(Token![asc]));
+        Some(OrderBy::new(field, desc))
+    }
+    /// Parse `[order by <field> [asc|desc]] [limit <n>] [offset <n>]`
+    fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> Self {
+        let mut window = Self::default();
+        window.order = Self::parse_order(state);
+        if state.okay() && state.cursor_rounded_eq(Token![limit]) {
+            state.cursor_ahead();
+            window.limit = Some(Self::parse_uint(state));
+        }
+        if state.okay() && state.cursor_rounded_eq(Token![offset]) {
+            state.cursor_ahead();
+            window.offset = Self::parse_uint(state);
+        }
+        window
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct SelectStatement<'a> {
     /// the entity
     pub(super) entity: EntityIDRef<'a>,
     /// fields in order of querying. will be zero when wildcard is set
-    pub(super) fields: Vec<Ident<'a>>,
+    pub(super) fields: Vec<SelectField<'a>>,
     /// whether a wildcard was passed
     pub(super) wildcard: bool,
     /// where clause
     pub(super) clause: WhereClause<'a>,
+    /// ordering and paging (only for range scans)
+    pub(super) window: SelectWindow<'a>,
+    /// whether the row version should be returned after the fields (`with version`)
+    pub(super) version: bool,
+    /// whether the row's write intent should be taken (`for update`)
+    pub(super) for_update: bool,
 }
 
 impl<'a> SelectStatement<'a> {
@@ -66,13 +290,18 @@ impl<'a> SelectStatement<'a> {
         wildcard: bool,
         clauses: WhereClauseCollection<'a>,
     ) -> SelectStatement<'a> {
-        Self::new(entity, fields, wildcard, clauses)
+        Self::new(
+            entity,
+            fields.into_iter().map(SelectField::from).collect(),
+            wildcard,
+            clauses,
+        )
     }
     #[inline(always)]
     #[cfg(test)]
-    fn new(
+    pub(crate) fn new(
         entity: EntityIDRef<'a>,
-        fields: Vec<Ident<'a>>,
+        fields: Vec<SelectField<'a>>,
         wildcard: bool,
         clauses: WhereClauseCollection<'a>,
     ) -> SelectStatement<'a> {
@@ -81,8 +310,26 @@ impl<'a> SelectStatement<'a> {
             fields,
             wildcard,
             clause: WhereClause::new(clauses),
+            window: SelectWindow::default(),
+            version: false,
+            for_update: false,
         }
     }
+    #[cfg(test)]
+    pub(crate) fn with_window(mut self, window: SelectWindow<'a>) -> Self {
+        self.window = window;
+        self
+    }
+    #[cfg(test)]
+    pub(crate) fn with_version(mut self) -> Self {
+        self.version = true;
+        self
+    }
+    #[cfg(test)]
+    pub(crate) fn with_for_update(mut self) -> Self {
+        self.for_update = true;
+        self
+    }
     pub fn entity(&self) -> EntityIDRef<'a> {
         self.entity
     }
@@ -92,9 +339,35 @@ impl<'a> SelectStatement<'a> {
     pub fn is_wildcard(&self) -> bool {
         self.wildcard
     }
-    pub fn into_fields(self) -> Vec<Ident<'a>> {
+    pub fn wants_version(&self) -> bool {
+        self.version
+    }
+    /// Returns true if the row's write intent should be held until the enclosing transaction ends (`for update`)
+    pub fn is_for_update(&self) -> bool {
+        self.for_update
+    }
+    pub fn is_range_scan(&self) -> bool {
+        self.clause.has_range()
+    }
+    /// Returns true if the rows are found using a secondary index (looking for words in a full-text indexed field
+    /// or for points in a circle)
+    pub fn is_index_search(&self) -> bool {
+        self.clause.has_text_search() | self.clause.has_geo_search()
+    }
+    /// Returns true if the rows are found by matching the primary key against a pattern (`pk like 'abc%'`)
+    pub fn is_pattern_scan(&self) -> bool {
+        self.clause.has_pattern()
+    }
+    /// Returns true if a batch of rows is found using their primary keys (`pk in (k1, k2, ...)`)
+    pub fn is_key_batch(&self) -> bool {
+        self.clause.has_in()
+    }
+    pub fn into_fields(self) -> Vec<SelectField<'a>> {
         self.fields
     }
+    pub fn take_window(&mut self) -> SelectWindow<'a> {
+        core::mem::take(&mut self.window)
+    }
 }
 
 impl<'a> SelectStatement<'a> {
@@ -112,11 +385,12 @@ impl<'a> SelectStatement<'a> {
         let is_wildcard = state.cursor_eq(Token![*]);
         state.cursor_ahead_if(is_wildcard);
         while state.not_exhausted() && state.okay() && !is_wildcard {
-            match state.read() {
-                Token::Ident(id) => select_fields.push(*id),
+            let field = match state.read() {
+                Token::Ident(id) => *id,
                 _ => break,
-            }
+            };
             state.cursor_ahead();
+            select_fields.push(SelectField::parse(state, field));
             let nx_comma = state.cursor_rounded_eq(Token![,]);
             let nx_from = state.cursor_rounded_eq(Token![from]);
             state.poison_if_not(nx_comma | nx_from);
@@ -136,6 +410,27 @@ impl<'a> SelectStatement<'a> {
             WhereClause::parse_where_and_append_to(state, &mut clauses);
             state.poison_if(clauses.is_empty());
         }
+        let clause = WhereClause::new(clauses);
+        let window = SelectWindow::parse(state);
+        // ordering and paging only make sense for range scans (and searches)
+        state.poison_if(
+            !(window.is_empty()
+                | clause.has_range()
+                | clause.has_text_search()
+                | clause.has_geo_search()
+                | clause.has_pattern()),
+        );
+        let version = super::parse_with_version(state);
+        // intents are only taken on single rows
+        let for_update = super::parse_for_update(state);
+        state.poison_if(
+            for_update
+                & (clause.has_range()
+                    | clause.has_text_search()
+                    | clause.has_geo_search()
+                    | clause.has_pattern()
+                    | clause.has_in()),
+        );
         if compiler::likely(state.okay()) {
             Ok(SelectStatement {
                 entity: unsafe {
@@ -144,7 +439,10 @@ impl<'a> SelectStatement<'a> {
                 },
                 fields: select_fields,
                 wildcard: is_wildcard,
-                clause: WhereClause::new(clauses),
+                clause,
+                window,
+                version,
+                for_update,
             })
         } else {
             compiler::cold_rerr(QueryError::QLInvalidSyntax)
@@ -158,6 +456,10 @@ pub struct SelectAllStatement<'a> {
     pub fields: Vec<Ident<'a>>,
     pub wildcard: bool,
     pub limit: u64,
+    pub order: Option<OrderBy<'a>>,
+    pub offset: u64,
+    /// whether the row version should be returned after the fields (`with version`)
+    pub version: bool,
 }
 
 impl<'a> SelectAllStatement<'a> {
@@ -176,6 +478,16 @@ impl<'a> SelectAllStatement<'a> {
             fields,
             wildcard,
             limit,
+            order: None,
+            offset: 0,
+            version: false,
+        }
+    }
+    pub fn window(&self) -> SelectWindow<'a> {
+        SelectWindow {
+            order: self.order,
+            limit: Some(self.limit),
+            offset: self.offset,
         }
     }
     fn parse<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
@@ -206,27 +518,25 @@ impl<'a> SelectAllStatement<'a> {
         state.poison_if_not(state.cursor_eq(Token![from]));
         state.cursor_ahead(); // ignore error
         let entity = state.try_entity_buffered_into_state_uninit();
-        state.poison_if_not(state.cursor_rounded_eq(Token![limit]));
-        state.cursor_ahead_if(state.okay()); // we did read limit
-        state.poison_if(state.exhausted()); // we MUST have the limit
-        if state.okay() {
-            let lit = unsafe { state.fw_read().uck_read_lit() };
-            match lit.try_uint() {
-                Some(limit) => {
-                    return unsafe {
-                        // UNSAFE(@ohsayan): state guarantees this works
-                        Ok(Self::new(
-                            entity.assume_init(),
-                            select_fields,
-                            is_wildcard,
-                            limit,
-                        ))
-                    };
-                }
-                _ => {}
+        let window = SelectWindow::parse(state);
+        let version = super::parse_with_version(state);
+        if state.okay() && window.limit.is_none() && state.exhausted() {
+            // we MUST have the limit
+            return Err(QueryError::QLUnexpectedEndOfStatement);
+        }
+        match window.limit {
+            Some(limit) if state.okay() => {
+                let mut select = unsafe {
+                    // UNSAFE(@ohsayan): state guarantees this works
+                    Self::new(entity.assume_init(), select_fields, is_wildcard, limit)
+                };
+                select.order = window.order;
+                select.offset = window.offset;
+                select.version = version;
+                Ok(select)
             }
+            _ => Err(QueryError::QLInvalidSyntax),
         }
-        Err(QueryError::QLInvalidSyntax)
     }
 }
 
@@ -25,7 +25,10 @@
 */
 
 use {
-    super::{u, WhereClause},
+    super::{
+        sel::{SelectField, SelectPathSegment},
+        u, Returning, WhereClause,
+    },
     crate::{
         engine::{
             core::{query_meta::AssignmentOperator, EntityIDRef},
@@ -53,10 +56,12 @@ static OPERATOR: [AssignmentOperator; 6] = [
     AssignmentOperator::DivAssign,
 ];
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct AssignmentExpression<'a> {
     /// the LHS ident
     pub lhs: Ident<'a>,
+    /// the list index, if a single element of a list is being updated (`lhs[index]`)
+    pub index: Option<u64>,
     /// the RHS lit
     pub rhs: Lit<'a>,
     /// operator
@@ -67,6 +72,21 @@ impl<'a> AssignmentExpression<'a> {
     pub fn new(lhs: Ident<'a>, rhs: Lit<'a>, operator_fn: AssignmentOperator) -> Self {
         Self {
             lhs,
+            index: None,
+            rhs,
+            operator_fn,
+        }
+    }
+    #[cfg(test)]
+    pub fn new_at(
+        lhs: Ident<'a>,
+        index: u64,
+        rhs: Lit<'a>,
+        operator_fn: AssignmentOperator,
+    ) -> Self {
+        Self {
+            lhs,
+            index: Some(index),
             rhs,
             operator_fn,
         }
@@ -85,6 +105,13 @@ impl<'a> AssignmentExpression<'a> {
         }
         let lhs = state.fw_read();
         state.poison_if_not(lhs.is_ident());
+        // only a single level of list elements can be updated
+        let mut path = SelectField::parse_path(state);
+        state.poison_if((path.len() > 1) | matches!(path.first(), Some(SelectPathSegment::Key(_))));
+        if compiler::unlikely(!state.has_remaining(2)) {
+            state.poison();
+            return;
+        }
         let op_ass = u(state.cursor_eq(Token![=]));
         let op_add = u(state.cursor_eq(Token![+])) * 2;
         let op_sub = u(state.cursor_eq(Token![-])) * 3;
@@ -104,19 +131,26 @@ impl<'a> AssignmentExpression<'a> {
         let single_assign_okay = operator_code == 1 && !double_assign_okay;
         state.poison_if_not(single_assign_okay | double_assign_okay);
         state.cursor_ahead_if(double_assign_okay);
-        state.poison_if_not(state.can_read_lit_rounded());
+        let rhs = match state.okay() {
+            true => super::parse_lit_or_func(state),
+            false => None,
+        };
+        state.poison_if(rhs.is_none());
 
-        if state.okay() {
+        if let (true, Some(rhs)) = (state.okay(), rhs) {
             unsafe {
-                // UNSAFE(@ohsayan): Checked lit, state flag ensures we have ident for lhs
-                let rhs = state.read_cursor_lit_unchecked();
-                state.cursor_ahead();
-                expressions.push(AssignmentExpression::new(
+                // UNSAFE(@ohsayan): state flag ensures we have ident for lhs
+                let mut expression = AssignmentExpression::new(
                     // UNSAFE(@ohsayan): we verified if `lhs` returns `is_ident`
                     lhs.uck_read_ident(),
                     rhs,
                     OPERATOR[operator_code as usize],
-                ))
+                );
+                expression.index = match path.pop() {
+                    Some(SelectPathSegment::Index(index)) => Some(index),
+                    _ => None,
+                };
+                expressions.push(expression)
             }
         }
     }
@@ -127,12 +161,19 @@ pub struct UpdateStatement<'a> {
     pub(super) entity: EntityIDRef<'a>,
     pub(super) expressions: Vec<AssignmentExpression<'a>>,
     pub(super) wc: WhereClause<'a>,
+    /// the version that the row must be at for the update to go through (`if version = <n>`)
+    pub(super) version: Option<u64>,
+    /// the fields of the updated rows to send back (`returning ...`)
+    pub(super) returning: Option<Returning<'a>>,
 }
 
 impl<'a> UpdateStatement<'a> {
     pub fn entity(&self) -> EntityIDRef<'a> {
         self.entity
     }
+    pub fn version(&self) -> Option<u64> {
+        self.version
+    }
     pub fn expressions(&self) -> &[AssignmentExpression<'a>] {
         &self.expressions
     }
@@ -142,6 +183,10 @@ impl<'a> UpdateStatement<'a> {
     pub fn into_expressions(self) -> Vec<AssignmentExpression<'a>> {
         self.expressions
     }
+    /// Take the `returning` clause (if any)
+    pub fn take_returning(&mut self) -> Option<Returning<'a>> {
+        self.returning.take()
+    }
 }
 
 impl<'a> UpdateStatement<'a> {
@@ -156,8 +201,20 @@ impl<'a> UpdateStatement<'a> {
             entity,
             expressions,
             wc,
+            version: None,
+            returning: None,
         }
     }
+    #[cfg(test)]
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self
+    }
+    #[cfg(test)]
+    pub fn with_returning(mut self, returning: Returning<'a>) -> Self {
+        self.returning = Some(returning);
+        self
+    }
     #[inline(always)]
     pub fn parse_update<Qd: QueryData<'a>>(state: &mut State<'a, Qd>) -> QueryResult<Self> {
         /*
@@ -194,6 +251,8 @@ impl<'a> UpdateStatement<'a> {
         let mut clauses = <_ as Default>::default();
         WhereClause::parse_where_and_append_to(state, &mut clauses);
         state.poison_if(clauses.is_empty()); // NOTE: volcano
+        let version = super::parse_if_version(state);
+        let returning = super::parse_returning(state);
         if compiler::likely(state.okay()) {
             Ok(Self {
                 entity: unsafe {
@@ -202,6 +261,8 @@ impl<'a> UpdateStatement<'a> {
                 },
                 expressions,
                 wc: WhereClause::new(clauses),
+                version,
+                returning,
             })
         } else {
             compiler::cold_rerr(QueryError::QLInvalidSyntax)
@@ -363,7 +363,20 @@ impl<'a> SecureLexer<'a> {
 }
 
 impl<'a> SecureLexer<'a> {
-    fn _lex(mut self) -> QueryResult<Vec<Token<'a>>> {
+    /// Lex a query that is prepared once and then executed with different parameters (see [`PreparedTokens`])
+    pub fn lex_prepared(q: &'a [u8]) -> QueryResult<PreparedTokens<'a>> {
+        let mut slots = vec![];
+        let tokens = Self::new_with_segments(q, b"")._lex_with(|slf| {
+            // every param is a placeholder for now
+            slots.push(slf.l.tokens.len());
+            slf.l.push_token(Token![null]);
+        })?;
+        Ok(PreparedTokens { tokens, slots })
+    }
+    fn _lex(self) -> QueryResult<Vec<Token<'a>>> {
+        self._lex_with(Self::scan_param)
+    }
+    fn _lex_with(mut self, mut on_param: impl FnMut(&mut Self)) -> QueryResult<Vec<Token<'a>>> {
         while self.l.no_error() & !self.l.token_buffer.eof() {
             let b = unsafe {
                 // UNSAFE(@ohsayan): loop invariant
@@ -377,32 +390,7 @@ impl<'a> SecureLexer<'a> {
                         // UNSAFE(@ohsayan): loop invariant
                         self.l.token_buffer.incr_cursor()
                     }
-                    // find target
-                    let ecc_code = SCAN_PARAM.len() - 1;
-                    let target_code = self.param_buffer.rounded_cursor_value();
-                    let target_fn = target_code.min(ecc_code as u8);
-                    // forward if we have target
-                    unsafe {
-                        self.param_buffer
-                            .incr_cursor_by((target_code == target_fn) as _)
-                    }
-                    // check requirements
-                    let has_enough = self
-                        .param_buffer
-                        .has_left(SCAN_PARAM_EXPECT[target_fn as usize] as _);
-                    let final_target =
-                        (has_enough as u8 * target_fn) | (!has_enough as u8 * ecc_code as u8);
-                    // exec
-                    let final_target = final_target as usize;
-                    unsafe {
-                        if final_target >= SCAN_PARAM.len() {
-                            impossible!()
-                        }
-                    }
-                    unsafe {
-                        // UNSAFE(@ohsayan): our computation above ensures that we're meeting the expected target
-                        SCAN_PARAM[final_target](&mut self)
-                    }
+                    on_param(&mut self)
                 }
                 b' ' | b'\t' | b'\n' => self.l.trim_ahead(),
                 sym => self.l.scan_byte(sym),
@@ -413,6 +401,66 @@ impl<'a> SecureLexer<'a> {
             Some(e) => Err(e),
         }
     }
+    /// Scan the next param from the param buffer
+    fn scan_param(&mut self) {
+        // find target
+        let ecc_code = SCAN_PARAM.len() - 1;
+        let target_code = self.param_buffer.rounded_cursor_value();
+        let target_fn = target_code.min(ecc_code as u8);
+        // forward if we have target
+        unsafe {
+            self.param_buffer
+                .incr_cursor_by((target_code == target_fn) as _)
+        }
+        // check requirements
+        let has_enough = self
+            .param_buffer
+            .has_left(SCAN_PARAM_EXPECT[target_fn as usize] as _);
+        let final_target = (has_enough as u8 * target_fn) | (!has_enough as u8 * ecc_code as u8);
+        // exec
+        let final_target = final_target as usize;
+        unsafe {
+            if final_target >= SCAN_PARAM.len() {
+                impossible!()
+            }
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): our computation above ensures that we're meeting the expected target
+            SCAN_PARAM[final_target](self)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+/// The tokens of a prepared query, with a placeholder in place of every param. Binding the params to the placeholders
+/// gives us the same tokens that lexing the query along with its params would have
+pub struct PreparedTokens<'a> {
+    tokens: Vec<Token<'a>>,
+    /// the positions of the placeholders (in order)
+    slots: Vec<usize>,
+}
+
+impl<'a> PreparedTokens<'a> {
+    pub fn tokens(&self) -> &[Token<'a>] {
+        &self.tokens
+    }
+    /// Returns the tokens of the query with the given params bound to its placeholders
+    pub fn bind<'b>(&self, params: &'b [u8]) -> QueryResult<Vec<Token<'b>>>
+    where
+        'a: 'b,
+    {
+        let mut lexer = SecureLexer::new_with_segments(b"", params);
+        lexer.l.tokens = self.tokens.clone();
+        for &slot in self.slots.iter() {
+            lexer.scan_param();
+            if let Some(e) = lexer.l.last_error {
+                return Err(e);
+            }
+            let param = lexer.l.tokens.pop().unwrap();
+            lexer.l.tokens[slot] = param;
+        }
+        Ok(lexer.l.tokens)
+    }
 }
 
 const SCAN_PARAM_EXPECT: [u8; 8] = [0, 1, 2, 2, 2, 2, 2, 0];
@@ -349,6 +349,8 @@ flattened_lut! {
                 Update = 9,
                 Delete = 10,
                 Exists = 11,
+                // misc
+                Explain = 12,
             }
         },
         /// Hi
@@ -376,6 +378,7 @@ flattened_lut! {
                 Sort,
                 Group,
                 Limit,
+                Offset,
                 Asc,
                 Desc,
                 All,
@@ -414,6 +417,7 @@ flattened_lut! {
                 // misc
                 Key,
                 Value,
+                Values,
                 Primary,
                 // temporarily reserved (will probably be removed in the future)
                 Truncate, // TODO: decide what we want to do with this
@@ -432,13 +436,14 @@ impl Keyword {
         }
     }
     fn compute(key: &[u8]) -> Option<Self> {
-        static G: [u8; 69] = [
-            0, 0, 9, 64, 16, 43, 7, 49, 24, 8, 41, 37, 19, 66, 18, 0, 17, 0, 12, 63, 34, 56, 3, 24,
-            55, 14, 0, 67, 7, 0, 39, 60, 56, 0, 51, 23, 31, 19, 30, 12, 10, 58, 20, 39, 32, 0, 6,
-            30, 26, 58, 52, 62, 39, 27, 24, 9, 4, 21, 24, 68, 10, 38, 40, 21, 62, 27, 53, 27, 44,
+        static G: [u8; 71] = [
+            25, 20, 25, 38, 48, 6, 18, 31, 23, 32, 16, 50, 52, 57, 49, 13, 39, 29, 45, 0, 43, 9,
+            24, 18, 51, 68, 28, 0, 8, 37, 0, 14, 62, 11, 44, 64, 0, 0, 49, 70, 41, 6, 45, 12, 42,
+            12, 1, 8, 13, 20, 69, 36, 10, 67, 67, 39, 55, 39, 42, 0, 53, 20, 63, 0, 17, 63, 14, 43,
+            43, 0, 69,
         ];
-        static M1: [u8; 11] = *b"D8N5FwqrxdA";
-        static M2: [u8; 11] = *b"FsIPJv9hsXx";
+        static M1: [u8; 11] = *b"iKTM6rnBtuv";
+        static M2: [u8; 11] = *b"nqvohmPGxr3";
         let h1 = Self::_sum(key, M1) % G.len();
         let h2 = Self::_sum(key, M2) % G.len();
         let h = (G[h1] + G[h2]) as usize % G.len();
@@ -465,4 +470,22 @@ impl KeywordStmt {
     pub const fn is_blocking(&self) -> bool {
         self.value_u8() <= Self::Drop.value_u8()
     }
+    /// Returns true if the statement changes definitions
+    pub const fn is_ddl(&self) -> bool {
+        matches!(self, Self::Create | Self::Alter | Self::Drop)
+    }
+    /// Returns true if the statement reads or changes data
+    pub const fn is_dml(&self) -> bool {
+        matches!(
+            self,
+            Self::Insert | Self::Select | Self::Update | Self::Delete
+        )
+    }
+    /// Returns true if the statement changes data or definitions
+    pub const fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Self::Create | Self::Alter | Self::Drop | Self::Insert | Self::Update | Self::Delete
+        )
+    }
 }
@@ -26,7 +26,7 @@
 
 use crate::engine::ql::{
     ast,
-    dcl::{self, SysctlCommand},
+    dcl::{self, DataFormat, ModelFile, SysctlCommand},
     tests::lex_insecure,
 };
 
@@ -37,6 +37,85 @@ fn report_status_simple() {
     assert_eq!(q, SysctlCommand::ReportStatus)
 }
 
+#[test]
+fn report_memory_simple() {
+    let query = lex_insecure(b"sysctl report memory").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::ReportMemory);
+    let query = lex_insecure(b"sysctl report memory myspace").unwrap();
+    assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+}
+
+#[test]
+fn report_startup_simple() {
+    let query = lex_insecure(b"sysctl report startup").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::ReportStartup);
+    let query = lex_insecure(b"sysctl report startup myspace").unwrap();
+    assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+}
+
+#[test]
+fn report_audit_simple() {
+    let query = lex_insecure(b"sysctl report audit").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::ReportAudit(None));
+    let query =
+        lex_insecure(b"sysctl report audit with { user: 'sayan', kind: 'login', count: 10 }")
+            .unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(
+        q,
+        SysctlCommand::ReportAudit(Some(into_dict!(
+            "user" => lit!("sayan"),
+            "kind" => lit!("login"),
+            "count" => lit!(10u64)
+        )))
+    );
+    for query in [
+        &b"sysctl report audit myspace"[..],
+        b"sysctl report audit with",
+        b"sysctl report audit with { user: 'sayan' } count",
+    ] {
+        let query = lex_insecure(query).unwrap();
+        assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+    }
+}
+
+#[test]
+fn report_queries_simple() {
+    let query = lex_insecure(b"sysctl report queries").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::ReportQueries);
+    let query = lex_insecure(b"sysctl report queries myspace").unwrap();
+    assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+}
+
+#[test]
+fn report_connections_simple() {
+    let query = lex_insecure(b"sysctl report connections").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::ReportConnections);
+    let query = lex_insecure(b"sysctl report connections myspace").unwrap();
+    assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+}
+
+#[test]
+fn kill_simple() {
+    let query = lex_insecure(b"sysctl kill 42").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::Kill(42));
+    for query in [
+        &b"sysctl kill"[..],
+        b"sysctl kill myquery",
+        b"sysctl kill 'myquery'",
+        b"sysctl kill 42 43",
+    ] {
+        let query = lex_insecure(query).unwrap();
+        assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+    }
+}
+
 #[test]
 fn create_user_simple() {
     let query = lex_insecure(b"sysctl create user sayan with { password: 'mypass123' }").unwrap();
@@ -63,6 +142,21 @@ fn alter_user_simple() {
     )
 }
 
+#[test]
+fn quota_user_simple() {
+    let query =
+        lex_insecure(b"sysctl quota user sayan with { queries_per_sec: 100, connections: 4 }")
+            .unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(
+        q,
+        SysctlCommand::QuotaUser(dcl::UserDecl::new(
+            "sayan".into(),
+            into_dict!("queries_per_sec" => lit!(100u64), "connections" => lit!(4u64))
+        ))
+    )
+}
+
 #[test]
 fn delete_user_simple() {
     let query = lex_insecure(b"sysctl drop user monster").unwrap();
@@ -72,3 +166,220 @@ fn delete_user_simple() {
         SysctlCommand::DropUser(dcl::UserDel::new("monster".into()))
     );
 }
+
+#[test]
+fn issue_token_simple() {
+    let query = lex_insecure(b"sysctl issue token").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::IssueToken);
+    let query = lex_insecure(b"sysctl issue token sayan").unwrap();
+    assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+}
+
+#[test]
+fn promote_simple() {
+    let query = lex_insecure(b"sysctl promote").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::Promote);
+    let query = lex_insecure(b"sysctl promote replica").unwrap();
+    assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+}
+
+#[test]
+fn shutdown_simple() {
+    let query = lex_insecure(b"sysctl shutdown").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::Shutdown);
+    let query = lex_insecure(b"sysctl shutdown now").unwrap();
+    assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+}
+
+#[test]
+fn log_level_simple() {
+    let query = lex_insecure(b"sysctl log level 'info,skyd::engine::net=debug'").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(
+        q,
+        SysctlCommand::LogLevel("info,skyd::engine::net=debug".into())
+    );
+    for query in [
+        &b"sysctl log level"[..],
+        b"sysctl log level ''",
+        b"sysctl log level debug",
+        b"sysctl log level 'debug' 'trace'",
+    ] {
+        let query = lex_insecure(query).unwrap();
+        assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+    }
+}
+
+#[test]
+fn backup_simple() {
+    let query = lex_insecure(b"sysctl backup '/var/backups/skytable'").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(q, SysctlCommand::Backup("/var/backups/skytable".into()));
+}
+
+#[test]
+fn backup_bad() {
+    for query in [
+        "sysctl backup",
+        "sysctl backup mybackup",
+        "sysctl backup ''",
+        "sysctl backup 12345",
+        "sysctl backup 'mybackup' 'another'",
+    ] {
+        let query = lex_insecure(query.as_bytes()).unwrap();
+        assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+    }
+}
+
+#[test]
+fn export_model() {
+    for (query, format) in [
+        (
+            "sysctl export model myspace.mymodel to '/tmp/mymodel.json' as json",
+            DataFormat::Json,
+        ),
+        (
+            "sysctl export model myspace.mymodel to '/tmp/mymodel.json' as CSV",
+            DataFormat::Csv,
+        ),
+    ] {
+        let query = lex_insecure(query.as_bytes()).unwrap();
+        let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+        assert_eq!(
+            q,
+            SysctlCommand::Export(ModelFile::new(
+                ("myspace", "mymodel").into(),
+                "/tmp/mymodel.json".into(),
+                format
+            ))
+        );
+    }
+}
+
+#[test]
+fn export_model_bad() {
+    for query in [
+        "sysctl export model",
+        "sysctl export model mymodel to '/tmp/mymodel.json' as json",
+        "sysctl export model myspace.mymodel",
+        "sysctl export model myspace.mymodel to '/tmp/mymodel.json'",
+        "sysctl export model myspace.mymodel to '' as json",
+        "sysctl export model myspace.mymodel to mymodel as json",
+        "sysctl export model myspace.mymodel to '/tmp/mymodel.xml' as xml",
+        "sysctl export model myspace.mymodel to '/tmp/mymodel.json' as json json",
+    ] {
+        let query = lex_insecure(query.as_bytes()).unwrap();
+        assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+    }
+}
+
+#[test]
+fn import_model() {
+    let query = lex_insecure(b"sysctl import model myspace.mymodel from '/tmp/mymodel.csv' as csv")
+        .unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(
+        q,
+        SysctlCommand::Import(ModelFile::new(
+            ("myspace", "mymodel").into(),
+            "/tmp/mymodel.csv".into(),
+            DataFormat::Csv
+        ))
+    );
+}
+
+#[test]
+fn import_model_bad() {
+    for query in [
+        "sysctl import model myspace.mymodel to '/tmp/mymodel.csv' as csv",
+        "sysctl import model myspace.mymodel from '/tmp/mymodel.csv'",
+        "sysctl import model myspace.mymodel from '' as csv",
+        "sysctl import myspace.mymodel from '/tmp/mymodel.csv' as csv",
+    ] {
+        let query = lex_insecure(query.as_bytes()).unwrap();
+        assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+    }
+}
+
+#[test]
+fn subscribe_model() {
+    let query = lex_insecure(b"sysctl subscribe model myspace.users, otherspace.logs").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(
+        q,
+        SysctlCommand::Subscribe(vec![
+            ("myspace", "users").into(),
+            ("otherspace", "logs").into()
+        ])
+    );
+}
+
+#[test]
+fn subscribe_model_bad() {
+    for query in [
+        "sysctl subscribe model",
+        "sysctl subscribe model users",
+        "sysctl subscribe model myspace.users,",
+        "sysctl subscribe model myspace.users otherspace.logs",
+        "sysctl subscribe model myspace.users, myspace.users",
+    ] {
+        let query = lex_insecure(query.as_bytes()).unwrap();
+        assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+    }
+}
+
+#[test]
+fn grant_user_space() {
+    let query = lex_insecure(b"sysctl grant user sayan on myspace").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(
+        q,
+        SysctlCommand::GrantUser(dcl::UserGrant::new("sayan".into(), "myspace".into(), None))
+    );
+}
+
+#[test]
+fn grant_user_model() {
+    let query = lex_insecure(b"sysctl grant user sayan on myspace.mymodel").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(
+        q,
+        SysctlCommand::GrantUser(dcl::UserGrant::new(
+            "sayan".into(),
+            "myspace".into(),
+            Some("mymodel".into())
+        ))
+    );
+}
+
+#[test]
+fn revoke_user_model() {
+    let query = lex_insecure(b"sysctl revoke user sayan on myspace.mymodel").unwrap();
+    let q = ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).unwrap();
+    assert_eq!(
+        q,
+        SysctlCommand::RevokeUser(dcl::UserGrant::new(
+            "sayan".into(),
+            "myspace".into(),
+            Some("mymodel".into())
+        ))
+    );
+}
+
+#[test]
+fn grant_user_bad() {
+    for query in [
+        "sysctl grant user sayan",
+        "sysctl grant user sayan on",
+        "sysctl grant user sayan myspace",
+        "sysctl grant user sayan on myspace.",
+        "sysctl grant user sayan on myspace.mymodel.extra",
+        "sysctl revoke user on myspace",
+    ] {
+        let query = lex_insecure(query.as_bytes()).unwrap();
+        assert!(ast::parse_ast_node_full::<dcl::SysctlCommand>(&query[1..]).is_err());
+    }
+}
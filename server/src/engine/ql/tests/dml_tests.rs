@@ -294,10 +294,13 @@ mod map_syntax {
 mod stmt_insert {
     use {
         super::*,
-        crate::engine::ql::{
-            ast::parse_ast_node_full,
-            dml::{self, ins::InsertStatement},
-            lex::Ident,
+        crate::engine::{
+            data::{cell::Datacell, dict::DictCell},
+            ql::{
+                ast::parse_ast_node_full,
+                dml::{self, ins::InsertStatement},
+                lex::Ident,
+            },
         },
     };
 
@@ -491,6 +494,164 @@ mod stmt_insert {
         );
         assert_eq!(ret, expected);
     }
+    #[test]
+    fn insert_tuple_fnsub_now() {
+        let tok = lex_insecure(br#"insert into jotsy.app("sayan", @now())"#).unwrap();
+        let ret = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        let expected = InsertStatement::new(
+            ("jotsy", "app").into(),
+            vec![
+                Datacell::from("sayan"),
+                Datacell::new_timestamp(dml::ins::T_TIMESEC * 1_000_000),
+            ]
+            .into(),
+        );
+        assert_eq!(ret, expected);
+    }
+    #[test]
+    fn insert_multi_tuple() {
+        let tok = lex_insecure(br#"insert into jotsy.app ("sayan", 1), ("elon", 2)"#).unwrap();
+        let ret = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        let expected = InsertStatement::new_multi(
+            ("jotsy", "app").into(),
+            vec![
+                into_array_nullable!["sayan", 1].to_vec().into(),
+                into_array_nullable!["elon", 2].to_vec().into(),
+            ],
+        );
+        assert_eq!(ret, expected);
+    }
+    #[test]
+    fn insert_multi_values() {
+        let tok = lex_insecure(
+            br#"insert into jotsy.app (username, followers) values ("sayan", 1), ("elon", null)"#,
+        )
+        .unwrap();
+        let ret = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        let expected = InsertStatement::new_multi(
+            ("jotsy", "app").into(),
+            vec![
+                dict_nullable! {
+                    Ident::from("username") => "sayan",
+                    "followers" => 1,
+                }
+                .into(),
+                dict_nullable! {
+                    Ident::from("username") => "elon",
+                    "followers" => Null,
+                }
+                .into(),
+            ],
+        );
+        assert_eq!(ret, expected);
+    }
+    #[test]
+    fn insert_multi_bad() {
+        for query in [
+            &br#"insert into jotsy.app ("sayan", 1),"#[..],
+            br#"insert into jotsy.app (username, followers) ("sayan", 1)"#,
+            br#"insert into jotsy.app (username, followers) values ("sayan")"#,
+            br#"insert into jotsy.app (username, username) values ("sayan", "elon")"#,
+            br#"insert into jotsy.app (username) values ("sayan"),"#,
+            br#"insert into jotsy.app (username) values { username: "sayan" }"#,
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full::<InsertStatement>(&tok[1..]).is_err());
+        }
+    }
+    #[test]
+    fn insert_returning() {
+        let tok =
+            lex_insecure(br#"insert into jotsy.app ("sayan", 1) returning id, followers"#).unwrap();
+        let ret = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        let expected = InsertStatement::new(
+            ("jotsy", "app").into(),
+            into_array_nullable!["sayan", 1].to_vec().into(),
+        )
+        .with_returning(dml::Returning::new(vec![
+            Ident::from("id"),
+            Ident::from("followers"),
+        ]));
+        assert_eq!(ret, expected);
+        let tok = lex_insecure(
+            br#"insert into jotsy.app (username) values ("sayan"), ("elon") RETURNING *"#,
+        )
+        .unwrap();
+        let ret = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        let expected = InsertStatement::new_multi(
+            ("jotsy", "app").into(),
+            vec![
+                dict_nullable! { Ident::from("username") => "sayan" }.into(),
+                dict_nullable! { Ident::from("username") => "elon" }.into(),
+            ],
+        )
+        .with_returning(dml::Returning::new_wildcard());
+        assert_eq!(ret, expected);
+    }
+    #[test]
+    fn insert_returning_bad() {
+        for query in [
+            &br#"insert into jotsy.app ("sayan", 1) returning"#[..],
+            br#"insert into jotsy.app ("sayan", 1) returning id,"#,
+            br#"insert into jotsy.app ("sayan", 1) returning id, id"#,
+            br#"insert into jotsy.app ("sayan", 1) returning "id""#,
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full::<InsertStatement>(&tok[1..]).is_err());
+        }
+    }
+    #[test]
+    fn insert_dict() {
+        let tok = lex_insecure(
+            br#"insert into jotsy.app ("sayan", { name: "Sayan", tags: ["a", "b"], address: { zip: 1 } }, [{ at: 1 }, {}])"#,
+        )
+        .unwrap();
+        let ret = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        let expected = InsertStatement::new(
+            ("jotsy", "app").into(),
+            vec![
+                Datacell::from("sayan"),
+                Datacell::new_dict(into_dict!(
+                    "name" => Datacell::from("Sayan"),
+                    "tags" => Datacell::new_list(vec!["a".into(), "b".into()]),
+                    "address" => Datacell::new_dict(into_dict!("zip" => Datacell::new_uint_default(1)))
+                )),
+                Datacell::new_list(vec![
+                    Datacell::new_dict(into_dict!("at" => Datacell::new_uint_default(1))),
+                    Datacell::new_dict(DictCell::new()),
+                ]),
+            ]
+            .into(),
+        );
+        assert_eq!(ret, expected);
+        let tok =
+            lex_insecure(br#"insert into jotsy.app { username: "sayan", profile: {} }"#).unwrap();
+        let ret = parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap();
+        let expected = InsertStatement::new(
+            ("jotsy", "app").into(),
+            dict_nullable! {
+                Ident::from("username") => "sayan",
+                Ident::from("profile") => Datacell::new_dict(DictCell::new()),
+            }
+            .into(),
+        );
+        assert_eq!(ret, expected);
+    }
+    #[test]
+    fn insert_dict_bad() {
+        for query in [
+            &br#"insert into jotsy.app ("sayan", { a: 1, a: 2 })"#[..],
+            br#"insert into jotsy.app ("sayan", { a: null })"#,
+            br#"insert into jotsy.app ("sayan", { "a": 1 })"#,
+            br#"insert into jotsy.app ("sayan", { a 1 })"#,
+            br#"insert into jotsy.app ("sayan", { a: 1, })"#,
+            br#"insert into jotsy.app ("sayan", { a: 1 )"#,
+            br#"insert into jotsy.app ("sayan", { a: 1"#,
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full::<InsertStatement>(&tok[1..]).is_err());
+        }
+    }
 }
 
 mod stmt_select {
@@ -500,7 +661,13 @@ mod stmt_select {
             data::lit::Lit,
             ql::{
                 ast::{parse_ast_node_full, parse_ast_node_full_with_space},
-                dml::{sel::SelectStatement, RelationalExpr},
+                dml::{
+                    sel::{
+                        OrderBy, SelectField, SelectLookup, SelectPathSegment, SelectStatement,
+                        SelectWindow,
+                    },
+                    RelationalExpr,
+                },
                 lex::Ident,
             },
         },
@@ -589,14 +756,258 @@ mod stmt_select {
         );
         assert_eq!(r, e);
     }
+    #[test]
+    fn select_list_elements() {
+        let tok = lex_insecure(
+            br#"
+                select username, notes[3], matrix[0][1] from users where username = "sayan"
+            "#,
+        )
+        .unwrap();
+        let r = parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").unwrap();
+        let e = SelectStatement::new(
+            ("apps", "users").into(),
+            vec![
+                Ident::from("username").into(),
+                SelectField::new(Ident::from("notes"), vec![SelectPathSegment::Index(3)]),
+                SelectField::new(
+                    Ident::from("matrix"),
+                    vec![SelectPathSegment::Index(0), SelectPathSegment::Index(1)],
+                ),
+            ],
+            false,
+            dict! {
+                Ident::from("username") => RelationalExpr::new(
+                    Ident::from("username"), Lit::new_str("sayan"), RelationalExpr::OP_EQ
+                ),
+            },
+        );
+        assert_eq!(r, e);
+    }
+    #[test]
+    fn select_lookup() {
+        let tok = lex_insecure(
+            br#"
+                select title, users[author], users[editor].name from posts where id = 1
+            "#,
+        )
+        .unwrap();
+        let r = parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").unwrap();
+        let e = SelectStatement::new(
+            ("apps", "posts").into(),
+            vec![
+                Ident::from("title").into(),
+                SelectField::new_lookup(
+                    Ident::from("author"),
+                    SelectLookup::new(Ident::from("users"), None),
+                ),
+                SelectField::new_lookup(
+                    Ident::from("editor"),
+                    SelectLookup::new(Ident::from("users"), Some(Ident::from("name"))),
+                ),
+            ],
+            false,
+            dict! {
+                Ident::from("id") => RelationalExpr::new(
+                    Ident::from("id"), Lit::new_uint(1), RelationalExpr::OP_EQ
+                ),
+            },
+        );
+        assert_eq!(r, e);
+        for query in [
+            &b"select users[author from posts where id = 1"[..],
+            b"select users[author]. from posts where id = 1",
+            b"select users[author].1 from posts where id = 1",
+            b"select users[author][0] from posts where id = 1",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").is_err());
+        }
+    }
+    #[test]
+    fn select_dict_keys() {
+        let tok = lex_insecure(
+            br#"
+                select profile.name, profile.address.city, logins[0].at from users where username = "sayan"
+            "#,
+        )
+        .unwrap();
+        let r = parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").unwrap();
+        let e = SelectStatement::new(
+            ("apps", "users").into(),
+            vec![
+                SelectField::new(
+                    Ident::from("profile"),
+                    vec![SelectPathSegment::Key(Ident::from("name"))],
+                ),
+                SelectField::new(
+                    Ident::from("profile"),
+                    vec![
+                        SelectPathSegment::Key(Ident::from("address")),
+                        SelectPathSegment::Key(Ident::from("city")),
+                    ],
+                ),
+                SelectField::new(
+                    Ident::from("logins"),
+                    vec![
+                        SelectPathSegment::Index(0),
+                        SelectPathSegment::Key(Ident::from("at")),
+                    ],
+                ),
+            ],
+            false,
+            dict! {
+                Ident::from("username") => RelationalExpr::new(
+                    Ident::from("username"), Lit::new_str("sayan"), RelationalExpr::OP_EQ
+                ),
+            },
+        );
+        assert_eq!(r, e);
+    }
+    #[test]
+    fn select_list_elements_bad() {
+        for query in [
+            &br#"select notes[] from users where username = "sayan""#[..],
+            br#"select notes["1"] from users where username = "sayan""#,
+            br#"select notes[-1] from users where username = "sayan""#,
+            br#"select notes[1 from users where username = "sayan""#,
+            br#"select profile. from users where username = "sayan""#,
+            br#"select profile.1 from users where username = "sayan""#,
+            br#"select profile."name" from users where username = "sayan""#,
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").is_err());
+        }
+    }
+    #[test]
+    fn select_range_window() {
+        let tok = lex_insecure(
+            br#"
+                select * from users where id > 10 order by username desc limit 5 offset 10
+            "#,
+        )
+        .unwrap();
+        let r = parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").unwrap();
+        let e = SelectStatement::new_test(
+            ("apps", "users").into(),
+            [].to_vec(),
+            true,
+            dict! {
+                Ident::from("id") => RelationalExpr::new(
+                    Ident::from("id"), Lit::new_uint(10), RelationalExpr::OP_GT
+                ),
+            },
+        )
+        .with_window(SelectWindow {
+            order: Some(OrderBy::new(Ident::from("username"), true)),
+            limit: Some(5),
+            offset: 10,
+        });
+        assert_eq!(r, e);
+    }
+    #[test]
+    fn select_range_window_partial() {
+        let tok = lex_insecure(b"select * from users where id > 10 offset 2").unwrap();
+        let r = parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").unwrap();
+        let e = SelectStatement::new_test(
+            ("apps", "users").into(),
+            [].to_vec(),
+            true,
+            dict! {
+                Ident::from("id") => RelationalExpr::new(
+                    Ident::from("id"), Lit::new_uint(10), RelationalExpr::OP_GT
+                ),
+            },
+        )
+        .with_window(SelectWindow {
+            order: None,
+            limit: None,
+            offset: 2,
+        });
+        assert_eq!(r, e);
+    }
+    #[test]
+    fn select_with_version() {
+        let tok =
+            lex_insecure(b"select * from users where username = 'sayan' with version").unwrap();
+        let r = parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").unwrap();
+        let e = SelectStatement::new_test(
+            ("apps", "users").into(),
+            [].to_vec(),
+            true,
+            dict! {
+                Ident::from("username") => RelationalExpr::new(
+                    Ident::from("username"), Lit::new_str("sayan"), RelationalExpr::OP_EQ
+                ),
+            },
+        )
+        .with_version();
+        assert_eq!(r, e);
+        let tok = lex_insecure(b"select * from users where username = 'sayan' with").unwrap();
+        assert!(parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").is_err());
+    }
+    #[test]
+    fn select_for_update() {
+        let tok =
+            lex_insecure(b"select * from users where username = 'sayan' with version for update")
+                .unwrap();
+        let r = parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").unwrap();
+        let e = SelectStatement::new_test(
+            ("apps", "users").into(),
+            [].to_vec(),
+            true,
+            dict! {
+                Ident::from("username") => RelationalExpr::new(
+                    Ident::from("username"), Lit::new_str("sayan"), RelationalExpr::OP_EQ
+                ),
+            },
+        )
+        .with_version()
+        .with_for_update();
+        assert_eq!(r, e);
+    }
+    #[test]
+    fn select_for_update_bad() {
+        for query in [
+            &b"select * from users where username = 'sayan' for"[..],
+            b"select * from users where username = 'sayan' for delete",
+            // intents are only taken on single rows
+            b"select * from users where id > 10 for update",
+            b"select * from users where id in (1, 2) for update",
+            b"select * from users where username like 'sa%' for update",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").is_err());
+        }
+    }
+    #[test]
+    fn select_window_bad() {
+        for query in [
+            // point selects return one row at most
+            &br#"select * from users where username = "sayan" limit 1"#[..],
+            br#"select * from users where username = "sayan" order by username"#,
+            br#"select * from users where id > 10 order username"#,
+            br#"select * from users where id > 10 order by"#,
+            br#"select * from users where id > 10 limit"#,
+            br#"select * from users where id > 10 limit -1"#,
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full_with_space::<SelectStatement>(&tok[1..], "apps").is_err());
+        }
+    }
 }
+
 mod expression_tests {
     use {
         super::*,
         crate::engine::{
             core::query_meta::AssignmentOperator,
             data::lit::Lit,
-            ql::{ast::parse_ast_node_full, dml::upd::AssignmentExpression, lex::Ident},
+            ql::{
+                ast::parse_ast_node_full,
+                dml::{self, upd::AssignmentExpression},
+                lex::Ident,
+            },
         },
     };
     #[test]
@@ -613,6 +1024,19 @@ mod expression_tests {
         );
     }
     #[test]
+    fn expr_assign_fnsub() {
+        let src = lex_insecure(b"last_seen = @now()").unwrap();
+        let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
+        assert_eq!(
+            r,
+            AssignmentExpression::new(
+                Ident::from("last_seen"),
+                Lit::new_timestamp(dml::ins::T_TIMESEC * 1_000_000),
+                AssignmentOperator::Assign
+            )
+        );
+    }
+    #[test]
     fn expr_add_assign() {
         let src = lex_insecure(b"followers += 100").unwrap();
         let r = parse_ast_node_full::<AssignmentExpression>(&src).unwrap();
@@ -675,13 +1099,22 @@ mod update_statement {
                 ast::{parse_ast_node_full, parse_ast_node_full_with_space},
                 dml::{
                     upd::{AssignmentExpression, UpdateStatement},
-                    RelationalExpr, WhereClause,
+                    RelationalExpr, Returning, WhereClause,
                 },
                 lex::Ident,
             },
         },
     };
     #[test]
+    fn update_unknown_fnsub() {
+        // only functions that produce a literal can be used
+        let tok = lex_insecure(
+            br#"update jotsy.app set last_seen = @uuidstr() where username = "sayan""#,
+        )
+        .unwrap();
+        assert!(parse_ast_node_full::<UpdateStatement>(&tok[1..]).is_err());
+    }
+    #[test]
     fn update_mini() {
         let tok = lex_insecure(
             br#"
@@ -746,6 +1179,116 @@ mod update_statement {
         );
         assert_eq!(r, e);
     }
+    #[test]
+    fn update_if_version() {
+        let tok = lex_insecure(
+            br#"
+                update app SET email = "sayan@example.com" where username = "sayan" if version = 2
+            "#,
+        )
+        .unwrap();
+        let r = parse_ast_node_full_with_space::<UpdateStatement>(&tok[1..], "apps").unwrap();
+        let e = UpdateStatement::new(
+            ("apps", "app").into(),
+            vec![AssignmentExpression::new(
+                Ident::from("email"),
+                Lit::new_str("sayan@example.com"),
+                AssignmentOperator::Assign,
+            )],
+            WhereClause::new(dict! {
+                Ident::from("username") => RelationalExpr::new(
+                    Ident::from("username"),
+                    Lit::new_str("sayan"),
+                    RelationalExpr::OP_EQ
+                )
+            }),
+        )
+        .with_version(2);
+        assert_eq!(r, e);
+    }
+    #[test]
+    fn update_list_elements() {
+        let tok = lex_insecure(
+            br#"
+                update app SET tags[1] = "a", tags[0] += "b", tags -= -1 where username = "sayan"
+            "#,
+        )
+        .unwrap();
+        let r = parse_ast_node_full_with_space::<UpdateStatement>(&tok[1..], "apps").unwrap();
+        let e = UpdateStatement::new(
+            ("apps", "app").into(),
+            vec![
+                AssignmentExpression::new_at(
+                    Ident::from("tags"),
+                    1,
+                    Lit::new_str("a"),
+                    AssignmentOperator::Assign,
+                ),
+                AssignmentExpression::new_at(
+                    Ident::from("tags"),
+                    0,
+                    Lit::new_str("b"),
+                    AssignmentOperator::AddAssign,
+                ),
+                AssignmentExpression::new(
+                    Ident::from("tags"),
+                    Lit::new_sint(-1),
+                    AssignmentOperator::SubAssign,
+                ),
+            ],
+            WhereClause::new(dict! {
+                Ident::from("username") => RelationalExpr::new(
+                    Ident::from("username"),
+                    Lit::new_str("sayan"),
+                    RelationalExpr::OP_EQ
+                )
+            }),
+        );
+        assert_eq!(r, e);
+    }
+    #[test]
+    fn update_list_elements_bad() {
+        for query in [
+            "update app set tags[0][1] = 'a' where username = 'sayan'",
+            "update app set profile.name = 'a' where username = 'sayan'",
+            "update app set tags[] = 'a' where username = 'sayan'",
+            "update app set tags['a'] = 'a' where username = 'sayan'",
+            "update app set tags[0 = 'a' where username = 'sayan'",
+            "update app set tags[0]",
+        ] {
+            let tok = lex_insecure(query.as_bytes()).unwrap();
+            assert!(
+                parse_ast_node_full_with_space::<UpdateStatement>(&tok[1..], "apps").is_err(),
+                "{query}"
+            );
+        }
+    }
+    #[test]
+    fn update_returning() {
+        let tok = lex_insecure(
+            b"update app set followers += 1 where username = 'sayan' if version = 2 returning *",
+        )
+        .unwrap();
+        let r = parse_ast_node_full_with_space::<UpdateStatement>(&tok[1..], "apps").unwrap();
+        let e = UpdateStatement::new(
+            ("apps", "app").into(),
+            vec![AssignmentExpression::new(
+                Ident::from("followers"),
+                Lit::new_uint(1),
+                AssignmentOperator::AddAssign,
+            )],
+            WhereClause::new(dict! {
+                Ident::from("username") => RelationalExpr::new(
+                    Ident::from("username"),
+                    Lit::new_str("sayan"),
+                    RelationalExpr::OP_EQ
+                )
+            }),
+        )
+        .with_version(2)
+        .with_returning(Returning::new_wildcard());
+        assert_eq!(r, e);
+    }
 }
 mod delete_stmt {
     use {
@@ -754,7 +1297,7 @@ mod delete_stmt {
             data::lit::Lit,
             ql::{
                 ast::{parse_ast_node_full, parse_ast_node_full_with_space},
-                dml::{del::DeleteStatement, RelationalExpr},
+                dml::{del::DeleteStatement, RelationalExpr, Returning},
                 lex::Ident,
             },
         },
@@ -806,13 +1349,103 @@ mod delete_stmt {
             e
         );
     }
+    #[test]
+    fn delete_if_version() {
+        let tok =
+            lex_insecure(b"delete from users where username = 'sayan' if VERSION = 10").unwrap();
+        let e = DeleteStatement::new_test(
+            ("apps", "users").into(),
+            dict! {
+                Ident::from("username") => RelationalExpr::new(
+                    Ident::from("username"),
+                    Lit::new_str("sayan"),
+                    RelationalExpr::OP_EQ
+                )
+            },
+        )
+        .with_version(10);
+        assert_eq!(
+            parse_ast_node_full_with_space::<DeleteStatement>(&tok[1..], "apps").unwrap(),
+            e
+        );
+    }
+    #[test]
+    fn delete_if_version_bad() {
+        for query in [
+            &b"delete from users where username = 'sayan' if"[..],
+            b"delete from users where username = 'sayan' if version",
+            b"delete from users where username = 'sayan' if version = 'ten'",
+            b"delete from users where username = 'sayan' if version > 10",
+            b"delete from users where username = 'sayan' if revision = 10",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full_with_space::<DeleteStatement>(&tok[1..], "apps").is_err());
+        }
+    }
+    #[test]
+    fn delete_limit() {
+        let tok = lex_insecure(b"delete from users where followers <= 10 limit 100").unwrap();
+        let e = DeleteStatement::new_test(
+            ("apps", "users").into(),
+            dict! {
+                Ident::from("followers") => RelationalExpr::new(
+                    Ident::from("followers"),
+                    Lit::new_uint(10),
+                    RelationalExpr::OP_LE
+                )
+            },
+        )
+        .with_limit(100);
+        assert_eq!(
+            parse_ast_node_full_with_space::<DeleteStatement>(&tok[1..], "apps").unwrap(),
+            e
+        );
+    }
+    #[test]
+    fn delete_returning() {
+        let tok =
+            lex_insecure(b"delete from users where followers <= 10 limit 100 returning username")
+                .unwrap();
+        let e = DeleteStatement::new_test(
+            ("apps", "users").into(),
+            dict! {
+                Ident::from("followers") => RelationalExpr::new(
+                    Ident::from("followers"),
+                    Lit::new_uint(10),
+                    RelationalExpr::OP_LE
+                )
+            },
+        )
+        .with_limit(100)
+        .with_returning(Returning::new(vec![Ident::from("username")]));
+        assert_eq!(
+            parse_ast_node_full_with_space::<DeleteStatement>(&tok[1..], "apps").unwrap(),
+            e
+        );
+    }
+    #[test]
+    fn delete_limit_bad() {
+        for query in [
+            &b"delete from users where followers <= 10 limit"[..],
+            b"delete from users where followers <= 10 limit 'ten'",
+            b"delete from users where followers <= 10 limit -1",
+            b"delete from users where username = 'sayan' limit 1 if version = 10",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full_with_space::<DeleteStatement>(&tok[1..], "apps").is_err());
+        }
+    }
 }
 mod relational_expr {
     use {
         super::*,
         crate::engine::{
             data::lit::Lit,
-            ql::{ast::parse_ast_node_full, dml::RelationalExpr, lex::Ident},
+            ql::{
+                ast::parse_ast_node_full,
+                dml::{self, RelationalExpr},
+                lex::Ident,
+            },
         },
     };
 
@@ -825,7 +1458,26 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_EQ
+                opc: RelationalExpr::OP_EQ,
+                upper: None,
+                center: None,
+                values: vec![]
+            }
+        );
+    }
+    #[test]
+    fn expr_lt_fnsub() {
+        let expr = lex_insecure(b"expires_at < @now()").unwrap();
+        let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+        assert_eq!(
+            r,
+            RelationalExpr {
+                rhs: Lit::new_timestamp(dml::ins::T_TIMESEC * 1_000_000),
+                lhs: Ident::from("expires_at"),
+                opc: RelationalExpr::OP_LT,
+                upper: None,
+                center: None,
+                values: vec![]
             }
         );
     }
@@ -838,7 +1490,10 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_NE
+                opc: RelationalExpr::OP_NE,
+                upper: None,
+                center: None,
+                values: vec![]
             }
         );
     }
@@ -851,7 +1506,10 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_GT
+                opc: RelationalExpr::OP_GT,
+                upper: None,
+                center: None,
+                values: vec![]
             }
         );
     }
@@ -864,7 +1522,10 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_GE
+                opc: RelationalExpr::OP_GE,
+                upper: None,
+                center: None,
+                values: vec![]
             }
         );
     }
@@ -877,7 +1538,10 @@ mod relational_expr {
             RelationalExpr {
                 rhs: Lit::new_uint(10),
                 lhs: Ident::from("primary_key"),
-                opc: RelationalExpr::OP_LT
+                opc: RelationalExpr::OP_LT,
+                upper: None,
+                center: None,
+                values: vec![]
             }
         );
     }
@@ -894,6 +1558,76 @@ mod relational_expr {
             )
         );
     }
+    #[test]
+    fn expr_contains() {
+        let expr = lex_insecure(b"body contains 'disk full'").unwrap();
+        let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+        assert!(r.is_text_search());
+        assert_eq!(
+            r,
+            RelationalExpr::new(
+                Ident::from("body"),
+                Lit::new_str("disk full"),
+                RelationalExpr::OP_CONTAINS
+            )
+        );
+    }
+    #[test]
+    fn expr_like() {
+        let expr = lex_insecure(b"username LIKE 'sa%'").unwrap();
+        let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+        assert!(r.is_pattern());
+        assert_eq!(
+            r,
+            RelationalExpr::new(
+                Ident::from("username"),
+                Lit::new_str("sa%"),
+                RelationalExpr::OP_LIKE
+            )
+        );
+    }
+    #[test]
+    fn expr_in() {
+        let expr = lex_insecure(b"username IN ('sayan', 'elana', @now())").unwrap();
+        let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+        assert!(r.is_in());
+        assert_eq!(
+            r.values(),
+            [
+                Lit::new_str("sayan"),
+                Lit::new_str("elana"),
+                Lit::new_timestamp(dml::ins::T_TIMESEC * 1_000_000)
+            ]
+        );
+        assert_eq!(r.rhs(), Lit::new_str("sayan"));
+        for bad in [
+            &b"username in ()"[..],
+            b"username in ('sayan',)",
+            b"username in ('sayan'",
+            b"username in 'sayan'",
+            b"username in ('sayan' 'elana')",
+        ] {
+            let expr = lex_insecure(bad).unwrap();
+            assert!(parse_ast_node_full::<RelationalExpr>(&expr).is_err());
+        }
+    }
+    #[test]
+    fn expr_within() {
+        let expr = lex_insecure(b"location WITHIN (48, -2, 1000)").unwrap();
+        let r = parse_ast_node_full::<RelationalExpr>(&expr).unwrap();
+        assert!(r.is_geo_search());
+        assert_eq!(r.circle(), Some(((48.0, -2.0), 1000.0)));
+        for bad in [
+            "location within (48, -2)",
+            "location within (48, -2, 1000",
+            "location within 48, -2, 1000",
+            "location within ('48', -2, 1000)",
+            "location within (48, -2, 1000, 1)",
+        ] {
+            let expr = lex_insecure(bad.as_bytes()).unwrap();
+            assert!(parse_ast_node_full::<RelationalExpr>(&expr).is_err());
+        }
+    }
 }
 mod where_clause {
     use {
@@ -956,6 +1690,51 @@ mod where_clause {
         .unwrap();
         assert!(parse_ast_node_full::<WhereClause>(&tok).is_err());
     }
+    #[test]
+    fn where_range() {
+        let tok = lex_insecure(
+            br#"
+                userid > 100 and userid <= 200
+            "#,
+        )
+        .unwrap();
+        let expected = WhereClause::new(dict! {
+            Ident::from("userid") => RelationalExpr::new_range(
+                Ident::from("userid"),
+                (RelationalExpr::OP_GT, Lit::new_uint(100)),
+                (RelationalExpr::OP_LE, Lit::new_uint(200)),
+            )
+        });
+        assert_eq!(expected, parse_ast_node_full::<WhereClause>(&tok).unwrap());
+    }
+    #[test]
+    fn where_range_reversed() {
+        let tok = lex_insecure(
+            br#"
+                userid < 200 and userid >= 100
+            "#,
+        )
+        .unwrap();
+        let expected = WhereClause::new(dict! {
+            Ident::from("userid") => RelationalExpr::new_range(
+                Ident::from("userid"),
+                (RelationalExpr::OP_GE, Lit::new_uint(100)),
+                (RelationalExpr::OP_LT, Lit::new_uint(200)),
+            )
+        });
+        assert_eq!(expected, parse_ast_node_full::<WhereClause>(&tok).unwrap());
+    }
+    #[test]
+    fn where_range_bad() {
+        for query in [
+            &b"userid > 100 and userid > 200"[..],
+            b"userid < 100 and userid <= 200",
+            b"userid > 100 and userid < 200 and userid < 300",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(parse_ast_node_full::<WhereClause>(&tok).is_err());
+        }
+    }
 }
 
 mod select_all {
@@ -963,7 +1742,11 @@ mod select_all {
         super::lex_insecure,
         crate::engine::{
             error::QueryError,
-            ql::{ast::parse_ast_node_full_with_space, dml::sel::SelectAllStatement},
+            ql::{
+                ast::parse_ast_node_full_with_space,
+                dml::sel::{OrderBy, SelectAllStatement},
+                lex::Ident,
+            },
         },
     };
 
@@ -990,6 +1773,31 @@ mod select_all {
         );
     }
 
+    #[test]
+    fn select_all_window() {
+        let tok =
+            lex_insecure(b"select all * from mymodel order by username asc limit 10 offset 20")
+                .unwrap();
+        let mut e = SelectAllStatement::test_new(("myspace", "mymodel").into(), vec![], true, 10);
+        e.order = Some(OrderBy::new(Ident::from("username"), false));
+        e.offset = 20;
+        assert_eq!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").unwrap(),
+            e
+        );
+    }
+
+    #[test]
+    fn select_all_with_version() {
+        let tok = lex_insecure(b"select all * from mymodel limit 10 with version").unwrap();
+        let mut e = SelectAllStatement::test_new(("myspace", "mymodel").into(), vec![], true, 10);
+        e.version = true;
+        assert_eq!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").unwrap(),
+            e
+        );
+    }
+
     #[test]
     fn select_all_missing_limit() {
         let tok = lex_insecure(b"select all * from mymodel").unwrap();
@@ -1002,5 +1810,168 @@ mod select_all {
             parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").unwrap_err(),
             QueryError::QLUnexpectedEndOfStatement
         );
+        let tok = lex_insecure(b"select all * from mymodel order by username").unwrap();
+        assert!(
+            parse_ast_node_full_with_space::<SelectAllStatement>(&tok[2..], "myspace").is_err()
+        );
+    }
+}
+
+mod aggregate {
+    use {
+        super::lex_insecure,
+        crate::engine::{
+            data::lit::Lit,
+            ql::{
+                ast::parse_ast_node_full_with_space,
+                dml::{
+                    agg::{Aggregate, AggregateFn, AggregateStatement},
+                    RelationalExpr,
+                },
+                lex::Ident,
+            },
+        },
+    };
+
+    #[test]
+    fn count_all() {
+        let tok = lex_insecure(b"select count(*) from mymodel").unwrap();
+        assert!(AggregateStatement::is_aggregate(&tok[1..]));
+        assert_eq!(
+            parse_ast_node_full_with_space::<AggregateStatement>(&tok[1..], "myspace").unwrap(),
+            AggregateStatement::new(
+                ("myspace", "mymodel").into(),
+                vec![Aggregate::new(AggregateFn::Count, None)],
+                dict! {}
+            )
+        );
+    }
+
+    #[test]
+    fn multiple_with_range() {
+        let tok = lex_insecure(
+            b"select COUNT(*), min(age), max(age), sum(age), avg(age) from mymodel where id > 10",
+        )
+        .unwrap();
+        assert_eq!(
+            parse_ast_node_full_with_space::<AggregateStatement>(&tok[1..], "myspace").unwrap(),
+            AggregateStatement::new(
+                ("myspace", "mymodel").into(),
+                vec![
+                    Aggregate::new(AggregateFn::Count, None),
+                    Aggregate::new(AggregateFn::Min, Some(Ident::from("age"))),
+                    Aggregate::new(AggregateFn::Max, Some(Ident::from("age"))),
+                    Aggregate::new(AggregateFn::Sum, Some(Ident::from("age"))),
+                    Aggregate::new(AggregateFn::Avg, Some(Ident::from("age"))),
+                ],
+                dict! {
+                    Ident::from("id") => RelationalExpr::new(
+                        Ident::from("id"), Lit::new_uint(10), RelationalExpr::OP_GT
+                    ),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn not_aggregate() {
+        let tok = lex_insecure(b"select count from mymodel where id = 1").unwrap();
+        assert!(!AggregateStatement::is_aggregate(&tok[1..]));
+    }
+
+    #[test]
+    fn bad_aggregates() {
+        for query in [
+            &b"select median(age) from mymodel"[..],
+            b"select sum(*) from mymodel",
+            b"select count() from mymodel",
+            b"select count(*, age) from mymodel",
+            b"select count(* from mymodel",
+            b"select count(*) mymodel",
+            b"select count(*), from mymodel",
+            b"select count(*), age from mymodel",
+            b"select count(*) from mymodel where",
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert!(
+                parse_ast_node_full_with_space::<AggregateStatement>(&tok[1..], "myspace").is_err()
+            );
+        }
+    }
+}
+
+mod explain {
+    use {
+        super::lex_insecure,
+        crate::engine::{
+            error::QueryError,
+            ql::{
+                ast::parse_ast_node_full_with_space,
+                dml::{explain::ExplainStatement, sel::SelectAllStatement},
+            },
+        },
+    };
+
+    #[test]
+    fn explain_select_all() {
+        let tok = lex_insecure(b"explain select all * from mymodel limit 10").unwrap();
+        assert_eq!(
+            parse_ast_node_full_with_space::<ExplainStatement>(&tok[1..], "myspace").unwrap(),
+            ExplainStatement::SelectAll(SelectAllStatement::test_new(
+                ("myspace", "mymodel").into(),
+                vec![],
+                true,
+                10
+            ))
+        );
+    }
+
+    #[test]
+    fn explain_dml() {
+        for (query, f) in [
+            (
+                &b"explain insert into mymodel('sayan', 'pass123')"[..],
+                (|e| matches!(e, ExplainStatement::Insert(_))) as fn(&ExplainStatement) -> bool,
+            ),
+            (
+                b"explain select * from mymodel where username = 'sayan'",
+                |e| matches!(e, ExplainStatement::Select(_)),
+            ),
+            (
+                b"explain update mymodel set pass = 'x' where username = 'sayan'",
+                |e| matches!(e, ExplainStatement::Update(_)),
+            ),
+            (
+                b"explain delete from mymodel where username = 'sayan'",
+                |e| matches!(e, ExplainStatement::Delete(_)),
+            ),
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            let stmt = parse_ast_node_full_with_space::<ExplainStatement>(&tok[1..], "myspace");
+            assert!(f(&stmt.unwrap()));
+        }
+    }
+
+    #[test]
+    fn explain_bad() {
+        for (query, error) in [
+            (&b"explain"[..], QueryError::QLUnexpectedEndOfStatement),
+            (
+                b"explain create model mymodel(username: string)",
+                QueryError::QLUnknownStatement,
+            ),
+            (
+                b"explain explain delete from mymodel where username = 'sayan'",
+                QueryError::QLUnknownStatement,
+            ),
+            (b"explain mymodel", QueryError::QLExpectedStatement),
+        ] {
+            let tok = lex_insecure(query).unwrap();
+            assert_eq!(
+                parse_ast_node_full_with_space::<ExplainStatement>(&tok[1..], "myspace")
+                    .unwrap_err(),
+                error
+            );
+        }
     }
 }
@@ -26,7 +26,7 @@
 
 use {
     super::{
-        super::lex::{Ident, Token},
+        super::lex::{Ident, SecureLexer, Token},
         lex_insecure, lex_secure,
     },
     crate::engine::{data::lit::Lit, error::QueryError},
@@ -319,3 +319,28 @@ fn safe_params_shuffled() {
         )
     }
 }
+
+#[test]
+fn prepared_bind() {
+    let query = b"select * from myspace.mymodel where username = ? and age = ?";
+    let prepared = SecureLexer::lex_prepared(query).unwrap();
+    for params in [
+        [SFQ_STRING, SFQ_UINT].concat(),
+        [SFQ_NULL, SFQ_SINT].concat(),
+        [SFQ_BINARY, SFQ_BOOL_TRUE].concat(),
+    ] {
+        // binding the params gives us the same tokens as lexing them along with the query
+        let (full, query_window) = make_safe_query(query, &params);
+        assert_eq!(
+            prepared.bind(&params).unwrap(),
+            lex_secure(&full, query_window).unwrap()
+        );
+    }
+    // bad params
+    for params in [&b"\x02\x00"[..], b"\x09"] {
+        assert_eq!(
+            prepared.bind(params).unwrap_err(),
+            QueryError::LexInvalidInput
+        );
+    }
+}
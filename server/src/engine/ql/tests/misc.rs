@@ -113,3 +113,13 @@ fn inspect_model() {
         Inspect::Model(("myspace", "mymodel").into())
     );
 }
+
+#[test]
+fn inspect_models() {
+    let t = lex_insecure(b"inspect models").unwrap();
+    let mut state = State::new_inplace(&t[1..]);
+    assert_eq!(
+        Inspect::test_parse_from_state(&mut state).unwrap(),
+        Inspect::Models
+    );
+}
@@ -332,6 +332,26 @@ mod fields {
         )
     }
     #[test]
+    fn field_default() {
+        let tok = lex_insecure(b"null status: string { default: 'active' }").unwrap();
+        let f = parse_ast_node_full::<FieldSpec>(&tok).unwrap();
+        assert_eq!(
+            f,
+            FieldSpec::new(
+                Ident::from("status"),
+                [LayerSpec::new(
+                    Ident::from("string"),
+                    null_dict! {
+                        "default" => Lit::new_str("active"),
+                    }
+                )]
+                .into(),
+                true,
+                false,
+            )
+        )
+    }
+    #[test]
     fn field_pro_max() {
         let tok = lex_insecure(
             b"
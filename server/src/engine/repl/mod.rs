@@ -0,0 +1,698 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Asynchronous replication of models from a primary to a replica
+//!
+//! A replica connects to the replication endpoint of the primary and repeatedly asks for the batch journal of each of
+//! its models, starting from the offset up to which it has already applied the journal. The batches that it receives
+//! are applied to the model on the replica just like any other write (see [`DataBatchRestoreDriver::apply_batch`]).
+//!
+//! The replica only keeps its replication offsets in memory. Whenever it doesn't have an offset for a model (because it
+//! just started), or when the primary has replaced the journal of a model (because it was compacted), the primary
+//! tells the replica to start over: the replica then empties the model and applies the journal from its first batch.
+//! The offsets are relative to a segment of the journal (identified by its epoch), and once the replica has read a
+//! sealed segment to its end, the primary continues with the first batch of the next segment.
+//!
+//! A replica is read-only (only the replicator writes to it) until it is promoted to a primary with `sysctl promote`.
+//! Promotion rotates the journals of all models, so replicas of the new primary start over from a clean journal.
+//!
+//! Replicas are served over TLS, using the settings of the TLS endpoint of the primary, and a replica only trusts a
+//! primary whose certificate is signed by the CA that it was configured with. If the primary asks for a client
+//! certificate, the replica presents the certificate of its own TLS endpoint.
+//!
+//! The protocol is a simple binary one (all integers are little endian):
+//! - the replica sends [`HANDSHAKE_MAGIC`], followed by how it authenticates ([`AUTH_TOKEN`] or [`AUTH_PASSWORD`]),
+//!   the length of the credential (`u64`) and the credential itself. The primary replies with a single byte
+//!   ([`HANDSHAKE_OKAY`] or [`HANDSHAKE_BAD_AUTH`]), and once the replica is authenticated, it follows up with a fresh
+//!   auth token for the root account (`[len: u64][token]`). The replica uses this token when it reconnects, and only
+//!   falls back to the root password when it doesn't have a token that the primary accepts (say, because the primary
+//!   was restarted or the token expired)
+//! - the replica then sends a request for every model: `[space len: u64][space][model len: u64][model][epoch: u128]
+//!   [offset: u64]`. The primary replies with either [`REPLY_NO_MODEL`], or with [`REPLY_SEGMENT`]/[`REPLY_RESET`]
+//!   followed by `[epoch: u128][header: 64B][offset: u64][checksum: u8][encoding: u8][len: u64][data]`, where the header
+//!   is the header of the segment (which its encrypted batches are bound to), the checksum is the one used in the
+//!   segment ([`CHECKSUM_CRC64`] or [`CHECKSUM_CRC32C`]) and the encoding is how the integers in its batches are encoded
+//!   ([`ENCODING_FIXED`] or [`ENCODING_VARINT`])
+
+#[cfg(test)]
+mod tests;
+
+use {
+    crate::engine::{
+        config::{ConfigEndpointTcp, ConfigEndpointTls, ConfigReplicaOf},
+        core::{dml::QueryExecMeta, model::Model, EntityIDRef},
+        error::RuntimeResult,
+        fractal::{sys_store::SysAuthUser, Global, GlobalInstanceLike},
+        net::{self, IoResult, ProtocolListener},
+        storage::{
+            v1::{
+                data_batch::{self, DataBatchRestoreDriver, JournalSegment},
+                encryption::FileBinding,
+                loader::SEInitState,
+                spec::{BatchEncoding, SDSSStaticHeaderV1Compact},
+            },
+            ChecksumKind,
+        },
+    },
+    openssl::ssl::{Ssl, SslAcceptor, SslConnector},
+    parking_lot::RwLock,
+    std::{
+        collections::HashMap,
+        io::{Error as IoError, ErrorKind as IoErrorKind},
+        mem,
+        pin::Pin,
+        time::Duration,
+    },
+    tokio::{
+        io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+        net::TcpStream,
+    },
+    tokio_openssl::SslStream,
+};
+
+/// Sent by the replica as soon as it connects
+const HANDSHAKE_MAGIC: [u8; 8] = *b"SKYREPL5";
+/// The replica authenticates with a token that the primary gave it earlier
+const AUTH_TOKEN: u8 = 0;
+/// The replica authenticates with the root password (of the primary)
+const AUTH_PASSWORD: u8 = 1;
+/// The replica was authenticated
+const HANDSHAKE_OKAY: u8 = 0;
+/// The replica didn't send a valid token or the root password of the primary
+const HANDSHAKE_BAD_AUTH: u8 = 1;
+/// The reply has the part of the journal that was asked for
+const REPLY_SEGMENT: u8 = 0;
+/// The reply has the journal from its first batch, because it isn't the journal that the replica asked for
+const REPLY_RESET: u8 = 1;
+/// The primary doesn't have the model that the replica asked for
+const REPLY_NO_MODEL: u8 = 2;
+/// The segment in the reply uses CRC64 checksums
+const CHECKSUM_CRC64: u8 = 0;
+/// The segment in the reply uses CRC32C checksums
+const CHECKSUM_CRC32C: u8 = 1;
+/// The segment in the reply uses fixed size integers
+const ENCODING_FIXED: u8 = 0;
+/// The segment in the reply uses varints
+const ENCODING_VARINT: u8 = 1;
+/// The largest part of a journal that is sent in a single reply
+const MAX_SEGMENT: u64 = 4 * 1024 * 1024;
+/// We won't read names, passwords or tokens larger than this
+const MAX_NAME: u64 = 1024;
+/// How long the replica waits before asking again when it is caught up with the primary
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long the replica waits before reconnecting to the primary
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/*
+    role
+*/
+
+/// Whether we're a replica (which only accepts writes from its primary) or a primary
+pub struct ReplicationRole {
+    replica: RwLock<bool>,
+}
+
+impl ReplicationRole {
+    pub fn new(replica: bool) -> Self {
+        Self {
+            replica: RwLock::new(replica),
+        }
+    }
+    pub fn is_replica(&self) -> bool {
+        *self.replica.read()
+    }
+    /// Run `f` if we're still a replica, making sure that we aren't promoted while it runs
+    pub fn while_replica<T>(&self, f: impl FnOnce() -> T) -> Option<T> {
+        let replica = self.replica.read();
+        if *replica {
+            Some(f())
+        } else {
+            None
+        }
+    }
+    /// Become a primary once `f` succeeds. `f` runs after any writes from the primary are done, and no other writes
+    /// are accepted until it returns. Returns false if we weren't a replica
+    pub fn promote(&self, f: impl FnOnce() -> RuntimeResult<()>) -> RuntimeResult<bool> {
+        let mut replica = self.replica.write();
+        if !*replica {
+            return Ok(false);
+        }
+        f()?;
+        *replica = false;
+        Ok(true)
+    }
+}
+
+/*
+    protocol
+*/
+
+fn invalid_data(msg: &str) -> IoError {
+    IoError::new(IoErrorKind::InvalidData, msg)
+}
+
+async fn read_blob<S: AsyncRead + Unpin>(stream: &mut S, max: u64) -> IoResult<Vec<u8>> {
+    let len = stream.read_u64_le().await?;
+    if len > max {
+        return Err(invalid_data("replication message is too large"));
+    }
+    let mut blob = vec![0; len as usize];
+    stream.read_exact(&mut blob).await?;
+    Ok(blob)
+}
+
+async fn write_blob<S: AsyncWrite + Unpin>(stream: &mut S, blob: &[u8]) -> IoResult<()> {
+    stream.write_u64_le(blob.len() as u64).await?;
+    stream.write_all(blob).await
+}
+
+async fn read_name<S: AsyncRead + Unpin>(stream: &mut S) -> IoResult<Box<str>> {
+    String::from_utf8(read_blob(stream, MAX_NAME).await?)
+        .map(String::into_boxed_str)
+        .map_err(|_| invalid_data("replication message has an invalid name"))
+}
+
+#[derive(Debug, PartialEq)]
+/// A request for the journal of a model, from where the replica left off
+struct SegmentRequest {
+    space: Box<str>,
+    model: Box<str>,
+    epoch: u128,
+    offset: u64,
+}
+
+impl SegmentRequest {
+    async fn read<S: AsyncRead + Unpin>(stream: &mut S) -> IoResult<Self> {
+        Ok(Self {
+            space: read_name(stream).await?,
+            model: read_name(stream).await?,
+            epoch: stream.read_u128_le().await?,
+            offset: stream.read_u64_le().await?,
+        })
+    }
+    async fn write<S: AsyncWrite + Unpin>(&self, stream: &mut S) -> IoResult<()> {
+        write_blob(stream, self.space.as_bytes()).await?;
+        write_blob(stream, self.model.as_bytes()).await?;
+        stream.write_u128_le(self.epoch).await?;
+        stream.write_u64_le(self.offset).await?;
+        stream.flush().await
+    }
+}
+
+async fn read_reply<S: AsyncRead + Unpin>(stream: &mut S) -> IoResult<Option<JournalSegment>> {
+    let reset = match stream.read_u8().await? {
+        REPLY_SEGMENT => false,
+        REPLY_RESET => true,
+        REPLY_NO_MODEL => return Ok(None),
+        _ => return Err(invalid_data("unknown replication reply")),
+    };
+    let epoch = stream.read_u128_le().await?;
+    let mut header = [0; SDSSStaticHeaderV1Compact::SIZE];
+    stream.read_exact(&mut header).await?;
+    let offset = stream.read_u64_le().await?;
+    let checksum = match stream.read_u8().await? {
+        CHECKSUM_CRC64 => ChecksumKind::Crc64,
+        CHECKSUM_CRC32C => ChecksumKind::Crc32c,
+        _ => return Err(invalid_data("unknown checksum")),
+    };
+    let encoding = match stream.read_u8().await? {
+        ENCODING_FIXED => BatchEncoding::Fixed,
+        ENCODING_VARINT => BatchEncoding::Varint,
+        _ => return Err(invalid_data("unknown encoding")),
+    };
+    let data = read_blob(stream, MAX_SEGMENT).await?;
+    Ok(Some(JournalSegment::new(
+        FileBinding::from_raw(header, epoch),
+        offset,
+        reset,
+        checksum,
+        encoding,
+        data,
+    )))
+}
+
+async fn write_reply<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    reply: &Option<JournalSegment>,
+) -> IoResult<()> {
+    match reply {
+        Some(segment) => {
+            stream
+                .write_u8(if segment.is_reset() {
+                    REPLY_RESET
+                } else {
+                    REPLY_SEGMENT
+                })
+                .await?;
+            stream.write_u128_le(segment.epoch()).await?;
+            stream.write_all(&segment.file().raw_header()).await?;
+            stream.write_u64_le(segment.offset()).await?;
+            stream
+                .write_u8(match segment.checksum() {
+                    ChecksumKind::Crc64 => CHECKSUM_CRC64,
+                    ChecksumKind::Crc32c => CHECKSUM_CRC32C,
+                })
+                .await?;
+            stream
+                .write_u8(match segment.encoding() {
+                    BatchEncoding::Fixed => ENCODING_FIXED,
+                    BatchEncoding::Varint => ENCODING_VARINT,
+                })
+                .await?;
+            write_blob(stream, segment.data()).await?;
+        }
+        None => stream.write_u8(REPLY_NO_MODEL).await?,
+    }
+    stream.flush().await
+}
+
+/*
+    primary
+*/
+
+/// Read the part of the journal of a model that a replica asked for
+fn read_journal<G: GlobalInstanceLike>(
+    global: &G,
+    request: &SegmentRequest,
+) -> RuntimeResult<Option<JournalSegment>> {
+    let path = {
+        let models = global.namespace().idx_models().read();
+        let Some(model) = models.get(&EntityIDRef::new(&request.space, &request.model)) else {
+            return Ok(None);
+        };
+        if model.is_ephemeral() {
+            // ephemeral models don't have a journal, so they aren't replicated
+            return Ok(None);
+        }
+        let spaces = global.namespace().idx().read();
+        let Some(space) = spaces.get(&request.space) else {
+            return Ok(None);
+        };
+        SEInitState::model_path(
+            &request.space,
+            space.get_uuid(),
+            &request.model,
+            model.get_uuid(),
+        )
+    };
+    data_batch::read_segment::<G::FileSystem>(&path, request.epoch, request.offset, MAX_SEGMENT)
+        .map(Some)
+}
+
+/// Check the credential that a replica sent, returning a fresh token for it if it's the root password or a token
+/// issued to the root account
+fn authenticate<G: GlobalInstanceLike>(global: &G, auth: u8, credential: &[u8]) -> Option<String> {
+    let sys_store = global.sys_store();
+    let verified = match auth {
+        AUTH_TOKEN => sys_store
+            .system_store()
+            .verify_token(SysAuthUser::USER_ROOT, credential),
+        // verifying the password is expensive (on purpose)
+        AUTH_PASSWORD => tokio::task::block_in_place(|| {
+            sys_store.verify_user_check_root(SysAuthUser::USER_ROOT, credential)
+        }),
+        _ => return None,
+    };
+    match verified {
+        Ok(true) => sys_store
+            .system_store()
+            .issue_token(SysAuthUser::USER_ROOT)
+            .ok(),
+        _ => None,
+    }
+}
+
+/// Authenticate a replica, returning false if it was turned away
+async fn accept_replica<S: AsyncRead + AsyncWrite + Unpin, G: GlobalInstanceLike>(
+    stream: &mut S,
+    global: &G,
+) -> RuntimeResult<bool> {
+    let mut magic = [0u8; HANDSHAKE_MAGIC.len()];
+    stream.read_exact(&mut magic).await?;
+    if magic != HANDSHAKE_MAGIC {
+        return Err(invalid_data("not a replica").into());
+    }
+    let auth = stream.read_u8().await?;
+    let credential = read_blob(stream, MAX_NAME).await?;
+    let Some(token) = authenticate(global, auth, &credential) else {
+        stream.write_u8(HANDSHAKE_BAD_AUTH).await?;
+        stream.flush().await?;
+        return Ok(false);
+    };
+    stream.write_u8(HANDSHAKE_OKAY).await?;
+    write_blob(stream, token.as_bytes()).await?;
+    stream.flush().await?;
+    Ok(true)
+}
+
+async fn serve<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    global: Global,
+) -> RuntimeResult<()> {
+    if !accept_replica(&mut stream, &global).await? {
+        return Ok(());
+    }
+    loop {
+        let request = match SegmentRequest::read(&mut stream).await {
+            Ok(request) => request,
+            // the replica went away
+            Err(e) if e.kind() == IoErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let reply = tokio::task::block_in_place(|| read_journal(&global, &request))?;
+        write_reply(&mut stream, &reply).await?;
+    }
+}
+
+/// A listener that ships batch journals to replicas (over TLS)
+pub struct ReplicationListener {
+    global: Global,
+    listener: ProtocolListener,
+    acceptor: SslAcceptor,
+}
+
+impl ReplicationListener {
+    /// Bind to the replication endpoint, serving replicas with the same TLS settings as the given TLS endpoint
+    pub async fn new(
+        ep: &ConfigEndpointTcp,
+        tls: &ConfigEndpointTls,
+        global: Global,
+    ) -> RuntimeResult<Self> {
+        let acceptor = net::Listener::<Global>::init_tls(
+            tls.cert(),
+            tls.private_key(),
+            tls.pkey_pass(),
+            tls.client_ca(),
+        )?;
+        Ok(Self {
+            global,
+            listener: ProtocolListener::bind(ep, "replication").await?,
+            acceptor,
+        })
+    }
+    pub async fn listen(&mut self) {
+        let Self {
+            global,
+            listener,
+            acceptor,
+        } = self;
+        listener
+            .listen(|stream, _| {
+                let global = global.clone();
+                let ssl = Ssl::new(acceptor.context());
+                async move {
+                    let mut stream = SslStream::new(ssl?, stream)?;
+                    Pin::new(&mut stream).accept().await?;
+                    serve(stream, global).await
+                }
+            })
+            .await
+    }
+}
+
+/*
+    replica
+*/
+
+#[derive(Default)]
+/// The replication state of a model on the replica
+struct ReplicaModel {
+    /// the journal (segment) on the primary that we're applying. its epoch is zero until we've heard from the primary
+    file: FileBinding,
+    /// the offset in the journal on the primary up to which it has been applied
+    offset: u64,
+    /// the checksum used in the segment of the journal that we're applying
+    checksum: ChecksumKind,
+    /// the encoding used in the segment of the journal that we're applying
+    encoding: BatchEncoding,
+    /// what we've received after `offset` but haven't applied yet (because the last batch is incomplete)
+    pending: Vec<u8>,
+    /// don't bother applying what's pending until there's at least this much of it (unless we've caught up with the
+    /// primary), so that a large batch isn't decoded over and over as it trickles in
+    apply_at: usize,
+    /// set once the journal couldn't be applied to the model, after which we stop replicating it
+    stalled: bool,
+}
+
+impl ReplicaModel {
+    fn request(&self, space: &str, model: &str) -> SegmentRequest {
+        SegmentRequest {
+            space: space.into(),
+            model: model.into(),
+            epoch: self.file.id(),
+            offset: self.offset + self.pending.len() as u64,
+        }
+    }
+    /// Apply a segment that we received from the primary. `caught_up` is set if the segment reaches the end of the
+    /// journal on the primary
+    fn apply<G: GlobalInstanceLike>(
+        &mut self,
+        global: &G,
+        space: &str,
+        model_name: &str,
+        segment: JournalSegment,
+        caught_up: bool,
+    ) -> RuntimeResult<()> {
+        let models = global.namespace().idx_models().read();
+        let Some(model) = models.get(&EntityIDRef::new(space, model_name)) else {
+            // the model was dropped in the meantime
+            return Ok(());
+        };
+        if segment.is_reset() {
+            model.truncate_replicated()?;
+            self.offset = segment.offset();
+            self.pending.clear();
+        } else if segment.epoch() != self.file.id() {
+            // the primary moved on to the next segment of its journal, so everything before it must have been complete
+            self.apply_pending(global, space, model_name, model)?;
+            if !self.pending.is_empty() {
+                return Err(
+                    invalid_data("the primary moved on before a batch was complete").into(),
+                );
+            }
+            self.offset = segment.offset();
+        } else if segment.offset() != self.offset + self.pending.len() as u64 {
+            return Err(invalid_data("the primary sent an unexpected part of the journal").into());
+        }
+        self.file = segment.file();
+        self.checksum = segment.checksum();
+        self.encoding = segment.encoding();
+        if segment.data().is_empty() {
+            return Ok(());
+        }
+        self.pending.extend(segment.into_data());
+        if !caught_up && self.pending.len() < self.apply_at {
+            return Ok(());
+        }
+        self.apply_pending(global, space, model_name, model)
+    }
+    /// Apply every complete batch that is pending to the model
+    fn apply_pending<G: GlobalInstanceLike>(
+        &mut self,
+        global: &G,
+        space: &str,
+        model_name: &str,
+        model: &Model,
+    ) -> RuntimeResult<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let mut driver = DataBatchRestoreDriver::<G::FileSystem>::new_segment(
+            mem::take(&mut self.pending),
+            self.offset,
+        )
+        .with_checksum(self.checksum)
+        .with_encoding(self.encoding)
+        .with_file(self.file);
+        let applied = driver.apply_batch(model);
+        self.pending = driver.into_segment();
+        let applied = applied?;
+        self.pending.drain(..(applied - self.offset) as usize);
+        // if nothing could be applied, wait until we have twice as much before trying again
+        self.apply_at = if applied == self.offset {
+            self.pending.len() * 2
+        } else {
+            0
+        };
+        self.offset = applied;
+        global.request_batch_resolve_if_cache_full(
+            space,
+            model_name,
+            model,
+            QueryExecMeta::new(model.delta_state().data_delta_queue_len()),
+        );
+        Ok(())
+    }
+}
+
+/// Authenticate with the primary, using the token that it gave us the last time around (if we have one) and otherwise
+/// the root password. On success, `token` is set to the fresh token that the primary sends back
+async fn connect_to_primary<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    token: &mut Option<String>,
+    password: &str,
+) -> RuntimeResult<()> {
+    stream.write_all(&HANDSHAKE_MAGIC).await?;
+    // if the token is rejected, we'll use the password the next time around
+    match token.take() {
+        Some(token) => {
+            stream.write_u8(AUTH_TOKEN).await?;
+            write_blob(stream, token.as_bytes()).await?;
+        }
+        None => {
+            stream.write_u8(AUTH_PASSWORD).await?;
+            write_blob(stream, password.as_bytes()).await?;
+        }
+    }
+    stream.flush().await?;
+    if stream.read_u8().await? != HANDSHAKE_OKAY {
+        return Err(IoError::new(
+            IoErrorKind::PermissionDenied,
+            "the primary rejected our credentials",
+        )
+        .into());
+    }
+    *token = Some(read_name(stream).await?.into());
+    Ok(())
+}
+
+/// Replicates every model from the primary
+pub struct Replicator {
+    global: Global,
+    host: String,
+    port: u16,
+    connector: SslConnector,
+    password: String,
+    /// the token that the primary gave us when we last connected
+    token: Option<String>,
+    models: HashMap<(Box<str>, Box<str>), ReplicaModel>,
+}
+
+impl Replicator {
+    /// Set up replication from the given primary. If we have a TLS endpoint, we present its certificate to the
+    /// primary (if it asks for one)
+    pub fn new(
+        replica_of: &ConfigReplicaOf,
+        identity: Option<&ConfigEndpointTls>,
+        password: String,
+        global: Global,
+    ) -> RuntimeResult<Self> {
+        let connector = net::init_tls_connector(
+            replica_of.ca(),
+            identity.map(|tls| (tls.cert(), tls.private_key(), tls.pkey_pass())),
+        )?;
+        Ok(Self {
+            global,
+            host: replica_of.primary.host().to_owned(),
+            port: replica_of.primary.port(),
+            connector,
+            password,
+            token: None,
+            models: HashMap::new(),
+        })
+    }
+    /// Replicate from the primary until we're promoted
+    pub async fn run(&mut self) {
+        loop {
+            match self.replicate().await {
+                Ok(()) => break,
+                Err(e) => warn!(
+                    "replication from `{}:{}` was interrupted: `{e}`. reconnecting ...",
+                    self.host, self.port
+                ),
+            }
+            tokio::time::sleep(RECONNECT_INTERVAL).await;
+            if !self.global.replication_role().is_replica() {
+                break;
+            }
+        }
+        info!("stopped replicating from {}:{}", self.host, self.port);
+    }
+    /// Returns once we've been promoted (and with an error if we lost the connection to the primary)
+    async fn replicate(&mut self) -> RuntimeResult<()> {
+        let stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        // the primary's certificate must be valid for the host that we connect to
+        let ssl = self.connector.configure()?.into_ssl(&self.host)?;
+        let mut stream = SslStream::new(ssl, stream)?;
+        Pin::new(&mut stream).connect().await?;
+        connect_to_primary(&mut stream, &mut self.token, &self.password).await?;
+        info!("replicating from {}:{}", self.host, self.port);
+        loop {
+            if !self.global.replication_role().is_replica() {
+                return Ok(());
+            }
+            // the models that we replicate are the ones that we have (and these can be created and dropped)
+            let names: Vec<(Box<str>, Box<str>)> = self
+                .global
+                .namespace()
+                .idx_models()
+                .read()
+                .keys()
+                .map(|id| (id.space().into(), id.entity().into()))
+                .collect();
+            self.models.retain(|name, _| names.contains(name));
+            let mut idle = true;
+            for (space, model) in names {
+                let state = self
+                    .models
+                    .entry((space.clone(), model.clone()))
+                    .or_default();
+                if state.stalled {
+                    continue;
+                }
+                state.request(&space, &model).write(&mut stream).await?;
+                let Some(segment) = read_reply(&mut stream).await? else {
+                    // the primary doesn't have this model
+                    continue;
+                };
+                let caught_up = (segment.data().len() as u64) < MAX_SEGMENT;
+                idle &= segment.data().is_empty();
+                /*
+                    there's no await while the model is updated, so we're never interrupted half way through (say,
+                    because we're shutting down)
+                */
+                let global = &self.global;
+                let applied = tokio::task::block_in_place(|| {
+                    global
+                        .replication_role()
+                        .while_replica(|| state.apply(global, &space, &model, segment, caught_up))
+                });
+                match applied {
+                    Some(Ok(())) => {}
+                    Some(Err(e)) => {
+                        error!("stopped replicating `{space}.{model}` since its journal couldn't be applied: `{e}`");
+                        state.stalled = true;
+                    }
+                    // we were promoted
+                    None => return Ok(()),
+                }
+            }
+            if idle {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,600 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{
+        accept_replica, connect_to_primary, read_journal, read_reply, write_reply, ReplicaModel,
+        SegmentRequest, MAX_SEGMENT,
+    },
+    crate::engine::{
+        core::{dml, model::Model, EntityIDRef},
+        error::RuntimeResult,
+        fractal::{test_utils::TestGlobal, Global, GlobalInstanceLike},
+        net::{self, protocol::ClientLocalState},
+        ql::{
+            ast::parse_ast_node_full,
+            ddl::crt::CreateModel,
+            dml::{del::DeleteStatement, ins::InsertStatement, upd::UpdateStatement},
+            tests::lex_insecure,
+        },
+        storage::{
+            v1::{
+                data_batch::{self, JournalSegment, RecoveryMode},
+                encryption::{self, EncryptionKey, FileBinding},
+                memfs::{NullFS, VirtualFS},
+                spec::{BatchCompression, BatchEncoding, SDSSStaticHeaderV1Compact},
+            },
+            ChecksumKind,
+        },
+    },
+    openssl::{
+        asn1::Asn1Time,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        nid::Nid,
+        pkey::PKey,
+        ssl::{Ssl, SslAcceptor, SslConnector},
+        symm::Cipher,
+        x509::{extension::SubjectAlternativeName, X509Builder, X509NameBuilder},
+    },
+    std::pin::Pin,
+    tokio_openssl::SslStream,
+};
+
+const MODEL: &str = "create model myspace.users(username: string, password: string)";
+
+fn new_global(model: &str) -> TestGlobal<NullFS> {
+    let global = TestGlobal::new_with_tmp_nullfs_driver();
+    global.namespace().create_empty_test_space("myspace");
+    let tok = lex_insecure(model.as_bytes()).unwrap();
+    let stmt = parse_ast_node_full::<CreateModel>(&tok[2..]).unwrap();
+    Model::transactional_exec_create(&global, stmt).unwrap();
+    global
+}
+
+fn with_model<T>(global: &impl GlobalInstanceLike, f: impl FnOnce(&Model) -> T) -> T {
+    global
+        .namespace()
+        .with_model(EntityIDRef::new("myspace", "users"), |model| Ok(f(model)))
+        .unwrap()
+}
+
+fn exec(global: &impl GlobalInstanceLike, queries: &[&str]) {
+    for query in queries {
+        let tok = lex_insecure(query.as_bytes()).unwrap();
+        match query.split_ascii_whitespace().next().unwrap() {
+            "insert" => dml::insert(
+                global,
+                parse_ast_node_full::<InsertStatement>(&tok[1..]).unwrap(),
+            ),
+            "update" => dml::update(
+                global,
+                parse_ast_node_full::<UpdateStatement>(&tok[1..]).unwrap(),
+            ),
+            "delete" => dml::delete(
+                global,
+                parse_ast_node_full::<DeleteStatement>(&tok[1..]).unwrap(),
+            ),
+            _ => unreachable!(),
+        }
+        .unwrap();
+    }
+}
+
+/*
+    the primary's journal. the virtual file system has a single cursor per file (shared by every descriptor), so the
+    journal is only kept open while it's written to (which leaves close and reopen markers in it, just like restarting
+    the primary would)
+*/
+
+fn create_journal(journal: &str) {
+    data_batch::create::<VirtualFS>(journal, BatchCompression::None, None)
+        .unwrap()
+        .close()
+        .unwrap();
+}
+
+fn with_journal(journal: &str, f: impl FnOnce(&mut data_batch::DataBatchPersistDriver<VirtualFS>)) {
+    // the journal is restored into a scratch model since we only want to write to it
+    let scratch = new_global(MODEL);
+    let (mut driver, _) = with_model(&scratch, |model| {
+        data_batch::reinit::<VirtualFS>(journal, model, RecoveryMode::Strict)
+    })
+    .unwrap();
+    f(&mut driver);
+    driver.close().unwrap();
+}
+
+/// Write the last `changes` changes to the primary's model to its journal
+fn flush(primary: &impl GlobalInstanceLike, journal: &str, changes: usize) {
+    with_journal(journal, |driver| {
+        with_model(primary, |model| {
+            driver.write_new_batch(model, changes).unwrap()
+        })
+    })
+}
+
+fn fetch(journal: &str, state: &ReplicaModel) -> JournalSegment {
+    let request = state.request("myspace", "users");
+    data_batch::read_segment::<VirtualFS>(journal, request.epoch, request.offset, MAX_SEGMENT)
+        .unwrap()
+}
+
+fn apply(replica: &TestGlobal<NullFS>, state: &mut ReplicaModel, segment: JournalSegment) -> bool {
+    state
+        .apply(replica, "myspace", "users", segment, true)
+        .is_ok()
+}
+
+fn rows(global: &impl GlobalInstanceLike) -> usize {
+    with_model(global, |model| model.primary_index().count())
+}
+
+fn password_of(global: &impl GlobalInstanceLike, username: &str) -> Option<String> {
+    let query = format!("select password from myspace.users where username = '{username}'");
+    let tok = lex_insecure(query.as_bytes()).unwrap();
+    let mut password = None;
+    dml::select_custom(
+        global,
+        &ClientLocalState::new_test("root", true),
+        parse_ast_node_full(&tok[1..]).unwrap(),
+        |cell| password = Some(cell.str().to_owned()),
+    )
+    .ok()?;
+    password
+}
+
+#[test]
+fn replicate_model() {
+    let journal = "repl_replicate_model.db-btlog";
+    let (primary, replica) = (new_global(MODEL), new_global(MODEL));
+    let mut state = ReplicaModel::default();
+    create_journal(journal);
+    exec(
+        &primary,
+        &[
+            "insert into myspace.users('sayan', 'pass123')",
+            "insert into myspace.users('elana', 'pass456')",
+        ],
+    );
+    flush(&primary, journal, 2);
+    // the replica has never seen this journal, so it starts from the first batch
+    let segment = fetch(journal, &state);
+    assert!(segment.is_reset());
+    let end = segment.offset() + segment.data().len() as u64;
+    assert!(apply(&replica, &mut state, segment));
+    // the journal ends with a close marker which is only skipped once there's something after it
+    assert_eq!(state.request("myspace", "users").offset, end);
+    assert_eq!(rows(&replica), 2);
+    assert_eq!(password_of(&replica, "elana").as_deref(), Some("pass456"));
+    // nothing new
+    let segment = fetch(journal, &state);
+    assert!(!segment.is_reset());
+    assert!(segment.data().is_empty());
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(state.request("myspace", "users").offset, end);
+    // now only what was written since is shipped
+    exec(
+        &primary,
+        &[
+            "update myspace.users set password = 'pass789' where username = 'sayan'",
+            "delete from myspace.users where username = 'elana'",
+            "insert into myspace.users('emily', 'pass000')",
+        ],
+    );
+    flush(&primary, journal, 3);
+    let segment = fetch(journal, &state);
+    assert!(!segment.is_reset());
+    assert_eq!(segment.offset(), end);
+    let end = end + segment.data().len() as u64;
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(state.request("myspace", "users").offset, end);
+    assert_eq!(rows(&replica), 2);
+    assert_eq!(password_of(&replica, "sayan").as_deref(), Some("pass789"));
+    assert_eq!(password_of(&replica, "elana"), None);
+    assert_eq!(password_of(&replica, "emily").as_deref(), Some("pass000"));
+}
+
+#[test]
+fn replicate_encrypted_journal() {
+    let journal = "repl_replicate_encrypted_journal.db-btlog";
+    let key = EncryptionKey::new([0x6B; encryption::KEY_SIZE]);
+    encryption::register(key.clone());
+    let (primary, replica) = (new_global(MODEL), new_global(MODEL));
+    let mut state = ReplicaModel::default();
+    data_batch::create::<VirtualFS>(journal, BatchCompression::None, Some(&key))
+        .unwrap()
+        .close()
+        .unwrap();
+    exec(
+        &primary,
+        &[
+            "insert into myspace.users('sayan', 'pass123')",
+            "insert into myspace.users('elana', 'pass456')",
+        ],
+    );
+    flush(&primary, journal, 2);
+    let segment = fetch(journal, &state);
+    let (offset, checksum, encoding, data) = (
+        segment.offset(),
+        segment.checksum(),
+        segment.encoding(),
+        segment.data().to_vec(),
+    );
+    // the batches are bound to the header of the journal that they were written to
+    let forged = FileBinding::from_raw([0; SDSSStaticHeaderV1Compact::SIZE], segment.epoch());
+    let forged = JournalSegment::new(forged, offset, true, checksum, encoding, data);
+    let mut forged_state = ReplicaModel::default();
+    assert!(apply(&replica, &mut forged_state, forged));
+    assert_eq!(rows(&replica), 0);
+    assert!(!forged_state.pending.is_empty());
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(password_of(&replica, "elana").as_deref(), Some("pass456"));
+}
+
+#[test]
+fn replicate_incomplete_batch() {
+    let journal = "repl_replicate_incomplete_batch.db-btlog";
+    let (primary, replica) = (new_global(MODEL), new_global(MODEL));
+    let mut state = ReplicaModel::default();
+    create_journal(journal);
+    exec(
+        &primary,
+        &[
+            "insert into myspace.users('sayan', 'pass123')",
+            "insert into myspace.users('elana', 'pass456')",
+        ],
+    );
+    flush(&primary, journal, 2);
+    let segment = fetch(journal, &state);
+    let (file, offset, checksum, encoding) = (
+        segment.file(),
+        segment.offset(),
+        segment.checksum(),
+        segment.encoding(),
+    );
+    let mut data = segment.into_data();
+    let rest = data.split_off(data.len() / 2);
+    let rest_offset = offset + data.len() as u64;
+    // the first half of the batch can't be applied yet
+    let segment = JournalSegment::new(file, offset, true, checksum, encoding, data);
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(rows(&replica), 0);
+    assert!(!state.pending.is_empty());
+    // so the replica asks for the rest of it
+    assert_eq!(state.request("myspace", "users").offset, rest_offset);
+    let segment = JournalSegment::new(file, rest_offset, false, checksum, encoding, rest);
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(rows(&replica), 2);
+    // a segment that doesn't continue from where we are is rejected
+    let segment = JournalSegment::new(file, offset, false, checksum, encoding, vec![0]);
+    assert!(!apply(&replica, &mut state, segment));
+}
+
+#[test]
+fn replicate_after_compaction() {
+    let journal = "repl_replicate_after_compaction.db-btlog";
+    let (primary, replica) = (new_global(MODEL), new_global(MODEL));
+    let mut state = ReplicaModel::default();
+    create_journal(journal);
+    exec(
+        &primary,
+        &[
+            "insert into myspace.users('sayan', 'pass123')",
+            "insert into myspace.users('elana', 'pass456')",
+        ],
+    );
+    flush(&primary, journal, 2);
+    let segment = fetch(journal, &state);
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(rows(&replica), 2);
+    // a row that the primary doesn't have (and that is lost when the replica starts over)
+    exec(&replica, &["insert into myspace.users('emily', 'pass000')"]);
+    exec(
+        &primary,
+        &["delete from myspace.users where username = 'elana'"],
+    );
+    flush(&primary, journal, 1);
+    with_journal(journal, |driver| {
+        with_model(&primary, |model| {
+            data_batch::compact(journal, model, driver, BatchCompression::None, None).unwrap()
+        })
+    });
+    // the compacted journal replaced the one that we were reading
+    let segment = fetch(journal, &state);
+    assert!(segment.is_reset());
+    assert_ne!(segment.epoch(), state.file.id());
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(rows(&replica), 1);
+    assert_eq!(password_of(&replica, "sayan").as_deref(), Some("pass123"));
+    assert_eq!(password_of(&replica, "elana"), None);
+    assert_eq!(password_of(&replica, "emily"), None);
+}
+
+#[test]
+fn replicate_across_rotation() {
+    let journal = "repl_replicate_across_rotation.db-btlog";
+    let (primary, replica) = (new_global(MODEL), new_global(MODEL));
+    let mut state = ReplicaModel::default();
+    create_journal(journal);
+    exec(
+        &primary,
+        &[
+            "insert into myspace.users('sayan', 'pass123')",
+            "insert into myspace.users('elana', 'pass456')",
+        ],
+    );
+    flush(&primary, journal, 2);
+    let segment = fetch(journal, &state);
+    assert!(apply(&replica, &mut state, segment));
+    let sealed_epoch = state.file.id();
+    // seal the segment that we're reading and write to the next one
+    with_journal(journal, |driver| {
+        data_batch::rotate(journal, driver).unwrap();
+        assert_eq!(driver.segment(), 1);
+    });
+    exec(&primary, &["insert into myspace.users('emily', 'pass000')"]);
+    flush(&primary, journal, 1);
+    // first the rest of the sealed segment
+    let segment = fetch(journal, &state);
+    assert!(!segment.is_reset());
+    assert_eq!(segment.epoch(), sealed_epoch);
+    assert!(apply(&replica, &mut state, segment));
+    // and then the next segment, from its first batch
+    let segment = fetch(journal, &state);
+    assert!(!segment.is_reset());
+    assert_ne!(segment.epoch(), sealed_epoch);
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(rows(&replica), 3);
+    assert_eq!(password_of(&replica, "emily").as_deref(), Some("pass000"));
+    // a new replica starts over from the oldest segment
+    let replica = new_global(MODEL);
+    let mut state = ReplicaModel::default();
+    let segment = fetch(journal, &state);
+    assert!(segment.is_reset());
+    assert_eq!(segment.epoch(), sealed_epoch);
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(rows(&replica), 2);
+    let segment = fetch(journal, &state);
+    assert!(!segment.is_reset());
+    assert!(apply(&replica, &mut state, segment));
+    assert_eq!(rows(&replica), 3);
+}
+
+#[test]
+fn replicate_mismatched_model() {
+    let journal = "repl_replicate_mismatched_model.db-btlog";
+    let primary = new_global(MODEL);
+    let replica =
+        new_global("create model myspace.users(username: string, password: string, age: uint8)");
+    let mut state = ReplicaModel::default();
+    create_journal(journal);
+    exec(&primary, &["insert into myspace.users('sayan', 'pass123')"]);
+    flush(&primary, journal, 1);
+    let segment = fetch(journal, &state);
+    assert!(!apply(&replica, &mut state, segment));
+    assert_eq!(rows(&replica), 0);
+}
+
+#[test]
+fn replicate_missing_model() {
+    let primary = new_global(MODEL);
+    let request = SegmentRequest {
+        space: "myspace".into(),
+        model: "posts".into(),
+        epoch: 0,
+        offset: 0,
+    };
+    assert_eq!(read_journal(&primary, &request).unwrap(), None);
+}
+
+#[tokio::test]
+async fn protocol_roundtrip() {
+    let (mut a, mut b) = tokio::io::duplex(1024);
+    let request = SegmentRequest {
+        space: "myspace".into(),
+        model: "users".into(),
+        epoch: u128::MAX,
+        offset: 42,
+    };
+    request.write(&mut a).await.unwrap();
+    assert_eq!(SegmentRequest::read(&mut b).await.unwrap(), request);
+    for reply in [
+        Some(JournalSegment::new(
+            FileBinding::from_raw([0xA1; SDSSStaticHeaderV1Compact::SIZE], 1),
+            2,
+            false,
+            ChecksumKind::Crc32c,
+            BatchEncoding::Varint,
+            b"batch".to_vec(),
+        )),
+        Some(JournalSegment::new(
+            FileBinding::from_raw([0xB3; SDSSStaticHeaderV1Compact::SIZE], 3),
+            4,
+            true,
+            ChecksumKind::Crc64,
+            BatchEncoding::Fixed,
+            vec![],
+        )),
+        None,
+    ] {
+        write_reply(&mut a, &reply).await.unwrap();
+        assert_eq!(read_reply(&mut b).await.unwrap(), reply);
+    }
+}
+
+/*
+    handshake
+*/
+
+const TLS_PASSPHRASE: &str = "tls-passphrase";
+
+/// A self-signed certificate for `localhost` and its private key (encrypted with [`TLS_PASSPHRASE`])
+fn self_signed_cert() -> (String, String) {
+    let key = EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap();
+    let key = PKey::from_ec_key(key).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "localhost").unwrap();
+    let name = name.build();
+    let mut cert = X509Builder::new().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_subject_name(&name).unwrap();
+    cert.set_issuer_name(&name).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+        .unwrap();
+    let san = SubjectAlternativeName::new()
+        .dns("localhost")
+        .build(&cert.x509v3_context(None, None))
+        .unwrap();
+    cert.append_extension(san).unwrap();
+    cert.sign(&key, MessageDigest::sha256()).unwrap();
+    (
+        String::from_utf8(cert.build().to_pem().unwrap()).unwrap(),
+        String::from_utf8(
+            key.private_key_to_pem_pkcs8_passphrase(
+                Cipher::aes_256_cbc(),
+                TLS_PASSPHRASE.as_bytes(),
+            )
+            .unwrap(),
+        )
+        .unwrap(),
+    )
+}
+
+/// Run the handshake between a primary and a replica over TLS, returning what each of them made of it
+async fn handshake(
+    primary: &TestGlobal<NullFS>,
+    acceptor: &SslAcceptor,
+    connector: &SslConnector,
+    token: &mut Option<String>,
+    password: &str,
+) -> (RuntimeResult<bool>, RuntimeResult<()>) {
+    let (a, b) = tokio::io::duplex(4096);
+    let primary = async {
+        let mut stream = SslStream::new(Ssl::new(acceptor.context())?, a)?;
+        Pin::new(&mut stream).accept().await?;
+        accept_replica(&mut stream, primary).await
+    };
+    let replica = async {
+        let ssl = connector.configure()?.into_ssl("localhost")?;
+        let mut stream = SslStream::new(ssl, b)?;
+        Pin::new(&mut stream).connect().await?;
+        connect_to_primary(&mut stream, token, password).await
+    };
+    tokio::join!(primary, replica)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handshake_with_token() {
+    let primary = new_global(MODEL);
+    let (cert, key) = self_signed_cert();
+    let acceptor = net::Listener::<Global>::init_tls(&cert, &key, TLS_PASSPHRASE, None).unwrap();
+    let connector = net::init_tls_connector(&cert, None).unwrap();
+    // we start out with the root password, and get a token for it
+    let mut token = None;
+    let (accepted, connected) = handshake(
+        &primary,
+        &acceptor,
+        &connector,
+        &mut token,
+        "password12345678",
+    )
+    .await;
+    assert!(accepted.unwrap());
+    connected.unwrap();
+    let first = token.clone().unwrap();
+    // which is used the next time around (so a wrong password doesn't matter), and replaced with a fresh one
+    let (accepted, connected) = handshake(
+        &primary,
+        &acceptor,
+        &connector,
+        &mut token,
+        "wrong password",
+    )
+    .await;
+    assert!(accepted.unwrap());
+    connected.unwrap();
+    assert!(token.is_some());
+    // a token that the primary didn't issue is rejected, and then we fall back to the password
+    let tampered = if first.ends_with('0') { '1' } else { '0' };
+    token = Some(format!("{}{tampered}", &first[..first.len() - 1]));
+    let (accepted, connected) = handshake(
+        &primary,
+        &acceptor,
+        &connector,
+        &mut token,
+        "wrong password",
+    )
+    .await;
+    assert!(!accepted.unwrap());
+    connected.unwrap_err();
+    assert_eq!(token, None);
+    let (accepted, connected) = handshake(
+        &primary,
+        &acceptor,
+        &connector,
+        &mut token,
+        "wrong password",
+    )
+    .await;
+    assert!(!accepted.unwrap());
+    connected.unwrap_err();
+    let (accepted, connected) = handshake(
+        &primary,
+        &acceptor,
+        &connector,
+        &mut token,
+        "password12345678",
+    )
+    .await;
+    assert!(accepted.unwrap());
+    connected.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handshake_with_untrusted_primary() {
+    let primary = new_global(MODEL);
+    let (cert, key) = self_signed_cert();
+    let (other_cert, _) = self_signed_cert();
+    let acceptor = net::Listener::<Global>::init_tls(&cert, &key, TLS_PASSPHRASE, None).unwrap();
+    // the replica only trusts a different certificate
+    let connector = net::init_tls_connector(&other_cert, None).unwrap();
+    let mut token = None;
+    let (accepted, connected) = handshake(
+        &primary,
+        &acceptor,
+        &connector,
+        &mut token,
+        "password12345678",
+    )
+    .await;
+    accepted.unwrap_err();
+    connected.unwrap_err();
+    assert_eq!(token, None);
+}
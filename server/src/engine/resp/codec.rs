@@ -0,0 +1,178 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Just enough of RESP (version 2) for the RESP adapter: commands sent as arrays of bulk strings (or inline, like
+//! `redis-cli` and telnet do) and the replies to them
+
+/// We won't accept commands with more arguments than this
+const MAX_ARGS: usize = 1024;
+/// We won't accept commands (all of their arguments together) larger than this
+pub const MAX_COMMAND: usize = 8 * 1024 * 1024;
+/// We won't accept inline commands larger than this
+const MAX_INLINE: usize = 64 * 1024;
+/// The longest line (`*<count>` or `$<length>`) that we'll look at before giving up
+const MAX_HEADER_LINE: usize = 32;
+
+/// The arguments of a command and the number of bytes it took up (if it's complete), or a protocol error
+type ParseResult = Result<Option<(Vec<Vec<u8>>, usize)>, &'static str>;
+
+/// Parse the command at the start of the buffer, returning its arguments and the number of bytes that it took up.
+/// Returns [`None`] if the command isn't complete yet, and an error (to be sent to the client, before closing the
+/// connection) if the client isn't speaking RESP
+pub fn parse_command(buf: &[u8]) -> ParseResult {
+    match buf.first() {
+        None => Ok(None),
+        Some(b'*') => parse_multibulk(buf),
+        Some(_) => parse_inline(buf),
+    }
+}
+
+fn parse_multibulk(buf: &[u8]) -> ParseResult {
+    let Some((count, mut pos)) = read_header(buf, 0, b'*', "invalid multibulk length")? else {
+        return Ok(None);
+    };
+    if count > MAX_ARGS as i64 {
+        return Err("invalid multibulk length");
+    }
+    // `*0` and `*-1` are empty commands, which are skipped
+    let mut args = Vec::with_capacity(count.max(0) as usize);
+    for _ in 0..count {
+        if pos == buf.len() {
+            return Ok(None);
+        }
+        if buf[pos] != b'$' {
+            return Err("expected '$'");
+        }
+        let Some((len, start)) = read_header(buf, pos, b'$', "invalid bulk length")? else {
+            return Ok(None);
+        };
+        if (len < 0) | (start as i64 + len > MAX_COMMAND as i64) {
+            return Err("invalid bulk length");
+        }
+        let end = start + len as usize;
+        if buf.len() < end + 2 {
+            return Ok(None);
+        }
+        if &buf[end..end + 2] != b"\r\n" {
+            return Err("expected CRLF after bulk string");
+        }
+        args.push(buf[start..end].to_vec());
+        pos = end + 2;
+    }
+    Ok(Some((args, pos)))
+}
+
+/// Read a `<prefix><integer>\r\n` line starting at `pos`, returning the integer and the position right after the line
+fn read_header(
+    buf: &[u8],
+    pos: usize,
+    prefix: u8,
+    error: &'static str,
+) -> Result<Option<(i64, usize)>, &'static str> {
+    debug_assert_eq!(buf[pos], prefix);
+    let line = &buf[pos + 1..];
+    let Some(cr) = line.iter().take(MAX_HEADER_LINE).position(|b| *b == b'\r') else {
+        return match line.len() < MAX_HEADER_LINE {
+            true => Ok(None),
+            false => Err(error),
+        };
+    };
+    match line.get(cr + 1) {
+        None => return Ok(None),
+        Some(b'\n') => {}
+        Some(_) => return Err(error),
+    }
+    match core::str::from_utf8(&line[..cr]).map(str::parse::<i64>) {
+        Ok(Ok(int)) => Ok(Some((int, pos + 1 + cr + 2))),
+        _ => Err(error),
+    }
+}
+
+/// Parse an inline command (`SET key value\r\n`), whose arguments are separated by spaces
+fn parse_inline(buf: &[u8]) -> ParseResult {
+    let Some(lf) = buf.iter().take(MAX_INLINE).position(|b| *b == b'\n') else {
+        return match buf.len() < MAX_INLINE {
+            true => Ok(None),
+            false => Err("too big inline request"),
+        };
+    };
+    let line = &buf[..lf];
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let args = line
+        .split(u8::is_ascii_whitespace)
+        .filter(|arg| !arg.is_empty())
+        .map(<[u8]>::to_vec)
+        .collect::<Vec<_>>();
+    if args.len() > MAX_ARGS {
+        return Err("too many arguments");
+    }
+    Ok(Some((args, lf + 1)))
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+    /// a simple string (like `OK`)
+    Simple(&'static str),
+    /// an error, starting with its kind (like `ERR` or `NOAUTH`)
+    Error(String),
+    Int(u64),
+    Bulk(Vec<u8>),
+    /// the null bulk string
+    Nil,
+}
+
+impl Reply {
+    pub const OK: Self = Self::Simple("OK");
+    pub fn error(kind: &str, message: impl AsRef<str>) -> Self {
+        // errors are sent as a single line
+        let message = message.as_ref().replace(['\r', '\n'], " ");
+        Self::Error(format!("{kind} {message}"))
+    }
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Simple(s) => {
+                out.push(b'+');
+                out.extend(s.as_bytes());
+            }
+            Self::Error(e) => {
+                out.push(b'-');
+                out.extend(e.as_bytes());
+            }
+            Self::Int(int) => {
+                out.push(b':');
+                out.extend(int.to_string().as_bytes());
+            }
+            Self::Bulk(data) => {
+                out.push(b'$');
+                out.extend(data.len().to_string().as_bytes());
+                out.extend(b"\r\n");
+                out.extend(data);
+            }
+            Self::Nil => out.extend(b"$-1"),
+        }
+        out.extend(b"\r\n");
+    }
+}
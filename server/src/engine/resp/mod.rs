@@ -0,0 +1,553 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! An adapter that lets Redis clients store keys in a model, so that applications can be migrated to Skytable before
+//! they're ported to a native driver
+//!
+//! The model (set with `--resp-model`) must have a primary key and exactly one other field, which hold the keys and
+//! their values. Commands are run as queries for the user that the client authenticated as with `AUTH` (`AUTH
+//! <password>` logs in as `root`), so permissions, quotas and the query timeout apply as usual:
+//!
+//! - `GET key` selects the value (missing rows and null values are returned as nil)
+//! - `SET key value` inserts the row, or updates its value if the key is already there
+//! - `DEL key [key ...]` and `EXISTS key [key ...]` delete and look up the keys one by one, returning how many were
+//!   there
+//!
+//! Keys and values are sent as the types of their fields: strings have to be valid UTF-8, and numbers and booleans are
+//! parsed from their text. `PING`, `ECHO`, `SELECT 0` and `QUIT` are also supported, and every other command is
+//! rejected
+
+mod codec;
+#[cfg(test)]
+mod tests;
+
+use {
+    self::codec::Reply,
+    crate::engine::{
+        config::ConfigResp,
+        data::tag::{TagClass, TagSelector},
+        error::{QueryError, QueryResult, RuntimeResult},
+        fractal::{sys_store::SysConnectionGuard, Global},
+        idx::geo,
+        net::{
+            protocol::{ClientLocalState, QueryHost, QueryKind, Response, ResponseType, SQuery},
+            IoResult, ProtocolListener,
+        },
+        web::{decode_line, decode_sized, login},
+    },
+    bytes::{Buf, BytesMut},
+    std::{net::SocketAddr, sync::Arc},
+    tokio::{
+        io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+        sync::broadcast,
+    },
+};
+
+/*
+    model layout
+*/
+
+#[derive(Debug, PartialEq)]
+/// The fields that keys and values are stored in
+struct Layout {
+    key: Box<str>,
+    key_class: TagClass,
+    value: Box<str>,
+    value_class: TagClass,
+}
+
+impl Layout {
+    /// Get the layout from the response to `inspect model`, or return why the model can't be used
+    fn from_inspect(r: Response) -> Result<Self, &'static str> {
+        let row = decode_row(r).ok_or("can't be inspected")?;
+        let [Cell::Scalar(p_key), .., Cell::List(fields)] = &row[..] else {
+            return Err("can't be inspected");
+        };
+        if fields.len() != 2 {
+            return Err("must have exactly one field besides its primary key");
+        }
+        let (mut key, mut value) = (None, None);
+        for field in fields {
+            let Cell::List(field) = field else {
+                return Err("can't be inspected");
+            };
+            let [Cell::Scalar(name), Cell::Scalar(ty), ..] = &field[..] else {
+                return Err("can't be inspected");
+            };
+            let name = core::str::from_utf8(name).map_err(|_| "can't be inspected")?;
+            let class = type_class(ty).ok_or("has a list or dict field, which isn't supported")?;
+            match name.as_bytes() == &p_key[..] {
+                true => key = Some((name.into(), class)),
+                false => value = Some((name.into(), class)),
+            }
+        }
+        match (key, value) {
+            (Some((key, key_class)), Some((value, value_class))) => Ok(Self {
+                key,
+                key_class,
+                value,
+                value_class,
+            }),
+            _ => Err("can't be inspected"),
+        }
+    }
+}
+
+/// Returns the class of a type as described by `inspect model` (or [`None`] for lists and dicts)
+fn type_class(ty: &[u8]) -> Option<TagClass> {
+    let class = match ty {
+        b"Bool" => TagClass::Bool,
+        b"UInt8" | b"UInt16" | b"UInt32" | b"UInt64" => TagClass::UnsignedInt,
+        b"SInt8" | b"SInt16" | b"SInt32" | b"SInt64" => TagClass::SignedInt,
+        b"Float32" | b"Float64" => TagClass::Float,
+        b"Timestamp" => TagClass::Timestamp,
+        b"SInt128" => TagClass::BigInt,
+        b"GeoPoint" => TagClass::Geo,
+        b"Binary" => TagClass::Bin,
+        b"String" => TagClass::Str,
+        _ => return None,
+    };
+    Some(class)
+}
+
+/*
+    queries and responses
+*/
+
+/// Encode an argument as a param of the given class, just like a client of the native protocol would
+fn encode_param(buf: &mut Vec<u8>, class: TagClass, arg: &[u8]) -> Result<(), Reply> {
+    let text = || core::str::from_utf8(arg).ok();
+    let invalid = |ty| Reply::error("ERR", format!("argument isn't a valid {ty}"));
+    match class {
+        TagClass::Bool => {
+            let b = match arg {
+                b"true" | b"1" => 1,
+                b"false" | b"0" => 0,
+                _ => return Err(invalid("boolean")),
+            };
+            buf.extend([1, b]);
+        }
+        TagClass::UnsignedInt | TagClass::Timestamp => {
+            let int = text()
+                .and_then(|t| t.parse::<u64>().ok())
+                .ok_or_else(|| invalid("unsigned integer"))?;
+            buf.push(2);
+            buf.extend(int.to_string().as_bytes());
+            buf.push(b'\n');
+        }
+        TagClass::SignedInt => {
+            let int = text()
+                .and_then(|t| t.parse::<i64>().ok())
+                .ok_or_else(|| invalid("signed integer"))?;
+            buf.push(3);
+            buf.extend(int.to_string().as_bytes());
+            buf.push(b'\n');
+        }
+        TagClass::Float => {
+            let float = text()
+                .and_then(|t| t.parse::<f64>().ok())
+                .filter(|f| f.is_finite())
+                .ok_or_else(|| invalid("float"))?;
+            buf.push(4);
+            buf.extend(float.to_string().as_bytes());
+            buf.push(b'\n');
+        }
+        TagClass::BigInt => {
+            // there are no 128-bit literals, so it's sent as a string (and parsed by the server)
+            let int = text()
+                .and_then(|t| t.parse::<i128>().ok())
+                .ok_or_else(|| invalid("128-bit signed integer"))?;
+            let int = int.to_string();
+            buf.push(6);
+            buf.extend(int.len().to_string().as_bytes());
+            buf.push(b'\n');
+            buf.extend(int.as_bytes());
+        }
+        TagClass::Geo => {
+            // points are sent as `"lat,lon"` strings too
+            if text().and_then(geo::parse_point).is_none() {
+                return Err(invalid("point (`lat,lon`)"));
+            }
+            buf.push(6);
+            buf.extend(arg.len().to_string().as_bytes());
+            buf.push(b'\n');
+            buf.extend(arg);
+        }
+        TagClass::Bin | TagClass::Str => {
+            if (class == TagClass::Str) & text().is_none() {
+                return Err(invalid("UTF-8 string"));
+            }
+            buf.push(if class == TagClass::Str { 6 } else { 5 });
+            buf.extend(arg.len().to_string().as_bytes());
+            buf.push(b'\n');
+            buf.extend(arg);
+        }
+        TagClass::List | TagClass::Dict => {
+            unreachable!("lists and dicts are rejected when reading the layout")
+        }
+    }
+    Ok(())
+}
+
+/// The error that is returned when a query fails
+fn query_error(e: QueryError) -> Reply {
+    Reply::error(
+        "ERR",
+        format!("query failed with {e:?} (code {})", e.value_u8()),
+    )
+}
+
+#[derive(Debug, PartialEq)]
+/// A cell of a row (as encoded for the native protocol), where scalars are kept as the bytes that are sent to clients
+enum Cell {
+    Null,
+    Scalar(Vec<u8>),
+    List(Vec<Cell>),
+}
+
+/// Decode the cells of a single row
+fn decode_row(r: Response) -> Option<Vec<Cell>> {
+    let Response::Serialized {
+        ty: ResponseType::Row,
+        size,
+        data,
+    } = r
+    else {
+        return None;
+    };
+    let data = data.to_vec();
+    let mut data = &data[..];
+    let row = (0..size)
+        .map(|_| decode_cell(&mut data))
+        .collect::<Option<Vec<_>>>()?;
+    data.is_empty().then_some(row)
+}
+
+fn decode_cell(data: &mut &[u8]) -> Option<Cell> {
+    let (&tag, rest) = data.split_first()?;
+    *data = rest;
+    if tag == 0 {
+        return Some(Cell::Null);
+    }
+    if tag - 1 > TagSelector::MAX {
+        return None;
+    }
+    let selector = unsafe {
+        // UNSAFE(@ohsayan): we just checked that this is a valid selector
+        TagSelector::from_raw(tag - 1)
+    };
+    let cell = match selector.tag_class() {
+        TagClass::Bool => {
+            let (&b, rest) = data.split_first()?;
+            *data = rest;
+            Cell::Scalar(if b == 1 { &b"true"[..] } else { b"false" }.to_vec())
+        }
+        TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Float | TagClass::Timestamp => {
+            Cell::Scalar(decode_line(data)?.to_vec())
+        }
+        TagClass::Bin | TagClass::Str | TagClass::BigInt | TagClass::Geo => {
+            Cell::Scalar(decode_sized(data)?.to_vec())
+        }
+        // dicts are sent as lists (see `encode_cell`)
+        TagClass::List | TagClass::Dict => {
+            let len = core::str::from_utf8(decode_line(data)?)
+                .ok()?
+                .parse::<usize>()
+                .ok()?;
+            Cell::List((0..len).map(|_| decode_cell(data)).collect::<Option<_>>()?)
+        }
+    };
+    Some(cell)
+}
+
+/*
+    connections
+*/
+
+/// The state of a client's connection
+struct Connection<'a, H> {
+    host: &'a H,
+    /// the designated model (`space.model`)
+    entity: &'a str,
+    peer: SocketAddr,
+    /// the user that the client authenticated as (and their connection, which counts towards their quota)
+    session: Option<(ClientLocalState, SysConnectionGuard<'a>)>,
+    /// the layout of the model, which is looked up again after a query fails (in case the model was altered)
+    layout: Option<Arc<Layout>>,
+}
+
+impl<'a, H: QueryHost> Connection<'a, H> {
+    fn new(host: &'a H, entity: &'a str, peer: SocketAddr) -> Self {
+        Self {
+            host,
+            entity,
+            peer,
+            session: None,
+            layout: None,
+        }
+    }
+    /// Run a command, returning the reply and whether the connection should be closed
+    async fn run(&mut self, command: &[Vec<u8>]) -> (Reply, bool) {
+        let name = command[0].to_ascii_uppercase();
+        let args = &command[1..];
+        let r = match (&name[..], args.len()) {
+            (b"QUIT", _) => return (Reply::OK, true),
+            (b"PING", 0) => Ok(Reply::Simple("PONG")),
+            (b"PING" | b"ECHO", 1) => Ok(Reply::Bulk(args[0].clone())),
+            (b"AUTH", 1) => Ok(self.auth(b"root", &args[0])),
+            (b"AUTH", 2) => Ok(self.auth(&args[0], &args[1])),
+            (b"PING" | b"ECHO" | b"AUTH", _) => Err(wrong_arity(&name)),
+            _ if self.session.is_none() => Err(Reply::error("NOAUTH", "Authentication required.")),
+            (b"SELECT", 1) => match &args[0][..] {
+                b"0" => Ok(Reply::OK),
+                _ => Err(Reply::error("ERR", "DB index is out of range")),
+            },
+            (b"GET", 1) => self.get(&args[0]).await,
+            (b"SET", 2) => self.set(&args[0], &args[1]).await,
+            (b"SET", 3..) => Err(Reply::error("ERR", "SET options aren't supported")),
+            (b"DEL", 1..) => self.count(args, true).await,
+            (b"EXISTS", 1..) => self.count(args, false).await,
+            (b"SELECT" | b"GET" | b"SET" | b"DEL" | b"EXISTS", _) => Err(wrong_arity(&name)),
+            _ => Err(Reply::error(
+                "ERR",
+                format!("unknown command '{}'", String::from_utf8_lossy(&command[0])),
+            )),
+        };
+        (r.unwrap_or_else(|e| e), false)
+    }
+    fn auth(&mut self, username: &[u8], password: &[u8]) -> Reply {
+        // whatever happens, the client is no longer logged in as the previous user
+        self.session = None;
+        let Some(cstate) = login(self.host, username, password, self.peer, "resp") else {
+            return Reply::error(
+                "WRONGPASS",
+                "invalid username-password pair or user is disabled.",
+            );
+        };
+        let sys_cfg = self.host.sys_store().system_store();
+        let quota = sys_cfg.user_quota(cstate.username());
+        match sys_cfg.quota_usage().connect(cstate.username(), quota) {
+            Some(connection) => {
+                self.session = Some((cstate, connection));
+                Reply::OK
+            }
+            None => Reply::error("ERR", "too many connections"),
+        }
+    }
+    async fn get(&mut self, key: &[u8]) -> Result<Reply, Reply> {
+        let layout = self.layout().await?;
+        let query = format!(
+            "select {} from {} where {} = ?",
+            layout.value, self.entity, layout.key
+        );
+        match self.exec(&query, &[(layout.key_class, key)]).await? {
+            Ok(r) => match decode_row(r).as_deref() {
+                Some([Cell::Scalar(value)]) => Ok(Reply::Bulk(value.clone())),
+                Some([Cell::Null]) => Ok(Reply::Nil),
+                _ => Err(query_error(QueryError::SysUnknownError)),
+            },
+            Err(QueryError::QExecDmlRowNotFound) => Ok(Reply::Nil),
+            Err(e) => Err(self.failed(e)),
+        }
+    }
+    async fn set(&mut self, key: &[u8], value: &[u8]) -> Result<Reply, Reply> {
+        let layout = self.layout().await?;
+        let insert = format!(
+            "insert into {} {{ {}: ?, {}: ? }}",
+            self.entity, layout.key, layout.value
+        );
+        let params = [(layout.key_class, key), (layout.value_class, value)];
+        match self.exec(&insert, &params).await? {
+            Ok(_) => return Ok(Reply::OK),
+            Err(QueryError::QExecDmlDuplicate) => {}
+            Err(e) => return Err(self.failed(e)),
+        }
+        let update = format!(
+            "update {} set {} = ? where {} = ?",
+            self.entity, layout.value, layout.key
+        );
+        let params = [(layout.value_class, value), (layout.key_class, key)];
+        match self.exec(&update, &params).await? {
+            Ok(_) => Ok(Reply::OK),
+            Err(e) => Err(self.failed(e)),
+        }
+    }
+    /// Delete (or look up) the keys, returning how many were there
+    async fn count(&mut self, keys: &[Vec<u8>], delete: bool) -> Result<Reply, Reply> {
+        let layout = self.layout().await?;
+        let query = match delete {
+            true => format!("delete from {} where {} = ?", self.entity, layout.key),
+            false => format!(
+                "select {} from {} where {} = ?",
+                layout.key, self.entity, layout.key
+            ),
+        };
+        let mut count = 0;
+        for key in keys {
+            match self.exec(&query, &[(layout.key_class, key)]).await? {
+                Ok(_) => count += 1,
+                Err(QueryError::QExecDmlRowNotFound) => {}
+                Err(e) => return Err(self.failed(e)),
+            }
+        }
+        Ok(Reply::Int(count))
+    }
+    /// Returns the layout of the model, looking it up if needed
+    async fn layout(&mut self) -> Result<Arc<Layout>, Reply> {
+        if let Some(layout) = &self.layout {
+            return Ok(layout.clone());
+        }
+        let query = format!("inspect model {}", self.entity);
+        let layout = match self.exec(&query, &[]).await? {
+            Ok(r) => Layout::from_inspect(r)
+                .map_err(|e| Reply::error("ERR", format!("{} {e}", self.entity)))?,
+            Err(e) => return Err(query_error(e)),
+        };
+        let layout = Arc::new(layout);
+        self.layout = Some(layout.clone());
+        Ok(layout)
+    }
+    /// Run a query with the given params, returning an error reply if the params couldn't be encoded
+    async fn exec(
+        &mut self,
+        query: &str,
+        params: &[(TagClass, &[u8])],
+    ) -> Result<QueryResult<Response>, Reply> {
+        let mut payload = query.as_bytes().to_vec();
+        for (class, param) in params {
+            encode_param(&mut payload, *class, param)?;
+        }
+        let Some((cstate, _)) = self.session.as_mut() else {
+            return Err(Reply::error("NOAUTH", "Authentication required."));
+        };
+        Ok(self
+            .host
+            .dispatch(
+                cstate,
+                SQuery::new(QueryKind::Simple, &payload, query.len()),
+            )
+            .await)
+    }
+    /// A query failed, so look up the layout again for the next command
+    fn failed(&mut self, e: QueryError) -> Reply {
+        self.layout = None;
+        query_error(e)
+    }
+}
+
+fn wrong_arity(name: &[u8]) -> Reply {
+    Reply::error(
+        "ERR",
+        format!(
+            "wrong number of arguments for '{}' command",
+            String::from_utf8_lossy(name).to_ascii_lowercase()
+        ),
+    )
+}
+
+async fn serve<S: AsyncRead + AsyncWrite + Unpin, H: QueryHost>(
+    mut stream: S,
+    peer: SocketAddr,
+    host: &H,
+    entity: &str,
+    mut term: broadcast::Receiver<()>,
+) -> IoResult<()> {
+    let mut buf = BytesMut::with_capacity(1024);
+    let mut out = vec![];
+    let mut connection = Connection::new(host, entity, peer);
+    loop {
+        // run every command that we have (so that pipelined commands are answered with a single write)
+        loop {
+            let (reply, close) = match codec::parse_command(&buf) {
+                Ok(Some((command, size))) => {
+                    buf.advance(size);
+                    if command.is_empty() {
+                        continue;
+                    }
+                    connection.run(&command).await
+                }
+                Ok(None) => break,
+                Err(e) => (Reply::error("ERR", format!("Protocol error: {e}")), true),
+            };
+            reply.encode(&mut out);
+            if close {
+                stream.write_all(&out).await?;
+                return stream.shutdown().await;
+            }
+        }
+        if !out.is_empty() {
+            stream.write_all(&out).await?;
+            out.clear();
+        }
+        let read = tokio::select! {
+            read = stream.read_buf(&mut buf) => read?,
+            _ = term.recv() => 0,
+        };
+        if read == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// A listener that serves the RESP adapter
+pub struct RespListener<H = Global> {
+    host: H,
+    /// the designated model (`space.model`)
+    entity: Arc<str>,
+    listener: ProtocolListener,
+    term: broadcast::Receiver<()>,
+}
+
+impl<H: QueryHost> RespListener<H> {
+    pub async fn new(
+        cfg: &ConfigResp,
+        host: H,
+        term: broadcast::Receiver<()>,
+    ) -> RuntimeResult<Self> {
+        Ok(Self {
+            host,
+            entity: format!("{}.{}", cfg.space(), cfg.model()).into(),
+            listener: ProtocolListener::bind(&cfg.endpoint, "resp").await?,
+            term,
+        })
+    }
+    pub async fn listen(&mut self) {
+        let Self {
+            host,
+            entity,
+            listener,
+            term,
+        } = self;
+        listener
+            .listen(|stream, peer| {
+                let host = host.clone();
+                let entity = entity.clone();
+                let term = term.resubscribe();
+                async move { serve(stream, peer, &host, &entity, term).await }
+            })
+            .await
+    }
+}
@@ -0,0 +1,233 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    super::{
+        codec::{parse_command, Reply, MAX_COMMAND},
+        decode_row, encode_param, Cell, Layout,
+    },
+    crate::engine::{
+        core::dml::encode_cell,
+        data::{cell::Datacell, tag::TagClass},
+        net::protocol::{Response, ResponseType},
+        ql::tests::lex_secure,
+    },
+};
+
+fn args(args: &[&str]) -> Vec<Vec<u8>> {
+    args.iter().map(|arg| arg.as_bytes().to_vec()).collect()
+}
+
+#[test]
+fn parse_multibulk() {
+    let command = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nva\r\nl\r\n";
+    assert_eq!(
+        parse_command(command),
+        Ok(Some((args(&["SET", "key", "va\r\nl"]), command.len())))
+    );
+    // incomplete commands are parsed again once we have the rest
+    for i in 0..command.len() {
+        assert_eq!(parse_command(&command[..i]), Ok(None));
+    }
+    // anything after the command is left alone
+    let mut pipelined = command.to_vec();
+    pipelined.extend(b"*1\r\n$4\r\nPING\r\n");
+    assert_eq!(
+        parse_command(&pipelined),
+        Ok(Some((args(&["SET", "key", "va\r\nl"]), command.len())))
+    );
+    assert_eq!(parse_command(b"*0\r\n"), Ok(Some((vec![], 4))));
+    assert_eq!(parse_command(b"*-1\r\n"), Ok(Some((vec![], 5))));
+    assert_eq!(
+        parse_command(b"*1\r\n$0\r\n\r\n"),
+        Ok(Some((vec![vec![]], 10)))
+    );
+}
+
+#[test]
+fn parse_multibulk_bad() {
+    for bad in [
+        &b"*x\r\n"[..],
+        b"*1\nx\r\n",
+        b"*1025\r\n",
+        b"*1\r\n+PING\r\n",
+        b"*1\r\n$-1\r\n",
+        b"*1\r\n$4\r\nPINGxx",
+        b"*11111111111111111111111111111111111",
+    ] {
+        assert!(parse_command(bad).is_err(), "{}", bad.escape_ascii());
+    }
+    // we won't wait for commands that are too large
+    assert!(parse_command(format!("*1\r\n${MAX_COMMAND}\r\n").as_bytes()).is_err());
+}
+
+#[test]
+fn parse_inline() {
+    assert_eq!(
+        parse_command(b"set  key value\r\nGET"),
+        Ok(Some((args(&["set", "key", "value"]), 16)))
+    );
+    assert_eq!(parse_command(b"PING\n"), Ok(Some((args(&["PING"]), 5))));
+    assert_eq!(parse_command(b"\r\n"), Ok(Some((vec![], 2))));
+    assert_eq!(parse_command(b"PING"), Ok(None));
+    assert!(parse_command(&vec![b'a'; 64 * 1024]).is_err());
+}
+
+#[test]
+fn reply_encode() {
+    let encode = |reply: Reply| {
+        let mut out = vec![];
+        reply.encode(&mut out);
+        out
+    };
+    assert_eq!(encode(Reply::OK), b"+OK\r\n");
+    assert_eq!(encode(Reply::Int(2)), b":2\r\n");
+    assert_eq!(encode(Reply::Bulk(b"a\r\nb".to_vec())), b"$4\r\na\r\nb\r\n");
+    assert_eq!(encode(Reply::Bulk(vec![])), b"$0\r\n\r\n");
+    assert_eq!(encode(Reply::Nil), b"$-1\r\n");
+    assert_eq!(
+        encode(Reply::error("ERR", "unknown command 'a\r\nb'")),
+        b"-ERR unknown command 'a  b'\r\n"
+    );
+}
+
+#[test]
+fn params() {
+    let query = "insert into myspace.kv(?, ?, ?, ?, ?, ?, ?)";
+    let mut payload = query.as_bytes().to_vec();
+    for (class, arg) in [
+        (TagClass::Str, &b"sayan"[..]),
+        (TagClass::Bin, b"\xff\x00"),
+        (TagClass::UnsignedInt, b"10"),
+        (TagClass::SignedInt, b"-10"),
+        (TagClass::Float, b"1.5"),
+        (TagClass::Bool, b"true"),
+        (TagClass::Bool, b"0"),
+    ] {
+        encode_param(&mut payload, class, arg).unwrap();
+    }
+    let mut expected = query.as_bytes().to_vec();
+    expected.extend(b"\x065\nsayan\x052\n\xff\x00\x0210\n\x03-10\n\x041.5\n\x01\x01\x01\x00");
+    assert_eq!(
+        lex_secure(&payload, query.len()).unwrap(),
+        lex_secure(&expected, query.len()).unwrap()
+    );
+    assert_eq!(payload, expected);
+    for (class, bad) in [
+        (TagClass::Str, &b"\xff"[..]),
+        (TagClass::UnsignedInt, b"-1"),
+        (TagClass::SignedInt, b"1.5"),
+        (TagClass::Float, b"inf"),
+        (TagClass::Bool, b"yes"),
+    ] {
+        assert!(encode_param(&mut vec![], class, bad).is_err());
+    }
+}
+
+fn row(cells: &[Datacell]) -> Response {
+    let mut data = vec![];
+    cells.iter().for_each(|cell| encode_cell(&mut data, cell));
+    Response::Serialized {
+        ty: ResponseType::Row,
+        size: cells.len(),
+        data: data.into(),
+    }
+}
+
+fn inspect(fields: &[(&str, &str)]) -> Response {
+    row(&[
+        Datacell::new_str("k".into()),
+        Datacell::new_str("{...}".into()),
+        Datacell::new_uint_default(0),
+        Datacell::new_uint_default(0),
+        Datacell::new_uint_default(0),
+        Datacell::new_list(
+            fields
+                .iter()
+                .map(|(name, ty)| {
+                    Datacell::from([
+                        Datacell::new_str((*name).into()),
+                        Datacell::new_str((*ty).into()),
+                        Datacell::new_bool(false),
+                    ])
+                })
+                .collect(),
+        ),
+    ])
+}
+
+#[test]
+fn layout() {
+    assert_eq!(
+        Layout::from_inspect(inspect(&[("k", "String"), ("v", "UInt8")])),
+        Ok(Layout {
+            key: "k".into(),
+            key_class: TagClass::Str,
+            value: "v".into(),
+            value_class: TagClass::UnsignedInt,
+        })
+    );
+    // the primary key doesn't have to come first
+    assert_eq!(
+        Layout::from_inspect(inspect(&[("v", "Binary"), ("k", "SInt64")])),
+        Ok(Layout {
+            key: "k".into(),
+            key_class: TagClass::SignedInt,
+            value: "v".into(),
+            value_class: TagClass::Bin,
+        })
+    );
+    for bad in [
+        &[("k", "String")][..],
+        &[("k", "String"), ("v", "String"), ("w", "String")],
+        &[("k", "String"), ("v", "[String]")],
+    ] {
+        assert!(Layout::from_inspect(inspect(bad)).is_err());
+    }
+}
+
+#[test]
+fn cells() {
+    assert_eq!(
+        decode_row(row(&[
+            Datacell::new_str("sayan".into()),
+            Datacell::new_bin(b"\x00\xff".to_vec().into_boxed_slice()),
+            Datacell::new_sint_default(-10),
+            Datacell::new_float_default(1.5),
+            Datacell::new_bool(true),
+            Datacell::null(),
+        ])),
+        Some(vec![
+            Cell::Scalar(b"sayan".to_vec()),
+            Cell::Scalar(b"\x00\xff".to_vec()),
+            Cell::Scalar(b"-10".to_vec()),
+            Cell::Scalar(b"1.5".to_vec()),
+            Cell::Scalar(b"true".to_vec()),
+            Cell::Null,
+        ])
+    );
+    assert_eq!(decode_row(Response::Empty), None);
+}
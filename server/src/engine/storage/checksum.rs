@@ -27,26 +27,217 @@
 use crc::{Crc, Digest, CRC_64_XZ};
 
 /*
-    NOTE(@ohsayan): we're currently using crc's impl. but the reason I decided to make a wrapper is because I have a
-    different impl in mind
+    NOTE(@ohsayan): CRC64 is computed with crc's (table based) impl. which is only kept around for files written before
+    we switched to CRC32C, which we compute ourselves since most CPUs have an instruction for it
 */
 
 const CRC64: Crc<u64> = Crc::<u64>::new(&CRC_64_XZ);
 
+/// The checksum algorithm used in a file, which is recorded in its header (see [`super::v1::spec::checksum_from_header`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumKind {
+    /// CRC-64/XZ, used by files written before we switched to CRC32C
+    Crc64,
+    /// CRC-32C (Castagnoli)
+    Crc32c,
+}
+
+impl ChecksumKind {
+    /// The checksum used in the files that we create
+    pub const NEW: Self = Self::Crc32c;
+}
+
+impl Default for ChecksumKind {
+    fn default() -> Self {
+        Self::NEW
+    }
+}
+
 pub struct SCrc {
-    digest: Digest<'static, u64>,
+    state: SCrcState,
+}
+
+enum SCrcState {
+    Crc64(Digest<'static, u64>),
+    /// the (inverted) CRC32C of everything so far
+    Crc32c(u32),
 }
 
 impl SCrc {
-    pub const fn new() -> Self {
+    pub const fn new(kind: ChecksumKind) -> Self {
         Self {
-            digest: CRC64.digest(),
+            state: match kind {
+                ChecksumKind::Crc64 => SCrcState::Crc64(CRC64.digest()),
+                ChecksumKind::Crc32c => SCrcState::Crc32c(!0),
+            },
+        }
+    }
+    pub fn kind(&self) -> ChecksumKind {
+        match self.state {
+            SCrcState::Crc64(_) => ChecksumKind::Crc64,
+            SCrcState::Crc32c(_) => ChecksumKind::Crc32c,
         }
     }
     pub fn recompute_with_new_var_block(&mut self, b: &[u8]) {
-        self.digest.update(b)
+        match &mut self.state {
+            SCrcState::Crc64(digest) => digest.update(b),
+            SCrcState::Crc32c(crc) => *crc = crc32c::update(*crc, b),
+        }
     }
     pub fn finish(self) -> u64 {
-        self.digest.finalize()
+        match self.state {
+            SCrcState::Crc64(digest) => digest.finalize(),
+            SCrcState::Crc32c(crc) => !crc as u64,
+        }
+    }
+}
+
+mod crc32c {
+    //! CRC32C, using the CRC32 instruction of SSE4.2 (on x86_64) or ARMv8 (on aarch64) if the CPU has it. This is
+    //! detected at runtime (std caches the result), and we fall back to a slicing-by-8 table otherwise
+
+    const POLY: u32 = 0x82F63B78;
+    static TABLE: [[u32; 256]; 8] = table();
+
+    const fn table() -> [[u32; 256]; 8] {
+        let mut table = [[0; 256]; 8];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ POLY
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[0][i] = crc;
+            i += 1;
+        }
+        let mut slice = 1;
+        while slice < 8 {
+            let mut i = 0;
+            while i < 256 {
+                let prev = table[slice - 1][i];
+                table[slice][i] = (prev >> 8) ^ table[0][(prev & 0xFF) as usize];
+                i += 1;
+            }
+            slice += 1;
+        }
+        table
+    }
+
+    pub fn update(crc: u32, data: &[u8]) -> u32 {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if std::arch::is_x86_feature_detected!("sse4.2") {
+                return unsafe {
+                    // UNSAFE(@ohsayan): just checked that the CPU supports SSE4.2
+                    update_sse42(crc, data)
+                };
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            if std::arch::is_aarch64_feature_detected!("crc") {
+                return unsafe {
+                    // UNSAFE(@ohsayan): just checked that the CPU supports the CRC32 instructions
+                    update_armv8(crc, data)
+                };
+            }
+        }
+        update_table(crc, data)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn update_sse42(crc: u32, data: &[u8]) -> u32 {
+        use core::arch::x86_64::{_mm_crc32_u64, _mm_crc32_u8};
+        let mut chunks = data.chunks_exact(8);
+        let mut crc = crc as u64;
+        for chunk in &mut chunks {
+            crc = _mm_crc32_u64(crc, u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let mut crc = crc as u32;
+        for &byte in chunks.remainder() {
+            crc = _mm_crc32_u8(crc, byte);
+        }
+        crc
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    #[target_feature(enable = "crc")]
+    unsafe fn update_armv8(mut crc: u32, data: &[u8]) -> u32 {
+        use core::arch::aarch64::{__crc32cb, __crc32cd};
+        let mut chunks = data.chunks_exact(8);
+        for chunk in &mut chunks {
+            crc = __crc32cd(crc, u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        for &byte in chunks.remainder() {
+            crc = __crc32cb(crc, byte);
+        }
+        crc
+    }
+
+    pub(super) fn update_table(mut crc: u32, data: &[u8]) -> u32 {
+        let mut chunks = data.chunks_exact(8);
+        for chunk in &mut chunks {
+            let lo = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) ^ crc;
+            let hi = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+            crc = TABLE[7][(lo & 0xFF) as usize]
+                ^ TABLE[6][((lo >> 8) & 0xFF) as usize]
+                ^ TABLE[5][((lo >> 16) & 0xFF) as usize]
+                ^ TABLE[4][(lo >> 24) as usize]
+                ^ TABLE[3][(hi & 0xFF) as usize]
+                ^ TABLE[2][((hi >> 8) & 0xFF) as usize]
+                ^ TABLE[1][((hi >> 16) & 0xFF) as usize]
+                ^ TABLE[0][(hi >> 24) as usize];
+        }
+        for &byte in chunks.remainder() {
+            crc = TABLE[0][((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+        }
+        crc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32c, ChecksumKind, SCrc};
+
+    fn checksum(kind: ChecksumKind, blocks: &[&[u8]]) -> u64 {
+        let mut crc = SCrc::new(kind);
+        for block in blocks {
+            crc.recompute_with_new_var_block(block);
+        }
+        crc.finish()
+    }
+
+    #[test]
+    fn known_values() {
+        assert_eq!(checksum(ChecksumKind::Crc32c, &[b"123456789"]), 0xE3069283);
+        assert_eq!(checksum(ChecksumKind::Crc32c, &[]), 0);
+        assert_eq!(
+            checksum(ChecksumKind::Crc64, &[b"123456789"]),
+            0x995DC9BBDF1939FA
+        );
+    }
+
+    #[test]
+    fn incremental_and_table_fallback() {
+        let data: Vec<u8> = (0..4099u32).map(|i| (i * 7 + i / 13) as u8).collect();
+        let full = checksum(ChecksumKind::Crc32c, &[&data]);
+        for split in [0, 1, 7, 8, 9, 1000, 4098] {
+            let (a, b) = data.split_at(split);
+            assert_eq!(checksum(ChecksumKind::Crc32c, &[a, b]), full);
+        }
+        // the table fallback must agree with whatever the CPU is using
+        for len in [0, 1, 5, 8, 15, 64, 4099] {
+            assert_eq!(
+                crc32c::update_table(!0, &data[..len]),
+                crc32c::update(!0, &data[..len])
+            );
+        }
     }
 }
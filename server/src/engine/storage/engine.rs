@@ -0,0 +1,315 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Storage engines
+//!
+//! A storage engine keeps the data of models. It creates the storage for new models, restores models from their
+//! storage on startup and hands out a [`ModelStorage`] for each model, which the fractal manager writes batches of
+//! deltas to and compacts, rotates and snapshots as it sees fit. The engine that's used is picked in the configuration
+//! (see [`StorageEngineKind`]), except for ephemeral models which are only ever kept in memory (see [`EphemeralEngine`]).
+//! So is the backend that the engine uses for file I/O (see [`IoBackend`])
+
+use {
+    super::v1::{
+        data_batch::{self, DataBatchRestoreReport, RecoveryMode},
+        durability::DurabilityPolicy,
+        encryption::EncryptionKey,
+        spec::BatchCompression,
+        LocalFS, RawFSInterface,
+    },
+    crate::engine::{core::model::Model, error::RuntimeResult},
+    serde::Deserialize,
+    std::marker::PhantomData,
+};
+
+/// The storage engines that can be selected in the configuration
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum StorageEngineKind {
+    /// The changes of a model are appended to a batch journal (see [`JournalEngine`])
+    #[serde(rename = "journal")]
+    Journal,
+}
+
+/// The backends for file I/O that can be selected in the configuration
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum IoBackend {
+    /// Blocking reads and writes through the standard library
+    #[serde(rename = "std")]
+    Std,
+    /// Batch journals are written using io_uring (see [`UringFS`](super::v1::uring::UringFS)). This is only
+    /// available on Linux, and we fall back to [`IoBackend::Std`] everywhere else (or if the kernel doesn't support it)
+    #[serde(rename = "io_uring")]
+    IoUring,
+    /// Batch journals are read and written using direct I/O, bypassing the page cache (see
+    /// [`DirectFS`](super::v1::direct::DirectFS)). This is only available on Linux, and we fall back to
+    /// [`IoBackend::Std`] everywhere else
+    #[serde(rename = "direct")]
+    Direct,
+}
+
+impl StorageEngineKind {
+    /// Returns the engine, which uses the file system `Fs`
+    pub fn engine<Fs: RawFSInterface>(self) -> &'static dyn StorageEngine {
+        match self {
+            Self::Journal => &JournalEngine::<Fs>(PhantomData),
+        }
+    }
+    /// Returns the engine that keeps the data of a model (which is this engine, unless the model is ephemeral)
+    pub fn model_engine<Fs: RawFSInterface>(self, ephemeral: bool) -> &'static dyn StorageEngine {
+        if ephemeral {
+            &EphemeralEngine
+        } else {
+            self.engine::<Fs>()
+        }
+    }
+    /// Returns the engine that keeps the data of a model on the local file system, using the given I/O backend
+    pub fn local_model_engine(self, io: IoBackend, ephemeral: bool) -> &'static dyn StorageEngine {
+        match io {
+            #[cfg(target_os = "linux")]
+            IoBackend::IoUring => self.model_engine::<super::v1::uring::UringFS>(ephemeral),
+            #[cfg(target_os = "linux")]
+            IoBackend::Direct => self.model_engine::<super::v1::direct::DirectFS>(ephemeral),
+            _ => self.model_engine::<LocalFS>(ephemeral),
+        }
+    }
+}
+
+/// A storage engine
+pub trait StorageEngine: Sync {
+    /// Create the storage for a new model at `path`, with the given compression (and encryption key, if any)
+    fn create_model(
+        &self,
+        path: &str,
+        compression: BatchCompression,
+        encryption: Option<&EncryptionKey>,
+    ) -> RuntimeResult<Box<dyn ModelStorage>>;
+    /// Restore the data of the model from its storage at `path` into the model. If `until` is set, only events up to
+    /// (and including) that txn id are restored and the storage is rewritten to hold only the recovered state.
+    /// Otherwise, storage that wasn't closed is handled as set by `recovery`
+    fn restore_model(
+        &self,
+        path: &str,
+        model: &Model,
+        until: Option<u64>,
+        recovery: RecoveryMode,
+    ) -> RuntimeResult<(Box<dyn ModelStorage>, DataBatchRestoreReport)>;
+}
+
+/// The storage of a single model
+///
+/// The caller must make sure that no batches are written while the storage is compacted, rotated, snapshotted or
+/// backed up, and that no inserts or deletes run while it's compacted or snapshotted
+pub trait ModelStorage: Send {
+    /// Write a batch with `observed_len` deltas (which were taken from the model's delta state)
+    fn append_batch(&mut self, model: &Model, observed_len: usize) -> RuntimeResult<()>;
+    /// Set when new batches are synced to disk
+    fn set_durability(&mut self, policy: DurabilityPolicy);
+    /// Sync any batches that weren't synced yet, if the sync interval has elapsed
+    fn sync_if_due(&mut self) -> RuntimeResult<()>;
+    /// Returns the current size of the storage on disk
+    fn size(&self) -> RuntimeResult<u64>;
+    /// Returns true if the storage has grown enough that it should be compacted
+    fn needs_compaction(&self) -> RuntimeResult<bool>;
+    /// Compact the storage at `path` so that it only holds the latest state of every row in the model (using the given
+    /// compression and encryption key from here on)
+    fn compact(
+        &mut self,
+        path: &str,
+        model: &Model,
+        compression: BatchCompression,
+        encryption: Option<&EncryptionKey>,
+    ) -> RuntimeResult<()>;
+    /// Returns true if the storage should be rotated
+    fn needs_rotation(&self) -> RuntimeResult<bool>;
+    /// Rotate the storage at `path`
+    fn rotate(&mut self, path: &str) -> RuntimeResult<()>;
+    /// Returns true if enough was written since the last snapshot that it's worth taking a new one
+    fn needs_snapshot(&self) -> RuntimeResult<bool>;
+    /// Take a snapshot of the model, whose storage is at `path`. Returns true if the snapshot was incremental
+    fn snapshot(&mut self, path: &str, model: &Model) -> RuntimeResult<bool>;
+    /// Copy the storage at `from` into `to`, so that it can be loaded independently
+    fn backup_to(&mut self, from: &str, to: &str) -> RuntimeResult<()>;
+    /// Close the storage, so that it's known to be complete on the next start
+    fn close(self: Box<Self>) -> RuntimeResult<()>;
+}
+
+/*
+    journal engine
+*/
+
+/// The batch journal engine. Every model has a batch journal (on the file system `Fs`) that batches of its changes are
+/// appended to (see [`data_batch`])
+pub struct JournalEngine<Fs>(PhantomData<fn() -> Fs>);
+
+impl<Fs: RawFSInterface> StorageEngine for JournalEngine<Fs> {
+    fn create_model(
+        &self,
+        path: &str,
+        compression: BatchCompression,
+        encryption: Option<&EncryptionKey>,
+    ) -> RuntimeResult<Box<dyn ModelStorage>> {
+        Ok(Box::new(data_batch::create::<Fs>(
+            path,
+            compression,
+            encryption,
+        )?))
+    }
+    fn restore_model(
+        &self,
+        path: &str,
+        model: &Model,
+        until: Option<u64>,
+        recovery: RecoveryMode,
+    ) -> RuntimeResult<(Box<dyn ModelStorage>, DataBatchRestoreReport)> {
+        let (driver, report) = match until {
+            Some(until) => data_batch::recover_until::<Fs>(path, model, until)?,
+            None => data_batch::reinit::<Fs>(path, model, recovery)?,
+        };
+        Ok((Box::new(driver), report))
+    }
+}
+
+impl<Fs: RawFSInterface> ModelStorage for data_batch::DataBatchPersistDriver<Fs> {
+    fn append_batch(&mut self, model: &Model, observed_len: usize) -> RuntimeResult<()> {
+        self.write_new_batch(model, observed_len)
+    }
+    fn set_durability(&mut self, policy: DurabilityPolicy) {
+        Self::set_durability(self, policy)
+    }
+    fn sync_if_due(&mut self) -> RuntimeResult<()> {
+        Self::sync_if_due(self)
+    }
+    fn size(&self) -> RuntimeResult<u64> {
+        self.file_length()
+    }
+    fn needs_compaction(&self) -> RuntimeResult<bool> {
+        Self::needs_compaction(self)
+    }
+    fn compact(
+        &mut self,
+        path: &str,
+        model: &Model,
+        compression: BatchCompression,
+        encryption: Option<&EncryptionKey>,
+    ) -> RuntimeResult<()> {
+        data_batch::compact(path, model, self, compression, encryption)
+    }
+    fn needs_rotation(&self) -> RuntimeResult<bool> {
+        Self::needs_rotation(self)
+    }
+    fn rotate(&mut self, path: &str) -> RuntimeResult<()> {
+        data_batch::rotate(path, self)
+    }
+    fn needs_snapshot(&self) -> RuntimeResult<bool> {
+        Self::needs_snapshot(self)
+    }
+    fn snapshot(&mut self, path: &str, model: &Model) -> RuntimeResult<bool> {
+        super::v1::snapshot::write(path, model, self)
+    }
+    fn backup_to(&mut self, from: &str, to: &str) -> RuntimeResult<()> {
+        Self::backup_to(self, from, to)
+    }
+    fn close(self: Box<Self>) -> RuntimeResult<()> {
+        Self::close(*self)
+    }
+}
+
+/*
+    ephemeral engine
+*/
+
+/// The engine for ephemeral models (see [`Model::is_ephemeral`]). Nothing is written to disk, so an ephemeral model is
+/// empty after a restart
+pub struct EphemeralEngine;
+
+impl StorageEngine for EphemeralEngine {
+    fn create_model(
+        &self,
+        _: &str,
+        _: BatchCompression,
+        _: Option<&EncryptionKey>,
+    ) -> RuntimeResult<Box<dyn ModelStorage>> {
+        Ok(Box::new(EphemeralStorage))
+    }
+    fn restore_model(
+        &self,
+        _: &str,
+        _: &Model,
+        _: Option<u64>,
+        _: RecoveryMode,
+    ) -> RuntimeResult<(Box<dyn ModelStorage>, DataBatchRestoreReport)> {
+        Ok((
+            Box::new(EphemeralStorage),
+            DataBatchRestoreReport::default(),
+        ))
+    }
+}
+
+/// The storage of an ephemeral model, which drops every batch that's written to it
+struct EphemeralStorage;
+
+impl ModelStorage for EphemeralStorage {
+    fn append_batch(&mut self, _: &Model, _: usize) -> RuntimeResult<()> {
+        Ok(())
+    }
+    fn set_durability(&mut self, _: DurabilityPolicy) {}
+    fn sync_if_due(&mut self) -> RuntimeResult<()> {
+        Ok(())
+    }
+    fn size(&self) -> RuntimeResult<u64> {
+        Ok(0)
+    }
+    fn needs_compaction(&self) -> RuntimeResult<bool> {
+        Ok(false)
+    }
+    fn compact(
+        &mut self,
+        _: &str,
+        _: &Model,
+        _: BatchCompression,
+        _: Option<&EncryptionKey>,
+    ) -> RuntimeResult<()> {
+        Ok(())
+    }
+    fn needs_rotation(&self) -> RuntimeResult<bool> {
+        Ok(false)
+    }
+    fn rotate(&mut self, _: &str) -> RuntimeResult<()> {
+        Ok(())
+    }
+    fn needs_snapshot(&self) -> RuntimeResult<bool> {
+        Ok(false)
+    }
+    fn snapshot(&mut self, _: &str, _: &Model) -> RuntimeResult<bool> {
+        Ok(false)
+    }
+    fn backup_to(&mut self, _: &str, _: &str) -> RuntimeResult<()> {
+        Ok(())
+    }
+    fn close(self: Box<Self>) -> RuntimeResult<()> {
+        Ok(())
+    }
+}
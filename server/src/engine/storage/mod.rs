@@ -29,6 +29,7 @@
 mod checksum;
 mod versions;
 // impls
+pub mod engine;
 pub mod v1;
 
-pub use checksum::SCrc;
+pub use checksum::{ChecksumKind, SCrc};
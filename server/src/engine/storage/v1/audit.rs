@@ -0,0 +1,430 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    audit log
+    ---
+    an append-only journal (with one event per audited action) that records who ran DDL statements and sysctl commands
+    and who logged in (or tried to), along with when and from where. the log is only ever read by scanning it from the
+    start, so we don't keep anything in memory
+
+    event payload:
+    +----------+----------+---------------+--------------+--------------+----------------+------+------+--------+
+    | TIME 8B  | KIND 1B  | SUCCEEDED 1B  | USER LEN 8B  | ADDR LEN 8B  | DETAIL LEN 8B  | USER | ADDR | DETAIL |
+    +----------+----------+---------------+--------------+--------------+----------------+------+------+--------+
+*/
+
+use {
+    super::{
+        encryption::{self, EncryptionKey},
+        journal::{self, JournalAdapter, JournalReader, JournalScan, JournalWriter},
+        rw::{FileOpen, RawFSInterface, SDSSFileIO},
+        spec::AuditLogV1,
+    },
+    crate::engine::{error::RuntimeResult, mem::BufferedScanner},
+    parking_lot::Mutex,
+    std::{
+        cell::RefCell,
+        collections::VecDeque,
+        fmt,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+pub const AUDIT_LOG_PATH: &str = "audit.db-tlog";
+/// The number of events that are returned if the reader doesn't set a limit
+pub const DEFAULT_READ_LIMIT: usize = 1000;
+
+/*
+    events
+*/
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sky_macros::EnumMethods)]
+pub enum AuditEventKind {
+    /// a client tried to log in
+    Login = 0,
+    /// a DDL statement
+    Ddl = 1,
+    /// a `sysctl` command (other than the ones that create or drop users)
+    Sysctl = 2,
+    /// `sysctl create user`
+    UserCreate = 3,
+    /// `sysctl drop user`
+    UserDrop = 4,
+}
+
+impl AuditEventKind {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Login => "login",
+            Self::Ddl => "ddl",
+            Self::Sysctl => "sysctl",
+            Self::UserCreate => "user_create",
+            Self::UserDrop => "user_drop",
+        }
+    }
+    pub fn from_str(kind: &str) -> Option<Self> {
+        [
+            Self::Login,
+            Self::Ddl,
+            Self::Sysctl,
+            Self::UserCreate,
+            Self::UserDrop,
+        ]
+        .into_iter()
+        .find(|k| k.as_str() == kind)
+    }
+    fn try_from_raw(kind: u8) -> Option<Self> {
+        if kind <= Self::MAX {
+            Some(unsafe {
+                // UNSAFE(@ohsayan): just verified the range
+                core::mem::transmute(kind)
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    /// milliseconds since the UNIX epoch
+    time: u64,
+    kind: AuditEventKind,
+    succeeded: bool,
+    user: Box<str>,
+    /// the address of the client (empty if unknown)
+    addr: Box<str>,
+    /// the query (which never has the values of its parameters) or, for logins, how the client authenticated
+    detail: Box<str>,
+}
+
+impl AuditEvent {
+    const HEADER_SIZE: usize = sizeof!(u64) + 2 + sizeof!(u64) * 3;
+    /// An event that happened just now
+    pub fn new(
+        kind: AuditEventKind,
+        succeeded: bool,
+        user: &str,
+        addr: &str,
+        detail: &str,
+    ) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        Self::new_at(time, kind, succeeded, user, addr, detail)
+    }
+    pub fn new_at(
+        time: u64,
+        kind: AuditEventKind,
+        succeeded: bool,
+        user: &str,
+        addr: &str,
+        detail: &str,
+    ) -> Self {
+        Self {
+            time,
+            kind,
+            succeeded,
+            user: user.into(),
+            addr: addr.into(),
+            detail: detail.into(),
+        }
+    }
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+    pub fn kind(&self) -> AuditEventKind {
+        self.kind
+    }
+    pub fn succeeded(&self) -> bool {
+        self.succeeded
+    }
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+    fn encode(&self) -> Box<[u8]> {
+        let mut buf = Vec::with_capacity(
+            Self::HEADER_SIZE + self.user.len() + self.addr.len() + self.detail.len(),
+        );
+        buf.extend(self.time.to_le_bytes());
+        buf.push(self.kind.value_u8());
+        buf.push(self.succeeded as u8);
+        for field in [&self.user, &self.addr, &self.detail] {
+            buf.extend((field.len() as u64).to_le_bytes());
+        }
+        for field in [&self.user, &self.addr, &self.detail] {
+            buf.extend(field.as_bytes());
+        }
+        buf.into_boxed_slice()
+    }
+    fn decode(payload: &[u8]) -> Option<Self> {
+        let mut scanner = BufferedScanner::new(payload);
+        if !scanner.has_left(Self::HEADER_SIZE) {
+            return None;
+        }
+        let (time, kind, succeeded, lens) = unsafe {
+            // UNSAFE(@ohsayan): +lenck
+            (
+                scanner.next_u64_le(),
+                scanner.next_byte(),
+                scanner.next_byte(),
+                [
+                    scanner.next_u64_le(),
+                    scanner.next_u64_le(),
+                    scanner.next_u64_le(),
+                ],
+            )
+        };
+        let kind = AuditEventKind::try_from_raw(kind)?;
+        if succeeded > 1 {
+            return None;
+        }
+        let mut fields = lens.into_iter().map(|len| {
+            scanner
+                .try_next_variable_block(usize::try_from(len).ok()?)
+                .and_then(|block| core::str::from_utf8(block).ok())
+        });
+        let (user, addr, detail) = (fields.next()??, fields.next()??, fields.next()??);
+        drop(fields);
+        if !scanner.eof() {
+            return None;
+        }
+        Some(Self::new_at(time, kind, succeeded == 1, user, addr, detail))
+    }
+}
+
+/*
+    reading
+*/
+
+/// The events to return when reading the audit log
+#[derive(Debug, PartialEq)]
+pub struct AuditFilter {
+    user: Option<Box<str>>,
+    kind: Option<AuditEventKind>,
+    /// (inclusive; milliseconds since the UNIX epoch)
+    since: Option<u64>,
+    /// (exclusive; milliseconds since the UNIX epoch)
+    until: Option<u64>,
+    /// only the most recent events that match are returned
+    limit: usize,
+}
+
+impl AuditFilter {
+    pub fn new(
+        user: Option<Box<str>>,
+        kind: Option<AuditEventKind>,
+        since: Option<u64>,
+        until: Option<u64>,
+        limit: Option<usize>,
+    ) -> Self {
+        Self {
+            user,
+            kind,
+            since,
+            until,
+            limit: limit.unwrap_or(DEFAULT_READ_LIMIT),
+        }
+    }
+    /// Every event (up to the default limit)
+    pub fn all() -> Self {
+        Self::new(None, None, None, None, None)
+    }
+    /// Doesn't return anything (we still have to decode every event to verify the log)
+    fn none() -> Self {
+        Self::new(None, None, None, None, Some(0))
+    }
+    fn matches(&self, event: &AuditEvent) -> bool {
+        self.user
+            .as_deref()
+            .map_or(true, |user| user == event.user())
+            & self.kind.map_or(true, |kind| kind == event.kind())
+            & self.since.map_or(true, |since| event.time() >= since)
+            & self.until.map_or(true, |until| event.time() < until)
+    }
+}
+
+/// The state that events are decoded into when the audit log is scanned
+pub struct AuditScan {
+    filter: AuditFilter,
+    events: RefCell<VecDeque<AuditEvent>>,
+}
+
+impl AuditScan {
+    fn new(filter: AuditFilter) -> Self {
+        Self {
+            filter,
+            events: RefCell::new(VecDeque::new()),
+        }
+    }
+    fn push(&self, event: AuditEvent) {
+        if self.filter.limit == 0 || !self.filter.matches(&event) {
+            return;
+        }
+        let mut events = self.events.borrow_mut();
+        if events.len() == self.filter.limit {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+}
+
+pub struct AuditLogAdapter;
+
+impl JournalAdapter for AuditLogAdapter {
+    const RECOVERY_PLUGIN: bool = true;
+    type JournalEvent = AuditEvent;
+    type GlobalState = AuditScan;
+    type Error = ();
+    fn encode(event: Self::JournalEvent) -> Box<[u8]> {
+        event.encode()
+    }
+    fn decode_and_update_state(payload: &[u8], gs: &Self::GlobalState) -> Result<(), Self::Error> {
+        gs.push(AuditEvent::decode(payload).ok_or(())?);
+        Ok(())
+    }
+}
+
+/*
+    log
+*/
+
+/// The audit log. Events are synced to disk as soon as they are recorded
+pub struct AuditLog<Fs: RawFSInterface> {
+    path: Box<str>,
+    writer: Mutex<Option<JournalWriter<Fs, AuditLogAdapter>>>,
+}
+
+impl<Fs: RawFSInterface> fmt::Debug for AuditLog<Fs> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Fs: RawFSInterface> AuditLog<Fs> {
+    /// Open the audit log at `path`, creating it if it doesn't exist (and encrypting its events using `encryption`, if
+    /// set). If the server crashed before the log was closed, the event that was being written (if any) is dropped
+    pub fn open(path: &str, encryption: Option<&EncryptionKey>) -> RuntimeResult<Self> {
+        let writer = match Fs::fs_fopen_or_create_rw(path)? {
+            FileOpen::Created(f) => {
+                // the journal driver creates the file itself
+                drop(f);
+                Fs::fs_remove_file(path)?;
+                journal::create_journal::<AuditLogAdapter, Fs, AuditLogV1>(path, encryption)?
+            }
+            FileOpen::Existing(f) => {
+                drop(f);
+                Self::recover(path)?;
+                journal::load_journal::<AuditLogAdapter, Fs, AuditLogV1>(
+                    path,
+                    &AuditScan::new(AuditFilter::none()),
+                )?
+            }
+        };
+        Ok(Self {
+            path: path.into(),
+            writer: Mutex::new(Some(writer)),
+        })
+    }
+    /// If the log wasn't closed, truncate it to the last event that was completely written and close it
+    fn recover(path: &str) -> RuntimeResult<()> {
+        let (scan, _) = Self::scan(path, AuditFilter::none())?;
+        if let Some(e) = scan.error {
+            warn!(
+                "audit log wasn't closed cleanly ({e}). truncating it to {} bytes",
+                scan.consistent_len
+            );
+            journal::truncate_and_close_journal::<Fs>(path, scan.consistent_len, scan.next_id)?;
+        }
+        Ok(())
+    }
+    fn scan(path: &str, filter: AuditFilter) -> RuntimeResult<(JournalScan, AuditScan)> {
+        let (f, header) = SDSSFileIO::<Fs>::open::<AuditLogV1>(path)?;
+        let key = encryption::file_key_for_header(&header)?;
+        let events = AuditScan::new(filter);
+        let scan = JournalReader::<AuditLogAdapter, Fs>::scan(f, &events, key)?;
+        Ok((scan, events))
+    }
+    /// Record an event. Failing to record an event doesn't fail the action that was audited, so errors are only
+    /// logged
+    pub fn record(&self, event: AuditEvent) {
+        let mut writer = self.writer.lock();
+        let Some(writer) = writer.as_mut() else {
+            warn!(
+                "audit log is closed. dropping {} event",
+                event.kind().as_str()
+            );
+            return;
+        };
+        if let Err(e) = writer.append_event_with_recovery_plugin(event) {
+            error!("failed to record audit event: {e}");
+        }
+    }
+    /// Returns the (most recent) events that match the filter, oldest first
+    pub fn read(&self, filter: AuditFilter) -> RuntimeResult<Vec<AuditEvent>> {
+        let writer = self.writer.lock();
+        let (scan, events) = match writer.as_ref() {
+            Some(writer) => {
+                // the log is still open, so read a (closed) copy of it instead
+                let copy_path = format!("{}.read", self.path);
+                writer.backup_to(&self.path, &copy_path)?;
+                let scan = Self::scan(&copy_path, filter);
+                Fs::fs_remove_file(&copy_path)?;
+                scan?
+            }
+            None => Self::scan(&self.path, filter)?,
+        };
+        match scan.error {
+            Some(e) => Err(e),
+            None => Ok(events.events.into_inner().into()),
+        }
+    }
+    /// Close the log. Any events that are recorded after this are dropped
+    pub fn close(&self) -> RuntimeResult<()> {
+        match self.writer.lock().take() {
+            Some(writer) => writer.close(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<Fs: RawFSInterface> Drop for AuditLog<Fs> {
+    fn drop(&mut self) {
+        if let Err(e) = self.close() {
+            error!("failed to close audit log: {e}");
+        }
+    }
+}
@@ -24,9 +24,24 @@
  *
 */
 
+//! Data batch journals
+//!
+//! A model's changes are written to its batch journal in batches. The journal is split into segments: once the active
+//! segment (the journal at the model's path) grows to the segment size, it is sealed and renamed to `data-<seq>.db-btlog`
+//! (with `seq` starting at 1) and a new active segment is started. The active segment records how many sealed segments
+//! came before it in its header, and restoring a model reads all the sealed segments in order before the active
+//! segment. Compacting the journal writes the state of the model into a new active segment that has no sealed
+//! segments before it, after which the old segments are deleted
+
 mod persist;
 mod restore;
 
+/// an encrypted batch (which is compressed before it's encrypted, if the journal uses compression)
+const MARKER_ENCRYPTED_BATCH: u8 = 0xF8;
+/// an lz4 compressed batch
+const MARKER_LZ4_BATCH: u8 = 0xF9;
+/// "real" batch event marker for a batch with per-event checksums
+const MARKER_CHECKED_BATCH_EVENT: u8 = 0xFA;
 /// the data batch file was reopened
 const MARKER_BATCH_REOPEN: u8 = 0xFB;
 /// the data batch file was closed
@@ -38,29 +53,579 @@ const MARKER_ACTUAL_BATCH_EVENT: u8 = 0xFE;
 /// recovery batch event marker
 const MARKER_RECOVERY_EVENT: u8 = 0xFF;
 
+/// the longest a varint encoded u64 can get
+const VARINT_MAX_LEN: usize = 10;
+
+/// Encode `v` as a (LEB128) varint, returning the buffer and the number of bytes of it that were used
+fn encode_varint(mut v: u64) -> ([u8; VARINT_MAX_LEN], usize) {
+    let mut buf = [0; VARINT_MAX_LEN];
+    let mut i = 0;
+    while v >= 0x80 {
+        buf[i] = v as u8 | 0x80;
+        v >>= 7;
+        i += 1;
+    }
+    buf[i] = v as u8;
+    (buf, i + 1)
+}
+
+/// Map a signed delta to an unsigned one so that small deltas (either way) become small varints
+const fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+/// Reverse [`zigzag`]
+const fn unzigzag(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+pub(super) use restore::{restore_snapshot_events, DecodedBatchEvent, DecodedBatchEventKind};
 #[cfg(test)]
-pub(super) use restore::{DecodedBatchEvent, DecodedBatchEventKind, NormalBatch};
-pub use {persist::DataBatchPersistDriver, restore::DataBatchRestoreDriver};
+pub(super) use restore::{BatchStreamItem, NormalBatch};
+pub use {
+    persist::DataBatchPersistDriver,
+    restore::{DataBatchRestoreDriver, DataBatchRestoreReport, RecoveryMode},
+};
 
 use {
-    super::{rw::SDSSFileIO, spec, RawFSInterface},
-    crate::engine::{core::model::Model, error::RuntimeResult},
+    super::{
+        encryption::{self, EncryptionKey, FileBinding},
+        rw::SDSSFileIO,
+        snapshot,
+        spec::{self, BatchCompression, BatchEncoding, SDSSStaticHeaderV1Compact},
+        RawFSInterface,
+    },
+    crate::engine::{
+        core::model::{delta::DeltaVersion, Model},
+        error::RuntimeResult,
+        fractal::startup,
+        storage::ChecksumKind,
+    },
 };
 
 /// Re-initialize an existing batch journal and read all its data into model
+///
+/// If the latest snapshot of the model was taken in this journal, the snapshot is loaded and only what was written to
+/// the journal after it is replayed
+///
+/// A journal that wasn't closed is handled as set by `recovery`. If it had to be recovered (see
+/// [`DataBatchRestoreReport::torn_tail`]), it is truncated to the last complete batch before we start appending to it
+/// again. The sealed segments were closed when they were sealed, so they're always restored strictly
 pub fn reinit<Fs: RawFSInterface>(
     name: &str,
     model: &Model,
+    recovery: RecoveryMode,
+) -> RuntimeResult<(DataBatchPersistDriver<Fs>, DataBatchRestoreReport)> {
+    finish_rotation::<Fs>(name)?;
+    let (mut f, header) = SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(name)?;
+    let compression = BatchCompression::from_header(&header)?;
+    let checksum = spec::checksum_from_header(&header);
+    let encoding = BatchEncoding::from_header(&header);
+    let segment = spec::batch_segment_from_header(&header);
+    let active = (header.epoch_time(), f.retrieve_cursor()?, f.file_length()?);
+    startup::set_total(sealed_len::<Fs>(name, segment)? + active.2);
+    let start = load_snapshot::<Fs>(name, segment, active, model)?;
+    // restore the sealed segments first
+    let (sealed_len, skipped_len, segment_reports) =
+        restore_segments::<Fs>(name, segment, model, None, start)?;
+    let mut snapshot_len = skipped_len;
+    if let Some((seq, offset)) = start {
+        snapshot_len += offset;
+        if seq > segment {
+            f.seek_from_start(offset)?;
+        }
+    }
+    let mut restore_driver = DataBatchRestoreDriver::new(f)?
+        .with_checksum(checksum)
+        .with_encoding(encoding)
+        .with_recovery(recovery)
+        .with_file(FileBinding::new(&header));
+    let mut report = restore_driver.read_data_batch_into_model(model)?;
+    for (seq, segment_report) in segment_reports {
+        report.add_segment(seq, segment_report);
+    }
+    let mut f = restore_driver.into_file()?;
+    if let Some((len, _)) = report.torn_tail() {
+        // drop the partial batch and close the journal, so that it's reopened just like a journal that was closed
+        f.truncate_to(len)?;
+        f.seek_from_start(len)?;
+        f.fsynced_write(&[MARKER_BATCH_CLOSED])?;
+    }
+    let persist_driver = DataBatchPersistDriver::new(f, false)?
+        .with_compression(compression)
+        .with_checksum(checksum)
+        .with_encoding(encoding)
+        .with_encryption(encryption::key_for_header(&header)?)
+        .with_segments(segment, sealed_len)
+        .with_file(FileBinding::new(&header))
+        .with_snapshot_len(snapshot_len);
+    Ok((persist_driver, report))
+}
+
+/// Load the latest snapshot of the model, if it was taken in one of the segments of the batch journal at `name`
+/// (`active` has the epoch of the active segment, and the offsets at which its batches start and end). Returns the
+/// segment (`segment + 1` for the active segment) and the offset in it from which the journal has to be replayed
+///
+/// A snapshot that can't be used is ignored, since we can always replay the whole journal instead
+fn load_snapshot<Fs: RawFSInterface>(
+    name: &str,
+    segment: u32,
+    active: (u128, u64, u64),
+    model: &Model,
+) -> RuntimeResult<Option<(u32, u64)>> {
+    let manifest = match snapshot::read_manifest::<Fs>(name) {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            warn!("ignoring the snapshot manifest of `{name}` since it couldn't be read: {e}");
+            return Ok(None);
+        }
+    };
+    let (epoch, first_batch, end) = active;
+    let mut start = None;
+    if (epoch == manifest.epoch()) & (first_batch..=end).contains(&manifest.offset()) {
+        start = Some(segment + 1);
+    }
+    for seq in 1..=segment {
+        if start.is_some() {
+            break;
+        }
+        let (_, header, first_batch, end) = open_segment::<Fs>(name, seq, segment)?;
+        if (header.epoch_time() == manifest.epoch())
+            & (first_batch..=end).contains(&manifest.offset())
+        {
+            start = Some(seq);
+        }
+    }
+    let Some(seq) = start else {
+        // the journal was replaced (compacted or recovered) after the snapshot was taken
+        return Ok(None);
+    };
+    match snapshot::load::<Fs>(name, &manifest, model) {
+        Ok(()) => Ok(Some((seq, manifest.offset()))),
+        Err(e) => {
+            warn!("ignoring the latest snapshot of `{name}` since it couldn't be loaded: {e}");
+            Ok(None)
+        }
+    }
+}
+
+/// Restore the sealed segments `1..=segment` of the batch journal at `name` into the model (only up to the txn id
+/// `until`, if provided), starting at `start` (a segment and the offset in it) if provided. Returns the total size of
+/// the sealed segments and the size of the sealed segments that were skipped (since they came before `start`), along
+/// with a report for every segment that was restored
+fn restore_segments<Fs: RawFSInterface>(
+    name: &str,
+    segment: u32,
+    model: &Model,
+    until: Option<DeltaVersion>,
+    start: Option<(u32, u64)>,
+) -> RuntimeResult<(u64, u64, Vec<(u32, DataBatchRestoreReport)>)> {
+    let (start, offset) = start.unwrap_or((1, 0));
+    let (mut sealed_len, mut skipped_len) = (0, 0);
+    let mut reports = Vec::new();
+    for seq in 1..=segment {
+        let (mut f, header) =
+            SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(&segment_path(name, seq))?;
+        let len = f.file_length()?;
+        sealed_len += len;
+        if seq < start {
+            skipped_len += len;
+            startup::advance(len);
+            continue;
+        }
+        if seq == start && offset != 0 {
+            f.seek_from_start(offset)?;
+        }
+        let mut restore_driver = DataBatchRestoreDriver::new(f)?
+            .with_checksum(spec::checksum_from_header(&header))
+            .with_encoding(BatchEncoding::from_header(&header))
+            .with_file(FileBinding::new(&header));
+        reports.push((
+            seq,
+            restore_driver.read_data_batch_into_model_until(model, until)?,
+        ));
+    }
+    Ok((sealed_len, skipped_len, reports))
+}
+
+/// Returns the total size of the sealed segments `1..=segment` of the batch journal at `name`
+fn sealed_len<Fs: RawFSInterface>(name: &str, segment: u32) -> RuntimeResult<u64> {
+    let mut sealed_len = 0;
+    for seq in 1..=segment {
+        sealed_len +=
+            SDSSFileIO::<Fs>::_new(Fs::fs_fopen_rw(&segment_path(name, seq))?).file_length()?;
+    }
+    Ok(sealed_len)
+}
+
+/// Returns the path of the sealed segment `seq` (starting at 1) of the batch journal at `name`. For example, the first
+/// sealed segment of `data.db-btlog` is `data-000001.db-btlog`
+pub fn segment_path(name: &str, seq: u32) -> String {
+    let file_name_start = name.rfind('/').map_or(0, |slash| slash + 1);
+    match name[file_name_start..].find('.') {
+        Some(ext) => {
+            let (stem, ext) = name.split_at(file_name_start + ext);
+            format!("{stem}-{seq:06}{ext}")
+        }
+        None => format!("{name}-{seq:06}"),
+    }
+}
+
+/// Re-initialize an existing batch journal, only restoring events up to (and including) the txn id `until`
+///
+/// The recovered state is written into a fresh journal which then replaces the original one (kept at
+/// `{name}.pitr-{until}`, with its sealed segments kept alongside it in the same way), so that the recovered state is
+/// what gets loaded on the next start
+pub fn recover_until<Fs: RawFSInterface>(
+    name: &str,
+    model: &Model,
+    until: u64,
+) -> RuntimeResult<(DataBatchPersistDriver<Fs>, DataBatchRestoreReport)> {
+    finish_rotation::<Fs>(name)?;
+    let (f, header) = SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(name)?;
+    let compression = BatchCompression::from_header(&header)?;
+    let key = encryption::key_for_header(&header)?;
+    let checksum = spec::checksum_from_header(&header);
+    let encoding = BatchEncoding::from_header(&header);
+    let segment = spec::batch_segment_from_header(&header);
+    startup::set_total(sealed_len::<Fs>(name, segment)? + f.file_length()?);
+    let until = DeltaVersion::__new(until);
+    let (_, _, segment_reports) = restore_segments::<Fs>(name, segment, model, Some(until), None)?;
+    let mut restore_driver = DataBatchRestoreDriver::new(f)?
+        .with_checksum(checksum)
+        .with_encoding(encoding)
+        .with_file(FileBinding::new(&header));
+    let mut report = restore_driver.read_data_batch_into_model_until(model, Some(until))?;
+    for (seq, segment_report) in segment_reports {
+        report.add_segment(seq, segment_report);
+    }
+    drop(restore_driver);
+    // write the recovered state into a new journal and only then swap it in
+    let recovered = format!("{name}.pitr-tmp");
+    let mut persist_driver = create::<Fs>(&recovered, compression, key.as_ref())?;
+    persist_driver.write_snapshot_batch(model)?;
+    persist_driver.close()?;
+    let until = until.value_u64();
+    for seq in 1..=segment {
+        let sealed = segment_path(name, seq);
+        Fs::fs_rename_file(&sealed, &format!("{sealed}.pitr-{until}"))?;
+    }
+    Fs::fs_rename_file(name, &format!("{name}.pitr-{until}"))?;
+    Fs::fs_rename_file(&recovered, name)?;
+    // reopen the recovered journal (we already have its data)
+    let persist_driver = open_at_end(name, false)?;
+    Ok((persist_driver, report))
+}
+
+/// Compact the batch journal at `name`, which is currently being written to by `driver`, so that it only holds the latest
+/// state of every row in the model
+///
+/// The live state of the model is written into `{name}.compact-tmp` which then atomically replaces the original journal,
+/// so a crash at any point leaves us with either the old or the compacted journal. The compacted journal uses the given
+/// compression and encryption key and has no sealed segments before it, so the sealed segments of the original journal
+/// are deleted once it has been replaced. The caller must make sure that no batches are written and that no inserts or deletes run while
+/// this is in progress
+pub fn compact<Fs: RawFSInterface>(
+    name: &str,
+    model: &Model,
+    driver: &mut DataBatchPersistDriver<Fs>,
+    compression: BatchCompression,
+    encryption: Option<&EncryptionKey>,
+) -> RuntimeResult<()> {
+    let compacted = format!("{name}.compact-tmp");
+    // a previous compaction might have crashed before the swap, in which case the original journal is intact
+    let _ = Fs::fs_remove_file(&compacted);
+    let mut persist_driver = create::<Fs>(&compacted, compression, encryption)?;
+    persist_driver.write_snapshot_batch(model)?;
+    persist_driver.close()?;
+    /*
+        switch over to the compacted journal so that the old journal is closed before we replace it (which also lets us
+        safely go back to it if the swap fails). the compacted journal isn't marked as reopened since nothing is written
+        to it until it is reopened in place
+    */
+    let state = driver.take_state();
+    let segment = driver.segment();
+    let placeholder = open_file_at_end::<Fs>(&compacted, true)?;
+    let closed = core::mem::replace(driver, placeholder).close();
+    if let Err(e) = closed.and_then(|_| Fs::fs_rename_file(&compacted, name)) {
+        // the original journal is intact (and has everything), so go back to it
+        *driver = open_at_end(name, false)?;
+        driver.restore_state(state);
+        return Err(e);
+    }
+    *driver = open_at_end(name, false)?;
+    driver.restore_state(state);
+    driver.mark_compacted()?;
+    /*
+        the compacted journal doesn't refer to the old segments anymore, so if we fail to delete one of them (or crash
+        while doing so) it's simply left behind (and overwritten once the journal is rotated enough times)
+    */
+    for seq in 1..=segment {
+        let _ = Fs::fs_remove_file(&segment_path(name, seq));
+    }
+    Ok(())
+}
+
+/// Seal the active segment of the batch journal at `name`, which is currently being written to by `driver`, and start a
+/// new active segment
+///
+/// The new segment is created at `{name}.rotate-tmp` first. The active segment is then renamed to the path of the next
+/// sealed segment, and the new segment takes its place. If we crash in between, the rotation is either rolled back or
+/// finished when the journal is next loaded. The caller must make sure that no batches are written while this is in
+/// progress
+pub fn rotate<Fs: RawFSInterface>(
+    name: &str,
+    driver: &mut DataBatchPersistDriver<Fs>,
+) -> RuntimeResult<()> {
+    let seq = driver.segment() + 1;
+    let rotated = rotation_path(name);
+    let _ = Fs::fs_remove_file(&rotated);
+    create_segment::<Fs>(&rotated, driver.compression(), driver.encryption(), seq)?.close()?;
+    // just like in a compaction, close the active segment before we move it
+    let state = driver.take_state();
+    let placeholder = open_file_at_end::<Fs>(&rotated, true)?;
+    let closed = core::mem::replace(driver, placeholder).close();
+    let sealed = segment_path(name, seq);
+    if let Err(e) = closed.and_then(|_| Fs::fs_rename_file(name, &sealed)) {
+        *driver = open_at_end(name, false)?;
+        driver.restore_state(state);
+        return Err(e);
+    }
+    if let Err(e) = Fs::fs_rename_file(&rotated, name) {
+        // move the sealed segment back (if we can't, the rotation is finished when the journal is next loaded)
+        Fs::fs_rename_file(&sealed, name)?;
+        *driver = open_at_end(name, false)?;
+        driver.restore_state(state);
+        return Err(e);
+    }
+    *driver = open_at_end(name, false)?;
+    driver.restore_state(state);
+    Ok(())
+}
+
+fn rotation_path(name: &str) -> String {
+    format!("{name}.rotate-tmp")
+}
+
+/// Finish (or roll back) a rotation of the batch journal at `name` that was interrupted by a crash
+fn finish_rotation<Fs: RawFSInterface>(name: &str) -> RuntimeResult<()> {
+    let rotated = rotation_path(name);
+    if Fs::fs_fopen_rw(&rotated).is_err() {
+        // no rotation was in progress
+        return Ok(());
+    }
+    if Fs::fs_fopen_rw(name).is_ok() {
+        // we crashed before the active segment was sealed, so it's still the active segment
+        Fs::fs_remove_file(&rotated)
+    } else {
+        // we crashed after the active segment was sealed, so the new segment takes its place
+        Fs::fs_rename_file(&rotated, name)
+    }
+}
+
+/// Truncate the batch journal at `name` to `len` bytes and close it
+pub fn truncate_and_close<Fs: RawFSInterface>(name: &str, len: u64) -> RuntimeResult<()> {
+    let mut f = SDSSFileIO::<Fs>::_new(Fs::fs_fopen_rw(name)?);
+    f.truncate_to(len)?;
+    f.seek_from_start(len)?;
+    f.fsynced_write(&[MARKER_BATCH_CLOSED])
+}
+
+/// A part of a batch journal that was read to be shipped to a replica
+#[derive(Debug, PartialEq)]
+pub struct JournalSegment {
+    file: FileBinding,
+    offset: u64,
+    reset: bool,
+    checksum: ChecksumKind,
+    encoding: BatchEncoding,
+    data: Vec<u8>,
+}
+
+impl JournalSegment {
+    pub fn new(
+        file: FileBinding,
+        offset: u64,
+        reset: bool,
+        checksum: ChecksumKind,
+        encoding: BatchEncoding,
+        data: Vec<u8>,
+    ) -> Self {
+        Self {
+            file,
+            offset,
+            reset,
+            checksum,
+            encoding,
+            data,
+        }
+    }
+    /// The time at which the journal was created. This tells journals apart since a journal is replaced (and not
+    /// rewritten in place) when it's compacted
+    pub fn epoch(&self) -> u128 {
+        self.file.id()
+    }
+    /// The journal that this segment was read from (which its encrypted batches are bound to)
+    pub fn file(&self) -> FileBinding {
+        self.file
+    }
+    /// The offset in the journal that this segment starts at
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    /// Returns true if the segment starts at the first batch in the journal (instead of where we were asked to start)
+    /// since the journal isn't the one that we were asked to read
+    pub fn is_reset(&self) -> bool {
+        self.reset
+    }
+    /// The checksum used in the journal that this segment was read from
+    pub fn checksum(&self) -> ChecksumKind {
+        self.checksum
+    }
+    /// The encoding of the integers in the journal that this segment was read from
+    pub fn encoding(&self) -> BatchEncoding {
+        self.encoding
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Read up to `max` bytes from the batch journal at `name`, starting at `offset` in its segment (sealed or active) with
+/// the given epoch
+///
+/// Once the end of a sealed segment is reached, the next segment is read from its first batch. If no segment has the
+/// given epoch (or the offset is invalid), the journal is read from the first batch of its oldest segment instead
+pub fn read_segment<Fs: RawFSInterface>(
+    name: &str,
+    epoch: u128,
+    offset: u64,
+    max: u64,
+) -> RuntimeResult<JournalSegment> {
+    let (_, header) = SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(name)?;
+    let segment = spec::batch_segment_from_header(&header);
+    // the active segment is the one that replicas usually ask for
+    for seq in core::iter::once(segment + 1).chain(1..=segment) {
+        let (f, header, first_batch, end) = open_segment::<Fs>(name, seq, segment)?;
+        if header.epoch_time() != epoch {
+            continue;
+        }
+        let sealed = seq <= segment;
+        if (first_batch..end).contains(&offset) | (!sealed & (offset == end)) {
+            return read_journal_file(f, &header, offset, end, max, false);
+        }
+        if sealed & (offset == end) {
+            // we're done with this segment, so move on to the next one
+            let (f, header, first_batch, end) = open_segment::<Fs>(name, seq + 1, segment)?;
+            return read_journal_file(f, &header, first_batch, end, max, false);
+        }
+        break;
+    }
+    // start over from the oldest segment
+    let (f, header, first_batch, end) = open_segment::<Fs>(name, 1, segment)?;
+    read_journal_file(f, &header, first_batch, end, max, true)
+}
+
+/// Open the sealed segment `seq` of the batch journal at `name` (or its active segment, if `seq` is greater than the
+/// number of sealed segments), returning it along with its header and the offsets at which its batches start and end
+fn open_segment<Fs: RawFSInterface>(
+    name: &str,
+    seq: u32,
+    segment: u32,
+) -> RuntimeResult<(SDSSFileIO<Fs>, SDSSStaticHeaderV1Compact, u64, u64)> {
+    let sealed = seq <= segment;
+    let (mut f, header) = if sealed {
+        SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(&segment_path(name, seq))?
+    } else {
+        SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(name)?
+    };
+    let first_batch = f.retrieve_cursor()?;
+    // a sealed segment always ends with a close marker, which we leave out
+    let end = f.file_length()? - sealed as u64;
+    Ok((f, header, first_batch, end))
+}
+
+/// Read up to `max` bytes (but not past `end`) from a segment of a batch journal, starting at `offset`
+fn read_journal_file<Fs: RawFSInterface>(
+    mut f: SDSSFileIO<Fs>,
+    header: &SDSSStaticHeaderV1Compact,
+    offset: u64,
+    end: u64,
+    max: u64,
+    reset: bool,
+) -> RuntimeResult<JournalSegment> {
+    f.seek_from_start(offset)?;
+    let mut data = vec![0; (end - offset).min(max) as usize];
+    f.read_to_buffer(&mut data)?;
+    Ok(JournalSegment {
+        file: FileBinding::new(header),
+        offset,
+        reset,
+        checksum: spec::checksum_from_header(header),
+        encoding: BatchEncoding::from_header(header),
+        data,
+    })
+}
+
+/// Open an existing batch journal for writing (at its end), without restoring its data
+fn open_at_end<Fs: RawFSInterface>(
+    name: &str,
+    is_new: bool,
 ) -> RuntimeResult<DataBatchPersistDriver<Fs>> {
-    let (f, _header) = SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(name)?;
-    // restore
-    let mut restore_driver = DataBatchRestoreDriver::new(f)?;
-    restore_driver.read_data_batch_into_model(model)?;
-    DataBatchPersistDriver::new(restore_driver.into_file()?, false)
+    let driver = open_file_at_end(name, is_new)?;
+    let segment = driver.segment();
+    // (we don't know when the last snapshot was taken, so we'll take one once enough has been written from here on)
+    let sealed_len = sealed_len::<Fs>(name, segment)?;
+    Ok(driver.with_segments(segment, sealed_len))
 }
 
-/// Create a new batch journal
-pub fn create<Fs: RawFSInterface>(path: &str) -> RuntimeResult<DataBatchPersistDriver<Fs>> {
-    let f = SDSSFileIO::<Fs>::create::<spec::DataBatchJournalV1>(path)?;
-    DataBatchPersistDriver::new(f, true)
+/// Open the file of an existing batch journal for writing (at its end), without looking at its sealed segments
+fn open_file_at_end<Fs: RawFSInterface>(
+    name: &str,
+    is_new: bool,
+) -> RuntimeResult<DataBatchPersistDriver<Fs>> {
+    let (mut f, header) = SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(name)?;
+    let compression = BatchCompression::from_header(&header)?;
+    let segment = spec::batch_segment_from_header(&header);
+    let len = f.file_length()?;
+    f.seek_from_start(len)?;
+    Ok(DataBatchPersistDriver::new(f, is_new)?
+        .with_compression(compression)
+        .with_checksum(spec::checksum_from_header(&header))
+        .with_encoding(BatchEncoding::from_header(&header))
+        .with_encryption(encryption::key_for_header(&header)?)
+        .with_segments(segment, 0)
+        .with_file(FileBinding::new(&header)))
+}
+
+/// Create a new batch journal, with batches compressed using the given compression (and encrypted using the given
+/// key, if any)
+pub fn create<Fs: RawFSInterface>(
+    path: &str,
+    compression: BatchCompression,
+    encryption: Option<&EncryptionKey>,
+) -> RuntimeResult<DataBatchPersistDriver<Fs>> {
+    create_segment(path, compression, encryption, 0)
+}
+
+/// Create a new active segment for a batch journal that has `segment` sealed segments before it
+fn create_segment<Fs: RawFSInterface>(
+    path: &str,
+    compression: BatchCompression,
+    encryption: Option<&EncryptionKey>,
+    segment: u32,
+) -> RuntimeResult<DataBatchPersistDriver<Fs>> {
+    let (f, header) = SDSSFileIO::<Fs>::create_with_format::<spec::DataBatchJournalV1>(
+        path,
+        encryption::header_format(compression.header_format(segment), encryption),
+    )?;
+    Ok(DataBatchPersistDriver::new(f, true)?
+        .with_compression(compression)
+        .with_encryption(encryption.cloned())
+        .with_segments(segment, 0)
+        .with_file(FileBinding::new(&header)))
 }
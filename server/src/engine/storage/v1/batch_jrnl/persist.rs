@@ -28,7 +28,8 @@ use crate::engine::storage::v1::inf::obj::cell;
 
 use {
     super::{
-        MARKER_ACTUAL_BATCH_EVENT, MARKER_BATCH_CLOSED, MARKER_BATCH_REOPEN, MARKER_END_OF_BATCH,
+        MARKER_ACTUAL_BATCH_EVENT, MARKER_BATCH_CLOSED, MARKER_BATCH_REOPEN,
+        MARKER_CHECKED_BATCH_EVENT, MARKER_ENCRYPTED_BATCH, MARKER_END_OF_BATCH, MARKER_LZ4_BATCH,
         MARKER_RECOVERY_EVENT,
     },
     crate::{
@@ -37,24 +38,83 @@ use {
                 index::{PrimaryIndexKey, RowData},
                 model::{
                     delta::{DataDelta, DataDeltaKind, DeltaVersion},
-                    Model,
+                    Field, Model,
                 },
             },
             data::{
                 cell::Datacell,
-                tag::{DataTag, TagUnique},
+                tag::{DataTag, TagClass, TagUnique},
             },
             error::{RuntimeResult, StorageError},
-            idx::STIndexSeq,
-            storage::v1::rw::{RawFSInterface, SDSSFileIO, SDSSFileTrackedWriter},
+            idx::{MTIndexExt, STIndexSeq},
+            storage::{
+                v1::{
+                    durability::{DurabilityPolicy, SyncState},
+                    encryption::{EncryptionKey, FileBinding},
+                    rw::{RawFSInterface, SDSSFileIO, SDSSFileTrackedWriter},
+                    spec::{BatchCompression, BatchEncoding},
+                },
+                ChecksumKind,
+            },
         },
         util::EndianQW,
     },
     crossbeam_epoch::pin,
 };
 
+/// The minimum size a batch journal needs to reach before we bother compacting it
+const COMPACTION_MIN_SIZE: u64 = 8 * 1024 * 1024;
+/// The size at which the active segment of a batch journal is sealed and a new segment is started
+const SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+/// How much disk space we reserve ahead of the end of the journal at a time
+const PREALLOCATION_CHUNK: u64 = 1024 * 1024;
+/// How much needs to be written to the journal after the last snapshot before we take a new one
+const SNAPSHOT_INTERVAL: u64 = 16 * 1024 * 1024;
+
 pub struct DataBatchPersistDriver<Fs: RawFSInterface> {
     f: SDSSFileTrackedWriter<Fs>,
+    /// the size of the journal right after it was last compacted (zero if it wasn't compacted since it was opened)
+    compacted_len: u64,
+    /// if set, new batches are written with a checksum for every event (so that a corrupted event doesn't take the
+    /// rest of its batch with it)
+    event_checksums: bool,
+    /// the compression used for new batches (this must match the format descriptor in the header)
+    compression: BatchCompression,
+    /// how integers are encoded in new batches (this must match the format features in the header)
+    encoding: BatchEncoding,
+    /// the txn id that the txn ids of the events in the current batch are encoded against (with varints)
+    txn_base: u64,
+    /// the key that new batches are encrypted with (this must match the key ID in the header)
+    encryption: Option<EncryptionKey>,
+    /// when new batches are synced to disk
+    sync: SyncState,
+    /// the number of sealed segments that came before this journal (recorded in its header)
+    segment: u32,
+    /// the total size of the sealed segments that came before this journal
+    sealed_len: u64,
+    /// the size at which this journal is sealed as a segment (see [`super::rotate`])
+    segment_size: u64,
+    /// the size up to which disk space was reserved for the journal
+    preallocated: u64,
+    /// the header of the journal, which encrypted batches are bound to. the epoch time recorded in it is the ID of the
+    /// journal (this tells journals apart, since sealed segments and compacted journals replace the journal at the
+    /// same path)
+    file: FileBinding,
+    /// the size of the journal (along with its sealed segments) when the last snapshot was taken
+    snapshot_len: u64,
+    /// the rows deleted since the last snapshot that was taken through this driver (or its predecessors), which an
+    /// incremental snapshot needs. this is unknown if no snapshot was taken since the journal was loaded or compacted
+    deleted_since_snapshot: Option<Vec<(PrimaryIndexKey, DeltaVersion)>>,
+}
+
+#[derive(Debug)]
+/// The state of a driver that carries over to the driver that replaces it (once its journal is rotated or compacted)
+pub(super) struct DriverState {
+    durability: DurabilityPolicy,
+    compacted_len: u64,
+    segment_size: u64,
+    snapshot_len: u64,
+    deleted_since_snapshot: Option<Vec<(PrimaryIndexKey, DeltaVersion)>>,
 }
 
 impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
@@ -64,8 +124,178 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
         }
         Ok(Self {
             f: SDSSFileTrackedWriter::new(file)?,
+            compacted_len: 0,
+            event_checksums: true,
+            compression: BatchCompression::None,
+            encoding: BatchEncoding::NEW,
+            txn_base: 0,
+            encryption: None,
+            sync: SyncState::new(DurabilityPolicy::Always),
+            segment: 0,
+            sealed_len: 0,
+            segment_size: SEGMENT_SIZE,
+            preallocated: 0,
+            file: FileBinding::default(),
+            snapshot_len: 0,
+            deleted_since_snapshot: None,
         })
     }
+    /// Compress new batches
+    pub fn with_compression(mut self, compression: BatchCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+    /// Checksum new batches with the given checksum (by default, the one used in new files). This must be the checksum
+    /// recorded in the header of the journal
+    pub fn with_checksum(mut self, checksum: ChecksumKind) -> Self {
+        self.f.set_checksum(checksum);
+        self
+    }
+    /// Encode the integers in new batches with the given encoding (by default, the one used in new files). This must be
+    /// the encoding recorded in the header of the journal
+    pub fn with_encoding(mut self, encoding: BatchEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+    /// Encrypt new batches with the given key (batches aren't encrypted by default)
+    pub fn with_encryption(mut self, encryption: Option<EncryptionKey>) -> Self {
+        self.encryption = encryption;
+        self
+    }
+    /// Set the sealed segments that came before this journal (there are none by default)
+    pub(super) fn with_segments(mut self, segment: u32, sealed_len: u64) -> Self {
+        self.segment = segment;
+        self.sealed_len = sealed_len;
+        self
+    }
+    /// Set the header of the journal
+    pub(super) fn with_file(mut self, file: FileBinding) -> Self {
+        self.file = file;
+        self
+    }
+    /// Set the size of the journal (along with its sealed segments) when the last snapshot was taken
+    pub(super) fn with_snapshot_len(mut self, snapshot_len: u64) -> Self {
+        self.snapshot_len = snapshot_len;
+        self
+    }
+    /// Seal the journal as a segment once it reaches the given size
+    #[cfg(test)]
+    pub fn with_segment_size(mut self, segment_size: u64) -> Self {
+        self.segment_size = segment_size;
+        self
+    }
+    /// Returns the number of sealed segments that came before this journal
+    pub fn segment(&self) -> u32 {
+        self.segment
+    }
+    /// Returns the epoch time recorded in the header of the journal
+    pub fn epoch(&self) -> u128 {
+        self.file.id()
+    }
+    pub(super) fn compression(&self) -> BatchCompression {
+        self.compression
+    }
+    /// Returns the key that new batches are encrypted with (if any)
+    pub(in crate::engine::storage::v1) fn encryption(&self) -> Option<&EncryptionKey> {
+        self.encryption.as_ref()
+    }
+    /// Take the state that carries over to the driver that replaces this one
+    pub(super) fn take_state(&mut self) -> DriverState {
+        DriverState {
+            durability: self.durability(),
+            compacted_len: self.compacted_len,
+            segment_size: self.segment_size,
+            snapshot_len: self.snapshot_len,
+            deleted_since_snapshot: self.deleted_since_snapshot.take(),
+        }
+    }
+    pub(super) fn restore_state(&mut self, state: DriverState) {
+        self.set_durability(state.durability);
+        self.compacted_len = state.compacted_len;
+        self.segment_size = state.segment_size;
+        self.snapshot_len = state.snapshot_len;
+        self.deleted_since_snapshot = state.deleted_since_snapshot;
+    }
+    /// Set when new batches are synced to disk (every batch is synced by default)
+    pub fn set_durability(&mut self, policy: DurabilityPolicy) {
+        self.sync = SyncState::new(policy);
+    }
+    /// Returns the policy that decides when new batches are synced to disk
+    pub fn durability(&self) -> DurabilityPolicy {
+        self.sync.policy()
+    }
+    /// Sync any batches that weren't synced yet, if the sync interval has elapsed
+    pub fn sync_if_due(&mut self) -> RuntimeResult<()> {
+        if self.sync.sync_due() {
+            self.f.sync_writes()?;
+            self.sync.synced();
+        }
+        Ok(())
+    }
+    /// Write new batches in the legacy format, with a single checksum for the whole batch
+    #[cfg(test)]
+    pub fn without_event_checksums(mut self) -> Self {
+        self.event_checksums = false;
+        self
+    }
+    /// Returns true if the journal (along with its sealed segments) has grown enough since it was last compacted (at
+    /// least twice the compacted size)
+    pub fn needs_compaction(&self) -> RuntimeResult<bool> {
+        let len = self.sealed_len + self.f.file_length()?;
+        Ok(len >= COMPACTION_MIN_SIZE.max(self.compacted_len * 2))
+    }
+    /// Returns true if enough has been written to the journal since the last snapshot that it's worth taking a new one
+    pub fn needs_snapshot(&self) -> RuntimeResult<bool> {
+        let len = self.sealed_len + self.f.file_length()?;
+        Ok(len >= self.snapshot_len + SNAPSHOT_INTERVAL)
+    }
+    /// Returns true if the journal has reached the segment size, and should be sealed
+    pub fn needs_rotation(&self) -> RuntimeResult<bool> {
+        Ok(self.f.file_length()? >= self.segment_size)
+    }
+    /// Returns the current size of the journal on disk
+    pub fn file_length(&self) -> RuntimeResult<u64> {
+        self.f.file_length()
+    }
+    /// Record the current size of the journal as its compacted size (a compacted journal holds the state of the model
+    /// just like a snapshot does)
+    pub(super) fn mark_compacted(&mut self) -> RuntimeResult<()> {
+        self.compacted_len = self.f.file_length()?;
+        self.snapshot_len = self.compacted_len;
+        // the last snapshot was taken in the journal that we just replaced, so it won't be loaded again
+        self.deleted_since_snapshot = None;
+        Ok(())
+    }
+    /// Sync everything that was written to the journal, returning its size (without its sealed segments)
+    pub(in crate::engine::storage::v1) fn sync_all(&mut self) -> RuntimeResult<u64> {
+        self.f.sync_writes()?;
+        self.sync.synced();
+        self.f.file_length()
+    }
+    /// Record that a snapshot was taken when the journal (without its sealed segments) had the given size
+    pub(in crate::engine::storage::v1) fn mark_snapshot(&mut self, len: u64) {
+        self.snapshot_len = self.sealed_len + len;
+        self.deleted_since_snapshot = Some(Vec::new());
+    }
+    /// Returns the rows deleted since the last snapshot, if we've been tracking them since it was taken
+    pub(in crate::engine::storage::v1) fn deleted_since_snapshot(
+        &self,
+    ) -> Option<&[(PrimaryIndexKey, DeltaVersion)]> {
+        self.deleted_since_snapshot.as_deref()
+    }
+    /// Reserve disk space ahead of the end of the journal, so that appending a batch doesn't have to allocate space for
+    /// the file most of the time
+    fn preallocate_ahead(&mut self) {
+        let Ok(len) = self.f.file_length() else {
+            return;
+        };
+        if len < self.preallocated {
+            return;
+        }
+        self.preallocated = (len + PREALLOCATION_CHUNK).min(len.max(self.segment_size));
+        // this is only an optimization, so there's nothing to do if it fails
+        let _ = self.f.preallocate(self.preallocated);
+    }
     pub fn close(self) -> RuntimeResult<()> {
         let mut slf = self.f.into_inner_file()?;
         if slf.fsynced_write(&[MARKER_BATCH_CLOSED]).is_ok() {
@@ -74,6 +304,24 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
             return Err(StorageError::DataBatchCloseError.into());
         }
     }
+    /// Copy this batch journal (stored at `from`) and its sealed segments into `to` and close the copy, so that it can
+    /// be loaded independently
+    pub fn backup_to(&mut self, from: &str, to: &str) -> RuntimeResult<()> {
+        // sealed segments are never written to again
+        for seq in 1..=self.segment {
+            Fs::fs_copy_file(
+                &super::segment_path(from, seq),
+                &super::segment_path(to, seq),
+            )?;
+        }
+        // make sure that all buffered writes have hit the disk before we copy
+        self.f.sync_writes()?;
+        Fs::fs_copy_file(from, to)?;
+        let mut copy = SDSSFileIO::<Fs>::_new(Fs::fs_fopen_rw(to)?);
+        let copy_len = copy.file_length()?;
+        copy.seek_from_start(copy_len)?;
+        copy.fsynced_write(&[MARKER_BATCH_CLOSED])
+    }
     pub fn write_new_batch(&mut self, model: &Model, observed_len: usize) -> RuntimeResult<()> {
         // pin model
         let schema_version = model.delta_state().schema_current_version();
@@ -88,6 +336,7 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
             self.write_batch_start(
                 observed_len,
                 schema_version,
+                model.delta_state().data_current_version(),
                 model.p_tag().tag_unique(),
                 model.fields().len() - 1,
             )?;
@@ -98,6 +347,11 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
                     DataDeltaKind::Delete => {
                         self.write_batch_item_common_row_data(&delta)?;
                         self.encode_pk_only(delta.row().d_key())?;
+                        self.end_block()?;
+                        if let Some(deleted) = self.deleted_since_snapshot.as_mut() {
+                            // (if this batch fails, the delete is republished and recorded again, which is harmless)
+                            deleted.push((delta.row().d_key().clone(), delta.data_version()));
+                        }
                     }
                     DataDeltaKind::Insert | DataDeltaKind::Update => {
                         // resolve deltas (this is yet another opportunity for us to reclaim memory from deleted items)
@@ -116,6 +370,7 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
                         // encode data
                         self.encode_pk_only(delta.row().d_key())?;
                         self.encode_row_data(model, &row_data)?;
+                        self.end_block()?;
                     }
                 }
                 i += 1;
@@ -123,7 +378,10 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
             return self.append_batch_summary_and_sync(observed_len, inconsistent_reads);
         };
         match exec() {
-            Ok(()) => Ok(()),
+            Ok(()) => {
+                self.preallocate_ahead();
+                Ok(())
+            }
             Err(e) => {
                 // republish changes since we failed to commit
                 restore_list.into_iter().for_each(|delta| {
@@ -137,29 +395,82 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
             }
         }
     }
+    /// Write the full state of the model as a single batch
+    ///
+    /// Inserts and deletes must not run concurrently (for example, during recovery or while holding the index latch
+    /// exclusively). Concurrent updates are fine since they are versioned after what we read
+    pub fn write_snapshot_batch(&mut self, model: &Model) -> RuntimeResult<()> {
+        let g = pin();
+        let row_count = model.primary_index().count();
+        self.write_batch_start(
+            row_count,
+            model.delta_state().schema_current_version(),
+            model.delta_state().data_current_version(),
+            model.p_tag().tag_unique(),
+            model.fields().len() - 1,
+        )?;
+        for row in model.primary_index().__raw_index().mt_iter_entry(&g) {
+            let row_data = row.resolve_schema_deltas_and_freeze(model.delta_state());
+            // every row is written as a fresh insert, keeping the txn id of its latest revision
+            let txn_id = row_data
+                .get_restored_txn_revised()
+                .max(row_data.get_txn_revised());
+            self.f
+                .tracked_write_unfsynced(&[DataDeltaKind::Insert.value_u8()])?;
+            self.write_txn_id(txn_id)?;
+            self.encode_pk_only(row.d_key())?;
+            self.encode_row_data(model, &row_data)?;
+            self.end_block()?;
+        }
+        self.append_batch_summary_and_sync(row_count, 0)
+    }
     /// Write the batch start block:
     /// - Batch start magic
     /// - Primary key type
     /// - Expected commit
     /// - Schema version
     /// - Column count
+    /// - Base txn id (only with varints)
+    /// - Checksum of the block (only with event checksums)
     fn write_batch_start(
         &mut self,
         observed_len: usize,
         schema_version: DeltaVersion,
+        txn_base: DeltaVersion,
         pk_tag: TagUnique,
         col_cnt: usize,
     ) -> RuntimeResult<()> {
+        if (self.compression != BatchCompression::None) | self.encryption.is_some() {
+            // stage the batch so that we can compress (or encrypt) it when it's complete
+            self.f.begin_block();
+        }
+        let batch_marker = if self.event_checksums {
+            MARKER_CHECKED_BATCH_EVENT
+        } else {
+            MARKER_ACTUAL_BATCH_EVENT
+        };
         self.f
-            .tracked_write_unfsynced(&[MARKER_ACTUAL_BATCH_EVENT, pk_tag.value_u8()])?;
-        let observed_len_bytes = observed_len.u64_bytes_le();
-        self.f.tracked_write_unfsynced(&observed_len_bytes)?;
-        self.f
-            .tracked_write_unfsynced(&schema_version.value_u64().to_le_bytes())?;
-        self.f.tracked_write_unfsynced(&col_cnt.u64_bytes_le())?;
+            .tracked_write_unfsynced(&[batch_marker, pk_tag.value_u8()])?;
+        self.write_uint(observed_len as u64)?;
+        self.write_uint(schema_version.value_u64())?;
+        self.write_uint(col_cnt as u64)?;
+        if self.encoding == BatchEncoding::Varint {
+            self.txn_base = txn_base.value_u64();
+            self.write_uint(self.txn_base)?;
+        }
+        self.end_block()
+    }
+    /// If we're using event checksums, append the checksum of everything written since the last checksum (the batch
+    /// start block or a single event)
+    fn end_block(&mut self) -> RuntimeResult<()> {
+        if self.event_checksums {
+            let cs = self.f.reset_and_finish_checksum().to_le_bytes();
+            self.f.untracked_write(&cs)?;
+        }
         Ok(())
     }
-    /// Append a summary of this batch and most importantly, **sync everything to disk**
+    /// Append a summary of this batch and most importantly, **sync everything to disk** (or at least hand it to the OS,
+    /// depending on the durability policy)
     fn append_batch_summary_and_sync(
         &mut self,
         observed_len: usize,
@@ -167,14 +478,57 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
     ) -> RuntimeResult<()> {
         // [0xFD][actual_commit][checksum]
         self.f.tracked_write_unfsynced(&[MARKER_END_OF_BATCH])?;
-        let actual_commit = (observed_len - inconsistent_reads).u64_bytes_le();
-        self.f.tracked_write_unfsynced(&actual_commit)?;
+        self.write_uint((observed_len - inconsistent_reads) as u64)?;
         let cs = self.f.reset_and_finish_checksum().to_le_bytes();
         self.f.untracked_write(&cs)?;
+        self.write_staged_batch()?;
         // IMPORTANT: now that all data has been written, we need to actually ensure that the writes pass through the cache
-        self.f.sync_writes()?;
+        if self.sync.commit() {
+            self.f.sync_writes()?;
+            self.sync.synced();
+        } else {
+            self.f.flush_writes()?;
+        }
         Ok(())
     }
+    /// If we're compressing batches, compress the staged batch:
+    /// - Compressed batch magic
+    /// - Uncompressed size
+    /// - Compressed size
+    /// - Compressed batch
+    ///
+    /// If we're encrypting batches, the (possibly compressed) batch is then encrypted:
+    /// - Encrypted batch magic
+    /// - Key ID
+    /// - Encrypted size
+    /// - Encrypted batch (nonce, ciphertext and tag), bound to the journal and to the offset of the magic
+    ///
+    /// Finally, the batch is written out
+    fn write_staged_batch(&mut self) -> RuntimeResult<()> {
+        if (self.compression == BatchCompression::None) & self.encryption.is_none() {
+            // nothing was staged
+            return Ok(());
+        }
+        let mut batch = self.f.take_block();
+        if self.compression == BatchCompression::Lz4 {
+            let compressed = lz4_flex::block::compress(&batch);
+            let mut frame = Vec::with_capacity(1 + sizeof!(u64, 2) + compressed.len());
+            frame.push(MARKER_LZ4_BATCH);
+            frame.extend_from_slice(&batch.len().u64_bytes_le());
+            frame.extend_from_slice(&compressed.len().u64_bytes_le());
+            frame.extend_from_slice(&compressed);
+            batch = frame;
+        }
+        let Some(key) = self.encryption.as_ref() else {
+            return self.f.untracked_write(&batch);
+        };
+        // (the key ID lets the batch be read without opening the journal, such as when it's shipped to a replica)
+        let sealed = key.seal(&batch, &self.file, self.f.cursor())?;
+        self.f.untracked_write(&[MARKER_ENCRYPTED_BATCH])?;
+        self.f.untracked_write(&key.id().to_le_bytes())?;
+        self.f.untracked_write(&sealed.len().u64_bytes_le())?;
+        self.f.untracked_write(&sealed)
+    }
     /// Attempt to fix the batch journal
     // TODO(@ohsayan): declare an "international system disaster" when this happens
     fn attempt_fix_data_batchfile(&mut self) -> RuntimeResult<()> {
@@ -182,6 +536,8 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
             attempt to append 0xFF to the part of the file where a corruption likely occurred, marking
             it recoverable
         */
+        // if the batch was being staged, none of it made it to the file
+        let _ = self.f.take_block();
         if self.f.untracked_write(&[MARKER_RECOVERY_EVENT]).is_ok() {
             return Ok(());
         }
@@ -192,7 +548,6 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
 impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
     /// encode the primary key only. this means NO TAG is encoded.
     fn encode_pk_only(&mut self, pk: &PrimaryIndexKey) -> RuntimeResult<()> {
-        let buf = &mut self.f;
         match pk.tag() {
             TagUnique::UnsignedInt | TagUnique::SignedInt => {
                 let data = unsafe {
@@ -200,16 +555,15 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
                     pk.read_uint()
                 }
                 .to_le_bytes();
-                buf.tracked_write_unfsynced(&data)?;
+                self.f.tracked_write_unfsynced(&data)?;
             }
             TagUnique::Str | TagUnique::Bin => {
                 let slice = unsafe {
                     // UNSAFE(@ohsayan): +tagck
                     pk.read_bin()
                 };
-                let slice_l = slice.len().u64_bytes_le();
-                buf.tracked_write_unfsynced(&slice_l)?;
-                buf.tracked_write_unfsynced(slice)?;
+                self.write_uint(slice.len() as u64)?;
+                self.f.tracked_write_unfsynced(slice)?;
             }
             TagUnique::Illegal => unsafe {
                 // UNSAFE(@ohsayan): a pk can't be constructed with illegal
@@ -218,19 +572,22 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
         }
         Ok(())
     }
-    /// Encode a single cell
-    fn encode_cell(&mut self, value: &Datacell) -> RuntimeResult<()> {
+    /// Encode the value of a field
+    fn encode_cell(&mut self, field: &Field, value: &Datacell) -> RuntimeResult<()> {
         let mut buf = vec![];
-        cell::encode(&mut buf, value);
+        match self.encoding {
+            BatchEncoding::Fixed => cell::encode_field(&mut buf, field, value),
+            BatchEncoding::Varint => encode_field_varint(&mut buf, field, value),
+        }
         self.f.tracked_write_unfsynced(&buf)?;
         Ok(())
     }
     /// Encode row data
     fn encode_row_data(&mut self, model: &Model, row_data: &RowData) -> RuntimeResult<()> {
-        for field_name in model.fields().stseq_ord_key() {
+        for (field_name, field) in model.fields().stseq_ord_kv() {
             match row_data.fields().get(field_name) {
                 Some(cell) => {
-                    self.encode_cell(cell)?;
+                    self.encode_cell(field, cell)?;
                 }
                 None if field_name.as_str() == model.p_key() => {}
                 None => self.f.tracked_write_unfsynced(&[0])?,
@@ -242,8 +599,84 @@ impl<Fs: RawFSInterface> DataBatchPersistDriver<Fs> {
     fn write_batch_item_common_row_data(&mut self, delta: &DataDelta) -> RuntimeResult<()> {
         let change_type = [delta.change().value_u8()];
         self.f.tracked_write_unfsynced(&change_type)?;
-        let txn_id = delta.data_version().value_u64().to_le_bytes();
-        self.f.tracked_write_unfsynced(&txn_id)?;
-        Ok(())
+        self.write_txn_id(delta.data_version())
+    }
+    /// Write the txn id of an event (with varints, this is the delta from the base txn id of the batch)
+    fn write_txn_id(&mut self, txn_id: DeltaVersion) -> RuntimeResult<()> {
+        let txn_id = match self.encoding {
+            BatchEncoding::Fixed => txn_id.value_u64(),
+            BatchEncoding::Varint => {
+                super::zigzag(txn_id.value_u64().wrapping_sub(self.txn_base) as i64)
+            }
+        };
+        self.write_uint(txn_id)
+    }
+    /// Write an integer (a length, count or txn id) in the encoding used by the journal
+    fn write_uint(&mut self, v: u64) -> RuntimeResult<()> {
+        match self.encoding {
+            BatchEncoding::Fixed => self.f.tracked_write_unfsynced(&v.to_le_bytes()),
+            BatchEncoding::Varint => {
+                let (buf, len) = super::encode_varint(v);
+                self.f.tracked_write_unfsynced(&buf[..len])
+            }
+        }
+    }
+}
+
+/// Encode the value of a field like [`cell::encode_field`], except that lengths are varints (see [`encode_cell_varint`])
+fn encode_field_varint(buf: &mut Vec<u8>, field: &Field, dc: &Datacell) {
+    match (field.dict_schema(), dc.try_dict()) {
+        (Some(schema), Some(dict)) => {
+            buf.push(cell::StorageCellTypeID::TypedDict.value_u8());
+            let (len, len_l) = super::encode_varint(schema.fields().len() as u64);
+            buf.extend_from_slice(&len[..len_l]);
+            for value in schema.values(dict) {
+                match value {
+                    Some(value) => encode_cell_varint(buf, value),
+                    None => buf.push(cell::StorageCellTypeID::Null.value_u8()),
+                }
+            }
+        }
+        _ => encode_cell_varint(buf, dc),
+    }
+}
+
+/// Encode a cell like [`cell::encode`], except that the lengths of strings, binaries, lists and dicts (and of the keys
+/// in them) are varints
+fn encode_cell_varint(buf: &mut Vec<u8>, dc: &Datacell) {
+    buf.push(cell::encode_tag(dc));
+    if dc.is_null() {
+        return;
+    }
+    unsafe {
+        // UNSAFE(@ohsayan): +tagck
+        match dc.tag().tag_class() {
+            TagClass::Str | TagClass::Bin => {
+                let slc = dc.read_bin();
+                let (len, len_l) = super::encode_varint(slc.len() as u64);
+                buf.extend_from_slice(&len[..len_l]);
+                buf.extend_from_slice(slc);
+            }
+            TagClass::List => {
+                let lst = dc.read_list().read();
+                let (len, len_l) = super::encode_varint(lst.len() as u64);
+                buf.extend_from_slice(&len[..len_l]);
+                for item in lst.iter() {
+                    encode_cell_varint(buf, item);
+                }
+            }
+            TagClass::Dict => {
+                let dict = dc.read_dict();
+                let (len, len_l) = super::encode_varint(dict.len() as u64);
+                buf.extend_from_slice(&len[..len_l]);
+                for (key, value) in dict {
+                    let (len, len_l) = super::encode_varint(key.len() as u64);
+                    buf.extend_from_slice(&len[..len_l]);
+                    buf.extend_from_slice(key.as_bytes());
+                    encode_cell_varint(buf, value);
+                }
+            }
+            _ => cell::encode_cell(buf, dc),
+        }
     }
 }
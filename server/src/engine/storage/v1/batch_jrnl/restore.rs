@@ -31,18 +31,28 @@ use crate::engine::storage::v1::inf::{
 
 use {
     super::{
-        MARKER_ACTUAL_BATCH_EVENT, MARKER_BATCH_CLOSED, MARKER_BATCH_REOPEN, MARKER_END_OF_BATCH,
-        MARKER_RECOVERY_EVENT,
+        MARKER_ACTUAL_BATCH_EVENT, MARKER_BATCH_CLOSED, MARKER_BATCH_REOPEN,
+        MARKER_CHECKED_BATCH_EVENT, MARKER_ENCRYPTED_BATCH, MARKER_END_OF_BATCH, MARKER_LZ4_BATCH,
+        MARKER_RECOVERY_EVENT, VARINT_MAX_LEN,
     },
     crate::engine::{
         core::{
+            dml::ReplicatedChange,
             index::{DcFieldIndex, PrimaryIndexKey, Row},
             model::{delta::DeltaVersion, Model},
         },
-        data::{cell::Datacell, tag::TagUnique},
+        data::{cell::Datacell, dict::DictCell, tag::TagUnique},
         error::{RuntimeResult, StorageError},
-        idx::{MTIndex, STIndex, STIndexSeq},
-        storage::v1::rw::{RawFSInterface, SDSSFileIO, SDSSFileTrackedReader},
+        fractal::startup,
+        idx::{MTIndex, MTIndexExt, STIndex, STIndexSeq},
+        storage::{
+            v1::{
+                encryption::{self, FileBinding},
+                rw::{RawFSInterface, SDSSFileIO, SDSSFileTrackedReader},
+                spec::BatchEncoding,
+            },
+            ChecksumKind,
+        },
     },
     std::{
         collections::{hash_map::Entry as HMEntry, HashMap},
@@ -78,12 +88,14 @@ pub(in crate::engine::storage::v1) enum DecodedBatchEventKind {
     Update(Vec<Datacell>),
 }
 
+#[cfg(test)]
 #[derive(Debug, PartialEq)]
 pub(in crate::engine::storage::v1) struct NormalBatch {
     events: Vec<DecodedBatchEvent>,
     schema_version: u64,
 }
 
+#[cfg(test)]
 impl NormalBatch {
     pub(in crate::engine::storage::v1) fn new(
         events: Vec<DecodedBatchEvent>,
@@ -96,70 +108,276 @@ impl NormalBatch {
     }
 }
 
+/// An item yielded while streaming the batches in a data batch journal
+#[derive(Debug, PartialEq)]
+pub(in crate::engine::storage::v1) enum BatchStreamItem {
+    /// a new batch was started; every event up to the next commit (or discard) belongs to it
+    Begin { schema_version: u64 },
+    /// an event in the current batch
+    Event(DecodedBatchEvent),
+    /// the current batch was read to the end and verified
+    Commit,
+    /// the current batch (if any) couldn't be read to the end, but the writer left a recovery marker (or it was cut
+    /// short by the end of the journal, see [`RecoveryMode::TruncateTail`])
+    Discard,
+}
+
+/// What to do when the journal ends without being closed, which happens if we crashed (or lost power) while the
+/// journal was open
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+pub enum RecoveryMode {
+    /// Refuse to restore the journal, so that it can be inspected (and repaired) first
+    #[serde(rename = "strict")]
+    Strict,
+    /// Discard the last batch if it was only partially written (the journal ends before the batch does, and no complete
+    /// batch follows it) and truncate the journal to the batches before it. A batch that's corrupted anywhere else is
+    /// still an error
+    #[serde(rename = "truncate")]
+    TruncateTail,
+}
+
+/// A report of everything that had to be skipped while restoring a data batch journal
+#[derive(Debug, Default, PartialEq)]
+pub struct DataBatchRestoreReport {
+    corrupted_events: Vec<u64>,
+    corrupted_segment_events: Vec<(u32, u64)>,
+    torn_tail: Option<(u64, u64)>,
+}
+
+impl DataBatchRestoreReport {
+    /// Returns true if nothing was skipped
+    #[cfg(test)]
+    pub fn is_clean(&self) -> bool {
+        self.corrupted_events.is_empty()
+            & self.corrupted_segment_events.is_empty()
+            & self.torn_tail.is_none()
+    }
+    /// If the journal wasn't closed (see [`RecoveryMode::TruncateTail`]), the offset that it has to be truncated to and
+    /// the number of bytes of a partially written batch after it that were discarded (if any)
+    pub fn torn_tail(&self) -> Option<(u64, u64)> {
+        self.torn_tail
+    }
+    /// The file offsets of the events that were skipped because they were corrupted (for an event in a compressed batch,
+    /// this is the offset of the batch)
+    pub fn corrupted_events(&self) -> &[u64] {
+        &self.corrupted_events
+    }
+    /// The sealed segments and file offsets of the events in sealed segments that were skipped because they were
+    /// corrupted (see [`Self::corrupted_events`])
+    pub fn corrupted_segment_events(&self) -> &[(u32, u64)] {
+        &self.corrupted_segment_events
+    }
+    /// Add everything that was skipped while restoring the sealed segment `seq`
+    pub(super) fn add_segment(&mut self, seq: u32, segment: Self) {
+        self.corrupted_segment_events.extend(
+            segment
+                .corrupted_events
+                .into_iter()
+                .map(|offset| (seq, offset)),
+        );
+    }
+}
+
 enum Batch {
     RecoveredFromerror,
-    Normal(NormalBatch),
-    FinishedEarly(NormalBatch),
+    /// the batch was read (with the number of events that were read, including skipped ones)
+    Normal(u64),
+    FinishedEarly(u64),
     BatchClosed,
 }
 
 pub struct DataBatchRestoreDriver<F: RawFSInterface> {
     f: SDSSFileTrackedReader<F>,
+    report: DataBatchRestoreReport,
+    /// the length of the journal up to the last batch (or directive) that was read successfully
+    consistent_len: u64,
+    /// how integers are encoded in the batches
+    encoding: BatchEncoding,
+    /// what to do if the journal wasn't closed
+    recovery: RecoveryMode,
+    /// the journal that encrypted batches are bound to
+    file: FileBinding,
 }
 
 impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
     pub fn new(f: SDSSFileIO<F>) -> RuntimeResult<Self> {
+        let f = SDSSFileTrackedReader::new(f)?;
         Ok(Self {
-            f: SDSSFileTrackedReader::new(f)?,
+            consistent_len: f.cursor(),
+            f,
+            report: DataBatchRestoreReport::default(),
+            encoding: BatchEncoding::NEW,
+            recovery: RecoveryMode::Strict,
+            file: FileBinding::default(),
         })
     }
+    /// Read a segment of a batch journal (that starts at `offset` in the journal) from memory instead of from the file
+    pub fn new_segment(segment: Vec<u8>, offset: u64) -> Self {
+        let f = SDSSFileTrackedReader::new_segment(segment, offset);
+        Self {
+            consistent_len: f.cursor(),
+            f,
+            report: DataBatchRestoreReport::default(),
+            encoding: BatchEncoding::NEW,
+            recovery: RecoveryMode::Strict,
+            file: FileBinding::default(),
+        }
+    }
+    /// Verify the batches with the given checksum (by default, the one used in new files). This must be the checksum
+    /// recorded in the header of the journal
+    pub fn with_checksum(mut self, checksum: ChecksumKind) -> Self {
+        self.f.set_checksum(checksum);
+        self
+    }
+    /// Decode the integers in the batches with the given encoding (by default, the one used in new files). This must be
+    /// the encoding recorded in the header of the journal
+    pub fn with_encoding(mut self, encoding: BatchEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+    /// Open encrypted batches as batches of the given journal. This must be the journal that we're reading from (or
+    /// that the segment was read from)
+    pub fn with_file(mut self, file: FileBinding) -> Self {
+        self.file = file;
+        self
+    }
+    /// Handle a journal that wasn't closed as set by `recovery` (by default, it's an error)
+    pub fn with_recovery(mut self, recovery: RecoveryMode) -> Self {
+        self.recovery = recovery;
+        self
+    }
     pub fn into_file(self) -> RuntimeResult<SDSSFileIO<F>> {
         self.f.into_inner_file()
     }
+    /// Returns the segment that we were reading from (see [`Self::new_segment`])
+    pub fn into_segment(self) -> Vec<u8> {
+        self.f.into_segment()
+    }
+    /// Apply every batch that can be read to the end to the (live) model, returning the offset in the journal up to
+    /// which the batches were applied
+    ///
+    /// This is used to apply the journal of a model on the primary to the same model on a replica. The primary might
+    /// still be writing to its journal, so the last batch can be incomplete, in which case it's left for when the rest
+    /// of it is available. Unlike a restore, the changes are applied like any other write to the model (so they're
+    /// written to the replica's own journal). If a batch doesn't match the model, the batches before it are still
+    /// applied
+    pub fn apply_batch(&mut self, model: &Model) -> RuntimeResult<u64> {
+        let mut batch = vec![];
+        let mut applied = Ok(());
+        /*
+            if we can't read any further, it's because the rest hasn't been written yet (a batch that is corrupted for
+            good would also stop the primary from restoring its journal), so we just stop at the last batch we could read
+        */
+        let _ = self.stream_batches(|item| match item {
+            BatchStreamItem::Begin { .. } | BatchStreamItem::Discard => batch.clear(),
+            BatchStreamItem::Event(event) => batch.push(event),
+            BatchStreamItem::Commit if applied.is_ok() => {
+                // events in a batch aren't ordered by txn id
+                batch.sort_by_key(|event: &DecodedBatchEvent| event.txn_id);
+                let changes = batch
+                    .drain(..)
+                    .map(|DecodedBatchEvent { pk, kind, .. }| match kind {
+                        DecodedBatchEventKind::Delete => ReplicatedChange::Delete(pk),
+                        DecodedBatchEventKind::Insert(row) | DecodedBatchEventKind::Update(row) => {
+                            ReplicatedChange::Upsert(pk, row)
+                        }
+                    })
+                    .collect();
+                applied = model.apply_replicated_batch(changes);
+            }
+            BatchStreamItem::Commit => {}
+        });
+        applied.map(|_| self.consistent_len)
+    }
     pub(in crate::engine::storage::v1) fn read_data_batch_into_model(
         &mut self,
         model: &Model,
-    ) -> RuntimeResult<()> {
-        self.read_all_batches_and_for_each(|batch| {
-            // apply the batch
-            Self::apply_batch(model, batch)
-        })
+    ) -> RuntimeResult<DataBatchRestoreReport> {
+        self.read_data_batch_into_model_until(model, None)
+    }
+    /// Read all batches into the model, skipping any event with a txn id greater than `until` (if provided)
+    pub(in crate::engine::storage::v1) fn read_data_batch_into_model_until(
+        &mut self,
+        model: &Model,
+        until: Option<DeltaVersion>,
+    ) -> RuntimeResult<DataBatchRestoreReport> {
+        let mut applier = BatchApplier::new(model, until);
+        self.stream_batches(|item| applier.apply(item))?;
+        if let Some(last_txn_id) = applier.last_txn_id {
+            // new deltas must be versioned after everything we've seen in the journal
+            model.delta_state().__restore_data_version(last_txn_id);
+        }
+        Ok(core::mem::take(&mut self.report))
+    }
+    /// Read all batches without restoring anything, returning everything that had to be skipped along with the error
+    /// that stopped us from reading the journal to the end (if any; see [`Self::consistent_len`])
+    pub(in crate::engine::storage::v1) fn scan(
+        &mut self,
+    ) -> (DataBatchRestoreReport, RuntimeResult<()>) {
+        let ret = self.stream_batches(|_| {});
+        (core::mem::take(&mut self.report), ret)
+    }
+    /// The length of the journal up to the last batch (or directive) that was read successfully
+    pub(in crate::engine::storage::v1) fn consistent_len(&self) -> u64 {
+        self.consistent_len
     }
     #[cfg(test)]
     pub(in crate::engine::storage::v1) fn read_all_batches(
         &mut self,
     ) -> RuntimeResult<Vec<NormalBatch>> {
         let mut all_batches = vec![];
-        self.read_all_batches_and_for_each(|batch| {
-            all_batches.push(batch);
-            Ok(())
+        let mut current = NormalBatch::new(vec![], 0);
+        self.stream_batches(|item| match item {
+            BatchStreamItem::Begin { schema_version } => {
+                current = NormalBatch::new(vec![], schema_version)
+            }
+            BatchStreamItem::Event(event) => current.events.push(event),
+            BatchStreamItem::Commit => all_batches.push(core::mem::replace(
+                &mut current,
+                NormalBatch::new(vec![], 0),
+            )),
+            BatchStreamItem::Discard => current.events.clear(),
         })?;
         Ok(all_batches)
     }
 }
 
 impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
-    fn read_all_batches_and_for_each(
+    /// Read all batches, passing each event to `f` as soon as it is decoded (instead of buffering whole batches)
+    ///
+    /// Events are yielded before the batch they belong to is verified, so `f` must be prepared to see a batch be
+    /// discarded after some of its events were yielded. This is only possible when the writer failed midway and left
+    /// a recovery marker, in which case it republishes the events of that batch and they'll show up again in a later
+    /// batch. Any other corruption in a batch is an error
+    pub(in crate::engine::storage::v1) fn stream_batches(
         &mut self,
-        mut f: impl FnMut(NormalBatch) -> RuntimeResult<()>,
+        mut f: impl FnMut(BatchStreamItem),
     ) -> RuntimeResult<()> {
         // begin
         let mut closed = false;
-        while !self.f.is_eof() && !closed {
+        let mut torn = false;
+        // everything before the cursor (the header, or what a snapshot let us skip) counts as restored
+        let mut reported = 0;
+        while !self.f.is_eof() && !closed && !torn {
+            // everything up to here was read successfully
+            self.consistent_len = self.f.cursor();
+            startup::advance(self.consistent_len - reported);
+            reported = self.consistent_len;
             self.f.__reset_checksum();
+            self.f.reset_ran_out();
             // try to decode this batch
-            let Ok(batch) = self.read_batch() else {
-                self.attempt_recover_data_batch()?;
+            let Ok(batch) = self.read_batch(&mut f) else {
+                torn = self.recover_and_discard(&mut f)?;
                 continue;
             };
             // see what happened when decoding it
-            let finished_early = matches!(batch, Batch::FinishedEarly { .. });
-            let batch = match batch {
+            let (events_read, finished_early) = match batch {
                 Batch::RecoveredFromerror => {
                     // there was an error, but it was safely "handled" because of a recovery byte mark
                     continue;
                 }
-                Batch::FinishedEarly(batch) | Batch::Normal(batch) => batch,
+                Batch::Normal(events_read) => (events_read, false),
+                Batch::FinishedEarly(events_read) => (events_read, true),
                 Batch::BatchClosed => {
                     // the batch was closed; this means that we probably are done with this round; but was it re-opened?
                     closed = self.handle_reopen_is_actual_close()?;
@@ -168,27 +386,94 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
             };
             // now we need to read the batch summary
             let Ok(actual_commit) = self.read_batch_summary(finished_early) else {
-                self.attempt_recover_data_batch()?;
+                torn = self.recover_and_discard(&mut f)?;
                 continue;
             };
-            // check if we have the expected batch size
-            if batch.events.len() as u64 != actual_commit {
+            // check if we have the expected batch size (and that a compressed batch had nothing else in it)
+            if (events_read != actual_commit) | !self.f.leave_block() {
                 // corrupted
-                self.attempt_recover_data_batch()?;
+                torn = self.recover_and_discard(&mut f)?;
                 continue;
             }
-            f(batch)?;
-            // apply the batch
+            f(BatchStreamItem::Commit);
         }
+        if !(closed | torn) {
+            self.consistent_len = self.f.cursor();
+        }
+        startup::advance(self.f.cursor() + self.f.remaining() - reported);
         if closed {
             if self.f.is_eof() {
                 // that was the last batch
                 return Ok(());
             }
+        } else if self.recovery == RecoveryMode::TruncateTail {
+            // we crashed with the journal open, so we just pick up after the last complete batch
+            let end = self.f.cursor() + self.f.remaining();
+            self.report.torn_tail = Some((self.consistent_len, end - self.consistent_len));
+            return Ok(());
         }
         // nope, this is a corrupted file
         Err(StorageError::DataBatchRestoreCorruptedBatchFile.into())
     }
+    /// Discard the batch that we failed to read, returning true if it was the torn tail of the journal (in which case
+    /// there's nothing left to read)
+    fn recover_and_discard(&mut self, f: &mut impl FnMut(BatchStreamItem)) -> RuntimeResult<bool> {
+        // if we were reading a compressed batch, the recovery marker follows it in the file
+        let _ = self.f.leave_block();
+        if (self.recovery == RecoveryMode::TruncateTail) & self.f.ran_out() {
+            /*
+                we needed more than what's left in the journal. the writer might have died while appending this batch,
+                but a corrupted length asks for too much as well. in that case there are good batches after this one,
+                and we can't throw those away
+            */
+            let start = self.consistent_len;
+            let tail = self.f.read_tail_from(start)?;
+            if self.has_batch_in(tail, start) {
+                return Err(StorageError::DataBatchRestoreCorruptedBatch.into());
+            }
+            // so the journal really ends before this batch does
+            f(BatchStreamItem::Discard);
+            return Ok(true);
+        }
+        self.attempt_recover_data_batch()?;
+        f(BatchStreamItem::Discard);
+        Ok(false)
+    }
+    /// Returns true if a complete batch (that passes its checksum) starts anywhere after the first byte of `tail`, which
+    /// was read from `offset` in the journal
+    fn has_batch_in(&self, tail: Vec<u8>, offset: u64) -> bool {
+        let candidates: Vec<_> = tail
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, b)| {
+                matches!(
+                    **b,
+                    MARKER_ACTUAL_BATCH_EVENT
+                        | MARKER_CHECKED_BATCH_EVENT
+                        | MARKER_LZ4_BATCH
+                        | MARKER_ENCRYPTED_BATCH
+                )
+            })
+            .map(|(i, _)| offset + i as u64)
+            .collect();
+        let mut scan = Self::new_segment(tail, offset)
+            .with_checksum(self.f.checksum_kind())
+            .with_encoding(self.encoding)
+            .with_file(self.file);
+        candidates.into_iter().any(|candidate| {
+            scan.f.seek_in_segment(candidate);
+            scan.f.__reset_checksum();
+            let (events_read, finished_early) = match scan.read_batch(&mut |_| {}) {
+                Ok(Batch::Normal(events_read)) => (events_read, false),
+                Ok(Batch::FinishedEarly(events_read)) => (events_read, true),
+                _ => return false,
+            };
+            scan.read_batch_summary(finished_early)
+                .is_ok_and(|actual_commit| actual_commit == events_read)
+                & scan.f.leave_block()
+        })
+    }
     fn handle_reopen_is_actual_close(&mut self) -> RuntimeResult<bool> {
         if self.f.is_eof() {
             // yup, it was closed
@@ -206,83 +491,178 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
     }
 }
 
-impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
-    fn apply_batch(
-        m: &Model,
-        NormalBatch {
-            events,
-            schema_version,
-        }: NormalBatch,
-    ) -> RuntimeResult<()> {
+/// Restore the events that were read from a snapshot of the model (written with the given schema version)
+pub(in crate::engine::storage::v1) fn restore_snapshot_events(
+    model: &Model,
+    schema_version: u64,
+    events: Vec<DecodedBatchEvent>,
+) {
+    // a snapshot is restored just like a batch that inserts every row (and deletes the rows deleted since the previous
+    // snapshot, for an incremental snapshot)
+    let mut applier = BatchApplier::new(model, None);
+    applier.apply(BatchStreamItem::Begin { schema_version });
+    if model.primary_index().count() == 0
+        && events
+            .iter()
+            .all(|event| !matches!(event.kind, DecodedBatchEventKind::Delete))
+    {
+        /*
+            this is the full snapshot that we start the restore with. every row appears exactly once and there's nothing
+            to reconcile with, so build the index in one go instead of inserting row by row
+        */
+        let g = unsafe {
+            // UNSAFE(@ohsayan): nobody else can see this model yet
+            crossbeam_epoch::unprotected()
+        };
+        let mut last_txn_id = None;
+        let rows = events
+            .into_iter()
+            .map(|DecodedBatchEvent { txn_id, pk, kind }| {
+                last_txn_id = last_txn_id.max(Some(txn_id));
+                match kind {
+                    DecodedBatchEventKind::Insert(new_row)
+                    | DecodedBatchEventKind::Update(new_row) => {
+                        applier.new_row(pk, new_row, txn_id)
+                    }
+                    DecodedBatchEventKind::Delete => unreachable!(),
+                }
+            });
+        model.primary_index().__raw_index().mt_bulk_extend(rows, g);
+        if let Some(last_txn_id) = last_txn_id {
+            model.delta_state().__restore_data_version(last_txn_id);
+        }
+        return;
+    }
+    for event in events {
+        applier.apply(BatchStreamItem::Event(event));
+    }
+    applier.apply(BatchStreamItem::Commit);
+    if let Some(last_txn_id) = applier.last_txn_id {
+        model.delta_state().__restore_data_version(last_txn_id);
+    }
+}
+
+/// Applies streamed batch events to a model
+struct BatchApplier<'a> {
+    model: &'a Model,
+    until: Option<DeltaVersion>,
+    last_txn_id: Option<DeltaVersion>,
+    schema_version: u64,
+    /// deletes in the current batch; events in a batch aren't ordered by txn id, so these are only applied once we've
+    /// seen every row in the batch
+    pending_delete: HashMap<PrimaryIndexKey, DeltaVersion>,
+}
+
+impl<'a> BatchApplier<'a> {
+    fn new(model: &'a Model, until: Option<DeltaVersion>) -> Self {
+        Self {
+            model,
+            until,
+            last_txn_id: None,
+            schema_version: 0,
+            pending_delete: HashMap::new(),
+        }
+    }
+    fn apply(&mut self, item: BatchStreamItem) {
+        match item {
+            BatchStreamItem::Begin { schema_version } => {
+                self.schema_version = schema_version;
+                self.pending_delete.clear();
+            }
+            BatchStreamItem::Event(event) => self.apply_event(event),
+            BatchStreamItem::Commit => self.apply_pending_deletes(),
+            BatchStreamItem::Discard => {
+                // the writer will republish these
+                self.pending_delete.clear();
+            }
+        }
+    }
+    fn apply_event(&mut self, DecodedBatchEvent { txn_id, pk, kind }: DecodedBatchEvent) {
         // NOTE(@ohsayan): current complexity is O(n) which is good enough (in the future I might revise this to a fancier impl)
         // pin model
         let g = unsafe { crossbeam_epoch::unprotected() };
-        let mut pending_delete = HashMap::new();
+        let m = self.model;
         let p_index = m.primary_index().__raw_index();
-        // scan rows
-        for DecodedBatchEvent { txn_id, pk, kind } in events {
-            match kind {
-                DecodedBatchEventKind::Insert(new_row) | DecodedBatchEventKind::Update(new_row) => {
-                    // this is more like a "newrow"
-                    match p_index.mt_get_element(&pk, &g) {
-                        Some(row) if row.d_data().read().get_restored_txn_revised() > txn_id => {
+        self.last_txn_id = self.last_txn_id.max(Some(txn_id));
+        if self.until.map_or(false, |until| txn_id > until) {
+            // this happened after the recovery point
+            return;
+        }
+        match kind {
+            DecodedBatchEventKind::Insert(new_row) | DecodedBatchEventKind::Update(new_row) => {
+                // this is more like a "newrow" (skews are rare, so we just build it upfront)
+                let new_row = self.new_row(pk, new_row, txn_id);
+                p_index
+                    .mt_entry(new_row.d_key(), &g)
+                    .and_modify(|row| {
+                        if row.d_data().read().get_restored_txn_revised() > txn_id {
                             // skewed
                             // resolve deltas if any
                             let _ = row.resolve_schema_deltas_and_freeze(m.delta_state());
-                            continue;
-                        }
-                        Some(_) | None => {
+                            row.clone()
+                        } else {
                             // new row (logically)
-                            let _ = p_index.mt_delete(&pk, &g);
-                            let mut data = DcFieldIndex::default();
-                            for (field_name, new_data) in m
-                                .fields()
-                                .stseq_ord_key()
-                                .filter(|key| key.as_str() != m.p_key())
-                                .zip(new_row)
-                            {
-                                data.st_insert(
-                                    unsafe {
-                                        // UNSAFE(@ohsayan): model in scope, we're good
-                                        field_name.clone()
-                                    },
-                                    new_data,
-                                );
-                            }
-                            let row = Row::new_restored(
-                                pk,
-                                data,
-                                DeltaVersion::__new(schema_version),
-                                DeltaVersion::__new(0),
-                                txn_id,
-                            );
-                            // resolve any deltas
-                            let _ = row.resolve_schema_deltas_and_freeze(m.delta_state());
-                            // put it back in (lol); blame @ohsayan for this joke
-                            p_index.mt_insert(row, &g);
+                            new_row.clone()
                         }
-                    }
-                }
-                DecodedBatchEventKind::Delete => {
-                    match pending_delete.entry(pk) {
-                        HMEntry::Occupied(mut existing_delete) => {
-                            if *existing_delete.get() > txn_id {
-                                // the existing delete "happened after" our delete, so it takes precedence
-                                continue;
-                            }
-                            // the existing delete happened before our delete, so our delete takes precedence
-                            // we have a newer delete for the same key
-                            *existing_delete.get_mut() = txn_id;
-                        }
-                        HMEntry::Vacant(new) => {
-                            // we never deleted this
-                            new.insert(txn_id);
+                    })
+                    .or_insert_with(|| new_row.clone());
+            }
+            DecodedBatchEventKind::Delete => {
+                match self.pending_delete.entry(pk) {
+                    HMEntry::Occupied(mut existing_delete) => {
+                        if *existing_delete.get() > txn_id {
+                            // the existing delete "happened after" our delete, so it takes precedence
+                            return;
                         }
+                        // the existing delete happened before our delete, so our delete takes precedence
+                        // we have a newer delete for the same key
+                        *existing_delete.get_mut() = txn_id;
+                    }
+                    HMEntry::Vacant(new) => {
+                        // we never deleted this
+                        new.insert(txn_id);
                     }
                 }
             }
         }
-        for (pk, txn_id) in pending_delete {
+    }
+    /// Create a row (with any schema deltas resolved) from the fields of a restored event
+    fn new_row(&self, pk: PrimaryIndexKey, new_row: Vec<Datacell>, txn_id: DeltaVersion) -> Row {
+        let m = self.model;
+        let mut data = DcFieldIndex::default();
+        for ((field_name, field), mut new_data) in m
+            .fields()
+            .stseq_ord_kv()
+            .filter(|(key, _)| key.as_str() != m.p_key())
+            .zip(new_row)
+        {
+            // dicts of fields with a schema are persisted (and restored) as lists
+            if let Some(schema) = field.dict_schema() {
+                schema.unpack(&mut new_data);
+            }
+            data.st_insert(
+                unsafe {
+                    // UNSAFE(@ohsayan): model in scope, we're good
+                    field_name.clone()
+                },
+                new_data,
+            );
+        }
+        let row = Row::new_restored(
+            pk,
+            data,
+            DeltaVersion::__new(self.schema_version),
+            DeltaVersion::__new(0),
+            txn_id,
+        );
+        // resolve any deltas
+        let _ = row.resolve_schema_deltas_and_freeze(m.delta_state());
+        row
+    }
+    fn apply_pending_deletes(&mut self) {
+        let g = unsafe { crossbeam_epoch::unprotected() };
+        let p_index = self.model.primary_index().__raw_index();
+        for (pk, txn_id) in self.pending_delete.drain() {
             match p_index.mt_get(&pk, &g) {
                 Some(row) => {
                     if row.read().get_restored_txn_revised() > txn_id {
@@ -293,12 +673,23 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
                     let _ = p_index.mt_delete(&pk, &g);
                 }
                 None => {
-                    // since we never delete rows until here, this is quite impossible
-                    unreachable!()
+                    // since we never delete rows until here, this can only be a delete for a row that was already
+                    // dropped when the journal was compacted
                 }
             }
         }
-        Ok(())
+    }
+}
+
+/// Decompress an lz4 compressed batch, making sure that it has the size that was recorded for it
+fn decompress_lz4_batch(compressed: &[u8], uncompressed_len: u64) -> RuntimeResult<Vec<u8>> {
+    // lz4 can't do better than 255:1, so don't trust absurd sizes
+    if uncompressed_len / 255 > compressed.len() as u64 {
+        return Err(StorageError::DataBatchRestoreCorruptedBatch.into());
+    }
+    match lz4_flex::block::decompress(compressed, uncompressed_len as usize) {
+        Ok(batch) if batch.len() as u64 == uncompressed_len => Ok(batch),
+        _ => Err(StorageError::DataBatchRestoreCorruptedBatch.into()),
     }
 }
 
@@ -312,7 +703,7 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
             }
         }
         // read actual commit
-        let actual_commit = self.f.read_u64_le()?;
+        let actual_commit = self.read_uint()?;
         // find actual checksum
         let actual_checksum = self.f.__reset_checksum();
         // find hardcoded checksum
@@ -324,12 +715,25 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
             Err(StorageError::DataBatchRestoreCorruptedBatch.into())
         }
     }
-    fn read_batch(&mut self) -> RuntimeResult<Batch> {
-        let mut this_batch = vec![];
+    fn read_batch(&mut self, f: &mut impl FnMut(BatchStreamItem)) -> RuntimeResult<Batch> {
         // check batch type
         let batch_type = self.f.read_byte()?;
-        match batch_type {
-            MARKER_ACTUAL_BATCH_EVENT => {}
+        let checked = match batch_type {
+            MARKER_ACTUAL_BATCH_EVENT => false,
+            MARKER_CHECKED_BATCH_EVENT => true,
+            MARKER_LZ4_BATCH | MARKER_ENCRYPTED_BATCH => {
+                // this batch is compressed (or encrypted); decompress (or decrypt) it and read the batch from it
+                if batch_type == MARKER_LZ4_BATCH {
+                    self.enter_lz4_batch()?;
+                } else {
+                    self.enter_encrypted_batch()?;
+                }
+                match self.f.read_byte()? {
+                    MARKER_ACTUAL_BATCH_EVENT => false,
+                    MARKER_CHECKED_BATCH_EVENT => true,
+                    _ => return Err(StorageError::DataBatchRestoreCorruptedBatch.into()),
+                }
+            }
             MARKER_RECOVERY_EVENT => {
                 // while attempting to write this batch, some sort of an error occurred but we got a nice recovery byte
                 // so proceed that way
@@ -343,35 +747,34 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
                 // this is the only singular byte that is expected to be intact. If this isn't intact either, I'm sorry
                 return Err(StorageError::DataBatchRestoreCorruptedBatch.into());
             }
-        }
+        };
         // decode batch start block
         let batch_start_block = self.read_start_batch_block()?;
-
+        if checked && !self.verify_block_checksum()? {
+            // we can't trust anything in this batch
+            return Err(StorageError::DataBatchRestoreCorruptedBatch.into());
+        }
+        f(BatchStreamItem::Begin {
+            schema_version: batch_start_block.schema_version(),
+        });
         let mut processed_in_this_batch = 0;
         while (processed_in_this_batch != batch_start_block.expected_commit()) & !self.f.is_eof() {
+            let event_offset = self.f.cursor();
             // decode common row data
             let change_type = self.f.read_byte()?;
             // now decode event
-            match change_type {
+            let event = match change_type {
                 MARKER_END_OF_BATCH => {
                     // the file tells us that we've reached the end of this batch; hmmm
-                    return Ok(Batch::FinishedEarly(NormalBatch::new(
-                        this_batch,
-                        batch_start_block.schema_version(),
-                    )));
+                    return Ok(Batch::FinishedEarly(processed_in_this_batch));
                 }
                 normal_event => {
-                    let txnid = self.f.read_u64_le()?;
+                    let txnid = self.read_txn_id(batch_start_block.txn_base())?;
                     match normal_event {
                         0 => {
                             // delete
                             let pk = self.decode_primary_key(batch_start_block.pk_tag())?;
-                            this_batch.push(DecodedBatchEvent::new(
-                                txnid,
-                                pk,
-                                DecodedBatchEventKind::Delete,
-                            ));
-                            processed_in_this_batch += 1;
+                            DecodedBatchEvent::new(txnid, pk, DecodedBatchEventKind::Delete)
                         }
                         1 | 2 => {
                             // insert or update
@@ -388,31 +791,95 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
                                 return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
                             }
                             if change_type == 1 {
-                                this_batch.push(DecodedBatchEvent::new(
+                                DecodedBatchEvent::new(
                                     txnid,
                                     pk,
                                     DecodedBatchEventKind::Insert(row),
-                                ));
+                                )
                             } else {
-                                this_batch.push(DecodedBatchEvent::new(
+                                DecodedBatchEvent::new(
                                     txnid,
                                     pk,
                                     DecodedBatchEventKind::Update(row),
-                                ));
+                                )
                             }
-                            processed_in_this_batch += 1;
                         }
                         _ => {
                             return Err(StorageError::DataBatchRestoreCorruptedBatch.into());
                         }
                     }
                 }
+            };
+            processed_in_this_batch += 1;
+            if checked && !self.verify_block_checksum()? {
+                // the event decoded fine but its data is corrupted; skip just this event
+                self.report.corrupted_events.push(event_offset);
+                continue;
             }
+            f(BatchStreamItem::Event(event));
+        }
+        Ok(Batch::Normal(processed_in_this_batch))
+    }
+    /// Decompress an lz4 compressed batch (the marker was just read) and read from it until the batch is complete
+    fn enter_lz4_batch(&mut self) -> RuntimeResult<()> {
+        let offset = self.f.cursor() - 1;
+        let mut len = [0; sizeof!(u64)];
+        self.f.untracked_read(&mut len)?;
+        let uncompressed_len = u64::from_le_bytes(len);
+        self.f.untracked_read(&mut len)?;
+        let compressed_len = u64::from_le_bytes(len);
+        if !self.f.has_left(compressed_len) {
+            return Err(StorageError::DataBatchRestoreCorruptedBatch.into());
         }
-        Ok(Batch::Normal(NormalBatch::new(
-            this_batch,
-            batch_start_block.schema_version(),
-        )))
+        let mut compressed = vec![0; compressed_len as usize];
+        self.f.untracked_read(&mut compressed)?;
+        let batch = decompress_lz4_batch(&compressed, uncompressed_len)?;
+        // the checksums only cover the batch itself
+        self.f.__reset_checksum();
+        self.f.enter_block(batch, offset);
+        Ok(())
+    }
+    /// Decrypt an encrypted batch (the marker was just read), decompressing it if needed, and read from it until the
+    /// batch is complete
+    fn enter_encrypted_batch(&mut self) -> RuntimeResult<()> {
+        let offset = self.f.cursor() - 1;
+        let mut key_id = [0; sizeof!(u32)];
+        self.f.untracked_read(&mut key_id)?;
+        let key = encryption::key(u32::from_le_bytes(key_id))?;
+        let mut len = [0; sizeof!(u64)];
+        self.f.untracked_read(&mut len)?;
+        let sealed_len = u64::from_le_bytes(len);
+        if !self.f.has_left(sealed_len) {
+            return Err(StorageError::DataBatchRestoreCorruptedBatch.into());
+        }
+        let mut sealed = vec![0; sealed_len as usize];
+        self.f.untracked_read(&mut sealed)?;
+        let mut batch = key.open(&sealed, &self.file, offset)?;
+        if let Some((&MARKER_LZ4_BATCH, frame)) = batch.split_first() {
+            // [marker][uncompressed size][compressed size][compressed batch]
+            let Some((lens, compressed)) = frame.split_first_chunk::<{ sizeof!(u64, 2) }>() else {
+                return Err(StorageError::DataBatchRestoreCorruptedBatch.into());
+            };
+            let (uncompressed_len, compressed_len) = lens.split_at(sizeof!(u64));
+            if u64::from_le_bytes(compressed_len.try_into().unwrap()) != compressed.len() as u64 {
+                return Err(StorageError::DataBatchRestoreCorruptedBatch.into());
+            }
+            batch = decompress_lz4_batch(
+                compressed,
+                u64::from_le_bytes(uncompressed_len.try_into().unwrap()),
+            )?;
+        }
+        // the checksums only cover the batch itself
+        self.f.__reset_checksum();
+        self.f.enter_block(batch, offset);
+        Ok(())
+    }
+    /// Verify the checksum of the block that was just read (in a batch with event checksums)
+    fn verify_block_checksum(&mut self) -> RuntimeResult<bool> {
+        let actual_checksum = self.f.__reset_checksum();
+        let mut hardcoded_checksum = [0; sizeof!(u64)];
+        self.f.untracked_read(&mut hardcoded_checksum)?;
+        Ok(actual_checksum == u64::from_le_bytes(hardcoded_checksum))
     }
     fn attempt_recover_data_batch(&mut self) -> RuntimeResult<()> {
         let mut buf = [0u8; 1];
@@ -424,16 +891,48 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
     }
     fn read_start_batch_block(&mut self) -> RuntimeResult<BatchStartBlock> {
         let pk_tag = self.f.read_byte()?;
-        let expected_commit = self.f.read_u64_le()?;
-        let schema_version = self.f.read_u64_le()?;
-        let column_cnt = self.f.read_u64_le()?;
+        let expected_commit = self.read_uint()?;
+        let schema_version = self.read_uint()?;
+        let column_cnt = self.read_uint()?;
+        let txn_base = match self.encoding {
+            BatchEncoding::Fixed => 0,
+            BatchEncoding::Varint => self.read_uint()?,
+        };
         Ok(BatchStartBlock::new(
             pk_tag,
             expected_commit,
             schema_version,
             column_cnt,
+            txn_base,
         ))
     }
+    /// Read the txn id of an event (with varints, this is the delta from the base txn id of the batch)
+    fn read_txn_id(&mut self, txn_base: u64) -> RuntimeResult<u64> {
+        let txn_id = self.read_uint()?;
+        Ok(match self.encoding {
+            BatchEncoding::Fixed => txn_id,
+            BatchEncoding::Varint => txn_base.wrapping_add(super::unzigzag(txn_id) as u64),
+        })
+    }
+    /// Read an integer (a length, count or txn id) in the encoding used by the journal
+    fn read_uint(&mut self) -> RuntimeResult<u64> {
+        if self.encoding == BatchEncoding::Fixed {
+            return self.f.read_u64_le();
+        }
+        let mut v = 0;
+        for i in 0..VARINT_MAX_LEN {
+            let b = self.f.read_byte()?;
+            if (i == VARINT_MAX_LEN - 1) & (b > 1) {
+                // the last byte only has the top bit of a u64
+                break;
+            }
+            v |= ((b & 0x7F) as u64) << (7 * i);
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(StorageError::DataBatchRestoreCorruptedEntry.into())
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -442,15 +941,24 @@ struct BatchStartBlock {
     expected_commit: u64,
     schema_version: u64,
     column_cnt: u64,
+    /// the txn id that the txn ids of the events in the batch are encoded against (with varints)
+    txn_base: u64,
 }
 
 impl BatchStartBlock {
-    const fn new(pk_tag: u8, expected_commit: u64, schema_version: u64, column_cnt: u64) -> Self {
+    const fn new(
+        pk_tag: u8,
+        expected_commit: u64,
+        schema_version: u64,
+        column_cnt: u64,
+        txn_base: u64,
+    ) -> Self {
         Self {
             pk_tag,
             expected_commit,
             schema_version,
             column_cnt,
+            txn_base,
         }
     }
     fn pk_tag(&self) -> u8 {
@@ -465,6 +973,9 @@ impl BatchStartBlock {
     fn column_cnt(&self) -> u64 {
         self.column_cnt
     }
+    fn txn_base(&self) -> u64 {
+        self.txn_base
+    }
 }
 
 impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
@@ -481,7 +992,10 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
                 }
             }
             TagUnique::Str | TagUnique::Bin => {
-                let len = self.f.read_u64_le()?;
+                let len = self.read_uint()?;
+                if !self.f.has_left(len) {
+                    return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
+                }
                 let mut data = vec![0; len as usize];
                 self.f.read_into_buffer(&mut data)?;
                 if pk_type == TagUnique::Str {
@@ -501,12 +1015,82 @@ impl<F: RawFSInterface> DataBatchRestoreDriver<F> {
             },
         })
     }
+    /// Decode a cell of a row (which, unlike the cells nested in it, can be a typed dict)
     fn decode_cell(&mut self) -> RuntimeResult<Datacell> {
+        let Some(dscr) = StorageCellTypeID::try_from_raw_row_cell(self.f.read_byte()?) else {
+            return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
+        };
+        match dscr {
+            StorageCellTypeID::Str => Ok(Datacell::new_str(self.read_str()?.into_boxed_str())),
+            StorageCellTypeID::Bin => Ok(Datacell::new_bin(self.read_bin()?.into_boxed_slice())),
+            StorageCellTypeID::List | StorageCellTypeID::TypedDict
+                if self.encoding == BatchEncoding::Varint =>
+            {
+                self.decode_list_varint()
+            }
+            StorageCellTypeID::Dict if self.encoding == BatchEncoding::Varint => {
+                self.decode_dict_varint()
+            }
+            _ => unsafe {
+                cell::decode_element::<Datacell, SDSSFileTrackedReader<F>>(&mut self.f, dscr)
+            }
+            .map_err(|e| e.0),
+        }
+    }
+    /// Decode a list (the descriptor was just read) whose length, and the lengths of the cells in it, are varints
+    fn decode_list_varint(&mut self) -> RuntimeResult<Datacell> {
+        let len = self.read_uint()?;
+        let mut l = vec![];
+        while l.len() as u64 != len {
+            l.push(self.decode_nested_cell_varint()?);
+        }
+        Ok(Datacell::new_list(l))
+    }
+    /// Decode a dict (the descriptor was just read) whose length, and the lengths of the keys and cells in it, are
+    /// varints
+    fn decode_dict_varint(&mut self) -> RuntimeResult<Datacell> {
+        let len = self.read_uint()?;
+        let mut d = DictCell::new();
+        while d.len() as u64 != len {
+            let key = self.read_str()?;
+            let value = self.decode_nested_cell_varint()?;
+            if d.insert(key.into_boxed_str(), value).is_some() {
+                return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
+            }
+        }
+        Ok(Datacell::new_dict(d))
+    }
+    /// Decode a cell in a list or dict
+    fn decode_nested_cell_varint(&mut self) -> RuntimeResult<Datacell> {
         let Some(dscr) = StorageCellTypeID::try_from_raw(self.f.read_byte()?) else {
             return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
         };
-        unsafe { cell::decode_element::<Datacell, SDSSFileTrackedReader<F>>(&mut self.f, dscr) }
-            .map_err(|e| e.0)
+        Ok(match dscr {
+            StorageCellTypeID::Str => Datacell::new_str(self.read_str()?.into_boxed_str()),
+            StorageCellTypeID::Bin => Datacell::new_bin(self.read_bin()?.into_boxed_slice()),
+            StorageCellTypeID::List => self.decode_list_varint()?,
+            StorageCellTypeID::Dict => self.decode_dict_varint()?,
+            _ => unsafe {
+                cell::decode_element::<Datacell, SDSSFileTrackedReader<F>>(&mut self.f, dscr)
+            }
+            .map_err(|e| e.0)?,
+        })
+    }
+    /// Read a string (see [`Self::read_bin`])
+    fn read_str(&mut self) -> RuntimeResult<String> {
+        String::from_utf8(self.read_bin()?)
+            .map_err(|_| StorageError::DataBatchRestoreCorruptedEntry.into())
+    }
+    /// Read a blob whose length is a uint (see [`Self::read_uint`]). The data is read straight into a buffer of the
+    /// exact size, so that a datacell can take it over without copying it again
+    fn read_bin(&mut self) -> RuntimeResult<Vec<u8>> {
+        let len = self.read_uint()?;
+        if !self.f.has_left(len) {
+            return Err(StorageError::DataBatchRestoreCorruptedEntry.into());
+        }
+        let mut data = vec![0; len as usize];
+        self.f.read_into_buffer(&mut data)?;
+        Ok(data)
     }
 }
 
@@ -525,7 +1109,7 @@ impl<F: RawFSInterface> DataSource for SDSSFileTrackedReader<F> {
     const RELIABLE_SOURCE: bool = false;
     type Error = ErrorHack;
     fn has_remaining(&self, cnt: usize) -> bool {
-        self.remaining() >= cnt as u64
+        self.has_left(cnt as u64)
     }
     unsafe fn read_next_byte(&mut self) -> Result<u8, Self::Error> {
         Ok(self.read_byte()?)
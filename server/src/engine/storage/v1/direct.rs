@@ -0,0 +1,415 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Direct I/O for batch journals
+//!
+//! On a dedicated database host, the page cache mostly holds a second copy of data that we already keep in memory, so
+//! batch journals can be read and written with `O_DIRECT` instead. Direct I/O needs the buffer, the offset and the
+//! length of every read and write to be aligned to the block size of the device, so the buffered reader and writer
+//! here only ever read and write whole (aligned) blocks:
+//! - The writer keeps the last (partial) block in its buffer and writes it out again (padded to the block size) every
+//!   time it's flushed, after which the padding is cut off the end of the file
+//! - The reader reads whole blocks and serves reads from them
+//!
+//! If we crash between writing out a padded block and cutting off the padding, the journal ends with some zeroes,
+//! which are treated like any other incomplete batch when the journal is restored
+//!
+//! Direct I/O is only enabled for the buffered reader and writer, so everything else (like reading and writing the
+//! header) goes through the page cache as usual. If the file system doesn't support direct I/O, the aligned reader and
+//! writer are used as they are (without `O_DIRECT`)
+
+use {
+    super::rw::{
+        self, FileOpen, LocalFS, LocalFSFile, RawFSInterface, RawFileInterface,
+        RawFileInterfaceBufferedWriter, RawFileInterfaceExt, RawFileInterfaceWriteExt,
+    },
+    crate::engine::error::RuntimeResult,
+    std::{
+        fs::File,
+        io::{self, Read, Seek, SeekFrom, Write},
+        os::{fd::AsRawFd, unix::fs::FileExt},
+        sync::Once,
+    },
+};
+
+/// the alignment that we use for all direct I/O (which is at least the logical block size on any device that we care
+/// about)
+pub const DIRECT_IO_ALIGNMENT: usize = 4096;
+/// the size of the buffers used by the aligned reader and writer
+const BUFFER_SIZE: usize = 16 * DIRECT_IO_ALIGNMENT;
+
+const fn align_down(v: u64) -> u64 {
+    v & !(DIRECT_IO_ALIGNMENT as u64 - 1)
+}
+
+const fn align_up(v: usize) -> usize {
+    (v + DIRECT_IO_ALIGNMENT - 1) & !(DIRECT_IO_ALIGNMENT - 1)
+}
+
+/// The local file system, but batch journals are read and written using direct I/O
+#[derive(Debug)]
+pub struct DirectFS;
+
+impl RawFSInterface for DirectFS {
+    type File = DirectFile;
+    fn fs_remove_file(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_remove_file(fpath)
+    }
+    fn fs_rename_file(from: &str, to: &str) -> RuntimeResult<()> {
+        LocalFS::fs_rename_file(from, to)
+    }
+    fn fs_copy_file(from: &str, to: &str) -> RuntimeResult<()> {
+        LocalFS::fs_copy_file(from, to)
+    }
+    fn fs_create_dir(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_create_dir(fpath)
+    }
+    fn fs_create_dir_all(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_create_dir_all(fpath)
+    }
+    fn fs_delete_dir(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_delete_dir(fpath)
+    }
+    fn fs_delete_dir_all(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_delete_dir_all(fpath)
+    }
+    fn fs_fopen_or_create_rw(fpath: &str) -> RuntimeResult<FileOpen<Self::File>> {
+        Ok(match LocalFS::fs_fopen_or_create_rw(fpath)? {
+            FileOpen::Created(f) => FileOpen::Created(DirectFile(f)),
+            FileOpen::Existing(f) => FileOpen::Existing(DirectFile(f)),
+        })
+    }
+    fn fs_fopen_rw(fpath: &str) -> RuntimeResult<Self::File> {
+        LocalFS::fs_fopen_rw(fpath).map(DirectFile)
+    }
+    fn fs_fcreate_rw(fpath: &str) -> RuntimeResult<Self::File> {
+        LocalFS::fs_fcreate_rw(fpath).map(DirectFile)
+    }
+}
+
+/// A file on the local file system, which uses direct I/O once it's upgraded to a buffered reader or writer
+#[derive(Debug)]
+pub struct DirectFile(File);
+
+impl Read for DirectFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for DirectFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl LocalFSFile for DirectFile {
+    fn file_mut(&mut self) -> &mut File {
+        &mut self.0
+    }
+    fn file(&self) -> &File {
+        &self.0
+    }
+}
+
+impl RawFileInterfaceWriteExt for DirectFile {
+    fn fwext_fsync_all(&mut self) -> RuntimeResult<()> {
+        self.0.fwext_fsync_all()
+    }
+    fn fwext_truncate_to(&mut self, to: u64) -> RuntimeResult<()> {
+        self.0.fwext_truncate_to(to)
+    }
+}
+
+impl RawFileInterface for DirectFile {
+    type BufReader = AlignedReader;
+    type BufWriter = AlignedWriter;
+    fn into_buffered_reader(self) -> RuntimeResult<Self::BufReader> {
+        AlignedReader::new(self.0)
+    }
+    fn downgrade_reader(r: Self::BufReader) -> RuntimeResult<Self> {
+        r.into_inner().map(Self)
+    }
+    fn into_buffered_writer(self) -> RuntimeResult<Self::BufWriter> {
+        AlignedWriter::new(self.0)
+    }
+    fn downgrade_writer(w: Self::BufWriter) -> RuntimeResult<Self> {
+        w.into_inner().map(Self)
+    }
+}
+
+/// Turn direct I/O on (or off) for the file. Turning it on fails quietly (with a warning, once) if the file system
+/// doesn't support it
+fn set_direct(f: &File, direct: bool) -> io::Result<()> {
+    static WARN_UNSUPPORTED: Once = Once::new();
+    let fd = f.as_raw_fd();
+    let ret = unsafe {
+        // UNSAFE(@ohsayan): the fd is valid for as long as we hold the file
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        if flags < 0 {
+            flags
+        } else if direct {
+            libc::fcntl(fd, libc::F_SETFL, flags | libc::O_DIRECT)
+        } else {
+            libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_DIRECT)
+        }
+    };
+    if ret >= 0 {
+        return Ok(());
+    }
+    let e = io::Error::last_os_error();
+    match e.raw_os_error() {
+        Some(libc::EINVAL) if direct => {
+            WARN_UNSUPPORTED.call_once(|| {
+                warn!("the file system doesn't support direct I/O. falling back to buffered I/O")
+            });
+            Ok(())
+        }
+        _ => Err(e),
+    }
+}
+
+/// A heap allocated buffer of [`BUFFER_SIZE`] bytes, aligned to [`DIRECT_IO_ALIGNMENT`]
+struct AlignedBuf {
+    raw: Vec<u8>,
+    start: usize,
+}
+
+impl AlignedBuf {
+    fn new() -> Self {
+        let raw = vec![0; BUFFER_SIZE + DIRECT_IO_ALIGNMENT];
+        // the vector is never resized, so the alignment holds for as long as we hold it
+        let start = raw.as_ptr().align_offset(DIRECT_IO_ALIGNMENT);
+        Self { raw, start }
+    }
+    fn get(&self) -> &[u8] {
+        &self.raw[self.start..self.start + BUFFER_SIZE]
+    }
+    fn get_mut(&mut self) -> &mut [u8] {
+        &mut self.raw[self.start..self.start + BUFFER_SIZE]
+    }
+}
+
+/// Read whole blocks into `buf`, starting at the (aligned) `offset`, until it's full or we hit the end of the file
+fn read_blocks_at(f: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match f.read_at(&mut buf[read..], offset + read as u64) {
+            Ok(0) => break,
+            Ok(n) => {
+                read += n;
+                // a short read that isn't aligned means that we've hit the end of the file
+                if read % DIRECT_IO_ALIGNMENT != 0 {
+                    break;
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+/// A buffered writer that only writes whole, aligned blocks
+///
+/// Unlike [`std::io::BufWriter`], buffered data is **not** written out on drop, so the writer must be flushed (or
+/// downgraded) before it's dropped
+pub struct AlignedWriter {
+    f: File,
+    buf: AlignedBuf,
+    /// the (aligned) offset in the file that the buffer starts at
+    block_start: u64,
+    /// the number of bytes in the buffer that we've written
+    len: usize,
+    /// the length of the file, without any padding
+    file_len: u64,
+}
+
+impl AlignedWriter {
+    fn new(mut f: File) -> RuntimeResult<Self> {
+        let pos = f.stream_position()?;
+        let file_len = f.metadata()?.len();
+        set_direct(&f, true)?;
+        let mut me = Self {
+            f,
+            buf: AlignedBuf::new(),
+            block_start: 0,
+            len: 0,
+            file_len,
+        };
+        me.load(pos)?;
+        Ok(me)
+    }
+    fn into_inner(mut self) -> RuntimeResult<File> {
+        self.flush_blocks()?;
+        set_direct(&self.f, false)?;
+        self.f
+            .seek(SeekFrom::Start(self.block_start + self.len as u64))?;
+        Ok(self.f)
+    }
+    /// Position the buffer at `pos`, loading what's already in the file for the block that `pos` is in
+    fn load(&mut self, pos: u64) -> io::Result<()> {
+        self.block_start = align_down(pos);
+        self.len = (pos - self.block_start) as usize;
+        let buf = self.buf.get_mut();
+        buf.fill(0);
+        if self.block_start < self.file_len {
+            read_blocks_at(&self.f, buf, self.block_start)?;
+        }
+        Ok(())
+    }
+    /// Write out the buffer (the last block is padded with whatever follows it in the file, or zeroes) and then cut
+    /// any padding off the end of the file
+    fn flush_blocks(&mut self) -> io::Result<()> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        let padded_len = align_up(self.len);
+        self.f
+            .write_all_at(&self.buf.get()[..padded_len], self.block_start)?;
+        let end = self.block_start + self.len as u64;
+        let padded_end = self.block_start + padded_len as u64;
+        if padded_end > self.file_len.max(end) {
+            self.f.set_len(self.file_len.max(end))?;
+        }
+        self.file_len = self.file_len.max(end);
+        Ok(())
+    }
+}
+
+impl Write for AlignedWriter {
+    fn write(&mut self, mut buf: &[u8]) -> io::Result<usize> {
+        let written = buf.len();
+        while !buf.is_empty() {
+            let n = buf.len().min(BUFFER_SIZE - self.len);
+            self.buf.get_mut()[self.len..self.len + n].copy_from_slice(&buf[..n]);
+            self.len += n;
+            buf = &buf[n..];
+            if self.len == BUFFER_SIZE {
+                // the buffer is full, so write it out and move on to the next set of blocks
+                self.flush_blocks()?;
+                self.load(self.block_start + BUFFER_SIZE as u64)?;
+            }
+        }
+        Ok(written)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_blocks()
+    }
+}
+
+impl RawFileInterfaceBufferedWriter for AlignedWriter {
+    fn flush_write_cache(&mut self) -> RuntimeResult<()> {
+        Ok(self.flush_blocks()?)
+    }
+    fn sync_write_cache(&mut self) -> RuntimeResult<()> {
+        // direct I/O bypasses the page cache, but not the device's cache (or the file's metadata), so we still sync
+        self.flush_blocks()?;
+        Ok(self.f.sync_all()?)
+    }
+    fn preallocate(&mut self, len: u64) -> RuntimeResult<()> {
+        rw::fallocate_keep_size(&self.f, len)
+    }
+}
+
+impl RawFileInterfaceExt for AlignedWriter {
+    fn fext_file_length(&self) -> RuntimeResult<u64> {
+        Ok(self.file_len)
+    }
+    fn fext_cursor(&mut self) -> RuntimeResult<u64> {
+        Ok(self.block_start + self.len as u64)
+    }
+    fn fext_seek_ahead_from_start_by(&mut self, by: u64) -> RuntimeResult<()> {
+        self.flush_blocks()?;
+        Ok(self.load(by)?)
+    }
+}
+
+/// A buffered reader that only reads whole, aligned blocks
+pub struct AlignedReader {
+    f: File,
+    buf: AlignedBuf,
+    /// the (aligned) offset in the file that the buffer starts at
+    block_start: u64,
+    /// the number of bytes in the buffer that were read from the file
+    filled: usize,
+    /// the offset in the file of the next byte that we'll return
+    pos: u64,
+}
+
+impl AlignedReader {
+    fn new(mut f: File) -> RuntimeResult<Self> {
+        let pos = f.stream_position()?;
+        set_direct(&f, true)?;
+        Ok(Self {
+            f,
+            buf: AlignedBuf::new(),
+            block_start: 0,
+            filled: 0,
+            pos,
+        })
+    }
+    fn into_inner(mut self) -> RuntimeResult<File> {
+        set_direct(&self.f, false)?;
+        self.f.seek(SeekFrom::Start(self.pos))?;
+        Ok(self.f)
+    }
+}
+
+impl Read for AlignedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let buffered = self.block_start..self.block_start + self.filled as u64;
+        if !buffered.contains(&self.pos) {
+            self.block_start = align_down(self.pos);
+            self.filled = read_blocks_at(&self.f, self.buf.get_mut(), self.block_start)?;
+        }
+        let start = (self.pos - self.block_start) as usize;
+        if start >= self.filled {
+            // we're at the end of the file
+            return Ok(0);
+        }
+        let n = buf.len().min(self.filled - start);
+        buf[..n].copy_from_slice(&self.buf.get()[start..start + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl RawFileInterfaceExt for AlignedReader {
+    fn fext_file_length(&self) -> RuntimeResult<u64> {
+        Ok(self.f.metadata()?.len())
+    }
+    fn fext_cursor(&mut self) -> RuntimeResult<u64> {
+        Ok(self.pos)
+    }
+    fn fext_seek_ahead_from_start_by(&mut self, by: u64) -> RuntimeResult<()> {
+        self.pos = by;
+        Ok(())
+    }
+}
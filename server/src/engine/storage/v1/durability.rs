@@ -0,0 +1,102 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Durability policies for journals
+//!
+//! Every commit to a journal (a data batch or a GNS event) is handed to the OS right away. The durability policy only
+//! decides when the journal is also synced to disk, trading durability for write throughput
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// When journals are synced to disk
+pub enum DurabilityPolicy {
+    /// sync on every commit. nothing that was committed is lost, even if the host crashes
+    #[default]
+    Always,
+    /// sync at most once every given number of milliseconds. if the host crashes, the commits since the last sync can
+    /// be lost
+    Periodic(u64),
+    /// never sync on commit and leave it to the OS. if the host crashes, anything that the OS hasn't written yet can be
+    /// lost
+    OsBuffered,
+}
+
+impl DurabilityPolicy {
+    /// Returns how often pending commits need to be synced (only for [`DurabilityPolicy::Periodic`])
+    pub fn sync_interval(&self) -> Option<Duration> {
+        match self {
+            Self::Periodic(ms) => Some(Duration::from_millis(*ms)),
+            Self::Always | Self::OsBuffered => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Keeps track of the commits to a journal that weren't synced yet
+pub struct SyncState {
+    policy: DurabilityPolicy,
+    last_sync: Instant,
+    pending: bool,
+}
+
+impl SyncState {
+    pub fn new(policy: DurabilityPolicy) -> Self {
+        Self {
+            policy,
+            last_sync: Instant::now(),
+            pending: false,
+        }
+    }
+    pub fn policy(&self) -> DurabilityPolicy {
+        self.policy
+    }
+    /// Record a commit, returning true if the journal must be synced right away
+    pub fn commit(&mut self) -> bool {
+        match self.policy {
+            DurabilityPolicy::Always => true,
+            DurabilityPolicy::Periodic(ms) => {
+                self.pending = true;
+                self.last_sync.elapsed() >= Duration::from_millis(ms)
+            }
+            DurabilityPolicy::OsBuffered => false,
+        }
+    }
+    /// Returns true if there are commits that weren't synced and the sync interval has elapsed
+    pub fn sync_due(&self) -> bool {
+        match self.policy {
+            DurabilityPolicy::Periodic(ms) => {
+                self.pending & (self.last_sync.elapsed() >= Duration::from_millis(ms))
+            }
+            DurabilityPolicy::Always | DurabilityPolicy::OsBuffered => false,
+        }
+    }
+    /// Record that the journal was synced
+    pub fn synced(&mut self) {
+        self.pending = false;
+        self.last_sync = Instant::now();
+    }
+}
@@ -0,0 +1,277 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Encryption at rest
+//!
+//! The payloads of SDSS files (the batches in a batch journal, the events in the GNS txn log and the data in a
+//! snapshot) can be encrypted with AES-256-GCM, using the master key that the server was started with. Headers are
+//! never encrypted. Instead, the header of an encrypted file records the ID of the key that it was encrypted with
+//! (the ID is derived from the key itself), so a file that was encrypted with a key we don't have refuses to open
+//! instead of failing somewhere in the middle. An ID of zero means that the file isn't encrypted.
+//!
+//! Every payload is bound to where it's stored (see [`FileBinding`]): the header of its file (which includes the key
+//! ID), the ID of the file and the offset of the payload in it are passed as associated data. A payload that is moved
+//! to another file or offset, or whose header was changed, fails to open like a tampered one does.
+//!
+//! Keys are looked up by their ID in the keyring, which holds every key the server was started with.
+
+use {
+    super::spec::SDSSStaticHeaderV1Compact,
+    crate::engine::error::{RuntimeResult, StorageError},
+    openssl::{rand, sha, symm},
+    parking_lot::RwLock,
+    std::fmt,
+};
+
+/// The size of a key (AES-256)
+pub const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+/// The key ID is stored in the last three bytes of the padding block of the header
+const HEADER_KEY_ID: core::ops::Range<usize> = 5..8;
+/// `[header][file id: u128][offset: u64]`
+const AAD_SIZE: usize = SDSSStaticHeaderV1Compact::SIZE + sizeof!(u128) + sizeof!(u64);
+
+static KEYRING: RwLock<Vec<EncryptionKey>> = RwLock::new(Vec::new());
+
+#[derive(Clone, PartialEq)]
+/// A key that the payloads of files are encrypted with
+pub struct EncryptionKey {
+    id: u32,
+    key: [u8; KEY_SIZE],
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // never print the key itself
+        f.debug_struct("EncryptionKey")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl EncryptionKey {
+    pub fn new(key: [u8; KEY_SIZE]) -> Self {
+        let digest = sha::sha256(&key);
+        // (zero is reserved for files that aren't encrypted)
+        let id = u32::from_le_bytes([digest[0], digest[1], digest[2], 0]).max(1);
+        Self { id, key }
+    }
+    /// Parse a key from the contents of a key file, which holds either the raw key or the key in hex (surrounding
+    /// whitespace is ignored in the latter case)
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() == KEY_SIZE {
+            let mut key = [0; KEY_SIZE];
+            key.copy_from_slice(data);
+            return Some(Self::new(key));
+        }
+        let hex = data.trim_ascii();
+        if hex.len() != KEY_SIZE * 2 {
+            return None;
+        }
+        let mut key = [0; KEY_SIZE];
+        for (byte, digits) in key.iter_mut().zip(hex.chunks_exact(2)) {
+            let digits = core::str::from_utf8(digits).ok()?;
+            *byte = u8::from_str_radix(digits, 16).ok()?;
+        }
+        Some(Self::new(key))
+    }
+    /// Returns the ID of the key (this is never zero)
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+    /// Encrypt the payload that is stored at `offset` in the given file, returning the nonce, the ciphertext and the
+    /// tag (in that order)
+    pub fn seal(&self, payload: &[u8], file: &FileBinding, offset: u64) -> RuntimeResult<Vec<u8>> {
+        let aad = file.associated_data(offset);
+        let mut nonce = [0; NONCE_SIZE];
+        let mut tag = [0; TAG_SIZE];
+        let ciphertext = rand::rand_bytes(&mut nonce).and_then(|_| {
+            symm::encrypt_aead(
+                symm::Cipher::aes_256_gcm(),
+                &self.key,
+                Some(&nonce),
+                &aad,
+                payload,
+                &mut tag,
+            )
+        });
+        let Ok(ciphertext) = ciphertext else {
+            return Err(StorageError::EncryptionFailed.into());
+        };
+        let mut sealed = Vec::with_capacity(NONCE_SIZE + ciphertext.len() + TAG_SIZE);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed.extend_from_slice(&tag);
+        Ok(sealed)
+    }
+    /// Decrypt a payload that was encrypted using [`Self::seal`], failing if it was tampered with or if it wasn't sealed
+    /// for the same file and offset
+    pub fn open(&self, sealed: &[u8], file: &FileBinding, offset: u64) -> RuntimeResult<Vec<u8>> {
+        if sealed.len() < NONCE_SIZE + TAG_SIZE {
+            return Err(StorageError::EncryptedPayloadCorrupted.into());
+        }
+        let (nonce, rest) = sealed.split_at(NONCE_SIZE);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_SIZE);
+        symm::decrypt_aead(
+            symm::Cipher::aes_256_gcm(),
+            &self.key,
+            Some(nonce),
+            &file.associated_data(offset),
+            ciphertext,
+            tag,
+        )
+        .map_err(|_| StorageError::EncryptedPayloadCorrupted.into())
+    }
+}
+
+/*
+    binding
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What the payloads of a file are bound to: the header of the file (as it's stored) and the ID of the file (the epoch
+/// time recorded in its header, which tells apart files that were created in place of one another)
+pub struct FileBinding {
+    header: [u8; SDSSStaticHeaderV1Compact::SIZE],
+    id: u128,
+}
+
+impl Default for FileBinding {
+    /// The binding of a file that we know nothing about (a payload sealed for it can't be opened for any other file)
+    fn default() -> Self {
+        Self::from_raw([0; SDSSStaticHeaderV1Compact::SIZE], 0)
+    }
+}
+
+impl FileBinding {
+    pub fn new(header: &SDSSStaticHeaderV1Compact) -> Self {
+        Self::from_raw(header.encoded(), header.epoch_time())
+    }
+    /// Bind to a file using its raw header (such as when the header was received from another server)
+    pub fn from_raw(header: [u8; SDSSStaticHeaderV1Compact::SIZE], id: u128) -> Self {
+        Self { header, id }
+    }
+    /// Returns the header of the file, as it's stored
+    pub fn raw_header(&self) -> [u8; SDSSStaticHeaderV1Compact::SIZE] {
+        self.header
+    }
+    /// Returns the ID of the file
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+    fn associated_data(&self, offset: u64) -> [u8; AAD_SIZE] {
+        let mut aad = [0; AAD_SIZE];
+        let (header, rest) = aad.split_at_mut(SDSSStaticHeaderV1Compact::SIZE);
+        let (id, offset_bytes) = rest.split_at_mut(sizeof!(u128));
+        header.copy_from_slice(&self.header);
+        id.copy_from_slice(&self.id.to_le_bytes());
+        offset_bytes.copy_from_slice(&offset.to_le_bytes());
+        aad
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The key that the payloads of a file are encrypted with, bound to that file
+pub struct FileKey {
+    key: EncryptionKey,
+    file: FileBinding,
+}
+
+impl FileKey {
+    pub fn new(key: EncryptionKey, file: FileBinding) -> Self {
+        Self { key, file }
+    }
+    /// Encrypt the payload that is stored at `offset` in the file (see [`EncryptionKey::seal`])
+    pub fn seal(&self, payload: &[u8], offset: u64) -> RuntimeResult<Vec<u8>> {
+        self.key.seal(payload, &self.file, offset)
+    }
+    /// Decrypt the payload that is stored at `offset` in the file (see [`EncryptionKey::open`])
+    pub fn open(&self, sealed: &[u8], offset: u64) -> RuntimeResult<Vec<u8>> {
+        self.key.open(sealed, &self.file, offset)
+    }
+}
+
+/*
+    keyring
+*/
+
+/// Add a key to the keyring, so that files encrypted with it can be read
+pub fn register(key: EncryptionKey) {
+    let mut keyring = KEYRING.write();
+    if !keyring.iter().any(|registered| registered.id == key.id) {
+        keyring.push(key);
+    }
+}
+
+/// Returns the key with the given ID from the keyring
+pub fn key(id: u32) -> RuntimeResult<EncryptionKey> {
+    KEYRING
+        .read()
+        .iter()
+        .find(|key| key.id == id)
+        .cloned()
+        .ok_or(StorageError::EncryptionKeyUnavailable.into())
+}
+
+/*
+    header
+*/
+
+/// Record the ID of the key (if any) in the format data of a header
+pub fn header_format(mut padding_block: [u8; 8], key: Option<&EncryptionKey>) -> [u8; 8] {
+    let id = key.map_or(0, EncryptionKey::id);
+    padding_block[HEADER_KEY_ID].copy_from_slice(&id.to_le_bytes()[..3]);
+    padding_block
+}
+
+/// Returns the ID of the key recorded in the header (zero if the file isn't encrypted)
+pub fn key_id_from_header(header: &SDSSStaticHeaderV1Compact) -> u32 {
+    key_id_from_format(header.padding_block())
+}
+
+/// Returns the ID of the key recorded in the format data of a header (see [`header_format`])
+pub fn key_id_from_format(padding_block: [u8; 8]) -> u32 {
+    u32::from_le_bytes([
+        padding_block[HEADER_KEY_ID.start],
+        padding_block[HEADER_KEY_ID.start + 1],
+        padding_block[HEADER_KEY_ID.start + 2],
+        0,
+    ])
+}
+
+/// Returns the key that the file with the given header was encrypted with, if it's encrypted
+pub fn key_for_header(header: &SDSSStaticHeaderV1Compact) -> RuntimeResult<Option<EncryptionKey>> {
+    match key_id_from_header(header) {
+        0 => Ok(None),
+        id => key(id).map(Some),
+    }
+}
+
+/// Returns the key that the file with the given header was encrypted with (bound to that file), if it's encrypted
+pub fn file_key_for_header(header: &SDSSStaticHeaderV1Compact) -> RuntimeResult<Option<FileKey>> {
+    Ok(key_for_header(header)?.map(|key| FileKey::new(key, FileBinding::new(header))))
+}
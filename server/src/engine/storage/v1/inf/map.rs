@@ -49,6 +49,85 @@ use {
 #[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
 pub struct MapIndexSizeMD(pub usize);
 
+/// Set in the key length of a map entry if its key was already written earlier in the same collection (a map along with
+/// the maps nested in it). The rest of the key length is then the index of that key in the order in which keys were
+/// first written, and the key itself isn't written again
+const KEY_REF: u64 = 1 << 63;
+
+/// The keys written so far in a persisted collection (see [`KEY_REF`])
+#[derive(Default)]
+pub struct KeyInterner {
+    keys: HashMap<Box<str>, u64>,
+    /// set if the key that was last passed to [`Self::key_len`] was already written
+    last_was_ref: bool,
+}
+
+impl KeyInterner {
+    /// Returns what goes into the key length of the entry for this key
+    pub fn key_len(&mut self, key: &str) -> u64 {
+        self.last_was_ref = false;
+        if let Some(idx) = self.keys.get(key) {
+            self.last_was_ref = true;
+            return KEY_REF | idx;
+        }
+        self.keys.insert(key.into(), self.keys.len() as u64);
+        key.len() as u64
+    }
+    /// Write the key that was last passed to [`Self::key_len`] (unless it was already written)
+    pub fn write_key(&self, buf: &mut VecU8, key: &str) {
+        if !self.last_was_ref {
+            buf.extend(key.as_bytes());
+        }
+    }
+}
+
+/// The key of a map entry, as recorded in the entry metadata
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKey {
+    /// the key (of this length) follows the metadata
+    Len(usize),
+    /// the key is the one at this index in the [`KeyTable`]
+    Ref(usize),
+}
+
+impl EntryKey {
+    fn decode(key_len: u64) -> Self {
+        if key_len & KEY_REF == 0 {
+            Self::Len(key_len as usize)
+        } else {
+            Self::Ref((key_len & !KEY_REF) as usize)
+        }
+    }
+    /// The number of bytes of the key that follow the metadata
+    fn len(&self) -> usize {
+        match self {
+            Self::Len(len) => *len,
+            Self::Ref(_) => 0,
+        }
+    }
+}
+
+/// The keys decoded so far in a persisted collection (see [`KEY_REF`])
+#[derive(Default)]
+pub struct KeyTable {
+    keys: Vec<Box<str>>,
+}
+
+impl KeyTable {
+    unsafe fn decode_key(&mut self, s: &mut BufferedScanner, key: EntryKey) -> Option<Box<str>> {
+        match key {
+            EntryKey::Len(len) => {
+                let key = inf::dec::utils::decode_string(s, len)
+                    .ok()?
+                    .into_boxed_str();
+                self.keys.push(key.clone());
+                Some(key)
+            }
+            EntryKey::Ref(idx) => self.keys.get(idx).cloned(),
+        }
+    }
+}
+
 /// This is more of a lazy hack than anything sensible. Just implement a spec and then use this wrapper for any enc/dec operations
 pub struct PersistMapImpl<'a, M: MapStorageSpec>(PhantomData<&'a M::InMemoryMap>);
 
@@ -70,23 +149,62 @@ impl<'a, M: MapStorageSpec> PersistObject for PersistMapImpl<'a, M> {
         Ok(MapIndexSizeMD(scanner.next_u64_le() as usize))
     }
     fn obj_enc(buf: &mut VecU8, map: Self::InputType) {
+        Self::obj_enc_with(buf, map, &mut KeyInterner::default())
+    }
+    unsafe fn obj_dec(
+        scanner: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        Self::obj_dec_with(scanner, md, &mut KeyTable::default())
+    }
+}
+
+impl<'a, M: MapStorageSpec> PersistMapImpl<'a, M> {
+    /// Encode a (nested) map, along with its metadata, into the collection whose keys are in `keys`
+    fn full_enc_with(buf: &mut VecU8, map: &'a M::InMemoryMap, keys: &mut KeyInterner) {
+        <Self as PersistObject>::meta_enc(buf, map);
+        Self::obj_enc_with(buf, map, keys)
+    }
+    /// Decode a (nested) map, along with its metadata, from the collection whose keys are in `keys`
+    fn full_dec_with(
+        scanner: &mut BufferedScanner,
+        keys: &mut KeyTable,
+    ) -> RuntimeResult<M::RestoredMap> {
+        if !<Self as PersistObject>::pretest_can_dec_metadata(scanner) {
+            return Err(StorageError::InternalDecodeStructureCorrupted.into());
+        }
+        let md = unsafe {
+            // UNSAFE(@ohsayan): +pretest
+            <Self as PersistObject>::meta_dec(scanner)?
+        };
+        if !<Self as PersistObject>::pretest_can_dec_object(scanner, &md) {
+            return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+        }
+        unsafe {
+            // UNSAFE(@ohsayan): +obj pretest
+            Self::obj_dec_with(scanner, md, keys)
+        }
+    }
+    /// Encode the entries of a map, interning their keys into `keys`
+    fn obj_enc_with(buf: &mut VecU8, map: &'a M::InMemoryMap, keys: &mut KeyInterner) {
         for (key, val) in M::get_iter_from_memory(map) {
-            M::encode_entry_meta(buf, key, val);
+            M::encode_entry_meta(buf, key, val, keys);
             if M::ENC_AS_ENTRY {
-                M::encode_entry_data(buf, key, val);
+                M::encode_entry_data(buf, key, val, keys);
             } else {
-                M::encode_entry_key(buf, key);
-                M::encode_entry_val(buf, val);
+                M::encode_entry_key(buf, key, keys);
+                M::encode_entry_val(buf, val, keys);
             }
         }
     }
-    unsafe fn obj_dec(
+    /// Decode the entries of a map, resolving (and recording) their keys using `keys`
+    unsafe fn obj_dec_with(
         scanner: &mut BufferedScanner,
-        MapIndexSizeMD(dict_size): Self::Metadata,
-    ) -> RuntimeResult<Self::OutputType> {
+        MapIndexSizeMD(dict_size): MapIndexSizeMD,
+        keys: &mut KeyTable,
+    ) -> RuntimeResult<M::RestoredMap> {
         let mut dict = M::RestoredMap::map_new();
-        let decode_pretest_for_entry_meta = M::decode_pretest_for_entry_meta(scanner);
-        while decode_pretest_for_entry_meta & (dict.map_length() != dict_size) {
+        while (dict.map_length() != dict_size) && M::decode_pretest_for_entry_meta(scanner) {
             let md = unsafe {
                 // UNSAFE(@ohsayan): +pretest
                 M::decode_entry_meta(scanner).ok_or::<StorageError>(
@@ -100,7 +218,7 @@ impl<'a, M: MapStorageSpec> PersistObject for PersistMapImpl<'a, M> {
             let val;
             unsafe {
                 if M::DEC_AS_ENTRY {
-                    match M::decode_entry_data(scanner, md) {
+                    match M::decode_entry_data(scanner, md, keys) {
                         Some((_k, _v)) => {
                             key = _k;
                             val = _v;
@@ -110,8 +228,8 @@ impl<'a, M: MapStorageSpec> PersistObject for PersistMapImpl<'a, M> {
                         }
                     }
                 } else {
-                    let _k = M::decode_entry_key(scanner, &md);
-                    let _v = M::decode_entry_val(scanner, &md);
+                    let _k = M::decode_entry_key(scanner, &md, keys);
+                    let _v = M::decode_entry_val(scanner, &md, keys);
                     match (_k, _v) {
                         (Some(_k), Some(_v)) => {
                             key = _k;
@@ -140,7 +258,7 @@ pub struct GenericDictSpec;
 
 /// generic dict entry metadata
 pub struct GenericDictEntryMetadata {
-    pub(crate) klen: usize,
+    pub(crate) key: EntryKey,
     pub(crate) dscr: u8,
 }
 
@@ -149,7 +267,7 @@ impl GenericDictEntryMetadata {
     /// if unsafe code is used to translate an incorrect dscr)
     pub(crate) fn decode(data: [u8; 9]) -> Self {
         Self {
-            klen: u64::from_le_bytes(memcpy(&data[..8])) as usize,
+            key: EntryKey::decode(u64::from_le_bytes(memcpy(&data[..8]))),
             dscr: data[8],
         }
     }
@@ -170,27 +288,37 @@ impl MapStorageSpec for GenericDictSpec {
     fn get_iter_from_memory<'a>(map: &'a Self::InMemoryMap) -> Self::InMemoryMapIter<'a> {
         map.iter()
     }
-    fn encode_entry_meta(buf: &mut VecU8, key: &Self::InMemoryKey, _: &Self::InMemoryVal) {
-        buf.extend(key.len().u64_bytes_le());
-    }
-    fn encode_entry_data(buf: &mut VecU8, key: &Self::InMemoryKey, val: &Self::InMemoryVal) {
+    fn encode_entry_meta(
+        buf: &mut VecU8,
+        key: &Self::InMemoryKey,
+        _: &Self::InMemoryVal,
+        keys: &mut KeyInterner,
+    ) {
+        buf.extend(keys.key_len(key).to_le_bytes());
+    }
+    fn encode_entry_data(
+        buf: &mut VecU8,
+        key: &Self::InMemoryKey,
+        val: &Self::InMemoryVal,
+        keys: &mut KeyInterner,
+    ) {
         match val {
             DictEntryGeneric::Map(map) => {
                 buf.push(StorageCellTypeID::Dict.value_u8());
-                buf.extend(key.as_bytes());
-                <PersistMapImpl<Self> as PersistObject>::default_full_enc(buf, map);
+                keys.write_key(buf, key);
+                PersistMapImpl::<Self>::full_enc_with(buf, map, keys);
             }
             DictEntryGeneric::Data(dc) => {
                 buf.push(cell::encode_tag(dc));
-                buf.extend(key.as_bytes());
+                keys.write_key(buf, key);
                 cell::encode_cell(buf, dc);
             }
         }
     }
-    fn encode_entry_key(_: &mut VecU8, _: &Self::InMemoryKey) {
+    fn encode_entry_key(_: &mut VecU8, _: &Self::InMemoryKey, _: &mut KeyInterner) {
         unimplemented!()
     }
-    fn encode_entry_val(_: &mut VecU8, _: &Self::InMemoryVal) {
+    fn encode_entry_val(_: &mut VecU8, _: &Self::InMemoryVal, _: &mut KeyInterner) {
         unimplemented!()
     }
     fn decode_pretest_for_entry_meta(scanner: &mut BufferedScanner) -> bool {
@@ -199,7 +327,7 @@ impl MapStorageSpec for GenericDictSpec {
     }
     fn decode_pretest_for_entry_data(s: &mut BufferedScanner, md: &Self::EntryMetadata) -> bool {
         StorageCellTypeID::is_valid(md.dscr)
-            & s.has_left(StorageCellTypeID::expect_atleast(md.dscr))
+            & s.has_left(md.key.len() + StorageCellTypeID::expect_atleast(md.dscr))
     }
     unsafe fn decode_entry_meta(s: &mut BufferedScanner) -> Option<Self::EntryMetadata> {
         Some(Self::EntryMetadata::decode(s.next_chunk()))
@@ -207,20 +335,21 @@ impl MapStorageSpec for GenericDictSpec {
     unsafe fn decode_entry_data(
         _: &mut BufferedScanner,
         _: Self::EntryMetadata,
+        _: &mut KeyTable,
     ) -> Option<(Self::RestoredKey, Self::RestoredVal)> {
         unimplemented!()
     }
     unsafe fn decode_entry_key(
         s: &mut BufferedScanner,
         md: &Self::EntryMetadata,
+        keys: &mut KeyTable,
     ) -> Option<Self::RestoredKey> {
-        inf::dec::utils::decode_string(s, md.klen as usize)
-            .map(|s| s.into_boxed_str())
-            .ok()
+        keys.decode_key(s, md.key)
     }
     unsafe fn decode_entry_val(
         scanner: &mut BufferedScanner,
         md: &Self::EntryMetadata,
+        keys: &mut KeyTable,
     ) -> Option<Self::RestoredVal> {
         Some(
             match cell::decode_element::<CanYieldDict, BufferedScanner>(
@@ -231,8 +360,7 @@ impl MapStorageSpec for GenericDictSpec {
             {
                 CanYieldDict::Data(d) => DictEntryGeneric::Data(d),
                 CanYieldDict::Dict => DictEntryGeneric::Map(
-                    <PersistMapImpl<GenericDictSpec> as PersistObject>::default_full_dec(scanner)
-                        .ok()?,
+                    PersistMapImpl::<GenericDictSpec>::full_dec_with(scanner, keys).ok()?,
                 ),
             },
         )
@@ -240,16 +368,16 @@ impl MapStorageSpec for GenericDictSpec {
 }
 
 pub struct FieldMapEntryMetadata {
-    field_id_l: u64,
+    field_id: EntryKey,
     field_prop_c: u64,
     field_layer_c: u64,
     null: u8,
 }
 
 impl FieldMapEntryMetadata {
-    const fn new(field_id_l: u64, field_prop_c: u64, field_layer_c: u64, null: u8) -> Self {
+    const fn new(field_id: EntryKey, field_prop_c: u64, field_layer_c: u64, null: u8) -> Self {
         Self {
-            field_id_l,
+            field_id,
             field_prop_c,
             field_layer_c,
             null,
@@ -280,9 +408,15 @@ impl FieldMapAny for HashMap<Box<str>, Field> {
     }
 }
 impl FieldMapAny for IndexSTSeqCns<crate::engine::mem::RawStr, Field> {
-    type Iterator<'a> = std::iter::Map<
-    crate::engine::idx::stdord_iter::IndexSTSeqDllIterOrdKV<'a, crate::engine::mem::RawStr, Field>,
-    fn((&crate::engine::mem::RawStr, &Field)) -> (&'a str, &'a Field)>
+    type Iterator<'a>
+        = std::iter::Map<
+        crate::engine::idx::stdord_iter::IndexSTSeqDllIterOrdKV<
+            'a,
+            crate::engine::mem::RawStr,
+            Field,
+        >,
+        fn((&crate::engine::mem::RawStr, &Field)) -> (&'a str, &'a Field),
+    >
     where
         Self: 'a;
 
@@ -295,9 +429,11 @@ impl FieldMapAny for IndexSTSeqCns<crate::engine::mem::RawStr, Field> {
     }
 }
 impl FieldMapAny for IndexSTSeqCns<Box<str>, Field> {
-    type Iterator<'a> = std::iter::Map<
-    crate::engine::idx::stdord_iter::IndexSTSeqDllIterOrdKV<'a, Box<str>, Field>,
-    fn((&Box<str>, &Field)) -> (&'a str, &'a Field)>
+    type Iterator<'a>
+        = std::iter::Map<
+        crate::engine::idx::stdord_iter::IndexSTSeqDllIterOrdKV<'a, Box<str>, Field>,
+        fn((&Box<str>, &Field)) -> (&'a str, &'a Field),
+    >
     where
         Self: 'a;
 
@@ -315,7 +451,10 @@ impl<FM: FieldMapAny> MapStorageSpec for FieldMapSpec<FM> {
     type InMemoryMap = FM;
     type InMemoryKey = str;
     type InMemoryVal = Field;
-    type InMemoryMapIter<'a> = FM::Iterator<'a> where FM: 'a;
+    type InMemoryMapIter<'a>
+        = FM::Iterator<'a>
+    where
+        FM: 'a;
     type RestoredKey = Box<str>;
     type RestoredVal = Field;
     type RestoredMap = IndexSTSeqCns<Box<str>, Field>;
@@ -325,32 +464,43 @@ impl<FM: FieldMapAny> MapStorageSpec for FieldMapSpec<FM> {
     fn get_iter_from_memory<'a>(map: &'a Self::InMemoryMap) -> Self::InMemoryMapIter<'a> {
         map.get_iter()
     }
-    fn encode_entry_meta(buf: &mut VecU8, key: &Self::InMemoryKey, val: &Self::InMemoryVal) {
-        buf.extend(key.len().u64_bytes_le());
-        buf.extend(0u64.to_le_bytes()); // TODO(@ohsayan): props
+    fn encode_entry_meta(
+        buf: &mut VecU8,
+        key: &Self::InMemoryKey,
+        val: &Self::InMemoryVal,
+        keys: &mut KeyInterner,
+    ) {
+        buf.extend(keys.key_len(key).to_le_bytes());
+        buf.extend(super::obj::FieldRef::props(val).to_le_bytes());
         buf.extend(val.layers().len().u64_bytes_le());
         buf.push(val.is_nullable() as u8);
     }
-    fn encode_entry_data(_: &mut VecU8, _: &Self::InMemoryKey, _: &Self::InMemoryVal) {
+    fn encode_entry_data(
+        _: &mut VecU8,
+        _: &Self::InMemoryKey,
+        _: &Self::InMemoryVal,
+        _: &mut KeyInterner,
+    ) {
         unimplemented!()
     }
-    fn encode_entry_key(buf: &mut VecU8, key: &Self::InMemoryKey) {
-        buf.extend(key.as_bytes());
+    fn encode_entry_key(buf: &mut VecU8, key: &Self::InMemoryKey, keys: &mut KeyInterner) {
+        keys.write_key(buf, key);
     }
-    fn encode_entry_val(buf: &mut VecU8, val: &Self::InMemoryVal) {
+    fn encode_entry_val(buf: &mut VecU8, val: &Self::InMemoryVal, _: &mut KeyInterner) {
         for layer in val.layers() {
             super::obj::LayerRef::default_full_enc(buf, super::obj::LayerRef(layer))
         }
+        super::obj::FieldRef::encode_props(buf, val);
     }
     fn decode_pretest_for_entry_meta(scanner: &mut BufferedScanner) -> bool {
         scanner.has_left(sizeof!(u64, 3) + 1)
     }
     fn decode_pretest_for_entry_data(s: &mut BufferedScanner, md: &Self::EntryMetadata) -> bool {
-        s.has_left(md.field_id_l as usize) // TODO(@ohsayan): we can enforce way more here such as atleast one field etc
+        s.has_left(md.field_id.len()) // TODO(@ohsayan): we can enforce way more here such as atleast one field etc
     }
     unsafe fn decode_entry_meta(scanner: &mut BufferedScanner) -> Option<Self::EntryMetadata> {
         Some(FieldMapEntryMetadata::new(
-            scanner.next_u64_le(),
+            EntryKey::decode(scanner.next_u64_le()),
             scanner.next_u64_le(),
             scanner.next_u64_le(),
             scanner.next_byte(),
@@ -359,20 +509,21 @@ impl<FM: FieldMapAny> MapStorageSpec for FieldMapSpec<FM> {
     unsafe fn decode_entry_data(
         _: &mut BufferedScanner,
         _: Self::EntryMetadata,
+        _: &mut KeyTable,
     ) -> Option<(Self::RestoredKey, Self::RestoredVal)> {
         unimplemented!()
     }
     unsafe fn decode_entry_key(
         scanner: &mut BufferedScanner,
         md: &Self::EntryMetadata,
+        keys: &mut KeyTable,
     ) -> Option<Self::RestoredKey> {
-        inf::dec::utils::decode_string(scanner, md.field_id_l as usize)
-            .map(|s| s.into_boxed_str())
-            .ok()
+        keys.decode_key(scanner, md.field_id)
     }
     unsafe fn decode_entry_val(
         scanner: &mut BufferedScanner,
         md: &Self::EntryMetadata,
+        _: &mut KeyTable,
     ) -> Option<Self::RestoredVal> {
         super::obj::FieldRef::obj_dec(
             scanner,
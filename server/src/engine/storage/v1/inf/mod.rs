@@ -32,10 +32,14 @@ pub mod obj;
 #[cfg(test)]
 mod tests;
 
-use crate::engine::{
-    error::{RuntimeResult, StorageError},
-    idx::{AsKey, AsValue, STIndex},
-    mem::{BufferedScanner, StatelessLen},
+use {
+    self::map::{KeyInterner, KeyTable},
+    crate::engine::{
+        data::uuid::Uuid,
+        error::{RuntimeResult, StorageError},
+        idx::{AsKey, AsValue, STIndex},
+        mem::{BufferedScanner, StatelessLen},
+    },
 };
 
 type VecU8 = Vec<u8>;
@@ -138,6 +142,45 @@ pub trait PersistObject {
     }
 }
 
+/// A fixed size field of a struct that derives `PersistObject` (see `sky_macros`), which is written into the metadata
+pub trait FixedSizeField: Sized {
+    /// The number of bytes that the field takes up
+    const SIZE: usize;
+    fn encode(&self, buf: &mut VecU8);
+    /// ## Safety
+    ///
+    /// The scanner must have atleast [`Self::SIZE`] bytes left
+    unsafe fn decode(scanner: &mut BufferedScanner) -> Self;
+}
+
+macro_rules! impl_fixed_size_field {
+    ($($ty:ty),*) => {
+        $(
+            impl FixedSizeField for $ty {
+                const SIZE: usize = sizeof!($ty);
+                fn encode(&self, buf: &mut VecU8) {
+                    buf.extend(self.to_le_bytes());
+                }
+                unsafe fn decode(scanner: &mut BufferedScanner) -> Self {
+                    <$ty>::from_le_bytes(scanner.next_chunk())
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_size_field!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl FixedSizeField for Uuid {
+    const SIZE: usize = sizeof!(u128);
+    fn encode(&self, buf: &mut VecU8) {
+        buf.extend(self.to_le_bytes());
+    }
+    unsafe fn decode(scanner: &mut BufferedScanner) -> Self {
+        Uuid::from_bytes(scanner.next_chunk())
+    }
+}
+
 /*
     map spec
 */
@@ -181,11 +224,21 @@ pub trait MapStorageSpec {
     const DEC_AS_ENTRY: bool;
     // iterator
     fn get_iter_from_memory<'a>(map: &'a Self::InMemoryMap) -> Self::InMemoryMapIter<'a>;
-    // encode
-    fn encode_entry_meta(buf: &mut VecU8, key: &Self::InMemoryKey, val: &Self::InMemoryVal);
-    fn encode_entry_data(buf: &mut VecU8, key: &Self::InMemoryKey, val: &Self::InMemoryVal);
-    fn encode_entry_key(buf: &mut VecU8, key: &Self::InMemoryKey);
-    fn encode_entry_val(buf: &mut VecU8, val: &Self::InMemoryVal);
+    // encode (keys are interned into the collection that the map is a part of)
+    fn encode_entry_meta(
+        buf: &mut VecU8,
+        key: &Self::InMemoryKey,
+        val: &Self::InMemoryVal,
+        keys: &mut KeyInterner,
+    );
+    fn encode_entry_data(
+        buf: &mut VecU8,
+        key: &Self::InMemoryKey,
+        val: &Self::InMemoryVal,
+        keys: &mut KeyInterner,
+    );
+    fn encode_entry_key(buf: &mut VecU8, key: &Self::InMemoryKey, keys: &mut KeyInterner);
+    fn encode_entry_val(buf: &mut VecU8, val: &Self::InMemoryVal, keys: &mut KeyInterner);
     // decode
     fn decode_pretest_for_map(_: &BufferedScanner, _: usize) -> bool {
         true
@@ -196,14 +249,17 @@ pub trait MapStorageSpec {
     unsafe fn decode_entry_data(
         s: &mut BufferedScanner,
         md: Self::EntryMetadata,
+        keys: &mut KeyTable,
     ) -> Option<(Self::RestoredKey, Self::RestoredVal)>;
     unsafe fn decode_entry_key(
         s: &mut BufferedScanner,
         md: &Self::EntryMetadata,
+        keys: &mut KeyTable,
     ) -> Option<Self::RestoredKey>;
     unsafe fn decode_entry_val(
         s: &mut BufferedScanner,
         md: &Self::EntryMetadata,
+        keys: &mut KeyTable,
     ) -> Option<Self::RestoredVal>;
 }
 
@@ -211,7 +267,6 @@ pub trait MapStorageSpec {
 pub mod enc {
     use super::{map, MapStorageSpec, PersistObject, VecU8};
     // obj
-    #[cfg(test)]
     pub fn enc_full<Obj: PersistObject>(obj: Obj::InputType) -> Vec<u8> {
         let mut v = vec![];
         enc_full_into_buffer::<Obj>(&mut v, obj);
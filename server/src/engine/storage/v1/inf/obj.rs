@@ -29,10 +29,14 @@ use {
     crate::{
         engine::{
             core::{
-                model::{Field, Layer, Model},
+                model::{
+                    compute::FieldCompute, keygen::KeyGenerator, schema::DictSchema, Field, Layer,
+                    Model,
+                },
                 space::Space,
             },
             data::{
+                cell::Datacell,
                 tag::{DataTag, TagClass, TagSelector},
                 uuid::Uuid,
                 DictGeneric,
@@ -53,8 +57,10 @@ use {
 pub mod cell {
     use crate::{
         engine::{
+            core::model::Field,
             data::{
                 cell::Datacell,
+                dict::DictCell,
                 tag::{DataTag, TagClass, TagSelector},
             },
             storage::v1::inf::{DataSource, VecU8},
@@ -82,6 +88,12 @@ pub mod cell {
         Str = 0x0D,
         List = 0x0E,
         Dict = 0x0F,
+        Timestamp = 0x10,
+        SInt128 = 0x11,
+        GeoPoint = 0x12,
+        // a dict of a field with a schema, which is just the list of its values (see `encode_field`). only the cells of
+        // rows can be typed dicts
+        TypedDict = 0x13,
     }
     impl StorageCellTypeID {
         pub const unsafe fn from_raw(v: u8) -> Self {
@@ -94,16 +106,36 @@ pub mod cell {
                 None
             }
         }
+        /// Like [`Self::try_from_raw`], but also accepts [`Self::TypedDict`]. Use this only for the cells of a row (and
+        /// not for the cells nested in them)
+        pub const fn try_from_raw_row_cell(v: u8) -> Option<Self> {
+            if v <= Self::MAX {
+                Some(unsafe { Self::from_raw(v) })
+            } else {
+                None
+            }
+        }
+        /// Returns true if this is a valid descriptor for any cell but a typed dict (which only rows can have)
         #[inline(always)]
         pub const fn is_valid(d: u8) -> bool {
-            d <= Self::MAX
+            (d <= Self::MAX) & (d != Self::TypedDict.value_u8())
         }
         const unsafe fn into_selector(self) -> TagSelector {
             debug_assert!(self.value_u8() != Self::Null.value_u8());
-            core::mem::transmute(self.value_u8() - 1)
+            match self {
+                Self::Dict => TagSelector::Dict,
+                Self::Timestamp => TagSelector::Timestamp,
+                Self::SInt128 => TagSelector::SInt128,
+                Self::GeoPoint => TagSelector::GeoPoint,
+                Self::TypedDict => TagSelector::List,
+                _ => TagSelector::from_raw(self.value_u8() - 1),
+            }
         }
         #[inline(always)]
         pub fn expect_atleast(d: u8) -> usize {
+            if (d == Self::SInt128.value_u8()) | (d == Self::GeoPoint.value_u8()) {
+                return 16;
+            }
             [0u8, 1, 8, 8][d.min(3) as usize] as usize
         }
     }
@@ -111,8 +143,35 @@ pub mod cell {
         buf.push(encode_tag(dc));
         encode_cell(buf, dc)
     }
+    /// Encode the value of a field like [`encode`], except that if the field has a schema, its dicts are encoded
+    /// without their keys: as the list of their values in the order of the schema (with a null for every key that a
+    /// dict doesn't have)
+    pub fn encode_field(buf: &mut VecU8, field: &Field, dc: &Datacell) {
+        match (field.dict_schema(), dc.try_dict()) {
+            (Some(schema), Some(dict)) => {
+                buf.push(StorageCellTypeID::TypedDict.value_u8());
+                buf.extend(schema.fields().len().u64_bytes_le());
+                for value in schema.values(dict) {
+                    match value {
+                        Some(value) => encode(buf, value),
+                        None => buf.push(StorageCellTypeID::Null.value_u8()),
+                    }
+                }
+            }
+            _ => encode(buf, dc),
+        }
+    }
     pub fn encode_tag(dc: &Datacell) -> u8 {
-        (dc.tag().tag_selector().value_u8() + 1) * (dc.is_init() as u8)
+        // timestamps (and 128-bit integers and points) came after dicts, so they can't use the selector's position (and
+        // dict cells use the ID of dicts)
+        let dscr = match dc.tag().tag_selector() {
+            TagSelector::Dict => StorageCellTypeID::Dict.value_u8(),
+            TagSelector::Timestamp => StorageCellTypeID::Timestamp.value_u8(),
+            TagSelector::SInt128 => StorageCellTypeID::SInt128.value_u8(),
+            TagSelector::GeoPoint => StorageCellTypeID::GeoPoint.value_u8(),
+            selector => selector.value_u8() + 1,
+        };
+        dscr * (dc.is_init() as u8)
     }
     pub fn encode_cell(buf: &mut VecU8, dc: &Datacell) {
         if dc.is_null() {
@@ -123,7 +182,15 @@ pub mod cell {
             match dc.tag().tag_class() {
                 Bool if dc.is_init() => buf.push(dc.read_bool() as u8),
                 Bool => {}
-                UnsignedInt | SignedInt | Float => buf.extend(dc.read_uint().to_le_bytes()),
+                UnsignedInt | SignedInt | Float | Timestamp => {
+                    buf.extend(dc.read_uint().to_le_bytes())
+                }
+                BigInt => buf.extend(dc.read_sint128().to_le_bytes()),
+                Geo => {
+                    let (lat, lon) = dc.read_geopoint();
+                    buf.extend(lat.to_le_bytes());
+                    buf.extend(lon.to_le_bytes());
+                }
                 Str | Bin => {
                     let slc = dc.read_bin();
                     buf.extend(slc.len().u64_bytes_le());
@@ -136,6 +203,15 @@ pub mod cell {
                         encode(buf, item);
                     }
                 }
+                Dict => {
+                    let dict = dc.read_dict();
+                    buf.extend(dict.len().u64_bytes_le());
+                    for (key, value) in dict {
+                        buf.extend(key.len().u64_bytes_le());
+                        buf.extend(key.as_bytes());
+                        encode(buf, value);
+                    }
+                }
             }
         }
     }
@@ -186,12 +262,12 @@ pub mod cell {
         DS::Error: From<EY::Error>,
         DS::Error: From<()>,
     {
-        if dscr == StorageCellTypeID::Dict {
-            if EY::CAN_YIELD_DICT {
-                return Ok(EY::yield_dict()?);
-            } else {
-                return Ok(EY::error()?);
-            }
+        /*
+            in (generic) metadata, a dict is a nested map that is decoded by the caller. there are no dict cells in
+            metadata, so this is never ambiguous
+        */
+        if (dscr == StorageCellTypeID::Dict) & EY::CAN_YIELD_DICT {
+            return Ok(EY::yield_dict()?);
         }
         if dscr == StorageCellTypeID::Null {
             return Ok(EY::yield_data(Datacell::null())?);
@@ -205,12 +281,21 @@ pub mod cell {
                 }
                 Datacell::new_bool(nx == 1)
             }
-            TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Float => {
+            TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Float | TagClass::Timestamp => {
                 let nx = s.read_next_u64_le()?;
                 Datacell::new_qw(nx, tag)
             }
+            TagClass::BigInt => Datacell::new_sint128(i128::from_le_bytes(s.read_next_block()?)),
+            TagClass::Geo => {
+                let lat = f64::from_le_bytes(s.read_next_block()?);
+                let lon = f64::from_le_bytes(s.read_next_block()?);
+                Datacell::new_geopoint(lat, lon)
+            }
             TagClass::Bin | TagClass::Str => {
                 let len = s.read_next_u64_le()? as usize;
+                if !s.has_remaining(len) {
+                    return Ok(EY::error()?);
+                }
                 let block = s.read_next_variable_block(len)?;
                 if tag.tag_class() == TagClass::Str {
                     match String::from_utf8(block).map(|s| Datacell::new_str(s.into_boxed_str())) {
@@ -228,7 +313,6 @@ pub mod cell {
                     let Some(dscr) = StorageCellTypeID::try_from_raw(s.read_next_byte()?) else {
                         return Ok(EY::error()?);
                     };
-                    // FIXME(@ohsayan): right now, a list cannot contain a dict!
                     if !s.has_remaining(StorageCellTypeID::expect_atleast(dscr.value_u8())) {
                         return Ok(EY::error()?);
                     }
@@ -239,6 +323,33 @@ pub mod cell {
                 }
                 Datacell::new_list(l)
             }
+            TagClass::Dict => {
+                let len = s.read_next_u64_le()? as usize;
+                let mut d = DictCell::new();
+                while (d.len() != len) & s.has_remaining(sizeof!(u64)) {
+                    let key_len = s.read_next_u64_le()? as usize;
+                    if !s.has_remaining(key_len + 1) {
+                        return Ok(EY::error()?);
+                    }
+                    let Ok(key) = String::from_utf8(s.read_next_variable_block(key_len)?) else {
+                        return Ok(EY::error()?);
+                    };
+                    let Some(dscr) = StorageCellTypeID::try_from_raw(s.read_next_byte()?) else {
+                        return Ok(EY::error()?);
+                    };
+                    if !s.has_remaining(StorageCellTypeID::expect_atleast(dscr.value_u8())) {
+                        return Ok(EY::error()?);
+                    }
+                    let value = self::decode_element::<Datacell, DS>(s, dscr)?;
+                    if d.insert(key.into_boxed_str(), value).is_some() {
+                        return Ok(EY::error()?);
+                    }
+                }
+                if d.len() != len {
+                    return Ok(EY::error()?);
+                }
+                Datacell::new_dict(d)
+            }
         };
         Ok(EY::yield_data(d)?)
     }
@@ -290,7 +401,7 @@ impl<'a> PersistObject for LayerRef<'a> {
         _: &mut BufferedScanner,
         md: Self::Metadata,
     ) -> RuntimeResult<Self::OutputType> {
-        if (md.type_selector > TagSelector::List.value_qword()) | (md.prop_set_arity != 0) {
+        if (md.type_selector > TagSelector::MAX as u64) | (md.prop_set_arity != 0) {
             return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
         }
         Ok(Layer::new_empty_props(
@@ -335,7 +446,7 @@ impl<'a> PersistObject for FieldRef<'a> {
     }
     fn meta_enc(buf: &mut VecU8, slf: Self::InputType) {
         // [prop_c][layer_c][null]
-        buf.extend(0u64.to_le_bytes());
+        buf.extend(Self::props(slf).to_le_bytes());
         buf.extend(slf.layers().len().u64_bytes_le());
         buf.push(slf.is_nullable() as u8);
     }
@@ -350,6 +461,7 @@ impl<'a> PersistObject for FieldRef<'a> {
         for layer in slf.layers() {
             LayerRef::default_full_enc(buf, LayerRef(layer));
         }
+        Self::encode_props(buf, slf);
     }
     unsafe fn obj_dec(
         scanner: &mut BufferedScanner,
@@ -370,8 +482,50 @@ impl<'a> PersistObject for FieldRef<'a> {
             fin = l.tag().tag_class() != TagClass::List;
             layers.push(l);
         }
-        let field = Field::new(layers, md.null == 1);
-        if (field.layers().len() as u64 == md.layer_c) & (md.null <= 1) & (md.prop_c == 0) & fin {
+        let mut field = Field::new(layers, md.null == 1);
+        let mut okay = (field.layers().len() as u64 == md.layer_c)
+            & (md.null <= 1)
+            & (md.prop_c
+                <= (Self::PROP_DEFAULT
+                    | Self::PROP_COMPUTE
+                    | Self::PROP_GENERATE
+                    | Self::PROP_FULLTEXT
+                    | Self::PROP_SCHEMA))
+            & fin;
+        if okay & (md.prop_c & Self::PROP_DEFAULT != 0) {
+            okay = match Self::decode_prop(scanner) {
+                Some(default) => field.set_default(default),
+                None => false,
+            };
+        }
+        if okay & (md.prop_c & Self::PROP_COMPUTE != 0) {
+            okay = match Self::decode_prop(scanner)
+                .and_then(Datacell::into_str)
+                .and_then(|decl| FieldCompute::parse(&decl))
+            {
+                Some(compute) => field.set_compute(compute),
+                None => false,
+            };
+        }
+        if okay & (md.prop_c & Self::PROP_GENERATE != 0) {
+            okay = match Self::decode_prop(scanner)
+                .and_then(Datacell::into_str)
+                .and_then(|generate| KeyGenerator::parse(&generate))
+            {
+                Some(generate) => field.set_generate(generate),
+                None => false,
+            };
+        }
+        if okay & (md.prop_c & Self::PROP_FULLTEXT != 0) {
+            okay = field.set_fulltext();
+        }
+        if okay & (md.prop_c & Self::PROP_SCHEMA != 0) {
+            okay = match Self::decode_prop(scanner).and_then(DictSchema::from_cell) {
+                Some(schema) => field.set_dict_schema(schema),
+                None => false,
+            };
+        }
+        if okay {
             Ok(field)
         } else {
             Err(StorageError::InternalDecodeStructureCorrupted.into())
@@ -379,6 +533,50 @@ impl<'a> PersistObject for FieldRef<'a> {
     }
 }
 
+impl<'a> FieldRef<'a> {
+    /*
+        the props of a field are a set of flags (older versions only had the default, which is the first flag) and
+        the props that are present follow the layers, in the order of their flags. some props (like fulltext) are
+        just the flag and have nothing that follows
+    */
+    const PROP_DEFAULT: u64 = 1 << 0;
+    const PROP_COMPUTE: u64 = 1 << 1;
+    const PROP_GENERATE: u64 = 1 << 2;
+    const PROP_FULLTEXT: u64 = 1 << 3;
+    const PROP_SCHEMA: u64 = 1 << 4;
+    pub(super) fn props(field: &Field) -> u64 {
+        (field.default().is_some() as u64 * Self::PROP_DEFAULT)
+            | (field.compute().is_some() as u64 * Self::PROP_COMPUTE)
+            | (field.generate().is_some() as u64 * Self::PROP_GENERATE)
+            | (field.is_fulltext() as u64 * Self::PROP_FULLTEXT)
+            | (field.dict_schema().is_some() as u64 * Self::PROP_SCHEMA)
+    }
+    pub(super) fn encode_props(buf: &mut VecU8, field: &Field) {
+        if let Some(default) = field.default() {
+            cell::encode(buf, default.cell());
+        }
+        if let Some(compute) = field.compute() {
+            cell::encode(buf, &Datacell::new_str(compute.decl().into()));
+        }
+        if let Some(generate) = field.generate() {
+            cell::encode(buf, &Datacell::new_str(generate.name().into()));
+        }
+        if let Some(schema) = field.dict_schema() {
+            cell::encode(buf, &schema.to_cell());
+        }
+    }
+    unsafe fn decode_prop(scanner: &mut BufferedScanner) -> Option<Datacell> {
+        if !scanner.has_left(1) {
+            return None;
+        }
+        let dscr = cell::StorageCellTypeID::try_from_raw(scanner.next_byte())?;
+        if !scanner.has_left(cell::StorageCellTypeID::expect_atleast(dscr.value_u8())) {
+            return None;
+        }
+        cell::decode_element::<Datacell, BufferedScanner>(scanner, dscr).ok()
+    }
+}
+
 #[derive(Debug)]
 pub struct ModelLayoutMD {
     model_uuid: Uuid,
@@ -413,6 +611,17 @@ impl<'a> From<&'a Model> for ModelLayoutRef<'a> {
         Self(mdl)
     }
 }
+impl<'a> ModelLayoutRef<'a> {
+    /*
+        the flags of a model are kept in the upper half of the qword that holds the tag of its primary key (older
+        versions didn't have any flags, and the tag always fits in the lower half)
+    */
+    const TAG_MASK: u64 = u32::MAX as u64;
+    const FLAG_EPHEMERAL: u64 = 1 << 32;
+    fn flags(model: &Model) -> u64 {
+        model.is_ephemeral() as u64 * Self::FLAG_EPHEMERAL
+    }
+}
 impl<'a> PersistObject for ModelLayoutRef<'a> {
     const METADATA_SIZE: usize = sizeof!(u128) + sizeof!(u64, 3);
     type InputType = ModelLayoutRef<'a>;
@@ -424,7 +633,9 @@ impl<'a> PersistObject for ModelLayoutRef<'a> {
     fn meta_enc(buf: &mut VecU8, ModelLayoutRef(model_def): Self::InputType) {
         buf.extend(model_def.get_uuid().to_le_bytes());
         buf.extend(model_def.p_key().len().u64_bytes_le());
-        buf.extend(model_def.p_tag().tag_selector().value_qword().to_le_bytes());
+        buf.extend(
+            (model_def.p_tag().tag_selector().value_qword() | Self::flags(model_def)).to_le_bytes(),
+        );
         buf.extend(model_def.fields().len().u64_bytes_le());
     }
     unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
@@ -452,17 +663,26 @@ impl<'a> PersistObject for ModelLayoutRef<'a> {
         > as PersistObject>::obj_dec(
             scanner, super::map::MapIndexSizeMD(md.field_c as usize)
         )?;
-        let ptag = if md.p_key_tag > TagSelector::MAX as u64 {
+        let (p_key_tag, flags) = (
+            md.p_key_tag & Self::TAG_MASK,
+            md.p_key_tag & !Self::TAG_MASK,
+        );
+        let ptag = if (p_key_tag > TagSelector::MAX as u64) | (flags & !Self::FLAG_EPHEMERAL != 0) {
             return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
         } else {
-            TagSelector::from_raw(md.p_key_tag as u8)
+            TagSelector::from_raw(p_key_tag as u8)
         };
-        Ok(Model::new_restore(
+        let model = Model::new_restore(
             md.model_uuid,
             key.into_boxed_str(),
             ptag.into_full(),
             fieldmap,
-        ))
+        );
+        Ok(if flags & Self::FLAG_EPHEMERAL != 0 {
+            model.into_ephemeral()
+        } else {
+            model
+        })
     }
 }
 
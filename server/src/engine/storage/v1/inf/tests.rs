@@ -28,16 +28,20 @@ use {
     super::obj,
     crate::engine::{
         core::{
-            model::{Field, Layer, Model},
+            model::{
+                compute::FieldCompute, keygen::KeyGenerator, schema::DictSchema, Field, Layer,
+                Model,
+            },
             space::Space,
         },
         data::{
             cell::Datacell,
-            dict::{DictEntryGeneric, DictGeneric},
+            dict::{DictCell, DictEntryGeneric, DictGeneric},
             tag::TagSelector,
             uuid::Uuid,
         },
         idx::{IndexBaseSpec, IndexSTSeqCns, STIndex, STIndexSeq},
+        mem::BufferedScanner,
     },
 };
 
@@ -56,6 +60,196 @@ fn dict() {
     assert_eq!(dict, decoded);
 }
 
+#[test]
+fn dict_with_repeated_keys() {
+    let dict: DictGeneric = into_dict! {
+        "a repeated key" => DictEntryGeneric::Map(into_dict!(
+            "a repeated key" => Datacell::new_uint_default(1),
+            "another key" => DictEntryGeneric::Map(into_dict!(
+                "a repeated key" => Datacell::new_str("hello".into()),
+            )),
+        )),
+        "another key" => Datacell::null(),
+    };
+    let encoded = super::enc::enc_dict_full::<super::map::GenericDictSpec>(&dict);
+    // every key is written once in the whole collection
+    for key in [&b"a repeated key"[..], b"another key"] {
+        assert_eq!(encoded.windows(key.len()).filter(|w| *w == key).count(), 1);
+    }
+    let decoded = super::dec::dec_dict_full::<super::map::GenericDictSpec>(&encoded).unwrap();
+    assert_eq!(dict, decoded);
+}
+
+#[test]
+fn dict_without_interned_keys() {
+    // before keys were interned, a repeated key was written again in full
+    let mut legacy = vec![];
+    legacy.extend(1u64.to_le_bytes());
+    legacy.extend(1u64.to_le_bytes());
+    legacy.push(obj::cell::StorageCellTypeID::Dict.value_u8());
+    legacy.push(b'k');
+    legacy.extend(1u64.to_le_bytes());
+    legacy.extend(1u64.to_le_bytes());
+    legacy.push(obj::cell::StorageCellTypeID::Null.value_u8());
+    legacy.push(b'k');
+    let dict: DictGeneric = into_dict! {
+        "k" => DictEntryGeneric::Map(into_dict!("k" => Datacell::null()))
+    };
+    assert_eq!(
+        super::dec::dec_dict_full::<super::map::GenericDictSpec>(&legacy).unwrap(),
+        dict
+    );
+    // while now it's a reference to the first key
+    let encoded = super::enc::enc_dict_full::<super::map::GenericDictSpec>(&dict);
+    assert_eq!(encoded.len(), legacy.len() - 1);
+    assert_eq!(encoded[26..34], (1u64 << 63).to_le_bytes());
+}
+
+#[test]
+fn dict_cell() {
+    let dict = Datacell::new_dict(into_dict! {
+        "name" => Datacell::from("sayan"),
+        "nothing" => Datacell::null(),
+        "scores" => Datacell::new_list(vec![
+            Datacell::new_dict(into_dict!("game" => Datacell::new_uint_default(1))),
+            Datacell::new_dict(DictCell::new()),
+        ]),
+        "nested" => Datacell::new_dict(into_dict!(
+            "deeper" => Datacell::new_dict(into_dict!("float" => Datacell::new_float_default(1.5))),
+            "sint" => Datacell::new_sint_default(-100),
+        )),
+    });
+    let mut encoded = vec![];
+    obj::cell::encode(&mut encoded, &dict);
+    assert_eq!(decode_cell(&encoded), Some(dict));
+    // a truncated dict is an error
+    for len in 0..encoded.len() {
+        assert_eq!(decode_cell(&encoded[..len]), None);
+    }
+}
+
+#[test]
+fn typed_dict_cell() {
+    let mut field = Field::new([Layer::dict()].into(), false);
+    assert!(field.set_dict_schema(
+        DictSchema::from_cell(Datacell::new_dict(into_dict!(
+            "name" => Datacell::from("string"),
+            "age" => Datacell::from("uint8"),
+            "email" => Datacell::from("string"),
+        )))
+        .unwrap()
+    ));
+    let dict = Datacell::new_dict(into_dict!(
+        "name" => Datacell::from("sayan"),
+        "age" => Datacell::new_uint_default(24),
+    ));
+    let mut generic = vec![];
+    obj::cell::encode(&mut generic, &dict);
+    let mut encoded = vec![];
+    obj::cell::encode_field(&mut encoded, &field, &dict);
+    // the keys (and their lengths) aren't encoded, but there's a null for the email
+    assert_eq!(
+        generic.len() - encoded.len(),
+        "name".len() + "age".len() + 2 * sizeof!(u64) - 1
+    );
+    // it's decoded as the list of its values (in the order of the schema), which is turned back into the dict
+    let mut decoded = decode_row_cell(&encoded).unwrap();
+    assert_eq!(
+        decoded,
+        Datacell::new_list(vec![
+            Datacell::new_uint_default(24),
+            Datacell::null(),
+            Datacell::from("sayan")
+        ])
+    );
+    field.dict_schema().unwrap().unpack(&mut decoded);
+    assert_eq!(decoded, dict);
+    // but a null is encoded as usual
+    let mut null = vec![];
+    obj::cell::encode_field(&mut null, &field, &Datacell::null());
+    assert_eq!(null, [obj::cell::StorageCellTypeID::Null.value_u8()]);
+    // only the cells of a row can be typed dicts, so they're rejected anywhere else
+    assert_eq!(decode_cell(&encoded), None);
+    let mut nested = vec![obj::cell::StorageCellTypeID::List.value_u8()];
+    nested.extend(1u64.to_le_bytes());
+    nested.extend(&encoded);
+    assert_eq!(decode_row_cell(&nested), None);
+    let props: DictGeneric = into_dict!("schema" => Datacell::new_list(vec![]));
+    let mut encoded_props = super::enc::enc_dict_full::<super::map::GenericDictSpec>(&props);
+    let list_at = encoded_props
+        .iter()
+        .rposition(|b| *b == obj::cell::StorageCellTypeID::List.value_u8())
+        .unwrap();
+    encoded_props[list_at] = obj::cell::StorageCellTypeID::TypedDict.value_u8();
+    assert!(super::dec::dec_dict_full::<super::map::GenericDictSpec>(&encoded_props).is_err());
+}
+
+fn decode_cell(data: &[u8]) -> Option<Datacell> {
+    decode_cell_with(data, obj::cell::StorageCellTypeID::try_from_raw)
+}
+
+fn decode_row_cell(data: &[u8]) -> Option<Datacell> {
+    decode_cell_with(data, obj::cell::StorageCellTypeID::try_from_raw_row_cell)
+}
+
+fn decode_cell_with(
+    data: &[u8],
+    dscr: fn(u8) -> Option<obj::cell::StorageCellTypeID>,
+) -> Option<Datacell> {
+    let mut scanner = BufferedScanner::new(data);
+    let dscr = scanner.try_next_byte().and_then(dscr).filter(|dscr| {
+        scanner.has_left(obj::cell::StorageCellTypeID::expect_atleast(
+            dscr.value_u8(),
+        ))
+    })?;
+    let decoded =
+        unsafe { obj::cell::decode_element::<Datacell, BufferedScanner>(&mut scanner, dscr) };
+    decoded.ok().filter(|_| scanner.eof())
+}
+
+#[derive(sky_macros::PersistObject, Debug, PartialEq)]
+#[persist(metadata_size = 41)]
+struct DerivedObject {
+    uuid: Uuid,
+    #[persist(str)]
+    name: Box<str>,
+    version: u64,
+    #[persist(bin)]
+    data: Box<[u8]>,
+    flags: u8,
+}
+
+#[test]
+fn derived_object() {
+    let obj = DerivedObject {
+        uuid: Uuid::new(),
+        name: "sayan".into(),
+        version: 42,
+        data: (*b"\x00\x01\x02").into(),
+        flags: 0b101,
+    };
+    let encoded = super::enc::enc_full::<&DerivedObject>(&obj);
+    // the metadata (with the fixed size fields and the lengths) comes first, followed by the strings and binaries
+    let mut expected = vec![];
+    expected.extend(obj.uuid.to_le_bytes());
+    expected.extend(5u64.to_le_bytes());
+    expected.extend(42u64.to_le_bytes());
+    expected.extend(3u64.to_le_bytes());
+    expected.push(0b101);
+    expected.extend(b"sayan\x00\x01\x02");
+    assert_eq!(encoded, expected);
+    assert_eq!(
+        super::dec::dec_full::<&DerivedObject>(&encoded).unwrap(),
+        obj
+    );
+    // the data has to be there
+    assert!(super::dec::dec_full::<&DerivedObject>(&encoded[..encoded.len() - 1]).is_err());
+    // and the name has to be a string
+    let mut bad_name = encoded.clone();
+    bad_name[41] = 0xFF;
+    assert!(super::dec::dec_full::<&DerivedObject>(&bad_name).is_err());
+}
+
 #[test]
 fn layer() {
     let layer = Layer::list();
@@ -72,6 +266,130 @@ fn field() {
     assert_eq!(field, dec);
 }
 
+#[test]
+fn field_with_timestamp() {
+    let field = Field::new([Layer::list(), Layer::timestamp()].into(), false);
+    let encoded = super::enc::enc_full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+    assert_eq!(field, dec);
+}
+
+#[test]
+fn field_with_sint128() {
+    let field = Field::new([Layer::list(), Layer::sint128()].into(), true);
+    let encoded = super::enc::enc_full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+    assert_eq!(field, dec);
+}
+
+#[test]
+fn field_with_geopoint() {
+    let field = Field::new([Layer::list(), Layer::geopoint()].into(), true);
+    let encoded = super::enc::enc_full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+    assert_eq!(field, dec);
+}
+
+#[test]
+fn field_with_dict() {
+    let field = Field::new([Layer::list(), Layer::dict()].into(), true);
+    let encoded = super::enc::enc_full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+    assert_eq!(field, dec);
+}
+
+#[test]
+fn field_with_dict_schema() {
+    let mut field = Field::new([Layer::dict()].into(), true);
+    assert!(field.set_dict_schema(
+        DictSchema::from_cell(Datacell::new_dict(into_dict!(
+            "name" => Datacell::from("string"),
+            "seen" => Datacell::from("timestamp"),
+        )))
+        .unwrap()
+    ));
+    let encoded = super::enc::enc_full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+    assert!(dec.dict_schema().is_some());
+    assert_eq!(field, dec);
+}
+
+#[test]
+fn field_with_fulltext() {
+    let mut field = Field::new([Layer::str()].into(), false);
+    assert!(field.set_fulltext());
+    let encoded = super::enc::enc_full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+    assert!(dec.is_fulltext());
+    assert_eq!(field, dec);
+}
+
+#[test]
+fn field_with_compute() {
+    let mut field = Field::new([Layer::str()].into(), true);
+    assert!(field.set_compute(FieldCompute::parse("concat(lower(first), ' ', last)").unwrap()));
+    let encoded = super::enc::enc_full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+    assert_eq!(field, dec);
+    // and in a field map, next to a field with a default
+    let mut fields = IndexSTSeqCns::<Box<str>, Field>::idx_init();
+    let mut status = Field::new([Layer::str()].into(), false);
+    assert!(status.set_default(Datacell::new_str("active".into())));
+    fields.st_insert("first".into(), Field::new([Layer::str()].into(), true));
+    fields.st_insert("last".into(), Field::new([Layer::str()].into(), true));
+    fields.st_insert("status".into(), status);
+    fields.st_insert("full".into(), field);
+    let enc = super::enc::enc_dict_full::<super::map::FieldMapSpec<_>>(&fields);
+    let dec = super::dec::dec_dict_full::<
+        super::map::FieldMapSpec<crate::engine::idx::IndexSTSeqCns<Box<str>, _>>,
+    >(&enc)
+    .unwrap();
+    assert!(fields.stseq_ord_kv().eq(dec.stseq_ord_kv()));
+}
+
+#[test]
+fn field_with_generate() {
+    for (layer, generator) in [
+        (Layer::uint64(), KeyGenerator::Increment),
+        (Layer::uint64(), KeyGenerator::Snowflake),
+        (Layer::str(), KeyGenerator::Uuid),
+        (Layer::bin(), KeyGenerator::Uuid),
+    ] {
+        let mut field = Field::new([layer].into(), false);
+        assert!(field.set_generate(generator));
+        let encoded = super::enc::enc_full::<obj::FieldRef>((&field).into());
+        let dec = super::dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+        assert_eq!(field, dec);
+        assert_eq!(dec.generate(), Some(generator));
+    }
+}
+
+#[test]
+fn field_with_default() {
+    let mut field = Field::new([Layer::uint8()].into(), false);
+    assert!(field.set_default(Datacell::new_uint_default(10)));
+    let encoded = super::enc::enc_full::<obj::FieldRef>((&field).into());
+    let dec = super::dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+    assert_eq!(field, dec);
+    assert_eq!(
+        dec.default().unwrap().cell().tag(),
+        TagSelector::UInt8.into_full()
+    );
+    // and in a field map
+    let mut fields = IndexSTSeqCns::<Box<str>, Field>::idx_init();
+    let mut status = Field::new([Layer::str()].into(), true);
+    assert!(status.set_default(Datacell::new_str("active".into())));
+    fields.st_insert("visits".into(), field);
+    fields.st_insert("status".into(), status);
+    fields.st_insert("email".into(), Field::new([Layer::str()].into(), true));
+    let enc = super::enc::enc_dict_full::<super::map::FieldMapSpec<_>>(&fields);
+    let dec = super::dec::dec_dict_full::<
+        super::map::FieldMapSpec<crate::engine::idx::IndexSTSeqCns<Box<str>, _>>,
+    >(&enc)
+    .unwrap();
+    assert!(fields.stseq_ord_kv().eq(dec.stseq_ord_kv()));
+}
+
 #[test]
 fn fieldmap() {
     let mut fields = IndexSTSeqCns::<Box<str>, Field>::idx_init();
@@ -110,6 +428,23 @@ fn model() {
     assert_eq!(model, dec);
 }
 
+#[test]
+fn model_ephemeral() {
+    let model = Model::new_restore(
+        Uuid::new(),
+        "key".into(),
+        TagSelector::String.into_full(),
+        into_dict! {
+            "value" => Field::new([Layer::bin()].into(), false),
+        },
+    )
+    .into_ephemeral();
+    let enc = super::enc::enc_full::<obj::ModelLayoutRef>(obj::ModelLayoutRef(&model));
+    let dec = super::dec::dec_full::<obj::ModelLayoutRef>(&enc).unwrap();
+    assert!(dec.is_ephemeral());
+    assert_eq!(model, dec);
+}
+
 #[test]
 fn space() {
     let uuid = Uuid::new();
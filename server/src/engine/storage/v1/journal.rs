@@ -33,7 +33,7 @@
   - Event source (1B) + 7B padding (for future metadata)
   - Event CRC32
   - Payload len: the size of the pyload
-  - Payload: the payload
+  - Payload: the payload (encrypted, if the header records a key ID)
 
 
   Notes on error tolerance:
@@ -43,11 +43,16 @@
 
 use {
     super::{
+        durability::{DurabilityPolicy, SyncState},
+        encryption::{self, EncryptionKey, FileBinding, FileKey},
         rw::{RawFSInterface, SDSSFileIO},
-        spec,
+        spec::{self, SDSSStaticHeaderV1Compact},
     },
     crate::{
-        engine::error::{RuntimeResult, StorageError},
+        engine::{
+            error::{RuntimeResult, StorageError},
+            fractal::error::Error,
+        },
         util::{compiler, copy_a_into_b, copy_slice_to_array as memcpy},
     },
     std::marker::PhantomData,
@@ -56,34 +61,83 @@ use {
 const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
 
 #[cfg(test)]
-pub fn open_or_create_journal<TA: JournalAdapter, Fs: RawFSInterface, F: spec::FileSpec>(
+pub fn open_or_create_journal<
+    TA: JournalAdapter,
+    Fs: RawFSInterface,
+    F: spec::FileSpec<Header = SDSSStaticHeaderV1Compact>,
+>(
     log_file_name: &str,
     gs: &TA::GlobalState,
 ) -> RuntimeResult<super::rw::FileOpen<JournalWriter<Fs, TA>>> {
     use super::rw::FileOpen;
-    let file = match SDSSFileIO::<Fs>::open_or_create_perm_rw::<F>(log_file_name)? {
-        FileOpen::Created(f) => return Ok(FileOpen::Created(JournalWriter::new(f, 0, true)?)),
-        FileOpen::Existing((file, _header)) => file,
+    let (file, header) = match SDSSFileIO::<Fs>::open_or_create_perm_rw::<F>(log_file_name)? {
+        FileOpen::Created(f) => {
+            return Ok(FileOpen::Created(JournalWriter::new(f, 0, true, None)?))
+        }
+        FileOpen::Existing(existing) => existing,
     };
-    let (file, last_txn) = JournalReader::<TA, Fs>::scroll(file, gs)?;
+    let key = encryption::file_key_for_header(&header)?;
+    let (file, last_txn) = JournalReader::<TA, Fs>::scroll(file, gs, key.clone())?;
     Ok(FileOpen::Existing(JournalWriter::new(
-        file, last_txn, false,
+        file, last_txn, false, key,
     )?))
 }
 
-pub fn create_journal<TA: JournalAdapter, Fs: RawFSInterface, F: spec::FileSpec>(
+/// Create a new journal, with events encrypted using the given key (if any)
+pub fn create_journal<
+    TA: JournalAdapter,
+    Fs: RawFSInterface,
+    F: spec::FileSpec<Header = SDSSStaticHeaderV1Compact>,
+>(
     log_file_name: &str,
+    encryption: Option<&EncryptionKey>,
 ) -> RuntimeResult<JournalWriter<Fs, TA>> {
-    JournalWriter::new(SDSSFileIO::create::<F>(log_file_name)?, 0, true)
+    let (file, header) = SDSSFileIO::create_with_format::<F>(
+        log_file_name,
+        encryption::header_format([0; 8], encryption),
+    )?;
+    let key = encryption.map(|key| FileKey::new(key.clone(), FileBinding::new(&header)));
+    JournalWriter::new(file, 0, true, key)
 }
 
-pub fn load_journal<TA: JournalAdapter, Fs: RawFSInterface, F: spec::FileSpec>(
+pub fn load_journal<
+    TA: JournalAdapter,
+    Fs: RawFSInterface,
+    F: spec::FileSpec<Header = SDSSStaticHeaderV1Compact>,
+>(
     log_file_name: &str,
     gs: &TA::GlobalState,
 ) -> RuntimeResult<JournalWriter<Fs, TA>> {
-    let (file, _) = SDSSFileIO::<Fs>::open::<F>(log_file_name)?;
-    let (file, last_txn_id) = JournalReader::<TA, Fs>::scroll(file, gs)?;
-    JournalWriter::new(file, last_txn_id, false)
+    let (file, header) = SDSSFileIO::<Fs>::open::<F>(log_file_name)?;
+    let key = encryption::file_key_for_header(&header)?;
+    let (file, last_txn_id) = JournalReader::<TA, Fs>::scroll(file, gs, key.clone())?;
+    JournalWriter::new(file, last_txn_id, false, key)
+}
+
+/// Truncate the journal at `log_file_name` to `len` bytes and close it (`next_id` is the ID of the close event)
+pub fn truncate_and_close_journal<Fs: RawFSInterface>(
+    log_file_name: &str,
+    len: u64,
+    next_id: u64,
+) -> RuntimeResult<()> {
+    let mut f = SDSSFileIO::<Fs>::_new(Fs::fs_fopen_rw(log_file_name)?);
+    f.truncate_to(len)?;
+    f.seek_from_start(len)?;
+    f.fsynced_write(
+        &JournalEntryMetadata::new(next_id as u128, EventSourceMarker::DRIVER_CLOSED, 0, 0)
+            .encoded(),
+    )
+}
+
+/// The result of scanning a journal (see [`JournalReader::scan`])
+#[derive(Debug)]
+pub struct JournalScan {
+    /// the length of the journal up to the last event that was read successfully
+    pub consistent_len: u64,
+    /// the ID of the event following the last event that was read successfully
+    pub next_id: u64,
+    /// the error that stopped us from reading the journal to the end (if any)
+    pub error: Option<Error>,
 }
 
 /// The journal adapter
@@ -197,17 +251,20 @@ pub struct JournalReader<TA, Fs: RawFSInterface> {
     evid: u64,
     closed: bool,
     remaining_bytes: u64,
+    /// the key that the payloads are encrypted with (if any)
+    key: Option<FileKey>,
     _m: PhantomData<TA>,
 }
 
 impl<TA: JournalAdapter, Fs: RawFSInterface> JournalReader<TA, Fs> {
-    pub fn new(log_file: SDSSFileIO<Fs>) -> RuntimeResult<Self> {
+    pub fn new(log_file: SDSSFileIO<Fs>, key: Option<FileKey>) -> RuntimeResult<Self> {
         let log_size = log_file.file_length()? - spec::SDSSStaticHeaderV1Compact::SIZE as u64;
         Ok(Self {
             log_file,
             evid: 0,
             closed: false,
             remaining_bytes: log_size,
+            key,
             _m: PhantomData,
         })
     }
@@ -261,6 +318,12 @@ impl<TA: JournalAdapter, Fs: RawFSInterface> JournalReader<TA, Fs> {
         if compiler::unlikely(CRC.checksum(&payload) != entry_metadata.event_crc) {
             return compiler::cold_call(|| self.try_recover_journal_strategy_simple_reverse());
         }
+        if let Some(key) = self.key.as_ref() {
+            // the checksum matched, so if this fails we have the wrong key (or the event was moved)
+            let offset = self.log_file.retrieve_cursor()?
+                - (JournalEntryMetadata::SIZE + payload.len()) as u64;
+            payload = key.open(&payload, offset)?;
+        }
         if compiler::unlikely(TA::decode_and_update_state(&payload, gs).is_err()) {
             return compiler::cold_call(|| self.try_recover_journal_strategy_simple_reverse());
         }
@@ -312,12 +375,45 @@ impl<TA: JournalAdapter, Fs: RawFSInterface> JournalReader<TA, Fs> {
             Err(StorageError::JournalCorrupted.into())
         }
     }
-    /// Read and apply all events in the given log file to the global state, returning the (open file, last event ID)
+    /// Read and apply events in the given log file (whose events are encrypted with `key`, if provided) to the global
+    /// state until we either reach the end of the journal or run into an error
+    pub fn scan(
+        file: SDSSFileIO<Fs>,
+        gs: &TA::GlobalState,
+        key: Option<FileKey>,
+    ) -> RuntimeResult<JournalScan> {
+        let mut slf = Self::new(file, key)?;
+        let mut consistent_len = slf.log_file.retrieve_cursor()?;
+        let mut next_id = 0;
+        while !slf.end_of_file() {
+            if let Err(e) = slf.rapply_next_event(gs) {
+                return Ok(JournalScan {
+                    consistent_len,
+                    next_id,
+                    error: Some(e),
+                });
+            }
+            if slf.closed {
+                // the close directive isn't part of what we'd keep if the journal were truncated
+                break;
+            }
+            consistent_len = slf.log_file.retrieve_cursor()?;
+            next_id = slf.evid;
+        }
+        Ok(JournalScan {
+            consistent_len,
+            next_id,
+            error: (!slf.closed).then(|| StorageError::JournalCorrupted.into()),
+        })
+    }
+    /// Read and apply all events in the given log file (whose events are encrypted with `key`, if provided) to the
+    /// global state, returning the (open file, last event ID)
     pub fn scroll(
         file: SDSSFileIO<Fs>,
         gs: &TA::GlobalState,
+        key: Option<FileKey>,
     ) -> RuntimeResult<(SDSSFileIO<Fs>, u64)> {
-        let mut slf = Self::new(file)?;
+        let mut slf = Self::new(file, key)?;
         while !slf.end_of_file() {
             slf.rapply_next_event(gs)?;
         }
@@ -363,10 +459,19 @@ pub struct JournalWriter<Fs: RawFSInterface, TA> {
     id: u64,
     _m: PhantomData<TA>,
     closed: bool,
+    /// when new events are synced to disk
+    sync: SyncState,
+    /// the key that new events are encrypted with (this must match the key ID in the header)
+    key: Option<FileKey>,
 }
 
 impl<Fs: RawFSInterface, TA: JournalAdapter> JournalWriter<Fs, TA> {
-    pub fn new(mut log_file: SDSSFileIO<Fs>, last_txn_id: u64, new: bool) -> RuntimeResult<Self> {
+    pub fn new(
+        mut log_file: SDSSFileIO<Fs>,
+        last_txn_id: u64,
+        new: bool,
+        key: Option<FileKey>,
+    ) -> RuntimeResult<Self> {
         let log_size = log_file.file_length()?;
         log_file.seek_from_start(log_size)?; // avoid jumbling with headers
         let mut slf = Self {
@@ -374,6 +479,8 @@ impl<Fs: RawFSInterface, TA: JournalAdapter> JournalWriter<Fs, TA> {
             id: last_txn_id,
             _m: PhantomData,
             closed: false,
+            sync: SyncState::new(DurabilityPolicy::Always),
+            key,
         };
         if !new {
             // IMPORTANT: don't forget this; otherwise the journal reader will report a false error!
@@ -382,7 +489,12 @@ impl<Fs: RawFSInterface, TA: JournalAdapter> JournalWriter<Fs, TA> {
         Ok(slf)
     }
     pub fn append_event(&mut self, event: TA::JournalEvent) -> RuntimeResult<()> {
-        let encoded = TA::encode(event);
+        let mut encoded = TA::encode(event);
+        if let Some(key) = self.key.as_ref() {
+            // (the event is bound to where it starts, which is where its metadata goes)
+            let offset = self.log_file.retrieve_cursor()?;
+            encoded = key.seal(&encoded, offset)?.into_boxed_slice();
+        }
         let md = JournalEntryMetadata::new(
             self._incr_id() as u128,
             EventSourceMarker::SERVER_STD,
@@ -392,7 +504,10 @@ impl<Fs: RawFSInterface, TA: JournalAdapter> JournalWriter<Fs, TA> {
         .encoded();
         self.log_file.unfsynced_write(&md)?;
         self.log_file.unfsynced_write(&encoded)?;
-        self.log_file.fsync_all()?;
+        if self.sync.commit() {
+            self.log_file.fsync_all()?;
+            self.sync.synced();
+        }
         Ok(())
     }
     pub fn append_event_with_recovery_plugin(
@@ -412,6 +527,18 @@ impl<Fs: RawFSInterface, TA: JournalAdapter> JournalWriter<Fs, TA> {
 }
 
 impl<Fs: RawFSInterface, TA> JournalWriter<Fs, TA> {
+    /// Set when new events are synced to disk (every event is synced by default)
+    pub fn set_durability(&mut self, policy: DurabilityPolicy) {
+        self.sync = SyncState::new(policy);
+    }
+    /// Sync any events that weren't synced yet, if the sync interval has elapsed
+    pub fn sync_if_due(&mut self) -> RuntimeResult<()> {
+        if self.sync.sync_due() {
+            self.log_file.fsync_all()?;
+            self.sync.synced();
+        }
+        Ok(())
+    }
     pub fn appendrec_journal_reverse_entry(&mut self) -> RuntimeResult<()> {
         let mut entry =
             JournalEntryMetadata::new(0, EventSourceMarker::RECOVERY_REVERSE_LAST_JOURNAL, 0, 0);
@@ -435,6 +562,19 @@ impl<Fs: RawFSInterface, TA> JournalWriter<Fs, TA> {
         )?;
         Ok(())
     }
+    /// Copy this journal (stored at `from`) into `to` and close the copy, so that it can be loaded independently
+    pub fn backup_to(&self, from: &str, to: &str) -> RuntimeResult<()> {
+        // every event is written to the OS on append, so the file on disk is complete (and the copy is synced)
+        Fs::fs_copy_file(from, to)?;
+        let mut copy = SDSSFileIO::<Fs>::_new(Fs::fs_fopen_rw(to)?);
+        let copy_len = copy.file_length()?;
+        copy.seek_from_start(copy_len)?;
+        // NB: the close event takes the ID of the next event in the source journal
+        copy.fsynced_write(
+            &JournalEntryMetadata::new(self.id as u128, EventSourceMarker::DRIVER_CLOSED, 0, 0)
+                .encoded(),
+        )
+    }
     pub fn close(mut self) -> RuntimeResult<()> {
         self.__close_mut()
     }
@@ -30,28 +30,37 @@ use crate::engine::storage::v1::{
     JournalWriter,
 };
 use crate::engine::{
+    config::ConfigRecoverUntil,
     core::{EntityIDRef, GlobalNS},
     data::uuid::Uuid,
-    error::RuntimeResult,
-    fractal::error::ErrorContext,
-    fractal::{FractalModelDriver, ModelDrivers, ModelUniqueID},
-    storage::v1::{batch_jrnl, journal, spec, LocalFS},
+    error::{ErrorKind, RuntimeResult},
+    fractal::error::{Error, ErrorContext},
+    fractal::{startup, FractalModelDriver, ModelDrivers, ModelUniqueID},
+    storage::{
+        engine::{IoBackend, StorageEngineKind},
+        v1::{data_batch::RecoveryMode, encryption::EncryptionKey, journal, spec, LocalFS},
+    },
     txn::gns::{GNSAdapter, GNSTransactionDriverAnyFS},
 };
+use std::{
+    fs::{self, File},
+    io,
+    path::Path,
+};
 
-const GNS_FILE_PATH: &str = "gns.db-tlog";
-const DATA_DIR: &str = "data";
+pub const GNS_FILE_PATH: &str = "gns.db-tlog";
+pub const DATA_DIR: &str = "data";
 
 pub struct SEInitState {
     pub txn_driver: GNSTransactionDriverAnyFS<super::LocalFS>,
-    pub model_drivers: ModelDrivers<LocalFS>,
+    pub model_drivers: ModelDrivers,
     pub gns: GlobalNS,
 }
 
 impl SEInitState {
     pub fn new(
         txn_driver: GNSTransactionDriverAnyFS<super::LocalFS>,
-        model_drivers: ModelDrivers<LocalFS>,
+        model_drivers: ModelDrivers,
         gns: GlobalNS,
     ) -> Self {
         Self {
@@ -60,10 +69,23 @@ impl SEInitState {
             gns,
         }
     }
-    pub fn try_init(is_new: bool) -> RuntimeResult<Self> {
+    /// Load the GNS and all models (or create the GNS txn log, encrypted using `encryption` if provided, if this is a
+    /// new instance). The models are restored using the given storage engine and I/O backend, and `recovery` decides
+    /// what happens to the storage of a model that wasn't closed
+    pub fn try_init(
+        is_new: bool,
+        recover_until: &[ConfigRecoverUntil],
+        encryption: Option<&EncryptionKey>,
+        storage_engine: StorageEngineKind,
+        io_backend: IoBackend,
+        recovery: RecoveryMode,
+    ) -> RuntimeResult<Self> {
         let gns = GlobalNS::empty();
         let gns_txn_driver = if is_new {
-            journal::create_journal::<GNSAdapter, LocalFS, spec::GNSTransactionLogV1>(GNS_FILE_PATH)
+            journal::create_journal::<GNSAdapter, LocalFS, spec::GNSTransactionLogV1>(
+                GNS_FILE_PATH,
+                encryption,
+            )
         } else {
             journal::load_journal::<GNSAdapter, LocalFS, spec::GNSTransactionLogV1>(
                 GNS_FILE_PATH,
@@ -72,30 +94,96 @@ impl SEInitState {
         }?;
         let mut model_drivers = ModelDrivers::new();
         let mut driver_guard = || {
+            // make sure that all recovery targets exist before we touch anything
+            for target in recover_until {
+                if !gns
+                    .idx_models()
+                    .read()
+                    .contains_key(&EntityIDRef::new(target.space(), target.model()))
+                {
+                    return Err(Error::with_kind(ErrorKind::Other(format!(
+                        "cannot recover `{}.{}` because no such model exists",
+                        target.space(),
+                        target.model()
+                    ))));
+                }
+            }
             if is_new {
                 std::fs::create_dir(DATA_DIR).inherit_set_dmsg("creating data directory")?;
             }
             if !is_new {
                 let mut models = gns.idx_models().write();
-                // this is an existing instance, so read in all data
-                for (space_name, space) in gns.idx().read().iter() {
+                let spaces = gns.idx().read();
+                // register everything up front so that the progress report shows what's left
+                let mut progress = startup::begin(spaces.iter().flat_map(|(space_name, space)| {
+                    space
+                        .models()
+                        .iter()
+                        .map(move |model_name| (&**space_name, &**model_name))
+                }))
+                .into_iter();
+                // this is an existing instance, so read in all data (in the order we registered the models in)
+                for (space_name, space) in spaces.iter() {
                     let space_uuid = space.get_uuid();
                     for model_name in space.models().iter() {
+                        let progress = progress.next().unwrap();
                         let model = models
                             .get_mut(&EntityIDRef::new(&space_name, &model_name))
                             .unwrap();
                         let path =
                             Self::model_path(space_name, space_uuid, model_name, model.get_uuid());
-                        let persist_driver = batch_jrnl::reinit(&path, model).inherit_set_dmsg(
-                            format!("failed to restore model data from journal in `{path}`"),
-                        )?;
+                        let recovery_target = recover_until.iter().find(|target| {
+                            (target.space() == &**space_name) & (target.model() == &**model_name)
+                        });
+                        // ephemeral models have nothing to restore
+                        let storage_engine =
+                            storage_engine.local_model_engine(io_backend, model.is_ephemeral());
+                        let (storage, report) = progress
+                            .track(|| match recovery_target {
+                                Some(target) => {
+                                    info!(
+                                        "recovering model {space_name}.{model_name} to txn {}",
+                                        target.txn_id()
+                                    );
+                                    storage_engine.restore_model(
+                                        &path,
+                                        model,
+                                        Some(target.txn_id()),
+                                        recovery,
+                                    )
+                                }
+                                None => storage_engine.restore_model(&path, model, None, recovery),
+                            })
+                            .inherit_set_dmsg(format!(
+                                "failed to restore model data from journal in `{path}`"
+                            ))?;
+                        if !report.corrupted_events().is_empty() {
+                            warn!(
+                                "skipped {} corrupted event(s) in `{path}` while restoring model {space_name}.{model_name} (at offsets {:?})",
+                                report.corrupted_events().len(),
+                                report.corrupted_events()
+                            );
+                        }
+                        if let Some((len, discarded)) = report.torn_tail() {
+                            warn!(
+                                "`{path}` wasn't closed, so it was truncated to the last complete batch at offset {len} while restoring model {space_name}.{model_name} (discarded {discarded} byte(s) of a partially written batch)"
+                            );
+                        }
+                        if !report.corrupted_segment_events().is_empty() {
+                            warn!(
+                                "skipped {} corrupted event(s) in the sealed segments of `{path}` while restoring model {space_name}.{model_name} (at segments and offsets {:?})",
+                                report.corrupted_segment_events().len(),
+                                report.corrupted_segment_events()
+                            );
+                        }
                         unsafe {
                             // UNSAFE(@ohsayan): all pieces of data are upgraded by now, so vacuum
                             model.model_mutator().vacuum_stashed();
                         }
+                        model.rebuild_secondary_indexes();
                         let _ = model_drivers.insert(
                             ModelUniqueID::new(space_name, model_name, model.get_uuid()),
-                            FractalModelDriver::init(persist_driver),
+                            FractalModelDriver::init(storage),
                         );
                     }
                 }
@@ -139,6 +227,45 @@ impl SEInitState {
     }
 }
 
+/// Restore a backup (created using `sysctl backup`) into the current directory
+///
+/// This must be run before anything is loaded. We will never overwrite an existing database, so this fails if any of the
+/// files or directories in the backup are already present
+pub fn restore_backup(backup_dir: &str) -> RuntimeResult<()> {
+    fn copy_dir_all(from: &Path, to: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            let target = to.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                fs::create_dir(&target)?;
+                copy_dir_all(&entry.path(), &target)?;
+            } else {
+                let mut src = File::open(entry.path())?;
+                let mut dst = File::options().write(true).create_new(true).open(&target)?;
+                io::copy(&mut src, &mut dst)?;
+                dst.sync_all()?;
+            }
+        }
+        Ok(())
+    }
+    let backup_dir = Path::new(backup_dir);
+    // check for an existing database before we touch anything
+    for entry in fs::read_dir(backup_dir).inherit_set_dmsg("failed to read backup directory")? {
+        let entry = entry?;
+        if Path::new(&entry.file_name()).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "refusing to overwrite existing `{}`",
+                    entry.file_name().to_string_lossy()
+                ),
+            )
+            .into());
+        }
+    }
+    copy_dir_all(backup_dir, Path::new(".")).inherit_set_dmsg("failed to restore backup")
+}
+
 #[cfg(test)]
 pub fn open_gns_driver<Fs: RawFSInterface>(
     path: &str,
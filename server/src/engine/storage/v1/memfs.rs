@@ -127,6 +127,19 @@ impl RawFSInterface for VirtualFS {
         // delete old file
         Self::fs_remove_file(from)
     }
+    fn fs_copy_file(from: &str, to: &str) -> RuntimeResult<()> {
+        // get file data
+        let data = with_file(from, |f| Ok(f.data.clone()))?;
+        // create new file
+        let file = VirtualFS::fs_fopen_or_create_rw(to)?;
+        match file {
+            FileOpen::Created(mut c) => c.fw_write_all(&data),
+            FileOpen::Existing(mut e) => {
+                e.fwext_truncate_to(0)?;
+                e.fw_write_all(&data)
+            }
+        }
+    }
     fn fs_remove_file(fpath: &str) -> RuntimeResult<()> {
         handle_item_mut(fpath, |e| match e.get() {
             VNode::File(_) => {
@@ -505,6 +518,9 @@ impl RawFSInterface for NullFS {
     fn fs_rename_file(_: &str, _: &str) -> RuntimeResult<()> {
         Ok(())
     }
+    fn fs_copy_file(_: &str, _: &str) -> RuntimeResult<()> {
+        Ok(())
+    }
     fn fs_remove_file(_: &str) -> RuntimeResult<()> {
         Ok(())
     }
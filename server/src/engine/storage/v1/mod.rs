@@ -25,12 +25,21 @@
 */
 
 // impls
+pub(in crate::engine) mod audit;
 mod batch_jrnl;
+#[cfg(target_os = "linux")]
+pub(in crate::engine) mod direct;
+pub mod durability;
+pub mod encryption;
 mod journal;
 pub(in crate::engine) mod loader;
+pub(in crate::engine) mod repair;
 mod rw;
+pub(in crate::engine) mod snapshot;
 pub mod spec;
 pub mod sysdb;
+#[cfg(target_os = "linux")]
+pub(in crate::engine) mod uring;
 // hl
 pub mod inf;
 // test
@@ -41,8 +50,14 @@ mod tests;
 // re-exports
 pub use {
     journal::{JournalAdapter, JournalWriter},
-    rw::{LocalFS, RawFSInterface, SDSSFileIO},
+    rw::{
+        LocalFS, RawFSInterface, RawFileInterfaceExt, RawFileInterfaceRead, RawFileInterfaceWrite,
+        RawFileInterfaceWriteExt, SDSSFileIO,
+    },
 };
 pub mod data_batch {
-    pub use super::batch_jrnl::{create, DataBatchPersistDriver};
+    pub use super::batch_jrnl::{
+        compact, create, read_segment, recover_until, reinit, rotate, DataBatchPersistDriver,
+        DataBatchRestoreDriver, DataBatchRestoreReport, JournalSegment, RecoveryMode,
+    };
 }
@@ -0,0 +1,200 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+/*
+    repair (`skyd --repair`)
+    ---
+    we scan the GNS txn log (loading it into an empty namespace, so that we know which data batch journals exist) and
+    then every data batch journal (along with its sealed segments). for every file we report whatever had to be skipped
+    and, if the file can't be read to the end, the last consistent point in the file (which is also what we truncate the
+    file to)
+*/
+
+use {
+    super::{
+        batch_jrnl::{self, DataBatchRestoreDriver},
+        encryption,
+        journal::{self, JournalReader},
+        loader::{SEInitState, GNS_FILE_PATH},
+        rw::{RawFSInterface, SDSSFileIO},
+        spec, LocalFS,
+    },
+    crate::engine::{
+        core::{EntityIDRef, GlobalNS},
+        error::RuntimeResult,
+        fractal::error::Error,
+        txn::gns::GNSAdapter,
+    },
+    std::path::Path,
+};
+
+/// The result of checking a single file
+#[derive(Debug)]
+pub struct FileCheck {
+    /// offsets of the events that were skipped because they were corrupted
+    corrupted_events: Vec<u64>,
+    /// if the file can't be read to the end: the length of the file up to its last consistent point and the error
+    unreadable: Option<(u64, Error)>,
+    /// set if the file was truncated to its last consistent point
+    truncated: bool,
+}
+
+impl FileCheck {
+    fn new(corrupted_events: Vec<u64>, unreadable: Option<(u64, Error)>) -> Self {
+        Self {
+            corrupted_events,
+            unreadable,
+            truncated: false,
+        }
+    }
+    pub fn corrupted_events(&self) -> &[u64] {
+        &self.corrupted_events
+    }
+    pub fn unreadable(&self) -> Option<&(u64, Error)> {
+        self.unreadable.as_ref()
+    }
+    /// Returns true if the file can be loaded (either because it was readable or because we truncated it)
+    pub fn is_loadable(&self) -> bool {
+        self.unreadable.is_none() | self.truncated
+    }
+}
+
+/// Check the GNS txn log at `path` (loading it into `gns`), truncating it to its last consistent point if needed (and
+/// asked to)
+pub fn check_gns<Fs: RawFSInterface>(
+    path: &str,
+    gns: &GlobalNS,
+    truncate: bool,
+) -> RuntimeResult<FileCheck> {
+    let (f, header) = SDSSFileIO::<Fs>::open::<spec::GNSTransactionLogV1>(path)?;
+    let key = encryption::file_key_for_header(&header)?;
+    let scan = JournalReader::<GNSAdapter, Fs>::scan(f, gns, key)?;
+    let mut check = FileCheck::new(vec![], scan.error.map(|e| (scan.consistent_len, e)));
+    if truncate & check.unreadable.is_some() {
+        journal::truncate_and_close_journal::<Fs>(path, scan.consistent_len, scan.next_id)?;
+        check.truncated = true;
+    }
+    Ok(check)
+}
+
+/// Check the data batch journal at `path`, truncating it to its last consistent point if needed (and asked to)
+pub fn check_data_batch<Fs: RawFSInterface>(
+    path: &str,
+    truncate: bool,
+) -> RuntimeResult<FileCheck> {
+    let (f, header) = SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(path)?;
+    let mut restore_driver = DataBatchRestoreDriver::new(f)?
+        .with_checksum(spec::checksum_from_header(&header))
+        .with_encoding(spec::BatchEncoding::from_header(&header))
+        .with_file(encryption::FileBinding::new(&header));
+    let (report, ret) = restore_driver.scan();
+    let consistent_len = restore_driver.consistent_len();
+    // release the file before we touch it
+    drop(restore_driver);
+    let mut check = FileCheck::new(
+        report.corrupted_events().to_vec(),
+        ret.err().map(|e| (consistent_len, e)),
+    );
+    if truncate & check.unreadable.is_some() {
+        batch_jrnl::truncate_and_close::<Fs>(path, consistent_len)?;
+        check.truncated = true;
+    }
+    Ok(check)
+}
+
+/// Returns the number of sealed segments of the data batch journal at `path`
+fn data_batch_segments<Fs: RawFSInterface>(path: &str) -> RuntimeResult<u32> {
+    let (_, header) = SDSSFileIO::<Fs>::open::<spec::DataBatchJournalV1>(path)?;
+    Ok(spec::batch_segment_from_header(&header))
+}
+
+/// Check (and if `truncate` is set, repair) all the database files in the current directory, logging everything we
+/// find. Returns true if the database can be loaded
+pub fn repair_all(truncate: bool) -> RuntimeResult<bool> {
+    if !Path::new(GNS_FILE_PATH).exists() {
+        info!("repair: no database found in the current directory");
+        return Ok(true);
+    }
+    let gns = GlobalNS::empty();
+    let mut loadable = log_check(
+        GNS_FILE_PATH,
+        check_gns::<LocalFS>(GNS_FILE_PATH, &gns, truncate),
+    );
+    let spaces = gns.idx().read();
+    let models = gns.idx_models().read();
+    for (space_name, space) in spaces.iter() {
+        for model_name in space.models().iter() {
+            let model = models
+                .get(&EntityIDRef::new(space_name, model_name))
+                .unwrap();
+            if model.is_ephemeral() {
+                // there's no journal to check
+                continue;
+            }
+            let path =
+                SEInitState::model_path(space_name, space.get_uuid(), model_name, model.get_uuid());
+            // if we can't read the header of the journal, that's reported when we check it below
+            let segment = data_batch_segments::<LocalFS>(&path).unwrap_or(0);
+            for seq in 1..=segment {
+                let sealed = batch_jrnl::segment_path(&path, seq);
+                loadable &= log_check(&sealed, check_data_batch::<LocalFS>(&sealed, truncate));
+            }
+            loadable &= log_check(&path, check_data_batch::<LocalFS>(&path, truncate));
+        }
+    }
+    Ok(loadable)
+}
+
+fn log_check(path: &str, check: RuntimeResult<FileCheck>) -> bool {
+    let check = match check {
+        Ok(check) => check,
+        Err(e) => {
+            error!("repair: failed to check `{path}`: {e}");
+            return false;
+        }
+    };
+    for offset in check.corrupted_events() {
+        warn!("repair: `{path}`: corrupted event at offset {offset} (it will be skipped)");
+    }
+    match check.unreadable() {
+        None => {
+            if check.corrupted_events().is_empty() {
+                info!("repair: `{path}`: ok");
+            }
+        }
+        Some((consistent_len, e)) => {
+            error!(
+                "repair: `{path}`: {e}. the file can only be read up to offset {consistent_len}"
+            );
+            if check.truncated {
+                info!("repair: `{path}`: truncated to {consistent_len} bytes");
+            } else {
+                info!("repair: `{path}`: use `--repair truncate` to truncate the file to this point (everything after it will be lost)");
+            }
+        }
+    }
+    check.is_loadable()
+}
@@ -25,12 +25,16 @@
 */
 
 use {
-    super::spec::{FileSpec, Header},
+    super::spec::{FileSpec, Header, SDSSStaticHeaderV1Compact},
     crate::{
-        engine::{error::RuntimeResult, storage::SCrc},
+        engine::{
+            error::RuntimeResult,
+            storage::{ChecksumKind, SCrc},
+        },
         util::os::SysIOError,
     },
     std::{
+        cell::Cell,
         fs::{self, File},
         io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
         marker::PhantomData,
@@ -71,15 +75,20 @@ impl<F> FileOpen<F> {
 }
 
 /// The specification for a file system interface (our own abstraction over the fs)
-pub trait RawFSInterface {
+///
+/// Files are handed between threads (a model's storage is written to by whichever thread flushes it), so they must be
+/// [`Send`]
+pub trait RawFSInterface: Send + 'static {
     /// asserts that the file system is not a null filesystem (like `/dev/null` for example)
     const NOT_NULL: bool = true;
     /// the file descriptor that is returned by the file system when a file is opened
-    type File: RawFileInterface;
+    type File: RawFileInterface + Send;
     /// Remove a file
     fn fs_remove_file(fpath: &str) -> RuntimeResult<()>;
     /// Rename a file
     fn fs_rename_file(from: &str, to: &str) -> RuntimeResult<()>;
+    /// Copy a file (the target is created or truncated)
+    fn fs_copy_file(from: &str, to: &str) -> RuntimeResult<()>;
     /// Create a directory
     fn fs_create_dir(fpath: &str) -> RuntimeResult<()>;
     /// Create a directory and all corresponding path components
@@ -109,7 +118,7 @@ where
         + RawFileInterfaceExt,
 {
     type BufReader: RawFileInterfaceBufferedReader;
-    type BufWriter: RawFileInterfaceBufferedWriter;
+    type BufWriter: RawFileInterfaceBufferedWriter + Send;
     fn into_buffered_reader(self) -> RuntimeResult<Self::BufReader>;
     fn downgrade_reader(r: Self::BufReader) -> RuntimeResult<Self>;
     fn into_buffered_writer(self) -> RuntimeResult<Self::BufWriter>;
@@ -120,9 +129,17 @@ pub trait RawFileInterfaceBufferedReader: RawFileInterfaceRead + RawFileInterfac
 impl<R: RawFileInterfaceRead + RawFileInterfaceExt> RawFileInterfaceBufferedReader for R {}
 
 pub trait RawFileInterfaceBufferedWriter: RawFileInterfaceWrite + RawFileInterfaceExt {
+    fn flush_write_cache(&mut self) -> RuntimeResult<()> {
+        Ok(())
+    }
     fn sync_write_cache(&mut self) -> RuntimeResult<()> {
         Ok(())
     }
+    /// Reserve disk space for the first `len` bytes of the file without changing its length. This is only a hint, so
+    /// it does nothing if it isn't supported
+    fn preallocate(&mut self, _len: u64) -> RuntimeResult<()> {
+        Ok(())
+    }
 }
 
 /// A file interface that supports read operations
@@ -177,6 +194,11 @@ impl RawFSInterface for LocalFS {
     fn fs_rename_file(from: &str, to: &str) -> RuntimeResult<()> {
         cvt(fs::rename(from, to))
     }
+    fn fs_copy_file(from: &str, to: &str) -> RuntimeResult<()> {
+        cvt(fs::copy(from, to))?;
+        // we want the copy to be durable, so sync it
+        cvt(File::open(to)?.sync_all())
+    }
     fn fs_create_dir(fpath: &str) -> RuntimeResult<()> {
         cvt(fs::create_dir(fpath))
     }
@@ -236,11 +258,42 @@ impl RawFileInterface for File {
 }
 
 impl RawFileInterfaceBufferedWriter for BufWriter<File> {
+    fn flush_write_cache(&mut self) -> RuntimeResult<()> {
+        cvt(self.flush())
+    }
     fn sync_write_cache(&mut self) -> RuntimeResult<()> {
         self.flush()?;
         self.get_mut().sync_all()?;
         Ok(())
     }
+    #[cfg(target_os = "linux")]
+    fn preallocate(&mut self, len: u64) -> RuntimeResult<()> {
+        fallocate_keep_size(self.get_ref(), len)
+    }
+}
+
+/// Reserve disk space for the first `len` bytes of the file without changing its length (if the file system supports it)
+#[cfg(target_os = "linux")]
+pub(super) fn fallocate_keep_size(f: &File, len: u64) -> RuntimeResult<()> {
+    use std::os::fd::AsRawFd;
+    let ret = unsafe {
+        // UNSAFE(@ohsayan): the fd is valid for as long as we hold the file
+        libc::fallocate(
+            f.as_raw_fd(),
+            libc::FALLOC_FL_KEEP_SIZE,
+            0,
+            len as libc::off_t,
+        )
+    };
+    if ret == 0 {
+        return Ok(());
+    }
+    let e = std::io::Error::last_os_error();
+    match e.raw_os_error() {
+        // not every file system supports this
+        Some(libc::EOPNOTSUPP) | Some(libc::ENOSYS) => Ok(()),
+        _ => Err(e.into()),
+    }
 }
 
 impl RawFileInterfaceWriteExt for File {
@@ -252,7 +305,7 @@ impl RawFileInterfaceWriteExt for File {
     }
 }
 
-trait LocalFSFile {
+pub(super) trait LocalFSFile {
     fn file_mut(&mut self) -> &mut File;
     fn file(&self) -> &File;
 }
@@ -299,30 +352,73 @@ impl<F: LocalFSFile> RawFileInterfaceExt for F {
 pub struct SDSSFileTrackedWriter<Fs: RawFSInterface> {
     f: SDSSFileIO<Fs, <Fs::File as RawFileInterface>::BufWriter>,
     cs: SCrc,
+    /// if set, writes are staged here (instead of going to the file) until the block is taken
+    block: Option<Vec<u8>>,
+    /// the offset in the file that the next write goes to
+    pos: u64,
 }
 
 impl<Fs: RawFSInterface> SDSSFileTrackedWriter<Fs> {
-    pub fn new(f: SDSSFileIO<Fs>) -> RuntimeResult<Self> {
+    pub fn new(mut f: SDSSFileIO<Fs>) -> RuntimeResult<Self> {
+        let pos = f.retrieve_cursor()?;
         Ok(Self {
             f: f.into_buffered_sdss_writer()?,
-            cs: SCrc::new(),
+            cs: SCrc::new(ChecksumKind::NEW),
+            block: None,
+            pos,
         })
     }
+    /// Returns the offset in the file that the next write goes to (staged writes only count once the block is taken
+    /// and written out)
+    pub fn cursor(&self) -> u64 {
+        self.pos
+    }
+    /// Use the given checksum (instead of the one used in new files) from here on
+    pub fn set_checksum(&mut self, kind: ChecksumKind) {
+        self.cs = SCrc::new(kind);
+    }
+    /// Stage all writes in memory until [`Self::take_block`] is called
+    pub fn begin_block(&mut self) {
+        self.block = Some(vec![]);
+    }
+    /// Stop staging writes, returning everything that was staged
+    pub fn take_block(&mut self) -> Vec<u8> {
+        self.block.take().unwrap_or_default()
+    }
     pub fn tracked_write_unfsynced(&mut self, block: &[u8]) -> RuntimeResult<()> {
         self.untracked_write(block)
             .map(|_| self.cs.recompute_with_new_var_block(block))
     }
     pub fn untracked_write(&mut self, block: &[u8]) -> RuntimeResult<()> {
+        if let Some(staged) = self.block.as_mut() {
+            staged.extend_from_slice(block);
+            return Ok(());
+        }
         match self.f.unfsynced_write(block) {
-            Ok(()) => Ok(()),
+            Ok(()) => {
+                self.pos += block.len() as u64;
+                Ok(())
+            }
             e => e,
         }
     }
+    /// Hand all buffered writes to the OS, without syncing them to disk
+    pub fn flush_writes(&mut self) -> RuntimeResult<()> {
+        self.f.f.flush_write_cache()
+    }
     pub fn sync_writes(&mut self) -> RuntimeResult<()> {
         self.f.f.sync_write_cache()
     }
+    /// Reserve disk space for the first `len` bytes of the file (see [`RawFileInterfaceBufferedWriter::preallocate`])
+    pub fn preallocate(&mut self, len: u64) -> RuntimeResult<()> {
+        self.f.f.preallocate(len)
+    }
+    pub fn file_length(&self) -> RuntimeResult<u64> {
+        self.f.file_length()
+    }
     pub fn reset_and_finish_checksum(&mut self) -> u64 {
-        let scrc = core::mem::replace(&mut self.cs, SCrc::new());
+        let kind = self.cs.kind();
+        let scrc = core::mem::replace(&mut self.cs, SCrc::new(kind));
         scrc.finish()
     }
     pub fn into_inner_file(self) -> RuntimeResult<SDSSFileIO<Fs>> {
@@ -333,10 +429,30 @@ impl<Fs: RawFSInterface> SDSSFileTrackedWriter<Fs> {
 /// [`SDSSFileLenTracked`] simply maintains application level length and checksum tracking to avoid frequent syscalls because we
 /// do not expect (even though it's very possible) users to randomly modify file lengths while we're reading them
 pub struct SDSSFileTrackedReader<Fs: RawFSInterface> {
-    f: SDSSFileIO<Fs, <Fs::File as RawFileInterface>::BufReader>,
+    f: TrackedReadSource<Fs>,
     len: u64,
     pos: u64,
     cs: SCrc,
+    /// if set, reads are served from this (in-memory) block instead of the file
+    block: Option<TrackedReadBlock>,
+    /// set once we needed more data than what's left in the file (see [`Self::ran_out`])
+    ran_out: Cell<bool>,
+}
+
+enum TrackedReadSource<Fs: RawFSInterface> {
+    File(SDSSFileIO<Fs, <Fs::File as RawFileInterface>::BufReader>),
+    /// a segment of a file that's already in memory (the reader was created at the offset that it starts at)
+    Segment {
+        data: Vec<u8>,
+        offset: u64,
+    },
+}
+
+struct TrackedReadBlock {
+    data: Vec<u8>,
+    pos: usize,
+    /// the offset in the file that this block was read from
+    offset: u64,
 }
 
 impl<Fs: RawFSInterface> SDSSFileTrackedReader<Fs> {
@@ -346,20 +462,81 @@ impl<Fs: RawFSInterface> SDSSFileTrackedReader<Fs> {
         let pos = f.retrieve_cursor()?;
         let f = f.into_buffered_sdss_reader()?;
         Ok(Self {
-            f,
+            f: TrackedReadSource::File(f),
             len,
             pos,
-            cs: SCrc::new(),
+            cs: SCrc::new(ChecksumKind::NEW),
+            block: None,
+            ran_out: Cell::new(false),
         })
     }
+    /// Read from a segment of a file that starts at `offset` in the file, instead of from the file itself
+    pub fn new_segment(data: Vec<u8>, offset: u64) -> Self {
+        Self {
+            len: offset + data.len() as u64,
+            pos: offset,
+            f: TrackedReadSource::Segment { data, offset },
+            cs: SCrc::new(ChecksumKind::NEW),
+            block: None,
+            ran_out: Cell::new(false),
+        }
+    }
+    /// Use the given checksum (instead of the one used in new files) from here on
+    pub fn set_checksum(&mut self, kind: ChecksumKind) {
+        self.cs = SCrc::new(kind);
+    }
+    pub fn checksum_kind(&self) -> ChecksumKind {
+        self.cs.kind()
+    }
+    /// Serve all reads from `data` (which was read from `offset` in the file) until [`Self::leave_block`] is called
+    ///
+    /// While in the block, the cursor stays at `offset`
+    pub fn enter_block(&mut self, data: Vec<u8>, offset: u64) {
+        self.block = Some(TrackedReadBlock {
+            data,
+            pos: 0,
+            offset,
+        });
+    }
+    /// Go back to reading from the file, returning true if the entire block was read
+    pub fn leave_block(&mut self) -> bool {
+        self.block
+            .take()
+            .map_or(true, |block| block.pos == block.data.len())
+    }
+    pub fn cursor(&self) -> u64 {
+        match self.block.as_ref() {
+            Some(block) => block.offset,
+            None => self.pos,
+        }
+    }
     pub fn remaining(&self) -> u64 {
-        self.len - self.pos
+        match self.block.as_ref() {
+            Some(block) => (block.data.len() - block.pos) as u64,
+            None => self.len - self.pos,
+        }
     }
     pub fn is_eof(&self) -> bool {
-        self.len == self.pos
+        self.remaining() == 0
     }
     pub fn has_left(&self, v: u64) -> bool {
-        self.remaining() >= v
+        let has_left = self.remaining() >= v;
+        if !has_left & self.block.is_none() {
+            self.ran_out.set(true);
+        }
+        has_left
+    }
+    /// Returns true if a read (or a check for what's left) needed more data than what was left in the file since
+    /// [`Self::reset_ran_out`] was last called. Running out of data in a block doesn't count, since the entire block was
+    /// read from the file
+    ///
+    /// This doesn't mean that the file was cut short: a corrupted length also asks for more than what's left, so use
+    /// [`Self::read_tail_from`] to look at what follows
+    pub fn ran_out(&self) -> bool {
+        self.ran_out.get()
+    }
+    pub fn reset_ran_out(&mut self) {
+        self.ran_out.set(false);
     }
     pub fn read_into_buffer(&mut self, buf: &mut [u8]) -> RuntimeResult<()> {
         self.untracked_read(buf)
@@ -370,25 +547,91 @@ impl<Fs: RawFSInterface> SDSSFileTrackedReader<Fs> {
         self.read_into_buffer(&mut buf).map(|_| buf[0])
     }
     pub fn __reset_checksum(&mut self) -> u64 {
-        let mut crc = SCrc::new();
+        let mut crc = SCrc::new(self.cs.kind());
         core::mem::swap(&mut crc, &mut self.cs);
         crc.finish()
     }
     pub fn untracked_read(&mut self, buf: &mut [u8]) -> RuntimeResult<()> {
-        if self.remaining() >= buf.len() as u64 {
-            match self.f.read_to_buffer(buf) {
-                Ok(()) => {
-                    self.pos += buf.len() as u64;
-                    Ok(())
+        if let Some(block) = self.block.as_mut() {
+            let Some(data) = block.data.get(block.pos..block.pos + buf.len()) else {
+                return Err(SysIOError::from(std::io::ErrorKind::InvalidInput).into());
+            };
+            buf.copy_from_slice(data);
+            block.pos += buf.len();
+            return Ok(());
+        }
+        if self.has_left(buf.len() as u64) {
+            match &mut self.f {
+                TrackedReadSource::File(f) => f.read_to_buffer(buf)?,
+                TrackedReadSource::Segment { data, offset } => {
+                    let start = (self.pos - *offset) as usize;
+                    buf.copy_from_slice(&data[start..start + buf.len()]);
                 }
-                Err(e) => return Err(e),
             }
+            self.pos += buf.len() as u64;
+            Ok(())
         } else {
             Err(SysIOError::from(std::io::ErrorKind::InvalidInput).into())
         }
     }
+    /// Read everything in the file from `offset` (which must not be ahead of the cursor) to the end, leaving the cursor
+    /// at the end of the file. This must not be called in a block
+    pub fn read_tail_from(&mut self, offset: u64) -> RuntimeResult<Vec<u8>> {
+        debug_assert!(self.block.is_none() & (offset <= self.pos));
+        let tail = match core::mem::replace(
+            &mut self.f,
+            TrackedReadSource::Segment {
+                data: vec![],
+                offset: 0,
+            },
+        ) {
+            TrackedReadSource::File(f) => {
+                // the buffered reader is ahead of our cursor, so go back through the file itself
+                let mut f = f.downgrade_reader()?;
+                f.seek_from_start(offset)?;
+                let tail = f.load_remaining_into_buffer()?;
+                self.f = TrackedReadSource::File(f.into_buffered_sdss_reader()?);
+                tail
+            }
+            TrackedReadSource::Segment {
+                data,
+                offset: start,
+            } => {
+                let tail = data[(offset - start) as usize..].to_vec();
+                self.f = TrackedReadSource::Segment {
+                    data,
+                    offset: start,
+                };
+                tail
+            }
+        };
+        self.pos = self.len;
+        Ok(tail)
+    }
     pub fn into_inner_file(self) -> RuntimeResult<SDSSFileIO<Fs>> {
-        self.f.downgrade_reader()
+        match self.f {
+            TrackedReadSource::File(f) => f.downgrade_reader(),
+            TrackedReadSource::Segment { .. } => {
+                Err(SysIOError::from(std::io::ErrorKind::Unsupported).into())
+            }
+        }
+    }
+    /// Move the cursor to `offset` in the file, which must be in the segment that we're reading from (leaving the
+    /// block that we were in, if any)
+    pub fn seek_in_segment(&mut self, offset: u64) {
+        debug_assert!(matches!(
+            self.f,
+            TrackedReadSource::Segment { offset: start, .. } if (start <= offset) & (offset <= self.len)
+        ));
+        self.block = None;
+        self.pos = offset;
+    }
+    /// Returns the segment that we were reading from (this is empty if we were reading from a file)
+    pub fn into_segment(self) -> Vec<u8> {
+        match self.f {
+            TrackedReadSource::File(_) => vec![],
+            TrackedReadSource::Segment { data, .. } => data,
+        }
     }
     pub fn read_block<const N: usize>(&mut self) -> RuntimeResult<[u8; N]> {
         if !self.has_left(N as _) {
@@ -420,6 +663,17 @@ impl<Fs: RawFSInterface> SDSSFileIO<Fs> {
         F::Header::encode(&mut f, F::ENCODE_DATA)?;
         Ok(f)
     }
+    /// Create a new file, recording the given file specific format data (starting with the format descriptor) in its
+    /// header. Returns the file along with its header
+    pub fn create_with_format<F: FileSpec<Header = SDSSStaticHeaderV1Compact>>(
+        fpath: &str,
+        padding_block: [u8; 8],
+    ) -> RuntimeResult<(Self, SDSSStaticHeaderV1Compact)> {
+        let mut f = Self::_new(Fs::fs_fcreate_rw(fpath)?);
+        let header =
+            SDSSStaticHeaderV1Compact::encode_with_format(&mut f, F::ENCODE_DATA, padding_block)?;
+        Ok((f, header))
+    }
     pub fn open_or_create_perm_rw<F: FileSpec>(
         fpath: &str,
     ) -> RuntimeResult<FileOpen<Self, (Self, F::Header)>> {
@@ -505,6 +759,9 @@ impl<Fs: RawFSInterface, F: RawFileInterfaceWrite> SDSSFileIO<Fs, F> {
 }
 
 impl<Fs: RawFSInterface, F: RawFileInterfaceWrite + RawFileInterfaceWriteExt> SDSSFileIO<Fs, F> {
+    pub fn truncate_to(&mut self, len: u64) -> RuntimeResult<()> {
+        self.f.fwext_truncate_to(len)
+    }
     pub fn fsync_all(&mut self) -> RuntimeResult<()> {
         self.f.fwext_fsync_all()?;
         Ok(())
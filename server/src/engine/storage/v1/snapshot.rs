@@ -0,0 +1,587 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Model data snapshots
+//!
+//! A snapshot holds the full state of a model at some point in its batch journal. Every snapshot is written to a new
+//! file (`data-<id>.db-snap`, next to the journal) which the manifest (`data.db-manifest`) only points to once the
+//! snapshot is complete and synced. Along with the snapshot, the manifest records the epoch of the journal segment and
+//! the offset in that segment at which the snapshot was taken, so that restoring the model only has to load the
+//! snapshot and replay what was written to the journal after it.
+//!
+//! Most snapshots after the first one are incremental: they only hold the rows that changed since the previous
+//! snapshot (along with the rows that were deleted since), so the manifest points to a full snapshot followed by the
+//! incremental snapshots taken after it, which are all loaded in order. We take a full snapshot again once the chain
+//! gets long, if the schema changed, or if we don't know what was deleted since the previous snapshot (we only keep
+//! track of that while the journal is open, so this is the case for the first snapshot after a restart).
+//!
+//! Snapshots are encrypted with the same key as the batch journal (if any). The manifest only points to snapshots, so
+//! it's never encrypted.
+//!
+//! The manifest is replaced atomically (a new manifest is written and then renamed into place), so a crash at any
+//! point leaves us with either the previous or the new snapshot. If the manifest points to a journal segment that no
+//! longer exists (for example, because the journal was compacted), the snapshot is ignored and the whole journal is
+//! replayed instead.
+
+use {
+    super::{
+        batch_jrnl::{DataBatchPersistDriver, DecodedBatchEvent, DecodedBatchEventKind},
+        encryption::{self, FileBinding},
+        inf::{
+            self,
+            obj::cell::{self, StorageCellTypeID},
+            PersistObject,
+        },
+        spec::{self, SDSSStaticHeaderV1Compact},
+        RawFSInterface, SDSSFileIO,
+    },
+    crate::{
+        engine::{
+            core::{
+                index::PrimaryIndexKey,
+                model::{delta::DeltaVersion, Model},
+            },
+            data::{
+                cell::Datacell,
+                tag::{DataTag, TagUnique},
+            },
+            error::{RuntimeResult, StorageError},
+            idx::{MTIndexExt, STIndexSeq},
+            mem::BufferedScanner,
+            storage::{ChecksumKind, SCrc},
+        },
+        util::EndianQW,
+    },
+    crossbeam_epoch::pin,
+    std::mem::ManuallyDrop,
+};
+
+/*
+    paths
+*/
+
+/// Returns the path of the journal at `journal`, without its extension
+fn journal_stem(journal: &str) -> &str {
+    let file_name_start = journal.rfind('/').map_or(0, |slash| slash + 1);
+    match journal[file_name_start..].find('.') {
+        Some(ext) => &journal[..file_name_start + ext],
+        None => journal,
+    }
+}
+
+/// Returns the path of the snapshot manifest for the batch journal at `journal`. For example, the manifest for
+/// `data.db-btlog` is `data.db-manifest`
+pub fn manifest_path(journal: &str) -> String {
+    format!("{}.db-manifest", journal_stem(journal))
+}
+
+/// Returns the path of the snapshot `id` for the batch journal at `journal`. For example, the first snapshot for
+/// `data.db-btlog` is `data-000001.db-snap`
+pub fn snapshot_path(journal: &str, id: u64) -> String {
+    format!("{}-{id:06}.db-snap", journal_stem(journal))
+}
+
+/*
+    manifest
+*/
+
+/// The number of incremental snapshots after which we take a full snapshot again
+const MAX_INCREMENTAL_SNAPSHOTS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Points to the latest snapshot of a model (a full snapshot and the incremental snapshots taken after it), along with
+/// where it was taken in the model's batch journal
+pub struct SnapshotManifest {
+    /// the snapshots to load, in order
+    snapshots: Vec<SnapshotRef>,
+    /// the epoch of the journal segment that the latest snapshot was taken in
+    epoch: u128,
+    /// the offset in the journal segment at which the latest snapshot was taken
+    offset: u64,
+    /// the data version when the latest snapshot was taken (every row changed after it has this or a newer version)
+    version: u64,
+    /// the schema version when the latest snapshot was taken
+    schema_version: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, sky_macros::PersistObject)]
+#[persist(metadata_size = 16)]
+/// A snapshot in the manifest
+struct SnapshotRef {
+    /// the id of the snapshot
+    id: u64,
+    /// the checksum of the snapshot's data
+    checksum: u64,
+}
+
+impl SnapshotManifest {
+    pub fn epoch(&self) -> u128 {
+        self.epoch
+    }
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    fn latest(&self) -> SnapshotRef {
+        self.snapshots[self.snapshots.len() - 1]
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SnapshotManifestRef<'a>(&'a SnapshotManifest);
+
+pub struct SnapshotManifestMD {
+    epoch: u128,
+    offset: u64,
+    version: u64,
+    schema_version: u64,
+    snapshot_c: u64,
+}
+
+impl<'a> PersistObject for SnapshotManifestRef<'a> {
+    const METADATA_SIZE: usize = sizeof!(u128) + sizeof!(u64, 4);
+    type InputType = Self;
+    type OutputType = SnapshotManifest;
+    type Metadata = SnapshotManifestMD;
+    fn pretest_can_dec_object(scanner: &BufferedScanner, md: &Self::Metadata) -> bool {
+        // there's at least one snapshot in a manifest
+        (md.snapshot_c != 0)
+            & scanner.has_left(
+                (md.snapshot_c as usize)
+                    .saturating_mul(<&SnapshotRef as PersistObject>::METADATA_SIZE),
+            )
+    }
+    fn meta_enc(buf: &mut Vec<u8>, SnapshotManifestRef(manifest): Self::InputType) {
+        buf.extend(manifest.epoch.to_le_bytes());
+        buf.extend(manifest.offset.to_le_bytes());
+        buf.extend(manifest.version.to_le_bytes());
+        buf.extend(manifest.schema_version.to_le_bytes());
+        buf.extend(manifest.snapshots.len().u64_bytes_le());
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        Ok(SnapshotManifestMD {
+            epoch: u128::from_le_bytes(scanner.next_chunk()),
+            offset: scanner.next_u64_le(),
+            version: scanner.next_u64_le(),
+            schema_version: scanner.next_u64_le(),
+            snapshot_c: scanner.next_u64_le(),
+        })
+    }
+    fn obj_enc(buf: &mut Vec<u8>, SnapshotManifestRef(manifest): Self::InputType) {
+        for snapshot in &manifest.snapshots {
+            <&SnapshotRef as PersistObject>::default_full_enc(buf, snapshot);
+        }
+    }
+    unsafe fn obj_dec(
+        scanner: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let snapshots = (0..md.snapshot_c)
+            .map(|_| <&SnapshotRef as PersistObject>::default_full_dec(scanner))
+            .collect::<RuntimeResult<_>>()?;
+        Ok(SnapshotManifest {
+            snapshots,
+            epoch: md.epoch,
+            offset: md.offset,
+            version: md.version,
+            schema_version: md.schema_version,
+        })
+    }
+}
+
+/// Read the snapshot manifest for the batch journal at `journal`, if there is one
+pub fn read_manifest<Fs: RawFSInterface>(journal: &str) -> RuntimeResult<Option<SnapshotManifest>> {
+    let path = manifest_path(journal);
+    if Fs::fs_fopen_rw(&path).is_err() {
+        return Ok(None);
+    }
+    let (mut f, _) = SDSSFileIO::<Fs>::open::<spec::ModelSnapshotManifestV1>(&path)?;
+    decode_exact::<SnapshotManifestRef>(&f.load_remaining_into_buffer()?).map(Some)
+}
+
+/*
+    snapshot data
+*/
+
+#[derive(Clone, Copy)]
+/// The rows in a model, as written to a snapshot: every row for a full snapshot, or only the rows that changed since the
+/// given version (along with the rows deleted since) for an incremental snapshot. Inserts and deletes must not run
+/// while this is encoded
+pub struct ModelDataRef<'a> {
+    model: &'a Model,
+    since: Option<DeltaVersion>,
+    deleted: &'a [(PrimaryIndexKey, DeltaVersion)],
+}
+
+impl<'a> ModelDataRef<'a> {
+    fn full(model: &'a Model) -> Self {
+        Self {
+            model,
+            since: None,
+            deleted: &[],
+        }
+    }
+    fn incremental(
+        model: &'a Model,
+        since: DeltaVersion,
+        deleted: &'a [(PrimaryIndexKey, DeltaVersion)],
+    ) -> Self {
+        Self {
+            model,
+            since: Some(since),
+            deleted,
+        }
+    }
+}
+
+pub struct ModelDataMD {
+    schema_version: u64,
+    pk_tag: u64,
+    column_c: u64,
+    delete_c: u64,
+}
+
+/// The rows in a model, as read from a snapshot (rows are inserts, and deleted rows are deletes)
+pub struct ModelData {
+    schema_version: u64,
+    pk_tag: TagUnique,
+    events: Vec<DecodedBatchEvent>,
+}
+
+impl<'a> PersistObject for ModelDataRef<'a> {
+    const METADATA_SIZE: usize = sizeof!(u64, 4);
+    type InputType = Self;
+    type OutputType = ModelData;
+    type Metadata = ModelDataMD;
+    fn pretest_can_dec_object(scanner: &BufferedScanner, md: &Self::Metadata) -> bool {
+        // every delete has a txn id and a primary key (which is at least a qword), followed by the row count
+        scanner.has_left(
+            (md.delete_c as usize)
+                .saturating_mul(sizeof!(u64, 2))
+                .saturating_add(sizeof!(u64)),
+        )
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        let model = data.model;
+        buf.extend(
+            model
+                .delta_state()
+                .schema_current_version()
+                .value_u64()
+                .to_le_bytes(),
+        );
+        buf.extend((model.p_tag().tag_unique().value_u8() as u64).to_le_bytes());
+        buf.extend((model.fields().len() - 1).u64_bytes_le());
+        buf.extend(data.deleted.len().u64_bytes_le());
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        Ok(ModelDataMD {
+            schema_version: scanner.next_u64_le(),
+            pk_tag: scanner.next_u64_le(),
+            column_c: scanner.next_u64_le(),
+            delete_c: scanner.next_u64_le(),
+        })
+    }
+    fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        let model = data.model;
+        for (pk, txn_id) in data.deleted {
+            buf.extend(txn_id.value_u64().to_le_bytes());
+            encode_pk(buf, pk);
+        }
+        /*
+            concurrent updates can change which rows are newer than `since` while we're at it, so we only know how many
+            rows we wrote once we're done
+        */
+        let row_c_at = buf.len();
+        buf.extend(0u64.to_le_bytes());
+        let mut row_c = 0u64;
+        let g = pin();
+        for row in model.primary_index().__raw_index().mt_iter_entry(&g) {
+            let row_data = row.resolve_schema_deltas_and_freeze(model.delta_state());
+            // just like in a compacted journal, every row keeps the txn id of its latest revision
+            let txn_id = row_data.get_version();
+            if data.since.is_some_and(|since| txn_id < since) {
+                // unchanged since the previous snapshot
+                continue;
+            }
+            buf.extend(txn_id.value_u64().to_le_bytes());
+            encode_pk(buf, row.d_key());
+            for (field_name, field) in model.fields().stseq_ord_kv() {
+                match row_data.fields().get(field_name) {
+                    Some(value) => cell::encode_field(buf, field, value),
+                    None if field_name.as_str() == model.p_key() => {}
+                    None => buf.push(StorageCellTypeID::Null.value_u8()),
+                }
+            }
+            row_c += 1;
+        }
+        buf[row_c_at..row_c_at + sizeof!(u64)].copy_from_slice(&row_c.to_le_bytes());
+    }
+    unsafe fn obj_dec(
+        scanner: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let pk_tag = u8::try_from(md.pk_tag)
+            .ok()
+            .and_then(TagUnique::try_from_raw)
+            .ok_or(StorageError::InternalDecodeStructureCorruptedPayload)?;
+        let mut events = Vec::with_capacity(md.delete_c as usize);
+        for _ in 0..md.delete_c {
+            // UNSAFE(@ohsayan): pretest
+            let txn_id = scanner.next_u64_le();
+            let pk = decode_pk(scanner, pk_tag)?;
+            events.push(DecodedBatchEvent::new(
+                txn_id,
+                pk,
+                DecodedBatchEventKind::Delete,
+            ));
+        }
+        if !scanner.has_left(sizeof!(u64)) {
+            return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+        }
+        let row_c = scanner.next_u64_le();
+        for _ in 0..row_c {
+            if !scanner.has_left(sizeof!(u64, 2)) {
+                return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+            }
+            let txn_id = scanner.next_u64_le();
+            let pk = decode_pk(scanner, pk_tag)?;
+            let mut row = vec![];
+            for _ in 0..md.column_c {
+                let dscr = scanner
+                    .try_next_byte()
+                    .and_then(StorageCellTypeID::try_from_raw_row_cell)
+                    .ok_or(StorageError::InternalDecodeStructureCorruptedPayload)?;
+                if !scanner.has_left(StorageCellTypeID::expect_atleast(dscr.value_u8())) {
+                    return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+                }
+                row.push(
+                    cell::decode_element::<Datacell, BufferedScanner>(scanner, dscr)
+                        .map_err(|_| StorageError::InternalDecodeStructureCorruptedPayload)?,
+                );
+            }
+            events.push(DecodedBatchEvent::new(
+                txn_id,
+                pk,
+                DecodedBatchEventKind::Insert(row),
+            ));
+        }
+        Ok(ModelData {
+            schema_version: md.schema_version,
+            pk_tag,
+            events,
+        })
+    }
+}
+
+/// Encode a primary key (without its tag), just like the batch journal does
+fn encode_pk(buf: &mut Vec<u8>, pk: &PrimaryIndexKey) {
+    match pk.tag() {
+        TagUnique::UnsignedInt | TagUnique::SignedInt => buf.extend(
+            unsafe {
+                // UNSAFE(@ohsayan): +tagck
+                pk.read_uint()
+            }
+            .to_le_bytes(),
+        ),
+        TagUnique::Str | TagUnique::Bin => {
+            let data = unsafe {
+                // UNSAFE(@ohsayan): +tagck
+                pk.read_bin()
+            };
+            buf.extend(data.len().u64_bytes_le());
+            buf.extend(data);
+        }
+        TagUnique::Illegal => unsafe {
+            // UNSAFE(@ohsayan): a pk can't be constructed with illegal
+            impossible!()
+        },
+    }
+}
+
+/// Decode a primary key with the given tag
+///
+/// ## Safety
+///
+/// There must be at least a qword left in the scanner
+unsafe fn decode_pk(
+    scanner: &mut BufferedScanner,
+    tag: TagUnique,
+) -> RuntimeResult<PrimaryIndexKey> {
+    Ok(match tag {
+        TagUnique::UnsignedInt | TagUnique::SignedInt => {
+            // UNSAFE(@ohsayan): +tagck
+            PrimaryIndexKey::new_from_qw(tag, scanner.next_u64_le())
+        }
+        TagUnique::Str | TagUnique::Bin => {
+            let len = scanner.next_u64_le();
+            let Some(data) = scanner.try_next_variable_block(len as usize) else {
+                return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+            };
+            if (tag == TagUnique::Str) & core::str::from_utf8(data).is_err() {
+                return Err(StorageError::InternalDecodeStructureCorruptedPayload.into());
+            }
+            // UNSAFE(@ohsayan): +tagck +verityck
+            let mut data = ManuallyDrop::new(data.to_vec().into_boxed_slice());
+            PrimaryIndexKey::new_from_dual(tag, len, data.as_mut_ptr() as usize)
+        }
+        TagUnique::Illegal => impossible!(),
+    })
+}
+
+/// Decode an object, making sure that nothing is left after it
+fn decode_exact<Obj: PersistObject>(data: &[u8]) -> RuntimeResult<Obj::OutputType> {
+    let mut scanner = BufferedScanner::new(data);
+    let obj = inf::dec::dec_full_from_scanner::<Obj>(&mut scanner)?;
+    if scanner.eof() {
+        Ok(obj)
+    } else {
+        Err(StorageError::InternalDecodeStructureIllegalData.into())
+    }
+}
+
+/*
+    snapshots
+*/
+
+/// Write a snapshot of the model, whose batch journal at `journal` is currently being written to by `driver`, and
+/// point the manifest to it. Returns true if the snapshot was incremental
+///
+/// The caller must make sure that every change to the model has been written to the journal, and that no batches are
+/// written and no inserts or deletes run while this is in progress
+pub fn write<Fs: RawFSInterface>(
+    journal: &str,
+    model: &Model,
+    driver: &mut DataBatchPersistDriver<Fs>,
+) -> RuntimeResult<bool> {
+    // if we can't read the previous manifest, we just start over
+    let previous = read_manifest::<Fs>(journal).ok().flatten();
+    let id = previous
+        .as_ref()
+        .map_or(1, |manifest| manifest.latest().id + 1);
+    // anything changed from here on is newer than this snapshot
+    let version = model.delta_state().data_current_version();
+    let schema_version = model.delta_state().schema_current_version().value_u64();
+    // a snapshot with this id can only be a leftover from a snapshot that crashed before it was referenced
+    let path = snapshot_path(journal, id);
+    let _ = Fs::fs_remove_file(&path);
+    let (data, mut snapshots) = match (&previous, driver.deleted_since_snapshot()) {
+        (Some(previous), Some(deleted))
+            if (previous.schema_version == schema_version)
+                & (previous.snapshots.len() <= MAX_INCREMENTAL_SNAPSHOTS) =>
+        {
+            let data = inf::enc::enc_full::<ModelDataRef>(ModelDataRef::incremental(
+                model,
+                DeltaVersion::__new(previous.version),
+                deleted,
+            ));
+            (data, previous.snapshots.clone())
+        }
+        _ => (
+            inf::enc::enc_full::<ModelDataRef>(ModelDataRef::full(model)),
+            vec![],
+        ),
+    };
+    let incremental = !snapshots.is_empty();
+    let (mut f, header) = SDSSFileIO::<Fs>::create_with_format::<spec::ModelDataSnapshotV1>(
+        &path,
+        encryption::header_format([0; 8], driver.encryption()),
+    )?;
+    let data = match driver.encryption() {
+        Some(key) => key.seal(
+            &data,
+            &FileBinding::new(&header),
+            SDSSStaticHeaderV1Compact::SIZE as u64,
+        )?,
+        None => data,
+    };
+    // (the header of a new snapshot says that it uses the checksum used in new files)
+    let mut checksum = SCrc::new(ChecksumKind::NEW);
+    checksum.recompute_with_new_var_block(&data);
+    f.fsynced_write(&data)?;
+    snapshots.push(SnapshotRef {
+        id,
+        checksum: checksum.finish(),
+    });
+    // the snapshot can't skip anything in the journal that could still be lost
+    let offset = driver.sync_all()?;
+    let manifest = SnapshotManifest {
+        snapshots,
+        epoch: driver.epoch(),
+        offset,
+        version: version.value_u64(),
+        schema_version,
+    };
+    let manifest_path = manifest_path(journal);
+    let manifest_cow = format!("{manifest_path}.cow");
+    let _ = Fs::fs_remove_file(&manifest_cow);
+    SDSSFileIO::<Fs>::create::<spec::ModelSnapshotManifestV1>(&manifest_cow)?.fsynced_write(
+        &inf::enc::enc_full::<SnapshotManifestRef>(SnapshotManifestRef(&manifest)),
+    )?;
+    Fs::fs_rename_file(&manifest_cow, &manifest_path)?;
+    driver.mark_snapshot(offset);
+    // nothing refers to the previous snapshots anymore if we started over with a full snapshot
+    if let (Some(previous), false) = (previous, incremental) {
+        for snapshot in previous.snapshots {
+            let _ = Fs::fs_remove_file(&snapshot_path(journal, snapshot.id));
+        }
+    }
+    Ok(incremental)
+}
+
+/// Load the snapshots that the manifest (of the batch journal at `journal`) points to into the model
+///
+/// Every snapshot is verified before anything is loaded, so the model is left untouched if this fails
+pub fn load<Fs: RawFSInterface>(
+    journal: &str,
+    manifest: &SnapshotManifest,
+    model: &Model,
+) -> RuntimeResult<()> {
+    let mut snapshots = Vec::with_capacity(manifest.snapshots.len());
+    for snapshot in &manifest.snapshots {
+        let (mut f, header) = SDSSFileIO::<Fs>::open::<spec::ModelDataSnapshotV1>(&snapshot_path(
+            journal,
+            snapshot.id,
+        ))?;
+        let mut data = f.load_remaining_into_buffer()?;
+        let mut checksum = SCrc::new(spec::checksum_from_header(&header));
+        checksum.recompute_with_new_var_block(&data);
+        if checksum.finish() != snapshot.checksum {
+            return Err(StorageError::SnapshotCorrupted.into());
+        }
+        if let Some(key) = encryption::file_key_for_header(&header)? {
+            data = key.open(&data, SDSSStaticHeaderV1Compact::SIZE as u64)?;
+        }
+        let snapshot = decode_exact::<ModelDataRef>(&data)?;
+        if snapshot.pk_tag != model.p_tag().tag_unique() {
+            return Err(StorageError::SnapshotCorrupted.into());
+        }
+        snapshots.push(snapshot);
+    }
+    for snapshot in snapshots {
+        super::batch_jrnl::restore_snapshot_events(model, snapshot.schema_version, snapshot.events);
+    }
+    Ok(())
+}
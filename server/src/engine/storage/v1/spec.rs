@@ -33,11 +33,17 @@
 */
 
 use {
-    super::rw::{RawFSInterface, SDSSFileIO},
+    super::{
+        encryption,
+        rw::{RawFSInterface, SDSSFileIO},
+    },
     crate::{
         engine::{
             error::{RuntimeResult, StorageError},
-            storage::versions::{self, DriverVersion, HeaderVersion, ServerVersion},
+            storage::{
+                versions::{self, DriverVersion, HeaderVersion, ServerVersion},
+                ChecksumKind,
+            },
         },
         util::os,
     },
@@ -185,6 +191,7 @@ pub enum FileScope {
     Journal = 0,
     DataBatch = 1,
     FlatmapData = 2,
+    Snapshot = 3,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sky_macros::EnumMethods)]
@@ -193,6 +200,9 @@ pub enum FileSpecifier {
     GNSTxnLog = 0,
     TableDataBatch = 1,
     SysDB = 2,
+    ModelDataSnapshot = 3,
+    ModelSnapshotManifest = 4,
+    AuditLog = 5,
     #[cfg(test)]
     TestTransactionLog = 0xFF,
 }
@@ -205,8 +215,142 @@ impl FileSpecifierVersion {
     }
 }
 
+/// The compression used for the batches in a data batch journal. This is recorded in the format descriptor of the
+/// header
+#[repr(u8)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sky_macros::EnumMethods, serde::Deserialize,
+)]
+pub enum BatchCompression {
+    #[serde(rename = "none")]
+    None = 0,
+    #[serde(rename = "lz4")]
+    Lz4 = 1,
+}
+
+impl BatchCompression {
+    /// Get the compression recorded in the header of a data batch journal
+    pub fn from_header(header: &SDSSStaticHeaderV1Compact) -> RuntimeResult<Self> {
+        let descriptor = header.format_descriptor();
+        if descriptor <= Self::MAX {
+            Ok(unsafe {
+                // UNSAFE(@ohsayan): just verified the range
+                transmute(descriptor)
+            })
+        } else {
+            Err(StorageError::HeaderDecodeDataMismatch.into())
+        }
+    }
+    /// The format data for the header of a data batch journal that uses this compression and that comes after the given
+    /// number of sealed segments (see [`batch_segment_from_header`])
+    pub fn header_format(&self, segment: u32) -> [u8; 8] {
+        let mut padding_block = [0; 8];
+        padding_block[0] = self.value_u8();
+        padding_block[1..5].copy_from_slice(&segment.to_le_bytes());
+        padding_block
+    }
+}
+
+/// How the integers (txn ids, lengths and counts) in the batches of a data batch journal are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchEncoding {
+    /// Fixed 8B little endian integers (journals written before [`FormatFeature::BATCH_VARINT`])
+    Fixed,
+    /// LEB128 varints, with the txn ids of events encoded as a (zigzag) delta from a base txn id in the batch start
+    /// block
+    Varint,
+}
+
+impl BatchEncoding {
+    /// The encoding used in new journals
+    pub const NEW: Self = Self::Varint;
+    /// Get the encoding used in a data batch journal from its header
+    pub fn from_header(header: &SDSSStaticHeaderV1Compact) -> Self {
+        if header.uses_feature(FormatFeature::BATCH_VARINT) {
+            Self::Varint
+        } else {
+            Self::Fixed
+        }
+    }
+}
+
+impl Default for BatchEncoding {
+    fn default() -> Self {
+        Self::NEW
+    }
+}
+
+/// Get the number of sealed segments that came before a data batch journal, from its header (this is zero for a
+/// journal that was never rotated)
+pub fn batch_segment_from_header(header: &SDSSStaticHeaderV1Compact) -> u32 {
+    let padding_block = header.padding_block();
+    u32::from_le_bytes([
+        padding_block[1],
+        padding_block[2],
+        padding_block[3],
+        padding_block[4],
+    ])
+}
+
+/// Get the checksum used in a file (data batch journals and snapshots use checksums) from its header
+pub fn checksum_from_header(header: &SDSSStaticHeaderV1Compact) -> ChecksumKind {
+    if header.uses_feature(FormatFeature::CHECKSUM_CRC32C) {
+        ChecksumKind::Crc32c
+    } else {
+        ChecksumKind::Crc64
+    }
+}
+
 const SDSS_MAGIC: u64 = 0x4F48534159414E21;
 
+flags! {
+    /// Format features that a file can use, which a server must support to be able to read the file. These are
+    /// recorded in the header so that a server can refuse a file written with a newer feature instead of running into
+    /// what looks like corruption. Every new format feature gets a new flag (and existing flags never change)
+    pub struct FormatFeature: u64 {
+        // the batches in the data batch journal are LZ4 compressed
+        BATCH_LZ4 = 1 << 0,
+        // the payload is encrypted with the key recorded in the header
+        ENCRYPTED = 1 << 1,
+        // the checksums in the file are CRC32C instead of CRC64
+        CHECKSUM_CRC32C = 1 << 2,
+        // the integers in the batches of the data batch journal are varints
+        BATCH_VARINT = 1 << 3,
+    }
+}
+
+impl FormatFeature {
+    /// Returns the features that a file with the given specifier and format data needs its reader to support
+    fn required_by(spec: FileSpecifier, padding_block: [u8; 8]) -> u64 {
+        let mut features = 0;
+        if (spec == FileSpecifier::TableDataBatch)
+            & (padding_block[0] == BatchCompression::Lz4.value_u8())
+        {
+            features |= Self::BATCH_LZ4.d();
+        }
+        // new data batch journals use varints (see [`BatchEncoding::NEW`])
+        if spec == FileSpecifier::TableDataBatch {
+            features |= Self::BATCH_VARINT.d();
+        }
+        if encryption::key_id_from_format(padding_block) != 0 {
+            features |= Self::ENCRYPTED.d();
+        }
+        // the files that we create with checksums in them use CRC32C (see [`ChecksumKind::NEW`])
+        if matches!(
+            spec,
+            FileSpecifier::TableDataBatch | FileSpecifier::ModelDataSnapshot
+        ) {
+            features |= Self::CHECKSUM_CRC32C.d();
+        }
+        features
+    }
+    /// Returns the first feature in the given set that this server doesn't support, if any
+    fn first_unsupported(features: u64) -> Option<u64> {
+        let unsupported = features & !Self::ALL;
+        (unsupported != 0).then(|| 1 << unsupported.trailing_zeros())
+    }
+}
+
 /// Specification for a SDSS file
 pub trait FileSpec {
     /// The header spec for the file
@@ -276,6 +420,45 @@ impl FileSpec for SysDBV1 {
     const VERIFY_DATA: <Self::Header as Header>::DecodeVerifyArgs = Self::ENCODE_DATA;
 }
 
+/// The file specification for a snapshot of the data in a model
+pub struct ModelDataSnapshotV1;
+impl FileSpec for ModelDataSnapshotV1 {
+    type Header = SDSSStaticHeaderV1Compact;
+    const ENCODE_DATA: <Self::Header as Header>::EncodeArgs = (
+        FileScope::Snapshot,
+        FileSpecifier::ModelDataSnapshot,
+        FileSpecifierVersion::__new(0),
+    );
+    const DECODE_DATA: <Self::Header as Header>::DecodeArgs = ();
+    const VERIFY_DATA: <Self::Header as Header>::DecodeVerifyArgs = Self::ENCODE_DATA;
+}
+
+/// The file specification for the manifest that points to the latest snapshot of a model
+pub struct ModelSnapshotManifestV1;
+impl FileSpec for ModelSnapshotManifestV1 {
+    type Header = SDSSStaticHeaderV1Compact;
+    const ENCODE_DATA: <Self::Header as Header>::EncodeArgs = (
+        FileScope::Snapshot,
+        FileSpecifier::ModelSnapshotManifest,
+        FileSpecifierVersion::__new(0),
+    );
+    const DECODE_DATA: <Self::Header as Header>::DecodeArgs = ();
+    const VERIFY_DATA: <Self::Header as Header>::DecodeVerifyArgs = Self::ENCODE_DATA;
+}
+
+/// The file specification for the audit log
+pub struct AuditLogV1;
+impl FileSpec for AuditLogV1 {
+    type Header = SDSSStaticHeaderV1Compact;
+    const ENCODE_DATA: <Self::Header as Header>::EncodeArgs = (
+        FileScope::Journal,
+        FileSpecifier::AuditLog,
+        FileSpecifierVersion::__new(0),
+    );
+    const DECODE_DATA: <Self::Header as Header>::DecodeArgs = ();
+    const VERIFY_DATA: <Self::Header as Header>::DecodeVerifyArgs = Self::ENCODE_DATA;
+}
+
 /*
     header spec
 */
@@ -347,8 +530,13 @@ macro_rules! var {
                 - File specifier (1B)
                 - File specifier version (2B)
         - 2.2: Genesis runtime record (16B)
-            - Host epoch (16B)
-    - 3: Padding block (8B)
+            - Host epoch (8B)
+            - Format features (8B): the flags for the format features used by the file (see [`FormatFeature`]). Files
+              that predate feature flags had a 16B host epoch, but since the epoch always fits in 8B these are zero
+    - 3: Padding block (8B):
+        - Format descriptor (1B): file specific (for example, the compression used in a data batch journal)
+        - Format data (7B): file specific (for example, the sequence number of a data batch journal segment), except
+          for the last 3B which hold the ID of the key that the payload is encrypted with (zero if it isn't encrypted)
 */
 
 #[repr(align(8))]
@@ -370,6 +558,7 @@ pub struct SDSSStaticHeaderV1Compact {
     genesis_static_file_specifier_version: FileSpecifierVersion,
     // 2.2
     genesis_runtime_epoch_time: u128,
+    genesis_runtime_format_features: u64,
     // 3
     genesis_padding_block: [u8; 8],
 }
@@ -384,10 +573,13 @@ impl SDSSStaticHeaderV1Compact {
     /// - If padding block was not zeroed, handle
     /// - No file metadata and is verified. Check!
     ///
+    /// Files written by older versions (of the header, driver and server) are accepted, as are files from newer servers
+    /// as long as they don't use a format feature that we don't support.
+    ///
     fn _decode(block: [u8; 64]) -> RuntimeResult<Self> {
         var!(let raw_magic, raw_header_version, raw_server_version, raw_driver_version, raw_host_os, raw_host_arch,
             raw_host_ptr_width, raw_host_endian, raw_file_class, raw_file_specifier, raw_file_specifier_version,
-            raw_runtime_epoch_time, raw_paddding_block,
+            raw_runtime_epoch_time, raw_runtime_format_features, raw_paddding_block,
         );
         macro_rules! u64 {
             ($pos:expr) => {
@@ -409,8 +601,8 @@ impl SDSSStaticHeaderV1Compact {
             raw_file_specifier_version = FileSpecifierVersion::__new(u16::from_le_bytes(memcpy(
                 &block[Self::SEG2_REC1_FILE_SPECIFIER_VERSION],
             )));
-            raw_runtime_epoch_time =
-                u128::from_le_bytes(memcpy(&block[Self::SEG2_REC2_RUNTIME_EPOCH_TIME]));
+            raw_runtime_epoch_time = u64!(Self::SEG2_REC2_RUNTIME_EPOCH_TIME) as u128;
+            raw_runtime_format_features = u64!(Self::SEG2_REC2_RUNTIME_FORMAT_FEATURES);
             raw_paddding_block = memcpy::<8>(&block[Self::SEG3_PADDING_BLK]);
         }
         macro_rules! okay {
@@ -418,15 +610,15 @@ impl SDSSStaticHeaderV1Compact {
                 $(($expr) &)*true
             }
         }
-        let okay_header_version = raw_header_version == versions::CURRENT_HEADER_VERSION;
-        let okay_server_version = raw_server_version == versions::CURRENT_SERVER_VERSION;
-        let okay_driver_version = raw_driver_version == versions::CURRENT_DRIVER_VERSION;
+        // the server version is only informational; a newer server uses feature flags for anything that it changes in
+        // the format of a file (newer header and driver versions imply a different layout, and are hence refused)
+        let okay_header_version = raw_header_version <= versions::CURRENT_HEADER_VERSION;
+        let okay_driver_version = raw_driver_version <= versions::CURRENT_DRIVER_VERSION;
         let okay = okay!(
             // 1.1 mgblk
             raw_magic == SDSS_MAGIC,
             okay_header_version,
             // 2.1.1
-            okay_server_version,
             okay_driver_version,
             // 2.1.2
             raw_host_os <= HostOS::MAX,
@@ -438,6 +630,9 @@ impl SDSSStaticHeaderV1Compact {
             raw_file_specifier <= FileSpecifier::MAX,
         );
         if okay {
+            if let Some(feature) = FormatFeature::first_unsupported(raw_runtime_format_features) {
+                return Err(StorageError::HeaderDecodeUnsupportedFeature(feature).into());
+            }
             Ok(unsafe {
                 // UNSAFE(@ohsayan): the block ranges are very well defined
                 Self {
@@ -457,12 +652,13 @@ impl SDSSStaticHeaderV1Compact {
                     genesis_static_file_specifier_version: raw_file_specifier_version,
                     // 2.2
                     genesis_runtime_epoch_time: raw_runtime_epoch_time,
+                    genesis_runtime_format_features: raw_runtime_format_features,
                     // 3
                     genesis_padding_block: raw_paddding_block,
                 }
             })
         } else {
-            let version_okay = okay_header_version & okay_server_version & okay_driver_version;
+            let version_okay = okay_header_version & okay_driver_version;
             let md = ManuallyDrop::new([
                 StorageError::HeaderDecodeCorruptedHeader,
                 StorageError::HeaderDecodeVersionMismatch,
@@ -487,8 +683,36 @@ impl SDSSStaticHeaderV1Compact {
     const SEG2_REC1_FILE_CLASS: usize = 36;
     const SEG2_REC1_FILE_SPECIFIER: usize = 37;
     const SEG2_REC1_FILE_SPECIFIER_VERSION: Range<usize> = 38..40;
-    const SEG2_REC2_RUNTIME_EPOCH_TIME: Range<usize> = 40..56;
+    const SEG2_REC2_RUNTIME_EPOCH_TIME: Range<usize> = 40..48;
+    const SEG2_REC2_RUNTIME_FORMAT_FEATURES: Range<usize> = 48..56;
     const SEG3_PADDING_BLK: Range<usize> = 56..64;
+    fn _new(
+        file_class: FileScope,
+        file_specifier: FileSpecifier,
+        file_specifier_version: FileSpecifierVersion,
+        epoch_time: u128,
+        padding_block: [u8; 8],
+    ) -> Self {
+        Self {
+            magic_header_version: versions::CURRENT_HEADER_VERSION,
+            genesis_static_sw_server_version: versions::CURRENT_SERVER_VERSION,
+            genesis_static_sw_driver_version: versions::CURRENT_DRIVER_VERSION,
+            genesis_static_host_os: HostOS::new(),
+            genesis_static_host_arch: HostArch::new(),
+            genesis_static_host_ptr_width: HostPointerWidth::new(),
+            genesis_static_host_endian: HostEndian::new(),
+            genesis_static_file_class: file_class,
+            genesis_static_file_specifier: file_specifier,
+            genesis_static_file_specifier_version: file_specifier_version,
+            // (only the low 64 bits are stored)
+            genesis_runtime_epoch_time: epoch_time as u64 as u128,
+            genesis_runtime_format_features: FormatFeature::required_by(
+                file_specifier,
+                padding_block,
+            ),
+            genesis_padding_block: padding_block,
+        }
+    }
     fn _encode(
         file_class: FileScope,
         file_specifier: FileSpecifier,
@@ -496,36 +720,58 @@ impl SDSSStaticHeaderV1Compact {
         epoch_time: u128,
         padding_block: [u8; 8],
     ) -> [u8; 64] {
+        Self::_new(
+            file_class,
+            file_specifier,
+            file_specifier_version,
+            epoch_time,
+            padding_block,
+        )
+        .encoded()
+    }
+    /// Returns the header as it's stored in the file
+    pub fn encoded(&self) -> [u8; 64] {
         let mut ret = [0; 64];
         // 1. mgblk
         ret[Self::SEG1_MAGIC].copy_from_slice(&SDSS_MAGIC.to_le_bytes());
         ret[Self::SEG1_HEADER_VERSION]
-            .copy_from_slice(&versions::CURRENT_HEADER_VERSION.little_endian_u64());
+            .copy_from_slice(&self.magic_header_version.little_endian_u64());
         // 2.1.1
         ret[Self::SEG2_REC1_SERVER_VERSION]
-            .copy_from_slice(&versions::CURRENT_SERVER_VERSION.little_endian());
+            .copy_from_slice(&self.genesis_static_sw_server_version.little_endian());
         ret[Self::SEG2_REC1_DRIVER_VERSION]
-            .copy_from_slice(&versions::CURRENT_DRIVER_VERSION.little_endian());
+            .copy_from_slice(&self.genesis_static_sw_driver_version.little_endian());
         // 2.1.2
-        ret[Self::SEG2_REC1_HOST_OS] = HostOS::new().value_u8();
-        ret[Self::SEG2_REC1_HOST_ARCH] = HostArch::new().value_u8();
-        ret[Self::SEG2_REC1_HOST_PTR_WIDTH] = HostPointerWidth::new().value_u8();
-        ret[Self::SEG2_REC1_HOST_ENDIAN] = HostEndian::new().value_u8();
+        ret[Self::SEG2_REC1_HOST_OS] = self.genesis_static_host_os.value_u8();
+        ret[Self::SEG2_REC1_HOST_ARCH] = self.genesis_static_host_arch.value_u8();
+        ret[Self::SEG2_REC1_HOST_PTR_WIDTH] = self.genesis_static_host_ptr_width.value_u8();
+        ret[Self::SEG2_REC1_HOST_ENDIAN] = self.genesis_static_host_endian.value_u8();
         // 2.1.3
-        ret[Self::SEG2_REC1_FILE_CLASS] = file_class.value_u8();
-        ret[Self::SEG2_REC1_FILE_SPECIFIER] = file_specifier.value_u8();
+        ret[Self::SEG2_REC1_FILE_CLASS] = self.genesis_static_file_class.value_u8();
+        ret[Self::SEG2_REC1_FILE_SPECIFIER] = self.genesis_static_file_specifier.value_u8();
         ret[Self::SEG2_REC1_FILE_SPECIFIER_VERSION]
-            .copy_from_slice(&file_specifier_version.0.to_le_bytes());
+            .copy_from_slice(&self.genesis_static_file_specifier_version.0.to_le_bytes());
         // 2.2
-        ret[Self::SEG2_REC2_RUNTIME_EPOCH_TIME].copy_from_slice(&epoch_time.to_le_bytes());
+        ret[Self::SEG2_REC2_RUNTIME_EPOCH_TIME]
+            .copy_from_slice(&(self.genesis_runtime_epoch_time as u64).to_le_bytes());
+        ret[Self::SEG2_REC2_RUNTIME_FORMAT_FEATURES]
+            .copy_from_slice(&self.genesis_runtime_format_features.to_le_bytes());
         // 3
-        ret[Self::SEG3_PADDING_BLK].copy_from_slice(&padding_block);
+        ret[Self::SEG3_PADDING_BLK].copy_from_slice(&self.genesis_padding_block);
         ret
     }
     pub fn _encode_auto(
         file_class: FileScope,
         file_specifier: FileSpecifier,
         file_specifier_version: FileSpecifierVersion,
+    ) -> [u8; 64] {
+        Self::_encode_auto_with_format(file_class, file_specifier, file_specifier_version, [0; 8])
+    }
+    pub fn _encode_auto_with_format(
+        file_class: FileScope,
+        file_specifier: FileSpecifier,
+        file_specifier_version: FileSpecifierVersion,
+        padding_block: [u8; 8],
     ) -> [u8; 64] {
         let epoch_time = os::get_epoch_time();
         Self::_encode(
@@ -533,9 +779,20 @@ impl SDSSStaticHeaderV1Compact {
             file_specifier,
             file_specifier_version,
             epoch_time,
-            [0; 8],
+            padding_block,
         )
     }
+    /// Encode the header with the given format descriptor and format data (see [`Self::padding_block`]), returning the
+    /// header
+    pub fn encode_with_format<Fs: RawFSInterface>(
+        f: &mut SDSSFileIO<Fs>,
+        (scope, spec, spec_v): <Self as Header>::EncodeArgs,
+        padding_block: [u8; 8],
+    ) -> RuntimeResult<Self> {
+        let header = Self::_new(scope, spec, spec_v, os::get_epoch_time(), padding_block);
+        f.fsynced_write(&header.encoded())?;
+        Ok(header)
+    }
 }
 
 #[allow(unused)]
@@ -573,9 +830,18 @@ impl SDSSStaticHeaderV1Compact {
     pub fn epoch_time(&self) -> u128 {
         self.genesis_runtime_epoch_time
     }
+    pub fn format_features(&self) -> u64 {
+        self.genesis_runtime_format_features
+    }
+    pub fn uses_feature(&self, feature: FormatFeature) -> bool {
+        self.genesis_runtime_format_features & feature.d() != 0
+    }
     pub fn padding_block(&self) -> [u8; 8] {
         self.genesis_padding_block
     }
+    pub fn format_descriptor(&self) -> u8 {
+        self.genesis_padding_block[0]
+    }
 }
 
 impl Header for SDSSStaticHeaderV1Compact {
@@ -602,7 +868,8 @@ impl Header for SDSSStaticHeaderV1Compact {
             & (self.file_specifier() == spec)
             & (self.file_specifier_version() == spec_v)
         {
-            Ok(())
+            // refuse to open a file that we can't decrypt
+            encryption::key_for_header(self).map(|_| ())
         } else {
             Err(StorageError::HeaderDecodeDataMismatch.into())
         }
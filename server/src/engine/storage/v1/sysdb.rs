@@ -28,9 +28,12 @@ use {
     super::rw::FileOpen,
     crate::engine::{
         config::{ConfigAuth, ConfigMode},
-        data::{cell::Datacell, DictEntryGeneric, DictGeneric},
+        data::{cell::Datacell, tag::TagClass, DictEntryGeneric, DictGeneric},
         error::{RuntimeResult, StorageError},
-        fractal::sys_store::{SysAuth, SysAuthUser, SysConfig, SysHostData, SystemStore},
+        fractal::sys_store::{
+            SysAuth, SysAuthGrants, SysAuthPolicy, SysAuthQuota, SysAuthUser, SysConfig,
+            SysHostData, SystemStore,
+        },
         storage::v1::{inf, spec, RawFSInterface, SDSSFileIO},
     },
     parking_lot::RwLock,
@@ -69,10 +72,12 @@ fn rkey<T>(
 }
 
 impl<Fs: RawFSInterface> SystemStore<Fs> {
-    const SYSDB_PATH: &'static str = "sys.db";
-    const SYSDB_COW_PATH: &'static str = "sys.db.cow";
+    pub(in crate::engine) const SYSDB_PATH: &'static str = "sys.db";
+    pub(in crate::engine) const SYSDB_COW_PATH: &'static str = "sys.db.cow";
     const SYS_KEY_AUTH: &'static str = "auth";
     const SYS_KEY_AUTH_USERS: &'static str = "users";
+    const SYS_KEY_AUTH_USER_GRANT_SEP: char = '.';
+    const SYS_KEY_AUTH_ROOT_ROTATE: &'static str = "root_rotate";
     const SYS_KEY_SYS: &'static str = "sys";
     const SYS_KEY_SYS_STARTUP_COUNTER: &'static str = "sc";
     const SYS_KEY_SYS_SETTINGS_VERSION: &'static str = "sv";
@@ -83,7 +88,16 @@ impl<Fs: RawFSInterface> SystemStore<Fs> {
         Self::open_with_name(Self::SYSDB_PATH, Self::SYSDB_COW_PATH, auth, run_mode)
     }
     pub fn sync_db(&self, auth: &SysAuth) -> RuntimeResult<()> {
-        self._sync_with(Self::SYSDB_PATH, Self::SYSDB_COW_PATH, auth)
+        let (sysdb_path, sysdb_cow_path) = self.sysdb_paths();
+        self._sync_with(sysdb_path, sysdb_cow_path, auth)
+    }
+    /// Write a copy of the system database into the given directory
+    pub fn backup_to(&self, dir: &str) -> RuntimeResult<()> {
+        self._sync_with(
+            &format!("{dir}/{}", Self::SYSDB_PATH),
+            &format!("{dir}/{}", Self::SYSDB_COW_PATH),
+            &self.system_store().auth_data().read(),
+        )
     }
     pub fn open_with_name(
         sysdb_name: &str,
@@ -93,7 +107,11 @@ impl<Fs: RawFSInterface> SystemStore<Fs> {
     ) -> RuntimeResult<(Self, SystemStoreInitState)> {
         match SDSSFileIO::open_or_create_perm_rw::<spec::SysDBV1>(sysdb_name)? {
             FileOpen::Created(new) => {
-                let me = Self::_new(SysConfig::new_auth(auth, run_mode));
+                let me = Self::_new_with_paths(
+                    SysConfig::new_auth(auth, run_mode),
+                    sysdb_name,
+                    sysdb_cow_path,
+                );
                 me._sync(new, &me.system_store().auth_data().read())?;
                 Ok((me, SystemStoreInitState::Created))
             }
@@ -124,16 +142,55 @@ impl<Fs: RawFSInterface> SystemStore<Fs> {
                 auth.users()
                     .iter()
                     .map(|(username, user)| {
+                        let mut userdata = vec![
+                            Datacell::new_bin(user.key().into()),
+                            Datacell::new_uint_default(user.cost() as _),
+                        ];
+                        let quota = user.quota();
+                        match user.grants() {
+                            Some(grants) => {
+                                /*
+                                    a restricted user has a third element: the list of grants, each of which is either
+                                    `space` or `space.model`. unrestricted users only have their key and its cost
+                                */
+                                let sep = Self::SYS_KEY_AUTH_USER_GRANT_SEP;
+                                userdata.push(Datacell::new_list(
+                                    grants
+                                        .spaces()
+                                        .map(|space| Datacell::new_str(space.into()))
+                                        .chain(grants.models().map(|(space, model)| {
+                                            Datacell::new_str(format!("{space}{sep}{model}").into())
+                                        }))
+                                        .collect(),
+                                ));
+                            }
+                            // an unrestricted user with a quota has a null in place of the grants
+                            None if !quota.is_unlimited() => userdata.push(Datacell::null()),
+                            None => {}
+                        }
+                        if !quota.is_unlimited() {
+                            // a limited user has a fourth element: the quota as [queries/sec, result bytes, connections]
+                            userdata.push(Datacell::new_list(vec![
+                                Datacell::new_uint_default(quota.queries_per_sec()),
+                                Datacell::new_uint_default(quota.result_bytes()),
+                                Datacell::new_uint_default(quota.connections()),
+                            ]));
+                        }
                         (
                             username.to_owned(),
-                            DictEntryGeneric::Data(Datacell::new_list(vec![Datacell::new_bin(
-                                user.key().into(),
-                            )])),
+                            DictEntryGeneric::Data(Datacell::new_list(userdata)),
                         )
                     })
                     .collect(),
             ),
         );
+        if auth.root_must_rotate() {
+            // only present until the bootstrapped root key is changed
+            auth_key.insert(
+                Self::SYS_KEY_AUTH_ROOT_ROTATE.into(),
+                DictEntryGeneric::Data(Datacell::new_bool(true)),
+            );
+        }
         // write
         let buf = super::inf::enc::enc_dict_full::<super::inf::map::GenericDictSpec>(&map);
         f.fsynced_write(&buf)
@@ -150,35 +207,37 @@ impl<Fs: RawFSInterface> SystemStore<Fs> {
         fname: &str,
         fcow_name: &str,
     ) -> RuntimeResult<(Self, SystemStoreInitState)> {
-        let prev_sysdb = Self::_restore(f, run_mode)?;
+        let prev_sysdb = Self::_restore(f, SysAuthPolicy::new(&auth), run_mode)?;
         let state;
-        // see if settings have changed
-        if prev_sysdb
-            .auth_data()
-            .read()
-            .verify_user(SysAuthUser::USER_ROOT, &auth.root_key)
-            .is_ok()
+        // see if settings have changed (a bootstrapped root key is never reset, so it can't have changed)
+        if auth.root_rotate
+            || prev_sysdb
+                .auth_data()
+                .read()
+                .verify_user(SysAuthUser::USER_ROOT, &auth.root_key)
+                .is_ok()
         {
             state = SystemStoreInitState::Unchanged;
         } else {
             state = SystemStoreInitState::UpdatedRoot;
         }
-        // create new config
-        let new_syscfg = SysConfig::new_full(
-            auth,
-            SysHostData::new(
-                prev_sysdb.host_data().startup_counter() + 1,
-                prev_sysdb.host_data().settings_version()
-                    + !matches!(state, SystemStoreInitState::Unchanged) as u32,
-            ),
-            run_mode,
+        // create new config (retaining all users)
+        let host_data = SysHostData::new(
+            prev_sysdb.host_data().startup_counter() + 1,
+            prev_sysdb.host_data().settings_version()
+                + !matches!(state, SystemStoreInitState::Unchanged) as u32,
         );
-        let slf = Self::_new(new_syscfg);
+        let new_syscfg = SysConfig::new_with_users_from(prev_sysdb, auth, host_data, run_mode);
+        let slf = Self::_new_with_paths(new_syscfg, fname, fcow_name);
         // now sync
         slf._sync_with(fname, fcow_name, &slf.system_store().auth_data().read())?;
         Ok((slf, state))
     }
-    fn _restore(mut f: SDSSFileIO<Fs>, run_mode: ConfigMode) -> RuntimeResult<SysConfig> {
+    fn _restore(
+        mut f: SDSSFileIO<Fs>,
+        auth_policy: SysAuthPolicy,
+        run_mode: ConfigMode,
+    ) -> RuntimeResult<SysConfig> {
         let mut sysdb_data =
             inf::dec::dec_dict_full::<inf::map::GenericDictSpec>(&f.load_remaining_into_buffer()?)?;
         // get our auth and sys stores
@@ -204,17 +263,47 @@ impl<Fs: RawFSInterface> SystemStore<Fs> {
             let mut userdata = userdata
                 .into_data()
                 .and_then(Datacell::into_list)
+                .ok_or(StorageError::SysDBCorrupted)?
+                .into_iter()
+                .peekable();
+            let user_password = userdata
+                .next()
+                .and_then(Datacell::into_bin)
                 .ok_or(StorageError::SysDBCorrupted)?;
-            if userdata.len() != 1 {
+            let user_cost = match userdata.next_if(|d| d.kind() == TagClass::UnsignedInt) {
+                Some(cost) => cost
+                    .into_uint()
+                    .and_then(|cost| u32::try_from(cost).ok())
+                    .ok_or(StorageError::SysDBCorrupted)?,
+                // older versions didn't store the cost, and always hashed with the default cost
+                None => rcrypt::DEFAULT_COST,
+            };
+            let user_grants = match userdata.next() {
+                Some(grants) if grants.is_null() => None,
+                grants => grants.map(Self::_restore_grants).transpose()?,
+            };
+            let user_quota = userdata
+                .next()
+                .map(Self::_restore_quota)
+                .transpose()?
+                .unwrap_or_default();
+            if userdata.next().is_some() {
                 return Err(StorageError::SysDBCorrupted.into());
             }
-            let user_password = userdata
-                .remove(0)
-                .into_bin()
-                .ok_or(StorageError::SysDBCorrupted)?;
-            loaded_users.insert(username, SysAuthUser::new(user_password.into_boxed_slice()));
+            loaded_users.insert(
+                username,
+                SysAuthUser::new_full(user_password.into_boxed_slice(), user_cost, user_grants)
+                    .with_quota(user_quota),
+            );
         }
-        let sys_auth = SysAuth::new(loaded_users);
+        let root_must_rotate = match auth_store.remove(Self::SYS_KEY_AUTH_ROOT_ROTATE) {
+            Some(rotate) => rotate
+                .into_data()
+                .and_then(|d| d.try_bool())
+                .ok_or(StorageError::SysDBCorrupted)?,
+            None => false,
+        };
+        let sys_auth = SysAuth::new(loaded_users).with_root_must_rotate(root_must_rotate);
         // load sys data
         let sc = rkey(&mut sys_store, Self::SYS_KEY_SYS_STARTUP_COUNTER, |d| {
             d.into_data()?.into_uint()
@@ -231,8 +320,40 @@ impl<Fs: RawFSInterface> SystemStore<Fs> {
         }
         Ok(SysConfig::new(
             RwLock::new(sys_auth),
+            auth_policy,
             SysHostData::new(sc, sv as u32),
             run_mode,
         ))
     }
+    fn _restore_quota(quota: Datacell) -> RuntimeResult<SysAuthQuota> {
+        let quota = quota
+            .into_list()
+            .ok_or(StorageError::SysDBCorrupted)?
+            .into_iter()
+            .map(|limit| limit.into_uint().ok_or(StorageError::SysDBCorrupted))
+            .collect::<Result<Vec<_>, _>>()?;
+        match quota.as_slice() {
+            &[queries_per_sec, result_bytes, connections] => Ok(SysAuthQuota::new(
+                queries_per_sec,
+                result_bytes,
+                connections,
+            )),
+            _ => Err(StorageError::SysDBCorrupted.into()),
+        }
+    }
+    fn _restore_grants(grants: Datacell) -> RuntimeResult<SysAuthGrants> {
+        let mut ret = SysAuthGrants::default();
+        for grant in grants.into_list().ok_or(StorageError::SysDBCorrupted)? {
+            let grant = grant.into_str().ok_or(StorageError::SysDBCorrupted)?;
+            let okay = match grant.split_once(Self::SYS_KEY_AUTH_USER_GRANT_SEP) {
+                Some((space, model)) => ret.grant(space, Some(model)),
+                None => ret.grant(&grant, None),
+            };
+            if !okay {
+                // duplicate grant
+                return Err(StorageError::SysDBCorrupted.into());
+            }
+        }
+        Ok(ret)
+    }
 }
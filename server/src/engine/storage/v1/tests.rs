@@ -26,17 +26,28 @@
 
 type VirtualFS = super::memfs::VirtualFS;
 
+mod audit;
 mod batch;
+#[cfg(target_os = "linux")]
+mod direct;
+mod durability;
+mod encryption;
+mod prop;
+mod repair;
 mod rw;
 mod tx;
+#[cfg(target_os = "linux")]
+mod uring;
 
 mod sysdb {
     use {
         super::{super::sysdb::SystemStoreInitState, VirtualFS as VFS},
         crate::engine::{
             config::{AuthDriver, ConfigAuth, ConfigMode},
-            fractal::sys_store::SystemStore,
+            error::QueryError,
+            fractal::sys_store::{SysAuthQuota, SysQuotaUsage, SystemStore},
         },
+        std::time::{Duration, Instant},
     };
     fn open_sysdb(
         auth_config: ConfigAuth,
@@ -112,4 +123,339 @@ mod sysdb {
         assert_eq!(config.system_store().host_data().settings_version(), 1);
         assert_eq!(config.system_store().host_data().startup_counter(), 1);
     }
+    #[test]
+    fn user_grants_survive_restart() {
+        let open = || {
+            open_sysdb(
+                ConfigAuth::new(AuthDriver::Pwd, "password12345678".into()),
+                "user_grants_survive_restart.sys.db",
+                "user_grants_survive_restart.sys.cow.db",
+            )
+        };
+        {
+            let (config, _) = open();
+            config
+                .create_new_user("sayan".into(), "password12345678".into())
+                .unwrap();
+            config
+                .create_new_user("guest".into(), "password12345678".into())
+                .unwrap();
+            config.grant_user("sayan", "myspace", None).unwrap();
+            config
+                .grant_user("sayan", "otherspace", Some("mymodel"))
+                .unwrap();
+            // can't grant twice or grant root
+            assert!(config.grant_user("sayan", "myspace", None).is_err());
+            assert!(config.grant_user("root", "myspace", None).is_err());
+        }
+        // reboot
+        let (config, _) = open();
+        let auth = config.system_store().auth_data().read();
+        assert!(auth.verify_user("sayan", "password12345678").is_ok());
+        // restricted
+        assert!(auth
+            .verify_user_access("sayan", "myspace", Some("anymodel"))
+            .is_ok());
+        assert!(auth.verify_user_access("sayan", "myspace", None).is_ok());
+        assert!(auth
+            .verify_user_access("sayan", "otherspace", Some("mymodel"))
+            .is_ok());
+        assert!(auth
+            .verify_user_access("sayan", "otherspace", Some("anothermodel"))
+            .is_err());
+        assert!(auth
+            .verify_user_access("sayan", "otherspace", None)
+            .is_err());
+        // unrestricted
+        assert!(auth
+            .verify_user_access("guest", "otherspace", Some("anothermodel"))
+            .is_ok());
+        drop(auth);
+        // revoking the last grant leaves the user restricted
+        config.revoke_user("sayan", "myspace", None).unwrap();
+        config
+            .revoke_user("sayan", "otherspace", Some("mymodel"))
+            .unwrap();
+        assert!(config.revoke_user("sayan", "myspace", None).is_err());
+        assert!(config
+            .system_store()
+            .auth_data()
+            .read()
+            .verify_user_access("sayan", "myspace", Some("anymodel"))
+            .is_err());
+    }
+    #[test]
+    fn root_bootstrap_rotate() {
+        let open = |root_rotate| {
+            let auth = ConfigAuth {
+                root_rotate,
+                ..ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+            };
+            open_sysdb(
+                auth,
+                "root_bootstrap_rotate.sys.db",
+                "root_bootstrap_rotate.sys.cow.db",
+            )
+        };
+        {
+            let (config, state) = open(true);
+            assert_eq!(state, SystemStoreInitState::Created);
+            assert!(config.system_store().root_must_rotate());
+        }
+        // reboot; still has to be rotated
+        {
+            let (config, state) = open(true);
+            assert_eq!(state, SystemStoreInitState::Unchanged);
+            assert!(config.system_store().root_must_rotate());
+            // the root password must be as long as the configured one
+            assert_eq!(
+                config.alter_user("root".into(), "password".into()),
+                Err(QueryError::SysAuthPasswordPolicy)
+            );
+            assert!(config.system_store().root_must_rotate());
+            config
+                .alter_user("root".into(), "password23456789".into())
+                .unwrap();
+            assert!(!config.system_store().root_must_rotate());
+        }
+        // reboot; the bootstrap key doesn't reset the rotated key
+        {
+            let (config, state) = open(true);
+            assert_eq!(state, SystemStoreInitState::Unchanged);
+            assert!(!config.system_store().root_must_rotate());
+            let auth = config.system_store().auth_data().read();
+            assert!(auth.verify_user("root", "password23456789").is_ok());
+            assert!(auth.verify_user("root", "password12345678").is_err());
+        }
+        // reboot without bootstrapping; the configured key is used as usual
+        let (config, state) = open(false);
+        assert_eq!(state, SystemStoreInitState::UpdatedRoot);
+        assert!(!config.system_store().root_must_rotate());
+        assert!(config
+            .system_store()
+            .auth_data()
+            .read()
+            .verify_user("root", "password12345678")
+            .is_ok());
+        assert_eq!(
+            config.alter_user("root".into(), "password23456789".into()),
+            Err(QueryError::SysAuthError)
+        );
+    }
+    #[test]
+    fn user_password_policy_and_rehash() {
+        let open = |hash_cost| {
+            let auth = ConfigAuth {
+                hash_cost,
+                ..ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+            };
+            open_sysdb(
+                auth,
+                "user_password_policy_and_rehash.sys.db",
+                "user_password_policy_and_rehash.sys.cow.db",
+            )
+        };
+        {
+            let (config, _) = open(4);
+            // too short
+            assert_eq!(
+                config.create_new_user("sayan".into(), "pass".into()),
+                Err(QueryError::SysAuthPasswordPolicy)
+            );
+            config
+                .create_new_user("sayan".into(), "password12345678".into())
+                .unwrap();
+            assert_eq!(
+                config.alter_user("sayan".into(), "pass".into()),
+                Err(QueryError::SysAuthPasswordPolicy)
+            );
+            // root can only be changed through the configuration
+            assert_eq!(
+                config.alter_user("root".into(), "password23456789".into()),
+                Err(QueryError::SysAuthError)
+            );
+            assert_eq!(
+                config.system_store().auth_data().read().users()["sayan"].cost(),
+                4
+            );
+        }
+        let (config, _) = open(5);
+        // root is rehashed on startup, while the user is rehashed once they log in
+        assert_eq!(
+            config.system_store().auth_data().read().users()["root"].cost(),
+            5
+        );
+        assert_eq!(
+            config.system_store().auth_data().read().users()["sayan"].cost(),
+            4
+        );
+        assert!(config
+            .verify_user_check_root("sayan", b"password23456789")
+            .is_err());
+        assert_eq!(
+            config.system_store().auth_data().read().users()["sayan"].cost(),
+            4
+        );
+        assert_eq!(
+            config.verify_user_check_root("sayan", b"password12345678"),
+            Ok(false)
+        );
+        assert_eq!(
+            config.system_store().auth_data().read().users()["sayan"].cost(),
+            5
+        );
+        assert!(config
+            .system_store()
+            .auth_data()
+            .read()
+            .verify_user("sayan", "password12345678")
+            .is_ok());
+    }
+    #[test]
+    fn user_tokens() {
+        let (config, _) = open_sysdb(
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into()),
+            "user_tokens.sys.db",
+            "user_tokens.sys.cow.db",
+        );
+        config
+            .create_new_user("sayan".into(), "password12345678".into())
+            .unwrap();
+        let syscfg = config.system_store();
+        let now = 1_700_000_000;
+        let token = syscfg.issue_token_at("sayan", now).unwrap();
+        assert_eq!(
+            syscfg.verify_token_at("sayan", token.as_bytes(), now + 1),
+            Ok(false)
+        );
+        // not for another user
+        assert!(syscfg
+            .verify_token_at("root", token.as_bytes(), now + 1)
+            .is_err());
+        // not tampered with
+        let mut tampered = token.clone().into_bytes();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(syscfg.verify_token_at("sayan", &tampered, now + 1).is_err());
+        let extended = token.replacen(&(now + 900).to_string(), &(now + 9000).to_string(), 1);
+        assert!(syscfg
+            .verify_token_at("sayan", extended.as_bytes(), now + 1000)
+            .is_err());
+        // not expired
+        assert!(syscfg
+            .verify_token_at("sayan", token.as_bytes(), now + 900)
+            .is_err());
+        // and revoked once the password is changed
+        config
+            .alter_user("sayan".into(), "password23456789".into())
+            .unwrap();
+        assert!(syscfg
+            .verify_token_at("sayan", token.as_bytes(), now + 1)
+            .is_err());
+        // root can get a token too
+        let token = syscfg.issue_token_at("root", now).unwrap();
+        assert_eq!(
+            syscfg.verify_token_at("root", token.as_bytes(), now + 1),
+            Ok(true)
+        );
+    }
+    #[test]
+    fn user_quotas_survive_restart() {
+        let open = || {
+            open_sysdb(
+                ConfigAuth::new(AuthDriver::Pwd, "password12345678".into()),
+                "user_quotas_survive_restart.sys.db",
+                "user_quotas_survive_restart.sys.cow.db",
+            )
+        };
+        {
+            let (config, _) = open();
+            for user in ["sayan", "guest", "admin"] {
+                config
+                    .create_new_user(user.into(), "password12345678".into())
+                    .unwrap();
+            }
+            // unrestricted, with a quota
+            config
+                .set_user_quota("sayan", Some(100), None, Some(4))
+                .unwrap();
+            config
+                .set_user_quota("sayan", None, Some(1024), None)
+                .unwrap();
+            // restricted, with a quota
+            config.grant_user("guest", "myspace", None).unwrap();
+            config
+                .set_user_quota("guest", Some(10), None, None)
+                .unwrap();
+            // a limit of zero removes it
+            config
+                .set_user_quota("admin", Some(10), None, None)
+                .unwrap();
+            config.set_user_quota("admin", Some(0), None, None).unwrap();
+            // root can't be limited
+            assert_eq!(
+                config.set_user_quota("root", Some(10), None, None),
+                Err(QueryError::SysAuthError)
+            );
+            assert_eq!(
+                config.set_user_quota("nobody", Some(10), None, None),
+                Err(QueryError::SysAuthError)
+            );
+        }
+        // reboot
+        let (config, _) = open();
+        let syscfg = config.system_store();
+        assert_eq!(syscfg.user_quota("sayan"), SysAuthQuota::new(100, 1024, 4));
+        assert_eq!(syscfg.user_quota("guest"), SysAuthQuota::new(10, 0, 0));
+        assert!(syscfg.user_quota("admin").is_unlimited());
+        assert!(syscfg.user_quota("root").is_unlimited());
+        let auth = syscfg.auth_data().read();
+        assert!(auth.verify_user_access("sayan", "otherspace", None).is_ok());
+        assert!(auth.verify_user_access("guest", "myspace", None).is_ok());
+        assert!(auth
+            .verify_user_access("guest", "otherspace", None)
+            .is_err());
+    }
+    #[test]
+    fn user_quota_usage() {
+        let usage = SysQuotaUsage::default();
+        let quota = SysAuthQuota::new(2, 0, 2);
+        // connections
+        let first = usage.connect("sayan", quota).unwrap();
+        let second = usage.connect("sayan", quota).unwrap();
+        assert!(usage.connect("sayan", quota).is_none());
+        assert_eq!(usage.connections("sayan"), 2);
+        // other users have their own quota
+        let other = usage.connect("guest", quota).unwrap();
+        drop(first);
+        let third = usage.connect("sayan", quota).unwrap();
+        // queries (across all connections)
+        let now = Instant::now();
+        assert!(usage.run_query_at("sayan", quota, now).is_ok());
+        assert!(usage.run_query_at("sayan", quota, now).is_ok());
+        assert_eq!(
+            usage.run_query_at("sayan", quota, now + Duration::from_millis(999)),
+            Err(QueryError::SysQuotaQueryRate)
+        );
+        assert!(usage.run_query_at("guest", quota, now).is_ok());
+        // the next second
+        assert!(usage
+            .run_query_at("sayan", quota, now + Duration::from_secs(1))
+            .is_ok());
+        // no limit
+        for _ in 0..10 {
+            assert!(usage
+                .run_query_at("sayan", SysAuthQuota::default(), now)
+                .is_ok());
+        }
+        drop((second, third, other));
+        assert_eq!(usage.connections("sayan"), 0);
+        assert_eq!(usage.connections("guest"), 0);
+    }
+    #[test]
+    fn user_quota_result_bytes() {
+        assert!(!SysAuthQuota::default().exceeds_result_bytes(usize::MAX));
+        let quota = SysAuthQuota::new(0, 1024, 0);
+        assert!(!quota.exceeds_result_bytes(1024));
+        assert!(quota.exceeds_result_bytes(1025));
+    }
 }
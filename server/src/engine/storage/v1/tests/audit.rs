@@ -0,0 +1,169 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::storage::v1::{
+    audit::{AuditEvent, AuditEventKind, AuditFilter, AuditLog},
+    memfs::VirtualFS,
+    rw::{RawFSInterface, RawFileInterfaceExt, RawFileInterfaceWrite},
+};
+
+fn events() -> Vec<AuditEvent> {
+    vec![
+        AuditEvent::new_at(
+            100,
+            AuditEventKind::Login,
+            true,
+            "root",
+            "127.0.0.1:3000",
+            "password",
+        ),
+        AuditEvent::new_at(
+            200,
+            AuditEventKind::UserCreate,
+            true,
+            "root",
+            "127.0.0.1:3000",
+            "sysctl create user sayan with { password: ? }",
+        ),
+        AuditEvent::new_at(
+            300,
+            AuditEventKind::Login,
+            false,
+            "sayan",
+            "127.0.0.1:3001",
+            "token",
+        ),
+        AuditEvent::new_at(
+            400,
+            AuditEventKind::Ddl,
+            false,
+            "sayan",
+            "127.0.0.1:3001",
+            "create space myspace",
+        ),
+    ]
+}
+
+fn open_with_events(path: &str) -> AuditLog<VirtualFS> {
+    let log = AuditLog::<VirtualFS>::open(path, None).unwrap();
+    for event in events() {
+        log.record(event);
+    }
+    log
+}
+
+#[test]
+fn record_and_read() {
+    let log = open_with_events("audit_record_and_read.db-tlog");
+    assert_eq!(log.read(AuditFilter::all()).unwrap(), events());
+}
+
+#[test]
+fn read_with_filter() {
+    let log = open_with_events("audit_read_with_filter.db-tlog");
+    let read = |user: Option<&str>, kind, since, until, limit| {
+        log.read(AuditFilter::new(
+            user.map(Into::into),
+            kind,
+            since,
+            until,
+            limit,
+        ))
+        .unwrap()
+        .into_iter()
+        .map(|event| event.time())
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(read(Some("sayan"), None, None, None, None), [300, 400]);
+    assert_eq!(
+        read(None, Some(AuditEventKind::Login), None, None, None),
+        [100, 300]
+    );
+    // since is inclusive, until is exclusive
+    assert_eq!(read(None, None, Some(200), Some(400), None), [200, 300]);
+    // only the most recent events are returned
+    assert_eq!(read(None, None, None, None, Some(3)), [200, 300, 400]);
+    assert_eq!(read(None, None, None, None, Some(0)), [0u64; 0]);
+    assert_eq!(
+        read(Some("root"), Some(AuditEventKind::Ddl), None, None, None),
+        [0u64; 0]
+    );
+}
+
+#[test]
+fn reopen() {
+    let path = "audit_reopen.db-tlog";
+    open_with_events(path).close().unwrap();
+    let log = AuditLog::<VirtualFS>::open(path, None).unwrap();
+    assert_eq!(log.read(AuditFilter::all()).unwrap(), events());
+    let event = AuditEvent::new(
+        AuditEventKind::Sysctl,
+        true,
+        "root",
+        "",
+        "sysctl report audit",
+    );
+    log.record(event.clone());
+    drop(log);
+    // closed on drop
+    let log = AuditLog::<VirtualFS>::open(path, None).unwrap();
+    let mut expected = events();
+    expected.push(event);
+    assert_eq!(log.read(AuditFilter::all()).unwrap(), expected);
+}
+
+#[test]
+fn events_after_close_are_dropped() {
+    let path = "audit_events_after_close.db-tlog";
+    let log = open_with_events(path);
+    log.close().unwrap();
+    log.record(AuditEvent::new(
+        AuditEventKind::Login,
+        true,
+        "root",
+        "",
+        "password",
+    ));
+    drop(log);
+    let log = AuditLog::<VirtualFS>::open(path, None).unwrap();
+    assert_eq!(log.read(AuditFilter::all()).unwrap(), events());
+}
+
+#[test]
+fn recover_unclosed() {
+    let path = "audit_recover_unclosed.db-tlog";
+    // crash without closing the log, in the middle of writing an event
+    core::mem::forget(open_with_events(path));
+    {
+        let mut f = VirtualFS::fs_fopen_rw(path).unwrap();
+        let len = f.fext_file_length().unwrap();
+        f.fext_seek_ahead_from_start_by(len).unwrap();
+        f.fw_write_all(&[0xAB; 64]).unwrap();
+    }
+    // everything that was completely written is still there
+    let log = AuditLog::<VirtualFS>::open(path, None).unwrap();
+    assert_eq!(log.read(AuditFilter::all()).unwrap(), events());
+}
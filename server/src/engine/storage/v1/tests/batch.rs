@@ -31,19 +31,34 @@ use {
                 index::{DcFieldIndex, PrimaryIndexKey, Row},
                 model::{
                     delta::{DataDelta, DataDeltaKind, DeltaVersion},
+                    schema::DictSchema,
                     Field, Layer, Model,
                 },
             },
-            data::{cell::Datacell, tag::TagSelector, uuid::Uuid},
+            data::{cell::Datacell, dict::DictCell, tag::TagSelector, uuid::Uuid},
+            error::{ErrorKind, RuntimeResult, StorageError},
+            fractal::startup,
             idx::MTIndex,
-            storage::v1::{
-                batch_jrnl::{
-                    DataBatchPersistDriver, DataBatchRestoreDriver, DecodedBatchEvent,
-                    DecodedBatchEventKind, NormalBatch,
+            storage::{
+                v1::{
+                    batch_jrnl::{
+                        self, BatchStreamItem, DataBatchPersistDriver, DataBatchRestoreDriver,
+                        DataBatchRestoreReport, DecodedBatchEvent, DecodedBatchEventKind,
+                        NormalBatch, RecoveryMode,
+                    },
+                    durability::DurabilityPolicy,
+                    encryption::{self, EncryptionKey},
+                    memfs::VirtualFS,
+                    rw::{
+                        FileOpen, RawFSInterface, RawFileInterfaceExt, RawFileInterfaceRead,
+                        RawFileInterfaceWrite, RawFileInterfaceWriteExt, SDSSFileIO,
+                    },
+                    snapshot,
+                    spec::{
+                        self, BatchCompression, BatchEncoding, FileSpec, SDSSStaticHeaderV1Compact,
+                    },
                 },
-                memfs::VirtualFS,
-                rw::{FileOpen, SDSSFileIO},
-                spec,
+                ChecksumKind,
             },
         },
         util::test_utils,
@@ -212,6 +227,411 @@ fn unskewed_delta() {
     )
 }
 
+#[test]
+fn timestamp_delta() {
+    let uuid = Uuid::new();
+    let mdl = Model::new_restore(
+        uuid,
+        "username".into(),
+        TagSelector::String.into_full(),
+        into_dict!(
+            "username" => Field::new([Layer::str()].into(), false),
+            "last_seen" => Field::new([Layer::timestamp()].into(), true),
+            "logins" => Field::new([Layer::list(), Layer::timestamp()].into(), false)
+        ),
+    );
+    let logins = || {
+        Datacell::new_list(vec![
+            Datacell::new_timestamp(1673187839000000),
+            Datacell::new_timestamp(u64::MAX),
+        ])
+    };
+    let deltas = [
+        new_delta(
+            0,
+            0,
+            "sayan",
+            into_dict!(
+                "last_seen" => Datacell::new_timestamp(1673187839000000),
+                "logins" => logins()
+            ),
+            DataDeltaKind::Insert,
+        ),
+        new_delta(
+            0,
+            1,
+            "elana",
+            into_dict!("last_seen" => Datacell::null(), "logins" => Datacell::new_list(vec![])),
+            DataDeltaKind::Insert,
+        ),
+    ];
+    let batches = flush_deltas_and_re_read(&mdl, deltas, "timestamp_delta.db-btlog");
+    assert_eq!(
+        batches,
+        vec![NormalBatch::new(
+            vec![
+                DecodedBatchEvent::new(
+                    0,
+                    pkey("sayan"),
+                    DecodedBatchEventKind::Insert(vec![
+                        Datacell::new_timestamp(1673187839000000),
+                        logins()
+                    ])
+                ),
+                DecodedBatchEvent::new(
+                    1,
+                    pkey("elana"),
+                    DecodedBatchEventKind::Insert(vec![
+                        Datacell::null(),
+                        Datacell::new_list(vec![])
+                    ])
+                ),
+            ],
+            0
+        )]
+    )
+}
+
+#[test]
+fn sint128_delta() {
+    let uuid = Uuid::new();
+    let mdl = Model::new_restore(
+        uuid,
+        "username".into(),
+        TagSelector::String.into_full(),
+        into_dict!(
+            "username" => Field::new([Layer::str()].into(), false),
+            "balance" => Field::new([Layer::sint128()].into(), true),
+            "history" => Field::new([Layer::list(), Layer::sint128()].into(), false)
+        ),
+    );
+    let history = || {
+        Datacell::new_list(vec![
+            Datacell::new_sint128(i128::MIN),
+            Datacell::new_sint128(i128::MAX),
+        ])
+    };
+    let deltas = [
+        new_delta(
+            0,
+            0,
+            "sayan",
+            into_dict!(
+                "balance" => Datacell::new_sint128(i128::MIN),
+                "history" => history()
+            ),
+            DataDeltaKind::Insert,
+        ),
+        new_delta(
+            0,
+            1,
+            "elana",
+            into_dict!("balance" => Datacell::null(), "history" => Datacell::new_list(vec![])),
+            DataDeltaKind::Insert,
+        ),
+    ];
+    let batches = flush_deltas_and_re_read(&mdl, deltas, "sint128_delta.db-btlog");
+    assert_eq!(
+        batches,
+        vec![NormalBatch::new(
+            vec![
+                DecodedBatchEvent::new(
+                    0,
+                    pkey("sayan"),
+                    DecodedBatchEventKind::Insert(vec![
+                        Datacell::new_sint128(i128::MIN),
+                        history()
+                    ])
+                ),
+                DecodedBatchEvent::new(
+                    1,
+                    pkey("elana"),
+                    DecodedBatchEventKind::Insert(vec![
+                        Datacell::null(),
+                        Datacell::new_list(vec![])
+                    ])
+                ),
+            ],
+            0
+        )]
+    )
+}
+
+#[test]
+fn geopoint_delta() {
+    let uuid = Uuid::new();
+    let mdl = Model::new_restore(
+        uuid,
+        "username".into(),
+        TagSelector::String.into_full(),
+        into_dict!(
+            "username" => Field::new([Layer::str()].into(), false),
+            "location" => Field::new([Layer::geopoint()].into(), true),
+            "trail" => Field::new([Layer::list(), Layer::geopoint()].into(), false)
+        ),
+    );
+    let trail = || {
+        Datacell::new_list(vec![
+            Datacell::new_geopoint(-90.0, -180.0),
+            Datacell::new_geopoint(48.8566, 2.3522),
+        ])
+    };
+    let deltas = [
+        new_delta(
+            0,
+            0,
+            "sayan",
+            into_dict!(
+                "location" => Datacell::new_geopoint(90.0, 180.0),
+                "trail" => trail()
+            ),
+            DataDeltaKind::Insert,
+        ),
+        new_delta(
+            0,
+            1,
+            "elana",
+            into_dict!("location" => Datacell::null(), "trail" => Datacell::new_list(vec![])),
+            DataDeltaKind::Insert,
+        ),
+    ];
+    let batches = flush_deltas_and_re_read(&mdl, deltas, "geopoint_delta.db-btlog");
+    assert_eq!(
+        batches,
+        vec![NormalBatch::new(
+            vec![
+                DecodedBatchEvent::new(
+                    0,
+                    pkey("sayan"),
+                    DecodedBatchEventKind::Insert(vec![
+                        Datacell::new_geopoint(90.0, 180.0),
+                        trail()
+                    ])
+                ),
+                DecodedBatchEvent::new(
+                    1,
+                    pkey("elana"),
+                    DecodedBatchEventKind::Insert(vec![
+                        Datacell::null(),
+                        Datacell::new_list(vec![])
+                    ])
+                ),
+            ],
+            0
+        )]
+    )
+}
+
+#[test]
+fn dict_delta() {
+    let new_model = || {
+        Model::new_restore(
+            Uuid::new(),
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!(
+                "username" => Field::new([Layer::str()].into(), false),
+                "profile" => Field::new([Layer::dict()].into(), true),
+                "history" => Field::new([Layer::list(), Layer::dict()].into(), false)
+            ),
+        )
+    };
+    let profile = || {
+        Datacell::new_dict(into_dict!(
+            "name" => Datacell::from("Sayan"),
+            "age" => Datacell::new_uint_default(24),
+            "avatar" => Datacell::new_bin(b"\x89PNG".to_vec().into_boxed_slice()),
+            "tags" => Datacell::new_list(vec!["rust".into(), "db".into()]),
+            "address" => Datacell::new_dict(into_dict!(
+                "city" => Datacell::from("Kolkata"),
+                "location" => Datacell::new_geopoint(22.5726, 88.3639),
+                "floors" => Datacell::new_list(vec![
+                    Datacell::new_dict(into_dict!("level" => Datacell::new_sint_default(-1))),
+                    Datacell::new_dict(DictCell::new()),
+                ])
+            ))
+        ))
+    };
+    let history = || {
+        Datacell::new_list(vec![
+            Datacell::new_dict(into_dict!("at" => Datacell::new_uint_default(1))),
+            Datacell::new_dict(DictCell::new()),
+        ])
+    };
+    let write = |fname: &str, encoding: BatchEncoding| {
+        let mdl = new_model();
+        let g = pin();
+        for delta in [
+            new_delta(
+                0,
+                0,
+                "sayan",
+                into_dict!("profile" => profile(), "history" => history()),
+                DataDeltaKind::Insert,
+            ),
+            new_delta(
+                0,
+                1,
+                "elana",
+                into_dict!("profile" => Datacell::null(), "history" => Datacell::new_list(vec![])),
+                DataDeltaKind::Insert,
+            ),
+        ] {
+            mdl.delta_state().append_new_data_delta(delta, &g);
+        }
+        let mut persist_driver =
+            DataBatchPersistDriver::new(open_file(fname).into_created().unwrap(), true)
+                .unwrap()
+                .with_encoding(encoding);
+        persist_driver.write_new_batch(&mdl, 2).unwrap();
+        persist_driver.close().unwrap();
+        DataBatchRestoreDriver::new(open_file(fname).into_existing().unwrap().0)
+            .unwrap()
+            .with_encoding(encoding)
+            .read_all_batches()
+            .unwrap()
+    };
+    let expected = vec![NormalBatch::new(
+        vec![
+            DecodedBatchEvent::new(
+                0,
+                pkey("sayan"),
+                DecodedBatchEventKind::Insert(vec![profile(), history()]),
+            ),
+            DecodedBatchEvent::new(
+                1,
+                pkey("elana"),
+                DecodedBatchEventKind::Insert(vec![Datacell::null(), Datacell::new_list(vec![])]),
+            ),
+        ],
+        0,
+    )];
+    assert_eq!(
+        write("dict_delta_fixed.db-btlog", BatchEncoding::Fixed),
+        expected
+    );
+    assert_eq!(
+        write("dict_delta.db-btlog", BatchEncoding::Varint),
+        expected
+    );
+}
+
+#[test]
+fn typed_dict_delta() {
+    let new_model = || {
+        let mut profile = Field::new([Layer::dict()].into(), true);
+        assert!(profile.set_dict_schema(
+            DictSchema::from_cell(Datacell::new_dict(into_dict!(
+                "name" => Datacell::from("string"),
+                "age" => Datacell::from("uint8"),
+                "home" => Datacell::from("geopoint")
+            )))
+            .unwrap()
+        ));
+        Model::new_restore(
+            Uuid::new(),
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!(
+                "username" => Field::new([Layer::str()].into(), false),
+                "profile" => profile
+            ),
+        )
+    };
+    let rows = [
+        (
+            "sayan",
+            Datacell::new_dict(into_dict!(
+                "name" => Datacell::from("Sayan"),
+                "age" => Datacell::new_uint_default(24),
+                "home" => Datacell::new_geopoint(22.5726, 88.3639)
+            )),
+        ),
+        (
+            "elana",
+            Datacell::new_dict(into_dict!("name" => Datacell::from("Elana"))),
+        ),
+        ("emily", Datacell::new_dict(DictCell::new())),
+        ("joseph", Datacell::null()),
+    ];
+    for (fname, encoding) in [
+        ("typed_dict_delta_fixed.db-btlog", BatchEncoding::Fixed),
+        ("typed_dict_delta.db-btlog", BatchEncoding::Varint),
+    ] {
+        let mdl = new_model();
+        let g = pin();
+        for (txn_id, (username, profile)) in rows.iter().enumerate() {
+            mdl.delta_state().append_new_data_delta(
+                new_delta(
+                    0,
+                    txn_id as u64,
+                    *username,
+                    into_dict!("profile" => profile.clone()),
+                    DataDeltaKind::Insert,
+                ),
+                &g,
+            );
+        }
+        let mut persist_driver =
+            DataBatchPersistDriver::new(open_file(fname).into_created().unwrap(), true)
+                .unwrap()
+                .with_encoding(encoding);
+        persist_driver.write_new_batch(&mdl, rows.len()).unwrap();
+        persist_driver.close().unwrap();
+        // the dicts are persisted as the lists of their values (in the order of the schema)
+        let persisted = [
+            Datacell::new_list(vec![
+                Datacell::new_uint_default(24),
+                Datacell::new_geopoint(22.5726, 88.3639),
+                Datacell::from("Sayan"),
+            ]),
+            Datacell::new_list(vec![
+                Datacell::null(),
+                Datacell::null(),
+                Datacell::from("Elana"),
+            ]),
+            Datacell::new_list(vec![Datacell::null(), Datacell::null(), Datacell::null()]),
+            Datacell::null(),
+        ];
+        assert_eq!(
+            DataBatchRestoreDriver::new(open_file(fname).into_existing().unwrap().0)
+                .unwrap()
+                .with_encoding(encoding)
+                .read_all_batches()
+                .unwrap(),
+            vec![NormalBatch::new(
+                rows.iter()
+                    .zip(persisted)
+                    .enumerate()
+                    .map(|(txn_id, ((username, _), profile))| {
+                        DecodedBatchEvent::new(
+                            txn_id as u64,
+                            pkey(*username),
+                            DecodedBatchEventKind::Insert(vec![profile]),
+                        )
+                    })
+                    .collect(),
+                0
+            )]
+        );
+        // and are dicts again once they're restored
+        let restored = new_model();
+        DataBatchRestoreDriver::new(open_file(fname).into_existing().unwrap().0)
+            .unwrap()
+            .with_encoding(encoding)
+            .read_data_batch_into_model(&restored)
+            .unwrap();
+        for (username, profile) in &rows {
+            let row = restored
+                .primary_index()
+                .__raw_index()
+                .mt_get(&pkey(*username), &g)
+                .unwrap();
+            assert_eq!(row.read().fields().get("profile").unwrap(), profile);
+        }
+    }
+}
+
 #[test]
 fn skewed_delta() {
     // prepare model definition
@@ -382,3 +802,1604 @@ fn skewed_shuffled_persist_restore() {
         );
     }
 }
+
+#[test]
+fn recover_until_txn_id() {
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let check_recovered = |model: &Model| {
+        let g = pin();
+        let idx = model.primary_index().__raw_index();
+        assert_eq!(idx.mt_len(), 2);
+        for (username, password) in [("sayan", "pwd123456"), ("joseph", "pwd234567")] {
+            let row = idx.mt_get(&pkey(username), &g).unwrap();
+            assert_eq!(
+                row.read().fields().get("password").unwrap(),
+                &Datacell::from(password)
+            );
+        }
+    };
+    let deltas = [
+        new_delta(
+            0,
+            0,
+            "sayan",
+            into_dict!("password" => "pwd123456"),
+            DataDeltaKind::Insert,
+        ),
+        new_delta(
+            0,
+            1,
+            "joseph",
+            into_dict!("password" => "pwd234567"),
+            DataDeltaKind::Insert,
+        ),
+        // the "mistakes"
+        new_delta(
+            0,
+            2,
+            "sayan",
+            into_dict!("password" => "oops"),
+            DataDeltaKind::Update,
+        ),
+        new_delta(0, 3, "joseph", into_dict!(), DataDeltaKind::Delete),
+    ];
+    let fname = "recover_until_txn_id.db-btlog";
+    drop(flush_batches_and_return_restore_driver(
+        deltas,
+        &new_model(),
+        fname,
+    ));
+    // recover
+    let recovered = new_model();
+    let (persist_driver, report) =
+        batch_jrnl::recover_until::<VirtualFS>(fname, &recovered, 1).unwrap();
+    assert!(report.is_clean());
+    persist_driver.close().unwrap();
+    check_recovered(&recovered);
+    // new deltas must be versioned after everything in the original journal
+    assert_eq!(
+        recovered.delta_state().create_new_data_delta_version(),
+        DeltaVersion::__new(4)
+    );
+    // the recovered state is what we load on the next start
+    let reloaded = new_model();
+    open_batch_data(fname, &reloaded).close().unwrap();
+    check_recovered(&reloaded);
+    // and the original journal is kept
+    let original = new_model();
+    open_batch_data(&format!("{fname}.pitr-1"), &original)
+        .close()
+        .unwrap();
+    let g = pin();
+    let idx = original.primary_index().__raw_index();
+    assert_eq!(idx.mt_len(), 1);
+    assert_eq!(
+        idx.mt_get(&pkey("sayan"), &g)
+            .unwrap()
+            .read()
+            .fields()
+            .get("password")
+            .unwrap(),
+        &Datacell::from("oops")
+    );
+}
+
+#[test]
+fn compact_keeps_latest_state() {
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let deltas = [
+        new_delta(
+            0,
+            0,
+            "sayan",
+            into_dict!("password" => "pwd123456"),
+            DataDeltaKind::Insert,
+        ),
+        new_delta(
+            0,
+            1,
+            "joseph",
+            into_dict!("password" => "pwd234567"),
+            DataDeltaKind::Insert,
+        ),
+        new_delta(
+            0,
+            2,
+            "sayan",
+            into_dict!("password" => "pwd345678"),
+            DataDeltaKind::Update,
+        ),
+        new_delta(0, 3, "joseph", into_dict!(), DataDeltaKind::Delete),
+        new_delta(
+            0,
+            4,
+            "emily",
+            into_dict!("password" => "pwd456789"),
+            DataDeltaKind::Insert,
+        ),
+    ];
+    let fname = "compact_keeps_latest_state.db-btlog";
+    drop(flush_batches_and_return_restore_driver(
+        deltas,
+        &new_model(),
+        fname,
+    ));
+    // leftovers from a compaction that crashed before the swap
+    drop(
+        open_file(&format!("{fname}.compact-tmp"))
+            .into_created()
+            .unwrap(),
+    );
+    {
+        let mdl = new_model();
+        let mut persist_driver = open_batch_data(fname, &mdl);
+        batch_jrnl::compact(
+            fname,
+            &mdl,
+            &mut persist_driver,
+            BatchCompression::None,
+            None,
+        )
+        .unwrap();
+        // a delete for a row that is in the compacted journal
+        let g = pin();
+        mdl.delta_state().append_new_data_delta(
+            new_delta(0, 5, "emily", into_dict!(), DataDeltaKind::Delete),
+            &g,
+        );
+        persist_driver.write_new_batch(&mdl, 1).unwrap();
+        // a delete for a row that was dropped by the compaction
+        mdl.delta_state().append_new_data_delta(
+            new_delta(0, 3, "joseph", into_dict!(), DataDeltaKind::Delete),
+            &g,
+        );
+        persist_driver.write_new_batch(&mdl, 1).unwrap();
+        persist_driver.close().unwrap();
+    }
+    // only the latest state is left in the compacted batch
+    let (f, _header) = open_file(fname).into_existing().unwrap();
+    let batches = DataBatchRestoreDriver::new(f)
+        .unwrap()
+        .read_all_batches()
+        .unwrap();
+    let sayan = || {
+        DecodedBatchEvent::new(
+            2,
+            pkey("sayan"),
+            DecodedBatchEventKind::Insert(vec![Datacell::from("pwd345678")]),
+        )
+    };
+    let emily = || {
+        DecodedBatchEvent::new(
+            4,
+            pkey("emily"),
+            DecodedBatchEventKind::Insert(vec![Datacell::from("pwd456789")]),
+        )
+    };
+    // (rows are snapshotted in index order)
+    assert!(
+        batches[0] == NormalBatch::new(vec![sayan(), emily()], 0)
+            || batches[0] == NormalBatch::new(vec![emily(), sayan()], 0)
+    );
+    assert_eq!(
+        batches[1..],
+        [
+            NormalBatch::new(
+                vec![DecodedBatchEvent::new(
+                    5,
+                    pkey("emily"),
+                    DecodedBatchEventKind::Delete
+                )],
+                0
+            ),
+            NormalBatch::new(
+                vec![DecodedBatchEvent::new(
+                    3,
+                    pkey("joseph"),
+                    DecodedBatchEventKind::Delete
+                )],
+                0
+            )
+        ]
+    );
+    // reload
+    let mdl = new_model();
+    open_batch_data(fname, &mdl).close().unwrap();
+    let g = pin();
+    let idx = mdl.primary_index().__raw_index();
+    assert_eq!(idx.mt_len(), 1);
+    assert_eq!(
+        idx.mt_get(&pkey("sayan"), &g)
+            .unwrap()
+            .read()
+            .fields()
+            .get("password")
+            .unwrap(),
+        &Datacell::from("pwd345678")
+    );
+}
+
+#[test]
+fn segment_paths() {
+    assert_eq!(
+        batch_jrnl::segment_path("data.db-btlog", 1),
+        "data-000001.db-btlog"
+    );
+    assert_eq!(
+        batch_jrnl::segment_path("my.space/data.db-btlog", 42),
+        "my.space/data-000042.db-btlog"
+    );
+    assert_eq!(batch_jrnl::segment_path("data", 7), "data-000007");
+}
+
+#[test]
+fn segmented_journal() {
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let users = [
+        ("sayan", "pwd123456"),
+        ("joseph", "pwd234567"),
+        ("emily", "pwd345678"),
+        ("elana", "pwd456789"),
+    ];
+    let fname = "segmented_journal.db-btlog";
+    let exists = |path: &str| VirtualFS::fs_fopen_rw(path).is_ok();
+    // every batch fills up a segment
+    {
+        let mdl = new_model();
+        let mut persist_driver =
+            batch_jrnl::create::<VirtualFS>(fname, BatchCompression::None, None)
+                .unwrap()
+                .with_segment_size(1);
+        let g = pin();
+        for (txn_id, (username, password)) in users.into_iter().enumerate() {
+            mdl.delta_state().append_new_data_delta(
+                new_delta(
+                    0,
+                    txn_id as u64,
+                    username,
+                    into_dict!("password" => password),
+                    DataDeltaKind::Insert,
+                ),
+                &g,
+            );
+            persist_driver.write_new_batch(&mdl, 1).unwrap();
+            assert!(persist_driver.needs_rotation().unwrap());
+            batch_jrnl::rotate(fname, &mut persist_driver).unwrap();
+        }
+        assert_eq!(persist_driver.segment(), 4);
+        persist_driver.close().unwrap();
+    }
+    assert!((1..=4).all(|seq| exists(&batch_jrnl::segment_path(fname, seq))));
+    // leftovers from a rotation that crashed before the active segment was sealed
+    drop(
+        open_file(&format!("{fname}.rotate-tmp"))
+            .into_created()
+            .unwrap(),
+    );
+    // all segments are restored
+    {
+        let mdl = new_model();
+        let (mut persist_driver, report) =
+            batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+        assert!(report.is_clean());
+        assert!(!exists(&format!("{fname}.rotate-tmp")));
+        assert_eq!(persist_driver.segment(), 4);
+        assert_users(&mdl, &users);
+        // a compaction leaves a single segment
+        batch_jrnl::compact(
+            fname,
+            &mdl,
+            &mut persist_driver,
+            BatchCompression::None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(persist_driver.segment(), 0);
+        persist_driver.close().unwrap();
+    }
+    assert!((1..=4).all(|seq| !exists(&batch_jrnl::segment_path(fname, seq))));
+    let mdl = new_model();
+    let (persist_driver, report) =
+        batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    assert_users(&mdl, &users);
+    persist_driver.close().unwrap();
+}
+
+#[test]
+fn startup_progress() {
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let users = [
+        ("sayan", "pwd123456"),
+        ("joseph", "pwd234567"),
+        ("emily", "pwd345678"),
+    ];
+    let fname = "startup_progress.db-btlog";
+    let file_len = |path: &str| {
+        SDSSFileIO::<VirtualFS>::_new(VirtualFS::fs_fopen_rw(path).unwrap())
+            .file_length()
+            .unwrap()
+    };
+    // every batch fills up a segment
+    {
+        let mdl = new_model();
+        let mut persist_driver =
+            batch_jrnl::create::<VirtualFS>(fname, BatchCompression::None, None)
+                .unwrap()
+                .with_segment_size(1);
+        let g = pin();
+        for (txn_id, (username, password)) in users.into_iter().enumerate() {
+            mdl.delta_state().append_new_data_delta(
+                new_delta(
+                    0,
+                    txn_id as u64,
+                    username,
+                    into_dict!("password" => password),
+                    DataDeltaKind::Insert,
+                ),
+                &g,
+            );
+            persist_driver.write_new_batch(&mdl, 1).unwrap();
+            batch_jrnl::rotate(fname, &mut persist_driver).unwrap();
+        }
+        persist_driver.close().unwrap();
+    }
+    // nothing is recorded unless the model is being tracked
+    let mdl = new_model();
+    let progress = startup::begin([("myspace", "mymodel")]);
+    batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict)
+        .unwrap()
+        .0
+        .close()
+        .unwrap();
+    let before = progress[0].progress();
+    assert!(!before.finished());
+    assert_eq!((before.done(), before.total()), (0, 0));
+    // every byte of every segment is accounted for
+    let total = file_len(fname)
+        + (1..=3)
+            .map(|seq| file_len(&batch_jrnl::segment_path(fname, seq)))
+            .sum::<u64>();
+    let mdl = new_model();
+    let (persist_driver, report) = progress[0]
+        .track(|| batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict))
+        .unwrap();
+    assert!(report.is_clean());
+    assert_users(&mdl, &users);
+    persist_driver.close().unwrap();
+    let after = progress[0].progress();
+    assert!(after.finished());
+    assert_eq!((after.space(), after.model()), ("myspace", "mymodel"));
+    assert_eq!((after.done(), after.total()), (total, total));
+}
+
+#[test]
+fn snapshot_paths() {
+    assert_eq!(snapshot::manifest_path("data.db-btlog"), "data.db-manifest");
+    assert_eq!(
+        snapshot::snapshot_path("my.space/data.db-btlog", 1),
+        "my.space/data-000001.db-snap"
+    );
+}
+
+#[test]
+fn snapshot_and_replay_tail() {
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let fname = "snapshot_and_replay_tail.db-btlog";
+    let exists = |path: &str| VirtualFS::fs_fopen_rw(path).is_ok();
+    let write_deltas = |mdl: &Model,
+                        persist_driver: &mut DataBatchPersistDriver<VirtualFS>,
+                        deltas: Vec<DataDelta>| {
+        let g = pin();
+        let count = deltas.len();
+        for delta in deltas {
+            mdl.delta_state().append_new_data_delta(delta, &g);
+        }
+        persist_driver.write_new_batch(mdl, count).unwrap();
+    };
+    // (deltas only reach the journal, so we take every snapshot from a restored model)
+    {
+        let mdl = new_model();
+        let mut persist_driver =
+            batch_jrnl::create::<VirtualFS>(fname, BatchCompression::None, None).unwrap();
+        write_deltas(
+            &mdl,
+            &mut persist_driver,
+            vec![
+                new_delta(
+                    0,
+                    0,
+                    "sayan",
+                    into_dict!("password" => "pwd123456"),
+                    DataDeltaKind::Insert,
+                ),
+                new_delta(
+                    0,
+                    1,
+                    "joseph",
+                    into_dict!("password" => "pwd234567"),
+                    DataDeltaKind::Insert,
+                ),
+            ],
+        );
+        persist_driver.close().unwrap();
+    }
+    {
+        let mdl = new_model();
+        let (mut persist_driver, _) =
+            batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+        snapshot::write(fname, &mdl, &mut persist_driver).unwrap();
+        assert!(!persist_driver.needs_snapshot().unwrap());
+        write_deltas(
+            &mdl,
+            &mut persist_driver,
+            vec![new_delta(
+                0,
+                2,
+                "emily",
+                into_dict!("password" => "pwd345678"),
+                DataDeltaKind::Insert,
+            )],
+        );
+        persist_driver.close().unwrap();
+    }
+    {
+        let mdl = new_model();
+        let (mut persist_driver, _) =
+            batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+        assert_users(
+            &mdl,
+            &[
+                ("sayan", "pwd123456"),
+                ("joseph", "pwd234567"),
+                ("emily", "pwd345678"),
+            ],
+        );
+        // the next snapshot replaces the first one
+        snapshot::write(fname, &mdl, &mut persist_driver).unwrap();
+        assert!(!exists(&snapshot::snapshot_path(fname, 1)));
+        assert!(exists(&snapshot::snapshot_path(fname, 2)));
+        // the tail
+        write_deltas(
+            &mdl,
+            &mut persist_driver,
+            vec![
+                new_delta(
+                    0,
+                    3,
+                    "sayan",
+                    into_dict!("password" => "pwd456789"),
+                    DataDeltaKind::Update,
+                ),
+                new_delta(0, 4, "joseph", into_dict!(), DataDeltaKind::Delete),
+                new_delta(
+                    0,
+                    5,
+                    "elana",
+                    into_dict!("password" => "pwd567890"),
+                    DataDeltaKind::Insert,
+                ),
+            ],
+        );
+        persist_driver.close().unwrap();
+    }
+    let users = [
+        ("sayan", "pwd456789"),
+        ("emily", "pwd345678"),
+        ("elana", "pwd567890"),
+    ];
+    // corrupt an event that the snapshot covers; since only the tail is replayed, we never see it
+    flip_bit(fname, b"pwd234567");
+    {
+        let mdl = new_model();
+        let (persist_driver, report) =
+            batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+        assert!(report.is_clean());
+        assert_users(&mdl, &users);
+        persist_driver.close().unwrap();
+    }
+    // a corrupted snapshot is ignored and the whole journal is replayed instead
+    flip_bit(&snapshot::snapshot_path(fname, 2), b"pwd345678");
+    {
+        let mdl = new_model();
+        let (mut persist_driver, report) =
+            batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+        assert!(!report.is_clean());
+        assert_users(&mdl, &users);
+        // a compacted journal is newer than the snapshot, so the snapshot is ignored from here on
+        batch_jrnl::compact(
+            fname,
+            &mdl,
+            &mut persist_driver,
+            BatchCompression::None,
+            None,
+        )
+        .unwrap();
+        persist_driver.close().unwrap();
+    }
+    let mdl = new_model();
+    let (persist_driver, report) =
+        batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    assert_users(&mdl, &users);
+    persist_driver.close().unwrap();
+}
+
+#[test]
+fn incremental_snapshots() {
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let fname = "incremental_snapshots.db-btlog";
+    let exists = |path: &str| VirtualFS::fs_fopen_rw(path).is_ok();
+    // change the model (just like a DML query would) and write the change to the journal
+    let change = |mdl: &Model,
+                  persist_driver: &mut DataBatchPersistDriver<VirtualFS>,
+                  username: &'static str,
+                  password: Option<&'static str>,
+                  kind: DataDeltaKind| {
+        let g = pin();
+        let txn_id = mdl
+            .delta_state()
+            .create_new_data_delta_version()
+            .value_u64();
+        let data = match password {
+            Some(password) => into_dict!("password" => password),
+            None => into_dict!(),
+        };
+        let delta = new_delta(0, txn_id, username, data, kind);
+        let idx = mdl.primary_index().__raw_index();
+        idx.mt_delete(&pkey(username), &g);
+        if kind != DataDeltaKind::Delete {
+            idx.mt_insert(delta.row().clone(), &g);
+        }
+        mdl.delta_state().append_new_data_delta(delta, &g);
+        persist_driver.write_new_batch(mdl, 1).unwrap();
+    };
+    {
+        let mdl = new_model();
+        let mut persist_driver =
+            batch_jrnl::create::<VirtualFS>(fname, BatchCompression::None, None).unwrap();
+        change(
+            &mdl,
+            &mut persist_driver,
+            "sayan",
+            Some("pwd123456"),
+            DataDeltaKind::Insert,
+        );
+        change(
+            &mdl,
+            &mut persist_driver,
+            "joseph",
+            Some("pwd234567"),
+            DataDeltaKind::Insert,
+        );
+        change(
+            &mdl,
+            &mut persist_driver,
+            "emily",
+            Some("pwd345678"),
+            DataDeltaKind::Insert,
+        );
+        // we don't know what was deleted before the first snapshot, so it's a full snapshot
+        assert!(!snapshot::write(fname, &mdl, &mut persist_driver).unwrap());
+        change(
+            &mdl,
+            &mut persist_driver,
+            "sayan",
+            Some("pwd456789"),
+            DataDeltaKind::Update,
+        );
+        change(
+            &mdl,
+            &mut persist_driver,
+            "elana",
+            Some("pwd567890"),
+            DataDeltaKind::Insert,
+        );
+        change(
+            &mdl,
+            &mut persist_driver,
+            "joseph",
+            None,
+            DataDeltaKind::Delete,
+        );
+        assert!(snapshot::write(fname, &mdl, &mut persist_driver).unwrap());
+        // the tail
+        change(
+            &mdl,
+            &mut persist_driver,
+            "emily",
+            None,
+            DataDeltaKind::Delete,
+        );
+        change(
+            &mdl,
+            &mut persist_driver,
+            "jordan",
+            Some("pwd678901"),
+            DataDeltaKind::Insert,
+        );
+        persist_driver.close().unwrap();
+    }
+    // the incremental snapshot only has what changed
+    assert!(exists(&snapshot::snapshot_path(fname, 1)));
+    let mut f = VirtualFS::fs_fopen_rw(&snapshot::snapshot_path(fname, 2)).unwrap();
+    let mut data = vec![0; f.fext_file_length().unwrap() as usize];
+    f.fr_read_exact(&mut data).unwrap();
+    f.fext_seek_ahead_from_start_by(0).unwrap();
+    assert!(!data.windows(9).any(|window| window == b"pwd345678"));
+    // everything that the snapshots cover is skipped
+    flip_bit(fname, b"pwd123456");
+    let users = [
+        ("sayan", "pwd456789"),
+        ("elana", "pwd567890"),
+        ("jordan", "pwd678901"),
+    ];
+    let mdl = new_model();
+    let (mut persist_driver, report) =
+        batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    assert_users(&mdl, &users);
+    // we don't know what was deleted before the restart, so we start over with a full snapshot
+    assert!(!snapshot::write(fname, &mdl, &mut persist_driver).unwrap());
+    assert!(!exists(&snapshot::snapshot_path(fname, 1)));
+    assert!(!exists(&snapshot::snapshot_path(fname, 2)));
+    // and we start over once the chain gets long enough
+    for i in 0..9 {
+        change(
+            &mdl,
+            &mut persist_driver,
+            "sayan",
+            Some("pwd456789"),
+            DataDeltaKind::Update,
+        );
+        assert_eq!(
+            snapshot::write(fname, &mdl, &mut persist_driver).unwrap(),
+            i < 8
+        );
+    }
+    persist_driver.close().unwrap();
+    let mdl = new_model();
+    let (persist_driver, report) =
+        batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    assert_users(&mdl, &users);
+    persist_driver.close().unwrap();
+}
+
+fn write_users_and_restore(
+    fname: &str,
+    event_checksums: bool,
+    corrupt: &[u8],
+) -> RuntimeResult<(Model, DataBatchRestoreReport)> {
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let mdl = new_model();
+    let g = pin();
+    for (txn_id, (username, password)) in [
+        ("sayan", "pwd123456"),
+        ("joseph", "pwd234567"),
+        ("emily", "pwd345678"),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        mdl.delta_state().append_new_data_delta(
+            new_delta(
+                0,
+                txn_id as u64,
+                username,
+                into_dict!("password" => password),
+                DataDeltaKind::Insert,
+            ),
+            &g,
+        );
+    }
+    let mut persist_driver =
+        DataBatchPersistDriver::new(open_file(fname).into_created().unwrap(), true).unwrap();
+    if !event_checksums {
+        persist_driver = persist_driver.without_event_checksums();
+    }
+    persist_driver.write_new_batch(&mdl, 3).unwrap();
+    persist_driver.close().unwrap();
+    if !corrupt.is_empty() {
+        flip_bit(fname, corrupt);
+    }
+    // restore
+    let restored = new_model();
+    let (f, _header) = open_file(fname).into_existing().unwrap();
+    let report = DataBatchRestoreDriver::new(f)
+        .unwrap()
+        .read_data_batch_into_model(&restored)?;
+    Ok((restored, report))
+}
+
+/// Flip a bit in the first occurrence of `corrupt` in the file
+fn flip_bit(fname: &str, corrupt: &[u8]) {
+    let mut f = VirtualFS::fs_fopen_rw(fname).unwrap();
+    let mut data = vec![0; f.fext_file_length().unwrap() as usize];
+    f.fr_read_exact(&mut data).unwrap();
+    let position = data
+        .windows(corrupt.len())
+        .position(|window| window == corrupt)
+        .unwrap();
+    f.fext_seek_ahead_from_start_by(position as u64).unwrap();
+    f.fw_write_all(&[data[position] ^ 1]).unwrap();
+}
+
+fn assert_users(mdl: &Model, users: &[(&'static str, &'static str)]) {
+    let g = pin();
+    let idx = mdl.primary_index().__raw_index();
+    assert_eq!(idx.mt_len(), users.len());
+    for (username, password) in users {
+        assert_eq!(
+            idx.mt_get(&pkey(*username), &g)
+                .unwrap()
+                .read()
+                .fields()
+                .get("password")
+                .unwrap(),
+            &Datacell::from(*password)
+        );
+    }
+}
+
+#[test]
+fn event_checksums_clean() {
+    for event_checksums in [false, true] {
+        let (mdl, report) = write_users_and_restore(
+            &format!("event_checksums_clean_{event_checksums}.db-btlog"),
+            event_checksums,
+            &[],
+        )
+        .unwrap();
+        assert!(report.is_clean());
+        assert_users(
+            &mdl,
+            &[
+                ("sayan", "pwd123456"),
+                ("joseph", "pwd234567"),
+                ("emily", "pwd345678"),
+            ],
+        );
+    }
+}
+
+#[test]
+fn event_checksums_skip_corrupted_event() {
+    let (mdl, report) = write_users_and_restore(
+        "event_checksums_skip_corrupted_event.db-btlog",
+        true,
+        b"pwd234567",
+    )
+    .unwrap();
+    assert_eq!(report.corrupted_events().len(), 1);
+    assert_users(&mdl, &[("sayan", "pwd123456"), ("emily", "pwd345678")]);
+}
+
+#[test]
+fn legacy_format_corrupted_event_fails_batch() {
+    assert!(write_users_and_restore(
+        "legacy_format_corrupted_event_fails_batch.db-btlog",
+        false,
+        b"pwd234567",
+    )
+    .is_err());
+}
+
+#[test]
+fn durability_policies() {
+    let users = [
+        ("sayan", "pwd123456"),
+        ("joseph", "pwd234567"),
+        ("emily", "pwd345678"),
+    ];
+    for (i, policy) in [
+        DurabilityPolicy::Always,
+        DurabilityPolicy::Periodic(60_000),
+        DurabilityPolicy::OsBuffered,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let new_model = || {
+            Model::new_restore(
+                Uuid::new(),
+                "username".into(),
+                TagSelector::String.into_full(),
+                into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+            )
+        };
+        let fname = format!("durability_policies_{i}.db-btlog");
+        let mdl = new_model();
+        let g = pin();
+        for (txn_id, (username, password)) in users.into_iter().enumerate() {
+            mdl.delta_state().append_new_data_delta(
+                new_delta(
+                    0,
+                    txn_id as u64,
+                    username,
+                    into_dict!("password" => password),
+                    DataDeltaKind::Insert,
+                ),
+                &g,
+            );
+        }
+        let mut persist_driver =
+            DataBatchPersistDriver::new(open_file(&fname).into_created().unwrap(), true).unwrap();
+        persist_driver.set_durability(policy);
+        persist_driver.write_new_batch(&mdl, 3).unwrap();
+        persist_driver.sync_if_due().unwrap();
+        persist_driver.close().unwrap();
+        // the policy survives a compaction
+        let mdl = new_model();
+        let mut persist_driver = open_batch_data(&fname, &mdl);
+        persist_driver.set_durability(policy);
+        batch_jrnl::compact(
+            &fname,
+            &mdl,
+            &mut persist_driver,
+            BatchCompression::None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(persist_driver.durability(), policy);
+        persist_driver.close().unwrap();
+        let restored = new_model();
+        let (f, _header) = open_file(&fname).into_existing().unwrap();
+        assert!(DataBatchRestoreDriver::new(f)
+            .unwrap()
+            .read_data_batch_into_model(&restored)
+            .unwrap()
+            .is_clean());
+        assert_users(&restored, &users);
+    }
+}
+
+#[test]
+fn stream_batches() {
+    let mdl = Model::new_restore(
+        Uuid::new(),
+        "username".into(),
+        TagSelector::String.into_full(),
+        into_dict!("username" => Field::new([Layer::str()].into(), false)),
+    );
+    let mut restore_driver = flush_batches_and_return_restore_driver(
+        [
+            new_delta(0, 0, "sayan", into_dict!(), DataDeltaKind::Insert),
+            new_delta(0, 1, "sayan", into_dict!(), DataDeltaKind::Delete),
+        ],
+        &mdl,
+        "stream_batches.db-btlog",
+    );
+    let mut items = vec![];
+    restore_driver
+        .stream_batches(|item| items.push(item))
+        .unwrap();
+    assert_eq!(
+        items,
+        vec![
+            BatchStreamItem::Begin { schema_version: 0 },
+            BatchStreamItem::Event(DecodedBatchEvent::new(
+                0,
+                pkey("sayan"),
+                DecodedBatchEventKind::Insert(vec![])
+            )),
+            BatchStreamItem::Event(DecodedBatchEvent::new(
+                1,
+                pkey("sayan"),
+                DecodedBatchEventKind::Delete
+            )),
+            BatchStreamItem::Commit,
+        ]
+    );
+}
+
+#[test]
+fn blob_heavy_restore() {
+    let new_model = || {
+        Model::new_restore(
+            Uuid::new(),
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!(
+                "username" => Field::new([Layer::str()].into(), false),
+                "avatar" => Field::new([Layer::bin()].into(), false),
+                "bio" => Field::new([Layer::str()].into(), false),
+                "tags" => Field::new([Layer::list(), Layer::str()].into(), false),
+                "visits" => Field::new([Layer::uint64()].into(), false)
+            ),
+        )
+    };
+    let user = |i: u64, version: u8| {
+        into_dict!(
+            "avatar" => Datacell::new_bin(vec![version; 4096 + i as usize].into()),
+            "bio" => format!("{}{i}", "bio".repeat(512 * version as usize)),
+            "tags" => Datacell::new_list(vec![Datacell::from(format!("tag-{i}-{version}"))]),
+            "visits" => Datacell::new_uint_default(i * version as u64)
+        )
+    };
+    let fname = "blob_heavy_restore.db-btlog";
+    let mdl = new_model();
+    let g = pin();
+    let mut persist_driver =
+        batch_jrnl::create::<VirtualFS>(fname, BatchCompression::None, None).unwrap();
+    // insert everyone, then in a second batch update every other user and delete every third
+    for i in 0..32 {
+        mdl.delta_state().append_new_data_delta(
+            new_delta(0, i, format!("user-{i}"), user(i, 1), DataDeltaKind::Insert),
+            &g,
+        );
+    }
+    persist_driver.write_new_batch(&mdl, 32).unwrap();
+    let mut txn_id = 32;
+    let mut changed = 0;
+    for i in 0..32 {
+        let kind = match i {
+            i if i % 3 == 0 => DataDeltaKind::Delete,
+            i if i % 2 == 0 => DataDeltaKind::Update,
+            _ => continue,
+        };
+        mdl.delta_state().append_new_data_delta(
+            new_delta(0, txn_id, format!("user-{i}"), user(i, 2), kind),
+            &g,
+        );
+        txn_id += 1;
+        changed += 1;
+    }
+    persist_driver.write_new_batch(&mdl, changed).unwrap();
+    persist_driver.close().unwrap();
+    // restore
+    let restored = new_model();
+    let (persist_driver, report) =
+        batch_jrnl::reinit::<VirtualFS>(fname, &restored, RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    persist_driver.close().unwrap();
+    let idx = restored.primary_index().__raw_index();
+    assert_eq!(idx.mt_len(), 21);
+    for i in (0..32).filter(|i| i % 3 != 0) {
+        let version = if i % 2 == 0 { 2 } else { 1 };
+        let row = idx.mt_get(&pkey(format!("user-{i}")), &g).unwrap();
+        let row = row.read();
+        for (field, value) in user(i, version).iter() {
+            assert_eq!(row.fields().get(field.as_str()).unwrap(), value);
+        }
+    }
+    // and the events themselves are decoded intact
+    let mut restore_driver =
+        DataBatchRestoreDriver::new(open_file(fname).into_existing().unwrap().0).unwrap();
+    let batches = restore_driver.read_all_batches().unwrap();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(
+        batches[0],
+        NormalBatch::new(
+            (0..32)
+                .map(|i| {
+                    let user = user(i, 1);
+                    DecodedBatchEvent::new(
+                        i,
+                        pkey(format!("user-{i}")),
+                        DecodedBatchEventKind::Insert(
+                            ["avatar", "bio", "tags", "visits"]
+                                .into_iter()
+                                .map(|field| user.get(field).unwrap().clone())
+                                .collect(),
+                        ),
+                    )
+                })
+                .collect(),
+            0
+        )
+    );
+}
+
+#[test]
+fn legacy_crc64_journal() {
+    let new_model = || {
+        Model::new_restore(
+            Uuid::new(),
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let fname = "legacy_crc64_journal.db-btlog";
+    // journals written before we switched to CRC32C don't have the feature flag in their header (or the one for varints)
+    let mut header = SDSSStaticHeaderV1Compact::_encode_auto(
+        spec::DataBatchJournalV1::ENCODE_DATA.0,
+        spec::DataBatchJournalV1::ENCODE_DATA.1,
+        spec::DataBatchJournalV1::ENCODE_DATA.2,
+    );
+    header[48..56].copy_from_slice(&0u64.to_le_bytes());
+    VirtualFS::fs_fcreate_rw(fname)
+        .unwrap()
+        .fw_write_all(&header)
+        .unwrap();
+    let (f, header) = SDSSFileIO::<VirtualFS>::open::<spec::DataBatchJournalV1>(fname).unwrap();
+    assert_eq!(spec::checksum_from_header(&header), ChecksumKind::Crc64);
+    assert_eq!(BatchEncoding::from_header(&header), BatchEncoding::Fixed);
+    let mdl = new_model();
+    let g = pin();
+    for (txn_id, (username, password)) in [("sayan", "pwd123456"), ("joseph", "pwd234567")]
+        .into_iter()
+        .enumerate()
+    {
+        mdl.delta_state().append_new_data_delta(
+            new_delta(
+                0,
+                txn_id as u64,
+                username,
+                into_dict!("password" => password),
+                DataDeltaKind::Insert,
+            ),
+            &g,
+        );
+    }
+    let mut persist_driver = DataBatchPersistDriver::new(f, true)
+        .unwrap()
+        .with_checksum(ChecksumKind::Crc64)
+        .with_encoding(BatchEncoding::Fixed);
+    persist_driver.write_new_batch(&mdl, 2).unwrap();
+    persist_driver.close().unwrap();
+    // restore it, and keep writing to it
+    {
+        let mdl = new_model();
+        let (mut persist_driver, report) =
+            batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+        assert!(report.is_clean());
+        assert_users(&mdl, &[("sayan", "pwd123456"), ("joseph", "pwd234567")]);
+        let row = mdl
+            .primary_index()
+            .__raw_index()
+            .mt_get_element(&pkey("joseph"), &g)
+            .unwrap()
+            .clone();
+        mdl.delta_state()
+            .append_new_data_delta(new_delta_with_row(2, row, DataDeltaKind::Delete), &g);
+        persist_driver.write_new_batch(&mdl, 1).unwrap();
+        persist_driver.close().unwrap();
+    }
+    let mdl = new_model();
+    let (persist_driver, report) =
+        batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    assert_users(&mdl, &[("sayan", "pwd123456")]);
+    persist_driver.close().unwrap();
+    // the batches don't verify with the checksum used in new journals
+    let (f, _) = open_file(fname).into_existing().unwrap();
+    assert!(DataBatchRestoreDriver::new(f)
+        .unwrap()
+        .read_all_batches()
+        .is_err());
+    // while a new journal uses CRC32C
+    drop(open_file("legacy_crc64_journal_new.db-btlog"));
+    let (_, header) = open_file("legacy_crc64_journal_new.db-btlog")
+        .into_existing()
+        .unwrap();
+    assert_eq!(spec::checksum_from_header(&header), ChecksumKind::Crc32c);
+}
+
+#[test]
+fn varint_journal() {
+    let new_model = || {
+        Model::new_restore(
+            Uuid::new(),
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!(
+                "username" => Field::new([Layer::str()].into(), false),
+                "password" => Field::new([Layer::str()].into(), false),
+                "tags" => Field::new([Layer::list(), Layer::str()].into(), false)
+            ),
+        )
+    };
+    let write = |fname: &str, encoding: BatchEncoding| {
+        let mdl = new_model();
+        // the txn ids are encoded against the next txn id of the model, so put it in the middle of the batch
+        mdl.delta_state()
+            .__restore_data_version(DeltaVersion::__new(1_000_050));
+        let g = pin();
+        for i in 0..100u64 {
+            mdl.delta_state().append_new_data_delta(
+                new_delta(
+                    0,
+                    1_000_000 + i,
+                    format!("user{i}"),
+                    into_dict!(
+                        "password" => format!("pwd{i}"),
+                        "tags" => Datacell::new_list(vec!["a".into(), format!("t{i}").into()])
+                    ),
+                    DataDeltaKind::Insert,
+                ),
+                &g,
+            );
+        }
+        let mut persist_driver =
+            DataBatchPersistDriver::new(open_file(fname).into_created().unwrap(), true)
+                .unwrap()
+                .with_encoding(encoding);
+        persist_driver.write_new_batch(&mdl, 100).unwrap();
+        persist_driver.close().unwrap();
+        let (f, _) = open_file(fname).into_existing().unwrap();
+        let len = f.file_length().unwrap();
+        let batches = DataBatchRestoreDriver::new(f)
+            .unwrap()
+            .with_encoding(encoding)
+            .read_all_batches()
+            .unwrap();
+        (batches, len)
+    };
+    let (fixed, fixed_len) = write("varint_journal_fixed.db-btlog", BatchEncoding::Fixed);
+    let (varint, varint_len) = write("varint_journal.db-btlog", BatchEncoding::Varint);
+    // both encodings decode to the same events (with the same txn ids), but varints take a lot less space
+    let expected = vec![NormalBatch::new(
+        (0..100u64)
+            .map(|i| {
+                DecodedBatchEvent::new(
+                    1_000_000 + i,
+                    pkey(format!("user{i}")),
+                    DecodedBatchEventKind::Insert(vec![
+                        Datacell::from(format!("pwd{i}")),
+                        Datacell::new_list(vec!["a".into(), format!("t{i}").into()]),
+                    ]),
+                )
+            })
+            .collect(),
+        0,
+    )];
+    assert_eq!(fixed, expected);
+    assert_eq!(varint, expected);
+    assert!(varint_len * 2 < fixed_len, "{varint_len} vs {fixed_len}");
+    // a new journal records that it uses varints
+    let (_, header) = open_file("varint_journal.db-btlog")
+        .into_existing()
+        .unwrap();
+    assert_eq!(BatchEncoding::from_header(&header), BatchEncoding::Varint);
+}
+
+#[test]
+fn lz4_compressed_batches() {
+    let new_model = || {
+        Model::new_restore(
+            Uuid::new(),
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let password = |i: u64| format!("{}{i}", "x".repeat(1024));
+    let write_users = |fname: &str, compression: BatchCompression| {
+        let mdl = new_model();
+        let g = pin();
+        for i in 0..10 {
+            mdl.delta_state().append_new_data_delta(
+                new_delta(
+                    0,
+                    i,
+                    format!("user-{i}"),
+                    into_dict!("password" => password(i)),
+                    DataDeltaKind::Insert,
+                ),
+                &g,
+            );
+        }
+        let mut persist_driver = batch_jrnl::create::<VirtualFS>(fname, compression, None).unwrap();
+        persist_driver.write_new_batch(&mdl, 10).unwrap();
+        persist_driver.close().unwrap();
+        let (f, header) = open_file(fname).into_existing().unwrap();
+        assert_eq!(BatchCompression::from_header(&header).unwrap(), compression);
+        f.file_length().unwrap()
+    };
+    let assert_passwords = |mdl: &Model, users: &[u64]| {
+        let g = pin();
+        let idx = mdl.primary_index().__raw_index();
+        assert_eq!(idx.mt_len(), users.len());
+        for i in users {
+            assert_eq!(
+                idx.mt_get(&pkey(format!("user-{i}")), &g)
+                    .unwrap()
+                    .read()
+                    .fields()
+                    .get("password")
+                    .unwrap()
+                    .str(),
+                password(*i)
+            );
+        }
+    };
+    let fname = "lz4_compressed_batches.db-btlog";
+    let uncompressed_len = write_users(
+        "lz4_compressed_batches_uncompressed.db-btlog",
+        BatchCompression::None,
+    );
+    let compressed_len = write_users(fname, BatchCompression::Lz4);
+    assert!(compressed_len * 4 < uncompressed_len);
+    // restore, and since we keep compressing after a reopen, delete a user
+    {
+        let mdl = new_model();
+        let (mut persist_driver, report) =
+            batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+        assert!(report.is_clean());
+        assert_passwords(&mdl, &(0..10).collect::<Vec<_>>());
+        let row = mdl
+            .primary_index()
+            .__raw_index()
+            .mt_get_element(&pkey("user-0"), &pin())
+            .unwrap()
+            .clone();
+        mdl.delta_state()
+            .append_new_data_delta(new_delta_with_row(10, row, DataDeltaKind::Delete), &pin());
+        persist_driver.write_new_batch(&mdl, 1).unwrap();
+        persist_driver.close().unwrap();
+    }
+    let mdl = new_model();
+    let (persist_driver, report) =
+        batch_jrnl::reinit::<VirtualFS>(fname, &mdl, RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    assert_passwords(&mdl, &(1..10).collect::<Vec<_>>());
+    persist_driver.close().unwrap();
+}
+
+#[test]
+fn encrypted_batches_and_snapshots() {
+    let key = EncryptionKey::new([0x5A; encryption::KEY_SIZE]);
+    encryption::register(key.clone());
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let contains = |path: &str, needle: &[u8]| {
+        let mut f = VirtualFS::fs_fopen_rw(path).unwrap();
+        let mut data = vec![0; f.fext_file_length().unwrap() as usize];
+        f.fr_read_exact(&mut data).unwrap();
+        f.fext_seek_ahead_from_start_by(0).unwrap();
+        data.windows(needle.len()).any(|window| window == needle)
+    };
+    let insert = |mdl: &Model,
+                  persist_driver: &mut DataBatchPersistDriver<VirtualFS>,
+                  users: &[(u64, &'static str, &'static str)]| {
+        let g = pin();
+        for (txn_id, username, password) in users {
+            mdl.delta_state().append_new_data_delta(
+                new_delta(
+                    0,
+                    *txn_id,
+                    *username,
+                    into_dict!("password" => *password),
+                    DataDeltaKind::Insert,
+                ),
+                &g,
+            );
+        }
+        persist_driver.write_new_batch(mdl, users.len()).unwrap();
+    };
+    for compression in [BatchCompression::None, BatchCompression::Lz4] {
+        let fname = format!("encrypted_batches_{}.db-btlog", compression.value_u8());
+        {
+            let mdl = new_model();
+            let mut persist_driver =
+                batch_jrnl::create::<VirtualFS>(&fname, compression, Some(&key)).unwrap();
+            insert(
+                &mdl,
+                &mut persist_driver,
+                &[(0, "sayan", "pwd123456"), (1, "joseph", "pwd234567")],
+            );
+            persist_driver.close().unwrap();
+        }
+        // the header records the key, and nothing is written in the clear
+        let (_, header) = open_file(&fname).into_existing().unwrap();
+        assert_eq!(encryption::key_id_from_header(&header), key.id());
+        assert!(!contains(&fname, b"pwd123456"));
+        // restore, take a snapshot (which is encrypted as well) and keep writing after the reopen
+        {
+            let mdl = new_model();
+            let (mut persist_driver, report) =
+                batch_jrnl::reinit::<VirtualFS>(&fname, &mdl, RecoveryMode::Strict).unwrap();
+            assert!(report.is_clean());
+            assert_users(&mdl, &[("sayan", "pwd123456"), ("joseph", "pwd234567")]);
+            snapshot::write(&fname, &mdl, &mut persist_driver).unwrap();
+            insert(&mdl, &mut persist_driver, &[(2, "elana", "pwd345678")]);
+            persist_driver.close().unwrap();
+        }
+        let snapshot_path = snapshot::snapshot_path(&fname, 1);
+        assert!(!contains(&snapshot_path, b"pwd123456"));
+        assert!(!contains(&fname, b"pwd345678"));
+        let mdl = new_model();
+        let manifest = snapshot::read_manifest::<VirtualFS>(&fname)
+            .unwrap()
+            .unwrap();
+        snapshot::load::<VirtualFS>(&fname, &manifest, &mdl).unwrap();
+        assert_users(&mdl, &[("sayan", "pwd123456"), ("joseph", "pwd234567")]);
+        let mdl = new_model();
+        let (persist_driver, report) =
+            batch_jrnl::reinit::<VirtualFS>(&fname, &mdl, RecoveryMode::Strict).unwrap();
+        assert!(report.is_clean());
+        assert_users(
+            &mdl,
+            &[
+                ("sayan", "pwd123456"),
+                ("joseph", "pwd234567"),
+                ("elana", "pwd345678"),
+            ],
+        );
+        persist_driver.close().unwrap();
+    }
+    // a journal that was encrypted with a key we don't have refuses to open
+    let fname = "encrypted_batches_unknown_key.db-btlog";
+    let unknown = EncryptionKey::new([0xA5; encryption::KEY_SIZE]);
+    batch_jrnl::create::<VirtualFS>(fname, BatchCompression::None, Some(&unknown))
+        .unwrap()
+        .close()
+        .unwrap();
+    let Err(e) = batch_jrnl::reinit::<VirtualFS>(fname, &new_model(), RecoveryMode::Strict) else {
+        panic!("opened a journal without its key");
+    };
+    assert_eq!(
+        e.kind(),
+        &ErrorKind::Storage(StorageError::EncryptionKeyUnavailable)
+    );
+}
+
+/// Cut the file short at `len`, like a crash while it was being written to
+fn truncate_file(fname: &str, len: u64) {
+    VirtualFS::fs_fopen_rw(fname)
+        .unwrap()
+        .fwext_truncate_to(len)
+        .unwrap();
+}
+
+#[test]
+fn torn_tail_recovery() {
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let insert = |mdl: &Model,
+                  persist_driver: &mut DataBatchPersistDriver<VirtualFS>,
+                  users: &[(u64, &'static str, &'static str)]| {
+        let g = pin();
+        for (txn_id, username, password) in users {
+            mdl.delta_state().append_new_data_delta(
+                new_delta(
+                    0,
+                    *txn_id,
+                    *username,
+                    into_dict!("password" => *password),
+                    DataDeltaKind::Insert,
+                ),
+                &g,
+            );
+        }
+        persist_driver.write_new_batch(mdl, users.len()).unwrap();
+    };
+    // write two batches, returning where the second one starts and ends
+    let write_users = |fname: &str, compression: BatchCompression| {
+        let mdl = new_model();
+        let mut persist_driver = batch_jrnl::create::<VirtualFS>(fname, compression, None).unwrap();
+        insert(
+            &mdl,
+            &mut persist_driver,
+            &[(0, "sayan", "pwd123456"), (1, "joseph", "pwd234567")],
+        );
+        let start = persist_driver.file_length().unwrap();
+        insert(&mdl, &mut persist_driver, &[(2, "emily", "pwd345678")]);
+        let end = persist_driver.file_length().unwrap();
+        persist_driver.close().unwrap();
+        (start, end)
+    };
+    for compression in [BatchCompression::None, BatchCompression::Lz4] {
+        // we crashed right after the last batch, so only the close marker is missing
+        let fname = format!("torn_tail_unclosed_{}.db-btlog", compression.value_u8());
+        let (_, end) = write_users(&fname, compression);
+        truncate_file(&fname, end);
+        assert!(
+            batch_jrnl::reinit::<VirtualFS>(&fname, &new_model(), RecoveryMode::Strict).is_err()
+        );
+        let mdl = new_model();
+        let (persist_driver, report) =
+            batch_jrnl::reinit::<VirtualFS>(&fname, &mdl, RecoveryMode::TruncateTail).unwrap();
+        assert_eq!(report.torn_tail(), Some((end, 0)));
+        assert_users(
+            &mdl,
+            &[
+                ("sayan", "pwd123456"),
+                ("joseph", "pwd234567"),
+                ("emily", "pwd345678"),
+            ],
+        );
+        persist_driver.close().unwrap();
+        // we crashed while appending the last batch
+        for part in 1..4 {
+            let fname = format!(
+                "torn_tail_partial_{}_{part}.db-btlog",
+                compression.value_u8()
+            );
+            let (start, end) = write_users(&fname, compression);
+            let cut = start + (end - start) * part / 4;
+            truncate_file(&fname, cut);
+            assert!(
+                batch_jrnl::reinit::<VirtualFS>(&fname, &new_model(), RecoveryMode::Strict)
+                    .is_err()
+            );
+            {
+                let mdl = new_model();
+                let (mut persist_driver, report) =
+                    batch_jrnl::reinit::<VirtualFS>(&fname, &mdl, RecoveryMode::TruncateTail)
+                        .unwrap();
+                assert_eq!(report.torn_tail(), Some((start, cut - start)));
+                assert_users(&mdl, &[("sayan", "pwd123456"), ("joseph", "pwd234567")]);
+                // the partial batch is gone, so we can keep writing to the journal
+                insert(&mdl, &mut persist_driver, &[(3, "elana", "pwd456789")]);
+                persist_driver.close().unwrap();
+            }
+            let mdl = new_model();
+            let (persist_driver, report) =
+                batch_jrnl::reinit::<VirtualFS>(&fname, &mdl, RecoveryMode::Strict).unwrap();
+            assert!(report.is_clean());
+            assert_users(
+                &mdl,
+                &[
+                    ("sayan", "pwd123456"),
+                    ("joseph", "pwd234567"),
+                    ("elana", "pwd456789"),
+                ],
+            );
+            persist_driver.close().unwrap();
+        }
+    }
+    // but a batch that's corrupted in the middle of the journal is still an error
+    let fname = "torn_tail_corrupted.db-btlog";
+    let mut persist_driver =
+        DataBatchPersistDriver::new(open_file(fname).into_created().unwrap(), true)
+            .unwrap()
+            .without_event_checksums();
+    insert(
+        &new_model(),
+        &mut persist_driver,
+        &[(0, "sayan", "pwd123456")],
+    );
+    insert(
+        &new_model(),
+        &mut persist_driver,
+        &[(1, "joseph", "pwd234567")],
+    );
+    let end = persist_driver.file_length().unwrap();
+    persist_driver.close().unwrap();
+    truncate_file(fname, end - 1);
+    flip_bit(fname, b"pwd123456");
+    let (f, _header) = open_file(fname).into_existing().unwrap();
+    let Err(e) = DataBatchRestoreDriver::new(f)
+        .unwrap()
+        .with_recovery(RecoveryMode::TruncateTail)
+        .read_data_batch_into_model(&new_model())
+    else {
+        panic!("restored a journal that's corrupted in the middle");
+    };
+    assert_eq!(
+        e.kind(),
+        &ErrorKind::Storage(StorageError::DataBatchRestoreCorruptedBatch)
+    );
+}
+
+#[test]
+fn torn_tail_recovery_keeps_later_batches() {
+    let uuid = Uuid::new();
+    let new_model = || {
+        Model::new_restore(
+            uuid,
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false), "password" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    let fname = "torn_tail_bad_length.db-btlog";
+    let mdl = new_model();
+    let mut persist_driver =
+        batch_jrnl::create::<VirtualFS>(fname, BatchCompression::None, None).unwrap();
+    let g = pin();
+    for (txn_id, username, password) in [
+        (0, "sayan", "pwd123456"),
+        (1, "joseph", "pwd234567"),
+        (2, "emily", "pwd345678"),
+    ] {
+        mdl.delta_state().append_new_data_delta(
+            new_delta(
+                0,
+                txn_id,
+                username,
+                into_dict!("password" => password),
+                DataDeltaKind::Insert,
+            ),
+            &g,
+        );
+        persist_driver.write_new_batch(&mdl, 1).unwrap();
+    }
+    persist_driver.close().unwrap();
+    // the length of the key in the first batch now runs past the end of the journal
+    let mut f = VirtualFS::fs_fopen_rw(fname).unwrap();
+    let len = f.fext_file_length().unwrap();
+    let mut data = vec![0; len as usize];
+    f.fr_read_exact(&mut data).unwrap();
+    let position = data
+        .windows(6)
+        .position(|window| window == b"\x05sayan")
+        .unwrap();
+    f.fext_seek_ahead_from_start_by(position as u64).unwrap();
+    f.fw_write_all(&[0x85]).unwrap();
+    drop(f);
+    for recovery in [RecoveryMode::Strict, RecoveryMode::TruncateTail] {
+        assert!(batch_jrnl::reinit::<VirtualFS>(fname, &new_model(), recovery).is_err());
+        // and the batches after it are still there
+        assert_eq!(
+            VirtualFS::fs_fopen_rw(fname)
+                .unwrap()
+                .fext_file_length()
+                .unwrap(),
+            len
+        );
+    }
+}
@@ -0,0 +1,176 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    crate::{
+        engine::{
+            core::{
+                index::{PrimaryIndexKey, Row},
+                model::{
+                    delta::{DataDelta, DataDeltaKind, DeltaVersion},
+                    Field, Layer, Model,
+                },
+            },
+            data::{cell::Datacell, tag::TagSelector, uuid::Uuid},
+            storage::v1::{
+                batch_jrnl::{DataBatchPersistDriver, DataBatchRestoreDriver},
+                direct::{DirectFS, DIRECT_IO_ALIGNMENT},
+                rw::{
+                    FileOpen, RawFSInterface, RawFileInterface, RawFileInterfaceBufferedWriter,
+                    RawFileInterfaceExt, RawFileInterfaceRead, RawFileInterfaceWrite, SDSSFileIO,
+                },
+                spec,
+            },
+        },
+        util::test_utils,
+    },
+    crossbeam_epoch::pin,
+};
+
+type DirectFile = <DirectFS as RawFSInterface>::File;
+
+#[test]
+fn aligned_writes() {
+    test_utils::with_files(["direct_aligned_writes.bin"], |[file]| {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        // a few bytes that go through the page cache, like a header
+        let mut f = DirectFS::fs_fopen_rw(file).unwrap();
+        f.fw_write_all(&data[..13]).unwrap();
+        let mut w = f.into_buffered_writer().unwrap();
+        assert_eq!(w.fext_cursor().unwrap(), 13);
+        // unaligned writes, some of which are larger than the buffer
+        for chunk in data[13..].chunks(DIRECT_IO_ALIGNMENT * 7 + 5) {
+            w.fw_write_all(chunk).unwrap();
+            w.flush_write_cache().unwrap();
+            // no padding is ever left at the end of the file
+            assert_eq!(w.fext_file_length().unwrap(), w.fext_cursor().unwrap());
+        }
+        w.sync_write_cache().unwrap();
+        assert_eq!(std::fs::read(file).unwrap(), data);
+        // overwrite a few bytes in the middle of a block, without touching anything around them
+        w.fext_seek_ahead_from_start_by(DIRECT_IO_ALIGNMENT as u64 + 3)
+            .unwrap();
+        w.fw_write_all(b"hello").unwrap();
+        let mut f = DirectFile::downgrade_writer(w).unwrap();
+        assert_eq!(f.fext_cursor().unwrap(), DIRECT_IO_ALIGNMENT as u64 + 8);
+        let mut expected = data.clone();
+        expected[DIRECT_IO_ALIGNMENT + 3..DIRECT_IO_ALIGNMENT + 8].copy_from_slice(b"hello");
+        assert_eq!(std::fs::read(file).unwrap(), expected);
+    })
+}
+
+#[test]
+fn aligned_reads() {
+    test_utils::with_files(["direct_aligned_reads.bin"], |[file]| {
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(file, &data).unwrap();
+        let mut f = DirectFS::fs_fopen_rw(file).unwrap();
+        let mut header = [0; 13];
+        f.fr_read_exact(&mut header).unwrap();
+        let mut r = f.into_buffered_reader().unwrap();
+        assert_eq!(r.fext_cursor().unwrap(), 13);
+        // unaligned reads, some of which cross the buffer
+        let mut read = header.to_vec();
+        while read.len() < data.len() {
+            let mut chunk = vec![0; (data.len() - read.len()).min(DIRECT_IO_ALIGNMENT * 3 + 7)];
+            r.fr_read_exact(&mut chunk).unwrap();
+            read.extend(chunk);
+        }
+        assert_eq!(read, data);
+        assert!(r.fr_read_exact(&mut [0]).is_err());
+        // jump back into the middle of a block
+        r.fext_seek_ahead_from_start_by(5).unwrap();
+        let mut chunk = [0; 10];
+        r.fr_read_exact(&mut chunk).unwrap();
+        assert_eq!(chunk, data[5..15]);
+        let mut f = DirectFile::downgrade_reader(r).unwrap();
+        assert_eq!(f.fext_cursor().unwrap(), 15);
+    })
+}
+
+fn new_model() -> Model {
+    Model::new_restore(
+        Uuid::new(),
+        "username".into(),
+        TagSelector::String.into_full(),
+        into_dict!(
+            "username" => Field::new([Layer::str()].into(), false),
+            "password" => Field::new([Layer::bin()].into(), false)
+        ),
+    )
+}
+
+fn insert(mdl: &Model, txn_id: u64, username: &str) {
+    let row = Row::new(
+        PrimaryIndexKey::try_from_dc(Datacell::new_str(username.into())).unwrap(),
+        into_dict!("password" => Datacell::new_bin(b"password".to_vec().into_boxed_slice())),
+        DeltaVersion::__new(0),
+        DeltaVersion::__new(txn_id),
+    );
+    mdl.delta_state().append_new_data_delta(
+        DataDelta::new(DeltaVersion::__new(txn_id), row, DataDeltaKind::Insert),
+        &pin(),
+    );
+}
+
+#[test]
+fn batch_journal_roundtrip() {
+    test_utils::with_files(["direct_batch_journal_roundtrip.db-btlog"], |[file]| {
+        let mdl = new_model();
+        let mut txn_id = 0;
+        for round in 0..3 {
+            // every reopen restores the journal using aligned reads
+            let restored = new_model();
+            let mut driver = match SDSSFileIO::<DirectFS>::open_or_create_perm_rw::<
+                spec::DataBatchJournalV1,
+            >(file)
+            .unwrap()
+            {
+                FileOpen::Created(f) => DataBatchPersistDriver::new(f, true),
+                FileOpen::Existing((f, _)) => {
+                    let mut restore = DataBatchRestoreDriver::new(f).unwrap();
+                    restore.read_data_batch_into_model(&restored).unwrap();
+                    DataBatchPersistDriver::new(restore.into_file().unwrap(), false)
+                }
+            }
+            .unwrap();
+            assert_eq!(restored.primary_index().count(), round * 100);
+            for i in 0..100 {
+                insert(&mdl, txn_id, &format!("user-{round}-{i}"));
+                txn_id += 1;
+            }
+            driver.write_new_batch(&mdl, 100).unwrap();
+            driver.close().unwrap();
+        }
+        let (f, _) = SDSSFileIO::<DirectFS>::open::<spec::DataBatchJournalV1>(file).unwrap();
+        let restored = new_model();
+        DataBatchRestoreDriver::new(f)
+            .unwrap()
+            .read_data_batch_into_model(&restored)
+            .unwrap();
+        assert_eq!(restored.primary_index().count(), 300);
+    })
+}
@@ -0,0 +1,68 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    crate::engine::storage::v1::durability::{DurabilityPolicy, SyncState},
+    std::{thread, time::Duration},
+};
+
+#[test]
+fn always_sync() {
+    let mut sync = SyncState::new(DurabilityPolicy::Always);
+    assert!(sync.commit());
+    sync.synced();
+    assert!(!sync.sync_due());
+    assert_eq!(DurabilityPolicy::Always.sync_interval(), None);
+}
+
+#[test]
+fn os_buffered_never_syncs() {
+    let mut sync = SyncState::new(DurabilityPolicy::OsBuffered);
+    assert!(!sync.commit());
+    thread::sleep(Duration::from_millis(10));
+    assert!(!sync.sync_due());
+    assert_eq!(DurabilityPolicy::OsBuffered.sync_interval(), None);
+}
+
+#[test]
+fn periodic_sync() {
+    let mut sync = SyncState::new(DurabilityPolicy::Periodic(50));
+    assert_eq!(
+        DurabilityPolicy::Periodic(50).sync_interval(),
+        Some(Duration::from_millis(50))
+    );
+    // not synced since the interval hasn't elapsed yet
+    assert!(!sync.commit());
+    assert!(!sync.sync_due());
+    thread::sleep(Duration::from_millis(60));
+    assert!(sync.sync_due());
+    sync.synced();
+    // nothing is pending
+    thread::sleep(Duration::from_millis(60));
+    assert!(!sync.sync_due());
+    // the interval elapsed since the last sync, so this commit is synced right away
+    assert!(sync.commit());
+}
@@ -0,0 +1,121 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    error::{ErrorKind, StorageError},
+    storage::v1::{
+        encryption::{self, EncryptionKey, FileBinding, KEY_SIZE},
+        spec::{self, SDSSStaticHeaderV1Compact},
+        SDSSFileIO,
+    },
+};
+
+fn new_file(name: &str, key: &EncryptionKey) -> FileBinding {
+    let (_, header) = SDSSFileIO::<super::VirtualFS>::create_with_format::<spec::TestFile>(
+        name,
+        encryption::header_format([0; 8], Some(key)),
+    )
+    .unwrap();
+    FileBinding::new(&header)
+}
+
+#[test]
+fn seal_and_open() {
+    let key = EncryptionKey::new([0x11; KEY_SIZE]);
+    let file = new_file("encryption_seal_and_open.db", &key);
+    let payload = b"sayan:pwd123456";
+    let sealed = key.seal(payload, &file, 64).unwrap();
+    assert!(!sealed
+        .windows(payload.len())
+        .any(|window| window == payload));
+    assert_eq!(key.open(&sealed, &file, 64).unwrap(), payload);
+    // every payload gets a fresh nonce
+    assert_ne!(key.seal(payload, &file, 64).unwrap(), sealed);
+    // a tampered payload or a different key is caught
+    let mut tampered = sealed.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let other = EncryptionKey::new([0x22; KEY_SIZE]);
+    for (key, sealed) in [
+        (&key, &tampered[..]),
+        (&other, &sealed[..]),
+        (&key, &sealed[..8]),
+    ] {
+        assert_eq!(
+            key.open(sealed, &file, 64).unwrap_err().kind(),
+            &ErrorKind::Storage(StorageError::EncryptedPayloadCorrupted)
+        );
+    }
+}
+
+#[test]
+fn sealed_payload_is_bound_to_its_place() {
+    let key = EncryptionKey::new([0x44; KEY_SIZE]);
+    let file = new_file("encryption_bound_a.db", &key);
+    let other_file = new_file("encryption_bound_b.db", &key);
+    let sealed = key.seal(b"sayan:pwd123456", &file, 128).unwrap();
+    assert_eq!(key.open(&sealed, &file, 128).unwrap(), b"sayan:pwd123456");
+    // moved to another offset, moved to another file, or with a header that was changed (here, the key ID)
+    let mut header = file.raw_header();
+    header[SDSSStaticHeaderV1Compact::SIZE - 1] ^= 1;
+    for (file, offset) in [
+        (file, 64),
+        (file, 129),
+        (other_file, 128),
+        (FileBinding::from_raw(header, file.id()), 128),
+        (FileBinding::from_raw(file.raw_header(), file.id() + 1), 128),
+    ] {
+        assert_eq!(
+            key.open(&sealed, &file, offset).unwrap_err().kind(),
+            &ErrorKind::Storage(StorageError::EncryptedPayloadCorrupted)
+        );
+    }
+}
+
+#[test]
+fn parse_key() {
+    let key = EncryptionKey::new([0xAB; KEY_SIZE]);
+    assert_ne!(key.id(), 0);
+    assert_ne!(key.id(), EncryptionKey::new([0xBA; KEY_SIZE]).id());
+    assert_eq!(EncryptionKey::parse(&[0xAB; KEY_SIZE]).unwrap(), key);
+    assert_eq!(
+        EncryptionKey::parse(format!("{}\n", "ab".repeat(KEY_SIZE)).as_bytes()).unwrap(),
+        key
+    );
+    for bad in ["ab".repeat(KEY_SIZE - 1), "zz".repeat(KEY_SIZE), "".into()] {
+        assert!(EncryptionKey::parse(bad.as_bytes()).is_none());
+    }
+}
+
+#[test]
+fn keyring() {
+    let key = EncryptionKey::new([0x33; KEY_SIZE]);
+    assert_eq!(
+        encryption::key(key.id()).unwrap_err().kind(),
+        &ErrorKind::Storage(StorageError::EncryptionKeyUnavailable)
+    );
+    encryption::register(key.clone());
+    assert_eq!(encryption::key(key.id()).unwrap(), key);
+}
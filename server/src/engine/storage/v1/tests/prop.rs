@@ -0,0 +1,578 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Property tests for the storage codecs: arbitrary dictionaries, fields, models and rows are round-tripped through
+//! [`inf`](super::super::inf) and the batch journal, and arbitrary (or corrupted) bytes are decoded to make sure that
+//! the decoders never panic
+
+use {
+    super::VirtualFS,
+    crate::{
+        engine::{
+            core::{
+                index::{DcFieldIndex, PrimaryIndexKey, Row},
+                model::{
+                    delta::{DataDelta, DataDeltaKind, DeltaVersion},
+                    keygen::KeyGenerator,
+                    Field, Layer, Model,
+                },
+            },
+            data::{
+                cell::Datacell,
+                dict::{DictCell, DictEntryGeneric, DictGeneric},
+                tag::{DataTag, FloatSpec, SIntSpec, TagSelector, UIntSpec},
+                uuid::Uuid,
+            },
+            idx::{IndexBaseSpec, IndexSTSeqCns, MTIndex, STIndex, STIndexSeq},
+            storage::{
+                v1::{
+                    batch_jrnl::{DataBatchPersistDriver, DataBatchRestoreDriver},
+                    inf::{dec, enc, map, obj},
+                    rw::{RawFSInterface, RawFileInterfaceExt, RawFileInterfaceRead, SDSSFileIO},
+                    spec::{self, BatchCompression, BatchEncoding, SDSSStaticHeaderV1Compact},
+                },
+                ChecksumKind,
+            },
+        },
+        util::test_utils::{self, Arbitrary, Gen},
+    },
+    crossbeam_epoch::pin,
+    rand::Rng,
+};
+
+/*
+    generators
+*/
+
+/// The types that a (non-list) cell can have
+const SCALAR_TAGS: [TagSelector; 16] = [
+    TagSelector::Bool,
+    TagSelector::UInt8,
+    TagSelector::UInt16,
+    TagSelector::UInt32,
+    TagSelector::UInt64,
+    TagSelector::SInt8,
+    TagSelector::SInt16,
+    TagSelector::SInt32,
+    TagSelector::SInt64,
+    TagSelector::Float32,
+    TagSelector::Float64,
+    TagSelector::Binary,
+    TagSelector::String,
+    TagSelector::Timestamp,
+    TagSelector::SInt128,
+    TagSelector::GeoPoint,
+];
+/// How deeply lists and dictionaries are nested
+const MAX_DEPTH: usize = 3;
+
+fn arbitrary_scalar(g: &mut Gen, tag: TagSelector) -> Datacell {
+    let full = tag.into_full();
+    let r = g.rng();
+    unsafe {
+        // UNSAFE(@ohsayan): every spec is created from a tag of its own class
+        match tag {
+            TagSelector::Bool => Datacell::new_bool(r.gen()),
+            TagSelector::UInt8 => Datacell::new_uint(r.gen::<u8>() as _, UIntSpec::from_full(full)),
+            TagSelector::UInt16 => {
+                Datacell::new_uint(r.gen::<u16>() as _, UIntSpec::from_full(full))
+            }
+            TagSelector::UInt32 => {
+                Datacell::new_uint(r.gen::<u32>() as _, UIntSpec::from_full(full))
+            }
+            TagSelector::UInt64 => Datacell::new_uint(r.gen(), UIntSpec::from_full(full)),
+            TagSelector::SInt8 => Datacell::new_sint(r.gen::<i8>() as _, SIntSpec::from_full(full)),
+            TagSelector::SInt16 => {
+                Datacell::new_sint(r.gen::<i16>() as _, SIntSpec::from_full(full))
+            }
+            TagSelector::SInt32 => {
+                Datacell::new_sint(r.gen::<i32>() as _, SIntSpec::from_full(full))
+            }
+            TagSelector::SInt64 => Datacell::new_sint(r.gen(), SIntSpec::from_full(full)),
+            // any float (including infinities and subnormals) but a NaN, since it isn't equal to itself
+            TagSelector::Float32 => {
+                let f = f32::from_bits(r.gen());
+                Datacell::new_float(
+                    if f.is_nan() { 0.0 } else { f as _ },
+                    FloatSpec::from_full(full),
+                )
+            }
+            TagSelector::Float64 => {
+                let f = f64::from_bits(r.gen());
+                Datacell::new_float(if f.is_nan() { 0.0 } else { f }, FloatSpec::from_full(full))
+            }
+            TagSelector::Binary => Datacell::new_bin(g.bytes(32).into()),
+            TagSelector::String => Datacell::new_str(g.string(16).into()),
+            TagSelector::Timestamp => Datacell::new_timestamp(r.gen()),
+            TagSelector::SInt128 => Datacell::new_sint128(r.gen()),
+            TagSelector::GeoPoint => {
+                Datacell::new_geopoint(r.gen_range(-90.0..=90.0), r.gen_range(-180.0..=180.0))
+            }
+            TagSelector::List | TagSelector::Dict => unreachable!(),
+        }
+    }
+}
+
+/// Generate a null or a cell of any type (including a list, with up to `depth` levels of nesting)
+fn arbitrary_cell(g: &mut Gen, depth: usize) -> Datacell {
+    match g.below(SCALAR_TAGS.len() + 2) {
+        n if n < SCALAR_TAGS.len() => arbitrary_scalar(g, SCALAR_TAGS[n]),
+        n if n == SCALAR_TAGS.len() && depth != 0 => {
+            let len = g.below(4);
+            Datacell::new_list((0..len).map(|_| arbitrary_cell(g, depth - 1)).collect())
+        }
+        _ => Datacell::null(),
+    }
+}
+
+/// Generate a key, which is often one of a few common keys (so that the same key shows up more than once)
+fn arbitrary_key(g: &mut Gen) -> Box<str> {
+    if g.bool() {
+        (*g.pick(&["a", "key", "a much longer key"])).into()
+    } else {
+        g.string(8).into()
+    }
+}
+
+fn arbitrary_dict(g: &mut Gen, depth: usize) -> DictGeneric {
+    let mut dict = DictGeneric::new();
+    for _ in 0..g.below(6) {
+        let entry = if depth != 0 && g.below(4) == 0 {
+            DictEntryGeneric::Map(arbitrary_dict(g, depth - 1))
+        } else {
+            DictEntryGeneric::Data(arbitrary_cell(g, depth))
+        };
+        dict.insert(arbitrary_key(g), entry);
+    }
+    dict
+}
+
+/// Generate a dict cell (with up to `depth` levels of nesting)
+fn arbitrary_dict_cell(g: &mut Gen, depth: usize) -> Datacell {
+    let mut dict = DictCell::new();
+    for _ in 0..g.below(6) {
+        let value = if depth != 0 && g.below(4) == 0 {
+            arbitrary_dict_cell(g, depth - 1)
+        } else {
+            arbitrary_cell(g, depth)
+        };
+        dict.insert(arbitrary_key(g), value);
+    }
+    Datacell::new_dict(dict)
+}
+
+/// Generate a value for a field with the given layers
+fn arbitrary_value(g: &mut Gen, layers: &[Layer]) -> Datacell {
+    match layers {
+        [layer] if layer.tag().tag_selector() == TagSelector::Dict => {
+            arbitrary_dict_cell(g, MAX_DEPTH)
+        }
+        [layer] => arbitrary_scalar(g, layer.tag().tag_selector()),
+        [_, rest @ ..] => {
+            let len = g.below(4);
+            Datacell::new_list((0..len).map(|_| arbitrary_value(g, rest)).collect())
+        }
+        [] => unreachable!(),
+    }
+}
+
+/// Generate the `i`th primary key of a model; every key is different
+fn arbitrary_pk(g: &mut Gen, tag: TagSelector, i: usize) -> Datacell {
+    match tag {
+        TagSelector::UInt64 => {
+            Datacell::new_uint_default(g.rng().gen::<u32>() as u64 * 1024 + i as u64)
+        }
+        TagSelector::SInt64 => {
+            Datacell::new_sint_default(g.rng().gen::<i32>() as i64 * 1024 + i as i64)
+        }
+        TagSelector::String => Datacell::new_str(format!("{i}:{}", g.string(8)).into()),
+        TagSelector::Binary => {
+            let mut pk = i.to_le_bytes().to_vec();
+            pk.extend(g.bytes(8));
+            Datacell::new_bin(pk.into())
+        }
+        _ => unreachable!(),
+    }
+}
+
+impl Arbitrary for Datacell {
+    fn arbitrary(g: &mut Gen) -> Self {
+        arbitrary_cell(g, MAX_DEPTH)
+    }
+}
+
+impl Arbitrary for DictGeneric {
+    fn arbitrary(g: &mut Gen) -> Self {
+        arbitrary_dict(g, MAX_DEPTH)
+    }
+}
+
+impl Arbitrary for Field {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let tag = match g.below(8) {
+            0 => TagSelector::Dict,
+            _ => *g.pick(&SCALAR_TAGS),
+        };
+        let mut layers: Vec<Layer> = (0..g.below(3)).map(|_| Layer::list()).collect();
+        layers.push(Layer::new(tag.into_full()));
+        let mut field = Field::new(layers.into_iter().collect(), g.bool());
+        // (these only stick if the field supports them)
+        match g.below(4) {
+            0 if tag != TagSelector::Dict => {
+                field.set_default(arbitrary_scalar(g, tag));
+            }
+            1 => {
+                field.set_generate(*g.pick(&[
+                    KeyGenerator::Increment,
+                    KeyGenerator::Snowflake,
+                    KeyGenerator::Uuid,
+                ]));
+            }
+            2 => {
+                field.set_fulltext();
+            }
+            _ => {}
+        }
+        field
+    }
+}
+
+impl Arbitrary for Model {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let p_tag = *g.pick(&[
+            TagSelector::UInt64,
+            TagSelector::SInt64,
+            TagSelector::String,
+            TagSelector::Binary,
+        ]);
+        let p_key = arbitrary_key(g);
+        let mut fields = IndexSTSeqCns::idx_init();
+        fields.st_insert(
+            p_key.clone(),
+            Field::new([Layer::new(p_tag.into_full())].into(), false),
+        );
+        for _ in 0..g.below(6) {
+            // (a field with the same name as an earlier one is dropped)
+            fields.st_insert(arbitrary_key(g), Field::arbitrary(g));
+        }
+        let model = Model::new_restore(Uuid::new(), p_key, p_tag.into_full(), fields);
+        if g.bool() {
+            model.into_ephemeral()
+        } else {
+            model
+        }
+    }
+}
+
+/// How a journal is written
+#[derive(Debug, Clone, Copy)]
+struct JournalOptions {
+    compression: BatchCompression,
+    checksum: ChecksumKind,
+    encoding: BatchEncoding,
+    event_checksums: bool,
+}
+
+impl Arbitrary for JournalOptions {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Self {
+            compression: *g.pick(&[BatchCompression::None, BatchCompression::Lz4]),
+            checksum: *g.pick(&[ChecksumKind::Crc64, ChecksumKind::Crc32c]),
+            encoding: *g.pick(&[BatchEncoding::Fixed, BatchEncoding::Varint]),
+            event_checksums: g.bool(),
+        }
+    }
+}
+
+/*
+    utils
+*/
+
+/// A row that was changed by [`arbitrary_deltas`]: its primary key and the values of the other fields (in the order
+/// of the model), unless it was deleted
+type ChangedRow = (Datacell, Option<Vec<Datacell>>);
+
+/// Returns the names of the fields of the model, except for the primary key (in the order of the model)
+fn data_fields(model: &Model) -> Vec<Box<str>> {
+    model
+        .fields()
+        .stseq_ord_key()
+        .map(|key| key.as_str())
+        .filter(|key| *key != model.p_key())
+        .map(Into::into)
+        .collect()
+}
+
+fn arbitrary_values(g: &mut Gen, model: &Model) -> Vec<Datacell> {
+    model
+        .fields()
+        .stseq_ord_kv()
+        .filter(|(key, _)| key.as_str() != model.p_key())
+        .map(|(_, field)| {
+            if field.is_nullable() && g.below(4) == 0 {
+                Datacell::null()
+            } else {
+                arbitrary_value(g, field.layers())
+            }
+        })
+        .collect()
+}
+
+/// Generate inserts, updates and deletes (in the order of their txn ids) for the rows of the model, returning the
+/// deltas and the rows as they are at the end
+fn arbitrary_deltas(g: &mut Gen, model: &Model) -> (Vec<DataDelta>, Vec<ChangedRow>) {
+    let p_tag = model.p_tag().tag_selector();
+    let mut deltas = vec![];
+    let mut rows: Vec<ChangedRow> = vec![];
+    for txn_id in 0..1 + g.below(24) as u64 {
+        let live: Vec<usize> = (0..rows.len()).filter(|i| rows[*i].1.is_some()).collect();
+        let (i, kind) = match if live.is_empty() { 0 } else { g.below(3) } {
+            0 => {
+                // a deleted key is never inserted again, so every insert is a new row
+                rows.push((arbitrary_pk(g, p_tag, rows.len()), None));
+                (rows.len() - 1, DataDeltaKind::Insert)
+            }
+            1 => (*g.pick(&live), DataDeltaKind::Update),
+            _ => (*g.pick(&live), DataDeltaKind::Delete),
+        };
+        let values = match kind {
+            DataDeltaKind::Delete => None,
+            _ => Some(arbitrary_values(g, model)),
+        };
+        let mut data = DcFieldIndex::default();
+        for (name, value) in model
+            .fields()
+            .stseq_ord_key()
+            .filter(|key| key.as_str() != model.p_key())
+            .zip(values.iter().flatten())
+        {
+            data.st_insert(
+                unsafe {
+                    // UNSAFE(@ohsayan): the model outlives the row
+                    name.clone()
+                },
+                value.clone(),
+            );
+        }
+        let row = Row::new(
+            PrimaryIndexKey::try_from_dc(rows[i].0.clone()).unwrap(),
+            data,
+            DeltaVersion::__new(0),
+            DeltaVersion::__new(txn_id),
+        );
+        deltas.push(DataDelta::new(DeltaVersion::__new(txn_id), row, kind));
+        rows[i].1 = values;
+    }
+    (deltas, rows)
+}
+
+/// Returns a model with the same layout as the given model (and no rows)
+fn copy_model(model: &Model) -> Model {
+    dec::dec_full::<obj::ModelLayoutRef>(&enc::enc_full::<obj::ModelLayoutRef>(model.into()))
+        .unwrap()
+}
+
+/// Write the deltas to a new journal (in batches of arbitrary sizes), returning what comes after the header
+fn write_journal(
+    g: &mut Gen,
+    fname: &str,
+    model: &Model,
+    deltas: Vec<DataDelta>,
+    options: JournalOptions,
+) -> Vec<u8> {
+    let _ = VirtualFS::fs_remove_file(fname);
+    let file = SDSSFileIO::<VirtualFS>::open_or_create_perm_rw::<spec::DataBatchJournalV1>(fname)
+        .unwrap()
+        .into_created()
+        .unwrap();
+    let mut persist_driver = DataBatchPersistDriver::new(file, true)
+        .unwrap()
+        .with_compression(options.compression)
+        .with_checksum(options.checksum)
+        .with_encoding(options.encoding);
+    if !options.event_checksums {
+        persist_driver = persist_driver.without_event_checksums();
+    }
+    let guard = pin();
+    let mut remaining = deltas.len();
+    for delta in deltas {
+        model.delta_state().append_new_data_delta(delta, &guard);
+    }
+    while remaining != 0 {
+        let batch_len = 1 + g.below(remaining);
+        persist_driver.write_new_batch(model, batch_len).unwrap();
+        remaining -= batch_len;
+    }
+    persist_driver.close().unwrap();
+    let mut f = VirtualFS::fs_fopen_rw(fname).unwrap();
+    let mut journal = vec![0; f.fext_file_length().unwrap() as usize];
+    f.fr_read_exact(&mut journal).unwrap();
+    VirtualFS::fs_remove_file(fname).unwrap();
+    journal.split_off(SDSSStaticHeaderV1Compact::SIZE)
+}
+
+fn restore_driver(journal: Vec<u8>, options: JournalOptions) -> DataBatchRestoreDriver<VirtualFS> {
+    DataBatchRestoreDriver::new_segment(journal, SDSSStaticHeaderV1Compact::SIZE as u64)
+        .with_checksum(options.checksum)
+        .with_encoding(options.encoding)
+}
+
+/// Flip, overwrite or cut off some of the bytes
+fn corrupt(g: &mut Gen, data: &mut Vec<u8>) {
+    for _ in 0..1 + g.below(4) {
+        if data.is_empty() {
+            return;
+        }
+        let i = g.below(data.len());
+        match g.below(3) {
+            0 => data[i] ^= 1 << g.below(8),
+            1 => data[i] = g.rng().gen(),
+            _ => data.truncate(i),
+        }
+    }
+}
+
+/// Try to decode the bytes as every kind of object (which can fail, but must never panic)
+fn decode_as_anything(data: &[u8]) {
+    let _ = dec::dec_dict_full::<map::GenericDictSpec>(data);
+    let _ = dec::dec_dict_full::<map::FieldMapSpec<IndexSTSeqCns<Box<str>, Field>>>(data);
+    let _ = dec::dec_full::<obj::LayerRef>(data);
+    let _ = dec::dec_full::<obj::FieldRef>(data);
+    let _ = dec::dec_full::<obj::ModelLayoutRef>(data);
+}
+
+/*
+    round trips
+*/
+
+#[test]
+fn dict_round_trip() {
+    test_utils::check(256, |dict: DictGeneric| {
+        let encoded = enc::enc_dict_full::<map::GenericDictSpec>(&dict);
+        let decoded = dec::dec_dict_full::<map::GenericDictSpec>(&encoded).unwrap();
+        assert_eq!(dict, decoded);
+    })
+}
+
+#[test]
+fn field_round_trip() {
+    test_utils::check(256, |field: Field| {
+        let encoded = enc::enc_full::<obj::FieldRef>(&field);
+        let decoded = dec::dec_full::<obj::FieldRef>(&encoded).unwrap();
+        assert_eq!(field, decoded);
+    })
+}
+
+#[test]
+fn model_round_trip() {
+    test_utils::check(256, |model: Model| {
+        assert_eq!(model, copy_model(&model));
+    })
+}
+
+#[test]
+fn batch_round_trip() {
+    test_utils::check_with(64, |g| {
+        let model = Model::arbitrary(g);
+        let options = JournalOptions::arbitrary(g);
+        let (deltas, rows) = arbitrary_deltas(g, &model);
+        let journal = write_journal(g, "prop_batch_round_trip.db-btlog", &model, deltas, options);
+        let restored = copy_model(&model);
+        let report = restore_driver(journal, options)
+            .read_data_batch_into_model(&restored)
+            .unwrap();
+        assert!(report.is_clean());
+        // every row is exactly as it was after the last change
+        let guard = pin();
+        let fields = data_fields(&model);
+        let index = restored.primary_index().__raw_index();
+        let live: Vec<_> = rows
+            .into_iter()
+            .filter_map(|(pk, values)| values.map(|values| (pk, values)))
+            .collect();
+        assert_eq!(index.mt_len(), live.len());
+        for (pk, values) in live {
+            let row = index
+                .mt_get(&PrimaryIndexKey::try_from_dc(pk).unwrap(), &guard)
+                .unwrap()
+                .read();
+            let restored_values: Vec<Datacell> = fields
+                .iter()
+                .map(|field| row.fields().st_get(field.as_ref()).unwrap().clone())
+                .collect();
+            assert_eq!(restored_values, values);
+        }
+    })
+}
+
+/*
+    decoding garbage
+*/
+
+#[test]
+fn dec_arbitrary_bytes() {
+    test_utils::check_with(1024, |g| decode_as_anything(&g.bytes(256)))
+}
+
+#[test]
+fn dec_corrupted_bytes() {
+    test_utils::check_with(1024, |g| {
+        let mut data = match g.below(3) {
+            0 => enc::enc_dict_full::<map::GenericDictSpec>(&DictGeneric::arbitrary(g)),
+            1 => enc::enc_full::<obj::FieldRef>(&Field::arbitrary(g)),
+            _ => enc::enc_full::<obj::ModelLayoutRef>((&Model::arbitrary(g)).into()),
+        };
+        corrupt(g, &mut data);
+        decode_as_anything(&data);
+    })
+}
+
+#[test]
+fn batch_dec_arbitrary_bytes() {
+    test_utils::check_with(1024, |g| {
+        let options = JournalOptions::arbitrary(g);
+        let _ = restore_driver(g.bytes(256), options).read_all_batches();
+    })
+}
+
+#[test]
+fn batch_dec_corrupted_bytes() {
+    test_utils::check_with(256, |g| {
+        let model = Model::arbitrary(g);
+        let options = JournalOptions::arbitrary(g);
+        let (deltas, _) = arbitrary_deltas(g, &model);
+        let mut journal = write_journal(
+            g,
+            "prop_batch_dec_corrupted.db-btlog",
+            &model,
+            deltas,
+            options,
+        );
+        corrupt(g, &mut journal);
+        let _ = restore_driver(journal.clone(), options).read_all_batches();
+        let _ = restore_driver(journal, options).read_data_batch_into_model(&copy_model(&model));
+    })
+}
@@ -0,0 +1,113 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::engine::{
+    core::{
+        model::{Field, Layer, Model},
+        space::Space,
+        GlobalNS,
+    },
+    data::{tag::TagSelector, uuid::Uuid},
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
+    ql::{ast::parse_ast_node_full, ddl::crt::CreateSpace, tests::lex_insecure},
+    storage::v1::{
+        data_batch::{self, RecoveryMode},
+        memfs::VirtualFS,
+        repair,
+        rw::{RawFSInterface, RawFileInterfaceExt, RawFileInterfaceWrite},
+        spec::BatchCompression,
+    },
+};
+
+/// Append some garbage to the file (like a torn write would), returning the length of the file before we did
+fn append_garbage(path: &str) -> u64 {
+    let mut f = VirtualFS::fs_fopen_rw(path).unwrap();
+    let len = f.fext_file_length().unwrap();
+    f.fext_seek_ahead_from_start_by(len).unwrap();
+    f.fw_write_all(&[0xAB; 64]).unwrap();
+    len
+}
+
+#[test]
+fn repair_gns() {
+    let log_name = "repair_gns.global.db-tlog";
+    {
+        let global = TestGlobal::new_with_vfs_driver(log_name);
+        let stmt = lex_insecure(b"create space myspace").unwrap();
+        let stmt = parse_ast_node_full::<CreateSpace>(&stmt[2..]).unwrap();
+        Space::transactional_exec_create(&global, stmt).unwrap();
+    }
+    let len = append_garbage(log_name);
+    // check
+    let check = repair::check_gns::<VirtualFS>(log_name, &GlobalNS::empty(), false).unwrap();
+    assert!(!check.is_loadable());
+    let (consistent_len, _) = check.unreadable().unwrap();
+    // the close event is dropped along with the garbage that follows it
+    assert!(*consistent_len < len);
+    // truncate
+    let check = repair::check_gns::<VirtualFS>(log_name, &GlobalNS::empty(), true).unwrap();
+    assert!(check.is_loadable());
+    let check = repair::check_gns::<VirtualFS>(log_name, &GlobalNS::empty(), false).unwrap();
+    assert!(check.unreadable().is_none());
+    // and everything up to the corruption is still there
+    let global = TestGlobal::new_with_vfs_driver(log_name);
+    assert!(global.namespace().idx().read().contains_key("myspace"));
+}
+
+#[test]
+fn repair_data_batch() {
+    let path = "repair_data_batch.db-btlog";
+    let new_model = || {
+        Model::new_restore(
+            Uuid::new(),
+            "username".into(),
+            TagSelector::String.into_full(),
+            into_dict!("username" => Field::new([Layer::str()].into(), false)),
+        )
+    };
+    data_batch::create::<VirtualFS>(path, BatchCompression::None, None)
+        .unwrap()
+        .close()
+        .unwrap();
+    let len = append_garbage(path);
+    // check
+    let check = repair::check_data_batch::<VirtualFS>(path, false).unwrap();
+    assert!(!check.is_loadable());
+    assert!(check.corrupted_events().is_empty());
+    let (consistent_len, _) = check.unreadable().unwrap();
+    // the close marker is dropped along with the garbage that follows it
+    assert_eq!(*consistent_len, len - 1);
+    assert!(data_batch::reinit::<VirtualFS>(path, &new_model(), RecoveryMode::Strict).is_err());
+    // truncate
+    let check = repair::check_data_batch::<VirtualFS>(path, true).unwrap();
+    assert!(check.is_loadable());
+    let check = repair::check_data_batch::<VirtualFS>(path, false).unwrap();
+    assert!(check.unreadable().is_none());
+    let (persist_driver, report) =
+        data_batch::reinit::<VirtualFS>(path, &new_model(), RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    persist_driver.close().unwrap();
+}
@@ -24,9 +24,16 @@
  *
 */
 
-use crate::engine::storage::v1::{
-    rw::{FileOpen, SDSSFileIO},
-    spec,
+use crate::engine::{
+    error::{ErrorKind, RuntimeResult, StorageError},
+    storage::{
+        v1::{
+            encryption::{self, EncryptionKey},
+            rw::{FileOpen, RawFSInterface, RawFileInterfaceWrite, SDSSFileIO},
+            spec::{self, FileSpec, FormatFeature, SDSSStaticHeaderV1Compact},
+        },
+        versions,
+    },
 };
 
 #[test]
@@ -50,3 +57,72 @@ fn create_delete() {
         _ => panic!(),
     };
 }
+
+#[test]
+fn header_format_features() {
+    let key = EncryptionKey::new([0x7E; encryption::KEY_SIZE]);
+    encryption::register(key.clone());
+    let plain = "header_features_plain.db-tlog";
+    let encrypted = "header_features_encrypted.db-tlog";
+    drop(
+        SDSSFileIO::<super::VirtualFS>::create_with_format::<spec::TestFile>(plain, [0; 8])
+            .unwrap(),
+    );
+    drop(
+        SDSSFileIO::<super::VirtualFS>::create_with_format::<spec::TestFile>(
+            encrypted,
+            encryption::header_format([0; 8], Some(&key)),
+        )
+        .unwrap(),
+    );
+    let (_, header) = SDSSFileIO::<super::VirtualFS>::open::<spec::TestFile>(plain).unwrap();
+    assert_eq!(header.format_features(), 0);
+    let (_, header) = SDSSFileIO::<super::VirtualFS>::open::<spec::TestFile>(encrypted).unwrap();
+    assert!(header.uses_feature(FormatFeature::ENCRYPTED));
+    assert!(!header.uses_feature(FormatFeature::BATCH_LZ4));
+}
+
+#[test]
+fn header_compatibility() {
+    fn open_with(fname: &str, modify: impl FnOnce(&mut [u8; 64])) -> RuntimeResult<()> {
+        let mut header = SDSSStaticHeaderV1Compact::_encode_auto(
+            spec::TestFile::ENCODE_DATA.0,
+            spec::TestFile::ENCODE_DATA.1,
+            spec::TestFile::ENCODE_DATA.2,
+        );
+        modify(&mut header);
+        let mut f = super::VirtualFS::fs_fcreate_rw(fname).unwrap();
+        f.fw_write_all(&header).unwrap();
+        drop(f);
+        SDSSFileIO::<super::VirtualFS>::open::<spec::TestFile>(fname).map(|_| ())
+    }
+    // a file from an older server
+    open_with("header_compat_older_server.db-tlog", |header| {
+        header[16..24].copy_from_slice(&0u64.to_le_bytes())
+    })
+    .unwrap();
+    // a file from a newer server that doesn't use any new features
+    open_with("header_compat_newer_server.db-tlog", |header| {
+        header[16..24].copy_from_slice(&u64::MAX.to_le_bytes())
+    })
+    .unwrap();
+    // a file from a newer server that uses a feature that we don't know about
+    assert_eq!(
+        open_with("header_compat_newer_feature.db-tlog", |header| {
+            header[48..56].copy_from_slice(&(1u64 << 40).to_le_bytes())
+        })
+        .unwrap_err()
+        .kind(),
+        &ErrorKind::Storage(StorageError::HeaderDecodeUnsupportedFeature(1 << 40))
+    );
+    // a file with a newer header layout
+    assert_eq!(
+        open_with("header_compat_newer_header.db-tlog", |header| {
+            header[8..16]
+                .copy_from_slice(&versions::HeaderVersion::__new(u64::MAX).little_endian_u64())
+        })
+        .unwrap_err()
+        .kind(),
+        &ErrorKind::Storage(StorageError::HeaderDecodeVersionMismatch)
+    );
+}
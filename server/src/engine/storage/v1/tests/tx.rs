@@ -29,8 +29,9 @@ use {
         engine::{
             error::{RuntimeResult, StorageError},
             storage::v1::{
+                encryption::{self, EncryptionKey},
                 journal::{self, JournalAdapter, JournalWriter},
-                spec,
+                spec, RawFSInterface, RawFileInterfaceExt, RawFileInterfaceRead,
             },
         },
         util,
@@ -199,3 +200,38 @@ fn oneboot_mod_twoboot_mod_thirdboot_read() {
             .as_ref()
     );
 }
+
+#[test]
+fn encrypted_journal() {
+    let log_name = "encrypted_txn.db-tlog";
+    let key = EncryptionKey::new([0x3C; encryption::KEY_SIZE]);
+    encryption::register(key.clone());
+    let db1 = Database::new();
+    let mut log = journal::create_journal::<DatabaseTxnAdapter, super::VirtualFS, spec::TestFile>(
+        log_name,
+        Some(&key),
+    )
+    .unwrap();
+    for i in 0..10 {
+        db1.txn_set(i, 0xA0 + i as u8, &mut log).unwrap();
+    }
+    log.close().unwrap();
+    // no event is written in the clear ([opcode][index][new value])
+    let mut f = super::VirtualFS::fs_fopen_rw(log_name).unwrap();
+    let mut data = vec![0; f.fext_file_length().unwrap() as usize];
+    f.fr_read_exact(&mut data).unwrap();
+    f.fext_seek_ahead_from_start_by(0).unwrap();
+    let mut event = vec![1];
+    event.extend(9u64.to_le_bytes());
+    event.push(0xA9);
+    assert!(!data.windows(event.len()).any(|window| window == event));
+    // restore, and keep writing (encrypted) events after the reopen
+    let db2 = Database::new();
+    let mut log = open_log(log_name, &db2).unwrap();
+    assert_eq!(db1.copy_data(), db2.copy_data());
+    db2.txn_set(0, 0xFF, &mut log).unwrap();
+    log.close().unwrap();
+    let db3 = Database::new();
+    open_log(log_name, &db3).unwrap().close().unwrap();
+    assert_eq!(db2.copy_data(), db3.copy_data());
+}
@@ -0,0 +1,164 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use {
+    crate::{
+        engine::{
+            core::{
+                index::{PrimaryIndexKey, Row},
+                model::{
+                    delta::{DataDelta, DataDeltaKind, DeltaVersion},
+                    Field, Layer, Model,
+                },
+            },
+            data::{cell::Datacell, tag::TagSelector, uuid::Uuid},
+            storage::v1::{
+                batch_jrnl::{DataBatchPersistDriver, DataBatchRestoreDriver},
+                rw::{
+                    FileOpen, RawFSInterface, RawFileInterface, RawFileInterfaceBufferedWriter,
+                    RawFileInterfaceExt, RawFileInterfaceWrite, SDSSFileIO,
+                },
+                spec,
+                uring::UringFS,
+                LocalFS,
+            },
+        },
+        util::test_utils,
+    },
+    crossbeam_epoch::pin,
+};
+
+#[test]
+fn buffered_writes() {
+    for use_io_uring in [true, false] {
+        test_utils::with_files(["uring_buffered_writes.bin"], |[file]| {
+            let f = UringFS::fs_fopen_rw(file).unwrap();
+            let mut w = f.into_buffered_writer().unwrap();
+            if !use_io_uring {
+                w.disable_io_uring();
+            }
+            // a small write stays buffered, but the cursor still moves
+            w.fw_write_all(b"hello").unwrap();
+            assert_eq!(w.fext_cursor().unwrap(), 5);
+            // this is more than we buffer, so it's submitted right away
+            let big: Vec<u8> = (0..20_000u32).map(|i| i as u8).collect();
+            w.fw_write_all(&big).unwrap();
+            w.sync_write_cache().unwrap();
+            assert_eq!(w.fext_file_length().unwrap(), 20_005);
+            // overwrite the start
+            w.fext_seek_ahead_from_start_by(0).unwrap();
+            w.fw_write_all(b"HELLO").unwrap();
+            w.flush_write_cache().unwrap();
+            // and downgrading leaves the file at the end of what we wrote
+            let mut f = UringFile::downgrade_writer(w).unwrap();
+            assert_eq!(f.fext_cursor().unwrap(), 5);
+            let mut expected = b"HELLO".to_vec();
+            expected.extend(big);
+            assert_eq!(std::fs::read(file).unwrap(), expected);
+        })
+    }
+}
+
+#[test]
+fn drop_flushes_buffer() {
+    for use_io_uring in [true, false] {
+        test_utils::with_files(["uring_drop_flushes_buffer.bin"], |[file]| {
+            let f = UringFS::fs_fopen_rw(file).unwrap();
+            let mut w = f.into_buffered_writer().unwrap();
+            if !use_io_uring {
+                w.disable_io_uring();
+            }
+            w.fw_write_all(b"hello").unwrap();
+            drop(w);
+            assert_eq!(std::fs::read(file).unwrap(), b"hello");
+        })
+    }
+}
+
+type UringFile = <UringFS as RawFSInterface>::File;
+
+fn new_model() -> Model {
+    Model::new_restore(
+        Uuid::new(),
+        "username".into(),
+        TagSelector::String.into_full(),
+        into_dict!(
+            "username" => Field::new([Layer::str()].into(), false),
+            "password" => Field::new([Layer::bin()].into(), false)
+        ),
+    )
+}
+
+fn insert(mdl: &Model, txn_id: u64, username: &str) {
+    let row = Row::new(
+        PrimaryIndexKey::try_from_dc(Datacell::new_str(username.into())).unwrap(),
+        into_dict!("password" => Datacell::new_bin(b"password".to_vec().into_boxed_slice())),
+        DeltaVersion::__new(0),
+        DeltaVersion::__new(txn_id),
+    );
+    mdl.delta_state().append_new_data_delta(
+        DataDelta::new(DeltaVersion::__new(txn_id), row, DataDeltaKind::Insert),
+        &pin(),
+    );
+}
+
+#[test]
+fn batch_journal_roundtrip() {
+    test_utils::with_files(["uring_batch_journal_roundtrip.db-btlog"], |[file]| {
+        let mdl = new_model();
+        // write batches (across reopens) through io_uring
+        let mut txn_id = 0;
+        for round in 0..3 {
+            let mut driver = match SDSSFileIO::<UringFS>::open_or_create_perm_rw::<
+                spec::DataBatchJournalV1,
+            >(file)
+            .unwrap()
+            {
+                FileOpen::Created(f) => DataBatchPersistDriver::new(f, true),
+                FileOpen::Existing((f, _)) => {
+                    let mut restore = DataBatchRestoreDriver::new(f).unwrap();
+                    restore.read_data_batch_into_model(&new_model()).unwrap();
+                    DataBatchPersistDriver::new(restore.into_file().unwrap(), false)
+                }
+            }
+            .unwrap();
+            for i in 0..100 {
+                insert(&mdl, txn_id, &format!("user-{round}-{i}"));
+                txn_id += 1;
+            }
+            driver.write_new_batch(&mdl, 100).unwrap();
+            driver.close().unwrap();
+        }
+        // and read them back using plain file I/O
+        let (f, _) = SDSSFileIO::<LocalFS>::open::<spec::DataBatchJournalV1>(file).unwrap();
+        let restored = new_model();
+        DataBatchRestoreDriver::new(f)
+            .unwrap()
+            .read_data_batch_into_model(&restored)
+            .unwrap();
+        assert_eq!(restored.primary_index().count(), 300);
+    })
+}
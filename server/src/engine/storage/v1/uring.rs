@@ -0,0 +1,384 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! An io_uring backend for batch journals
+//!
+//! Batch journals only ever append to the end of the file and sync every so often, so the writer buffers the appends
+//! and submits them as a single write, which is linked to the fsync when the journal is synced (that way a sync only
+//! needs one trip into the kernel). Everything else (opening files, reading and so on) just uses [`LocalFS`]. If the
+//! kernel doesn't support io_uring (or the operations that we need), we quietly fall back to standard file I/O
+
+use {
+    super::rw::{
+        self, FileOpen, LocalFS, LocalFSFile, RawFSInterface, RawFileInterface,
+        RawFileInterfaceBufferedWriter, RawFileInterfaceExt, RawFileInterfaceWriteExt,
+    },
+    crate::engine::error::RuntimeResult,
+    io_uring::{opcode, squeue, types, EnterFlags, IoUring, Probe},
+    std::{
+        fs::File,
+        io::{self, BufReader, Read, Seek, SeekFrom, Write},
+        os::{fd::AsRawFd, unix::fs::FileExt},
+        sync::Once,
+    },
+};
+
+/// the number of buffered bytes after which we submit the appends (without waiting for a flush)
+const WRITE_BUFFER_SIZE: usize = 8 * 1024;
+/// we never have more than a write and an fsync in flight
+const RING_ENTRIES: u32 = 2;
+const OP_WRITE: u64 = 0;
+const OP_FSYNC: u64 = 1;
+
+/// The local file system, but batch journals are written using io_uring
+#[derive(Debug)]
+pub struct UringFS;
+
+impl RawFSInterface for UringFS {
+    type File = UringFile;
+    fn fs_remove_file(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_remove_file(fpath)
+    }
+    fn fs_rename_file(from: &str, to: &str) -> RuntimeResult<()> {
+        LocalFS::fs_rename_file(from, to)
+    }
+    fn fs_copy_file(from: &str, to: &str) -> RuntimeResult<()> {
+        LocalFS::fs_copy_file(from, to)
+    }
+    fn fs_create_dir(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_create_dir(fpath)
+    }
+    fn fs_create_dir_all(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_create_dir_all(fpath)
+    }
+    fn fs_delete_dir(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_delete_dir(fpath)
+    }
+    fn fs_delete_dir_all(fpath: &str) -> RuntimeResult<()> {
+        LocalFS::fs_delete_dir_all(fpath)
+    }
+    fn fs_fopen_or_create_rw(fpath: &str) -> RuntimeResult<FileOpen<Self::File>> {
+        Ok(match LocalFS::fs_fopen_or_create_rw(fpath)? {
+            FileOpen::Created(f) => FileOpen::Created(UringFile(f)),
+            FileOpen::Existing(f) => FileOpen::Existing(UringFile(f)),
+        })
+    }
+    fn fs_fopen_rw(fpath: &str) -> RuntimeResult<Self::File> {
+        LocalFS::fs_fopen_rw(fpath).map(UringFile)
+    }
+    fn fs_fcreate_rw(fpath: &str) -> RuntimeResult<Self::File> {
+        LocalFS::fs_fcreate_rw(fpath).map(UringFile)
+    }
+}
+
+/// A file on the local file system, which is written to using io_uring once it's upgraded to a buffered writer
+#[derive(Debug)]
+pub struct UringFile(File);
+
+impl Read for UringFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for UringFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl LocalFSFile for UringFile {
+    fn file_mut(&mut self) -> &mut File {
+        &mut self.0
+    }
+    fn file(&self) -> &File {
+        &self.0
+    }
+}
+
+impl RawFileInterfaceWriteExt for UringFile {
+    fn fwext_fsync_all(&mut self) -> RuntimeResult<()> {
+        self.0.fwext_fsync_all()
+    }
+    fn fwext_truncate_to(&mut self, to: u64) -> RuntimeResult<()> {
+        self.0.fwext_truncate_to(to)
+    }
+}
+
+impl RawFileInterface for UringFile {
+    type BufReader = BufReader<File>;
+    type BufWriter = UringWriter;
+    fn into_buffered_reader(self) -> RuntimeResult<Self::BufReader> {
+        Ok(BufReader::new(self.0))
+    }
+    fn downgrade_reader(r: Self::BufReader) -> RuntimeResult<Self> {
+        Ok(Self(r.into_inner()))
+    }
+    fn into_buffered_writer(self) -> RuntimeResult<Self::BufWriter> {
+        UringWriter::new(self.0)
+    }
+    fn downgrade_writer(w: Self::BufWriter) -> RuntimeResult<Self> {
+        w.into_inner().map(Self)
+    }
+}
+
+/// A buffered writer that appends to a file using io_uring (or positional writes, if io_uring isn't available)
+///
+/// Like [`std::io::BufWriter`], buffered data is written out on drop, but errors are ignored at that point, so the
+/// writer should be flushed (or downgraded) before it's dropped
+pub struct UringWriter {
+    /// (this is only taken when the writer is downgraded)
+    f: Option<File>,
+    /// this is dropped if the ring runs into an error, after which we use standard file I/O
+    ring: Option<IoUring>,
+    buf: Vec<u8>,
+    /// the offset in the file at which the buffered data is written
+    pos: u64,
+}
+
+impl UringWriter {
+    fn new(mut f: File) -> RuntimeResult<Self> {
+        let pos = f.stream_position()?;
+        Ok(Self {
+            f: Some(f),
+            ring: new_ring(),
+            buf: Vec::with_capacity(WRITE_BUFFER_SIZE),
+            pos,
+        })
+    }
+    fn into_inner(mut self) -> RuntimeResult<File> {
+        self.submit(false)?;
+        let mut f = self.f.take().unwrap();
+        f.seek(SeekFrom::Start(self.pos))?;
+        Ok(f)
+    }
+    fn file(&self) -> &File {
+        self.f.as_ref().unwrap()
+    }
+    #[cfg(test)]
+    /// Use standard file I/O, just like we do when io_uring isn't available
+    pub fn disable_io_uring(&mut self) {
+        self.ring = None;
+    }
+    /// Write out all buffered data (and sync the file, if `sync` is set)
+    fn submit(&mut self, sync: bool) -> io::Result<()> {
+        let f = self.f.as_ref().unwrap();
+        match self.ring.as_mut() {
+            Some(ring) => {
+                if let Err(e) = submit_uring(ring, f, &self.buf, self.pos, sync) {
+                    // the ring might still have entries queued up that point into the buffer (which is about to
+                    // change), so it must never be submitted to again
+                    self.ring = None;
+                    return Err(e);
+                }
+            }
+            None => {
+                f.write_all_at(&self.buf, self.pos)?;
+                if sync {
+                    f.sync_all()?;
+                }
+            }
+        }
+        self.pos += self.buf.len() as u64;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+/// Set up a ring, if the kernel supports io_uring along with the operations that we need
+fn new_ring() -> Option<IoUring> {
+    static WARN_FALLBACK: Once = Once::new();
+    let ring = IoUring::new(RING_ENTRIES).and_then(|ring| {
+        let mut probe = Probe::new();
+        ring.submitter().register_probe(&mut probe)?;
+        if probe.is_supported(opcode::Write::CODE) & probe.is_supported(opcode::Fsync::CODE) {
+            Ok(ring)
+        } else {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+    });
+    match ring {
+        Ok(ring) => Some(ring),
+        Err(e) => {
+            WARN_FALLBACK.call_once(|| {
+                warn!("io_uring is not available ({e}). falling back to standard file I/O")
+            });
+            None
+        }
+    }
+}
+
+/// Write `buf` to `f` at `pos` (and sync it if `sync` is set) using the ring
+///
+/// The write and the fsync are submitted together, with the fsync linked to the write. If the write is short, the
+/// kernel cancels the fsync, so we just go again with what's left
+fn submit_uring(ring: &mut IoUring, f: &File, buf: &[u8], pos: u64, sync: bool) -> io::Result<()> {
+    let fd = types::Fd(f.as_raw_fd());
+    let mut written = 0;
+    let mut synced = !sync;
+    while (written < buf.len()) | !synced {
+        let remaining = &buf[written..];
+        let mut in_flight = 0;
+        {
+            let mut sq = ring.submission();
+            if !remaining.is_empty() {
+                let mut write = opcode::Write::new(
+                    fd,
+                    remaining.as_ptr(),
+                    remaining.len().min(u32::MAX as usize) as u32,
+                )
+                .offset(pos + written as u64)
+                .build()
+                .user_data(OP_WRITE);
+                if sync {
+                    write = write.flags(squeue::Flags::IO_LINK);
+                }
+                unsafe {
+                    // UNSAFE(@ohsayan): the buffer outlives the write since we wait for all completions below
+                    sq.push(&write)
+                }
+                .map_err(|_| io::Error::other("io_uring queue is full"))?;
+                in_flight += 1;
+            }
+            if sync {
+                let fsync = opcode::Fsync::new(fd).build().user_data(OP_FSYNC);
+                unsafe {
+                    // UNSAFE(@ohsayan): no buffers are involved
+                    sq.push(&fsync)
+                }
+                .map_err(|_| io::Error::other("io_uring queue is full"))?;
+                in_flight += 1;
+            }
+        }
+        loop {
+            match ring.submit_and_wait(in_flight) {
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    // whatever made it to the kernel still uses the buffer, so wait for it (and drain the completion
+                    // queue) before we bail out
+                    let submitted = in_flight - ring.submission().len();
+                    drain_completions(ring, submitted);
+                    return Err(e);
+                }
+            }
+        }
+        // drain every completion before we bail out, so that nothing is left over for the next submission
+        let mut error = None;
+        for cqe in ring.completion() {
+            let ret = cqe.result();
+            match cqe.user_data() {
+                OP_WRITE if ret < 0 => error = Some(io::Error::from_raw_os_error(-ret)),
+                OP_WRITE if ret == 0 => error = Some(io::ErrorKind::WriteZero.into()),
+                OP_WRITE => written += ret as usize,
+                // the write was short (or failed), so the linked fsync was cancelled
+                _ if ret == -libc::ECANCELED => {}
+                _ if ret < 0 => error = Some(io::Error::from_raw_os_error(-ret)),
+                _ => synced = true,
+            }
+        }
+        if let Some(e) = error {
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Wait for `count` completions (without submitting anything), and then drain the completion queue
+fn drain_completions(ring: &mut IoUring, count: usize) {
+    let mut completed = 0;
+    loop {
+        completed += ring.completion().count();
+        if completed >= count {
+            break;
+        }
+        let waited = unsafe {
+            // UNSAFE(@ohsayan): we don't submit anything and don't pass any arguments
+            ring.submitter().enter::<libc::sigset_t>(
+                0,
+                (count - completed) as u32,
+                EnterFlags::GETEVENTS.bits(),
+                None,
+            )
+        };
+        match waited {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            // we can't wait on the ring at all, so there's nothing else that we can do
+            Err(_) => break,
+        }
+    }
+}
+
+impl Write for UringWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        if self.buf.len() >= WRITE_BUFFER_SIZE {
+            self.submit(false)?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.submit(false)
+    }
+}
+
+impl RawFileInterfaceBufferedWriter for UringWriter {
+    fn flush_write_cache(&mut self) -> RuntimeResult<()> {
+        Ok(self.submit(false)?)
+    }
+    fn sync_write_cache(&mut self) -> RuntimeResult<()> {
+        Ok(self.submit(true)?)
+    }
+    fn preallocate(&mut self, len: u64) -> RuntimeResult<()> {
+        rw::fallocate_keep_size(self.file(), len)
+    }
+}
+
+impl RawFileInterfaceExt for UringWriter {
+    fn fext_file_length(&self) -> RuntimeResult<u64> {
+        Ok(self.file().metadata()?.len())
+    }
+    fn fext_cursor(&mut self) -> RuntimeResult<u64> {
+        Ok(self.pos + self.buf.len() as u64)
+    }
+    fn fext_seek_ahead_from_start_by(&mut self, by: u64) -> RuntimeResult<()> {
+        self.submit(false)?;
+        self.pos = by;
+        Ok(())
+    }
+}
+
+impl Drop for UringWriter {
+    fn drop(&mut self) {
+        if self.f.is_some() && !self.buf.is_empty() {
+            let _ = self.submit(false);
+        }
+    }
+}
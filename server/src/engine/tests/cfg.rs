@@ -25,9 +25,17 @@
 */
 
 use crate::{
-    engine::config::{
-        self, AuthDriver, CLIConfigParseReturn, ConfigAuth, ConfigEndpoint, ConfigEndpointTcp,
-        ConfigEndpointTls, ConfigMode, ConfigReturn, ConfigSystem, Configuration, ParsedRawArgs,
+    engine::{
+        config::{
+            self, AuthDriver, CLIConfigParseReturn, ConfigAuth, ConfigEndpoint, ConfigEndpointTcp,
+            ConfigEndpointTls, ConfigMode, ConfigRecoverUntil, ConfigRecovery, ConfigRepairMode,
+            ConfigReplicaOf, ConfigResp, ConfigReturn, ConfigSystem, Configuration, ParsedRawArgs,
+        },
+        fractal::{self, BackpressurePolicy},
+        storage::{
+            engine::IoBackend,
+            v1::{data_batch::RecoveryMode, durability::DurabilityPolicy, spec::BatchCompression},
+        },
     },
     util::test_utils::with_files,
 };
@@ -119,7 +127,8 @@ fn parse_validate_cli_args() {
                             ConfigEndpointTcp::new("127.0.0.2".into(), 2004),
                             "".into(),
                             "".into(),
-                            "".into()
+                            "".into(),
+                            None
                         )
                     ),
                     ConfigMode::Dev,
@@ -131,6 +140,49 @@ fn parse_validate_cli_args() {
     );
 }
 #[test]
+fn parse_validate_cli_args_tls_client_ca() {
+    with_files(
+        [
+            "__cli_args_test_client_ca_private.key",
+            "__cli_args_test_client_ca_cert.pem",
+            "__cli_args_test_client_ca_passphrase.key",
+            "__cli_args_test_client_ca.pem",
+        ],
+        |[pkey, cert, pass, client_ca]| {
+            let payload = format!(
+                "skyd --mode=dev \
+                --endpoint tls@127.0.0.1:2004 \
+                --tlskey {pkey} \
+                --tlscert {cert} \
+                --tls-passphrase {pass} \
+                --tls-client-ca {client_ca} \
+                --auth-plugin pwd \
+                --auth-root-password password12345678
+                "
+            );
+            let cfg = extract_cli_args(&payload);
+            let ret = config::apply_and_validate::<config::CSCommandLine>(cfg)
+                .unwrap()
+                .into_config();
+            assert_eq!(
+                ret,
+                Configuration::new(
+                    ConfigEndpoint::Secure(ConfigEndpointTls::new(
+                        ConfigEndpointTcp::new("127.0.0.1".into(), 2004),
+                        "".into(),
+                        "".into(),
+                        "".into(),
+                        Some("".into())
+                    )),
+                    ConfigMode::Dev,
+                    ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+                    ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+                )
+            )
+        },
+    );
+}
+#[test]
 fn parse_validate_cli_args_help_and_version() {
     let pl1 = "skyd --help";
     let pl2 = "skyd --version";
@@ -154,6 +206,651 @@ fn parse_validate_cli_args_help_and_version() {
         ))
     );
 }
+#[test]
+fn parse_validate_cli_args_restore() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd --auth-root-password password12345678 --restore mybackup"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap();
+    assert_eq!(
+        ret,
+        ConfigReturn::Recover(
+            Configuration::new(
+                ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+                ConfigMode::Dev,
+                ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+                ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+            ),
+            ConfigRecovery {
+                restore: Some("mybackup".into()),
+                until: vec![],
+            }
+        )
+    );
+}
+#[test]
+fn parse_validate_cli_args_batch_compression() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd --auth-root-password password12345678 \
+        --batch-compression lz4"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW)
+                .with_batch_compression(BatchCompression::Lz4),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+    );
+}
+#[test]
+fn parse_validate_cli_args_batch_compression_bad() {
+    for compression in ["zip", "lz4 --batch-compression none"] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 --batch-compression {compression}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        assert!(config::check_configuration().is_err());
+    }
+}
+#[test]
+fn parse_validate_cli_args_storage_engine_bad() {
+    for engine in ["lsm", "journal --storage-engine journal"] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 --storage-engine {engine}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        assert!(config::check_configuration().is_err());
+    }
+}
+#[test]
+fn parse_validate_cli_args_io_backend() {
+    for (backend, expected) in [
+        ("std", IoBackend::Std),
+        ("io_uring", IoBackend::IoUring),
+        ("direct", IoBackend::Direct),
+    ] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 --io-backend {backend}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        let ret = config::check_configuration().unwrap().into_config();
+        assert_eq!(ret.system.io_backend, expected);
+    }
+    for backend in ["aio", "std --io-backend io_uring"] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 --io-backend {backend}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        assert!(config::check_configuration().is_err());
+    }
+}
+#[test]
+fn parse_validate_cli_args_io_threads() {
+    config::set_cli_src(
+        "skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 --io-threads 4"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(ret.system.io_threads, Some(4));
+    for threads in ["0", "-1", "many", "4 --io-threads 8"] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 --io-threads {threads}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        assert!(config::check_configuration().is_err());
+    }
+}
+#[test]
+fn parse_validate_cli_args_recovery_mode() {
+    config::set_cli_src(
+        "skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(ret.system.recovery_mode, RecoveryMode::Strict);
+    for (mode, expected) in [
+        ("strict", RecoveryMode::Strict),
+        ("truncate", RecoveryMode::TruncateTail),
+    ] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 --recovery-mode {mode}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        let ret = config::check_configuration().unwrap().into_config();
+        assert_eq!(ret.system.recovery_mode, expected);
+    }
+    for mode in ["lenient", "strict --recovery-mode truncate"] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 --recovery-mode {mode}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        assert!(config::check_configuration().is_err());
+    }
+}
+#[test]
+fn parse_validate_cli_args_durability() {
+    for (durability, policy) in [
+        ("always", DurabilityPolicy::Always),
+        ("os", DurabilityPolicy::OsBuffered),
+        ("250ms", DurabilityPolicy::Periodic(250)),
+    ] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd --auth-root-password password12345678 --durability {durability}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        let ret = config::check_configuration().unwrap().into_config();
+        assert_eq!(
+            ret,
+            Configuration::new(
+                ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+                ConfigMode::Dev,
+                ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW).with_durability(policy),
+                ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+            )
+        );
+    }
+}
+#[test]
+fn parse_validate_cli_args_durability_bad() {
+    for durability in ["never", "0ms", "250", "1s", "os --durability always"] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 --durability {durability}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        assert!(config::check_configuration().is_err());
+    }
+}
+#[test]
+fn parse_validate_cli_args_backpressure() {
+    for (args, policy) in [
+        ("--backpressure-limit 1000", BackpressurePolicy::Stall),
+        (
+            "--backpressure stall --backpressure-limit 1000",
+            BackpressurePolicy::Stall,
+        ),
+        (
+            "--backpressure reject --backpressure-limit 1000",
+            BackpressurePolicy::Reject,
+        ),
+    ] {
+        config::set_cli_src(
+            format!("skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd --auth-root-password password12345678 {args}")
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        let ret = config::check_configuration().unwrap().into_config();
+        assert_eq!(
+            ret,
+            Configuration::new(
+                ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+                ConfigMode::Dev,
+                ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW).with_backpressure(policy, 1000),
+                ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+            )
+        );
+    }
+}
+#[test]
+fn parse_validate_cli_args_backpressure_bad() {
+    for args in [
+        // needs a limit
+        "--backpressure reject",
+        "--backpressure drop --backpressure-limit 1000",
+        "--backpressure-limit 0",
+        "--backpressure-limit many",
+        "--backpressure-limit 10 --backpressure-limit 20",
+    ] {
+        config::set_cli_src(
+            format!(
+                "skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 {args}"
+            )
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+        );
+        assert!(config::check_configuration().is_err(), "{args}");
+    }
+}
+#[test]
+fn parse_validate_cli_args_row_cache_size() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd \
+        --auth-root-password password12345678 --row-cache-size 1048576"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW).with_row_cache_size(1048576),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+    );
+    for args in ["--row-cache-size 0", "--row-cache-size lots"] {
+        config::set_cli_src(
+            format!(
+                "skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 {args}"
+            )
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+        );
+        assert!(config::check_configuration().is_err(), "{args}");
+    }
+}
+#[test]
+fn parse_validate_cli_args_memory_limits() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd \
+        --auth-root-password password12345678 --memory-limit 1073741824 --model-memory-limit 268435456"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW)
+                .with_memory_limits(Some(1073741824), Some(268435456)),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+    );
+    for args in [
+        "--memory-limit 0",
+        "--model-memory-limit lots",
+        "--memory-limit 10 --memory-limit 20",
+    ] {
+        config::set_cli_src(
+            format!(
+                "skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 {args}"
+            )
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+        );
+        assert!(config::check_configuration().is_err(), "{args}");
+    }
+}
+#[test]
+fn parse_validate_cli_args_query_timeout() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd \
+        --auth-root-password password12345678 --query-timeout 5000"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW).with_query_timeout(5000),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+    );
+    for args in [
+        "--query-timeout 0",
+        "--query-timeout 5s",
+        "--query-timeout 10 --query-timeout 20",
+    ] {
+        config::set_cli_src(
+            format!(
+                "skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 {args}"
+            )
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+        );
+        assert!(config::check_configuration().is_err(), "{args}");
+    }
+}
+#[test]
+fn parse_validate_cli_args_export_dir() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd \
+        --auth-root-password password12345678 --export-dir ."
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW).with_export_dir("."),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+    );
+    with_files(["__export_dir_is_a_file"], |[file]| {
+        for args in [
+            format!("--export-dir {file}"),
+            "--export-dir __missing_export_dir".into(),
+            "--export-dir . --export-dir ..".into(),
+        ] {
+            config::set_cli_src(
+                format!(
+                    "skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 {args}"
+                )
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+            );
+            assert!(config::check_configuration().is_err(), "{args}");
+        }
+    })
+}
+#[test]
+fn parse_validate_cli_args_idle_timeout() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd \
+        --auth-root-password password12345678 --idle-timeout 300"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW).with_idle_timeout(300),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+    );
+    for args in [
+        "--idle-timeout 0",
+        "--idle-timeout 5m",
+        "--idle-timeout 10 --idle-timeout 20",
+    ] {
+        config::set_cli_src(
+            format!(
+                "skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 {args}"
+            )
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+        );
+        assert!(config::check_configuration().is_err(), "{args}");
+    }
+}
+#[test]
+fn parse_validate_cli_args_metrics_endpoint() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --endpoint http@127.0.0.1:2005 \
+        --auth-plugin pwd --auth-root-password password12345678"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+        .with_metrics(ConfigEndpointTcp::new("127.0.0.1".into(), 2005))
+    );
+    // only one metrics endpoint is allowed
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint http@127.0.0.1:2005 --endpoint http@127.0.0.1:2006 \
+        --auth-plugin pwd --auth-root-password password12345678"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    assert!(config::check_configuration().is_err());
+}
+#[test]
+fn parse_validate_cli_args_web_endpoint() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --endpoint web@0.0.0.0:2007 \
+        --auth-plugin pwd --auth-root-password password12345678"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+        .with_web(ConfigEndpointTcp::new("0.0.0.0".into(), 2007))
+    );
+    // only one web endpoint is allowed
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint web@127.0.0.1:2007 --endpoint web@127.0.0.1:2008 \
+        --auth-plugin pwd --auth-root-password password12345678"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    assert!(config::check_configuration().is_err());
+}
+#[test]
+fn parse_validate_cli_args_resp_endpoint() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --endpoint resp@0.0.0.0:6379 \
+        --resp-model myspace.kv --auth-plugin pwd --auth-root-password password12345678"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+        .with_resp(ConfigResp::new(
+            ConfigEndpointTcp::new("0.0.0.0".into(), 6379),
+            "myspace".into(),
+            "kv".into()
+        ))
+    );
+    // the model is required, and has to be `space.model`
+    for bad in [
+        "--endpoint resp@127.0.0.1:6379",
+        "--endpoint resp@127.0.0.1:6379 --resp-model kv",
+        "--endpoint resp@127.0.0.1:6379 --resp-model myspace.",
+        "--endpoint resp@127.0.0.1:6379 --resp-model a.b.c",
+    ] {
+        config::set_cli_src(
+            format!(
+                "skyd --mode=dev {bad} --auth-plugin pwd --auth-root-password password12345678"
+            )
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+        );
+        assert!(config::check_configuration().is_err(), "{bad}");
+    }
+}
+#[test]
+fn parse_validate_cli_args_replication() {
+    with_files(
+        [
+            "__cli_args_test_repl_private.key",
+            "__cli_args_test_repl_cert.pem",
+            "__cli_args_test_repl_passphrase.key",
+            "__cli_args_test_repl_ca.pem",
+        ],
+        |[pkey, cert, pass, ca]| {
+            let tls = format!("--endpoint tls@127.0.0.1:2004 --tlskey {pkey} --tlscert {cert} --tls-passphrase {pass}");
+            config::set_cli_src(
+                format!(
+                    "skyd --mode=dev {tls} --endpoint repl@0.0.0.0:2006 --replica-of 10.0.0.2:2006 \
+                    --replica-ca {ca} --auth-plugin pwd --auth-root-password password12345678"
+                )
+                .split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+            );
+            let ret = config::check_configuration().unwrap().into_config();
+            assert_eq!(
+                ret,
+                Configuration::new(
+                    ConfigEndpoint::Secure(ConfigEndpointTls::new(
+                        ConfigEndpointTcp::new("127.0.0.1".into(), 2004),
+                        "".into(),
+                        "".into(),
+                        "".into(),
+                        None
+                    )),
+                    ConfigMode::Dev,
+                    ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+                    ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+                )
+                .with_replication(ConfigEndpointTcp::new("0.0.0.0".into(), 2006))
+                .with_replica_of(ConfigReplicaOf::new(
+                    ConfigEndpointTcp::new("10.0.0.2".into(), 2006),
+                    "".into()
+                ))
+            );
+            for bad in [
+                // replicas are only served over TLS
+                "--endpoint tcp@127.0.0.1:2003 --endpoint repl@0.0.0.0:2006".to_owned(),
+                // and a replica has to know which primary to trust
+                format!("{tls} --replica-of 10.0.0.2:2006"),
+                format!("{tls} --replica-ca {ca}"),
+                format!("{tls} --replica-of 10.0.0.2:2006 --replica-ca __missing_repl_ca.pem"),
+                format!("{tls} --replica-of 10.0.0.2 --replica-ca {ca}"),
+                format!("{tls} --replica-of 10.0.0.2:port --replica-ca {ca}"),
+                format!(
+                    "{tls} --replica-of 10.0.0.2:2006 --replica-of 10.0.0.3:2006 --replica-ca {ca}"
+                ),
+            ] {
+                config::set_cli_src(
+                    format!("skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 {bad}")
+                        .split_ascii_whitespace()
+                        .map(ToString::to_string)
+                        .collect(),
+                );
+                assert!(config::check_configuration().is_err(), "{bad}");
+            }
+        },
+    );
+}
+#[test]
+fn parse_validate_cli_args_password_policy() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd --auth-root-password password12345678 \
+        --auth-password-min-length 12 --auth-hash-cost 10"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        ret,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+            ConfigAuth {
+                password_min_len: 12,
+                hash_cost: 10,
+                ..ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+            }
+        )
+    );
+}
+#[test]
+fn parse_validate_cli_args_password_policy_bad() {
+    for policy in [
+        "--auth-password-min-length -1",
+        "--auth-password-min-length eight",
+        "--auth-hash-cost 3",
+        "--auth-hash-cost 32",
+        "--auth-hash-cost 10 --auth-hash-cost 12",
+    ] {
+        config::set_cli_src(
+            format!(
+                "skyd --mode=dev --auth-plugin pwd --auth-root-password password12345678 {policy}"
+            )
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+        );
+        assert!(config::check_configuration().is_err());
+    }
+}
+#[test]
+fn parse_validate_cli_args_root_password_file_bad() {
+    with_files(["__cli_args_test_root_password_bad.txt"], |[file]| {
+        std::fs::write(file, "password12345678\n").unwrap();
+        for auth in [
+            // both a password and a password file
+            format!("--auth-root-password password12345678 --auth-root-password-file {file}"),
+            // missing file
+            "--auth-root-password-file __cli_args_test_no_such_file.txt".to_owned(),
+            // not a boolean
+            format!("--auth-root-password-file {file} --auth-root-rotate yes"),
+        ] {
+            config::set_cli_src(
+                format!("skyd --mode=dev --auth-plugin pwd {auth}")
+                    .split_ascii_whitespace()
+                    .map(ToString::to_string)
+                    .collect(),
+            );
+            assert!(config::check_configuration().is_err());
+        }
+    })
+}
 
 /*
     env tests
@@ -236,7 +933,8 @@ fn parse_validate_env_args() {
                             ConfigEndpointTcp::new("localhost".into(), 8081),
                             "".into(),
                             "".into(),
-                            "".into()
+                            "".into(),
+                            None
                         )
                     ),
                     ConfigMode::Dev,
@@ -247,6 +945,33 @@ fn parse_validate_env_args() {
         },
     );
 }
+#[test]
+fn parse_validate_env_args_root_password_file() {
+    with_files(["__env_args_test_root_password.txt"], |[file]| {
+        std::fs::write(file, "password12345678\n").unwrap();
+        let variables = [
+            format!("SKYDB_AUTH_PLUGIN=pwd"),
+            format!("SKYDB_AUTH_ROOT_PASSWORD_FILE={file}"),
+            format!("SKYDB_AUTH_ROOT_ROTATE=true"),
+            format!("SKYDB_ENDPOINTS=tcp@localhost:8080"),
+            format!("SKYDB_RUN_MODE=dev"),
+        ];
+        config::set_env_src(variables.into());
+        let cfg = config::check_configuration().unwrap().into_config();
+        assert_eq!(
+            cfg,
+            Configuration::new(
+                ConfigEndpoint::Insecure(ConfigEndpointTcp::new("localhost".into(), 8080)),
+                ConfigMode::Dev,
+                ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+                ConfigAuth {
+                    root_rotate: true,
+                    ..ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+                }
+            )
+        )
+    });
+}
 const CONFIG_FILE: &str = "\
 system:
   mode: dev
@@ -288,7 +1013,8 @@ fn test_config_file() {
                             ConfigEndpointTcp::new("127.0.0.1".into(), 2004),
                             "".into(),
                             "".into(),
-                            "".into()
+                            "".into(),
+                            None
                         )
                     ),
                     ConfigMode::Dev,
@@ -299,3 +1025,162 @@ fn test_config_file() {
         },
     )
 }
+
+const CONFIG_FILE_RESP: &str = "\
+system:
+  mode: dev
+auth:
+  plugin: pwd
+  root_pass: password12345678
+endpoints:
+  insecure:
+    host: 127.0.0.1
+    port: 2003
+  resp:
+    host: 127.0.0.1
+    port: 6379
+    model: myspace.kv
+    ";
+#[test]
+fn test_config_file_resp() {
+    config::set_cli_src(vec!["skyd".into(), "--config=config.yml".into()]);
+    config::set_file_src(CONFIG_FILE_RESP);
+    let cfg = config::check_configuration().unwrap().into_config();
+    assert_eq!(
+        cfg,
+        Configuration::new(
+            ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+            ConfigMode::Dev,
+            ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+            ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+        )
+        .with_resp(ConfigResp::new(
+            ConfigEndpointTcp::new("127.0.0.1".into(), 6379),
+            "myspace".into(),
+            "kv".into()
+        ))
+    )
+}
+
+#[test]
+fn parse_validate_cli_args_recover_until() {
+    config::set_cli_src(
+        "skyd --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd --auth-root-password password12345678 \
+        --recover-until myspace.users@1024 --recover-until myspace.posts@0"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    let ret = config::check_configuration().unwrap();
+    assert_eq!(
+        ret,
+        ConfigReturn::Recover(
+            Configuration::new(
+                ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+                ConfigMode::Dev,
+                ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+                ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+            ),
+            ConfigRecovery {
+                restore: None,
+                until: vec![
+                    ConfigRecoverUntil::new("myspace".into(), "users".into(), 1024),
+                    ConfigRecoverUntil::new("myspace".into(), "posts".into(), 0),
+                ],
+            }
+        )
+    );
+}
+
+#[test]
+fn parse_validate_cli_args_recover_until_bad() {
+    for target in [
+        "myspace.users",
+        "myspace@10",
+        ".users@10",
+        "myspace.users@ten",
+    ] {
+        config::set_cli_src(vec![
+            "skyd".into(),
+            "--auth-root-password".into(),
+            "password12345678".into(),
+            "--recover-until".into(),
+            target.into(),
+        ]);
+        assert!(config::check_configuration().is_err());
+    }
+}
+
+#[test]
+fn parse_validate_cli_args_repair() {
+    for (mode, expected) in [
+        ("check", ConfigRepairMode::Check),
+        ("truncate", ConfigRepairMode::Truncate),
+    ] {
+        config::set_cli_src(vec!["skyd".into(), "--repair".into(), mode.into()]);
+        assert_eq!(
+            config::check_configuration().unwrap(),
+            ConfigReturn::Repair(expected, None)
+        );
+    }
+}
+
+#[test]
+fn parse_validate_cli_args_repair_bad() {
+    for args in [
+        "skyd --repair fix",
+        "skyd --repair check --repair truncate",
+        "skyd --repair check --auth-root-password password12345678",
+    ] {
+        config::set_cli_src(
+            args.split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        assert!(config::check_configuration().is_err());
+    }
+}
+
+#[test]
+fn parse_validate_cli_args_validate_config() {
+    config::set_cli_src(
+        "skyd --validate-config --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd --auth-root-password password12345678 --restore mybackup"
+            .split_ascii_whitespace()
+            .map(ToString::to_string)
+            .collect(),
+    );
+    assert_eq!(
+        config::check_configuration().unwrap(),
+        ConfigReturn::Validate(
+            Configuration::new(
+                ConfigEndpoint::Insecure(ConfigEndpointTcp::new("127.0.0.1".into(), 2003)),
+                ConfigMode::Dev,
+                ConfigSystem::new(fractal::GENERAL_EXECUTOR_WINDOW),
+                ConfigAuth::new(AuthDriver::Pwd, "password12345678".into())
+            ),
+            ConfigRecovery {
+                restore: Some("mybackup".into()),
+                until: vec![],
+            }
+        )
+    );
+}
+
+#[test]
+fn parse_validate_cli_args_validate_config_bad() {
+    for args in [
+        // the configuration is still checked
+        "skyd --validate-config --mode=dev --endpoint tcp@127.0.0.1:2003 --auth-plugin pwd --auth-root-password short",
+        // and there's nothing to validate with a repair
+        "skyd --validate-config --repair check",
+        // missing TLS files are reported as configuration errors
+        "skyd --validate-config --mode=dev --endpoint tls@127.0.0.1:2004 --tlscert __missing_cert.pem --tlskey __missing_key.pem --tls-passphrase __missing_pass.key --auth-plugin pwd --auth-root-password password12345678",
+    ] {
+        config::set_cli_src(
+            args.split_ascii_whitespace()
+                .map(ToString::to_string)
+                .collect(),
+        );
+        assert!(config::check_configuration().is_err());
+    }
+}
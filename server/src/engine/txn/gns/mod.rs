@@ -51,7 +51,7 @@ mod tests;
 pub use {
     model::{
         AlterModelAddTxn, AlterModelRemoveTxn, AlterModelUpdateTxn, CreateModelTxn, DropModelTxn,
-        ModelIDRef,
+        ModelIDRef, ReserveModelKeysTxn,
     },
     space::{AlterSpaceTxn, CreateSpaceTxn, DropSpaceTxn},
 };
@@ -71,6 +71,14 @@ impl<Fs: RawFSInterface> GNSTransactionDriverAnyFS<Fs> {
     pub fn __journal_mut(&mut self) -> &mut JournalWriter<Fs, GNSAdapter> {
         &mut self.journal
     }
+    /// Sync any events that weren't synced yet, if the sync interval has elapsed. See [`JournalWriter::sync_if_due`]
+    pub fn sync_if_due(&mut self) -> RuntimeResult<()> {
+        self.journal.sync_if_due()
+    }
+    /// Copy the journal (stored at `from`) into `to`. See [`JournalWriter::backup_to`]
+    pub fn backup_to(&self, from: &str, to: &str) -> RuntimeResult<()> {
+        self.journal.backup_to(from, to)
+    }
     /// Attempts to commit the given event into the journal, handling any possible recovery triggers and returning
     /// errors (if any)
     pub fn try_commit<GE: GNSEvent>(&mut self, gns_event: GE) -> RuntimeResult<()> {
@@ -108,7 +116,7 @@ impl JournalAdapter for GNSAdapter {
                 [$(<$item as GNSEvent>::decode_and_update_global_state),*, |_, _| Err(TransactionError::DecodeUnknownTxnOp.into())]
             };
         }
-        static DISPATCH: [fn(&mut BufferedScanner, &GlobalNS) -> RuntimeResult<()>; 9] = dispatch!(
+        static DISPATCH: [fn(&mut BufferedScanner, &GlobalNS) -> RuntimeResult<()>; 10] = dispatch!(
             CreateSpaceTxn,
             AlterSpaceTxn,
             DropSpaceTxn,
@@ -116,7 +124,8 @@ impl JournalAdapter for GNSAdapter {
             AlterModelAddTxn,
             AlterModelRemoveTxn,
             AlterModelUpdateTxn,
-            DropModelTxn
+            DropModelTxn,
+            ReserveModelKeysTxn
         );
         let mut scanner = BufferedScanner::new(&payload);
         let opc = unsafe {
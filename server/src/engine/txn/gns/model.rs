@@ -712,3 +712,109 @@ impl<'a> GNSEvent for DropModelTxn<'a> {
         })
     }
 }
+
+/*
+    reserve model keys
+*/
+
+#[derive(Debug, Clone, Copy)]
+/// Transaction commit payload for raising the high water mark of the keys generated for a model. This doesn't carry
+/// the space's UUID since it's committed by inserts, which don't lock the space index (the model's UUID is enough)
+pub struct ReserveModelKeysTxn<'a> {
+    model_id: EntityIDRef<'a>,
+    model_uuid: Uuid,
+    high_water: u64,
+}
+
+impl<'a> ReserveModelKeysTxn<'a> {
+    pub const fn new(model_id: EntityIDRef<'a>, model_uuid: Uuid, high_water: u64) -> Self {
+        Self {
+            model_id,
+            model_uuid,
+            high_water,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ReserveModelKeysTxnRestorePL {
+    pub(super) space_name: Box<str>,
+    pub(super) model_name: Box<str>,
+    pub(super) model_uuid: Uuid,
+    pub(super) high_water: u64,
+}
+
+pub struct ReserveModelKeysTxnMD {
+    space_name_l: u64,
+    model_name_l: u64,
+    model_uuid: Uuid,
+    high_water: u64,
+}
+
+impl<'a> PersistObject for ReserveModelKeysTxn<'a> {
+    const METADATA_SIZE: usize = sizeof!(u64, 3) + sizeof!(u128);
+    type InputType = ReserveModelKeysTxn<'a>;
+    type OutputType = ReserveModelKeysTxnRestorePL;
+    type Metadata = ReserveModelKeysTxnMD;
+    fn pretest_can_dec_object(scanner: &BufferedScanner, md: &Self::Metadata) -> bool {
+        scanner.has_left((md.space_name_l + md.model_name_l) as usize)
+    }
+    fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend(data.model_id.space().len().u64_bytes_le());
+        buf.extend(data.model_id.entity().len().u64_bytes_le());
+        buf.extend(data.model_uuid.to_le_bytes());
+        buf.extend(data.high_water.to_le_bytes());
+    }
+    unsafe fn meta_dec(scanner: &mut BufferedScanner) -> RuntimeResult<Self::Metadata> {
+        Ok(ReserveModelKeysTxnMD {
+            space_name_l: scanner.next_u64_le(),
+            model_name_l: scanner.next_u64_le(),
+            model_uuid: Uuid::from_bytes(scanner.next_chunk()),
+            high_water: scanner.next_u64_le(),
+        })
+    }
+    fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+        buf.extend(data.model_id.space().as_bytes());
+        buf.extend(data.model_id.entity().as_bytes());
+    }
+    unsafe fn obj_dec(
+        s: &mut BufferedScanner,
+        md: Self::Metadata,
+    ) -> RuntimeResult<Self::OutputType> {
+        let space_name =
+            inf::dec::utils::decode_string(s, md.space_name_l as usize)?.into_boxed_str();
+        let model_name =
+            inf::dec::utils::decode_string(s, md.model_name_l as usize)?.into_boxed_str();
+        Ok(ReserveModelKeysTxnRestorePL {
+            space_name,
+            model_name,
+            model_uuid: md.model_uuid,
+            high_water: md.high_water,
+        })
+    }
+}
+
+impl<'a> GNSEvent for ReserveModelKeysTxn<'a> {
+    const OPC: u16 = 8;
+    type CommitType = ReserveModelKeysTxn<'a>;
+    type RestoreType = ReserveModelKeysTxnRestorePL;
+    fn update_global_state(
+        ReserveModelKeysTxnRestorePL {
+            space_name,
+            model_name,
+            model_uuid,
+            high_water,
+        }: Self::RestoreType,
+        gns: &GlobalNS,
+    ) -> RuntimeResult<()> {
+        let models = gns.idx_models().read();
+        let Some(model) = models.get(&EntityIDRef::new(&space_name, &model_name)) else {
+            return Err(TransactionError::OnRestoreDataMissing.into());
+        };
+        if model.get_uuid() != model_uuid {
+            return Err(TransactionError::OnRestoreDataConflictMismatch.into());
+        }
+        model.restore_key_high_water(high_water);
+        Ok(())
+    }
+}
@@ -26,18 +26,28 @@
 
 use crate::engine::{
     core::{
+        dml::{self, Transaction},
         model::{Field, Layer, Model},
         space::Space,
+        EntityIDRef,
     },
     data::{cell::Datacell, tag::TagSelector, uuid::Uuid, DictEntryGeneric},
     error::QueryError,
-    fractal::{test_utils::TestGlobal, GlobalInstanceLike},
-    idx::STIndex,
+    fractal::{test_utils::TestGlobal, GlobalInstanceLike, ModelUniqueID},
+    idx::{MTIndexExt, STIndex},
     ql::{
         ast::parse_ast_node_full,
         ddl::crt::{CreateModel, CreateSpace},
+        dml::ins::InsertStatement,
         tests::lex_insecure,
     },
+    storage::v1::{
+        data_batch::{self, RecoveryMode},
+        loader::SEInitState,
+        memfs::VirtualFS,
+        RawFSInterface,
+    },
+    sync::atm::cpin,
 };
 
 fn multirun(f: impl FnOnce() + Copy) {
@@ -318,3 +328,277 @@ fn drop_model() {
         })
     })
 }
+
+#[test]
+fn reserve_model_keys() {
+    fn insert(global: &impl GlobalInstanceLike, username: &str) {
+        let query = format!("insert into myspace.mymodel('{username}')");
+        let stmt = lex_insecure(query.as_bytes()).unwrap();
+        let stmt = parse_ast_node_full::<InsertStatement>(&stmt[1..]).unwrap();
+        dml::insert(global, stmt).unwrap();
+    }
+    fn high_water(global: &impl GlobalInstanceLike) -> u64 {
+        global
+            .namespace()
+            .with_model(("myspace", "mymodel").into(), |model| {
+                Ok(model.key_high_water())
+            })
+            .unwrap()
+    }
+    with_variable("reserve_model_keys_test.global.db-tlog", |log_name| {
+        {
+            let global = TestGlobal::new_with_vfs_driver(log_name);
+            init_space(&global, "myspace", "{}");
+            init_model(
+                &global,
+                "myspace",
+                "mymodel",
+                "id: uint64 { generate: 'increment' }, username: string",
+            );
+            insert(&global, "sayan");
+            insert(&global, "elana");
+            assert_eq!(high_water(&global), 2 + 1024);
+        }
+        {
+            // keys continue from the high water mark
+            let global = TestGlobal::new_with_vfs_driver(log_name);
+            assert_eq!(high_water(&global), 2 + 1024);
+            insert(&global, "sayan");
+            assert_eq!(high_water(&global), 2 + 1024 + 1 + 1024);
+        }
+        multirun(|| {
+            let global = TestGlobal::new_with_vfs_driver(log_name);
+            assert_eq!(high_water(&global), 2 + 1024 + 1 + 1024);
+        })
+    })
+}
+
+#[test]
+fn backup_space_and_model() {
+    with_variable("backup_test.global.db-tlog", |log_name| {
+        let uuid_space;
+        let uuid_model;
+        {
+            let global = TestGlobal::new_with_vfs_driver(log_name);
+            uuid_space = init_space(&global, "myspace", "{}");
+            uuid_model = init_model(
+                &global,
+                "myspace",
+                "mymodel",
+                "username: string, password: string",
+            );
+            let stmt = lex_insecure(b"insert into myspace.mymodel('sayan', 'pass123')").unwrap();
+            let stmt = parse_ast_node_full::<InsertStatement>(&stmt[1..]).unwrap();
+            dml::insert(&global, stmt).unwrap();
+            global.backup_to("backup_test.backup").unwrap();
+            // the backup directory must not already exist
+            assert!(global.backup_to("backup_test.backup").is_err());
+        }
+        multirun(|| {
+            let global = TestGlobal::new_with_vfs_driver("backup_test.backup/gns.db-tlog");
+            let models = global.namespace().idx_models().read();
+            let model = models.get(&EntityIDRef::new("myspace", "mymodel")).unwrap();
+            assert_eq!(model.get_uuid(), uuid_model);
+            let model_path = format!(
+                "backup_test.backup/{}",
+                SEInitState::model_path("myspace", uuid_space, "mymodel", uuid_model)
+            );
+            let (persist_driver, report) =
+                data_batch::reinit::<VirtualFS>(&model_path, model, RecoveryMode::Strict).unwrap();
+            assert!(report.is_clean());
+            persist_driver.close().unwrap();
+            assert_eq!(model.primary_index().count(), 1);
+        })
+    })
+}
+
+#[test]
+fn promote_replica() {
+    with_variable("promote_test.global.db-tlog", |log_name| {
+        let uuid_space;
+        let uuid_model;
+        {
+            let global = TestGlobal::new_with_vfs_driver(log_name).with_replica();
+            uuid_space = init_space(&global, "myspace", "{}");
+            uuid_model = init_model(
+                &global,
+                "myspace",
+                "mymodel",
+                "username: string, password: string",
+            );
+            let stmt = lex_insecure(b"insert into myspace.mymodel('sayan', 'pass123')").unwrap();
+            let stmt = parse_ast_node_full::<InsertStatement>(&stmt[1..]).unwrap();
+            dml::insert(&global, stmt).unwrap();
+            assert!(global.replication_role().is_replica());
+            assert!(global.promote().unwrap());
+            assert!(!global.replication_role().is_replica());
+            assert_eq!(global.replication_role().while_replica(|| ()), None);
+            // already a primary
+            assert!(!global.promote().unwrap());
+            global
+                .model_drivers()
+                .write()
+                .remove(&ModelUniqueID::new("myspace", "mymodel", uuid_model))
+                .unwrap()
+                .close()
+                .unwrap();
+        }
+        // the rotated journal has everything
+        let global = TestGlobal::new_with_vfs_driver(log_name);
+        let models = global.namespace().idx_models().read();
+        let model = models.get(&EntityIDRef::new("myspace", "mymodel")).unwrap();
+        let (persist_driver, report) = data_batch::reinit::<VirtualFS>(
+            &SEInitState::model_path("myspace", uuid_space, "mymodel", uuid_model),
+            model,
+            RecoveryMode::Strict,
+        )
+        .unwrap();
+        assert!(report.is_clean());
+        persist_driver.close().unwrap();
+        assert_eq!(model.primary_index().count(), 1);
+    })
+}
+
+#[test]
+fn shutdown_flushes_and_closes() {
+    with_variable("shutdown_test.global.db-tlog", |log_name| {
+        let uuid_space;
+        let uuid_model;
+        {
+            let global = TestGlobal::new_with_vfs_driver(log_name);
+            uuid_space = init_space(&global, "myspace", "{}");
+            uuid_model = init_model(
+                &global,
+                "myspace",
+                "mymodel",
+                "username: string, password: string",
+            );
+            let stmt = lex_insecure(b"insert into myspace.mymodel('sayan', 'pass123')").unwrap();
+            let stmt = parse_ast_node_full::<InsertStatement>(&stmt[1..]).unwrap();
+            dml::insert(&global, stmt).unwrap();
+            // the insert is still in the delta queue
+            assert!(global.close_all_models());
+            assert!(global.model_drivers().read().is_empty());
+        }
+        // the journal was closed, and has the insert
+        let global = TestGlobal::new_with_vfs_driver(log_name);
+        let models = global.namespace().idx_models().read();
+        let model = models.get(&EntityIDRef::new("myspace", "mymodel")).unwrap();
+        let (persist_driver, report) = data_batch::reinit::<VirtualFS>(
+            &SEInitState::model_path("myspace", uuid_space, "mymodel", uuid_model),
+            model,
+            RecoveryMode::Strict,
+        )
+        .unwrap();
+        assert!(report.is_clean());
+        persist_driver.close().unwrap();
+        assert_eq!(model.primary_index().count(), 1);
+    })
+}
+
+#[test]
+fn ephemeral_model() {
+    with_variable("ephemeral_model_test.global.db-tlog", |log_name| {
+        let uuid_space;
+        let uuid_model;
+        {
+            let global = TestGlobal::new_with_vfs_driver(log_name);
+            uuid_space = init_space(&global, "myspace", "{}");
+            let stmt = lex_insecure(
+                b"create model myspace.mymodel(username: string, password: string) with { durability: 'none' }",
+            )
+            .unwrap();
+            let stmt = parse_ast_node_full::<CreateModel>(&stmt[2..]).unwrap();
+            Model::transactional_exec_create(&global, stmt).unwrap();
+            uuid_model = global
+                .namespace()
+                .with_model(("myspace", "mymodel").into(), |model| Ok(model.get_uuid()))
+                .unwrap();
+            let stmt = lex_insecure(b"insert into myspace.mymodel('sayan', 'pass123')").unwrap();
+            let stmt = parse_ast_node_full::<InsertStatement>(&stmt[1..]).unwrap();
+            dml::insert(&global, stmt).unwrap();
+            assert!(global.close_all_models());
+        }
+        // no journal was created, so the model is empty after a restart (but still ephemeral)
+        let global = TestGlobal::new_with_vfs_driver(log_name);
+        assert!(VirtualFS::fs_fopen_rw(&SEInitState::model_path(
+            "myspace", uuid_space, "mymodel", uuid_model
+        ))
+        .is_err());
+        global
+            .namespace()
+            .with_model(("myspace", "mymodel").into(), |model| {
+                assert!(model.is_ephemeral());
+                assert_eq!(model.primary_index().count(), 0);
+                Ok(())
+            })
+            .unwrap();
+    })
+}
+
+#[test]
+fn transaction_journaled_on_commit() {
+    with_variable("transaction_journal_test.global.db-tlog", |log_name| {
+        let uuid_space;
+        let uuid_model;
+        {
+            let global = TestGlobal::new_with_vfs_driver(log_name);
+            uuid_space = init_space(&global, "myspace", "{}");
+            uuid_model = init_model(
+                &global,
+                "myspace",
+                "mymodel",
+                "username: string, password: string",
+            );
+            let insert = |query: &str| {
+                let stmt = lex_insecure(query.as_bytes()).unwrap();
+                dml::insert(
+                    &global,
+                    parse_ast_node_full::<InsertStatement>(&stmt[1..]).unwrap(),
+                )
+            };
+            insert("insert into myspace.mymodel('sayan', 'pass123')").unwrap();
+            // a batch that fails partway is rolled back
+            let mut failed = Transaction::new();
+            let r = failed.scope(|| {
+                insert("insert into myspace.mymodel('elana', 'pass123')")?;
+                insert("insert into myspace.mymodel('sayan', 'pass321')")
+            });
+            assert_eq!(r.unwrap_err(), QueryError::QExecDmlDuplicate);
+            failed.rollback(&global).unwrap();
+            let mut committed = Transaction::new();
+            committed
+                .scope(|| insert("insert into myspace.mymodel('joe', 'pass123')"))
+                .unwrap();
+            committed.commit(&global);
+            // this one is still running when we go down
+            let mut running = Transaction::new();
+            running
+                .scope(|| insert("insert into myspace.mymodel('bill', 'pass123')"))
+                .unwrap();
+            assert!(global.close_all_models());
+            core::mem::forget(running);
+        }
+        // only the committed changes were journaled
+        let global = TestGlobal::new_with_vfs_driver(log_name);
+        let models = global.namespace().idx_models().read();
+        let model = models.get(&EntityIDRef::new("myspace", "mymodel")).unwrap();
+        let (persist_driver, report) = data_batch::reinit::<VirtualFS>(
+            &SEInitState::model_path("myspace", uuid_space, "mymodel", uuid_model),
+            model,
+            RecoveryMode::Strict,
+        )
+        .unwrap();
+        assert!(report.is_clean());
+        persist_driver.close().unwrap();
+        let g = cpin();
+        let mut users: Vec<_> = model
+            .primary_index()
+            .__raw_index()
+            .mt_iter_entry(&g)
+            .map(|row| row.d_key().str().unwrap().to_owned())
+            .collect();
+        users.sort();
+        assert_eq!(users, ["joe", "sayan"]);
+    })
+}
@@ -0,0 +1,250 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Just enough JSON for the web bridge: a parser for the requests, and what's needed to write the responses
+
+use core::fmt::Write;
+
+/// We won't parse values that are nested deeper than this
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    /// a number, as it was written (so that large integers don't lose precision)
+    Number(Box<str>),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parse a JSON document. Returns [`None`] if it isn't valid JSON
+    pub fn parse(src: &[u8]) -> Option<Self> {
+        let mut parser = Parser { src, cursor: 0 };
+        let value = parser.value(0)?;
+        parser.skip_whitespace();
+        (parser.cursor == src.len()).then_some(value)
+    }
+    /// Returns the value of the given key if this is an object that has it
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.cursor).copied()
+    }
+    fn next(&mut self) -> Option<u8> {
+        let b = self.peek()?;
+        self.cursor += 1;
+        Some(b)
+    }
+    fn eat(&mut self, lit: &[u8]) -> bool {
+        let matched = self.src[self.cursor..].starts_with(lit);
+        self.cursor += matched as usize * lit.len();
+        matched
+    }
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.cursor += 1;
+        }
+    }
+    fn value(&mut self, depth: usize) -> Option<Json> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        self.skip_whitespace();
+        let value = match self.peek()? {
+            b'n' if self.eat(b"null") => Json::Null,
+            b't' if self.eat(b"true") => Json::Bool(true),
+            b'f' if self.eat(b"false") => Json::Bool(false),
+            b'"' => Json::String(self.string()?),
+            b'-' | b'0'..=b'9' => self.number()?,
+            b'[' => {
+                self.cursor += 1;
+                let mut items = vec![];
+                self.skip_whitespace();
+                if !self.eat(b"]") {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        self.skip_whitespace();
+                        match self.next()? {
+                            b',' => {}
+                            b']' => break,
+                            _ => return None,
+                        }
+                    }
+                }
+                Json::Array(items)
+            }
+            b'{' => {
+                self.cursor += 1;
+                let mut fields = vec![];
+                self.skip_whitespace();
+                if !self.eat(b"}") {
+                    loop {
+                        self.skip_whitespace();
+                        if self.peek()? != b'"' {
+                            return None;
+                        }
+                        let key = self.string()?;
+                        self.skip_whitespace();
+                        if !self.eat(b":") {
+                            return None;
+                        }
+                        fields.push((key, self.value(depth + 1)?));
+                        self.skip_whitespace();
+                        match self.next()? {
+                            b',' => {}
+                            b'}' => break,
+                            _ => return None,
+                        }
+                    }
+                }
+                Json::Object(fields)
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+    fn digits(&mut self) -> bool {
+        let start = self.cursor;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.cursor += 1;
+        }
+        self.cursor != start
+    }
+    fn number(&mut self) -> Option<Json> {
+        let start = self.cursor;
+        self.eat(b"-");
+        // no leading zeroes
+        if !(self.eat(b"0") || self.digits()) {
+            return None;
+        }
+        if self.eat(b".") && !self.digits() {
+            return None;
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.cursor += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.cursor += 1;
+            }
+            if !self.digits() {
+                return None;
+            }
+        }
+        let number = core::str::from_utf8(&self.src[start..self.cursor]).ok()?;
+        Some(Json::Number(number.into()))
+    }
+    fn hex4(&mut self) -> Option<u32> {
+        let hex = self.src.get(self.cursor..self.cursor + 4)?;
+        self.cursor += 4;
+        if !hex.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+        u32::from_str_radix(core::str::from_utf8(hex).ok()?, 16).ok()
+    }
+    fn string(&mut self) -> Option<String> {
+        // skip the opening quote
+        self.cursor += 1;
+        let mut s = vec![];
+        loop {
+            match self.next()? {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\x08',
+                        b'f' => '\x0c',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut c = self.hex4()?;
+                            if (0xD800..0xDC00).contains(&c) {
+                                // a surrogate pair
+                                if !self.eat(b"\\u") {
+                                    return None;
+                                }
+                                let low = self.hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return None;
+                                }
+                                c = 0x10000 + ((c - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(c)?
+                        }
+                        _ => return None,
+                    };
+                    s.extend(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                // control characters have to be escaped
+                0x00..=0x1f => return None,
+                b => s.push(b),
+            }
+        }
+        String::from_utf8(s).ok()
+    }
+}
+
+/// Write a string as a JSON string
+pub fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // writing to a string is infallible
+            '\x00'..='\x1f' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
@@ -0,0 +1,719 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! A bridge that lets browsers (and tools like curl) run queries without a native driver
+//!
+//! - `POST /query` runs the query in the body (`{"query": "select * from myspace.mymodel where username = ?",
+//!   "params": ["sayan"]}`) and responds with `{"result": ...}`, or with `{"error": {"code": ..., "name": ...}}` (and a
+//!   `400`) if the query failed
+//! - `GET /ws` upgrades to a websocket, on which every message is a query (just like the body above) and is answered
+//!   with its result. After a `subscribe`, changes are streamed as `{"event": [...]}` messages until the client sends
+//!   another message (which ends the subscription and is answered with `{"result": null}`, but isn't run)
+//!
+//! Clients authenticate with HTTP basic auth. Browsers can't set headers on websockets, so a websocket can also be
+//! opened without credentials, in which case the first message has to be `{"username": ..., "password": ...}`. Every
+//! request (and websocket) counts as a connection towards the user's quota.
+//!
+//! Params are passed by their JSON type: `null`, booleans, integers (unsigned unless they're negative), floats and
+//! strings. Rows are returned as arrays of their cells (where binary values are arrays of bytes), and multiple rows as
+//! arrays of rows
+
+mod json;
+#[cfg(test)]
+mod tests;
+mod ws;
+
+use {
+    self::{
+        json::Json,
+        ws::{Message, WebSocket},
+    },
+    crate::engine::{
+        config::ConfigEndpointTcp,
+        data::tag::{TagClass, TagSelector},
+        error::{QueryError, QueryResult, RuntimeResult},
+        fractal::{cdc::Subscription, Global},
+        net::{
+            protocol::{ClientLocalState, QueryHost, QueryKind, Response, ResponseType, SQuery},
+            IoResult, ProtocolListener,
+        },
+        storage::v1::audit::{AuditEvent, AuditEventKind},
+    },
+    bytes::BytesMut,
+    core::fmt::Write,
+    std::{net::SocketAddr, time::Duration},
+    tokio::{
+        io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+        sync::broadcast,
+    },
+};
+
+/// We won't read request heads larger than this
+const MAX_REQUEST_HEAD: usize = 8192;
+/// We won't read request bodies larger than this
+const MAX_REQUEST_BODY: usize = ws::MAX_MESSAGE;
+/// How long a client has to send us the request (but not to send websocket messages)
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// What we respond with when a subscription ends, or when a websocket client authenticates
+const RESULT_EMPTY: &str = r#"{"result":null}"#;
+
+/*
+    requests
+*/
+
+#[derive(Debug, PartialEq)]
+struct Request<'a> {
+    method: &'a [u8],
+    /// the path, without the query string
+    path: &'a [u8],
+    headers: Vec<(&'a [u8], &'a [u8])>,
+}
+
+impl<'a> Request<'a> {
+    /// Parse the request head (everything up to the empty line)
+    fn parse(head: &'a [u8]) -> Option<Self> {
+        let mut lines = head
+            .split(|b| *b == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+        let mut request_line = lines.next()?.split(|b| *b == b' ');
+        let (method, path, version) = (
+            request_line.next()?,
+            request_line.next()?,
+            request_line.next()?,
+        );
+        if !version.starts_with(b"HTTP/1.") | request_line.next().is_some() {
+            return None;
+        }
+        let path = path.split(|b| *b == b'?').next().unwrap_or_default();
+        let mut headers = vec![];
+        for line in lines.take_while(|line| !line.is_empty()) {
+            let colon = line.iter().position(|b| *b == b':')?;
+            headers.push((&line[..colon], line[colon + 1..].trim_ascii()));
+        }
+        Some(Self {
+            method,
+            path,
+            headers,
+        })
+    }
+    fn header(&self, name: &str) -> Option<&'a [u8]> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name.as_bytes()))
+            .map(|(_, v)| *v)
+    }
+    /// Returns true if the header is a list that has the given token (like `Connection: keep-alive, Upgrade`)
+    fn header_has(&self, name: &str, token: &str) -> bool {
+        self.header(name).is_some_and(|v| {
+            v.split(|b| *b == b',')
+                .any(|t| t.trim_ascii().eq_ignore_ascii_case(token.as_bytes()))
+        })
+    }
+    /// Returns the username and password if the request has basic auth credentials
+    fn basic_auth(&self) -> Option<(Vec<u8>, Vec<u8>)> {
+        let auth = self.header("authorization")?;
+        let (scheme, credentials) = auth.split_at(auth.iter().position(|b| *b == b' ')?);
+        if !scheme.eq_ignore_ascii_case(b"basic") {
+            return None;
+        }
+        let credentials =
+            openssl::base64::decode_block(core::str::from_utf8(credentials.trim_ascii()).ok()?)
+                .ok()?;
+        let colon = credentials.iter().position(|b| *b == b':')?;
+        Some((
+            credentials[..colon].to_vec(),
+            credentials[colon + 1..].to_vec(),
+        ))
+    }
+    /// Returns the size of the body (if the client set one that we can accept)
+    fn content_length(&self) -> Result<usize, &'static str> {
+        if self.header("transfer-encoding").is_some() {
+            return Err("411 Length Required");
+        }
+        let Some(len) = self.header("content-length") else {
+            return Err("411 Length Required");
+        };
+        match core::str::from_utf8(len).map(str::parse::<usize>) {
+            Ok(Ok(len)) if len <= MAX_REQUEST_BODY => Ok(len),
+            Ok(Ok(_)) => Err("413 Content Too Large"),
+            _ => Err("400 Bad Request"),
+        }
+    }
+}
+
+/// Turn a query (`{"query": ..., "params": [...]}`) into the payload of a query packet, returning it along with the
+/// size of the query (everything after it are the params)
+fn encode_query(query: &Json) -> Option<(Vec<u8>, usize)> {
+    let text = query.get("query")?.as_str()?;
+    let mut payload = text.as_bytes().to_vec();
+    match query.get("params") {
+        None => {}
+        Some(Json::Array(params)) => {
+            for param in params {
+                encode_param(&mut payload, param)?;
+            }
+        }
+        Some(_) => return None,
+    }
+    Some((payload, text.len()))
+}
+
+/// Encode a param just like a client of the native protocol would
+fn encode_param(buf: &mut Vec<u8>, param: &Json) -> Option<()> {
+    match param {
+        Json::Null => buf.push(0),
+        Json::Bool(b) => buf.extend([1, *b as u8]),
+        Json::Number(n) => {
+            if let Ok(uint) = n.parse::<u64>() {
+                buf.push(2);
+                buf.extend(uint.to_string().as_bytes());
+            } else if let Ok(sint) = n.parse::<i64>() {
+                buf.push(3);
+                buf.extend(sint.to_string().as_bytes());
+            } else {
+                let float = n.parse::<f64>().ok().filter(|f| f.is_finite())?;
+                buf.push(4);
+                buf.extend(float.to_string().as_bytes());
+            }
+            buf.push(b'\n');
+        }
+        Json::String(s) => {
+            buf.push(6);
+            buf.extend(s.len().to_string().as_bytes());
+            buf.push(b'\n');
+            buf.extend(s.as_bytes());
+        }
+        Json::Array(_) | Json::Object(_) => return None,
+    }
+    Some(())
+}
+
+/*
+    responses
+*/
+
+/// Returns `{"result": ...}` or `{"error": ...}` for the result of a query
+fn result_json(r: QueryResult<Response>) -> String {
+    wrap_json("result", r)
+}
+
+/// Returns `{"event": [...]}` or `{"error": ...}` for a change that a subscriber received
+fn event_json(r: QueryResult<Response>) -> String {
+    wrap_json("event", r)
+}
+
+fn wrap_json(key: &str, r: QueryResult<Response>) -> String {
+    let mut out = String::new();
+    match r.and_then(|r| response_json(r).ok_or(QueryError::SysUnknownError)) {
+        Ok(value) => {
+            let _ = write!(out, "{{\"{key}\":{value}}}");
+        }
+        Err(e) => {
+            let _ = write!(
+                out,
+                "{{\"error\":{{\"code\":{},\"name\":\"{e:?}\"}}}}",
+                e.value_u8()
+            );
+        }
+    }
+    out
+}
+
+/// Returns the response as a JSON value
+fn response_json(r: Response) -> Option<String> {
+    let mut out = String::new();
+    match r {
+        Response::Empty | Response::Null => out.push_str("null"),
+        Response::Bool(b) => out.push_str(if b { "true" } else { "false" }),
+        Response::UInt(u) => out.push_str(&u.to_string()),
+        Response::Serialized { ty, size, data } => {
+            let data = data.to_vec();
+            let mut data = &data[..];
+            match ty {
+                ResponseType::Row | ResponseType::Event => decode_cells(&mut data, size, &mut out)?,
+                ResponseType::MultiRow => {
+                    let columns = core::str::from_utf8(decode_line(&mut data)?)
+                        .ok()?
+                        .parse()
+                        .ok()?;
+                    out.push('[');
+                    for i in 0..size {
+                        if i != 0 {
+                            out.push(',');
+                        }
+                        decode_cells(&mut data, columns, &mut out)?;
+                    }
+                    out.push(']');
+                }
+                ResponseType::String => {
+                    json::write_str(&mut out, core::str::from_utf8(data).ok()?);
+                    data = &[];
+                }
+                _ => return None,
+            }
+            if !data.is_empty() {
+                return None;
+            }
+        }
+        Response::Subscription(_) => return None,
+    }
+    Some(out)
+}
+
+/// Decode `count` cells into a JSON array
+fn decode_cells(data: &mut &[u8], count: usize, out: &mut String) -> Option<()> {
+    out.push('[');
+    for i in 0..count {
+        if i != 0 {
+            out.push(',');
+        }
+        decode_cell(data, out)?;
+    }
+    out.push(']');
+    Some(())
+}
+
+/// Returns everything up to the next LF (and skips the LF)
+pub(super) fn decode_line<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let lf = data.iter().position(|b| *b == b'\n')?;
+    let line = &data[..lf];
+    *data = &data[lf + 1..];
+    Some(line)
+}
+
+/// Returns the next `len` bytes, where `len` is the next line
+pub(super) fn decode_sized<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = core::str::from_utf8(decode_line(data)?)
+        .ok()?
+        .parse::<usize>()
+        .ok()?;
+    if data.len() < len {
+        return None;
+    }
+    let (chunk, rest) = data.split_at(len);
+    *data = rest;
+    Some(chunk)
+}
+
+/// Decode a cell (as encoded for the native protocol) into a JSON value
+fn decode_cell(data: &mut &[u8], out: &mut String) -> Option<()> {
+    let (&tag, rest) = data.split_first()?;
+    *data = rest;
+    if tag == 0 {
+        out.push_str("null");
+        return Some(());
+    }
+    if tag - 1 > TagSelector::MAX {
+        return None;
+    }
+    let selector = unsafe {
+        // UNSAFE(@ohsayan): we just checked that this is a valid selector
+        TagSelector::from_raw(tag - 1)
+    };
+    match selector.tag_class() {
+        TagClass::Bool => {
+            let (&b, rest) = data.split_first()?;
+            *data = rest;
+            out.push_str(if b == 1 { "true" } else { "false" });
+        }
+        TagClass::UnsignedInt | TagClass::SignedInt | TagClass::Timestamp => {
+            out.push_str(core::str::from_utf8(decode_line(data)?).ok()?)
+        }
+        TagClass::Float => {
+            let float = core::str::from_utf8(decode_line(data)?).ok()?;
+            // JSON has no infinities or NaNs
+            match float.parse::<f64>().ok()?.is_finite() {
+                true => out.push_str(float),
+                false => out.push_str("null"),
+            }
+        }
+        TagClass::Bin => {
+            out.push('[');
+            for (i, b) in decode_sized(data)?.iter().enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                let _ = write!(out, "{b}");
+            }
+            out.push(']');
+        }
+        // 128-bit integers are strings so that they aren't rounded by JSON parsers (and points are `"lat,lon"`)
+        TagClass::Str | TagClass::BigInt | TagClass::Geo => {
+            json::write_str(out, core::str::from_utf8(decode_sized(data)?).ok()?)
+        }
+        // dicts are sent as lists (see `encode_cell`)
+        TagClass::List | TagClass::Dict => {
+            let len = core::str::from_utf8(decode_line(data)?)
+                .ok()?
+                .parse()
+                .ok()?;
+            decode_cells(data, len, out)?;
+        }
+    }
+    Some(())
+}
+
+/// Returns an HTTP response with a JSON body
+fn http_response(status: &str, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+        Access-Control-Allow-Origin: *\r\nConnection: close\r\n{headers}\r\n{body}",
+        body.len()
+    )
+}
+
+/// Returns an HTTP response for a request that failed before we got to run a query
+fn http_error(status: &str, headers: &str) -> String {
+    let mut body = String::from("{\"error\":");
+    json::write_str(&mut body, &status[4..].to_ascii_lowercase());
+    body.push('}');
+    http_response(status, headers, &body)
+}
+
+/*
+    serving
+*/
+
+/// Log the client in with the given credentials (the attempt is audited as made through `via`)
+pub(super) fn login<H: QueryHost>(
+    host: &H,
+    username: &[u8],
+    password: &[u8],
+    peer: SocketAddr,
+    via: &str,
+) -> Option<ClientLocalState> {
+    let r = match core::str::from_utf8(username) {
+        Ok(username) => host
+            .sys_store()
+            .verify_user_check_root(username, password)
+            .map(|root| ClientLocalState::new_bridged(username.into(), root).with_peer(peer)),
+        Err(_) => Err(QueryError::SysAuthError),
+    };
+    host.sys_store().audit(AuditEvent::new(
+        AuditEventKind::Login,
+        r.is_ok(),
+        &String::from_utf8_lossy(username),
+        &peer.to_string(),
+        via,
+    ));
+    r.ok()
+}
+
+/// Read the request head (everything up to the first empty line), returning its size. Returns [`None`] if the head was
+/// too large or if the client closed the connection before sending all of it. Anything after the head stays in the
+/// buffer
+async fn read_request_head<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buf: &mut BytesMut,
+) -> IoResult<Option<usize>> {
+    loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            return Ok(Some(end + 4));
+        }
+        if buf.len() >= MAX_REQUEST_HEAD || stream.read_buf(buf).await? == 0 {
+            return Ok(None);
+        }
+    }
+}
+
+async fn serve<S: AsyncRead + AsyncWrite + Unpin, H: QueryHost>(
+    mut stream: S,
+    peer: SocketAddr,
+    host: &H,
+    term: broadcast::Receiver<()>,
+) -> IoResult<()> {
+    let mut buf = BytesMut::with_capacity(1024);
+    let response =
+        match tokio::time::timeout(READ_TIMEOUT, read_request_head(&mut stream, &mut buf)).await {
+            Ok(Ok(Some(head_len))) => {
+                let head = buf.split_to(head_len);
+                match Request::parse(&head) {
+                    Some(request) => match (request.method, request.path) {
+                        (b"POST", b"/query") => {
+                            serve_query(&mut stream, &mut buf, &request, peer, host).await?
+                        }
+                        (b"GET", b"/ws") => {
+                            return serve_upgrade(stream, buf, &request, peer, host, term).await
+                        }
+                        (b"OPTIONS", b"/query" | b"/ws") => http_response(
+                            "204 No Content",
+                            "Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+                            Access-Control-Allow-Headers: Authorization, Content-Type\r\n",
+                            "",
+                        ),
+                        (_, b"/query" | b"/ws") => http_error("405 Method Not Allowed", ""),
+                        _ => http_error("404 Not Found", ""),
+                    },
+                    None => http_error("400 Bad Request", ""),
+                }
+            }
+            Ok(Ok(None)) => http_error("400 Bad Request", ""),
+            Ok(Err(e)) => return Err(e),
+            // the client took too long, so just close the connection
+            Err(_) => return Ok(()),
+        };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Run the query in the body of the request, returning the response
+async fn serve_query<S: AsyncRead + Unpin, H: QueryHost>(
+    stream: &mut S,
+    buf: &mut BytesMut,
+    request: &Request<'_>,
+    peer: SocketAddr,
+    host: &H,
+) -> IoResult<String> {
+    let len = match request.content_length() {
+        Ok(len) => len,
+        Err(status) => return Ok(http_error(status, "")),
+    };
+    let Some(mut cstate) = request
+        .basic_auth()
+        .and_then(|(username, password)| login(host, &username, &password, peer, "web"))
+    else {
+        return Ok(http_error(
+            "401 Unauthorized",
+            "WWW-Authenticate: Basic realm=\"skytable\"\r\n",
+        ));
+    };
+    let sys_cfg = host.sys_store().system_store();
+    let quota = sys_cfg.user_quota(cstate.username());
+    let Some(_connection) = sys_cfg.quota_usage().connect(cstate.username(), quota) else {
+        return Ok(http_error("429 Too Many Requests", ""));
+    };
+    let read_body = async {
+        while buf.len() < len {
+            if stream.read_buf(buf).await? == 0 {
+                return Ok(false);
+            }
+        }
+        Ok::<_, std::io::Error>(true)
+    };
+    match tokio::time::timeout(READ_TIMEOUT, read_body).await {
+        Ok(Ok(true)) => {}
+        Ok(Ok(false)) | Err(_) => return Ok(http_error("400 Bad Request", "")),
+        Ok(Err(e)) => return Err(e),
+    }
+    let r = match Json::parse(&buf[..len]).as_ref().and_then(encode_query) {
+        Some((payload, q_window)) => {
+            host.dispatch(
+                &mut cstate,
+                SQuery::new(QueryKind::Simple, &payload, q_window),
+            )
+            .await
+        }
+        None => Err(QueryError::SysNetworkSystemIllegalClientPacket),
+    };
+    let status = match r {
+        Ok(Response::Subscription(_)) => {
+            return Ok(http_response(
+                "400 Bad Request",
+                "",
+                r#"{"error":"subscriptions need a websocket"}"#,
+            ))
+        }
+        Ok(_) => "200 OK",
+        Err(_) => "400 Bad Request",
+    };
+    Ok(http_response(status, "", &result_json(r)))
+}
+
+/// Upgrade to a websocket and serve it
+async fn serve_upgrade<S: AsyncRead + AsyncWrite + Unpin, H: QueryHost>(
+    mut stream: S,
+    buf: BytesMut,
+    request: &Request<'_>,
+    peer: SocketAddr,
+    host: &H,
+    mut term: broadcast::Receiver<()>,
+) -> IoResult<()> {
+    let key = match request.header("sec-websocket-key") {
+        Some(key)
+            if request.header_has("upgrade", "websocket")
+                & request.header_has("connection", "upgrade")
+                & (request.header("sec-websocket-version") == Some(b"13")) =>
+        {
+            key
+        }
+        _ => {
+            let response = http_error("426 Upgrade Required", "Sec-WebSocket-Version: 13\r\n");
+            stream.write_all(response.as_bytes()).await?;
+            return stream.shutdown().await;
+        }
+    };
+    let cstate = match request.basic_auth() {
+        Some((username, password)) => match login(host, &username, &password, peer, "web") {
+            Some(cstate) => Some(cstate),
+            None => {
+                let response = http_error(
+                    "401 Unauthorized",
+                    "WWW-Authenticate: Basic realm=\"skytable\"\r\n",
+                );
+                stream.write_all(response.as_bytes()).await?;
+                return stream.shutdown().await;
+            }
+        },
+        None => None,
+    };
+    let upgrade = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+        Sec-WebSocket-Accept: {}\r\n\r\n",
+        ws::accept_key(key)
+    );
+    stream.write_all(upgrade.as_bytes()).await?;
+    let mut ws = WebSocket::new(stream, buf);
+    let mut cstate = match cstate {
+        Some(cstate) => cstate,
+        None => {
+            // the first message has the credentials
+            let message = tokio::select! {
+                message = ws.read() => message?,
+                _ = term.recv() => return ws.close().await,
+            };
+            let credentials = match &message {
+                Message::Text(text) => Json::parse(text.as_bytes()),
+                Message::Close => return ws.close().await,
+            };
+            let cstate = credentials.as_ref().and_then(|credentials| {
+                let username = credentials.get("username")?.as_str()?;
+                let password = credentials.get("password")?.as_str()?;
+                login(host, username.as_bytes(), password.as_bytes(), peer, "web")
+            });
+            match cstate {
+                Some(cstate) => {
+                    ws.write_text(RESULT_EMPTY).await?;
+                    cstate
+                }
+                None => {
+                    ws.write_text(&result_json(Err(QueryError::SysAuthError)))
+                        .await?;
+                    return ws.close().await;
+                }
+            }
+        }
+    };
+    let sys_cfg = host.sys_store().system_store();
+    let quota = sys_cfg.user_quota(cstate.username());
+    let Some(_connection) = sys_cfg.quota_usage().connect(cstate.username(), quota) else {
+        ws.write_text(r#"{"error":"too many connections"}"#).await?;
+        return ws.close().await;
+    };
+    loop {
+        let message = tokio::select! {
+            message = ws.read() => message?,
+            _ = term.recv() => return ws.close().await,
+        };
+        let Message::Text(query) = message else {
+            return ws.close().await;
+        };
+        let r = match Json::parse(query.as_bytes())
+            .as_ref()
+            .and_then(encode_query)
+        {
+            Some((payload, q_window)) => {
+                host.dispatch(
+                    &mut cstate,
+                    SQuery::new(QueryKind::Simple, &payload, q_window),
+                )
+                .await
+            }
+            None => Err(QueryError::SysNetworkSystemIllegalClientPacket),
+        };
+        match r {
+            Ok(Response::Subscription(sub)) => {
+                ws.write_text(RESULT_EMPTY).await?;
+                if stream_events(&mut ws, sub, &mut term).await? {
+                    return ws.close().await;
+                }
+            }
+            r => ws.write_text(&result_json(r)).await?,
+        }
+    }
+}
+
+/// Stream changes to the client until it sends a message (which is discarded) or until the subscriber falls behind.
+/// Returns true if the websocket should be closed
+async fn stream_events<S: AsyncRead + AsyncWrite + Unpin>(
+    ws: &mut WebSocket<S>,
+    mut sub: Subscription,
+    term: &mut broadcast::Receiver<()>,
+) -> IoResult<bool> {
+    loop {
+        tokio::select! {
+            _ = term.recv() => return Ok(true),
+            message = ws.read() => {
+                if message? == Message::Close {
+                    return Ok(true);
+                }
+                ws.write_text(RESULT_EMPTY).await?;
+                return Ok(false);
+            }
+            event = sub.next() => {
+                let lagged = event.is_err();
+                ws.write_text(&event_json(event)).await?;
+                if lagged {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+}
+
+/// A listener that serves the web bridge
+pub struct WebListener<H = Global> {
+    host: H,
+    listener: ProtocolListener,
+    term: broadcast::Receiver<()>,
+}
+
+impl<H: QueryHost> WebListener<H> {
+    pub async fn new(
+        ep: &ConfigEndpointTcp,
+        host: H,
+        term: broadcast::Receiver<()>,
+    ) -> RuntimeResult<Self> {
+        Ok(Self {
+            host,
+            listener: ProtocolListener::bind(ep, "web").await?,
+            term,
+        })
+    }
+    pub async fn listen(&mut self) {
+        let Self {
+            host,
+            listener,
+            term,
+        } = self;
+        listener
+            .listen(|stream, peer| {
+                let host = host.clone();
+                let term = term.resubscribe();
+                async move { serve(stream, peer, &host, term).await }
+            })
+            .await
+    }
+}
@@ -0,0 +1,192 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! Just enough of the websocket protocol (RFC 6455) for the web bridge: text messages (which may be fragmented), pings
+//! and closes. Binary messages and extensions are not supported
+
+use {
+    crate::engine::net::IoResult,
+    bytes::{Buf, BytesMut},
+    std::io::{Error, ErrorKind},
+    tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+};
+
+/// The GUID that is appended to the client's key to compute the accept key
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// We won't accept messages larger than this
+pub const MAX_MESSAGE: usize = 4 * 1024 * 1024;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Returns the `Sec-WebSocket-Accept` value for the client's `Sec-WebSocket-Key`
+pub fn accept_key(key: &[u8]) -> String {
+    let mut src = key.to_vec();
+    src.extend(GUID.as_bytes());
+    openssl::base64::encode_block(&openssl::sha::sha1(&src))
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Text(String),
+    Close,
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) struct Frame {
+    pub(super) fin: bool,
+    pub(super) opcode: u8,
+    pub(super) payload: Vec<u8>,
+}
+
+/// Parse a (masked) client frame from the start of the buffer. Returns [`None`] if we don't have all of it yet
+pub(super) fn parse_frame(buf: &mut BytesMut) -> IoResult<Option<Frame>> {
+    let [b0, b1, ..] = buf[..] else {
+        return Ok(None);
+    };
+    let (fin, opcode, masked) = (b0 & 0x80 != 0, b0 & 0x0F, b1 & 0x80 != 0);
+    if (b0 & 0x70 != 0) | !masked {
+        // no extensions, and clients must mask their frames
+        return Err(invalid("bad websocket frame"));
+    }
+    let (len, header) = match b1 & 0x7F {
+        126 if buf.len() >= 4 => (u16::from_be_bytes([buf[2], buf[3]]) as u64, 4),
+        127 if buf.len() >= 10 => (u64::from_be_bytes(buf[2..10].try_into().unwrap()), 10),
+        126 | 127 => return Ok(None),
+        len => (len as u64, 2),
+    };
+    if len > MAX_MESSAGE as u64 {
+        return Err(invalid("websocket message too large"));
+    }
+    let len = len as usize;
+    if buf.len() < header + 4 + len {
+        return Ok(None);
+    }
+    buf.advance(header);
+    let mask = buf.split_to(4);
+    let payload = buf
+        .split_to(len)
+        .iter()
+        .enumerate()
+        .map(|(i, b)| b ^ mask[i % 4])
+        .collect();
+    Ok(Some(Frame {
+        fin,
+        opcode,
+        payload,
+    }))
+}
+
+/// Encode an (unmasked) server frame
+pub(super) fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len @ 0..=125 => frame.push(len as u8),
+        len @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+    frame.extend(payload);
+    frame
+}
+
+fn invalid(msg: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// A websocket connection (after the upgrade)
+pub struct WebSocket<S> {
+    stream: S,
+    buf: BytesMut,
+    /// the fragments of the message that we're reading
+    partial: Option<Vec<u8>>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> WebSocket<S> {
+    /// Anything in `buf` is what the client sent after the upgrade request
+    pub fn new(stream: S, buf: BytesMut) -> Self {
+        Self {
+            stream,
+            buf,
+            partial: None,
+        }
+    }
+    /// Read the next message, answering any pings on the way. Returns [`Message::Close`] if the client closed the
+    /// connection (with or without a close frame)
+    ///
+    /// This is cancel safe: if the future is dropped, no part of the message is lost
+    pub async fn read(&mut self) -> IoResult<Message> {
+        loop {
+            while let Some(frame) = parse_frame(&mut self.buf)? {
+                match (frame.opcode, self.partial.as_mut()) {
+                    (OP_CLOSE, _) => return Ok(Message::Close),
+                    (OP_PING, _) => self.write_frame(OP_PONG, &frame.payload).await?,
+                    (OP_PONG, _) => {}
+                    (OP_TEXT, None) => self.partial = Some(frame.payload),
+                    (OP_CONTINUATION, Some(partial)) => {
+                        if partial.len() + frame.payload.len() > MAX_MESSAGE {
+                            return Err(invalid("websocket message too large"));
+                        }
+                        partial.extend(frame.payload);
+                    }
+                    _ => return Err(invalid("unexpected websocket frame")),
+                }
+                if frame.fin & (frame.opcode <= OP_TEXT) {
+                    let message = self.partial.take().unwrap_or_default();
+                    return String::from_utf8(message)
+                        .map(Message::Text)
+                        .map_err(|_| invalid("websocket message is not UTF-8"));
+                }
+            }
+            if self.stream.read_buf(&mut self.buf).await? == 0 {
+                return Ok(Message::Close);
+            }
+        }
+    }
+    pub async fn write_text(&mut self, text: &str) -> IoResult<()> {
+        self.write_frame(OP_TEXT, text.as_bytes()).await
+    }
+    /// Close the connection (without waiting for the client to acknowledge it)
+    pub async fn close(&mut self) -> IoResult<()> {
+        self.write_frame(OP_CLOSE, &[]).await?;
+        self.stream.shutdown().await
+    }
+    async fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> IoResult<()> {
+        self.stream
+            .write_all(&encode_frame(opcode, payload))
+            .await?;
+        self.stream.flush().await
+    }
+}
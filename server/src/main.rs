@@ -33,8 +33,6 @@
 //! is the most important part of the project. There are several modules within this crate; see
 //! the modules for their respective documentation.
 
-use {env_logger::Builder, std::env};
-
 #[macro_use]
 extern crate log;
 #[macro_use]
@@ -64,11 +62,10 @@ type IoResult<T> = std::io::Result<T>;
 const SKY_PID_FILE: &str = ".sky_pid";
 
 fn main() {
-    Builder::new()
-        .parse_filters(&env::var("SKY_LOG").unwrap_or_else(|_| "info".to_owned()))
-        .init();
+    engine::init_logger();
     println!("{TEXT}\nSkytable v{VERSION} | {URL}\n");
     let run = || {
+        let config = engine::load_config()?;
         engine::set_context_init("locking PID file");
         let pid_file = util::os::FileLock::new(SKY_PID_FILE)?;
         let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -79,7 +76,7 @@ fn main() {
         let g = runtime.block_on(async move {
             engine::set_context_init("binding system signals");
             let signal = util::os::TerminationSignal::init()?;
-            let (config, global) = tokio::task::spawn_blocking(|| engine::load_all())
+            let (config, global) = tokio::task::spawn_blocking(|| engine::load_all(config))
                 .await
                 .unwrap()?;
             let g = global.global.clone();
@@ -59,6 +59,12 @@ impl From<std::io::ErrorKind> for SysIOError {
     }
 }
 
+impl SysIOError {
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.0.kind()
+    }
+}
+
 impl fmt::Display for SysIOError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.0.fmt(f)
@@ -77,8 +83,11 @@ mod unix {
     use {
         libc::{rlimit, RLIMIT_NOFILE},
         std::{
+            ffi::CString,
             future::Future,
             io::Error as IoError,
+            os::unix::ffi::OsStrExt,
+            path::Path,
             pin::Pin,
             task::{Context, Poll},
         },
@@ -133,6 +142,21 @@ mod unix {
         let _ = ResourceLimit::get().unwrap();
     }
 
+    /// Check if we (the effective user) can write to the given path (for a directory, this means that we can create
+    /// and remove files in it)
+    pub fn is_writable(path: &Path) -> crate::IoResult<bool> {
+        let mode = if path.metadata()?.is_dir() {
+            libc::W_OK | libc::X_OK
+        } else {
+            libc::W_OK
+        };
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        Ok(unsafe {
+            // UNSAFE(@ohsayan): the path is a valid, nul terminated string
+            libc::access(path.as_ptr(), mode) == 0
+        })
+    }
+
     pub struct TerminationSignal {
         sigint: Signal,
         sigterm: Signal,
@@ -165,12 +189,18 @@ mod windows {
     use {
         std::{
             future::Future,
+            path::Path,
             pin::Pin,
             task::{Context, Poll},
         },
         tokio::signal::windows::{ctrl_break, ctrl_c, CtrlBreak, CtrlC},
     };
 
+    /// Check if the given path can be written to (we can only look at the read-only attribute here)
+    pub fn is_writable(path: &Path) -> crate::IoResult<bool> {
+        Ok(!path.metadata()?.permissions().readonly())
+    }
+
     pub struct TerminationSignal {
         ctrl_c: CtrlC,
         ctrl_break: CtrlBreak,
@@ -27,14 +27,16 @@
 use {
     rand::{
         distributions::{uniform::SampleUniform, Alphanumeric},
-        rngs::ThreadRng,
+        rngs::{StdRng, ThreadRng},
         seq::SliceRandom,
-        Rng,
+        Rng, SeedableRng,
     },
     std::{
         collections::hash_map::RandomState,
+        env,
         hash::{BuildHasher, Hash, Hasher},
         io::Read,
+        panic::{self, AssertUnwindSafe},
     },
 };
 
@@ -141,3 +143,74 @@ pub fn hash_rs<T: Hash + ?Sized>(rs: &RandomState, item: &T) -> u64 {
 pub fn randomizer() -> ThreadRng {
     rand::thread_rng()
 }
+
+/*
+    property tests
+*/
+
+/// The environment variable that makes [`check`] (and [`check_with`]) run a single case with the given seed, to
+/// reproduce a failure
+pub const PROPERTY_SEED_VAR: &str = "SKY_PROPERTY_SEED";
+
+/// A seeded source of arbitrary values for property tests
+pub struct Gen {
+    rng: StdRng,
+}
+
+impl Gen {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+    pub fn bool(&mut self) -> bool {
+        random_bool(&mut self.rng)
+    }
+    /// Returns a number in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        random_number(0, n, &mut self.rng)
+    }
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        items.choose(&mut self.rng).unwrap()
+    }
+    /// Returns up to `max_len` random bytes
+    pub fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| self.rng.gen()).collect()
+    }
+    /// Returns a string with up to `max_len` chars from anywhere in unicode
+    pub fn string(&mut self, max_len: usize) -> String {
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| self.rng.gen::<char>()).collect()
+    }
+}
+
+/// A type that can be generated for a property test
+pub trait Arbitrary: Sized {
+    fn arbitrary(g: &mut Gen) -> Self;
+}
+
+/// Check that `property` holds for `cases` arbitrary values (see [`check_with`])
+pub fn check<T: Arbitrary>(cases: usize, property: impl Fn(T)) {
+    check_with(cases, |g| property(T::arbitrary(g)))
+}
+
+/// Run `property` for `cases` randomly seeded generators. If a case panics, the test fails with the seed of that case
+/// (set [`PROPERTY_SEED_VAR`] to the seed to run only that case)
+pub fn check_with(cases: usize, property: impl Fn(&mut Gen)) {
+    let seeds: Vec<u64> = match env::var(PROPERTY_SEED_VAR) {
+        Ok(seed) => vec![seed.parse().expect("bad seed")],
+        Err(_) => {
+            let mut rng = rng();
+            (0..cases).map(|_| rng.gen()).collect()
+        }
+    };
+    for seed in seeds {
+        if panic::catch_unwind(AssertUnwindSafe(|| property(&mut Gen::new(seed)))).is_err() {
+            panic!("property failed with seed {seed} (rerun with {PROPERTY_SEED_VAR}={seed})");
+        }
+    }
+}
@@ -50,6 +50,34 @@ pub enum BenchEngine {
     Fury,
 }
 
+/// How the keys that a workload reads and updates are picked
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum KeyDistribution {
+    /// every key is equally likely
+    #[default]
+    Uniform,
+    /// a few keys are far more likely than the rest (like most real-world access patterns)
+    Zipfian,
+    /// keys are picked one after the other
+    Sequential,
+}
+
+/// A mixed workload that is run on a model loaded with `--rowcount` rows
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Workload {
+    /// the percentage of queries that insert new rows
+    pub insert: u8,
+    /// the percentage of queries that select rows
+    pub select: u8,
+    /// the percentage of queries that update rows
+    pub update: u8,
+    pub distribution: KeyDistribution,
+    /// the size of the value (in bytes) that is stored in every row
+    pub value_size: usize,
+    /// the number of queries in the mixed run
+    pub operations: usize,
+}
+
 #[derive(Debug)]
 pub struct BenchConfig {
     pub host: String,
@@ -60,6 +88,7 @@ pub struct BenchConfig {
     pub query_count: usize,
     pub engine: BenchEngine,
     pub connections: usize,
+    pub workload: Option<Workload>,
 }
 
 impl BenchConfig {
@@ -72,6 +101,7 @@ impl BenchConfig {
         query_count: usize,
         engine: BenchEngine,
         connections: usize,
+        workload: Option<Workload>,
     ) -> Self {
         Self {
             host,
@@ -82,6 +112,7 @@ impl BenchConfig {
             query_count,
             engine,
             connections,
+            workload,
         }
     }
 }
@@ -95,6 +126,35 @@ fn load_env() -> BenchResult<TaskInner> {
     }
 }
 
+/// Parse a workload mix like `select:80,update:15,insert:5` (the query kinds that are left out make up 0% of the
+/// workload)
+fn parse_mix(mix: &str) -> Option<(u8, u8, u8)> {
+    let (mut insert, mut select, mut update) = (None, None, None);
+    for item in mix.split(',') {
+        let (kind, percentage) = item.trim().split_once(':')?;
+        let percentage: u8 = percentage.trim().parse().ok()?;
+        let slot = match kind.trim() {
+            "insert" => &mut insert,
+            "select" => &mut select,
+            "update" => &mut update,
+            _ => return None,
+        };
+        if slot.replace(percentage).is_some() {
+            return None;
+        }
+    }
+    let (insert, select, update) = (
+        insert.unwrap_or(0),
+        select.unwrap_or(0),
+        update.unwrap_or(0),
+    );
+    if insert as u16 + select as u16 + update as u16 == 100 {
+        Some((insert, select, update))
+    } else {
+        None
+    }
+}
+
 fn cdig(n: usize) -> usize {
     if n == 0 {
         1
@@ -173,7 +233,68 @@ pub fn parse() -> BenchResult<Task> {
             }
         },
     };
-    let need_atleast = cdig(query_count);
+    // workload
+    let workload = match args.remove("--workload") {
+        None => {
+            for arg in ["--distribution", "--valuesize", "--operations"] {
+                if args.contains_key(arg) {
+                    return Err(BenchError::ArgsErr(format!(
+                        "`{arg}` can only be used with `--workload`"
+                    )));
+                }
+            }
+            None
+        }
+        Some(mix) => {
+            let Some((insert, select, update)) = parse_mix(&mix) else {
+                return Err(BenchError::ArgsErr(
+                    "bad value for `--workload`. expected a mix like `select:80,update:15,insert:5` that adds up to 100".into(),
+                ));
+            };
+            let distribution = match args.remove("--distribution").as_deref() {
+                None | Some("uniform") => KeyDistribution::Uniform,
+                Some("zipfian") => KeyDistribution::Zipfian,
+                Some("sequential") => KeyDistribution::Sequential,
+                Some(d) => {
+                    return Err(BenchError::ArgsErr(format!(
+                        "bad value for `--distribution`. got `{d}` but expected uniform, zipfian or sequential"
+                    )))
+                }
+            };
+            let value_size = match args.remove("--valuesize") {
+                None => 32,
+                Some(vs) => match vs.parse() {
+                    Ok(vs) => vs,
+                    Err(_) => {
+                        return Err(BenchError::ArgsErr(
+                            "bad value for `--valuesize`. must be a positive integer".into(),
+                        ))
+                    }
+                },
+            };
+            let operations = match args.remove("--operations") {
+                None => query_count,
+                Some(ops) => match ops.parse() {
+                    Ok(ops) if ops != 0 => ops,
+                    Err(_) | Ok(_) => {
+                        return Err(BenchError::ArgsErr(
+                            "bad value for `--operations`. must be a nonzero value".into(),
+                        ))
+                    }
+                },
+            };
+            Some(Workload {
+                insert,
+                select,
+                update,
+                distribution,
+                value_size,
+                operations,
+            })
+        }
+    };
+    // rows inserted by the workload get keys that follow the loaded rows
+    let need_atleast = cdig(query_count + workload.map_or(0, |w| w.operations));
     let key_size = match args.remove("--keysize") {
         None => need_atleast,
         Some(ks) => match ks.parse() {
@@ -224,6 +345,7 @@ pub fn parse() -> BenchResult<Task> {
             query_count,
             engine,
             connections,
+            workload,
         )))
     } else {
         Err(BenchError::ArgsErr(format!("unrecognized arguments")))
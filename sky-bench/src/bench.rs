@@ -30,7 +30,7 @@ use crate::args::BenchEngine;
 
 use {
     crate::{
-        args::BenchConfig,
+        args::{BenchConfig, KeyDistribution, Workload},
         error::{self, BenchResult},
         runtime::{fury, rookie, RuntimeStats},
     },
@@ -120,11 +120,16 @@ pub fn run(bench: BenchConfig) -> error::BenchResult<()> {
         "root",
         &bench.root_pass,
     ));
-    info!("running preliminary checks and creating model `bench.bench` with definition: `{{un: binary, pw: uint8}}`");
+    // a workload stores a value of the given size in every row (instead of a counter)
+    let model_fields = match bench.workload {
+        None => "un: binary, pw: uint8",
+        Some(_) => "un: binary, val: binary",
+    };
+    info!("running preliminary checks and creating model `bench.bench` with definition: `{{{model_fields}}}`");
     let mut main_thread_db = bench_config.config.connect()?;
     main_thread_db.query_parse::<()>(&query!("create space bench"))?;
     main_thread_db.query_parse::<()>(&query!(format!(
-        "create model {BENCHMARK_SPACE_ID}.{BENCHMARK_MODEL_ID}(un: binary, pw: uint8)"
+        "create model {BENCHMARK_SPACE_ID}.{BENCHMARK_MODEL_ID}({model_fields})"
     )))?;
     let stats = match bench.engine {
         BenchEngine::Rookie => bench_rookie(bench_config, bench),
@@ -165,24 +170,34 @@ fn cleanup(mut main_thread_db: Connection) -> Result<(), error::BenchError> {
 }
 
 fn print_table(data: Vec<(&'static str, RuntimeStats)>) {
+    const LINE: &str = "+---------+--------------------------+-------------+-------------+-------------+-----------------------+------------------------+";
+    println!("{LINE}");
     println!(
-        "+---------+--------------------------+-----------------------+------------------------+"
-    );
-    println!(
-        "| Query   | Effective real-world QPS | Slowest Query (nanos) | Fastest Query (nanos)  |"
+        "| Query   | Effective real-world QPS | p50 (nanos) | p95 (nanos) | p99 (nanos) | Slowest Query (nanos) | Fastest Query (nanos)  |"
     );
-    println!(
-        "+---------+--------------------------+-----------------------+------------------------+"
-    );
-    for (query, RuntimeStats { qps, head, tail }) in data {
+    println!("{LINE}");
+    for (
+        query,
+        RuntimeStats {
+            qps,
+            head,
+            tail,
+            latencies,
+        },
+    ) in data
+    {
         println!(
-            "| {:<7} | {:>24.2} | {:>21} | {:>22} |",
-            query, qps, tail, head
+            "| {:<7} | {:>24.2} | {:>11} | {:>11} | {:>11} | {:>21} | {:>22} |",
+            query,
+            qps,
+            latencies.percentile(50.0),
+            latencies.percentile(95.0),
+            latencies.percentile(99.0),
+            tail,
+            head
         );
     }
-    println!(
-        "+---------+--------------------------+-----------------------+------------------------+"
-    );
+    println!("{LINE}");
 }
 
 /*
@@ -194,6 +209,9 @@ pub struct BenchmarkTask {
     gen_query: fn(&Self, u64) -> Query,
     check_resp: fn(&Self, u64, Response) -> bool,
     pk_len: usize,
+    /// the number of rows that were loaded (only used by workloads)
+    rows: u64,
+    workload: Workload,
 }
 
 impl BenchmarkTask {
@@ -206,11 +224,61 @@ impl BenchmarkTask {
             gen_query,
             check_resp,
             pk_len,
+            rows: 0,
+            workload: Workload::default(),
+        }
+    }
+    fn new_workload(
+        pk_len: usize,
+        rows: u64,
+        workload: Workload,
+        gen_query: fn(&Self, u64) -> Query,
+        check_resp: fn(&Self, u64, Response) -> bool,
+    ) -> Self {
+        Self {
+            gen_query,
+            check_resp,
+            pk_len,
+            rows,
+            workload,
         }
     }
     fn fmt_pk(&self, current: u64) -> Vec<u8> {
         format!("{:0>width$}", current, width = self.pk_len).into_bytes()
     }
+    fn value(&self) -> Vec<u8> {
+        vec![b'x'; self.workload.value_size]
+    }
+    /// Pick the query that the `current`th query of the workload runs. Since every query has a different `current`,
+    /// we derive the random choices from it (and so we can pick the same query again when we check the response)
+    fn workload_op(&self, current: u64) -> WorkloadOp {
+        let Workload { insert, select, .. } = self.workload;
+        let pick = (mix64(current) % 100) as u8;
+        if pick < insert {
+            // keys for new rows follow the keys of the loaded rows
+            WorkloadOp::Insert(self.rows + current)
+        } else if pick < insert + select {
+            WorkloadOp::Select(self.workload_key(current))
+        } else {
+            WorkloadOp::Update(self.workload_key(current))
+        }
+    }
+    /// Pick one of the loaded rows (with keys `1..=rows`)
+    fn workload_key(&self, current: u64) -> u64 {
+        let random = mix64(current ^ 0x5bd1_e995_5bd1_e995);
+        match self.workload.distribution {
+            KeyDistribution::Uniform => 1 + random % self.rows,
+            KeyDistribution::Sequential => 1 + current % self.rows,
+            KeyDistribution::Zipfian => {
+                // invert the CDF of a power law (with the exponent below) over `1..=rows`
+                const THETA: f64 = 0.99;
+                let uniform = (random >> 11) as f64 / (1u64 << 53) as f64;
+                let span = (self.rows as f64 + 1.0).powf(1.0 - THETA) - 1.0;
+                let key = (span * uniform + 1.0).powf(1.0 / (1.0 - THETA)) as u64;
+                key.clamp(1, self.rows)
+            }
+        }
+    }
     pub fn generate_query(&self, current: u64) -> Query {
         (self.gen_query)(self, current)
     }
@@ -219,6 +287,21 @@ impl BenchmarkTask {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WorkloadOp {
+    Insert(u64),
+    Select(u64),
+    Update(u64),
+}
+
+/// The splitmix64 finalizer, used to get a well mixed (random looking) number out of a counter
+fn mix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
 struct BenchItem {
     name: &'static str,
     spec: BenchmarkTask,
@@ -249,6 +332,9 @@ impl BenchItem {
 }
 
 fn prepare_bench_spec(bench: &BenchConfig) -> Vec<BenchItem> {
+    if let Some(workload) = bench.workload {
+        return prepare_workload_spec(bench, workload);
+    }
     vec![
         BenchItem::new(
             "INSERT",
@@ -300,6 +386,58 @@ fn prepare_bench_spec(bench: &BenchConfig) -> Vec<BenchItem> {
     ]
 }
 
+/// A workload first loads the rows (`LOAD`) and then runs the mix of queries on them (`MIXED`)
+fn prepare_workload_spec(bench: &BenchConfig, workload: Workload) -> Vec<BenchItem> {
+    let rows = bench.query_count as u64;
+    vec![
+        BenchItem::new(
+            "LOAD",
+            BenchmarkTask::new_workload(
+                bench.key_size,
+                rows,
+                workload,
+                |me, current| query!("insert into bench(?, ?)", me.fmt_pk(current), me.value()),
+                |_, _, resp| resp == Response::Empty,
+            ),
+            bench.query_count,
+        ),
+        BenchItem::new(
+            "MIXED",
+            BenchmarkTask::new_workload(
+                bench.key_size,
+                rows,
+                workload,
+                |me, current| match me.workload_op(current) {
+                    WorkloadOp::Insert(key) => {
+                        query!("insert into bench(?, ?)", me.fmt_pk(key), me.value())
+                    }
+                    WorkloadOp::Select(key) => {
+                        query!("select * from bench where un = ?", me.fmt_pk(key))
+                    }
+                    WorkloadOp::Update(key) => {
+                        query!(
+                            "update bench set val = ? where un = ?",
+                            me.value(),
+                            me.fmt_pk(key)
+                        )
+                    }
+                },
+                |me, current, resp| match me.workload_op(current) {
+                    WorkloadOp::Insert(_) | WorkloadOp::Update(_) => resp == Response::Empty,
+                    WorkloadOp::Select(key) => match resp {
+                        Response::Row(r) => {
+                            r.into_values()
+                                == vec![Value::Binary(me.fmt_pk(key)), Value::Binary(me.value())]
+                        }
+                        _ => false,
+                    },
+                },
+            ),
+            workload.operations,
+        ),
+    ]
+}
+
 fn fmt_u64(n: u64) -> String {
     let num_str = n.to_string();
     let mut result = String::new();
@@ -326,7 +464,7 @@ fn bench_rookie(
     // prepare benches
     let benches = prepare_bench_spec(&bench);
     // bench
-    let total_queries = bench.query_count as u64 * benches.len() as u64;
+    let total_queries = benches.iter().map(|item| item.count as u64).sum();
     let mut results = vec![];
     for task in benches {
         let name = task.name;
@@ -356,7 +494,7 @@ fn bench_fury(bench: BenchConfig) -> BenchResult<(u64, Vec<(&'static str, Runtim
         // prepare benches
         let benches = prepare_bench_spec(&bench);
         // bench
-        let total_queries = bench.query_count as u64 * benches.len() as u64;
+        let total_queries = benches.iter().map(|item| item.count as u64).sum();
         let mut results = vec![];
         for task in benches {
             let name = task.name;
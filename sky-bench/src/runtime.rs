@@ -47,6 +47,7 @@ pub struct RuntimeStats {
     pub qps: f64,
     pub head: u128,
     pub tail: u128,
+    pub latencies: Histogram,
 }
 
 #[derive(Debug)]
@@ -55,15 +56,83 @@ struct WorkerLocalStats {
     elapsed: u128,
     head: u128,
     tail: u128,
+    latencies: Histogram,
 }
 
 impl WorkerLocalStats {
-    fn new(start: Instant, elapsed: u128, head: u128, tail: u128) -> Self {
+    fn new(start: Instant, elapsed: u128, head: u128, tail: u128, latencies: Histogram) -> Self {
         Self {
             start,
             elapsed,
             head,
             tail,
+            latencies,
         }
     }
 }
+
+/*
+    latency histogram
+*/
+
+const SUB_BUCKET_BITS: u32 = 5;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const BUCKETS: usize = (u64::BITS - SUB_BUCKET_BITS + 1) as usize * SUB_BUCKETS;
+
+/// A histogram of query latencies (in nanoseconds). Every power of two is split into [`SUB_BUCKETS`] buckets, so
+/// that a percentile is never off by more than ~3% while the histogram stays the same (small) size no matter how
+/// many queries are recorded
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    buckets: Box<[u64]>,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Self {
+            buckets: vec![0; BUCKETS].into_boxed_slice(),
+            count: 0,
+        }
+    }
+    pub fn record(&mut self, nanos: u128) {
+        let nanos = u64::try_from(nanos).unwrap_or(u64::MAX);
+        self.buckets[Self::bucket_of(nanos)] += 1;
+        self.count += 1;
+    }
+    pub fn merge(&mut self, other: &Self) {
+        self.buckets
+            .iter_mut()
+            .zip(other.buckets.iter())
+            .for_each(|(this, other)| *this += other);
+        self.count += other.count;
+    }
+    /// Returns the latency that `percentile`% of the queries completed within (rounded up to the end of its bucket)
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        let rank = ((percentile / 100.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Self::bucket_max(bucket);
+            }
+        }
+        0
+    }
+    fn bucket_of(nanos: u64) -> usize {
+        if nanos < SUB_BUCKETS as u64 {
+            return nanos as usize;
+        }
+        let shift = u64::BITS - 1 - nanos.leading_zeros() - SUB_BUCKET_BITS;
+        let sub_bucket = (nanos >> shift) as usize - SUB_BUCKETS;
+        (shift as usize + 1) * SUB_BUCKETS + sub_bucket
+    }
+    fn bucket_max(bucket: usize) -> u64 {
+        if bucket < SUB_BUCKETS {
+            return bucket as u64;
+        }
+        let shift = bucket / SUB_BUCKETS - 1;
+        let sub_bucket = (bucket % SUB_BUCKETS) as u64;
+        ((SUB_BUCKETS as u64 + sub_bucket + 1) << shift).wrapping_sub(1)
+    }
+}
@@ -25,7 +25,7 @@
 */
 
 use {
-    super::{RuntimeStats, WorkerLocalStats, WorkerTask},
+    super::{Histogram, RuntimeStats, WorkerLocalStats, WorkerTask},
     crate::bench::{BenchmarkTask, BENCHMARK_SPACE_ID},
     skytable::Config,
     std::{
@@ -167,6 +167,7 @@ impl Fury {
         let mut global_stop = None;
         let mut global_head = u128::MAX;
         let mut global_tail = 0u128;
+        let mut global_latencies = Histogram::new();
         let mut remaining = self.client_count;
         while remaining != 0 {
             let WorkerLocalStats {
@@ -174,6 +175,7 @@ impl Fury {
                 elapsed: this_elapsed,
                 head: this_head,
                 tail: this_tail,
+                latencies: this_latencies,
             } = match self.rx_task_result.recv().await {
                 None => {
                     return Err(FuryError::Dead);
@@ -203,6 +205,7 @@ impl Fury {
             if this_tail > global_tail {
                 global_tail = this_tail;
             }
+            global_latencies.merge(&this_latencies);
             remaining -= 1;
         }
         Ok(RuntimeStats {
@@ -215,6 +218,7 @@ impl Fury {
             ),
             head: global_head,
             tail: global_tail,
+            latencies: global_latencies,
         })
     }
 }
@@ -269,6 +273,7 @@ async fn worker_svc(
         let mut local_elapsed = 0u128;
         let mut local_head = u128::MAX;
         let mut local_tail = 0u128;
+        let mut local_latencies = Histogram::new();
         while (current != 0) && !exit_now {
             // prepare query
             let query = task.generate_query(current as _);
@@ -320,6 +325,7 @@ async fn worker_svc(
             if elapsed < local_head {
                 local_head = elapsed;
             }
+            local_latencies.record(elapsed);
             current = grefresh_target();
             exit_now = grefresh_early_exit();
         }
@@ -333,6 +339,7 @@ async fn worker_svc(
                 local_elapsed,
                 local_head,
                 local_tail,
+                local_latencies,
             )))
             .await
             .is_err()
@@ -25,7 +25,7 @@
 */
 
 use {
-    super::{RuntimeStats, WorkerLocalStats, WorkerTask},
+    super::{Histogram, RuntimeStats, WorkerLocalStats, WorkerTask},
     crossbeam_channel::{unbounded, Receiver, Sender},
     std::{
         fmt::{self, Display},
@@ -186,6 +186,7 @@ impl Worker {
                         let mut local_elapsed = 0u128;
                         let mut local_head = u128::MAX;
                         let mut local_tail = 0;
+                        let mut local_latencies = Histogram::new();
                         // bombard
                         while (global_position != 0) & global_okay {
                             let task = Bt::generate_task(&task, global_position);
@@ -208,6 +209,7 @@ impl Worker {
                             if this_elapsed > local_tail {
                                 local_tail = this_elapsed;
                             }
+                            local_latencies.record(this_elapsed);
                             global_position = GPState::get().update_target();
                             global_okay = GPState::get().load_okay();
                         }
@@ -219,6 +221,7 @@ impl Worker {
                                     local_elapsed,
                                     local_head,
                                     local_tail,
+                                    local_latencies,
                                 )))
                                 .unwrap();
                         }
@@ -293,6 +296,7 @@ impl<Bt: ThreadedBombardTask> BombardPool<Bt> {
             let mut global_stop = None;
             let mut global_head = u128::MAX;
             let mut global_tail = 0u128;
+            let mut global_latencies = Histogram::new();
             for (_, sender) in self.workers.iter() {
                 sender
                     .send(WorkerTask::Task(task_description.clone()))
@@ -312,6 +316,7 @@ impl<Bt: ThreadedBombardTask> BombardPool<Bt> {
                     elapsed,
                     head,
                     tail,
+                    latencies,
                 } = match results {
                     WorkerResult::Completed(r) => r,
                     WorkerResult::Errored(e) => return Err(BombardError::WorkerTaskError(e)),
@@ -346,6 +351,7 @@ impl<Bt: ThreadedBombardTask> BombardPool<Bt> {
                 if tail > global_tail {
                     global_tail = tail;
                 }
+                global_latencies.merge(&latencies);
                 received += 1;
             }
             // reset global pool state
@@ -359,6 +365,7 @@ impl<Bt: ThreadedBombardTask> BombardPool<Bt> {
                 qps: super::qps(count, global_elapsed),
                 head: global_head,
                 tail: global_tail,
+                latencies: global_latencies,
             })
         })
     }
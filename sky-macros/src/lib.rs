@@ -51,6 +51,7 @@ use {
 };
 
 mod dbtest;
+mod persist;
 mod util;
 
 #[proc_macro_attribute]
@@ -58,6 +59,14 @@ pub fn dbtest(attrs: TokenStream, item: TokenStream) -> TokenStream {
     dbtest::dbtest(attrs, item)
 }
 
+#[proc_macro_derive(PersistObject, attributes(persist))]
+/// Implements `PersistObject` for a reference to a struct, generating the metadata struct along with the enc/dec routines
+/// and pretests from the field annotations (see `persist.rs` for the annotations)
+pub fn derive_persist_object(t: TokenStream) -> TokenStream {
+    let item = syn::parse_macro_input!(t as DeriveInput);
+    persist::derive_persist_object(item).into()
+}
+
 #[proc_macro_derive(Wrapper)]
 /// Implements necessary traits for some type `T` to make it identify as a different type but mimic the functionality
 /// as the inner type it wraps around
@@ -0,0 +1,243 @@
+/*
+ * Created on Sun Oct 18 2026
+ *
+ * This file is a part of Skytable
+ * Skytable (formerly known as TerrabaseDB or Skybase) is a free and open-source
+ * NoSQL database written by Sayan Nandan ("the Author") with the
+ * vision to provide flexibility in data modelling without compromising
+ * on performance, queryability or scalability.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! `#[derive(PersistObject)]`: implements `PersistObject` for a reference to a struct with owned fields, so that it can
+//! be encoded and decoded with the `inf` routines in the server
+//!
+//! Every field is one of:
+//! - a fixed size field (the default), which must implement `FixedSizeField` and is written into the metadata
+//! - a string (`#[persist(str)]`, for a `Box<str>`) or a binary (`#[persist(bin)]`, for a `Box<[u8]>`), whose length is
+//!   written into the metadata and whose data is written into the object
+//!
+//! The struct must declare the size of its metadata with `#[persist(metadata_size = ...)]`. The metadata is part of the
+//! on-disk format, so changing the fields of the struct in a way that changes it is a compile error until the declared
+//! size is updated as well
+//!
+//! A `{name}MD` struct is generated for the metadata, with the fixed size fields and a `{field}_l` length for every
+//! string or binary field
+
+use {
+    crate::util::{self, AttributeKind},
+    proc_macro2::TokenStream as TokenStream2,
+    quote::{format_ident, quote},
+    syn::{Data, DataStruct, DeriveInput, Fields, Ident, Type},
+};
+
+enum FieldKind {
+    Fixed,
+    Str,
+    Bin,
+}
+
+struct PersistField {
+    name: Ident,
+    ty: Type,
+    kind: FieldKind,
+}
+
+impl PersistField {
+    fn len_name(&self) -> Ident {
+        format_ident!("{}_l", self.name)
+    }
+}
+
+/// Returns the attributes in the `#[persist(...)]` attributes among `attrs`
+fn persist_attributes(attrs: &[syn::Attribute]) -> Vec<AttributeKind> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("persist"))
+        .flat_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => list
+                .nested
+                .iter()
+                .map(util::extract_attribute)
+                .collect::<Vec<_>>(),
+            _ => panic!("expected `#[persist(...)]`"),
+        })
+        .collect()
+}
+
+fn field_kind(attrs: &[syn::Attribute]) -> FieldKind {
+    let mut kind = FieldKind::Fixed;
+    for attr in persist_attributes(attrs) {
+        match attr {
+            AttributeKind::Path(p) if p.is_ident("str") => kind = FieldKind::Str,
+            AttributeKind::Path(p) if p.is_ident("bin") => kind = FieldKind::Bin,
+            _ => panic!("expected `#[persist(str)]` or `#[persist(bin)]` on a field"),
+        }
+    }
+    kind
+}
+
+fn metadata_size(attrs: &[syn::Attribute]) -> usize {
+    let mut size = None;
+    for attr in persist_attributes(attrs) {
+        let (name, lit) = attr.into_pair();
+        if name != "metadata_size" {
+            panic!("unknown attribute `{name}`");
+        }
+        size = Some(
+            util::extract_int_from_lit(&lit).expect("`metadata_size` must be an integer literal"),
+        );
+    }
+    size.expect("must declare the size of the metadata with `#[persist(metadata_size = ...)]`")
+}
+
+pub fn derive_persist_object(item: DeriveInput) -> TokenStream2 {
+    if !item.generics.params.is_empty() {
+        panic!("only works on structs without generics");
+    }
+    let fields = match item.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(ref f),
+            ..
+        }) => f,
+        _ => panic!("only works on structs with named fields"),
+    };
+    let fields: Vec<_> = fields
+        .named
+        .iter()
+        .map(|f| PersistField {
+            name: f.ident.clone().unwrap(),
+            ty: f.ty.clone(),
+            kind: field_kind(&f.attrs),
+        })
+        .collect();
+    let declared_size = metadata_size(&item.attrs);
+    let (vis, st_name) = (&item.vis, &item.ident);
+    let md_name = format_ident!("{}MD", st_name);
+    let inf = quote!(crate::engine::storage::v1::inf);
+    let scanner = quote!(crate::engine::mem::BufferedScanner);
+    // metadata struct
+    let md_fields = fields.iter().map(|f| match f.kind {
+        FieldKind::Fixed => {
+            let (name, ty) = (&f.name, &f.ty);
+            quote!(#name: #ty)
+        }
+        FieldKind::Str | FieldKind::Bin => {
+            let name = f.len_name();
+            quote!(#name: u64)
+        }
+    });
+    let md_size = fields.iter().map(|f| match f.kind {
+        FieldKind::Fixed => {
+            let ty = &f.ty;
+            quote!(<#ty as #inf::FixedSizeField>::SIZE)
+        }
+        FieldKind::Str | FieldKind::Bin => quote!(::core::mem::size_of::<u64>()),
+    });
+    // object lengths
+    let obj_lens = fields
+        .iter()
+        .filter(|f| !matches!(f.kind, FieldKind::Fixed))
+        .map(|f| {
+            let name = f.len_name();
+            quote!(len = len.saturating_add(md.#name as usize);)
+        });
+    // enc
+    let meta_enc = fields.iter().map(|f| {
+        let name = &f.name;
+        match f.kind {
+            FieldKind::Fixed => quote!(#inf::FixedSizeField::encode(&data.#name, buf);),
+            FieldKind::Str | FieldKind::Bin => {
+                quote!(buf.extend((data.#name.len() as u64).to_le_bytes());)
+            }
+        }
+    });
+    let obj_enc = fields.iter().map(|f| {
+        let name = &f.name;
+        match f.kind {
+            FieldKind::Fixed => quote!(),
+            FieldKind::Str => quote!(buf.extend(data.#name.as_bytes());),
+            FieldKind::Bin => quote!(buf.extend(data.#name.iter());),
+        }
+    });
+    // dec
+    let meta_dec = fields.iter().map(|f| match f.kind {
+        FieldKind::Fixed => {
+            let (name, ty) = (&f.name, &f.ty);
+            quote!(#name: <#ty as #inf::FixedSizeField>::decode(scanner))
+        }
+        FieldKind::Str | FieldKind::Bin => {
+            let name = f.len_name();
+            quote!(#name: scanner.next_u64_le())
+        }
+    });
+    let obj_dec = fields.iter().map(|f| {
+        let name = &f.name;
+        let len = f.len_name();
+        match f.kind {
+            FieldKind::Fixed => quote!(#name: md.#name),
+            FieldKind::Str => quote! {
+                #name: #inf::dec::utils::decode_string(s, md.#len as usize)?.into_boxed_str()
+            },
+            FieldKind::Bin => quote!(#name: s.next_chunk_variable(md.#len as usize).into()),
+        }
+    });
+    quote! {
+        #[automatically_derived]
+        #vis struct #md_name {
+            #(#md_fields,)*
+        }
+        #[automatically_derived]
+        impl<'a> #inf::PersistObject for &'a #st_name {
+            const METADATA_SIZE: usize = 0 #(+ #md_size)*;
+            type InputType = Self;
+            type OutputType = #st_name;
+            type Metadata = #md_name;
+            fn pretest_can_dec_object(scanner: &#scanner, md: &Self::Metadata) -> bool {
+                let mut len = 0usize;
+                #(#obj_lens)*
+                scanner.has_left(len)
+            }
+            fn meta_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+                #(#meta_enc)*
+            }
+            unsafe fn meta_dec(
+                scanner: &mut #scanner,
+            ) -> crate::engine::error::RuntimeResult<Self::Metadata> {
+                Ok(#md_name {
+                    #(#meta_dec,)*
+                })
+            }
+            fn obj_enc(buf: &mut Vec<u8>, data: Self::InputType) {
+                #(#obj_enc)*
+            }
+            unsafe fn obj_dec(
+                s: &mut #scanner,
+                md: Self::Metadata,
+            ) -> crate::engine::error::RuntimeResult<Self::OutputType> {
+                Ok(#st_name {
+                    #(#obj_dec,)*
+                })
+            }
+        }
+        const _: () = assert!(
+            <&#st_name as #inf::PersistObject>::METADATA_SIZE == #declared_size,
+            "the metadata layout changed; update `metadata_size` if this is intended (this changes the on-disk format)"
+        );
+    }
+}